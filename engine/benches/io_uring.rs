@@ -0,0 +1,121 @@
+//! Cold-query latency of the io_uring read path against the default mmap
+//! path. Run with `cargo bench --bench io_uring --features io-uring`.
+//!
+//! Each iteration opens the index afresh and evicts its files from the page
+//! cache, so reads go to the disk; the `warm` groups keep the cache.
+//! Eviction is best-effort (`posix_fadvise(DONTNEED)`): on tmpfs it does
+//! nothing, so point `TMPDIR` at the NVMe device being measured.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use flapjack::{Document, FieldValue, IndexManager, Sort, SortOrder};
+use std::collections::HashMap;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+const MODES: [(&str, &str); 2] = [("mmap", "false"), ("io_uring", "true")];
+
+fn setup_tenant(manager: &IndexManager, tenant_id: &str, num_docs: usize) {
+    manager.create_tenant(tenant_id).unwrap();
+
+    let mut docs = Vec::new();
+    for i in 0..num_docs {
+        let mut doc = Document {
+            id: format!("doc_{}", i),
+            fields: HashMap::new(),
+        };
+        doc.fields.insert(
+            "title".to_string(),
+            FieldValue::Text(format!("Laptop Product {}", i)),
+        );
+        doc.fields.insert(
+            "description".to_string(),
+            FieldValue::Text(format!(
+                "Gaming laptop description {} {}",
+                i,
+                "x".repeat(200)
+            )),
+        );
+        doc.fields.insert(
+            "price".to_string(),
+            FieldValue::Integer((100 + i * 5) as i64),
+        );
+        docs.push(doc);
+    }
+
+    manager.add_documents(tenant_id, docs).unwrap();
+}
+
+fn evict_page_cache(dir: &Path) {
+    for entry in walk(dir) {
+        if let Ok(file) = std::fs::File::open(&entry) {
+            unsafe {
+                libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED);
+            }
+        }
+    }
+}
+
+fn walk(dir: &Path) -> Vec<std::path::PathBuf> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).unwrap().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            files.extend(walk(&path));
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// A manager with the tenant loaded, on the read path `FLAPJACK_IO_URING`
+/// selects.
+fn open(base: &Path, io_uring: &str, cold: bool) -> Arc<IndexManager> {
+    std::env::set_var("FLAPJACK_IO_URING", io_uring);
+    let manager = IndexManager::new(base);
+    manager.get_or_load("bench").unwrap();
+    if cold {
+        evict_page_cache(base);
+    }
+    manager
+}
+
+fn read_paths(c: &mut Criterion) {
+    let temp = TempDir::new().unwrap();
+    setup_tenant(&IndexManager::new(temp.path()), "bench", 5000);
+    let sort = Sort::ByField {
+        field: "price".to_string(),
+        order: SortOrder::Asc,
+    };
+
+    for (temperature, cold) in [("cold", true), ("warm", false)] {
+        let mut group = c.benchmark_group(format!("{}_reads", temperature));
+        for (mode, env) in MODES {
+            group.bench_function(format!("{}/doc_store_100_hits", mode), |b| {
+                b.iter_batched(
+                    || open(temp.path(), env, cold),
+                    |manager| manager.search("bench", "laptop", None, None, 100).unwrap(),
+                    BatchSize::PerIteration,
+                )
+            });
+            group.bench_function(format!("{}/fast_field_sort", mode), |b| {
+                b.iter_batched(
+                    || open(temp.path(), env, cold),
+                    |manager| {
+                        manager
+                            .search("bench", "laptop", None, Some(&sort), 10)
+                            .unwrap()
+                    },
+                    BatchSize::PerIteration,
+                )
+            });
+        }
+        group.finish();
+    }
+    std::env::remove_var("FLAPJACK_IO_URING");
+}
+
+criterion_group!(benches, read_paths);
+criterion_main!(benches);
@@ -134,6 +134,8 @@ export interface ExperimentResultsResponse {
   bayesian: { probVariantBetter: number } | null;
   sampleRatioMismatch: boolean;
   cupedApplied: boolean;
+  cupedCovariates: string[];
+  cupedVarianceReduction: number | null;
   guardRailAlerts: GuardRailAlertResponse[];
   outlierUsersExcluded: number;
   noStableIdQueries: number;
@@ -70,6 +70,9 @@ export interface EmbedderConfig {
   headers?: Record<string, string>;
   documentTemplate?: string;
   documentTemplateMaxBytes?: number;
+  maxBatchSize?: number;
+  requestsPerMinute?: number;
+  costPerMillionTokens?: number;
 }
 
 export type IndexMode = 'neuralSearch' | 'keywordSearch';
@@ -112,6 +112,8 @@ function mockResults(overrides: Record<string, unknown> = {}) {
     bayesian: { probVariantBetter: 0.78 },
     sampleRatioMismatch: false,
     cupedApplied: false,
+    cupedCovariates: [],
+    cupedVarianceReduction: null,
     guardRailAlerts: [],
     outlierUsersExcluded: 0,
     noStableIdQueries: 0,
@@ -273,11 +275,17 @@ describe('ExperimentDetail', () => {
         winner: 'variant',
       },
       cupedApplied: true,
+      cupedCovariates: ['ctr', 'clicks'],
+      cupedVarianceReduction: 0.31,
     });
     renderWithRoute('exp-1');
 
     expect(screen.getByTestId('cuped-badge')).toBeInTheDocument();
     expect(screen.getByTestId('cuped-badge')).toHaveTextContent('CUPED');
+    expect(screen.getByTestId('cuped-badge')).toHaveAttribute(
+      'title',
+      'Covariates: ctr, clicks (variance -31.0%)',
+    );
   });
 
   it('does not show CUPED badge when cupedApplied is false', () => {
@@ -580,6 +580,11 @@ export function ExperimentDetail() {
                 variant="outline"
                 className="border-emerald-300 bg-emerald-50 text-emerald-800"
                 data-testid="cuped-badge"
+                title={`Covariates: ${results.cupedCovariates.join(', ')}${
+                  results.cupedVarianceReduction != null
+                    ? ` (variance -${(results.cupedVarianceReduction * 100).toFixed(1)}%)`
+                    : ''
+                }`}
               >
                 CUPED
               </Badge>
@@ -3,19 +3,81 @@
 //! When peers are configured, queries all peers in parallel and merges results.
 //! Each peer receives the same analytics query with `X-Flapjack-Local-Only: true`
 //! to prevent re-entrant fan-out.
+//!
+//! A slow or dead peer never fails the whole query: each peer gets
+//! `FLAPJACK_ANALYTICS_PEER_TIMEOUT_MS` (default 5000) to answer, and the
+//! response is merged from the nodes that did, marked `degraded: true` with
+//! the missing nodes listed under `cluster.failed_nodes`. Partial responses
+//! are counted in `/metrics`.
 
 use flapjack::analytics::merge;
 use flapjack::analytics::types::{ClusterMetadata, NodeDetail, NodeStatus, PeerResult};
 use flapjack_replication::config::{NodeConfig, PeerConfig};
 use serde_json::json;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+const DEFAULT_PEER_TIMEOUT_MS: u64 = 5000;
+
+fn peer_timeout_from_env() -> Duration {
+    Duration::from_millis(
+        std::env::var("FLAPJACK_ANALYTICS_PEER_TIMEOUT_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&ms| ms > 0)
+            .unwrap_or(DEFAULT_PEER_TIMEOUT_MS),
+    )
+}
+
+/// Fan-out outcomes since startup, exported by `/metrics`.
+#[derive(Default)]
+pub struct FanOutStats {
+    queries: AtomicU64,
+    partial: AtomicU64,
+    /// Failed peer queries by node ID.
+    peer_failures: dashmap::DashMap<String, u64>,
+}
+
+impl FanOutStats {
+    pub fn global() -> &'static FanOutStats {
+        static STATS: OnceCell<FanOutStats> = OnceCell::new();
+        STATS.get_or_init(FanOutStats::default)
+    }
+
+    fn record(&self, failed_nodes: &[String]) {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        if !failed_nodes.is_empty() {
+            self.partial.fetch_add(1, Ordering::Relaxed);
+        }
+        for node_id in failed_nodes {
+            *self.peer_failures.entry(node_id.clone()).or_insert(0) += 1;
+        }
+    }
+
+    pub fn queries(&self) -> u64 {
+        self.queries.load(Ordering::Relaxed)
+    }
+
+    pub fn partial(&self) -> u64 {
+        self.partial.load(Ordering::Relaxed)
+    }
+
+    pub fn peer_failures(&self) -> Vec<(String, u64)> {
+        self.peer_failures
+            .iter()
+            .map(|e| (e.key().clone(), *e.value()))
+            .collect()
+    }
+}
+
 /// Fan-out coordinator for cluster analytics queries.
 pub struct AnalyticsClusterClient {
     node_id: String,
     peers: Vec<PeerConfig>,
     http_client: reqwest::Client,
+    /// How long each peer has to answer a fan-out query.
+    peer_timeout: Duration,
 }
 
 impl AnalyticsClusterClient {
@@ -35,6 +97,7 @@ impl AnalyticsClusterClient {
             node_id: node_config.node_id.clone(),
             peers: node_config.peers.clone(),
             http_client,
+            peer_timeout: peer_timeout_from_env(),
         }))
     }
 
@@ -86,10 +149,14 @@ impl AnalyticsClusterClient {
             let peer_id = peer.node_id.clone();
             let api_key = api_key.clone();
             let app_id = app_id.clone();
+            let peer_timeout = self.peer_timeout;
 
             handles.push(tokio::spawn(async move {
                 let start = Instant::now();
-                let mut req = client.get(&url).header("X-Flapjack-Local-Only", "true");
+                let mut req = client
+                    .get(&url)
+                    .timeout(peer_timeout)
+                    .header("X-Flapjack-Local-Only", "true");
                 if let Some(key) = &api_key {
                     req = req.header("X-Algolia-API-Key", key);
                 }
@@ -220,6 +287,7 @@ impl AnalyticsClusterClient {
 
         // Collect all results: local + successful peers
         let mut all_results = vec![local_result];
+        let mut failed_nodes = Vec::new();
         let mut node_details = vec![NodeDetail {
             node_id: self.node_id.clone(),
             status: NodeStatus::Ok,
@@ -237,6 +305,7 @@ impl AnalyticsClusterClient {
                     });
                 }
                 Err(err) => {
+                    failed_nodes.push(pr.node_id.clone());
                     let status = if err == "circuit_breaker_open" {
                         NodeStatus::Skipped
                     } else if err == "timeout" {
@@ -257,16 +326,25 @@ impl AnalyticsClusterClient {
             }
         }
 
+        // Peers whose task panicked never produced a result
+        for peer in &self.peers {
+            if !peer_results.iter().any(|pr| pr.node_id == peer.node_id) {
+                failed_nodes.push(peer.node_id.clone());
+            }
+        }
+
         let nodes_total = 1 + self.peers.len();
         let nodes_responding = all_results.len();
         let partial = nodes_responding < nodes_total;
+        FanOutStats::global().record(&failed_nodes);
 
         if partial {
             tracing::warn!(
-                "[HA-analytics] partial results: {}/{} nodes for {}",
+                "[HA-analytics] partial results: {}/{} nodes for {} (failed: {})",
                 nodes_responding,
                 nodes_total,
-                endpoint
+                endpoint,
+                failed_nodes.join(", ")
             );
         }
 
@@ -276,10 +354,14 @@ impl AnalyticsClusterClient {
             nodes_total,
             nodes_responding,
             partial,
+            failed_nodes,
             node_details,
         };
 
         if let Some(obj) = merged.as_object_mut() {
+            if partial {
+                obj.insert("degraded".to_string(), json!(true));
+            }
             obj.insert(
                 "cluster".to_string(),
                 serde_json::to_value(&cluster_meta).unwrap_or(json!(null)),
@@ -378,6 +460,67 @@ mod tests {
         };
         assert!(matches!(status, NodeStatus::Error(ref s) if s == "connection refused"));
     }
+
+    #[tokio::test]
+    async fn slow_peer_yields_degraded_partial_result() {
+        let fast = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let fast_addr = fast.local_addr().unwrap();
+        let app = axum::Router::new().route(
+            "/2/searches/count",
+            axum::routing::get(|| async { axum::Json(json!({"count": 5, "dates": []})) }),
+        );
+        tokio::spawn(async move { axum::serve(fast, app).await.unwrap() });
+
+        // Accepts connections but never answers
+        let slow = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let slow_addr = slow.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = slow.accept().await {
+                held.push(socket);
+            }
+        });
+
+        let client = AnalyticsClusterClient {
+            node_id: "node-a".to_string(),
+            peers: vec![
+                PeerConfig {
+                    node_id: "node-fast".to_string(),
+                    addr: format!("http://{}", fast_addr),
+                },
+                PeerConfig {
+                    node_id: "node-slow".to_string(),
+                    addr: format!("http://{}", slow_addr),
+                },
+            ],
+            http_client: reqwest::Client::new(),
+            peer_timeout: Duration::from_millis(200),
+        };
+        let partial_before = FanOutStats::global().partial();
+
+        let start = Instant::now();
+        let merged = client
+            .fan_out_and_merge(
+                "searches/count",
+                "/2/searches/count",
+                "",
+                json!({"count": 3, "dates": []}),
+                10,
+                &axum::http::HeaderMap::new(),
+            )
+            .await;
+        assert!(start.elapsed() < Duration::from_secs(2));
+
+        assert_eq!(merged["count"], 8);
+        assert_eq!(merged["degraded"], true);
+        assert_eq!(merged["cluster"]["partial"], true);
+        assert_eq!(merged["cluster"]["nodes_responding"], 2);
+        assert_eq!(merged["cluster"]["failed_nodes"], json!(["node-slow"]));
+        assert!(FanOutStats::global().partial() > partial_before);
+        assert!(FanOutStats::global()
+            .peer_failures()
+            .contains(&("node-slow".to_string(), 1)));
+    }
 }
 
 // ── Phase 4: Analytics Rollup Exchange (HA Analytics Tier 2) ─────────────────
@@ -0,0 +1,212 @@
+//! Signed anonymous user tokens.
+//!
+//! `POST /1/users/token` issues tokens of the form
+//! `anon-<32 hex id>-<expiry unix secs>-<32 hex signature>`, which fit the
+//! Insights API userToken charset. The token is also set as the
+//! `flapjack_anon_token` cookie so browsers send it back on every search;
+//! searches without an `x-algolia-usertoken` header then use the cookie token
+//! for analytics and experiment assignment instead of falling back to the
+//! per-query ID.
+//!
+//! Tokens are signed with HMAC-SHA256 using `FLAPJACK_USER_TOKEN_SECRET`, or a
+//! random secret generated once and stored in `{data_dir}/.user_token_secret`.
+//! Multi-node deployments should set the env var so every node accepts the
+//! same tokens.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use dashmap::DashMap;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+pub const TOKEN_PREFIX: &str = "anon-";
+pub const COOKIE_NAME: &str = "flapjack_anon_token";
+pub const SECRET_FILE: &str = ".user_token_secret";
+pub const DEFAULT_TTL_SECS: u64 = 30 * 24 * 60 * 60;
+pub const MAX_TTL_SECS: u64 = 365 * 24 * 60 * 60;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub struct AnonymousTokenIssuer {
+    secret: Vec<u8>,
+}
+
+static ISSUERS: OnceLock<DashMap<PathBuf, Arc<AnonymousTokenIssuer>>> = OnceLock::new();
+
+impl AnonymousTokenIssuer {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            secret: secret.to_vec(),
+        }
+    }
+
+    /// The issuer for a data directory, loading or creating its secret once.
+    pub fn for_data_dir(data_dir: &Path) -> std::io::Result<Arc<Self>> {
+        let issuers = ISSUERS.get_or_init(DashMap::new);
+        if let Some(issuer) = issuers.get(data_dir) {
+            return Ok(Arc::clone(&issuer));
+        }
+        let issuer = Arc::new(Self::new(&load_or_create_secret(data_dir)?));
+        Ok(Arc::clone(
+            &issuers.entry(data_dir.to_path_buf()).or_insert(issuer),
+        ))
+    }
+
+    fn mac(&self, id: &str, expires_at: i64) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(format!("{}{}-{}", TOKEN_PREFIX, id, expires_at).as_bytes());
+        mac
+    }
+
+    /// Issue a token valid for `ttl_secs` from `now_secs`.
+    /// Returns the token and its expiry (unix seconds).
+    pub fn issue(&self, ttl_secs: u64, now_secs: i64) -> (String, i64) {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let expires_at = now_secs + ttl_secs.min(MAX_TTL_SECS) as i64;
+        let signature = hex::encode(&self.mac(&id, expires_at).finalize().into_bytes()[..16]);
+        (
+            format!("{}{}-{}-{}", TOKEN_PREFIX, id, expires_at, signature),
+            expires_at,
+        )
+    }
+
+    /// True if `token` was issued by this issuer and has not expired.
+    pub fn verify(&self, token: &str, now_secs: i64) -> bool {
+        let Some(rest) = token.strip_prefix(TOKEN_PREFIX) else {
+            return false;
+        };
+        let mut parts = rest.split('-');
+        let (Some(id), Some(expires_at), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return false;
+        };
+        let Ok(expires_at) = expires_at.parse::<i64>() else {
+            return false;
+        };
+        if now_secs >= expires_at {
+            return false;
+        }
+        let Ok(signature) = hex::decode(signature) else {
+            return false;
+        };
+        signature.len() == 16
+            && self
+                .mac(id, expires_at)
+                .verify_truncated_left(&signature)
+                .is_ok()
+    }
+}
+
+fn load_or_create_secret(data_dir: &Path) -> std::io::Result<Vec<u8>> {
+    if let Ok(secret) = std::env::var("FLAPJACK_USER_TOKEN_SECRET") {
+        if !secret.is_empty() {
+            return Ok(secret.into_bytes());
+        }
+    }
+    let path = data_dir.join(SECRET_FILE);
+    if let Ok(existing) = std::fs::read_to_string(&path) {
+        if let Ok(secret) = hex::decode(existing.trim()) {
+            if !secret.is_empty() {
+                return Ok(secret);
+            }
+        }
+    }
+    let secret: [u8; 32] = rand::random();
+    std::fs::create_dir_all(data_dir)?;
+    std::fs::write(&path, hex::encode(secret))?;
+    Ok(secret.to_vec())
+}
+
+/// `Set-Cookie` value for an issued token.
+pub fn cookie_header(token: &str, ttl_secs: u64) -> String {
+    format!(
+        "{}={}; Max-Age={}; Path=/; SameSite=Lax",
+        COOKIE_NAME, token, ttl_secs
+    )
+}
+
+/// The anonymous token from the request's `Cookie` header, if present.
+/// The token is not verified.
+pub fn token_from_cookie(headers: &axum::http::HeaderMap) -> Option<String> {
+    headers
+        .get_all(axum::http::header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == COOKIE_NAME)
+        .map(|(_, value)| value.to_string())
+}
+
+/// A verified anonymous token from the request cookie, for use as userToken
+/// when the client did not send one.
+pub fn verified_cookie_token(headers: &axum::http::HeaderMap, data_dir: &Path) -> Option<String> {
+    let token = token_from_cookie(headers)?;
+    let issuer = AnonymousTokenIssuer::for_data_dir(data_dir).ok()?;
+    issuer
+        .verify(&token, chrono::Utc::now().timestamp())
+        .then_some(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issued_tokens_verify_until_expiry() {
+        let issuer = AnonymousTokenIssuer::new(b"secret");
+        let (token, expires_at) = issuer.issue(60, 1_000);
+        assert_eq!(expires_at, 1_060);
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert!(token.len() <= 129);
+        assert!(token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+
+        assert!(issuer.verify(&token, 1_059));
+        assert!(!issuer.verify(&token, 1_060));
+    }
+
+    #[test]
+    fn tampered_or_foreign_tokens_are_rejected() {
+        let issuer = AnonymousTokenIssuer::new(b"secret");
+        let (token, _) = issuer.issue(60, 1_000);
+
+        assert!(!AnonymousTokenIssuer::new(b"other").verify(&token, 1_000));
+        let extended = token.replace("-1060-", "-9999999999-");
+        assert!(!issuer.verify(&extended, 1_000));
+        assert!(!issuer.verify("user-42", 1_000));
+        assert!(!issuer.verify("anon-abc-1060", 1_000));
+        let truncated = &token[..token.len() - 2];
+        assert!(!issuer.verify(truncated, 1_000));
+    }
+
+    #[test]
+    fn ttl_is_capped() {
+        let issuer = AnonymousTokenIssuer::new(b"secret");
+        let (_, expires_at) = issuer.issue(u64::MAX, 0);
+        assert_eq!(expires_at, MAX_TTL_SECS as i64);
+    }
+
+    #[test]
+    fn secret_is_persisted_per_data_dir() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let first = load_or_create_secret(tmp.path()).unwrap();
+        assert_eq!(load_or_create_secret(tmp.path()).unwrap(), first);
+        assert!(tmp.path().join(SECRET_FILE).exists());
+    }
+
+    #[test]
+    fn cookie_token_is_extracted() {
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(
+            axum::http::header::COOKIE,
+            "theme=dark; flapjack_anon_token=anon-abc; other=1"
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(token_from_cookie(&headers).as_deref(), Some("anon-abc"));
+        assert_eq!(token_from_cookie(&axum::http::HeaderMap::new()), None);
+    }
+}
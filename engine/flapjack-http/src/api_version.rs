@@ -0,0 +1,205 @@
+//! Search response compatibility modes.
+//!
+//! Clients choose how search responses are shaped with the
+//! `X-Flapjack-API-Version` header or the `apiVersion` query parameter (the
+//! parameter wins when both are sent):
+//!
+//! - `native` (default) — everything Flapjack returns, including fields
+//!   Algolia does not have, such as the hybrid score breakdown in
+//!   `_rankingInfo`, `didYouMean` or `interleavedTeams`.
+//! - `algolia` — only the fields of Algolia's search response, for SDKs whose
+//!   parsers reject unknown fields. This is an allow-list, so fields added to
+//!   the native response later never show up in this mode.
+//!
+//! `FLAPJACK_DEFAULT_API_VERSION` sets the mode for clients that send
+//! neither, e.g. to serve an unmodified Algolia SDK in strict mode.
+
+use std::sync::OnceLock;
+
+use axum::http::HeaderMap;
+use flapjack::error::FlapjackError;
+
+pub const API_VERSION_HEADER: &str = "x-flapjack-api-version";
+pub const API_VERSION_PARAM: &str = "apiVersion";
+
+/// Top-level fields of Algolia's search response.
+const ALGOLIA_RESPONSE_FIELDS: &[&str] = &[
+    "abTestID",
+    "abTestVariantID",
+    "aroundLatLng",
+    "automaticRadius",
+    "exhaustive",
+    "appliedRules",
+    "exhaustiveFacetsCount",
+    "exhaustiveNbHits",
+    "exhaustiveTypo",
+    "facets",
+    "facets_stats",
+    "index",
+    "indexUsed",
+    "message",
+    "nbSortedHits",
+    "parsedQuery",
+    "processingTimeMS",
+    "processingTimingsMS",
+    "queryAfterRemoval",
+    "redirect",
+    "renderingContent",
+    "serverTimeMS",
+    "serverUsed",
+    "userData",
+    "queryID",
+    "page",
+    "nbHits",
+    "nbPages",
+    "hitsPerPage",
+    "hits",
+    "query",
+    "params",
+    "cursor",
+];
+
+/// Fields of Algolia's per-hit `_rankingInfo`.
+const ALGOLIA_RANKING_INFO_FIELDS: &[&str] = &[
+    "filters",
+    "firstMatchedWord",
+    "geoDistance",
+    "geoPrecision",
+    "matchedGeoLocation",
+    "personalization",
+    "nbExactWords",
+    "nbTypos",
+    "promoted",
+    "proximityDistance",
+    "userScore",
+    "words",
+    "promotedByReRanking",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ApiVersion {
+    #[default]
+    Native,
+    Algolia,
+}
+
+impl ApiVersion {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "native" => Some(ApiVersion::Native),
+            "algolia" => Some(ApiVersion::Algolia),
+            _ => None,
+        }
+    }
+
+    /// The server-wide default from `FLAPJACK_DEFAULT_API_VERSION`; an
+    /// unknown value is logged once and ignored.
+    pub fn server_default() -> Self {
+        static DEFAULT: OnceLock<ApiVersion> = OnceLock::new();
+        *DEFAULT.get_or_init(|| match std::env::var("FLAPJACK_DEFAULT_API_VERSION") {
+            Ok(value) => ApiVersion::parse(&value).unwrap_or_else(|| {
+                tracing::warn!(
+                    "FLAPJACK_DEFAULT_API_VERSION={} is not 'native' or 'algolia', using native",
+                    value
+                );
+                ApiVersion::Native
+            }),
+            Err(_) => ApiVersion::Native,
+        })
+    }
+
+    /// The mode a request asked for, from its query string and headers.
+    pub fn from_request(headers: &HeaderMap, query: Option<&str>) -> Result<Self, FlapjackError> {
+        let param = query.and_then(|q| {
+            url::form_urlencoded::parse(q.as_bytes())
+                .find(|(k, _)| k == API_VERSION_PARAM)
+                .map(|(_, v)| v.into_owned())
+        });
+        let requested = match param {
+            Some(value) => Some(value),
+            None => headers
+                .get(API_VERSION_HEADER)
+                .map(|v| v.to_str().unwrap_or_default().to_string()),
+        };
+        match requested {
+            Some(value) => ApiVersion::parse(&value).ok_or_else(|| {
+                FlapjackError::InvalidQuery(format!(
+                    "Unsupported API version '{}': expected 'native' or 'algolia'",
+                    value
+                ))
+            }),
+            None => Ok(ApiVersion::server_default()),
+        }
+    }
+
+    /// Bring a search response in line with this mode.
+    pub fn shape_search_response(self, response: &mut serde_json::Value) {
+        if self == ApiVersion::Native {
+            return;
+        }
+        let Some(obj) = response.as_object_mut() else {
+            return;
+        };
+        obj.retain(|key, _| ALGOLIA_RESPONSE_FIELDS.contains(&key.as_str()));
+        if let Some(serde_json::Value::Array(hits)) = obj.get_mut("hits") {
+            for hit in hits {
+                if let Some(serde_json::Value::Object(info)) = hit.get_mut("_rankingInfo") {
+                    info.retain(|key, _| ALGOLIA_RANKING_INFO_FIELDS.contains(&key.as_str()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn query_param_wins_over_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(API_VERSION_HEADER, "native".parse().unwrap());
+        assert_eq!(
+            ApiVersion::from_request(&headers, Some("x=1&apiVersion=Algolia")).unwrap(),
+            ApiVersion::Algolia
+        );
+        assert_eq!(
+            ApiVersion::from_request(&headers, None).unwrap(),
+            ApiVersion::Native
+        );
+        headers.insert(API_VERSION_HEADER, "v9".parse().unwrap());
+        assert!(ApiVersion::from_request(&headers, None).is_err());
+    }
+
+    #[test]
+    fn algolia_mode_keeps_only_algolia_fields() {
+        let native = json!({
+            "hits": [{
+                "objectID": "1",
+                "didYouMean": "kept, it is a record attribute",
+                "_rankingInfo": {"nbTypos": 0, "semanticScore": 0.9, "retrievedBy": "both"}
+            }],
+            "nbHits": 1,
+            "didYouMean": ["shoes"],
+            "interleavedTeams": {"1": "control"}
+        });
+
+        let mut response = native.clone();
+        ApiVersion::Native.shape_search_response(&mut response);
+        assert_eq!(response, native);
+
+        ApiVersion::Algolia.shape_search_response(&mut response);
+        assert_eq!(
+            response,
+            json!({
+                "hits": [{
+                    "objectID": "1",
+                    "didYouMean": "kept, it is a record attribute",
+                    "_rankingInfo": {"nbTypos": 0}
+                }],
+                "nbHits": 1
+            })
+        );
+    }
+}
@@ -1,18 +1,23 @@
 use axum::{
     extract::Request,
-    http::{Method, StatusCode},
+    http::{header, Method},
     middleware::Next,
-    response::{IntoResponse, Response},
+    response::Response,
 };
 use serde::{Deserialize, Serialize};
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use chrono::Utc;
+use flapjack::index::namespace::parse_namespaced_tenant;
+use flapjack::ErrorCode;
 use hmac::{Hmac, Mac};
 use rand::Rng;
 use sha2::Sha256;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::RwLock;
+use std::sync::{OnceLock, RwLock};
+
+use crate::key_monitor::{report_anomalies, AuthFailure, KeyMonitor};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiKey {
@@ -43,6 +48,73 @@ pub struct ApiKey {
     pub referers: Vec<String>,
     #[serde(default)]
     pub validity: i64,
+    /// Free-form attributes exposed to secured-key filter templates as
+    /// `{key.<name>}`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, String>,
+    /// Hard expiry (ms since epoch) set when the key is rotated out; the key
+    /// keeps working until then so clients can switch over.
+    #[serde(default, rename = "expiresAt", skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    /// When non-empty, the only attributes this key can see in hits,
+    /// highlights and facets, or use in filters (`objectID` is always kept).
+    #[serde(
+        default,
+        rename = "allowedAttributes",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub allowed_attributes: Vec<String>,
+    /// Attributes this key can never see or filter on. Nested paths
+    /// (`pricing.cost`) are supported and win over `allowedAttributes`.
+    #[serde(
+        default,
+        rename = "deniedAttributes",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub denied_attributes: Vec<String>,
+    /// When non-empty, browser requests (those with an `Origin` header) are
+    /// only accepted from these origins; one `*` wildcard is allowed.
+    #[serde(
+        default,
+        rename = "allowedOrigins",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub allowed_origins: Vec<String>,
+    /// Tenant namespace the key is confined to. Its requests name that
+    /// namespace's indexes without the prefix and reach no other index.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+}
+
+impl ApiKey {
+    /// True once either `validity` or a rotation grace window has elapsed.
+    pub fn is_expired(&self, now_ms: i64) -> bool {
+        if self.validity > 0 && now_ms > self.created_at + self.validity * 1000 {
+            return true;
+        }
+        self.expires_at.is_some_and(|at| now_ms >= at)
+    }
+}
+
+/// Maximum number of entries kept in `KeyStoreData::audit_log`.
+const MAX_AUDIT_EVENTS: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyAuditEvent {
+    /// Event kind, e.g. `"rotate"`.
+    pub event: String,
+    /// Milliseconds since epoch.
+    pub timestamp: i64,
+    pub description: String,
+    /// Hash of the key that was rotated out.
+    pub key_hash: String,
+    /// Hash of the replacement key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_key_hash: Option<String>,
+    /// When the old key stops working (ms since epoch).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -50,28 +122,35 @@ pub struct KeyStoreData {
     pub keys: Vec<ApiKey>,
     #[serde(default)]
     pub deleted_keys: Vec<ApiKey>,
+    #[serde(default)]
+    pub audit_log: Vec<KeyAuditEvent>,
 }
 
 pub struct KeyStore {
     data: RwLock<KeyStoreData>,
     file_path: PathBuf,
     admin_key_value: String,
+    /// Why an existing keys.json could not be loaded, if it couldn't.
+    load_error: Option<String>,
 }
 
 impl KeyStore {
     pub fn load_or_create(data_dir: &Path, admin_key: &str) -> Self {
         let file_path = data_dir.join("keys.json");
+        let mut load_error = None;
         let mut data = if file_path.exists() {
             match std::fs::read_to_string(&file_path) {
                 Ok(contents) => match serde_json::from_str::<KeyStoreData>(&contents) {
                     Ok(d) => d,
                     Err(e) => {
                         tracing::warn!("Failed to parse keys.json, recreating: {}", e);
+                        load_error = Some(format!("failed to parse keys.json: {}", e));
                         Self::create_default_keys(admin_key)
                     }
                 },
                 Err(e) => {
                     tracing::warn!("Failed to read keys.json, recreating: {}", e);
+                    load_error = Some(format!("failed to read keys.json: {}", e));
                     Self::create_default_keys(admin_key)
                 }
             }
@@ -100,11 +179,18 @@ impl KeyStore {
             data: RwLock::new(data),
             file_path,
             admin_key_value: admin_key.to_string(),
+            load_error,
         };
         store.save();
         store
     }
 
+    /// Set when keys.json existed but could not be loaded at startup, so
+    /// only the default keys are known.
+    pub fn load_error(&self) -> Option<&str> {
+        self.load_error.as_deref()
+    }
+
     fn create_default_keys(admin_key: &str) -> KeyStoreData {
         let now = Utc::now().timestamp_millis();
         let all_acls = vec![
@@ -141,6 +227,12 @@ impl KeyStore {
             query_parameters: String::new(),
             referers: vec![],
             validity: 0,
+            metadata: HashMap::new(),
+            expires_at: None,
+            allowed_attributes: Vec::new(),
+            denied_attributes: Vec::new(),
+            allowed_origins: Vec::new(),
+            namespace: None,
         };
 
         let search_key_value = format!("fj_search_{}", generate_hex_key());
@@ -160,11 +252,18 @@ impl KeyStore {
             query_parameters: String::new(),
             referers: vec![],
             validity: 0,
+            metadata: HashMap::new(),
+            expires_at: None,
+            allowed_attributes: Vec::new(),
+            denied_attributes: Vec::new(),
+            allowed_origins: Vec::new(),
+            namespace: None,
         };
 
         KeyStoreData {
             keys: vec![admin, search_key],
             deleted_keys: vec![],
+            audit_log: vec![],
         }
     }
 
@@ -229,6 +328,7 @@ impl KeyStore {
             updated.hash = existing.hash.clone();
             updated.salt = existing.salt.clone();
             updated.created_at = existing.created_at;
+            updated.expires_at = existing.expires_at;
             *existing = updated.clone();
             drop(data);
             self.save();
@@ -281,11 +381,280 @@ impl KeyStore {
         }
     }
 
+    /// Issue a replacement for `key_value` with the same configuration. The
+    /// old key stays valid for `grace_period_secs` (0 retires it immediately)
+    /// and the rotation is recorded in the audit log. Returns the new key, its
+    /// plaintext value and the old key's expiry (ms). The admin key cannot be
+    /// rotated here; it follows `FLAPJACK_ADMIN_KEY`.
+    pub fn rotate_key(
+        &self,
+        key_value: &str,
+        grace_period_secs: u64,
+    ) -> Option<(ApiKey, String, i64)> {
+        let now = Utc::now().timestamp_millis();
+        let mut data = self.data.write().unwrap();
+        let pos = data
+            .keys
+            .iter()
+            .position(|k| verify_key(key_value, &k.hash, &k.salt))?;
+        if data.keys[pos].description == "Admin API Key" || data.keys[pos].is_expired(now) {
+            return None;
+        }
+
+        let plaintext_value = format!("fj_search_{}", generate_hex_key());
+        let salt = generate_salt();
+        let mut replacement = data.keys[pos].clone();
+        replacement.hash = hash_key(&plaintext_value, &salt);
+        replacement.salt = salt;
+        replacement.created_at = now;
+        replacement.hmac_key = Some(plaintext_value.clone());
+        replacement.expires_at = None;
+
+        let grace_ms = (grace_period_secs as i64).saturating_mul(1000);
+        let expires_at = now.saturating_add(grace_ms);
+        let old = &mut data.keys[pos];
+        // Rotating twice must not extend an already-scheduled expiry.
+        old.expires_at = Some(old.expires_at.map_or(expires_at, |at| at.min(expires_at)));
+        let event = KeyAuditEvent {
+            event: "rotate".to_string(),
+            timestamp: now,
+            description: old.description.clone(),
+            key_hash: old.hash.clone(),
+            new_key_hash: Some(replacement.hash.clone()),
+            expires_at: old.expires_at,
+        };
+        let old_expires_at = old.expires_at.unwrap_or(expires_at);
+
+        data.keys.push(replacement.clone());
+        Self::retire_expired(&mut data, now);
+        data.audit_log.push(event);
+        let overflow = data.audit_log.len().saturating_sub(MAX_AUDIT_EVENTS);
+        data.audit_log.drain(..overflow);
+        drop(data);
+        self.save();
+
+        Some((replacement, plaintext_value, old_expires_at))
+    }
+
+    /// Keys rotated out but still inside their grace window.
+    pub fn pending_expiry(&self) -> Vec<ApiKey> {
+        let now = Utc::now().timestamp_millis();
+        let data = self.data.read().unwrap();
+        data.keys
+            .iter()
+            .filter(|k| k.expires_at.is_some() && !k.is_expired(now))
+            .cloned()
+            .collect()
+    }
+
+    /// A deleted key, by value.
+    pub fn lookup_deleted(&self, key_value: &str) -> Option<ApiKey> {
+        let data = self.data.read().unwrap();
+        data.deleted_keys
+            .iter()
+            .find(|k| verify_key(key_value, &k.hash, &k.salt))
+            .cloned()
+    }
+
+    pub fn audit_log(&self) -> Vec<KeyAuditEvent> {
+        self.data.read().unwrap().audit_log.clone()
+    }
+
+    /// Move keys whose rotation grace window has passed to `deleted_keys`.
+    fn retire_expired(data: &mut KeyStoreData, now: i64) {
+        let (expired, live): (Vec<ApiKey>, Vec<ApiKey>) = std::mem::take(&mut data.keys)
+            .into_iter()
+            .partition(|k| k.is_expired(now) && k.expires_at.is_some());
+        data.keys = live;
+        data.deleted_keys.extend(expired);
+    }
+
     pub fn admin_key_value(&self) -> &str {
         &self.admin_key_value
     }
 }
 
+/// Value of the authenticated key (the parent key for secured keys; the
+/// admin key's hash), inserted into request extensions on every
+/// authenticated request.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedKey(pub String);
+
+/// Indexes the authenticated key may read: its own `indexes` and, for a
+/// secured key, `restrictIndices`. Inserted into request extensions for the
+/// handlers that take index names from the body, like `getObjects`.
+#[derive(Debug, Clone, Default)]
+pub struct IndexScope {
+    pub indexes: Vec<String>,
+    pub restrict_indices: Option<Vec<String>>,
+    /// Body index names of namespace keys arrive qualified with it.
+    pub namespace: Option<String>,
+}
+
+impl IndexScope {
+    pub fn allows(&self, index_name: &str) -> bool {
+        let index_name = match &self.namespace {
+            Some(namespace) => match parse_namespaced_tenant(index_name) {
+                Some((ns, name)) if ns == namespace => name,
+                _ => return false,
+            },
+            None => index_name,
+        };
+        index_pattern_matches(&self.indexes, index_name)
+            && self
+                .restrict_indices
+                .as_ref()
+                .is_none_or(|r| index_pattern_matches(r, index_name))
+    }
+}
+
+/// Namespace of a namespace-scoped key, inserted into request extensions.
+/// `admin` is set when the key also has the `admin` ACL, which lets it
+/// manage the keys of its own namespace.
+#[derive(Debug, Clone)]
+pub struct KeyNamespace {
+    pub name: String,
+    pub admin: bool,
+}
+
+impl KeyNamespace {
+    fn for_key(key: &ApiKey) -> Option<Self> {
+        Some(Self {
+            name: key.namespace.clone()?,
+            admin: key.acl.iter().any(|a| a == "admin"),
+        })
+    }
+
+    /// Admin-ACL routes a namespace admin key may use: key management
+    /// (scoped to the namespace by the handlers) and its own namespace.
+    fn admin_allows(&self, method: &Method, path: &str) -> bool {
+        self.admin
+            && ((path.starts_with("/1/keys") && path != "/1/keys/audit")
+                || (*method == Method::GET
+                    && path.strip_prefix("/1/namespaces/") == Some(self.name.as_str())))
+    }
+}
+
+/// Attribute visibility for the authenticated key, from its
+/// `allowedAttributes`/`deniedAttributes`. Inserted into request extensions
+/// only when the key restricts something; handlers apply it to hits,
+/// highlights, snippets, facets and filters.
+#[derive(Debug, Clone, Default)]
+pub struct AttributeRestrictions {
+    pub allowed: Vec<String>,
+    pub denied: Vec<String>,
+}
+
+impl AttributeRestrictions {
+    pub fn for_key(key: &ApiKey) -> Option<Self> {
+        if key.allowed_attributes.is_empty() && key.denied_attributes.is_empty() {
+            return None;
+        }
+        Some(Self {
+            allowed: key.allowed_attributes.clone(),
+            denied: key.denied_attributes.clone(),
+        })
+    }
+
+    /// Whether the key may see (and filter on) `path`.
+    pub fn is_visible(&self, path: &str) -> bool {
+        if path == "objectID" {
+            return true;
+        }
+        if self.denied.iter().any(|d| path_covers(d, path)) {
+            return false;
+        }
+        self.allowed.is_empty() || self.allowed.iter().any(|a| path_covers(a, path))
+    }
+
+    /// Remove everything the key may not see from a hit or from its
+    /// `_highlightResult`/`_snippetResult` object.
+    pub fn strip(&self, object: &mut serde_json::Map<String, serde_json::Value>) {
+        self.strip_at("", object);
+    }
+
+    fn strip_at(&self, prefix: &str, object: &mut serde_json::Map<String, serde_json::Value>) {
+        object.retain(|key, value| {
+            let path = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            let visible = self.is_visible(&path);
+            let denied_below = self
+                .denied
+                .iter()
+                .any(|d| path_covers(&path, d) && *d != path);
+            let allowed_below = self
+                .allowed
+                .iter()
+                .any(|a| path_covers(&path, a) && *a != path);
+            if visible && !denied_below {
+                return true;
+            }
+            if !visible && !allowed_below {
+                return false;
+            }
+            match value {
+                serde_json::Value::Object(child) => self.strip_at(&path, child),
+                serde_json::Value::Array(items) => {
+                    for item in items.iter_mut() {
+                        if let serde_json::Value::Object(child) = item {
+                            self.strip_at(&path, child);
+                        }
+                    }
+                }
+                // A scalar where the key was only allowed a nested field.
+                _ => return visible,
+            }
+            true
+        });
+    }
+
+    /// Reject a filter that mentions an attribute the key may not see;
+    /// otherwise filtering would reveal hidden values one probe at a time.
+    pub fn check_filter(&self, filter: &flapjack::types::Filter) -> Result<(), String> {
+        use flapjack::types::Filter;
+        match filter {
+            Filter::Equals { field, .. }
+            | Filter::NotEquals { field, .. }
+            | Filter::GreaterThan { field, .. }
+            | Filter::GreaterThanOrEqual { field, .. }
+            | Filter::LessThan { field, .. }
+            | Filter::LessThanOrEqual { field, .. }
+            | Filter::Range { field, .. } => self.check_attribute(field),
+            Filter::Not(inner) => self.check_filter(inner),
+            Filter::And(parts) | Filter::Or(parts) => {
+                parts.iter().try_for_each(|f| self.check_filter(f))
+            }
+        }
+    }
+
+    pub fn check_attribute(&self, attribute: &str) -> Result<(), String> {
+        if self.is_visible(attribute) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Attribute `{}` is not accessible with this API key",
+                attribute
+            ))
+        }
+    }
+}
+
+/// `attr` is `path` or one of its parents (`a` covers `a.b`).
+fn path_covers(attr: &str, path: &str) -> bool {
+    path == attr
+        || path
+            .strip_prefix(attr)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Header carrying an HS256 JWT whose claims fill `{claim.<name>}`
+/// placeholders in secured-key filters. The token must be signed with the
+/// parent key, so only the backend that mints secured keys can mint claims.
+pub const USER_CLAIMS_HEADER: &str = "x-flapjack-user-claims";
+
 #[derive(Debug, Clone, Default)]
 pub struct SecuredKeyRestrictions {
     pub filters: Option<String>,
@@ -294,6 +663,10 @@ pub struct SecuredKeyRestrictions {
     pub user_token: Option<String>,
     pub hits_per_page: Option<usize>,
     pub restrict_sources: Option<String>,
+    /// Validated claims from [`USER_CLAIMS_HEADER`] (`{claim.<name>}`).
+    pub claims: HashMap<String, String>,
+    /// Parent key metadata (`{key.<name>}`).
+    pub key_metadata: HashMap<String, String>,
 }
 
 impl SecuredKeyRestrictions {
@@ -331,8 +704,125 @@ impl SecuredKeyRestrictions {
             user_token,
             hits_per_page,
             restrict_sources,
+            claims: HashMap::new(),
+            key_metadata: HashMap::new(),
+        }
+    }
+
+    /// Forced `filters` with `{claim.<name>}` and `{key.<name>}` placeholders
+    /// substituted. Numeric values are inserted bare, anything else as a
+    /// quoted facet value so a claim cannot inject filter syntax.
+    ///
+    /// Fails closed: an unknown or unresolvable placeholder is an error rather
+    /// than a filter that silently matches everything.
+    pub fn resolved_filters(&self) -> Result<Option<String>, String> {
+        let Some(template) = &self.filters else {
+            return Ok(None);
+        };
+        let mut resolved = String::with_capacity(template.len());
+        let mut rest = template.as_str();
+        while let Some(open) = rest.find('{') {
+            resolved.push_str(&rest[..open]);
+            let after = &rest[open + 1..];
+            let close = after
+                .find('}')
+                .ok_or_else(|| "Unterminated placeholder in secured API key filters".to_string())?;
+            let name = after[..close].trim();
+            let value = if let Some(claim) = name.strip_prefix("claim.") {
+                self.claims.get(claim)
+            } else if let Some(field) = name.strip_prefix("key.") {
+                self.key_metadata.get(field)
+            } else {
+                return Err(format!(
+                    "Unknown placeholder {{{}}} in secured API key filters",
+                    name
+                ));
+            };
+            let value = value.ok_or_else(|| {
+                format!(
+                    "Secured API key filters require {{{}}}, which was not provided",
+                    name
+                )
+            })?;
+            resolved.push_str(&template_value(value)?);
+            rest = &after[close + 1..];
         }
+        resolved.push_str(rest);
+        Ok(Some(resolved))
+    }
+}
+
+fn template_value(value: &str) -> Result<String, String> {
+    if value.parse::<f64>().is_ok() {
+        return Ok(value.to_string());
+    }
+    if value.contains('"') {
+        return Err("Secured API key filter values may not contain '\"'".to_string());
+    }
+    Ok(format!("\"{}\"", value))
+}
+
+/// Sign `claims` as an HS256 JWT with `secret` (the parent API key).
+pub fn sign_claims_token(claims: &serde_json::Value, secret: &str) -> String {
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    type HmacSha256 = Hmac<Sha256>;
+    let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"HS256","typ":"JWT"}"#);
+    let payload = URL_SAFE_NO_PAD.encode(claims.to_string().as_bytes());
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{}.{}", header, payload).as_bytes());
+    let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    format!("{}.{}.{}", header, payload, signature)
+}
+
+/// Verify an HS256 JWT signed with `secret` and return its top-level claims
+/// as strings (numbers and booleans are stringified, nested values dropped).
+/// Tokens past `exp` or before `nbf` are rejected.
+pub fn validate_claims_token(token: &str, secret: &str) -> Option<HashMap<String, String>> {
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    type HmacSha256 = Hmac<Sha256>;
+
+    let mut parts = token.trim().split('.');
+    let (header_b64, payload_b64, signature_b64) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let header: serde_json::Value =
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header_b64).ok()?).ok()?;
+    if header.get("alg").and_then(|a| a.as_str()) != Some("HS256") {
+        return None;
     }
+
+    let signature = URL_SAFE_NO_PAD.decode(signature_b64).ok()?;
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{}.{}", header_b64, payload_b64).as_bytes());
+    mac.verify_slice(&signature).ok()?;
+
+    let payload: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload_b64).ok()?).ok()?;
+    let now = Utc::now().timestamp();
+    let claim_ts = |name: &str| payload.get(name).and_then(|v| v.as_i64());
+    if claim_ts("exp").is_some_and(|exp| now >= exp) || claim_ts("nbf").is_some_and(|nbf| now < nbf)
+    {
+        return None;
+    }
+
+    Some(
+        payload
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let value = match value {
+                    serde_json::Value::String(s) => s,
+                    serde_json::Value::Number(n) => n.to_string(),
+                    serde_json::Value::Bool(b) => b.to_string(),
+                    _ => return None,
+                };
+                Some((name, value))
+            })
+            .collect(),
+    )
 }
 
 pub fn generate_secured_api_key(parent_key: &str, params: &str) -> String {
@@ -491,15 +981,64 @@ pub fn reset_admin_key(data_dir: &Path) -> Result<String, String> {
 }
 
 pub fn required_acl_for_route(method: &Method, path: &str) -> Option<&'static str> {
-    if path.starts_with("/1/keys") {
+    if path.starts_with("/1/keys")
+        || path.starts_with("/1/schedules")
+        || path.starts_with("/1/clusters")
+        || path.starts_with("/1/namespaces")
+    {
+        return Some("admin");
+    }
+
+    // Bulk settings span indexes that a key's `indexes` restriction
+    // can't be checked against up front
+    if path == "/1/settings/bulk" {
+        return Some("admin");
+    }
+
+    // Archive imports name their index in the query or the archive itself,
+    // so a key's `indexes` restriction can't be checked up front
+    if path == "/1/indexes:import" {
+        return Some("admin");
+    }
+
+    // Feature flags list the API key values they target
+    if path.starts_with("/1/flags") {
         return Some("admin");
     }
 
+    // Index templates are settings bundles: read with "settings", write with "editSettings"
+    if path.starts_with("/1/templates") {
+        return match *method {
+            Method::GET => Some("settings"),
+            _ => Some("editSettings"),
+        };
+    }
+
     // Analytics API endpoints (/2/*) require "analytics" ACL
     if path.starts_with("/2/") {
         return Some("analytics");
     }
 
+    // Rejected Insights events expose user tokens: analytics access only
+    if path == "/1/events/rejected" {
+        return Some("analytics");
+    }
+
+    // Search traffic anomalies are analytics findings
+    if path == "/1/analytics/anomalies" {
+        return Some("analytics");
+    }
+
+    // Anonymous userToken issuance is client-facing, like search
+    if path == "/1/users/token" {
+        return Some("search");
+    }
+
+    // Request logs expose request bodies and headers of every index
+    if path == "/1/logs" {
+        return Some("logs");
+    }
+
     // Insights API (/1/events) — uses "search" ACL (client-facing, matches Algolia behavior)
     if path == "/1/events" {
         return Some("search");
@@ -534,24 +1073,53 @@ pub fn required_acl_for_route(method: &Method, path: &str) -> Option<&'static st
                 "queries" => Some("search"),
                 "browse" => Some("browse"),
                 "batch" => Some("addObject"),
+                "files" => Some("addObject"),
                 "clear" => Some("deleteObject"),
                 "deleteByQuery" => Some("deleteObject"),
                 "operation" => Some("addObject"),
+                "clone" => Some("addObject"),
                 "objects" => Some("search"),
                 "settings" => match *method {
                     Method::GET => Some("settings"),
                     _ => Some("editSettings"),
                 },
                 "facets" => Some("search"),
+                "synonyms" if parts.len() == 5 && parts[4] == "stats" => Some("analytics"),
                 "synonyms" => match *method {
                     Method::GET => Some("settings"),
                     _ => Some("editSettings"),
                 },
+                "rules" if parts.get(5) == Some(&"stats") => Some("analytics"),
                 "rules" => match *method {
                     Method::GET => Some("settings"),
                     _ => Some("editSettings"),
                 },
+                "campaigns" if parts.get(5) == Some(&"stats") => Some("analytics"),
+                "campaigns" => match *method {
+                    Method::GET => Some("settings"),
+                    _ => Some("editSettings"),
+                },
                 "task" => Some("search"),
+                // Legal takedowns: keys that edit rules must not lift them
+                "blocklist" => Some("admin"),
+                "cluster-snapshots" => Some("admin"),
+                "tier" => match *method {
+                    Method::GET => Some("settings"),
+                    _ => Some("admin"),
+                },
+                // Crawls fetch arbitrary URLs from the server's network
+                "crawler" => Some("admin"),
+                "stats" => Some("settings"),
+                // Lists objectIDs, like browsing
+                "duplicates" => Some("browse"),
+                "warmup" | "mode" => match *method {
+                    Method::GET => Some("settings"),
+                    _ => Some("editSettings"),
+                },
+                "vectors" => match *method {
+                    Method::GET => Some("settings"),
+                    _ => Some("editSettings"),
+                },
                 _ => match *method {
                     Method::GET => Some("search"),
                     Method::PUT => Some("addObject"),
@@ -601,7 +1169,9 @@ fn extract_index_name(path: &str) -> Option<String> {
     let parts: Vec<&str> = path.trim_start_matches('/').split('/').collect();
     if parts.len() >= 3 && parts[0] == "1" && parts[1] == "indexes" {
         let name = parts[2];
-        if name != "queries" && name != "objects" {
+        // getObjects checks each requested index against the `IndexScope`.
+        let multi_index_objects = name == "*" && parts.get(3) == Some(&"objects");
+        if name != "queries" && name != "objects" && !multi_index_objects {
             return Some(name.to_string());
         }
     }
@@ -630,14 +1200,23 @@ fn has_header_or_param(request: &Request, key: &str) -> bool {
             .is_some_and(|q| q.contains(&format!("{}=", key)))
 }
 
-fn error_json(message: &str, status: u16) -> Response {
-    let body = serde_json::json!({ "message": message, "status": status });
-    (
-        StatusCode::from_u16(status).unwrap_or(StatusCode::FORBIDDEN),
-        [("content-type", "application/json")],
-        body.to_string(),
+fn error_json(message: &str, code: ErrorCode) -> Response {
+    crate::error_codes::error_response(code, message)
+}
+
+fn invalid_credentials() -> Response {
+    error_json(
+        "Invalid Application-ID or API key",
+        ErrorCode::InvalidCredentials,
     )
-        .into_response()
+}
+
+fn acl_denied() -> Response {
+    error_json("Method not allowed with this API key", ErrorCode::AclDenied)
+}
+
+fn origin_denied() -> Response {
+    error_json("Origin not allowed with this API key", ErrorCode::Forbidden)
 }
 
 pub async fn authenticate_and_authorize(
@@ -652,6 +1231,7 @@ pub async fn authenticate_and_authorize(
 
     // Skip auth for public endpoints: health check, metrics, dashboard UI, API docs
     if path == "/health"
+        || path.starts_with("/health/")
         || path == "/metrics"
         || path.starts_with("/dashboard")
         || path.starts_with("/swagger-ui")
@@ -667,28 +1247,57 @@ pub async fn authenticate_and_authorize(
     }
 
     let key_store = key_store.unwrap().clone();
+    let ip = crate::request_log::client_ip(request.headers());
+    let monitor = KeyMonitor::global();
+    let fail = |reason: AuthFailure, key: Option<&str>, response: Response| {
+        monitor.record_failure(reason, key, &ip);
+        response
+    };
 
     if !has_header_or_param(&request, "x-algolia-application-id") {
-        return Err(error_json("Invalid Application-ID or API key", 403));
+        return Err(fail(
+            AuthFailure::MissingCredentials,
+            None,
+            invalid_credentials(),
+        ));
     }
 
     let api_key_value = match extract_api_key(&request) {
         Some(k) => k,
-        None => return Err(error_json("Invalid Application-ID or API key", 403)),
+        None => {
+            return Err(fail(
+                AuthFailure::MissingCredentials,
+                None,
+                invalid_credentials(),
+            ))
+        }
     };
 
     let (api_key, secured_restrictions) = match key_store.lookup(&api_key_value) {
         Some(k) => (k, None),
         None => match validate_secured_key(&api_key_value, &key_store) {
             Some((parent_key, restrictions)) => (parent_key, Some(restrictions)),
-            None => return Err(error_json("Invalid Application-ID or API key", 403)),
+            None => return Err(fail(AuthFailure::InvalidKey, None, invalid_credentials())),
         },
     };
-
-    if api_key.validity > 0 {
-        let expires_at = api_key.created_at + (api_key.validity * 1000);
-        if Utc::now().timestamp_millis() > expires_at {
-            return Err(error_json("Invalid Application-ID or API key", 403));
+    // Keys are tracked by their value (kept as `hmac_key`, which is also the
+    // parent value of secured keys); the admin key has none and uses its hash.
+    let key_id = api_key
+        .hmac_key
+        .clone()
+        .unwrap_or_else(|| api_key.hash.clone());
+    let key = Some(key_id.as_str());
+
+    if api_key.is_expired(Utc::now().timestamp_millis()) {
+        return Err(fail(AuthFailure::ExpiredKey, key, invalid_credentials()));
+    }
+
+    if let Some(origin) = request.headers().get(header::ORIGIN) {
+        let allowed = origin
+            .to_str()
+            .is_ok_and(|o| crate::cors::origin_matches(&api_key.allowed_origins, o));
+        if !allowed {
+            return Err(fail(AuthFailure::OriginDenied, key, origin_denied()));
         }
     }
 
@@ -705,40 +1314,94 @@ pub async fn authenticate_and_authorize(
                         && parts[1] == "keys"
                         && parts[2] == api_key_value
                 };
-                if !is_get_own_key {
-                    return Err(error_json("Method not allowed with this API key", 403));
+                let is_namespace_admin = KeyNamespace::for_key(&api_key)
+                    .is_some_and(|ns| ns.admin_allows(&method, &path));
+                if !is_get_own_key && !is_namespace_admin {
+                    return Err(fail(AuthFailure::AclDenied, key, acl_denied()));
                 }
             }
         } else if !api_key.acl.iter().any(|a| a == acl) {
-            return Err(error_json("Method not allowed with this API key", 403));
+            return Err(fail(AuthFailure::AclDenied, key, acl_denied()));
         }
     }
 
     if let Some(ref restrictions) = secured_restrictions {
         if let Some(ref index_name) = extract_index_name(&path) {
             if !api_key.indexes.is_empty() && !index_pattern_matches(&api_key.indexes, index_name) {
-                return Err(error_json("Invalid Application-ID or API key", 403));
+                return Err(fail(AuthFailure::IndexDenied, key, invalid_credentials()));
             }
             if let Some(ref restrict_indices) = restrictions.restrict_indices {
                 if !index_pattern_matches(restrict_indices, index_name) {
-                    return Err(error_json("Invalid Application-ID or API key", 403));
+                    return Err(fail(AuthFailure::IndexDenied, key, invalid_credentials()));
                 }
             }
         }
     } else if let Some(index_name) = extract_index_name(&path) {
         if !index_pattern_matches(&api_key.indexes, &index_name) {
-            return Err(error_json("Invalid Application-ID or API key", 403));
+            return Err(fail(AuthFailure::IndexDenied, key, invalid_credentials()));
         }
     }
 
     let mut request = request;
-    if let Some(restrictions) = secured_restrictions {
+    request
+        .extensions_mut()
+        .insert(AuthenticatedKey(key_id.clone()));
+    request.extensions_mut().insert(IndexScope {
+        indexes: api_key.indexes.clone(),
+        restrict_indices: secured_restrictions
+            .as_ref()
+            .and_then(|r| r.restrict_indices.clone()),
+        namespace: api_key.namespace.clone(),
+    });
+    if let Some(attributes) = AttributeRestrictions::for_key(&api_key) {
+        request.extensions_mut().insert(attributes);
+    }
+    if let Some(namespace) = KeyNamespace::for_key(&api_key) {
+        request.extensions_mut().insert(namespace);
+    }
+    if let Some(mut restrictions) = secured_restrictions {
+        if let Some(token) = request.headers().get(USER_CLAIMS_HEADER) {
+            let secret = api_key.hmac_key.as_deref().unwrap_or_default();
+            match token
+                .to_str()
+                .ok()
+                .and_then(|t| validate_claims_token(t, secret))
+            {
+                Some(claims) => restrictions.claims = claims,
+                None => {
+                    return Err(fail(
+                        AuthFailure::InvalidClaims,
+                        key,
+                        error_json("Invalid user claims token", ErrorCode::InvalidCredentials),
+                    ))
+                }
+            }
+        }
+        restrictions.key_metadata = api_key.metadata.clone();
         request.extensions_mut().insert(restrictions);
     }
 
+    let country = request
+        .headers()
+        .get(country_header())
+        .and_then(|v| v.to_str().ok());
+    let anomalies = monitor.record_use(&key_id, country, Utc::now().timestamp_millis());
+    report_anomalies(&key_id, &ip, anomalies);
+
     Ok(next.run(request).await)
 }
 
+/// Request header carrying the client's country code, set by the CDN or
+/// proxy in front of the node.
+fn country_header() -> &'static str {
+    static HEADER: OnceLock<String> = OnceLock::new();
+    HEADER.get_or_init(|| {
+        std::env::var("FLAPJACK_COUNTRY_HEADER")
+            .map(|h| h.to_ascii_lowercase())
+            .unwrap_or_else(|_| "cf-ipcountry".to_string())
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -879,6 +1542,32 @@ mod tests {
         assert_eq!(extract_index_name("/1/indexes/queries"), None);
     }
 
+    #[test]
+    fn index_scope_checks_body_index_names() {
+        assert_eq!(extract_index_name("/1/indexes/*/objects"), None);
+        assert_eq!(
+            extract_index_name("/1/indexes/*/queries"),
+            Some("*".to_string())
+        );
+
+        let scope = IndexScope {
+            indexes: vec!["prod_*".to_string()],
+            restrict_indices: Some(vec!["prod_us".to_string()]),
+            namespace: None,
+        };
+        assert!(scope.allows("prod_us"));
+        assert!(!scope.allows("prod_eu"));
+        assert!(!scope.allows("secrets"));
+
+        let scope = IndexScope {
+            namespace: Some("acme".to_string()),
+            ..Default::default()
+        };
+        assert!(scope.allows("acme~products"));
+        assert!(!scope.allows("globex~products"));
+        assert!(!scope.allows("products"));
+    }
+
     #[test]
     fn extract_index_name_objects_excluded() {
         assert_eq!(extract_index_name("/1/indexes/objects"), None);
@@ -908,6 +1597,119 @@ mod tests {
         );
     }
 
+    #[test]
+    fn acl_clusters_require_admin() {
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/clusters"),
+            Some("admin")
+        );
+        assert_eq!(
+            required_acl_for_route(&Method::POST, "/1/clusters/mapping/batch"),
+            Some("admin")
+        );
+    }
+
+    #[test]
+    fn acl_schedules_require_admin() {
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/schedules"),
+            Some("admin")
+        );
+        assert_eq!(
+            required_acl_for_route(&Method::POST, "/1/schedules/abc/run"),
+            Some("admin")
+        );
+    }
+
+    #[test]
+    fn acl_namespaces_require_admin() {
+        assert_eq!(
+            required_acl_for_route(&Method::PUT, "/1/namespaces/acme"),
+            Some("admin")
+        );
+    }
+
+    #[test]
+    fn namespace_admin_keys_manage_only_keys_and_own_namespace() {
+        let ns = KeyNamespace {
+            name: "acme".to_string(),
+            admin: true,
+        };
+        assert!(ns.admin_allows(&Method::POST, "/1/keys"));
+        assert!(ns.admin_allows(&Method::DELETE, "/1/keys/fj_search_abc"));
+        assert!(!ns.admin_allows(&Method::GET, "/1/keys/audit"));
+        assert!(ns.admin_allows(&Method::GET, "/1/namespaces/acme"));
+        assert!(!ns.admin_allows(&Method::PUT, "/1/namespaces/acme"));
+        assert!(!ns.admin_allows(&Method::GET, "/1/namespaces/globex"));
+        assert!(!ns.admin_allows(&Method::GET, "/1/clusters"));
+        let member = KeyNamespace { admin: false, ..ns };
+        assert!(!member.admin_allows(&Method::POST, "/1/keys"));
+    }
+
+    #[test]
+    fn acl_archive_import_requires_admin() {
+        assert_eq!(
+            required_acl_for_route(&Method::POST, "/1/indexes:import"),
+            Some("admin")
+        );
+    }
+
+    #[test]
+    fn acl_bulk_settings_require_admin() {
+        assert_eq!(
+            required_acl_for_route(&Method::POST, "/1/settings/bulk"),
+            Some("admin")
+        );
+    }
+
+    #[test]
+    fn acl_blocklist_requires_admin() {
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/indexes/products/blocklist"),
+            Some("admin")
+        );
+        assert_eq!(
+            required_acl_for_route(&Method::DELETE, "/1/indexes/products/blocklist/takedown-1"),
+            Some("admin")
+        );
+    }
+
+    #[test]
+    fn acl_campaigns_follow_rules_acls() {
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/indexes/products/campaigns"),
+            Some("settings")
+        );
+        assert_eq!(
+            required_acl_for_route(&Method::PUT, "/1/indexes/products/campaigns/summer"),
+            Some("editSettings")
+        );
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/indexes/products/campaigns/summer/stats"),
+            Some("analytics")
+        );
+    }
+
+    #[test]
+    fn acl_templates_follow_settings_acls() {
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/templates/ecommerce-default"),
+            Some("settings")
+        );
+        assert_eq!(
+            required_acl_for_route(&Method::PUT, "/1/templates/ecommerce-default"),
+            Some("editSettings")
+        );
+    }
+
+    #[test]
+    fn acl_clone_requires_add_object() {
+        assert_eq!(
+            required_acl_for_route(&Method::POST, "/1/indexes/products/clone"),
+            Some("addObject")
+        );
+    }
+
     #[test]
     fn acl_analytics_endpoint() {
         assert_eq!(
@@ -924,6 +1726,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn acl_rejected_events_analytics() {
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/events/rejected"),
+            Some("analytics")
+        );
+    }
+
+    #[test]
+    fn acl_search_anomalies_analytics() {
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/analytics/anomalies"),
+            Some("analytics")
+        );
+    }
+
+    #[test]
+    fn acl_index_mode_settings() {
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/indexes/products/mode"),
+            Some("settings")
+        );
+        assert_eq!(
+            required_acl_for_route(&Method::POST, "/1/indexes/products/mode"),
+            Some("editSettings")
+        );
+    }
+
+    #[test]
+    fn acl_feature_flags_require_admin() {
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/flags"),
+            Some("admin")
+        );
+        assert_eq!(
+            required_acl_for_route(&Method::PUT, "/1/flags/reRanking"),
+            Some("admin")
+        );
+    }
+
+    #[test]
+    fn acl_user_token_search() {
+        assert_eq!(
+            required_acl_for_route(&Method::POST, "/1/users/token"),
+            Some("search")
+        );
+    }
+
+    #[test]
+    fn acl_request_logs() {
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/logs"),
+            Some("logs")
+        );
+    }
+
     #[test]
     fn acl_list_indexes() {
         assert_eq!(
@@ -994,6 +1852,307 @@ mod tests {
             required_acl_for_route(&Method::GET, "/1/tasks/123"),
             Some("search")
         );
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/tasks"),
+            Some("search")
+        );
+    }
+
+    #[test]
+    fn acl_cluster_snapshots_require_admin() {
+        assert_eq!(
+            required_acl_for_route(&Method::POST, "/1/indexes/products/cluster-snapshots"),
+            Some("admin")
+        );
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/indexes/products/cluster-snapshots"),
+            Some("admin")
+        );
+    }
+
+    #[test]
+    fn acl_tier_changes_require_admin() {
+        assert_eq!(
+            required_acl_for_route(&Method::PUT, "/1/indexes/products/tier"),
+            Some("admin")
+        );
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/indexes/products/tier"),
+            Some("settings")
+        );
+    }
+
+    #[test]
+    fn acl_index_stats_requires_settings() {
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/indexes/products/stats"),
+            Some("settings")
+        );
+    }
+
+    #[test]
+    fn acl_vector_rebuild_requires_edit_settings() {
+        assert_eq!(
+            required_acl_for_route(&Method::POST, "/1/indexes/products/vectors/rebuild"),
+            Some("editSettings")
+        );
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/indexes/products/vectors/usage"),
+            Some("settings")
+        );
+    }
+
+    #[test]
+    fn acl_rule_stats_requires_analytics() {
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/indexes/products/rules/pin-1/stats"),
+            Some("analytics")
+        );
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/indexes/products/rules/stats"),
+            Some("settings")
+        );
+    }
+
+    #[test]
+    fn acl_synonym_stats_requires_analytics() {
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/indexes/products/synonyms/stats"),
+            Some("analytics")
+        );
+        assert_eq!(
+            required_acl_for_route(&Method::GET, "/1/indexes/products/synonyms/syn-1"),
+            Some("settings")
+        );
+    }
+
+    // ── AttributeRestrictions ──
+
+    fn restrictions(allowed: &[&str], denied: &[&str]) -> AttributeRestrictions {
+        AttributeRestrictions {
+            allowed: allowed.iter().map(|s| s.to_string()).collect(),
+            denied: denied.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn attribute_restrictions_visibility() {
+        let r = restrictions(&["title", "pricing"], &["pricing.cost"]);
+        assert!(r.is_visible("objectID"));
+        assert!(r.is_visible("title"));
+        assert!(r.is_visible("pricing.price"));
+        assert!(!r.is_visible("pricing.cost"));
+        assert!(!r.is_visible("margin"));
+        assert!(!r.is_visible("titles"));
+    }
+
+    #[test]
+    fn attribute_restrictions_strip_nested_and_highlight_maps() {
+        let r = restrictions(&["title", "pricing"], &["pricing.cost"]);
+        let mut hit = serde_json::json!({
+            "objectID": "1",
+            "title": "Shoe",
+            "margin": 0.4,
+            "pricing": {"price": 40, "cost": 12}
+        });
+        r.strip(hit.as_object_mut().unwrap());
+        assert_eq!(
+            hit,
+            serde_json::json!({"objectID": "1", "title": "Shoe", "pricing": {"price": 40}})
+        );
+
+        let r = restrictions(&["specs.color"], &[]);
+        let mut highlight = serde_json::json!({
+            "title": {"value": "Shoe", "matchLevel": "none"},
+            "specs": {
+                "color": {"value": "red", "matchLevel": "full"},
+                "supplier": {"value": "Acme", "matchLevel": "none"}
+            }
+        });
+        r.strip(highlight.as_object_mut().unwrap());
+        assert_eq!(
+            highlight,
+            serde_json::json!({"specs": {"color": {"value": "red", "matchLevel": "full"}}})
+        );
+    }
+
+    #[test]
+    fn attribute_restrictions_reject_hidden_filters() {
+        use flapjack::types::{FieldValue, Filter};
+        let r = restrictions(&[], &["cost"]);
+        let visible = Filter::Equals {
+            field: "brand".to_string(),
+            value: FieldValue::Text("Nike".to_string()),
+        };
+        let hidden = Filter::Not(Box::new(Filter::Range {
+            field: "cost".to_string(),
+            min: 0.0,
+            max: 10.0,
+        }));
+        assert!(r.check_filter(&visible).is_ok());
+        assert!(r.check_filter(&Filter::Or(vec![visible, hidden])).is_err());
+    }
+
+    #[test]
+    fn attribute_restrictions_only_for_restricted_keys() {
+        let mut key: ApiKey = serde_json::from_value(serde_json::json!({
+            "hash": "h", "salt": "s", "createdAt": 0, "acl": ["search"]
+        }))
+        .unwrap();
+        assert!(AttributeRestrictions::for_key(&key).is_none());
+        key.denied_attributes = vec!["cost".to_string()];
+        let r = AttributeRestrictions::for_key(&key).unwrap();
+        assert!(!r.is_visible("cost"));
+    }
+
+    // ── KeyStore::rotate_key ──
+
+    fn custom_key(store: &KeyStore) -> String {
+        let (_, plaintext) = store.create_key(ApiKey {
+            hash: String::new(),
+            salt: String::new(),
+            hmac_key: None,
+            created_at: 0,
+            acl: vec!["search".into()],
+            description: "rotating".into(),
+            indexes: vec!["products".into()],
+            max_hits_per_query: 0,
+            max_queries_per_ip_per_hour: 0,
+            query_parameters: String::new(),
+            referers: vec![],
+            validity: 0,
+            metadata: HashMap::new(),
+            expires_at: None,
+            allowed_attributes: Vec::new(),
+            denied_attributes: Vec::new(),
+            allowed_origins: Vec::new(),
+            namespace: None,
+        });
+        plaintext
+    }
+
+    #[test]
+    fn rotate_key_keeps_old_key_during_grace() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = KeyStore::load_or_create(dir.path(), "admin");
+        let old = custom_key(&store);
+
+        let (new_key, new_value, expires_at) = store.rotate_key(&old, 3600).unwrap();
+        assert_eq!(new_key.indexes, vec!["products".to_string()]);
+        assert_eq!(new_key.hmac_key.as_deref(), Some(new_value.as_str()));
+        assert!(expires_at > Utc::now().timestamp_millis());
+
+        let old_key = store.lookup(&old).unwrap();
+        assert_eq!(old_key.expires_at, Some(expires_at));
+        assert!(!old_key.is_expired(Utc::now().timestamp_millis()));
+        assert!(store.lookup(&new_value).unwrap().expires_at.is_none());
+        assert_eq!(store.pending_expiry().len(), 1);
+
+        let log = store.audit_log();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].event, "rotate");
+        assert_eq!(log[0].key_hash, old_key.hash);
+        assert_eq!(log[0].new_key_hash.as_deref(), Some(new_key.hash.as_str()));
+    }
+
+    #[test]
+    fn rotate_key_zero_grace_retires_immediately() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = KeyStore::load_or_create(dir.path(), "admin");
+        let old = custom_key(&store);
+
+        let (_, new_value, _) = store.rotate_key(&old, 0).unwrap();
+        assert!(store.lookup(&old).is_none());
+        assert!(store.lookup(&new_value).is_some());
+        assert!(store.rotate_key(&old, 60).is_none());
+    }
+
+    #[test]
+    fn rotate_key_rejects_admin_and_unknown() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = KeyStore::load_or_create(dir.path(), "admin");
+        assert!(store.rotate_key("admin", 60).is_none());
+        assert!(store.rotate_key("nope", 60).is_none());
+    }
+
+    // ── filter templates / claims tokens ──
+
+    fn templated(filters: &str) -> SecuredKeyRestrictions {
+        SecuredKeyRestrictions {
+            filters: Some(filters.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn resolved_filters_substitutes_claims_and_metadata() {
+        let mut r =
+            templated("tenant_id:{claim.tenant} AND region:{key.region} AND tier >= {claim.tier}");
+        r.claims.insert("tenant".into(), "acme corp".into());
+        r.claims.insert("tier".into(), "2".into());
+        r.key_metadata.insert("region".into(), "eu".into());
+        assert_eq!(
+            r.resolved_filters().unwrap(),
+            Some("tenant_id:\"acme corp\" AND region:\"eu\" AND tier >= 2".to_string())
+        );
+    }
+
+    #[test]
+    fn resolved_filters_without_placeholders_is_unchanged() {
+        let r = templated("brand:Nike");
+        assert_eq!(
+            r.resolved_filters().unwrap(),
+            Some("brand:Nike".to_string())
+        );
+        assert_eq!(
+            SecuredKeyRestrictions::default()
+                .resolved_filters()
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn resolved_filters_fails_closed() {
+        assert!(templated("tenant_id:{claim.tenant}")
+            .resolved_filters()
+            .is_err());
+        assert!(templated("tenant_id:{user.tenant}")
+            .resolved_filters()
+            .is_err());
+        assert!(templated("tenant_id:{claim.tenant")
+            .resolved_filters()
+            .is_err());
+
+        let mut r = templated("tenant_id:{claim.tenant}");
+        r.claims.insert("tenant".into(), "x\" OR brand:\"y".into());
+        assert!(r.resolved_filters().is_err(), "quotes must not break out");
+    }
+
+    #[test]
+    fn claims_token_roundtrip() {
+        let token = sign_claims_token(
+            &serde_json::json!({"tenant": "acme", "tier": 2, "admin": false, "nested": {"a": 1}}),
+            "parent_key",
+        );
+        let claims = validate_claims_token(&token, "parent_key").unwrap();
+        assert_eq!(claims.get("tenant").map(String::as_str), Some("acme"));
+        assert_eq!(claims.get("tier").map(String::as_str), Some("2"));
+        assert_eq!(claims.get("admin").map(String::as_str), Some("false"));
+        assert!(!claims.contains_key("nested"));
+    }
+
+    #[test]
+    fn claims_token_rejects_wrong_secret_and_expired() {
+        let token = sign_claims_token(&serde_json::json!({"tenant": "acme"}), "parent_key");
+        assert!(validate_claims_token(&token, "other_key").is_none());
+
+        let expired = sign_claims_token(
+            &serde_json::json!({"tenant": "acme", "exp": Utc::now().timestamp() - 10}),
+            "parent_key",
+        );
+        assert!(validate_claims_token(&expired, "parent_key").is_none());
+        assert!(validate_claims_token("not.a.jwt", "parent_key").is_none());
     }
 
     // ── SecuredKeyRestrictions::from_params ──
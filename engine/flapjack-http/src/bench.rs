@@ -0,0 +1,518 @@
+//! `flapjack bench`: send searches and/or writes to a running server at a
+//! fixed rate and report latency percentiles and error rates.
+//!
+//! Requests are scheduled open-loop: request `n` is due at `n / qps`
+//! seconds whether or not earlier ones have answered, and its latency is
+//! measured from that due time, so a slow server shows up as latency
+//! instead of as a lower request rate. When `concurrency` requests are
+//! already in flight, a due request is dropped and counted.
+//!
+//! Search queries are the index's top searches of the last 30 days
+//! (`GET /2/searches`), replayed in proportion to their counts. Without
+//! analytics they are drawn from the fixture corpus of the index's preset
+//! (see `flapjack::fixtures`), as are the records written.
+
+use flapjack::fixtures::{corpus, Preset};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Semaphore};
+
+/// Synthetic queries and write records generated when the server has no
+/// analytics to replay.
+const SYNTHETIC_POOL: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scenario {
+    Search,
+    Write,
+    Mixed,
+}
+
+impl Scenario {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scenario::Search => "search",
+            Scenario::Write => "write",
+            Scenario::Mixed => "mixed",
+        }
+    }
+}
+
+impl fmt::Display for Scenario {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scenario {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "search" => Ok(Scenario::Search),
+            "write" => Ok(Scenario::Write),
+            "mixed" => Ok(Scenario::Mixed),
+            _ => Err(format!(
+                "unknown scenario '{}' (expected search, write or mixed)",
+                s
+            )),
+        }
+    }
+}
+
+pub struct BenchOptions {
+    /// Base URL of the server, e.g. `http://127.0.0.1:7700`
+    pub target: String,
+    pub api_key: Option<String>,
+    pub index: String,
+    pub scenario: Scenario,
+    pub qps: f64,
+    pub duration: Duration,
+    /// Most requests in flight at once
+    pub concurrency: usize,
+    /// Share of `mixed` requests that are writes
+    pub write_ratio: f64,
+    /// Records per write request
+    pub batch_size: usize,
+    /// Corpus for synthetic queries and written records (default: guessed
+    /// from the index name, else e-commerce)
+    pub preset: Option<Preset>,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Search,
+    Write,
+}
+
+struct Sample {
+    op: Op,
+    latency: Duration,
+    /// HTTP status, or `None` when no response arrived
+    status: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyMs {
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpReport {
+    pub requests: usize,
+    pub errors: usize,
+    pub error_rate: f64,
+    /// Responses by status code; `"error"` counts requests that got none
+    pub statuses: BTreeMap<String, usize>,
+    pub latency_ms: LatencyMs,
+    /// Responses per second over the run
+    pub throughput: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchReport {
+    pub target: String,
+    pub index: String,
+    pub scenario: String,
+    pub qps: f64,
+    pub duration_secs: f64,
+    /// `analytics` when recorded queries were replayed, `synthetic` when
+    /// they came from the fixture corpus, `none` for the write scenario
+    pub query_source: String,
+    pub distinct_queries: usize,
+    /// Due requests not sent because `concurrency` were in flight
+    pub dropped: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<OpReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write: Option<OpReport>,
+}
+
+impl BenchReport {
+    /// Human-readable summary printed by `flapjack bench`.
+    pub fn summary(&self) -> String {
+        let mut out = format!(
+            "{} scenario against {} (index '{}'): {} qps for {:.0}s, {} {} queries\n",
+            self.scenario,
+            self.target,
+            self.index,
+            self.qps,
+            self.duration_secs,
+            self.distinct_queries,
+            self.query_source
+        );
+        out.push_str(&format!(
+            "{:<8} {:>9} {:>8} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}\n",
+            "op", "requests", "errors", "req/s", "mean ms", "p50 ms", "p90 ms", "p99 ms", "max ms"
+        ));
+        for (name, report) in [("search", &self.search), ("write", &self.write)] {
+            let Some(r) = report else { continue };
+            out.push_str(&format!(
+                "{:<8} {:>9} {:>7.2}% {:>9.1} {:>9.2} {:>9.2} {:>9.2} {:>9.2} {:>9.2}\n",
+                name,
+                r.requests,
+                r.error_rate * 100.0,
+                r.throughput,
+                r.latency_ms.mean,
+                r.latency_ms.p50,
+                r.latency_ms.p90,
+                r.latency_ms.p99,
+                r.latency_ms.max
+            ));
+            let failures: Vec<String> = r
+                .statuses
+                .iter()
+                .filter(|(status, _)| !status.starts_with('2'))
+                .map(|(status, n)| format!("{} × {}", n, status))
+                .collect();
+            if !failures.is_empty() {
+                out.push_str(&format!("         failures: {}\n", failures.join(", ")));
+            }
+        }
+        if self.dropped > 0 {
+            out.push_str(&format!(
+                "{} requests dropped with all connections busy; raise --concurrency or lower --qps\n",
+                self.dropped
+            ));
+        }
+        out
+    }
+}
+
+/// Nearest-rank percentile of sorted values.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn op_report(samples: &[&Sample], elapsed: Duration) -> OpReport {
+    let mut latencies: Vec<f64> = samples
+        .iter()
+        .map(|s| s.latency.as_secs_f64() * 1000.0)
+        .collect();
+    latencies.sort_by(|a, b| a.total_cmp(b));
+    let mut statuses = BTreeMap::new();
+    let mut errors = 0;
+    for s in samples {
+        let key = match s.status {
+            Some(code) => code.to_string(),
+            None => "error".to_string(),
+        };
+        if !matches!(s.status, Some(200..=299)) {
+            errors += 1;
+        }
+        *statuses.entry(key).or_insert(0) += 1;
+    }
+    let requests = samples.len();
+    OpReport {
+        requests,
+        errors,
+        error_rate: if requests == 0 {
+            0.0
+        } else {
+            errors as f64 / requests as f64
+        },
+        statuses,
+        latency_ms: LatencyMs {
+            mean: if requests == 0 {
+                0.0
+            } else {
+                latencies.iter().sum::<f64>() / requests as f64
+            },
+            p50: percentile(&latencies, 50.0),
+            p90: percentile(&latencies, 90.0),
+            p99: percentile(&latencies, 99.0),
+            max: latencies.last().copied().unwrap_or(0.0),
+        },
+        throughput: requests as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+    }
+}
+
+fn with_key(req: reqwest::RequestBuilder, api_key: Option<&str>) -> reqwest::RequestBuilder {
+    let req = req.header("X-Algolia-Application-Id", "flapjack");
+    match api_key {
+        Some(key) => req.header("X-Algolia-API-Key", key),
+        None => req,
+    }
+}
+
+/// The index's top searches with their counts, or an empty list when the
+/// server has none (or no analytics).
+async fn recorded_queries(client: &reqwest::Client, opts: &BenchOptions) -> Vec<(String, u64)> {
+    let start = (chrono::Utc::now() - chrono::Duration::days(30)).format("%Y-%m-%d");
+    let url = format!(
+        "{}/2/searches?index={}&limit=1000&startDate={}",
+        opts.target,
+        urlencoding::encode(&opts.index),
+        start
+    );
+    let Ok(resp) = with_key(client.get(&url), opts.api_key.as_deref())
+        .send()
+        .await
+    else {
+        return Vec::new();
+    };
+    if !resp.status().is_success() {
+        return Vec::new();
+    }
+    let body: Value = resp.json().await.unwrap_or_default();
+    body["searches"]
+        .as_array()
+        .map(|rows| {
+            rows.iter()
+                .filter_map(|row| {
+                    Some((row["search"].as_str()?.to_string(), row["count"].as_u64()?))
+                })
+                .filter(|(_, count)| *count > 0)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// One- and two-word queries taken from the names and titles of the
+/// preset's corpus.
+fn synthetic_queries(records: &[Value]) -> Vec<(String, u64)> {
+    let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+    for record in records {
+        let Some(text) = record["name"].as_str().or(record["title"].as_str()) else {
+            continue;
+        };
+        let words: Vec<&str> = text.split_whitespace().collect();
+        for n in 1..=2.min(words.len()) {
+            let query = words[words.len() - n..].join(" ").to_lowercase();
+            *counts.entry(query).or_insert(0) += 1;
+        }
+    }
+    counts.into_iter().collect()
+}
+
+/// Run the benchmark described by `opts`.
+pub async fn run(opts: &BenchOptions) -> Result<BenchReport, String> {
+    if !opts.qps.is_finite() || opts.qps <= 0.0 {
+        return Err("qps must be positive".to_string());
+    }
+    if !(0.0..=1.0).contains(&opts.write_ratio) {
+        return Err("write ratio must be between 0 and 1".to_string());
+    }
+    let target = opts.target.trim_end_matches('/').to_string();
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .pool_max_idle_per_host(opts.concurrency)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let preset = opts
+        .preset
+        .or_else(|| Preset::for_index(&opts.index))
+        .unwrap_or(Preset::Ecommerce);
+    let records: Vec<Value> = corpus::records(preset, SYNTHETIC_POOL, opts.seed as u32).collect();
+
+    let (queries, query_source) = match opts.scenario {
+        Scenario::Write => (Vec::new(), "none"),
+        _ => match recorded_queries(&client, opts).await {
+            recorded if !recorded.is_empty() => (recorded, "analytics"),
+            _ => (synthetic_queries(&records), "synthetic"),
+        },
+    };
+    let query_total: u64 = queries.iter().map(|(_, count)| count).sum();
+
+    let search_url = format!(
+        "{}/1/indexes/{}/query",
+        target,
+        urlencoding::encode(&opts.index)
+    );
+    let batch_url = format!(
+        "{}/1/indexes/{}/batch",
+        target,
+        urlencoding::encode(&opts.index)
+    );
+    let total = (opts.qps * opts.duration.as_secs_f64()).round() as u64;
+    let semaphore = Arc::new(Semaphore::new(opts.concurrency.max(1)));
+    let (tx, mut rx) = mpsc::unbounded_channel::<Sample>();
+    let mut rng = StdRng::seed_from_u64(opts.seed);
+    let mut dropped = 0;
+    let mut written = 0usize;
+
+    let start = Instant::now();
+    for n in 0..total {
+        let due = start + Duration::from_secs_f64(n as f64 / opts.qps);
+        tokio::time::sleep_until(due.into()).await;
+        let Ok(permit) = Arc::clone(&semaphore).try_acquire_owned() else {
+            dropped += 1;
+            continue;
+        };
+
+        let op = match opts.scenario {
+            Scenario::Search => Op::Search,
+            Scenario::Write => Op::Write,
+            Scenario::Mixed if rng.gen::<f64>() < opts.write_ratio => Op::Write,
+            Scenario::Mixed => Op::Search,
+        };
+        let req = match op {
+            Op::Search => {
+                let mut pick = rng.gen_range(0..query_total.max(1));
+                let query = queries
+                    .iter()
+                    .find(|(_, count)| {
+                        let hit = pick < *count;
+                        pick = pick.saturating_sub(*count);
+                        hit
+                    })
+                    .map(|(q, _)| q.as_str())
+                    .unwrap_or("");
+                client.post(&search_url).json(&json!({ "query": query }))
+            }
+            Op::Write => {
+                let requests: Vec<Value> = (0..opts.batch_size.max(1))
+                    .map(|_| {
+                        let mut body = records[written % records.len()].clone();
+                        body["objectID"] = json!(format!("bench-{:06}", written + 1));
+                        written += 1;
+                        json!({ "action": "addObject", "body": body })
+                    })
+                    .collect();
+                client
+                    .post(&batch_url)
+                    .json(&json!({ "requests": requests }))
+            }
+        };
+        let req = with_key(req, opts.api_key.as_deref());
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let status = match req.send().await {
+                Ok(resp) => {
+                    let status = resp.status().as_u16();
+                    // Latency includes reading the body.
+                    resp.bytes().await.ok().map(|_| status)
+                }
+                Err(_) => None,
+            };
+            let _ = tx.send(Sample {
+                op,
+                latency: due.elapsed(),
+                status,
+            });
+            drop(permit);
+        });
+    }
+    drop(tx);
+
+    let mut samples = Vec::with_capacity(total as usize);
+    while let Some(sample) = rx.recv().await {
+        samples.push(sample);
+    }
+    let elapsed = start.elapsed();
+    let report_for = |op: Op| {
+        let of_op: Vec<&Sample> = samples.iter().filter(|s| s.op == op).collect();
+        (!of_op.is_empty()).then(|| op_report(&of_op, elapsed))
+    };
+
+    Ok(BenchReport {
+        target,
+        index: opts.index.clone(),
+        scenario: opts.scenario.to_string(),
+        qps: opts.qps,
+        duration_secs: opts.duration.as_secs_f64(),
+        query_source: query_source.to_string(),
+        distinct_queries: queries.len(),
+        dropped,
+        search: report_for(Op::Search),
+        write: report_for(Op::Write),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let values: Vec<f64> = (1..=100).map(|v| v as f64).collect();
+        assert_eq!(percentile(&values, 50.0), 50.0);
+        assert_eq!(percentile(&values, 99.0), 99.0);
+        assert_eq!(percentile(&values, 100.0), 100.0);
+        assert_eq!(percentile(&[7.0], 0.0), 7.0);
+        assert_eq!(percentile(&[], 50.0), 0.0);
+    }
+
+    #[test]
+    fn synthetic_queries_come_from_the_corpus() {
+        let records: Vec<Value> = corpus::records(Preset::Ecommerce, 50, 1).collect();
+        let queries = synthetic_queries(&records);
+        assert!(!queries.is_empty());
+        assert!(queries
+            .iter()
+            .all(|(q, n)| *n > 0 && !q.is_empty() && q.split(' ').count() <= 2));
+    }
+
+    #[tokio::test]
+    async fn mixed_run_replays_recorded_queries_and_counts_errors() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new()
+            .route(
+                "/2/searches",
+                get(|| async {
+                    Json(json!({"searches": [
+                        {"search": "laptop", "count": 3, "nbHits": 10},
+                        {"search": "phone", "count": 1, "nbHits": 4},
+                    ]}))
+                }),
+            )
+            .route(
+                "/1/indexes/products/query",
+                post(|| async { Json(json!({"hits": [], "nbHits": 0})) }),
+            )
+            .route(
+                "/1/indexes/products/batch",
+                post(|| async { StatusCode::SERVICE_UNAVAILABLE }),
+            );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let report = run(&BenchOptions {
+            target,
+            api_key: Some("key".to_string()),
+            index: "products".to_string(),
+            scenario: Scenario::Mixed,
+            qps: 100.0,
+            duration: Duration::from_millis(500),
+            concurrency: 16,
+            write_ratio: 0.5,
+            batch_size: 2,
+            preset: None,
+            seed: 7,
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(report.query_source, "analytics");
+        assert_eq!(report.distinct_queries, 2);
+        let search = report.search.unwrap();
+        let write = report.write.unwrap();
+        assert_eq!(search.requests + write.requests + report.dropped, 50);
+        assert_eq!(search.errors, 0);
+        assert_eq!(write.errors, write.requests);
+        assert_eq!(write.statuses.get("503"), Some(&write.requests));
+        assert!(search.latency_ms.p50 <= search.latency_ms.p99);
+    }
+}
@@ -0,0 +1,256 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header::CONTENT_LENGTH, header::CONTENT_TYPE, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http_body_util::Limited;
+use std::sync::OnceLock;
+
+use flapjack::error::FlapjackError;
+
+const MB: usize = 1024 * 1024;
+
+/// Which request-body limit applies to a route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteClass {
+    /// Queries, browse, facet search, getObjects and rule/synonym search.
+    Search,
+    /// Everything else: batch ingest, settings, imports.
+    Ingest,
+}
+
+impl RouteClass {
+    pub fn classify(method: &Method, path: &str) -> Self {
+        if method != Method::POST {
+            return RouteClass::Ingest;
+        }
+        let Some(rest) = path.strip_prefix("/1/indexes/") else {
+            return RouteClass::Ingest;
+        };
+        let segments: Vec<&str> = rest.split('/').collect();
+        match segments.as_slice() {
+            [_, "query" | "queries" | "browse" | "objects"] => RouteClass::Search,
+            [_, "facets", _, "query" | "searchForFacetValues"] => RouteClass::Search,
+            [_, "synonyms" | "rules", "search"] => RouteClass::Search,
+            _ => RouteClass::Ingest,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RouteClass::Search => "search",
+            RouteClass::Ingest => "ingest",
+        }
+    }
+}
+
+/// Per-route-class body size caps, in bytes.
+///
+/// - `FLAPJACK_MAX_SEARCH_BODY_MB` (default 10) for search-type routes
+/// - `FLAPJACK_MAX_BODY_MB` (default 100) for ingest and everything else
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyLimits {
+    pub search: usize,
+    pub ingest: usize,
+}
+
+impl Default for BodyLimits {
+    fn default() -> Self {
+        BodyLimits {
+            search: 10 * MB,
+            ingest: 100 * MB,
+        }
+    }
+}
+
+impl BodyLimits {
+    pub fn from_env() -> Self {
+        let defaults = BodyLimits::default();
+        let mb = |var: &str, default: usize| {
+            std::env::var(var)
+                .ok()
+                .and_then(|s| s.parse::<usize>().ok())
+                .map(|v| v * MB)
+                .unwrap_or(default)
+        };
+        BodyLimits {
+            search: mb("FLAPJACK_MAX_SEARCH_BODY_MB", defaults.search),
+            ingest: mb("FLAPJACK_MAX_BODY_MB", defaults.ingest),
+        }
+    }
+
+    /// Process-wide limits, read from the environment once.
+    pub fn global() -> &'static BodyLimits {
+        static LIMITS: OnceLock<BodyLimits> = OnceLock::new();
+        LIMITS.get_or_init(BodyLimits::from_env)
+    }
+
+    pub fn for_class(&self, class: RouteClass) -> usize {
+        match class {
+            RouteClass::Search => self.search,
+            RouteClass::Ingest => self.ingest,
+        }
+    }
+
+    /// The largest limit of any class; used for the outer `DefaultBodyLimit`.
+    pub fn max(&self) -> usize {
+        self.search.max(self.ingest)
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "search": self.search,
+            "ingest": self.ingest,
+        })
+    }
+}
+
+/// Reject bodies over the route class limit with a structured 413.
+///
+/// Requests with a `Content-Length` are rejected up front. Other bodies are
+/// wrapped in a [`Limited`] with the class limit, so reading past it fails
+/// in the extractor; its plain-text 413 is rewritten into the same JSON
+/// error here.
+pub async fn enforce_body_limits(request: Request, next: Next) -> Response {
+    enforce(request, next, BodyLimits::global()).await
+}
+
+async fn enforce(request: Request, next: Next, limits: &BodyLimits) -> Response {
+    let class = RouteClass::classify(request.method(), request.uri().path());
+    let max = limits.for_class(class);
+    let too_large = || {
+        FlapjackError::PayloadTooLarge {
+            max,
+            route_class: class.as_str().to_string(),
+        }
+        .into_response()
+    };
+
+    let declared = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if declared.is_some_and(|len| len > max) {
+        return too_large();
+    }
+
+    let (parts, body) = request.into_parts();
+    let request = Request::from_parts(parts, Body::new(Limited::new(body, max)));
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/json"));
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        return too_large();
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_search_routes() {
+        for path in [
+            "/1/indexes/products/query",
+            "/1/indexes/products/queries",
+            "/1/indexes/*/queries",
+            "/1/indexes/*/objects",
+            "/1/indexes/products/browse",
+            "/1/indexes/products/facets/brand/query",
+            "/1/indexes/products/synonyms/search",
+            "/1/indexes/products/rules/search",
+        ] {
+            assert_eq!(
+                RouteClass::classify(&Method::POST, path),
+                RouteClass::Search,
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn classify_ingest_routes() {
+        for (method, path) in [
+            (Method::POST, "/1/indexes/products/batch"),
+            (Method::POST, "/1/indexes/products"),
+            (Method::PUT, "/1/indexes/products/settings"),
+            (Method::POST, "/1/indexes/products/import"),
+            (Method::PUT, "/1/indexes/products/query"),
+            (Method::POST, "/1/keys"),
+        ] {
+            assert_eq!(
+                RouteClass::classify(&method, path),
+                RouteClass::Ingest,
+                "{} {}",
+                method,
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn limits_per_class() {
+        let limits = BodyLimits::default();
+        assert_eq!(limits.for_class(RouteClass::Search), 10 * MB);
+        assert_eq!(limits.for_class(RouteClass::Ingest), 100 * MB);
+        assert_eq!(limits.max(), 100 * MB);
+    }
+
+    #[tokio::test]
+    async fn streamed_bodies_are_capped_per_class() {
+        use axum::body::Bytes;
+        use axum::routing::post;
+        use axum::Router;
+        use tower::ServiceExt;
+
+        static LIMITS: BodyLimits = BodyLimits {
+            search: 16,
+            ingest: 1024,
+        };
+        let app = Router::new()
+            .route(
+                "/1/indexes/:index/query",
+                post(|body: Bytes| async move { body.len().to_string() }),
+            )
+            .route(
+                "/1/indexes/:index/batch",
+                post(|body: Bytes| async move { body.len().to_string() }),
+            )
+            .layer(axum::middleware::from_fn(|request: Request, next: Next| {
+                enforce(request, next, &LIMITS)
+            }));
+        // No Content-Length: the size is only known once the body is read.
+        let streamed = |path: &str| {
+            let chunks = vec![Ok::<_, std::io::Error>(Bytes::from(vec![b'a'; 64]))];
+            axum::http::Request::post(path)
+                .body(Body::from_stream(futures_util::stream::iter(chunks)))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(streamed("/1/indexes/products/query"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let content_type = response.headers().get(CONTENT_TYPE).unwrap();
+        assert!(content_type
+            .to_str()
+            .unwrap()
+            .starts_with("application/json"));
+
+        let response = app
+            .oneshot(streamed("/1/indexes/products/batch"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
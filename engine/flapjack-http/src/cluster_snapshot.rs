@@ -0,0 +1,450 @@
+//! Cluster-wide consistent snapshots of one index.
+//!
+//! Per-node snapshots are taken at different moments, so restoring them
+//! leaves nodes that disagree. A cluster snapshot is coordinated by the node
+//! that receives `POST /1/indexes/{index}/cluster-snapshots`:
+//!
+//! 1. Writes to the index are paused on every node (`/internal/pause`).
+//! 2. The coordinator polls `/internal/snapshot/{index}/barrier` on every
+//!    node until all of them have no queued writes and their oplog positions
+//!    have not moved for a quiet period, so replicated ops still in flight
+//!    have landed. The positions at that point are the snapshot's LSNs.
+//! 3. Each node exports the index to `{data_dir}/.snapshots/{id}/` via
+//!    `/internal/snapshot/{index}/capture`. A node whose oplog moved past
+//!    its barrier LSN fails the snapshot.
+//! 4. Writes are resumed everywhere, whether or not the snapshot succeeded.
+//!
+//! The manifest listing every node's copy is kept next to the
+//! coordinator's copy.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::handlers::AppState;
+
+pub const SNAPSHOTS_DIR: &str = ".snapshots";
+const MANIFEST_FILE: &str = "manifest.json";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How long every node's oplog must stay put before the barrier is reached.
+/// Covers the replication batch window and the batcher's 2s retry.
+const QUIET_PERIOD: Duration = Duration::from_secs(3);
+const PEER_TIMEOUT_SECS: u64 = 300;
+
+/// One node's position, as reported by the barrier endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeBarrier {
+    pub node_id: String,
+    pub seq: u64,
+    pub pending_tasks: usize,
+}
+
+/// One node's copy of a cluster snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeSnapshot {
+    pub node_id: String,
+    /// Oplog sequence the copy was taken at.
+    pub seq: u64,
+    pub bytes: u64,
+    /// Path of the tar.gz on that node.
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterSnapshotManifest {
+    pub snapshot_id: String,
+    pub index_name: String,
+    pub created_at: String,
+    pub nodes: Vec<NodeSnapshot>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureRequest {
+    pub snapshot_id: String,
+    /// Oplog sequence the coordinator saw at the barrier.
+    pub seq: u64,
+}
+
+#[derive(Debug)]
+pub enum ClusterSnapshotError {
+    IndexNotFound,
+    /// Another cluster snapshot of the index is running, or the index was
+    /// already paused by someone else.
+    Busy(String),
+    Timeout(String),
+    Failed(String),
+}
+
+impl std::fmt::Display for ClusterSnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IndexNotFound => write!(f, "Index not found"),
+            Self::Busy(m) | Self::Timeout(m) | Self::Failed(m) => write!(f, "{}", m),
+        }
+    }
+}
+
+fn barrier_timeout() -> Duration {
+    let secs = std::env::var("FLAPJACK_CLUSTER_SNAPSHOT_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(60);
+    Duration::from_secs(secs)
+}
+
+fn in_progress() -> &'static Mutex<HashSet<String>> {
+    static IN_PROGRESS: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    IN_PROGRESS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+fn peer_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(PEER_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new())
+    })
+}
+
+fn snapshot_dir(base_path: &Path, snapshot_id: &str) -> PathBuf {
+    base_path.join(SNAPSHOTS_DIR).join(snapshot_id)
+}
+
+/// Snapshot IDs become directory names on every node.
+fn valid_snapshot_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 64
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// This node's position for `index_name`.
+pub fn local_barrier(state: &AppState, index_name: &str) -> NodeBarrier {
+    NodeBarrier {
+        node_id: crate::mcm::local_cluster_name(state),
+        seq: state
+            .manager
+            .get_oplog(index_name)
+            .map(|oplog| oplog.current_seq())
+            .unwrap_or(0),
+        pending_tasks: state.manager.pending_task_count(index_name),
+    }
+}
+
+/// Export this node's copy of `index_name` for `req.snapshot_id`. Fails if
+/// the oplog moved past the barrier, i.e. a write slipped in.
+pub fn capture_local(
+    state: &AppState,
+    index_name: &str,
+    req: &CaptureRequest,
+) -> Result<NodeSnapshot, String> {
+    if !valid_snapshot_id(&req.snapshot_id) {
+        return Err(format!("invalid snapshot id '{}'", req.snapshot_id));
+    }
+    let barrier = local_barrier(state, index_name);
+    if barrier.seq != req.seq || barrier.pending_tasks > 0 {
+        return Err(format!(
+            "{} moved past the barrier (seq {} -> {}, {} pending)",
+            barrier.node_id, req.seq, barrier.seq, barrier.pending_tasks
+        ));
+    }
+
+    let bytes =
+        flapjack::index::snapshot::export_to_bytes(&state.manager.base_path.join(index_name))
+            .map_err(|e| format!("export of {} failed: {}", index_name, e))?;
+    let dir = snapshot_dir(&state.manager.base_path, &req.snapshot_id);
+    let path = dir.join(format!("{}.tar.gz", index_name));
+    std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(&path, &bytes))
+        .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+
+    Ok(NodeSnapshot {
+        node_id: barrier.node_id,
+        seq: barrier.seq,
+        bytes: bytes.len() as u64,
+        path: path.to_string_lossy().to_string(),
+    })
+}
+
+/// Manifests of the cluster snapshots of `index_name` coordinated by this
+/// node, newest first.
+pub fn list_manifests(base_path: &Path, index_name: &str) -> Vec<ClusterSnapshotManifest> {
+    let Ok(entries) = std::fs::read_dir(base_path.join(SNAPSHOTS_DIR)) else {
+        return Vec::new();
+    };
+    let mut manifests: Vec<ClusterSnapshotManifest> = entries
+        .flatten()
+        .filter_map(|entry| std::fs::read(entry.path().join(MANIFEST_FILE)).ok())
+        .filter_map(|bytes| serde_json::from_slice::<ClusterSnapshotManifest>(&bytes).ok())
+        .filter(|m| m.index_name == index_name)
+        .collect();
+    manifests.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    manifests
+}
+
+/// True once every node is idle and no node's position changed between
+/// `previous` and `current`.
+fn is_quiescent(previous: &[NodeBarrier], current: &[NodeBarrier]) -> bool {
+    previous.len() == current.len()
+        && current.iter().all(|b| b.pending_tasks == 0)
+        && previous
+            .iter()
+            .zip(current)
+            .all(|(p, c)| p.node_id == c.node_id && p.seq == c.seq)
+}
+
+async fn peer_post(
+    base_url: &str,
+    path: &str,
+    body: Option<serde_json::Value>,
+) -> Result<reqwest::Response, String> {
+    let mut request = peer_client().post(format!("{}{}", base_url, path));
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("failed to reach {}: {}", base_url, e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(format!("{} returned {}: {}", base_url, status, text));
+    }
+    Ok(response)
+}
+
+async fn peer_barrier(base_url: &str, index_name: &str) -> Result<NodeBarrier, String> {
+    let response = peer_client()
+        .get(format!(
+            "{}/internal/snapshot/{}/barrier",
+            base_url, index_name
+        ))
+        .send()
+        .await
+        .map_err(|e| format!("failed to reach {}: {}", base_url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned {}", base_url, response.status()));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("bad barrier response from {}: {}", base_url, e))
+}
+
+/// Peer node IDs and base URLs.
+fn peers(state: &AppState) -> Vec<(String, String)> {
+    let Some(repl) = &state.replication_manager else {
+        return Vec::new();
+    };
+    repl.cluster_names()
+        .into_iter()
+        .skip(1)
+        .filter_map(|id| repl.peer(&id))
+        .map(|peer| (peer.peer_id().to_string(), peer.base_url().to_string()))
+        .collect()
+}
+
+/// Take a consistent snapshot of `index_name` on every node.
+pub async fn create_cluster_snapshot(
+    state: &AppState,
+    index_name: &str,
+) -> Result<ClusterSnapshotManifest, ClusterSnapshotError> {
+    if !state.manager.base_path.join(index_name).exists() {
+        return Err(ClusterSnapshotError::IndexNotFound);
+    }
+    if state.paused_indexes.is_paused(index_name) {
+        return Err(ClusterSnapshotError::Busy(format!(
+            "index '{}' is paused",
+            index_name
+        )));
+    }
+    if !in_progress().lock().unwrap().insert(index_name.to_string()) {
+        return Err(ClusterSnapshotError::Busy(format!(
+            "a cluster snapshot of '{}' is already running",
+            index_name
+        )));
+    }
+
+    let peers = peers(state);
+    state.paused_indexes.pause(index_name);
+    let result = async {
+        let pause_path = format!("/internal/pause/{}", index_name);
+        for (_, base_url) in &peers {
+            peer_post(base_url, &pause_path, None)
+                .await
+                .map_err(ClusterSnapshotError::Failed)?;
+        }
+        let barrier = wait_for_barrier(state, index_name, &peers).await?;
+        capture_all(state, index_name, &peers, &barrier).await
+    }
+    .await;
+
+    state.paused_indexes.resume(index_name);
+    let resume_path = format!("/internal/resume/{}", index_name);
+    for (peer_id, base_url) in &peers {
+        if let Err(e) = peer_post(base_url, &resume_path, None).await {
+            tracing::error!(
+                "[SNAPSHOT] failed to resume '{}' on {}: {}",
+                index_name,
+                peer_id,
+                e
+            );
+        }
+    }
+    in_progress().lock().unwrap().remove(index_name);
+    result
+}
+
+async fn wait_for_barrier(
+    state: &AppState,
+    index_name: &str,
+    peers: &[(String, String)],
+) -> Result<Vec<NodeBarrier>, ClusterSnapshotError> {
+    let deadline = Instant::now() + barrier_timeout();
+    let mut previous: Vec<NodeBarrier> = Vec::new();
+    let mut quiet_since = Instant::now();
+    loop {
+        let mut current = vec![local_barrier(state, index_name)];
+        for (_, base_url) in peers {
+            current.push(
+                peer_barrier(base_url, index_name)
+                    .await
+                    .map_err(ClusterSnapshotError::Failed)?,
+            );
+        }
+        if !is_quiescent(&previous, &current) {
+            quiet_since = Instant::now();
+        } else if quiet_since.elapsed() >= QUIET_PERIOD {
+            return Ok(current);
+        }
+        if Instant::now() >= deadline {
+            return Err(ClusterSnapshotError::Timeout(format!(
+                "nodes did not drain writes to '{}' within {}s",
+                index_name,
+                barrier_timeout().as_secs()
+            )));
+        }
+        previous = current;
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn capture_all(
+    state: &AppState,
+    index_name: &str,
+    peers: &[(String, String)],
+    barrier: &[NodeBarrier],
+) -> Result<ClusterSnapshotManifest, ClusterSnapshotError> {
+    let snapshot_id = uuid::Uuid::new_v4().to_string();
+    let mut nodes = vec![capture_local(
+        state,
+        index_name,
+        &CaptureRequest {
+            snapshot_id: snapshot_id.clone(),
+            seq: barrier[0].seq,
+        },
+    )
+    .map_err(ClusterSnapshotError::Failed)?];
+
+    let capture_path = format!("/internal/snapshot/{}/capture", index_name);
+    for ((_, base_url), position) in peers.iter().zip(&barrier[1..]) {
+        let body = serde_json::json!({"snapshotId": snapshot_id, "seq": position.seq});
+        let node = peer_post(base_url, &capture_path, Some(body))
+            .await
+            .map_err(ClusterSnapshotError::Failed)?
+            .json::<NodeSnapshot>()
+            .await
+            .map_err(|e| ClusterSnapshotError::Failed(format!("bad capture response: {}", e)))?;
+        nodes.push(node);
+    }
+
+    let manifest = ClusterSnapshotManifest {
+        snapshot_id: snapshot_id.clone(),
+        index_name: index_name.to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        nodes,
+    };
+    let path = snapshot_dir(&state.manager.base_path, &snapshot_id).join(MANIFEST_FILE);
+    let json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| ClusterSnapshotError::Failed(e.to_string()))?;
+    std::fs::write(&path, json).map_err(|e| {
+        ClusterSnapshotError::Failed(format!("failed to write {}: {}", path.display(), e))
+    })?;
+    tracing::info!(
+        "[SNAPSHOT] cluster snapshot {} of '{}' taken on {} node(s)",
+        snapshot_id,
+        index_name,
+        manifest.nodes.len()
+    );
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(node: &str, seq: u64, pending: usize) -> NodeBarrier {
+        NodeBarrier {
+            node_id: node.to_string(),
+            seq,
+            pending_tasks: pending,
+        }
+    }
+
+    #[test]
+    fn quiescent_requires_idle_and_unchanged_positions() {
+        let before = vec![at("a", 5, 0), at("b", 3, 0)];
+        assert!(is_quiescent(&before, &[at("a", 5, 0), at("b", 3, 0)]));
+        assert!(!is_quiescent(&before, &[at("a", 5, 0), at("b", 4, 0)]));
+        assert!(!is_quiescent(&before, &[at("a", 5, 1), at("b", 3, 0)]));
+        assert!(!is_quiescent(&[], &[at("a", 5, 0)]));
+    }
+
+    #[test]
+    fn snapshot_ids_are_safe_directory_names() {
+        assert!(valid_snapshot_id("0f8c6a1e-2b7d-4c4e-9a53-7d2f1e0b9c11"));
+        assert!(!valid_snapshot_id(""));
+        assert!(!valid_snapshot_id("../products"));
+        assert!(!valid_snapshot_id("a/b"));
+    }
+
+    #[test]
+    fn manifests_are_listed_per_index_newest_first() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        for (id, index, created_at) in [
+            ("s1", "products", "2026-01-01T00:00:00+00:00"),
+            ("s2", "products", "2026-02-01T00:00:00+00:00"),
+            ("s3", "users", "2026-03-01T00:00:00+00:00"),
+        ] {
+            let dir = snapshot_dir(tmp.path(), id);
+            std::fs::create_dir_all(&dir).unwrap();
+            let manifest = ClusterSnapshotManifest {
+                snapshot_id: id.to_string(),
+                index_name: index.to_string(),
+                created_at: created_at.to_string(),
+                nodes: Vec::new(),
+            };
+            std::fs::write(
+                dir.join(MANIFEST_FILE),
+                serde_json::to_vec(&manifest).unwrap(),
+            )
+            .unwrap();
+        }
+
+        let ids: Vec<String> = list_manifests(tmp.path(), "products")
+            .into_iter()
+            .map(|m| m.snapshot_id)
+            .collect();
+        assert_eq!(ids, vec!["s2", "s1"]);
+    }
+}
@@ -0,0 +1,176 @@
+//! Background compaction driven by each index's `compaction` setting.
+//!
+//! Every `FLAPJACK_COMPACTION_TICK_SECS` seconds (default 300) the scheduler
+//! checks the loaded indexes that have a policy and compacts those inside
+//! their window whose fragmentation crosses a threshold. Compactions run one
+//! at a time, through the index's write queue, and show up in the task API
+//! with per-merge progress. Nothing is compacted while the node is read-only
+//! for lack of disk space, since merges need room for the merged segment,
+//! nor any index whose maintenance mode blocks writes.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use flapjack::types::TaskStatus;
+use flapjack::IndexManager;
+
+use crate::disk_watchdog::DiskWatchdog;
+use crate::handlers::AppState;
+use crate::maintenance::check_writable;
+
+const TASK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Run one scheduler pass; returns the indexes that were compacted.
+pub async fn run_compaction_pass(state: &AppState) -> Vec<String> {
+    let mut compacted = Vec::new();
+    if DiskWatchdog::global().is_read_only() {
+        return compacted;
+    }
+    let now = chrono::Utc::now().time();
+    let mut tenants = state.manager.loaded_tenant_ids();
+    tenants.sort();
+    for name in tenants {
+        let Some(settings) = state.manager.get_settings(&name) else {
+            continue;
+        };
+        let Some(policy) = settings.compaction.as_ref() else {
+            continue;
+        };
+        if !policy.in_window(now)
+            || state.paused_indexes.is_paused(&name)
+            || check_writable(&state.manager.base_path, &name).is_err()
+        {
+            continue;
+        }
+        let Some(fragmentation) = state.manager.fragmentation(&name) else {
+            continue;
+        };
+        let since_last = state
+            .manager
+            .last_compaction(&name)
+            .and_then(|t| t.elapsed().ok());
+        let Some(reason) = policy.compaction_reason(&fragmentation, since_last) else {
+            continue;
+        };
+
+        tracing::info!("[COMPACTION] {}: compacting ({})", name, reason);
+        let task = match state
+            .manager
+            .compact_index_with(&name, policy.compact_options())
+        {
+            Ok(task) => task,
+            Err(e) => {
+                tracing::warn!("[COMPACTION] {}: failed to enqueue: {}", name, e);
+                continue;
+            }
+        };
+        match wait_for_task(&state.manager, &task.id).await {
+            TaskStatus::Failed(e) => tracing::warn!("[COMPACTION] {}: failed: {}", name, e),
+            _ => compacted.push(name),
+        }
+    }
+    compacted
+}
+
+async fn wait_for_task(manager: &IndexManager, task_id: &str) -> TaskStatus {
+    loop {
+        match manager.get_task(task_id) {
+            Ok(task) if matches!(task.status, TaskStatus::Enqueued | TaskStatus::Processing) => {
+                tokio::time::sleep(TASK_POLL_INTERVAL).await;
+            }
+            Ok(task) => return task.status,
+            Err(e) => return TaskStatus::Failed(e.to_string()),
+        }
+    }
+}
+
+pub fn spawn_compaction_scheduler(state: Arc<AppState>, tick_secs: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(tick_secs.max(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            run_compaction_pass(&state).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::app_state;
+    use flapjack::index::compaction::CompactionPolicy;
+    use flapjack::index::settings::IndexSettings;
+    use flapjack::types::{Document, FieldValue};
+    use tempfile::TempDir;
+
+    fn doc(id: &str) -> Document {
+        Document {
+            id: id.to_string(),
+            fields: [("title".to_string(), FieldValue::Text(id.to_string()))]
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    async fn fragmented_index(state: &AppState, name: &str, policy: Option<CompactionPolicy>) {
+        state.manager.create_tenant(name).unwrap();
+        IndexSettings {
+            compaction: policy,
+            ..Default::default()
+        }
+        .save(state.manager.base_path.join(name).join("settings.json"))
+        .unwrap();
+        for batch in [["a", "b"], ["c", "d"], ["e", "f"]] {
+            let docs = batch.into_iter().map(doc).collect();
+            state.manager.add_documents_sync(name, docs).await.unwrap();
+        }
+        state
+            .manager
+            .delete_documents_sync(name, vec!["a".into(), "c".into()])
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn compacts_fragmented_indexes_with_a_policy() {
+        let tmp = TempDir::new().unwrap();
+        let state = app_state(&tmp);
+        let policy = CompactionPolicy {
+            min_interval_secs: 0,
+            merge_batch_segments: 2,
+            ..Default::default()
+        };
+        fragmented_index(&state, "products", Some(policy)).await;
+        fragmented_index(&state, "manual", None).await;
+        let before = state.manager.fragmentation("products").unwrap();
+        assert!(before.deleted_documents > 0);
+
+        assert_eq!(run_compaction_pass(&state).await, vec!["products"]);
+        let after = state.manager.fragmentation("products").unwrap();
+        assert_eq!(after.deleted_documents, 0);
+        assert_eq!(after.documents, 4);
+        assert!(state.manager.last_compaction("manual").is_none());
+    }
+
+    #[tokio::test]
+    async fn skips_indexes_outside_their_window() {
+        let tmp = TempDir::new().unwrap();
+        let state = app_state(&tmp);
+        let now = chrono::Utc::now();
+        let later = |h: i64| {
+            (now + chrono::Duration::hours(h))
+                .format("%H:%M")
+                .to_string()
+        };
+        let policy: CompactionPolicy = serde_json::from_value(serde_json::json!({
+            "window": {"start": later(2), "end": later(3)},
+            "minIntervalSecs": 0
+        }))
+        .unwrap();
+        fragmented_index(&state, "products", Some(policy)).await;
+
+        assert!(run_compaction_pass(&state).await.is_empty());
+    }
+}
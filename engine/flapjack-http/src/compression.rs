@@ -0,0 +1,148 @@
+//! HTTP body compression.
+//!
+//! Request bodies sent with `Content-Encoding: gzip` or `br` are decompressed
+//! before routing, so batch and import clients can ship compressed payloads.
+//! Decompressed bodies are still capped by the route's body limit.
+//!
+//! Responses are compressed with gzip or brotli when the client advertises it
+//! in `Accept-Encoding` and the body is at least
+//! `FLAPJACK_COMPRESSION_MIN_BYTES` (default 1024) long. Images and event
+//! streams are never compressed. `FLAPJACK_COMPRESSION=false` turns response
+//! compression off; compressed requests are always accepted.
+
+use tower_http::compression::{
+    predicate::{And, DefaultPredicate, Predicate, SizeAbove},
+    CompressionLayer,
+};
+use tower_http::decompression::RequestDecompressionLayer;
+
+pub const DEFAULT_MIN_BYTES: u16 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionConfig {
+    pub enabled: bool,
+    pub min_bytes: u16,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_bytes: DEFAULT_MIN_BYTES,
+        }
+    }
+}
+
+impl CompressionConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("FLAPJACK_COMPRESSION")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(defaults.enabled),
+            min_bytes: std::env::var("FLAPJACK_COMPRESSION_MIN_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.min_bytes),
+        }
+    }
+
+    /// Response compression, negotiated from `Accept-Encoding`.
+    pub fn response_layer(&self) -> CompressionLayer<And<DefaultPredicate, SizeAbove>> {
+        CompressionLayer::new()
+            .gzip(self.enabled)
+            .br(self.enabled)
+            .compress_when(DefaultPredicate::new().and(SizeAbove::new(self.min_bytes)))
+    }
+}
+
+/// Decompression of gzip and brotli request bodies.
+pub fn request_layer() -> RequestDecompressionLayer {
+    RequestDecompressionLayer::new().gzip(true).br(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::{Body, Bytes},
+        http::{header, Request, StatusCode},
+        routing::post,
+        Router,
+    };
+    use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+    use std::io::{Read, Write};
+    use tower::ServiceExt;
+
+    fn app(config: CompressionConfig) -> Router {
+        Router::new()
+            .route("/echo", post(|body: Bytes| async move { body }))
+            .layer(request_layer())
+            .layer(config.response_layer())
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    async fn echo(
+        config: CompressionConfig,
+        payload: &[u8],
+        gzip_request: bool,
+    ) -> (Option<String>, Vec<u8>) {
+        let mut request = Request::post("/echo").header(header::ACCEPT_ENCODING, "gzip");
+        let body = if gzip_request {
+            request = request.header(header::CONTENT_ENCODING, "gzip");
+            gzip(payload)
+        } else {
+            payload.to_vec()
+        };
+        let response = app(config)
+            .oneshot(request.body(Body::from(body)).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let encoding = response
+            .headers()
+            .get(header::CONTENT_ENCODING)
+            .map(|v| v.to_str().unwrap().to_string());
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (encoding, bytes.to_vec())
+    }
+
+    #[tokio::test]
+    async fn gzip_request_bodies_are_decompressed() {
+        let payload = br#"{"requests":[{"action":"addObject","body":{"objectID":"1"}}]}"#;
+        let (encoding, body) = echo(CompressionConfig::default(), payload, true).await;
+        assert_eq!(encoding, None, "small responses stay uncompressed");
+        assert_eq!(body, payload);
+    }
+
+    #[tokio::test]
+    async fn large_responses_are_compressed() {
+        let payload = "x".repeat(4096);
+        let (encoding, body) = echo(CompressionConfig::default(), payload.as_bytes(), false).await;
+        assert_eq!(encoding.as_deref(), Some("gzip"));
+        let mut decoded = String::new();
+        GzDecoder::new(&body[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, payload);
+    }
+
+    #[tokio::test]
+    async fn disabled_config_skips_response_compression() {
+        let config = CompressionConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        let payload = "x".repeat(4096);
+        let (encoding, body) = echo(config, payload.as_bytes(), false).await;
+        assert_eq!(encoding, None);
+        assert_eq!(body, payload.as_bytes());
+    }
+}
@@ -0,0 +1,188 @@
+//! Server-wide CORS policy.
+//!
+//! By default any origin may call the API (the origin and requested headers
+//! are mirrored back). Setting `FLAPJACK_CORS_ALLOWED_ORIGINS` locks browsers
+//! to a list of origins instead; entries may hold one `*` wildcard, e.g.
+//! `https://*.example.com`. Keys can narrow this further with their own
+//! `allowedOrigins`, which the auth middleware enforces.
+
+use axum::http::{HeaderName, HeaderValue};
+use std::time::Duration;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(86400);
+
+/// - `FLAPJACK_CORS_ALLOWED_ORIGINS`: comma-separated origins (default: any)
+/// - `FLAPJACK_CORS_ALLOWED_HEADERS`: comma-separated request headers
+///   (default: whatever the preflight asks for)
+/// - `FLAPJACK_CORS_MAX_AGE_SECS`: preflight cache lifetime (default 86400)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsConfig {
+    /// Empty allows any origin.
+    pub allowed_origins: Vec<String>,
+    /// Empty allows any requested header.
+    pub allowed_headers: Vec<String>,
+    pub max_age: Duration,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        CorsConfig {
+            allowed_origins: Vec::new(),
+            allowed_headers: Vec::new(),
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+}
+
+fn list(var: &str) -> Vec<String> {
+    std::env::var(var)
+        .map(|v| {
+            v.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl CorsConfig {
+    pub fn from_env() -> Self {
+        let mut allowed_origins = list("FLAPJACK_CORS_ALLOWED_ORIGINS");
+        if allowed_origins.iter().any(|o| o == "*") {
+            allowed_origins.clear();
+        }
+        CorsConfig {
+            allowed_origins,
+            allowed_headers: list("FLAPJACK_CORS_ALLOWED_HEADERS"),
+            max_age: std::env::var("FLAPJACK_CORS_MAX_AGE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_MAX_AGE),
+        }
+    }
+
+    pub fn layer(&self) -> CorsLayer {
+        let allow_origin = if self.allowed_origins.is_empty() {
+            AllowOrigin::mirror_request()
+        } else {
+            let patterns = self.allowed_origins.clone();
+            AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+                origin
+                    .to_str()
+                    .is_ok_and(|origin| origin_matches(&patterns, origin))
+            })
+        };
+        let allow_headers = if self.allowed_headers.is_empty() {
+            AllowHeaders::mirror_request()
+        } else {
+            AllowHeaders::list(
+                self.allowed_headers
+                    .iter()
+                    .filter_map(|h| HeaderName::try_from(h.as_str()).ok()),
+            )
+        };
+        CorsLayer::new()
+            .allow_credentials(true)
+            .allow_methods(AllowMethods::mirror_request())
+            .allow_origin(allow_origin)
+            .allow_headers(allow_headers)
+            .max_age(self.max_age)
+    }
+}
+
+/// Whether `origin` matches one of `patterns` (case-insensitive; a pattern
+/// may contain a single `*`). An empty list matches everything.
+pub fn origin_matches(patterns: &[String], origin: &str) -> bool {
+    if patterns.is_empty() {
+        return true;
+    }
+    let origin = origin.to_ascii_lowercase();
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.to_ascii_lowercase();
+        match pattern.split_once('*') {
+            Some((prefix, suffix)) => {
+                origin.len() >= prefix.len() + suffix.len()
+                    && origin.starts_with(prefix)
+                    && origin.ends_with(suffix)
+            }
+            None => pattern.trim_end_matches('/') == origin,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, extract::Request, http::StatusCode, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn patterns(p: &[&str]) -> Vec<String> {
+        p.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn origin_patterns() {
+        let allowed = patterns(&["https://shop.example.com", "https://*.example.org"]);
+        assert!(origin_matches(&allowed, "https://shop.example.com"));
+        assert!(origin_matches(&allowed, "HTTPS://Shop.Example.com"));
+        assert!(origin_matches(&allowed, "https://eu.example.org"));
+        assert!(!origin_matches(&allowed, "https://example.org"));
+        assert!(!origin_matches(&allowed, "https://evil.com"));
+        assert!(!origin_matches(&allowed, "http://shop.example.com"));
+        assert!(origin_matches(&[], "https://evil.com"));
+    }
+
+    async fn preflight(config: &CorsConfig, origin: &str) -> axum::response::Response {
+        let app = Router::new()
+            .route("/1/indexes/products/query", get(|| async { "ok" }))
+            .layer(config.layer());
+        let request = Request::options("/1/indexes/products/query")
+            .header("origin", origin)
+            .header("access-control-request-method", "POST")
+            .header("access-control-request-headers", "x-algolia-api-key")
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn default_mirrors_any_origin() {
+        let response = preflight(&CorsConfig::default(), "https://anywhere.dev").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://anywhere.dev"
+        );
+        assert_eq!(headers["access-control-allow-headers"], "x-algolia-api-key");
+        assert_eq!(headers["access-control-max-age"], "86400");
+    }
+
+    #[tokio::test]
+    async fn restricted_origins_and_headers() {
+        let config = CorsConfig {
+            allowed_origins: patterns(&["https://*.example.com"]),
+            allowed_headers: patterns(&["x-algolia-api-key", "content-type"]),
+            max_age: Duration::from_secs(600),
+        };
+        let response = preflight(&config, "https://shop.example.com").await;
+        let headers = response.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://shop.example.com"
+        );
+        assert_eq!(
+            headers["access-control-allow-headers"],
+            "x-algolia-api-key,content-type"
+        );
+        assert_eq!(headers["access-control-max-age"], "600");
+
+        let response = preflight(&config, "https://evil.com").await;
+        assert!(response
+            .headers()
+            .get("access-control-allow-origin")
+            .is_none());
+    }
+}
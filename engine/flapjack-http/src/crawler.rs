@@ -0,0 +1,854 @@
+//! Website crawler: keep an index in sync with a site's pages.
+//!
+//! Each index can have one crawler (`PUT /1/indexes/:indexName/crawler`,
+//! stored as `crawler.json` in the index directory). A crawl fetches the
+//! configured start URLs and sitemaps, follows links within the allowed
+//! hosts, obeys robots.txt and `<meta name="robots">`, and upserts one record
+//! per page (objectID = URL) with `url`, `title`, `description`, `content`,
+//! `lang` and `crawledAt`.
+//!
+//! Crawls run on `POST /1/indexes/:indexName/crawler/run`, from a `crawl`
+//! schedule (`/1/schedules`), or from `flapjack crawl --config crawler.yaml`,
+//! which pushes the records to a running server over the batch API.
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use url::Url;
+
+use axum::extract::State;
+use axum::Json;
+
+use crate::dto::{AddDocumentsRequest, AddDocumentsResponse, BatchOperation};
+use crate::handlers::AppState;
+
+/// Records sent per batch request.
+const BATCH_SIZE: usize = 100;
+/// Page text beyond this many characters is not indexed.
+const MAX_CONTENT_CHARS: usize = 20_000;
+/// Sitemap indexes are followed this many levels deep.
+const MAX_SITEMAP_DEPTH: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrawlerConfig {
+    /// Target index. Taken from the path for crawlers managed over the API.
+    #[serde(default)]
+    pub index_name: String,
+    #[serde(default)]
+    pub start_urls: Vec<String>,
+    /// Sitemaps (or sitemap indexes) listing pages to fetch.
+    #[serde(default)]
+    pub sitemaps: Vec<String>,
+    /// Hosts pages may be fetched from (default: the hosts of `startUrls`
+    /// and `sitemaps`).
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+    /// URLs containing any of these strings are skipped.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Follow links found on fetched pages.
+    #[serde(default = "default_follow_links")]
+    pub follow_links: bool,
+    #[serde(default = "default_max_pages")]
+    pub max_pages: usize,
+    /// Pause between page fetches, in milliseconds.
+    #[serde(default = "default_delay_ms")]
+    pub delay_ms: u64,
+    #[serde(default = "default_user_agent")]
+    pub user_agent: String,
+}
+
+fn default_follow_links() -> bool {
+    true
+}
+
+fn default_max_pages() -> usize {
+    1000
+}
+
+fn default_delay_ms() -> u64 {
+    250
+}
+
+fn default_user_agent() -> String {
+    "FlapjackBot/1.0".to_string()
+}
+
+impl CrawlerConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.index_name.is_empty() {
+            return Err("indexName is required".to_string());
+        }
+        if self.start_urls.is_empty() && self.sitemaps.is_empty() {
+            return Err("startUrls or sitemaps is required".to_string());
+        }
+        for url in self.start_urls.iter().chain(&self.sitemaps) {
+            match Url::parse(url) {
+                Ok(u) if matches!(u.scheme(), "http" | "https") => {}
+                _ => return Err(format!("'{}' is not an http(s) URL", url)),
+            }
+        }
+        if self.max_pages == 0 {
+            return Err("maxPages must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+
+    fn hosts(&self) -> HashSet<String> {
+        if !self.allowed_hosts.is_empty() {
+            return self
+                .allowed_hosts
+                .iter()
+                .map(|h| h.to_ascii_lowercase())
+                .collect();
+        }
+        self.start_urls
+            .iter()
+            .chain(&self.sitemaps)
+            .filter_map(|u| Url::parse(u).ok())
+            .filter_map(|u| u.host_str().map(str::to_string))
+            .collect()
+    }
+}
+
+/// The rules of a robots.txt that apply to one user agent.
+#[derive(Debug, Clone, Default)]
+pub struct Robots {
+    /// (allow, path pattern)
+    rules: Vec<(bool, String)>,
+}
+
+impl Robots {
+    /// Rules of the groups naming `user_agent`'s product token, or of the
+    /// `*` groups when none does.
+    pub fn parse(txt: &str, user_agent: &str) -> Self {
+        let token = user_agent
+            .split('/')
+            .next()
+            .unwrap_or(user_agent)
+            .trim()
+            .to_ascii_lowercase();
+        let mut specific = Vec::new();
+        let mut wildcard = Vec::new();
+        let mut agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+        for line in txt.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim();
+            match key.as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        agents.clear();
+                        in_rules = false;
+                    }
+                    agents.push(value.to_ascii_lowercase());
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    // An empty Disallow allows everything
+                    if value.is_empty() {
+                        continue;
+                    }
+                    let rule = (key == "allow", value.to_string());
+                    if agents
+                        .iter()
+                        .any(|a| a != "*" && token.contains(a.as_str()))
+                    {
+                        specific.push(rule.clone());
+                    }
+                    if agents.iter().any(|a| a == "*") {
+                        wildcard.push(rule);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Robots {
+            rules: if specific.is_empty() {
+                wildcard
+            } else {
+                specific
+            },
+        }
+    }
+
+    /// Whether `path` (path and query) may be fetched: the longest matching
+    /// rule wins, Allow on a tie.
+    pub fn allows(&self, path: &str) -> bool {
+        let mut best: Option<(usize, bool)> = None;
+        for (allow, pattern) in &self.rules {
+            if !rule_matches(pattern, path) {
+                continue;
+            }
+            let len = pattern.len();
+            best = match best {
+                Some((best_len, best_allow))
+                    if best_len > len || (best_len == len && best_allow) =>
+                {
+                    Some((best_len, best_allow))
+                }
+                _ => Some((len, *allow)),
+            };
+        }
+        best.map_or(true, |(_, allow)| allow)
+    }
+}
+
+/// robots.txt path matching: a prefix, with `*` for any run of characters
+/// and a trailing `$` anchoring the end.
+fn rule_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if !path.starts_with(parts[0]) {
+        return false;
+    }
+    let mut pos = parts[0].len();
+    for (i, part) in parts.iter().enumerate().skip(1) {
+        if anchored && i == parts.len() - 1 {
+            return path.len() >= pos + part.len() && path.ends_with(part);
+        }
+        match path[pos..].find(part) {
+            Some(j) => pos += j + part.len(),
+            None => return false,
+        }
+    }
+    !anchored || pos == path.len()
+}
+
+/// `<loc>` entries of a sitemap, and whether it is a sitemap index.
+pub fn sitemap_locs(xml: &str) -> (Vec<String>, bool) {
+    let lower = xml.to_ascii_lowercase();
+    let mut locs = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = lower[pos..].find("<loc>") {
+        let start = pos + start + "<loc>".len();
+        let Some(end) = lower[start..].find("</loc>") else {
+            break;
+        };
+        let loc = xml[start..start + end].trim();
+        let loc = loc
+            .strip_prefix("<![CDATA[")
+            .and_then(|l| l.strip_suffix("]]>"))
+            .unwrap_or(loc);
+        locs.push(crate::html_text::decode_entities(loc.trim()));
+        pos = start + end;
+    }
+    (locs, lower.contains("<sitemapindex"))
+}
+
+/// Where crawled records go.
+pub enum CrawlTarget {
+    /// This server's batch handler.
+    Local(Arc<AppState>),
+    /// A running server's batch API (`flapjack crawl`).
+    Remote {
+        server: String,
+        api_key: Option<String>,
+        client: reqwest::Client,
+    },
+}
+
+impl CrawlTarget {
+    async fn upsert(
+        &self,
+        index_name: &str,
+        records: Vec<serde_json::Value>,
+    ) -> Result<(), String> {
+        match self {
+            CrawlTarget::Local(state) => {
+                // Local batches skip the HTTP middleware, so check disk
+                // space and the index's maintenance mode here.
+                crate::disk_watchdog::DiskWatchdog::global()
+                    .check_writable()
+                    .map_err(|e| e.to_string())?;
+                crate::maintenance::check_writable(&state.manager.base_path, index_name)
+                    .map_err(|e| e.to_string())?;
+                let requests = records
+                    .into_iter()
+                    .map(|record| BatchOperation {
+                        action: "addObject".to_string(),
+                        body: serde_json::from_value(record).unwrap_or_default(),
+                        create_if_not_exists: None,
+                    })
+                    .collect();
+                crate::handlers::objects::add_documents_batch_impl(
+                    State(Arc::clone(state)),
+                    index_name.to_string(),
+                    AddDocumentsRequest::Batch { requests },
+                )
+                .await
+                .map(|Json(response)| {
+                    if let AddDocumentsResponse::Algolia { rejected, .. } = response {
+                        for page in rejected {
+                            tracing::warn!(
+                                "[CRAWLER] {} rejected by documentSchema: {}",
+                                page.object_id,
+                                page.errors.join("; ")
+                            );
+                        }
+                    }
+                })
+                .map_err(|e| e.to_string())
+            }
+            CrawlTarget::Remote {
+                server,
+                api_key,
+                client,
+            } => {
+                let requests: Vec<_> = records
+                    .into_iter()
+                    .map(|record| json!({"action": "addObject", "body": record}))
+                    .collect();
+                let url = format!(
+                    "{}/1/indexes/{}/batch",
+                    server.trim_end_matches('/'),
+                    urlencoding::encode(index_name)
+                );
+                let mut req = client.post(&url).json(&json!({ "requests": requests }));
+                if let Some(key) = api_key {
+                    req = req.header("X-Algolia-API-Key", key);
+                }
+                let response = req.send().await.map_err(|e| e.to_string())?;
+                if !response.status().is_success() {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(format!("{} from {}: {}", status, url, body));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrawlCounts {
+    pub pages_fetched: usize,
+    pub pages_indexed: usize,
+    /// Disallowed by robots.txt, `noindex`, not HTML or without text.
+    pub pages_skipped: usize,
+    pub errors: usize,
+}
+
+fn allowed_url(url: &Url, hosts: &HashSet<String>, exclude: &[String]) -> bool {
+    matches!(url.scheme(), "http" | "https")
+        && url
+            .host_str()
+            .is_some_and(|h| hosts.contains(&h.to_ascii_lowercase()))
+        && !exclude.iter().any(|e| url.as_str().contains(e.as_str()))
+}
+
+fn page_record(url: &Url, page: crate::html_text::HtmlPage) -> serde_json::Value {
+    let mut record = json!({
+        "objectID": url.as_str(),
+        "url": url.as_str(),
+        "content": page.text.chars().take(MAX_CONTENT_CHARS).collect::<String>(),
+        "crawledAt": chrono::Utc::now().timestamp(),
+    });
+    for (key, value) in [
+        ("title", page.title),
+        ("description", page.description),
+        ("lang", page.lang),
+    ] {
+        if let Some(value) = value {
+            record[key] = value.into();
+        }
+    }
+    record
+}
+
+async fn fetch_text(client: &reqwest::Client, url: &Url) -> Result<(Url, String, bool), String> {
+    let response = client
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    let html = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|t| t.contains("html"));
+    let final_url = response.url().clone();
+    let text = response.text().await.map_err(|e| e.to_string())?;
+    Ok((final_url, text, html))
+}
+
+/// Crawl the site described by `config` into `target`, calling `progress`
+/// after each page. Fails only if records cannot be written; pages that
+/// cannot be fetched are counted as errors.
+pub async fn crawl(
+    config: &CrawlerConfig,
+    target: &CrawlTarget,
+    progress: &(dyn Fn(CrawlCounts) + Send + Sync),
+) -> Result<CrawlCounts, String> {
+    config.validate()?;
+    let client = reqwest::Client::builder()
+        .user_agent(config.user_agent.as_str())
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let hosts = config.hosts();
+    let mut counts = CrawlCounts::default();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut queue: VecDeque<Url> = VecDeque::new();
+    let mut enqueue = |url: Url, queue: &mut VecDeque<Url>| {
+        let mut url = url;
+        url.set_fragment(None);
+        if allowed_url(&url, &hosts, &config.exclude) && seen.insert(url.to_string()) {
+            queue.push_back(url);
+        }
+    };
+
+    for start in &config.start_urls {
+        if let Ok(url) = Url::parse(start) {
+            enqueue(url, &mut queue);
+        }
+    }
+    let mut sitemaps: Vec<(String, usize)> =
+        config.sitemaps.iter().map(|s| (s.clone(), 0)).collect();
+    while let Some((sitemap, depth)) = sitemaps.pop() {
+        let Ok(url) = Url::parse(&sitemap) else {
+            continue;
+        };
+        match fetch_text(&client, &url).await {
+            Ok((_, xml, _)) => {
+                let (locs, is_index) = sitemap_locs(&xml);
+                for loc in locs {
+                    if is_index {
+                        if depth + 1 < MAX_SITEMAP_DEPTH {
+                            sitemaps.push((loc, depth + 1));
+                        }
+                    } else if let Ok(page) = Url::parse(&loc) {
+                        enqueue(page, &mut queue);
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::warn!("[CRAWLER] sitemap {}: {}", sitemap, e);
+                counts.errors += 1;
+            }
+        }
+    }
+
+    let mut robots: HashMap<String, Robots> = HashMap::new();
+    let mut batch = Vec::new();
+    while let Some(url) = queue.pop_front() {
+        if counts.pages_fetched >= config.max_pages {
+            break;
+        }
+        let origin = url.origin().ascii_serialization();
+        if !robots.contains_key(&origin) {
+            let rules =
+                match fetch_text(&client, &url.join("/robots.txt").unwrap_or(url.clone())).await {
+                    Ok((_, txt, _)) => Robots::parse(&txt, &config.user_agent),
+                    Err(_) => Robots::default(),
+                };
+            robots.insert(origin.clone(), rules);
+        }
+        let path = match url.query() {
+            Some(q) => format!("{}?{}", url.path(), q),
+            None => url.path().to_string(),
+        };
+        if !robots[&origin].allows(&path) {
+            counts.pages_skipped += 1;
+            progress(counts);
+            continue;
+        }
+
+        if counts.pages_fetched > 0 && config.delay_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(config.delay_ms)).await;
+        }
+        counts.pages_fetched += 1;
+        let (final_url, body, is_html) = match fetch_text(&client, &url).await {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                tracing::debug!("[CRAWLER] {}: {}", url, e);
+                counts.errors += 1;
+                progress(counts);
+                continue;
+            }
+        };
+        if !is_html || !allowed_url(&final_url, &hosts, &config.exclude) {
+            counts.pages_skipped += 1;
+            progress(counts);
+            continue;
+        }
+        let page = crate::html_text::parse(&body);
+        if config.follow_links && !page.nofollow {
+            for link in &page.links {
+                if let Ok(next) = final_url.join(link) {
+                    enqueue(next, &mut queue);
+                }
+            }
+        }
+        if page.noindex || page.text.is_empty() {
+            counts.pages_skipped += 1;
+            progress(counts);
+            continue;
+        }
+        batch.push(page_record(&final_url, page));
+        if batch.len() >= BATCH_SIZE {
+            let records = std::mem::take(&mut batch);
+            counts.pages_indexed += records.len();
+            target.upsert(&config.index_name, records).await?;
+        }
+        progress(counts);
+    }
+    if !batch.is_empty() {
+        counts.pages_indexed += batch.len();
+        target.upsert(&config.index_name, batch).await?;
+        progress(counts);
+    }
+    Ok(counts)
+}
+
+fn config_path(base_path: &Path, index_name: &str) -> PathBuf {
+    base_path.join(index_name).join("crawler.json")
+}
+
+pub fn load_config(base_path: &Path, index_name: &str) -> std::io::Result<Option<CrawlerConfig>> {
+    let path = config_path(base_path, index_name);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json = std::fs::read_to_string(path)?;
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+pub fn save_config(base_path: &Path, config: &CrawlerConfig) -> std::io::Result<()> {
+    let path = config_path(base_path, &config.index_name);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(config).map_err(std::io::Error::other)?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(tmp, path)
+}
+
+pub fn delete_config(base_path: &Path, index_name: &str) -> std::io::Result<bool> {
+    let path = config_path(base_path, index_name);
+    if !path.exists() {
+        return Ok(false);
+    }
+    std::fs::remove_file(path)?;
+    Ok(true)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CrawlState {
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrawlStatus {
+    pub state: CrawlState,
+    #[serde(flatten)]
+    pub counts: CrawlCounts,
+    pub started_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Latest crawl of each index on this node.
+#[derive(Default)]
+pub struct CrawlRegistry {
+    statuses: DashMap<String, CrawlStatus>,
+}
+
+impl CrawlRegistry {
+    pub fn global() -> &'static CrawlRegistry {
+        static REGISTRY: OnceLock<CrawlRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(CrawlRegistry::default)
+    }
+
+    pub fn get(&self, index_name: &str) -> Option<CrawlStatus> {
+        self.statuses.get(index_name).map(|s| s.clone())
+    }
+
+    pub fn is_running(&self, index_name: &str) -> bool {
+        self.get(index_name)
+            .is_some_and(|s| s.state == CrawlState::Running)
+    }
+
+    /// Record the start of a crawl; false if one is already running.
+    fn begin(&self, index_name: &str) -> bool {
+        let mut running = false;
+        let started = || CrawlStatus {
+            state: CrawlState::Running,
+            counts: CrawlCounts::default(),
+            started_at: chrono::Utc::now().to_rfc3339(),
+            finished_at: None,
+            duration_ms: None,
+            message: None,
+        };
+        self.statuses
+            .entry(index_name.to_string())
+            .and_modify(|s| {
+                if s.state == CrawlState::Running {
+                    running = true;
+                } else {
+                    *s = started();
+                }
+            })
+            .or_insert_with(started);
+        !running
+    }
+
+    fn update(&self, index_name: &str, f: impl FnOnce(&mut CrawlStatus)) {
+        if let Some(mut status) = self.statuses.get_mut(index_name) {
+            f(&mut status);
+        }
+    }
+}
+
+/// Crawl the site configured for `index_name` into it, now.
+pub async fn crawl_index(state: Arc<AppState>, index_name: String) -> Result<CrawlCounts, String> {
+    let config = load_config(&state.manager.base_path, &index_name)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No crawler configured for index '{}'", index_name))?;
+    let registry = CrawlRegistry::global();
+    if !registry.begin(&index_name) {
+        return Err(format!("A crawl of '{}' is already running", index_name));
+    }
+    let start = Instant::now();
+    let progress = |counts: CrawlCounts| registry.update(&index_name, |s| s.counts = counts);
+    let result = crawl(&config, &CrawlTarget::Local(state), &progress).await;
+    registry.update(&index_name, |s| {
+        s.state = if result.is_ok() {
+            CrawlState::Done
+        } else {
+            CrawlState::Failed
+        };
+        if let Ok(counts) = &result {
+            s.counts = *counts;
+        }
+        s.message = result.as_ref().err().cloned();
+        s.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        s.duration_ms = Some(start.elapsed().as_millis() as u64);
+    });
+    match &result {
+        Ok(counts) => tracing::info!(
+            "[CRAWLER] {}: indexed {} of {} pages in {}ms",
+            index_name,
+            counts.pages_indexed,
+            counts.pages_fetched,
+            start.elapsed().as_millis()
+        ),
+        Err(e) => tracing::warn!("[CRAWLER] {}: {}", index_name, e),
+    }
+    result
+}
+
+/// Crawl `index_name` in the background.
+pub fn spawn_crawl(state: Arc<AppState>, index_name: String) {
+    tokio::spawn(async move {
+        let _ = crawl_index(state, index_name).await;
+    });
+}
+
+/// Run `flapjack crawl`: crawl with the YAML (or JSON) config at `path` and
+/// push the records to the server at `server`.
+pub async fn crawl_to_server(
+    path: &Path,
+    server: &str,
+    api_key: Option<String>,
+) -> Result<CrawlCounts, String> {
+    let raw = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let config: CrawlerConfig =
+        serde_yaml::from_str(&raw).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let target = CrawlTarget::Remote {
+        server: server.to_string(),
+        api_key,
+        client: reqwest::Client::new(),
+    };
+    crawl(&config, &target, &|_| {}).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::app_state;
+    use axum::response::Html;
+    use axum::routing::get;
+    use axum::Router;
+    use flapjack::types::FieldValue;
+    use tempfile::TempDir;
+
+    #[test]
+    fn robots_groups_and_longest_match() {
+        let txt = "\
+            # comment\n\
+            User-agent: *\n\
+            Disallow: /private\n\
+            Allow: /private/press\n\
+            Disallow: /*.pdf$\n\
+            Sitemap: https://example.com/sitemap.xml\n\
+            \n\
+            User-agent: OtherBot\n\
+            Disallow: /\n";
+        let robots = Robots::parse(txt, "FlapjackBot/1.0");
+        assert!(robots.allows("/docs/start"));
+        assert!(!robots.allows("/private/team"));
+        assert!(robots.allows("/private/press/2024"));
+        assert!(!robots.allows("/files/guide.pdf"));
+        assert!(robots.allows("/files/guide.pdf?download=1"));
+
+        let robots = Robots::parse(txt, "OtherBot/2.0");
+        assert!(!robots.allows("/docs/start"));
+
+        let robots = Robots::parse("User-agent: flapjackbot\nDisallow:\n", "FlapjackBot/1.0");
+        assert!(robots.allows("/anything"));
+    }
+
+    #[test]
+    fn sitemap_entries() {
+        let xml = r#"<?xml version="1.0"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <url><loc>https://example.com/a?x=1&amp;y=2</loc></url>
+              <url><loc> <![CDATA[https://example.com/b]]> </loc></url>
+            </urlset>"#;
+        let (locs, is_index) = sitemap_locs(xml);
+        assert_eq!(
+            locs,
+            vec!["https://example.com/a?x=1&y=2", "https://example.com/b"]
+        );
+        assert!(!is_index);
+        let (_, is_index) =
+            sitemap_locs("<sitemapindex><sitemap><loc>https://e.com/s1.xml</loc></sitemap>");
+        assert!(is_index);
+    }
+
+    #[test]
+    fn config_needs_an_index_and_urls() {
+        let config: CrawlerConfig =
+            serde_yaml::from_str("indexName: docs\nstartUrls: [\"https://example.com/\"]\n")
+                .unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.max_pages, 1000);
+        assert!(config.follow_links);
+        assert_eq!(config.hosts(), HashSet::from(["example.com".to_string()]));
+
+        let config = CrawlerConfig {
+            start_urls: vec!["ftp://example.com/".to_string()],
+            ..config
+        };
+        assert!(config.validate().is_err());
+    }
+
+    async fn serve_site() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let base = format!("http://{}", addr);
+        let sitemap = format!("<urlset><url><loc>{}/docs/a</loc></url></urlset>", base);
+        let app = Router::new()
+            .route(
+                "/robots.txt",
+                get(|| async { "User-agent: *\nDisallow: /private\n" }),
+            )
+            .route("/sitemap.xml", get(move || async move { sitemap }))
+            .route(
+                "/docs/a",
+                get(|| async {
+                    Html(
+                        r#"<html><head><title>Page A</title></head><body>
+                        <p>Alpha page.</p>
+                        <a href="/docs/b#intro">B</a> <a href="/private/x">Private</a>
+                        <a href="hidden">Hidden</a> <a href="https://elsewhere.test/">Out</a>
+                        </body></html>"#,
+                    )
+                }),
+            )
+            .route(
+                "/docs/b",
+                get(|| async { Html("<title>Page B</title><p>Beta page.</p>") }),
+            )
+            .route(
+                "/docs/hidden",
+                get(|| async {
+                    Html(r#"<meta name="robots" content="noindex"><p>Hidden page.</p>"#)
+                }),
+            )
+            .route(
+                "/private/x",
+                get(|| async { Html("<p>Never fetched.</p>") }),
+            );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        base
+    }
+
+    #[tokio::test]
+    async fn crawls_sitemap_and_links_into_the_index() {
+        let base = serve_site().await;
+        let tmp = TempDir::new().unwrap();
+        let state = app_state(&tmp);
+        state.manager.create_tenant("site").unwrap();
+        let config = CrawlerConfig {
+            index_name: "site".to_string(),
+            start_urls: Vec::new(),
+            sitemaps: vec![format!("{}/sitemap.xml", base)],
+            allowed_hosts: Vec::new(),
+            exclude: Vec::new(),
+            follow_links: true,
+            max_pages: 10,
+            delay_ms: 0,
+            user_agent: default_user_agent(),
+        };
+        save_config(tmp.path(), &config).unwrap();
+        assert_eq!(load_config(tmp.path(), "site").unwrap(), Some(config));
+
+        let counts = crawl_index(state.clone(), "site".to_string())
+            .await
+            .unwrap();
+        assert_eq!(counts.pages_indexed, 2);
+        // /private/x by robots.txt, /docs/hidden by its noindex meta
+        assert_eq!(counts.pages_skipped, 2);
+        assert_eq!(counts.errors, 0);
+        let status = CrawlRegistry::global().get("site").unwrap();
+        assert_eq!(status.state, CrawlState::Done);
+
+        let page_b = format!("{}/docs/b", base);
+        let mut doc = None;
+        for _ in 0..200 {
+            doc = state.manager.get_document("site", &page_b).unwrap();
+            if doc.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let doc = doc.expect("page B was not indexed");
+        assert_eq!(
+            doc.fields.get("title"),
+            Some(&FieldValue::Text("Page B".into()))
+        );
+        assert_eq!(
+            doc.fields.get("content"),
+            Some(&FieldValue::Text("Beta page.".into()))
+        );
+    }
+}
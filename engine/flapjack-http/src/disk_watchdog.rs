@@ -0,0 +1,338 @@
+//! Disk-space watchdog: puts the node in read-only mode when the data volume
+//! runs low and lifts it once space is back.
+//!
+//! The node goes read-only when free space drops below
+//! `FLAPJACK_DISK_READONLY_FREE_MB` and writable again once it is above
+//! `FLAPJACK_DISK_RESUME_FREE_MB`; the gap keeps it from flapping around a
+//! single threshold. While read-only, [`read_only_guard`] rejects writes with
+//! 507 Insufficient Storage and searches keep working. Background writers
+//! that bypass the middleware (crawls, schedules, compaction, TTL sweeps)
+//! hold off too. Transitions are logged
+//! and, with `FLAPJACK_DISK_WEBHOOK_URL` set, POSTed as `disk.readOnly` /
+//! `disk.recovered` events.
+
+use axum::{
+    extract::Request,
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use flapjack::error::FlapjackError;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::body_limits::RouteClass;
+
+const MB: u64 = 1024 * 1024;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// `free_bytes` before the first measurement.
+const UNKNOWN: u64 = u64::MAX;
+
+/// Free-space thresholds, in bytes.
+///
+/// - `FLAPJACK_DISK_READONLY_FREE_MB` (default 2048, `0` disables the watchdog)
+/// - `FLAPJACK_DISK_RESUME_FREE_MB` (default 3072, never below the read-only threshold)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskThresholds {
+    pub read_only_below: u64,
+    pub resume_above: u64,
+}
+
+impl Default for DiskThresholds {
+    fn default() -> Self {
+        DiskThresholds {
+            read_only_below: 2048 * MB,
+            resume_above: 3072 * MB,
+        }
+    }
+}
+
+impl DiskThresholds {
+    pub fn from_env() -> Self {
+        let defaults = DiskThresholds::default();
+        let mb = |var: &str, default: u64| {
+            std::env::var(var)
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .map(|v| v * MB)
+                .unwrap_or(default)
+        };
+        let read_only_below = mb("FLAPJACK_DISK_READONLY_FREE_MB", defaults.read_only_below);
+        DiskThresholds {
+            read_only_below,
+            resume_above: mb("FLAPJACK_DISK_RESUME_FREE_MB", defaults.resume_above)
+                .max(read_only_below),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiskTransition {
+    ReadOnly,
+    Recovered,
+}
+
+impl DiskTransition {
+    fn event(&self) -> &'static str {
+        match self {
+            DiskTransition::ReadOnly => "disk.readOnly",
+            DiskTransition::Recovered => "disk.recovered",
+        }
+    }
+}
+
+pub struct DiskWatchdog {
+    thresholds: DiskThresholds,
+    read_only: AtomicBool,
+    free_bytes: AtomicU64,
+}
+
+impl DiskWatchdog {
+    pub fn new(thresholds: DiskThresholds) -> Self {
+        DiskWatchdog {
+            thresholds,
+            read_only: AtomicBool::new(false),
+            free_bytes: AtomicU64::new(UNKNOWN),
+        }
+    }
+
+    /// Process-wide watchdog, with thresholds read from the environment once.
+    pub fn global() -> &'static DiskWatchdog {
+        static WATCHDOG: OnceLock<DiskWatchdog> = OnceLock::new();
+        WATCHDOG.get_or_init(|| DiskWatchdog::new(DiskThresholds::from_env()))
+    }
+
+    pub fn thresholds(&self) -> DiskThresholds {
+        self.thresholds
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    /// Free bytes at the last check; `None` until the first one.
+    pub fn free_bytes(&self) -> Option<u64> {
+        match self.free_bytes.load(Ordering::Relaxed) {
+            UNKNOWN => None,
+            free => Some(free),
+        }
+    }
+
+    /// Record a free-space measurement, returning the mode change it caused.
+    pub fn observe(&self, free: u64) -> Option<DiskTransition> {
+        self.free_bytes.store(free, Ordering::Relaxed);
+        if self.is_read_only() {
+            if free > self.thresholds.resume_above {
+                self.read_only.store(false, Ordering::Relaxed);
+                return Some(DiskTransition::Recovered);
+            }
+        } else if free < self.thresholds.read_only_below {
+            self.read_only.store(true, Ordering::Relaxed);
+            return Some(DiskTransition::ReadOnly);
+        }
+        None
+    }
+
+    /// `Err` while read-only, for background writers that run past
+    /// [`read_only_guard`].
+    pub fn check_writable(&self) -> Result<(), FlapjackError> {
+        if self.is_read_only() {
+            Err(self.rejection())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn rejection(&self) -> FlapjackError {
+        FlapjackError::InsufficientStorage {
+            free_bytes: self.free_bytes().unwrap_or(0),
+            min_bytes: self.thresholds.resume_above,
+        }
+    }
+}
+
+/// Check free space under `data_dir` every `interval` until the process
+/// exits. The first check runs immediately.
+pub async fn run_disk_watchdog(data_dir: PathBuf, interval: Duration, webhook_url: Option<String>) {
+    let watchdog = DiskWatchdog::global();
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        let free = match fs2::available_space(&data_dir) {
+            Ok(free) => free,
+            Err(e) => {
+                tracing::warn!("[DISK] Failed to read free space of {:?}: {}", data_dir, e);
+                continue;
+            }
+        };
+        let Some(transition) = watchdog.observe(free) else {
+            continue;
+        };
+        let thresholds = watchdog.thresholds();
+        match transition {
+            DiskTransition::ReadOnly => tracing::error!(
+                "[DISK] {} bytes free (< {}); rejecting writes until above {}",
+                free,
+                thresholds.read_only_below,
+                thresholds.resume_above
+            ),
+            DiskTransition::Recovered => tracing::info!(
+                "[DISK] {} bytes free (> {}); accepting writes again",
+                free,
+                thresholds.resume_above
+            ),
+        }
+        if let Some(url) = &webhook_url {
+            notify_webhook(url, transition, free, thresholds).await;
+        }
+    }
+}
+
+async fn notify_webhook(url: &str, transition: DiskTransition, free: u64, t: DiskThresholds) {
+    let payload = serde_json::json!({
+        "event": transition.event(),
+        "freeBytes": free,
+        "readOnlyBelowBytes": t.read_only_below,
+        "resumeAboveBytes": t.resume_above,
+        "at": chrono::Utc::now().to_rfc3339(),
+    });
+    let result = reqwest::Client::new()
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&payload)
+        .send()
+        .await;
+    match result {
+        Ok(resp) if resp.status().is_success() => {}
+        Ok(resp) => tracing::warn!(
+            "[DISK] {} webhook returned {}",
+            transition.event(),
+            resp.status()
+        ),
+        Err(e) => tracing::warn!("[DISK] {} webhook failed: {}", transition.event(), e),
+    }
+}
+
+/// Whether a request is rejected while the node is read-only: everything
+/// except reads, search-type POSTs, health probes and index deletion (which
+/// is how operators reclaim space).
+fn is_blocked_write(method: &Method, path: &str) -> bool {
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return false;
+    }
+    if path == "/health" || path.starts_with("/health/") || path == "/internal/status" {
+        return false;
+    }
+    if method == Method::DELETE {
+        if let Some(rest) = path.strip_prefix("/1/indexes/") {
+            if !rest.is_empty() && !rest.contains('/') {
+                return false;
+            }
+        }
+    }
+    RouteClass::classify(method, path) != RouteClass::Search
+}
+
+/// Reject writes with 507 while `watchdog` has the node in read-only mode.
+pub async fn read_only_guard(request: Request, next: Next, watchdog: &DiskWatchdog) -> Response {
+    if watchdog.is_read_only() && is_blocked_write(request.method(), request.uri().path()) {
+        return watchdog.rejection().into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, middleware, routing::any, Router};
+    use tower::ServiceExt;
+
+    fn thresholds() -> DiskThresholds {
+        DiskThresholds {
+            read_only_below: 100,
+            resume_above: 200,
+        }
+    }
+
+    #[test]
+    fn read_only_with_hysteresis() {
+        let w = DiskWatchdog::new(thresholds());
+        assert_eq!(w.free_bytes(), None);
+        assert_eq!(w.observe(150), None);
+        assert!(!w.is_read_only());
+        assert_eq!(w.observe(99), Some(DiskTransition::ReadOnly));
+        assert!(w.is_read_only());
+        // Back above the read-only threshold but not the resume one.
+        assert_eq!(w.observe(150), None);
+        assert!(w.is_read_only());
+        assert_eq!(w.observe(201), Some(DiskTransition::Recovered));
+        assert!(!w.is_read_only());
+        assert_eq!(w.free_bytes(), Some(201));
+    }
+
+    #[test]
+    fn zero_threshold_disables() {
+        let w = DiskWatchdog::new(DiskThresholds {
+            read_only_below: 0,
+            resume_above: 0,
+        });
+        assert_eq!(w.observe(0), None);
+        assert!(!w.is_read_only());
+    }
+
+    #[test]
+    fn searches_and_index_deletion_are_not_blocked() {
+        assert!(!is_blocked_write(&Method::GET, "/1/indexes/products"));
+        assert!(!is_blocked_write(
+            &Method::POST,
+            "/1/indexes/products/query"
+        ));
+        assert!(!is_blocked_write(&Method::POST, "/1/indexes/*/queries"));
+        assert!(!is_blocked_write(&Method::DELETE, "/1/indexes/products"));
+        assert!(!is_blocked_write(&Method::POST, "/health/ready"));
+        assert!(is_blocked_write(&Method::POST, "/1/indexes/products/batch"));
+        assert!(is_blocked_write(
+            &Method::PUT,
+            "/1/indexes/products/settings"
+        ));
+        assert!(is_blocked_write(&Method::DELETE, "/1/indexes/products/abc"));
+        assert!(is_blocked_write(&Method::POST, "/1/keys"));
+    }
+
+    #[tokio::test]
+    async fn guard_rejects_writes_with_507() {
+        let watchdog: &'static DiskWatchdog = Box::leak(Box::new(DiskWatchdog::new(thresholds())));
+        watchdog.observe(10);
+        let app = Router::new()
+            .route("/*path", any(|| async { "ok" }))
+            .layer(middleware::from_fn(move |req: Request, next: Next| {
+                read_only_guard(req, next, watchdog)
+            }));
+
+        let write = Request::post("/1/indexes/products/batch")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(write).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INSUFFICIENT_STORAGE);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["code"], "insufficient_storage");
+
+        let search = Request::post("/1/indexes/products/query")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(search).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        watchdog.observe(500);
+        let write = Request::post("/1/indexes/products/batch")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(write).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
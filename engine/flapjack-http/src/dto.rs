@@ -52,6 +52,10 @@ pub struct CreateIndexRequest {
     pub uid: String,
     #[serde(default)]
     pub schema: IndexSchema,
+    /// Name of an index template whose settings, synonyms and rules are
+    /// applied to the new index.
+    #[serde(default)]
+    pub template: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Default, ToSchema)]
@@ -100,6 +104,9 @@ pub enum AddDocumentsResponse {
         task_id: i64,
         #[serde(rename = "objectIDs")]
         object_ids: Vec<String>,
+        /// Records refused by the index's `documentSchema`; not written.
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        rejected: Vec<RejectedObject>,
     },
     Legacy {
         task_uid: String,
@@ -108,6 +115,13 @@ pub enum AddDocumentsResponse {
     },
 }
 
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RejectedObject {
+    #[serde(rename = "objectID")]
+    pub object_id: String,
+    pub errors: Vec<String>,
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TaskResponse {
@@ -119,6 +133,7 @@ pub struct TaskResponse {
     pub rejected_count: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    pub created_at: String,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -170,10 +185,19 @@ pub struct SearchRequest {
     pub hits_per_page: Option<usize>,
     #[serde(default)]
     pub page: usize,
+    /// Deep-paging cursor: `""` starts an iteration, and each response
+    /// carries the `cursor` for the next page. `page` is ignored and
+    /// `paginationLimitedTo` does not apply.
+    #[serde(default)]
+    pub cursor: Option<String>,
     #[serde(default, deserialize_with = "deserialize_string_or_vec")]
     pub facets: Option<Vec<String>>,
     #[serde(default)]
     pub sort: Option<Vec<String>>,
+    /// Name of one of the index's `sortReplicas`: the search runs on that
+    /// replica instead, or with its `sort`.
+    #[serde(default, rename = "sortBy")]
+    pub sort_by: Option<String>,
     #[serde(default)]
     pub distinct: Option<serde_json::Value>,
     #[serde(default)]
@@ -236,6 +260,10 @@ pub struct SearchRequest {
     pub get_ranking_info: Option<bool>,
     #[serde(default, rename = "responseFields")]
     pub response_fields: Option<Vec<String>>,
+    /// `true` forces an exact `nbHits` even past the count budget
+    /// (`FLAPJACK_COUNT_BUDGET`) for filter-only queries.
+    #[serde(default, rename = "exhaustiveNbHits")]
+    pub exhaustive_nb_hits: Option<bool>,
     #[serde(default, rename = "aroundLatLng")]
     pub around_lat_lng: Option<String>,
     #[serde(default, rename = "aroundRadius")]
@@ -253,6 +281,38 @@ pub struct SearchRequest {
     /// Client IP — not deserialized from JSON, set by handler from headers
     #[serde(skip)]
     pub user_ip: Option<String>,
+    /// Arm chosen by the caller for `assignmentMode: "external"` experiments —
+    /// set by handler from the `X-Flapjack-AB-Variant` header
+    #[serde(skip)]
+    pub ab_test_variant: Option<String>,
+    /// Session ID for experiments assigned by session — set by handler from
+    /// the `X-Flapjack-Session-ID` header
+    #[serde(skip)]
+    pub session_id: Option<String>,
+    /// Values of the headers running experiments assign by (lowercased
+    /// name → value) — set by handler
+    #[serde(skip)]
+    pub assignment_headers: HashMap<String, String>,
+    /// The calling API key, for feature flag targeting — set by handler from
+    /// the auth middleware's request extensions
+    #[serde(skip)]
+    pub api_key: Option<String>,
+    /// Attribute visibility of the calling key — set by handler from the
+    /// auth middleware's request extensions
+    #[serde(skip)]
+    pub attribute_restrictions: Option<crate::auth::AttributeRestrictions>,
+    /// Set on queries the server runs itself (shadow evaluations, warm-up),
+    /// which bypass experiments, shadow sampling and usage counters
+    #[serde(skip)]
+    pub internal: bool,
+    /// Response compatibility mode — set by handler from the
+    /// `X-Flapjack-API-Version` header or `apiVersion` query parameter
+    #[serde(skip)]
+    pub api_version: crate::api_version::ApiVersion,
+    /// `attributeWeights` overrides of the experiment arm serving the
+    /// query — set by handler
+    #[serde(skip)]
+    pub attribute_weights: Option<HashMap<String, f32>>,
     #[serde(default, rename = "aroundLatLngViaIP")]
     pub around_lat_lng_via_ip: Option<bool>,
     #[serde(default, rename = "removeStopWords")]
@@ -265,13 +325,41 @@ pub struct SearchRequest {
     pub mode: Option<flapjack::index::settings::IndexMode>,
     #[serde(default)]
     pub hybrid: Option<HybridSearchParams>,
+    /// Precomputed query vector, used instead of embedding `query`. Without
+    /// `hybrid` it runs a pure vector search (`semanticRatio: 1.0`).
+    #[serde(default)]
+    pub vector: Option<Vec<f32>>,
+    /// Maximal-marginal-relevance re-ranking of hybrid and vector results,
+    /// from 0.0 (off) to 1.0: near-duplicate hits are pushed down the page.
+    #[serde(default)]
+    pub diversity: Option<f64>,
+    #[serde(default, rename = "enableReRanking")]
+    pub enable_re_ranking: Option<bool>,
+    #[serde(default, rename = "autoCorrectIfNoResults")]
+    pub auto_correct_if_no_results: Option<bool>,
 }
 
+/// Response fields returned even when `responseFields` leaves them out, so
+/// warnings are never silently dropped.
+const ALWAYS_RETURNED_FIELDS: &[&str] = &["message", "warning", "cursor"];
+
 impl SearchRequest {
     pub fn effective_hits_per_page(&self) -> usize {
         self.hits_per_page.unwrap_or(20)
     }
 
+    /// Whether the top-level response field `field` should be returned:
+    /// always when `responseFields` is unset or contains `*`.
+    pub fn wants_response_field(&self, field: &str) -> bool {
+        match &self.response_fields {
+            None => true,
+            Some(fields) => {
+                ALWAYS_RETURNED_FIELDS.contains(&field)
+                    || fields.iter().any(|f| f == "*" || f == field)
+            }
+        }
+    }
+
     /// Clamp hybrid search ratio to [0.0, 1.0] if present.
     pub fn clamp_hybrid_ratio(&mut self) {
         if let Some(ref mut h) = self.hybrid {
@@ -412,6 +500,16 @@ impl SearchRequest {
                         self.enable_rules = value.parse().ok();
                     }
                 }
+                "autoCorrectIfNoResults" => {
+                    if self.auto_correct_if_no_results.is_none() {
+                        self.auto_correct_if_no_results = value.parse().ok();
+                    }
+                }
+                "enableReRanking" => {
+                    if self.enable_re_ranking.is_none() {
+                        self.enable_re_ranking = value.parse().ok();
+                    }
+                }
                 "ruleContexts" => {
                     if self.rule_contexts.is_none() {
                         if let Ok(v) = serde_json::from_str::<Vec<String>>(&value) {
@@ -469,6 +567,16 @@ impl SearchRequest {
                 "getRankingInfo" => {
                     self.get_ranking_info = value.parse().ok();
                 }
+                "cursor" => {
+                    if self.cursor.is_none() {
+                        self.cursor = Some(value.into_owned());
+                    }
+                }
+                "exhaustiveNbHits" => {
+                    if self.exhaustive_nb_hits.is_none() {
+                        self.exhaustive_nb_hits = value.parse().ok();
+                    }
+                }
                 "responseFields" => {
                     if self.response_fields.is_none() {
                         if let Ok(v) = serde_json::from_str::<Vec<String>>(&value) {
@@ -548,6 +656,11 @@ impl SearchRequest {
                         }
                     }
                 }
+                "sortBy" => {
+                    if self.sort_by.is_none() {
+                        self.sort_by = Some(value.into_owned());
+                    }
+                }
                 "queryLanguages" => {
                     if self.query_languages.is_none() {
                         if let Ok(v) = serde_json::from_str::<Vec<String>>(&value) {
@@ -568,6 +681,16 @@ impl SearchRequest {
                         };
                     }
                 }
+                "diversity" => {
+                    if self.diversity.is_none() {
+                        self.diversity = value.parse().ok();
+                    }
+                }
+                "vector" => {
+                    if self.vector.is_none() {
+                        self.vector = serde_json::from_str(&value).ok();
+                    }
+                }
                 "hybrid" => {
                     if self.hybrid.is_none() {
                         if let Ok(mut h) = serde_json::from_str::<HybridSearchParams>(&value) {
@@ -752,6 +875,11 @@ fn parse_numeric_filter_string(s: &str) -> Option<flapjack::types::Filter> {
                 FieldValue::Integer(i)
             } else if let Ok(f) = val_str.parse::<f64>() {
                 FieldValue::Float(f)
+            } else if let Some(ts) = crate::filter_parser::parse_date_expr(
+                val_str.trim_matches('"'),
+                chrono::Utc::now().timestamp(),
+            ) {
+                FieldValue::Date(ts)
             } else {
                 return None;
             };
@@ -968,6 +1096,9 @@ pub struct GetObjectRequest {
 #[derive(Debug, Serialize, ToSchema)]
 pub struct GetObjectsResponse {
     pub results: Vec<serde_json::Value>,
+    /// Lists the objectIDs that came back as `null`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -1099,6 +1230,16 @@ mod tests {
         assert_eq!(req.analytics, Some(true));
     }
 
+    #[test]
+    fn apply_params_string_sets_enable_re_ranking() {
+        let mut req = SearchRequest {
+            params: Some("enableReRanking=false".to_string()),
+            ..Default::default()
+        };
+        req.apply_params_string();
+        assert_eq!(req.enable_re_ranking, Some(false));
+    }
+
     // ── parse_facet_filter_string ──
 
     #[test]
@@ -1289,6 +1430,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn numeric_filters_date_values() {
+        let v = serde_json::json!("published_at>=2024-01-15");
+        match numeric_filters_to_ast(&v).unwrap() {
+            flapjack::types::Filter::GreaterThanOrEqual { field, value } => {
+                assert_eq!(field, "published_at");
+                assert_eq!(value, flapjack::types::FieldValue::Date(1_705_276_800));
+            }
+            _ => panic!("expected GreaterThanOrEqual"),
+        }
+
+        let v = serde_json::json!("published_at>now-1d");
+        assert!(matches!(
+            numeric_filters_to_ast(&v),
+            Some(flapjack::types::Filter::GreaterThan {
+                value: flapjack::types::FieldValue::Date(_),
+                ..
+            })
+        ));
+    }
+
     #[test]
     fn numeric_filters_array_and() {
         let v = serde_json::json!(["price>=10", "price<=100"]);
@@ -6,6 +6,7 @@ use flapjack::error::FlapjackError;
 use flapjack::index::settings::IndexSettings;
 use flapjack::vector::config::EmbedderConfig;
 use flapjack::vector::embedder::{create_embedder, Embedder};
+use flapjack::vector::scheduler::EmbeddingScheduler;
 
 /// Application-level cache for instantiated embedders.
 ///
@@ -42,27 +43,7 @@ impl EmbedderStore {
         }
 
         // Cache miss — parse config and create embedder
-        let embedders_map = settings.embedders.as_ref().ok_or_else(|| {
-            FlapjackError::InvalidQuery(format!(
-                "no embedders configured for tenant '{}'",
-                tenant_id
-            ))
-        })?;
-
-        let raw_config = embedders_map.get(embedder_name).ok_or_else(|| {
-            FlapjackError::InvalidQuery(format!(
-                "embedder '{}' not found in settings for tenant '{}'",
-                embedder_name, tenant_id
-            ))
-        })?;
-
-        let config: EmbedderConfig = serde_json::from_value(raw_config.clone()).map_err(|e| {
-            FlapjackError::InvalidQuery(format!(
-                "invalid embedder config for '{}': {}",
-                embedder_name, e
-            ))
-        })?;
-
+        let config = embedder_config(tenant_id, embedder_name, settings)?;
         let embedder = create_embedder(&config).map_err(|e| {
             FlapjackError::InvalidQuery(format!(
                 "failed to create embedder '{}': {}",
@@ -75,6 +56,30 @@ impl EmbedderStore {
         Ok(arc)
     }
 
+    /// Embed a search query, from the query cache when possible. Misses go
+    /// through the shared [`EmbeddingScheduler`], which batches concurrent
+    /// queries and counts their usage against the index.
+    pub async fn embed_query(
+        &self,
+        tenant_id: &str,
+        embedder_name: &str,
+        settings: &IndexSettings,
+        query: &str,
+    ) -> Result<Vec<f32>, FlapjackError> {
+        if let Some(cached) = self.query_cache.get(embedder_name, query) {
+            return Ok(cached);
+        }
+        let embedder = self.get_or_create(tenant_id, embedder_name, settings)?;
+        let config = embedder_config(tenant_id, embedder_name, settings)?;
+        let vector = EmbeddingScheduler::global()
+            .embed_query(tenant_id, embedder_name, embedder, &config, query)
+            .await
+            .map_err(|e| FlapjackError::InvalidQuery(e.to_string()))?;
+        self.query_cache
+            .insert(embedder_name, query, vector.clone());
+        Ok(vector)
+    }
+
     /// Remove all cached embedders for a tenant.
     ///
     /// Called when settings change to ensure the next search picks up
@@ -84,6 +89,33 @@ impl EmbedderStore {
     }
 }
 
+fn embedder_config(
+    tenant_id: &str,
+    embedder_name: &str,
+    settings: &IndexSettings,
+) -> Result<EmbedderConfig, FlapjackError> {
+    let embedders_map = settings.embedders.as_ref().ok_or_else(|| {
+        FlapjackError::InvalidQuery(format!(
+            "no embedders configured for tenant '{}'",
+            tenant_id
+        ))
+    })?;
+
+    let raw_config = embedders_map.get(embedder_name).ok_or_else(|| {
+        FlapjackError::InvalidQuery(format!(
+            "embedder '{}' not found in settings for tenant '{}'",
+            embedder_name, tenant_id
+        ))
+    })?;
+
+    serde_json::from_value(raw_config.clone()).map_err(|e| {
+        FlapjackError::InvalidQuery(format!(
+            "invalid embedder config for '{}': {}",
+            embedder_name, e
+        ))
+    })
+}
+
 impl Default for EmbedderStore {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,191 @@
+//! Uniform error bodies.
+//!
+//! Every error response leaves the server as a JSON object with `message`,
+//! `code` and `status`. [`FlapjackError`](flapjack::FlapjackError) responses
+//! and handlers that know a specific [`ErrorCode`] set `code` themselves; this
+//! middleware fills in the rest: JSON bodies missing `code` get the generic
+//! code for their status, and plain-text bodies (axum extractor rejections,
+//! handlers returning `(StatusCode, String)`) are wrapped in a JSON object.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use flapjack::ErrorCode;
+use serde_json::{json, Value};
+
+/// Error responses larger than this are passed through untouched.
+const MAX_ERROR_BODY_BYTES: usize = 1024 * 1024;
+
+/// JSON error response with an explicit code.
+pub fn error_response(code: ErrorCode, message: impl Into<String>) -> Response {
+    let status = code.status();
+    (
+        status,
+        Json(json!({
+            "message": message.into(),
+            "code": code,
+            "status": status.as_u16(),
+        })),
+    )
+        .into_response()
+}
+
+/// Add `code` and `status` (and `message`, if only `error` was set) to a JSON
+/// error body. Returns false if the body is not a JSON object.
+fn enrich(body: &mut Value, status: StatusCode) -> bool {
+    let Some(obj) = body.as_object_mut() else {
+        return false;
+    };
+    if !obj.contains_key("message") {
+        let message = obj
+            .get("error")
+            .and_then(|e| e.as_str())
+            .map(String::from)
+            .unwrap_or_else(|| status.canonical_reason().unwrap_or("Error").to_string());
+        obj.insert("message".into(), json!(message));
+    }
+    if !obj.get("code").is_some_and(|c| c.is_string()) {
+        obj.insert("code".into(), json!(ErrorCode::for_status(status)));
+    }
+    if !obj.contains_key("status") {
+        obj.insert("status".into(), json!(status.as_u16()));
+    }
+    true
+}
+
+fn content_type(response: &Response) -> Option<&str> {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+}
+
+/// Axum middleware that normalizes 4xx/5xx bodies to `{message, code, status}`.
+pub async fn attach_error_codes(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    let status = response.status();
+    if !(status.is_client_error() || status.is_server_error()) {
+        return response;
+    }
+    let is_json = content_type(&response).is_some_and(|ct| ct.starts_with("application/json"));
+    let is_text = content_type(&response).map_or(true, |ct| ct.starts_with("text/plain"));
+    if !is_json && !is_text {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_ERROR_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return error_response(ErrorCode::for_status(status), "Error body too large"),
+    };
+
+    let mut value = if is_json {
+        match serde_json::from_slice::<Value>(&bytes) {
+            Ok(value) => value,
+            Err(_) => return Response::from_parts(parts, Body::from(bytes)),
+        }
+    } else {
+        let text = String::from_utf8_lossy(&bytes).trim().to_string();
+        let message = if text.is_empty() {
+            status.canonical_reason().unwrap_or("Error").to_string()
+        } else {
+            text
+        };
+        json!({ "message": message })
+    };
+    if !enrich(&mut value, status) {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    Response::from_parts(parts, Body::from(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use tower::ServiceExt;
+
+    async fn call(router: Router) -> (StatusCode, Value) {
+        let app = router.layer(axum::middleware::from_fn(attach_error_codes));
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    #[tokio::test]
+    async fn plain_text_errors_are_wrapped() {
+        let router = Router::new().route(
+            "/",
+            get(|| async { (StatusCode::NOT_FOUND, "Object 1 not found".to_string()) }),
+        );
+        let (status, body) = call(router).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(
+            body,
+            json!({"message": "Object 1 not found", "code": "not_found", "status": 404})
+        );
+    }
+
+    #[tokio::test]
+    async fn json_errors_keep_their_code() {
+        let router = Router::new().route(
+            "/",
+            get(|| async { error_response(ErrorCode::KeyNotFound, "Key not found") }),
+        );
+        let (status, body) = call(router).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "key_not_found");
+        assert_eq!(body["message"], "Key not found");
+    }
+
+    #[tokio::test]
+    async fn json_errors_without_code_get_generic_code() {
+        let router = Router::new().route(
+            "/",
+            get(|| async {
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(json!({"error": "memory_pressure"})),
+                )
+            }),
+        );
+        let (_, body) = call(router).await;
+        assert_eq!(body["message"], "memory_pressure");
+        assert_eq!(body["code"], "service_unavailable");
+        assert_eq!(body["status"], 503);
+    }
+
+    #[tokio::test]
+    async fn successful_responses_are_untouched() {
+        let router = Router::new().route("/", get(|| async { "ok" }));
+        let app = router.layer(axum::middleware::from_fn(attach_error_codes));
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&bytes[..], b"ok");
+    }
+}
@@ -0,0 +1,258 @@
+//! Background auto-stop for running experiments.
+//!
+//! Every `FLAPJACK_EXPERIMENT_AUTOSTOP_SECS` seconds (default 300) each running
+//! experiment with an `autoStop` policy has its results recomputed. It is
+//! stopped when a sample ratio mismatch is detected (`stopOnSrm`) or when
+//! guard-rail alerts have fired on every check for `guardRailHours`. The stop
+//! reason is stored on the experiment and POSTed to `webhookUrl` if set.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use flapjack::experiments::config::{AutoStopPolicy, Experiment, ExperimentStatus};
+use flapjack::experiments::store::ExperimentFilter;
+
+use crate::handlers::experiments::{compute_results, GuardRailAlertResponse};
+use crate::handlers::AppState;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AutoStopDecision {
+    /// Stop the experiment for the given reason.
+    Stop(String),
+    /// Keep running; persist the (possibly cleared) start of the alert window.
+    Continue { guard_rail_alert_since: Option<i64> },
+}
+
+/// Decide whether an experiment should be stopped given its latest results.
+pub fn evaluate(
+    policy: &AutoStopPolicy,
+    alert_since: Option<i64>,
+    sample_ratio_mismatch: bool,
+    alerts: &[GuardRailAlertResponse],
+    now_ms: i64,
+) -> AutoStopDecision {
+    if policy.stop_on_srm && sample_ratio_mismatch {
+        return AutoStopDecision::Stop("sample ratio mismatch detected".to_string());
+    }
+    if alerts.is_empty() {
+        return AutoStopDecision::Continue {
+            guard_rail_alert_since: None,
+        };
+    }
+
+    let since = alert_since.unwrap_or(now_ms);
+    if let Some(hours) = policy.guard_rail_hours {
+        if (now_ms - since) as f64 >= hours * 3_600_000.0 {
+            let metrics: Vec<&str> = alerts.iter().map(|a| a.metric_name.as_str()).collect();
+            return AutoStopDecision::Stop(format!(
+                "guard-rail alert on {} persisted for {}h",
+                metrics.join(", "),
+                hours
+            ));
+        }
+    }
+    AutoStopDecision::Continue {
+        guard_rail_alert_since: Some(since),
+    }
+}
+
+async fn notify_webhook(url: &str, experiment: &Experiment, reason: &str) {
+    let payload = serde_json::json!({
+        "event": "experiment.autoStopped",
+        "experimentID": experiment.id,
+        "name": experiment.name,
+        "indexName": experiment.index_name,
+        "reason": reason,
+        "stoppedAt": experiment.ended_at,
+    });
+    let result = reqwest::Client::new()
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&payload)
+        .send()
+        .await;
+    match result {
+        Ok(resp) if resp.status().is_success() => {}
+        Ok(resp) => tracing::warn!(
+            "[EXPERIMENTS] auto-stop webhook for {} returned {}",
+            experiment.id,
+            resp.status()
+        ),
+        Err(e) => tracing::warn!(
+            "[EXPERIMENTS] auto-stop webhook for {} failed: {}",
+            experiment.id,
+            e
+        ),
+    }
+}
+
+/// Evaluate every running experiment that has an auto-stop policy. Returns
+/// the IDs of the experiments that were stopped.
+pub async fn run_auto_stop_checks(state: &AppState) -> Vec<String> {
+    let Some(store) = state.experiment_store.as_deref() else {
+        return Vec::new();
+    };
+    let running = store.list(Some(ExperimentFilter {
+        index_name: None,
+        status: Some(ExperimentStatus::Running),
+    }));
+
+    let mut stopped = Vec::new();
+    for experiment in running {
+        let Some(policy) = experiment.auto_stop.clone() else {
+            continue;
+        };
+        let results = compute_results(state, &experiment).await;
+        let decision = evaluate(
+            &policy,
+            experiment.guard_rail_alert_since,
+            results.sample_ratio_mismatch,
+            &results.guard_rail_alerts,
+            chrono::Utc::now().timestamp_millis(),
+        );
+        match decision {
+            AutoStopDecision::Continue {
+                guard_rail_alert_since,
+            } => {
+                if let Err(e) =
+                    store.set_guard_rail_alert_since(&experiment.id, guard_rail_alert_since)
+                {
+                    tracing::warn!(
+                        "[EXPERIMENTS] failed to record alert window for {}: {}",
+                        experiment.id,
+                        e
+                    );
+                }
+            }
+            AutoStopDecision::Stop(reason) => {
+                let updated = match store.stop_with_reason(&experiment.id, &reason) {
+                    Ok(updated) => updated,
+                    Err(e) => {
+                        tracing::warn!(
+                            "[EXPERIMENTS] failed to auto-stop {}: {}",
+                            experiment.id,
+                            e
+                        );
+                        continue;
+                    }
+                };
+                tracing::warn!(
+                    "[EXPERIMENTS] auto-stopped experiment {} ({}): {}",
+                    updated.id,
+                    updated.name,
+                    reason
+                );
+                if let Some(url) = policy.webhook_url.as_deref() {
+                    notify_webhook(url, &updated, &reason).await;
+                }
+                stopped.push(updated.id);
+            }
+        }
+    }
+    stopped
+}
+
+pub fn spawn_auto_stop_checker(state: Arc<AppState>, interval_secs: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            run_auto_stop_checks(&state).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR_MS: i64 = 3_600_000;
+
+    fn alert(metric: &str) -> GuardRailAlertResponse {
+        GuardRailAlertResponse {
+            metric_name: metric.to_string(),
+            control_value: 0.10,
+            variant_value: 0.05,
+            drop_pct: 50.0,
+        }
+    }
+
+    fn policy(hours: Option<f64>, stop_on_srm: bool) -> AutoStopPolicy {
+        AutoStopPolicy {
+            guard_rail_hours: hours,
+            stop_on_srm,
+            webhook_url: None,
+        }
+    }
+
+    #[test]
+    fn srm_stops_only_when_enabled() {
+        let now = 100 * HOUR_MS;
+        assert!(matches!(
+            evaluate(&policy(None, true), None, true, &[], now),
+            AutoStopDecision::Stop(_)
+        ));
+        assert_eq!(
+            evaluate(&policy(None, false), None, true, &[], now),
+            AutoStopDecision::Continue {
+                guard_rail_alert_since: None
+            }
+        );
+    }
+
+    #[test]
+    fn guard_rail_alert_must_persist() {
+        let now = 100 * HOUR_MS;
+        let p = policy(Some(24.0), false);
+        let alerts = [alert("ctr")];
+
+        // First sighting opens the window.
+        assert_eq!(
+            evaluate(&p, None, false, &alerts, now),
+            AutoStopDecision::Continue {
+                guard_rail_alert_since: Some(now)
+            }
+        );
+        // Still inside the window.
+        assert_eq!(
+            evaluate(&p, Some(now - 23 * HOUR_MS), false, &alerts, now),
+            AutoStopDecision::Continue {
+                guard_rail_alert_since: Some(now - 23 * HOUR_MS)
+            }
+        );
+        // Persisted long enough.
+        match evaluate(&p, Some(now - 24 * HOUR_MS), false, &alerts, now) {
+            AutoStopDecision::Stop(reason) => assert!(reason.contains("ctr"), "{}", reason),
+            other => panic!("expected stop, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn alert_window_resets_when_alerts_clear() {
+        let now = 100 * HOUR_MS;
+        assert_eq!(
+            evaluate(
+                &policy(Some(24.0), false),
+                Some(now - HOUR_MS),
+                false,
+                &[],
+                now
+            ),
+            AutoStopDecision::Continue {
+                guard_rail_alert_since: None
+            }
+        );
+    }
+
+    #[test]
+    fn guard_rail_alerts_without_hours_never_stop() {
+        let now = 100 * HOUR_MS;
+        assert!(matches!(
+            evaluate(&policy(None, true), Some(0), false, &[alert("ctr")], now),
+            AutoStopDecision::Continue { .. }
+        ));
+    }
+}
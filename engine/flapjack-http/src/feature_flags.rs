@@ -0,0 +1,335 @@
+//! Feature flags for search features (`/1/flags`).
+//!
+//! A flag turns one engine feature on for part of the search traffic without
+//! touching index settings, so a feature can be dark-launched and rolled
+//! back instantly. The search path reads these flags:
+//!
+//! - `hybridSearch` — run queries in `neuralSearch` mode
+//! - `reRanking` — apply the index's re-ranking model
+//!
+//! Other keys can be stored and read back for features evaluated elsewhere.
+//!
+//! A flag applies to the indexes matching `indices` (all when empty; a
+//! per-user tenant `products@alice` counts as `products`). A disabled flag
+//! turns the feature off everywhere it applies. An enabled one turns it on
+//! for the calling keys in `apiKeys`, the tenants whose userID is in
+//! `tenants`, and `rolloutPercentage` percent of the remaining traffic,
+//! bucketed by userToken (else the API key) so a user keeps the same
+//! outcome; everyone else gets the feature off. Explicit query parameters
+//! (`mode`, `hybrid`, `enableReRanking`) still win.
+//!
+//! Flags live in `{data_dir}/feature_flags.json` and are cached in memory.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::dto::SearchRequest;
+
+pub const HYBRID_SEARCH: &str = "hybridSearch";
+pub const RE_RANKING: &str = "reRanking";
+
+const FLAGS_FILE: &str = "feature_flags.json";
+const MAX_KEY_LEN: usize = 64;
+
+static STORES: OnceLock<DashMap<PathBuf, Arc<FlagStore>>> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureFlag {
+    /// Set from the URL on `PUT /1/flags/:key`.
+    #[serde(default)]
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub enabled: bool,
+    /// Index name patterns (`*` wildcards, as on API keys).
+    #[serde(default)]
+    pub indices: Vec<String>,
+    #[serde(default)]
+    pub rollout_percentage: f64,
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+    #[serde(default)]
+    pub tenants: Vec<String>,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+impl FeatureFlag {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.key.is_empty()
+            || self.key.len() > MAX_KEY_LEN
+            || !self
+                .key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "flag key must be 1-{} characters of [A-Za-z0-9_-]",
+                MAX_KEY_LEN
+            ));
+        }
+        if !(0.0..=100.0).contains(&self.rollout_percentage) {
+            return Err("rolloutPercentage must be between 0 and 100".to_string());
+        }
+        Ok(())
+    }
+
+    /// Whether the flag is on for a query on `index_name`, or `None` when it
+    /// does not apply to that index. `unit` is what rollouts bucket by;
+    /// without one each query is bucketed on its own.
+    pub fn evaluate(
+        &self,
+        index_name: &str,
+        unit: Option<&str>,
+        api_key: Option<&str>,
+    ) -> Option<bool> {
+        let (base_index, user_id) =
+            match flapjack_replication::user_mapping::parse_user_tenant(index_name) {
+                Some((index, user)) => (index, Some(user)),
+                None => (index_name, None),
+            };
+        if !crate::auth::index_pattern_matches(&self.indices, base_index)
+            && !crate::auth::index_pattern_matches(&self.indices, index_name)
+        {
+            return None;
+        }
+        if !self.enabled {
+            return Some(false);
+        }
+        let targeted = api_key.is_some_and(|k| self.api_keys.iter().any(|t| t == k))
+            || user_id.is_some_and(|u| self.tenants.iter().any(|t| t == u));
+        if targeted {
+            return Some(true);
+        }
+        let bucket = match unit {
+            Some(unit) => rollout_bucket(&self.key, unit),
+            None => rand::random::<f64>() * 100.0,
+        };
+        Some(bucket < self.rollout_percentage)
+    }
+}
+
+/// Stable position of `unit` in `[0, 100)` for flag `key`.
+fn rollout_bucket(key: &str, unit: &str) -> f64 {
+    let digest = Sha256::digest(format!("{}:{}", key, unit).as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(bytes) % 10_000) as f64 / 100.0
+}
+
+/// The feature flags of one data directory.
+pub struct FlagStore {
+    path: PathBuf,
+    flags: RwLock<BTreeMap<String, FeatureFlag>>,
+}
+
+impl FlagStore {
+    /// The store for a data directory, loading its flags once.
+    pub fn for_data_dir(data_dir: &Path) -> std::io::Result<Arc<Self>> {
+        let stores = STORES.get_or_init(DashMap::new);
+        if let Some(store) = stores.get(data_dir) {
+            return Ok(Arc::clone(&store));
+        }
+        let store = Arc::new(Self::load(data_dir.join(FLAGS_FILE))?);
+        Ok(Arc::clone(
+            &stores.entry(data_dir.to_path_buf()).or_insert(store),
+        ))
+    }
+
+    fn load(path: PathBuf) -> std::io::Result<Self> {
+        let flags: Vec<FeatureFlag> = if path.exists() {
+            let json = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&json)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            path,
+            flags: RwLock::new(flags.into_iter().map(|f| (f.key.clone(), f)).collect()),
+        })
+    }
+
+    fn save(&self, flags: &BTreeMap<String, FeatureFlag>) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let list: Vec<&FeatureFlag> = flags.values().collect();
+        let json = serde_json::to_string_pretty(&list).map_err(std::io::Error::other)?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(tmp, &self.path)
+    }
+
+    pub fn list(&self) -> Vec<FeatureFlag> {
+        let flags = self.flags.read().unwrap_or_else(|e| e.into_inner());
+        flags.values().cloned().collect()
+    }
+
+    pub fn get(&self, key: &str) -> Option<FeatureFlag> {
+        let flags = self.flags.read().unwrap_or_else(|e| e.into_inner());
+        flags.get(key).cloned()
+    }
+
+    /// Create or replace a flag.
+    pub fn put(&self, flag: FeatureFlag) -> std::io::Result<()> {
+        let mut flags = self.flags.write().unwrap_or_else(|e| e.into_inner());
+        let mut updated = flags.clone();
+        updated.insert(flag.key.clone(), flag);
+        self.save(&updated)?;
+        *flags = updated;
+        Ok(())
+    }
+
+    pub fn delete(&self, key: &str) -> std::io::Result<bool> {
+        let mut flags = self.flags.write().unwrap_or_else(|e| e.into_inner());
+        if !flags.contains_key(key) {
+            return Ok(false);
+        }
+        let mut updated = flags.clone();
+        updated.remove(key);
+        self.save(&updated)?;
+        *flags = updated;
+        Ok(true)
+    }
+
+    /// Fill in the flag-controlled features `req` leaves unset.
+    pub fn apply_to_search(&self, index_name: &str, req: &mut SearchRequest) {
+        let flags = self.flags.read().unwrap_or_else(|e| e.into_inner());
+        if flags.is_empty() {
+            return;
+        }
+        let api_key = req.api_key.as_deref();
+        let unit = req.user_token.as_deref().or(api_key);
+        let evaluate = |key: &str| flags.get(key)?.evaluate(index_name, unit, api_key);
+
+        if req.mode.is_none() && req.hybrid.is_none() {
+            if let Some(on) = evaluate(HYBRID_SEARCH) {
+                use flapjack::index::settings::IndexMode;
+                req.mode = Some(if on {
+                    IndexMode::NeuralSearch
+                } else {
+                    IndexMode::KeywordSearch
+                });
+            }
+        }
+        if req.enable_re_ranking.is_none() {
+            if let Some(on) = evaluate(RE_RANKING) {
+                req.enable_re_ranking = Some(on);
+            }
+        }
+    }
+}
+
+/// Apply the data directory's flags to a search on `index_name`.
+pub fn apply_to_search(data_dir: &Path, index_name: &str, req: &mut SearchRequest) {
+    match FlagStore::for_data_dir(data_dir) {
+        Ok(store) => store.apply_to_search(index_name, req),
+        Err(e) => tracing::warn!("feature flags unavailable: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag(key: &str) -> FeatureFlag {
+        FeatureFlag {
+            key: key.to_string(),
+            description: None,
+            enabled: true,
+            indices: vec!["products".to_string()],
+            rollout_percentage: 0.0,
+            api_keys: Vec::new(),
+            tenants: Vec::new(),
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn validate_rejects_bad_keys_and_percentages() {
+        assert!(flag("reRanking").validate().is_ok());
+        assert!(flag("").validate().is_err());
+        assert!(flag("new tokenizer").validate().is_err());
+        let mut f = flag("reRanking");
+        f.rollout_percentage = 100.5;
+        assert!(f.validate().is_err());
+    }
+
+    #[test]
+    fn evaluate_targets_keys_and_tenants_before_rollout() {
+        let mut f = flag("hybridSearch");
+        f.api_keys = vec!["key-1".to_string()];
+        f.tenants = vec!["alice".to_string()];
+
+        assert_eq!(f.evaluate("articles", Some("u1"), None), None);
+        assert_eq!(f.evaluate("products", Some("u1"), None), Some(false));
+        assert_eq!(
+            f.evaluate("products", Some("u1"), Some("key-1")),
+            Some(true)
+        );
+        assert_eq!(f.evaluate("products@alice", Some("u1"), None), Some(true));
+        assert_eq!(f.evaluate("products@bob", Some("u1"), None), Some(false));
+
+        f.enabled = false;
+        assert_eq!(
+            f.evaluate("products", Some("u1"), Some("key-1")),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn rollout_is_sticky_and_proportional() {
+        let mut f = flag("reRanking");
+        f.rollout_percentage = 30.0;
+        let on = (0..2000)
+            .filter(|i| f.evaluate("products", Some(&format!("user-{i}")), None) == Some(true))
+            .count();
+        assert!((500..700).contains(&on), "{on} of 2000 users enabled");
+        let first = f.evaluate("products", Some("user-7"), None);
+        assert!((0..10).all(|_| f.evaluate("products", Some("user-7"), None) == first));
+
+        f.rollout_percentage = 100.0;
+        assert_eq!(f.evaluate("products", None, None), Some(true));
+    }
+
+    #[test]
+    fn store_persists_and_fills_unset_search_params() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let store = FlagStore::load(tmp.path().join(FLAGS_FILE)).unwrap();
+        let mut f = flag(RE_RANKING);
+        f.rollout_percentage = 100.0;
+        store.put(f.clone()).unwrap();
+        let mut hybrid = flag(HYBRID_SEARCH);
+        hybrid.enabled = false;
+        store.put(hybrid).unwrap();
+
+        let reloaded = FlagStore::load(tmp.path().join(FLAGS_FILE)).unwrap();
+        assert_eq!(reloaded.get(RE_RANKING), Some(f));
+
+        let mut req = SearchRequest::default();
+        reloaded.apply_to_search("products", &mut req);
+        assert_eq!(req.enable_re_ranking, Some(true));
+        assert_eq!(
+            req.mode,
+            Some(flapjack::index::settings::IndexMode::KeywordSearch)
+        );
+
+        let mut explicit = SearchRequest {
+            enable_re_ranking: Some(false),
+            ..Default::default()
+        };
+        reloaded.apply_to_search("products", &mut explicit);
+        assert_eq!(explicit.enable_re_ranking, Some(false));
+
+        assert!(reloaded.delete(RE_RANKING).unwrap());
+        assert!(!reloaded.delete(RE_RANKING).unwrap());
+        assert_eq!(reloaded.list().len(), 1);
+    }
+}
@@ -0,0 +1,371 @@
+//! Text extraction and chunking for uploaded files (`file-ingest` feature).
+//!
+//! `POST /1/indexes/:indexName/files` takes a PDF, DOCX, HTML or plain-text
+//! file as the raw request body. Its text is extracted here, split into
+//! overlapping chunks, and each chunk is indexed as its own record carrying
+//! the file's metadata (see `handlers::files`). Indexes with embedders embed
+//! the chunks like any other record.
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::{Cursor, Read};
+
+use crate::html_text::normalize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Pdf,
+    Docx,
+    Html,
+    Text,
+}
+
+impl FileKind {
+    /// Work out the file type from the `Content-Type` header, then the file
+    /// name's extension, then the leading bytes.
+    pub fn detect(
+        content_type: Option<&str>,
+        file_name: Option<&str>,
+        bytes: &[u8],
+    ) -> Option<Self> {
+        let by_type = content_type
+            .and_then(|t| t.split(';').next())
+            .map(|t| t.trim().to_ascii_lowercase())
+            .and_then(|t| match t.as_str() {
+                "application/pdf" => Some(FileKind::Pdf),
+                "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => {
+                    Some(FileKind::Docx)
+                }
+                "text/html" | "application/xhtml+xml" => Some(FileKind::Html),
+                "text/plain" | "text/markdown" => Some(FileKind::Text),
+                _ => None,
+            });
+        let by_name = file_name
+            .and_then(|name| name.rsplit_once('.'))
+            .map(|(_, ext)| ext.to_ascii_lowercase())
+            .and_then(|ext| match ext.as_str() {
+                "pdf" => Some(FileKind::Pdf),
+                "docx" => Some(FileKind::Docx),
+                "html" | "htm" | "xhtml" => Some(FileKind::Html),
+                "txt" | "md" | "markdown" => Some(FileKind::Text),
+                _ => None,
+            });
+        by_type.or(by_name).or_else(|| Self::sniff(bytes))
+    }
+
+    fn sniff(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"%PDF-") {
+            return Some(FileKind::Pdf);
+        }
+        // Any other zip archive is assumed to be a DOCX; extraction fails
+        // cleanly if it has no word/document.xml.
+        if bytes.starts_with(b"PK\x03\x04") {
+            return Some(FileKind::Docx);
+        }
+        let text = std::str::from_utf8(bytes).ok()?;
+        let head = text
+            .trim_start()
+            .get(..15)
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        if head.starts_with("<!doctype html") || head.starts_with("<html") {
+            Some(FileKind::Html)
+        } else {
+            Some(FileKind::Text)
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileKind::Pdf => "pdf",
+            FileKind::Docx => "docx",
+            FileKind::Html => "html",
+            FileKind::Text => "text",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Extracted {
+    /// Document title, from HTML `<title>` or DOCX properties.
+    pub title: Option<String>,
+    /// One line per paragraph, whitespace collapsed.
+    pub text: String,
+}
+
+/// Extract the text of a file. CPU-bound; call it from a blocking task.
+pub fn extract(kind: FileKind, bytes: &[u8]) -> Result<Extracted, String> {
+    let extracted = match kind {
+        FileKind::Pdf => Extracted {
+            title: None,
+            text: pdf_extract::extract_text_from_mem(bytes)
+                .map_err(|e| format!("invalid PDF: {}", e))?,
+        },
+        FileKind::Docx => extract_docx(bytes)?,
+        FileKind::Html => {
+            let page = crate::html_text::parse(&String::from_utf8_lossy(bytes));
+            Extracted {
+                title: page.title,
+                text: page.text,
+            }
+        }
+        FileKind::Text => Extracted {
+            title: None,
+            text: String::from_utf8_lossy(bytes).into_owned(),
+        },
+    };
+    Ok(Extracted {
+        title: extracted.title.filter(|t| !t.is_empty()),
+        text: normalize(&extracted.text),
+    })
+}
+
+fn extract_docx(bytes: &[u8]) -> Result<Extracted, String> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("invalid DOCX: {}", e))?;
+    let document = read_entry(&mut archive, "word/document.xml")?
+        .ok_or_else(|| "invalid DOCX: missing word/document.xml".to_string())?;
+    let title = read_entry(&mut archive, "docProps/core.xml")
+        .ok()
+        .flatten()
+        .and_then(|core| docx_title(&core));
+    Ok(Extracted {
+        title,
+        text: docx_text(&document)?,
+    })
+}
+
+fn read_entry(
+    archive: &mut zip::ZipArchive<Cursor<&[u8]>>,
+    name: &str,
+) -> Result<Option<String>, String> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("invalid DOCX: {}", e)),
+    };
+    let mut content = String::new();
+    file.read_to_string(&mut content)
+        .map_err(|e| format!("invalid DOCX: {}", e))?;
+    Ok(Some(content))
+}
+
+/// Body text of `word/document.xml`: runs of `<w:t>`, one line per paragraph.
+fn docx_text(xml: &str) -> Result<String, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut text = String::new();
+    let mut in_run_text = false;
+    loop {
+        match reader
+            .read_event()
+            .map_err(|e| format!("invalid DOCX: {}", e))?
+        {
+            Event::Start(e) if e.local_name().as_ref() == b"t" => in_run_text = true,
+            Event::End(e) => match e.local_name().as_ref() {
+                b"t" => in_run_text = false,
+                b"p" => text.push('\n'),
+                _ => {}
+            },
+            Event::Empty(e) => match e.local_name().as_ref() {
+                b"tab" => text.push(' '),
+                b"br" | b"cr" => text.push('\n'),
+                _ => {}
+            },
+            Event::Text(t) if in_run_text => {
+                text.push_str(&t.unescape().map_err(|e| format!("invalid DOCX: {}", e))?)
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(text)
+}
+
+/// `<dc:title>` from `docProps/core.xml`.
+fn docx_title(xml: &str) -> Option<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut in_title = false;
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(e) if e.local_name().as_ref() == b"title" => in_title = true,
+            Event::Text(t) if in_title => return t.unescape().ok().map(|t| normalize(&t)),
+            Event::End(_) => in_title = false,
+            Event::Eof => return None,
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkOptions {
+    /// Maximum characters per chunk (a single longer word is kept whole).
+    pub size: usize,
+    /// Characters repeated from the end of the previous chunk.
+    pub overlap: usize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        ChunkOptions {
+            size: 1000,
+            overlap: 100,
+        }
+    }
+}
+
+/// Split `text` into chunks between words.
+pub fn chunk_text(text: &str, options: ChunkOptions) -> Vec<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < words.len() {
+        let mut end = start;
+        let mut len = 0;
+        while end < words.len() {
+            let added = words[end].chars().count() + usize::from(end > start);
+            if end > start && len + added > options.size {
+                break;
+            }
+            len += added;
+            end += 1;
+        }
+        chunks.push(words[start..end].join(" "));
+        if end == words.len() {
+            break;
+        }
+        // Back up over the overlap, but always move forward by a word.
+        let mut next = end;
+        let mut overlap = 0;
+        while next > start + 1 {
+            let added = words[next - 1].chars().count() + 1;
+            if overlap + added > options.overlap {
+                break;
+            }
+            overlap += added;
+            next -= 1;
+        }
+        start = next;
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn detects_kind_from_header_name_and_bytes() {
+        assert_eq!(
+            FileKind::detect(Some("application/pdf"), Some("notes.txt"), b""),
+            Some(FileKind::Pdf)
+        );
+        assert_eq!(
+            FileKind::detect(Some("application/octet-stream"), Some("Report.DOCX"), b""),
+            Some(FileKind::Docx)
+        );
+        assert_eq!(
+            FileKind::detect(None, None, b"%PDF-1.7 ..."),
+            Some(FileKind::Pdf)
+        );
+        assert_eq!(
+            FileKind::detect(None, None, b"  <!DOCTYPE html><html></html>"),
+            Some(FileKind::Html)
+        );
+        assert_eq!(FileKind::detect(None, None, b"hello"), Some(FileKind::Text));
+        assert_eq!(FileKind::detect(None, None, &[0xff, 0xfe, 0x00]), None);
+    }
+
+    #[test]
+    fn html_text_skips_markup_and_scripts() {
+        let html = r#"<!DOCTYPE html>
+            <html><head><title>Return &amp; refunds</title>
+            <style>p { color: red }</style></head>
+            <body><h1>Returns</h1><!-- internal note -->
+            <p>Items can be returned within <b>30</b>&nbsp;days.</p>
+            <script>track("view")</script><ul><li>Shoes</li><li>Bags</li></ul>
+            </body></html>"#;
+        let extracted = extract(FileKind::Html, html.as_bytes()).unwrap();
+        assert_eq!(extracted.title.as_deref(), Some("Return & refunds"));
+        assert_eq!(
+            extracted.text,
+            "Returns\nItems can be returned within 30 days.\nShoes\nBags"
+        );
+    }
+
+    #[test]
+    fn docx_text_and_title() {
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buf);
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            zip.start_file("word/document.xml", options).unwrap();
+            zip.write_all(
+                br#"<w:document xmlns:w="w"><w:body>
+                <w:p>
+                  <w:r><w:t>Quarterly</w:t></w:r>
+                  <w:r><w:t xml:space="preserve"> report</w:t></w:r>
+                </w:p>
+                <w:p><w:r><w:t>Revenue &amp; costs</w:t></w:r></w:p>
+                </w:body></w:document>"#,
+            )
+            .unwrap();
+            zip.start_file("docProps/core.xml", options).unwrap();
+            zip.write_all(
+                br#"<cp:coreProperties xmlns:cp="cp" xmlns:dc="dc">
+                <dc:title>Q3</dc:title></cp:coreProperties>"#,
+            )
+            .unwrap();
+            zip.finish().unwrap();
+        }
+        let extracted = extract(FileKind::Docx, buf.get_ref()).unwrap();
+        assert_eq!(extracted.title.as_deref(), Some("Q3"));
+        assert_eq!(extracted.text, "Quarterly report\nRevenue & costs");
+
+        assert!(extract(FileKind::Docx, b"not a zip").is_err());
+    }
+
+    #[test]
+    fn chunks_overlap_and_cover_every_word() {
+        let text = "one two three four five six seven eight nine ten";
+        let chunks = chunk_text(
+            text,
+            ChunkOptions {
+                size: 15,
+                overlap: 6,
+            },
+        );
+        assert_eq!(
+            chunks,
+            vec![
+                "one two three",
+                "three four five",
+                "five six seven",
+                "seven eight",
+                "eight nine ten",
+            ]
+        );
+        assert!(chunks.iter().all(|c| c.len() <= 15));
+
+        let chunks = chunk_text(
+            text,
+            ChunkOptions {
+                size: 1000,
+                overlap: 100,
+            },
+        );
+        assert_eq!(chunks, vec![text]);
+        assert!(chunk_text("  \n ", ChunkOptions::default()).is_empty());
+        assert_eq!(
+            chunk_text(
+                "supercalifragilistic word",
+                ChunkOptions {
+                    size: 5,
+                    overlap: 0
+                }
+            ),
+            vec!["supercalifragilistic", "word"]
+        );
+    }
+}
@@ -4,6 +4,9 @@
 //! - Comparisons: `field = 'value'`, `price > 100`, `stock <= 50`
 //! - Logical: `AND`, `OR`, `NOT`
 //! - Grouping: `(price > 50 AND stock > 0) OR featured = 'true'`
+//! - Nested paths: `variants.price < 50` (matches any element of a nested array)
+//! - Dates: `published_at > now-7d`, `published_at >= 2024-01-15`, resolved to
+//!   unix seconds as [`FieldValue::Date`]
 //!
 //! Keywords require word boundaries. Without boundaries, they parse as identifiers:
 //! - `NOT category` → keyword + field
@@ -155,6 +158,25 @@ fn comparison(input: &str) -> IResult<&str, Filter> {
         ));
     }
 
+    // Date range: `published_at:now-30d TO now`
+    if let Ok((remaining, (min, _, max))) = tuple((
+        date_value,
+        delimited(multispace1, tag_no_case("TO"), multispace1),
+        date_value,
+    ))(input)
+    {
+        if let (FieldValue::Date(min), FieldValue::Date(max)) = (min, max) {
+            return Ok((
+                remaining,
+                Filter::Range {
+                    field: field.to_string(),
+                    min: min as f64,
+                    max: max as f64,
+                },
+            ));
+        }
+    }
+
     // Try facet value (string)
     if let Ok((remaining, text)) = facet_value(input) {
         return Ok((
@@ -183,7 +205,7 @@ fn numeric_comparison(input: &str) -> IResult<&str, Filter> {
     )(input)?;
     let (input, value) = context(
         "numeric value",
-        delimited(multispace0, number_value, multispace0),
+        delimited(multispace0, alt((date_value, number_value)), multispace0),
     )(input)?;
 
     let field = field.to_string();
@@ -212,7 +234,7 @@ fn operator(input: &str) -> IResult<&str, &str> {
 }
 
 fn identifier(input: &str) -> IResult<&str, &str> {
-    take_while1(|c: char| c.is_alphanumeric() || c == '_')(input)
+    take_while1(|c: char| c.is_alphanumeric() || c == '_' || c == '.')(input)
 }
 
 fn facet_value(input: &str) -> IResult<&str, &str> {
@@ -242,6 +264,67 @@ fn number_value(input: &str) -> IResult<&str, FieldValue> {
     }
 }
 
+/// Date operand: date math relative to now, an RFC 3339 timestamp or a plain
+/// `YYYY-MM-DD` date, optionally quoted. Plain numbers are left to
+/// [`number_value`].
+fn date_value(input: &str) -> IResult<&str, FieldValue> {
+    let (remaining, token) = alt((
+        quoted_string,
+        take_while1(|c: char| c.is_alphanumeric() || matches!(c, '-' | '+' | ':' | '.')),
+    ))(input)?;
+    match parse_date_expr(token, chrono::Utc::now().timestamp()) {
+        Some(ts) => Ok((remaining, FieldValue::Date(ts))),
+        None => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Verify,
+        ))),
+    }
+}
+
+/// Resolve a date expression to unix seconds.
+///
+/// Accepts `now`, `now-7d` / `now+12h` (units `s`, `m`, `h`, `d`, `w`),
+/// RFC 3339 timestamps (`2024-01-15T10:00:00Z`) and plain dates
+/// (`2024-01-15`, midnight UTC). Returns `None` for anything else, including
+/// bare numbers, which callers treat as epoch values already.
+pub fn parse_date_expr(token: &str, now: i64) -> Option<i64> {
+    let token = token.trim();
+    if token
+        .get(..3)
+        .is_some_and(|p| p.eq_ignore_ascii_case("now"))
+    {
+        let offset = &token[3..];
+        if offset.is_empty() {
+            return Some(now);
+        }
+        let (sign, amount) = if let Some(rest) = offset.strip_prefix('+') {
+            (1, rest)
+        } else if let Some(rest) = offset.strip_prefix('-') {
+            (-1, rest)
+        } else {
+            return None;
+        };
+        let unit = amount.chars().last()?;
+        let seconds_per_unit = match unit {
+            's' => 1,
+            'm' => 60,
+            'h' => 3_600,
+            'd' => 86_400,
+            'w' => 604_800,
+            _ => return None,
+        };
+        let n: i64 = amount[..amount.len() - 1].parse().ok()?;
+        return Some(now.saturating_add(sign * n.saturating_mul(seconds_per_unit)));
+    }
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(token) {
+        return Some(dt.timestamp());
+    }
+    chrono::NaiveDate::parse_from_str(token, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc().timestamp())
+}
+
 fn quoted_string(input: &str) -> IResult<&str, &str> {
     delimited(char('"'), take_while1(|c| c != '"'), char('"'))(input)
 }
@@ -334,6 +417,74 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_nested_path_numeric_comparison() {
+        match parse_filter("variants.price < 50").unwrap() {
+            Filter::LessThan { field, value } => {
+                assert_eq!(field, "variants.price");
+                assert_eq!(value, FieldValue::Integer(50));
+            }
+            other => panic!("Expected LessThan filter, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_date_math_comparison() {
+        let before = chrono::Utc::now().timestamp() - 7 * 86_400;
+        match parse_filter("published_at > now-7d").unwrap() {
+            Filter::GreaterThan {
+                field,
+                value: FieldValue::Date(ts),
+            } => {
+                assert_eq!(field, "published_at");
+                assert!((ts - before).abs() <= 5, "ts={} expected~{}", ts, before);
+            }
+            other => panic!("Expected GreaterThan date filter, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_iso_date_comparison() {
+        match parse_filter("published_at >= 2024-01-15").unwrap() {
+            Filter::GreaterThanOrEqual { field, value } => {
+                assert_eq!(field, "published_at");
+                assert_eq!(value, FieldValue::Date(1_705_276_800));
+            }
+            other => panic!("Expected GreaterThanOrEqual filter, got {:?}", other),
+        }
+        match parse_filter("published_at < \"2024-01-15T01:00:00Z\"").unwrap() {
+            Filter::LessThan { value, .. } => {
+                assert_eq!(value, FieldValue::Date(1_705_280_400));
+            }
+            other => panic!("Expected LessThan filter, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_date_range() {
+        match parse_filter("published_at:2024-01-01 TO 2024-01-02").unwrap() {
+            Filter::Range { field, min, max } => {
+                assert_eq!(field, "published_at");
+                assert_eq!(min, 1_704_067_200.0);
+                assert_eq!(max, 1_704_153_600.0);
+            }
+            other => panic!("Expected Range filter, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_date_expr_units() {
+        let now = 1_000_000;
+        assert_eq!(parse_date_expr("now", now), Some(now));
+        assert_eq!(parse_date_expr("now-30s", now), Some(now - 30));
+        assert_eq!(parse_date_expr("now+2h", now), Some(now + 7_200));
+        assert_eq!(parse_date_expr("NOW-1w", now), Some(now - 604_800));
+        assert_eq!(parse_date_expr("now-7", now), None);
+        assert_eq!(parse_date_expr("now*7d", now), None);
+        assert_eq!(parse_date_expr("100", now), None);
+        assert_eq!(parse_date_expr("nowhere", now), None);
+    }
+
     #[test]
     fn test_complex_algolia_style() {
         let result = parse_filter("(author:\"Stephen King\" OR genre:Horror) AND price < 20");
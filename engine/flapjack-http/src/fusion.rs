@@ -9,8 +9,22 @@ pub struct FusedResult {
     pub fused_score: f64,
     /// Raw cosine similarity (1.0 - distance) from vector search.
     /// None if document only appeared in BM25 results.
-    /// Internal — NOT exposed in HTTP response in stage 6.
     pub semantic_score: Option<f32>,
+    /// 1-based rank in the BM25 results, if it appeared there.
+    pub keyword_rank: Option<usize>,
+    /// 1-based rank in the vector results, if it appeared there.
+    pub semantic_rank: Option<usize>,
+}
+
+impl FusedResult {
+    /// Which search retrieved the document: `keyword`, `semantic` or `both`.
+    pub fn retrieved_by(&self) -> &'static str {
+        match (self.keyword_rank, self.semantic_rank) {
+            (Some(_), Some(_)) => "both",
+            (None, Some(_)) => "semantic",
+            _ => "keyword",
+        }
+    }
 }
 
 /// Reciprocal Rank Fusion: merge BM25 and vector results with weighting.
@@ -33,32 +47,41 @@ pub fn rrf_fuse(
     let k_f64 = k as f64;
 
     // Accumulate scores per document
-    let mut scores: HashMap<String, (f64, Option<f32>)> = HashMap::new();
+    let mut scores: HashMap<String, FusedResult> = HashMap::new();
+    fn entry<'a>(
+        scores: &'a mut HashMap<String, FusedResult>,
+        doc_id: &str,
+    ) -> &'a mut FusedResult {
+        scores
+            .entry(doc_id.to_string())
+            .or_insert_with(|| FusedResult {
+                doc_id: doc_id.to_string(),
+                fused_score: 0.0,
+                semantic_score: None,
+                keyword_rank: None,
+                semantic_rank: None,
+            })
+    }
 
     // BM25 contributions
     for (rank, doc_id) in bm25_doc_ids.iter().enumerate() {
         let rrf_score = bm25_weight / (k_f64 + rank as f64 + 1.0);
-        let entry = scores.entry(doc_id.clone()).or_insert((0.0, None));
-        entry.0 += rrf_score;
+        let e = entry(&mut scores, doc_id);
+        e.fused_score += rrf_score;
+        e.keyword_rank.get_or_insert(rank + 1);
     }
 
     // Vector contributions
     for (rank, vsr) in vector_results.iter().enumerate() {
         let rrf_score = vec_weight / (k_f64 + rank as f64 + 1.0);
         let similarity = 1.0 - vsr.distance;
-        let entry = scores.entry(vsr.doc_id.clone()).or_insert((0.0, None));
-        entry.0 += rrf_score;
-        entry.1 = Some(similarity);
+        let e = entry(&mut scores, &vsr.doc_id);
+        e.fused_score += rrf_score;
+        e.semantic_score = Some(similarity);
+        e.semantic_rank.get_or_insert(rank + 1);
     }
 
-    let mut results: Vec<FusedResult> = scores
-        .into_iter()
-        .map(|(doc_id, (fused_score, semantic_score))| FusedResult {
-            doc_id,
-            fused_score,
-            semantic_score,
-        })
-        .collect();
+    let mut results: Vec<FusedResult> = scores.into_values().collect();
 
     // Sort by fused_score descending, stable
     results.sort_by(|a, b| {
@@ -70,6 +93,65 @@ pub fn rrf_fuse(
     results
 }
 
+/// Maximal marginal relevance: the order in which to show hits ranked
+/// `scores` (best first), so each pick trades relevance against similarity
+/// to the hits already picked.
+///
+/// `diversity` 0.0 keeps the input order and 1.0 picks by dissimilarity
+/// alone after the first hit. Relevance is a hit's score relative to the
+/// best one; a hit without a vector counts as unlike every other.
+pub fn mmr_order(scores: &[f64], vectors: &[Option<Vec<f32>>], diversity: f64) -> Vec<usize> {
+    let n = scores.len();
+    let max_score = scores.iter().cloned().fold(0.0, f64::max);
+    let relevance = |i: usize| {
+        if max_score > 0.0 {
+            scores[i] / max_score
+        } else {
+            0.0
+        }
+    };
+    let lambda = 1.0 - diversity.clamp(0.0, 1.0);
+
+    let mut order = Vec::with_capacity(n);
+    let mut picked = vec![false; n];
+    // Highest similarity of each hit to any picked hit.
+    let mut max_sim = vec![0.0f64; n];
+    while order.len() < n {
+        let mut best: Option<(usize, f64)> = None;
+        for i in (0..n).filter(|&i| !picked[i]) {
+            let mmr = lambda * relevance(i) - (1.0 - lambda) * max_sim[i];
+            // Strictly greater keeps the input order on ties.
+            if best.is_none_or(|(_, b)| mmr > b) {
+                best = Some((i, mmr));
+            }
+        }
+        let Some((pick, _)) = best else { break };
+        picked[pick] = true;
+        order.push(pick);
+        if let Some(ref v) = vectors[pick] {
+            for i in (0..n).filter(|&i| !picked[i]) {
+                if let Some(ref w) = vectors[i] {
+                    max_sim[i] = max_sim[i].max(cosine_similarity(v, w));
+                }
+            }
+        }
+    }
+    order
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f64, 0.0f64, 0.0f64);
+    for (x, y) in a.iter().zip(b) {
+        dot += (*x as f64) * (*y as f64);
+        norm_a += (*x as f64) * (*x as f64);
+        norm_b += (*y as f64) * (*y as f64);
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -199,4 +281,56 @@ mod tests {
         assert!(c.semantic_score.is_some());
         assert!((c.semantic_score.unwrap() - 0.7).abs() < 0.001); // 1.0 - 0.3 distance
     }
+
+    #[test]
+    fn test_rrf_records_source_ranks() {
+        let bm25 = vec!["A".to_string(), "B".to_string()];
+        let vector = vec![vsr("C", 0.1), vsr("A", 0.2)];
+        let results = rrf_fuse(&bm25, &vector, 0.5, 60);
+        let get = |id: &str| results.iter().find(|r| r.doc_id == id).unwrap();
+
+        assert_eq!(
+            (get("A").keyword_rank, get("A").semantic_rank),
+            (Some(1), Some(2))
+        );
+        assert_eq!(get("A").retrieved_by(), "both");
+        assert_eq!(
+            (get("B").keyword_rank, get("B").semantic_rank),
+            (Some(2), None)
+        );
+        assert_eq!(get("B").retrieved_by(), "keyword");
+        assert_eq!(
+            (get("C").keyword_rank, get("C").semantic_rank),
+            (None, Some(1))
+        );
+        assert_eq!(get("C").retrieved_by(), "semantic");
+    }
+
+    #[test]
+    fn test_mmr_zero_diversity_keeps_order() {
+        let vectors = vec![
+            Some(vec![1.0, 0.0]),
+            Some(vec![1.0, 0.0]),
+            Some(vec![0.0, 1.0]),
+        ];
+        assert_eq!(mmr_order(&[0.9, 0.8, 0.7], &vectors, 0.0), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_mmr_demotes_near_duplicates() {
+        // Two colorways of the same product, then a different one.
+        let vectors = vec![
+            Some(vec![1.0, 0.0]),
+            Some(vec![0.99, 0.01]),
+            Some(vec![0.0, 1.0]),
+            None,
+        ];
+        let order = mmr_order(&[0.9, 0.88, 0.7, 0.5], &vectors, 0.5);
+        assert_eq!(order[0], 0);
+        assert_eq!(
+            order[1], 2,
+            "the dissimilar hit should move above the duplicate"
+        );
+        assert_eq!(order.len(), 4);
+    }
 }
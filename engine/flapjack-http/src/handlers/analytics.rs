@@ -546,7 +546,7 @@ pub async fn seed_analytics(
     let days = body.days.unwrap_or(30).min(90);
 
     let config = engine.config();
-    let result = flapjack::analytics::seed::seed_analytics(config, &index, days)
+    let result = flapjack::fixtures::analytics::seed_analytics(config, &index, days)
         .map_err(|e| FlapjackError::InvalidQuery(format!("Seed error: {}", e)))?;
 
     Ok(Json(serde_json::json!({
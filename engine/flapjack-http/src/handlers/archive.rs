@@ -0,0 +1,60 @@
+//! Import of portable `.fjpack` index archives (see
+//! `flapjack::index::archive`).
+
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    Json,
+};
+use flapjack::error::FlapjackError;
+use flapjack::index::archive::{ArchiveReader, ImportSummary};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use super::AppState;
+use crate::pause_registry::check_not_paused;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportArchiveParams {
+    /// Index to import into (default: the index the archive was exported from)
+    pub index_name: Option<String>,
+}
+
+/// Load a `.fjpack` archive into an index, replacing its documents,
+/// settings, synonyms and rules
+///
+/// `POST /1/indexes:import`. The archive is the raw request body; answers
+/// once every document is indexed.
+pub async fn import_archive(
+    State(state): State<Arc<AppState>>,
+    Path(action): Path<String>,
+    Query(params): Query<ImportArchiveParams>,
+    body: Bytes,
+) -> Result<Json<ImportSummary>, FlapjackError> {
+    // The router reads `:import` in `/1/indexes:import` as a parameter, so
+    // this handler also receives any other `/1/indexes<suffix>` path.
+    if action != ":import" {
+        return Err(FlapjackError::TenantNotFound(format!("indexes{}", action)));
+    }
+    if body.is_empty() {
+        return Err(FlapjackError::InvalidDocument("Empty archive".to_string()));
+    }
+
+    let reader = tokio::task::spawn_blocking(move || ArchiveReader::open(&body[..]))
+        .await
+        .map_err(|e| FlapjackError::Io(e.to_string()))??;
+    let index_name = params
+        .index_name
+        .unwrap_or_else(|| reader.manifest.index_name.clone());
+    check_not_paused(&state.paused_indexes, &index_name)?;
+
+    let summary = reader.import_into(&state.manager, &index_name).await?;
+    tracing::info!(
+        "[archive] imported {} documents into '{}' from an archive written by {}",
+        summary.documents,
+        index_name,
+        reader.manifest.engine_version
+    );
+    Ok(Json(summary))
+}
@@ -0,0 +1,325 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::AppState;
+use crate::filter_parser::parse_filter;
+use flapjack::index::blocklist::{Blocked, BlockedResults, Blocklist, BLOCKLIST_FILE};
+use flapjack::index::settings::IndexSettings;
+use flapjack::types::{FieldValue, Filter, ScoredDocument};
+use flapjack::IndexManager;
+
+/// List the blocked results of an index
+#[utoipa::path(
+    get,
+    path = "/1/indexes/{indexName}/blocklist",
+    tag = "blocklist",
+    params(
+        ("indexName" = String, Path, description = "Index name")
+    ),
+    responses(
+        (status = 200, description = "All blocked results", body = serde_json::Value)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn list_blocked_results(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let entries = state
+        .manager
+        .get_blocklist(&index_name)
+        .map(|b| b.all())
+        .unwrap_or_default();
+    Ok(Json(serde_json::json!({
+        "nbHits": entries.len(),
+        "hits": entries
+    })))
+}
+
+/// Get blocked results by ID
+#[utoipa::path(
+    get,
+    path = "/1/indexes/{indexName}/blocklist/{objectID}",
+    tag = "blocklist",
+    params(
+        ("indexName" = String, Path, description = "Index name"),
+        ("objectID" = String, Path, description = "Blocked results ID")
+    ),
+    responses(
+        (status = 200, description = "Blocked results retrieved", body = serde_json::Value),
+        (status = 404, description = "Blocked results not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn get_blocked_results(
+    State(state): State<Arc<AppState>>,
+    Path((index_name, object_id)): Path<(String, String)>,
+) -> Result<Json<BlockedResults>, (StatusCode, String)> {
+    state
+        .manager
+        .get_blocklist(&index_name)
+        .and_then(|b| b.get(&object_id).cloned())
+        .map(Json)
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Blocked results {} not found", object_id),
+            )
+        })
+}
+
+/// Create or update blocked results
+#[utoipa::path(
+    put,
+    path = "/1/indexes/{indexName}/blocklist/{objectID}",
+    tag = "blocklist",
+    params(
+        ("indexName" = String, Path, description = "Index name"),
+        ("objectID" = String, Path, description = "Blocked results ID")
+    ),
+    request_body(content = serde_json::Value, description = "Query pattern and the objectIDs or filters it must never return"),
+    responses(
+        (status = 200, description = "Blocked results saved", body = serde_json::Value),
+        (status = 400, description = "Invalid entry")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn save_blocked_results(
+    State(state): State<Arc<AppState>>,
+    Path((index_name, object_id)): Path<(String, String)>,
+    Json(mut entry): Json<BlockedResults>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    entry.object_id = object_id;
+    entry.validate().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    if let Some(filters) = entry.filters.as_ref().filter(|f| !f.trim().is_empty()) {
+        let filter = parse_filter(filters)
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid filters: {}", e)))?;
+        let facets = state
+            .manager
+            .get_settings(&index_name)
+            .map(|s| s.facet_set())
+            .unwrap_or_default();
+        if let Some(attribute) = unfaceted_attribute(&filter, &facets) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Invalid filters: {} must be in attributesForFaceting to be blocked on",
+                    attribute
+                ),
+            ));
+        }
+    }
+
+    state
+        .manager
+        .create_tenant(&index_name)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let path = state
+        .manager
+        .base_path
+        .join(&index_name)
+        .join(BLOCKLIST_FILE);
+    let mut blocklist = if path.exists() {
+        Blocklist::load(&path).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    } else {
+        Blocklist::new()
+    };
+
+    blocklist.insert(entry.clone());
+
+    blocklist
+        .save(&path)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    state.manager.invalidate_blocklist_cache(&index_name);
+
+    state.manager.append_oplog(
+        &index_name,
+        "save_blocked_results",
+        serde_json::to_value(&entry).unwrap_or_default(),
+    );
+
+    let task = state
+        .manager
+        .make_noop_task(&index_name)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(serde_json::json!({
+        "taskID": task.numeric_id,
+        "updatedAt": chrono::Utc::now().to_rfc3339(),
+        "id": entry.object_id
+    })))
+}
+
+/// Delete blocked results by ID
+#[utoipa::path(
+    delete,
+    path = "/1/indexes/{indexName}/blocklist/{objectID}",
+    tag = "blocklist",
+    params(
+        ("indexName" = String, Path, description = "Index name"),
+        ("objectID" = String, Path, description = "Blocked results ID")
+    ),
+    responses(
+        (status = 200, description = "Blocked results deleted", body = serde_json::Value),
+        (status = 404, description = "Blocked results not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn delete_blocked_results(
+    State(state): State<Arc<AppState>>,
+    Path((index_name, object_id)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let path = state
+        .manager
+        .base_path
+        .join(&index_name)
+        .join(BLOCKLIST_FILE);
+    let not_found = || {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Blocked results {} not found", object_id),
+        )
+    };
+
+    if !path.exists() {
+        return Err(not_found());
+    }
+
+    let mut blocklist =
+        Blocklist::load(&path).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    blocklist.remove(&object_id).ok_or_else(not_found)?;
+
+    blocklist
+        .save(&path)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    state.manager.invalidate_blocklist_cache(&index_name);
+
+    state.manager.append_oplog(
+        &index_name,
+        "delete_blocked_results",
+        serde_json::json!({"objectID": object_id}),
+    );
+
+    let task = state
+        .manager
+        .make_noop_task(&index_name)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(serde_json::json!({
+        "taskID": task.numeric_id,
+        "deletedAt": chrono::Utc::now().to_rfc3339()
+    })))
+}
+
+/// Text equality only matches faceted attributes, and a filter that can never
+/// match empties the whole search once it is negated and ANDed in.
+fn unfaceted_attribute(filter: &Filter, facets: &HashSet<String>) -> Option<String> {
+    match filter {
+        Filter::Equals {
+            field,
+            value: FieldValue::Text(_),
+        } if !facets.contains(field) => Some(field.clone()),
+        Filter::Not(inner) => unfaceted_attribute(inner, facets),
+        Filter::And(filters) | Filter::Or(filters) => {
+            filters.iter().find_map(|f| unfaceted_attribute(f, facets))
+        }
+        _ => None,
+    }
+}
+
+/// What `query` may not return from `index_name`, or from `effective_index`
+/// when the search was routed to a variant or sort replica.
+pub(crate) fn blocked_for(
+    manager: &IndexManager,
+    index_name: &str,
+    effective_index: &str,
+    query: &str,
+) -> Blocked {
+    let mut blocked = Blocked::default();
+    let mut indexes = vec![index_name];
+    if effective_index != index_name {
+        indexes.push(effective_index);
+    }
+    for index in indexes {
+        if let Some(blocklist) = manager.get_blocklist(index) {
+            let more = blocklist.blocked(query);
+            blocked.object_ids.extend(more.object_ids);
+            blocked.filters.extend(more.filters);
+        }
+    }
+    blocked
+}
+
+/// The records `blocked` filters out, or `None` when it only names
+/// objectIDs. Filters that can no longer match (their attribute stopped
+/// being a facet) are skipped rather than emptying the search.
+pub(crate) fn blocked_filter(
+    blocked: &Blocked,
+    settings: Option<&IndexSettings>,
+) -> Option<Filter> {
+    let facets = settings.map(|s| s.facet_set()).unwrap_or_default();
+    let mut filters: Vec<Filter> = blocked
+        .filters
+        .iter()
+        .filter_map(|f| parse_filter(f).ok())
+        .filter(|f| unfaceted_attribute(f, &facets).is_none())
+        .collect();
+    match filters.len() {
+        0 => None,
+        1 => filters.pop(),
+        _ => Some(Filter::Or(filters)),
+    }
+}
+
+/// Drop blocked hits that reached the page without passing the search
+/// filter: rule pins, vector-only matches and auto-corrected retries.
+pub(crate) fn drop_blocked_hits(
+    manager: &IndexManager,
+    index_name: &str,
+    documents: &mut Vec<ScoredDocument>,
+    blocked: &Blocked,
+    filter: Option<&Filter>,
+) -> flapjack::error::Result<()> {
+    let mut dropped: HashSet<String> = documents
+        .iter()
+        .filter(|d| blocked.object_ids.contains(&d.document.id))
+        .map(|d| d.document.id.clone())
+        .collect();
+    if let Some(filter) = filter {
+        let ids: Vec<String> = documents.iter().map(|d| d.document.id.clone()).collect();
+        dropped.extend(manager.filter_object_ids(index_name, &ids, filter)?);
+    }
+    documents.retain(|d| !dropped.contains(&d.document.id));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unfaceted_text_equality_is_reported() {
+        let facets: HashSet<String> = ["brand".to_string()].into_iter().collect();
+        let filter = parse_filter("brand:Acme OR price > 10").unwrap();
+        assert_eq!(unfaceted_attribute(&filter, &facets), None);
+        let filter = parse_filter("brand:Acme AND NOT color:red").unwrap();
+        assert_eq!(
+            unfaceted_attribute(&filter, &facets),
+            Some("color".to_string())
+        );
+    }
+}
@@ -1,32 +1,74 @@
 use axum::{
     extract::{Path, State},
-    Json,
+    Extension, Json,
 };
 use base64::Engine;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use super::AppState;
+use crate::auth::{AttributeRestrictions, SecuredKeyRestrictions};
+use crate::dto::SearchRequest;
 use crate::filter_parser::parse_filter;
+use crate::search_cursor::index_generation;
 use flapjack::error::FlapjackError;
 
 use super::field_value_to_json;
 
+/// Browse takes the query endpoint's filtering and projection parameters;
+/// ranking, highlighting and faceting parameters are ignored.
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct BrowseRequest {
     #[serde(default)]
     pub cursor: Option<String>,
 
+    #[serde(default)]
+    pub query: String,
+
     #[serde(default)]
     pub filters: Option<String>,
 
-    #[serde(default = "default_browse_hits_per_page")]
-    #[serde(rename = "hitsPerPage")]
-    pub hits_per_page: usize,
+    #[serde(default)]
+    pub facet_filters: Option<serde_json::Value>,
+
+    #[serde(default)]
+    pub numeric_filters: Option<serde_json::Value>,
+
+    #[serde(default)]
+    pub tag_filters: Option<serde_json::Value>,
+
+    #[serde(default)]
+    pub attributes_to_retrieve: Option<Vec<String>>,
+
+    #[serde(default)]
+    pub hits_per_page: Option<usize>,
+
+    /// URL-encoded parameters, as sent by the Algolia SDKs.
+    #[serde(default)]
+    pub params: Option<String>,
 }
 
-fn default_browse_hits_per_page() -> usize {
-    1000
+const MAX_BROWSE_HITS_PER_PAGE: usize = 1000;
+
+impl BrowseRequest {
+    /// The browse parameters as a search request, so filters and secured-key
+    /// restrictions are handled exactly as on the query endpoint.
+    fn into_search_request(self) -> SearchRequest {
+        let mut req = SearchRequest {
+            query: self.query,
+            filters: self.filters,
+            facet_filters: self.facet_filters,
+            numeric_filters: self.numeric_filters,
+            tag_filters: self.tag_filters,
+            attributes_to_retrieve: self.attributes_to_retrieve,
+            hits_per_page: self.hits_per_page,
+            params: self.params,
+            ..Default::default()
+        };
+        req.apply_params_string();
+        req
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -43,7 +85,7 @@ struct BrowseCursor {
     params(
         ("indexName" = String, Path, description = "Index name")
     ),
-    request_body(content = serde_json::Value, description = "Browse request with optional cursor"),
+    request_body(content = serde_json::Value, description = "Optional cursor, plus query, filters, facetFilters, numericFilters, tagFilters, attributesToRetrieve and hitsPerPage, or the same as a params string"),
     responses(
         (status = 200, description = "Documents page with cursor", body = serde_json::Value),
         (status = 404, description = "Index not found")
@@ -55,26 +97,20 @@ struct BrowseCursor {
 pub async fn browse_index(
     State(state): State<Arc<AppState>>,
     Path(index_name): Path<String>,
+    attributes: Option<Extension<AttributeRestrictions>>,
+    secured: Option<Extension<SecuredKeyRestrictions>>,
     Json(req): Json<BrowseRequest>,
 ) -> Result<Json<serde_json::Value>, FlapjackError> {
+    let attributes = attributes.map(|Extension(a)| a);
+    let cursor = req.cursor.clone();
+    let mut req = req.into_search_request();
+    if let Some(Extension(ref restrictions)) = secured {
+        super::search::merge_secured_filters(&mut req, restrictions)?;
+    }
     let index = state.manager.get_or_load(&index_name)?;
-    let reader = index.reader();
-    let searcher = reader.searcher();
-    let current_generation = searcher
-        .segment_readers()
-        .iter()
-        .map(|sr| sr.segment_id().uuid_string())
-        .collect::<Vec<_>>()
-        .join("-");
-    let current_gen_hash = {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        let mut hasher = DefaultHasher::new();
-        current_generation.hash(&mut hasher);
-        hasher.finish()
-    };
+    let current_gen_hash = index_generation(&index);
 
-    let (offset, _expected_gen) = if let Some(cursor_str) = &req.cursor {
+    let (offset, _expected_gen) = if let Some(cursor_str) = &cursor {
         let decoded = base64::engine::general_purpose::STANDARD
             .decode(cursor_str)
             .map_err(|_| FlapjackError::InvalidQuery("Invalid cursor".to_string()))?;
@@ -94,20 +130,27 @@ pub async fn browse_index(
         (0, None)
     };
 
-    let filter = if let Some(filter_str) = &req.filters {
-        Some(
-            parse_filter(filter_str)
-                .map_err(|e| FlapjackError::InvalidQuery(format!("Filter parse error: {}", e)))?,
-        )
-    } else {
-        None
-    };
-
-    let hits_per_page = req.hits_per_page.min(1000);
+    // `build_combined_filter` skips a `filters` string it cannot parse, which
+    // would also drop a secured key's filter merged into it.
+    if let Some(filter_str) = &req.filters {
+        parse_filter(filter_str)
+            .map_err(|e| FlapjackError::InvalidQuery(format!("Filter parse error: {}", e)))?;
+    }
+    let filter = req.build_combined_filter();
+    if let (Some(attributes), Some(filter)) = (&attributes, &filter) {
+        attributes
+            .check_filter(filter)
+            .map_err(FlapjackError::InvalidQuery)?;
+    }
+
+    let hits_per_page = req
+        .hits_per_page
+        .unwrap_or(MAX_BROWSE_HITS_PER_PAGE)
+        .min(MAX_BROWSE_HITS_PER_PAGE);
 
     let result = state.manager.search_with_facets(
         &index_name,
-        "",
+        &req.query,
         filter.as_ref(),
         None,
         hits_per_page,
@@ -117,6 +160,7 @@ pub async fn browse_index(
 
     let total = result.total;
     let page_docs = &result.documents;
+    let settings = state.manager.get_settings(&index_name);
 
     let hits: Vec<serde_json::Value> = page_docs
         .iter()
@@ -128,8 +172,23 @@ pub async fn browse_index(
             );
 
             for (key, value) in &scored_doc.document.fields {
+                if let Some(ref attrs) = req.attributes_to_retrieve {
+                    if !attrs.contains(key) && !attrs.iter().any(|a| a == "*") {
+                        continue;
+                    }
+                } else if let Some(ref settings) = settings {
+                    if !settings.should_retrieve(key) {
+                        continue;
+                    }
+                }
                 doc_map.insert(key.clone(), field_value_to_json(value));
             }
+            if let Some(ref settings) = settings {
+                settings.strip_unretrievable(&mut doc_map);
+            }
+            if let Some(ref attributes) = attributes {
+                attributes.strip(&mut doc_map);
+            }
 
             serde_json::Value::Object(doc_map)
         })
@@ -0,0 +1,313 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use super::AppState;
+use flapjack::index::campaigns::{Campaign, CampaignStore, CAMPAIGNS_FILE};
+
+fn with_status(campaign: &Campaign, now: i64) -> serde_json::Value {
+    let mut value = serde_json::to_value(campaign).unwrap_or_default();
+    value["status"] = serde_json::json!(campaign.status(now));
+    value
+}
+
+fn load_campaign(
+    state: &AppState,
+    index_name: &str,
+    object_id: &str,
+) -> Result<Campaign, (StatusCode, String)> {
+    let path = state
+        .manager
+        .base_path
+        .join(index_name)
+        .join(CAMPAIGNS_FILE);
+    let store = if path.exists() {
+        CampaignStore::load(&path)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    } else {
+        CampaignStore::new()
+    };
+    store.get(object_id).cloned().ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Campaign {} not found", object_id),
+        )
+    })
+}
+
+/// List the campaigns of an index
+#[utoipa::path(
+    get,
+    path = "/1/indexes/{indexName}/campaigns",
+    tag = "campaigns",
+    params(
+        ("indexName" = String, Path, description = "Index name")
+    ),
+    responses(
+        (status = 200, description = "All campaigns with their current status", body = serde_json::Value)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn list_campaigns(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let path = state
+        .manager
+        .base_path
+        .join(&index_name)
+        .join(CAMPAIGNS_FILE);
+    let campaigns = if path.exists() {
+        CampaignStore::load(&path)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .all()
+    } else {
+        Vec::new()
+    };
+    let now = chrono::Utc::now().timestamp();
+    let hits: Vec<serde_json::Value> = campaigns.iter().map(|c| with_status(c, now)).collect();
+    Ok(Json(serde_json::json!({
+        "hits": hits,
+        "nbHits": hits.len()
+    })))
+}
+
+/// Get a campaign by ID
+#[utoipa::path(
+    get,
+    path = "/1/indexes/{indexName}/campaigns/{objectID}",
+    tag = "campaigns",
+    params(
+        ("indexName" = String, Path, description = "Index name"),
+        ("objectID" = String, Path, description = "Campaign ID")
+    ),
+    responses(
+        (status = 200, description = "Campaign retrieved", body = serde_json::Value),
+        (status = 404, description = "Campaign not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn get_campaign(
+    State(state): State<Arc<AppState>>,
+    Path((index_name, object_id)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let campaign = load_campaign(&state, &index_name, &object_id)?;
+    Ok(Json(with_status(&campaign, chrono::Utc::now().timestamp())))
+}
+
+/// Create or update a campaign
+#[utoipa::path(
+    put,
+    path = "/1/indexes/{indexName}/campaigns/{objectID}",
+    tag = "campaigns",
+    params(
+        ("indexName" = String, Path, description = "Index name"),
+        ("objectID" = String, Path, description = "Campaign ID")
+    ),
+    request_body(content = serde_json::Value, description = "Campaign: name, owner, validity, impressionQuota and its rules"),
+    responses(
+        (status = 200, description = "Campaign saved", body = serde_json::Value),
+        (status = 400, description = "Invalid campaign")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn save_campaign(
+    State(state): State<Arc<AppState>>,
+    Path((index_name, object_id)): Path<(String, String)>,
+    Json(mut campaign): Json<Campaign>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    campaign.object_id = object_id;
+    campaign
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    state
+        .manager
+        .create_tenant(&index_name)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let path = state
+        .manager
+        .base_path
+        .join(&index_name)
+        .join(CAMPAIGNS_FILE);
+    let mut store = if path.exists() {
+        CampaignStore::load(&path)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    } else {
+        CampaignStore::new()
+    };
+
+    store.insert(campaign.clone());
+
+    store
+        .save(&path)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Campaign rules are searched from the rules cache.
+    state.manager.invalidate_rules_cache(&index_name);
+
+    state.manager.append_oplog(
+        &index_name,
+        "save_campaign",
+        serde_json::to_value(&campaign).unwrap_or_default(),
+    );
+
+    let task = state
+        .manager
+        .make_noop_task(&index_name)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(serde_json::json!({
+        "taskID": task.numeric_id,
+        "updatedAt": chrono::Utc::now().to_rfc3339(),
+        "id": campaign.object_id
+    })))
+}
+
+/// Delete a campaign and all its rules
+#[utoipa::path(
+    delete,
+    path = "/1/indexes/{indexName}/campaigns/{objectID}",
+    tag = "campaigns",
+    params(
+        ("indexName" = String, Path, description = "Index name"),
+        ("objectID" = String, Path, description = "Campaign ID")
+    ),
+    responses(
+        (status = 200, description = "Campaign deleted", body = serde_json::Value),
+        (status = 404, description = "Campaign not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn delete_campaign(
+    State(state): State<Arc<AppState>>,
+    Path((index_name, object_id)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let path = state
+        .manager
+        .base_path
+        .join(&index_name)
+        .join(CAMPAIGNS_FILE);
+    let not_found = || {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Campaign {} not found", object_id),
+        )
+    };
+
+    if !path.exists() {
+        return Err(not_found());
+    }
+
+    let mut store = CampaignStore::load(&path)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    store.remove(&object_id).ok_or_else(not_found)?;
+
+    store
+        .save(&path)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    state.manager.invalidate_rules_cache(&index_name);
+
+    state.manager.append_oplog(
+        &index_name,
+        "delete_campaign",
+        serde_json::json!({"objectID": object_id}),
+    );
+
+    let task = state
+        .manager
+        .make_noop_task(&index_name)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(serde_json::json!({
+        "taskID": task.numeric_id,
+        "deletedAt": chrono::Utc::now().to_rfc3339()
+    })))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CampaignStatsParams {
+    #[serde(default)]
+    pub start_date: Option<String>,
+    #[serde(default)]
+    pub end_date: Option<String>,
+}
+
+/// Impressions, clicks on pinned objects and quota use of a campaign
+#[utoipa::path(
+    get,
+    path = "/1/indexes/{indexName}/campaigns/{objectID}/stats",
+    tag = "campaigns",
+    params(
+        ("indexName" = String, Path, description = "Index name"),
+        ("objectID" = String, Path, description = "Campaign ID"),
+        ("startDate" = Option<String>, Query, description = "First day (YYYY-MM-DD), default the campaign's start or 8 days ago"),
+        ("endDate" = Option<String>, Query, description = "Last day (YYYY-MM-DD), default today")
+    ),
+    responses(
+        (status = 200, description = "Campaign performance with daily breakdown", body = serde_json::Value),
+        (status = 404, description = "Campaign not found"),
+        (status = 503, description = "Analytics is disabled")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn get_campaign_stats(
+    State(state): State<Arc<AppState>>,
+    Path((index_name, object_id)): Path<(String, String)>,
+    Query(params): Query<CampaignStatsParams>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let engine = state.analytics_engine.as_ref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Analytics is disabled".to_string(),
+    ))?;
+    let campaign = load_campaign(&state, &index_name, &object_id)?;
+    let now = chrono::Utc::now();
+    let start_date = params.start_date.unwrap_or_else(|| {
+        let start = campaign
+            .validity
+            .iter()
+            .map(|r| r.from)
+            .min()
+            .and_then(|from| chrono::DateTime::from_timestamp(from, 0))
+            .filter(|start| *start <= now)
+            .unwrap_or(now - chrono::Duration::days(8));
+        start.format("%Y-%m-%d").to_string()
+    });
+    let end_date = params
+        .end_date
+        .unwrap_or_else(|| now.format("%Y-%m-%d").to_string());
+
+    let mut stats = engine
+        .campaign_stats(
+            &index_name,
+            &object_id,
+            &campaign.pinned_object_ids(),
+            &start_date,
+            &end_date,
+        )
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Analytics error: {}", e)))?;
+    stats["status"] = serde_json::json!(campaign.status(now.timestamp()));
+    if let Some(quota) = campaign.impression_quota {
+        let impressions = stats["impressions"].as_i64().unwrap_or(0);
+        stats["impressionQuota"] = serde_json::json!(quota);
+        stats["quotaUsed"] =
+            serde_json::json!((impressions as f64 / quota as f64 * 1000.0).round() / 1000.0);
+    }
+    Ok(Json(stats))
+}
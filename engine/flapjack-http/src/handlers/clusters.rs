@@ -0,0 +1,469 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::IntoResponse,
+    Json,
+};
+use flapjack::ErrorCode;
+use flapjack_replication::user_mapping::{validate_user_id, UserId};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use super::AppState;
+use crate::error_codes::error_response;
+use crate::mcm::{cluster_names, mapping_store, publish_mapping, USER_ID_HEADER};
+
+const DEFAULT_HITS_PER_PAGE: usize = 20;
+const MAX_HITS_PER_PAGE: usize = 1000;
+const TOP_USERS_PER_CLUSTER: usize = 10;
+
+fn io_error(e: std::io::Error) -> axum::response::Response {
+    error_response(ErrorCode::IoError, e.to_string())
+}
+
+fn header_user_id(headers: &HeaderMap) -> Result<String, axum::response::Response> {
+    let user_id = headers
+        .get(USER_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| {
+            error_response(
+                ErrorCode::BadRequest,
+                "X-Algolia-User-ID header is required",
+            )
+        })?;
+    validate_user_id(user_id).map_err(|m| error_response(ErrorCode::BadRequest, m))?;
+    Ok(user_id.to_string())
+}
+
+fn check_cluster(state: &AppState, cluster: &str) -> Result<(), axum::response::Response> {
+    if cluster_names(state).iter().any(|c| c == cluster) {
+        Ok(())
+    } else {
+        Err(error_response(
+            ErrorCode::BadRequest,
+            format!("Unknown cluster '{}'", cluster),
+        ))
+    }
+}
+
+fn page<T: Clone>(items: &[T], page: usize, hits_per_page: usize) -> Vec<T> {
+    items
+        .iter()
+        .skip(page.saturating_mul(hits_per_page))
+        .take(hits_per_page)
+        .cloned()
+        .collect()
+}
+
+/// GET /1/clusters — clusters with their user and record counts
+pub async fn list_clusters(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let store = match mapping_store(&state) {
+        Ok(store) => store,
+        Err(e) => return io_error(e),
+    };
+    let snapshot = store.snapshot();
+    let users: Vec<UserId> = snapshot.user_ids().collect();
+    let clusters: Vec<_> = cluster_names(&state)
+        .into_iter()
+        .map(|name| {
+            let on_cluster = users.iter().filter(|u| u.cluster_name == name);
+            let (nb_user_ids, nb_records, data_size) = on_cluster
+                .fold((0u64, 0u64, 0u64), |(n, r, d), u| {
+                    (n + 1, r + u.nb_records, d + u.data_size)
+                });
+            json!({
+                "clusterName": name,
+                "nbRecords": nb_records,
+                "nbUserIDs": nb_user_ids,
+                "dataSize": data_size,
+            })
+        })
+        .collect();
+    Json(json!({ "clusters": clusters })).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssignUserIdRequest {
+    pub cluster: String,
+}
+
+/// POST /1/clusters/mapping — pin the X-Algolia-User-ID user to a cluster
+pub async fn assign_user_id(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(body): Json<AssignUserIdRequest>,
+) -> impl IntoResponse {
+    let user_id = match header_user_id(&headers) {
+        Ok(u) => u,
+        Err(resp) => return resp,
+    };
+    assign(&state, vec![user_id], &body.cluster)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchAssignUserIdsRequest {
+    pub cluster: String,
+    pub users: Vec<String>,
+}
+
+/// POST /1/clusters/mapping/batch — pin several users to a cluster
+pub async fn batch_assign_user_ids(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<BatchAssignUserIdsRequest>,
+) -> impl IntoResponse {
+    if body.users.is_empty() {
+        return error_response(ErrorCode::BadRequest, "users must not be empty");
+    }
+    for user in &body.users {
+        if let Err(m) = validate_user_id(user) {
+            return error_response(ErrorCode::BadRequest, m);
+        }
+    }
+    assign(&state, body.users, &body.cluster)
+}
+
+fn assign(state: &AppState, users: Vec<String>, cluster: &str) -> axum::response::Response {
+    if let Err(resp) = check_cluster(state, cluster) {
+        return resp;
+    }
+    let store = match mapping_store(state) {
+        Ok(store) => store,
+        Err(e) => return io_error(e),
+    };
+    let now = chrono::Utc::now();
+    if let Err(e) = store.assign(&users, cluster, now.timestamp_millis()) {
+        return io_error(e);
+    }
+    publish_mapping(state, &store);
+    Json(json!({ "createdAt": now.to_rfc3339() })).into_response()
+}
+
+/// GET /1/clusters/mapping/:userID
+pub async fn get_user_id(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+) -> impl IntoResponse {
+    let store = match mapping_store(&state) {
+        Ok(store) => store,
+        Err(e) => return io_error(e),
+    };
+    match store.user_id(&user_id) {
+        Some(user) => Json(json!(user)).into_response(),
+        None => error_response(
+            ErrorCode::NotFound,
+            format!("userID '{}' not found", user_id),
+        ),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListUserIdsParams {
+    #[serde(default)]
+    pub page: usize,
+    pub hits_per_page: Option<usize>,
+}
+
+/// GET /1/clusters/mapping?page=&hitsPerPage=
+pub async fn list_user_ids(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ListUserIdsParams>,
+) -> impl IntoResponse {
+    let store = match mapping_store(&state) {
+        Ok(store) => store,
+        Err(e) => return io_error(e),
+    };
+    let hits_per_page = params
+        .hits_per_page
+        .unwrap_or(DEFAULT_HITS_PER_PAGE)
+        .clamp(1, MAX_HITS_PER_PAGE);
+    let users: Vec<UserId> = store.snapshot().user_ids().collect();
+    Json(json!({
+        "userIDs": page(&users, params.page, hits_per_page),
+        "page": params.page,
+        "hitsPerPage": hits_per_page,
+    }))
+    .into_response()
+}
+
+/// GET /1/clusters/mapping/top — users with the most records per cluster
+pub async fn get_top_user_ids(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match mapping_store(&state) {
+        Ok(store) => {
+            Json(json!({ "topUsers": store.top_users(TOP_USERS_PER_CLUSTER) })).into_response()
+        }
+        Err(e) => io_error(e),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchUserIdsRequest {
+    #[serde(default)]
+    pub query: String,
+    #[serde(default)]
+    pub cluster_name: Option<String>,
+    #[serde(default)]
+    pub page: usize,
+    #[serde(default)]
+    pub hits_per_page: Option<usize>,
+}
+
+/// POST /1/clusters/mapping/search
+pub async fn search_user_ids(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<SearchUserIdsRequest>,
+) -> impl IntoResponse {
+    let store = match mapping_store(&state) {
+        Ok(store) => store,
+        Err(e) => return io_error(e),
+    };
+    let hits_per_page = body
+        .hits_per_page
+        .unwrap_or(DEFAULT_HITS_PER_PAGE)
+        .clamp(1, MAX_HITS_PER_PAGE);
+    let hits = store.search(&body.query, body.cluster_name.as_deref());
+    Json(json!({
+        "hits": page(&hits, body.page, hits_per_page),
+        "nbHits": hits.len(),
+        "page": body.page,
+        "hitsPerPage": hits_per_page,
+        "updatedAt": chrono::Utc::now().to_rfc3339(),
+    }))
+    .into_response()
+}
+
+/// DELETE /1/clusters/mapping — remove the X-Algolia-User-ID user and its data
+pub async fn remove_user_id(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let user_id = match header_user_id(&headers) {
+        Ok(u) => u,
+        Err(resp) => return resp,
+    };
+    let store = match mapping_store(&state) {
+        Ok(store) => store,
+        Err(e) => return io_error(e),
+    };
+    let now = chrono::Utc::now();
+    match store.remove(&user_id, now.timestamp_millis()) {
+        Ok(Some(_)) => {
+            publish_mapping(&state, &store);
+            Json(json!({ "deletedAt": now.to_rfc3339() })).into_response()
+        }
+        Ok(None) => error_response(
+            ErrorCode::NotFound,
+            format!("userID '{}' not found", user_id),
+        ),
+        Err(e) => io_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingParams {
+    #[serde(default)]
+    pub get_clusters: bool,
+}
+
+/// GET /1/clusters/mapping/pending — users whose data is still being moved
+pub async fn has_pending_mappings(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<PendingParams>,
+) -> impl IntoResponse {
+    let store = match mapping_store(&state) {
+        Ok(store) => store,
+        Err(e) => return io_error(e),
+    };
+    let pending = store.pending();
+    let mut body = json!({ "pending": !pending.is_empty() });
+    if params.get_clusters {
+        body["clusters"] = json!(pending);
+    }
+    Json(body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::app_state;
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode},
+        routing::{get, post},
+        Router,
+    };
+    use tempfile::TempDir;
+    use tower::ServiceExt;
+
+    fn router(state: Arc<AppState>) -> Router {
+        Router::new()
+            .route("/1/clusters", get(list_clusters))
+            .route(
+                "/1/clusters/mapping",
+                post(assign_user_id)
+                    .get(list_user_ids)
+                    .delete(remove_user_id),
+            )
+            .route("/1/clusters/mapping/batch", post(batch_assign_user_ids))
+            .route("/1/clusters/mapping/top", get(get_top_user_ids))
+            .route("/1/clusters/mapping/search", post(search_user_ids))
+            .route("/1/clusters/mapping/pending", get(has_pending_mappings))
+            .route("/1/clusters/mapping/:userID", get(get_user_id))
+            .with_state(state)
+    }
+
+    async fn send(
+        app: &Router,
+        method: Method,
+        uri: &str,
+        user: Option<&str>,
+        body: Option<serde_json::Value>,
+    ) -> (StatusCode, serde_json::Value) {
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json");
+        if let Some(user) = user {
+            builder = builder.header(USER_ID_HEADER, user);
+        }
+        let body = body.map(|b| Body::from(b.to_string())).unwrap_or_default();
+        let resp = app
+            .clone()
+            .oneshot(builder.body(body).unwrap())
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn assign_get_list_and_remove_user_ids() {
+        let tmp = TempDir::new().unwrap();
+        let state = app_state(&tmp);
+        let cluster = crate::mcm::local_cluster_name(&state);
+        let app = router(state);
+
+        let (status, _) = send(
+            &app,
+            Method::POST,
+            "/1/clusters/mapping",
+            Some("alice"),
+            Some(json!({"cluster": cluster})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(
+            &app,
+            Method::POST,
+            "/1/clusters/mapping/batch",
+            None,
+            Some(json!({"cluster": cluster, "users": ["bob", "carol"]})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = send(&app, Method::GET, "/1/clusters/mapping/alice", None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["userID"], "alice");
+        assert_eq!(body["clusterName"], cluster.as_str());
+
+        let (_, body) = send(&app, Method::GET, "/1/clusters", None, None).await;
+        assert_eq!(body["clusters"][0]["nbUserIDs"], 3);
+
+        let (_, body) = send(
+            &app,
+            Method::GET,
+            "/1/clusters/mapping?hitsPerPage=2&page=1",
+            None,
+            None,
+        )
+        .await;
+        assert_eq!(body["userIDs"].as_array().unwrap().len(), 1);
+        assert_eq!(body["userIDs"][0]["userID"], "carol");
+
+        let (_, body) = send(
+            &app,
+            Method::POST,
+            "/1/clusters/mapping/search",
+            None,
+            Some(json!({"query": "ar"})),
+        )
+        .await;
+        assert_eq!(body["nbHits"], 1);
+        assert_eq!(body["hits"][0]["userID"], "carol");
+
+        let (status, _) = send(
+            &app,
+            Method::DELETE,
+            "/1/clusters/mapping",
+            Some("alice"),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(&app, Method::GET, "/1/clusters/mapping/alice", None, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send(
+            &app,
+            Method::DELETE,
+            "/1/clusters/mapping",
+            Some("alice"),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (_, body) = send(
+            &app,
+            Method::GET,
+            "/1/clusters/mapping/pending?getClusters=true",
+            None,
+            None,
+        )
+        .await;
+        assert_eq!(body["pending"], false);
+        assert_eq!(body["clusters"], json!({}));
+    }
+
+    #[tokio::test]
+    async fn assign_rejects_unknown_cluster_and_bad_user_ids() {
+        let tmp = TempDir::new().unwrap();
+        let app = router(app_state(&tmp));
+
+        let (status, _) = send(
+            &app,
+            Method::POST,
+            "/1/clusters/mapping",
+            Some("alice"),
+            Some(json!({"cluster": "no-such-node"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = send(
+            &app,
+            Method::POST,
+            "/1/clusters/mapping",
+            None,
+            Some(json!({"cluster": "x"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = send(
+            &app,
+            Method::POST,
+            "/1/clusters/mapping/batch",
+            None,
+            Some(json!({"cluster": "x", "users": ["ok", "not ok"]})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,101 @@
+use axum::{extract::State, Json};
+use std::sync::Arc;
+
+use super::AppState;
+use crate::body_limits::BodyLimits;
+use flapjack::index::settings::IndexSettings;
+use flapjack::query::filter::FilterCompiler;
+
+/// Server features, limits and defaults clients should respect when
+/// building requests
+///
+/// Lets SDKs and the dashboard adapt to a deployment (e.g. hide hybrid
+/// search when the server was built without vector search) instead of
+/// hard-coding assumptions.
+#[utoipa::path(
+    get,
+    path = "/1/configuration",
+    tag = "health",
+    responses(
+        (status = 200, description = "Server configuration", body = serde_json::Value)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn get_configuration(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let budget = flapjack::get_global_budget();
+    let defaults = IndexSettings::default();
+    Json(serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "features": {
+            "vectorSearch": cfg!(feature = "vector-search"),
+            "localEmbeddings": cfg!(feature = "vector-search-local"),
+            "analytics": state.analytics_engine.is_some(),
+            "experiments": state.experiment_store.is_some(),
+            "replication": state.replication_manager.is_some(),
+            "apiKeys": state.key_store.is_some(),
+            "tls": state.ssl_manager.is_some(),
+        },
+        "maxBodySize": BodyLimits::global().to_json(),
+        "limits": {
+            "maxDocumentSize": budget.max_document_size_bytes(),
+            "maxConcurrentWriters": budget.max_concurrent_writers(),
+            "cpus": flapjack::ResourceLimits::global().cpus,
+            "maxFilterClauses": FilterCompiler::MAX_BOOLEAN_CLAUSES,
+            "maxFilterDepth": FilterCompiler::MAX_FILTER_DEPTH,
+        },
+        "defaults": {
+            "hitsPerPage": defaults.hits_per_page,
+            "maxValuesPerFacet": defaults.max_values_per_facet,
+            "paginationLimitedTo": defaults.pagination_limited_to,
+            "queryType": defaults.query_type,
+            "minWordSizefor1Typo": defaults.min_word_size_for_1_typo,
+            "minWordSizefor2Typos": defaults.min_word_size_for_2_typos,
+            "highlightPreTag": defaults.highlight_pre_tag,
+            "highlightPostTag": defaults.highlight_post_tag,
+            "ranking": defaults.ranking,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::metrics::MetricsState;
+    use crate::test_support::bare_app_state;
+    use tempfile::TempDir;
+
+    fn make_state(tmp: &TempDir) -> Arc<AppState> {
+        Arc::new(AppState {
+            metrics_state: Some(MetricsState::new()),
+            ..bare_app_state(tmp)
+        })
+    }
+
+    #[tokio::test]
+    async fn configuration_reports_body_limits_per_route_class() {
+        let tmp = TempDir::new().unwrap();
+        let Json(body) = get_configuration(State(make_state(&tmp))).await;
+        let limits = BodyLimits::global();
+        assert_eq!(body["maxBodySize"]["search"], limits.search);
+        assert_eq!(body["maxBodySize"]["ingest"], limits.ingest);
+    }
+
+    #[tokio::test]
+    async fn configuration_reports_features_limits_and_defaults() {
+        let tmp = TempDir::new().unwrap();
+        let Json(body) = get_configuration(State(make_state(&tmp))).await;
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            body["features"]["vectorSearch"],
+            cfg!(feature = "vector-search")
+        );
+        assert_eq!(body["features"]["replication"], false);
+        assert_eq!(body["features"]["apiKeys"], false);
+        assert_eq!(body["limits"]["maxFilterClauses"], 1000);
+        assert!(body["limits"]["maxDocumentSize"].as_u64().unwrap() > 0);
+        assert_eq!(body["defaults"]["hitsPerPage"], 20);
+        assert_eq!(body["defaults"]["paginationLimitedTo"], 1000);
+    }
+}
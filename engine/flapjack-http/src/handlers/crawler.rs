@@ -0,0 +1,97 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use flapjack::ErrorCode;
+use serde_json::json;
+use std::sync::Arc;
+
+use super::AppState;
+use crate::crawler::{self, CrawlRegistry, CrawlerConfig};
+use crate::error_codes::error_response;
+
+fn not_found(index_name: &str) -> axum::response::Response {
+    error_response(
+        ErrorCode::NotFound,
+        format!("No crawler configured for index '{}'", index_name),
+    )
+}
+
+/// GET /1/indexes/:indexName/crawler — configuration and latest crawl
+pub async fn get_crawler(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> impl IntoResponse {
+    match crawler::load_config(&state.manager.base_path, &index_name) {
+        Ok(Some(config)) => {
+            let status = match CrawlRegistry::global().get(&index_name) {
+                Some(status) => json!(status),
+                None => json!({"state": "notStarted"}),
+            };
+            Json(json!({"config": config, "status": status})).into_response()
+        }
+        Ok(None) => not_found(&index_name),
+        Err(e) => error_response(ErrorCode::IoError, e.to_string()),
+    }
+}
+
+/// PUT /1/indexes/:indexName/crawler — create or replace the crawler
+pub async fn put_crawler(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+    Json(mut config): Json<CrawlerConfig>,
+) -> impl IntoResponse {
+    config.index_name = index_name;
+    if let Err(message) = config.validate() {
+        return error_response(ErrorCode::BadRequest, message);
+    }
+    if let Err(e) = state.manager.create_tenant(&config.index_name) {
+        return error_response(ErrorCode::InternalError, e.to_string());
+    }
+    match crawler::save_config(&state.manager.base_path, &config) {
+        Ok(()) => Json(json!({
+            "updatedAt": chrono::Utc::now().to_rfc3339(),
+            "config": config,
+        }))
+        .into_response(),
+        Err(e) => error_response(ErrorCode::IoError, e.to_string()),
+    }
+}
+
+/// DELETE /1/indexes/:indexName/crawler — remove the crawler, keeping its records
+pub async fn delete_crawler(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> impl IntoResponse {
+    match crawler::delete_config(&state.manager.base_path, &index_name) {
+        Ok(true) => Json(json!({"deletedAt": chrono::Utc::now().to_rfc3339()})).into_response(),
+        Ok(false) => not_found(&index_name),
+        Err(e) => error_response(ErrorCode::IoError, e.to_string()),
+    }
+}
+
+/// POST /1/indexes/:indexName/crawler/run — start a crawl in the background
+pub async fn run_crawler(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> impl IntoResponse {
+    match crawler::load_config(&state.manager.base_path, &index_name) {
+        Ok(Some(_)) => {}
+        Ok(None) => return not_found(&index_name),
+        Err(e) => return error_response(ErrorCode::IoError, e.to_string()),
+    }
+    if CrawlRegistry::global().is_running(&index_name) {
+        return error_response(
+            ErrorCode::Conflict,
+            format!("A crawl of '{}' is already running", index_name),
+        );
+    }
+    crawler::spawn_crawl(state, index_name);
+    (
+        StatusCode::ACCEPTED,
+        Json(json!({"startedAt": chrono::Utc::now().to_rfc3339()})),
+    )
+        .into_response()
+}
@@ -0,0 +1,18 @@
+use axum::{response::IntoResponse, Json};
+use flapjack::ErrorCode;
+use serde_json::json;
+
+/// GET /1/errors — catalog of machine-readable error codes
+pub async fn list_error_codes() -> impl IntoResponse {
+    let errors: Vec<_> = ErrorCode::ALL
+        .iter()
+        .map(|code| {
+            json!({
+                "code": code,
+                "status": code.status().as_u16(),
+                "description": code.description(),
+            })
+        })
+        .collect();
+    Json(json!({ "errors": errors }))
+}
@@ -6,15 +6,17 @@ use axum::{
     Json,
 };
 use flapjack::experiments::{
+    assignment::{self, AssignmentMethod},
     config::{
-        Experiment, ExperimentArm, ExperimentConclusion, ExperimentError, ExperimentStatus,
-        PrimaryMetric,
+        AssignmentMode, AssignmentUnit, AutoStopPolicy, Experiment, ExperimentArm,
+        ExperimentConclusion, ExperimentError, ExperimentStatus, PrimaryMetric,
     },
-    metrics, stats,
+    metrics, provisioning,
+    shadow::ShadowConfig,
+    stats,
     store::{ExperimentFilter, ExperimentStore},
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::sync::Arc;
 
 use super::AppState;
@@ -22,6 +24,21 @@ use super::AppState;
 const DEFAULT_LIST_LIMIT: usize = 20;
 const DEFAULT_LIST_OFFSET: usize = 0;
 const DEFAULT_MINIMUM_DAYS: u32 = 14;
+/// Expected loss below which an arm is recommended (0.1 percentage points
+/// for rate metrics) when the experiment does not set its own threshold.
+const DEFAULT_BAYESIAN_LOSS_THRESHOLD: f64 = 0.001;
+const CREDIBLE_INTERVAL_LEVEL: f64 = 0.95;
+const MAX_EXPOSURES_PER_REQUEST: usize = 1000;
+/// Analytics tag on exposure rows so they can be told apart from real searches.
+const EXPOSURE_ANALYTICS_TAG: &str = "abtest-exposure";
+/// Significance level of the per-day tests of an A/A experiment, i.e. the
+/// false-positive rate a sound pipeline should show.
+const AA_TEST_ALPHA: f64 = 0.05;
+/// Users each arm needs on a day for that day to be tested.
+const AA_TEST_MIN_DAILY_USERS: u64 = 10;
+/// An A/A test is flagged once its false positives would happen less than
+/// this often with a sound pipeline.
+const AA_TEST_SUSPECT_P_VALUE: f64 = 0.01;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -38,6 +55,64 @@ pub struct CreateExperimentRequest {
     pub winsorization_cap: Option<f64>,
     #[serde(default)]
     pub interleaving: Option<bool>,
+    #[serde(default)]
+    pub aa_test: Option<bool>,
+    #[serde(default)]
+    pub assignment_mode: Option<AssignmentMode>,
+    #[serde(default)]
+    pub assignment_unit: Option<AssignmentUnit>,
+    #[serde(default)]
+    pub assignment_header: Option<String>,
+    #[serde(default)]
+    pub assignment_salt: Option<String>,
+    #[serde(default)]
+    pub layer: Option<String>,
+    #[serde(default)]
+    pub layer_traffic: Option<f64>,
+    #[serde(default)]
+    pub bayesian_loss_threshold: Option<f64>,
+    #[serde(default)]
+    pub auto_stop: Option<AutoStopPolicy>,
+    #[serde(default)]
+    pub scheduled_start_at: Option<i64>,
+    #[serde(default)]
+    pub scheduled_end_at: Option<i64>,
+    #[serde(default)]
+    pub ramp_stages: Option<Vec<f64>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RampRequest {
+    /// Defaults to the next of the experiment's `rampStages`.
+    #[serde(default)]
+    pub traffic_split: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposureRequest {
+    pub exposures: Vec<Exposure>,
+}
+
+/// One user seeing one arm of an experiment outside of a search request.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Exposure {
+    pub user_token: String,
+    /// "control" or "variant".
+    #[serde(rename = "variantID")]
+    pub variant_id: String,
+    /// Send the same ID as `queryID` on click/conversion events to attribute
+    /// them to this exposure. Generated when omitted.
+    #[serde(default, rename = "queryID")]
+    pub query_id: Option<String>,
+    /// Unix ms; defaults to the time the request is received.
+    #[serde(default)]
+    pub timestamp: Option<i64>,
+    /// Number of items shown. Defaults to 1; 0 counts as a zero-result view.
+    #[serde(default)]
+    pub nb_hits: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -94,10 +169,64 @@ pub struct ResultsResponse {
     pub sample_ratio_mismatch: bool,
     pub guard_rail_alerts: Vec<GuardRailAlertResponse>,
     pub cuped_applied: bool,
+    /// Pre-experiment covariates used by the CUPED adjustment (empty when not applied).
+    pub cuped_covariates: Vec<String>,
+    /// Fraction of the primary metric's variance removed by CUPED.
+    pub cuped_variance_reduction: Option<f64>,
     pub outlier_users_excluded: usize,
     pub no_stable_id_queries: u64,
     pub recommendation: Option<String>,
     pub interleaving: Option<InterleavingResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aa_test: Option<AaTestResponse>,
+    /// Per-stage results once the experiment has been ramped; the headline
+    /// numbers above then cover the current stage only.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stages: Vec<StageResponse>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageResponse {
+    pub traffic_split: f64,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub control: ArmResponse,
+    pub variant: ArmResponse,
+    pub sample_ratio_mismatch: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HoldoutRequest {
+    /// Percentage of the index's users (0–100) kept out of every experiment.
+    pub percentage: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimeseriesQuery {
+    /// `json` (default) or `csv`.
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeseriesResponse {
+    #[serde(rename = "experimentID")]
+    pub experiment_id: String,
+    pub primary_metric: PrimaryMetric,
+    pub days: Vec<TimeseriesDayResponse>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeseriesDayResponse {
+    /// `YYYY-MM-DD` (UTC).
+    pub date: String,
+    pub control: ArmResponse,
+    pub variant: ArmResponse,
+    /// Relative change of the primary metric (variant vs control) on that
+    /// day; `None` when the control value is zero.
+    pub lift: Option<f64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -113,6 +242,34 @@ pub struct InterleavingResponse {
     pub data_quality_ok: bool,
 }
 
+/// False positives of an A/A experiment: each day's searches are tested on
+/// their own, so a sound pipeline flags about `expectedRate` of the days.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AaTestResponse {
+    pub days_tested: u64,
+    pub false_positives: u64,
+    pub false_positive_rate: Option<f64>,
+    pub expected_rate: f64,
+    /// Chance of at least `falsePositives` out of `daysTested` with a sound
+    /// pipeline.
+    pub p_value: f64,
+    /// Too many false positives: assignment or metrics are likely biased.
+    pub pipeline_suspect: bool,
+    pub days: Vec<AaTestDayResponse>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AaTestDayResponse {
+    /// `YYYY-MM-DD` (UTC).
+    pub date: String,
+    pub p_value: f64,
+    pub significant: bool,
+    /// False-positive rate over the days tested so far.
+    pub false_positive_rate: f64,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GuardRailAlertResponse {
@@ -166,6 +323,17 @@ pub struct SignificanceResponse {
 #[serde(rename_all = "camelCase")]
 pub struct BayesianResponse {
     pub prob_variant_better: f64,
+    /// Expected drop in the primary metric if the variant is shipped and
+    /// control was actually better (and vice versa).
+    pub expected_loss_variant: f64,
+    pub expected_loss_control: f64,
+    /// 95% credible intervals of each arm's rate.
+    pub control_credible_interval: [f64; 2],
+    pub variant_credible_interval: [f64; 2],
+    pub loss_threshold: f64,
+    /// Arm whose expected loss is below `lossThreshold` ("variant" is
+    /// preferred when both are), or `None` to keep collecting data.
+    pub decision: Option<String>,
 }
 
 fn experiment_store_unavailable_response() -> Response {
@@ -217,6 +385,72 @@ fn validate_conclusion_winner(winner: Option<String>) -> Result<Option<String>,
     }
 }
 
+/// Provision or refresh the private variant index of a `settingsDelta`
+/// experiment, and drop a previously provisioned one that is no longer used.
+async fn reconcile_variant_index(
+    state: &AppState,
+    experiment: &mut Experiment,
+    previous: Option<&Experiment>,
+) -> Result<(), Response> {
+    let previous_variant = previous
+        .filter(|p| p.index_name == experiment.index_name)
+        .and_then(provisioning::provisioned_variant)
+        .map(str::to_string);
+    let stale_variant = previous
+        .and_then(provisioning::provisioned_variant)
+        .map(str::to_string);
+
+    if let Some(delta) = experiment.variant.settings_delta.clone() {
+        if experiment.variant.index_name.is_some()
+            && experiment.variant.index_name != previous_variant
+        {
+            return Err(experiment_error_to_response(
+                ExperimentError::InvalidConfig(
+                    "variant settingsDelta and indexName are mutually exclusive".to_string(),
+                ),
+            ));
+        }
+        let variant_index = match previous_variant {
+            Some(name) => {
+                provisioning::apply_settings_delta(
+                    &state.manager,
+                    &experiment.index_name,
+                    &name,
+                    &delta,
+                )
+                .map_err(IntoResponse::into_response)?;
+                name
+            }
+            None => {
+                let name = provisioning::variant_index_name(&experiment.index_name, &experiment.id);
+                provisioning::provision_variant_index(
+                    &state.manager,
+                    &experiment.index_name,
+                    &name,
+                    &delta,
+                )
+                .await
+                .map_err(IntoResponse::into_response)?;
+                name
+            }
+        };
+        experiment.variant.index_name = Some(variant_index);
+    }
+
+    if let Some(stale) = stale_variant {
+        if experiment.variant.index_name.as_deref() != Some(stale.as_str()) {
+            teardown_variant_index(state, &stale).await;
+        }
+    }
+    Ok(())
+}
+
+async fn teardown_variant_index(state: &AppState, variant_index: &str) {
+    if let Err(e) = provisioning::teardown_variant_index(&state.manager, variant_index).await {
+        tracing::error!("failed to remove variant index {}: {}", variant_index, e);
+    }
+}
+
 pub async fn create_experiment(
     State(state): State<Arc<AppState>>,
     Json(body): Json<CreateExperimentRequest>,
@@ -226,7 +460,7 @@ pub async fn create_experiment(
         None => return experiment_store_unavailable_response(),
     };
 
-    let experiment = Experiment {
+    let mut experiment = Experiment {
         id: uuid::Uuid::new_v4().to_string(),
         name: body.name,
         index_name: body.index_name,
@@ -242,11 +476,40 @@ pub async fn create_experiment(
         winsorization_cap: body.winsorization_cap,
         conclusion: None,
         interleaving: body.interleaving,
+        aa_test: body.aa_test,
+        assignment_mode: body.assignment_mode,
+        assignment_unit: body.assignment_unit,
+        assignment_header: body.assignment_header,
+        assignment_salt: body.assignment_salt,
+        auto_stop: body.auto_stop,
+        guard_rail_alert_since: None,
+        stop_reason: None,
+        scheduled_start_at: body.scheduled_start_at,
+        scheduled_end_at: body.scheduled_end_at,
+        ramp_stages: body.ramp_stages,
+        traffic_history: Vec::new(),
+        layer: body.layer,
+        layer_traffic: body.layer_traffic,
+        layer_buckets: None,
+        bayesian_loss_threshold: body.bayesian_loss_threshold,
     };
 
+    if let Err(err) = experiment.validate() {
+        return experiment_error_to_response(err);
+    }
+    if let Err(resp) = reconcile_variant_index(&state, &mut experiment, None).await {
+        return resp;
+    }
+    let provisioned = provisioning::provisioned_variant(&experiment).map(str::to_string);
+
     match store.create(experiment) {
         Ok(created) => (StatusCode::CREATED, Json(created)).into_response(),
-        Err(err) => experiment_error_to_response(err),
+        Err(err) => {
+            if let Some(variant) = provisioned {
+                teardown_variant_index(&state, &variant).await;
+            }
+            experiment_error_to_response(err)
+        }
     }
 }
 
@@ -322,8 +585,16 @@ pub async fn update_experiment(
         Ok(experiment) => experiment,
         Err(err) => return experiment_error_to_response(err),
     };
+    // Checked up front so a rejected update never touches variant indexes.
+    if existing.status != ExperimentStatus::Draft {
+        return experiment_error_to_response(ExperimentError::InvalidStatus(format!(
+            "{:?}",
+            existing.status
+        )));
+    }
+    let previous = existing.clone();
 
-    let updated = Experiment {
+    let mut updated = Experiment {
         id: existing.id,
         name: body.name,
         index_name: body.index_name,
@@ -339,8 +610,33 @@ pub async fn update_experiment(
         winsorization_cap: body.winsorization_cap.or(existing.winsorization_cap),
         conclusion: existing.conclusion,
         interleaving: body.interleaving.or(existing.interleaving),
+        aa_test: body.aa_test.or(existing.aa_test),
+        assignment_mode: body.assignment_mode.or(existing.assignment_mode),
+        assignment_unit: body.assignment_unit.or(existing.assignment_unit),
+        assignment_header: body.assignment_header.or(existing.assignment_header),
+        assignment_salt: body.assignment_salt.or(existing.assignment_salt),
+        auto_stop: body.auto_stop.or(existing.auto_stop),
+        guard_rail_alert_since: existing.guard_rail_alert_since,
+        stop_reason: existing.stop_reason,
+        scheduled_start_at: body.scheduled_start_at.or(existing.scheduled_start_at),
+        scheduled_end_at: body.scheduled_end_at.or(existing.scheduled_end_at),
+        ramp_stages: body.ramp_stages.or(existing.ramp_stages),
+        traffic_history: existing.traffic_history,
+        layer: body.layer.or(existing.layer),
+        layer_traffic: body.layer_traffic.or(existing.layer_traffic),
+        layer_buckets: None,
+        bayesian_loss_threshold: body
+            .bayesian_loss_threshold
+            .or(existing.bayesian_loss_threshold),
     };
 
+    if let Err(err) = updated.validate() {
+        return experiment_error_to_response(err);
+    }
+    if let Err(resp) = reconcile_variant_index(&state, &mut updated, Some(&previous)).await {
+        return resp;
+    }
+
     match store.update(updated) {
         Ok(experiment) => Json(experiment).into_response(),
         Err(err) => experiment_error_to_response(err),
@@ -356,8 +652,18 @@ pub async fn delete_experiment(
         None => return experiment_store_unavailable_response(),
     };
 
+    let provisioned = store
+        .get(&id)
+        .ok()
+        .and_then(|e| provisioning::provisioned_variant(&e).map(str::to_string));
+
     match store.delete(&id) {
-        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Ok(()) => {
+            if let Some(variant) = provisioned {
+                teardown_variant_index(&state, &variant).await;
+            }
+            StatusCode::NO_CONTENT.into_response()
+        }
         Err(err) => experiment_error_to_response(err),
     }
 }
@@ -372,7 +678,21 @@ pub async fn start_experiment(
     };
 
     match store.start(&id) {
-        Ok(experiment) => Json(experiment).into_response(),
+        Ok(experiment) => {
+            // Catch the variant up on writes made while the experiment was a draft.
+            if let Some(variant) = provisioning::provisioned_variant(&experiment) {
+                if let Err(e) = provisioning::sync_variant_index(
+                    &state.manager,
+                    &experiment.index_name,
+                    variant,
+                )
+                .await
+                {
+                    tracing::warn!("failed to sync variant index {}: {}", variant, e);
+                }
+            }
+            Json(experiment).into_response()
+        }
         Err(err) => experiment_error_to_response(err),
     }
 }
@@ -392,6 +712,295 @@ pub async fn stop_experiment(
     }
 }
 
+/// POST /2/abtests/:id/salt — give a draft experiment a fresh assignment
+/// salt, so its units are split independently of earlier experiments.
+pub async fn rotate_assignment_salt(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response {
+    let store = match get_experiment_store(&state) {
+        Some(store) => store,
+        None => return experiment_store_unavailable_response(),
+    };
+
+    match store.rotate_salt(&id, uuid::Uuid::new_v4().simple().to_string()) {
+        Ok(experiment) => Json(experiment).into_response(),
+        Err(err) => experiment_error_to_response(err),
+    }
+}
+
+/// POST /2/abtests/:id/ramp — move a running experiment to its next traffic
+/// stage, or to `trafficSplit` when given
+pub async fn ramp_experiment(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    body: Option<Json<RampRequest>>,
+) -> Response {
+    let store = match get_experiment_store(&state) {
+        Some(store) => store,
+        None => return experiment_store_unavailable_response(),
+    };
+
+    let experiment = match store.get(&id) {
+        Ok(experiment) => experiment,
+        Err(err) => return experiment_error_to_response(err),
+    };
+    if experiment.status != ExperimentStatus::Running {
+        return experiment_error_to_response(ExperimentError::InvalidStatus(format!(
+            "{:?}",
+            experiment.status
+        )));
+    }
+    let requested = body.and_then(|Json(b)| b.traffic_split);
+    let Some(traffic_split) = requested.or_else(|| experiment.next_ramp_stage()) else {
+        return experiment_error_to_response(ExperimentError::InvalidConfig(
+            "no further rampStages; pass trafficSplit".to_string(),
+        ));
+    };
+
+    match store.ramp(&id, traffic_split) {
+        Ok(experiment) => Json(experiment).into_response(),
+        Err(err) => experiment_error_to_response(err),
+    }
+}
+
+/// GET /2/holdouts — holdout percentage per index
+pub async fn list_holdouts(State(state): State<Arc<AppState>>) -> Response {
+    let store = match get_experiment_store(&state) {
+        Some(store) => store,
+        None => return experiment_store_unavailable_response(),
+    };
+    Json(serde_json::json!({ "holdouts": store.list_holdouts() })).into_response()
+}
+
+/// PUT /2/holdouts/:indexName — reserve a share of the index's users that
+/// never enters an experiment (`0` removes the holdout)
+pub async fn set_holdout(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+    Json(body): Json<HoldoutRequest>,
+) -> Response {
+    let store = match get_experiment_store(&state) {
+        Some(store) => store,
+        None => return experiment_store_unavailable_response(),
+    };
+    match store.set_holdout_percent(&index_name, body.percentage) {
+        Ok(()) => Json(serde_json::json!({
+            "indexName": index_name,
+            "percentage": store.holdout_percent(&index_name),
+        }))
+        .into_response(),
+        Err(err) => experiment_error_to_response(err),
+    }
+}
+
+fn shadow_json(
+    store: &ExperimentStore,
+    index_name: &str,
+    config: &ShadowConfig,
+) -> serde_json::Value {
+    let mut body = serde_json::to_value(config).unwrap_or_default();
+    body["indexName"] = serde_json::json!(index_name);
+    body["stats"] = serde_json::to_value(store.shadow_report(index_name)).unwrap_or_default();
+    body
+}
+
+fn shadow_not_found(index_name: &str) -> Response {
+    experiment_error_to_response(ExperimentError::NotFound(format!(
+        "no shadow evaluation for index '{}'",
+        index_name
+    )))
+}
+
+/// GET /2/shadows — shadow evaluations and their comparison totals
+pub async fn list_shadows(State(state): State<Arc<AppState>>) -> Response {
+    let store = match get_experiment_store(&state) {
+        Some(store) => store,
+        None => return experiment_store_unavailable_response(),
+    };
+    let shadows: Vec<serde_json::Value> = store
+        .list_shadows()
+        .iter()
+        .map(|(index_name, config)| shadow_json(store, index_name, config))
+        .collect();
+    Json(serde_json::json!({ "shadows": shadows })).into_response()
+}
+
+/// GET /2/shadows/:indexName
+pub async fn get_shadow(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> Response {
+    let store = match get_experiment_store(&state) {
+        Some(store) => store,
+        None => return experiment_store_unavailable_response(),
+    };
+    match store.shadow_for(&index_name) {
+        Some(config) => Json(shadow_json(store, &index_name, &config)).into_response(),
+        None => shadow_not_found(&index_name),
+    }
+}
+
+/// PUT /2/shadows/:indexName — re-run a share of the index's queries against
+/// a candidate index and/or query overrides, and compare the results
+pub async fn set_shadow(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+    Json(body): Json<ShadowConfig>,
+) -> Response {
+    let store = match get_experiment_store(&state) {
+        Some(store) => store,
+        None => return experiment_store_unavailable_response(),
+    };
+    if let Some(candidate) = body.candidate_index.as_deref() {
+        if !state.manager.base_path.join(candidate).exists() {
+            return experiment_error_to_response(ExperimentError::InvalidConfig(format!(
+                "candidate index '{}' does not exist",
+                candidate
+            )));
+        }
+    }
+    match store.set_shadow(&index_name, body) {
+        Ok(config) => Json(shadow_json(store, &index_name, &config)).into_response(),
+        Err(err) => experiment_error_to_response(err),
+    }
+}
+
+/// DELETE /2/shadows/:indexName
+pub async fn delete_shadow(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> Response {
+    let store = match get_experiment_store(&state) {
+        Some(store) => store,
+        None => return experiment_store_unavailable_response(),
+    };
+    match store.remove_shadow(&index_name) {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => shadow_not_found(&index_name),
+        Err(err) => experiment_error_to_response(err),
+    }
+}
+
+/// Turn an exposure into a search analytics row attributed to the
+/// experiment, so it flows through the same results pipeline as searches.
+fn build_exposure_event(
+    experiment: &Experiment,
+    exposure: Exposure,
+    now_ms: i64,
+) -> Result<flapjack::analytics::schema::SearchEvent, ExperimentError> {
+    let Some(assignment) = assignment::external_assignment(
+        &exposure.variant_id,
+        Some(exposure.user_token.as_str()).filter(|t| !t.is_empty()),
+        None,
+    )
+    .filter(|a| a.method == AssignmentMethod::External) else {
+        return Err(ExperimentError::InvalidConfig(format!(
+            "each exposure needs a userToken and a variantID of 'control' or 'variant' (got '{}')",
+            exposure.variant_id
+        )));
+    };
+    let arm = if assignment.arm == "variant" {
+        &experiment.variant
+    } else {
+        &experiment.control
+    };
+    let nb_hits = exposure.nb_hits.unwrap_or(1);
+    Ok(flapjack::analytics::schema::SearchEvent {
+        timestamp_ms: exposure.timestamp.unwrap_or(now_ms),
+        query: String::new(),
+        query_id: Some(
+            exposure
+                .query_id
+                .unwrap_or_else(|| hex::encode(uuid::Uuid::new_v4().as_bytes())),
+        ),
+        index_name: arm
+            .index_name
+            .clone()
+            .unwrap_or_else(|| experiment.index_name.clone()),
+        nb_hits,
+        processing_time_ms: 0,
+        user_token: Some(exposure.user_token),
+        user_ip: None,
+        filters: None,
+        facets: None,
+        analytics_tags: Some(EXPOSURE_ANALYTICS_TAG.to_string()),
+        page: 0,
+        hits_per_page: nb_hits,
+        has_results: nb_hits > 0,
+        country: None,
+        region: None,
+        experiment_id: Some(experiment.id.clone()),
+        variant_id: Some(assignment.arm.to_string()),
+        assignment_method: Some("external".to_string()),
+        applied_rules: Vec::new(),
+        synonyms_triggered: Vec::new(),
+        synonyms_matched: Vec::new(),
+        query_language: None,
+    })
+}
+
+/// POST /2/abtests/:id/exposures — log arms shown on non-search surfaces
+/// (app screens, emails, recommendations) for a running experiment.
+pub async fn log_exposures(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(body): Json<ExposureRequest>,
+) -> Response {
+    let store = match get_experiment_store(&state) {
+        Some(store) => store,
+        None => return experiment_store_unavailable_response(),
+    };
+    let experiment = match store.get(&id) {
+        Ok(exp) => exp,
+        Err(err) => return experiment_error_to_response(err),
+    };
+    if experiment.status != ExperimentStatus::Running {
+        return experiment_error_to_response(ExperimentError::InvalidStatus(format!(
+            "{:?}",
+            experiment.status
+        )));
+    }
+    if experiment.interleaving == Some(true) {
+        return experiment_error_to_response(ExperimentError::InvalidConfig(
+            "exposures cannot be logged for interleaving experiments".to_string(),
+        ));
+    }
+    if body.exposures.is_empty() || body.exposures.len() > MAX_EXPOSURES_PER_REQUEST {
+        return experiment_error_to_response(ExperimentError::InvalidConfig(format!(
+            "exposures must contain between 1 and {} entries",
+            MAX_EXPOSURES_PER_REQUEST
+        )));
+    }
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let mut events = Vec::with_capacity(body.exposures.len());
+    for exposure in body.exposures {
+        match build_exposure_event(&experiment, exposure, now_ms) {
+            Ok(event) => events.push(event),
+            Err(err) => return experiment_error_to_response(err),
+        }
+    }
+
+    let Some(collector) = flapjack::analytics::get_global_collector() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({"message": "analytics collector unavailable"})),
+        )
+            .into_response();
+    };
+    let query_ids: Vec<Option<String>> = events.iter().map(|e| e.query_id.clone()).collect();
+    for event in events {
+        collector.record_search(event);
+    }
+    Json(serde_json::json!({
+        "abTestID": experiment.id,
+        "recorded": query_ids.len(),
+        "queryIDs": query_ids,
+    }))
+    .into_response()
+}
+
 pub async fn conclude_experiment(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
@@ -432,6 +1041,10 @@ pub async fn conclude_experiment(
                     // with a warning header so the caller knows promotion was partial.
                 }
             }
+            // Promotion reads the variant settings, so tear down afterwards.
+            if let Some(variant) = provisioning::provisioned_variant(&experiment) {
+                teardown_variant_index(&state, variant).await;
+            }
             Json(experiment).into_response()
         }
         Err(err) => experiment_error_to_response(err),
@@ -441,8 +1054,8 @@ pub async fn conclude_experiment(
 /// Applies the winning variant's settings to the main index.
 ///
 /// - Mode B: copies settings.json from variant index to main index
-/// - Mode A: applies promotable query overrides (custom_ranking, remove_words_if_no_results)
-///   to the main index settings. Query-time-only fields (typo_tolerance, enable_synonyms, etc.)
+/// - Mode A: applies promotable query overrides (custom_ranking, remove_words_if_no_results,
+///   enable_re_ranking) to the main index settings. Query-time-only fields (typo_tolerance, enable_synonyms, etc.)
 ///   have no index-level equivalent and are logged as skipped.
 fn promote_variant_settings(state: &AppState, experiment: &Experiment) -> Result<(), String> {
     use flapjack::index::settings::IndexSettings;
@@ -491,6 +1104,9 @@ fn promote_variant_settings(state: &AppState, experiment: &Experiment) -> Result
         if let Some(ref rw) = overrides.remove_words_if_no_results {
             settings.remove_words_if_no_results = rw.clone();
         }
+        if let Some(rr) = overrides.enable_re_ranking {
+            settings.enable_re_ranking = rr;
+        }
 
         // Log query-time-only fields that cannot be promoted to index settings
         let query_only_fields: Vec<&str> = [
@@ -540,6 +1156,12 @@ pub async fn get_experiment_results(
         Err(err) => return experiment_error_to_response(err),
     };
 
+    Json(compute_results(&state, &experiment).await).into_response()
+}
+
+/// Fetch metrics for `experiment` and compute its full results report.
+/// Shared by the `/results` endpoint and the auto-stop checker.
+pub(crate) async fn compute_results(state: &AppState, experiment: &Experiment) -> ResultsResponse {
     // Get analytics data dir (needed for metrics queries)
     let analytics_data_dir = state
         .analytics_engine
@@ -554,16 +1176,43 @@ pub async fn get_experiment_results(
         }
     }
 
+    // Once ramped, earlier stages ran at a different split: pooling them
+    // would skew the comparison and trip the SRM check, so the headline
+    // numbers come from the current stage and each stage is reported apart.
+    let windows = if experiment.traffic_history.len() > 1 {
+        experiment.stage_windows()
+    } else {
+        Vec::new()
+    };
+
     // Fetch metrics from analytics parquet files
+    let mut stage_metrics = Vec::new();
     let experiment_metrics = if let Some(ref data_dir) = analytics_data_dir {
-        match metrics::get_experiment_metrics(
-            &experiment.id,
-            &index_names,
-            data_dir,
-            experiment.winsorization_cap,
-        )
-        .await
-        {
+        let fetched = if windows.is_empty() {
+            metrics::get_experiment_metrics(
+                &experiment.id,
+                &index_names,
+                data_dir,
+                experiment.winsorization_cap,
+            )
+            .await
+        } else {
+            metrics::get_experiment_window_metrics(
+                &experiment.id,
+                &index_names,
+                data_dir,
+                experiment.winsorization_cap,
+                &windows,
+            )
+            .await
+            .map(|mut stages| {
+                let current = stages.pop();
+                stage_metrics = stages;
+                current
+            })
+            .and_then(|current| current.ok_or_else(|| "no traffic stages".to_string()))
+        };
+        match fetched {
             Ok(m) => Some(m),
             Err(e) => {
                 tracing::warn!("Failed to fetch experiment metrics: {}", e);
@@ -616,16 +1265,254 @@ pub async fn get_experiment_results(
     };
 
     // Compute gate, stats, and build response
-    let response = build_results_response(
-        &experiment,
+    let mut results = build_results_response(
+        experiment,
         experiment_metrics.as_ref(),
         covariates.as_ref(),
         interleaving_metrics.as_ref(),
     );
-    Json(response).into_response()
+
+    if let Some(current) = &experiment_metrics {
+        if !windows.is_empty() {
+            let all: Vec<&metrics::ExperimentMetrics> =
+                stage_metrics.iter().chain(Some(current)).collect();
+            results.stages = build_stage_responses(experiment, &windows, &all);
+        }
+    }
+
+    if experiment.is_aa_test() {
+        let days = match analytics_data_dir {
+            Some(ref data_dir) => metrics::get_experiment_daily_metrics(
+                &experiment.id,
+                &index_names,
+                data_dir,
+                experiment.winsorization_cap,
+            )
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to fetch A/A test daily metrics: {}", e);
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+        results.aa_test = Some(build_aa_test_response(experiment, &days));
+    }
+    results
+}
+
+fn build_stage_responses(
+    experiment: &Experiment,
+    windows: &[(i64, Option<i64>)],
+    stages: &[&metrics::ExperimentMetrics],
+) -> Vec<StageResponse> {
+    let rfc3339 = |ms: i64| {
+        chrono::DateTime::from_timestamp_millis(ms)
+            .map(|dt| dt.to_rfc3339())
+            .unwrap_or_default()
+    };
+    experiment
+        .traffic_history
+        .iter()
+        .zip(windows)
+        .zip(stages)
+        .map(|((stage, &(start, end)), m)| StageResponse {
+            traffic_split: stage.traffic_split,
+            started_at: rfc3339(start),
+            ended_at: end.map(rfc3339),
+            control: arm_to_response(&m.control),
+            variant: arm_to_response(&m.variant),
+            sample_ratio_mismatch: stats::check_sample_ratio_mismatch(
+                m.control.searches,
+                m.variant.searches,
+                stage.traffic_split,
+            ),
+        })
+        .collect()
+}
+
+/// Primary-metric test of one pair of arms, without CUPED.
+fn primary_metric_stat(
+    experiment: &Experiment,
+    control: &metrics::ArmMetrics,
+    variant: &metrics::ArmMetrics,
+) -> stats::StatResult {
+    match experiment.primary_metric {
+        PrimaryMetric::RevenuePerSearch => {
+            stats::welch_t_test(&control.per_user_revenues, &variant.per_user_revenues)
+        }
+        _ => stats::delta_method_z_test(
+            arm_delta_samples(control, &experiment.primary_metric),
+            arm_delta_samples(variant, &experiment.primary_metric),
+        ),
+    }
+}
+
+/// Test every day with enough users in both arms on its own and count the
+/// days an A/A experiment looked significant.
+fn build_aa_test_response(
+    experiment: &Experiment,
+    days: &[metrics::DailyMetrics],
+) -> AaTestResponse {
+    let mut tested = 0;
+    let mut false_positives = 0;
+    let days: Vec<AaTestDayResponse> = days
+        .iter()
+        .filter(|day| {
+            day.control.users >= AA_TEST_MIN_DAILY_USERS
+                && day.variant.users >= AA_TEST_MIN_DAILY_USERS
+        })
+        .map(|day| {
+            let stat = primary_metric_stat(experiment, &day.control, &day.variant);
+            let significant = stat.p_value < AA_TEST_ALPHA;
+            tested += 1;
+            false_positives += u64::from(significant);
+            AaTestDayResponse {
+                date: day.date.clone(),
+                p_value: stat.p_value,
+                significant,
+                false_positive_rate: false_positives as f64 / tested as f64,
+            }
+        })
+        .collect();
+    let p_value = stats::binomial_upper_tail(false_positives, tested, AA_TEST_ALPHA);
+    AaTestResponse {
+        days_tested: tested,
+        false_positives,
+        false_positive_rate: (tested > 0).then(|| false_positives as f64 / tested as f64),
+        expected_rate: AA_TEST_ALPHA,
+        p_value,
+        pipeline_suspect: p_value < AA_TEST_SUSPECT_P_VALUE,
+        days,
+    }
 }
 
 /// Compute the primary metric value for an arm.
+fn build_timeseries_response(
+    experiment: &Experiment,
+    days: &[metrics::DailyMetrics],
+) -> TimeseriesResponse {
+    let days = days
+        .iter()
+        .map(|day| {
+            let control = arm_primary_metric(&day.control, &experiment.primary_metric);
+            let variant = arm_primary_metric(&day.variant, &experiment.primary_metric);
+            TimeseriesDayResponse {
+                date: day.date.clone(),
+                control: arm_to_response(&day.control),
+                variant: arm_to_response(&day.variant),
+                lift: (control != 0.0).then(|| (variant - control) / control),
+            }
+        })
+        .collect();
+    TimeseriesResponse {
+        experiment_id: experiment.id.clone(),
+        primary_metric: experiment.primary_metric.clone(),
+        days,
+    }
+}
+
+/// One row per day and arm.
+fn timeseries_to_csv(timeseries: &TimeseriesResponse) -> String {
+    let mut out = String::from(
+        "date,arm,searches,users,clicks,conversions,revenue,ctr,conversionRate,\
+         revenuePerSearch,zeroResultRate,abandonmentRate,meanClickRank,lift\n",
+    );
+    for day in &timeseries.days {
+        for (arm, lift) in [(&day.control, None), (&day.variant, day.lift)] {
+            out.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+                day.date,
+                arm.name.replace(',', " "),
+                arm.searches,
+                arm.users,
+                arm.clicks,
+                arm.conversions,
+                arm.revenue,
+                arm.ctr,
+                arm.conversion_rate,
+                arm.revenue_per_search,
+                arm.zero_result_rate,
+                arm.abandonment_rate,
+                arm.mean_click_rank,
+                lift.map(|l: f64| l.to_string()).unwrap_or_default(),
+            ));
+        }
+    }
+    out
+}
+
+/// GET /2/abtests/:id/results/timeseries — per-day arm metrics, as JSON or
+/// (with `?format=csv`) as a CSV download.
+pub async fn get_experiment_timeseries(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<TimeseriesQuery>,
+) -> Response {
+    let store = match get_experiment_store(&state) {
+        Some(store) => store,
+        None => return experiment_store_unavailable_response(),
+    };
+    let experiment = match store.get(&id) {
+        Ok(exp) => exp,
+        Err(err) => return experiment_error_to_response(err),
+    };
+    let csv = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("csv") => true,
+        Some(other) => {
+            return experiment_error_to_response(ExperimentError::InvalidConfig(format!(
+                "format must be 'json' or 'csv', got '{other}'"
+            )))
+        }
+    };
+
+    let mut index_names = vec![experiment.index_name.as_str()];
+    if let Some(ref variant_index) = experiment.variant.index_name {
+        if variant_index != &experiment.index_name {
+            index_names.push(variant_index.as_str());
+        }
+    }
+    let days = match state.analytics_engine.as_ref() {
+        Some(engine) => match metrics::get_experiment_daily_metrics(
+            &experiment.id,
+            &index_names,
+            &engine.config().data_dir,
+            experiment.winsorization_cap,
+        )
+        .await
+        {
+            Ok(days) => days,
+            Err(e) => {
+                tracing::warn!("Failed to fetch experiment time series: {}", e);
+                Vec::new()
+            }
+        },
+        None => Vec::new(),
+    };
+
+    let timeseries = build_timeseries_response(&experiment, &days);
+    if !csv {
+        return Json(timeseries).into_response();
+    }
+    (
+        [
+            (
+                axum::http::header::CONTENT_TYPE,
+                "text/csv; charset=utf-8".to_string(),
+            ),
+            (
+                axum::http::header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"abtest-{}-timeseries.csv\"",
+                    experiment.id
+                ),
+            ),
+        ],
+        timeseries_to_csv(&timeseries),
+    )
+        .into_response()
+}
+
 fn arm_primary_metric(arm: &metrics::ArmMetrics, metric: &PrimaryMetric) -> f64 {
     match metric {
         PrimaryMetric::Ctr => arm.ctr,
@@ -688,22 +1575,34 @@ fn rate_variance(samples: &[(f64, f64)]) -> f64 {
     rates.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (rates.len() - 1) as f64
 }
 
+/// Outcome of a successful CUPED adjustment.
+struct CupedOutcome {
+    control: Vec<(f64, f64)>,
+    variant: Vec<(f64, f64)>,
+    covariates: Vec<String>,
+    variance_reduction: f64,
+}
+
 /// Attempt CUPED variance reduction on per-user ratio metric samples.
 ///
-/// Returns `(cuped_applied, adjusted_control, adjusted_variant)`.
-/// Falls back to raw if covariates unavailable, insufficient matched users,
-/// or adjusted variance >= raw variance (Statsig safety check).
+/// Constant and collinear covariates are dropped before the regression.
+/// Returns `None` (use raw samples) if covariates are unavailable, matched
+/// users are insufficient, or adjusted variance >= raw variance (Statsig
+/// safety check).
 fn try_cuped_adjustment(
     raw_control: &[(f64, f64)],
     raw_variant: &[(f64, f64)],
     control_ids: &[String],
     variant_ids: &[String],
-    covariates: Option<&HashMap<String, f64>>,
-) -> (bool, Option<Vec<(f64, f64)>>, Option<Vec<(f64, f64)>>) {
-    let covs = match covariates {
-        Some(c) if !c.is_empty() => c,
-        _ => return (false, None, None),
-    };
+    covariates: Option<&metrics::PreExperimentCovariates>,
+) -> Option<CupedOutcome> {
+    let covariates = covariates.filter(|c| !c.is_empty())?;
+    let columns = stats::cuped_select_covariates(&covariates.values);
+    if columns.is_empty() {
+        return None;
+    }
+    let covariates = covariates.select(&columns);
+    let covs = &covariates.values;
 
     // Require CUPED coverage threshold in BOTH arms; asymmetrical adjustment biases comparisons.
     let matched_count = |samples: &[(f64, f64)], ids: &[String]| -> usize {
@@ -720,29 +1619,30 @@ fn try_cuped_adjustment(
     if control_matched < stats::CUPED_MIN_MATCHED_USERS
         || variant_matched < stats::CUPED_MIN_MATCHED_USERS
     {
-        return (false, None, None);
+        return None;
     }
 
-    let adj_control = stats::cuped_adjust(raw_control, control_ids, covs);
-    let adj_variant = stats::cuped_adjust(raw_variant, variant_ids, covs);
+    let adj_control = stats::cuped_adjust_multi(raw_control, control_ids, covs);
+    let adj_variant = stats::cuped_adjust_multi(raw_variant, variant_ids, covs);
 
     // Safety check: only use CUPED-adjusted values when adjusted variance is lower.
     // If CUPED increases variance (weak covariate correlation), fall back to raw.
     let raw_var = rate_variance(raw_control) + rate_variance(raw_variant);
     let adj_var = rate_variance(&adj_control) + rate_variance(&adj_variant);
 
-    if adj_var < raw_var {
-        (true, Some(adj_control), Some(adj_variant))
-    } else {
-        (false, None, None)
-    }
+    (adj_var < raw_var).then(|| CupedOutcome {
+        control: adj_control,
+        variant: adj_variant,
+        covariates: covariates.names,
+        variance_reduction: 1.0 - adj_var / raw_var,
+    })
 }
 
 /// Build the full results response from an experiment and its metrics.
 fn build_results_response(
     experiment: &Experiment,
     metrics: Option<&metrics::ExperimentMetrics>,
-    covariates: Option<&HashMap<String, f64>>,
+    covariates: Option<&metrics::PreExperimentCovariates>,
     interleaving_metrics: Option<&metrics::InterleavingMetrics>,
 ) -> ResultsResponse {
     let (control_arm, variant_arm) = match metrics {
@@ -851,13 +1751,42 @@ fn build_results_response(
             }
         };
         let prob = stats::beta_binomial_prob_b_greater_a(a_success, a_total, b_success, b_total);
-        let prob_variant_better = if metric_prefers_lower(&experiment.primary_metric) {
-            1.0 - prob
+        let lower_is_better = metric_prefers_lower(&experiment.primary_metric);
+        let prob_variant_better = if lower_is_better { 1.0 - prob } else { prob };
+
+        // Loss of shipping an arm = how far the other arm could be ahead of it;
+        // for lower-is-better metrics "ahead" means a lower rate.
+        let control_loss =
+            stats::beta_binomial_expected_loss(a_success, a_total, b_success, b_total);
+        let variant_loss =
+            stats::beta_binomial_expected_loss(b_success, b_total, a_success, a_total);
+        let (expected_loss_variant, expected_loss_control) = if lower_is_better {
+            (control_loss, variant_loss)
+        } else {
+            (variant_loss, control_loss)
+        };
+        let loss_threshold = experiment
+            .bayesian_loss_threshold
+            .unwrap_or(DEFAULT_BAYESIAN_LOSS_THRESHOLD);
+        let decision = if expected_loss_variant < loss_threshold {
+            Some("variant".to_string())
+        } else if expected_loss_control < loss_threshold {
+            Some("control".to_string())
         } else {
-            prob
+            None
         };
+        let (c_lo, c_hi) =
+            stats::beta_credible_interval(a_success, a_total, CREDIBLE_INTERVAL_LEVEL);
+        let (v_lo, v_hi) =
+            stats::beta_credible_interval(b_success, b_total, CREDIBLE_INTERVAL_LEVEL);
         BayesianResponse {
             prob_variant_better,
+            expected_loss_variant,
+            expected_loss_control,
+            control_credible_interval: [c_lo, c_hi],
+            variant_credible_interval: [v_lo, v_hi],
+            loss_threshold,
+            decision,
         }
     });
 
@@ -874,11 +1803,11 @@ fn build_results_response(
     // The minimum_days gate is a soft override — significance is available once
     // the required sample size is met, but the UI warns about novelty effects
     // if minimum_days hasn't elapsed yet.
-    let (significance, recommendation, cuped_applied) = if gate.minimum_n_reached {
+    let (significance, recommendation, cuped) = if gate.minimum_n_reached {
         if let Some(m) = metrics {
             // Try CUPED adjustment for ratio metrics (not revenue, which uses Welch t-test)
-            let (cuped_applied, adj_ctrl, adj_var) = match experiment.primary_metric {
-                PrimaryMetric::RevenuePerSearch => (false, None, None),
+            let cuped = match experiment.primary_metric {
+                PrimaryMetric::RevenuePerSearch => None,
                 _ => try_cuped_adjustment(
                     arm_delta_samples(&m.control, &experiment.primary_metric),
                     arm_delta_samples(&m.variant, &experiment.primary_metric),
@@ -893,12 +1822,14 @@ fn build_results_response(
                     stats::welch_t_test(&m.control.per_user_revenues, &m.variant.per_user_revenues)
                 }
                 _ => {
-                    let ctrl_samples = adj_ctrl.as_deref().unwrap_or_else(|| {
-                        arm_delta_samples(&m.control, &experiment.primary_metric)
-                    });
-                    let var_samples = adj_var.as_deref().unwrap_or_else(|| {
-                        arm_delta_samples(&m.variant, &experiment.primary_metric)
-                    });
+                    let ctrl_samples = cuped.as_ref().map_or_else(
+                        || arm_delta_samples(&m.control, &experiment.primary_metric),
+                        |c| c.control.as_slice(),
+                    );
+                    let var_samples = cuped.as_ref().map_or_else(
+                        || arm_delta_samples(&m.variant, &experiment.primary_metric),
+                        |c| c.variant.as_slice(),
+                    );
                     stats::delta_method_z_test(ctrl_samples, var_samples)
                 }
             };
@@ -906,6 +1837,12 @@ fn build_results_response(
 
             let rec = if srm {
                 Some("Sample ratio mismatch detected — investigate assignment before declaring a winner.".to_string())
+            } else if experiment.is_aa_test() {
+                Some(if stat.significant {
+                    "A/A test shows a significant difference between identical arms — check assignment and metrics before trusting other experiments.".to_string()
+                } else {
+                    "A/A test shows no difference between identical arms.".to_string()
+                })
             } else if stat.significant {
                 stat.winner.as_ref().map(|w| {
                     format!(
@@ -931,10 +1868,10 @@ fn build_results_response(
                     winner: stat.winner,
                 }),
                 rec,
-                cuped_applied,
+                cuped,
             )
         } else {
-            (None, None, false)
+            (None, None, None)
         }
     } else {
         // Gate not ready: SRM warning as recommendation if detected, no significance yet.
@@ -943,7 +1880,7 @@ fn build_results_response(
         } else {
             None
         };
-        (None, rec, false)
+        (None, rec, None)
     };
 
     let start_date = experiment.started_at.map(|ms| {
@@ -1046,11 +1983,17 @@ fn build_results_response(
         bayesian,
         sample_ratio_mismatch: srm,
         guard_rail_alerts,
-        cuped_applied,
+        cuped_applied: cuped.is_some(),
+        cuped_covariates: cuped
+            .as_ref()
+            .map_or_else(Vec::new, |c| c.covariates.clone()),
+        cuped_variance_reduction: cuped.as_ref().map(|c| c.variance_reduction),
         outlier_users_excluded: metrics.map_or(0, |m| m.outlier_users_excluded),
         no_stable_id_queries: metrics.map_or(0, |m| m.no_stable_id_queries),
         recommendation,
         interleaving,
+        aa_test: None,
+        stages: Vec::new(),
     }
 }
 
@@ -1140,8 +2083,22 @@ mod tests {
             )
             .route("/2/abtests/:id/start", post(start_experiment))
             .route("/2/abtests/:id/stop", post(stop_experiment))
+            .route("/2/abtests/:id/salt", post(rotate_assignment_salt))
+            .route("/2/abtests/:id/ramp", post(ramp_experiment))
             .route("/2/abtests/:id/conclude", post(conclude_experiment))
+            .route("/2/abtests/:id/exposures", post(log_exposures))
+            .route("/2/holdouts", get(list_holdouts))
+            .route("/2/holdouts/:indexName", axum::routing::put(set_holdout))
+            .route("/2/shadows", get(list_shadows))
+            .route(
+                "/2/shadows/:indexName",
+                get(get_shadow).put(set_shadow).delete(delete_shadow),
+            )
             .route("/2/abtests/:id/results", get(get_experiment_results))
+            .route(
+                "/2/abtests/:id/results/timeseries",
+                get(get_experiment_timeseries),
+            )
             .with_state(state)
     }
 
@@ -1212,71 +2169,328 @@ mod tests {
             .unwrap()
     }
 
-    async fn body_json(resp: axum::http::Response<Body>) -> serde_json::Value {
-        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
-            .await
-            .unwrap();
-        serde_json::from_slice(&bytes).unwrap()
+    async fn body_json(resp: axum::http::Response<Body>) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    async fn create_experiment_and_get_id(app: &Router) -> String {
+        let resp =
+            send_json_request(app, Method::POST, "/2/abtests", create_experiment_body()).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let json = body_json(resp).await;
+        json["id"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn create_experiment_returns_201() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_experiments_state(&tmp);
+        let app = app_router(state);
+
+        let resp =
+            send_json_request(&app, Method::POST, "/2/abtests", create_experiment_body()).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let json = body_json(resp).await;
+        assert!(json["id"].as_str().is_some());
+        assert_eq!(json["status"], "draft");
+        assert_eq!(json["name"], "Ranking test");
+        assert_eq!(json["indexName"], "products");
+        assert_eq!(json["trafficSplit"], 0.5);
+        assert_eq!(json["primaryMetric"], "ctr");
+        assert!(json["endedAt"].is_null());
+        assert!(json["conclusion"].is_null());
+        assert_eq!(json["minimumDays"], 14);
+        assert_eq!(json["control"]["name"], "control");
+        assert_eq!(json["variant"]["name"], "variant");
+        assert!(json["createdAt"].as_i64().is_some());
+        assert_eq!(json["startedAt"], serde_json::Value::Null);
+        assert_eq!(json["endedAt"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn create_experiment_invalid_traffic_split_returns_400() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_experiments_state(&tmp);
+        let app = app_router(state);
+
+        let mut body = create_experiment_body();
+        body["trafficSplit"] = serde_json::json!(1.0);
+
+        let resp = send_json_request(&app, Method::POST, "/2/abtests", body).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn create_experiment_missing_variant_config_returns_400() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_experiments_state(&tmp);
+        let app = app_router(state);
+
+        let mut body = create_experiment_body();
+        body["variant"] = serde_json::json!({ "name": "variant" });
+
+        let resp = send_json_request(&app, Method::POST, "/2/abtests", body).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn settings_delta_variant_is_provisioned_and_torn_down() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_experiments_state(&tmp);
+        state.manager.create_tenant("products").unwrap();
+        let app = app_router(state.clone());
+
+        let mut body = create_experiment_body();
+        body["variant"] = serde_json::json!({
+            "name": "variant",
+            "settingsDelta": {"customRanking": ["desc(sales)"]}
+        });
+        let resp = send_json_request(&app, Method::POST, "/2/abtests", body).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let created = body_json(resp).await;
+        let id = created["id"].as_str().unwrap().to_string();
+        let variant_index = created["variant"]["indexName"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(variant_index.starts_with("products__exp_"));
+        assert_eq!(
+            state
+                .manager
+                .get_settings(&variant_index)
+                .unwrap()
+                .custom_ranking,
+            Some(vec!["desc(sales)".to_string()])
+        );
+
+        let resp = send_empty_request(&app, Method::POST, &format!("/2/abtests/{id}/start")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = send_json_request(
+            &app,
+            Method::POST,
+            &format!("/2/abtests/{id}/conclude"),
+            conclude_experiment_body(),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(!tmp.path().join(&variant_index).exists());
+    }
+
+    #[tokio::test]
+    async fn settings_delta_with_index_name_returns_400() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_experiments_state(&tmp);
+        state.manager.create_tenant("products").unwrap();
+        let app = app_router(state);
+
+        let mut body = create_experiment_body();
+        body["variant"] = serde_json::json!({
+            "name": "variant",
+            "indexName": "products",
+            "settingsDelta": {"customRanking": ["desc(sales)"]}
+        });
+        let resp = send_json_request(&app, Method::POST, "/2/abtests", body).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert!(tmp.path().join("products").exists());
+    }
+
+    #[tokio::test]
+    async fn create_experiment_accepts_external_assignment_mode() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_experiments_state(&tmp);
+        let app = app_router(state);
+
+        let mut body = create_experiment_body();
+        body["assignmentMode"] = serde_json::json!("external");
+        let resp = send_json_request(&app, Method::POST, "/2/abtests", body).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(body_json(resp).await["assignmentMode"], "external");
+    }
+
+    #[tokio::test]
+    async fn holdouts_can_be_set_and_listed() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_experiments_state(&tmp);
+        let app = app_router(state);
+
+        let resp = send_json_request(
+            &app,
+            Method::PUT,
+            "/2/holdouts/products",
+            serde_json::json!({"percentage": 5.0}),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(body_json(resp).await["percentage"], 5.0);
+
+        let resp = send_json_request(
+            &app,
+            Method::PUT,
+            "/2/holdouts/products",
+            serde_json::json!({"percentage": 120.0}),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = send_empty_request(&app, Method::GET, "/2/holdouts").await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(body_json(resp).await["holdouts"]["products"], 5.0);
     }
 
-    async fn create_experiment_and_get_id(app: &Router) -> String {
-        let resp =
-            send_json_request(app, Method::POST, "/2/abtests", create_experiment_body()).await;
-        assert_eq!(resp.status(), StatusCode::CREATED);
-        let json = body_json(resp).await;
-        json["id"].as_str().unwrap().to_string()
+    #[tokio::test]
+    async fn shadows_can_be_set_read_and_removed() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_experiments_state(&tmp);
+        state.manager.create_tenant("products_v2").unwrap();
+        let app = app_router(state);
+
+        let resp = send_json_request(
+            &app,
+            Method::PUT,
+            "/2/shadows/products",
+            serde_json::json!({"percentage": 10.0, "candidateIndex": "missing"}),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = send_json_request(
+            &app,
+            Method::PUT,
+            "/2/shadows/products",
+            serde_json::json!({"percentage": 10.0, "candidateIndex": "products_v2"}),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = body_json(resp).await;
+        assert_eq!(body["candidateIndex"], "products_v2");
+        assert_eq!(body["stats"]["queries"], 0);
+
+        let resp = send_empty_request(&app, Method::GET, "/2/shadows").await;
+        assert_eq!(body_json(resp).await["shadows"][0]["indexName"], "products");
+
+        let resp = send_empty_request(&app, Method::DELETE, "/2/shadows/products").await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let resp = send_empty_request(&app, Method::GET, "/2/shadows/products").await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
     }
 
     #[tokio::test]
-    async fn create_experiment_returns_201() {
+    async fn layered_experiments_can_run_concurrently_on_one_index() {
         let tmp = TempDir::new().unwrap();
         let state = make_experiments_state(&tmp);
         let app = app_router(state);
 
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let mut body = create_experiment_body();
+            body["layer"] = serde_json::json!("ranking");
+            body["layerTraffic"] = serde_json::json!(0.5);
+            let resp = send_json_request(&app, Method::POST, "/2/abtests", body).await;
+            assert_eq!(resp.status(), StatusCode::CREATED);
+            ids.push(body_json(resp).await["id"].as_str().unwrap().to_string());
+        }
+
+        let mut buckets = Vec::new();
+        for id in &ids[..2] {
+            let resp =
+                send_empty_request(&app, Method::POST, &format!("/2/abtests/{id}/start")).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            buckets.push(body_json(resp).await["layerBuckets"].clone());
+        }
+        assert_ne!(buckets[0], buckets[1]);
+
+        // The layer is full.
         let resp =
-            send_json_request(&app, Method::POST, "/2/abtests", create_experiment_body()).await;
-        assert_eq!(resp.status(), StatusCode::CREATED);
-        let json = body_json(resp).await;
-        assert!(json["id"].as_str().is_some());
-        assert_eq!(json["status"], "draft");
-        assert_eq!(json["name"], "Ranking test");
-        assert_eq!(json["indexName"], "products");
-        assert_eq!(json["trafficSplit"], 0.5);
-        assert_eq!(json["primaryMetric"], "ctr");
-        assert!(json["endedAt"].is_null());
-        assert!(json["conclusion"].is_null());
-        assert_eq!(json["minimumDays"], 14);
-        assert_eq!(json["control"]["name"], "control");
-        assert_eq!(json["variant"]["name"], "variant");
-        assert!(json["createdAt"].as_i64().is_some());
-        assert_eq!(json["startedAt"], serde_json::Value::Null);
-        assert_eq!(json["endedAt"], serde_json::Value::Null);
+            send_empty_request(&app, Method::POST, &format!("/2/abtests/{}/start", ids[2])).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
     #[tokio::test]
-    async fn create_experiment_invalid_traffic_split_returns_400() {
+    async fn log_exposures_requires_running_experiment() {
         let tmp = TempDir::new().unwrap();
         let state = make_experiments_state(&tmp);
         let app = app_router(state);
+        let id = create_experiment_and_get_id(&app).await;
+        let exposures = serde_json::json!({
+            "exposures": [{"userToken": "u1", "variantID": "variant"}]
+        });
 
-        let mut body = create_experiment_body();
-        body["trafficSplit"] = serde_json::json!(1.0);
+        let resp = send_json_request(
+            &app,
+            Method::POST,
+            &format!("/2/abtests/{id}/exposures"),
+            exposures.clone(),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
 
-        let resp = send_json_request(&app, Method::POST, "/2/abtests", body).await;
-        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = send_json_request(
+            &app,
+            Method::POST,
+            "/2/abtests/missing/exposures",
+            exposures,
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
     }
 
     #[tokio::test]
-    async fn create_experiment_missing_variant_config_returns_400() {
+    async fn log_exposures_rejects_unknown_variant_and_empty_batch() {
         let tmp = TempDir::new().unwrap();
         let state = make_experiments_state(&tmp);
         let app = app_router(state);
+        let id = create_experiment_and_get_id(&app).await;
+        send_empty_request(&app, Method::POST, &format!("/2/abtests/{id}/start")).await;
+        let uri = format!("/2/abtests/{id}/exposures");
+
+        for body in [
+            serde_json::json!({"exposures": [{"userToken": "u1", "variantID": "treatment"}]}),
+            serde_json::json!({"exposures": [{"userToken": "", "variantID": "control"}]}),
+            serde_json::json!({"exposures": []}),
+        ] {
+            let resp = send_json_request(&app, Method::POST, &uri, body.clone()).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{}", body);
+        }
+    }
 
-        let mut body = create_experiment_body();
-        body["variant"] = serde_json::json!({ "name": "variant" });
+    #[test]
+    fn exposure_event_is_attributed_to_arm_index() {
+        let mut experiment: Experiment = serde_json::from_value(serde_json::json!({
+            "id": "exp-1",
+            "name": "app screen",
+            "indexName": "products",
+            "status": "running",
+            "trafficSplit": 0.5,
+            "control": {"name": "control"},
+            "variant": {"name": "variant", "indexName": "products_v2"},
+            "primaryMetric": "ctr",
+            "createdAt": 0,
+            "minimumDays": 14
+        }))
+        .unwrap();
+        let exposure = |variant: &str| Exposure {
+            user_token: "u1".to_string(),
+            variant_id: variant.to_string(),
+            query_id: Some("q1".to_string()),
+            timestamp: Some(42),
+            nb_hits: None,
+        };
 
-        let resp = send_json_request(&app, Method::POST, "/2/abtests", body).await;
-        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let event = build_exposure_event(&experiment, exposure("variant"), 1_000).unwrap();
+        assert_eq!(event.index_name, "products_v2");
+        assert_eq!(event.variant_id.as_deref(), Some("variant"));
+        assert_eq!(event.assignment_method.as_deref(), Some("external"));
+        assert_eq!(event.query_id.as_deref(), Some("q1"));
+        assert_eq!(event.timestamp_ms, 42);
+        assert!(event.has_results);
+
+        experiment.variant.index_name = None;
+        let event = build_exposure_event(&experiment, exposure("control"), 1_000).unwrap();
+        assert_eq!(event.index_name, "products");
+        assert_eq!(event.experiment_id.as_deref(), Some("exp-1"));
     }
 
     #[tokio::test]
@@ -1809,6 +3023,7 @@ mod tests {
             flush_interval_secs: 3600,
             flush_size: 100_000,
             retention_days: 90,
+            pii: Default::default(),
         };
         Arc::new(AppState {
             manager: IndexManager::new(tmp.path()),
@@ -1873,6 +3088,10 @@ mod tests {
                 experiment_id: Some(id.clone()),
                 variant_id: Some(variant.to_string()),
                 assignment_method: Some("user_token".to_string()),
+                applied_rules: Vec::new(),
+                synonyms_triggered: Vec::new(),
+                synonyms_matched: Vec::new(),
+                query_language: None,
             });
 
             // Give some clicks (6 for control arm qids 0-5, 8 for variant arm qids 10-17)
@@ -1920,6 +3139,201 @@ mod tests {
         assert!(json["significance"].is_null());
     }
 
+    #[tokio::test]
+    async fn timeseries_groups_seeded_searches_by_day() {
+        use flapjack::analytics::schema::SearchEvent;
+        use flapjack::analytics::writer;
+
+        let tmp = TempDir::new().unwrap();
+        let analytics_dir = tmp.path().join("analytics");
+        let state = make_experiments_state_with_analytics(&tmp, &analytics_dir);
+        let app = app_router(state.clone());
+        let id = create_experiment_and_get_id(&app).await;
+
+        // 2024-01-01T12:00:00Z and one day later.
+        let day0 = 1_704_110_400_000i64;
+        let search_events: Vec<SearchEvent> = (0..6i64)
+            .map(|i| SearchEvent {
+                timestamp_ms: day0 + (i / 4) * 86_400_000,
+                query: "test".to_string(),
+                query_id: Some(format!("qid_{}", i)),
+                index_name: "products".to_string(),
+                nb_hits: 5,
+                processing_time_ms: 3,
+                user_token: Some(format!("user_{}", i)),
+                user_ip: None,
+                filters: None,
+                facets: None,
+                analytics_tags: None,
+                page: 0,
+                hits_per_page: 20,
+                has_results: true,
+                country: None,
+                region: None,
+                experiment_id: Some(id.clone()),
+                variant_id: Some(if i % 2 == 0 { "control" } else { "variant" }.to_string()),
+                assignment_method: Some("user_token".to_string()),
+                applied_rules: Vec::new(),
+                synonyms_triggered: Vec::new(),
+                synonyms_matched: Vec::new(),
+                query_language: None,
+            })
+            .collect();
+        writer::flush_search_events(
+            &search_events,
+            &analytics_dir.join("products").join("searches"),
+        )
+        .unwrap();
+
+        let uri = format!("/2/abtests/{id}/results/timeseries");
+        let resp = send_empty_request(&app, Method::GET, &uri).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let json = body_json(resp).await;
+        assert_eq!(json["experimentID"], id);
+        let days = json["days"].as_array().unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0]["date"], "2024-01-01");
+        assert_eq!(days[0]["control"]["searches"], 2);
+        assert_eq!(days[0]["variant"]["searches"], 2);
+        assert_eq!(days[1]["date"], "2024-01-02");
+        assert_eq!(days[1]["control"]["searches"], 1);
+
+        let resp = send_empty_request(&app, Method::GET, &format!("{uri}?format=csv")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/csv"));
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let csv = String::from_utf8(bytes.to_vec()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 5, "header + 2 days x 2 arms:\n{csv}");
+        assert!(lines[0].starts_with("date,arm,searches,"));
+        assert!(lines[1].starts_with("2024-01-01,control,2,"));
+        assert!(lines[4].starts_with("2024-01-02,variant,1,"));
+
+        let resp = send_empty_request(&app, Method::GET, &format!("{uri}?format=xml")).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn timeseries_lift_is_relative_to_control() {
+        let experiment: Experiment = serde_json::from_value(serde_json::json!({
+            "id": "exp-1",
+            "name": "lift",
+            "indexName": "products",
+            "status": "running",
+            "trafficSplit": 0.5,
+            "control": {"name": "control"},
+            "variant": {"name": "variant", "queryOverrides": {}},
+            "primaryMetric": "ctr",
+            "createdAt": 0,
+            "minimumDays": 14
+        }))
+        .unwrap();
+        let arm = |name: &str, ctr: f64| {
+            let mut m = metrics::ArmMetrics::empty(name);
+            m.ctr = ctr;
+            m
+        };
+        let days = vec![
+            metrics::DailyMetrics {
+                date: "2024-01-01".to_string(),
+                control: arm("control", 0.10),
+                variant: arm("variant", 0.12),
+            },
+            metrics::DailyMetrics {
+                date: "2024-01-02".to_string(),
+                control: arm("control", 0.0),
+                variant: arm("variant", 0.05),
+            },
+        ];
+
+        let ts = build_timeseries_response(&experiment, &days);
+        assert!((ts.days[0].lift.unwrap() - 0.2).abs() < 1e-9);
+        assert!(ts.days[1].lift.is_none());
+
+        // Lift is only reported on the variant row.
+        let csv = timeseries_to_csv(&ts);
+        let lift_column = |line: usize| {
+            csv.lines()
+                .nth(line)
+                .unwrap()
+                .rsplit(',')
+                .next()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(lift_column(1), "");
+        assert!((lift_column(2).parse::<f64>().unwrap() - 0.2).abs() < 1e-9);
+        assert_eq!(lift_column(4), "");
+    }
+
+    #[test]
+    fn aa_test_counts_significant_days_as_false_positives() {
+        let experiment: Experiment = serde_json::from_value(serde_json::json!({
+            "id": "exp-aa",
+            "name": "pipeline check",
+            "indexName": "products",
+            "status": "running",
+            "trafficSplit": 0.5,
+            "control": {"name": "control"},
+            "variant": {"name": "variant"},
+            "primaryMetric": "ctr",
+            "createdAt": 0,
+            "minimumDays": 14,
+            "aaTest": true
+        }))
+        .unwrap();
+        assert!(experiment.validate().is_ok());
+        let arm = |name: &str, users: u64, clicks: [f64; 2]| {
+            let mut m = metrics::ArmMetrics::empty(name);
+            m.users = users;
+            m.per_user_ctrs = (0..users).map(|i| (clicks[i as usize % 2], 10.0)).collect();
+            m
+        };
+        let day = |date: &str, control, variant| metrics::DailyMetrics {
+            date: date.to_string(),
+            control,
+            variant,
+        };
+        let days = vec![
+            day(
+                "2024-01-01",
+                arm("control", 20, [1.0, 2.0]),
+                arm("variant", 20, [2.0, 1.0]),
+            ),
+            day(
+                "2024-01-02",
+                arm("control", 20, [1.0, 2.0]),
+                arm("variant", 20, [8.0, 9.0]),
+            ),
+            // Too few users to test.
+            day(
+                "2024-01-03",
+                arm("control", 5, [1.0, 2.0]),
+                arm("variant", 5, [8.0, 9.0]),
+            ),
+        ];
+
+        let aa = build_aa_test_response(&experiment, &days);
+        assert_eq!(aa.days_tested, 2);
+        assert_eq!(aa.false_positives, 1);
+        assert_eq!(aa.false_positive_rate, Some(0.5));
+        let rates: Vec<f64> = aa.days.iter().map(|d| d.false_positive_rate).collect();
+        assert_eq!(rates, vec![0.0, 0.5]);
+        assert!(!aa.days[0].significant && aa.days[1].significant);
+        assert!((aa.p_value - (1.0 - 0.95f64.powi(2))).abs() < 1e-9);
+        assert!(!aa.pipeline_suspect);
+
+        let empty = build_aa_test_response(&experiment, &[]);
+        assert_eq!(empty.days_tested, 0);
+        assert_eq!(empty.false_positive_rate, None);
+        assert!(!empty.pipeline_suspect);
+    }
+
     #[tokio::test]
     async fn experiment_store_unavailable_returns_503() {
         let tmp = TempDir::new().unwrap();
@@ -2066,6 +3480,66 @@ mod tests {
         assert_eq!(json["endedAt"], serde_json::Value::Null);
     }
 
+    #[tokio::test]
+    async fn rotate_salt_changes_draft_salt_only() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_experiments_state(&tmp);
+        let app = app_router(state);
+
+        let id = create_experiment_and_get_id(&app).await;
+        let uri = format!("/2/abtests/{id}/salt");
+        let first = body_json(send_empty_request(&app, Method::POST, &uri).await).await;
+        let second = body_json(send_empty_request(&app, Method::POST, &uri).await).await;
+        let salt = first["assignmentSalt"].as_str().unwrap();
+        assert!(!salt.is_empty());
+        assert_ne!(second["assignmentSalt"], first["assignmentSalt"]);
+
+        send_empty_request(&app, Method::POST, &format!("/2/abtests/{id}/start")).await;
+        let resp = send_empty_request(&app, Method::POST, &uri).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn ramp_advances_through_stages() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_experiments_state(&tmp);
+        let app = app_router(state);
+
+        let mut body = create_experiment_body();
+        body["rampStages"] = serde_json::json!([0.01, 0.05, 0.5]);
+        let resp = send_json_request(&app, Method::POST, "/2/abtests", body).await;
+        let id = body_json(resp).await["id"].as_str().unwrap().to_string();
+        let uri = format!("/2/abtests/{id}/ramp");
+        let resp = send_empty_request(&app, Method::POST, &uri).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        let started = body_json(
+            send_empty_request(&app, Method::POST, &format!("/2/abtests/{id}/start")).await,
+        )
+        .await;
+        assert_eq!(started["trafficSplit"], 0.01);
+
+        let ramped = body_json(send_empty_request(&app, Method::POST, &uri).await).await;
+        assert_eq!(ramped["trafficSplit"], 0.05);
+        let ramped = body_json(
+            send_json_request(
+                &app,
+                Method::POST,
+                &uri,
+                serde_json::json!({"trafficSplit": 0.5}),
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(ramped["trafficSplit"], 0.5);
+        let history = ramped["trafficHistory"].as_array().unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[1]["trafficSplit"], 0.05);
+
+        let resp = send_empty_request(&app, Method::POST, &uri).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
     #[tokio::test]
     async fn stop_experiment_sets_ended_at_timestamp() {
         let tmp = TempDir::new().unwrap();
@@ -2133,11 +3607,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: now - 1_000,
@@ -2147,6 +3623,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let metrics = metrics::ExperimentMetrics {
@@ -2224,11 +3716,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: now - 1_000,
@@ -2238,6 +3732,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         // Heavily skewed split: 4500 vs 5500 at 50/50 → SRM should fire
@@ -2326,11 +3836,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: started_at - 1_000,
@@ -2340,6 +3852,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         // High baseline CTR (0.5) keeps required_sample_size low (~13k per arm).
@@ -2440,11 +3968,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::ConversionRate,
             created_at: started_at - 1_000,
@@ -2454,6 +3984,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let users = 3000;
@@ -2531,6 +4077,93 @@ mod tests {
         );
     }
 
+    fn bayesian_test_experiment(metric: PrimaryMetric, threshold: Option<f64>) -> Experiment {
+        let mut experiment: Experiment = serde_json::from_value(serde_json::json!({
+            "id": "exp-bayes",
+            "name": "Bayesian decision",
+            "indexName": "products",
+            "status": "running",
+            "trafficSplit": 0.5,
+            "control": {"name": "control"},
+            "variant": {"name": "variant", "queryOverrides": {}},
+            "primaryMetric": "ctr",
+            "createdAt": 0,
+            "startedAt": 0,
+            "minimumDays": 14
+        }))
+        .unwrap();
+        experiment.primary_metric = metric;
+        experiment.bayesian_loss_threshold = threshold;
+        experiment
+    }
+
+    fn bayesian_test_metrics(
+        control: (u64, u64, u64),
+        variant: (u64, u64, u64),
+    ) -> metrics::ExperimentMetrics {
+        // (searches, clicks, zero-result searches)
+        let arm = |name: &str, (searches, clicks, zero): (u64, u64, u64)| {
+            let mut m = metrics::ArmMetrics::empty(name);
+            m.searches = searches;
+            m.clicks = clicks;
+            m.zero_result_searches = zero;
+            m
+        };
+        metrics::ExperimentMetrics {
+            control: arm("control", control),
+            variant: arm("variant", variant),
+            outlier_users_excluded: 0,
+            no_stable_id_queries: 0,
+            winsorization_cap_applied: None,
+        }
+    }
+
+    #[test]
+    fn build_results_response_bayesian_decision_and_intervals() {
+        let experiment = bayesian_test_experiment(PrimaryMetric::Ctr, None);
+        let metrics = bayesian_test_metrics((1000, 100, 0), (1000, 200, 0));
+
+        let bayesian = build_results_response(&experiment, Some(&metrics), None, None)
+            .bayesian
+            .expect("bayesian must be present");
+        assert!(bayesian.expected_loss_variant < 1e-6);
+        assert!((bayesian.expected_loss_control - 0.1).abs() < 0.005);
+        assert_eq!(bayesian.loss_threshold, DEFAULT_BAYESIAN_LOSS_THRESHOLD);
+        assert_eq!(bayesian.decision.as_deref(), Some("variant"));
+
+        let [lo, hi] = bayesian.control_credible_interval;
+        assert!(lo < 0.1 && 0.1 < hi && hi - lo < 0.05, "[{lo}, {hi}]");
+        let [lo, hi] = bayesian.variant_credible_interval;
+        assert!(lo < 0.2 && 0.2 < hi, "[{lo}, {hi}]");
+    }
+
+    #[test]
+    fn build_results_response_bayesian_waits_below_threshold() {
+        // Nearly identical arms: neither can be shipped with loss < 1e-6.
+        let experiment = bayesian_test_experiment(PrimaryMetric::Ctr, Some(1e-6));
+        let metrics = bayesian_test_metrics((1000, 100, 0), (1000, 102, 0));
+
+        let bayesian = build_results_response(&experiment, Some(&metrics), None, None)
+            .bayesian
+            .unwrap();
+        assert_eq!(bayesian.loss_threshold, 1e-6);
+        assert!(bayesian.decision.is_none());
+    }
+
+    #[test]
+    fn build_results_response_bayesian_loss_flips_for_lower_is_better() {
+        // Variant halves the zero-result rate, which is an improvement.
+        let experiment = bayesian_test_experiment(PrimaryMetric::ZeroResultRate, None);
+        let metrics = bayesian_test_metrics((1000, 0, 200), (1000, 0, 100));
+
+        let bayesian = build_results_response(&experiment, Some(&metrics), None, None)
+            .bayesian
+            .unwrap();
+        assert!(bayesian.expected_loss_variant < 1e-6);
+        assert!(bayesian.expected_loss_control > 0.05);
+        assert_eq!(bayesian.decision.as_deref(), Some("variant"));
+    }
+
     #[test]
     fn build_results_response_bayesian_uses_primary_metric_data() {
         // ConversionRate experiment where CTR favors variant but conversion rate favors control.
@@ -2548,11 +4181,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::ConversionRate,
             created_at: started_at - 1_000,
@@ -2562,6 +4197,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let n = 10_000_u64;
@@ -2642,11 +4293,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::ZeroResultRate,
             created_at: started_at - 1_000,
@@ -2656,6 +4309,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let n = 10_000_u64;
@@ -2733,11 +4402,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::ZeroResultRate,
             created_at: started_at - 1_000,
@@ -2747,6 +4418,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let users = 3000;
@@ -2842,11 +4529,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::AbandonmentRate,
             created_at: started_at - 1_000,
@@ -2856,6 +4545,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let users = 3000;
@@ -2952,11 +4657,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: started_at - 1_000,
@@ -2966,6 +4673,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let metrics = metrics::ExperimentMetrics {
@@ -3049,11 +4772,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: started_at - 1_000,
@@ -3063,6 +4788,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         // High baseline CTR (0.5) keeps required_sample_size low (~13k per arm).
@@ -3201,11 +4942,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: Some("products_v2".to_string()),
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: started_at - 1_000,
@@ -3215,6 +4958,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: Some(true),
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let interleaving_metrics = metrics::InterleavingMetrics {
@@ -3258,11 +5017,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: started_at - 1_000,
@@ -3272,6 +5033,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let interleaving_metrics = metrics::InterleavingMetrics {
@@ -3307,11 +5084,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: Some("products_control".to_string()),
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: None,
                 index_name: Some("products_variant".to_string()),
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: started_at - 1_000,
@@ -3321,6 +5100,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: Some(true),
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         // Balanced first-team distribution (0.50) → data_quality_ok = true
@@ -3398,11 +5193,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: started_at - 1_000,
@@ -3412,6 +5209,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let users = 200;
@@ -3448,8 +5261,12 @@ mod tests {
         };
 
         let raw_response = build_results_response(&experiment, Some(&metrics), None, None);
-        let cuped_response =
-            build_results_response(&experiment, Some(&metrics), Some(&covariates), None);
+        let cuped_response = build_results_response(
+            &experiment,
+            Some(&metrics),
+            Some(&metrics::PreExperimentCovariates::single("ctr", covariates)),
+            None,
+        );
 
         let raw_sig = raw_response
             .significance
@@ -3462,6 +5279,10 @@ mod tests {
             cuped_response.cuped_applied,
             "CUPED should be applied with >=100 matched users and a correlated covariate"
         );
+        assert_eq!(cuped_response.cuped_covariates, vec!["ctr".to_string()]);
+        assert!(cuped_response.cuped_variance_reduction.unwrap() > 0.0);
+        assert!(raw_response.cuped_covariates.is_empty());
+        assert!(raw_response.cuped_variance_reduction.is_none());
         assert!(
             cuped_sig.z_score.abs() > raw_sig.z_score.abs(),
             "CUPED should improve signal-to-noise when covariate is strongly correlated"
@@ -3472,6 +5293,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn build_results_response_cuped_uses_multiple_covariates() {
+        let experiment = bayesian_test_experiment(PrimaryMetric::Ctr, None);
+        let users = 200;
+
+        let mut control_ids = Vec::with_capacity(users);
+        let mut variant_ids = Vec::with_capacity(users);
+        let mut control_samples = Vec::with_capacity(users);
+        let mut variant_samples = Vec::with_capacity(users);
+        let mut values = std::collections::HashMap::new();
+        for i in 0..users {
+            let x = i as f64;
+            let pre_clicks = ((i * 7) % 11) as f64;
+            let residual = (i % 3) as f64;
+            let clicks = 40.0 + 0.1 * x + pre_clicks + residual;
+
+            let control_id = format!("c{i}");
+            let variant_id = format!("v{i}");
+            // Constant pre-period searches and zero revenue carry no signal.
+            values.insert(control_id.clone(), vec![x, 10.0, pre_clicks, 0.0]);
+            values.insert(variant_id.clone(), vec![x, 10.0, pre_clicks, 0.0]);
+            control_ids.push(control_id);
+            variant_ids.push(variant_id);
+            control_samples.push((clicks, 100.0));
+            variant_samples.push((clicks + 4.0, 100.0));
+        }
+        let metrics = metrics::ExperimentMetrics {
+            control: build_ctr_arm_metrics("control", control_samples, control_ids),
+            variant: build_ctr_arm_metrics("variant", variant_samples, variant_ids),
+            outlier_users_excluded: 0,
+            no_stable_id_queries: 0,
+            winsorization_cap_applied: None,
+        };
+        let all = metrics::PreExperimentCovariates {
+            names: ["ctr", "searches", "clicks", "revenue"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            values,
+        };
+
+        let multi = build_results_response(&experiment, Some(&metrics), Some(&all), None);
+        let single =
+            build_results_response(&experiment, Some(&metrics), Some(&all.select(&[0])), None);
+
+        assert!(multi.cuped_applied);
+        assert_eq!(
+            multi.cuped_covariates,
+            vec!["ctr".to_string(), "clicks".to_string()]
+        );
+        assert_eq!(single.cuped_covariates, vec!["ctr".to_string()]);
+        assert!(
+            multi.cuped_variance_reduction.unwrap() > single.cuped_variance_reduction.unwrap(),
+            "multi={:?} single={:?}",
+            multi.cuped_variance_reduction,
+            single.cuped_variance_reduction
+        );
+    }
+
     #[test]
     fn build_results_response_skips_cuped_when_insufficient_coverage() {
         let now = chrono::Utc::now().timestamp_millis();
@@ -3486,11 +5366,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: started_at - 1_000,
@@ -3500,6 +5382,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let users = 200;
@@ -3538,8 +5436,15 @@ mod tests {
         };
 
         let raw_response = build_results_response(&experiment, Some(&metrics), None, None);
-        let sparse_cov_response =
-            build_results_response(&experiment, Some(&metrics), Some(&sparse_covariates), None);
+        let sparse_cov_response = build_results_response(
+            &experiment,
+            Some(&metrics),
+            Some(&metrics::PreExperimentCovariates::single(
+                "ctr",
+                sparse_covariates,
+            )),
+            None,
+        );
 
         let raw_sig = raw_response
             .significance
@@ -3576,11 +5481,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: started_at - 1_000,
@@ -3590,6 +5497,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let users = 200;
@@ -3631,8 +5554,15 @@ mod tests {
         };
 
         let raw_response = build_results_response(&experiment, Some(&metrics), None, None);
-        let partial_cov_response =
-            build_results_response(&experiment, Some(&metrics), Some(&partial_covariates), None);
+        let partial_cov_response = build_results_response(
+            &experiment,
+            Some(&metrics),
+            Some(&metrics::PreExperimentCovariates::single(
+                "ctr",
+                partial_covariates,
+            )),
+            None,
+        );
 
         let raw_sig = raw_response
             .significance
@@ -3971,11 +5901,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: now - 1_000,
@@ -3985,6 +5917,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let metrics = metrics::ExperimentMetrics {
@@ -4065,11 +6013,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: now - 1_000,
@@ -4079,6 +6029,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let metrics = metrics::ExperimentMetrics {
@@ -4154,11 +6120,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: now - 20 * 24 * 60 * 60 * 1000,
@@ -4168,6 +6136,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let metrics = metrics::ExperimentMetrics {
@@ -4252,11 +6236,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(Default::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: started_at - 1_000,
@@ -4266,6 +6252,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         };
 
         let users = 200;
@@ -4305,8 +6307,12 @@ mod tests {
         };
 
         let raw_response = build_results_response(&experiment, Some(&metrics), None, None);
-        let cuped_response =
-            build_results_response(&experiment, Some(&metrics), Some(&covariates), None);
+        let cuped_response = build_results_response(
+            &experiment,
+            Some(&metrics),
+            Some(&metrics::PreExperimentCovariates::single("ctr", covariates)),
+            None,
+        );
 
         // Safety check should have detected that CUPED doesn't help and fallen back
         assert!(
@@ -1,12 +1,14 @@
 use super::AppState;
+use crate::auth::AttributeRestrictions;
 use crate::dto::{FacetHit, SearchFacetValuesRequest, SearchFacetValuesResponse};
 use crate::filter_parser::parse_filter;
 use axum::{
     extract::{Path, State},
-    Json,
+    Extension, Json,
 };
 use flapjack::error::FlapjackError;
 use flapjack::index::settings::IndexSettings;
+use flapjack::tokenizer::TextNormalization;
 use flapjack::types::FacetRequest;
 use std::sync::Arc;
 use std::time::Instant;
@@ -32,10 +34,50 @@ pub fn parse_facet_params(params_str: &str) -> SearchFacetValuesRequest {
     }
 }
 
-fn highlight_facet_match(value: &str, query: &str) -> String {
+fn check_filter_access(
+    attributes: Option<&AttributeRestrictions>,
+    filter: Option<&flapjack::types::Filter>,
+) -> Result<(), FlapjackError> {
+    match (attributes, filter) {
+        (Some(attributes), Some(filter)) => attributes
+            .check_filter(filter)
+            .map_err(FlapjackError::InvalidQuery),
+        _ => Ok(()),
+    }
+}
+
+/// Lowercase `text`, applying the index's text normalization if it has one.
+fn fold_facet_text(text: &str, normalization: Option<&TextNormalization>) -> String {
+    match normalization {
+        Some(n) => n.normalize(text),
+        None => text.to_lowercase(),
+    }
+}
+
+fn highlight_facet_match(
+    value: &str,
+    query: &str,
+    normalization: Option<&TextNormalization>,
+) -> String {
     if query.is_empty() {
         return value.to_string();
     }
+    if let Some(n) = normalization {
+        let (value_folded, offsets) = n.normalize_with_offsets(value);
+        let query_folded = n.normalize(query);
+        return match value_folded.find(&query_folded) {
+            Some(pos) if !query_folded.is_empty() => {
+                let (start, end) = (offsets[pos], offsets[pos + query_folded.len()]);
+                format!(
+                    "{}<em>{}</em>{}",
+                    &value[..start],
+                    &value[start..end],
+                    &value[end..]
+                )
+            }
+            _ => value.to_string(),
+        };
+    }
     let value_lower = value.to_lowercase();
     let query_lower = query.to_lowercase();
     if let Some(pos) = value_lower.find(&query_lower) {
@@ -61,6 +103,7 @@ pub async fn search_facet_values_inline(
     facet_query: &str,
     max_facet_hits: usize,
     filters: Option<&str>,
+    attributes: Option<&AttributeRestrictions>,
 ) -> Result<serde_json::Value, FlapjackError> {
     let start = Instant::now();
 
@@ -81,7 +124,10 @@ pub async fn search_facet_values_inline(
     };
 
     let searchable_facets = settings.searchable_facet_set();
-    if !searchable_facets.contains(facet_name) {
+    if !searchable_facets.contains(facet_name)
+        || settings.is_unretrievable(facet_name)
+        || attributes.is_some_and(|a| !a.is_visible(facet_name))
+    {
         return Ok(serde_json::json!({
             "facetHits": [],
             "exhaustiveFacetsCount": true,
@@ -97,6 +143,7 @@ pub async fn search_facet_values_inline(
     } else {
         None
     };
+    check_filter_access(attributes, filter.as_ref())?;
 
     let facet_request = FacetRequest {
         field: facet_name.to_string(),
@@ -116,7 +163,8 @@ pub async fn search_facet_values_inline(
     )?;
 
     let facet_counts = result.facets.get(facet_name);
-    let query_lower = facet_query.to_lowercase();
+    let normalization = TextNormalization::from_settings(&settings);
+    let query_lower = fold_facet_text(facet_query, normalization.as_ref());
     let empty_vec = Vec::new();
     let counts = facet_counts.unwrap_or(&empty_vec);
 
@@ -127,7 +175,7 @@ pub async fn search_facet_values_inline(
                 return true;
             }
             let leaf_value = fc.path.rsplit(" > ").next().unwrap_or(&fc.path);
-            leaf_value.to_lowercase().contains(&query_lower)
+            fold_facet_text(leaf_value, normalization.as_ref()).contains(&query_lower)
         })
         .collect();
 
@@ -141,7 +189,7 @@ pub async fn search_facet_values_inline(
             let highlighted = if facet_query.is_empty() {
                 value.clone()
             } else {
-                highlight_facet_match(&value, facet_query)
+                highlight_facet_match(&value, facet_query, normalization.as_ref())
             };
             serde_json::json!({
                 "value": value,
@@ -179,9 +227,16 @@ pub async fn search_facet_values_inline(
 pub async fn search_facet_values(
     State(state): State<Arc<AppState>>,
     Path((index_name, facet_name)): Path<(String, String)>,
+    attributes: Option<Extension<AttributeRestrictions>>,
     body: axum::body::Bytes,
 ) -> Result<Json<SearchFacetValuesResponse>, FlapjackError> {
     let start = Instant::now();
+    let attributes = attributes.map(|Extension(a)| a);
+    if let Some(ref attributes) = attributes {
+        attributes
+            .check_attribute(&facet_name)
+            .map_err(FlapjackError::InvalidQuery)?;
+    }
 
     let body_str = String::from_utf8_lossy(&body);
 
@@ -238,6 +293,13 @@ pub async fn search_facet_values(
         ));
     };
 
+    if settings.is_unretrievable(&facet_name) {
+        return Err(FlapjackError::InvalidQuery(format!(
+            "Cannot search in `{}` attribute, it is listed in unretrievableAttributes.",
+            facet_name
+        )));
+    }
+
     let searchable_facets = settings.searchable_facet_set();
     if !searchable_facets.contains(&facet_name) {
         return Err(FlapjackError::InvalidQuery(
@@ -253,6 +315,7 @@ pub async fn search_facet_values(
     } else {
         None
     };
+    check_filter_access(attributes.as_ref(), filter.as_ref())?;
 
     let facet_request = FacetRequest {
         field: facet_name.clone(),
@@ -273,7 +336,8 @@ pub async fn search_facet_values(
 
     let facet_counts = result.facets.get(&facet_name);
 
-    let query_lower = req.facet_query.to_lowercase();
+    let normalization = TextNormalization::from_settings(&settings);
+    let query_lower = fold_facet_text(&req.facet_query, normalization.as_ref());
     let empty_vec = Vec::new();
     let counts = facet_counts.unwrap_or(&empty_vec);
 
@@ -284,7 +348,7 @@ pub async fn search_facet_values(
                 return true;
             }
             let leaf_value = fc.path.rsplit(" > ").next().unwrap_or(&fc.path);
-            leaf_value.to_lowercase().contains(&query_lower)
+            fold_facet_text(leaf_value, normalization.as_ref()).contains(&query_lower)
         })
         .collect();
 
@@ -298,7 +362,7 @@ pub async fn search_facet_values(
             let highlighted = if req.facet_query.is_empty() {
                 value.clone()
             } else {
-                highlight_facet_match(&value, &req.facet_query)
+                highlight_facet_match(&value, &req.facet_query, normalization.as_ref())
             };
 
             FacetHit {
@@ -324,13 +388,13 @@ mod tests {
 
     #[test]
     fn highlight_exact_match() {
-        assert_eq!(highlight_facet_match("Nike", "Nike"), "<em>Nike</em>");
+        assert_eq!(highlight_facet_match("Nike", "Nike", None), "<em>Nike</em>");
     }
 
     #[test]
     fn highlight_prefix_match() {
         assert_eq!(
-            highlight_facet_match("Nike Air", "Nike"),
+            highlight_facet_match("Nike Air", "Nike", None),
             "<em>Nike</em> Air"
         );
     }
@@ -338,7 +402,7 @@ mod tests {
     #[test]
     fn highlight_suffix_match() {
         assert_eq!(
-            highlight_facet_match("Air Nike", "Nike"),
+            highlight_facet_match("Air Nike", "Nike", None),
             "Air <em>Nike</em>"
         );
     }
@@ -346,29 +410,42 @@ mod tests {
     #[test]
     fn highlight_case_insensitive() {
         assert_eq!(
-            highlight_facet_match("NIKE Shoes", "nike"),
+            highlight_facet_match("NIKE Shoes", "nike", None),
             "<em>NIKE</em> Shoes"
         );
     }
 
     #[test]
     fn highlight_no_match() {
-        assert_eq!(highlight_facet_match("Adidas", "Nike"), "Adidas");
+        assert_eq!(highlight_facet_match("Adidas", "Nike", None), "Adidas");
     }
 
     #[test]
     fn highlight_empty_query() {
-        assert_eq!(highlight_facet_match("Nike", ""), "Nike");
+        assert_eq!(highlight_facet_match("Nike", "", None), "Nike");
     }
 
     #[test]
     fn highlight_middle_match() {
         assert_eq!(
-            highlight_facet_match("Air Nike Max", "Nike"),
+            highlight_facet_match("Air Nike Max", "Nike", None),
             "Air <em>Nike</em> Max"
         );
     }
 
+    #[test]
+    fn highlight_normalized_match_keeps_original_text() {
+        let settings = IndexSettings {
+            remove_diacritics: true,
+            ..Default::default()
+        };
+        let normalization = TextNormalization::from_settings(&settings);
+        assert_eq!(
+            highlight_facet_match("Crème Brûlée", "brul", normalization.as_ref()),
+            "Crème <em>Brûl</em>ée"
+        );
+    }
+
     // ── parse_facet_params ──
 
     #[test]
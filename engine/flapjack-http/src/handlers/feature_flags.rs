@@ -0,0 +1,79 @@
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
+use flapjack::ErrorCode;
+use serde_json::json;
+use std::sync::Arc;
+
+use super::AppState;
+use crate::error_codes::error_response;
+use crate::feature_flags::{FeatureFlag, FlagStore};
+
+fn io_error(e: std::io::Error) -> axum::response::Response {
+    error_response(ErrorCode::IoError, e.to_string())
+}
+
+fn not_found(key: &str) -> axum::response::Response {
+    error_response(ErrorCode::NotFound, format!("Flag '{}' not found", key))
+}
+
+/// PUT /1/flags/:key — create or replace a feature flag
+pub async fn put_flag(
+    State(state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+    Json(mut flag): Json<FeatureFlag>,
+) -> impl IntoResponse {
+    flag.key = key;
+    flag.updated_at = chrono::Utc::now().timestamp_millis();
+    if let Err(message) = flag.validate() {
+        return error_response(ErrorCode::BadRequest, message);
+    }
+    let store = match FlagStore::for_data_dir(&state.manager.base_path) {
+        Ok(store) => store,
+        Err(e) => return io_error(e),
+    };
+    match store.put(flag.clone()) {
+        Ok(()) => Json(json!(flag)).into_response(),
+        Err(e) => io_error(e),
+    }
+}
+
+/// GET /1/flags — list all feature flags
+pub async fn list_flags(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match FlagStore::for_data_dir(&state.manager.base_path) {
+        Ok(store) => Json(json!({ "flags": store.list() })).into_response(),
+        Err(e) => io_error(e),
+    }
+}
+
+/// GET /1/flags/:key
+pub async fn get_flag(
+    State(state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+) -> impl IntoResponse {
+    match FlagStore::for_data_dir(&state.manager.base_path) {
+        Ok(store) => match store.get(&key) {
+            Some(flag) => Json(json!(flag)).into_response(),
+            None => not_found(&key),
+        },
+        Err(e) => io_error(e),
+    }
+}
+
+/// DELETE /1/flags/:key — flag-controlled features fall back to index settings
+pub async fn delete_flag(
+    State(state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+) -> impl IntoResponse {
+    let store = match FlagStore::for_data_dir(&state.manager.base_path) {
+        Ok(store) => store,
+        Err(e) => return io_error(e),
+    };
+    match store.delete(&key) {
+        Ok(true) => Json(json!({"deletedAt": chrono::Utc::now().to_rfc3339()})).into_response(),
+        Ok(false) => not_found(&key),
+        Err(e) => io_error(e),
+    }
+}
@@ -0,0 +1,286 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    Json,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::objects::add_documents_batch_impl;
+use super::AppState;
+use crate::dto::{AddDocumentsRequest, AddDocumentsResponse, BatchOperation};
+use crate::file_ingest::{chunk_text, extract, ChunkOptions, FileKind};
+use crate::pause_registry::check_not_paused;
+use flapjack::error::FlapjackError;
+use flapjack::types::FieldValue;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadFileParams {
+    /// objectID of the file; its chunks are `{objectID}-{n}` (default: the file name)
+    #[serde(rename = "objectID")]
+    pub object_id: Option<String>,
+    /// Original file name, stored on every chunk and used to detect the file type
+    pub file_name: Option<String>,
+    /// Maximum characters per chunk (default 1000)
+    pub chunk_size: Option<usize>,
+    /// Characters repeated from the end of the previous chunk (default 100)
+    pub chunk_overlap: Option<usize>,
+    /// JSON object of extra attributes copied onto every chunk
+    pub metadata: Option<String>,
+}
+
+/// Index a PDF, DOCX, HTML or text file as chunked records
+///
+/// `POST /1/indexes/{indexName}/files`. The file is the raw request body.
+/// Its text is split into chunks indexed as `{objectID}-0`, `{objectID}-1`,
+/// … with `parentID`, `fileName`, `fileType`, `title`, `chunkIndex`,
+/// `chunkCount` and `content`. Uploading the same objectID again replaces
+/// the previous chunks.
+pub async fn upload_file(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+    Query(params): Query<UploadFileParams>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<serde_json::Value>, FlapjackError> {
+    check_not_paused(&state.paused_indexes, &index_name)?;
+
+    let defaults = ChunkOptions::default();
+    let options = ChunkOptions {
+        size: params.chunk_size.unwrap_or(defaults.size),
+        overlap: params.chunk_overlap.unwrap_or(defaults.overlap),
+    };
+    if options.size == 0 || options.overlap >= options.size {
+        return Err(FlapjackError::InvalidQuery(
+            "chunkSize must be positive and larger than chunkOverlap".to_string(),
+        ));
+    }
+    let metadata: serde_json::Map<String, serde_json::Value> = match &params.metadata {
+        Some(raw) => serde_json::from_str(raw).map_err(|e| {
+            FlapjackError::InvalidQuery(format!("metadata must be a JSON object: {}", e))
+        })?,
+        None => serde_json::Map::new(),
+    };
+    if body.is_empty() {
+        return Err(FlapjackError::InvalidDocument("Empty file".to_string()));
+    }
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok());
+    let kind =
+        FileKind::detect(content_type, params.file_name.as_deref(), &body).ok_or_else(|| {
+            FlapjackError::InvalidDocument(
+                "Unsupported file type; expected PDF, DOCX, HTML or text".to_string(),
+            )
+        })?;
+    let extracted = tokio::task::spawn_blocking(move || extract(kind, &body))
+        .await
+        .map_err(|_| FlapjackError::InvalidDocument(format!("Could not read {}", kind.as_str())))?
+        .map_err(FlapjackError::InvalidDocument)?;
+    let chunks = chunk_text(&extracted.text, options);
+    if chunks.is_empty() {
+        return Err(FlapjackError::InvalidDocument(
+            "No text could be extracted from the file".to_string(),
+        ));
+    }
+
+    let parent_id = params
+        .object_id
+        .clone()
+        .or_else(|| params.file_name.clone())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    // Chunks left over from a longer earlier version of the file
+    let previous_count = state
+        .manager
+        .get_document(&index_name, &format!("{}-0", parent_id))
+        .ok()
+        .flatten()
+        .and_then(|doc| match doc.fields.get("chunkCount") {
+            Some(FieldValue::Integer(n)) => Some(*n as usize),
+            _ => None,
+        })
+        .unwrap_or(0);
+
+    let chunk_count = chunks.len();
+    let mut chunk_ids = Vec::with_capacity(chunk_count);
+    let mut requests = Vec::with_capacity(chunk_count.max(previous_count));
+    for (i, content) in chunks.into_iter().enumerate() {
+        let object_id = format!("{}-{}", parent_id, i);
+        let mut body: HashMap<String, serde_json::Value> = metadata.clone().into_iter().collect();
+        body.insert("objectID".into(), object_id.clone().into());
+        body.insert("parentID".into(), parent_id.clone().into());
+        if let Some(name) = &params.file_name {
+            body.insert("fileName".into(), name.clone().into());
+        }
+        body.insert("fileType".into(), kind.as_str().into());
+        if let Some(title) = &extracted.title {
+            body.insert("title".into(), title.clone().into());
+        }
+        body.insert("chunkIndex".into(), i.into());
+        body.insert("chunkCount".into(), chunk_count.into());
+        body.insert("content".into(), content.into());
+        chunk_ids.push(object_id);
+        requests.push(BatchOperation {
+            action: "addObject".to_string(),
+            body,
+            create_if_not_exists: None,
+        });
+    }
+    for i in chunk_count..previous_count {
+        requests.push(BatchOperation {
+            action: "deleteObject".to_string(),
+            body: HashMap::from([(
+                "objectID".to_string(),
+                format!("{}-{}", parent_id, i).into(),
+            )]),
+            create_if_not_exists: None,
+        });
+    }
+
+    let Json(response) = add_documents_batch_impl(
+        State(state),
+        index_name,
+        AddDocumentsRequest::Batch { requests },
+    )
+    .await?;
+    let (task_id, rejected) = match response {
+        AddDocumentsResponse::Algolia {
+            task_id, rejected, ..
+        } => (task_id, rejected),
+        AddDocumentsResponse::Legacy { .. } => (0, Vec::new()),
+    };
+    let mut response = serde_json::json!({
+        "taskID": task_id,
+        "objectID": parent_id,
+        "objectIDs": chunk_ids,
+        "fileType": kind.as_str(),
+        "chunks": chunk_count,
+    });
+    if !rejected.is_empty() {
+        response["rejected"] = serde_json::json!(rejected);
+    }
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::app_state;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use tempfile::TempDir;
+    use tower::ServiceExt;
+
+    async fn upload(
+        app: &Router,
+        query: &str,
+        content_type: &str,
+        body: &str,
+    ) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/1/indexes/docs/files?{}", query))
+                    .header("Content-Type", content_type)
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or_default())
+    }
+
+    /// Poll until the chunk's content matches (up to ~2s).
+    async fn wait_for_content(state: &AppState, object_id: &str, expected: &str) {
+        for _ in 0..200 {
+            if let Ok(Some(doc)) = state.manager.get_document("docs", object_id) {
+                if matches!(doc.fields.get("content"), Some(FieldValue::Text(s)) if s == expected) {
+                    return;
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("docs[{}] never had content {:?}", object_id, expected);
+    }
+
+    #[tokio::test]
+    async fn html_file_is_indexed_as_chunks_and_replaced_on_reupload() {
+        let tmp = TempDir::new().unwrap();
+        let state = app_state(&tmp);
+        let app = Router::new()
+            .route("/1/indexes/:indexName/files", post(upload_file))
+            .with_state(state.clone());
+
+        let html = "<html><head><title>Care guide</title></head><body>\
+                    <p>Wash leather shoes by hand.</p>\
+                    <p>Dry them away from heat.</p></body></html>";
+        let (status, body) = upload(
+            &app,
+            "objectID=care&fileName=care.html&chunkSize=30&chunkOverlap=0\
+             &metadata=%7B%22brand%22%3A%22acme%22%7D",
+            "text/html",
+            html,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["fileType"], "html");
+        assert_eq!(body["chunks"], 2);
+        assert_eq!(body["objectIDs"], serde_json::json!(["care-0", "care-1"]));
+
+        wait_for_content(&state, "care-0", "Wash leather shoes by hand.").await;
+        let first = state
+            .manager
+            .get_document("docs", "care-0")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            first.fields.get("title"),
+            Some(&FieldValue::Text("Care guide".into()))
+        );
+        assert_eq!(
+            first.fields.get("brand"),
+            Some(&FieldValue::Text("acme".into()))
+        );
+        assert_eq!(
+            first.fields.get("chunkCount"),
+            Some(&FieldValue::Integer(2))
+        );
+
+        let (status, body) = upload(&app, "objectID=care", "text/plain", "Hand wash only.").await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["chunks"], 1);
+        wait_for_content(&state, "care-0", "Hand wash only.").await;
+        assert!(state
+            .manager
+            .get_document("docs", "care-1")
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_unreadable_files() {
+        let tmp = TempDir::new().unwrap();
+        let app = Router::new()
+            .route("/1/indexes/:indexName/files", post(upload_file))
+            .with_state(app_state(&tmp));
+
+        let (status, _) = upload(&app, "", "application/pdf", "not really a pdf").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = upload(&app, "", "text/html", "<script>x()</script>").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = upload(&app, "chunkSize=10&chunkOverlap=10", "text/plain", "hi").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,7 +1,203 @@
-use axum::{extract::State, Json};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
 use std::sync::Arc;
 
 use super::AppState;
+use crate::startup_catchup::CatchupProgress;
+
+/// Free space on the data volume below which the node reports not ready.
+fn min_free_disk_bytes() -> u64 {
+    std::env::var("FLAPJACK_READY_MIN_FREE_DISK_MB")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(1024)
+        * 1024
+        * 1024
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum CheckStatus {
+    Ok,
+    /// Working with reduced function; the node still takes traffic.
+    Degraded,
+    Fail,
+}
+
+impl CheckStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CheckStatus::Ok => "ok",
+            CheckStatus::Degraded => "degraded",
+            CheckStatus::Fail => "fail",
+        }
+    }
+}
+
+struct Check {
+    name: &'static str,
+    status: CheckStatus,
+    detail: serde_json::Value,
+}
+
+fn readiness_checks(state: &AppState) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let catching_up = CatchupProgress::global().is_catching_up();
+    checks.push(Check {
+        name: "catchup",
+        status: if catching_up {
+            CheckStatus::Fail
+        } else {
+            CheckStatus::Ok
+        },
+        detail: serde_json::json!({ "catchingUp": catching_up }),
+    });
+
+    let min_free = min_free_disk_bytes();
+    checks.push(match fs2::available_space(&state.manager.base_path) {
+        Ok(free) => Check {
+            name: "disk",
+            status: if free < min_free {
+                CheckStatus::Fail
+            } else {
+                CheckStatus::Ok
+            },
+            detail: serde_json::json!({ "freeBytes": free, "minFreeBytes": min_free }),
+        },
+        Err(e) => Check {
+            name: "disk",
+            status: CheckStatus::Fail,
+            detail: serde_json::json!({ "error": e.to_string() }),
+        },
+    });
+
+    // Searches are still served in read-only mode, so the node stays ready.
+    let watchdog = crate::disk_watchdog::DiskWatchdog::global();
+    checks.push(Check {
+        name: "writes",
+        status: if watchdog.is_read_only() {
+            CheckStatus::Degraded
+        } else {
+            CheckStatus::Ok
+        },
+        detail: serde_json::json!({
+            "readOnly": watchdog.is_read_only(),
+            "resumeAboveBytes": watchdog.thresholds().resume_above,
+        }),
+    });
+
+    if let Some(key_store) = &state.key_store {
+        checks.push(Check {
+            name: "keyStore",
+            status: if key_store.load_error().is_some() {
+                CheckStatus::Fail
+            } else {
+                CheckStatus::Ok
+            },
+            detail: serde_json::json!({ "error": key_store.load_error() }),
+        });
+    }
+
+    // Search keeps working without analytics, so a failing writer only
+    // degrades the node.
+    if let Some(collector) = flapjack::analytics::get_global_collector() {
+        let error = collector.flush_error();
+        checks.push(Check {
+            name: "analyticsWriter",
+            status: if error.is_some() {
+                CheckStatus::Degraded
+            } else {
+                CheckStatus::Ok
+            },
+            detail: serde_json::json!({ "error": error }),
+        });
+    }
+
+    checks
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct HealthParams {
+    #[serde(default)]
+    pub verbose: Option<String>,
+}
+
+impl HealthParams {
+    fn is_verbose(&self) -> bool {
+        matches!(self.verbose.as_deref(), Some("" | "1" | "true"))
+    }
+}
+
+/// Liveness probe
+///
+/// 200 as long as the process serves requests. Restart the node only when
+/// this fails; use `/health/ready` to take it out of load balancing.
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses(
+        (status = 200, description = "Process is alive", body = serde_json::Value)
+    )
+)]
+pub async fn health_live() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+/// Readiness probe
+///
+/// 503 while the node should not take traffic: catching up with peers, low
+/// on disk, or unable to load its API keys. Disk read-only mode and a failing
+/// analytics writer report `degraded` but keep the node ready.
+/// `?verbose=true` lists every subsystem check.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    params(
+        ("verbose" = Option<bool>, Query, description = "Include per-subsystem checks")
+    ),
+    responses(
+        (status = 200, description = "Ready for traffic", body = serde_json::Value),
+        (status = 503, description = "Not ready", body = serde_json::Value)
+    )
+)]
+pub async fn health_ready(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HealthParams>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let checks = readiness_checks(&state);
+    let overall = checks
+        .iter()
+        .map(|c| c.status)
+        .max()
+        .unwrap_or(CheckStatus::Ok);
+    let code = if overall == CheckStatus::Fail {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+
+    let mut body = serde_json::json!({ "status": overall.as_str() });
+    if params.is_verbose() {
+        let detail: serde_json::Map<String, serde_json::Value> = checks
+            .into_iter()
+            .map(|c| {
+                let mut entry = serde_json::json!({ "status": c.status.as_str() });
+                if let (Some(entry), Some(detail)) = (entry.as_object_mut(), c.detail.as_object()) {
+                    entry.extend(detail.clone());
+                }
+                (c.name.to_string(), entry)
+            })
+            .collect();
+        body["checks"] = serde_json::Value::Object(detail);
+    }
+    (code, Json(body))
+}
 
 /// Health check endpoint
 #[utoipa::path(
@@ -9,16 +205,25 @@ use super::AppState;
     path = "/health",
     tag = "health",
     responses(
-        (status = 200, description = "Server is healthy", body = serde_json::Value)
+        (status = 200, description = "Server is healthy", body = serde_json::Value),
+        (status = 503, description = "Catching up with peers", body = serde_json::Value)
     )
 )]
-pub async fn health(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+pub async fn health(State(state): State<Arc<AppState>>) -> (StatusCode, Json<serde_json::Value>) {
     let budget = flapjack::get_global_budget();
     let observer = flapjack::MemoryObserver::global();
     let mem_stats = observer.stats();
 
-    Json(serde_json::json!({
-        "status": "ok",
+    // Not ready to take traffic until startup catch-up from peers finishes.
+    let catching_up = CatchupProgress::global().is_catching_up();
+    let (code, status) = if catching_up {
+        (StatusCode::SERVICE_UNAVAILABLE, "catching_up")
+    } else {
+        (StatusCode::OK, "ok")
+    };
+
+    let body = Json(serde_json::json!({
+        "status": status,
         "active_writers": budget.active_writers(),
         "max_concurrent_writers": budget.max_concurrent_writers(),
         "facet_cache_entries": state.manager.facet_cache.len(),
@@ -31,7 +236,8 @@ pub async fn health(State(state): State<Arc<AppState>>) -> Json<serde_json::Valu
         "tenants_loaded": state.manager.loaded_count(),
         "uptime_secs": state.start_time.elapsed().as_secs(),
         "version": env!("CARGO_PKG_VERSION"),
-    }))
+    }));
+    (code, body)
 }
 
 #[cfg(test)]
@@ -153,4 +359,57 @@ mod tests {
             "version should match CARGO_PKG_VERSION"
         );
     }
+
+    async fn get_json(app: Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let resp = app
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = resp.status();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn live_and_ready_probes() {
+        let tmp = TempDir::new().unwrap();
+        let app = Router::new()
+            .route("/health/live", get(health_live))
+            .route("/health/ready", get(health_ready))
+            .with_state(make_health_state(&tmp));
+
+        let (code, json) = get_json(app.clone(), "/health/live").await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(json["status"], "ok");
+
+        let (_, json) = get_json(app.clone(), "/health/ready").await;
+        assert!(json.get("checks").is_none(), "checks only in verbose mode");
+
+        let (_, json) = get_json(app, "/health/ready?verbose=true").await;
+        let checks = json["checks"].as_object().unwrap();
+        assert!(checks["disk"]["freeBytes"].as_u64().is_some());
+        assert!(checks.contains_key("catchup"));
+        assert_eq!(checks["writes"]["readOnly"], false);
+        assert!(!checks.contains_key("keyStore"), "auth is off");
+    }
+
+    #[tokio::test]
+    async fn ready_fails_when_key_store_did_not_load() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("keys.json"), "{not json").unwrap();
+        let key_store = crate::auth::KeyStore::load_or_create(tmp.path(), "admin-key-123456");
+        assert!(key_store.load_error().is_some());
+        let mut state = Arc::try_unwrap(make_health_state(&tmp)).ok().unwrap();
+        state.key_store = Some(Arc::new(key_store));
+        let app = Router::new()
+            .route("/health/ready", get(health_ready))
+            .with_state(Arc::new(state));
+
+        let (code, json) = get_json(app, "/health/ready?verbose=1").await;
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json["status"], "fail");
+        assert_eq!(json["checks"]["keyStore"]["status"], "fail");
+    }
 }
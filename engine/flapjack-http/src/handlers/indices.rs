@@ -1,12 +1,15 @@
 use axum::{
     extract::{Path, State},
-    Json,
+    Extension, Json,
 };
 use std::sync::Arc;
 
 use super::AppState;
+use crate::auth::KeyNamespace;
 use crate::dto::CreateIndexRequest;
+use crate::maintenance::{MaintenanceMode, MaintenanceRegistry};
 use flapjack::error::FlapjackError;
+use flapjack::index::templates::TemplateStore;
 
 /// Recursively compute total size of all files in a directory.
 fn dir_size(path: &std::path::Path) -> u64 {
@@ -31,6 +34,8 @@ fn dir_size(path: &std::path::Path) -> u64 {
 pub struct CreateIndexResponse {
     pub uid: String,
     pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
 }
 
 /// Create a new index
@@ -41,8 +46,8 @@ pub struct CreateIndexResponse {
     request_body = CreateIndexRequest,
     responses(
         (status = 200, description = "Index created successfully", body = CreateIndexResponse),
-        (status = 400, description = "Invalid request"),
-        (status = 409, description = "Index already exists")
+        (status = 400, description = "Invalid request or unknown template"),
+        (status = 409, description = "Index already exists (when creating from a template)")
     ),
     security(
         ("api_key" = [])
@@ -52,11 +57,31 @@ pub async fn create_index(
     State(state): State<Arc<AppState>>,
     Json(req): Json<CreateIndexRequest>,
 ) -> Result<Json<CreateIndexResponse>, FlapjackError> {
+    let template = match &req.template {
+        Some(name) => {
+            let template = TemplateStore::new(&state.manager.base_path)
+                .get(name)?
+                .ok_or_else(|| {
+                    FlapjackError::InvalidQuery(format!("Template '{}' not found", name))
+                })?;
+            // Never overlay a template onto an index that already has data.
+            if state.manager.base_path.join(&req.uid).exists() {
+                return Err(FlapjackError::IndexAlreadyExists(req.uid));
+            }
+            Some(template)
+        }
+        None => None,
+    };
+
     state.manager.create_tenant(&req.uid)?;
+    if let Some(template) = &template {
+        template.apply(&state.manager, &req.uid)?;
+    }
 
     Ok(Json(CreateIndexResponse {
         uid: req.uid,
         created_at: chrono::Utc::now().to_rfc3339(),
+        template: req.template,
     }))
 }
 
@@ -81,6 +106,12 @@ pub async fn delete_index(
     Path(index_name): Path<String>,
 ) -> Result<Json<serde_json::Value>, FlapjackError> {
     state.manager.delete_tenant(&index_name).await?;
+    MaintenanceRegistry::for_data_dir(&state.manager.base_path)?.set(
+        &index_name,
+        MaintenanceMode::ReadWrite,
+        None,
+        0,
+    )?;
     let task = state.manager.make_noop_task(&index_name)?;
     Ok(Json(serde_json::json!({
         "taskID": task.numeric_id,
@@ -102,8 +133,11 @@ pub async fn delete_index(
 )]
 pub async fn list_indices(
     State(state): State<Arc<AppState>>,
+    scope: Option<Extension<KeyNamespace>>,
 ) -> Result<Json<serde_json::Value>, FlapjackError> {
     let mut items = Vec::new();
+    let user_ids = crate::mcm::mapping_store(&state)?.snapshot().users;
+    let maintenance = MaintenanceRegistry::for_data_dir(&state.manager.base_path)?;
 
     for entry in std::fs::read_dir(&state.manager.base_path)? {
         let entry = entry?;
@@ -112,26 +146,48 @@ pub async fn list_indices(
         }
 
         let name = entry.file_name().to_string_lossy().to_string();
+        // Namespace-scoped keys see their namespace's indexes by plain name.
+        let display_name = match &scope {
+            Some(Extension(ns)) => {
+                match flapjack::index::namespace::parse_namespaced_tenant(&name) {
+                    Some((namespace, index_name)) if namespace == ns.name => index_name.to_string(),
+                    _ => continue,
+                }
+            }
+            None => name.clone(),
+        };
+        // Per-user tenants belong to the index they were written through.
+        if flapjack_replication::user_mapping::parse_user_tenant(&name)
+            .is_some_and(|(_, user)| user_ids.contains_key(user))
+        {
+            continue;
+        }
         let index_path = entry.path();
-        let size = dir_size(&index_path);
+        // Cold indexes are listed from their manifest, without fetching
+        // their segments.
+        let cold = state.manager.cold_manifest(&name).ok().flatten();
+        let size = dir_size(&index_path) + cold.as_ref().map_or(0, |m| m.bytes());
         tracing::debug!(index = %name, path = ?index_path, bytes = size, "Index directory size");
 
-        let entries = match state.manager.get_or_load(&name) {
-            Ok(index) => {
-                let reader = index.reader();
-                let searcher = reader.searcher();
-                searcher.num_docs()
-            }
-            Err(e) => {
-                tracing::warn!("Failed to load index {}: {}", name, e);
-                continue;
-            }
+        let entries = match &cold {
+            Some(manifest) => manifest.documents,
+            None => match state.manager.get_or_load(&name) {
+                Ok(index) => {
+                    let reader = index.reader();
+                    let searcher = reader.searcher();
+                    searcher.num_docs()
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to load index {}: {}", name, e);
+                    continue;
+                }
+            },
         };
 
         let pending = state.manager.pending_task_count(&name);
 
-        items.push(serde_json::json!({
-            "name": name,
+        let mut item = serde_json::json!({
+            "name": display_name,
             "createdAt": "2024-01-01T00:00:00Z",
             "updatedAt": chrono::Utc::now().to_rfc3339(),
             "entries": entries,
@@ -139,7 +195,14 @@ pub async fn list_indices(
             "fileSize": size,
             "numberOfPendingTasks": pending,
             "pendingTask": pending > 0
-        }));
+        });
+        if let Some(mode) = maintenance.get(&name) {
+            item["maintenance"] = serde_json::json!(mode);
+        }
+        if cold.is_some() {
+            item["tier"] = serde_json::json!("cold");
+        }
+        items.push(item);
     }
 
     Ok(Json(serde_json::json!({
@@ -231,9 +294,287 @@ pub async fn compact_index(
     })))
 }
 
+/// Index statistics for capacity planning
+///
+/// Document and segment counts, bytes on disk per component (docstore,
+/// postings, fast fields, vectors, ...), and the size and attribute count
+/// distribution of a sample of documents.
+#[utoipa::path(
+    get,
+    path = "/1/indexes/{indexName}/stats",
+    tag = "indices",
+    params(
+        ("indexName" = String, Path, description = "Index name")
+    ),
+    responses(
+        (status = 200, description = "Index statistics", body = serde_json::Value),
+        (status = 404, description = "Index not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn index_stats(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> Result<Json<flapjack::index::stats::IndexStats>, FlapjackError> {
+    let manager = Arc::clone(&state.manager);
+    let stats = tokio::task::spawn_blocking(move || manager.index_stats(&index_name))
+        .await
+        .map_err(|e| FlapjackError::Io(e.to_string()))??;
+    Ok(Json(stats))
+}
+
+/// Duplicate record clusters
+///
+/// Groups records whose fingerprints match under the index's
+/// `duplicateDetection` setting. Records indexed before the setting was
+/// enabled are fingerprinted by the reindex it triggers.
+#[utoipa::path(
+    get,
+    path = "/1/indexes/{indexName}/duplicates",
+    tag = "indices",
+    params(
+        ("indexName" = String, Path, description = "Index name")
+    ),
+    responses(
+        (status = 200, description = "Duplicate clusters", body = serde_json::Value),
+        (status = 400, description = "duplicateDetection is not enabled"),
+        (status = 404, description = "Index not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn duplicate_report(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> Result<Json<flapjack::index::duplicates::DuplicateReport>, FlapjackError> {
+    require_index(&state, &index_name)?;
+    let manager = Arc::clone(&state.manager);
+    let report = tokio::task::spawn_blocking(move || manager.duplicate_report(&index_name))
+        .await
+        .map_err(|e| FlapjackError::Io(e.to_string()))??;
+    Ok(Json(report))
+}
+
+fn require_index(state: &AppState, index_name: &str) -> Result<(), FlapjackError> {
+    if state.manager.base_path.join(index_name).is_dir() {
+        Ok(())
+    } else {
+        Err(FlapjackError::TenantNotFound(index_name.to_string()))
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetIndexModeRequest {
+    pub mode: MaintenanceMode,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+fn index_mode_json(index_name: &str, registry: &MaintenanceRegistry) -> serde_json::Value {
+    match registry.get(index_name) {
+        Some(maintenance) => serde_json::json!({
+            "indexName": index_name,
+            "mode": maintenance.mode,
+            "reason": maintenance.reason,
+            "since": maintenance.since,
+        }),
+        None => serde_json::json!({
+            "indexName": index_name,
+            "mode": MaintenanceMode::ReadWrite,
+        }),
+    }
+}
+
+/// Maintenance mode of an index
+#[utoipa::path(
+    get,
+    path = "/1/indexes/{indexName}/mode",
+    tag = "indices",
+    params(
+        ("indexName" = String, Path, description = "Index name")
+    ),
+    responses(
+        (status = 200, description = "Current mode (readWrite when none is set)", body = serde_json::Value)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn get_index_mode(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> Result<Json<serde_json::Value>, FlapjackError> {
+    let registry = MaintenanceRegistry::for_data_dir(&state.manager.base_path)?;
+    Ok(Json(index_mode_json(&index_name, &registry)))
+}
+
+/// Put an index in a maintenance mode
+///
+/// `readOnly` rejects writes, `writeOnly` rejects searches and reads,
+/// `offline` rejects both, and `readWrite` restores normal service.
+#[utoipa::path(
+    post,
+    path = "/1/indexes/{indexName}/mode",
+    tag = "indices",
+    params(
+        ("indexName" = String, Path, description = "Index name")
+    ),
+    request_body(content = serde_json::Value, description = "mode (readWrite, readOnly, writeOnly or offline) and an optional reason shown in rejections"),
+    responses(
+        (status = 200, description = "Mode updated", body = serde_json::Value),
+        (status = 404, description = "Index not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn set_index_mode(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+    Json(body): Json<SetIndexModeRequest>,
+) -> Result<Json<serde_json::Value>, FlapjackError> {
+    require_index(&state, &index_name)?;
+    let registry = MaintenanceRegistry::for_data_dir(&state.manager.base_path)?;
+    registry.set(
+        &index_name,
+        body.mode,
+        body.reason,
+        chrono::Utc::now().timestamp_millis(),
+    )?;
+    tracing::info!(
+        "index {} maintenance mode set to {}",
+        index_name,
+        body.mode.as_str()
+    );
+    let mut response = index_mode_json(&index_name, &registry);
+    response["updatedAt"] = serde_json::json!(chrono::Utc::now().to_rfc3339());
+    Ok(Json(response))
+}
+
+/// Latest warm-up of an index on this node
+#[utoipa::path(
+    get,
+    path = "/1/indexes/{indexName}/warmup",
+    tag = "indices",
+    params(
+        ("indexName" = String, Path, description = "Index name")
+    ),
+    responses(
+        (status = 200, description = "Warm-up status", body = serde_json::Value),
+        (status = 404, description = "Index not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn get_warmup(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> Result<Json<serde_json::Value>, FlapjackError> {
+    require_index(&state, &index_name)?;
+    let status = crate::warmup::WarmupRegistry::global().get(&index_name);
+    Ok(Json(match status {
+        Some(status) => serde_json::to_value(status).unwrap_or_default(),
+        None => serde_json::json!({ "state": "notStarted" }),
+    }))
+}
+
+/// Replay the index's top recent queries to warm its caches
+///
+/// Runs in the background; poll `GET /1/indexes/{indexName}/warmup` for
+/// progress. Does nothing if a warm-up of the index is already running.
+#[utoipa::path(
+    post,
+    path = "/1/indexes/{indexName}/warmup",
+    tag = "indices",
+    params(
+        ("indexName" = String, Path, description = "Index name")
+    ),
+    responses(
+        (status = 202, description = "Warm-up started", body = serde_json::Value),
+        (status = 404, description = "Index not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn start_warmup(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> Result<(axum::http::StatusCode, Json<serde_json::Value>), FlapjackError> {
+    require_index(&state, &index_name)?;
+    crate::warmup::spawn_warmup(
+        Arc::clone(&state),
+        index_name.clone(),
+        crate::warmup::WarmupTrigger::Manual,
+    );
+    Ok((
+        axum::http::StatusCode::ACCEPTED,
+        Json(serde_json::json!({
+            "indexName": index_name,
+            "startedAt": chrono::Utc::now().to_rfc3339()
+        })),
+    ))
+}
+
+/// Rebuild an index's HNSW graph from its live vectors
+///
+/// `POST /1/indexes/{indexName}/vectors/rebuild`. Deletes leave tombstones in
+/// the graph; a rebuild drops them. The saved graph is shipped to replication
+/// peers so they serve identical hybrid results without rebuilding themselves.
+/// Peers that could not be reached are listed in `unshippedPeers`; they pull
+/// the files when they next catch up.
+#[cfg(feature = "vector-search")]
+pub async fn rebuild_vectors(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> Result<Json<serde_json::Value>, FlapjackError> {
+    let manager = Arc::clone(&state.manager);
+    let tenant = index_name.clone();
+    let count = tokio::task::spawn_blocking(move || manager.rebuild_vector_index(&tenant))
+        .await
+        .map_err(|e| FlapjackError::Io(e.to_string()))??
+        .ok_or_else(|| {
+            FlapjackError::InvalidQuery(format!("Index {} has no vector index", index_name))
+        })?;
+
+    let mut unshipped = Vec::new();
+    if let Some(repl) = &state.replication_manager {
+        let vectors_dir = state.manager.base_path.join(&index_name).join("vectors");
+        let bytes = flapjack::index::snapshot::export_to_bytes(&vectors_dir)?;
+        unshipped = repl.ship_vectors(&index_name, bytes).await;
+    }
+
+    Ok(Json(serde_json::json!({
+        "vectors": count,
+        "unshippedPeers": unshipped,
+        "updatedAt": chrono::Utc::now().to_rfc3339()
+    })))
+}
+
+/// Embedding usage of an index since startup
+///
+/// `GET /1/indexes/{indexName}/vectors/usage`. Per embedder: requests sent
+/// (retries included), texts embedded, estimated tokens and cost,
+/// rate-limited requests and batches that failed.
+#[cfg(feature = "vector-search")]
+pub async fn vector_usage(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> Result<Json<serde_json::Value>, FlapjackError> {
+    state.manager.get_or_load(&index_name)?;
+    let usage = flapjack::vector::scheduler::EmbeddingScheduler::global().usage(&index_name);
+    Ok(Json(serde_json::json!({ "embedders": usage })))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::app_state;
 
     #[test]
     fn dir_size_nonexistent() {
@@ -263,6 +604,103 @@ mod tests {
         std::fs::write(sub.join("nested.txt"), "cde").unwrap(); // 3 bytes
         assert_eq!(dir_size(dir.path()), 5);
     }
+
+    fn create_request(uid: &str, template: Option<&str>) -> CreateIndexRequest {
+        serde_json::from_value(serde_json::json!({"uid": uid, "template": template})).unwrap()
+    }
+
+    #[tokio::test]
+    async fn create_index_from_template_applies_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = app_state(&dir);
+        let template: flapjack::index::templates::IndexTemplate =
+            serde_json::from_value(serde_json::json!({
+                "name": "ecommerce-default",
+                "settings": {"attributesForFaceting": ["brand"]}
+            }))
+            .unwrap();
+        TemplateStore::new(dir.path()).put(template).unwrap();
+
+        let Json(resp) = create_index(
+            State(state.clone()),
+            Json(create_request("shop", Some("ecommerce-default"))),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.template.as_deref(), Some("ecommerce-default"));
+
+        let settings = state.manager.get_settings("shop").unwrap();
+        assert_eq!(settings.attributes_for_faceting, vec!["brand".to_string()]);
+
+        // A second create with a template must not overwrite the existing index.
+        let err = create_index(
+            State(state),
+            Json(create_request("shop", Some("ecommerce-default"))),
+        )
+        .await
+        .err()
+        .unwrap();
+        assert!(matches!(err, FlapjackError::IndexAlreadyExists(_)));
+    }
+
+    #[tokio::test]
+    async fn create_index_with_unknown_template_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = app_state(&dir);
+        let err = create_index(State(state), Json(create_request("shop", Some("nope"))))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, FlapjackError::InvalidQuery(_)));
+        assert!(!dir.path().join("shop").exists());
+    }
+
+    fn clone_request(body: serde_json::Value) -> Json<CloneIndexRequest> {
+        Json(serde_json::from_value(body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn clone_settings_only_and_refuses_existing_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = app_state(&dir);
+        state.manager.create_tenant("products").unwrap();
+        state.manager.create_tenant("taken").unwrap();
+
+        let Json(resp) = clone_index(
+            State(state.clone()),
+            Path("products".to_string()),
+            clone_request(serde_json::json!({
+                "destination": "products_mode_b",
+                "mode": "settingsOnly"
+            })),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp["nbDocuments"], 0);
+        assert!(dir
+            .path()
+            .join("products_mode_b")
+            .join("settings.json")
+            .exists());
+
+        let err = clone_index(
+            State(state.clone()),
+            Path("products".to_string()),
+            clone_request(serde_json::json!({"destination": "taken"})),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, FlapjackError::IndexAlreadyExists(_)));
+
+        let err = clone_index(
+            State(state),
+            Path("products".to_string()),
+            clone_request(serde_json::json!({"destination": "s", "mode": "sample"})),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, FlapjackError::InvalidQuery(_)));
+    }
 }
 
 #[derive(serde::Deserialize, utoipa::ToSchema)]
@@ -321,3 +759,104 @@ pub async fn operation_index(
         "updatedAt": chrono::Utc::now().to_rfc3339()
     })))
 }
+
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum CloneMode {
+    /// Documents, settings, synonyms and rules.
+    #[default]
+    Full,
+    /// Settings, synonyms and rules only; the new index is empty.
+    SettingsOnly,
+    /// Configuration plus `samplePercent` of the documents.
+    Sample,
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CloneIndexRequest {
+    pub destination: String,
+    #[serde(default)]
+    pub mode: CloneMode,
+    /// Percentage of documents (0-100) to copy in `sample` mode.
+    pub sample_percent: Option<f64>,
+}
+
+/// Clone an index into a new index
+#[utoipa::path(
+    post,
+    path = "/1/indexes/{indexName}/clone",
+    tag = "indices",
+    params(
+        ("indexName" = String, Path, description = "Source index name")
+    ),
+    request_body = CloneIndexRequest,
+    responses(
+        (status = 200, description = "Index cloned", body = serde_json::Value),
+        (status = 400, description = "Invalid clone request"),
+        (status = 404, description = "Source index not found"),
+        (status = 409, description = "Destination index already exists")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn clone_index(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+    Json(req): Json<CloneIndexRequest>,
+) -> Result<Json<serde_json::Value>, FlapjackError> {
+    if !state.manager.base_path.join(&index_name).exists() {
+        return Err(FlapjackError::TenantNotFound(index_name));
+    }
+    if req.destination.is_empty() || req.destination == index_name {
+        return Err(FlapjackError::InvalidQuery(
+            "destination must be a different index name".to_string(),
+        ));
+    }
+    // Cloning never overwrites: use /operation with "copy" for that.
+    if state.manager.base_path.join(&req.destination).exists() {
+        return Err(FlapjackError::IndexAlreadyExists(req.destination));
+    }
+
+    let (task, nb_documents) = match req.mode {
+        CloneMode::Full => {
+            let task = state
+                .manager
+                .copy_index(&index_name, &req.destination, None)
+                .await?;
+            state.manager.create_tenant(&req.destination)?;
+            (task, state.manager.tenant_doc_count(&req.destination))
+        }
+        CloneMode::SettingsOnly => {
+            let scopes = ["settings", "synonyms", "rules"].map(String::from);
+            let task = state
+                .manager
+                .copy_index(&index_name, &req.destination, Some(&scopes))
+                .await?;
+            (task, Some(0))
+        }
+        CloneMode::Sample => {
+            let percent = req.sample_percent.ok_or_else(|| {
+                FlapjackError::InvalidQuery("samplePercent is required in sample mode".to_string())
+            })?;
+            if !(0.0..=100.0).contains(&percent) {
+                return Err(FlapjackError::InvalidQuery(
+                    "samplePercent must be between 0 and 100".to_string(),
+                ));
+            }
+            let (task, copied) = state
+                .manager
+                .clone_index_sampled(&index_name, &req.destination, percent)
+                .await?;
+            (task, Some(copied as u64))
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "taskID": task.numeric_id,
+        "destination": req.destination,
+        "nbDocuments": nb_documents,
+        "updatedAt": chrono::Utc::now().to_rfc3339()
+    })))
+}
@@ -1,11 +1,22 @@
-use axum::{extract::State, Json};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
 use std::sync::Arc;
 
+use flapjack::analytics::dead_letter::RejectReason;
 use flapjack::analytics::schema::InsightEvent;
 use flapjack::analytics::AnalyticsCollector;
 use flapjack::error::FlapjackError;
 
+/// Default and maximum page size for `GET /1/events/rejected`.
+const DEFAULT_REJECTED_LIMIT: usize = 100;
+const MAX_REJECTED_LIMIT: usize = 1000;
+
 /// POST /1/events - Algolia Insights API compatible event ingestion
+///
+/// Events that fail validation are recorded in the dead-letter queue
+/// (`GET /1/events/rejected`) instead of being dropped silently.
 pub async fn post_events(
     State(collector): State<Arc<AnalyticsCollector>>,
     Json(body): Json<InsightsRequest>,
@@ -18,15 +29,17 @@ pub async fn post_events(
 
     let mut accepted = 0;
     let mut errors: Vec<String> = Vec::new();
+    let now_ms = chrono::Utc::now().timestamp_millis();
 
     for event in body.events {
-        match event.validate() {
+        match collector.check_insight(&event, now_ms) {
             Ok(()) => {
                 collector.record_insight(event);
                 accepted += 1;
             }
-            Err(e) => {
-                errors.push(e);
+            Err((reason, message)) => {
+                errors.push(message.clone());
+                collector.reject_insight(event, reason, message);
             }
         }
     }
@@ -48,3 +61,138 @@ pub async fn post_events(
 pub struct InsightsRequest {
     pub events: Vec<InsightEvent>,
 }
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct RejectedEventsQuery {
+    pub index: Option<String>,
+    pub reason: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// GET /1/events/rejected - most recently rejected Insights events
+pub async fn get_rejected_events(
+    State(collector): State<Arc<AnalyticsCollector>>,
+    Query(params): Query<RejectedEventsQuery>,
+) -> Result<Json<serde_json::Value>, FlapjackError> {
+    let reason = match params.reason.as_deref() {
+        Some(r) => Some(RejectReason::parse(r).ok_or_else(|| {
+            FlapjackError::InvalidQuery(format!(
+                "Unknown reason '{}', expected one of: {}",
+                r,
+                RejectReason::ALL.map(|r| r.as_str()).join(", ")
+            ))
+        })?),
+        None => None,
+    };
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_REJECTED_LIMIT)
+        .clamp(1, MAX_REJECTED_LIMIT);
+
+    let dead_letters = collector.dead_letters();
+    let events = dead_letters.recent(params.index.as_deref(), reason, limit);
+    let totals: serde_json::Map<String, serde_json::Value> = RejectReason::ALL
+        .iter()
+        .map(|r| {
+            (
+                r.as_str().to_string(),
+                dead_letters.rejected_total(*r).into(),
+            )
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "events": events,
+        "count": events.len(),
+        "rejectedTotal": totals,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::routing::{get, post};
+    use axum::Router;
+    use flapjack::analytics::AnalyticsConfig;
+    use tower::ServiceExt;
+
+    fn app(collector: Arc<AnalyticsCollector>) -> Router {
+        Router::new()
+            .route("/1/events", post(post_events))
+            .route("/1/events/rejected", get(get_rejected_events))
+            .with_state(collector)
+    }
+
+    async fn send(app: &Router, req: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let resp = app.clone().oneshot(req).await.unwrap();
+        let status = resp.status();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    fn event(event_type: &str, timestamp: i64) -> serde_json::Value {
+        serde_json::json!({
+            "eventType": event_type,
+            "eventName": "Clicked",
+            "index": "products",
+            "userToken": "u1",
+            "objectIDs": ["a"],
+            "timestamp": timestamp
+        })
+    }
+
+    #[tokio::test]
+    async fn rejected_events_are_queryable() {
+        let collector = AnalyticsCollector::new(AnalyticsConfig::disabled());
+        let app = app(collector);
+        let now = chrono::Utc::now().timestamp_millis();
+        let body = serde_json::json!({
+            "events": [event("view", now), event("view", now + 24 * 3_600_000), event("hover", now)]
+        });
+        let (status, _) = send(
+            &app,
+            Request::post("/1/events")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, json) = send(
+            &app,
+            Request::get("/1/events/rejected")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["count"], 2);
+        assert_eq!(json["events"][0]["reason"], "invalid");
+        assert_eq!(json["events"][0]["event"]["eventType"], "hover");
+        assert_eq!(json["events"][1]["reason"], "timestampSkew");
+        assert_eq!(json["rejectedTotal"]["timestampSkew"], 1);
+
+        let (_, json) = send(
+            &app,
+            Request::get("/1/events/rejected?reason=timestampSkew&index=products")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(json["count"], 1);
+
+        let (status, _) = send(
+            &app,
+            Request::get("/1/events/rejected?reason=bogus")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,16 +1,20 @@
 use crate::handlers::AppState;
 use axum::{
+    body::Bytes,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
 use flapjack::index::oplog::OpLogEntry;
+use flapjack::query_suggestions::{QsConfig, QsConfigStore};
 use flapjack::types::Document;
 use flapjack::IndexManager;
+use flapjack_replication::protocol::{self, ProtocolInfo, PROTOCOL_HEADER};
 use flapjack_replication::types::{
     GetOpsQuery, GetOpsResponse, ReplicateOpsRequest, ReplicateOpsResponse,
 };
+use flapjack_replication::user_mapping::{is_user_tenant, MappingSnapshot};
 use std::sync::Arc;
 
 /// Core apply logic: parse ops and write to IndexManager.
@@ -104,6 +108,9 @@ pub async fn apply_ops_to_manager(
                     deletes.push(id.to_string());
                 }
             }
+            // Marker only: the rebuilt HNSW files are shipped to peers
+            // separately (or pulled during catch-up), never replayed here.
+            "vectors_rebuilt" => {}
             _ => tracing::warn!(
                 "[REPL {}] unknown op_type {} at seq {}",
                 tenant_id,
@@ -153,15 +160,58 @@ pub async fn apply_ops_to_manager(
     Ok(max_seq)
 }
 
+/// GET /internal/protocol
+/// Replication protocol versions this node speaks (handshake for peers).
+pub async fn protocol_info() -> impl IntoResponse {
+    Json(ProtocolInfo::local())
+}
+
+/// Reject requests sent with a protocol version outside this node's window
+/// rather than risk misreading their payload.
+fn check_protocol(headers: &HeaderMap) -> Result<u32, axum::response::Response> {
+    let header = headers.get(PROTOCOL_HEADER).and_then(|v| v.to_str().ok());
+    protocol::check_request_version(header).map_err(|e| {
+        tracing::warn!("[REPL] rejecting request: {}", e);
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e,
+                "supported": ProtocolInfo::local(),
+            })),
+        )
+            .into_response()
+    })
+}
+
 /// POST /internal/replicate
 /// Receive operations from a peer and apply them to local index.
 pub async fn replicate_ops(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(req): Json<ReplicateOpsRequest>,
 ) -> impl IntoResponse {
+    let version = match check_protocol(&headers) {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
     let tenant_id = req.tenant_id.clone();
 
-    match apply_ops_to_manager(&state.manager, &tenant_id, &req.ops).await {
+    #[cfg(feature = "fault-injection")]
+    if state.manager.faults().before_replicate(&tenant_id).await == flapjack::faults::Delivery::Drop
+    {
+        tracing::warn!("[REPL {}] dropped delivery (fault injection)", tenant_id);
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "error": "delivery dropped by fault injection" })),
+        )
+            .into_response();
+    }
+
+    let applied = apply_ops_to_manager(&state.manager, &tenant_id, &req.ops).await;
+    #[cfg(feature = "fault-injection")]
+    state.manager.faults().after_replicate();
+
+    match applied {
         Ok(max_seq) => {
             tracing::info!(
                 "[REPL {}] applied {} ops (max_seq={})",
@@ -174,6 +224,7 @@ pub async fn replicate_ops(
                 Json(ReplicateOpsResponse {
                     tenant_id,
                     acked_seq: max_seq,
+                    protocol_version: version,
                 }),
             )
                 .into_response()
@@ -189,12 +240,45 @@ pub async fn replicate_ops(
     }
 }
 
+/// GET /internal/faults
+/// Faults still armed on this node and those injected so far.
+#[cfg(feature = "fault-injection")]
+pub async fn get_faults(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(state.manager.faults().status())
+}
+
+/// PUT /internal/faults
+/// Arm faults on this node (see `flapjack::faults::FaultConfig`), replacing
+/// those armed before.
+#[cfg(feature = "fault-injection")]
+pub async fn set_faults(
+    State(state): State<Arc<AppState>>,
+    Json(config): Json<flapjack::faults::FaultConfig>,
+) -> impl IntoResponse {
+    tracing::warn!("[FAULTS] armed: {:?}", config);
+    state.manager.faults().set(config);
+    Json(state.manager.faults().status())
+}
+
+/// DELETE /internal/faults
+/// Disarm all faults, reset the counts and release held deliveries.
+#[cfg(feature = "fault-injection")]
+pub async fn clear_faults(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    state.manager.faults().clear();
+    Json(state.manager.faults().status())
+}
+
 /// GET /internal/ops?tenant_id=X&since_seq=N
 /// Fetch operations since a given sequence number for catch-up
 pub async fn get_ops(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Query(query): Query<GetOpsQuery>,
 ) -> impl IntoResponse {
+    let version = match check_protocol(&headers) {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
     let tenant_id = query.tenant_id.clone();
 
     // Get oplog for tenant
@@ -241,6 +325,7 @@ pub async fn get_ops(
         tenant_id,
         ops,
         current_seq,
+        protocol_version: version,
     };
 
     (StatusCode::OK, Json(response)).into_response()
@@ -286,11 +371,81 @@ pub async fn replication_status(State(state): State<Arc<AppState>>) -> impl Into
         "storage_total_bytes": storage_total_bytes,
         "tenant_count": tenant_count,
         "vector_memory_bytes": vector_memory_bytes,
+        "catchup": crate::startup_catchup::CatchupProgress::global().status(),
     });
 
     (StatusCode::OK, Json(response)).into_response()
 }
 
+/// POST /internal/cluster/mapping
+/// Merge a peer's copy of the multi-cluster user mapping.
+pub async fn receive_user_mapping(
+    State(state): State<Arc<AppState>>,
+    Json(snapshot): Json<MappingSnapshot>,
+) -> impl IntoResponse {
+    let result = crate::mcm::mapping_store(&state).and_then(|store| store.merge(&snapshot));
+    match result {
+        Ok(changed) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "changed": changed })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("[MCM] failed to merge user mapping: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// POST /internal/cluster/import/:tenant
+/// Receive a per-user tenant (tar.gz snapshot) migrated from another node.
+pub async fn import_user_tenant(
+    State(state): State<Arc<AppState>>,
+    Path(tenant_id): Path<String>,
+    body: Bytes,
+) -> impl IntoResponse {
+    if !is_user_tenant(&tenant_id) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "only per-user tenants can be imported" })),
+        )
+            .into_response();
+    }
+    let index_path = state.manager.base_path.join(&tenant_id);
+    state.manager.unload_tenant(&tenant_id);
+    if index_path.exists() {
+        if let Err(e) = std::fs::remove_dir_all(&index_path) {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response();
+        }
+    }
+    match flapjack::index::snapshot::import_from_bytes(&body, &index_path) {
+        Ok(()) => {
+            tracing::info!("[MCM] imported tenant {}", tenant_id);
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({ "status": "imported" })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("[MCM] import of {} failed: {}", tenant_id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    }
+}
+
 /// GET /internal/cluster/status
 /// Return health status of all peers based on last_success timestamps.
 /// Provides quick cluster health overview without active probing.
@@ -1234,6 +1389,177 @@ mod tests {
         );
     }
 
+    // ── Protocol versioning ──
+
+    #[tokio::test]
+    async fn replicate_rejects_unsupported_protocol_version() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_storage_state(&tmp);
+        let app = Router::new()
+            .route(
+                "/internal/replicate",
+                axum::routing::post(super::replicate_ops),
+            )
+            .with_state(state);
+
+        let body = serde_json::json!({"tenant_id": "pv", "ops": []}).to_string();
+        let too_new = (protocol::PROTOCOL_VERSION + 1).to_string();
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/internal/replicate")
+                    .header("content-type", "application/json")
+                    .header(PROTOCOL_HEADER, too_new)
+                    .body(Body::from(body.clone()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // A v1 sender sends no header at all and must still be accepted.
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/internal/replicate")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["protocol_version"], 1);
+    }
+
+    #[tokio::test]
+    async fn protocol_endpoint_reports_supported_versions() {
+        let app = Router::new().route("/internal/protocol", get(super::protocol_info));
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri("/internal/protocol")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let info: ProtocolInfo = serde_json::from_slice(&body).unwrap();
+        assert_eq!(info, ProtocolInfo::local());
+    }
+
+    // ── Fault injection ──
+
+    #[cfg(feature = "fault-injection")]
+    #[tokio::test]
+    async fn armed_drop_rejects_deliveries_until_used_up() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_storage_state(&tmp);
+        let app = Router::new()
+            .route(
+                "/internal/replicate",
+                axum::routing::post(super::replicate_ops),
+            )
+            .route(
+                "/internal/faults",
+                get(super::get_faults)
+                    .put(super::set_faults)
+                    .delete(super::clear_faults),
+            )
+            .with_state(state.clone());
+        let send = |method: &str, uri: &str, body: serde_json::Value| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(send(
+                "PUT",
+                "/internal/faults",
+                serde_json::json!({"tenant": "chaos", "replicateDrop": 1}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let delivery = serde_json::json!({
+            "tenant_id": "chaos",
+            "ops": [{
+                "seq": 1, "timestamp_ms": 1000, "node_id": "node-a", "tenant_id": "chaos",
+                "op_type": "upsert", "payload": {"body": {"_id": "d1", "title": "Hi"}},
+            }],
+        });
+        let statuses = [
+            app.clone()
+                .oneshot(send("POST", "/internal/replicate", delivery.clone()))
+                .await
+                .unwrap()
+                .status(),
+            app.clone()
+                .oneshot(send("POST", "/internal/replicate", delivery))
+                .await
+                .unwrap()
+                .status(),
+        ];
+        assert_eq!(statuses, [StatusCode::SERVICE_UNAVAILABLE, StatusCode::OK]);
+        assert_eq!(
+            state.manager.faults().status().injected.replicate_dropped,
+            1
+        );
+
+        let resp = app
+            .oneshot(send("DELETE", "/internal/faults", serde_json::json!({})))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["injected"]["replicateDropped"], 0);
+        assert_eq!(json["config"]["replicateDrop"], 0);
+    }
+
+    #[tokio::test]
+    async fn qs_import_refuses_indexes_without_a_config() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_storage_state(&tmp);
+        state.manager.create_tenant("products").unwrap();
+        let app = Router::new()
+            .route(
+                "/internal/qs/indexes/:indexName",
+                axum::routing::post(super::import_suggestions),
+            )
+            .with_state(Arc::clone(&state));
+
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/internal/qs/indexes/products")
+                    .body(Body::from("not a snapshot"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert!(tmp.path().join("products").exists());
+    }
+
     // ── /internal/status enhancements ──
 
     #[tokio::test]
@@ -1839,3 +2165,306 @@ pub async fn resume_index(
     )
         .into_response()
 }
+
+/// GET /internal/snapshot/:indexName/barrier
+/// This node's oplog position and queued writes, polled by a cluster
+/// snapshot coordinator while writes are paused.
+pub async fn snapshot_barrier(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        Json(crate::cluster_snapshot::local_barrier(&state, &index_name)),
+    )
+        .into_response()
+}
+
+/// POST /internal/snapshot/:indexName/capture
+/// Export this node's copy of an index for a cluster snapshot.
+pub async fn capture_snapshot(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+    Json(req): Json<crate::cluster_snapshot::CaptureRequest>,
+) -> impl IntoResponse {
+    match crate::cluster_snapshot::capture_local(&state, &index_name, &req) {
+        Ok(node) => (StatusCode::OK, Json(node)).into_response(),
+        Err(e) => {
+            tracing::error!("[SNAPSHOT] capture of '{}' failed: {}", index_name, e);
+            (
+                StatusCode::CONFLICT,
+                Json(serde_json::json!({ "error": e })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// POST /internal/qs/configs
+/// Store a Query Suggestions configuration pushed by a peer.
+pub async fn receive_qs_config(
+    State(state): State<Arc<AppState>>,
+    Json(config): Json<QsConfig>,
+) -> impl IntoResponse {
+    match QsConfigStore::new(&state.manager.base_path).save_config(&config) {
+        Ok(()) => (StatusCode::OK, Json(serde_json::json!({"status": "ok"}))).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// POST /internal/schedules
+/// Store a schedule created, changed or run on a peer.
+pub async fn receive_schedule(
+    State(state): State<Arc<AppState>>,
+    Json(schedule): Json<crate::scheduler::Schedule>,
+) -> impl IntoResponse {
+    match crate::scheduler::ScheduleStore::new(&state.manager.base_path).upsert(schedule) {
+        Ok(()) => (StatusCode::OK, Json(serde_json::json!({"status": "ok"}))).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// DELETE /internal/schedules/:id
+/// Delete a schedule removed on a peer.
+pub async fn delete_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match crate::scheduler::ScheduleStore::new(&state.manager.base_path).delete(&id) {
+        Ok(deleted) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "deleted": deleted })),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// DELETE /internal/qs/configs/:indexName
+/// Delete a Query Suggestions configuration removed on a peer.
+pub async fn delete_qs_config(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> impl IntoResponse {
+    match QsConfigStore::new(&state.manager.base_path).delete_config(&index_name) {
+        Ok(deleted) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "deleted": deleted })),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// POST /internal/qs/build/:indexName
+/// Build a suggestions index on this node, on behalf of a peer that
+/// considers this node the primary. Never delegated again, so nodes that
+/// briefly disagree on the primary cannot bounce a build between them.
+pub async fn build_qs(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> impl IntoResponse {
+    let store = QsConfigStore::new(&state.manager.base_path);
+    let config = match store.load_config(&index_name) {
+        Ok(Some(config)) => config,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "no configuration" })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    };
+    let mut status = store.load_status(&index_name);
+    if status.is_running {
+        return (
+            StatusCode::OK,
+            Json(serde_json::json!({ "status": "already_running" })),
+        )
+            .into_response();
+    }
+    status.is_running = true;
+    store.save_status(&status).ok();
+    crate::handlers::query_suggestions::spawn_local_build(Arc::clone(&state), config);
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "status": "started" })),
+    )
+        .into_response()
+}
+
+/// POST /internal/qs/indexes/:indexName
+/// Replace the local suggestions index with one built by the primary.
+pub async fn import_suggestions(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+    body: Bytes,
+) -> impl IntoResponse {
+    let store = QsConfigStore::new(&state.manager.base_path);
+    // Only suggestions indexes are shipped this way; refuse to overwrite
+    // anything else.
+    if !store.config_exists(&index_name) {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "no Query Suggestions configuration" })),
+        )
+            .into_response();
+    }
+    let index_path = state.manager.base_path.join(&index_name);
+    state.manager.unload_tenant(&index_name);
+    if index_path.exists() {
+        if let Err(e) = std::fs::remove_dir_all(&index_path) {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response();
+        }
+    }
+    if let Err(e) = flapjack::index::snapshot::import_from_bytes(&body, &index_path) {
+        tracing::error!("[QS] import of {} failed: {}", index_name, e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response();
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut status = store.load_status(&index_name);
+    status.is_running = false;
+    status.last_built_at = Some(now.clone());
+    status.last_successful_built_at = Some(now);
+    store.save_status(&status).ok();
+    tracing::info!(
+        "[QS] imported suggestions index {} from primary",
+        index_name
+    );
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "status": "imported" })),
+    )
+        .into_response()
+}
+
+/// Install a tarball of a tenant's `vectors/` directory and load it, keeping
+/// the previous files until the new ones are unpacked.
+#[cfg(feature = "vector-search")]
+pub fn install_vectors(
+    manager: &IndexManager,
+    tenant_id: &str,
+    body: &[u8],
+) -> Result<usize, String> {
+    let tenant_dir = manager.base_path.join(tenant_id);
+    if !tenant_dir.exists() {
+        return Err(format!("tenant {} does not exist", tenant_id));
+    }
+    let incoming = tenant_dir.join("vectors.incoming");
+    let target = tenant_dir.join("vectors");
+    if incoming.exists() {
+        std::fs::remove_dir_all(&incoming).map_err(|e| e.to_string())?;
+    }
+    flapjack::index::snapshot::import_from_bytes(body, &incoming).map_err(|e| e.to_string())?;
+    if target.exists() {
+        std::fs::remove_dir_all(&target).map_err(|e| e.to_string())?;
+    }
+    std::fs::rename(&incoming, &target).map_err(|e| e.to_string())?;
+    manager
+        .reload_vector_index(tenant_id)
+        .map_err(|e| e.to_string())
+}
+
+/// POST /internal/vectors/:indexName
+/// Replace the local vector index with one rebuilt on a peer.
+#[cfg(feature = "vector-search")]
+pub async fn import_vectors(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+    body: Bytes,
+) -> impl IntoResponse {
+    let manager = Arc::clone(&state.manager);
+    let tenant = index_name.clone();
+    let result =
+        tokio::task::spawn_blocking(move || install_vectors(&manager, &tenant, &body)).await;
+    match result {
+        Ok(Ok(count)) => {
+            tracing::info!(
+                "[VECTORS {}] installed rebuilt index from peer ({} vectors)",
+                index_name,
+                count
+            );
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({ "status": "imported", "vectors": count })),
+            )
+                .into_response()
+        }
+        Ok(Err(e)) => {
+            tracing::error!("[VECTORS {}] import failed: {}", index_name, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e })),
+            )
+                .into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// GET /internal/vectors/:indexName
+/// Tarball of the tenant's saved vector index, for peers catching up past a
+/// rebuild.
+#[cfg(feature = "vector-search")]
+pub async fn export_vectors(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> impl IntoResponse {
+    let vectors_dir = state.manager.base_path.join(&index_name).join("vectors");
+    if !vectors_dir.exists() {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "no vector index" })),
+        )
+            .into_response();
+    }
+    match flapjack::index::snapshot::export_to_bytes(&vectors_dir) {
+        Ok(bytes) => (
+            StatusCode::OK,
+            [(axum::http::header::CONTENT_TYPE, "application/gzip")],
+            bytes,
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
@@ -1,14 +1,17 @@
 use axum::{
     extract::{Path, State},
     http::StatusCode,
-    response::IntoResponse,
-    Json,
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
 use chrono::Utc;
+use flapjack::ErrorCode;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::auth::KeyStore;
+use crate::auth::{ApiKey, KeyNamespace, KeyStore};
+use crate::error_codes::error_response;
 
 #[derive(Debug, Deserialize)]
 pub struct CreateKeyRequest {
@@ -27,6 +30,57 @@ pub struct CreateKeyRequest {
     pub referers: Option<Vec<String>>,
     #[serde(default)]
     pub validity: Option<i64>,
+    /// Values available to secured-key filter templates as `{key.<name>}`.
+    #[serde(default)]
+    pub metadata: Option<HashMap<String, String>>,
+    /// Attributes the key is limited to (hits, highlights, facets, filters).
+    #[serde(default, rename = "allowedAttributes")]
+    pub allowed_attributes: Option<Vec<String>>,
+    /// Attributes the key can never see or filter on.
+    #[serde(default, rename = "deniedAttributes")]
+    pub denied_attributes: Option<Vec<String>>,
+    /// Browser origins the key may be used from.
+    #[serde(default, rename = "allowedOrigins")]
+    pub allowed_origins: Option<Vec<String>>,
+    /// Tenant namespace to confine the key to.
+    #[serde(default)]
+    pub namespace: Option<String>,
+}
+
+fn key_not_found() -> Response {
+    error_response(ErrorCode::KeyNotFound, "Key not found")
+}
+
+/// Namespace admin keys only see and manage the keys of their namespace.
+fn in_scope(scope: &Option<Extension<KeyNamespace>>, key: &ApiKey) -> bool {
+    match scope {
+        Some(Extension(ns)) => key.namespace.as_deref() == Some(ns.name.as_str()),
+        None => true,
+    }
+}
+
+/// The namespace a created or updated key gets: the caller's own for
+/// namespace admin keys, else the requested one.
+fn key_namespace(
+    scope: &Option<Extension<KeyNamespace>>,
+    requested: Option<String>,
+) -> Result<Option<String>, Response> {
+    let namespace = match (scope, requested) {
+        (Some(Extension(ns)), Some(requested)) if requested != ns.name => {
+            return Err(error_response(
+                ErrorCode::Forbidden,
+                format!("Keys of namespace '{}' can only manage keys in it", ns.name),
+            ));
+        }
+        (Some(Extension(ns)), _) => Some(ns.name.clone()),
+        (None, requested) => requested,
+    };
+    if let Some(name) = &namespace {
+        if let Err(message) = flapjack::index::namespace::validate_namespace(name) {
+            return Err(error_response(ErrorCode::BadRequest, message));
+        }
+    }
+    Ok(namespace)
 }
 
 /// Create a new API key
@@ -44,8 +98,13 @@ pub struct CreateKeyRequest {
 )]
 pub async fn create_key(
     State(key_store): State<Arc<KeyStore>>,
+    scope: Option<Extension<KeyNamespace>>,
     Json(body): Json<CreateKeyRequest>,
 ) -> impl IntoResponse {
+    let namespace = match key_namespace(&scope, body.namespace) {
+        Ok(namespace) => namespace,
+        Err(response) => return response,
+    };
     let key = crate::auth::ApiKey {
         hash: String::new(),
         salt: String::new(),
@@ -59,6 +118,12 @@ pub async fn create_key(
         query_parameters: body.query_parameters.unwrap_or_default(),
         referers: body.referers.unwrap_or_default(),
         validity: body.validity.unwrap_or(0),
+        metadata: body.metadata.unwrap_or_default(),
+        expires_at: None,
+        allowed_attributes: body.allowed_attributes.unwrap_or_default(),
+        denied_attributes: body.denied_attributes.unwrap_or_default(),
+        allowed_origins: body.allowed_origins.unwrap_or_default(),
+        namespace,
     };
 
     let (_created, plaintext_value) = key_store.create_key(key);
@@ -69,7 +134,7 @@ pub async fn create_key(
         "createdAt": Utc::now().to_rfc3339(),
     });
 
-    (StatusCode::CREATED, Json(response))
+    (StatusCode::CREATED, Json(response)).into_response()
 }
 
 /// List all API keys
@@ -84,9 +149,108 @@ pub async fn create_key(
         ("api_key" = [])
     )
 )]
-pub async fn list_keys(State(key_store): State<Arc<KeyStore>>) -> impl IntoResponse {
-    let keys = key_store.list_all();
-    Json(serde_json::json!({ "keys": keys }))
+pub async fn list_keys(
+    State(key_store): State<Arc<KeyStore>>,
+    scope: Option<Extension<KeyNamespace>>,
+) -> impl IntoResponse {
+    let keys: Vec<ApiKey> = key_store
+        .list_all()
+        .into_iter()
+        .filter(|k| in_scope(&scope, k))
+        .collect();
+    let pending_expiry: Vec<ApiKey> = key_store
+        .pending_expiry()
+        .into_iter()
+        .filter(|k| in_scope(&scope, k))
+        .collect();
+    Json(serde_json::json!({ "keys": keys, "pendingExpiry": pending_expiry }))
+}
+
+/// Default grace window for `POST /1/keys/{key}/rotate` (24 hours).
+const DEFAULT_ROTATION_GRACE_SECS: u64 = 86_400;
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RotateKeyRequest {
+    /// Seconds the old key keeps working after rotation.
+    #[serde(default)]
+    pub grace_period: Option<u64>,
+}
+
+/// Rotate an API key, keeping the old value valid for a grace window
+#[utoipa::path(
+    post,
+    path = "/1/keys/{key}/rotate",
+    tag = "keys",
+    params(
+        ("key" = String, Path, description = "API key value")
+    ),
+    request_body(content = serde_json::Value, description = "Optional `gracePeriod` in seconds (default 86400)"),
+    responses(
+        (status = 201, description = "Replacement key created", body = serde_json::Value),
+        (status = 403, description = "Admin key cannot be rotated"),
+        (status = 404, description = "Key not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn rotate_key(
+    State(key_store): State<Arc<KeyStore>>,
+    scope: Option<Extension<KeyNamespace>>,
+    Path(key_value): Path<String>,
+    body: Option<Json<RotateKeyRequest>>,
+) -> impl IntoResponse {
+    if scope.is_some()
+        && !key_store
+            .lookup(&key_value)
+            .is_some_and(|k| in_scope(&scope, &k))
+    {
+        return key_not_found();
+    }
+    if key_store.is_admin(&key_value) {
+        return error_response(
+            ErrorCode::Forbidden,
+            "Cannot rotate admin key; set FLAPJACK_ADMIN_KEY instead",
+        );
+    }
+
+    let grace_period = body
+        .and_then(|Json(b)| b.grace_period)
+        .unwrap_or(DEFAULT_ROTATION_GRACE_SECS);
+    match key_store.rotate_key(&key_value, grace_period) {
+        Some((_new_key, plaintext_value, old_expires_at)) => {
+            let expires_at = chrono::DateTime::from_timestamp_millis(old_expires_at)
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default();
+            (
+                StatusCode::CREATED,
+                Json(serde_json::json!({
+                    "key": plaintext_value,
+                    "createdAt": Utc::now().to_rfc3339(),
+                    "previousKeyExpiresAt": expires_at,
+                })),
+            )
+                .into_response()
+        }
+        None => key_not_found(),
+    }
+}
+
+/// List key lifecycle events (rotations)
+#[utoipa::path(
+    get,
+    path = "/1/keys/audit",
+    tag = "keys",
+    responses(
+        (status = 200, description = "Key audit log, oldest first", body = serde_json::Value)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn key_audit_log(State(key_store): State<Arc<KeyStore>>) -> impl IntoResponse {
+    Json(serde_json::json!({ "events": key_store.audit_log() }))
 }
 
 /// Get an API key by value
@@ -107,15 +271,12 @@ pub async fn list_keys(State(key_store): State<Arc<KeyStore>>) -> impl IntoRespo
 )]
 pub async fn get_key(
     State(key_store): State<Arc<KeyStore>>,
+    scope: Option<Extension<KeyNamespace>>,
     Path(key_value): Path<String>,
 ) -> impl IntoResponse {
-    match key_store.lookup(&key_value) {
+    match key_store.lookup(&key_value).filter(|k| in_scope(&scope, k)) {
         Some(key) => Json(serde_json::to_value(key).unwrap()).into_response(),
-        None => (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({"message": "Key not found", "status": 404})),
-        )
-            .into_response(),
+        None => key_not_found(),
     }
 }
 
@@ -138,9 +299,21 @@ pub async fn get_key(
 )]
 pub async fn update_key(
     State(key_store): State<Arc<KeyStore>>,
+    scope: Option<Extension<KeyNamespace>>,
     Path(key_value): Path<String>,
     Json(body): Json<CreateKeyRequest>,
 ) -> impl IntoResponse {
+    if scope.is_some()
+        && !key_store
+            .lookup(&key_value)
+            .is_some_and(|k| in_scope(&scope, &k))
+    {
+        return key_not_found();
+    }
+    let namespace = match key_namespace(&scope, body.namespace) {
+        Ok(namespace) => namespace,
+        Err(response) => return response,
+    };
     let updated = crate::auth::ApiKey {
         hash: String::new(),
         salt: String::new(),
@@ -154,6 +327,12 @@ pub async fn update_key(
         query_parameters: body.query_parameters.unwrap_or_default(),
         referers: body.referers.unwrap_or_default(),
         validity: body.validity.unwrap_or(0),
+        metadata: body.metadata.unwrap_or_default(),
+        expires_at: None,
+        allowed_attributes: body.allowed_attributes.unwrap_or_default(),
+        denied_attributes: body.denied_attributes.unwrap_or_default(),
+        allowed_origins: body.allowed_origins.unwrap_or_default(),
+        namespace,
     };
 
     match key_store.update_key(&key_value, updated) {
@@ -162,11 +341,7 @@ pub async fn update_key(
             "updatedAt": Utc::now().to_rfc3339(),
         }))
         .into_response(),
-        None => (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({"message": "Key not found", "status": 404})),
-        )
-            .into_response(),
+        None => key_not_found(),
     }
 }
 
@@ -188,14 +363,18 @@ pub async fn update_key(
 )]
 pub async fn delete_key(
     State(key_store): State<Arc<KeyStore>>,
+    scope: Option<Extension<KeyNamespace>>,
     Path(key_value): Path<String>,
 ) -> impl IntoResponse {
+    if scope.is_some()
+        && !key_store
+            .lookup(&key_value)
+            .is_some_and(|k| in_scope(&scope, &k))
+    {
+        return key_not_found();
+    }
     if key_store.is_admin(&key_value) {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(serde_json::json!({"message": "Cannot delete admin key", "status": 403})),
-        )
-            .into_response();
+        return error_response(ErrorCode::Forbidden, "Cannot delete admin key");
     }
 
     if key_store.delete_key(&key_value) {
@@ -204,11 +383,7 @@ pub async fn delete_key(
         }))
         .into_response()
     } else {
-        (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({"message": "Key not found", "status": 404})),
-        )
-            .into_response()
+        key_not_found()
     }
 }
 
@@ -230,18 +405,22 @@ pub async fn delete_key(
 )]
 pub async fn restore_key(
     State(key_store): State<Arc<KeyStore>>,
+    scope: Option<Extension<KeyNamespace>>,
     Path(key_value): Path<String>,
 ) -> impl IntoResponse {
+    if scope.is_some()
+        && !key_store
+            .lookup_deleted(&key_value)
+            .is_some_and(|k| in_scope(&scope, &k))
+    {
+        return key_not_found();
+    }
     match key_store.restore_key(&key_value) {
         Some(_) => Json(serde_json::json!({
             "createdAt": Utc::now().to_rfc3339(),
         }))
         .into_response(),
-        None => (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({"message": "Key not found", "status": 404})),
-        )
-            .into_response(),
+        None => key_not_found(),
     }
 }
 #[derive(Debug, Deserialize)]
@@ -283,27 +462,24 @@ pub struct SecuredKeyRestrictions {
 )]
 pub async fn generate_secured_key(
     State(key_store): State<Arc<KeyStore>>,
+    scope: Option<Extension<KeyNamespace>>,
     Json(body): Json<GenerateSecuredKeyRequest>,
 ) -> impl IntoResponse {
     // Look up the parent key
-    let parent_key = match key_store.lookup(&body.parent_api_key) {
+    let parent_key = match key_store
+        .lookup(&body.parent_api_key)
+        .filter(|k| in_scope(&scope, k))
+    {
         Some(k) => k,
-        None => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(serde_json::json!({"message": "Parent key not found", "status": 404})),
-            )
-                .into_response()
-        }
+        None => return error_response(ErrorCode::KeyNotFound, "Parent key not found"),
     };
 
     // Admin keys cannot be used as parents for secured keys
     if parent_key.hmac_key.is_none() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({"message": "Cannot generate secured key from admin key", "status": 400})),
-        )
-            .into_response();
+        return error_response(
+            ErrorCode::BadRequest,
+            "Cannot generate secured key from admin key",
+        );
     }
 
     let mut params = Vec::new();
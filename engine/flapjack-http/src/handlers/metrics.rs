@@ -1,7 +1,8 @@
 //! Prometheus `/metrics` endpoint.
 //!
-//! Exposes system-wide gauges (writers, memory, tenants, facet cache) and
-//! per-tenant storage gauges in Prometheus text exposition format.
+//! Exposes system-wide gauges (writers, memory, disk, tenants, facet cache),
+//! per-tenant storage gauges, Insights rejection, auth failure and cluster
+//! analytics fan-out counters in Prometheus text exposition format.
 
 use axum::extract::State;
 use axum::http::{header, StatusCode};
@@ -60,6 +61,22 @@ pub async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoRes
         pressure_level,
     );
 
+    let disk = crate::disk_watchdog::DiskWatchdog::global();
+    if let Some(free) = disk.free_bytes() {
+        register_gauge(
+            &registry,
+            "flapjack_disk_free_bytes",
+            "Free bytes on the data volume at the last watchdog check",
+            free as f64,
+        );
+    }
+    register_gauge(
+        &registry,
+        "flapjack_read_only",
+        "1 while writes are rejected because disk space is low",
+        if disk.is_read_only() { 1.0 } else { 0.0 },
+    );
+
     register_gauge(
         &registry,
         "flapjack_facet_cache_entries",
@@ -265,6 +282,166 @@ pub async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoRes
         }
     }
 
+    // --- Embedding usage counters ---
+    #[cfg(feature = "vector-search")]
+    {
+        let usage = flapjack::vector::scheduler::EmbeddingScheduler::global().all_usage();
+        let labels = &["index", "embedder"];
+        let requests_gauge = GaugeVec::new(
+            Opts::new(
+                "flapjack_embedding_requests_total",
+                "Embedding provider requests per index and embedder, retries included",
+            ),
+            labels,
+        )
+        .unwrap();
+        let tokens_gauge = GaugeVec::new(
+            Opts::new(
+                "flapjack_embedding_tokens_total",
+                "Estimated tokens embedded per index and embedder",
+            ),
+            labels,
+        )
+        .unwrap();
+        let cost_gauge = GaugeVec::new(
+            Opts::new(
+                "flapjack_embedding_cost_total",
+                "Estimated embedding cost per index and embedder",
+            ),
+            labels,
+        )
+        .unwrap();
+        let rate_limited_gauge = GaugeVec::new(
+            Opts::new(
+                "flapjack_embedding_rate_limited_total",
+                "Embedding requests rejected by the provider's rate limit",
+            ),
+            labels,
+        )
+        .unwrap();
+        registry.register(Box::new(requests_gauge.clone())).unwrap();
+        registry.register(Box::new(tokens_gauge.clone())).unwrap();
+        registry.register(Box::new(cost_gauge.clone())).unwrap();
+        registry
+            .register(Box::new(rate_limited_gauge.clone()))
+            .unwrap();
+        for ((index, embedder), u) in &usage {
+            let values = [index.as_str(), embedder.as_str()];
+            requests_gauge
+                .with_label_values(&values)
+                .set(u.requests as f64);
+            tokens_gauge
+                .with_label_values(&values)
+                .set(u.estimated_tokens as f64);
+            cost_gauge.with_label_values(&values).set(u.estimated_cost);
+            rate_limited_gauge
+                .with_label_values(&values)
+                .set(u.rate_limited as f64);
+        }
+    }
+
+    // --- Insights dead-letter counters ---
+    if let Some(collector) = flapjack::analytics::get_global_collector() {
+        let rejected_gauge = GaugeVec::new(
+            Opts::new(
+                "flapjack_insights_events_rejected_total",
+                "Insights events rejected since startup, by reason",
+            ),
+            &["reason"],
+        )
+        .unwrap();
+        registry.register(Box::new(rejected_gauge.clone())).unwrap();
+        for reason in flapjack::analytics::dead_letter::RejectReason::ALL {
+            rejected_gauge
+                .with_label_values(&[reason.as_str()])
+                .set(collector.dead_letters().rejected_total(reason) as f64);
+        }
+    }
+
+    // --- Auth failures and key anomalies ---
+    {
+        let monitor = crate::key_monitor::KeyMonitor::global();
+        let by_reason = GaugeVec::new(
+            Opts::new(
+                "flapjack_auth_failures_total",
+                "Rejected requests since startup, by reason",
+            ),
+            &["reason"],
+        )
+        .unwrap();
+        let by_key = GaugeVec::new(
+            Opts::new(
+                "flapjack_auth_failures_by_key_total",
+                "Rejected requests since startup, by key prefix (`unknown` without a valid key)",
+            ),
+            &["key"],
+        )
+        .unwrap();
+        let by_ip = GaugeVec::new(
+            Opts::new(
+                "flapjack_auth_failures_by_ip_total",
+                "Rejected requests since startup, by client IP",
+            ),
+            &["ip"],
+        )
+        .unwrap();
+        let anomalies = GaugeVec::new(
+            Opts::new(
+                "flapjack_key_anomalies_total",
+                "Key usage anomalies detected since startup, by key prefix and type",
+            ),
+            &["key", "type"],
+        )
+        .unwrap();
+        registry.register(Box::new(by_reason.clone())).unwrap();
+        registry.register(Box::new(by_key.clone())).unwrap();
+        registry.register(Box::new(by_ip.clone())).unwrap();
+        registry.register(Box::new(anomalies.clone())).unwrap();
+        for reason in crate::key_monitor::AuthFailure::ALL {
+            by_reason
+                .with_label_values(&[reason.as_str()])
+                .set(monitor.failures_by_reason(reason) as f64);
+        }
+        for (key, count) in monitor.failures_by_key() {
+            by_key.with_label_values(&[&key]).set(count as f64);
+        }
+        for (ip, count) in monitor.failures_by_ip() {
+            by_ip.with_label_values(&[&ip]).set(count as f64);
+        }
+        for ((key, kind), count) in monitor.anomalies() {
+            anomalies.with_label_values(&[&key, kind]).set(count as f64);
+        }
+    }
+
+    // --- Cluster analytics fan-out ---
+    if crate::analytics_cluster::get_global_cluster().is_some() {
+        let stats = crate::analytics_cluster::FanOutStats::global();
+        register_gauge(
+            &registry,
+            "flapjack_analytics_fanout_queries_total",
+            "Analytics queries fanned out to peers since startup",
+            stats.queries() as f64,
+        );
+        register_gauge(
+            &registry,
+            "flapjack_analytics_fanout_partial_total",
+            "Fanned-out analytics queries answered without every peer",
+            stats.partial() as f64,
+        );
+        let peer_failures = GaugeVec::new(
+            Opts::new(
+                "flapjack_analytics_fanout_peer_failures_total",
+                "Peers that timed out, failed or were skipped in a fan-out, by node",
+            ),
+            &["node"],
+        )
+        .unwrap();
+        registry.register(Box::new(peer_failures.clone())).unwrap();
+        for (node, count) in stats.peer_failures() {
+            peer_failures.with_label_values(&[&node]).set(count as f64);
+        }
+    }
+
     // Encode to text
     let encoder = TextEncoder::new();
     let metric_families = registry.gather();
@@ -10,10 +10,20 @@ use flapjack_replication::manager::ReplicationManager;
 use std::sync::Arc;
 
 pub mod analytics;
+pub mod archive;
+pub mod blocklist;
 pub mod browse;
+pub mod campaigns;
+pub mod clusters;
+pub mod configuration;
+pub mod crawler;
 pub mod dashboard;
+pub mod errors;
 pub mod experiments;
 pub mod facets;
+pub mod feature_flags;
+#[cfg(feature = "file-ingest")]
+pub mod files;
 pub mod health;
 pub mod indices;
 pub mod insights;
@@ -21,14 +31,22 @@ pub mod internal;
 pub mod keys;
 pub mod metrics;
 pub mod migration;
+pub mod namespaces;
 pub mod objects;
 pub mod query_suggestions;
+pub mod request_logs;
 pub mod rules;
+pub mod saved_searches;
+pub mod schedules;
 pub mod search;
+pub mod search_anomalies;
 pub mod settings;
 pub mod snapshot;
 pub mod synonyms;
 pub mod tasks;
+pub mod templates;
+pub mod tier;
+pub mod user_tokens;
 
 pub struct AppState {
     pub manager: Arc<IndexManager>,
@@ -68,12 +86,14 @@ pub(crate) fn field_value_to_json(value: &flapjack::types::FieldValue) -> serde_
 
 pub use browse::browse_index;
 pub use facets::{parse_facet_params, search_facet_values};
-pub use health::health;
+pub use health::{health, health_live, health_ready};
 pub use indices::{
-    clear_index, compact_index, create_index, delete_index, list_indices, operation_index,
+    clear_index, clone_index, compact_index, create_index, delete_index, duplicate_report,
+    get_warmup, index_stats, list_indices, operation_index, start_warmup,
 };
 pub use keys::{
-    create_key, delete_key, generate_secured_key, get_key, list_keys, restore_key, update_key,
+    create_key, delete_key, generate_secured_key, get_key, key_audit_log, list_keys, restore_key,
+    rotate_key, update_key,
 };
 pub use metrics::metrics_handler;
 pub use migration::{list_algolia_indexes, migrate_from_algolia};
@@ -81,10 +101,13 @@ pub use objects::{
     add_documents, add_record_auto_id, delete_by_query, delete_object, get_object, get_objects,
     partial_update_object, put_object,
 };
-pub use rules::{clear_rules, delete_rule, get_rule, save_rule, save_rules, search_rules};
-pub use search::{batch_search, search};
-pub use settings::{get_settings, set_settings};
+pub use rules::{
+    clear_rules, delete_rule, get_rule, get_rule_stats, save_rule, save_rules, search_rules,
+};
+pub use search::{analyze_query, batch_search, search};
+pub use settings::{bulk_set_settings, get_settings, set_settings};
 pub use synonyms::{
-    clear_synonyms, delete_synonym, get_synonym, save_synonym, save_synonyms, search_synonyms,
+    clear_synonyms, delete_synonym, get_synonym, get_synonym_stats, save_synonym, save_synonyms,
+    search_synonyms,
 };
-pub use tasks::{get_task, get_task_for_index};
+pub use tasks::{get_task, get_task_for_index, list_tasks};
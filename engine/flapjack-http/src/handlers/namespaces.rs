@@ -0,0 +1,134 @@
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
+use flapjack::index::namespace::{
+    namespaced_tenant, parse_namespaced_tenant, validate_namespace, Namespace,
+};
+use flapjack::ErrorCode;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use super::AppState;
+use crate::error_codes::error_response;
+
+fn not_found(name: &str) -> axum::response::Response {
+    error_response(
+        ErrorCode::NotFound,
+        format!("Namespace '{}' not found", name),
+    )
+}
+
+/// Stored size of the namespace's indexes plus its request counts since
+/// startup.
+fn usage_json(state: &AppState, name: &str) -> serde_json::Value {
+    let mut usage = json!(state.manager.namespace_usage(name));
+    let prefix = namespaced_tenant(name, "");
+    let (mut searches, mut writes, mut reads) = (0u64, 0u64, 0u64);
+    for entry in state.usage_counters.iter() {
+        if entry.key().starts_with(&prefix) {
+            searches += entry.search_count.load(Ordering::Relaxed);
+            writes += entry.write_count.load(Ordering::Relaxed);
+            reads += entry.read_count.load(Ordering::Relaxed);
+        }
+    }
+    usage["searches"] = json!(searches);
+    usage["writes"] = json!(writes);
+    usage["reads"] = json!(reads);
+    usage
+}
+
+fn namespace_json(state: &AppState, namespace: &Namespace) -> serde_json::Value {
+    let mut value = json!(namespace);
+    value["usage"] = usage_json(state, &namespace.name);
+    value
+}
+
+/// Registered namespaces plus those that only exist as index prefixes.
+fn all_namespaces(state: &AppState) -> Vec<Namespace> {
+    let mut namespaces: BTreeMap<String, Namespace> = state
+        .manager
+        .namespaces()
+        .list()
+        .into_iter()
+        .map(|n| (n.name.clone(), n))
+        .collect();
+    if let Ok(entries) = std::fs::read_dir(&state.manager.base_path) {
+        for entry in entries.flatten() {
+            let dir = entry.file_name().to_string_lossy().to_string();
+            if let Some((name, _)) = parse_namespaced_tenant(&dir) {
+                namespaces
+                    .entry(name.to_string())
+                    .or_insert_with(|| Namespace {
+                        name: name.to_string(),
+                        limits: Default::default(),
+                        created_at: 0,
+                    });
+            }
+        }
+    }
+    namespaces.into_values().collect()
+}
+
+/// GET /1/namespaces — namespaces with their limits and usage
+pub async fn list_namespaces(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let namespaces: Vec<serde_json::Value> = all_namespaces(&state)
+        .iter()
+        .map(|n| namespace_json(&state, n))
+        .collect();
+    Json(json!({ "namespaces": namespaces }))
+}
+
+/// GET /1/namespaces/:name
+pub async fn get_namespace(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    match all_namespaces(&state).into_iter().find(|n| n.name == name) {
+        Some(namespace) => Json(namespace_json(&state, &namespace)).into_response(),
+        None => not_found(&name),
+    }
+}
+
+/// PUT /1/namespaces/:name — register a namespace or replace its limits
+pub async fn put_namespace(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(mut namespace): Json<Namespace>,
+) -> impl IntoResponse {
+    if let Err(message) = validate_namespace(&name) {
+        return error_response(ErrorCode::BadRequest, message);
+    }
+    namespace.name = name;
+    namespace.created_at = chrono::Utc::now().timestamp_millis();
+    match state.manager.namespaces().put(namespace) {
+        Ok(saved) => Json(namespace_json(&state, &saved)).into_response(),
+        Err(e) => error_response(ErrorCode::InternalError, e.to_string()),
+    }
+}
+
+/// DELETE /1/namespaces/:name — drop a namespace's registration and limits.
+/// Its indexes must be deleted first.
+pub async fn delete_namespace(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let indexes = state.manager.namespace_tenants(&name).len();
+    if indexes > 0 {
+        return error_response(
+            ErrorCode::Conflict,
+            format!(
+                "Namespace '{}' still has {} indexes; delete them first",
+                name, indexes
+            ),
+        );
+    }
+    match state.manager.namespaces().delete(&name) {
+        Ok(true) => Json(json!({"deletedAt": chrono::Utc::now().to_rfc3339()})).into_response(),
+        Ok(false) => not_found(&name),
+        Err(e) => error_response(ErrorCode::InternalError, e.to_string()),
+    }
+}
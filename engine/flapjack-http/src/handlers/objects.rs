@@ -1,20 +1,216 @@
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
-    Json,
+    Extension, Json,
 };
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use super::AppState;
+use crate::auth::{AttributeRestrictions, IndexScope};
 use crate::dto::{
     AddDocumentsRequest, AddDocumentsResponse, BatchOperation, DeleteByQueryRequest,
-    GetObjectsRequest, GetObjectsResponse,
+    GetObjectsRequest, GetObjectsResponse, RejectedObject,
 };
 use crate::filter_parser::parse_filter;
 use crate::pause_registry::check_not_paused;
 use flapjack::error::FlapjackError;
+use flapjack::index::document_schema::DocumentSchema;
+use flapjack::index::duplicates::{
+    DuplicateDetection, DuplicatePolicy, Fingerprint, DUPLICATE_OF_ATTRIBUTE,
+};
+use flapjack::index::ingest_transform::IngestTransform;
 use flapjack::types::{Document, FieldValue};
 
+/// An index's `documentSchema` and `duplicateDetection`, applied to records
+/// before they are queued so rejections can be reported in the response.
+struct RecordCheck {
+    schema: Option<DocumentSchema>,
+    duplicates: Option<DuplicateDetection>,
+    transforms: Vec<IngestTransform>,
+}
+
+/// Indexed records fetched per duplicate lookup.
+const DUPLICATE_CANDIDATES: usize = 20;
+
+impl RecordCheck {
+    fn for_index(state: &AppState, index_name: &str) -> Option<Self> {
+        let settings = state.manager.get_settings(index_name)?;
+        let schema = settings.compiled_document_schema().unwrap_or_else(|e| {
+            tracing::warn!("[{}] documentSchema not enforced: {}", index_name, e);
+            None
+        });
+        if schema.is_none() && settings.duplicate_detection.is_none() {
+            return None;
+        }
+        Some(RecordCheck {
+            schema,
+            duplicates: settings.duplicate_detection.clone(),
+            transforms: settings.ingest_transforms.clone(),
+        })
+    }
+
+    /// `record` as it will be indexed.
+    fn prepared(
+        &self,
+        record: &serde_json::Map<String, serde_json::Value>,
+    ) -> serde_json::Map<String, serde_json::Value> {
+        let mut record = record.clone();
+        record.remove("_id");
+        record.remove("objectID");
+        for transform in &self.transforms {
+            transform.apply(&mut record);
+        }
+        record
+    }
+
+    /// Schema violations of `record` as it will be indexed.
+    fn errors(&self, record: &serde_json::Map<String, serde_json::Value>) -> Vec<String> {
+        match &self.schema {
+            Some(schema) => schema.validate_record(&self.prepared(record)),
+            None => Vec::new(),
+        }
+    }
+
+    fn check(
+        check: &Option<RecordCheck>,
+        object_id: &str,
+        record: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<(), FlapjackError> {
+        let Some(check) = check else {
+            return Ok(());
+        };
+        let errors = check.errors(record);
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(FlapjackError::InvalidDocument(format!(
+            "Record '{}' does not match documentSchema: {}",
+            object_id,
+            errors.join("; ")
+        )))
+    }
+
+    /// Apply the duplicate policy to `documents`, in order, comparing each
+    /// with indexed records and the earlier documents. Records replaced
+    /// under `overwrite` are added to `deletes`; rejected records are
+    /// removed from `documents` and returned.
+    fn resolve_duplicates(
+        &self,
+        state: &AppState,
+        index_name: &str,
+        documents: &mut Vec<Document>,
+        deletes: &mut Vec<String>,
+    ) -> Vec<RejectedObject> {
+        let Some(detection) = &self.duplicates else {
+            return Vec::new();
+        };
+        let mut rejected = Vec::new();
+        let mut kept: Vec<(Document, Option<Fingerprint>)> = Vec::new();
+        for mut doc in documents.drain(..) {
+            doc.fields.remove(DUPLICATE_OF_ATTRIBUTE);
+            let Some(fingerprint) = detection.fingerprint(&self.prepared(&document_record(&doc)))
+            else {
+                kept.push((doc, None));
+                continue;
+            };
+            let duplicate_of = kept
+                .iter()
+                .find(|(other, other_fp)| {
+                    other.id != doc.id
+                        && other_fp.is_some_and(|fp| detection.is_duplicate(&fp, &fingerprint))
+                })
+                .map(|(other, _)| other.id.clone())
+                .or_else(|| indexed_duplicate(state, index_name, detection, &doc.id, &fingerprint));
+            match (duplicate_of, detection.policy) {
+                (None, _) => {}
+                (Some(original), DuplicatePolicy::Reject) => {
+                    rejected.push(RejectedObject {
+                        object_id: doc.id,
+                        errors: vec![format!("duplicate of '{}'", original)],
+                    });
+                    continue;
+                }
+                (Some(original), DuplicatePolicy::Overwrite) => {
+                    kept.retain(|(other, _)| other.id != original);
+                    deletes.push(original);
+                }
+                (Some(original), DuplicatePolicy::Tag) => {
+                    doc.fields.insert(
+                        DUPLICATE_OF_ATTRIBUTE.to_string(),
+                        FieldValue::Text(original),
+                    );
+                }
+            }
+            kept.push((doc, Some(fingerprint)));
+        }
+        documents.extend(kept.into_iter().map(|(doc, _)| doc));
+        rejected
+    }
+
+    /// `resolve_duplicates` for a single-record write: the documents to
+    /// write and the records to delete, or an error if the record is
+    /// rejected.
+    fn resolve_duplicate(
+        check: &Option<RecordCheck>,
+        state: &AppState,
+        index_name: &str,
+        doc: Document,
+    ) -> Result<(Vec<Document>, Vec<String>), FlapjackError> {
+        let mut documents = vec![doc];
+        let mut deletes = Vec::new();
+        let Some(check) = check else {
+            return Ok((documents, deletes));
+        };
+        let rejected = check.resolve_duplicates(state, index_name, &mut documents, &mut deletes);
+        if let Some(rejection) = rejected.first() {
+            return Err(FlapjackError::InvalidDocument(format!(
+                "Record '{}' rejected: {}",
+                rejection.object_id,
+                rejection.errors.join("; ")
+            )));
+        }
+        Ok((documents, deletes))
+    }
+}
+
+/// An indexed record other than `object_id` that duplicates `fingerprint`.
+fn indexed_duplicate(
+    state: &AppState,
+    index_name: &str,
+    detection: &DuplicateDetection,
+    object_id: &str,
+    fingerprint: &Fingerprint,
+) -> Option<String> {
+    let filter = detection.candidate_filter(fingerprint);
+    let search = state
+        .manager
+        .search(index_name, "", Some(&filter), None, DUPLICATE_CANDIDATES);
+    let candidates = match search {
+        Ok(result) => result.documents,
+        Err(e) => {
+            tracing::warn!("[{}] duplicate lookup failed: {}", index_name, e);
+            return None;
+        }
+    };
+    candidates
+        .into_iter()
+        .map(|scored| scored.document)
+        .filter(|doc| doc.id != object_id)
+        .find(|doc| {
+            Fingerprint::from_document(doc)
+                .is_some_and(|fp| detection.is_duplicate(&fp, fingerprint))
+        })
+        .map(|doc| doc.id)
+}
+
+fn document_record(doc: &Document) -> serde_json::Map<String, serde_json::Value> {
+    match doc.to_json() {
+        serde_json::Value::Object(map) => map,
+        _ => serde_json::Map::new(),
+    }
+}
+
 /// Apply a built-in partial update operation (Increment, Decrement, Add, Remove, AddUnique).
 /// Returns the new FieldValue for the field, or None if the operation is invalid.
 fn apply_operation(
@@ -202,6 +398,19 @@ pub async fn add_documents_batch_impl(
     let mut documents = Vec::new();
     let mut deletes = Vec::new();
     let mut explicit_delete_count: u64 = 0;
+    let mut rejected = Vec::new();
+    let check = RecordCheck::for_index(&state, &index_name);
+    let mut rejects = |object_id: &str, record: &serde_json::Map<String, serde_json::Value>| {
+        let errors = check.as_ref().map(|c| c.errors(record)).unwrap_or_default();
+        if errors.is_empty() {
+            return false;
+        }
+        rejected.push(RejectedObject {
+            object_id: object_id.to_string(),
+            errors,
+        });
+        true
+    };
 
     let operations = match req {
         AddDocumentsRequest::Batch { requests } => requests,
@@ -257,8 +466,6 @@ pub async fn add_documents_batch_impl(
                     })?
                     .to_string();
 
-                object_ids.push(object_id.clone());
-
                 let create_if_not_exists = if op.action == "partialUpdateObjectNoCreate" {
                     false
                 } else {
@@ -266,17 +473,22 @@ pub async fn add_documents_batch_impl(
                 };
 
                 let existing = state.manager.get_document(&index_name, &object_id)?;
-                if existing.is_some() {
-                    deletes.push(object_id.clone());
-                }
+                let existed = existing.is_some();
 
                 let body_map: serde_json::Map<String, serde_json::Value> =
                     op.body.into_iter().collect();
-                if let Some(doc) =
-                    merge_partial_update(existing, &object_id, &body_map, create_if_not_exists)?
-                {
-                    documents.push(doc);
+                let merged =
+                    merge_partial_update(existing, &object_id, &body_map, create_if_not_exists)?;
+                if let Some(doc) = &merged {
+                    if rejects(&object_id, &document_record(doc)) {
+                        continue;
+                    }
+                }
+                object_ids.push(object_id.clone());
+                if existed {
+                    deletes.push(object_id.clone());
                 }
+                documents.extend(merged);
             }
             "updateObject" => {
                 let object_id = op
@@ -289,8 +501,6 @@ pub async fn add_documents_batch_impl(
                     })?
                     .to_string();
 
-                object_ids.push(object_id.clone());
-
                 let mut doc_map = op.body;
                 doc_map.remove("objectID");
                 doc_map.remove("id");
@@ -303,6 +513,10 @@ pub async fn add_documents_batch_impl(
                 for (k, v) in doc_map {
                     json_obj.insert(k, v);
                 }
+                if rejects(&object_id, &json_obj) {
+                    continue;
+                }
+                object_ids.push(object_id.clone());
 
                 let document = Document::from_json(&serde_json::Value::Object(json_obj))?;
                 documents.push(document);
@@ -315,13 +529,15 @@ pub async fn add_documents_batch_impl(
                     .and_then(|v| v.as_str().map(String::from))
                     .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
 
-                object_ids.push(id.clone());
-
                 let mut json_obj = serde_json::Map::new();
                 json_obj.insert("_id".to_string(), serde_json::Value::String(id.clone()));
                 for (k, v) in doc_map {
                     json_obj.insert(k, v);
                 }
+                if rejects(&id, &json_obj) {
+                    continue;
+                }
+                object_ids.push(id.clone());
 
                 let document = Document::from_json(&serde_json::Value::Object(json_obj))?;
                 documents.push(document);
@@ -335,6 +551,16 @@ pub async fn add_documents_batch_impl(
         }
     }
 
+    if let Some(check) = &check {
+        let duplicates =
+            check.resolve_duplicates(&state, &index_name, &mut documents, &mut deletes);
+        let is_rejected = |id: &String| duplicates.iter().any(|r| &r.object_id == id);
+        object_ids.retain(|id| !is_rejected(id));
+        // A rejected partial update leaves the existing record in place.
+        deletes.retain(|id| !is_rejected(id));
+        rejected.extend(duplicates);
+    }
+
     // Capture oplog seq before write so we can replicate only the new ops.
     let pre_seq = state
         .manager
@@ -363,6 +589,7 @@ pub async fn add_documents_batch_impl(
         return Ok(Json(AddDocumentsResponse::Algolia {
             task_id: noop.numeric_id,
             object_ids,
+            rejected,
         }));
     } else if !deletes.is_empty() {
         // Batch has explicit deletes (e.g. partialUpdateObject) — delete first, then add
@@ -403,6 +630,7 @@ pub async fn add_documents_batch_impl(
     Ok(Json(AddDocumentsResponse::Algolia {
         task_id: task.numeric_id,
         object_ids,
+        rejected,
     }))
 }
 
@@ -499,11 +727,22 @@ pub async fn add_documents(
         id: id.clone(),
         fields,
     };
-    let task = state.manager.add_documents(&index_name, vec![document])?;
+    let check = RecordCheck::for_index(&state, &index_name);
+    RecordCheck::check(&check, &id, &document_record(&document))?;
+    let (documents, replaced) =
+        RecordCheck::resolve_duplicate(&check, &state, &index_name, document)?;
+    if !replaced.is_empty() {
+        state
+            .manager
+            .delete_documents_sync(&index_name, replaced)
+            .await?;
+    }
+    let task = state.manager.add_documents(&index_name, documents)?;
 
     Ok(Json(AddDocumentsResponse::Algolia {
         task_id: task.numeric_id,
         object_ids: vec![id],
+        rejected: Vec::new(),
     }))
 }
 
@@ -527,6 +766,7 @@ pub async fn add_documents(
 pub async fn get_object(
     State(state): State<Arc<AppState>>,
     Path((index_name, object_id)): Path<(String, String)>,
+    attributes: Option<Extension<AttributeRestrictions>>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
     let doc = state
         .manager
@@ -548,6 +788,9 @@ pub async fn get_object(
             for (key, value) in document.fields {
                 obj.insert(key, field_value_to_json(&value));
             }
+            if let Some(Extension(attributes)) = attributes {
+                attributes.strip(&mut obj);
+            }
 
             Ok(Json(serde_json::Value::Object(obj)))
         }
@@ -639,8 +882,13 @@ pub async fn put_object(
     for (k, v) in body {
         json_obj.insert(k, v);
     }
+    let check = RecordCheck::for_index(&state, &index_name);
+    RecordCheck::check(&check, &object_id, &json_obj)?;
 
     let document = Document::from_json(&serde_json::Value::Object(json_obj))?;
+    let (documents, mut deletes) =
+        RecordCheck::resolve_duplicate(&check, &state, &index_name, document)?;
+    deletes.push(object_id.clone());
 
     let pre_seq = state
         .manager
@@ -649,11 +897,11 @@ pub async fn put_object(
         .unwrap_or(0);
     state
         .manager
-        .delete_documents_sync(&index_name, vec![object_id.clone()])
+        .delete_documents_sync(&index_name, deletes)
         .await?;
     state
         .manager
-        .add_documents_sync(&index_name, vec![document])
+        .add_documents_sync(&index_name, documents)
         .await?;
     trigger_replication(&state, &index_name, pre_seq, false);
 
@@ -673,17 +921,21 @@ pub async fn put_object(
     })))
 }
 
+/// Most objects one getObjects call may request, as on Algolia.
+pub const MAX_GET_OBJECTS: usize = 1000;
+
 /// Get multiple objects by ID in batch
 #[utoipa::path(
     post,
     path = "/1/indexes/{indexName}/objects",
     tag = "documents",
     params(
-        ("indexName" = String, Path, description = "Index name")
+        ("indexName" = String, Path, description = "Index name, or `*`: each request names its own index")
     ),
     request_body = GetObjectsRequest,
     responses(
-        (status = 200, description = "Objects retrieved successfully", body = GetObjectsResponse)
+        (status = 200, description = "Objects in request order, null for those not found", body = GetObjectsResponse),
+        (status = 400, description = "More than 1000 requests")
     ),
     security(
         ("api_key" = [])
@@ -691,43 +943,81 @@ pub async fn put_object(
 )]
 pub async fn get_objects(
     State(state): State<Arc<AppState>>,
+    attributes: Option<Extension<AttributeRestrictions>>,
+    scope: Option<Extension<IndexScope>>,
     Json(req): Json<GetObjectsRequest>,
 ) -> Result<Json<GetObjectsResponse>, FlapjackError> {
-    let mut results = Vec::new();
+    if req.requests.len() > MAX_GET_OBJECTS {
+        return Err(FlapjackError::InvalidQuery(format!(
+            "getObjects takes at most {} requests, got {}",
+            MAX_GET_OBJECTS,
+            req.requests.len()
+        )));
+    }
 
-    for request in req.requests {
-        match state
-            .manager
-            .get_document(&request.index_name, &request.object_id)
-        {
-            Ok(Some(document)) => {
-                let mut obj = serde_json::Map::new();
-                obj.insert(
-                    "objectID".to_string(),
-                    serde_json::Value::String(document.id),
-                );
+    // One read per index. Indexes the key may not read, or that are missing
+    // or in maintenance, find nothing rather than failing the whole call.
+    let mut ids_by_index: HashMap<&str, Vec<String>> = HashMap::new();
+    for request in &req.requests {
+        ids_by_index
+            .entry(request.index_name.as_str())
+            .or_default()
+            .push(request.object_id.clone());
+    }
+    let mut found: HashMap<&str, HashMap<String, Document>> = HashMap::new();
+    let mut index_settings = HashMap::new();
+    for (index_name, ids) in ids_by_index {
+        let readable = scope
+            .as_ref()
+            .is_none_or(|Extension(scope)| scope.allows(index_name))
+            && crate::maintenance::check_readable(&state.manager.base_path, index_name).is_ok();
+        if !readable {
+            continue;
+        }
+        if let Ok(documents) = state.manager.get_documents(index_name, &ids) {
+            found.insert(index_name, documents);
+            index_settings.insert(index_name, state.manager.get_settings(index_name));
+        }
+    }
 
-                for (key, value) in document.fields {
-                    if let Some(attrs) = &request.attributes_to_retrieve {
-                        if !attrs.contains(&key) {
-                            continue;
-                        }
-                    }
-                    obj.insert(key, field_value_to_json(&value));
-                }
+    let mut results = Vec::with_capacity(req.requests.len());
+    let mut missing = Vec::new();
+    for request in &req.requests {
+        let index_name = request.index_name.as_str();
+        let Some(document) = found
+            .get(index_name)
+            .and_then(|documents| documents.get(&request.object_id))
+        else {
+            missing.push(request.object_id.as_str());
+            results.push(serde_json::Value::Null);
+            continue;
+        };
 
-                results.push(serde_json::Value::Object(obj));
-            }
-            Ok(None) => {
-                results.push(serde_json::Value::Null);
-            }
-            Err(_) => {
-                results.push(serde_json::Value::Null);
+        let mut obj = serde_json::Map::new();
+        obj.insert(
+            "objectID".to_string(),
+            serde_json::Value::String(document.id.clone()),
+        );
+        for (key, value) in &document.fields {
+            if let Some(attrs) = &request.attributes_to_retrieve {
+                if !attrs.contains(key) && !attrs.iter().any(|a| a == "*") {
+                    continue;
+                }
             }
+            obj.insert(key.clone(), field_value_to_json(value));
+        }
+        if let Some(Some(settings)) = index_settings.get(index_name) {
+            settings.strip_unretrievable(&mut obj);
+        }
+        if let Some(Extension(ref attributes)) = attributes {
+            attributes.strip(&mut obj);
         }
+        results.push(serde_json::Value::Object(obj));
     }
 
-    Ok(Json(GetObjectsResponse { results }))
+    let message =
+        (!missing.is_empty()).then(|| format!("ObjectID does not exist: {}", missing.join(", ")));
+    Ok(Json(GetObjectsResponse { results, message }))
 }
 
 /// Delete objects matching a filter query
@@ -870,14 +1160,24 @@ pub async fn add_record_auto_id(
     for (k, v) in body {
         json_obj.insert(k, v);
     }
+    let check = RecordCheck::for_index(&state, &index_name);
+    RecordCheck::check(&check, &generated_id, &json_obj)?;
 
     let document = Document::from_json(&serde_json::Value::Object(json_obj))?;
+    let (documents, replaced) =
+        RecordCheck::resolve_duplicate(&check, &state, &index_name, document)?;
     let pre_seq = state
         .manager
         .get_oplog(&index_name)
         .map(|ol| ol.current_seq())
         .unwrap_or(0);
-    let task = state.manager.add_documents(&index_name, vec![document])?;
+    if !replaced.is_empty() {
+        state
+            .manager
+            .delete_documents_sync(&index_name, replaced)
+            .await?;
+    }
+    let task = state.manager.add_documents(&index_name, documents)?;
     trigger_replication(&state, &index_name, pre_seq, true);
 
     // Increment usage counter: 1 document indexed (auto-id create)
@@ -924,6 +1224,19 @@ pub async fn partial_update_object(
 
     let create_if_not_exists = params.create_if_not_exists.unwrap_or(true);
     let existing = state.manager.get_document(&index_name, &object_id)?;
+    let existed = existing.is_some();
+    let merged = merge_partial_update(existing, &object_id, &body, create_if_not_exists)?;
+    let (documents, mut deletes) = match merged {
+        Some(doc) => {
+            let check = RecordCheck::for_index(&state, &index_name);
+            RecordCheck::check(&check, &object_id, &document_record(&doc))?;
+            RecordCheck::resolve_duplicate(&check, &state, &index_name, doc)?
+        }
+        None => (Vec::new(), Vec::new()),
+    };
+    if existed {
+        deletes.push(object_id.clone());
+    }
 
     let pre_seq = state
         .manager
@@ -931,17 +1244,17 @@ pub async fn partial_update_object(
         .map(|ol| ol.current_seq())
         .unwrap_or(0);
 
-    if existing.is_some() {
+    if !deletes.is_empty() {
         state
             .manager
-            .delete_documents_sync(&index_name, vec![object_id.clone()])
+            .delete_documents_sync(&index_name, deletes)
             .await?;
     }
 
-    if let Some(doc) = merge_partial_update(existing, &object_id, &body, create_if_not_exists)? {
+    if !documents.is_empty() {
         state
             .manager
-            .add_documents_sync(&index_name, vec![doc])
+            .add_documents_sync(&index_name, documents)
             .await?;
     }
 
@@ -1522,4 +1835,167 @@ mod tests {
             "writes to 'bar' should NOT be blocked when only 'foo' is paused; got 503"
         );
     }
+
+    // ── documentSchema enforcement ──────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_batch_rejects_records_that_break_document_schema() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_write_guard_state(&tmp);
+        state.manager.create_tenant("products").unwrap();
+        let settings: flapjack::index::settings::IndexSettings =
+            serde_json::from_value(serde_json::json!({
+                "ingestTransforms": [{"type": "rename", "from": "cost", "to": "price"}],
+                "documentSchema": {
+                    "type": "object",
+                    "required": ["price"],
+                    "properties": {"price": {"type": "number"}}
+                }
+            }))
+            .unwrap();
+        settings
+            .save(tmp.path().join("products/settings.json"))
+            .unwrap();
+        let app = make_write_guard_app(state.clone());
+
+        let batch = serde_json::json!({"requests": [
+            {"action": "addObject", "body": {"objectID": "ok", "cost": 12.5}},
+            {"action": "addObject", "body": {"objectID": "bad", "cost": "12.50"}},
+            {"action": "updateObject", "body": {"objectID": "missing", "name": "x"}}
+        ]});
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/1/indexes/products/batch")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(batch.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["objectIDs"], serde_json::json!(["ok"]));
+        assert_eq!(
+            json["rejected"],
+            serde_json::json!([
+                {"objectID": "bad", "errors": ["/price: expected number, got string"]},
+                {"objectID": "missing", "errors": ["/price: required"]}
+            ])
+        );
+
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri("/1/indexes/products/bad")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(r#"{"cost": "free"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert!(state
+            .manager
+            .get_document("products", "bad")
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_policies_on_write() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_write_guard_state(&tmp);
+        state.manager.create_tenant("products").unwrap();
+        let save_policy = |policy: &str| {
+            let settings: flapjack::index::settings::IndexSettings =
+                serde_json::from_value(serde_json::json!({
+                    "duplicateDetection": {"attributes": ["title"], "policy": policy}
+                }))
+                .unwrap();
+            settings
+                .save(tmp.path().join("products/settings.json"))
+                .unwrap();
+            state.manager.invalidate_settings_cache("products");
+        };
+        save_policy("reject");
+        let original = Document::from_json(&serde_json::json!({
+            "_id": "a", "title": "Trail Runner 2", "feed": "one"
+        }))
+        .unwrap();
+        state
+            .manager
+            .add_documents_sync("products", vec![original])
+            .await
+            .unwrap();
+        let app = make_write_guard_app(state.clone());
+
+        let batch = serde_json::json!({"requests": [
+            {"action": "addObject", "body": {"objectID": "b", "title": "trail runner-2"}},
+            {"action": "addObject", "body": {"objectID": "c", "title": "Desk lamp"}},
+            {"action": "addObject", "body": {"objectID": "d", "title": "DESK LAMP"}},
+            {"action": "updateObject", "body": {"objectID": "a", "title": "Trail Runner 2"}}
+        ]});
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/1/indexes/products/batch")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(batch.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["objectIDs"], serde_json::json!(["c", "a"]));
+        assert_eq!(
+            json["rejected"],
+            serde_json::json!([
+                {"objectID": "b", "errors": ["duplicate of 'a'"]},
+                {"objectID": "d", "errors": ["duplicate of 'c'"]}
+            ])
+        );
+
+        save_policy("tag");
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri("/1/indexes/products/e")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(r#"{"title": "Trail runner 2!"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let tagged = state
+            .manager
+            .get_document("products", "e")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            tagged.fields.get(DUPLICATE_OF_ATTRIBUTE),
+            Some(&FieldValue::Text("a".to_string()))
+        );
+        assert!(Fingerprint::from_document(&tagged).is_some());
+
+        let report = state.manager.duplicate_report("products").unwrap();
+        assert_eq!(report.nb_records, 3);
+        assert_eq!(report.nb_clusters, 1);
+        assert_eq!(report.clusters[0].object_ids, vec!["a", "e"]);
+        assert!(report.clusters[0].exact);
+    }
 }
@@ -1,12 +1,20 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     Json,
 };
-use flapjack::query_suggestions::{build_suggestions_index, QsConfig, QsConfigStore};
+use flapjack::query_suggestions::{build_suggestions_index, LogEntry, QsConfig, QsConfigStore};
+use flapjack_replication::manager::ReplicationManager;
+use futures_util::stream::{self, StreamExt};
+use serde::Deserialize;
 use serde_json::json;
+use std::convert::Infallible;
 use std::sync::Arc;
+use std::time::Duration;
 
 use super::AppState;
 
@@ -52,6 +60,8 @@ pub async fn create_config(
             .into_response();
     }
 
+    publish_config(&state, &config);
+
     // Mark as running and fire off async build
     let mut status = s.load_status(&config.index_name);
     status.is_running = true;
@@ -116,6 +126,8 @@ pub async fn update_config(
             .into_response();
     }
 
+    publish_config(&state, &config);
+
     // Guard against concurrent builds: two simultaneous builds on the same staging
     // index would corrupt each other (both writing to {indexName}__building).
     let status = s.load_status(&config.index_name);
@@ -149,14 +161,19 @@ pub async fn delete_config(
     Path(index_name): Path<String>,
 ) -> impl IntoResponse {
     match store(&state).delete_config(&index_name) {
-        Ok(true) => (
-            StatusCode::OK,
-            Json(json!({
-                "status": 200,
-                "message": "Configuration was deleted with success."
-            })),
-        )
-            .into_response(),
+        Ok(true) => {
+            if let Some(repl) = &state.replication_manager {
+                repl.broadcast_qs_config_deletion(&index_name);
+            }
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": 200,
+                    "message": "Configuration was deleted with success."
+                })),
+            )
+                .into_response()
+        }
         Ok(false) => (
             StatusCode::NOT_FOUND,
             Json(json!({"message": format!("No configuration found for '{}'.", index_name)})),
@@ -186,13 +203,110 @@ pub async fn get_status(
     Json(json!(status)).into_response()
 }
 
+/// How often a followed build log is re-read for new entries.
+const LOG_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Longest `wait` a long-poll on the build log may ask for, in seconds.
+const MAX_LOG_WAIT_SECS: u64 = 60;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogsParams {
+    /// Only return entries with a `seq` above this one.
+    #[serde(default)]
+    pub since: Option<u64>,
+    /// Seconds to wait for new entries while a build is running.
+    #[serde(default)]
+    pub wait: Option<u64>,
+}
+
+fn read_logs(store: &QsConfigStore, index_name: &str, since: Option<u64>) -> Vec<LogEntry> {
+    match since {
+        Some(since) => store.read_logs_since(index_name, since),
+        None => store.read_logs(index_name),
+    }
+}
+
 /// GET /1/logs/:indexName — build logs
+///
+/// `since` and `wait` turn this into a long-poll: the response is held until
+/// entries newer than `since` are written, the build stops or `wait` seconds
+/// pass. With `Accept: text/event-stream` the log is streamed instead, one
+/// `log` event per entry (its `seq` as the event id) and a final `done`
+/// event carrying the build status once the build is over.
 pub async fn get_logs(
     State(state): State<Arc<AppState>>,
     Path(index_name): Path<String>,
-) -> impl IntoResponse {
-    let logs = store(&state).read_logs(&index_name);
-    Json(json!(logs)).into_response()
+    Query(params): Query<LogsParams>,
+    headers: HeaderMap,
+) -> Response {
+    let s = store(&state);
+
+    let wants_stream = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/event-stream"));
+    if wants_stream {
+        // A reconnecting EventSource resends the original URL, so the last
+        // event it saw is the more recent position.
+        let last_event_id = headers
+            .get("last-event-id")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok());
+        return stream_logs(s, index_name, last_event_id.or(params.since));
+    }
+
+    let wait = Duration::from_secs(params.wait.unwrap_or(0).min(MAX_LOG_WAIT_SECS));
+    let deadline = tokio::time::Instant::now() + wait;
+    loop {
+        let running = s.load_status(&index_name).is_running;
+        let logs = read_logs(&s, &index_name, params.since);
+        if !logs.is_empty() || !running || tokio::time::Instant::now() >= deadline {
+            return Json(json!(logs)).into_response();
+        }
+        tokio::time::sleep(LOG_POLL_INTERVAL).await;
+    }
+}
+
+/// Follow the build log as server-sent events until the build is over.
+fn stream_logs(store: QsConfigStore, index_name: String, since: Option<u64>) -> Response {
+    let events = stream::unfold(Some((store, index_name, since)), |follow| async move {
+        let (store, index_name, mut since) = follow?;
+        loop {
+            // Status before entries: whatever a finished build logged is read
+            // below, so `done` never goes out ahead of its entries.
+            let status = store.load_status(&index_name);
+            let entries = read_logs(&store, &index_name, since);
+            let mut events: Vec<Event> = vec![];
+            for entry in &entries {
+                if let Ok(event) = Event::default()
+                    .event("log")
+                    .id(entry.seq.to_string())
+                    .json_data(entry)
+                {
+                    events.push(event);
+                }
+            }
+            if let Some(last) = entries.last() {
+                since = Some(last.seq);
+            }
+            if !status.is_running {
+                if let Ok(event) = Event::default().event("done").json_data(&status) {
+                    events.push(event);
+                }
+                return Some((events, None));
+            }
+            if !events.is_empty() {
+                return Some((events, Some((store, index_name, since))));
+            }
+            tokio::time::sleep(LOG_POLL_INTERVAL).await;
+        }
+    })
+    .flat_map(|events| stream::iter(events.into_iter().map(Ok::<_, Infallible>)));
+
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
 }
 
 /// POST /1/configs/:indexName/build — trigger an immediate rebuild (Flapjack extension)
@@ -242,8 +356,59 @@ pub async fn trigger_build(
         .into_response()
 }
 
-/// Spawn a background build task.
+/// Push a configuration change to replication peers.
+fn publish_config(state: &AppState, config: &QsConfig) {
+    if let Some(repl) = &state.replication_manager {
+        repl.broadcast_qs_config(config.clone());
+    }
+}
+
+/// Start a build. In a cluster only the primary node builds (from its
+/// analytics) and ships the result to the other nodes, so every node serves
+/// the same suggestions; other nodes hand the build to the primary.
 fn spawn_build(state: Arc<AppState>, config: QsConfig) {
+    let primary = match &state.replication_manager {
+        Some(repl) if !repl.is_primary() => repl.peer(&repl.primary_node_id()),
+        _ => None,
+    };
+    let Some(primary) = primary else {
+        spawn_local_build(state, config);
+        return;
+    };
+
+    tokio::spawn(async move {
+        let store = store(&state);
+        let result = primary.trigger_qs_build(&config.index_name).await;
+        let message = match &result {
+            Ok(()) => format!("Build delegated to primary node {}", primary.peer_id()),
+            Err(e) => format!("Primary unreachable, building locally: {}", e),
+        };
+        tracing::info!("[query-suggestions] '{}': {}", config.index_name, message);
+        store
+            .append_log(
+                &config.index_name,
+                &[LogEntry {
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                    level: "INFO".to_string(),
+                    message,
+                    context_level: 1,
+                    ..Default::default()
+                }],
+            )
+            .ok();
+
+        if result.is_ok() {
+            let mut status = store.load_status(&config.index_name);
+            status.is_running = false;
+            store.save_status(&status).ok();
+        } else {
+            spawn_local_build(state, config);
+        }
+    });
+}
+
+/// Build on this node and ship the result to replication peers.
+pub(crate) fn spawn_local_build(state: Arc<AppState>, config: QsConfig) {
     let manager = Arc::clone(&state.manager);
     let analytics_engine = state.analytics_engine.clone();
     let base_path = state.manager.base_path.clone();
@@ -266,21 +431,64 @@ fn spawn_build(state: Arc<AppState>, config: QsConfig) {
         };
 
         match build_suggestions_index(&config, &store, &manager, &engine).await {
-            Ok(count) => tracing::info!(
-                "[query-suggestions] Build complete for '{}': {} suggestions",
-                config.index_name,
-                count
-            ),
+            Ok(count) => {
+                tracing::info!(
+                    "[query-suggestions] Build complete for '{}': {} suggestions",
+                    config.index_name,
+                    count
+                );
+                if let Some(repl) = &state.replication_manager {
+                    ship_to_peers(repl, &base_path, &config.index_name).await;
+                }
+            }
             Err(e) => {
                 tracing::error!(
                     "[query-suggestions] Build failed for '{}': {}",
                     config.index_name,
                     e
                 );
+                store
+                    .append_log(
+                        &config.index_name,
+                        &[LogEntry {
+                            timestamp: chrono::Utc::now().to_rfc3339(),
+                            level: "ERROR".to_string(),
+                            message: format!("Build failed: {}", e),
+                            context_level: 0,
+                            ..Default::default()
+                        }],
+                    )
+                    .ok();
                 let mut status = store.load_status(&config.index_name);
                 status.is_running = false;
+                status.phase = None;
                 store.save_status(&status).ok();
             }
         }
     });
 }
+
+async fn ship_to_peers(repl: &ReplicationManager, base_path: &std::path::Path, index_name: &str) {
+    if repl.peer_count() == 0 {
+        return;
+    }
+    let bytes = match flapjack::index::snapshot::export_to_bytes(&base_path.join(index_name)) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!(
+                "[query-suggestions] Export of '{}' for peers failed: {}",
+                index_name,
+                e
+            );
+            return;
+        }
+    };
+    let failed = repl.ship_suggestions(index_name, bytes).await;
+    if !failed.is_empty() {
+        tracing::warn!(
+            "[query-suggestions] '{}' not shipped to {} (kept until next build)",
+            index_name,
+            failed.join(", ")
+        );
+    }
+}
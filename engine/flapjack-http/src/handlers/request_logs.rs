@@ -0,0 +1,42 @@
+use axum::{extract::Query, response::IntoResponse, Json};
+use flapjack::ErrorCode;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::error_codes::error_response;
+use crate::request_log::{LogType, RequestLog, DEFAULT_PAGE_LENGTH, MAX_PAGE_LENGTH};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogsParams {
+    pub offset: Option<usize>,
+    pub length: Option<usize>,
+    #[serde(rename = "type")]
+    pub log_type: Option<String>,
+    pub index_name: Option<String>,
+}
+
+/// GET /1/logs — recent API requests, newest first (Algolia logs API)
+pub async fn get_request_logs(Query(params): Query<LogsParams>) -> impl IntoResponse {
+    let log_type = match params.log_type.as_deref() {
+        None => LogType::All,
+        Some(t) => match LogType::parse(t) {
+            Some(t) => t,
+            None => {
+                let message = format!("Invalid type '{}': expected all, query, build or error", t);
+                return error_response(ErrorCode::BadRequest, message);
+            }
+        },
+    };
+    let length = params
+        .length
+        .unwrap_or(DEFAULT_PAGE_LENGTH)
+        .min(MAX_PAGE_LENGTH);
+    let logs = RequestLog::global().query(
+        params.offset.unwrap_or(0),
+        length,
+        log_type,
+        params.index_name.as_deref(),
+    );
+    Json(json!({ "logs": logs })).into_response()
+}
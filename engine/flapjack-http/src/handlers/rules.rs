@@ -347,3 +347,57 @@ pub async fn search_rules(
         "nbPages": nb_pages
     })))
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleStatsParams {
+    #[serde(default)]
+    pub start_date: Option<String>,
+    #[serde(default)]
+    pub end_date: Option<String>,
+}
+
+/// How often a rule fired, with click-through and conversion rates
+#[utoipa::path(
+    get,
+    path = "/1/indexes/{indexName}/rules/{objectID}/stats",
+    tag = "rules",
+    params(
+        ("indexName" = String, Path, description = "Index name"),
+        ("objectID" = String, Path, description = "Rule ID"),
+        ("startDate" = Option<String>, Query, description = "First day (YYYY-MM-DD), default 8 days ago"),
+        ("endDate" = Option<String>, Query, description = "Last day (YYYY-MM-DD), default today")
+    ),
+    responses(
+        (status = 200, description = "Fire counts with daily breakdown", body = serde_json::Value),
+        (status = 503, description = "Analytics is disabled")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn get_rule_stats(
+    State(state): State<Arc<AppState>>,
+    Path((index_name, object_id)): Path<(String, String)>,
+    Query(params): Query<RuleStatsParams>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let engine = state.analytics_engine.as_ref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Analytics is disabled".to_string(),
+    ))?;
+    let now = chrono::Utc::now();
+    let start_date = params.start_date.unwrap_or_else(|| {
+        (now - chrono::Duration::days(8))
+            .format("%Y-%m-%d")
+            .to_string()
+    });
+    let end_date = params
+        .end_date
+        .unwrap_or_else(|| now.format("%Y-%m-%d").to_string());
+
+    engine
+        .rule_stats(&index_name, &object_id, &start_date, &end_date)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Analytics error: {}", e)))
+}
@@ -0,0 +1,175 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use flapjack::ErrorCode;
+use serde_json::json;
+use std::sync::Arc;
+
+use super::AppState;
+use crate::error_codes::error_response;
+use crate::saved_searches::{CreateAlertRequest, CreateSavedSearchRequest, SavedSearchStore};
+
+fn store(state: &AppState) -> SavedSearchStore {
+    SavedSearchStore::new(&state.manager.base_path)
+}
+
+fn io_error(e: std::io::Error) -> axum::response::Response {
+    error_response(ErrorCode::IoError, e.to_string())
+}
+
+fn not_found(kind: &str, id: &str) -> axum::response::Response {
+    error_response(ErrorCode::NotFound, format!("{} '{}' not found", kind, id))
+}
+
+fn analytics_disabled() -> axum::response::Response {
+    error_response(
+        ErrorCode::ServiceUnavailable,
+        "Analytics is disabled on this server",
+    )
+}
+
+/// POST /2/savedSearches — save a named analytics query
+pub async fn create_saved_search(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CreateSavedSearchRequest>,
+) -> impl IntoResponse {
+    let search = match body.into_saved_search(chrono::Utc::now().timestamp_millis()) {
+        Ok(s) => s,
+        Err(message) => return error_response(ErrorCode::BadRequest, message),
+    };
+    match store(&state).insert_search(search.clone()) {
+        Ok(()) => (StatusCode::CREATED, Json(json!(search))).into_response(),
+        Err(e) => io_error(e),
+    }
+}
+
+/// GET /2/savedSearches
+pub async fn list_saved_searches(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match store(&state).list_searches() {
+        Ok(searches) => Json(json!({ "savedSearches": searches })).into_response(),
+        Err(e) => io_error(e),
+    }
+}
+
+/// GET /2/savedSearches/:id
+pub async fn get_saved_search(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match store(&state).get_search(&id) {
+        Ok(Some(search)) => Json(json!(search)).into_response(),
+        Ok(None) => not_found("Saved search", &id),
+        Err(e) => io_error(e),
+    }
+}
+
+/// DELETE /2/savedSearches/:id — also deletes its alerts
+pub async fn delete_saved_search(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match store(&state).delete_search(&id) {
+        Ok(true) => Json(json!({"deletedAt": chrono::Utc::now().to_rfc3339()})).into_response(),
+        Ok(false) => not_found("Saved search", &id),
+        Err(e) => io_error(e),
+    }
+}
+
+/// GET /2/savedSearches/:id/run — the metric over the current window
+pub async fn run_saved_search(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let Some(engine) = state.analytics_engine.as_deref() else {
+        return analytics_disabled();
+    };
+    let search = match store(&state).get_search(&id) {
+        Ok(Some(search)) => search,
+        Ok(None) => return not_found("Saved search", &id),
+        Err(e) => return io_error(e),
+    };
+    let today = chrono::Utc::now().date_naive();
+    let current = search.evaluate(engine, today, 0).await;
+    let previous = search.evaluate(engine, today, 1).await;
+    match (current, previous) {
+        (Ok(value), Ok(previous)) => Json(json!({
+            "savedSearch": search,
+            "value": value,
+            "previousValue": previous,
+        }))
+        .into_response(),
+        (Err(e), _) | (_, Err(e)) => error_response(ErrorCode::BadRequest, e),
+    }
+}
+
+/// POST /2/alerts — alert on a saved search
+pub async fn create_alert(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CreateAlertRequest>,
+) -> impl IntoResponse {
+    let alert = match body.into_alert(chrono::Utc::now().timestamp_millis()) {
+        Ok(a) => a,
+        Err(message) => return error_response(ErrorCode::BadRequest, message),
+    };
+    match store(&state).insert_alert(alert.clone()) {
+        Ok(true) => (StatusCode::CREATED, Json(json!(alert))).into_response(),
+        Ok(false) => not_found("Saved search", &alert.saved_search_id),
+        Err(e) => io_error(e),
+    }
+}
+
+/// GET /2/alerts
+pub async fn list_alerts(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match store(&state).list_alerts() {
+        Ok(alerts) => Json(json!({ "alerts": alerts })).into_response(),
+        Err(e) => io_error(e),
+    }
+}
+
+/// GET /2/alerts/:id
+pub async fn get_alert(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match store(&state).get_alert(&id) {
+        Ok(Some(alert)) => Json(json!(alert)).into_response(),
+        Ok(None) => not_found("Alert", &id),
+        Err(e) => io_error(e),
+    }
+}
+
+/// DELETE /2/alerts/:id
+pub async fn delete_alert(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match store(&state).delete_alert(&id) {
+        Ok(true) => Json(json!({"deletedAt": chrono::Utc::now().to_rfc3339()})).into_response(),
+        Ok(false) => not_found("Alert", &id),
+        Err(e) => io_error(e),
+    }
+}
+
+/// POST /2/alerts/:id/check — check now, notifying as a scheduled check would
+pub async fn check_alert(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let Some(engine) = state.analytics_engine.as_deref() else {
+        return analytics_disabled();
+    };
+    let s = store(&state);
+    let alert = match s.get_alert(&id) {
+        Ok(Some(alert)) => alert,
+        Ok(None) => return not_found("Alert", &id),
+        Err(e) => return io_error(e),
+    };
+    match crate::saved_searches::check_alert(engine, &s, &alert).await {
+        Ok(Some(updated)) => Json(json!(updated)).into_response(),
+        Ok(None) => not_found("Alert", &id),
+        Err(e) => io_error(e),
+    }
+}
@@ -0,0 +1,125 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use flapjack::ErrorCode;
+use serde_json::json;
+use std::sync::Arc;
+
+use super::AppState;
+use crate::error_codes::error_response;
+use crate::scheduler::{
+    publish_schedule, publish_schedule_deletion, CreateScheduleRequest, ScheduleStore,
+};
+
+fn store(state: &AppState) -> ScheduleStore {
+    ScheduleStore::new(&state.manager.base_path)
+}
+
+fn io_error(e: std::io::Error) -> axum::response::Response {
+    error_response(ErrorCode::IoError, e.to_string())
+}
+
+fn not_found(id: &str) -> axum::response::Response {
+    error_response(ErrorCode::NotFound, format!("Schedule '{}' not found", id))
+}
+
+/// POST /1/schedules — create a delayed or recurring operation
+pub async fn create_schedule(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CreateScheduleRequest>,
+) -> impl IntoResponse {
+    let schedule = match body.into_schedule(chrono::Utc::now().timestamp_millis()) {
+        Ok(s) => s,
+        Err(message) => return error_response(ErrorCode::BadRequest, message),
+    };
+    match store(&state).insert(schedule.clone()) {
+        Ok(()) => {
+            publish_schedule(&state, &schedule);
+            (StatusCode::CREATED, Json(json!(schedule))).into_response()
+        }
+        Err(e) => io_error(e),
+    }
+}
+
+/// GET /1/schedules — list all schedules
+pub async fn list_schedules(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match store(&state).list() {
+        Ok(schedules) => Json(json!({ "schedules": schedules })).into_response(),
+        Err(e) => io_error(e),
+    }
+}
+
+/// GET /1/schedules/:id
+pub async fn get_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match store(&state).get(&id) {
+        Ok(Some(schedule)) => Json(json!(schedule)).into_response(),
+        Ok(None) => not_found(&id),
+        Err(e) => io_error(e),
+    }
+}
+
+/// DELETE /1/schedules/:id
+pub async fn delete_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match store(&state).delete(&id) {
+        Ok(true) => {
+            publish_schedule_deletion(&state, &id);
+            Json(json!({"deletedAt": chrono::Utc::now().to_rfc3339()})).into_response()
+        }
+        Ok(false) => not_found(&id),
+        Err(e) => io_error(e),
+    }
+}
+
+async fn set_enabled(state: &AppState, id: &str, enabled: bool) -> axum::response::Response {
+    match store(state).update(id, |s| s.enabled = enabled) {
+        Ok(Some(schedule)) => {
+            publish_schedule(state, &schedule);
+            Json(json!(schedule)).into_response()
+        }
+        Ok(None) => not_found(id),
+        Err(e) => io_error(e),
+    }
+}
+
+/// POST /1/schedules/:id/pause — stop firing until resumed
+pub async fn pause_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    set_enabled(&state, &id, false).await
+}
+
+/// POST /1/schedules/:id/resume
+pub async fn resume_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    set_enabled(&state, &id, true).await
+}
+
+/// POST /1/schedules/:id/run — run immediately without changing the next slot
+pub async fn run_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let s = store(&state);
+    let schedule = match s.get(&id) {
+        Ok(Some(schedule)) => schedule,
+        Ok(None) => return not_found(&id),
+        Err(e) => return io_error(e),
+    };
+    match crate::scheduler::execute_schedule(&state, &s, &schedule).await {
+        Ok(Some(updated)) => Json(json!(updated)).into_response(),
+        Ok(None) => not_found(&id),
+        Err(e) => io_error(e),
+    }
+}
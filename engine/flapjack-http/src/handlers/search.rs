@@ -3,8 +3,9 @@ use axum::{
     Json,
 };
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use flapjack::error::FlapjackError;
 use flapjack::experiments::{
@@ -12,22 +13,42 @@ use flapjack::experiments::{
     assignment::AssignmentMethod,
     config::QueryOverrides,
     interleaving::{team_draft_interleave, Team},
+    shadow::ShadowSample,
 };
+use flapjack::index::namespace::parse_namespaced_tenant;
+use flapjack::index::reranking::{ReRankingModel, ReRankingSettings};
+use flapjack::index::settings::{IndexSettings, SortReplica};
 
 use super::AppState;
+use crate::api_version::ApiVersion;
 use crate::dto::SearchRequest;
+use crate::search_cursor::{index_generation, SearchCursor, Snapshot, SnapshotHit, SnapshotStore};
 use flapjack::query::highlighter::{
     extract_query_words, parse_snippet_spec, HighlightValue, Highlighter, MatchLevel, SnippetValue,
 };
-use flapjack::types::{FacetRequest, FieldValue, Sort, SortOrder};
+use flapjack::types::{
+    FacetRequest, FieldValue, Filter, ScoredDocument, SearchResult, Sort, SortOrder,
+};
 
 use super::field_value_to_json;
 
+/// Queries with fewer hits than this get "did you mean" suggestions.
+const DID_YOU_MEAN_MAX_HITS: usize = 3;
+const DID_YOU_MEAN_MAX_SUGGESTIONS: usize = 3;
+
+/// Shadow queries allowed to run at once; sampled queries beyond this are
+/// not shadowed, so an evaluation never queues up behind live traffic.
+const MAX_SHADOW_IN_FLIGHT: usize = 16;
+static SHADOW_IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
 #[derive(Debug, Clone)]
 struct ExperimentContext {
     experiment_id: String,
     variant_id: String,
     assignment_method: String,
+    /// Session ID or header value the search was assigned by. It stands in
+    /// for a missing userToken in analytics, so arm statistics are per unit.
+    assignment_unit: Option<String>,
     interleaving_variant_index: Option<String>,
     interleaved_teams: Option<HashMap<String, String>>,
 }
@@ -51,6 +72,41 @@ fn extract_analytics_headers(headers: &axum::http::HeaderMap) -> (Option<String>
     (user_token, user_ip)
 }
 
+/// Arm requested by the caller for experiments with `assignmentMode: "external"`.
+fn extract_ab_variant_header(headers: &axum::http::HeaderMap) -> Option<String> {
+    headers
+        .get("x-flapjack-ab-variant")
+        .and_then(|v| v.to_str().ok())
+        .map(String::from)
+}
+
+/// Session ID for experiments with `assignmentUnit: "session"`.
+fn extract_session_header(headers: &axum::http::HeaderMap) -> Option<String> {
+    headers
+        .get("x-flapjack-session-id")
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(String::from)
+}
+
+/// Values of the headers that running experiments assign traffic by.
+fn extract_assignment_headers(
+    state: &AppState,
+    headers: &axum::http::HeaderMap,
+) -> HashMap<String, String> {
+    let Some(store) = state.experiment_store.as_ref() else {
+        return HashMap::new();
+    };
+    store
+        .assignment_headers()
+        .into_iter()
+        .filter_map(|name| {
+            let value = headers.get(name.as_str())?.to_str().ok()?;
+            (!value.is_empty()).then(|| (name, value.to_string()))
+        })
+        .collect()
+}
+
 fn extract_single_geoloc(value: &FieldValue) -> Option<(f64, f64)> {
     match value {
         FieldValue::Object(map) => {
@@ -227,7 +283,9 @@ fn assignment_method_str(method: &AssignmentMethod) -> &'static str {
     match method {
         AssignmentMethod::UserToken => "user_token",
         AssignmentMethod::SessionId => "session_id",
+        AssignmentMethod::Header => "header",
         AssignmentMethod::QueryId => "query_id",
+        AssignmentMethod::External => "external",
     }
 }
 
@@ -259,13 +317,102 @@ fn apply_query_overrides(req: &mut SearchRequest, overrides: &QueryOverrides) {
     if let Some(ref remove_words_if_no_results) = overrides.remove_words_if_no_results {
         req.remove_words_if_no_results = Some(remove_words_if_no_results.clone());
     }
+    if let Some(enable_re_ranking) = overrides.enable_re_ranking {
+        req.enable_re_ranking = Some(enable_re_ranking);
+    }
+    if let Some(ref attribute_weights) = overrides.attribute_weights {
+        req.attribute_weights = Some(attribute_weights.clone());
+    }
 
     if overrides.custom_ranking.is_some() {
         tracing::debug!("skipping custom_ranking query override (index-level only)");
     }
-    if overrides.attribute_weights.is_some() {
-        tracing::debug!("skipping attribute_weights query override (index-level only)");
+}
+
+/// Resolve the re-ranking model for a query. Re-ranking only applies to plain
+/// relevance ordering: an explicit sort, geo ranking, hybrid fusion or
+/// interleaving already own the result order, so `order_is_fixed` skips it.
+fn resolve_reranker(
+    state: &AppState,
+    index_name: &str,
+    req: &SearchRequest,
+    settings: Option<&IndexSettings>,
+    order_is_fixed: bool,
+) -> Option<(Arc<ReRankingModel>, ReRankingSettings)> {
+    let enabled = req
+        .enable_re_ranking
+        .unwrap_or_else(|| settings.is_some_and(|s| s.enable_re_ranking));
+    if !enabled || order_is_fixed {
+        return None;
+    }
+    let config = settings.map(|s| s.re_ranking_config()).unwrap_or_default();
+    // Freshness needs no engagement history, so it works before the first
+    // training run.
+    let model = state
+        .manager
+        .get_reranking_model(index_name)
+        .unwrap_or_default();
+    if model.is_empty() && config.freshness_attribute.is_none() {
+        return None;
+    }
+    Some((model, config))
+}
+
+/// Re-rank an over-fetched result (fetched from offset 0) and slice out the
+/// page starting at `page_start`.
+fn rerank_and_paginate(
+    mut result: flapjack::types::SearchResult,
+    model: &ReRankingModel,
+    config: &ReRankingSettings,
+    page_start: usize,
+    hits_per_page: usize,
+) -> flapjack::types::SearchResult {
+    let now_secs = chrono::Utc::now().timestamp();
+    model.rerank(&mut result.documents, config, now_secs);
+    let page_start = page_start.min(result.documents.len());
+    let page_end = (page_start + hits_per_page).min(result.documents.len());
+    result.documents = result.documents[page_start..page_end].to_vec();
+    result
+}
+
+/// Offset of `page`, or an error when it starts past the index's
+/// `paginationLimitedTo`. Page 0 is always allowed.
+fn check_pagination_limit(
+    page: usize,
+    hits_per_page: usize,
+    pagination_limit: usize,
+) -> Result<usize, FlapjackError> {
+    let offset = page.saturating_mul(hits_per_page);
+    if page > 0 && offset >= pagination_limit {
+        return Err(FlapjackError::InvalidQuery(format!(
+            "page {} with hitsPerPage {} starts at hit {}, past paginationLimitedTo ({}). \
+             Use `cursor` to iterate deeper.",
+            page, hits_per_page, offset, pagination_limit
+        )));
+    }
+    Ok(offset)
+}
+
+/// One page of a stored cursor window. Documents deleted since the window
+/// was stored are skipped.
+fn snapshot_page(
+    state: &AppState,
+    index_name: &str,
+    snapshot: &Snapshot,
+    offset: usize,
+    hits_per_page: usize,
+) -> Result<SearchResult, FlapjackError> {
+    let end = (offset + hits_per_page).min(snapshot.hits.len());
+    let mut result = snapshot.result.clone();
+    for hit in &snapshot.hits[offset.min(end)..end] {
+        if let Some(document) = state.manager.get_document(index_name, &hit.object_id)? {
+            result.documents.push(ScoredDocument {
+                document,
+                score: hit.score,
+            });
+        }
     }
+    Ok(result)
 }
 
 fn resolve_experiment_context(
@@ -278,10 +425,18 @@ fn resolve_experiment_context(
     let Some(store) = state.experiment_store.as_ref() else {
         return (effective_index, None);
     };
-    let Some(experiment) = store.get_active_for_index(index_name) else {
+    let running = store.running_for_index(index_name);
+    if running.is_empty() {
+        return (effective_index, None);
+    }
+    // Holdout and layer bucketing use the same unit as arm assignment.
+    let unit = req.user_token.as_deref().unwrap_or(assignment_query_id);
+    if assignment::in_holdout(index_name, store.holdout_percent(index_name), unit) {
+        return (effective_index, None);
+    }
+    let Some(experiment) = assignment::select_experiment(&running, unit).cloned() else {
         return (effective_index, None);
     };
-    // get_active_for_index already filters for Running status
 
     if experiment.interleaving == Some(true) {
         if let Some(variant_index_name) = experiment.variant.index_name {
@@ -291,6 +446,7 @@ fn resolve_experiment_context(
                     experiment_id: experiment.id,
                     variant_id: "interleaved".to_string(),
                     assignment_method: "interleaved".to_string(),
+                    assignment_unit: None,
                     interleaving_variant_index: Some(variant_index_name),
                     interleaved_teams: None,
                 }),
@@ -299,12 +455,38 @@ fn resolve_experiment_context(
         return (effective_index, None);
     }
 
-    let assignment = assignment::assign_variant(
-        &experiment,
-        req.user_token.as_deref(),
-        None,
-        assignment_query_id,
-    );
+    let header_value = experiment
+        .assignment_header
+        .as_deref()
+        .and_then(|name| req.assignment_headers.get(&name.to_ascii_lowercase()))
+        .cloned();
+    let assignment = if experiment.is_externally_assigned() {
+        // The caller owns the split; without a usable arm the search runs
+        // untouched and is not attributed to the experiment.
+        let Some(assignment) = req.ab_test_variant.as_deref().and_then(|arm| {
+            assignment::external_assignment(
+                arm,
+                req.user_token.as_deref(),
+                req.session_id.as_deref(),
+            )
+        }) else {
+            return (effective_index, None);
+        };
+        assignment
+    } else {
+        assignment::assign_variant(
+            &experiment,
+            req.user_token.as_deref(),
+            req.session_id.as_deref(),
+            header_value.as_deref(),
+            assignment_query_id,
+        )
+    };
+    let assignment_unit = match assignment.method {
+        AssignmentMethod::SessionId => req.session_id.clone(),
+        AssignmentMethod::Header => header_value,
+        _ => None,
+    };
     let (variant_id, arm) = if assignment.arm == "variant" {
         ("variant", &experiment.variant)
     } else {
@@ -324,12 +506,57 @@ fn resolve_experiment_context(
             experiment_id: experiment.id,
             variant_id: variant_id.to_string(),
             assignment_method: assignment_method_str(&assignment.method).to_string(),
+            assignment_unit,
             interleaving_variant_index: None,
             interleaved_teams: None,
         }),
     )
 }
 
+/// Apply `sortBy` from the requested index's `sortReplicas`: the search
+/// moves to the replica index, or takes on the replica's `sort`.
+/// `relevance` is the default ranking unless the index defines it.
+fn resolve_sort_by(
+    state: &AppState,
+    index_name: &str,
+    effective_index: String,
+    req: &mut SearchRequest,
+) -> Result<String, FlapjackError> {
+    let Some(sort_by) = req.sort_by.as_deref() else {
+        return Ok(effective_index);
+    };
+    let settings = state.manager.get_settings(index_name);
+    match settings.as_ref().and_then(|s| s.sort_replicas.get(sort_by)) {
+        Some(SortReplica {
+            index: Some(replica),
+            ..
+        }) => {
+            // A namespace's index may only sort through the namespace's own
+            // replicas, whatever its settings say.
+            if let Some((namespace, _)) = parse_namespaced_tenant(index_name) {
+                if parse_namespaced_tenant(replica).map(|(ns, _)| ns) != Some(namespace) {
+                    return Err(FlapjackError::InvalidQuery(format!(
+                        "sortBy '{}' of index '{}' points outside its namespace",
+                        sort_by, index_name
+                    )));
+                }
+            }
+            Ok(replica.clone())
+        }
+        Some(SortReplica {
+            sort: Some(sort), ..
+        }) => {
+            req.sort = Some(sort.clone());
+            Ok(effective_index)
+        }
+        _ if sort_by == "relevance" => Ok(effective_index),
+        _ => Err(FlapjackError::InvalidQuery(format!(
+            "Unknown sortBy '{}' for index '{}'",
+            sort_by, index_name
+        ))),
+    }
+}
+
 fn build_search_event(
     req: &SearchRequest,
     query_id: Option<String>,
@@ -339,6 +566,7 @@ fn build_search_event(
     page: usize,
     hits_per_page: usize,
     experiment_ctx: Option<&ExperimentContext>,
+    applied_rules: &[String],
 ) -> flapjack::analytics::schema::SearchEvent {
     let analytics_tags = req.analytics_tags.as_ref().map(|tags| tags.join(","));
     let facets = req
@@ -352,7 +580,10 @@ fn build_search_event(
         index_name,
         nb_hits: nb_hits as u32,
         processing_time_ms,
-        user_token: req.user_token.clone(),
+        user_token: req
+            .user_token
+            .clone()
+            .or_else(|| experiment_ctx.and_then(|ctx| ctx.assignment_unit.clone())),
         user_ip: req.user_ip.clone(),
         filters: req.filters.clone(),
         facets,
@@ -365,20 +596,62 @@ fn build_search_event(
         experiment_id: experiment_ctx.map(|ctx| ctx.experiment_id.clone()),
         variant_id: experiment_ctx.map(|ctx| ctx.variant_id.clone()),
         assignment_method: experiment_ctx.map(|ctx| ctx.assignment_method.clone()),
+        applied_rules: applied_rules.to_vec(),
+        synonyms_triggered: Vec::new(),
+        synonyms_matched: Vec::new(),
+        query_language: req
+            .query_languages
+            .as_ref()
+            .and_then(|langs| langs.first().cloned())
+            .or_else(|| flapjack::index::language::detect_query_language(&req.query)),
+    }
+}
+
+/// Reject filters, optional filters and sorts on attributes the calling key
+/// may not see.
+fn check_attribute_access(
+    attributes: &crate::auth::AttributeRestrictions,
+    req: &SearchRequest,
+    filter: Option<&flapjack::types::Filter>,
+) -> Result<(), FlapjackError> {
+    if let Some(filter) = filter {
+        attributes
+            .check_filter(filter)
+            .map_err(FlapjackError::InvalidQuery)?;
+    }
+    if let Some(ref optional) = req.optional_filters {
+        for (attribute, _, _) in crate::dto::parse_optional_filters(optional) {
+            attributes
+                .check_attribute(&attribute)
+                .map_err(FlapjackError::InvalidQuery)?;
+        }
     }
+    for spec in req.sort.iter().flatten() {
+        let attribute = spec
+            .strip_suffix(":asc")
+            .or_else(|| spec.strip_suffix(":desc"))
+            .unwrap_or(spec);
+        attributes
+            .check_attribute(attribute)
+            .map_err(FlapjackError::InvalidQuery)?;
+    }
+    Ok(())
 }
 
-fn merge_secured_filters(
+pub(crate) fn merge_secured_filters(
     req: &mut SearchRequest,
     restrictions: &crate::auth::SecuredKeyRestrictions,
-) {
-    if let Some(ref forced_filters) = restrictions.filters {
+) -> Result<(), FlapjackError> {
+    let forced_filters = restrictions
+        .resolved_filters()
+        .map_err(FlapjackError::InvalidQuery)?;
+    if let Some(forced_filters) = forced_filters {
         match &req.filters {
             Some(existing) => {
                 req.filters = Some(format!("({}) AND ({})", existing, forced_filters));
             }
             None => {
-                req.filters = Some(forced_filters.clone());
+                req.filters = Some(forced_filters);
             }
         }
     }
@@ -387,6 +660,7 @@ fn merge_secured_filters(
             req.hits_per_page = Some(hpp);
         }
     }
+    Ok(())
 }
 
 /// Batch search across multiple queries
@@ -395,7 +669,9 @@ fn merge_secured_filters(
     path = "/1/indexes/{indexName}/queries",
     tag = "search",
     params(
-        ("indexName" = String, Path, description = "Index to search")
+        ("indexName" = String, Path, description = "Index to search"),
+        ("X-Flapjack-API-Version" = Option<String>, Header, description = "Response compatibility mode: native (default) or algolia"),
+        ("apiVersion" = Option<String>, Query, description = "Overrides X-Flapjack-API-Version")
     ),
     request_body(content = serde_json::Value, description = "Batch search request with multiple queries"),
     responses(
@@ -414,10 +690,29 @@ pub async fn batch_search(
         .extensions()
         .get::<crate::auth::SecuredKeyRestrictions>()
         .cloned();
+    let attribute_restrictions = request
+        .extensions()
+        .get::<crate::auth::AttributeRestrictions>()
+        .cloned();
+    let api_key = request
+        .extensions()
+        .get::<crate::auth::AuthenticatedKey>()
+        .map(|k| k.0.clone());
     let (user_token_header, user_ip) = extract_analytics_headers(request.headers());
-    let body_bytes = axum::body::to_bytes(request.into_body(), 10_000_000)
+    let user_token_header = user_token_header.or_else(|| {
+        crate::anonymous_tokens::verified_cookie_token(request.headers(), &state.manager.base_path)
+    });
+    let ab_variant_header = extract_ab_variant_header(request.headers());
+    let session_header = extract_session_header(request.headers());
+    let assignment_headers = extract_assignment_headers(&state, request.headers());
+    let api_version = ApiVersion::from_request(request.headers(), request.uri().query())?;
+    let max_body = crate::body_limits::BodyLimits::global().search;
+    let body_bytes = axum::body::to_bytes(request.into_body(), max_body)
         .await
-        .map_err(|e| FlapjackError::InvalidQuery(format!("Failed to read body: {}", e)))?;
+        .map_err(|_| FlapjackError::PayloadTooLarge {
+            max: max_body,
+            route_class: "search".to_string(),
+        })?;
     let body: serde_json::Value = serde_json::from_slice(&body_bytes)
         .map_err(|e| FlapjackError::InvalidQuery(format!("Invalid JSON: {}", e)))?;
     #[derive(serde::Deserialize)]
@@ -438,8 +733,14 @@ pub async fn batch_search(
             req.user_token = user_token_header.clone();
         }
         req.user_ip = user_ip.clone();
+        req.ab_test_variant = ab_variant_header.clone();
+        req.session_id = session_header.clone();
+        req.assignment_headers = assignment_headers.clone();
+        req.api_key = api_key.clone();
+        req.api_version = api_version;
+        req.attribute_restrictions = attribute_restrictions.clone();
         if let Some(ref restrictions) = secured_restrictions {
-            merge_secured_filters(&mut req, restrictions);
+            merge_secured_filters(&mut req, restrictions)?;
             if let Some(ref restrict_indices) = restrictions.restrict_indices {
                 if let Some(ref idx) = req.index_name {
                     if !crate::auth::index_pattern_matches(restrict_indices, idx) {
@@ -452,6 +753,7 @@ pub async fn batch_search(
             .index_name
             .clone()
             .ok_or_else(|| FlapjackError::InvalidQuery("Missing indexName".to_string()))?;
+        crate::maintenance::check_readable(&state.manager.base_path, &index_name)?;
         prepared.push((i, index_name, req));
     }
 
@@ -464,6 +766,7 @@ pub async fn batch_search(
             let facet_query = req.facet_query.clone().unwrap_or_default();
             let max_facet_hits = req.max_facet_hits.unwrap_or(10);
             let filters = req.filters.clone();
+            let attributes = req.attribute_restrictions.clone();
             join_set.spawn(async move {
                 let result = super::facets::search_facet_values_inline(
                     state,
@@ -472,6 +775,7 @@ pub async fn batch_search(
                     &facet_query,
                     max_facet_hits,
                     filters.as_deref(),
+                    attributes.as_ref(),
                 )
                 .await?;
                 Ok::<_, FlapjackError>((i, result))
@@ -500,6 +804,112 @@ pub async fn search_single(
     State(state): State<Arc<AppState>>,
     index_name: String,
     mut req: SearchRequest,
+) -> Result<Json<serde_json::Value>, FlapjackError> {
+    if !req.internal {
+        crate::feature_flags::apply_to_search(&state.manager.base_path, &index_name, &mut req);
+    }
+    let shadow = shadow_query(&state, &index_name, &req);
+    let api_version = req.api_version;
+    let start = Instant::now();
+    let mut response = execute_search(state.clone(), index_name.clone(), req).await?;
+    if let Some((candidate_index, shadow_req)) = shadow {
+        spawn_shadow(
+            state,
+            index_name,
+            candidate_index,
+            shadow_req,
+            &response.0,
+            start.elapsed(),
+        );
+    }
+    api_version.shape_search_response(&mut response.0);
+    Ok(response)
+}
+
+/// The copy of `req` to re-run for the index's shadow evaluation, and the
+/// index to run it on, when this query is sampled.
+fn shadow_query(
+    state: &AppState,
+    index_name: &str,
+    req: &SearchRequest,
+) -> Option<(String, SearchRequest)> {
+    if req.internal || req.cursor.is_some() {
+        return None;
+    }
+    let config = state.experiment_store.as_ref()?.shadow_for(index_name)?;
+    if rand::random::<f64>() * 100.0 >= config.percentage
+        || SHADOW_IN_FLIGHT.load(Ordering::Relaxed) >= MAX_SHADOW_IN_FLIGHT
+    {
+        return None;
+    }
+    let mut shadow_req = req.clone();
+    shadow_req.internal = true;
+    shadow_req.analytics = Some(false);
+    shadow_req.click_analytics = None;
+    shadow_req.response_fields = None;
+    if let Some(ref overrides) = config.query_overrides {
+        apply_query_overrides(&mut shadow_req, overrides);
+    }
+    let candidate_index = config
+        .candidate_index
+        .unwrap_or_else(|| index_name.to_string());
+    Some((candidate_index, shadow_req))
+}
+
+fn hit_ids(response: &serde_json::Value) -> Option<Vec<String>> {
+    let hits = response.get("hits")?.as_array()?;
+    Some(
+        hits.iter()
+            .filter_map(|hit| hit["objectID"].as_str().map(str::to_string))
+            .collect(),
+    )
+}
+
+/// Run the shadow query in the background and record how its results
+/// compare with the response already sent.
+fn spawn_shadow(
+    state: Arc<AppState>,
+    index_name: String,
+    candidate_index: String,
+    req: SearchRequest,
+    primary: &serde_json::Value,
+    primary_elapsed: Duration,
+) {
+    // Without hits (`responseFields`) there is nothing to compare against.
+    let Some(primary_ids) = hit_ids(primary) else {
+        return;
+    };
+    let primary_hits = primary["nbHits"].as_u64().unwrap_or(0);
+    SHADOW_IN_FLIGHT.fetch_add(1, Ordering::Relaxed);
+    tokio::spawn(async move {
+        let start = Instant::now();
+        let result = execute_search(state.clone(), candidate_index, req).await;
+        let candidate_elapsed = start.elapsed();
+        let sample = match result {
+            Ok(response) => hit_ids(&response.0).map(|candidate_ids| ShadowSample {
+                primary_ids,
+                candidate_ids,
+                primary_hits,
+                candidate_hits: response.0["nbHits"].as_u64().unwrap_or(0),
+                primary_ms: primary_elapsed.as_secs_f64() * 1000.0,
+                candidate_ms: candidate_elapsed.as_secs_f64() * 1000.0,
+            }),
+            Err(e) => {
+                tracing::debug!("shadow query for '{}' failed: {}", index_name, e);
+                None
+            }
+        };
+        if let Some(store) = state.experiment_store.as_ref() {
+            store.record_shadow(&index_name, sample.as_ref());
+        }
+        SHADOW_IN_FLIGHT.fetch_sub(1, Ordering::Relaxed);
+    });
+}
+
+async fn execute_search(
+    state: Arc<AppState>,
+    index_name: String,
+    mut req: SearchRequest,
 ) -> Result<Json<serde_json::Value>, FlapjackError> {
     // Move CPU-bound search + highlighting + JSON serialization off the async
     // runtime. On t4g.micro (2 vCPUs) this prevents worker-thread starvation
@@ -507,7 +917,9 @@ pub async fn search_single(
     let enqueue_time = Instant::now();
 
     // Generate queryID for click analytics correlation before assignment.
-    let query_id = if req.click_analytics == Some(true) {
+    // `analytics: false` opts the query out entirely: no event is recorded, so
+    // no queryID is issued either (events sent with it could never resolve).
+    let query_id = if req.click_analytics == Some(true) && req.analytics != Some(false) {
         Some(hex::encode(uuid::Uuid::new_v4().as_bytes()))
     } else {
         None
@@ -515,8 +927,12 @@ pub async fn search_single(
     let assignment_query_id = query_id
         .clone()
         .unwrap_or_else(|| hex::encode(uuid::Uuid::new_v4().as_bytes()));
-    let (effective_index, experiment_ctx) =
-        resolve_experiment_context(&state, &index_name, &mut req, &assignment_query_id);
+    let (effective_index, experiment_ctx) = if req.internal {
+        (index_name.clone(), None)
+    } else {
+        resolve_experiment_context(&state, &index_name, &mut req, &assignment_query_id)
+    };
+    let effective_index = resolve_sort_by(&state, &index_name, effective_index, &mut req)?;
 
     // --- Hybrid search: resolve query vector before spawn_blocking ---
     #[cfg(feature = "vector-search")]
@@ -537,7 +953,18 @@ pub async fn search_single(
             false
         };
 
-        if is_hybrid {
+        if let Some(ref vector) = req.vector {
+            // Client-supplied query vector: nothing to embed.
+            let mut params = req.hybrid.clone().unwrap_or(HybridSearchParams {
+                semantic_ratio: 1.0,
+                embedder: "default".to_string(),
+            });
+            params.clamp_ratio();
+            if params.semantic_ratio > 0.0 {
+                qv = Some(vector.clone());
+                hp = Some(params);
+            }
+        } else if is_hybrid {
             // Resolve hybrid params: explicit from request, or synthesized from neuralSearch mode
             let mut params = req.hybrid.clone().unwrap_or(HybridSearchParams {
                 semantic_ratio: 0.5,
@@ -549,44 +976,19 @@ pub async fn search_single(
             if params.semantic_ratio > 0.0 {
                 let embedder_name = params.embedder.as_str();
 
-                // Try query cache first
-                if let Some(cached) = state
-                    .embedder_store
-                    .query_cache
-                    .get(embedder_name, &req.query)
-                {
-                    qv = Some(cached);
-                } else {
-                    // Cache miss — try to embed the query
-                    if let Some(ref s) = settings {
-                        match state
-                            .embedder_store
-                            .get_or_create(&effective_index, embedder_name, s)
-                        {
-                            Ok(embedder) => match embedder.embed_query(&req.query).await {
-                                Ok(vec) => {
-                                    state.embedder_store.query_cache.insert(
-                                        embedder_name,
-                                        &req.query,
-                                        vec.clone(),
-                                    );
-                                    qv = Some(vec);
-                                }
-                                Err(e) => {
-                                    tracing::warn!(
-                                        "hybrid search: embedding failed for '{}': {}",
-                                        effective_index,
-                                        e
-                                    );
-                                }
-                            },
-                            Err(e) => {
-                                tracing::warn!(
-                                    "hybrid search: embedder resolution failed for '{}': {}",
-                                    effective_index,
-                                    e
-                                );
-                            }
+                if let Some(ref s) = settings {
+                    match state
+                        .embedder_store
+                        .embed_query(&effective_index, embedder_name, s, &req.query)
+                        .await
+                    {
+                        Ok(vec) => qv = Some(vec),
+                        Err(e) => {
+                            tracing::warn!(
+                                "hybrid search: query embedding failed for '{}': {}",
+                                effective_index,
+                                e
+                            );
                         }
                     }
                 }
@@ -637,6 +1039,9 @@ fn search_single_sync(
     let start = Instant::now();
 
     let filter = req.build_combined_filter();
+    if let Some(ref attributes) = req.attribute_restrictions {
+        check_attribute_access(attributes, &req, filter.as_ref())?;
+    }
 
     let sort = if let Some(sort_specs) = &req.sort {
         if let Some(first) = sort_specs.first() {
@@ -664,39 +1069,68 @@ fn search_single_sync(
 
     let loaded_settings = state.manager.get_settings(&effective_index);
 
-    let facet_requests = req.facets.as_ref().and_then(|facets| {
-        let allowed_facets = loaded_settings.as_ref().map(|s| s.facet_set());
+    // Blocked results apply to the query as typed, whatever rules or
+    // auto-correction later do with it.
+    let blocked =
+        super::blocklist::blocked_for(&state.manager, &index_name, &effective_index, &req.query);
+    let blocked_filter = super::blocklist::blocked_filter(&blocked, loaded_settings.as_deref());
+    let filter = match (filter, &blocked_filter) {
+        (filter, None) => filter,
+        (None, Some(b)) => Some(Filter::Not(Box::new(b.clone()))),
+        (Some(f), Some(b)) => Some(Filter::And(vec![f, Filter::Not(Box::new(b.clone()))])),
+    };
 
-        let effective_facets: Vec<String> = if facets.iter().any(|f| f == "*") {
-            match &allowed_facets {
-                Some(allowed) => allowed.iter().cloned().collect(),
-                None => Vec::new(),
-            }
-        } else {
-            facets
+    // Facet counting is skipped entirely when the caller filtered facets out
+    // of the response.
+    let wants_facets =
+        req.wants_response_field("facets") || req.wants_response_field("facets_stats");
+    let facet_requests = req
+        .facets
+        .as_ref()
+        .filter(|_| wants_facets)
+        .and_then(|facets| {
+            let allowed_facets = loaded_settings.as_ref().map(|s| s.countable_facet_set());
+
+            let effective_facets: Vec<String> = if facets.iter().any(|f| f == "*") {
+                match &allowed_facets {
+                    Some(allowed) => allowed.iter().cloned().collect(),
+                    None => Vec::new(),
+                }
+            } else {
+                facets
+                    .iter()
+                    .filter(|f| match &allowed_facets {
+                        Some(allowed) => allowed.contains(f.as_str()),
+                        None => true,
+                    })
+                    .cloned()
+                    .collect()
+            };
+
+            let filtered_facets: Vec<FacetRequest> = effective_facets
                 .iter()
-                .filter(|f| match &allowed_facets {
-                    Some(allowed) => allowed.contains(f.as_str()),
-                    None => true,
+                .filter(|f| {
+                    !loaded_settings
+                        .as_ref()
+                        .is_some_and(|s| s.is_unretrievable(f))
                 })
-                .cloned()
-                .collect()
-        };
-
-        let filtered_facets: Vec<FacetRequest> = effective_facets
-            .iter()
-            .map(|f| FacetRequest {
-                field: f.clone(),
-                path: format!("/{}", f),
-            })
-            .collect();
+                .filter(|f| {
+                    req.attribute_restrictions
+                        .as_ref()
+                        .is_none_or(|a| a.is_visible(f))
+                })
+                .map(|f| FacetRequest {
+                    field: f.clone(),
+                    path: format!("/{}", f),
+                })
+                .collect();
 
-        if filtered_facets.is_empty() {
-            None
-        } else {
-            Some(filtered_facets)
-        }
-    });
+            if filtered_facets.is_empty() {
+                None
+            } else {
+                Some(filtered_facets)
+            }
+        });
 
     let distinct_count = match &req.distinct {
         Some(serde_json::Value::Bool(true)) => loaded_settings
@@ -716,6 +1150,41 @@ fn search_single_sync(
 
     let hits_per_page = req.effective_hits_per_page();
 
+    // With a cursor the page starts where the previous one ended; otherwise
+    // at `page * hitsPerPage`, which must stay within paginationLimitedTo.
+    let cursor_mode = req.cursor.is_some();
+    let cursor = match req.cursor.as_deref() {
+        Some(c) => SearchCursor::decode(c)?,
+        None => None,
+    };
+    let pagination_limit = loaded_settings
+        .as_ref()
+        .map_or(1000, |s| s.pagination_limited_to as usize);
+    let page_offset = match &cursor {
+        Some(c) => c.offset,
+        None if cursor_mode => 0,
+        None => check_pagination_limit(req.page, hits_per_page, pagination_limit)?,
+    };
+    let generation = if cursor_mode {
+        index_generation(&state.manager.get_or_load(&effective_index)?)
+    } else {
+        0
+    };
+    let snapshot = match &cursor {
+        Some(SearchCursor {
+            snapshot: Some(id), ..
+        }) => Some((*id, SnapshotStore::global().get(*id, &effective_index)?)),
+        Some(c) if c.generation != generation => {
+            return Err(FlapjackError::InvalidQuery(
+                "Cursor is not valid anymore (index modified)".to_string(),
+            ));
+        }
+        _ => None,
+    };
+    // The ranked geo or hybrid window, kept on the first page of a cursor
+    // iteration so later pages don't re-rank it.
+    let mut ranked_window: Option<Vec<SnapshotHit>> = None;
+
     // For hybrid search, over-fetch BM25 results for RRF fusion (re-ranking
     // invalidates source-level pagination). Both BM25 and vector fetch this
     // many results; post-fusion pagination selects the requested window.
@@ -724,22 +1193,38 @@ fn search_single_sync(
     #[cfg(not(feature = "vector-search"))]
     let is_hybrid_active = false;
 
+    let is_interleaving_requested = experiment_ctx
+        .as_ref()
+        .is_some_and(|ctx| ctx.interleaving_variant_index.is_some());
+    let reranker = resolve_reranker(
+        &state,
+        &effective_index,
+        &req,
+        loaded_settings.as_deref(),
+        sort.is_some()
+            || geo_params.has_geo_filter()
+            || is_hybrid_active
+            || is_interleaving_requested,
+    );
+
     let (fetch_limit, fetch_offset) = if is_hybrid_active {
-        let limit = (hits_per_page * (req.page + 1) + 50).max(200);
+        let limit = (page_offset + hits_per_page + 50).max(200);
         (limit, 0)
     } else if geo_params.has_geo_filter() {
         (
-            (hits_per_page + req.page * hits_per_page)
-                .saturating_mul(10)
-                .max(1000),
+            (page_offset + hits_per_page).saturating_mul(10).max(1000),
             0,
         )
+    } else if let Some((_, ref config)) = reranker {
+        // Re-ranking reorders the top-K window, so fetch it whole from offset 0
+        // and paginate after re-scoring.
+        ((page_offset + hits_per_page).max(config.top_k), 0)
     } else {
-        (hits_per_page, req.page * hits_per_page)
+        (hits_per_page, page_offset)
     };
     let typo_tolerance = match &req.typo_tolerance {
-        Some(serde_json::Value::Bool(false)) => Some(false),
-        Some(serde_json::Value::String(s)) if s == "false" => Some(false),
+        Some(serde_json::Value::Bool(enabled)) => Some(*enabled),
+        Some(serde_json::Value::String(s)) => Some(s != "false"),
         _ => None,
     };
     let optional_filter_specs = req
@@ -748,10 +1233,10 @@ fn search_single_sync(
         .map(crate::dto::parse_optional_filters)
         .filter(|v| !v.is_empty());
 
-    let run_search = |tenant_id: &str, limit: usize, offset: usize| {
+    let run_search = |tenant_id: &str, query: &str, limit: usize, offset: usize| {
         state.manager.search_full_with_stop_words(
             tenant_id,
-            &req.query,
+            query,
             filter.as_ref(),
             sort.as_ref(),
             limit,
@@ -771,6 +1256,8 @@ fn search_single_sync(
             req.enable_rules,
             req.rule_contexts.as_deref(),
             req.restrict_searchable_attributes.as_deref(),
+            req.exhaustive_nb_hits,
+            req.attribute_weights.as_ref(),
         )
     };
 
@@ -779,12 +1266,20 @@ fn search_single_sync(
         .and_then(|ctx| ctx.interleaving_variant_index.clone());
     let mut _is_interleaving = interleaving_variant_index.is_some();
 
-    let result = if let Some(variant_index) = interleaving_variant_index {
+    let result = if let Some((_, ref snapshot)) = snapshot {
+        snapshot_page(
+            &state,
+            &effective_index,
+            snapshot,
+            page_offset,
+            hits_per_page,
+        )?
+    } else if let Some(variant_index) = interleaving_variant_index {
         // To serve page N, team-draft needs the top (offset + limit) docs from each arm.
         let interleave_k = fetch_limit.saturating_add(fetch_offset).max(hits_per_page);
-        let control_result = run_search(&effective_index, interleave_k, 0)?;
+        let control_result = run_search(&effective_index, &req.query, interleave_k, 0)?;
 
-        match run_search(&variant_index, interleave_k, 0) {
+        match run_search(&variant_index, &req.query, interleave_k, 0) {
             Ok(variant_result) => {
                 let control_ids: Vec<&str> = control_result
                     .documents
@@ -843,7 +1338,7 @@ fn search_single_sync(
                         .collect();
 
                 let total_interleaved = interleaved_docs_with_team.len();
-                let page_start = (req.page * hits_per_page).min(total_interleaved);
+                let page_start = page_offset.min(total_interleaved);
                 let page_end = (page_start + hits_per_page).min(total_interleaved);
                 let page_slice = &interleaved_docs_with_team[page_start..page_end];
 
@@ -863,6 +1358,11 @@ fn search_single_sync(
                     facets: control_result.facets,
                     user_data: control_result.user_data,
                     applied_rules: control_result.applied_rules,
+                    rendering_content: control_result.rendering_content,
+                    exhaustive_nb_hits: control_result.exhaustive_nb_hits
+                        && variant_result.exhaustive_nb_hits,
+                    synonyms_triggered: control_result.synonyms_triggered,
+                    synonyms_matched: control_result.synonyms_matched,
                 }
             }
             Err(FlapjackError::TenantNotFound(_)) => {
@@ -872,12 +1372,19 @@ fn search_single_sync(
                 // misleading "interleaved" assignment method.
                 experiment_ctx = None;
                 _is_interleaving = false;
-                run_search(&effective_index, fetch_limit, fetch_offset)?
+                run_search(&effective_index, &req.query, fetch_limit, fetch_offset)?
             }
             Err(err) => return Err(err),
         }
     } else {
-        run_search(&effective_index, fetch_limit, fetch_offset)?
+        run_search(&effective_index, &req.query, fetch_limit, fetch_offset)?
+    };
+
+    let result = match &reranker {
+        Some((model, config)) => {
+            rerank_and_paginate(result, model, config, page_offset, hits_per_page)
+        }
+        None => result,
     };
 
     // --- Hybrid search: RRF fusion with vector results ---
@@ -886,28 +1393,57 @@ fn search_single_sync(
 
     #[cfg(feature = "vector-search")]
     let mut result = result;
+    // Per-hit score breakdown for `_rankingInfo` when hybrid search ran.
+    #[cfg(feature = "vector-search")]
+    let mut hybrid_scores: HashMap<String, serde_json::Value> = HashMap::new();
 
     #[cfg(feature = "vector-search")]
-    if !_is_interleaving {
+    if !_is_interleaving && snapshot.is_none() {
         if let (Some(qv), Some(ref hp)) = (&query_vector, &hybrid_params) {
             let vi_opt = state.manager.get_vector_index(&effective_index);
             match vi_opt {
                 Some(vi_arc) => match vi_arc.read() {
                     Ok(vi_guard) => {
+                        if req.vector.is_some() && qv.len() != vi_guard.dimensions() {
+                            return Err(FlapjackError::InvalidQuery(format!(
+                                "vector has {} dimensions, index vectors have {}",
+                                qv.len(),
+                                vi_guard.dimensions()
+                            )));
+                        }
                         if vi_guard.is_empty() {
                             fallback_message = Some(
                                 "Hybrid search unavailable: vector index is empty. Falling back to keyword search.".to_string()
                             );
                         } else {
-                            let vec_fetch_limit = (hits_per_page * (req.page + 1) + 50).max(200);
+                            let vec_fetch_limit = (page_offset + hits_per_page + 50).max(200);
                             match vi_guard.search(qv, vec_fetch_limit) {
-                                Ok(vector_results) => {
-                                    // Extract BM25 doc IDs in ranked order
-                                    let bm25_ids: Vec<String> = result
-                                        .documents
-                                        .iter()
-                                        .map(|d| d.document.id.clone())
-                                        .collect();
+                                Ok(mut vector_results) => {
+                                    // The vector index knows nothing of filters.
+                                    if let Some(ref f) = filter {
+                                        let ids: Vec<String> = vector_results
+                                            .iter()
+                                            .map(|r| r.doc_id.clone())
+                                            .collect();
+                                        let matching = state.manager.filter_object_ids(
+                                            &effective_index,
+                                            &ids,
+                                            f,
+                                        )?;
+                                        vector_results.retain(|r| matching.contains(&r.doc_id));
+                                    }
+
+                                    // Extract BM25 doc IDs in ranked order; a pure
+                                    // vector search (ratio 1.0) ranks by similarity only.
+                                    let bm25_ids: Vec<String> = if hp.semantic_ratio >= 1.0 {
+                                        Vec::new()
+                                    } else {
+                                        result
+                                            .documents
+                                            .iter()
+                                            .map(|d| d.document.id.clone())
+                                            .collect()
+                                    };
 
                                     let fused = crate::fusion::rrf_fuse(
                                         &bm25_ids,
@@ -926,6 +1462,24 @@ fn search_single_sync(
                                         .map(|sd| (sd.document.id.clone(), sd))
                                         .collect();
 
+                                    if req.get_ranking_info == Some(true) {
+                                        for fr in &fused {
+                                            let keyword_score =
+                                                bm25_map.get(&fr.doc_id).map(|sd| sd.score);
+                                            hybrid_scores.insert(
+                                                fr.doc_id.clone(),
+                                                serde_json::json!({
+                                                    "semanticScore": fr.semantic_score,
+                                                    "keywordScore": keyword_score,
+                                                    "fusedScore": fr.fused_score,
+                                                    "semanticRank": fr.semantic_rank,
+                                                    "keywordRank": fr.keyword_rank,
+                                                    "retrievedBy": fr.retrieved_by(),
+                                                }),
+                                            );
+                                        }
+                                    }
+
                                     // Build fused document list, fetching vector-only docs as needed
                                     let mut fused_docs = Vec::new();
                                     for fr in &fused {
@@ -964,10 +1518,39 @@ fn search_single_sync(
                                         }
                                     }
 
+                                    if let Some(diversity) = req.diversity.filter(|d| *d > 0.0) {
+                                        let scores: Vec<f64> =
+                                            fused_docs.iter().map(|d| d.score as f64).collect();
+                                        let vectors: Vec<Option<Vec<f32>>> = fused_docs
+                                            .iter()
+                                            .map(|d| vi_guard.get(&d.document.id))
+                                            .collect();
+                                        let order =
+                                            crate::fusion::mmr_order(&scores, &vectors, diversity);
+                                        let mut slots: Vec<_> =
+                                            fused_docs.into_iter().map(Some).collect();
+                                        fused_docs = order
+                                            .into_iter()
+                                            .filter_map(|i| slots[i].take())
+                                            .collect();
+                                    }
+
                                     let total_fused = fused_docs.len();
+                                    if cursor_mode {
+                                        ranked_window = Some(
+                                            fused_docs
+                                                .iter()
+                                                .map(|d| SnapshotHit {
+                                                    object_id: d.document.id.clone(),
+                                                    score: d.score,
+                                                    geo: None,
+                                                })
+                                                .collect(),
+                                        );
+                                    }
 
                                     // Paginate the fused results to the requested window
-                                    let page_start = (req.page * hits_per_page).min(total_fused);
+                                    let page_start = page_offset.min(total_fused);
                                     let page_end = (page_start + hits_per_page).min(total_fused);
                                     result.documents = fused_docs[page_start..page_end].to_vec();
                                     result.total = total_fused;
@@ -1016,10 +1599,68 @@ fn search_single_sync(
         }
     }
 
+    // "Did you mean": suggest corrections for low-recall queries. When
+    // autoCorrectIfNoResults is on and the query still has zero hits (after
+    // removeWordsIfNoResults has been exhausted), search the best correction.
+    let mut did_you_mean: Vec<String> = Vec::new();
+    let mut corrected_query: Option<String> = None;
+    let result = if result.total < DID_YOU_MEAN_MAX_HITS
+        && !req.query.trim().is_empty()
+        && snapshot.is_none()
+    {
+        did_you_mean = state
+            .manager
+            .suggest_spellings(&effective_index, &req.query, DID_YOU_MEAN_MAX_SUGGESTIONS)
+            .unwrap_or_default();
+        let auto_correct = req.auto_correct_if_no_results.unwrap_or_else(|| {
+            loaded_settings
+                .as_ref()
+                .is_some_and(|s| s.auto_correct_if_no_results)
+        });
+        let can_retry = result.total == 0
+            && auto_correct
+            && !geo_params.has_geo_filter()
+            && !is_hybrid_active
+            && !_is_interleaving;
+        match did_you_mean.first() {
+            Some(best) if can_retry => {
+                let retry = run_search(&effective_index, best, fetch_limit, fetch_offset)?;
+                let retry = match &reranker {
+                    Some((model, config)) => {
+                        rerank_and_paginate(retry, model, config, page_offset, hits_per_page)
+                    }
+                    None => retry,
+                };
+                if retry.total > 0 {
+                    corrected_query = Some(best.clone());
+                    retry
+                } else {
+                    result
+                }
+            }
+            _ => result,
+        }
+    } else {
+        result
+    };
+    let mut result = result;
+    if !blocked.is_empty() {
+        super::blocklist::drop_blocked_hits(
+            &state.manager,
+            &effective_index,
+            &mut result.documents,
+            &blocked,
+            blocked_filter.as_ref(),
+        )?;
+    }
+
     let search_elapsed = start.elapsed();
 
+    // Highlight against the corrected query when auto-correction kicked in.
+    let highlight_query = corrected_query.as_deref().unwrap_or(&req.query);
+
     // Extract original query words for matchedWords (Algolia compatibility)
-    let original_query_words = extract_query_words(&req.query);
+    let original_query_words = extract_query_words(highlight_query);
 
     // For highlighting, expand to include synonyms to highlight all variant matches
     let mut query_words = original_query_words.clone();
@@ -1030,7 +1671,7 @@ fn search_single_sync(
     // If synonyms are enabled, expand query words to highlight all synonym matches
     if req.enable_synonyms.unwrap_or(true) {
         if let Some(synonym_store) = state.manager.get_synonyms(&effective_index) {
-            let expanded_queries = synonym_store.expand_query(&req.query);
+            let expanded_queries = synonym_store.expand_query(highlight_query);
             // Extract words from all expanded queries and add to query_words
             let mut all_words: std::collections::HashSet<String> =
                 query_words.iter().cloned().collect();
@@ -1069,18 +1710,41 @@ fn search_single_sync(
     let highlighter = match (&req.highlight_pre_tag, &req.highlight_post_tag) {
         (Some(pre), Some(post)) => Highlighter::new(pre.clone(), post.clone()),
         _ => Highlighter::default(),
-    };
+    }
+    .with_normalization(
+        loaded_settings
+            .as_ref()
+            .and_then(|s| flapjack::tokenizer::TextNormalization::from_settings(s))
+            .map(std::sync::Arc::new),
+    );
 
     let searchable_paths = loaded_settings
         .as_ref()
-        .and_then(|s| s.searchable_attributes.as_ref())
-        .cloned()
+        .and_then(|s| s.searchable_paths())
         .unwrap_or_default();
+    let localized_attributes = loaded_settings
+        .as_ref()
+        .map(|s| s.localized_attributes.as_slice())
+        .unwrap_or(&[]);
+    let query_languages = req
+        .query_languages
+        .as_deref()
+        .or(loaded_settings
+            .as_ref()
+            .map(|s| s.query_languages.as_slice()))
+        .unwrap_or(&[]);
 
     let mut geo_distances: HashMap<String, (f64, f64, f64)> = HashMap::new();
     let mut automatic_radius: Option<u64> = None;
+    if let Some((_, ref snapshot)) = snapshot {
+        for hit in snapshot.hits.iter().skip(page_offset).take(hits_per_page) {
+            if let Some(geo) = hit.geo {
+                geo_distances.insert(hit.object_id.clone(), geo);
+            }
+        }
+    }
 
-    let result = if geo_params.has_geo_filter() {
+    let result = if geo_params.has_geo_filter() && snapshot.is_none() {
         let mut geo_docs: Vec<(flapjack::types::ScoredDocument, Option<f64>)> = result
             .documents
             .into_iter()
@@ -1144,7 +1808,19 @@ fn search_single_sync(
         }
 
         let total_geo = geo_docs.len();
-        let start = (req.page * hits_per_page).min(total_geo);
+        if cursor_mode {
+            ranked_window = Some(
+                geo_docs
+                    .iter()
+                    .map(|(d, _)| SnapshotHit {
+                        object_id: d.document.id.clone(),
+                        score: d.score,
+                        geo: geo_distances.get(&d.document.id).copied(),
+                    })
+                    .collect(),
+            );
+        }
+        let start = page_offset.min(total_geo);
         let end = (start + hits_per_page).min(total_geo);
         let docs: Vec<flapjack::types::ScoredDocument> = geo_docs[start..end]
             .iter()
@@ -1156,23 +1832,71 @@ fn search_single_sync(
             facets: result.facets,
             user_data: result.user_data,
             applied_rules: result.applied_rules,
+            rendering_content: result.rendering_content,
+            exhaustive_nb_hits: result.exhaustive_nb_hits,
+            synonyms_triggered: result.synonyms_triggered,
+            synonyms_matched: result.synonyms_matched,
         }
     } else {
         result
     };
 
+    // paginationLimitedTo also cuts the last reachable page short.
+    let mut result = result;
+    if !cursor_mode {
+        result
+            .documents
+            .truncate(pagination_limit.saturating_sub(page_offset));
+    }
+
+    let next_cursor = if cursor_mode {
+        let next_offset = page_offset + hits_per_page;
+        if hits_per_page > 0 && next_offset < result.total {
+            let snapshot = match (&snapshot, ranked_window) {
+                (Some((id, _)), _) => Some(*id),
+                (None, Some(hits)) => Some(SnapshotStore::global().insert(Snapshot {
+                    index: effective_index.clone(),
+                    hits,
+                    result: result.clone(),
+                })),
+                (None, None) => None,
+            };
+            let cursor = SearchCursor {
+                offset: next_offset,
+                generation,
+                snapshot,
+            };
+            Some(cursor.encode())
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
     let highlight_start = Instant::now();
-    let hits: Vec<serde_json::Value> = result
-        .documents
+    // Without `hits` in responseFields there is nothing to project or
+    // highlight.
+    let hit_documents: &[flapjack::types::ScoredDocument] = if req.wants_response_field("hits") {
+        &result.documents
+    } else {
+        &[]
+    };
+    let hits: Vec<serde_json::Value> = hit_documents
         .iter()
         .map(|scored_doc| {
+            let document = flapjack::index::localized::localize(
+                &scored_doc.document,
+                localized_attributes,
+                query_languages,
+            );
             let mut doc_map = serde_json::Map::new();
             doc_map.insert(
                 "objectID".to_string(),
-                serde_json::Value::String(scored_doc.document.id.clone()),
+                serde_json::Value::String(document.id.clone()),
             );
 
-            for (key, value) in &scored_doc.document.fields {
+            for (key, value) in &document.fields {
                 if let Some(ref attrs) = req.attributes_to_retrieve {
                     if !attrs.contains(key) && !attrs.iter().any(|a| a == "*") {
                         continue;
@@ -1188,11 +1912,8 @@ fn search_single_sync(
             let skip_highlight =
                 matches!(&req.attributes_to_highlight, Some(attrs) if attrs.is_empty());
             if !skip_highlight {
-                let mut highlight_map = highlighter.highlight_document(
-                    &scored_doc.document,
-                    &query_words,
-                    &searchable_paths,
-                );
+                let mut highlight_map =
+                    highlighter.highlight_document(&document, &query_words, &searchable_paths);
 
                 // Map synonym matches back to original query terms (replaceSynonymsInHighlight=false)
                 if !synonym_map.is_empty() {
@@ -1218,11 +1939,8 @@ fn search_single_sync(
                         .iter()
                         .map(|s| parse_snippet_spec(s.as_str()))
                         .collect();
-                    let snippet_map = highlighter.snippet_document(
-                        &scored_doc.document,
-                        &query_words,
-                        &snippet_specs,
-                    );
+                    let snippet_map =
+                        highlighter.snippet_document(&document, &query_words, &snippet_specs);
                     let snippet_json = snippet_value_map_to_json(&snippet_map);
                     doc_map.insert("_snippetResult".to_string(), snippet_json);
                 }
@@ -1262,8 +1980,44 @@ fn search_single_sync(
                         "distance": dist as u64
                     });
                 }
-                doc_map.insert("_rankingInfo".to_string(), ranking_info);
-            }
+                #[cfg(feature = "vector-search")]
+                if let Some(serde_json::Value::Object(scores)) =
+                    hybrid_scores.get(&scored_doc.document.id)
+                {
+                    for (key, value) in scores {
+                        ranking_info[key] = value.clone();
+                    }
+                }
+                doc_map.insert("_rankingInfo".to_string(), ranking_info);
+            }
+
+            // Applied last so no request parameter can bring these back.
+            if let Some(ref settings) = loaded_settings {
+                settings.strip_unretrievable(&mut doc_map);
+                for key in ["_highlightResult", "_snippetResult"] {
+                    if let Some(serde_json::Value::Object(m)) = doc_map.get_mut(key) {
+                        settings.strip_unretrievable(m);
+                    }
+                }
+            }
+            if let Some(ref attributes) = req.attribute_restrictions {
+                // Strip the hit and its highlight/snippet maps separately so
+                // the `_`-prefixed keys are not mistaken for attributes.
+                let meta: Vec<(&str, serde_json::Value)> =
+                    ["_highlightResult", "_snippetResult", "_rankingInfo"]
+                        .into_iter()
+                        .filter_map(|k| doc_map.remove(k).map(|v| (k, v)))
+                        .collect();
+                attributes.strip(&mut doc_map);
+                for (key, mut value) in meta {
+                    if key != "_rankingInfo" {
+                        if let serde_json::Value::Object(ref mut m) = value {
+                            attributes.strip(m);
+                        }
+                    }
+                    doc_map.insert(key.to_string(), value);
+                }
+            }
 
             serde_json::Value::Object(doc_map)
         })
@@ -1294,10 +2048,18 @@ fn search_single_sync(
         None
     };
 
-    let page = req.page;
-    let hits_per_page = req.effective_hits_per_page();
+    let page = if cursor_mode && hits_per_page > 0 {
+        page_offset / hits_per_page
+    } else {
+        req.page
+    };
     let nb_pages = if result.total > 0 && hits_per_page > 0 {
-        result.total.div_ceil(hits_per_page)
+        let nb_pages = result.total.div_ceil(hits_per_page);
+        if cursor_mode {
+            nb_pages
+        } else {
+            nb_pages.min(pagination_limit.div_ceil(hits_per_page))
+        }
     } else {
         0
     };
@@ -1329,7 +2091,7 @@ fn search_single_sync(
     };
 
     let mut exhaustive_obj = serde_json::json!({
-        "nbHits": true,
+        "nbHits": result.exhaustive_nb_hits,
         "typo": true
     });
 
@@ -1350,7 +2112,7 @@ fn search_single_sync(
         "query": req.query,
         "params": params_str,
         "exhaustive": exhaustive_obj,
-        "exhaustiveNbHits": true,
+        "exhaustiveNbHits": result.exhaustive_nb_hits,
         "exhaustiveTypo": true,
         "index": index_name,
         "renderingContent": {},
@@ -1366,6 +2128,10 @@ fn search_single_sync(
         response["exhaustiveFacetsCount"] = serde_json::json!(true);
     }
 
+    if cursor_mode {
+        response["cursor"] = serde_json::json!(next_cursor);
+    }
+
     match facet_distribution {
         Some(facets) if facets.is_empty() => {
             response["facets"] = serde_json::json!({});
@@ -1376,10 +2142,35 @@ fn search_single_sync(
         None => {}
     }
 
+    if !did_you_mean.is_empty() {
+        response["didYouMean"] = serde_json::json!(did_you_mean);
+    }
+
+    if let Some(ref corrected) = corrected_query {
+        response["autoCorrectedQuery"] = serde_json::json!(corrected);
+    }
+
     if !result.user_data.is_empty() {
         response["userData"] = serde_json::Value::Array(result.user_data);
     }
 
+    let index_content = loaded_settings
+        .as_ref()
+        .and_then(|s| s.rendering_content.as_ref());
+    let rendering_content = match (result.rendering_content, index_content) {
+        (Some(mut content), Some(index_content)) => {
+            content.merge(index_content);
+            Some(content)
+        }
+        (content, index_content) => content.or_else(|| index_content.cloned()),
+    };
+    if let Some(content) = rendering_content.filter(|c| !c.is_empty()) {
+        if let Some(ref redirect) = content.redirect {
+            response["redirect"] = serde_json::json!({ "url": redirect.url });
+        }
+        response["renderingContent"] = serde_json::to_value(&content).unwrap_or_default();
+    }
+
     if let Some(auto_r) = automatic_radius {
         response["automaticRadius"] = serde_json::json!(auto_r.to_string());
     }
@@ -1388,7 +2179,7 @@ fn search_single_sync(
         response["appliedRules"] = serde_json::Value::Array(
             result
                 .applied_rules
-                .into_iter()
+                .iter()
                 .map(|id| serde_json::json!({ "objectID": id }))
                 .collect(),
         );
@@ -1404,15 +2195,9 @@ fn search_single_sync(
         response["message"] = serde_json::json!(msg);
     }
 
-    if let Some(ref fields) = req.response_fields {
-        if !fields.contains(&"*".to_string()) {
-            let response_obj = response.as_object_mut().unwrap();
-            let keys: Vec<String> = response_obj.keys().cloned().collect();
-            for key in keys {
-                if !fields.contains(&key) {
-                    response_obj.remove(&key);
-                }
-            }
+    if req.response_fields.is_some() {
+        if let Some(response_obj) = response.as_object_mut() {
+            response_obj.retain(|key, _| req.wants_response_field(key));
         }
     }
 
@@ -1428,7 +2213,7 @@ fn search_single_sync(
     }
 
     // Increment usage counter: search_results_total
-    {
+    if !req.internal {
         let entry = state
             .usage_counters
             .entry(effective_index.clone())
@@ -1441,7 +2226,7 @@ fn search_single_sync(
     // Record analytics event (fire-and-forget, never blocks search response)
     if req.analytics != Some(false) {
         if let Some(collector) = flapjack::analytics::get_global_collector() {
-            collector.record_search(build_search_event(
+            let mut event = build_search_event(
                 &req,
                 query_id.clone(),
                 effective_index.clone(),
@@ -1450,7 +2235,11 @@ fn search_single_sync(
                 page,
                 hits_per_page,
                 experiment_ctx.as_ref(),
-            ));
+                &result.applied_rules,
+            );
+            event.synonyms_triggered = result.synonyms_triggered.clone();
+            event.synonyms_matched = result.synonyms_matched.clone();
+            collector.record_search(event);
         }
     }
 
@@ -1463,7 +2252,9 @@ fn search_single_sync(
     path = "/1/indexes/{indexName}/query",
     tag = "search",
     params(
-        ("indexName" = String, Path, description = "Index to search")
+        ("indexName" = String, Path, description = "Index to search"),
+        ("X-Flapjack-API-Version" = Option<String>, Header, description = "Response compatibility mode: native (default) or algolia"),
+        ("apiVersion" = Option<String>, Query, description = "Overrides X-Flapjack-API-Version")
     ),
     request_body(content = SearchRequest, description = "Search parameters including query, filters, facets, and pagination"),
     responses(
@@ -1483,22 +2274,82 @@ pub async fn search(
         .extensions()
         .get::<crate::auth::SecuredKeyRestrictions>()
         .cloned();
+    let attribute_restrictions = request
+        .extensions()
+        .get::<crate::auth::AttributeRestrictions>()
+        .cloned();
+    let api_key = request
+        .extensions()
+        .get::<crate::auth::AuthenticatedKey>()
+        .map(|k| k.0.clone());
     let (user_token_header, user_ip) = extract_analytics_headers(request.headers());
-    let body_bytes = axum::body::to_bytes(request.into_body(), 10_000_000)
+    let user_token_header = user_token_header.or_else(|| {
+        crate::anonymous_tokens::verified_cookie_token(request.headers(), &state.manager.base_path)
+    });
+    let ab_variant_header = extract_ab_variant_header(request.headers());
+    let session_header = extract_session_header(request.headers());
+    let assignment_headers = extract_assignment_headers(&state, request.headers());
+    let api_version = ApiVersion::from_request(request.headers(), request.uri().query())?;
+    let max_body = crate::body_limits::BodyLimits::global().search;
+    let body_bytes = axum::body::to_bytes(request.into_body(), max_body)
         .await
-        .map_err(|e| FlapjackError::InvalidQuery(format!("Failed to read body: {}", e)))?;
+        .map_err(|_| FlapjackError::PayloadTooLarge {
+            max: max_body,
+            route_class: "search".to_string(),
+        })?;
     let mut req: SearchRequest = serde_json::from_slice(&body_bytes)
         .map_err(|e| FlapjackError::InvalidQuery(format!("Invalid JSON: {}", e)))?;
     if let Some(ref restrictions) = secured_restrictions {
-        merge_secured_filters(&mut req, restrictions);
+        merge_secured_filters(&mut req, restrictions)?;
     }
     if req.user_token.is_none() {
         req.user_token = user_token_header;
     }
     req.user_ip = user_ip;
+    req.ab_test_variant = ab_variant_header;
+    req.session_id = session_header;
+    req.assignment_headers = assignment_headers;
+    req.api_key = api_key;
+    req.api_version = api_version;
+    req.attribute_restrictions = attribute_restrictions;
     search_single(State(state), index_name, req).await
 }
 
+/// Preview stop-word removal and plural expansion for a query
+#[utoipa::path(
+    post,
+    path = "/1/indexes/{indexName}/query/analyze",
+    tag = "search",
+    params(
+        ("indexName" = String, Path, description = "Index whose settings to apply")
+    ),
+    request_body(content = SearchRequest, description = "query, plus optional removeStopWords, ignorePlurals, queryLanguages and queryType overrides"),
+    responses(
+        (status = 200, description = "Removed stop words, plural forms and final terms", body = serde_json::Value),
+        (status = 404, description = "Index not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn analyze_query(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+    Json(req): Json<SearchRequest>,
+) -> Result<Json<flapjack::query::analysis::QueryAnalysis>, FlapjackError> {
+    state
+        .manager
+        .analyze_query(
+            &index_name,
+            &req.query,
+            req.remove_stop_words.as_ref(),
+            req.ignore_plurals.as_ref(),
+            req.query_languages.as_ref(),
+            req.query_type_prefix.as_deref(),
+        )
+        .map(Json)
+}
+
 /// Resolve the effective search mode from per-query override and index settings.
 ///
 /// Priority: query mode > settings mode > KeywordSearch default.
@@ -1529,7 +2380,10 @@ mod tests {
     };
     use flapjack::experiments::{
         assignment::{self, AssignmentMethod},
-        config::{Experiment, ExperimentArm, ExperimentStatus, PrimaryMetric, QueryOverrides},
+        config::{
+            AssignmentMode, Experiment, ExperimentArm, ExperimentStatus, PrimaryMetric,
+            QueryOverrides,
+        },
         store::ExperimentStore,
     };
     use flapjack::types::Document;
@@ -1682,7 +2536,7 @@ mod tests {
             filters: Some("brand:Nike".to_string()),
             ..Default::default()
         };
-        merge_secured_filters(&mut req, &restrictions);
+        merge_secured_filters(&mut req, &restrictions).unwrap();
         assert_eq!(req.filters, Some("brand:Nike".to_string()));
     }
 
@@ -1696,7 +2550,7 @@ mod tests {
             filters: Some("brand:Nike".to_string()),
             ..Default::default()
         };
-        merge_secured_filters(&mut req, &restrictions);
+        merge_secured_filters(&mut req, &restrictions).unwrap();
         assert_eq!(
             req.filters,
             Some("(color:Red) AND (brand:Nike)".to_string())
@@ -1707,10 +2561,20 @@ mod tests {
     fn merge_secured_filters_no_filters() {
         let mut req = SearchRequest::default();
         let restrictions = crate::auth::SecuredKeyRestrictions::default();
-        merge_secured_filters(&mut req, &restrictions);
+        merge_secured_filters(&mut req, &restrictions).unwrap();
         assert!(req.filters.is_none());
     }
 
+    #[test]
+    fn pagination_limit_rejects_pages_past_it() {
+        assert_eq!(check_pagination_limit(0, 20, 1000).unwrap(), 0);
+        assert_eq!(check_pagination_limit(49, 20, 1000).unwrap(), 980);
+        assert!(check_pagination_limit(50, 20, 1000).is_err());
+        // The first page is always served, even when the limit is tiny.
+        assert_eq!(check_pagination_limit(0, 20, 5).unwrap(), 0);
+        assert!(check_pagination_limit(1, 20, 5).is_err());
+    }
+
     #[test]
     fn merge_secured_filters_caps_hits_per_page() {
         let mut req = SearchRequest {
@@ -1721,7 +2585,7 @@ mod tests {
             hits_per_page: Some(20),
             ..Default::default()
         };
-        merge_secured_filters(&mut req, &restrictions);
+        merge_secured_filters(&mut req, &restrictions).unwrap();
         assert_eq!(req.hits_per_page, Some(20));
     }
 
@@ -1735,7 +2599,7 @@ mod tests {
             hits_per_page: Some(20),
             ..Default::default()
         };
-        merge_secured_filters(&mut req, &restrictions);
+        merge_secured_filters(&mut req, &restrictions).unwrap();
         assert_eq!(req.hits_per_page, Some(10));
     }
 
@@ -1747,7 +2611,7 @@ mod tests {
             hits_per_page: Some(20),
             ..Default::default()
         };
-        merge_secured_filters(&mut req, &restrictions);
+        merge_secured_filters(&mut req, &restrictions).unwrap();
         assert_eq!(req.hits_per_page, Some(20));
     }
 
@@ -1761,7 +2625,7 @@ mod tests {
             filters: Some("".to_string()),
             ..Default::default()
         };
-        merge_secured_filters(&mut req, &restrictions);
+        merge_secured_filters(&mut req, &restrictions).unwrap();
         // Empty string still gets combined — caller should avoid passing empty
         assert_eq!(req.filters, Some("(color:Red) AND ()".to_string()));
     }
@@ -1777,11 +2641,46 @@ mod tests {
             hits_per_page: Some(20),
             ..Default::default()
         };
-        merge_secured_filters(&mut req, &restrictions);
+        merge_secured_filters(&mut req, &restrictions).unwrap();
         assert_eq!(req.filters, Some("brand:Nike".to_string()));
         assert_eq!(req.hits_per_page, Some(20));
     }
 
+    #[test]
+    fn merge_secured_filters_resolves_templates() {
+        let mut req = SearchRequest {
+            filters: Some("color:Red".to_string()),
+            ..Default::default()
+        };
+        let mut restrictions = crate::auth::SecuredKeyRestrictions {
+            filters: Some("tenant_id:{claim.tenant} AND plan:{key.plan}".to_string()),
+            ..Default::default()
+        };
+        restrictions.claims.insert("tenant".into(), "42".into());
+        restrictions
+            .key_metadata
+            .insert("plan".into(), "pro".into());
+        merge_secured_filters(&mut req, &restrictions).unwrap();
+        assert_eq!(
+            req.filters,
+            Some("(color:Red) AND (tenant_id:42 AND plan:\"pro\")".to_string())
+        );
+    }
+
+    #[test]
+    fn merge_secured_filters_rejects_unresolved_template() {
+        let mut req = SearchRequest::default();
+        let restrictions = crate::auth::SecuredKeyRestrictions {
+            filters: Some("tenant_id:{claim.tenant}".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            merge_secured_filters(&mut req, &restrictions),
+            Err(FlapjackError::InvalidQuery(_))
+        ));
+        assert!(req.filters.is_none());
+    }
+
     // ── resolve_search_mode ──
 
     #[test]
@@ -1926,6 +2825,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn apply_overrides_enable_re_ranking() {
+        let mut req = SearchRequest {
+            enable_re_ranking: Some(true),
+            ..Default::default()
+        };
+        let overrides = QueryOverrides {
+            enable_re_ranking: Some(false),
+            ..Default::default()
+        };
+        apply_query_overrides(&mut req, &overrides);
+        assert_eq!(req.enable_re_ranking, Some(false));
+    }
+
     #[test]
     fn apply_overrides_skips_none_fields() {
         let mut req = SearchRequest {
@@ -1969,7 +2882,6 @@ mod tests {
         };
         let overrides = QueryOverrides {
             custom_ranking: Some(vec!["desc(popularity)".to_string()]),
-            attribute_weights: Some(std::iter::once(("title".to_string(), 10.0)).collect()),
             ..Default::default()
         };
 
@@ -1983,6 +2895,20 @@ mod tests {
         assert_eq!(req.enable_synonyms, Some(true));
     }
 
+    #[test]
+    fn apply_overrides_sets_attribute_weights() {
+        let mut req = SearchRequest::default();
+        let overrides = QueryOverrides {
+            attribute_weights: Some(std::iter::once(("title".to_string(), 10.0)).collect()),
+            ..Default::default()
+        };
+
+        apply_query_overrides(&mut req, &overrides);
+
+        assert_eq!(req.attribute_weights, overrides.attribute_weights);
+        assert_eq!(req.filters, None);
+    }
+
     // ── A6: assignment_method_str ──
 
     #[test]
@@ -2009,6 +2935,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn assignment_method_to_string_header() {
+        assert_eq!(assignment_method_str(&AssignmentMethod::Header), "header");
+    }
+
+    #[test]
+    fn search_event_uses_assignment_unit_without_user_token() {
+        let ctx = ExperimentContext {
+            experiment_id: "exp-123".to_string(),
+            variant_id: "control".to_string(),
+            assignment_method: "session_id".to_string(),
+            assignment_unit: Some("sess-42".to_string()),
+            interleaving_variant_index: None,
+            interleaved_teams: None,
+        };
+        let mut req = SearchRequest {
+            query: "shoe".to_string(),
+            ..Default::default()
+        };
+        let event = |req: &SearchRequest| {
+            build_search_event(
+                req,
+                None,
+                "products".to_string(),
+                1,
+                1,
+                0,
+                20,
+                Some(&ctx),
+                &[],
+            )
+        };
+        assert_eq!(event(&req).user_token.as_deref(), Some("sess-42"));
+        req.user_token = Some("user-a".to_string());
+        assert_eq!(event(&req).user_token.as_deref(), Some("user-a"));
+    }
+
+    #[test]
+    fn assignment_method_to_string_external() {
+        assert_eq!(
+            assignment_method_str(&AssignmentMethod::External),
+            "external"
+        );
+    }
+
     fn make_doc(id: &str, title: &str) -> Document {
         let mut fields = HashMap::new();
         fields.insert("title".to_string(), FieldValue::Text(title.to_string()));
@@ -2029,6 +3000,7 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
@@ -2037,6 +3009,7 @@ mod tests {
                     ..Default::default()
                 }),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: chrono::Utc::now().timestamp_millis(),
@@ -2046,6 +3019,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         }
     }
 
@@ -2060,11 +3049,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: None,
                 index_name: Some(variant_index_name.to_string()),
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: chrono::Utc::now().timestamp_millis(),
@@ -2074,6 +3065,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         }
     }
 
@@ -2247,7 +3254,8 @@ mod tests {
     fn find_user_token_for_arm(experiment: &Experiment, target_arm: &str) -> String {
         for i in 0..100_000 {
             let candidate = format!("tok-{i}");
-            let assignment = assignment::assign_variant(experiment, Some(&candidate), None, "qid");
+            let assignment =
+                assignment::assign_variant(experiment, Some(&candidate), None, None, "qid");
             if assignment.arm == target_arm {
                 return candidate;
             }
@@ -2304,6 +3312,163 @@ mod tests {
         assert!(body.get("abTestVariantID").is_none());
     }
 
+    #[tokio::test]
+    async fn shadow_queries_are_compared_in_the_background() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_search_experiment_state(&tmp).await;
+        let store = state.experiment_store.clone().unwrap();
+        store
+            .set_shadow(
+                "products_no_experiment",
+                flapjack::experiments::shadow::ShadowConfig {
+                    percentage: 100.0,
+                    candidate_index: Some("products_mode_b_variant".to_string()),
+                    query_overrides: None,
+                    created_at: 0,
+                },
+            )
+            .unwrap();
+        let app = search_router(state.clone());
+
+        let resp = post_search(
+            &app,
+            "products_no_experiment",
+            json!({ "query": "document" }),
+            None,
+        )
+        .await;
+        let body = body_json(resp).await;
+        assert_eq!(body["hits"][0]["objectID"], "n1");
+
+        let mut report = store.shadow_report("products_no_experiment");
+        for _ in 0..100 {
+            if report.queries > 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            report = store.shadow_report("products_no_experiment");
+        }
+        assert_eq!(report.queries, 1);
+        assert_eq!(report.errors, 0);
+        assert_eq!(report.mean_overlap, 0.0);
+        assert_eq!(report.top_hit_agreement, 0.0);
+        assert!(
+            !state.usage_counters.contains_key("products_mode_b_variant"),
+            "shadow queries must not count as usage"
+        );
+    }
+
+    #[tokio::test]
+    async fn external_assignment_uses_arm_from_header() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_search_experiment_state(&tmp).await;
+        state.manager.create_tenant("products_external").unwrap();
+        let mut experiment = mode_b_experiment(
+            "exp-external",
+            "products_external",
+            "products_mode_b_variant",
+        );
+        experiment.assignment_mode = Some(AssignmentMode::External);
+        let store = state.experiment_store.as_ref().unwrap();
+        store.create(experiment).unwrap();
+        store.start("exp-external").unwrap();
+        let app = search_router(state);
+
+        let post_with_arm = |arm: Option<&'static str>| {
+            let mut builder = Request::builder()
+                .method(Method::POST)
+                .uri("/1/indexes/products_external/query")
+                .header("content-type", "application/json")
+                .header("x-algolia-usertoken", "user-a");
+            if let Some(arm) = arm {
+                builder = builder.header("x-flapjack-ab-variant", arm);
+            }
+            app.clone().oneshot(
+                builder
+                    .body(Body::from(json!({ "query": "document" }).to_string()))
+                    .unwrap(),
+            )
+        };
+
+        let body = body_json(post_with_arm(Some("variant")).await.unwrap()).await;
+        assert_eq!(body["abTestID"], "exp-external");
+        assert_eq!(body["abTestVariantID"], "variant");
+        assert_eq!(body["indexUsed"], "products_mode_b_variant");
+
+        let body = body_json(post_with_arm(Some("control")).await.unwrap()).await;
+        assert_eq!(body["abTestVariantID"], "control");
+        assert!(body.get("indexUsed").is_none());
+
+        // No (or an unknown) arm: the search is not part of the experiment.
+        for arm in [None, Some("treatment")] {
+            let body = body_json(post_with_arm(arm).await.unwrap()).await;
+            assert!(body.get("abTestID").is_none(), "arm {:?}", arm);
+            assert!(body.get("indexUsed").is_none(), "arm {:?}", arm);
+        }
+    }
+
+    #[tokio::test]
+    async fn layered_experiments_split_users_and_respect_holdout() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_search_experiment_state(&tmp).await;
+        state.manager.create_tenant("products_layered").unwrap();
+        let store = state.experiment_store.as_ref().unwrap().clone();
+        for id in ["exp-layer-a", "exp-layer-b"] {
+            let mut experiment = mode_a_experiment(id, "products_layered");
+            experiment.layer = Some("ranking".to_string());
+            experiment.layer_traffic = Some(0.5);
+            store.create(experiment).unwrap();
+            store.start(id).unwrap();
+        }
+        let app = search_router(state);
+
+        let mut seen = HashSet::new();
+        for i in 0..20 {
+            let token = format!("user-{i}");
+            let body = body_json(
+                post_search(
+                    &app,
+                    "products_layered",
+                    json!({ "query": "x" }),
+                    Some(&token),
+                )
+                .await,
+            )
+            .await;
+            let id = body["abTestID"].as_str().unwrap().to_string();
+            let again = body_json(
+                post_search(
+                    &app,
+                    "products_layered",
+                    json!({ "query": "x" }),
+                    Some(&token),
+                )
+                .await,
+            )
+            .await;
+            assert_eq!(again["abTestID"], id.as_str(), "sticky per user");
+            seen.insert(id);
+        }
+        assert_eq!(seen.len(), 2, "both layered experiments get traffic");
+
+        store.set_holdout_percent("products_layered", 50.0).unwrap();
+        let held_out = (0..1_000)
+            .map(|i| format!("user-{i}"))
+            .find(|t| assignment::in_holdout("products_layered", 50.0, t))
+            .unwrap();
+        let body = body_json(
+            post_search(
+                &app,
+                "products_layered",
+                json!({ "query": "x" }),
+                Some(&held_out),
+            )
+            .await,
+        )
+        .await;
+        assert!(body.get("abTestID").is_none());
+    }
+
     #[tokio::test]
     async fn mode_b_variant_reroutes_shows_index_used() {
         let tmp = TempDir::new().unwrap();
@@ -2375,6 +3540,263 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn sort_by_routes_to_replica_or_virtual_sort() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_search_experiment_state(&tmp).await;
+        state
+            .manager
+            .add_documents_sync(
+                "products_no_experiment",
+                vec![make_doc("n2", "plain index document two")],
+            )
+            .await
+            .unwrap();
+        let replicas = [
+            (
+                "oldest".to_string(),
+                SortReplica {
+                    index: None,
+                    sort: Some(vec!["objectID:asc".to_string()]),
+                },
+            ),
+            (
+                "variant".to_string(),
+                SortReplica {
+                    index: Some("products_mode_b_variant".to_string()),
+                    sort: None,
+                },
+            ),
+        ];
+        IndexSettings {
+            sort_replicas: replicas.into_iter().collect(),
+            ..Default::default()
+        }
+        .save(tmp.path().join("products_no_experiment/settings.json"))
+        .unwrap();
+        state
+            .manager
+            .invalidate_settings_cache("products_no_experiment");
+        let app = search_router(state);
+
+        let body = body_json(
+            post_search(&app, "products_no_experiment", json!({ "query": "" }), None).await,
+        )
+        .await;
+        assert_eq!(body["hits"][0]["objectID"], "n2");
+        let body = body_json(
+            post_search(
+                &app,
+                "products_no_experiment",
+                json!({ "query": "", "sortBy": "oldest" }),
+                None,
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(body["hits"][0]["objectID"], "n1");
+        assert!(body.get("indexUsed").is_none());
+
+        let body = body_json(
+            post_search(
+                &app,
+                "products_no_experiment",
+                json!({ "query": "document", "sortBy": "variant" }),
+                None,
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(body["indexUsed"], "products_mode_b_variant");
+        assert_eq!(body["hits"][0]["objectID"], "mv1");
+
+        let resp = post_search(
+            &app,
+            "products_no_experiment",
+            json!({ "query": "", "sortBy": "relevance" }),
+            None,
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = post_search(
+            &app,
+            "products_no_experiment",
+            json!({ "query": "", "sortBy": "cheapest" }),
+            None,
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn freshness_reranks_without_a_trained_model() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_search_experiment_state(&tmp).await;
+        let req = SearchRequest::default();
+        let fresh = IndexSettings {
+            enable_re_ranking: true,
+            re_ranking: Some(ReRankingSettings {
+                freshness_attribute: Some("published_at".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let (model, config) = resolve_reranker(&state, "news", &req, Some(&fresh), false)
+            .expect("freshness alone enables re-ranking");
+        assert!(model.is_empty());
+        assert_eq!(config.freshness_attribute.as_deref(), Some("published_at"));
+
+        let plain = IndexSettings {
+            enable_re_ranking: true,
+            ..Default::default()
+        };
+        assert!(resolve_reranker(&state, "news", &req, Some(&plain), false).is_none());
+    }
+
+    #[tokio::test]
+    async fn sort_by_stays_inside_the_index_namespace() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_search_experiment_state(&tmp).await;
+        state.manager.create_tenant("acme~products").unwrap();
+        state
+            .manager
+            .add_documents_sync("acme~products", vec![make_doc("a1", "acme document")])
+            .await
+            .unwrap();
+        let replicas = [
+            (
+                "outside".to_string(),
+                SortReplica {
+                    index: Some("products_mode_b_variant".to_string()),
+                    sort: None,
+                },
+            ),
+            (
+                "inside".to_string(),
+                SortReplica {
+                    index: Some("acme~products".to_string()),
+                    sort: None,
+                },
+            ),
+        ];
+        IndexSettings {
+            sort_replicas: replicas.into_iter().collect(),
+            ..Default::default()
+        }
+        .save(tmp.path().join("acme~products/settings.json"))
+        .unwrap();
+        state.manager.invalidate_settings_cache("acme~products");
+        let app = search_router(state);
+
+        let resp = post_search(
+            &app,
+            "acme~products",
+            json!({ "query": "", "sortBy": "outside" }),
+            None,
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = post_search(
+            &app,
+            "acme~products",
+            json!({ "query": "", "sortBy": "inside" }),
+            None,
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn blocked_results_survive_rules_and_filters() {
+        use flapjack::index::blocklist::{Blocklist, BLOCKLIST_FILE};
+        use flapjack::index::rules::{Rule, RuleStore};
+
+        let tmp = TempDir::new().unwrap();
+        let state = make_search_experiment_state(&tmp).await;
+        let index = "products_blocked";
+        state.manager.create_tenant(index).unwrap();
+        IndexSettings {
+            attributes_for_faceting: vec!["brand".to_string()],
+            ..Default::default()
+        }
+        .save(tmp.path().join(index).join("settings.json"))
+        .unwrap();
+        state.manager.invalidate_settings_cache(index);
+        let docs = [
+            ("b1", "Acme", "Acme running shoe, the Globex classic"),
+            ("b2", "Globex", "Globex running shoe"),
+            ("b3", "Initech", "Initech running shoe"),
+        ]
+        .into_iter()
+        .map(|(id, brand, title)| {
+            Document::from_json(&json!({"objectID": id, "brand": brand, "title": title})).unwrap()
+        })
+        .collect();
+        state.manager.add_documents_sync(index, docs).await.unwrap();
+
+        // Merchandising rules pin both blocked records.
+        let mut rules = RuleStore::new();
+        for (pattern, pinned) in [("shoe", "b1"), ("globex", "b2")] {
+            let rule: Rule = serde_json::from_value(json!({
+                "objectID": format!("pin-{}", pinned),
+                "conditions": [{"pattern": pattern, "anchoring": "contains"}],
+                "consequence": {"promote": [{"objectID": pinned, "position": 0}]},
+            }))
+            .unwrap();
+            rules.insert(rule);
+        }
+        rules
+            .save(&tmp.path().join(index).join("rules.json"))
+            .unwrap();
+        let mut blocklist = Blocklist::new();
+        for entry in [
+            json!({"objectID": "t1", "pattern": "shoe", "anchoring": "endsWith",
+                   "objectIDs": ["b1"]}),
+            json!({"objectID": "t2", "pattern": "globex", "anchoring": "contains",
+                   "filters": "brand:Globex"}),
+        ] {
+            blocklist.insert(serde_json::from_value(entry).unwrap());
+        }
+        blocklist
+            .save(&tmp.path().join(index).join(BLOCKLIST_FILE))
+            .unwrap();
+        let app = search_router(state);
+
+        let ids = |body: &Value| -> Vec<String> {
+            body["hits"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|h| h["objectID"].as_str().unwrap().to_string())
+                .collect()
+        };
+        let body =
+            body_json(post_search(&app, index, json!({"query": "running shoe"}), None).await).await;
+        assert!(!ids(&body).contains(&"b1".to_string()));
+        assert_eq!(body["nbHits"], 2);
+
+        let body =
+            body_json(post_search(&app, index, json!({"query": "globex"}), None).await).await;
+        assert_eq!(ids(&body), vec!["b1".to_string()], "{body}");
+
+        let body = body_json(
+            post_search(
+                &app,
+                index,
+                json!({"query": "globex", "filters": "brand:Globex OR brand:Acme"}),
+                None,
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(ids(&body), vec!["b1".to_string()]);
+
+        // Queries no entry matches are untouched.
+        let body =
+            body_json(post_search(&app, index, json!({"query": "running"}), None).await).await;
+        assert_eq!(body["nbHits"], 3);
+    }
+
     #[tokio::test]
     async fn test_interleaving_experiment_returns_interleaved_results() {
         let tmp = TempDir::new().unwrap();
@@ -2456,6 +3878,52 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn algolia_api_version_drops_native_fields() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_search_experiment_state(&tmp).await;
+        let app = search_router(state);
+        let search = |uri: &str, version: &str| {
+            Request::builder()
+                .method(Method::POST)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("x-algolia-usertoken", "user-a")
+                .header("x-flapjack-api-version", version)
+                .body(Body::from(json!({ "query": "interleave" }).to_string()))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(search("/1/indexes/products_interleave/query", "algolia"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = body_json(resp).await;
+        assert!(body.get("interleavedTeams").is_none());
+        assert!(body["abTestID"].is_string());
+        assert!(!body["hits"].as_array().unwrap().is_empty());
+
+        // The query parameter overrides the header.
+        let resp = app
+            .clone()
+            .oneshot(search(
+                "/1/indexes/products_interleave/query?apiVersion=native",
+                "algolia",
+            ))
+            .await
+            .unwrap();
+        assert!(body_json(resp).await["interleavedTeams"].is_object());
+
+        let resp = app
+            .clone()
+            .oneshot(search("/1/indexes/products_interleave/query", "v2"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
     #[tokio::test]
     async fn test_interleaving_experiment_preserves_non_interleaving_behavior() {
         let tmp = TempDir::new().unwrap();
@@ -2605,6 +4073,82 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn analytics_opt_out_suppresses_query_id() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_search_experiment_state(&tmp).await;
+        let app = search_router(state);
+
+        let resp = post_search(
+            &app,
+            "products",
+            json!({ "query": "shoe", "clickAnalytics": true }),
+            None,
+        )
+        .await;
+        assert!(body_json(resp).await["queryID"].is_string());
+
+        let resp = post_search(
+            &app,
+            "products",
+            json!({ "query": "shoe", "clickAnalytics": true, "analytics": false }),
+            None,
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(body_json(resp).await.get("queryID").is_none());
+    }
+
+    #[tokio::test]
+    async fn anonymous_cookie_token_gives_stable_assignment() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_search_experiment_state(&tmp).await;
+        let issuer =
+            crate::anonymous_tokens::AnonymousTokenIssuer::for_data_dir(&state.manager.base_path)
+                .unwrap();
+        let (token, _) = issuer.issue(3600, chrono::Utc::now().timestamp());
+        let app = search_router(state);
+
+        let search_with_cookie = |cookie: String| {
+            let app = app.clone();
+            async move {
+                let resp = app
+                    .oneshot(
+                        Request::builder()
+                            .method(Method::POST)
+                            .uri("/1/indexes/products/query")
+                            .header("content-type", "application/json")
+                            .header("cookie", cookie)
+                            .body(Body::from(
+                                json!({ "query": "shoe", "clickAnalytics": false }).to_string(),
+                            ))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                body_json(resp).await["abTestVariantID"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            }
+        };
+
+        let mut signed_arms = std::collections::HashSet::new();
+        let mut forged_arms = std::collections::HashSet::new();
+        for i in 0..32 {
+            signed_arms.insert(search_with_cookie(format!("flapjack_anon_token={token}")).await);
+            forged_arms.insert(
+                search_with_cookie(format!("flapjack_anon_token=anon-{i:032x}-9999999999-00"))
+                    .await,
+            );
+        }
+        assert_eq!(signed_arms.len(), 1, "signed token must pin the arm");
+        assert!(
+            forged_arms.len() > 1,
+            "unverified cookies must fall back to per-query assignment"
+        );
+    }
+
     #[tokio::test]
     async fn batch_search_with_active_experiment_is_annotated() {
         let tmp = TempDir::new().unwrap();
@@ -2701,15 +4245,18 @@ mod tests {
                 experiment_id: "exp-123".to_string(),
                 variant_id: "variant".to_string(),
                 assignment_method: "user_token".to_string(),
+                assignment_unit: None,
                 interleaving_variant_index: None,
                 interleaved_teams: None,
             }),
+            &["pin-shoes".to_string()],
         );
 
         assert_eq!(event.experiment_id.as_deref(), Some("exp-123"));
         assert_eq!(event.variant_id.as_deref(), Some("variant"));
         assert_eq!(event.assignment_method.as_deref(), Some("user_token"));
         assert_eq!(event.index_name, "products");
+        assert_eq!(event.applied_rules, vec!["pin-shoes".to_string()]);
     }
 
     #[test]
@@ -2727,6 +4274,7 @@ mod tests {
             0,
             20,
             None,
+            &[],
         );
 
         assert!(
@@ -3005,6 +4553,153 @@ mod tests {
             );
         }
 
+        #[tokio::test]
+        async fn test_hybrid_ranking_info_breaks_down_scores() {
+            let tmp = TempDir::new().unwrap();
+            let state = make_test_state(&tmp);
+            let idx = "test_hybrid_ranking_info";
+            state.manager.create_tenant(idx).unwrap();
+
+            let docs = vec![
+                make_doc("doc1", "machine learning algorithms"),
+                make_doc("doc4", "artificial intelligence research"),
+            ];
+            state.manager.add_documents_sync(idx, docs).await.unwrap();
+            save_settings(&state, idx, &settings_with_embedder());
+            let query_vec = setup_vector_index(&state, idx);
+            cache_query_vector(&state, "default", "learning", query_vec);
+
+            let req = SearchRequest {
+                query: "learning".to_string(),
+                hybrid: Some(HybridSearchParams {
+                    semantic_ratio: 0.5,
+                    embedder: "default".to_string(),
+                }),
+                get_ranking_info: Some(true),
+                ..Default::default()
+            };
+            let result = search_single(State(state.clone()), idx.to_string(), req)
+                .await
+                .unwrap();
+            let hits = result.0["hits"].as_array().unwrap();
+            let info = |id: &str| {
+                hits.iter()
+                    .find(|h| h["objectID"] == id)
+                    .map(|h| h["_rankingInfo"].clone())
+                    .unwrap()
+            };
+
+            let doc1 = info("doc1");
+            assert_eq!(doc1["retrievedBy"], "both");
+            assert_eq!(doc1["keywordRank"], 1);
+            assert!(doc1["keywordScore"].as_f64().unwrap() > 0.0);
+            assert!(doc1["semanticScore"].as_f64().unwrap() > 0.9);
+            assert!(doc1["fusedScore"].as_f64().unwrap() > 0.0);
+
+            let doc4 = info("doc4");
+            assert_eq!(doc4["retrievedBy"], "semantic");
+            assert!(doc4["keywordScore"].is_null());
+            assert!(doc4["semanticRank"].as_u64().is_some());
+        }
+
+        #[tokio::test]
+        async fn test_injected_vector_pure_search_honors_filters() {
+            let tmp = TempDir::new().unwrap();
+            let state = make_test_state(&tmp);
+            let idx = "test_injected_vector";
+            state.manager.create_tenant(idx).unwrap();
+            let mut settings = settings_with_embedder();
+            settings.attributes_for_faceting = vec!["kind".to_string()];
+            save_settings(&state, idx, &settings);
+
+            let docs = ["doc1", "doc2", "doc3", "doc4"]
+                .iter()
+                .map(|id| {
+                    let mut doc = make_doc(id, "photo");
+                    let kind = if *id == "doc4" { "sketch" } else { "photo" };
+                    doc.fields
+                        .insert("kind".to_string(), FieldValue::Text(kind.to_string()));
+                    doc
+                })
+                .collect();
+            state.manager.add_documents_sync(idx, docs).await.unwrap();
+            setup_vector_index(&state, idx);
+
+            // No query text and no embedder call: the vector alone ranks.
+            let req = SearchRequest {
+                vector: Some(vec![1.0, 0.0, 0.0]),
+                filters: Some("kind:photo".to_string()),
+                hits_per_page: Some(2),
+                attributes_to_retrieve: Some(vec!["kind".to_string()]),
+                ..Default::default()
+            };
+            let result = search_single(State(state.clone()), idx.to_string(), req)
+                .await
+                .unwrap();
+            let hits = result.0["hits"].as_array().unwrap();
+            let ids: Vec<&str> = hits
+                .iter()
+                .map(|h| h["objectID"].as_str().unwrap())
+                .collect();
+            // doc4 is the second closest vector but filtered out.
+            assert_eq!(ids, vec!["doc1", "doc2"]);
+            assert_eq!(result.0["nbHits"], 3);
+            assert!(hits[0].get("title").is_none());
+
+            let wrong_dims = SearchRequest {
+                vector: Some(vec![1.0, 0.0]),
+                ..Default::default()
+            };
+            assert!(
+                search_single(State(state.clone()), idx.to_string(), wrong_dims)
+                    .await
+                    .is_err()
+            );
+        }
+
+        #[tokio::test]
+        async fn test_diversity_demotes_near_duplicate_vectors() {
+            let tmp = TempDir::new().unwrap();
+            let state = make_test_state(&tmp);
+            let idx = "test_diversity";
+            state.manager.create_tenant(idx).unwrap();
+            save_settings(&state, idx, &settings_with_embedder());
+            let docs = ["doc1", "doc2", "doc3", "doc4"]
+                .iter()
+                .map(|id| make_doc(id, "dress"))
+                .collect();
+            state.manager.add_documents_sync(idx, docs).await.unwrap();
+            let query = setup_vector_index(&state, idx);
+
+            let ids = |result: &serde_json::Value| -> Vec<String> {
+                result["hits"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|h| h["objectID"].as_str().unwrap().to_string())
+                    .collect()
+            };
+            let search = |diversity: Option<f64>| SearchRequest {
+                vector: Some(query.clone()),
+                diversity,
+                ..Default::default()
+            };
+
+            let plain = search_single(State(state.clone()), idx.to_string(), search(None))
+                .await
+                .unwrap();
+            assert_eq!(ids(&plain.0)[..2], ["doc1", "doc4"]);
+
+            // doc4 is nearly the same vector as doc1, so it loses its second place.
+            let diverse = search_single(State(state.clone()), idx.to_string(), search(Some(0.5)))
+                .await
+                .unwrap();
+            let diverse_ids = ids(&diverse.0);
+            assert_eq!(diverse_ids[0], "doc1");
+            assert_ne!(diverse_ids[1], "doc4");
+            assert_eq!(diverse_ids.len(), 4);
+        }
+
         #[tokio::test]
         async fn test_hybrid_search_no_embedder_fallback() {
             let tmp = TempDir::new().unwrap();
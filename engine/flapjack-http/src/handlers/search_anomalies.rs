@@ -0,0 +1,29 @@
+use axum::{
+    extract::{Query, State},
+    response::IntoResponse,
+    Json,
+};
+use flapjack::ErrorCode;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use super::AppState;
+use crate::error_codes::error_response;
+use crate::search_anomalies::AnomalyStore;
+
+#[derive(Debug, Deserialize)]
+pub struct AnomaliesQuery {
+    pub index: Option<String>,
+}
+
+/// GET /1/analytics/anomalies — detected search traffic anomalies, newest first
+pub async fn list_anomalies(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AnomaliesQuery>,
+) -> impl IntoResponse {
+    match AnomalyStore::new(&state.manager.base_path).list(params.index.as_deref()) {
+        Ok(anomalies) => Json(json!({ "anomalies": anomalies })).into_response(),
+        Err(e) => error_response(ErrorCode::IoError, e.to_string()),
+    }
+}
@@ -9,12 +9,18 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use super::AppState;
+use crate::maintenance::MaintenanceRegistry;
+use flapjack::index::compaction::CompactionPolicy;
+use flapjack::index::facet_normalization::FacetValueNormalization;
+use flapjack::index::ingest_transform::IngestTransform;
+use flapjack::index::reranking::ReRankingSettings;
 use flapjack::index::settings::{
     detect_embedder_changes, DistinctValue, EmbedderChange, IndexMode, IndexSettings,
-    SemanticSearchSettings,
+    SemanticSearchSettings, SortReplica,
 };
+use flapjack::types::{TaskIndexResult, TaskInfo, TaskProgress, TaskStatus};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SetSettingsRequest {
     #[serde(rename = "attributesForFaceting")]
     pub attributes_for_faceting: Option<Vec<String>>,
@@ -57,10 +63,114 @@ pub struct SetSettingsRequest {
     #[serde(rename = "semanticSearch", skip_serializing_if = "Option::is_none")]
     pub semantic_search: Option<SemanticSearchSettings>,
 
+    #[serde(rename = "enableReRanking", skip_serializing_if = "Option::is_none")]
+    pub enable_re_ranking: Option<bool>,
+
+    #[serde(rename = "reRanking", skip_serializing_if = "Option::is_none")]
+    pub re_ranking: Option<ReRankingSettings>,
+
+    #[serde(
+        rename = "autoCorrectIfNoResults",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub auto_correct_if_no_results: Option<bool>,
+
+    #[serde(rename = "autoDetectLanguage", skip_serializing_if = "Option::is_none")]
+    pub auto_detect_language: Option<bool>,
+
+    #[serde(rename = "normalizeUnicode", skip_serializing_if = "Option::is_none")]
+    pub normalize_unicode: Option<bool>,
+
+    #[serde(rename = "removeDiacritics", skip_serializing_if = "Option::is_none")]
+    pub remove_diacritics: Option<bool>,
+
+    #[serde(
+        rename = "keepDiacriticsOnCharacters",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub keep_diacritics_on_characters: Option<String>,
+
+    #[serde(rename = "transliterate", skip_serializing_if = "Option::is_none")]
+    pub transliterate: Option<Vec<String>>,
+
+    #[serde(rename = "splitCamelCase", skip_serializing_if = "Option::is_none")]
+    pub split_camel_case: Option<bool>,
+
+    #[serde(rename = "typoTolerance", skip_serializing_if = "Option::is_none")]
+    pub typo_tolerance: Option<serde_json::Value>,
+
+    #[serde(
+        rename = "facetValueNormalization",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub facet_value_normalization: Option<HashMap<String, FacetValueNormalization>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compaction: Option<CompactionPolicy>,
+
+    #[serde(rename = "ingestTransforms", skip_serializing_if = "Option::is_none")]
+    pub ingest_transforms: Option<Vec<IngestTransform>>,
+
+    /// `null` removes the schema.
+    #[serde(
+        rename = "documentSchema",
+        default,
+        deserialize_with = "deserialize_present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub document_schema: Option<serde_json::Value>,
+
+    /// `null` turns duplicate detection off.
+    #[serde(
+        rename = "duplicateDetection",
+        default,
+        deserialize_with = "deserialize_present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub duplicate_detection: Option<serde_json::Value>,
+
+    /// Seconds; `null` stops stamping new documents.
+    #[serde(
+        rename = "defaultTtl",
+        default,
+        deserialize_with = "deserialize_present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub default_ttl: Option<serde_json::Value>,
+
+    #[serde(
+        rename = "localizedAttributes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub localized_attributes: Option<Vec<String>>,
+
+    #[serde(rename = "attributeWeights", skip_serializing_if = "Option::is_none")]
+    pub attribute_weights: Option<HashMap<String, f32>>,
+
+    #[serde(rename = "sortReplicas", skip_serializing_if = "Option::is_none")]
+    pub sort_replicas: Option<HashMap<String, SortReplica>>,
+
+    /// Facet ordering and redirect for InstantSearch; `null` removes them.
+    #[serde(
+        rename = "renderingContent",
+        default,
+        deserialize_with = "deserialize_present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rendering_content: Option<serde_json::Value>,
+
     #[serde(flatten)]
     pub other: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
+/// Keeps an explicit `null` as `Some(Value::Null)` so it can clear a setting.
+fn deserialize_present<'de, D>(deserializer: D) -> Result<Option<serde_json::Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    serde_json::Value::deserialize(deserializer).map(Some)
+}
+
 #[derive(Debug, Serialize)]
 pub struct SetSettingsResponse {
     #[serde(rename = "updatedAt")]
@@ -95,17 +205,36 @@ pub async fn set_settings(
     Path(index_name): Path<String>,
     Json(payload): Json<SetSettingsRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    state
-        .manager
-        .create_tenant(&index_name)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let (unsupported, reindex_task) = apply_settings(&state, &index_name, payload)?;
+
+    // Waiting on the task waits for the re-indexing the change started.
+    let task = match reindex_task {
+        Some(task) => task,
+        None => state
+            .manager
+            .make_noop_task(&index_name)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
+    };
+    let response = SetSettingsResponse {
+        updated_at: chrono::Utc::now().to_rfc3339(),
+        task_id: task.numeric_id,
+        unsupported_params: if unsupported.is_empty() {
+            None
+        } else {
+            Some(unsupported)
+        },
+    };
 
-    let settings_path = state
-        .manager
-        .base_path
-        .join(&index_name)
-        .join("settings.json");
+    let status = if response.unsupported_params.is_some() {
+        StatusCode::MULTI_STATUS
+    } else {
+        StatusCode::OK
+    };
 
+    Ok((status, Json(response)))
+}
+
+fn unsupported_params(payload: &SetSettingsRequest) -> Vec<String> {
     let mut unsupported = Vec::new();
 
     if payload.ranking.is_some() {
@@ -117,6 +246,30 @@ pub async fn set_settings(
             unsupported.push(key.clone());
         }
     }
+    unsupported
+}
+
+/// Merge a settings patch into an index's settings, validate and save them,
+/// and kick off the re-indexing the change needs. Returns the parameters
+/// the patch set that are not supported, and the re-indexing task if one
+/// was started.
+pub(crate) fn apply_settings(
+    state: &Arc<AppState>,
+    index_name: &str,
+    payload: SetSettingsRequest,
+) -> Result<(Vec<String>, Option<TaskInfo>), (StatusCode, String)> {
+    state
+        .manager
+        .create_tenant(index_name)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let settings_path = state
+        .manager
+        .base_path
+        .join(index_name)
+        .join("settings.json");
+
+    let unsupported = unsupported_params(&payload);
 
     let distinct_value = payload.distinct.and_then(|v| match v {
         serde_json::Value::Bool(b) => Some(DistinctValue::Bool(b)),
@@ -130,6 +283,7 @@ pub async fn set_settings(
     } else {
         IndexSettings::default()
     };
+    let previous = settings.clone();
 
     if let Some(facets) = payload.attributes_for_faceting {
         settings.attributes_for_faceting = facets;
@@ -183,6 +337,90 @@ pub async fn set_settings(
     if let Some(ss) = payload.semantic_search {
         settings.semantic_search = Some(ss);
     }
+    if let Some(enabled) = payload.enable_re_ranking {
+        settings.enable_re_ranking = enabled;
+    }
+    if let Some(rr) = payload.re_ranking {
+        settings.re_ranking = Some(rr);
+    }
+    if let Some(ac) = payload.auto_correct_if_no_results {
+        settings.auto_correct_if_no_results = ac;
+    }
+    if let Some(detect) = payload.auto_detect_language {
+        settings.auto_detect_language = detect;
+    }
+    if let Some(nfkc) = payload.normalize_unicode {
+        settings.normalize_unicode = nfkc;
+    }
+    if let Some(remove) = payload.remove_diacritics {
+        settings.remove_diacritics = remove;
+    }
+    if let Some(keep) = payload.keep_diacritics_on_characters {
+        settings.keep_diacritics_on_characters = keep;
+    }
+    if let Some(scripts) = payload.transliterate {
+        settings.transliterate = scripts;
+    }
+    if let Some(split) = payload.split_camel_case {
+        settings.split_camel_case = split;
+    }
+    if let Some(typo) = payload.typo_tolerance {
+        settings.typo_tolerance = Some(typo);
+    }
+    if let Some(facet_normalization) = payload.facet_value_normalization {
+        settings.facet_value_normalization = facet_normalization;
+    }
+    if let Some(compaction) = payload.compaction {
+        settings.compaction = Some(compaction);
+    }
+    if let Some(transforms) = payload.ingest_transforms {
+        settings.ingest_transforms = transforms;
+    }
+    if let Some(schema) = payload.document_schema {
+        settings.document_schema = Some(schema).filter(|s| !s.is_null());
+    }
+    if let Some(ttl) = payload.default_ttl {
+        settings.default_ttl = match ttl {
+            serde_json::Value::Null => None,
+            value => Some(value.as_u64().ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    "defaultTtl must be a whole number of seconds".to_string(),
+                )
+            })?),
+        };
+    }
+    if let Some(localized) = payload.localized_attributes {
+        settings.localized_attributes = localized;
+    }
+    if let Some(weights) = payload.attribute_weights {
+        settings.attribute_weights = weights;
+    }
+    if let Some(replicas) = payload.sort_replicas {
+        settings.sort_replicas = replicas;
+    }
+    if let Some(content) = payload.rendering_content {
+        settings.rendering_content = match content {
+            serde_json::Value::Null => None,
+            value => Some(serde_json::from_value(value).map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("invalid renderingContent: {}", e),
+                )
+            })?),
+        };
+    }
+    if let Some(detection) = payload.duplicate_detection {
+        settings.duplicate_detection = match detection {
+            serde_json::Value::Null => None,
+            value => Some(serde_json::from_value(value).map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("invalid duplicateDetection: {}", e),
+                )
+            })?),
+        };
+    }
 
     // Warn if neuralSearch mode is set without embedders configured
     if settings.mode == Some(IndexMode::NeuralSearch) && settings.embedders.is_none() {
@@ -195,6 +433,33 @@ pub async fn set_settings(
     settings
         .validate_embedders()
         .map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+    settings
+        .validate_transliterate()
+        .map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+    settings
+        .validate_compaction()
+        .map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+    settings
+        .validate_ingest_transforms()
+        .map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+    settings
+        .compiled_document_schema()
+        .map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+    settings
+        .validate_duplicate_detection()
+        .map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+    settings
+        .validate_default_ttl()
+        .map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+    settings
+        .validate_localized_attributes()
+        .map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+    settings
+        .validate_attribute_weights()
+        .map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+    settings
+        .validate_sort_replicas()
+        .map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
 
     // Stale vector detection
     for change in detect_embedder_changes(&old_embedders, &settings.embedders) {
@@ -230,42 +495,171 @@ pub async fn set_settings(
         .save(&settings_path)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    state.manager.invalidate_settings_cache(&index_name);
-    state.manager.invalidate_facet_cache(&index_name);
+    state.manager.invalidate_settings_cache(index_name);
+    state.manager.invalidate_facet_cache(index_name);
+    crate::warmup::spawn_warmup(
+        Arc::clone(state),
+        index_name.to_string(),
+        crate::warmup::WarmupTrigger::Settings,
+    );
 
     // Invalidate cached embedders when embedder config changes
     #[cfg(feature = "vector-search")]
     if embedders_updated {
-        state.embedder_store.invalidate(&index_name);
+        state.embedder_store.invalidate(index_name);
     }
 
     state.manager.append_oplog(
-        &index_name,
+        index_name,
         "settings",
         serde_json::to_value(&settings).unwrap_or_default(),
     );
 
-    let noop_task = state
-        .manager
-        .make_noop_task(&index_name)
+    // Documents written under the previous settings keep their old
+    // tokens, facet values and fingerprints until they are re-indexed.
+    let reasons = settings.reindex_reasons(&previous);
+    let reindex_task = if reasons.is_empty() {
+        None
+    } else {
+        tracing::info!(
+            "[settings] re-indexing '{}' for changed {}",
+            index_name,
+            reasons.join(", ")
+        );
+        Some(state.manager.spawn_reindex(index_name))
+    };
+
+    Ok((unsupported, reindex_task))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkSettingsRequest {
+    /// Index name pattern, with `*` wildcards as on API keys (`products_*`).
+    pub index_pattern: String,
+    pub settings: SetSettingsRequest,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkSettingsResponse {
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+
+    #[serde(rename = "taskID")]
+    pub task_id: i64,
+
+    /// The indexes the patch is applied to, in order.
+    pub indices: Vec<String>,
+
+    #[serde(rename = "unsupportedParams", skip_serializing_if = "Option::is_none")]
+    pub unsupported_params: Option<Vec<String>>,
+}
+
+/// POST /1/settings/bulk — apply a settings patch to every index matching a
+/// pattern. The patch is applied one index at a time in a background task;
+/// `GET /1/tasks/:taskID` reports progress and, per index, whether it was
+/// updated. A failed index (invalid result, maintenance mode) does not stop
+/// the others; the task fails if any index did.
+pub async fn bulk_set_settings(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<BulkSettingsRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let pattern = payload.index_pattern.trim();
+    if pattern.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "indexPattern is required".to_string(),
+        ));
+    }
+    let patterns = [pattern.to_string()];
+    let entries = std::fs::read_dir(&state.manager.base_path)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    let response = SetSettingsResponse {
+    let mut indices: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| !name.starts_with('.'))
+        .filter(|name| crate::auth::index_pattern_matches(&patterns, name))
+        .collect();
+    indices.sort();
+    if indices.is_empty() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("No index matches '{}'", pattern),
+        ));
+    }
+
+    let unsupported = unsupported_params(&payload.settings);
+    let task = state
+        .manager
+        .start_task(format!("bulk_settings_{}", uuid::Uuid::new_v4()));
+    let total = indices.len();
+    state.manager.update_task(&task.id, |t| {
+        t.progress = Some(TaskProgress {
+            completed: 0,
+            total,
+        });
+    });
+    tokio::spawn(run_bulk_settings(
+        Arc::clone(&state),
+        task.id,
+        indices.clone(),
+        payload.settings,
+    ));
+
+    Ok(Json(BulkSettingsResponse {
         updated_at: chrono::Utc::now().to_rfc3339(),
-        task_id: noop_task.numeric_id,
+        task_id: task.numeric_id,
+        indices,
         unsupported_params: if unsupported.is_empty() {
             None
         } else {
             Some(unsupported)
         },
-    };
+    }))
+}
 
-    let status = if response.unsupported_params.is_some() {
-        StatusCode::MULTI_STATUS
+async fn run_bulk_settings(
+    state: Arc<AppState>,
+    task_id: String,
+    indices: Vec<String>,
+    settings: SetSettingsRequest,
+) {
+    let total = indices.len();
+    let mut failed = 0;
+    for (completed, index_name) in indices.into_iter().enumerate() {
+        let error = MaintenanceRegistry::for_data_dir(&state.manager.base_path)
+            .map_err(|e| e.to_string())
+            .and_then(|registry| registry.check(&index_name, true).map_err(|e| e.to_string()))
+            .and_then(|()| {
+                apply_settings(&state, &index_name, settings.clone())
+                    .map(|_| ())
+                    .map_err(|(_, message)| message)
+            })
+            .err();
+        if let Some(e) = &error {
+            failed += 1;
+            tracing::warn!("[settings] bulk update of '{}' failed: {}", index_name, e);
+        }
+        let result = TaskIndexResult { index_name, error };
+        state.manager.update_task(&task_id, |t| {
+            t.index_results.push(result.clone());
+            t.progress = Some(TaskProgress {
+                completed: completed + 1,
+                total,
+            });
+        });
+        tokio::task::yield_now().await;
+    }
+
+    let status = if failed == 0 {
+        TaskStatus::Succeeded
     } else {
-        StatusCode::OK
+        TaskStatus::Failed(format!("{} of {} indexes failed", failed, total))
     };
-
-    Ok((status, Json(response)))
+    state
+        .manager
+        .update_task(&task_id, |t| t.status = status.clone());
 }
 
 /// Get index settings
@@ -566,6 +960,26 @@ mod tests {
         assert_eq!(event_sources[1], "idx2");
     }
 
+    #[tokio::test]
+    async fn test_set_settings_re_ranking() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_settings_state(&tmp);
+        let app = settings_router(state);
+
+        let resp = post_settings(
+            &app,
+            r#"{"enableReRanking": true, "reRanking": {"topK": 20, "freshnessAttribute": "publishedAt"}}"#,
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let json = get_settings_json(&app).await;
+        assert_eq!(json["enableReRanking"], true);
+        assert_eq!(json["reRanking"]["topK"], 20);
+        assert_eq!(json["reRanking"]["lookbackDays"], 30);
+        assert_eq!(json["reRanking"]["freshnessAttribute"], "publishedAt");
+    }
+
     #[tokio::test]
     async fn test_set_settings_mode_and_embedders_together() {
         let tmp = TempDir::new().unwrap();
@@ -669,4 +1083,169 @@ mod tests {
             "embedders should be cleared after empty map"
         );
     }
+
+    #[tokio::test]
+    async fn test_set_settings_rendering_content_persists_and_clears() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_settings_state(&tmp);
+        let app = settings_router(state);
+
+        let resp = post_settings(
+            &app,
+            r#"{"renderingContent": {"facetOrdering": {
+                "facets": {"order": ["brand", "*"]},
+                "values": {"brand": {"order": ["Acme"], "sortRemainingBy": "alpha"}}
+            }}}"#,
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let json = get_settings_json(&app).await;
+        let ordering = &json["renderingContent"]["facetOrdering"];
+        assert_eq!(ordering["facets"]["order"][0], "brand");
+        assert_eq!(ordering["values"]["brand"]["sortRemainingBy"], "alpha");
+
+        let resp = post_settings(
+            &app,
+            r#"{"renderingContent": {"facetOrdering": {"values": {"brand": {"sortRemainingBy": "price"}}}}}"#,
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        post_settings(&app, r#"{"renderingContent": null}"#).await;
+        let json = get_settings_json(&app).await;
+        assert!(json.get("renderingContent").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bulk_settings_reports_each_matching_index() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_settings_state(&tmp);
+        for name in ["products_a", "products_b", "articles"] {
+            state.manager.create_tenant(name).unwrap();
+        }
+        MaintenanceRegistry::for_data_dir(tmp.path())
+            .unwrap()
+            .set(
+                "products_b",
+                crate::maintenance::MaintenanceMode::ReadOnly,
+                None,
+                0,
+            )
+            .unwrap();
+        let app = Router::new()
+            .route("/1/settings/bulk", axum::routing::post(bulk_set_settings))
+            .with_state(Arc::clone(&state));
+
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/1/settings/bulk")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"indexPattern": "products_*", "settings": {"searchableAttributes": ["title"]}}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json["indices"],
+            serde_json::json!(["products_a", "products_b"])
+        );
+
+        let task_id = json["taskID"].as_i64().unwrap().to_string();
+        let task = loop {
+            let task = state.manager.get_task(&task_id).unwrap();
+            if task.status != TaskStatus::Processing {
+                break task;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+        assert!(matches!(task.status, TaskStatus::Failed(_)));
+        assert_eq!(task.index_results.len(), 2);
+        assert_eq!(task.index_results[0].error, None);
+        assert!(task.index_results[1].error.is_some());
+
+        let load = |name: &str| {
+            IndexSettings::load(tmp.path().join(name).join("settings.json"))
+                .unwrap_or_default()
+                .searchable_attributes
+        };
+        assert_eq!(load("products_a"), Some(vec!["title".to_string()]));
+        assert_eq!(load("products_b"), None);
+        assert_eq!(load("articles"), None);
+    }
+
+    #[tokio::test]
+    async fn test_faceting_change_reindexes_existing_documents() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_settings_state(&tmp);
+        state.manager.create_tenant("test_idx").unwrap();
+        let docs = [("1", "Acme"), ("2", "Globex")]
+            .into_iter()
+            .map(|(id, brand)| {
+                flapjack::types::Document::from_json(
+                    &serde_json::json!({"objectID": id, "brand": brand}),
+                )
+                .unwrap()
+            })
+            .collect();
+        state
+            .manager
+            .add_documents_sync("test_idx", docs)
+            .await
+            .unwrap();
+        let app = settings_router(Arc::clone(&state));
+
+        let resp = post_settings(&app, r#"{"attributesForFaceting": ["brand"]}"#).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let task_id = json["taskID"].as_i64().unwrap().to_string();
+        let task = loop {
+            let task = state.manager.get_task(&task_id).unwrap();
+            if task.status != TaskStatus::Processing {
+                break task;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+        assert_eq!(task.status, TaskStatus::Succeeded);
+        assert_eq!(
+            task.progress,
+            Some(TaskProgress {
+                completed: 2,
+                total: 2
+            })
+        );
+
+        let facets = [flapjack::types::FacetRequest {
+            field: "brand".to_string(),
+            path: "/brand".to_string(),
+        }];
+        let result = state
+            .manager
+            .search_with_facets("test_idx", "", None, None, 10, 0, Some(&facets))
+            .unwrap();
+        let counted: u64 = result.facets["brand"].iter().map(|c| c.count).sum();
+        assert_eq!(counted, 2);
+
+        // Searchable attributes are applied at query time.
+        let resp = post_settings(&app, r#"{"searchableAttributes": ["brand"]}"#).await;
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let task_id = json["taskID"].as_i64().unwrap().to_string();
+        let task = state.manager.get_task(&task_id).unwrap();
+        assert_eq!(task.status, TaskStatus::Succeeded);
+        assert_eq!(task.progress, None);
+    }
 }
@@ -1,4 +1,6 @@
 use super::AppState;
+use crate::cluster_snapshot::{self, ClusterSnapshotError};
+use crate::error_codes::error_response;
 use axum::{
     body::Bytes,
     extract::{Path, State},
@@ -8,6 +10,7 @@ use axum::{
 };
 use flapjack::index::s3::S3Config;
 use flapjack::index::snapshot::{export_to_bytes, import_from_bytes};
+use flapjack::ErrorCode;
 use std::sync::Arc;
 
 /// Export index as downloadable snapshot
@@ -295,3 +298,67 @@ pub async fn list_s3_snapshots(Path(index_name): Path<String>) -> impl IntoRespo
             .into_response(),
     }
 }
+
+/// Take a consistent snapshot of an index on every node of the cluster
+#[utoipa::path(
+    post,
+    path = "/1/indexes/{indexName}/cluster-snapshots",
+    tag = "snapshots",
+    params(
+        ("indexName" = String, Path, description = "Index name")
+    ),
+    responses(
+        (status = 200, description = "Snapshot manifest", body = serde_json::Value),
+        (status = 404, description = "Index not found"),
+        (status = 409, description = "Index paused or snapshot already running"),
+        (status = 503, description = "Nodes did not drain writes in time")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn create_cluster_snapshot(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> impl IntoResponse {
+    match cluster_snapshot::create_cluster_snapshot(&state, &index_name).await {
+        Ok(manifest) => Json(manifest).into_response(),
+        Err(e) => {
+            let code = match e {
+                ClusterSnapshotError::IndexNotFound => ErrorCode::IndexNotFound,
+                ClusterSnapshotError::Busy(_) => ErrorCode::Conflict,
+                ClusterSnapshotError::Timeout(_) => ErrorCode::ServiceUnavailable,
+                ClusterSnapshotError::Failed(_) => ErrorCode::InternalError,
+            };
+            tracing::error!(
+                "[SNAPSHOT] cluster snapshot of '{}' failed: {}",
+                index_name,
+                e
+            );
+            error_response(code, e.to_string())
+        }
+    }
+}
+
+/// List the cluster snapshots of an index coordinated by this node
+#[utoipa::path(
+    get,
+    path = "/1/indexes/{indexName}/cluster-snapshots",
+    tag = "snapshots",
+    params(
+        ("indexName" = String, Path, description = "Index name")
+    ),
+    responses(
+        (status = 200, description = "Snapshot manifests, newest first", body = serde_json::Value)
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn list_cluster_snapshots(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> impl IntoResponse {
+    let snapshots = cluster_snapshot::list_manifests(&state.manager.base_path, &index_name);
+    Json(serde_json::json!({ "snapshots": snapshots })).into_response()
+}
@@ -376,3 +376,89 @@ pub async fn search_synonyms(
         "nbHits": total
     })))
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SynonymStatsParams {
+    #[serde(default)]
+    pub start_date: Option<String>,
+    #[serde(default)]
+    pub end_date: Option<String>,
+}
+
+/// How often each synonym's expansion ran and added hits
+#[utoipa::path(
+    get,
+    path = "/1/indexes/{indexName}/synonyms/stats",
+    tag = "synonyms",
+    params(
+        ("indexName" = String, Path, description = "Index name"),
+        ("startDate" = Option<String>, Query, description = "First day (YYYY-MM-DD), default 8 days ago"),
+        ("endDate" = Option<String>, Query, description = "Last day (YYYY-MM-DD), default today")
+    ),
+    responses(
+        (status = 200, description = "Per-synonym usage and the synonyms that never added hits", body = serde_json::Value),
+        (status = 503, description = "Analytics is disabled")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn get_synonym_stats(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+    Query(params): Query<SynonymStatsParams>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let engine = state.analytics_engine.as_ref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Analytics is disabled".to_string(),
+    ))?;
+    let now = chrono::Utc::now();
+    let start_date = params.start_date.unwrap_or_else(|| {
+        (now - chrono::Duration::days(8))
+            .format("%Y-%m-%d")
+            .to_string()
+    });
+    let end_date = params
+        .end_date
+        .unwrap_or_else(|| now.format("%Y-%m-%d").to_string());
+
+    let usage = engine
+        .synonym_usage(&index_name, &start_date, &end_date)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Analytics error: {}", e)))?;
+
+    let synonyms_path = state
+        .manager
+        .base_path
+        .join(&index_name)
+        .join("synonyms.json");
+    let store = if synonyms_path.exists() {
+        SynonymStore::load(&synonyms_path)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    } else {
+        SynonymStore::new()
+    };
+
+    let mut hits = Vec::new();
+    let mut zero_impact = Vec::new();
+    for synonym in store.iter() {
+        let (triggered, matched) = usage.get(synonym.object_id()).copied().unwrap_or_default();
+        if matched == 0 {
+            zero_impact.push(synonym.object_id().to_string());
+        }
+        hits.push(serde_json::json!({
+            "objectID": synonym.object_id(),
+            "type": synonym.synonym_type(),
+            "triggered": triggered,
+            "matched": matched
+        }));
+    }
+
+    Ok(Json(serde_json::json!({
+        "startDate": start_date,
+        "endDate": end_date,
+        "hits": hits,
+        "zeroImpact": zero_impact
+    })))
+}
@@ -1,14 +1,18 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     Json,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use utoipa::ToSchema;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use utoipa::{IntoParams, ToSchema};
 
 use super::AppState;
 use flapjack::error::FlapjackError;
-use flapjack::types::TaskStatus;
+use flapjack::types::{TaskInfo, TaskStatus};
+
+const DEFAULT_LIST_LIMIT: usize = 100;
+const MAX_LIST_LIMIT: usize = 1000;
 
 #[derive(Debug, Serialize, ToSchema)]
 pub struct TaskResponse {
@@ -19,6 +23,28 @@ pub struct TaskResponse {
     pub rejected_documents: Vec<DocFailureDto>,
     pub rejected_count: usize,
     pub error: Option<String>,
+    pub created_at: String,
+    /// Steps done, for tasks that report progress (compaction merges).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<TaskProgressDto>,
+    /// Per-index outcome, for tasks that span several indexes.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub index_results: Vec<TaskIndexResultDto>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskProgressDto {
+    pub completed: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskIndexResultDto {
+    pub index_name: String,
+    /// `succeeded` or `failed`
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -28,28 +54,7 @@ pub struct DocFailureDto {
     pub message: String,
 }
 
-/// Get task status by ID
-#[utoipa::path(
-    get,
-    path = "/1/tasks/{task_id}",
-    tag = "tasks",
-    params(
-        ("task_id" = String, Path, description = "Task ID")
-    ),
-    responses(
-        (status = 200, description = "Task status and results", body = TaskResponse),
-        (status = 404, description = "Task not found")
-    ),
-    security(
-        ("api_key" = [])
-    )
-)]
-pub async fn get_task(
-    State(state): State<Arc<AppState>>,
-    Path(task_id): Path<String>,
-) -> Result<Json<TaskResponse>, FlapjackError> {
-    let task = state.manager.get_task(&task_id)?;
-
+fn task_response(task: TaskInfo) -> TaskResponse {
     let status_str = match &task.status {
         TaskStatus::Enqueued | TaskStatus::Processing => "notPublished",
         TaskStatus::Succeeded => "published",
@@ -71,7 +76,7 @@ pub async fn get_task(
         })
         .collect();
 
-    Ok(Json(TaskResponse {
+    TaskResponse {
         task_uid: task.id,
         status: status_str.to_string(),
         received_documents: task.received_documents,
@@ -79,7 +84,115 @@ pub async fn get_task(
         rejected_documents: rejected_docs,
         rejected_count: task.rejected_count,
         error,
-    }))
+        created_at: chrono::DateTime::<chrono::Utc>::from(task.created_at).to_rfc3339(),
+        progress: task.progress.map(|p| TaskProgressDto {
+            completed: p.completed,
+            total: p.total,
+        }),
+        index_results: task
+            .index_results
+            .into_iter()
+            .map(|r| TaskIndexResultDto {
+                index_name: r.index_name,
+                status: if r.error.is_some() {
+                    "failed"
+                } else {
+                    "succeeded"
+                }
+                .to_string(),
+                error: r.error,
+            })
+            .collect(),
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskListResponse {
+    pub tasks: Vec<TaskResponse>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct ListTasksParams {
+    /// Only tasks created at or after this time (epoch milliseconds or RFC 3339)
+    pub since: Option<String>,
+    /// Maximum number of tasks to return (default 100, max 1000)
+    pub limit: Option<usize>,
+    /// Only tasks for this index
+    pub index_name: Option<String>,
+}
+
+fn parse_since(since: &str) -> Option<SystemTime> {
+    if let Ok(ms) = since.parse::<u64>() {
+        return Some(UNIX_EPOCH + Duration::from_millis(ms));
+    }
+    chrono::DateTime::parse_from_rfc3339(since)
+        .ok()
+        .map(SystemTime::from)
+}
+
+/// List tasks, oldest first, including history from before a restart
+#[utoipa::path(
+    get,
+    path = "/1/tasks",
+    tag = "tasks",
+    params(ListTasksParams),
+    responses(
+        (status = 200, description = "Tasks created since the given time", body = TaskListResponse),
+        (status = 400, description = "Invalid since parameter")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn list_tasks(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ListTasksParams>,
+) -> Result<Json<TaskListResponse>, FlapjackError> {
+    let since = match params.since.as_deref() {
+        None => UNIX_EPOCH,
+        Some(s) => parse_since(s).ok_or_else(|| {
+            FlapjackError::InvalidQuery(format!(
+                "Invalid since '{}': expected epoch milliseconds or an RFC 3339 timestamp",
+                s
+            ))
+        })?,
+    };
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_LIST_LIMIT)
+        .clamp(1, MAX_LIST_LIMIT);
+    let tasks = state
+        .manager
+        .list_tasks(since, params.index_name.as_deref(), limit)
+        .into_iter()
+        .map(task_response)
+        .collect();
+    Ok(Json(TaskListResponse { tasks }))
+}
+
+/// Get task status by ID
+#[utoipa::path(
+    get,
+    path = "/1/tasks/{task_id}",
+    tag = "tasks",
+    params(
+        ("task_id" = String, Path, description = "Task ID")
+    ),
+    responses(
+        (status = 200, description = "Task status and results", body = TaskResponse),
+        (status = 404, description = "Task not found")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+pub async fn get_task(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<String>,
+) -> Result<Json<TaskResponse>, FlapjackError> {
+    let task = state.manager.get_task(&task_id)?;
+    Ok(Json(task_response(task)))
 }
 
 /// Get task status for a specific index
@@ -123,34 +236,23 @@ pub async fn get_task_for_index(
         return Err(FlapjackError::TaskNotFound(task_id));
     }
 
-    let status_str = match &task.status {
-        TaskStatus::Enqueued | TaskStatus::Processing => "notPublished",
-        TaskStatus::Succeeded => "published",
-        TaskStatus::Failed(_) => "error",
-    };
-
-    let error = match &task.status {
-        TaskStatus::Failed(e) => Some(e.clone()),
-        _ => None,
-    };
+    Ok(Json(task_response(task)))
+}
 
-    let rejected_docs = task
-        .rejected_documents
-        .iter()
-        .map(|df| DocFailureDto {
-            doc_id: df.doc_id.clone(),
-            error: df.error.clone(),
-            message: df.message.clone(),
-        })
-        .collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(Json(TaskResponse {
-        task_uid: task.id,
-        status: status_str.to_string(),
-        received_documents: task.received_documents,
-        indexed_documents: task.indexed_documents,
-        rejected_documents: rejected_docs,
-        rejected_count: task.rejected_count,
-        error,
-    }))
+    #[test]
+    fn parse_since_accepts_millis_and_rfc3339() {
+        assert_eq!(
+            parse_since("1700000000000"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+        assert_eq!(
+            parse_since("2023-11-14T22:13:20Z"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+        assert_eq!(parse_since("yesterday"), None);
+    }
 }
@@ -0,0 +1,66 @@
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
+use flapjack::ErrorCode;
+use serde_json::json;
+use std::sync::Arc;
+
+use super::AppState;
+use crate::error_codes::error_response;
+use flapjack::error::FlapjackError;
+use flapjack::index::templates::{IndexTemplate, TemplateStore};
+
+fn store(state: &AppState) -> TemplateStore {
+    TemplateStore::new(&state.manager.base_path)
+}
+
+fn not_found(name: &str) -> axum::response::Response {
+    error_response(
+        ErrorCode::NotFound,
+        format!("Template '{}' not found", name),
+    )
+}
+
+/// PUT /1/templates/:name — create or replace a named index template
+pub async fn put_template(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(mut template): Json<IndexTemplate>,
+) -> Result<impl IntoResponse, FlapjackError> {
+    template.name = name;
+    store(&state).put(template)?;
+    Ok(Json(json!({"updatedAt": chrono::Utc::now().to_rfc3339()})))
+}
+
+/// GET /1/templates — list all templates
+pub async fn list_templates(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, FlapjackError> {
+    let templates = store(&state).list()?;
+    Ok(Json(json!({ "templates": templates })))
+}
+
+/// GET /1/templates/:name
+pub async fn get_template(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<axum::response::Response, FlapjackError> {
+    Ok(match store(&state).get(&name)? {
+        Some(template) => Json(json!(template)).into_response(),
+        None => not_found(&name),
+    })
+}
+
+/// DELETE /1/templates/:name — indexes already created from it are unaffected
+pub async fn delete_template(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<axum::response::Response, FlapjackError> {
+    Ok(if store(&state).delete(&name)? {
+        Json(json!({"deletedAt": chrono::Utc::now().to_rfc3339()})).into_response()
+    } else {
+        not_found(&name)
+    })
+}
@@ -0,0 +1,109 @@
+//! Storage tier of an index (see `flapjack::index::tiered`).
+
+use axum::{
+    extract::{Path, Request, State},
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use flapjack::error::FlapjackError;
+use flapjack::IndexManager;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use super::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tier {
+    Hot,
+    Cold,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetTierRequest {
+    pub tier: Tier,
+}
+
+fn tier_json(state: &AppState, index_name: &str) -> Result<serde_json::Value, FlapjackError> {
+    let mut body = match state.manager.cold_manifest(index_name)? {
+        Some(manifest) => json!({
+            "tier": "cold",
+            "files": manifest.files.len(),
+            "bytes": manifest.bytes(),
+            "documents": manifest.documents,
+            "demotedAt": chrono::DateTime::from_timestamp_millis(manifest.demoted_at)
+                .map(|t| t.to_rfc3339()),
+        }),
+        None => json!({"tier": "hot"}),
+    };
+    body["indexName"] = json!(index_name);
+    body["segmentCache"] = json!(state.manager.segment_cache()?.stats());
+    Ok(body)
+}
+
+fn require_index(state: &AppState, index_name: &str) -> Result<(), FlapjackError> {
+    if !state.manager.base_path.join(index_name).exists() {
+        return Err(FlapjackError::TenantNotFound(index_name.to_string()));
+    }
+    Ok(())
+}
+
+/// GET /1/indexes/:indexName/tier — whether the index is hot or cold, and
+/// the node's segment cache usage
+pub async fn get_tier(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> Result<Json<serde_json::Value>, FlapjackError> {
+    require_index(&state, &index_name)?;
+    Ok(Json(tier_json(&state, &index_name)?))
+}
+
+/// PUT /1/indexes/:indexName/tier — `{"tier": "cold"}` demotes the index to
+/// object storage, `{"tier": "hot"}` brings it back. Answers once the
+/// segments have moved.
+pub async fn set_tier(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+    Json(body): Json<SetTierRequest>,
+) -> Result<Json<serde_json::Value>, FlapjackError> {
+    require_index(&state, &index_name)?;
+    let manager = Arc::clone(&state.manager);
+    let tenant = index_name.clone();
+    tokio::task::spawn_blocking(move || match body.tier {
+        Tier::Cold => manager.demote_tenant(&tenant).map(|_| ()),
+        Tier::Hot => manager.promote_tenant(&tenant),
+    })
+    .await
+    .map_err(|e| FlapjackError::Io(e.to_string()))??;
+    Ok(Json(tier_json(&state, &index_name)?))
+}
+
+/// Promote a cold index before a write to it reaches its handler, so the
+/// segments download on a blocking thread instead of inside the write path.
+/// Deleting the index and changing its tier need no promotion.
+pub async fn promote_before_write(
+    request: Request,
+    next: Next,
+    manager: &Arc<IndexManager>,
+) -> Response {
+    let promote = {
+        let (method, path) = (request.method(), request.uri().path());
+        match crate::maintenance::classify(method, path) {
+            Some((index_name, true)) => {
+                let deletes_index = *method == Method::DELETE
+                    && path.trim_end_matches('/') == format!("/1/indexes/{}", index_name);
+                (!deletes_index && !path.ends_with("/tier")).then_some(index_name)
+            }
+            _ => None,
+        }
+    };
+    if let Some(index_name) = promote {
+        if let Err(e) = manager.promote_for_write(&index_name).await {
+            return e.into_response();
+        }
+    }
+    next.run(request).await
+}
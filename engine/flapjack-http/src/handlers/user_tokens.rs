@@ -0,0 +1,61 @@
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use flapjack::ErrorCode;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use super::AppState;
+use crate::anonymous_tokens::{self, AnonymousTokenIssuer};
+use crate::error_codes::error_response;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueTokenRequest {
+    /// Token lifetime in seconds (default 30 days, capped at 365 days).
+    pub ttl_seconds: Option<u64>,
+}
+
+/// POST /1/users/token — issue a signed anonymous userToken
+///
+/// The token is returned in the body and set as the `flapjack_anon_token`
+/// cookie. Searches that carry the cookie but no `x-algolia-usertoken`
+/// header use it as their userToken.
+pub async fn issue_user_token(
+    State(state): State<Arc<AppState>>,
+    body: Option<Json<IssueTokenRequest>>,
+) -> Response {
+    let ttl = body
+        .and_then(|Json(b)| b.ttl_seconds)
+        .unwrap_or(anonymous_tokens::DEFAULT_TTL_SECS)
+        .clamp(1, anonymous_tokens::MAX_TTL_SECS);
+    let issuer = match AnonymousTokenIssuer::for_data_dir(&state.manager.base_path) {
+        Ok(issuer) => issuer,
+        Err(e) => {
+            tracing::error!("failed to load user token secret: {}", e);
+            return error_response(
+                ErrorCode::InternalError,
+                "User token signing is unavailable",
+            );
+        }
+    };
+
+    let (token, expires_at) = issuer.issue(ttl, chrono::Utc::now().timestamp());
+    let expires_at = chrono::DateTime::from_timestamp(expires_at, 0)
+        .map(|d| d.to_rfc3339())
+        .unwrap_or_default();
+    (
+        [(
+            header::SET_COOKIE,
+            anonymous_tokens::cookie_header(&token, ttl),
+        )],
+        Json(serde_json::json!({
+            "userToken": token,
+            "expiresAt": expires_at,
+        })),
+    )
+        .into_response()
+}
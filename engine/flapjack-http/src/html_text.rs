@@ -0,0 +1,276 @@
+//! Lenient HTML-to-text conversion shared by file uploads and the crawler.
+//!
+//! Not a full HTML parser: it walks tags, drops scripts, styles and the
+//! document head, starts a new line at block elements and decodes common
+//! entities. Along the way it picks up what indexing needs from the markup:
+//! the title, meta description and robots directives, and link targets.
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HtmlPage {
+    pub title: Option<String>,
+    /// `<meta name="description">`.
+    pub description: Option<String>,
+    /// `<html lang>`.
+    pub lang: Option<String>,
+    /// One line per block element, whitespace collapsed.
+    pub text: String,
+    /// `href` of every `<a>`, as written.
+    pub links: Vec<String>,
+    /// `<meta name="robots" content="noindex">`.
+    pub noindex: bool,
+    /// `<meta name="robots" content="nofollow">`.
+    pub nofollow: bool,
+}
+
+/// Tags that start a new line of text.
+const BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+
+/// Tags whose content is not text.
+const SKIPPED_TAGS: &[&str] = &["noscript", "script", "style", "svg", "template"];
+
+pub fn parse(html: &str) -> HtmlPage {
+    let lower = html.to_ascii_lowercase();
+    let mut page = HtmlPage::default();
+    let mut text = String::new();
+    let mut pos = 0;
+
+    while let Some(offset) = html[pos..].find('<') {
+        text.push_str(&decode_entities(&html[pos..pos + offset]));
+        pos += offset;
+        if lower[pos..].starts_with("<!--") {
+            pos = lower[pos..].find("-->").map_or(html.len(), |i| pos + i + 3);
+            continue;
+        }
+        let Some(end) = html[pos..].find('>') else {
+            pos = html.len();
+            break;
+        };
+        let source = &html[pos + 1..pos + end];
+        let tag = &lower[pos + 1..pos + end];
+        pos += end + 1;
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+        if closing {
+            if BLOCK_TAGS.contains(&name) {
+                text.push('\n');
+            }
+            continue;
+        }
+
+        match name {
+            "title" | "head" => {
+                // The head holds no text, only the title and meta tags
+                let close = format!("</{}", name);
+                let content_end = lower[pos..].find(&close).map_or(html.len(), |i| pos + i);
+                if name == "title" {
+                    if page.title.is_none() {
+                        page.title = Some(normalize(&decode_entities(&html[pos..content_end])));
+                    }
+                } else {
+                    let head = parse(&html[pos..content_end]);
+                    page.title = page.title.or(head.title);
+                    page.description = page.description.or(head.description);
+                    page.noindex |= head.noindex;
+                    page.nofollow |= head.nofollow;
+                }
+                pos = content_end;
+            }
+            _ if SKIPPED_TAGS.contains(&name) => {
+                let close = format!("</{}", name);
+                pos = lower[pos..].find(&close).map_or(html.len(), |i| pos + i);
+            }
+            "html" => page.lang = attribute(source, "lang"),
+            "meta" => {
+                let meta_name = attribute(source, "name").map(|n| n.to_ascii_lowercase());
+                let content = attribute(source, "content");
+                match (meta_name.as_deref(), content) {
+                    (Some("description"), Some(content)) => {
+                        page.description = Some(normalize(&content));
+                    }
+                    (Some("robots"), Some(content)) => {
+                        let content = content.to_ascii_lowercase();
+                        page.noindex |= content.contains("noindex") || content.contains("none");
+                        page.nofollow |= content.contains("nofollow") || content.contains("none");
+                    }
+                    _ => {}
+                }
+            }
+            "a" => {
+                if let Some(href) = attribute(source, "href") {
+                    page.links.push(href);
+                }
+            }
+            _ if BLOCK_TAGS.contains(&name) => text.push('\n'),
+            _ => {}
+        }
+    }
+    text.push_str(&decode_entities(&html[pos..]));
+    page.text = normalize(&text);
+    page.title = page.title.filter(|t| !t.is_empty());
+    page.description = page.description.filter(|d| !d.is_empty());
+    page
+}
+
+/// Value of attribute `name` in a tag's source (`a href="/x" class=y`),
+/// entity-decoded.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(i) = lower[from..].find(name) {
+        let start = from + i;
+        from = start + name.len();
+        let preceded_by_space = lower[..start].ends_with(|c: char| c.is_whitespace());
+        let rest = lower[from..].trim_start();
+        if !preceded_by_space || !rest.starts_with('=') {
+            continue;
+        }
+        let value_start = tag.len() - rest.len() + 1;
+        let value = tag[value_start..].trim_start();
+        let raw = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or(""),
+            _ => value
+                .split(|c: char| c.is_whitespace() || c == '>')
+                .next()
+                .unwrap_or(""),
+        };
+        return Some(decode_entities(raw));
+    }
+    None
+}
+
+/// Collapse whitespace within lines and drop empty lines.
+pub(crate) fn normalize(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub(crate) fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..].find(';').filter(|&i| i <= 10).and_then(|i| {
+            let entity = &rest[1..=i];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16).ok())
+                    .unwrap_or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, i + 2))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_text_skips_markup_and_scripts() {
+        let html = r#"<!DOCTYPE html>
+            <html lang="en"><head><title>Return &amp; refunds</title>
+            <meta name="description" content="How to send items back">
+            <style>p { color: red }</style></head>
+            <body><h1>Returns</h1><!-- internal note -->
+            <p>Items can be returned within <b>30</b>&nbsp;days.</p>
+            <script>track("view")</script><ul><li>Shoes</li><li>Bags</li></ul>
+            </body></html>"#;
+        let page = parse(html);
+        assert_eq!(page.title.as_deref(), Some("Return & refunds"));
+        assert_eq!(page.description.as_deref(), Some("How to send items back"));
+        assert_eq!(page.lang.as_deref(), Some("en"));
+        assert_eq!(
+            page.text,
+            "Returns\nItems can be returned within 30 days.\nShoes\nBags"
+        );
+        assert!(!page.noindex);
+    }
+
+    #[test]
+    fn links_and_robots_meta() {
+        let html = r#"<head><META NAME="robots" CONTENT="noindex, follow"></head>
+            <a class="nav" href="/docs/start">Start</a>
+            <a href='guide.html?a=1&amp;b=2'>Guide</a> <a name="top">Top</a>
+            <a href=https://other.example.com/x>Other</a>"#;
+        let page = parse(html);
+        assert!(page.noindex);
+        assert!(!page.nofollow);
+        assert_eq!(
+            page.links,
+            vec![
+                "/docs/start",
+                "guide.html?a=1&b=2",
+                "https://other.example.com/x"
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_numeric_entities() {
+        assert_eq!(
+            decode_entities("caf&#233; &#x2014; a&b &bogus;"),
+            "café — a&b &bogus;"
+        );
+    }
+}
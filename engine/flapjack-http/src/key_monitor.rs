@@ -0,0 +1,376 @@
+//! Auth failure counters and per-key anomaly detection.
+//!
+//! Every rejected request is counted by reason, by key and by client IP for
+//! `/metrics`. Successful requests feed a per-key detector that flags:
+//!
+//! - `usageSpike`: a key's requests in the current minute reach
+//!   `FLAPJACK_KEY_ANOMALY_FACTOR` (default 10) times its moving per-minute
+//!   average, and at least `FLAPJACK_KEY_ANOMALY_MIN_REQUESTS` (default 100).
+//! - `newCountry`: a key is used from a country it was not seen from since
+//!   the node started (the first one is its baseline). The country comes
+//!   from the header named by `FLAPJACK_COUNTRY_HEADER` (default
+//!   `cf-ipcountry`), set by the CDN or proxy in front of the node.
+//!
+//! Anomalies are logged, counted, and POSTed as `key.anomaly` events to
+//! `FLAPJACK_KEY_ANOMALY_WEBHOOK_URL` when set. Keys are only ever reported by
+//! their first [`KEY_LABEL_LEN`] characters, since `/metrics` is public.
+
+use dashmap::DashMap;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+pub const KEY_LABEL_LEN: usize = 8;
+/// Label for failures whose key is missing or unknown; attacker-supplied
+/// values never become labels.
+const UNKNOWN_KEY: &str = "unknown";
+/// Client IPs tracked individually; later ones are counted as `other`.
+const MAX_TRACKED_IPS: usize = 10_000;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Smoothing of the per-minute moving average.
+const EWMA_ALPHA: f64 = 0.1;
+/// Completed minutes of history before spikes are reported.
+const WARMUP_MINUTES: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AuthFailure {
+    MissingCredentials,
+    InvalidKey,
+    ExpiredKey,
+    AclDenied,
+    IndexDenied,
+    InvalidClaims,
+    OriginDenied,
+}
+
+impl AuthFailure {
+    pub const ALL: [AuthFailure; 7] = [
+        AuthFailure::MissingCredentials,
+        AuthFailure::InvalidKey,
+        AuthFailure::ExpiredKey,
+        AuthFailure::AclDenied,
+        AuthFailure::IndexDenied,
+        AuthFailure::InvalidClaims,
+        AuthFailure::OriginDenied,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthFailure::MissingCredentials => "missing_credentials",
+            AuthFailure::InvalidKey => "invalid_key",
+            AuthFailure::ExpiredKey => "expired_key",
+            AuthFailure::AclDenied => "acl_denied",
+            AuthFailure::IndexDenied => "index_denied",
+            AuthFailure::InvalidClaims => "invalid_claims",
+            AuthFailure::OriginDenied => "origin_denied",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyAnomaly {
+    UsageSpike { requests: u64, baseline: f64 },
+    NewCountry { country: String },
+}
+
+impl KeyAnomaly {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            KeyAnomaly::UsageSpike { .. } => "usageSpike",
+            KeyAnomaly::NewCountry { .. } => "newCountry",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnomalyConfig {
+    pub spike_factor: f64,
+    pub spike_min_requests: u64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        AnomalyConfig {
+            spike_factor: 10.0,
+            spike_min_requests: 100,
+        }
+    }
+}
+
+impl AnomalyConfig {
+    pub fn from_env() -> Self {
+        let defaults = AnomalyConfig::default();
+        AnomalyConfig {
+            spike_factor: std::env::var("FLAPJACK_KEY_ANOMALY_FACTOR")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.spike_factor),
+            spike_min_requests: std::env::var("FLAPJACK_KEY_ANOMALY_MIN_REQUESTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.spike_min_requests),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct KeyUsage {
+    minute: i64,
+    count: u64,
+    /// Moving average of requests per minute over completed minutes.
+    baseline: f64,
+    minutes_observed: u32,
+    spike_reported: bool,
+    countries: HashSet<String>,
+}
+
+pub struct KeyMonitor {
+    config: AnomalyConfig,
+    failures_by_reason: DashMap<AuthFailure, u64>,
+    failures_by_key: DashMap<String, u64>,
+    failures_by_ip: DashMap<String, u64>,
+    anomalies: DashMap<(String, &'static str), u64>,
+    usage: DashMap<String, KeyUsage>,
+}
+
+pub fn key_label(key: &str) -> String {
+    key.chars().take(KEY_LABEL_LEN).collect()
+}
+
+impl KeyMonitor {
+    pub fn new(config: AnomalyConfig) -> Self {
+        KeyMonitor {
+            config,
+            failures_by_reason: DashMap::new(),
+            failures_by_key: DashMap::new(),
+            failures_by_ip: DashMap::new(),
+            anomalies: DashMap::new(),
+            usage: DashMap::new(),
+        }
+    }
+
+    pub fn global() -> &'static KeyMonitor {
+        static MONITOR: OnceLock<KeyMonitor> = OnceLock::new();
+        MONITOR.get_or_init(|| KeyMonitor::new(AnomalyConfig::from_env()))
+    }
+
+    /// Count a rejected request. `key` is the matched key, if any.
+    pub fn record_failure(&self, reason: AuthFailure, key: Option<&str>, ip: &str) {
+        *self.failures_by_reason.entry(reason).or_default() += 1;
+        let key = key
+            .map(key_label)
+            .unwrap_or_else(|| UNKNOWN_KEY.to_string());
+        *self.failures_by_key.entry(key).or_default() += 1;
+        let ip = if ip.is_empty() {
+            "unknown"
+        } else if self.failures_by_ip.len() >= MAX_TRACKED_IPS
+            && !self.failures_by_ip.contains_key(ip)
+        {
+            "other"
+        } else {
+            ip
+        };
+        *self.failures_by_ip.entry(ip.to_string()).or_default() += 1;
+    }
+
+    /// Record an authenticated request and return the anomalies it reveals.
+    pub fn record_use(&self, key: &str, country: Option<&str>, now_ms: i64) -> Vec<KeyAnomaly> {
+        let mut found = Vec::new();
+        let minute = now_ms.div_euclid(60_000);
+        let mut usage = self
+            .usage
+            .entry(key.to_string())
+            .or_insert_with(|| KeyUsage {
+                minute,
+                ..Default::default()
+            });
+
+        if minute > usage.minute {
+            // Fold the finished minute, then any idle minutes, into the average.
+            let idle = (minute - usage.minute - 1).min(60);
+            for count in std::iter::once(usage.count).chain((0..idle).map(|_| 0)) {
+                usage.baseline = if usage.minutes_observed == 0 {
+                    count as f64
+                } else {
+                    EWMA_ALPHA * count as f64 + (1.0 - EWMA_ALPHA) * usage.baseline
+                };
+                usage.minutes_observed = usage.minutes_observed.saturating_add(1);
+            }
+            usage.minute = minute;
+            usage.count = 0;
+            usage.spike_reported = false;
+        }
+        usage.count += 1;
+
+        if usage.minutes_observed >= WARMUP_MINUTES
+            && !usage.spike_reported
+            && usage.count >= self.config.spike_min_requests
+            && usage.count as f64 >= self.config.spike_factor * usage.baseline.max(1.0)
+        {
+            usage.spike_reported = true;
+            found.push(KeyAnomaly::UsageSpike {
+                requests: usage.count,
+                baseline: usage.baseline,
+            });
+        }
+
+        if let Some(country) = country.filter(|c| !c.is_empty()) {
+            let country = country.to_ascii_uppercase();
+            if !usage.countries.contains(&country) {
+                // The first country a key is seen from is its baseline.
+                if !usage.countries.is_empty() {
+                    found.push(KeyAnomaly::NewCountry {
+                        country: country.clone(),
+                    });
+                }
+                usage.countries.insert(country);
+            }
+        }
+        drop(usage);
+
+        for anomaly in &found {
+            *self
+                .anomalies
+                .entry((key_label(key), anomaly.kind()))
+                .or_default() += 1;
+        }
+        found
+    }
+
+    pub fn failures_by_reason(&self, reason: AuthFailure) -> u64 {
+        self.failures_by_reason.get(&reason).map_or(0, |v| *v)
+    }
+
+    pub fn failures_by_key(&self) -> Vec<(String, u64)> {
+        self.failures_by_key
+            .iter()
+            .map(|e| (e.key().clone(), *e.value()))
+            .collect()
+    }
+
+    pub fn failures_by_ip(&self) -> Vec<(String, u64)> {
+        self.failures_by_ip
+            .iter()
+            .map(|e| (e.key().clone(), *e.value()))
+            .collect()
+    }
+
+    /// `((key label, anomaly kind), count)` pairs.
+    pub fn anomalies(&self) -> Vec<((String, &'static str), u64)> {
+        self.anomalies
+            .iter()
+            .map(|e| (e.key().clone(), *e.value()))
+            .collect()
+    }
+}
+
+/// Log `anomalies` for `key` and send them to the anomaly webhook, if any.
+pub fn report_anomalies(key: &str, ip: &str, anomalies: Vec<KeyAnomaly>) {
+    if anomalies.is_empty() {
+        return;
+    }
+    let label = key_label(key);
+    for anomaly in &anomalies {
+        tracing::warn!(
+            "[AUTH] anomaly on key {}…: {:?} (ip {})",
+            label,
+            anomaly,
+            ip
+        );
+    }
+    let Ok(url) = std::env::var("FLAPJACK_KEY_ANOMALY_WEBHOOK_URL") else {
+        return;
+    };
+    let ip = ip.to_string();
+    tokio::spawn(async move {
+        for anomaly in anomalies {
+            notify_webhook(&url, &label, &ip, &anomaly).await;
+        }
+    });
+}
+
+async fn notify_webhook(url: &str, label: &str, ip: &str, anomaly: &KeyAnomaly) {
+    let mut payload = serde_json::json!({
+        "event": "key.anomaly",
+        "type": anomaly.kind(),
+        "keyPrefix": label,
+        "ip": ip,
+        "at": chrono::Utc::now().to_rfc3339(),
+    });
+    match anomaly {
+        KeyAnomaly::UsageSpike { requests, baseline } => {
+            payload["requestsThisMinute"] = serde_json::json!(requests);
+            payload["averagePerMinute"] = serde_json::json!(baseline);
+        }
+        KeyAnomaly::NewCountry { country } => {
+            payload["country"] = serde_json::json!(country);
+        }
+    }
+    let result = reqwest::Client::new()
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&payload)
+        .send()
+        .await;
+    match result {
+        Ok(resp) if resp.status().is_success() => {}
+        Ok(resp) => tracing::warn!("[AUTH] anomaly webhook returned {}", resp.status()),
+        Err(e) => tracing::warn!("[AUTH] anomaly webhook failed: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: i64 = 60_000;
+
+    #[test]
+    fn failures_never_label_unknown_keys() {
+        let m = KeyMonitor::new(AnomalyConfig::default());
+        m.record_failure(AuthFailure::InvalidKey, None, "1.2.3.4");
+        m.record_failure(AuthFailure::AclDenied, Some("abcdef0123456789"), "1.2.3.4");
+        assert_eq!(m.failures_by_reason(AuthFailure::InvalidKey), 1);
+        let mut by_key = m.failures_by_key();
+        by_key.sort();
+        assert_eq!(
+            by_key,
+            vec![("abcdef01".to_string(), 1), ("unknown".to_string(), 1)]
+        );
+        assert_eq!(m.failures_by_ip(), vec![("1.2.3.4".to_string(), 2)]);
+    }
+
+    #[test]
+    fn tenfold_usage_is_a_spike() {
+        let m = KeyMonitor::new(AnomalyConfig {
+            spike_factor: 10.0,
+            spike_min_requests: 50,
+        });
+        // Twenty minutes at 10 requests per minute.
+        for minute in 0..20 {
+            for _ in 0..10 {
+                assert!(m.record_use("k", None, minute * MINUTE).is_empty());
+            }
+        }
+        let mut spikes = 0;
+        for _ in 0..150 {
+            spikes += m.record_use("k", None, 20 * MINUTE).len();
+        }
+        assert_eq!(spikes, 1, "reported once per minute");
+        assert_eq!(m.anomalies(), vec![(("k".to_string(), "usageSpike"), 1)]);
+    }
+
+    #[test]
+    fn new_country_after_the_first() {
+        let m = KeyMonitor::new(AnomalyConfig::default());
+        assert!(m.record_use("k", Some("us"), 0).is_empty());
+        assert!(m.record_use("k", Some("US"), 1).is_empty());
+        assert_eq!(
+            m.record_use("k", Some("RU"), 2),
+            vec![KeyAnomaly::NewCountry {
+                country: "RU".into()
+            }]
+        );
+        assert!(m.record_use("k", Some("RU"), 3).is_empty());
+    }
+}
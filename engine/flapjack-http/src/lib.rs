@@ -1,19 +1,49 @@
 pub mod analytics_cluster;
+pub mod anonymous_tokens;
+pub mod api_version;
 pub mod auth;
+pub mod bench;
+pub mod body_limits;
+pub mod cluster_snapshot;
+pub mod compaction_scheduler;
+pub mod compression;
+pub mod cors;
+pub mod crawler;
+pub mod disk_watchdog;
 pub mod dto;
+pub mod error_codes;
+pub mod experiment_auto_stop;
+pub mod feature_flags;
 pub mod filter_parser;
 pub mod handlers;
+pub mod html_text;
+pub mod key_monitor;
+pub mod maintenance;
+pub mod mcm;
 pub mod memory_middleware;
 pub mod middleware;
+pub mod namespaces;
 pub mod openapi;
 pub mod pause_registry;
+pub mod request_log;
+pub mod reranking_trainer;
 pub mod rollup_broadcaster;
+pub mod saved_searches;
+pub mod scheduler;
+pub mod search_anomalies;
+pub mod search_cursor;
 pub mod server;
 pub mod startup_catchup;
+#[cfg(test)]
+mod test_support;
+pub mod ttl_sweeper;
 pub mod usage_middleware;
+pub mod warmup;
 
 #[cfg(feature = "vector-search")]
 pub mod embedder_store;
+#[cfg(feature = "file-ingest")]
+pub mod file_ingest;
 #[cfg(feature = "vector-search")]
 pub mod fusion;
 
@@ -0,0 +1,304 @@
+//! Per-index maintenance modes (`/1/indexes/:index/mode`).
+//!
+//! An operator can take one index out of normal service for a migration or
+//! during an incident:
+//!
+//! - `readOnly` — searches and reads work, writes are rejected
+//! - `writeOnly` — writes work, searches and reads are rejected
+//! - `offline` — both are rejected
+//! - `readWrite` — normal service (clears the mode)
+//!
+//! Rejected requests get 503 `index_maintenance` with the mode and the
+//! operator's reason. The mode endpoint itself and task polling always work.
+//! Background writers (TTL sweeps, scheduled compaction, crawls, schedules)
+//! leave an index alone while its mode blocks writes.
+//! Modes persist in `{data_dir}/maintenance.json` and are listed on
+//! `GET /1/indexes`.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use axum::{
+    extract::Request,
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use flapjack::error::FlapjackError;
+use serde::{Deserialize, Serialize};
+
+use crate::body_limits::RouteClass;
+
+const MAINTENANCE_FILE: &str = "maintenance.json";
+
+static REGISTRIES: OnceLock<DashMap<PathBuf, Arc<MaintenanceRegistry>>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MaintenanceMode {
+    ReadWrite,
+    ReadOnly,
+    WriteOnly,
+    Offline,
+}
+
+impl MaintenanceMode {
+    pub fn allows_reads(self) -> bool {
+        matches!(self, MaintenanceMode::ReadWrite | MaintenanceMode::ReadOnly)
+    }
+
+    pub fn allows_writes(self) -> bool {
+        matches!(
+            self,
+            MaintenanceMode::ReadWrite | MaintenanceMode::WriteOnly
+        )
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MaintenanceMode::ReadWrite => "readWrite",
+            MaintenanceMode::ReadOnly => "readOnly",
+            MaintenanceMode::WriteOnly => "writeOnly",
+            MaintenanceMode::Offline => "offline",
+        }
+    }
+}
+
+/// The maintenance mode an index is in, and since when (Unix ms).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexMaintenance {
+    pub mode: MaintenanceMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub since: i64,
+}
+
+impl IndexMaintenance {
+    /// The rejection for a read (`write == false`) or write, if this mode
+    /// blocks it.
+    pub fn rejection(&self, index_name: &str, write: bool) -> Option<FlapjackError> {
+        let allowed = if write {
+            self.mode.allows_writes()
+        } else {
+            self.mode.allows_reads()
+        };
+        if allowed {
+            return None;
+        }
+        let rejected = match self.mode {
+            MaintenanceMode::Offline => "the index is offline",
+            _ if write => "writes are rejected",
+            _ => "searches and reads are rejected",
+        };
+        let detail = match &self.reason {
+            Some(reason) => format!("{} ({})", rejected, reason),
+            None => rejected.to_string(),
+        };
+        Some(FlapjackError::IndexMaintenance {
+            index: index_name.to_string(),
+            mode: self.mode.as_str().to_string(),
+            detail,
+        })
+    }
+}
+
+/// The maintenance modes of one data directory's indexes.
+pub struct MaintenanceRegistry {
+    path: PathBuf,
+    modes: RwLock<BTreeMap<String, IndexMaintenance>>,
+}
+
+impl MaintenanceRegistry {
+    /// The registry for a data directory, loading its modes once.
+    pub fn for_data_dir(data_dir: &Path) -> std::io::Result<Arc<Self>> {
+        let registries = REGISTRIES.get_or_init(DashMap::new);
+        if let Some(registry) = registries.get(data_dir) {
+            return Ok(Arc::clone(&registry));
+        }
+        let registry = Arc::new(Self::load(data_dir.join(MAINTENANCE_FILE))?);
+        Ok(Arc::clone(
+            &registries.entry(data_dir.to_path_buf()).or_insert(registry),
+        ))
+    }
+
+    fn load(path: PathBuf) -> std::io::Result<Self> {
+        let modes = if path.exists() {
+            let json = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&json)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self {
+            path,
+            modes: RwLock::new(modes),
+        })
+    }
+
+    fn save(&self, modes: &BTreeMap<String, IndexMaintenance>) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(modes).map_err(std::io::Error::other)?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(tmp, &self.path)
+    }
+
+    pub fn get(&self, index_name: &str) -> Option<IndexMaintenance> {
+        let modes = self.modes.read().unwrap_or_else(|e| e.into_inner());
+        modes.get(index_name).cloned()
+    }
+
+    /// Put an index in `mode`; `readWrite` clears it.
+    pub fn set(
+        &self,
+        index_name: &str,
+        mode: MaintenanceMode,
+        reason: Option<String>,
+        now_ms: i64,
+    ) -> std::io::Result<()> {
+        let mut modes = self.modes.write().unwrap_or_else(|e| e.into_inner());
+        let mut updated = modes.clone();
+        if mode == MaintenanceMode::ReadWrite {
+            if updated.remove(index_name).is_none() {
+                return Ok(());
+            }
+        } else {
+            updated.insert(
+                index_name.to_string(),
+                IndexMaintenance {
+                    mode,
+                    reason,
+                    since: now_ms,
+                },
+            );
+        }
+        self.save(&updated)?;
+        *modes = updated;
+        Ok(())
+    }
+
+    pub fn check(&self, index_name: &str, write: bool) -> Result<(), FlapjackError> {
+        let modes = self.modes.read().unwrap_or_else(|e| e.into_inner());
+        match modes
+            .get(index_name)
+            .and_then(|m| m.rejection(index_name, write))
+        {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reject a read of `index_name` (for handlers that take index names from
+/// the request body, like multi-index queries).
+pub fn check_readable(data_dir: &Path, index_name: &str) -> Result<(), FlapjackError> {
+    MaintenanceRegistry::for_data_dir(data_dir)?.check(index_name, false)
+}
+
+/// Reject a write to `index_name` (for background writers, which run past
+/// [`maintenance_guard`]).
+pub fn check_writable(data_dir: &Path, index_name: &str) -> Result<(), FlapjackError> {
+    MaintenanceRegistry::for_data_dir(data_dir)?.check(index_name, true)
+}
+
+/// The index a request addresses by path, and whether it writes to it.
+/// `None` for requests no maintenance mode blocks.
+pub(crate) fn classify(method: &Method, path: &str) -> Option<(String, bool)> {
+    let rest = path.strip_prefix("/1/indexes/")?;
+    let mut segments = rest.split('/');
+    // `/1/indexes/{*,queries,objects}` name their indexes in the body.
+    let index_name = segments
+        .next()
+        .filter(|s| !matches!(*s, "" | "*" | "queries" | "objects"))?;
+    if matches!(segments.next(), Some("mode" | "task")) {
+        return None;
+    }
+    let read = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+        || RouteClass::classify(method, path) == RouteClass::Search;
+    Some((index_name.to_string(), !read))
+}
+
+/// Reject requests to indexes whose maintenance mode blocks them.
+pub async fn maintenance_guard(request: Request, next: Next, data_dir: &Path) -> Response {
+    if let Some((index_name, write)) = classify(request.method(), request.uri().path()) {
+        let checked = MaintenanceRegistry::for_data_dir(data_dir)
+            .map_err(FlapjackError::from)
+            .and_then(|registry| registry.check(&index_name, write));
+        if let Err(err) = checked {
+            return err.into_response();
+        }
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_reads_writes_and_exemptions() {
+        let read = |m: Method, p: &str| classify(&m, p);
+        assert_eq!(
+            read(Method::POST, "/1/indexes/products/query"),
+            Some(("products".to_string(), false))
+        );
+        assert_eq!(
+            read(Method::GET, "/1/indexes/products/abc"),
+            Some(("products".to_string(), false))
+        );
+        assert_eq!(
+            read(Method::POST, "/1/indexes/products/batch"),
+            Some(("products".to_string(), true))
+        );
+        assert_eq!(
+            read(Method::DELETE, "/1/indexes/products"),
+            Some(("products".to_string(), true))
+        );
+        assert_eq!(read(Method::POST, "/1/indexes/products/mode"), None);
+        assert_eq!(read(Method::GET, "/1/indexes/products/task/12"), None);
+        assert_eq!(read(Method::POST, "/1/indexes/*/queries"), None);
+        assert_eq!(read(Method::GET, "/1/indexes"), None);
+    }
+
+    #[test]
+    fn modes_block_and_persist() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join(MAINTENANCE_FILE);
+        let registry = MaintenanceRegistry::load(path.clone()).unwrap();
+        registry
+            .set(
+                "products",
+                MaintenanceMode::ReadOnly,
+                Some("migrating".to_string()),
+                1,
+            )
+            .unwrap();
+        registry
+            .set("logs", MaintenanceMode::WriteOnly, None, 2)
+            .unwrap();
+
+        let reloaded = MaintenanceRegistry::load(path).unwrap();
+        assert!(reloaded.check("products", false).is_ok());
+        let err = reloaded.check("products", true).unwrap_err();
+        assert!(err.to_string().contains("readOnly"), "{err}");
+        assert!(err.to_string().contains("migrating"), "{err}");
+        assert!(reloaded.check("logs", false).is_err());
+        assert!(reloaded.check("logs", true).is_ok());
+        assert!(reloaded.check("other", true).is_ok());
+
+        reloaded
+            .set("products", MaintenanceMode::Offline, None, 3)
+            .unwrap();
+        assert!(reloaded.check("products", false).is_err());
+        reloaded
+            .set("products", MaintenanceMode::ReadWrite, None, 4)
+            .unwrap();
+        assert_eq!(reloaded.get("products"), None);
+        assert!(reloaded.check("products", true).is_ok());
+    }
+}
@@ -0,0 +1,472 @@
+//! Multi-cluster user mapping (MCM): request routing and data migration.
+//!
+//! The mapping model lives in `flapjack_replication::user_mapping`; this
+//! module wires it into the server:
+//!
+//! - [`route_user_requests`] wraps the whole router (it rewrites URIs, so it
+//!   must run before routing). `/1/indexes/{index}/...` requests carrying
+//!   `X-Algolia-User-ID` are rewritten to the user's tenant (`{index}@{userID}`)
+//!   when the user is served by this node, or forwarded to the owning node.
+//! - [`spawn_mcm_worker`] ships the tenants of users migrating away from this
+//!   node, deletes the tenants of removed users, and publishes record counts
+//!   for users served here.
+
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::{header, Uri};
+use axum::middleware::Next;
+use axum::response::Response;
+use flapjack::ErrorCode;
+use flapjack_replication::user_mapping::{self, UserMappingStore};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use crate::error_codes::error_response;
+use crate::handlers::AppState;
+
+pub const USER_ID_HEADER: &str = "x-algolia-user-id";
+
+/// Marks requests forwarded to the owning node so they are never forwarded
+/// twice (two nodes with diverging mappings would otherwise bounce them).
+const FORWARDED_HEADER: &str = "x-flapjack-mcm-forwarded";
+
+const FORWARD_TIMEOUT_SECS: u64 = 60;
+
+pub fn local_cluster_name(state: &AppState) -> String {
+    match &state.replication_manager {
+        Some(repl) => repl.node_id().to_string(),
+        None => std::env::var("FLAPJACK_NODE_ID").unwrap_or_else(|_| "unknown".to_string()),
+    }
+}
+
+/// This node followed by its replication peers.
+pub fn cluster_names(state: &AppState) -> Vec<String> {
+    match &state.replication_manager {
+        Some(repl) => repl.cluster_names(),
+        None => vec![local_cluster_name(state)],
+    }
+}
+
+pub fn mapping_store(state: &AppState) -> std::io::Result<Arc<UserMappingStore>> {
+    UserMappingStore::for_data_dir(&state.manager.base_path)
+}
+
+/// Push the local mapping to peers after a change.
+pub fn publish_mapping(state: &AppState, store: &UserMappingStore) {
+    if let Some(repl) = &state.replication_manager {
+        repl.broadcast_user_mapping(store.snapshot());
+    }
+}
+
+/// Raw `{index}` segment of `/1/indexes/{index}/...`.
+fn index_segment(path: &str) -> Option<&str> {
+    let segment = path.strip_prefix("/1/indexes/")?.split('/').next()?;
+    (!segment.is_empty()).then_some(segment)
+}
+
+/// Route `X-Algolia-User-ID` requests to the user's tenant or owning node.
+pub async fn route_user_requests(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(segment) = index_segment(request.uri().path()).map(str::to_string) else {
+        return next.run(request).await;
+    };
+    let user_id = request
+        .headers()
+        .get(USER_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let Some(user_id) = user_id else {
+        // Per-user tenants are only reachable through the header.
+        let index = segment.replace("%40", "@");
+        if let Some((_, user)) = user_mapping::parse_user_tenant(&index) {
+            let reserved = mapping_store(&state)
+                .map(|s| s.snapshot().users.contains_key(user))
+                .unwrap_or(false);
+            if reserved {
+                return error_response(
+                    ErrorCode::BadRequest,
+                    format!(
+                        "Index '{}' holds data for userID '{}'; use the X-Algolia-User-ID header",
+                        index, user
+                    ),
+                );
+            }
+        }
+        return next.run(request).await;
+    };
+
+    if let Err(message) = user_mapping::validate_user_id(&user_id) {
+        return error_response(ErrorCode::BadRequest, message);
+    }
+    if segment == "*" {
+        return error_response(
+            ErrorCode::BadRequest,
+            "X-Algolia-User-ID is not supported on multi-index routes",
+        );
+    }
+    let store = match mapping_store(&state) {
+        Ok(store) => store,
+        Err(e) => return error_response(ErrorCode::IoError, e.to_string()),
+    };
+    let Some(entry) = store.get(&user_id) else {
+        return error_response(
+            ErrorCode::NotFound,
+            format!(
+                "userID '{}' is not assigned to a cluster; assign it with POST /1/clusters/mapping",
+                user_id
+            ),
+        );
+    };
+
+    let serving = entry.serving_cluster();
+    if serving == local_cluster_name(&state) {
+        let path = request.uri().path();
+        let rewritten = format!(
+            "/1/indexes/{}{}",
+            user_mapping::user_tenant(&segment, &user_id),
+            &path["/1/indexes/".len() + segment.len()..]
+        );
+        let rewritten = match request.uri().query() {
+            Some(query) => format!("{}?{}", rewritten, query),
+            None => rewritten,
+        };
+        match rewritten.parse::<Uri>() {
+            Ok(uri) => *request.uri_mut() = uri,
+            Err(e) => return error_response(ErrorCode::BadRequest, e.to_string()),
+        }
+        return next.run(request).await;
+    }
+
+    if request.headers().contains_key(FORWARDED_HEADER) {
+        return error_response(
+            ErrorCode::ServiceUnavailable,
+            format!(
+                "userID '{}' is served by cluster '{}'; mapping is still propagating, retry shortly",
+                user_id, serving
+            ),
+        );
+    }
+    let Some(peer) = state
+        .replication_manager
+        .as_ref()
+        .and_then(|repl| repl.peer(serving))
+    else {
+        return error_response(
+            ErrorCode::ServiceUnavailable,
+            format!("Cluster '{}' is not a known peer", serving),
+        );
+    };
+    forward(peer.base_url(), request).await
+}
+
+fn forward_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(FORWARD_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new())
+    })
+}
+
+/// Proxy `request` unchanged (auth headers included) to `base_url`.
+async fn forward(base_url: &str, request: Request) -> Response {
+    let (parts, body) = request.into_parts();
+    let body =
+        match axum::body::to_bytes(body, crate::body_limits::BodyLimits::global().max()).await {
+            Ok(bytes) => bytes,
+            Err(e) => return error_response(ErrorCode::PayloadTooLarge, e.to_string()),
+        };
+    let path = parts
+        .uri
+        .path_and_query()
+        .map(|pq| pq.as_str())
+        .unwrap_or("/");
+
+    let mut outbound = forward_client()
+        .request(parts.method, format!("{}{}", base_url, path))
+        .header(FORWARDED_HEADER, "1")
+        .body(body);
+    for (name, value) in &parts.headers {
+        if name != header::HOST && name != header::CONTENT_LENGTH {
+            outbound = outbound.header(name, value);
+        }
+    }
+
+    let upstream = match outbound.send().await {
+        Ok(resp) => resp,
+        Err(e) => {
+            return error_response(
+                ErrorCode::BadGateway,
+                format!("Failed to reach {}: {}", base_url, e),
+            )
+        }
+    };
+    let status = upstream.status();
+    let headers = upstream.headers().clone();
+    let bytes = match upstream.bytes().await {
+        Ok(bytes) => bytes,
+        Err(e) => return error_response(ErrorCode::BadGateway, e.to_string()),
+    };
+    let mut response = Response::new(Body::from(bytes));
+    *response.status_mut() = status;
+    for (name, value) in &headers {
+        if name != header::CONTENT_LENGTH && name != header::TRANSFER_ENCODING {
+            response.headers_mut().append(name, value.clone());
+        }
+    }
+    response
+}
+
+/// Local per-user tenants grouped by userID.
+fn local_user_tenants(base_path: &Path) -> BTreeMap<String, Vec<String>> {
+    let mut tenants: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let Ok(entries) = std::fs::read_dir(base_path) else {
+        return tenants;
+    };
+    for entry in entries.flatten() {
+        if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        if let Some((_, user)) = user_mapping::parse_user_tenant(&name) {
+            tenants.entry(user.to_string()).or_default().push(name);
+        }
+    }
+    tenants
+}
+
+/// Copy `tenants` to `cluster` and delete them locally. Each tenant is paused
+/// while it is exported so no write lands after the copy is taken.
+async fn ship_tenants(state: &AppState, cluster: &str, tenants: &[String]) -> Result<(), String> {
+    let peer = state
+        .replication_manager
+        .as_ref()
+        .and_then(|repl| repl.peer(cluster))
+        .ok_or_else(|| format!("cluster '{}' is not a known peer", cluster))?;
+
+    for tenant in tenants {
+        state.paused_indexes.pause(tenant);
+        let result = async {
+            while state.manager.pending_task_count(tenant) > 0 {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            let bytes =
+                flapjack::index::snapshot::export_to_bytes(&state.manager.base_path.join(tenant))
+                    .map_err(|e| format!("export of {} failed: {}", tenant, e))?;
+            peer.import_tenant(tenant, bytes).await?;
+            state
+                .manager
+                .delete_tenant(tenant)
+                .await
+                .map_err(|e| format!("delete of {} failed: {}", tenant, e))
+        }
+        .await;
+        state.paused_indexes.resume(tenant);
+        result?;
+    }
+    Ok(())
+}
+
+/// One pass of MCM housekeeping for this node.
+pub async fn run_mcm_pass(state: &AppState) {
+    let store = match mapping_store(state) {
+        Ok(store) => store,
+        Err(e) => {
+            tracing::warn!("[MCM] Failed to load user mapping: {}", e);
+            return;
+        }
+    };
+    let local = local_cluster_name(state);
+    let snapshot = store.snapshot();
+    let tenants = local_user_tenants(&state.manager.base_path);
+    let mut changed = false;
+
+    for (user, entry) in &snapshot.users {
+        let user_tenants = tenants.get(user).map(Vec::as_slice).unwrap_or(&[]);
+        if entry.deleted {
+            for tenant in user_tenants {
+                if let Err(e) = state.manager.delete_tenant(tenant).await {
+                    tracing::warn!("[MCM] Failed to delete {}: {}", tenant, e);
+                }
+            }
+            continue;
+        }
+        if entry.migrating_from.as_deref() != Some(local.as_str()) {
+            continue;
+        }
+        match ship_tenants(state, &entry.cluster_name, user_tenants).await {
+            Ok(()) => {
+                let now = chrono::Utc::now().timestamp_millis();
+                match store.complete_migration(user, &local, now) {
+                    Ok(done) => changed |= done,
+                    Err(e) => tracing::warn!("[MCM] Failed to save mapping: {}", e),
+                }
+                tracing::info!(
+                    "[MCM] Migrated userID {} ({} tenants) to {}",
+                    user,
+                    user_tenants.len(),
+                    entry.cluster_name
+                );
+            }
+            Err(e) => tracing::warn!("[MCM] Migration of userID {} failed: {}", user, e),
+        }
+    }
+
+    let stats: Vec<(String, u64, u64)> = snapshot
+        .users
+        .iter()
+        .filter(|(_, e)| !e.deleted && e.serving_cluster() == local)
+        .map(|(user, _)| {
+            let user_tenants = tenants.get(user).map(Vec::as_slice).unwrap_or(&[]);
+            let nb_records = user_tenants
+                .iter()
+                .filter_map(|t| state.manager.get_or_load(t).ok())
+                .map(|index| index.reader().searcher().num_docs())
+                .sum();
+            let data_size = user_tenants
+                .iter()
+                .map(|t| state.manager.tenant_storage_bytes(t))
+                .sum();
+            (user.clone(), nb_records, data_size)
+        })
+        .collect();
+    match store.update_stats(&stats, chrono::Utc::now().timestamp_millis()) {
+        Ok(updated) => changed |= updated,
+        Err(e) => tracing::warn!("[MCM] Failed to save user stats: {}", e),
+    }
+
+    if changed {
+        publish_mapping(state, &store);
+    }
+}
+
+/// Spawn the background MCM loop.
+pub fn spawn_mcm_worker(state: Arc<AppState>, tick_secs: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(tick_secs.max(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            run_mcm_pass(&state).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::app_state;
+    use axum::extract::Path as AxumPath;
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use axum::Router;
+    use serde_json::json;
+    use tempfile::TempDir;
+    use tower::ServiceExt;
+
+    async fn call(state: &Arc<AppState>, path: &str, user: Option<&str>) -> (StatusCode, String) {
+        let mut builder = Request::builder().uri(path);
+        if let Some(user) = user {
+            builder = builder.header(USER_ID_HEADER, user);
+        }
+        let router = Router::new().route(
+            "/1/indexes/:indexName/query",
+            get(|AxumPath(index): AxumPath<String>| async move { index }),
+        );
+        let app = axum::middleware::from_fn_with_state(Arc::clone(state), route_user_requests)
+            .layer(router);
+        let resp = app
+            .oneshot(builder.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = resp.status();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8_lossy(&body).to_string())
+    }
+
+    #[tokio::test]
+    async fn user_requests_are_rewritten_to_user_tenant() {
+        let tmp = TempDir::new().unwrap();
+        let state = app_state(&tmp);
+        let store = mapping_store(&state).unwrap();
+        store
+            .assign(&["u1".to_string()], &local_cluster_name(&state), 1)
+            .unwrap();
+
+        assert_eq!(
+            call(&state, "/1/indexes/products/query?x=1", Some("u1")).await,
+            (StatusCode::OK, "products@u1".to_string())
+        );
+        // Without the header the shared index is used.
+        assert_eq!(
+            call(&state, "/1/indexes/products/query", None).await,
+            (StatusCode::OK, "products".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn user_tenants_are_not_addressable_directly() {
+        let tmp = TempDir::new().unwrap();
+        let state = app_state(&tmp);
+        mapping_store(&state)
+            .unwrap()
+            .assign(&["u1".to_string()], &local_cluster_name(&state), 1)
+            .unwrap();
+
+        let (status, _) = call(&state, "/1/indexes/products%40u1/query", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        // Unrelated index names containing '@' keep working.
+        let (status, _) = call(&state, "/1/indexes/team@home/query", None).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn unassigned_or_invalid_user_ids_are_rejected() {
+        let tmp = TempDir::new().unwrap();
+        let state = app_state(&tmp);
+
+        let (status, _) = call(&state, "/1/indexes/products/query", Some("nobody")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = call(&state, "/1/indexes/products/query", Some("a@b")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = call(&state, "/1/indexes/*/query", Some("nobody")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn mcm_pass_records_stats_and_drops_removed_users() {
+        let tmp = TempDir::new().unwrap();
+        let state = app_state(&tmp);
+        let store = mapping_store(&state).unwrap();
+        let local = local_cluster_name(&state);
+        store
+            .assign(&["u1".to_string(), "u2".to_string()], &local, 1)
+            .unwrap();
+        for tenant in ["products@u1", "products@u2"] {
+            state.manager.create_tenant(tenant).unwrap();
+            let doc = flapjack::types::Document::from_json(&json!({"objectID": "1"})).unwrap();
+            state
+                .manager
+                .add_documents_sync(tenant, vec![doc])
+                .await
+                .unwrap();
+        }
+        store.remove("u2", 2).unwrap();
+
+        run_mcm_pass(&state).await;
+
+        assert_eq!(store.user_id("u1").unwrap().nb_records, 1);
+        assert!(!tmp.path().join("products@u2").exists());
+        assert!(tmp.path().join("products@u1").exists());
+    }
+}
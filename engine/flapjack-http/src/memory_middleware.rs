@@ -50,7 +50,7 @@ pub async fn memory_pressure_guard(
             let path = request.uri().path().to_string();
             let method = request.method().clone();
 
-            if path == "/health" || path.starts_with("/internal/") {
+            if path == "/health" || path.starts_with("/health/") || path.starts_with("/internal/") {
                 return next.run(request).await;
             }
 
@@ -63,7 +63,7 @@ pub async fn memory_pressure_guard(
         PressureLevel::Critical => {
             let path = request.uri().path().to_string();
 
-            if path == "/health" || path == "/internal/status" {
+            if path == "/health" || path.starts_with("/health/") || path == "/internal/status" {
                 return next.run(request).await;
             }
 
@@ -79,6 +79,9 @@ fn reject_memory_pressure(observer: &flapjack::MemoryObserver) -> Response {
 
     let body = serde_json::json!({
         "error": "memory_pressure",
+        "message": "Server is under memory pressure",
+        "code": flapjack::ErrorCode::MemoryPressure,
+        "status": 503,
         "allocated_mb": allocated_mb,
         "limit_mb": limit_mb,
         "level": stats.pressure_level.to_string(),
@@ -0,0 +1,282 @@
+//! Namespace-scoped API keys (see `flapjack::index::namespace`).
+//!
+//! A key with a `namespace` names its indexes without the namespace prefix.
+//! [`scope_namespace_requests`] runs right after authentication (so the
+//! key's `indexes` patterns match the plain names) and rewrites
+//! `/1/indexes/products/...` to `/1/indexes/acme~products/...`. Index names
+//! in request bodies are qualified likewise: the `indexName`s of multi-index
+//! queries and `getObjects`, the `uid` of a new index, the `destination` of a
+//! copy, move or clone and the replica `index`es of `sortReplicas`.
+//! `GET /1/indexes` lists only the namespace's indexes.
+//!
+//! Everything else — server-wide APIs, analytics, other namespaces — is
+//! refused with 403, except the key management and `GET /1/namespaces/:name`
+//! that auth lets namespace admin keys through to.
+
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::{header, Method, Uri};
+use axum::middleware::Next;
+use axum::response::Response;
+use flapjack::index::namespace::{namespaced_tenant, parse_namespaced_tenant};
+use flapjack::ErrorCode;
+use serde_json::json;
+
+use crate::auth::KeyNamespace;
+use crate::error_codes::error_response;
+
+/// Whether a namespace-scoped key may use `path` at all.
+fn allowed(namespace: &KeyNamespace, path: &str) -> bool {
+    path == "/1/indexes"
+        || path.starts_with("/1/indexes/")
+        || path.starts_with("/1/keys")
+        || path.strip_prefix("/1/namespaces/") == Some(namespace.name.as_str())
+}
+
+/// `path` with its `/1/indexes/{index}` segment qualified by `namespace`.
+/// `None` for paths that name no single index.
+fn scoped_path(namespace: &str, path: &str) -> Option<String> {
+    let rest = path.strip_prefix("/1/indexes/")?;
+    let (segment, tail) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, ""),
+    };
+    if segment.is_empty() || segment == "*" {
+        return None;
+    }
+    Some(format!(
+        "/1/indexes/{}{}",
+        namespaced_tenant(namespace, segment),
+        tail
+    ))
+}
+
+/// The body fields of a route that name indexes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BodyFields {
+    /// `requests[].indexName` of multi-index queries and `getObjects`.
+    Requests,
+    /// `uid` of `POST /1/indexes`.
+    Uid,
+    /// `destination` of a copy, move or clone.
+    Destination,
+    /// `sortReplicas.*.index` of a settings update.
+    SortReplicas,
+}
+
+fn body_fields(method: &Method, path: &str) -> Option<BodyFields> {
+    if path == "/1/indexes/*/queries" || path == "/1/indexes/*/objects" {
+        return Some(BodyFields::Requests);
+    }
+    if path == "/1/indexes" {
+        return (method == Method::POST).then_some(BodyFields::Uid);
+    }
+    let rest = path.strip_prefix("/1/indexes/")?;
+    match rest.split_once('/')?.1 {
+        "operation" | "clone" if method == Method::POST => Some(BodyFields::Destination),
+        "settings" if method == Method::POST || method == Method::PUT => {
+            Some(BodyFields::SortReplicas)
+        }
+        _ => None,
+    }
+}
+
+/// Qualify the index names in `fields` of a JSON body. Bodies that are not
+/// JSON are left for the handler to reject.
+fn scope_body(namespace: &str, fields: BodyFields, body: &[u8]) -> Option<Vec<u8>> {
+    let mut json: serde_json::Value = serde_json::from_slice(body).ok()?;
+    match fields {
+        BodyFields::Requests => {
+            for request in json.get_mut("requests")?.as_array_mut()? {
+                if let Some(name) = request.get("indexName").and_then(|n| n.as_str()) {
+                    request["indexName"] = json!(namespaced_tenant(namespace, name));
+                }
+            }
+        }
+        BodyFields::Uid | BodyFields::Destination => {
+            let field = if fields == BodyFields::Uid {
+                "uid"
+            } else {
+                "destination"
+            };
+            if let Some(name) = json.get(field).and_then(|n| n.as_str()) {
+                json[field] = json!(namespaced_tenant(namespace, name));
+            }
+        }
+        BodyFields::SortReplicas => {
+            let replicas = json.get_mut("sortReplicas")?.as_object_mut()?;
+            for replica in replicas.values_mut() {
+                let Some(name) = replica.get("index").and_then(|n| n.as_str()) else {
+                    continue;
+                };
+                // Settings read back show qualified replica names; saving
+                // them again must not qualify them twice.
+                let qualified = match parse_namespaced_tenant(name) {
+                    Some((ns, _)) if ns == namespace => name.to_string(),
+                    _ => namespaced_tenant(namespace, name),
+                };
+                replica["index"] = json!(qualified);
+            }
+        }
+    }
+    serde_json::to_vec(&json).ok()
+}
+
+/// Confine requests made with a namespace-scoped key to its namespace.
+pub async fn scope_namespace_requests(mut request: Request, next: Next) -> Response {
+    let Some(namespace) = request.extensions().get::<KeyNamespace>().cloned() else {
+        return next.run(request).await;
+    };
+    let path = request.uri().path().to_string();
+    if !allowed(&namespace, &path) {
+        return error_response(
+            ErrorCode::Forbidden,
+            format!(
+                "Keys of namespace '{}' can only reach the namespace's indexes",
+                namespace.name
+            ),
+        );
+    }
+
+    let fields = body_fields(request.method(), &path);
+    if let Some(scoped) = scoped_path(&namespace.name, &path) {
+        let scoped = match request.uri().query() {
+            Some(query) => format!("{}?{}", scoped, query),
+            None => scoped,
+        };
+        match scoped.parse::<Uri>() {
+            Ok(uri) => *request.uri_mut() = uri,
+            Err(e) => return error_response(ErrorCode::BadRequest, e.to_string()),
+        }
+    } else if path.starts_with("/1/indexes/") && fields != Some(BodyFields::Requests) {
+        return error_response(
+            ErrorCode::Forbidden,
+            "Multi-index routes other than queries and objects are not available to namespace keys",
+        );
+    }
+
+    if let Some(fields) = fields {
+        let (mut parts, body) = request.into_parts();
+        let bytes = match axum::body::to_bytes(body, crate::body_limits::BodyLimits::global().max())
+            .await
+        {
+            Ok(bytes) => bytes,
+            Err(e) => return error_response(ErrorCode::PayloadTooLarge, e.to_string()),
+        };
+        let body = match scope_body(&namespace.name, fields, &bytes) {
+            Some(scoped) => {
+                parts.headers.remove(header::CONTENT_LENGTH);
+                Body::from(scoped)
+            }
+            None => Body::from(bytes),
+        };
+        request = Request::from_parts(parts, body);
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_and_bodies_are_qualified() {
+        assert_eq!(
+            scoped_path("acme", "/1/indexes/products/query").as_deref(),
+            Some("/1/indexes/acme~products/query")
+        );
+        assert_eq!(
+            scoped_path("acme", "/1/indexes/products").as_deref(),
+            Some("/1/indexes/acme~products")
+        );
+        assert_eq!(scoped_path("acme", "/1/indexes/*/queries"), None);
+        assert_eq!(scoped_path("acme", "/1/indexes"), None);
+
+        let body = br#"{"requests":[{"indexName":"products","query":"a"},{"params":""}]}"#;
+        let scoped: serde_json::Value =
+            serde_json::from_slice(&scope_body("acme", BodyFields::Requests, body).unwrap())
+                .unwrap();
+        assert_eq!(scoped["requests"][0]["indexName"], "acme~products");
+        assert_eq!(scoped["requests"][0]["query"], "a");
+        assert!(scoped["requests"][1].get("indexName").is_none());
+        assert_eq!(scope_body("acme", BodyFields::Requests, b"not json"), None);
+    }
+
+    fn scoped(method: Method, path: &str, body: serde_json::Value) -> serde_json::Value {
+        let fields = body_fields(&method, path).expect("route has index names in its body");
+        let bytes = scope_body("acme", fields, body.to_string().as_bytes()).unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[test]
+    fn created_index_uid_is_qualified() {
+        let body = scoped(Method::POST, "/1/indexes", json!({"uid": "victim"}));
+        assert_eq!(body["uid"], "acme~victim");
+        assert_eq!(body_fields(&Method::GET, "/1/indexes"), None);
+    }
+
+    #[test]
+    fn operation_destination_is_qualified() {
+        for operation in ["copy", "move"] {
+            let body = scoped(
+                Method::POST,
+                "/1/indexes/products/operation",
+                json!({"operation": operation, "destination": "victim"}),
+            );
+            assert_eq!(body["destination"], "acme~victim");
+            assert_eq!(body["operation"], operation);
+        }
+    }
+
+    #[test]
+    fn clone_destination_is_qualified() {
+        let body = scoped(
+            Method::POST,
+            "/1/indexes/products/clone",
+            json!({"destination": "victim", "mode": "settingsOnly"}),
+        );
+        assert_eq!(body["destination"], "acme~victim");
+    }
+
+    #[test]
+    fn sort_replica_indexes_are_qualified_once() {
+        let body = scoped(
+            Method::PUT,
+            "/1/indexes/products/settings",
+            json!({"sortReplicas": {
+                "cheapest": {"index": "victim"},
+                "saved": {"index": "acme~products_newest"},
+                "oldest": {"sort": ["date:asc"]}
+            }}),
+        );
+        assert_eq!(body["sortReplicas"]["cheapest"]["index"], "acme~victim");
+        assert_eq!(
+            body["sortReplicas"]["saved"]["index"],
+            "acme~products_newest"
+        );
+        assert!(body["sortReplicas"]["oldest"].get("index").is_none());
+        assert_eq!(
+            scope_body(
+                "acme",
+                BodyFields::SortReplicas,
+                br#"{"searchableAttributes":[]}"#
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn namespace_keys_only_reach_index_and_key_routes() {
+        let ns = KeyNamespace {
+            name: "acme".to_string(),
+            admin: false,
+        };
+        assert!(allowed(&ns, "/1/indexes"));
+        assert!(allowed(&ns, "/1/indexes/products/batch"));
+        assert!(allowed(&ns, "/1/namespaces/acme"));
+        assert!(!allowed(&ns, "/1/namespaces/globex"));
+        assert!(!allowed(&ns, "/1/tasks/123"));
+        assert!(!allowed(&ns, "/2/searches"));
+        assert!(!allowed(&ns, "/1/settings/bulk"));
+    }
+}
@@ -18,13 +18,23 @@ use utoipa::OpenApi;
     ),
     paths(
         crate::handlers::health::health,
+        crate::handlers::health::health_live,
+        crate::handlers::health::health_ready,
         crate::handlers::indices::create_index,
         crate::handlers::indices::delete_index,
         crate::handlers::indices::list_indices,
         crate::handlers::indices::clear_index,
         crate::handlers::indices::operation_index,
+        crate::handlers::indices::clone_index,
+        crate::handlers::indices::index_stats,
+        crate::handlers::indices::duplicate_report,
+        crate::handlers::indices::get_index_mode,
+        crate::handlers::indices::set_index_mode,
+        crate::handlers::indices::get_warmup,
+        crate::handlers::indices::start_warmup,
         crate::handlers::search::search,
         crate::handlers::search::batch_search,
+        crate::handlers::search::analyze_query,
         crate::handlers::objects::add_documents,
         crate::handlers::objects::get_object,
         crate::handlers::objects::delete_object,
@@ -37,30 +47,47 @@ use utoipa::OpenApi;
         crate::handlers::settings::set_settings,
         crate::handlers::tasks::get_task,
         crate::handlers::tasks::get_task_for_index,
+        crate::handlers::tasks::list_tasks,
         crate::handlers::synonyms::get_synonym,
         crate::handlers::synonyms::save_synonym,
         crate::handlers::synonyms::delete_synonym,
         crate::handlers::synonyms::save_synonyms,
         crate::handlers::synonyms::clear_synonyms,
         crate::handlers::synonyms::search_synonyms,
+        crate::handlers::synonyms::get_synonym_stats,
         crate::handlers::rules::get_rule,
         crate::handlers::rules::save_rule,
         crate::handlers::rules::delete_rule,
         crate::handlers::rules::save_rules,
         crate::handlers::rules::clear_rules,
         crate::handlers::rules::search_rules,
+        crate::handlers::rules::get_rule_stats,
+        crate::handlers::blocklist::list_blocked_results,
+        crate::handlers::blocklist::get_blocked_results,
+        crate::handlers::blocklist::save_blocked_results,
+        crate::handlers::blocklist::delete_blocked_results,
+        crate::handlers::campaigns::list_campaigns,
+        crate::handlers::campaigns::get_campaign,
+        crate::handlers::campaigns::save_campaign,
+        crate::handlers::campaigns::delete_campaign,
+        crate::handlers::campaigns::get_campaign_stats,
+        crate::handlers::configuration::get_configuration,
         crate::handlers::keys::create_key,
         crate::handlers::keys::list_keys,
         crate::handlers::keys::get_key,
         crate::handlers::keys::update_key,
         crate::handlers::keys::delete_key,
         crate::handlers::keys::restore_key,
+        crate::handlers::keys::rotate_key,
+        crate::handlers::keys::key_audit_log,
         crate::handlers::keys::generate_secured_key,
         crate::handlers::snapshot::export_snapshot,
         crate::handlers::snapshot::import_snapshot,
         crate::handlers::snapshot::snapshot_to_s3,
         crate::handlers::snapshot::restore_from_s3,
         crate::handlers::snapshot::list_s3_snapshots,
+        crate::handlers::snapshot::create_cluster_snapshot,
+        crate::handlers::snapshot::list_cluster_snapshots,
     ),
     components(
         schemas(
@@ -68,10 +95,13 @@ use utoipa::OpenApi;
             crate::dto::IndexSchema,
             crate::handlers::indices::CreateIndexResponse,
             crate::handlers::indices::OperationIndexRequest,
+            crate::handlers::indices::CloneIndexRequest,
+            crate::handlers::indices::CloneMode,
             crate::dto::SearchRequest,
             crate::dto::AddDocumentsRequest,
             crate::dto::BatchOperation,
             crate::dto::AddDocumentsResponse,
+            crate::dto::RejectedObject,
             crate::dto::GetObjectsRequest,
             crate::dto::GetObjectRequest,
             crate::dto::GetObjectsResponse,
@@ -80,6 +110,7 @@ use utoipa::OpenApi;
             crate::dto::SearchFacetValuesResponse,
             crate::dto::FacetHit,
             crate::dto::TaskResponse,
+            crate::handlers::tasks::TaskListResponse,
             crate::dto::DocFailureDto,
         )
     ),
@@ -91,6 +122,8 @@ use utoipa::OpenApi;
         (name = "settings", description = "Index settings"),
         (name = "synonyms", description = "Synonym management"),
         (name = "rules", description = "Query rules"),
+        (name = "blocklist", description = "Results never returned for a query"),
+        (name = "campaigns", description = "Merchandising campaigns grouping rules"),
         (name = "keys", description = "API key management"),
         (name = "snapshots", description = "Backup and restore operations"),
         (name = "tasks", description = "Task status endpoints"),
@@ -0,0 +1,541 @@
+//! In-memory request log backing `GET /1/logs`.
+//!
+//! Every API request (except health, metrics, dashboard, internal replication
+//! traffic, the logs endpoint itself and the key and user token endpoints,
+//! whose bodies carry plaintext credentials) is recorded in a fixed-size ring
+//! buffer with the fields of the Algolia logs API: method, URL, status,
+//! processing time, redacted headers and truncated request/response bodies.
+//! All values are strings, as in Algolia's response.
+//!
+//! The buffer holds `FLAPJACK_REQUEST_LOG_SIZE` entries (default 1000, `0`
+//! disables logging). Logs are per node and are lost on restart.
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{ready, Context, Poll};
+use std::time::Instant;
+
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::Request,
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use flapjack::ErrorCode;
+use http_body::{Frame, SizeHint};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::error_codes::error_response;
+
+pub const DEFAULT_CAPACITY: usize = 1000;
+/// Largest page `GET /1/logs` returns, as in Algolia.
+pub const MAX_PAGE_LENGTH: usize = 1000;
+pub const DEFAULT_PAGE_LENGTH: usize = 10;
+/// Request and response bodies are cut to this many characters.
+pub const MAX_BODY_CHARS: usize = 1000;
+/// Bytes kept from each body: enough for [`MAX_BODY_CHARS`] characters of
+/// any width.
+const MAX_CAPTURE_BYTES: usize = MAX_BODY_CHARS * 4;
+
+const REDACTED_HEADERS: &[&str] = &["x-algolia-api-key", "authorization", "cookie"];
+
+/// Which entries `GET /1/logs?type=` returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogType {
+    All,
+    /// Search, multi-search, browse and facet search requests.
+    Query,
+    /// Requests that change data or settings.
+    Build,
+    /// Requests answered with a 4xx or 5xx status.
+    Error,
+}
+
+impl LogType {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "all" => Some(LogType::All),
+            "query" => Some(LogType::Query),
+            "build" => Some(LogType::Build),
+            "error" => Some(LogType::Error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestLogEntry {
+    pub timestamp: String,
+    pub method: String,
+    pub answer_code: String,
+    pub query_body: String,
+    pub answer: String,
+    pub url: String,
+    pub ip: String,
+    pub query_headers: String,
+    pub sha1: String,
+    pub nb_api_calls: String,
+    pub processing_time_ms: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_nb_hits: Option<String>,
+    #[serde(skip)]
+    pub is_query: bool,
+}
+
+impl RequestLogEntry {
+    fn matches(&self, log_type: LogType, index: Option<&str>) -> bool {
+        if let Some(index) = index {
+            if self.index.as_deref() != Some(index) {
+                return false;
+            }
+        }
+        match log_type {
+            LogType::All => true,
+            LogType::Query => self.is_query,
+            LogType::Build => !self.is_query && self.method != "GET",
+            LogType::Error => {
+                self.answer_code.starts_with('4') || self.answer_code.starts_with('5')
+            }
+        }
+    }
+}
+
+pub struct RequestLog {
+    entries: Mutex<VecDeque<RequestLogEntry>>,
+    capacity: usize,
+}
+
+impl RequestLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// The process-wide log, sized from `FLAPJACK_REQUEST_LOG_SIZE`.
+    pub fn global() -> &'static RequestLog {
+        static LOG: OnceLock<RequestLog> = OnceLock::new();
+        LOG.get_or_init(|| {
+            let capacity = std::env::var("FLAPJACK_REQUEST_LOG_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_CAPACITY);
+            RequestLog::new(capacity)
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Append an entry, evicting the oldest one when full.
+    pub fn push(&self, entry: RequestLogEntry) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Matching entries, newest first, skipping `offset` and returning at most
+    /// `length`.
+    pub fn query(
+        &self,
+        offset: usize,
+        length: usize,
+        log_type: LogType,
+        index: Option<&str>,
+    ) -> Vec<RequestLogEntry> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .iter()
+            .rev()
+            .filter(|e| e.matches(log_type, index))
+            .skip(offset)
+            .take(length)
+            .cloned()
+            .collect()
+    }
+}
+
+fn is_logged_path(path: &str) -> bool {
+    !(path == "/1/logs"
+        || path.starts_with("/1/keys")
+        || path == "/1/users/token"
+        || path == "/health"
+        || path.starts_with("/health/")
+        || path == "/metrics"
+        || path.starts_with("/dashboard")
+        || path.starts_with("/internal/")
+        || path.starts_with("/swagger-ui")
+        || path.starts_with("/api-docs"))
+}
+
+fn is_query_path(path: &str) -> bool {
+    path.ends_with("/query")
+        || path.ends_with("/queries")
+        || path.ends_with("/browse")
+        || path.contains("/facets/")
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_BODY_CHARS) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text.to_string(),
+    }
+}
+
+fn redact(value: &str) -> String {
+    let visible: String = value.chars().take(4).collect();
+    format!("{}****", visible)
+}
+
+/// Headers as `Name: value` lines, with credentials redacted.
+fn format_headers(headers: &HeaderMap) -> String {
+    let mut out = String::new();
+    for (name, value) in headers {
+        let value = value.to_str().unwrap_or("");
+        let value = if REDACTED_HEADERS.contains(&name.as_str()) {
+            redact(value)
+        } else {
+            value.to_string()
+        };
+        out.push_str(name.as_str());
+        out.push_str(": ");
+        out.push_str(&value);
+        out.push('\n');
+    }
+    out
+}
+
+pub(crate) fn client_ip(headers: &HeaderMap) -> String {
+    headers
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
+        .or_else(|| {
+            headers
+                .get("x-real-ip")
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        })
+        .unwrap_or_default()
+}
+
+/// `nbHits` of a search response, summed over `results` for multi-search.
+fn nb_hits(answer: &[u8]) -> Option<u64> {
+    let value: serde_json::Value = serde_json::from_slice(answer).ok()?;
+    if let Some(hits) = value.get("nbHits").and_then(|v| v.as_u64()) {
+        return Some(hits);
+    }
+    let results = value.get("results")?.as_array()?;
+    Some(
+        results
+            .iter()
+            .filter_map(|r| r.get("nbHits").and_then(|v| v.as_u64()))
+            .sum(),
+    )
+}
+
+fn entry_id(timestamp: &chrono::DateTime<chrono::Utc>, url: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(
+        timestamp
+            .timestamp_nanos_opt()
+            .unwrap_or_default()
+            .to_le_bytes(),
+    );
+    hasher.update(url.as_bytes());
+    hasher.update(body);
+    hasher.update(uuid::Uuid::new_v4().as_bytes());
+    hex::encode(&hasher.finalize()[..20])
+}
+
+/// The first [`MAX_CAPTURE_BYTES`] of a body, shared with the [`Tee`] that
+/// fills it.
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<u8>>>);
+
+impl Capture {
+    fn record(&self, data: &[u8]) {
+        let mut captured = self.0.lock().unwrap();
+        let room = MAX_CAPTURE_BYTES.saturating_sub(captured.len());
+        captured.extend_from_slice(&data[..room.min(data.len())]);
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn text(&self) -> String {
+        truncate(&String::from_utf8_lossy(&self.0.lock().unwrap()))
+    }
+}
+
+/// Streams a body through unchanged, recording its start in a [`Capture`].
+/// A response tee carries the request's log entry and pushes it, with the
+/// captured answer, once the body is dropped: fully sent or abandoned.
+struct Tee {
+    inner: Body,
+    capture: Capture,
+    pending: Option<RequestLogEntry>,
+}
+
+impl HttpBody for Tee {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, axum::Error>>> {
+        let frame = ready!(Pin::new(&mut self.inner).poll_frame(cx));
+        if let Some(data) = frame.as_ref().and_then(|f| f.as_ref().ok()?.data_ref()) {
+            self.capture.record(data);
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for Tee {
+    fn drop(&mut self) {
+        if let Some(mut entry) = self.pending.take() {
+            entry.answer = self.capture.text();
+            RequestLog::global().push(entry);
+        }
+    }
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/json"))
+}
+
+/// Axum middleware that records each request in [`RequestLog::global`].
+pub async fn request_log_layer(request: Request, next: Next) -> Response {
+    let log = RequestLog::global();
+    let path = request.uri().path().to_string();
+    if !log.is_enabled() || !is_logged_path(&path) {
+        return next.run(request).await;
+    }
+
+    let started = Instant::now();
+    let timestamp = chrono::Utc::now();
+    let method = request.method().clone();
+    let url = request
+        .uri()
+        .path_and_query()
+        .map(|pq| pq.as_str().to_string())
+        .unwrap_or(path.clone());
+    let query_headers = format_headers(request.headers());
+    let ip = client_ip(request.headers());
+
+    let (parts, body) = request.into_parts();
+    let query_body = Capture::default();
+    let tee = Tee {
+        inner: body,
+        capture: query_body.clone(),
+        pending: None,
+    };
+    let response = next.run(Request::from_parts(parts, Body::new(tee))).await;
+
+    let is_query = is_query_path(&path);
+    let query_body_bytes = query_body.bytes();
+    let mut entry = RequestLogEntry {
+        sha1: entry_id(&timestamp, &url, &query_body_bytes),
+        timestamp: timestamp.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        method: method.to_string(),
+        answer_code: response.status().as_u16().to_string(),
+        query_body: query_body.text(),
+        answer: String::new(),
+        url,
+        ip,
+        query_headers,
+        nb_api_calls: "1".to_string(),
+        processing_time_ms: started.elapsed().as_millis().to_string(),
+        index: crate::usage_middleware::extract_index_name(&path),
+        query_nb_hits: None,
+        is_query,
+    };
+    if !is_json(response.headers()) {
+        log.push(entry);
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    // Search answers are serialized in memory, so reading one whole for its
+    // `nbHits` costs no extra buffering. Anything else streams through.
+    if let Some(len) = body.size_hint().exact().filter(|_| is_query) {
+        let bytes = match axum::body::to_bytes(body, len as usize).await {
+            Ok(bytes) => bytes,
+            Err(e) => return error_response(ErrorCode::InternalError, e.to_string()),
+        };
+        entry.answer = truncate(&String::from_utf8_lossy(&bytes));
+        entry.query_nb_hits = nb_hits(&bytes).map(|n| n.to_string());
+        log.push(entry);
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    let tee = Tee {
+        inner: body,
+        capture: Capture::default(),
+        pending: Some(entry),
+    };
+    Response::from_parts(parts, Body::new(tee))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(method: &str, url: &str, code: u16, index: Option<&str>) -> RequestLogEntry {
+        RequestLogEntry {
+            timestamp: "2026-01-01T00:00:00Z".to_string(),
+            method: method.to_string(),
+            answer_code: code.to_string(),
+            query_body: String::new(),
+            answer: String::new(),
+            url: url.to_string(),
+            ip: String::new(),
+            query_headers: String::new(),
+            sha1: String::new(),
+            nb_api_calls: "1".to_string(),
+            processing_time_ms: "1".to_string(),
+            index: index.map(String::from),
+            query_nb_hits: None,
+            is_query: is_query_path(url),
+        }
+    }
+
+    fn urls(entries: &[RequestLogEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.url.as_str()).collect()
+    }
+
+    #[test]
+    fn ring_buffer_evicts_oldest() {
+        let log = RequestLog::new(2);
+        log.push(entry("GET", "/a", 200, None));
+        log.push(entry("GET", "/b", 200, None));
+        log.push(entry("GET", "/c", 200, None));
+        assert_eq!(urls(&log.query(0, 10, LogType::All, None)), ["/c", "/b"]);
+    }
+
+    #[test]
+    fn query_pages_newest_first() {
+        let log = RequestLog::new(10);
+        for url in ["/1", "/2", "/3", "/4"] {
+            log.push(entry("GET", url, 200, None));
+        }
+        assert_eq!(urls(&log.query(1, 2, LogType::All, None)), ["/3", "/2"]);
+    }
+
+    #[test]
+    fn filters_by_type_and_index() {
+        let log = RequestLog::new(10);
+        log.push(entry("POST", "/1/indexes/a/query", 200, Some("a")));
+        log.push(entry("POST", "/1/indexes/a/batch", 200, Some("a")));
+        log.push(entry("GET", "/1/indexes/b/settings", 404, Some("b")));
+
+        assert_eq!(
+            urls(&log.query(0, 10, LogType::Query, None)),
+            ["/1/indexes/a/query"]
+        );
+        assert_eq!(
+            urls(&log.query(0, 10, LogType::Build, None)),
+            ["/1/indexes/a/batch"]
+        );
+        assert_eq!(
+            urls(&log.query(0, 10, LogType::Error, None)),
+            ["/1/indexes/b/settings"]
+        );
+        assert_eq!(log.query(0, 10, LogType::All, Some("a")).len(), 2);
+    }
+
+    #[test]
+    fn zero_capacity_disables_logging() {
+        let log = RequestLog::new(0);
+        log.push(entry("GET", "/a", 200, None));
+        assert!(log.query(0, 10, LogType::All, None).is_empty());
+    }
+
+    #[test]
+    fn api_keys_are_redacted() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-algolia-api-key", "secretkey123".parse().unwrap());
+        headers.insert("x-algolia-application-id", "app".parse().unwrap());
+        let formatted = format_headers(&headers);
+        assert!(formatted.contains("x-algolia-api-key: secr****\n"));
+        assert!(formatted.contains("x-algolia-application-id: app\n"));
+        assert!(!formatted.contains("secretkey123"));
+    }
+
+    #[test]
+    fn credential_endpoints_are_not_logged() {
+        assert!(!is_logged_path("/1/keys"));
+        assert!(!is_logged_path("/1/keys/abc/rotate"));
+        assert!(!is_logged_path("/1/keys/generateSecuredApiKey"));
+        assert!(!is_logged_path("/1/users/token"));
+        assert!(is_logged_path("/1/indexes/products/query"));
+    }
+
+    #[test]
+    fn bodies_are_truncated_on_char_boundaries() {
+        let long = "é".repeat(MAX_BODY_CHARS + 5);
+        assert_eq!(truncate(&long).chars().count(), MAX_BODY_CHARS);
+        assert_eq!(truncate("short"), "short");
+    }
+
+    #[tokio::test]
+    async fn tee_passes_bodies_through_and_keeps_a_bounded_prefix() {
+        let body = "x".repeat(MAX_CAPTURE_BYTES * 3);
+        let capture = Capture::default();
+        let tee = Tee {
+            inner: Body::from(body.clone()),
+            capture: capture.clone(),
+            pending: None,
+        };
+        let streamed = axum::body::to_bytes(Body::new(tee), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(streamed, body.as_bytes());
+        assert_eq!(capture.bytes().len(), MAX_CAPTURE_BYTES);
+        assert_eq!(capture.text().chars().count(), MAX_BODY_CHARS);
+    }
+
+    #[test]
+    fn nb_hits_reads_single_and_multi_search() {
+        assert_eq!(nb_hits(br#"{"hits":[],"nbHits":7}"#), Some(7));
+        assert_eq!(
+            nb_hits(br#"{"results":[{"nbHits":2},{"nbHits":3}]}"#),
+            Some(5)
+        );
+        assert_eq!(nb_hits(b"not json"), None);
+    }
+
+    #[test]
+    fn log_type_parses_algolia_values() {
+        assert_eq!(LogType::parse("all"), Some(LogType::All));
+        assert_eq!(LogType::parse("query"), Some(LogType::Query));
+        assert_eq!(LogType::parse("build"), Some(LogType::Build));
+        assert_eq!(LogType::parse("error"), Some(LogType::Error));
+        assert_eq!(LogType::parse("other"), None);
+    }
+}
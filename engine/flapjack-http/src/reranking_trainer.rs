@@ -0,0 +1,137 @@
+//! Background re-ranking model trainer.
+//!
+//! Every `FLAPJACK_RERANKING_INTERVAL_SECS` seconds (default 86400, i.e.
+//! nightly), this task:
+//!   1. Lists the tenant directories under the IndexManager base path.
+//!   2. Skips tenants whose settings neither enable re-ranking nor carry a
+//!      `reRanking` block.
+//!   3. Aggregates click/conversion events over the configured lookback
+//!      window and writes a fresh `reranking.json` model for the tenant.
+//!
+//! Search picks the new model up immediately via the IndexManager cache.
+
+use crate::rollup_broadcaster::discover_indexes;
+use flapjack::analytics::AnalyticsQueryEngine;
+use flapjack::index::reranking::ReRankingModel;
+use flapjack::IndexManager;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Train and persist the re-ranking model for a single index.
+///
+/// Returns the number of objectIDs with engagement priors in the new model.
+pub async fn train_index(
+    manager: &IndexManager,
+    engine: &AnalyticsQueryEngine,
+    index: &str,
+    lookback_days: u32,
+) -> Result<usize, String> {
+    let now = chrono::Utc::now();
+    let start = (now - chrono::Duration::days(lookback_days as i64))
+        .format("%Y-%m-%d")
+        .to_string();
+    let end = now.format("%Y-%m-%d").to_string();
+
+    let engagement = engine.object_engagement(index, &start, &end).await?;
+    let model = ReRankingModel::train(engagement, lookback_days, now.timestamp_millis());
+    let priors = model.priors.len();
+    manager
+        .save_reranking_model(index, model)
+        .map_err(|e| e.to_string())?;
+    Ok(priors)
+}
+
+/// Run one training cycle over every index that opted into re-ranking.
+pub async fn run_training_cycle(manager: &IndexManager, engine: &AnalyticsQueryEngine) {
+    for index in discover_indexes(&manager.base_path).await {
+        let settings = match manager.get_settings(&index) {
+            Some(s) if s.enable_re_ranking || s.re_ranking.is_some() => s,
+            _ => continue,
+        };
+        let lookback_days = settings.re_ranking_config().lookback_days;
+        match train_index(manager, engine, &index, lookback_days).await {
+            Ok(priors) => tracing::info!(
+                "[RERANKING] Trained model index={} lookback_days={} priors={}",
+                index,
+                lookback_days,
+                priors
+            ),
+            Err(e) => tracing::warn!("[RERANKING] Training failed for {}: {}", index, e),
+        }
+    }
+}
+
+/// Spawn the background re-ranking trainer.
+///
+/// Unlike the rollup broadcaster, the first cycle runs right away so that
+/// indexes with re-ranking enabled get a model without waiting a full day.
+/// Only called when analytics is enabled.
+pub fn spawn_reranking_trainer(
+    manager: Arc<IndexManager>,
+    engine: Arc<AnalyticsQueryEngine>,
+    interval_secs: u64,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            run_training_cycle(&manager, &engine).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flapjack::analytics::AnalyticsConfig;
+    use flapjack::index::settings::IndexSettings;
+    use tempfile::TempDir;
+
+    fn engine_for(dir: &TempDir) -> AnalyticsQueryEngine {
+        AnalyticsQueryEngine::new(AnalyticsConfig {
+            enabled: true,
+            data_dir: dir.path().join("analytics"),
+            flush_interval_secs: 3600,
+            flush_size: 100_000,
+            retention_days: 90,
+            pii: Default::default(),
+        })
+    }
+
+    #[tokio::test]
+    async fn training_cycle_skips_indexes_without_reranking() {
+        let dir = TempDir::new().unwrap();
+        let manager = IndexManager::new(dir.path().join("data"));
+        manager.create_tenant("plain").unwrap();
+        let engine = engine_for(&dir);
+
+        run_training_cycle(&manager, &engine).await;
+
+        assert!(manager.get_reranking_model("plain").is_none());
+    }
+
+    #[tokio::test]
+    async fn training_cycle_writes_model_for_enabled_index() {
+        let dir = TempDir::new().unwrap();
+        let manager = IndexManager::new(dir.path().join("data"));
+        manager.create_tenant("products").unwrap();
+        let settings = IndexSettings {
+            enable_re_ranking: true,
+            ..Default::default()
+        };
+        settings
+            .save(dir.path().join("data/products/settings.json"))
+            .unwrap();
+        let engine = engine_for(&dir);
+
+        run_training_cycle(&manager, &engine).await;
+
+        let model = manager
+            .get_reranking_model("products")
+            .expect("model should be written");
+        assert_eq!(model.lookback_days, 30);
+        assert!(model.trained_at > 0);
+    }
+}
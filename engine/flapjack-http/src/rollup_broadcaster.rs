@@ -230,6 +230,7 @@ mod tests {
             flush_interval_secs: 3600,
             flush_size: 100_000,
             retention_days: 90,
+            pii: Default::default(),
         };
         let engine = AnalyticsQueryEngine::new(config);
 
@@ -275,10 +276,11 @@ mod tests {
             flush_interval_secs: 3600,
             flush_size: 100_000,
             retention_days: 90,
+            pii: Default::default(),
         };
 
         // Seed 1 day of analytics
-        flapjack::analytics::seed::seed_analytics(&config, "products", 1)
+        flapjack::fixtures::analytics::seed_analytics(&config, "products", 1)
             .expect("seed must succeed");
 
         let engine = AnalyticsQueryEngine::new(config.clone());
@@ -308,6 +310,7 @@ mod tests {
             flush_interval_secs: 3600,
             flush_size: 100_000,
             retention_days: 90,
+            pii: Default::default(),
         };
         let engine = Arc::new(AnalyticsQueryEngine::new(config.clone()));
 
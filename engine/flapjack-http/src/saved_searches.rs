@@ -0,0 +1,676 @@
+//! Saved analytics queries and alerts on them (`/2/savedSearches`,
+//! `/2/alerts`).
+//!
+//! A saved search names one analytics metric of one index over a rolling
+//! window of days, e.g. the no-result rate of `brand_pages` over the last
+//! day. An alert checks a saved search every `intervalSecs` against a
+//! threshold (`above`, `below`) or against the window before it
+//! (`increaseFactor`: 2 fires when the value doubles), and POSTs
+//! `alert.triggered` to its `webhookUrl` when it starts firing and
+//! `alert.resolved` when it stops.
+//!
+//! Both live in `{data_dir}/saved_searches.json`. A background task wakes
+//! every `FLAPJACK_ALERT_TICK_SECS` seconds (default 60) and checks the
+//! alerts that are due.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use flapjack::analytics::AnalyticsQueryEngine;
+
+use crate::handlers::AppState;
+
+/// Serializes read-modify-write cycles on `saved_searches.json` between the
+/// API handlers and the background checker.
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_WINDOW_DAYS: u32 = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SavedMetric {
+    SearchCount,
+    NoResultRate,
+    NoClickRate,
+    ClickThroughRate,
+    ConversionRate,
+    AverageClickPosition,
+    UsersCount,
+}
+
+impl SavedMetric {
+    /// Value of the metric for `index` between two `YYYY-MM-DD` dates.
+    pub async fn value(
+        self,
+        engine: &AnalyticsQueryEngine,
+        index: &str,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<f64, String> {
+        let (result, field) = match self {
+            SavedMetric::SearchCount => (
+                engine.search_count(index, start_date, end_date).await?,
+                "count",
+            ),
+            SavedMetric::NoResultRate => (
+                engine.no_results_rate(index, start_date, end_date).await?,
+                "rate",
+            ),
+            SavedMetric::NoClickRate => (
+                engine.no_click_rate(index, start_date, end_date).await?,
+                "rate",
+            ),
+            SavedMetric::ClickThroughRate => (
+                engine
+                    .click_through_rate(index, start_date, end_date)
+                    .await?,
+                "rate",
+            ),
+            SavedMetric::ConversionRate => (
+                engine.conversion_rate(index, start_date, end_date).await?,
+                "rate",
+            ),
+            SavedMetric::AverageClickPosition => (
+                engine
+                    .average_click_position(index, start_date, end_date)
+                    .await?,
+                "average",
+            ),
+            SavedMetric::UsersCount => (
+                engine.users_count(index, start_date, end_date).await?,
+                "count",
+            ),
+        };
+        Ok(result.get(field).and_then(|v| v.as_f64()).unwrap_or(0.0))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearch {
+    pub id: String,
+    pub name: String,
+    pub index: String,
+    pub metric: SavedMetric,
+    /// Days up to and including today.
+    pub window_days: u32,
+    pub created_at: i64,
+}
+
+/// First and last `YYYY-MM-DD` day of a window of `days` ending `periods_back`
+/// windows before the one that ends today.
+fn window_dates(today: chrono::NaiveDate, days: u32, periods_back: u32) -> (String, String) {
+    let end = today - chrono::Duration::days(i64::from(days) * i64::from(periods_back));
+    let start = end - chrono::Duration::days(i64::from(days) - 1);
+    (
+        start.format("%Y-%m-%d").to_string(),
+        end.format("%Y-%m-%d").to_string(),
+    )
+}
+
+impl SavedSearch {
+    /// The metric over the window ending today (`periods_back` 0) or an
+    /// earlier window of the same length.
+    pub async fn evaluate(
+        &self,
+        engine: &AnalyticsQueryEngine,
+        today: chrono::NaiveDate,
+        periods_back: u32,
+    ) -> Result<f64, String> {
+        let (start, end) = window_dates(today, self.window_days, periods_back);
+        self.metric.value(engine, &self.index, &start, &end).await
+    }
+}
+
+/// Body of `POST /2/savedSearches`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSavedSearchRequest {
+    #[serde(default)]
+    pub name: String,
+    pub index: String,
+    pub metric: SavedMetric,
+    #[serde(default = "default_window_days")]
+    pub window_days: u32,
+}
+
+fn default_window_days() -> u32 {
+    1
+}
+
+impl CreateSavedSearchRequest {
+    pub fn into_saved_search(self, now_ms: i64) -> Result<SavedSearch, String> {
+        if self.index.is_empty() {
+            return Err("index is required".to_string());
+        }
+        if !(1..=MAX_WINDOW_DAYS).contains(&self.window_days) {
+            return Err(format!(
+                "windowDays must be between 1 and {}",
+                MAX_WINDOW_DAYS
+            ));
+        }
+        Ok(SavedSearch {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: self.name,
+            index: self.index,
+            metric: self.metric,
+            window_days: self.window_days,
+            created_at: now_ms,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AlertCondition {
+    Above(f64),
+    Below(f64),
+    /// Current window at least this many times the previous one.
+    IncreaseFactor(f64),
+}
+
+impl AlertCondition {
+    pub fn needs_baseline(&self) -> bool {
+        matches!(self, AlertCondition::IncreaseFactor(_))
+    }
+
+    pub fn is_met(&self, value: f64, baseline: Option<f64>) -> bool {
+        match *self {
+            AlertCondition::Above(threshold) => value > threshold,
+            AlertCondition::Below(threshold) => value < threshold,
+            AlertCondition::IncreaseFactor(factor) => {
+                baseline.is_some_and(|b| b > 0.0 && value >= b * factor)
+            }
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match *self {
+            AlertCondition::Above(v) | AlertCondition::Below(v) if !v.is_finite() => {
+                Err("condition threshold must be a number".to_string())
+            }
+            AlertCondition::IncreaseFactor(f) if !(f.is_finite() && f > 1.0) => {
+                Err("condition.increaseFactor must be greater than 1".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertCheck {
+    /// Milliseconds since epoch.
+    pub checked_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub saved_search_id: String,
+    pub condition: AlertCondition,
+    pub interval_secs: u64,
+    pub webhook_url: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub next_check_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_check: Option<AlertCheck>,
+    #[serde(default)]
+    pub firing: bool,
+    /// When it last started firing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub triggered_at: Option<i64>,
+    pub created_at: i64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Alert {
+    pub fn is_due(&self, now_ms: i64) -> bool {
+        self.enabled && self.next_check_at <= now_ms
+    }
+
+    /// Record a check and return the notification it calls for, if the
+    /// alert started or stopped firing. Failed checks leave `firing` alone.
+    pub fn record_check(&mut self, check: AlertCheck) -> Option<&'static str> {
+        self.next_check_at = check.checked_at + self.interval_secs as i64 * 1000;
+        let met = check
+            .value
+            .map(|value| self.condition.is_met(value, check.baseline));
+        let checked_at = check.checked_at;
+        self.last_check = Some(check);
+        match met {
+            Some(true) if !self.firing => {
+                self.firing = true;
+                self.triggered_at = Some(checked_at);
+                Some("alert.triggered")
+            }
+            Some(false) if self.firing => {
+                self.firing = false;
+                Some("alert.resolved")
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Body of `POST /2/alerts`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAlertRequest {
+    #[serde(default)]
+    pub name: String,
+    pub saved_search_id: String,
+    pub condition: AlertCondition,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    pub webhook_url: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_interval_secs() -> u64 {
+    3600
+}
+
+impl CreateAlertRequest {
+    pub fn into_alert(self, now_ms: i64) -> Result<Alert, String> {
+        self.condition.validate()?;
+        if self.interval_secs < 60 {
+            return Err("intervalSecs must be at least 60".to_string());
+        }
+        if !(self.webhook_url.starts_with("http://") || self.webhook_url.starts_with("https://")) {
+            return Err("webhookUrl must be an http(s) URL".to_string());
+        }
+        Ok(Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: self.name,
+            saved_search_id: self.saved_search_id,
+            condition: self.condition,
+            interval_secs: self.interval_secs,
+            webhook_url: self.webhook_url,
+            enabled: self.enabled,
+            next_check_at: now_ms,
+            last_check: None,
+            firing: false,
+            triggered_at: None,
+            created_at: now_ms,
+        })
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoreFile {
+    #[serde(default)]
+    saved_searches: Vec<SavedSearch>,
+    #[serde(default)]
+    alerts: Vec<Alert>,
+}
+
+/// Saved searches and alerts persisted in `{base_dir}/saved_searches.json`.
+pub struct SavedSearchStore {
+    path: PathBuf,
+}
+
+impl SavedSearchStore {
+    pub fn new(base_dir: &std::path::Path) -> Self {
+        Self {
+            path: base_dir.join("saved_searches.json"),
+        }
+    }
+
+    fn load(&self) -> std::io::Result<StoreFile> {
+        if !self.path.exists() {
+            return Ok(StoreFile::default());
+        }
+        let json = std::fs::read_to_string(&self.path)?;
+        serde_json::from_str(&json)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    fn save(&self, file: &StoreFile) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(file).map_err(std::io::Error::other)?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(tmp, &self.path)
+    }
+
+    fn modify<T>(&self, f: impl FnOnce(&mut StoreFile) -> T) -> std::io::Result<T> {
+        let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = self.load()?;
+        let result = f(&mut file);
+        self.save(&file)?;
+        Ok(result)
+    }
+
+    pub fn list_searches(&self) -> std::io::Result<Vec<SavedSearch>> {
+        Ok(self.load()?.saved_searches)
+    }
+
+    pub fn get_search(&self, id: &str) -> std::io::Result<Option<SavedSearch>> {
+        Ok(self.list_searches()?.into_iter().find(|s| s.id == id))
+    }
+
+    pub fn insert_search(&self, search: SavedSearch) -> std::io::Result<()> {
+        self.modify(|file| file.saved_searches.push(search))
+    }
+
+    /// Delete a saved search and the alerts on it.
+    pub fn delete_search(&self, id: &str) -> std::io::Result<bool> {
+        self.modify(|file| {
+            let before = file.saved_searches.len();
+            file.saved_searches.retain(|s| s.id != id);
+            file.alerts.retain(|a| a.saved_search_id != id);
+            file.saved_searches.len() != before
+        })
+    }
+
+    pub fn list_alerts(&self) -> std::io::Result<Vec<Alert>> {
+        Ok(self.load()?.alerts)
+    }
+
+    pub fn get_alert(&self, id: &str) -> std::io::Result<Option<Alert>> {
+        Ok(self.list_alerts()?.into_iter().find(|a| a.id == id))
+    }
+
+    /// Add an alert; `false` if its saved search does not exist.
+    pub fn insert_alert(&self, alert: Alert) -> std::io::Result<bool> {
+        self.modify(|file| {
+            let known = file
+                .saved_searches
+                .iter()
+                .any(|s| s.id == alert.saved_search_id);
+            if known {
+                file.alerts.push(alert);
+            }
+            known
+        })
+    }
+
+    /// Apply `f` to the alert with `id` and persist it. Returns the updated
+    /// alert with `f`'s result, or `None` if it does not exist.
+    pub fn update_alert<T, F>(&self, id: &str, f: F) -> std::io::Result<Option<(Alert, T)>>
+    where
+        F: FnOnce(&mut Alert) -> T,
+    {
+        self.modify(|file| {
+            let alert = file.alerts.iter_mut().find(|a| a.id == id)?;
+            let result = f(alert);
+            Some((alert.clone(), result))
+        })
+    }
+
+    pub fn delete_alert(&self, id: &str) -> std::io::Result<bool> {
+        self.modify(|file| {
+            let before = file.alerts.len();
+            file.alerts.retain(|a| a.id != id);
+            file.alerts.len() != before
+        })
+    }
+}
+
+async fn notify_webhook(alert: &Alert, search: &SavedSearch, event: &str) {
+    let check = alert.last_check.as_ref();
+    let payload = serde_json::json!({
+        "event": event,
+        "alertID": alert.id,
+        "name": alert.name,
+        "savedSearch": search,
+        "condition": alert.condition,
+        "value": check.and_then(|c| c.value),
+        "baseline": check.and_then(|c| c.baseline),
+        "checkedAt": check.map(|c| c.checked_at),
+    });
+    let result = reqwest::Client::new()
+        .post(&alert.webhook_url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&payload)
+        .send()
+        .await;
+    match result {
+        Ok(resp) if resp.status().is_success() => {}
+        Ok(resp) => tracing::warn!(
+            "[ALERTS] {} webhook for {} returned {}",
+            event,
+            alert.id,
+            resp.status()
+        ),
+        Err(e) => tracing::warn!("[ALERTS] {} webhook for {} failed: {}", event, alert.id, e),
+    }
+}
+
+/// Check one alert now, persist the outcome and send any notification.
+/// Returns the updated alert, or `None` if it no longer exists.
+pub async fn check_alert(
+    engine: &AnalyticsQueryEngine,
+    store: &SavedSearchStore,
+    alert: &Alert,
+) -> std::io::Result<Option<Alert>> {
+    let Some(search) = store.get_search(&alert.saved_search_id)? else {
+        return Ok(None);
+    };
+    let today = chrono::Utc::now().date_naive();
+    let value = search.evaluate(engine, today, 0).await;
+    let baseline = match (&value, alert.condition.needs_baseline()) {
+        (Ok(_), true) => Some(search.evaluate(engine, today, 1).await),
+        _ => None,
+    };
+    let error = match (&value, &baseline) {
+        (Err(e), _) | (_, Some(Err(e))) => Some(e.clone()),
+        _ => None,
+    };
+    let check = AlertCheck {
+        checked_at: chrono::Utc::now().timestamp_millis(),
+        value: value.ok().filter(|_| error.is_none()),
+        baseline: baseline.and_then(Result::ok),
+        error,
+    };
+    if let Some(e) = &check.error {
+        tracing::warn!("[ALERTS] check of {} failed: {}", alert.id, e);
+    }
+    let Some((updated, event)) = store.update_alert(&alert.id, |a| a.record_check(check))? else {
+        return Ok(None);
+    };
+    if let Some(event) = event {
+        tracing::info!("[ALERTS] {}: {}", updated.id, event);
+        notify_webhook(&updated, &search, event).await;
+    }
+    Ok(Some(updated))
+}
+
+/// Check every due alert once, sequentially.
+pub async fn run_due_alerts(state: &AppState) {
+    let Some(engine) = state.analytics_engine.as_deref() else {
+        return;
+    };
+    let store = SavedSearchStore::new(&state.manager.base_path);
+    let alerts = match store.list_alerts() {
+        Ok(alerts) => alerts,
+        Err(e) => {
+            tracing::warn!("[ALERTS] Failed to load alerts: {}", e);
+            return;
+        }
+    };
+    let now = chrono::Utc::now().timestamp_millis();
+    for alert in alerts.iter().filter(|a| a.is_due(now)) {
+        if let Err(e) = check_alert(engine, &store, alert).await {
+            tracing::warn!("[ALERTS] Failed to record check for {}: {}", alert.id, e);
+        }
+    }
+}
+
+pub fn spawn_alert_checker(state: Arc<AppState>, tick_secs: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(tick_secs.max(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            run_due_alerts(&state).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn alert_request(json: serde_json::Value) -> CreateAlertRequest {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn check(checked_at: i64, value: f64, baseline: Option<f64>) -> AlertCheck {
+        AlertCheck {
+            checked_at,
+            value: Some(value),
+            baseline,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn windows_are_consecutive() {
+        let today = chrono::NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        assert_eq!(
+            window_dates(today, 1, 0),
+            ("2024-03-10".to_string(), "2024-03-10".to_string())
+        );
+        assert_eq!(
+            window_dates(today, 7, 0),
+            ("2024-03-04".to_string(), "2024-03-10".to_string())
+        );
+        assert_eq!(
+            window_dates(today, 7, 1),
+            ("2024-02-26".to_string(), "2024-03-03".to_string())
+        );
+    }
+
+    #[test]
+    fn create_requests_validate() {
+        let search: CreateSavedSearchRequest = serde_json::from_value(serde_json::json!({
+            "name": "zero results on brand pages",
+            "index": "brand_pages",
+            "metric": "noResultRate"
+        }))
+        .unwrap();
+        let search = search.into_saved_search(0).unwrap();
+        assert_eq!(search.window_days, 1);
+        assert_eq!(search.metric, SavedMetric::NoResultRate);
+
+        let alert = alert_request(serde_json::json!({
+            "savedSearchId": search.id,
+            "condition": {"increaseFactor": 2.0},
+            "webhookUrl": "https://hooks.example.com/ops"
+        }))
+        .into_alert(5_000)
+        .unwrap();
+        assert_eq!(alert.interval_secs, 3600);
+        assert!(alert.is_due(5_000));
+
+        for bad in [
+            serde_json::json!({"savedSearchId": "s", "condition": {"increaseFactor": 0.5},
+                "webhookUrl": "https://hooks.example.com"}),
+            serde_json::json!({"savedSearchId": "s", "condition": {"above": 0.1},
+                "webhookUrl": "ftp://hooks.example.com"}),
+            serde_json::json!({"savedSearchId": "s", "condition": {"below": 10},
+                "intervalSecs": 5, "webhookUrl": "https://hooks.example.com"}),
+        ] {
+            assert!(alert_request(bad).into_alert(0).is_err());
+        }
+    }
+
+    #[test]
+    fn alert_notifies_on_transitions_only() {
+        let mut alert = alert_request(serde_json::json!({
+            "savedSearchId": "s",
+            "condition": {"increaseFactor": 2.0},
+            "intervalSecs": 60,
+            "webhookUrl": "https://hooks.example.com"
+        }))
+        .into_alert(0)
+        .unwrap();
+
+        assert_eq!(alert.record_check(check(1_000, 0.1, Some(0.08))), None);
+        assert_eq!(alert.next_check_at, 61_000);
+        assert_eq!(
+            alert.record_check(check(2_000, 0.2, Some(0.08))),
+            Some("alert.triggered")
+        );
+        assert_eq!(alert.triggered_at, Some(2_000));
+        assert_eq!(alert.record_check(check(3_000, 0.3, Some(0.08))), None);
+        let failed = AlertCheck {
+            checked_at: 4_000,
+            value: None,
+            baseline: None,
+            error: Some("no data".to_string()),
+        };
+        assert_eq!(alert.record_check(failed), None);
+        assert!(alert.firing);
+        assert_eq!(
+            alert.record_check(check(5_000, 0.1, Some(0.08))),
+            Some("alert.resolved")
+        );
+        // No baseline traffic: nothing to compare against.
+        assert_eq!(alert.record_check(check(6_000, 0.5, Some(0.0))), None);
+    }
+
+    #[test]
+    fn store_cascades_deletes_to_alerts() {
+        let tmp = TempDir::new().unwrap();
+        let store = SavedSearchStore::new(tmp.path());
+        let search = CreateSavedSearchRequest {
+            name: String::new(),
+            index: "products".to_string(),
+            metric: SavedMetric::SearchCount,
+            window_days: 7,
+        }
+        .into_saved_search(0)
+        .unwrap();
+        store.insert_search(search.clone()).unwrap();
+        let alert = alert_request(serde_json::json!({
+            "savedSearchId": search.id,
+            "condition": {"below": 100},
+            "webhookUrl": "https://hooks.example.com"
+        }))
+        .into_alert(0)
+        .unwrap();
+        assert!(store.insert_alert(alert.clone()).unwrap());
+        let orphan = Alert {
+            id: "orphan".to_string(),
+            saved_search_id: "missing".to_string(),
+            ..alert.clone()
+        };
+        assert!(!store.insert_alert(orphan).unwrap());
+
+        let (updated, event) = store
+            .update_alert(&alert.id, |a| a.record_check(check(10, 50.0, None)))
+            .unwrap()
+            .unwrap();
+        assert_eq!(event, Some("alert.triggered"));
+        assert_eq!(store.get_alert(&alert.id).unwrap(), Some(updated));
+
+        assert!(store.delete_search(&search.id).unwrap());
+        assert!(store.list_alerts().unwrap().is_empty());
+        assert!(!store.delete_search(&search.id).unwrap());
+    }
+}
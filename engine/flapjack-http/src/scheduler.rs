@@ -0,0 +1,708 @@
+//! Delayed and recurring operations (`/1/schedules`).
+//!
+//! Schedules live in `{data_dir}/schedules.json`. A background task wakes
+//! every `FLAPJACK_SCHEDULER_TICK_SECS` seconds (default 15), runs every
+//! enabled schedule whose `nextRunAt` has passed, records the outcome in
+//! `lastRun`, and either advances `nextRunAt` by `intervalSecs` (recurring)
+//! or clears it (one-shot).
+//!
+//! Actions reuse the HTTP handlers, so a scheduled operation behaves exactly
+//! like the equivalent API call. That includes being refused while the disk
+//! is nearly full or an index's maintenance mode blocks it.
+//!
+//! In a replicated cluster every node keeps a copy of `schedules.json`:
+//! changes made through the API, and the outcome of each run, are pushed to
+//! the other nodes. Only the primary node (see
+//! `ReplicationManager::is_primary`) runs schedules; the writes they make
+//! replicate like any API write, so each fires once rather than once per
+//! node, and another node picks the schedules up when the primary goes down.
+
+use axum::extract::{Path, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use flapjack::error::FlapjackError;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::disk_watchdog::DiskWatchdog;
+use crate::handlers::AppState;
+use crate::maintenance::MaintenanceRegistry;
+
+/// Serializes read-modify-write cycles on `schedules.json` between the API
+/// handlers and the background runner.
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Operation a schedule performs when it fires.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ScheduledAction {
+    /// Remove all records, keeping settings.
+    #[serde(rename_all = "camelCase")]
+    ClearIndex { index_name: String },
+    /// Replace `destination` with a copy of `index_name` (e.g. nightly
+    /// refresh of a staging index from production).
+    #[serde(rename_all = "camelCase")]
+    CopyIndex {
+        index_name: String,
+        destination: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        scope: Option<Vec<String>>,
+    },
+    /// Merge segments.
+    #[serde(rename_all = "camelCase")]
+    CompactIndex { index_name: String },
+    /// Rebuild a Query Suggestions index from its `/1/configs` entry.
+    #[serde(rename_all = "camelCase")]
+    BuildQuerySuggestions { index_name: String },
+    /// Upload a snapshot to S3 (requires `FLAPJACK_S3_BUCKET`).
+    #[serde(rename_all = "camelCase")]
+    Snapshot { index_name: String },
+    /// Start the index's website crawler (`/1/indexes/:indexName/crawler`).
+    #[serde(rename_all = "camelCase")]
+    Crawl { index_name: String },
+}
+
+impl ScheduledAction {
+    pub fn index_name(&self) -> &str {
+        match self {
+            ScheduledAction::ClearIndex { index_name }
+            | ScheduledAction::CopyIndex { index_name, .. }
+            | ScheduledAction::CompactIndex { index_name }
+            | ScheduledAction::BuildQuerySuggestions { index_name }
+            | ScheduledAction::Snapshot { index_name }
+            | ScheduledAction::Crawl { index_name } => index_name,
+        }
+    }
+
+    /// Indexes the action touches, and whether it writes to each.
+    fn accesses(&self) -> Vec<(&str, bool)> {
+        match self {
+            ScheduledAction::CopyIndex {
+                index_name,
+                destination,
+                ..
+            } => vec![(index_name, false), (destination, true)],
+            ScheduledAction::Snapshot { index_name } => vec![(index_name, false)],
+            ScheduledAction::ClearIndex { index_name }
+            | ScheduledAction::CompactIndex { index_name }
+            | ScheduledAction::BuildQuerySuggestions { index_name }
+            | ScheduledAction::Crawl { index_name } => vec![(index_name, true)],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleRun {
+    /// Milliseconds since epoch.
+    pub started_at: i64,
+    pub finished_at: i64,
+    pub succeeded: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schedule {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub action: ScheduledAction,
+    /// Repeat every N seconds; `None` runs once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval_secs: Option<u64>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Next due time (ms since epoch); `None` once a one-shot has run.
+    #[serde(default)]
+    pub next_run_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<ScheduleRun>,
+    #[serde(default)]
+    pub run_count: u64,
+    pub created_at: i64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// `secs` in milliseconds, or `None` if that overflows an `i64`.
+fn secs_to_ms(secs: u64) -> Option<i64> {
+    i64::try_from(secs).ok()?.checked_mul(1000)
+}
+
+impl Schedule {
+    pub fn is_due(&self, now_ms: i64) -> bool {
+        self.enabled && self.next_run_at.is_some_and(|at| at <= now_ms)
+    }
+
+    /// Record a finished run and compute the next due time. Recurring
+    /// schedules skip intervals missed while the server was down instead of
+    /// firing once per missed interval; a manual run ahead of schedule keeps
+    /// the pending slot.
+    pub fn record_run(&mut self, run: ScheduleRun) {
+        self.run_count += 1;
+        self.next_run_at = match (self.interval_secs, self.next_run_at) {
+            (_, Some(due)) if due > run.finished_at => Some(due),
+            (Some(secs), Some(due)) if secs > 0 => secs_to_ms(secs).and_then(|step| {
+                let missed = (run.finished_at - due).max(0) / step;
+                due.checked_add((missed + 1).checked_mul(step)?)
+            }),
+            _ => None,
+        };
+        self.last_run = Some(run);
+    }
+}
+
+/// Body of `POST /1/schedules`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateScheduleRequest {
+    #[serde(default)]
+    pub name: String,
+    pub action: ScheduledAction,
+    /// First run (ms since epoch). Defaults to now + `delaySecs`.
+    #[serde(default)]
+    pub start_at: Option<i64>,
+    /// Delay before the first run when `startAt` is absent.
+    #[serde(default)]
+    pub delay_secs: Option<u64>,
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+impl CreateScheduleRequest {
+    pub fn into_schedule(self, now_ms: i64) -> Result<Schedule, String> {
+        if self.action.index_name().is_empty() {
+            return Err("action.indexName is required".to_string());
+        }
+        if self.interval_secs == Some(0) {
+            return Err("intervalSecs must be greater than 0".to_string());
+        }
+        if self
+            .interval_secs
+            .is_some_and(|secs| secs_to_ms(secs).is_none())
+        {
+            return Err("intervalSecs is out of range".to_string());
+        }
+        let first_run = match self.start_at {
+            Some(at) => at,
+            None => secs_to_ms(self.delay_secs.unwrap_or(0))
+                .and_then(|delay| now_ms.checked_add(delay))
+                .ok_or_else(|| "delaySecs is out of range".to_string())?,
+        };
+        Ok(Schedule {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: self.name,
+            action: self.action,
+            interval_secs: self.interval_secs,
+            enabled: self.enabled,
+            next_run_at: Some(first_run),
+            last_run: None,
+            run_count: 0,
+            created_at: now_ms,
+        })
+    }
+}
+
+/// Schedules persisted as one JSON array in `{base_dir}/schedules.json`.
+pub struct ScheduleStore {
+    path: PathBuf,
+}
+
+impl ScheduleStore {
+    pub fn new(base_dir: &std::path::Path) -> Self {
+        Self {
+            path: base_dir.join("schedules.json"),
+        }
+    }
+
+    pub fn list(&self) -> std::io::Result<Vec<Schedule>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let json = std::fs::read_to_string(&self.path)?;
+        serde_json::from_str(&json)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn get(&self, id: &str) -> std::io::Result<Option<Schedule>> {
+        Ok(self.list()?.into_iter().find(|s| s.id == id))
+    }
+
+    fn save_all(&self, schedules: &[Schedule]) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(schedules).map_err(std::io::Error::other)?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(tmp, &self.path)
+    }
+
+    pub fn insert(&self, schedule: Schedule) -> std::io::Result<()> {
+        let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut all = self.list()?;
+        all.push(schedule);
+        self.save_all(&all)
+    }
+
+    /// Apply `f` to the schedule with `id` and persist it. Returns the
+    /// updated schedule, or `None` if it does not exist.
+    pub fn update<F>(&self, id: &str, f: F) -> std::io::Result<Option<Schedule>>
+    where
+        F: FnOnce(&mut Schedule),
+    {
+        let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut all = self.list()?;
+        let Some(schedule) = all.iter_mut().find(|s| s.id == id) else {
+            return Ok(None);
+        };
+        f(schedule);
+        let updated = schedule.clone();
+        self.save_all(&all)?;
+        Ok(Some(updated))
+    }
+
+    /// Store a schedule received from a peer, replacing any with its ID.
+    pub fn upsert(&self, schedule: Schedule) -> std::io::Result<()> {
+        let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut all = self.list()?;
+        match all.iter_mut().find(|s| s.id == schedule.id) {
+            Some(existing) => *existing = schedule,
+            None => all.push(schedule),
+        }
+        self.save_all(&all)
+    }
+
+    pub fn delete(&self, id: &str) -> std::io::Result<bool> {
+        let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut all = self.list()?;
+        let before = all.len();
+        all.retain(|s| s.id != id);
+        if all.len() == before {
+            return Ok(false);
+        }
+        self.save_all(&all)?;
+        Ok(true)
+    }
+}
+
+/// Push a created or updated schedule to replication peers.
+pub fn publish_schedule(state: &AppState, schedule: &Schedule) {
+    if let Some(repl) = &state.replication_manager {
+        match serde_json::to_value(schedule) {
+            Ok(value) => repl.broadcast_schedule(value),
+            Err(e) => tracing::warn!("[SCHEDULER] Failed to encode {}: {}", schedule.id, e),
+        }
+    }
+}
+
+/// Push a schedule deletion to replication peers.
+pub fn publish_schedule_deletion(state: &AppState, id: &str) {
+    if let Some(repl) = &state.replication_manager {
+        repl.broadcast_schedule_deletion(id);
+    }
+}
+
+/// Refuse what the API would: actions call the handlers directly, past the
+/// middleware that rejects writes while the disk is nearly full and requests
+/// an index's maintenance mode blocks.
+fn check_allowed(state: &AppState, action: &ScheduledAction) -> Result<(), FlapjackError> {
+    let accesses = action.accesses();
+    if accesses.iter().any(|&(_, write)| write) {
+        DiskWatchdog::global().check_writable()?;
+    }
+    let registry = MaintenanceRegistry::for_data_dir(&state.manager.base_path)?;
+    for (index_name, write) in accesses {
+        registry.check(index_name, write)?;
+    }
+    Ok(())
+}
+
+/// Run `action` once through the matching HTTP handler.
+pub async fn run_action(state: &Arc<AppState>, action: &ScheduledAction) -> Result<(), String> {
+    check_allowed(state, action).map_err(|e| e.to_string())?;
+    let response = match action.clone() {
+        ScheduledAction::ClearIndex { index_name } => {
+            crate::handlers::clear_index(State(Arc::clone(state)), Path(index_name))
+                .await
+                .into_response()
+        }
+        ScheduledAction::CopyIndex {
+            index_name,
+            destination,
+            scope,
+        } => crate::handlers::operation_index(
+            State(Arc::clone(state)),
+            Path(index_name),
+            Json(crate::handlers::indices::OperationIndexRequest {
+                operation: "copy".to_string(),
+                destination,
+                scope,
+            }),
+        )
+        .await
+        .into_response(),
+        ScheduledAction::CompactIndex { index_name } => {
+            crate::handlers::compact_index(State(Arc::clone(state)), Path(index_name))
+                .await
+                .into_response()
+        }
+        ScheduledAction::BuildQuerySuggestions { index_name } => {
+            crate::handlers::query_suggestions::trigger_build(
+                State(Arc::clone(state)),
+                Path(index_name),
+            )
+            .await
+            .into_response()
+        }
+        ScheduledAction::Snapshot { index_name } => {
+            crate::handlers::snapshot::snapshot_to_s3(State(Arc::clone(state)), Path(index_name))
+                .await
+                .into_response()
+        }
+        ScheduledAction::Crawl { index_name } => {
+            crate::handlers::crawler::run_crawler(State(Arc::clone(state)), Path(index_name))
+                .await
+                .into_response()
+        }
+    };
+
+    if response.status().is_success() {
+        return Ok(());
+    }
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), 64 * 1024)
+        .await
+        .unwrap_or_default();
+    Err(format!("{}: {}", status, String::from_utf8_lossy(&body)))
+}
+
+/// Run a schedule now and persist the outcome.
+pub async fn execute_schedule(
+    state: &Arc<AppState>,
+    store: &ScheduleStore,
+    schedule: &Schedule,
+) -> std::io::Result<Option<Schedule>> {
+    let started_at = chrono::Utc::now().timestamp_millis();
+    let result = run_action(state, &schedule.action).await;
+    let finished_at = chrono::Utc::now().timestamp_millis();
+    match &result {
+        Ok(()) => tracing::info!(
+            "[SCHEDULER] Ran schedule id={} action={:?}",
+            schedule.id,
+            schedule.action
+        ),
+        Err(e) => tracing::warn!("[SCHEDULER] Schedule id={} failed: {}", schedule.id, e),
+    }
+    let run = ScheduleRun {
+        started_at,
+        finished_at,
+        succeeded: result.is_ok(),
+        message: result.err(),
+    };
+    let updated = store.update(&schedule.id, |s| s.record_run(run))?;
+    if let Some(updated) = &updated {
+        publish_schedule(state, updated);
+    }
+    Ok(updated)
+}
+
+/// Run every due schedule once, sequentially.
+pub async fn run_due_schedules(state: &Arc<AppState>) {
+    let store = ScheduleStore::new(&state.manager.base_path);
+    let schedules = match store.list() {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("[SCHEDULER] Failed to load schedules: {}", e);
+            return;
+        }
+    };
+    let now = chrono::Utc::now().timestamp_millis();
+    for schedule in schedules.iter().filter(|s| s.is_due(now)) {
+        if let Err(e) = execute_schedule(state, &store, schedule).await {
+            tracing::warn!(
+                "[SCHEDULER] Failed to record run for {}: {}",
+                schedule.id,
+                e
+            );
+        }
+    }
+}
+
+/// Whether this node runs schedules: a standalone node always does, a
+/// replicated one only while it is the primary.
+fn runs_schedules(state: &AppState) -> bool {
+    state
+        .replication_manager
+        .as_ref()
+        .is_none_or(|repl| repl.is_primary())
+}
+
+/// Spawn the background scheduler loop.
+pub fn spawn_scheduler(state: Arc<AppState>, tick_secs: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(tick_secs.max(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            if runs_schedules(&state) {
+                run_due_schedules(&state).await;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{app_state, bare_app_state};
+    use tempfile::TempDir;
+
+    fn request(json: serde_json::Value) -> CreateScheduleRequest {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn action_json_shape() {
+        let action: ScheduledAction = serde_json::from_value(serde_json::json!({
+            "type": "copyIndex",
+            "indexName": "products",
+            "destination": "products_staging"
+        }))
+        .unwrap();
+        assert_eq!(
+            action,
+            ScheduledAction::CopyIndex {
+                index_name: "products".into(),
+                destination: "products_staging".into(),
+                scope: None,
+            }
+        );
+        assert_eq!(action.index_name(), "products");
+    }
+
+    #[test]
+    fn create_request_defaults_and_validation() {
+        let s = request(serde_json::json!({
+            "action": {"type": "compactIndex", "indexName": "products"},
+            "delaySecs": 60
+        }))
+        .into_schedule(1_000)
+        .unwrap();
+        assert_eq!(s.next_run_at, Some(61_000));
+        assert!(s.enabled);
+        assert!(s.interval_secs.is_none());
+
+        assert!(request(serde_json::json!({
+            "action": {"type": "compactIndex", "indexName": "products"},
+            "intervalSecs": 0
+        }))
+        .into_schedule(0)
+        .is_err());
+    }
+
+    fn run(finished_at: i64) -> ScheduleRun {
+        ScheduleRun {
+            started_at: finished_at,
+            finished_at,
+            succeeded: true,
+            message: None,
+        }
+    }
+
+    #[test]
+    fn one_shot_is_not_rescheduled() {
+        let mut s = request(serde_json::json!({
+            "action": {"type": "compactIndex", "indexName": "products"}
+        }))
+        .into_schedule(0)
+        .unwrap();
+        assert!(s.is_due(0));
+        s.record_run(run(10));
+        assert_eq!(s.next_run_at, None);
+        assert!(!s.is_due(i64::MAX));
+        assert_eq!(s.run_count, 1);
+    }
+
+    #[test]
+    fn recurring_skips_missed_intervals() {
+        let mut s = request(serde_json::json!({
+            "action": {"type": "compactIndex", "indexName": "products"},
+            "startAt": 0,
+            "intervalSecs": 10
+        }))
+        .into_schedule(0)
+        .unwrap();
+        s.record_run(run(500));
+        assert_eq!(s.next_run_at, Some(10_000));
+        // Server was down for 35s: next run is the next future slot.
+        s.record_run(run(45_000));
+        assert_eq!(s.next_run_at, Some(50_000));
+        // Manual run before the slot leaves it alone.
+        s.record_run(run(46_000));
+        assert_eq!(s.next_run_at, Some(50_000));
+        assert_eq!(s.run_count, 3);
+    }
+
+    #[test]
+    fn out_of_range_durations_are_rejected() {
+        for body in [
+            serde_json::json!({
+                "action": {"type": "compactIndex", "indexName": "products"},
+                "delaySecs": u64::MAX
+            }),
+            serde_json::json!({
+                "action": {"type": "compactIndex", "indexName": "products"},
+                "intervalSecs": u64::MAX / 1000
+            }),
+        ] {
+            assert!(request(body).into_schedule(0).is_err());
+        }
+    }
+
+    #[test]
+    fn disabled_schedule_is_never_due() {
+        let mut s = request(serde_json::json!({
+            "action": {"type": "compactIndex", "indexName": "products"}
+        }))
+        .into_schedule(0)
+        .unwrap();
+        s.enabled = false;
+        assert!(!s.is_due(i64::MAX));
+    }
+
+    #[test]
+    fn only_the_primary_runs_schedules() {
+        use flapjack_replication::config::{NodeConfig, PeerConfig};
+        use flapjack_replication::manager::ReplicationManager;
+
+        let tmp = TempDir::new().unwrap();
+        assert!(runs_schedules(&app_state(&tmp)));
+
+        let clustered = |node_id: &str, peer_id: &str| AppState {
+            replication_manager: Some(ReplicationManager::new(NodeConfig {
+                node_id: node_id.to_string(),
+                bind_addr: "0.0.0.0:7700".to_string(),
+                peers: vec![PeerConfig {
+                    node_id: peer_id.to_string(),
+                    addr: format!("http://{}:7700", peer_id),
+                }],
+            })),
+            ..bare_app_state(&tmp)
+        };
+        assert!(runs_schedules(&clustered("node-a", "node-b")));
+        assert!(!runs_schedules(&clustered("node-b", "node-a")));
+    }
+
+    #[tokio::test]
+    async fn actions_respect_maintenance_modes() {
+        use crate::maintenance::MaintenanceMode;
+
+        let tmp = TempDir::new().unwrap();
+        let state = app_state(&tmp);
+        state.manager.create_tenant("prod").unwrap();
+        state.manager.create_tenant("staging").unwrap();
+        MaintenanceRegistry::for_data_dir(tmp.path())
+            .unwrap()
+            .set("staging", MaintenanceMode::ReadOnly, None, 0)
+            .unwrap();
+
+        let clear = ScheduledAction::ClearIndex {
+            index_name: "staging".to_string(),
+        };
+        assert!(run_action(&state, &clear).await.is_err());
+        let copy_from_staging = ScheduledAction::CopyIndex {
+            index_name: "staging".to_string(),
+            destination: "prod".to_string(),
+            scope: None,
+        };
+        assert!(check_allowed(&state, &copy_from_staging).is_ok());
+        let copy_to_staging = ScheduledAction::CopyIndex {
+            index_name: "prod".to_string(),
+            destination: "staging".to_string(),
+            scope: None,
+        };
+        assert!(check_allowed(&state, &copy_to_staging).is_err());
+    }
+
+    #[test]
+    fn upsert_replaces_by_id() {
+        let tmp = TempDir::new().unwrap();
+        let store = ScheduleStore::new(tmp.path());
+        let mut schedule = request(serde_json::json!({
+            "action": {"type": "compactIndex", "indexName": "products"}
+        }))
+        .into_schedule(0)
+        .unwrap();
+        store.upsert(schedule.clone()).unwrap();
+        schedule.enabled = false;
+        store.upsert(schedule.clone()).unwrap();
+        assert_eq!(store.list().unwrap(), vec![schedule]);
+    }
+
+    #[tokio::test]
+    async fn run_due_schedules_records_outcomes() {
+        let tmp = TempDir::new().unwrap();
+        let state = app_state(&tmp);
+        state.manager.create_tenant("staging").unwrap();
+        let store = ScheduleStore::new(tmp.path());
+
+        let clear = request(serde_json::json!({
+            "action": {"type": "clearIndex", "indexName": "staging"}
+        }))
+        .into_schedule(0)
+        .unwrap();
+        let later = request(serde_json::json!({
+            "action": {"type": "clearIndex", "indexName": "staging"},
+            "startAt": i64::MAX
+        }))
+        .into_schedule(0)
+        .unwrap();
+        let (clear_id, later_id) = (clear.id.clone(), later.id.clone());
+        store.insert(clear).unwrap();
+        store.insert(later).unwrap();
+
+        run_due_schedules(&state).await;
+
+        let clear = store.get(&clear_id).unwrap().unwrap();
+        assert_eq!(clear.run_count, 1);
+        assert!(clear.last_run.as_ref().unwrap().succeeded);
+        assert_eq!(clear.next_run_at, None);
+        assert_eq!(store.get(&later_id).unwrap().unwrap().run_count, 0);
+    }
+
+    #[test]
+    fn store_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let store = ScheduleStore::new(dir.path());
+        assert!(store.list().unwrap().is_empty());
+
+        let s = request(serde_json::json!({
+            "name": "nightly",
+            "action": {"type": "clearIndex", "indexName": "staging"},
+            "intervalSecs": 86400
+        }))
+        .into_schedule(0)
+        .unwrap();
+        let id = s.id.clone();
+        store.insert(s).unwrap();
+
+        let paused = store.update(&id, |s| s.enabled = false).unwrap().unwrap();
+        assert!(!paused.enabled);
+        assert_eq!(store.get(&id).unwrap().unwrap().name, "nightly");
+        assert!(store.update("missing", |_| {}).unwrap().is_none());
+
+        assert!(store.delete(&id).unwrap());
+        assert!(!store.delete(&id).unwrap());
+        assert!(store.list().unwrap().is_empty());
+    }
+}
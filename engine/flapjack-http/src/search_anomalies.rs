@@ -0,0 +1,511 @@
+//! Anomaly detection on search traffic (`/1/analytics/anomalies`).
+//!
+//! Every `FLAPJACK_ANOMALY_TICK_SECS` seconds (default 900) each index with
+//! analytics is checked for the last complete hour against a seasonal
+//! baseline: the same hour of the week over the previous four weeks. An hour
+//! is flagged when a metric sits more than three standard deviations from
+//! that baseline and also moves by a meaningful amount:
+//!
+//! - `trafficSpike`: at least twice the usual number of searches
+//! - `zeroResultSurge`: the no-result rate up by 10 points or more
+//! - `latencyRegression`: average processing time at least doubled
+//! - `ctrCliff`: click-through rate on tracked searches halved or worse
+//!
+//! Hours with fewer than 50 searches (tracked searches for `ctrCliff`) are
+//! not judged, and a metric needs at least two weeks of history. Findings
+//! are kept in `{data_dir}/anomalies.json` (the most recent 1000) and each
+//! new one is POSTed as `analytics.anomaly` to `FLAPJACK_ANOMALY_WEBHOOK_URL`
+//! when set.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use flapjack::analytics::AnalyticsQueryEngine;
+
+use crate::handlers::AppState;
+
+/// Serializes read-modify-write cycles on `anomalies.json`.
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const HOUR_MS: i64 = 3_600_000;
+const WEEK_MS: i64 = 7 * 24 * HOUR_MS;
+const BASELINE_WEEKS: i64 = 4;
+const MIN_BASELINE_SAMPLES: usize = 2;
+const MIN_VOLUME: f64 = 50.0;
+const Z_THRESHOLD: f64 = 3.0;
+const MAX_STORED: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AnomalyKind {
+    TrafficSpike,
+    ZeroResultSurge,
+    LatencyRegression,
+    CtrCliff,
+}
+
+/// Search metrics of one index over one hour.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HourStats {
+    pub searches: f64,
+    pub no_results: f64,
+    pub avg_latency_ms: f64,
+    /// Searches with a `queryID`, i.e. that clicks can be attributed to.
+    pub tracked: f64,
+    /// Distinct tracked searches with at least one click.
+    pub clicked: f64,
+}
+
+impl HourStats {
+    fn no_result_rate(&self) -> Option<f64> {
+        (self.searches > 0.0).then(|| self.no_results / self.searches)
+    }
+
+    fn latency(&self) -> Option<f64> {
+        (self.searches > 0.0).then_some(self.avg_latency_ms)
+    }
+
+    fn ctr(&self) -> Option<f64> {
+        (self.tracked > 0.0).then(|| (self.clicked / self.tracked).min(1.0))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Anomaly {
+    pub index: String,
+    pub kind: AnomalyKind,
+    /// Start of the flagged hour (Unix ms).
+    pub hour_start: i64,
+    pub value: f64,
+    /// Mean of the same hour in previous weeks.
+    pub baseline: f64,
+    pub detected_at: i64,
+}
+
+/// Mean and population standard deviation of `samples`.
+fn mean_std(samples: &[f64]) -> (f64, f64) {
+    let n = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance.sqrt())
+}
+
+/// The baseline mean if `value` is more than `Z_THRESHOLD` deviations above
+/// (or, with `upward` false, below) `samples`. The deviation is floored at
+/// `min_spread` so a perfectly steady history does not flag noise.
+fn deviation(value: f64, samples: &[f64], min_spread: f64, upward: bool) -> Option<f64> {
+    if samples.len() < MIN_BASELINE_SAMPLES {
+        return None;
+    }
+    let (mean, std) = mean_std(samples);
+    let spread = std.max(min_spread);
+    let z = (value - mean) / spread;
+    let flagged = if upward {
+        z > Z_THRESHOLD
+    } else {
+        z < -Z_THRESHOLD
+    };
+    flagged.then_some(mean)
+}
+
+/// Anomalies of `index` in the hour starting at `hour_start`, given its
+/// hourly stats keyed by hour start. Hours without searches are missing from
+/// `hours` and do not count towards baselines.
+pub fn detect(
+    index: &str,
+    hours: &HashMap<i64, HourStats>,
+    hour_start: i64,
+    now_ms: i64,
+) -> Vec<Anomaly> {
+    let Some(current) = hours.get(&hour_start) else {
+        return Vec::new();
+    };
+    let history: Vec<&HourStats> = (1..=BASELINE_WEEKS)
+        .filter_map(|week| hours.get(&(hour_start - week * WEEK_MS)))
+        .collect();
+    let samples = |metric: fn(&HourStats) -> Option<f64>| -> Vec<f64> {
+        history.iter().filter_map(|h| metric(h)).collect()
+    };
+    let mut found = Vec::new();
+    let mut flag = |kind, value, baseline| {
+        found.push(Anomaly {
+            index: index.to_string(),
+            kind,
+            hour_start,
+            value,
+            baseline,
+            detected_at: now_ms,
+        })
+    };
+
+    if current.searches >= MIN_VOLUME {
+        let traffic = samples(|h| Some(h.searches));
+        let floor = traffic.iter().sum::<f64>() / traffic.len().max(1) as f64 * 0.1;
+        if let Some(mean) = deviation(current.searches, &traffic, floor.max(1.0), true) {
+            if current.searches >= 2.0 * mean {
+                flag(AnomalyKind::TrafficSpike, current.searches, mean);
+            }
+        }
+
+        let rate = current.no_result_rate().unwrap_or(0.0);
+        if let Some(mean) = deviation(rate, &samples(HourStats::no_result_rate), 0.02, true) {
+            if rate - mean >= 0.1 {
+                flag(AnomalyKind::ZeroResultSurge, rate, mean);
+            }
+        }
+
+        let latency = current.avg_latency_ms;
+        let latencies = samples(HourStats::latency);
+        let floor = latencies.iter().sum::<f64>() / latencies.len().max(1) as f64 * 0.1;
+        if let Some(mean) = deviation(latency, &latencies, floor.max(1.0), true) {
+            if latency >= 2.0 * mean {
+                flag(AnomalyKind::LatencyRegression, latency, mean);
+            }
+        }
+    }
+
+    if current.tracked >= MIN_VOLUME {
+        let ctr = current.ctr().unwrap_or(0.0);
+        if let Some(mean) = deviation(ctr, &samples(HourStats::ctr), 0.02, false) {
+            if ctr <= mean / 2.0 {
+                flag(AnomalyKind::CtrCliff, ctr, mean);
+            }
+        }
+    }
+    found
+}
+
+fn number(row: &serde_json::Value, field: &str) -> f64 {
+    row.get(field).and_then(|v| v.as_f64()).unwrap_or(0.0)
+}
+
+/// Hourly stats of `index` for `[start_ms, end_ms)`, keyed by hour start.
+pub async fn hourly_stats(
+    engine: &AnalyticsQueryEngine,
+    index: &str,
+    start_ms: i64,
+    end_ms: i64,
+) -> Result<HashMap<i64, HourStats>, String> {
+    let search_sql = format!(
+        "SELECT CAST(timestamp_ms / {hour} * {hour} AS BIGINT) as hour_ms, \
+         COUNT(*) as searches, \
+         SUM(CASE WHEN has_results THEN 0 ELSE 1 END) as no_results, \
+         AVG(CAST(processing_time_ms AS DOUBLE)) as avg_latency_ms, \
+         COUNT(query_id) as tracked \
+         FROM searches \
+         WHERE timestamp_ms >= {start} AND timestamp_ms < {end} \
+         GROUP BY hour_ms",
+        hour = HOUR_MS,
+        start = start_ms,
+        end = end_ms
+    );
+    let mut hours: HashMap<i64, HourStats> = engine
+        .query_searches(index, &search_sql)
+        .await?
+        .iter()
+        .filter_map(|row| {
+            let hour = row.get("hour_ms")?.as_i64()?;
+            let stats = HourStats {
+                searches: number(row, "searches"),
+                no_results: number(row, "no_results"),
+                avg_latency_ms: number(row, "avg_latency_ms"),
+                tracked: number(row, "tracked"),
+                clicked: 0.0,
+            };
+            Some((hour, stats))
+        })
+        .collect();
+
+    let click_sql = format!(
+        "SELECT CAST(timestamp_ms / {hour} * {hour} AS BIGINT) as hour_ms, \
+         COUNT(DISTINCT query_id) as clicked \
+         FROM events \
+         WHERE timestamp_ms >= {start} AND timestamp_ms < {end} \
+           AND event_type = 'click' AND query_id IS NOT NULL \
+         GROUP BY hour_ms",
+        hour = HOUR_MS,
+        start = start_ms,
+        end = end_ms
+    );
+    for row in engine.query_events(index, &click_sql).await? {
+        let Some(hour) = row.get("hour_ms").and_then(|v| v.as_i64()) else {
+            continue;
+        };
+        if let Some(stats) = hours.get_mut(&hour) {
+            stats.clicked = number(&row, "clicked");
+        }
+    }
+    Ok(hours)
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoreFile {
+    #[serde(default)]
+    anomalies: Vec<Anomaly>,
+}
+
+/// Detected anomalies persisted in `{base_dir}/anomalies.json`, oldest first.
+pub struct AnomalyStore {
+    path: PathBuf,
+}
+
+impl AnomalyStore {
+    pub fn new(base_dir: &std::path::Path) -> Self {
+        Self {
+            path: base_dir.join("anomalies.json"),
+        }
+    }
+
+    fn load(&self) -> std::io::Result<StoreFile> {
+        if !self.path.exists() {
+            return Ok(StoreFile::default());
+        }
+        let json = std::fs::read_to_string(&self.path)?;
+        serde_json::from_str(&json)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    fn save(&self, file: &StoreFile) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(file).map_err(std::io::Error::other)?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(tmp, &self.path)
+    }
+
+    /// Anomalies, newest first, optionally only those of `index`.
+    pub fn list(&self, index: Option<&str>) -> std::io::Result<Vec<Anomaly>> {
+        let mut anomalies = self.load()?.anomalies;
+        anomalies.retain(|a| index.is_none_or(|i| a.index == i));
+        anomalies.reverse();
+        Ok(anomalies)
+    }
+
+    /// Store the anomalies not already recorded for the same index, kind and
+    /// hour, and return them.
+    pub fn record(&self, found: Vec<Anomaly>) -> std::io::Result<Vec<Anomaly>> {
+        let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = self.load()?;
+        let new: Vec<Anomaly> = found
+            .into_iter()
+            .filter(|a| {
+                !file.anomalies.iter().any(|known| {
+                    known.index == a.index
+                        && known.kind == a.kind
+                        && known.hour_start == a.hour_start
+                })
+            })
+            .collect();
+        if new.is_empty() {
+            return Ok(new);
+        }
+        file.anomalies.extend(new.iter().cloned());
+        let excess = file.anomalies.len().saturating_sub(MAX_STORED);
+        file.anomalies.drain(..excess);
+        self.save(&file)?;
+        Ok(new)
+    }
+}
+
+async fn notify_webhook(url: &str, anomaly: &Anomaly) {
+    let mut payload = serde_json::json!({ "event": "analytics.anomaly" });
+    if let (Some(payload), serde_json::Value::Object(fields)) =
+        (payload.as_object_mut(), serde_json::json!(anomaly))
+    {
+        payload.extend(fields);
+    }
+    let result = reqwest::Client::new()
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&payload)
+        .send()
+        .await;
+    match result {
+        Ok(resp) if resp.status().is_success() => {}
+        Ok(resp) => tracing::warn!("[ANOMALY] webhook returned {}", resp.status()),
+        Err(e) => tracing::warn!("[ANOMALY] webhook failed: {}", e),
+    }
+}
+
+/// Check the last complete hour of every index with analytics; returns the
+/// anomalies not seen before.
+pub async fn run_detection(state: &AppState) -> Vec<Anomaly> {
+    let Some(engine) = state.analytics_engine.as_deref() else {
+        return Vec::new();
+    };
+    let indices = match engine.list_analytics_indices() {
+        Ok(indices) => indices,
+        Err(e) => {
+            tracing::warn!("[ANOMALY] Failed to list analytics indices: {}", e);
+            return Vec::new();
+        }
+    };
+    let now = chrono::Utc::now().timestamp_millis();
+    let hour_start = now / HOUR_MS * HOUR_MS - HOUR_MS;
+    let since = hour_start - BASELINE_WEEKS * WEEK_MS;
+    let mut found = Vec::new();
+    for index in indices {
+        match hourly_stats(engine, &index, since, hour_start + HOUR_MS).await {
+            Ok(hours) => found.extend(detect(&index, &hours, hour_start, now)),
+            Err(e) => tracing::warn!("[ANOMALY] Failed to read analytics of {}: {}", index, e),
+        }
+    }
+    let new = match AnomalyStore::new(&state.manager.base_path).record(found) {
+        Ok(new) => new,
+        Err(e) => {
+            tracing::warn!("[ANOMALY] Failed to store anomalies: {}", e);
+            return Vec::new();
+        }
+    };
+    for anomaly in &new {
+        tracing::warn!(
+            "[ANOMALY] {:?} on {}: {:.3} against a baseline of {:.3}",
+            anomaly.kind,
+            anomaly.index,
+            anomaly.value,
+            anomaly.baseline
+        );
+    }
+    if let Ok(url) = std::env::var("FLAPJACK_ANOMALY_WEBHOOK_URL") {
+        for anomaly in &new {
+            notify_webhook(&url, anomaly).await;
+        }
+    }
+    new
+}
+
+pub fn spawn_anomaly_detector(state: Arc<AppState>, tick_secs: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(tick_secs.max(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            run_detection(&state).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const HOUR: i64 = 1_000 * WEEK_MS;
+
+    fn stats(
+        searches: f64,
+        no_results: f64,
+        latency: f64,
+        tracked: f64,
+        clicked: f64,
+    ) -> HourStats {
+        HourStats {
+            searches,
+            no_results,
+            avg_latency_ms: latency,
+            tracked,
+            clicked,
+        }
+    }
+
+    /// Four weeks of a steady hour followed by `current`.
+    fn history(current: HourStats) -> HashMap<i64, HourStats> {
+        let mut hours: HashMap<i64, HourStats> = [(100.0, 5.0, 20.0), (110.0, 6.0, 22.0)]
+            .iter()
+            .cycle()
+            .take(4)
+            .enumerate()
+            .map(|(i, &(searches, no_results, latency))| {
+                let week = i as i64 + 1;
+                let past = stats(searches, no_results, latency, 80.0, 24.0);
+                (HOUR - week * WEEK_MS, past)
+            })
+            .collect();
+        // The hour before is not part of the seasonal baseline.
+        hours.insert(HOUR - HOUR_MS, stats(5_000.0, 0.0, 500.0, 0.0, 0.0));
+        hours.insert(HOUR, current);
+        hours
+    }
+
+    fn kinds(hours: &HashMap<i64, HourStats>) -> Vec<AnomalyKind> {
+        detect("products", hours, HOUR, 0)
+            .into_iter()
+            .map(|a| a.kind)
+            .collect()
+    }
+
+    #[test]
+    fn steady_hour_is_not_flagged() {
+        assert!(kinds(&history(stats(120.0, 7.0, 24.0, 90.0, 25.0))).is_empty());
+    }
+
+    #[test]
+    fn flags_each_kind_against_seasonal_baseline() {
+        assert_eq!(
+            kinds(&history(stats(400.0, 20.0, 21.0, 300.0, 90.0))),
+            vec![AnomalyKind::TrafficSpike]
+        );
+        assert_eq!(
+            kinds(&history(stats(105.0, 40.0, 21.0, 80.0, 24.0))),
+            vec![AnomalyKind::ZeroResultSurge]
+        );
+        assert_eq!(
+            kinds(&history(stats(105.0, 5.0, 90.0, 80.0, 24.0))),
+            vec![AnomalyKind::LatencyRegression]
+        );
+        assert_eq!(
+            kinds(&history(stats(105.0, 5.0, 21.0, 80.0, 4.0))),
+            vec![AnomalyKind::CtrCliff]
+        );
+    }
+
+    #[test]
+    fn low_volume_and_short_history_are_not_judged() {
+        assert!(kinds(&history(stats(40.0, 40.0, 90.0, 40.0, 0.0))).is_empty());
+
+        let mut hours = history(stats(400.0, 5.0, 21.0, 80.0, 24.0));
+        for week in 2..=BASELINE_WEEKS {
+            hours.remove(&(HOUR - week * WEEK_MS));
+        }
+        assert!(kinds(&hours).is_empty());
+    }
+
+    #[test]
+    fn store_dedupes_and_lists_newest_first() {
+        let dir = TempDir::new().unwrap();
+        let store = AnomalyStore::new(dir.path());
+        let anomaly = |index: &str, kind, hour_start| Anomaly {
+            index: index.to_string(),
+            kind,
+            hour_start,
+            value: 1.0,
+            baseline: 0.5,
+            detected_at: 0,
+        };
+
+        let first = vec![
+            anomaly("a", AnomalyKind::TrafficSpike, HOUR),
+            anomaly("b", AnomalyKind::CtrCliff, HOUR),
+        ];
+        assert_eq!(store.record(first.clone()).unwrap(), first);
+        let again = vec![
+            anomaly("a", AnomalyKind::TrafficSpike, HOUR),
+            anomaly("a", AnomalyKind::TrafficSpike, HOUR + HOUR_MS),
+        ];
+        assert_eq!(store.record(again).unwrap().len(), 1);
+
+        let listed = store.list(None).unwrap();
+        assert_eq!(listed.len(), 3);
+        assert_eq!(listed[0].hour_start, HOUR + HOUR_MS);
+        let only_b = store.list(Some("b")).unwrap();
+        assert_eq!(only_b, vec![anomaly("b", AnomalyKind::CtrCliff, HOUR)]);
+    }
+}
@@ -0,0 +1,229 @@
+//! Deep-paging cursors for `/query`.
+//!
+//! `page`/`hitsPerPage` paging stops at the index's `paginationLimitedTo`.
+//! A search sent with `cursor: ""` instead gets a `cursor` in its response
+//! that resumes right after the last hit, with no depth limit.
+//!
+//! Most queries resume by offset, like browse cursors, and the cursor is
+//! invalidated when the index changes. Geo and hybrid queries rank a
+//! candidate window in the handler, so re-running them for every page means
+//! re-fetching and re-sorting everything before the page. For those the
+//! first page stores the ranked window here, and later pages are read from
+//! it by objectID.
+
+use base64::Engine;
+use flapjack::error::FlapjackError;
+use flapjack::types::SearchResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How long a stored window stays readable after it was last used.
+const SNAPSHOT_TTL: Duration = Duration::from_secs(300);
+/// Stored windows kept at once; the least recently used one is evicted.
+const MAX_SNAPSHOTS: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchCursor {
+    /// Position of the next hit to return.
+    pub offset: usize,
+    /// Index generation the offset was computed against.
+    pub generation: u64,
+    /// Stored window to read from, for geo and hybrid queries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<u64>,
+}
+
+impl SearchCursor {
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_string(self).unwrap();
+        base64::engine::general_purpose::STANDARD.encode(json.as_bytes())
+    }
+
+    /// Parse the `cursor` request parameter. An empty string starts a new
+    /// iteration.
+    pub fn decode(cursor: &str) -> Result<Option<Self>, FlapjackError> {
+        if cursor.is_empty() {
+            return Ok(None);
+        }
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(cursor)
+            .map_err(|_| FlapjackError::InvalidQuery("Invalid cursor".to_string()))?;
+        serde_json::from_slice(&decoded)
+            .map(Some)
+            .map_err(|_| FlapjackError::InvalidQuery("Invalid cursor format".to_string()))
+    }
+}
+
+/// Hash of the index's segment set; changes whenever a commit lands.
+pub fn index_generation(index: &flapjack::Index) -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    let generation = index
+        .reader()
+        .searcher()
+        .segment_readers()
+        .iter()
+        .map(|sr| sr.segment_id().uuid_string())
+        .collect::<Vec<_>>()
+        .join("-");
+    let mut hasher = DefaultHasher::new();
+    generation.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// One ranked hit of a stored window.
+#[derive(Debug, Clone)]
+pub struct SnapshotHit {
+    pub object_id: String,
+    pub score: f32,
+    /// `(distance, lat, lng)` for geo queries.
+    pub geo: Option<(f64, f64, f64)>,
+}
+
+/// A ranked window plus the page-independent parts of the first response
+/// (total, facets, rule output). `result.documents` is always empty.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub index: String,
+    pub hits: Vec<SnapshotHit>,
+    pub result: SearchResult,
+}
+
+struct Entry {
+    snapshot: Snapshot,
+    last_used: Instant,
+}
+
+pub struct SnapshotStore {
+    entries: Mutex<HashMap<u64, Entry>>,
+    next_id: Mutex<u64>,
+}
+
+impl SnapshotStore {
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            next_id: Mutex::new(rand::random()),
+        }
+    }
+
+    pub fn global() -> &'static SnapshotStore {
+        static STORE: OnceLock<SnapshotStore> = OnceLock::new();
+        STORE.get_or_init(SnapshotStore::new)
+    }
+
+    /// Store a window and return its id.
+    pub fn insert(&self, mut snapshot: Snapshot) -> u64 {
+        snapshot.result.documents.clear();
+        let id = {
+            let mut next = self.next_id.lock().unwrap();
+            *next = next.wrapping_add(1);
+            *next
+        };
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, e| e.last_used.elapsed() < SNAPSHOT_TTL);
+        if entries.len() >= MAX_SNAPSHOTS {
+            if let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(id, e)| (e.last_used, **id))
+                .map(|(id, _)| *id)
+            {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            id,
+            Entry {
+                snapshot,
+                last_used: Instant::now(),
+            },
+        );
+        id
+    }
+
+    /// The window `id` for `index`, refreshing its TTL. Errors when it has
+    /// expired or belongs to another index.
+    pub fn get(&self, id: u64, index: &str) -> Result<Snapshot, FlapjackError> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get_mut(&id) {
+            Some(e) if e.snapshot.index == index && e.last_used.elapsed() < SNAPSHOT_TTL => {
+                e.last_used = Instant::now();
+                Ok(e.snapshot.clone())
+            }
+            _ => Err(FlapjackError::InvalidQuery(
+                "Cursor is not valid anymore (expired)".to_string(),
+            )),
+        }
+    }
+}
+
+impl Default for SnapshotStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(index: &str, n: usize) -> Snapshot {
+        Snapshot {
+            index: index.to_string(),
+            hits: (0..n)
+                .map(|i| SnapshotHit {
+                    object_id: i.to_string(),
+                    score: 1.0,
+                    geo: None,
+                })
+                .collect(),
+            result: SearchResult {
+                documents: Vec::new(),
+                total: n,
+                facets: HashMap::new(),
+                user_data: Vec::new(),
+                applied_rules: Vec::new(),
+                rendering_content: None,
+                exhaustive_nb_hits: true,
+                synonyms_triggered: Vec::new(),
+                synonyms_matched: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        let cursor = SearchCursor {
+            offset: 40,
+            generation: 7,
+            snapshot: Some(3),
+        };
+        assert_eq!(
+            SearchCursor::decode(&cursor.encode()).unwrap(),
+            Some(cursor)
+        );
+        assert_eq!(SearchCursor::decode("").unwrap(), None);
+        assert!(SearchCursor::decode("not base64!").is_err());
+    }
+
+    #[test]
+    fn snapshots_are_scoped_to_their_index() {
+        let store = SnapshotStore::new();
+        let id = store.insert(snapshot("products", 5));
+        assert_eq!(store.get(id, "products").unwrap().hits.len(), 5);
+        assert!(store.get(id, "other").is_err());
+        assert!(store.get(id.wrapping_add(1), "products").is_err());
+    }
+
+    #[test]
+    fn oldest_snapshot_is_evicted_at_capacity() {
+        let store = SnapshotStore::new();
+        let first = store.insert(snapshot("products", 1));
+        for _ in 0..MAX_SNAPSHOTS {
+            store.insert(snapshot("products", 1));
+        }
+        assert!(store.get(first, "products").is_err());
+    }
+}
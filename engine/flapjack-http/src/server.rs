@@ -8,7 +8,6 @@ use fs2::FileExt;
 use std::fs::OpenOptions;
 use std::path::Path;
 use std::sync::Arc;
-use tower_http::cors::CorsLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
@@ -17,12 +16,14 @@ use crate::auth::{authenticate_and_authorize, generate_admin_key, generate_hex_k
 use crate::handlers::dashboard::dashboard_handler;
 use crate::handlers::snapshot;
 use crate::handlers::{
-    add_documents, add_record_auto_id, batch_search, browse_index, clear_index, clear_rules,
-    clear_synonyms, compact_index, create_index, delete_by_query, delete_index, delete_object,
-    delete_rule, delete_synonym, get_object, get_objects, get_rule, get_synonym, get_task,
-    get_task_for_index, health, list_algolia_indexes, list_indices, migrate_from_algolia,
-    operation_index, partial_update_object, put_object, save_rule, save_rules, save_synonym,
-    save_synonyms, search, search_facet_values, search_rules, search_synonyms, AppState,
+    add_documents, add_record_auto_id, analyze_query, batch_search, browse_index, clear_index,
+    clear_rules, clear_synonyms, clone_index, compact_index, create_index, delete_by_query,
+    delete_index, delete_object, delete_rule, delete_synonym, duplicate_report, get_object,
+    get_objects, get_rule, get_rule_stats, get_synonym, get_synonym_stats, get_task,
+    get_task_for_index, get_warmup, health, health_live, health_ready, index_stats,
+    list_algolia_indexes, list_indices, list_tasks, migrate_from_algolia, operation_index,
+    partial_update_object, put_object, save_rule, save_rules, save_synonym, save_synonyms, search,
+    search_facet_values, search_rules, search_synonyms, start_warmup, AppState,
 };
 use crate::middleware::{allow_private_network, normalize_content_type};
 use crate::openapi::ApiDoc;
@@ -82,6 +83,15 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
             max_concurrent_writers = budget.max_concurrent_writers(),
             "Memory configuration loaded"
         );
+        let limits = flapjack::ResourceLimits::global();
+        tracing::info!(
+            cpus = limits.cpus,
+            cpu_source = limits.cpu_source,
+            worker_threads = limits.worker_threads(),
+            blocking_threads = limits.blocking_threads(),
+            facet_cache_cap = limits.facet_cache_cap(),
+            "Resource limits detected"
+        );
     }
 
     let data_dir = std::env::var("FLAPJACK_DATA_DIR").unwrap_or_else(|_| "./data".to_string());
@@ -157,6 +167,10 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     let manager = IndexManager::new(&data_dir);
+    manager.facet_cache_cap.store(
+        flapjack::ResourceLimits::global().facet_cache_cap(),
+        std::sync::atomic::Ordering::Relaxed,
+    );
 
     // Load replication config and initialize ReplicationManager
     let node_config =
@@ -238,6 +252,23 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
 
     if let Some(s3_config) = flapjack::index::s3::S3Config::from_env() {
         auto_restore_from_s3(&data_dir, &s3_config, &manager).await;
+        manager.set_cold_storage(Arc::new(flapjack::index::tiered::S3ColdStorage::new(
+            s3_config.clone(),
+        )));
+        let cold_after_days: u64 = std::env::var("FLAPJACK_COLD_AFTER_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        if cold_after_days > 0 {
+            let mgr = Arc::clone(&manager);
+            tokio::spawn(async move {
+                demote_idle_indexes(mgr, cold_after_days).await;
+            });
+            tracing::info!(
+                "Indexes not searched for {} days move to cold storage",
+                cold_after_days
+            );
+        }
         let interval_secs: u64 = std::env::var("FLAPJACK_SNAPSHOT_INTERVAL")
             .ok()
             .and_then(|v| v.parse().ok())
@@ -253,6 +284,27 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    let disk_check_secs: u64 = std::env::var("FLAPJACK_DISK_CHECK_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10);
+    if crate::disk_watchdog::DiskWatchdog::global()
+        .thresholds()
+        .read_only_below
+        > 0
+    {
+        let dd = std::path::PathBuf::from(&data_dir);
+        let webhook_url = std::env::var("FLAPJACK_DISK_WEBHOOK_URL").ok();
+        tokio::spawn(async move {
+            crate::disk_watchdog::run_disk_watchdog(
+                dd,
+                std::time::Duration::from_secs(disk_check_secs.max(1)),
+                webhook_url,
+            )
+            .await;
+        });
+    }
+
     // Initialize analytics subsystem
     let analytics_config = flapjack::analytics::AnalyticsConfig::from_env();
     let analytics_collector =
@@ -304,6 +356,17 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
                 rollup_interval_secs
             );
         }
+
+        // Retrain per-index re-ranking models from click/conversion events.
+        let reranking_interval_secs: u64 = std::env::var("FLAPJACK_RERANKING_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(86400);
+        crate::reranking_trainer::spawn_reranking_trainer(
+            Arc::clone(&manager),
+            Arc::clone(&analytics_engine),
+            reranking_interval_secs,
+        );
     } else {
         tracing::info!("[analytics] Analytics disabled");
     }
@@ -327,11 +390,13 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
         embedder_store: Arc::new(crate::embedder_store::EmbedderStore::new()),
     });
 
-    // Startup catch-up: if replication is enabled, fetch missed ops from peers.
-    // Runs in background — does not delay server startup.
+    // Re-queue uncommitted writes from the local WAL before the listener is
+    // bound, so every client write lands behind them in its index's queue.
+    crate::startup_catchup::replay_write_ahead_logs(&state).await;
+    // Startup catch-up: (if replication is enabled) fetch missed ops from
+    // peers. Runs in background — does not delay server startup.
+    crate::startup_catchup::spawn_startup_catchup(Arc::clone(&state));
     if state.replication_manager.is_some() {
-        crate::startup_catchup::spawn_startup_catchup(Arc::clone(&state));
-
         // P0: Periodic anti-entropy sync — pulls missed ops from peers on a timer.
         // Closes the partition recovery gap: if nodes can't communicate for a while
         // but remain running, writes made during the partition are synced when
@@ -347,6 +412,94 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
         );
     }
 
+    // Delayed and recurring operations defined via /1/schedules.
+    let scheduler_tick_secs: u64 = std::env::var("FLAPJACK_SCHEDULER_TICK_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(15);
+    crate::scheduler::spawn_scheduler(Arc::clone(&state), scheduler_tick_secs);
+
+    // Background compaction for indexes with a `compaction` settings policy.
+    let compaction_tick_secs: u64 = std::env::var("FLAPJACK_COMPACTION_TICK_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(300);
+    crate::compaction_scheduler::spawn_compaction_scheduler(
+        Arc::clone(&state),
+        compaction_tick_secs,
+    );
+
+    // Deletes documents whose `_expiresAt` has passed.
+    let ttl_sweep_secs: u64 = std::env::var("FLAPJACK_TTL_SWEEP_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60);
+    crate::ttl_sweeper::spawn_ttl_sweeper(Arc::clone(&state), ttl_sweep_secs);
+
+    // Alerts on saved analytics queries (/2/alerts).
+    let alert_tick_secs: u64 = std::env::var("FLAPJACK_ALERT_TICK_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60);
+    crate::saved_searches::spawn_alert_checker(Arc::clone(&state), alert_tick_secs);
+
+    // Seasonal anomaly detection on search analytics (/1/analytics/anomalies).
+    let anomaly_tick_secs: u64 = std::env::var("FLAPJACK_ANOMALY_TICK_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(900);
+    crate::search_anomalies::spawn_anomaly_detector(Arc::clone(&state), anomaly_tick_secs);
+
+    // Multi-cluster user mapping: migrate reassigned users, publish user stats.
+    let mcm_tick_secs: u64 = std::env::var("FLAPJACK_MCM_TICK_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30);
+    crate::mcm::spawn_mcm_worker(Arc::clone(&state), mcm_tick_secs);
+
+    // Replay base-index writes into auto-provisioned experiment variant indexes.
+    if let Some(store) = &state.experiment_store {
+        let variant_sync_secs: u64 = std::env::var("FLAPJACK_EXPERIMENT_SYNC_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5);
+        flapjack::experiments::provisioning::spawn_variant_sync(
+            Arc::clone(&state.manager),
+            Arc::clone(store),
+            variant_sync_secs,
+        );
+
+        // Stop experiments whose autoStop policy trips (SRM, persistent guard rails).
+        let auto_stop_secs: u64 = std::env::var("FLAPJACK_EXPERIMENT_AUTOSTOP_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300);
+        crate::experiment_auto_stop::spawn_auto_stop_checker(Arc::clone(&state), auto_stop_secs);
+
+        // Start and stop experiments at their scheduledStartAt / scheduledEndAt.
+        let schedule_tick_secs: u64 = std::env::var("FLAPJACK_EXPERIMENT_SCHEDULE_TICK_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+        let store = Arc::clone(store);
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(tokio::time::Duration::from_secs(schedule_tick_secs.max(1)));
+            loop {
+                interval.tick().await;
+                for experiment in
+                    store.run_scheduled_transitions(chrono::Utc::now().timestamp_millis())
+                {
+                    tracing::info!(
+                        "[EXPERIMENTS] scheduled transition: {} is now {:?}",
+                        experiment.id,
+                        experiment.status
+                    );
+                }
+            }
+        });
+    }
+
     // Background poller: update per-tenant storage gauges every 60s
     {
         let mgr = Arc::clone(&state.manager);
@@ -377,7 +530,9 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
                     .put(crate::handlers::update_key)
                     .delete(crate::handlers::delete_key),
             )
+            .route("/1/keys/audit", get(crate::handlers::key_audit_log))
             .route("/1/keys/:key/restore", post(crate::handlers::restore_key))
+            .route("/1/keys/:key/rotate", post(crate::handlers::rotate_key))
             .route(
                 "/1/keys/generateSecuredApiKey",
                 post(crate::handlers::generate_secured_key),
@@ -390,11 +545,37 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
     let protected = Router::new()
         .route("/1/indexes", post(create_index))
         .route("/1/indexes", get(list_indices))
+        .route(
+            "/1/indexes:import",
+            post(crate::handlers::archive::import_archive),
+        )
         .route("/1/indexes/:indexName/browse", post(browse_index))
         .route("/1/indexes/:indexName/clear", post(clear_index))
         .route("/1/indexes/:indexName/compact", post(compact_index))
+        .route("/1/indexes/:indexName/stats", get(index_stats))
+        .route("/1/indexes/:indexName/duplicates", get(duplicate_report))
+        .route(
+            "/1/indexes/:indexName/mode",
+            get(crate::handlers::indices::get_index_mode)
+                .post(crate::handlers::indices::set_index_mode),
+        )
+        .route(
+            "/1/indexes/:indexName/warmup",
+            get(get_warmup).post(start_warmup),
+        )
+        .route(
+            "/1/indexes/:indexName/crawler",
+            get(crate::handlers::crawler::get_crawler)
+                .put(crate::handlers::crawler::put_crawler)
+                .delete(crate::handlers::crawler::delete_crawler),
+        )
+        .route(
+            "/1/indexes/:indexName/crawler/run",
+            post(crate::handlers::crawler::run_crawler),
+        )
         .route("/1/indexes/:indexName/batch", post(add_documents))
         .route("/1/indexes/:indexName/query", post(search))
+        .route("/1/indexes/:indexName/query/analyze", post(analyze_query))
         .route("/1/indexes/:indexName/deleteByQuery", post(delete_by_query))
         .route(
             "/1/indexes/:indexName/facets/:facetName/query",
@@ -419,6 +600,34 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
             "/1/indexes/:indexName/synonyms/search",
             post(search_synonyms),
         )
+        .route(
+            "/1/indexes/:indexName/synonyms/stats",
+            get(get_synonym_stats),
+        )
+        .route(
+            "/1/indexes/:indexName/blocklist",
+            get(crate::handlers::blocklist::list_blocked_results),
+        )
+        .route(
+            "/1/indexes/:indexName/blocklist/:objectID",
+            get(crate::handlers::blocklist::get_blocked_results)
+                .put(crate::handlers::blocklist::save_blocked_results)
+                .delete(crate::handlers::blocklist::delete_blocked_results),
+        )
+        .route(
+            "/1/indexes/:indexName/campaigns",
+            get(crate::handlers::campaigns::list_campaigns),
+        )
+        .route(
+            "/1/indexes/:indexName/campaigns/:objectID",
+            get(crate::handlers::campaigns::get_campaign)
+                .put(crate::handlers::campaigns::save_campaign)
+                .delete(crate::handlers::campaigns::delete_campaign),
+        )
+        .route(
+            "/1/indexes/:indexName/campaigns/:objectID/stats",
+            get(crate::handlers::campaigns::get_campaign_stats),
+        )
         .route("/1/indexes/:indexName/rules/:objectID", get(get_rule))
         .route(
             "/1/indexes/:indexName/rules/:objectID",
@@ -428,7 +637,12 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
         .route("/1/indexes/:indexName/rules/batch", post(save_rules))
         .route("/1/indexes/:indexName/rules/clear", post(clear_rules))
         .route("/1/indexes/:indexName/rules/search", post(search_rules))
+        .route(
+            "/1/indexes/:indexName/rules/:objectID/stats",
+            get(get_rule_stats),
+        )
         .route("/1/indexes/:indexName/operation", post(operation_index))
+        .route("/1/indexes/:indexName/clone", post(clone_index))
         .route(
             "/1/indexes/:indexName/export",
             get(snapshot::export_snapshot),
@@ -449,6 +663,14 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
             "/1/indexes/:indexName/snapshots",
             get(snapshot::list_s3_snapshots),
         )
+        .route(
+            "/1/indexes/:indexName/cluster-snapshots",
+            get(snapshot::list_cluster_snapshots).post(snapshot::create_cluster_snapshot),
+        )
+        .route(
+            "/1/indexes/:indexName/tier",
+            get(crate::handlers::tier::get_tier).put(crate::handlers::tier::set_tier),
+        )
         .route("/1/indexes/:indexName/queries", post(batch_search))
         .route("/1/indexes/:indexName/objects", post(get_objects))
         .route(
@@ -473,7 +695,93 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
         )
         .route("/1/migrate-from-algolia", post(migrate_from_algolia))
         .route("/1/algolia-list-indexes", post(list_algolia_indexes))
+        .route("/1/tasks", get(list_tasks))
         .route("/1/tasks/:task_id", get(get_task))
+        .route(
+            "/1/configuration",
+            get(crate::handlers::configuration::get_configuration),
+        )
+        .route("/1/clusters", get(crate::handlers::clusters::list_clusters))
+        .route(
+            "/1/clusters/mapping",
+            post(crate::handlers::clusters::assign_user_id)
+                .get(crate::handlers::clusters::list_user_ids)
+                .delete(crate::handlers::clusters::remove_user_id),
+        )
+        .route(
+            "/1/clusters/mapping/batch",
+            post(crate::handlers::clusters::batch_assign_user_ids),
+        )
+        .route(
+            "/1/clusters/mapping/top",
+            get(crate::handlers::clusters::get_top_user_ids),
+        )
+        .route(
+            "/1/clusters/mapping/search",
+            post(crate::handlers::clusters::search_user_ids),
+        )
+        .route(
+            "/1/clusters/mapping/pending",
+            get(crate::handlers::clusters::has_pending_mappings),
+        )
+        .route(
+            "/1/clusters/mapping/:userID",
+            get(crate::handlers::clusters::get_user_id),
+        )
+        .route(
+            "/1/schedules",
+            post(crate::handlers::schedules::create_schedule)
+                .get(crate::handlers::schedules::list_schedules),
+        )
+        .route(
+            "/1/schedules/:id",
+            get(crate::handlers::schedules::get_schedule)
+                .delete(crate::handlers::schedules::delete_schedule),
+        )
+        .route(
+            "/1/schedules/:id/pause",
+            post(crate::handlers::schedules::pause_schedule),
+        )
+        .route(
+            "/1/schedules/:id/resume",
+            post(crate::handlers::schedules::resume_schedule),
+        )
+        .route(
+            "/1/schedules/:id/run",
+            post(crate::handlers::schedules::run_schedule),
+        )
+        .route("/1/flags", get(crate::handlers::feature_flags::list_flags))
+        .route(
+            "/1/flags/:key",
+            get(crate::handlers::feature_flags::get_flag)
+                .put(crate::handlers::feature_flags::put_flag)
+                .delete(crate::handlers::feature_flags::delete_flag),
+        )
+        .route(
+            "/1/namespaces",
+            get(crate::handlers::namespaces::list_namespaces),
+        )
+        .route(
+            "/1/namespaces/:name",
+            get(crate::handlers::namespaces::get_namespace)
+                .put(crate::handlers::namespaces::put_namespace)
+                .delete(crate::handlers::namespaces::delete_namespace),
+        )
+        .route("/1/settings/bulk", post(crate::handlers::bulk_set_settings))
+        .route(
+            "/1/templates",
+            get(crate::handlers::templates::list_templates),
+        )
+        .route(
+            "/1/templates/:name",
+            get(crate::handlers::templates::get_template)
+                .put(crate::handlers::templates::put_template)
+                .delete(crate::handlers::templates::delete_template),
+        )
+        .route(
+            "/1/users/token",
+            post(crate::handlers::user_tokens::issue_user_token),
+        )
         .route(
             "/1/indexes/:indexName/task/:task_id",
             get(get_task_for_index),
@@ -502,7 +810,34 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
             "/1/logs/:indexName",
             get(crate::handlers::query_suggestions::get_logs),
         )
+        .route(
+            "/1/logs",
+            get(crate::handlers::request_logs::get_request_logs),
+        )
+        .route("/1/errors", get(crate::handlers::errors::list_error_codes))
         .with_state(state.clone());
+    #[cfg(feature = "vector-search")]
+    let protected = protected.merge(
+        Router::new()
+            .route(
+                "/1/indexes/:indexName/vectors/rebuild",
+                post(crate::handlers::indices::rebuild_vectors),
+            )
+            .route(
+                "/1/indexes/:indexName/vectors/usage",
+                get(crate::handlers::indices::vector_usage),
+            )
+            .with_state(state.clone()),
+    );
+    #[cfg(feature = "file-ingest")]
+    let protected = protected.merge(
+        Router::new()
+            .route(
+                "/1/indexes/:indexName/files",
+                post(crate::handlers::files::upload_file),
+            )
+            .with_state(state.clone()),
+    );
 
     let usage_counters_for_mw = usage_counters.clone();
     let protected =
@@ -538,6 +873,10 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
             post(crate::handlers::internal::replicate_ops),
         )
         .route("/internal/ops", get(crate::handlers::internal::get_ops))
+        .route(
+            "/internal/protocol",
+            get(crate::handlers::internal::protocol_info),
+        )
         .route(
             "/internal/status",
             get(crate::handlers::internal::replication_status),
@@ -546,6 +885,14 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
             "/internal/cluster/status",
             get(crate::handlers::internal::cluster_status),
         )
+        .route(
+            "/internal/cluster/mapping",
+            post(crate::handlers::internal::receive_user_mapping),
+        )
+        .route(
+            "/internal/cluster/import/:tenant",
+            post(crate::handlers::internal::import_user_tenant),
+        )
         .route(
             "/internal/analytics-rollup",
             post(crate::handlers::internal::receive_analytics_rollup),
@@ -574,7 +921,60 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
             "/internal/resume/:indexName",
             post(crate::handlers::internal::resume_index),
         )
+        .route(
+            "/internal/snapshot/:indexName/barrier",
+            get(crate::handlers::internal::snapshot_barrier),
+        )
+        .route(
+            "/internal/snapshot/:indexName/capture",
+            post(crate::handlers::internal::capture_snapshot),
+        )
+        .route(
+            "/internal/qs/configs",
+            post(crate::handlers::internal::receive_qs_config),
+        )
+        .route(
+            "/internal/qs/configs/:indexName",
+            delete(crate::handlers::internal::delete_qs_config),
+        )
+        .route(
+            "/internal/schedules",
+            post(crate::handlers::internal::receive_schedule),
+        )
+        .route(
+            "/internal/schedules/:id",
+            delete(crate::handlers::internal::delete_schedule),
+        )
+        .route(
+            "/internal/qs/build/:indexName",
+            post(crate::handlers::internal::build_qs),
+        )
+        .route(
+            "/internal/qs/indexes/:indexName",
+            post(crate::handlers::internal::import_suggestions),
+        )
         .with_state(state.clone());
+    #[cfg(feature = "fault-injection")]
+    let internal = internal.merge(
+        Router::new()
+            .route(
+                "/internal/faults",
+                get(crate::handlers::internal::get_faults)
+                    .put(crate::handlers::internal::set_faults)
+                    .delete(crate::handlers::internal::clear_faults),
+            )
+            .with_state(state.clone()),
+    );
+    #[cfg(feature = "vector-search")]
+    let internal = internal.merge(
+        Router::new()
+            .route(
+                "/internal/vectors/:indexName",
+                get(crate::handlers::internal::export_vectors)
+                    .post(crate::handlers::internal::import_vectors),
+            )
+            .with_state(state.clone()),
+    );
 
     // Analytics API endpoints (Algolia Analytics API v2 compatible)
     let analytics_routes = Router::new()
@@ -675,6 +1075,41 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
         )
         .with_state(state.clone());
 
+    let saved_search_routes = Router::new()
+        .route(
+            "/2/savedSearches",
+            post(crate::handlers::saved_searches::create_saved_search)
+                .get(crate::handlers::saved_searches::list_saved_searches),
+        )
+        .route(
+            "/2/savedSearches/:id",
+            get(crate::handlers::saved_searches::get_saved_search)
+                .delete(crate::handlers::saved_searches::delete_saved_search),
+        )
+        .route(
+            "/2/savedSearches/:id/run",
+            get(crate::handlers::saved_searches::run_saved_search),
+        )
+        .route(
+            "/2/alerts",
+            post(crate::handlers::saved_searches::create_alert)
+                .get(crate::handlers::saved_searches::list_alerts),
+        )
+        .route(
+            "/2/alerts/:id",
+            get(crate::handlers::saved_searches::get_alert)
+                .delete(crate::handlers::saved_searches::delete_alert),
+        )
+        .route(
+            "/2/alerts/:id/check",
+            post(crate::handlers::saved_searches::check_alert),
+        )
+        .route(
+            "/1/analytics/anomalies",
+            get(crate::handlers::search_anomalies::list_anomalies),
+        )
+        .with_state(state.clone());
+
     let experiments_routes = Router::new()
         .route(
             "/2/abtests",
@@ -695,24 +1130,64 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
             "/2/abtests/:id/stop",
             post(crate::handlers::experiments::stop_experiment),
         )
+        .route(
+            "/2/abtests/:id/salt",
+            post(crate::handlers::experiments::rotate_assignment_salt),
+        )
+        .route(
+            "/2/abtests/:id/ramp",
+            post(crate::handlers::experiments::ramp_experiment),
+        )
         .route(
             "/2/abtests/:id/conclude",
             post(crate::handlers::experiments::conclude_experiment),
         )
+        .route(
+            "/2/abtests/:id/exposures",
+            post(crate::handlers::experiments::log_exposures),
+        )
+        .route(
+            "/2/holdouts",
+            get(crate::handlers::experiments::list_holdouts),
+        )
+        .route(
+            "/2/holdouts/:indexName",
+            axum::routing::put(crate::handlers::experiments::set_holdout),
+        )
+        .route(
+            "/2/shadows",
+            get(crate::handlers::experiments::list_shadows),
+        )
+        .route(
+            "/2/shadows/:indexName",
+            get(crate::handlers::experiments::get_shadow)
+                .put(crate::handlers::experiments::set_shadow)
+                .delete(crate::handlers::experiments::delete_shadow),
+        )
         .route(
             "/2/abtests/:id/results",
             get(crate::handlers::experiments::get_experiment_results),
         )
+        .route(
+            "/2/abtests/:id/results/timeseries",
+            get(crate::handlers::experiments::get_experiment_timeseries),
+        )
         .with_state(state.clone());
 
     // Insights API (event ingestion - Algolia compatible)
     let analytics_collector_for_shutdown = Arc::clone(&analytics_collector);
     let insights_routes = Router::new()
         .route("/1/events", post(crate::handlers::insights::post_events))
+        .route(
+            "/1/events/rejected",
+            get(crate::handlers::insights::get_rejected_events),
+        )
         .with_state(analytics_collector);
 
     let health_route = Router::new()
         .route("/health", get(health))
+        .route("/health/live", get(health_live))
+        .route("/health/ready", get(health_ready))
         .route("/metrics", get(crate::handlers::metrics_handler))
         .with_state(state.clone());
 
@@ -724,6 +1199,7 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
         .merge(analytics_routes)
         .merge(analytics_cleanup_routes)
         .merge(experiments_routes)
+        .merge(saved_search_routes)
         .merge(insights_routes)
         .merge(internal);
 
@@ -731,10 +1207,7 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
     let dashboard_routes = Router::new().fallback(get(dashboard_handler));
     let app = app.nest("/dashboard", dashboard_routes);
 
-    let max_body_mb: usize = std::env::var("FLAPJACK_MAX_BODY_MB")
-        .ok()
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(100);
+    let body_limits = crate::body_limits::BodyLimits::global();
     let mgr_for_pressure = Arc::clone(&state.manager);
     let default_facet_cache_cap = state
         .manager
@@ -754,12 +1227,58 @@ pub async fn serve() -> Result<(), Box<dyn std::error::Error>> {
             }
         },
     );
-    let app = app.layer(auth_middleware);
+    let data_dir_for_maintenance = state.manager.base_path.clone();
+    let maintenance_middleware = middleware::from_fn(
+        move |request: axum::extract::Request, next: middleware::Next| {
+            let data_dir = data_dir_for_maintenance.clone();
+            async move { crate::maintenance::maintenance_guard(request, next, &data_dir).await }
+        },
+    );
+    let mgr_for_tiering = Arc::clone(&state.manager);
+    let cold_write_middleware = middleware::from_fn(
+        move |request: axum::extract::Request, next: middleware::Next| {
+            let mgr = mgr_for_tiering.clone();
+            async move { crate::handlers::tier::promote_before_write(request, next, &mgr).await }
+        },
+    );
+    let read_only_middleware =
+        middleware::from_fn(|request: axum::extract::Request, next: middleware::Next| {
+            crate::disk_watchdog::read_only_guard(
+                request,
+                next,
+                crate::disk_watchdog::DiskWatchdog::global(),
+            )
+        });
+    // Multi-cluster user mapping rewrites request URIs, so it wraps the routed
+    // app instead of running as a route layer. Auth still sees the original
+    // index name.
+    let app = Router::new().fallback_service(
+        middleware::from_fn_with_state(Arc::clone(&state), crate::mcm::route_user_requests)
+            .layer(app),
+    );
+    // Maintenance modes are checked after auth so unauthenticated callers
+    // learn nothing about an index, and after namespace-scoped keys' index
+    // names are qualified. The request log sits outside auth so rejected
+    // requests are recorded too. Cold indexes are promoted for writes only
+    // once the maintenance check has let the write through.
+    let app = app
+        .layer(cold_write_middleware)
+        .layer(maintenance_middleware)
+        .layer(middleware::from_fn(
+            crate::namespaces::scope_namespace_requests,
+        ))
+        .layer(auth_middleware)
+        .layer(middleware::from_fn(crate::request_log::request_log_layer));
     let app = app
+        .layer(read_only_middleware)
         .layer(memory_middleware)
-        .layer(DefaultBodyLimit::max(max_body_mb * 1024 * 1024))
+        .layer(DefaultBodyLimit::max(body_limits.max()))
+        .layer(middleware::from_fn(crate::body_limits::enforce_body_limits))
         .layer(middleware::from_fn(normalize_content_type))
-        .layer(CorsLayer::very_permissive().max_age(std::time::Duration::from_secs(86400)))
+        .layer(middleware::from_fn(crate::error_codes::attach_error_codes))
+        .layer(crate::compression::request_layer())
+        .layer(crate::compression::CompressionConfig::from_env().response_layer())
+        .layer(crate::cors::CorsConfig::from_env().layer())
         .layer(middleware::from_fn(allow_private_network));
 
     let listener = tokio::net::TcpListener::bind(&bind_addr).await?;
@@ -1078,10 +1597,28 @@ async fn auto_restore_from_s3(
     }
 }
 
+/// Hourly, demote indexes that have not been searched for `days` days.
+async fn demote_idle_indexes(manager: std::sync::Arc<flapjack::IndexManager>, days: u64) {
+    let idle = std::time::Duration::from_secs(days * 24 * 3600);
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3600));
+    loop {
+        interval.tick().await;
+        for tid in manager.idle_tenants(idle) {
+            let mgr = std::sync::Arc::clone(&manager);
+            let tenant = tid.clone();
+            match tokio::task::spawn_blocking(move || mgr.demote_tenant(&tenant)).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => tracing::warn!("[TIER] demoting {} failed: {}", tid, e),
+                Err(e) => tracing::error!("[TIER] demoting {} panicked: {}", tid, e),
+            }
+        }
+    }
+}
+
 async fn scheduled_s3_backups(
     data_dir: String,
     s3_config: flapjack::index::s3::S3Config,
-    _manager: std::sync::Arc<flapjack::IndexManager>,
+    manager: std::sync::Arc<flapjack::IndexManager>,
     interval_secs: u64,
 ) {
     let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_secs));
@@ -1106,6 +1643,10 @@ async fn scheduled_s3_backups(
             }
         };
         for tid in &tenant_dirs {
+            // A cold index's segments are already in the bucket.
+            if manager.is_cold(tid) {
+                continue;
+            }
             let index_path = data_path.join(tid);
             match flapjack::index::snapshot::export_to_bytes(&index_path) {
                 Ok(bytes) => {
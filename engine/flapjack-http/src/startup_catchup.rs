@@ -1,20 +1,64 @@
 //! Startup catch-up and periodic anti-entropy sync.
 //!
+//! - `replay_write_ahead_logs`: runs once at boot, before the listener is
+//!   bound, to re-queue writes that were acknowledged but not committed
+//!   before the last shutdown or crash.
 //! - `spawn_startup_catchup`: runs once at boot (3s delay) to fetch missed ops.
 //! - `spawn_periodic_sync`:  runs on a timer (P0) to close partition gaps without restart.
 //!
 //! Both use the same core logic: iterate local tenant dirs, compare local oplog
 //! seq against peers, pull and apply any missing ops via LWW conflict resolution.
+//! Tenants are caught up in parallel; startup progress is tracked in
+//! [`CatchupProgress`].
 
 use crate::handlers::internal::apply_ops_to_manager;
 use crate::handlers::AppState;
-use std::sync::Arc;
+use flapjack_replication::manager::ReplicationManager;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+/// Re-queue uncommitted writes from every local tenant's WAL. Runs whether or
+/// not replication is enabled. Returns the number of writes replayed.
+pub async fn replay_write_ahead_logs(state: &AppState) -> usize {
+    let entries = match std::fs::read_dir(&state.manager.base_path) {
+        Ok(e) => e,
+        Err(e) => {
+            tracing::warn!("[WAL] Cannot read data dir: {}", e);
+            return 0;
+        }
+    };
+
+    let mut replayed = 0;
+    for entry in entries.flatten() {
+        if !entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+            continue;
+        }
+        let tenant_id = entry.file_name().to_string_lossy().to_string();
+        if tenant_id.starts_with('.') {
+            continue;
+        }
+        match state.manager.replay_wal(&tenant_id).await {
+            Ok(n) => replayed += n,
+            Err(e) => tracing::error!("[WAL] Failed to replay writes for '{}': {}", tenant_id, e),
+        }
+    }
+    replayed
+}
 
 /// Spawn a background task that catches up all local tenants from peers.
 /// Returns immediately — the catch-up runs concurrently with normal traffic.
+/// Call it after [`replay_write_ahead_logs`] so peers' ops are applied on top
+/// of the replayed writes; indexes are warmed up once catch-up is done.
+/// While replication catch-up is pending, `/health` reports `catching_up`.
 pub fn spawn_startup_catchup(state: Arc<AppState>) {
+    if state.replication_manager.is_some() {
+        CatchupProgress::global().begin();
+    }
     tokio::spawn(async move {
-        run_startup_catchup(state).await;
+        run_startup_catchup(Arc::clone(&state)).await;
+        crate::warmup::warm_all_indexes(state).await;
     });
 }
 
@@ -24,13 +68,23 @@ pub async fn run_startup_catchup(state: Arc<AppState>) {
         return; // Standalone mode — nothing to do
     }
 
+    let progress = CatchupProgress::global();
+    progress.begin();
+
     // Wait briefly so the server is accepting requests before catch-up starts.
     // This avoids log noise during normal single-node startup.
     tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
 
     tracing::info!("[REPL-catchup] Starting startup catch-up from peers");
-    catchup_all_tenants(&state, "REPL-catchup").await;
-    tracing::info!("[REPL-catchup] Startup catch-up complete");
+    catchup_all_tenants(&state, "REPL-catchup", Some(progress)).await;
+    progress.finish();
+    let status = progress.status();
+    tracing::info!(
+        "[REPL-catchup] Startup catch-up complete: {} ops applied across {} tenants in {}s",
+        status.ops_applied,
+        status.tenants_total,
+        status.elapsed_secs
+    );
 }
 
 /// Run one round of catch-up from peers for all local tenants.
@@ -41,7 +95,7 @@ pub async fn run_periodic_catchup(state: Arc<AppState>) {
     if state.replication_manager.is_none() {
         return;
     }
-    catchup_all_tenants(&state, "REPL-sync").await;
+    catchup_all_tenants(&state, "REPL-sync", None).await;
 }
 
 /// Spawn a background task that runs catch-up from peers on a timer.
@@ -64,9 +118,13 @@ pub fn spawn_periodic_sync(state: Arc<AppState>, interval_secs: u64) {
 }
 
 /// Core catch-up logic shared by startup and periodic sync.
-/// Iterates all local tenant directories, compares local oplog sequence
-/// against peers, and pulls any missed ops.
-async fn catchup_all_tenants(state: &AppState, log_prefix: &str) {
+/// Lists local tenant directories, then fetches and applies missed ops for up
+/// to `FLAPJACK_CATCHUP_CONCURRENCY` tenants at a time (default 4).
+async fn catchup_all_tenants(
+    state: &Arc<AppState>,
+    log_prefix: &'static str,
+    progress: Option<&'static CatchupProgress>,
+) {
     let repl_mgr = match &state.replication_manager {
         Some(r) => Arc::clone(r),
         None => return,
@@ -80,58 +138,295 @@ async fn catchup_all_tenants(state: &AppState, log_prefix: &str) {
         }
     };
 
-    for entry in entries.flatten() {
-        if !entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
-            continue;
-        }
-        let tenant_id = entry.file_name().to_string_lossy().to_string();
-
+    let tenants: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
         // Skip hidden dirs or non-index dirs
-        if tenant_id.starts_with('.') {
-            continue;
-        }
+        .filter(|tenant_id| !tenant_id.starts_with('.'))
+        .collect();
 
-        let local_seq = state
-            .manager
-            .get_oplog(&tenant_id)
-            .map(|ol| ol.current_seq())
-            .unwrap_or(0);
+    if let Some(p) = progress {
+        p.set_tenants(tenants.len());
+    }
 
-        match repl_mgr.catch_up_from_peer(&tenant_id, local_seq).await {
-            Ok(ops) if !ops.is_empty() => {
-                tracing::info!(
-                    "[{}] {} missed ops for tenant '{}' (local_seq={})",
-                    log_prefix,
-                    ops.len(),
-                    tenant_id,
-                    local_seq
-                );
-                match apply_ops_to_manager(&state.manager, &tenant_id, &ops).await {
-                    Ok(applied_seq) => tracing::info!(
-                        "[{}] Applied ops up to seq {} for tenant '{}'",
-                        log_prefix,
-                        applied_seq,
-                        tenant_id
-                    ),
-                    Err(e) => tracing::error!(
-                        "[{}] Failed to apply ops for '{}': {}",
-                        log_prefix,
-                        tenant_id,
-                        e
-                    ),
-                }
+    let concurrency = std::env::var("FLAPJACK_CATCHUP_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(4);
+    let permits = Arc::new(tokio::sync::Semaphore::new(concurrency));
+    let mut workers = tokio::task::JoinSet::new();
+
+    for tenant_id in tenants {
+        let permit = match Arc::clone(&permits).acquire_owned().await {
+            Ok(p) => p,
+            Err(_) => break,
+        };
+        let state = Arc::clone(state);
+        let repl_mgr = Arc::clone(&repl_mgr);
+        workers.spawn(async move {
+            catchup_tenant(&state, &repl_mgr, &tenant_id, log_prefix, progress).await;
+            if let Some(p) = progress {
+                p.tenant_done();
             }
-            Ok(_) => {
-                tracing::debug!("[{}] Tenant '{}' is up-to-date", log_prefix, tenant_id);
+            drop(permit);
+        });
+    }
+
+    while let Some(result) = workers.join_next().await {
+        if let Err(e) = result {
+            tracing::error!("[{}] catch-up worker panicked: {}", log_prefix, e);
+        }
+    }
+}
+
+async fn catchup_tenant(
+    state: &AppState,
+    repl_mgr: &ReplicationManager,
+    tenant_id: &str,
+    log_prefix: &str,
+    progress: Option<&CatchupProgress>,
+) {
+    let local_seq = state
+        .manager
+        .get_oplog(tenant_id)
+        .map(|ol| ol.current_seq())
+        .unwrap_or(0);
+
+    match repl_mgr.catch_up_from_peer(tenant_id, local_seq).await {
+        Ok(ops) if !ops.is_empty() => {
+            tracing::info!(
+                "[{}] {} missed ops for tenant '{}' (local_seq={})",
+                log_prefix,
+                ops.len(),
+                tenant_id,
+                local_seq
+            );
+            if let Some(p) = progress {
+                p.ops_fetched(ops.len() as u64);
             }
-            Err(e) => {
-                tracing::debug!(
-                    "[{}] Could not reach peer for '{}': {}",
+            match apply_ops_to_manager(&state.manager, tenant_id, &ops).await {
+                Ok(applied_seq) => tracing::info!(
+                    "[{}] Applied ops up to seq {} for tenant '{}'",
+                    log_prefix,
+                    applied_seq,
+                    tenant_id
+                ),
+                Err(e) => tracing::error!(
+                    "[{}] Failed to apply ops for '{}': {}",
                     log_prefix,
                     tenant_id,
                     e
-                );
+                ),
+            }
+            #[cfg(feature = "vector-search")]
+            if let Some(rebuild) = ops.iter().rev().find(|op| op.op_type == "vectors_rebuilt") {
+                pull_rebuilt_vectors(state, repl_mgr, tenant_id, &rebuild.node_id, log_prefix)
+                    .await;
             }
+            // Counted even on failure so the remaining count drains.
+            if let Some(p) = progress {
+                p.ops_applied(ops.len() as u64);
+            }
+        }
+        Ok(_) => {
+            tracing::debug!("[{}] Tenant '{}' is up-to-date", log_prefix, tenant_id);
         }
+        Err(e) => {
+            tracing::debug!(
+                "[{}] Could not reach peer for '{}': {}",
+                log_prefix,
+                tenant_id,
+                e
+            );
+        }
+    }
+}
+
+/// A peer rebuilt the tenant's HNSW graph while this node was away. Replaying
+/// the individual vector writes would not reproduce that graph, so fetch the
+/// saved files from the node that rebuilt it.
+#[cfg(feature = "vector-search")]
+async fn pull_rebuilt_vectors(
+    state: &AppState,
+    repl_mgr: &ReplicationManager,
+    tenant_id: &str,
+    origin: &str,
+    log_prefix: &str,
+) {
+    let Some(peer) = repl_mgr.peer(origin) else {
+        tracing::warn!(
+            "[{}] '{}' was rebuilt on {}, which is not a peer; keeping local vectors",
+            log_prefix,
+            tenant_id,
+            origin
+        );
+        return;
+    };
+    let bytes = match peer.fetch_vectors(tenant_id).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("[{}] {}", log_prefix, e);
+            return;
+        }
+    };
+    let manager = Arc::clone(&state.manager);
+    let tenant = tenant_id.to_string();
+    let result = tokio::task::spawn_blocking(move || {
+        crate::handlers::internal::install_vectors(&manager, &tenant, &bytes)
+    })
+    .await;
+    match result {
+        Ok(Ok(count)) => tracing::info!(
+            "[{}] Installed rebuilt vector index for '{}' from {} ({} vectors)",
+            log_prefix,
+            tenant_id,
+            origin,
+            count
+        ),
+        Ok(Err(e)) => tracing::error!(
+            "[{}] Failed to install vectors for '{}': {}",
+            log_prefix,
+            tenant_id,
+            e
+        ),
+        Err(e) => tracing::error!("[{}] vector install task failed: {}", log_prefix, e),
+    }
+}
+
+/// Progress of the startup catch-up, reported on `/internal/status` and used
+/// to gate `/health` until the node has caught up with its peers.
+#[derive(Default)]
+pub struct CatchupProgress {
+    running: AtomicBool,
+    finished: AtomicBool,
+    tenants_total: AtomicUsize,
+    tenants_done: AtomicUsize,
+    ops_fetched: AtomicU64,
+    ops_applied: AtomicU64,
+    started_at: Mutex<Option<Instant>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CatchupStatus {
+    /// `idle` (no catch-up this process), `catching_up` or `done`
+    pub state: &'static str,
+    pub tenants_total: usize,
+    pub tenants_done: usize,
+    /// Ops fetched from peers but not yet applied. Tenants whose ops have
+    /// not been fetched yet are not included.
+    pub ops_remaining: u64,
+    pub ops_applied: u64,
+    pub elapsed_secs: u64,
+    /// Estimated seconds until the fetched ops are applied, from the apply
+    /// rate so far. `None` until some ops have been applied.
+    pub eta_secs: Option<u64>,
+}
+
+impl CatchupProgress {
+    pub fn global() -> &'static CatchupProgress {
+        static PROGRESS: OnceLock<CatchupProgress> = OnceLock::new();
+        PROGRESS.get_or_init(CatchupProgress::default)
+    }
+
+    pub fn begin(&self) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        self.finished.store(false, Ordering::SeqCst);
+        self.tenants_total.store(0, Ordering::SeqCst);
+        self.tenants_done.store(0, Ordering::SeqCst);
+        self.ops_fetched.store(0, Ordering::SeqCst);
+        self.ops_applied.store(0, Ordering::SeqCst);
+        *self.started_at.lock().unwrap() = Some(Instant::now());
+    }
+
+    pub fn finish(&self) {
+        self.finished.store(true, Ordering::SeqCst);
+        self.running.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_catching_up(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    fn set_tenants(&self, n: usize) {
+        self.tenants_total.store(n, Ordering::SeqCst);
+    }
+
+    fn tenant_done(&self) {
+        self.tenants_done.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn ops_fetched(&self, n: u64) {
+        self.ops_fetched.fetch_add(n, Ordering::SeqCst);
+    }
+
+    fn ops_applied(&self, n: u64) {
+        self.ops_applied.fetch_add(n, Ordering::SeqCst);
+    }
+
+    pub fn status(&self) -> CatchupStatus {
+        let state = if self.is_catching_up() {
+            "catching_up"
+        } else if self.finished.load(Ordering::SeqCst) {
+            "done"
+        } else {
+            "idle"
+        };
+        let fetched = self.ops_fetched.load(Ordering::SeqCst);
+        let applied = self.ops_applied.load(Ordering::SeqCst);
+        let ops_remaining = fetched.saturating_sub(applied);
+        let elapsed = self
+            .started_at
+            .lock()
+            .unwrap()
+            .map(|t| t.elapsed())
+            .unwrap_or_default();
+        let eta_secs = if applied > 0 && state == "catching_up" {
+            let rate = applied as f64 / elapsed.as_secs_f64().max(0.001);
+            Some((ops_remaining as f64 / rate).ceil() as u64)
+        } else {
+            None
+        };
+        CatchupStatus {
+            state,
+            tenants_total: self.tenants_total.load(Ordering::SeqCst),
+            tenants_done: self.tenants_done.load(Ordering::SeqCst),
+            ops_remaining,
+            ops_applied: applied,
+            elapsed_secs: elapsed.as_secs(),
+            eta_secs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_reports_remaining_ops_and_eta() {
+        let progress = CatchupProgress::default();
+        assert_eq!(progress.status().state, "idle");
+
+        progress.begin();
+        progress.set_tenants(2);
+        progress.ops_fetched(100);
+        progress.ops_applied(40);
+        progress.tenant_done();
+        let status = progress.status();
+        assert_eq!(status.state, "catching_up");
+        assert_eq!(status.tenants_total, 2);
+        assert_eq!(status.tenants_done, 1);
+        assert_eq!(status.ops_remaining, 60);
+        assert!(status.eta_secs.is_some());
+
+        progress.finish();
+        let status = progress.status();
+        assert_eq!(status.state, "done");
+        assert!(!progress.is_catching_up());
+        assert_eq!(status.eta_secs, None);
     }
 }
@@ -0,0 +1,32 @@
+//! Shared fixtures for the crate's unit tests.
+
+use std::sync::Arc;
+
+use tempfile::TempDir;
+
+use crate::handlers::AppState;
+
+/// An `AppState` over an empty index directory in `tmp`, with every optional
+/// subsystem off. Tests that need one switch it on with struct update syntax:
+/// `AppState { metrics_state: Some(..), ..bare_app_state(&tmp) }`.
+pub fn bare_app_state(tmp: &TempDir) -> AppState {
+    AppState {
+        manager: flapjack::IndexManager::new(tmp.path()),
+        key_store: None,
+        replication_manager: None,
+        ssl_manager: None,
+        analytics_engine: None,
+        experiment_store: None,
+        metrics_state: None,
+        usage_counters: Arc::new(dashmap::DashMap::new()),
+        paused_indexes: crate::pause_registry::PausedIndexes::new(),
+        start_time: std::time::Instant::now(),
+        #[cfg(feature = "vector-search")]
+        embedder_store: Arc::new(crate::embedder_store::EmbedderStore::new()),
+    }
+}
+
+/// [`bare_app_state`], shared.
+pub fn app_state(tmp: &TempDir) -> Arc<AppState> {
+    Arc::new(bare_app_state(tmp))
+}
@@ -0,0 +1,200 @@
+//! Background deletion of expired documents.
+//!
+//! Every `FLAPJACK_TTL_SWEEP_SECS` seconds (default 60) the sweeper deletes
+//! the documents whose `_expiresAt` has passed from each loaded index that
+//! holds expiring documents. Searches already leave expired documents out;
+//! the sweep reclaims their space. Each node sweeps its own copy, so the
+//! deletes are not replicated. Indexes whose maintenance mode blocks writes
+//! are skipped.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use flapjack::index::expiry;
+
+use crate::disk_watchdog::DiskWatchdog;
+use crate::handlers::AppState;
+use crate::maintenance::check_writable;
+
+/// Run one sweep; returns the indexes that had documents deleted, with counts.
+pub async fn run_ttl_sweep(state: &AppState) -> Vec<(String, usize)> {
+    let mut swept = Vec::new();
+    if DiskWatchdog::global().is_read_only() {
+        return swept;
+    }
+    let mut tenants = state.manager.loaded_tenant_ids();
+    tenants.sort();
+    for name in tenants {
+        if state.paused_indexes.is_paused(&name)
+            || check_writable(&state.manager.base_path, &name).is_err()
+            || !expiry::has_expiring(&state.manager.base_path.join(&name))
+        {
+            continue;
+        }
+        match state.manager.purge_expired(&name).await {
+            Ok(0) => {}
+            Ok(count) => {
+                tracing::info!("[TTL] {}: deleted {} expired documents", name, count);
+                swept.push((name, count));
+            }
+            Err(e) => tracing::warn!("[TTL] {}: sweep failed: {}", name, e),
+        }
+    }
+    swept
+}
+
+pub fn spawn_ttl_sweeper(state: Arc<AppState>, tick_secs: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(tick_secs.max(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            run_ttl_sweep(&state).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::app_state;
+    use flapjack::index::settings::IndexSettings;
+    use flapjack::types::{Document, FieldValue};
+    use tempfile::TempDir;
+
+    fn doc(id: &str, expires_at: Option<i64>) -> Document {
+        let mut fields: std::collections::HashMap<String, FieldValue> = [(
+            "title".to_string(),
+            FieldValue::Text(format!("sale {}", id)),
+        )]
+        .into_iter()
+        .collect();
+        if let Some(at) = expires_at {
+            fields.insert(
+                expiry::EXPIRES_AT_ATTRIBUTE.to_string(),
+                FieldValue::Integer(at),
+            );
+        }
+        Document {
+            id: id.to_string(),
+            fields,
+        }
+    }
+
+    #[tokio::test]
+    async fn expired_documents_are_hidden_then_swept() {
+        let tmp = TempDir::new().unwrap();
+        let state = app_state(&tmp);
+        state.manager.create_tenant("sales").unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let docs = vec![
+            doc("past", Some(now - 10)),
+            doc("future", Some(now + 3600)),
+            doc("forever", None),
+        ];
+        state
+            .manager
+            .add_documents_sync("sales", docs)
+            .await
+            .unwrap();
+
+        let hits = |result: flapjack::types::SearchResult| {
+            let mut ids: Vec<String> = result
+                .documents
+                .into_iter()
+                .map(|d| d.document.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        let result = state
+            .manager
+            .search("sales", "sale", None, None, 10)
+            .unwrap();
+        assert_eq!(hits(result), vec!["forever", "future"]);
+        let expired = expiry::expired_filter(now);
+        let result = state
+            .manager
+            .search("sales", "", Some(&expired), None, 10)
+            .unwrap();
+        assert_eq!(hits(result), vec!["past"]);
+
+        assert_eq!(run_ttl_sweep(&state).await, vec![("sales".to_string(), 1)]);
+        assert!(state
+            .manager
+            .get_document("sales", "past")
+            .unwrap()
+            .is_none());
+        assert!(state
+            .manager
+            .get_document("sales", "future")
+            .unwrap()
+            .is_some());
+        assert!(run_ttl_sweep(&state).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn indexes_in_read_only_maintenance_are_not_swept() {
+        use crate::maintenance::{MaintenanceMode, MaintenanceRegistry};
+
+        let tmp = TempDir::new().unwrap();
+        let state = app_state(&tmp);
+        state.manager.create_tenant("sales").unwrap();
+        let now = chrono::Utc::now().timestamp();
+        state
+            .manager
+            .add_documents_sync("sales", vec![doc("past", Some(now - 10))])
+            .await
+            .unwrap();
+        let registry = MaintenanceRegistry::for_data_dir(tmp.path()).unwrap();
+        registry
+            .set("sales", MaintenanceMode::ReadOnly, None, 0)
+            .unwrap();
+
+        assert!(run_ttl_sweep(&state).await.is_empty());
+        registry
+            .set("sales", MaintenanceMode::ReadWrite, None, 0)
+            .unwrap();
+        assert_eq!(run_ttl_sweep(&state).await, vec![("sales".to_string(), 1)]);
+    }
+
+    #[tokio::test]
+    async fn default_ttl_stamps_written_documents() {
+        let tmp = TempDir::new().unwrap();
+        let state = app_state(&tmp);
+        state.manager.create_tenant("events").unwrap();
+        IndexSettings {
+            default_ttl: Some(60),
+            ..Default::default()
+        }
+        .save(state.manager.base_path.join("events").join("settings.json"))
+        .unwrap();
+        let before = chrono::Utc::now().timestamp();
+        let docs = vec![doc("talk", None), doc("keynote", Some(before + 5))];
+        state
+            .manager
+            .add_documents_sync("events", docs)
+            .await
+            .unwrap();
+
+        let talk = state
+            .manager
+            .get_document("events", "talk")
+            .unwrap()
+            .unwrap();
+        match talk.fields.get(expiry::EXPIRES_AT_ATTRIBUTE) {
+            Some(FieldValue::Integer(at)) => assert!((before + 60..=before + 62).contains(at)),
+            other => panic!("unexpected _expiresAt {:?}", other),
+        }
+        let keynote = state
+            .manager
+            .get_document("events", "keynote")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            keynote.fields.get(expiry::EXPIRES_AT_ATTRIBUTE),
+            Some(&FieldValue::Integer(before + 5))
+        );
+    }
+}
@@ -0,0 +1,367 @@
+//! Index warm-up: replay an index's most frequent recent queries so the
+//! first real users after a restart or settings change don't pay for cold
+//! caches.
+//!
+//! Warm-up runs after startup (once WAL replay and peer catch-up are done),
+//! after a settings change, and on `POST /1/indexes/:indexName/warmup`, which
+//! failover tooling can call before shifting traffic to a node. It replays
+//! the top `FLAPJACK_WARMUP_QUERIES` (default 100, `0` disables) queries of
+//! the last `FLAPJACK_WARMUP_DAYS` (default 7) days from analytics, one at a
+//! time, for at most `FLAPJACK_WARMUP_TIMEOUT_SECS` (default 60). Indexes
+//! without recent searches are skipped. Replayed queries are not recorded in
+//! analytics, experiments or usage. `GET /1/indexes/:indexName/warmup`
+//! reports the latest run.
+
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use axum::extract::State;
+
+use crate::dto::SearchRequest;
+use crate::handlers::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WarmupConfig {
+    pub queries: usize,
+    pub days: u32,
+    pub timeout: Duration,
+}
+
+impl Default for WarmupConfig {
+    fn default() -> Self {
+        WarmupConfig {
+            queries: 100,
+            days: 7,
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+impl WarmupConfig {
+    pub fn from_env() -> Self {
+        let defaults = WarmupConfig::default();
+        WarmupConfig {
+            queries: std::env::var("FLAPJACK_WARMUP_QUERIES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.queries),
+            days: std::env::var("FLAPJACK_WARMUP_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.days),
+            timeout: std::env::var("FLAPJACK_WARMUP_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.timeout),
+        }
+    }
+
+    pub fn global() -> &'static WarmupConfig {
+        static CONFIG: OnceLock<WarmupConfig> = OnceLock::new();
+        CONFIG.get_or_init(WarmupConfig::from_env)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WarmupTrigger {
+    Startup,
+    Settings,
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WarmupState {
+    Running,
+    Done,
+    /// No recent searches to replay.
+    Skipped,
+    /// Stopped at the time budget.
+    TimedOut,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WarmupStatus {
+    pub state: WarmupState,
+    pub trigger: WarmupTrigger,
+    pub queries_total: usize,
+    pub queries_run: usize,
+    pub queries_failed: usize,
+    pub started_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Latest warm-up of each index on this node.
+#[derive(Default)]
+pub struct WarmupRegistry {
+    statuses: DashMap<String, WarmupStatus>,
+}
+
+impl WarmupRegistry {
+    pub fn global() -> &'static WarmupRegistry {
+        static REGISTRY: OnceLock<WarmupRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(WarmupRegistry::default)
+    }
+
+    pub fn get(&self, index_name: &str) -> Option<WarmupStatus> {
+        self.statuses.get(index_name).map(|s| s.clone())
+    }
+
+    /// Record the start of a warm-up; false if one is already running.
+    fn begin(&self, index_name: &str, trigger: WarmupTrigger) -> bool {
+        let mut running = false;
+        self.statuses
+            .entry(index_name.to_string())
+            .and_modify(|s| {
+                if s.state == WarmupState::Running {
+                    running = true;
+                } else {
+                    *s = WarmupStatus::started(trigger);
+                }
+            })
+            .or_insert_with(|| WarmupStatus::started(trigger));
+        !running
+    }
+
+    fn update(&self, index_name: &str, f: impl FnOnce(&mut WarmupStatus)) {
+        if let Some(mut status) = self.statuses.get_mut(index_name) {
+            f(&mut status);
+        }
+    }
+}
+
+impl WarmupStatus {
+    fn started(trigger: WarmupTrigger) -> Self {
+        WarmupStatus {
+            state: WarmupState::Running,
+            trigger,
+            queries_total: 0,
+            queries_run: 0,
+            queries_failed: 0,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            finished_at: None,
+            duration_ms: None,
+            message: None,
+        }
+    }
+}
+
+/// The index's most frequent queries over the configured window.
+async fn top_queries(
+    state: &AppState,
+    index_name: &str,
+    config: &WarmupConfig,
+) -> Result<Vec<String>, String> {
+    let Some(engine) = state.analytics_engine.as_ref() else {
+        return Ok(Vec::new());
+    };
+    let now = chrono::Utc::now();
+    let start = (now - chrono::Duration::days(config.days as i64))
+        .format("%Y-%m-%d")
+        .to_string();
+    let end = now.format("%Y-%m-%d").to_string();
+    let top = engine
+        .top_searches(index_name, &start, &end, config.queries, false, None, None)
+        .await?;
+    Ok(top["searches"]
+        .as_array()
+        .map(|rows| {
+            rows.iter()
+                .filter_map(|row| row["search"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Warm `index_name` up now, unless warm-up is disabled or already running.
+pub async fn warm_index(
+    state: Arc<AppState>,
+    index_name: String,
+    trigger: WarmupTrigger,
+    config: WarmupConfig,
+) {
+    let registry = WarmupRegistry::global();
+    if config.queries == 0 || !registry.begin(&index_name, trigger) {
+        return;
+    }
+    let start = Instant::now();
+    let finish = |outcome: WarmupState, message: Option<String>| {
+        registry.update(&index_name, |s| {
+            s.state = outcome;
+            s.message = message;
+            s.finished_at = Some(chrono::Utc::now().to_rfc3339());
+            s.duration_ms = Some(start.elapsed().as_millis() as u64);
+        });
+    };
+
+    let queries = match top_queries(&state, &index_name, &config).await {
+        Ok(queries) if queries.is_empty() => {
+            return finish(WarmupState::Skipped, Some("no recent searches".into()));
+        }
+        Ok(queries) => queries,
+        Err(e) => return finish(WarmupState::Failed, Some(e)),
+    };
+    registry.update(&index_name, |s| s.queries_total = queries.len());
+
+    for query in queries {
+        if start.elapsed() >= config.timeout {
+            tracing::info!("[WARMUP] {}: stopped at the time budget", index_name);
+            return finish(WarmupState::TimedOut, None);
+        }
+        let req = SearchRequest {
+            query,
+            analytics: Some(false),
+            internal: true,
+            ..Default::default()
+        };
+        let ok =
+            crate::handlers::search::search_single(State(state.clone()), index_name.clone(), req)
+                .await
+                .is_ok();
+        registry.update(&index_name, |s| {
+            s.queries_run += 1;
+            if !ok {
+                s.queries_failed += 1;
+            }
+        });
+    }
+    tracing::info!(
+        "[WARMUP] {}: replayed top queries in {}ms",
+        index_name,
+        start.elapsed().as_millis()
+    );
+    finish(WarmupState::Done, None);
+}
+
+/// Warm `index_name` up in the background.
+pub fn spawn_warmup(state: Arc<AppState>, index_name: String, trigger: WarmupTrigger) {
+    let config = *WarmupConfig::global();
+    if config.queries == 0 {
+        return;
+    }
+    tokio::spawn(warm_index(state, index_name, trigger, config));
+}
+
+/// Warm up every local index, one at a time.
+pub async fn warm_all_indexes(state: Arc<AppState>) {
+    let config = *WarmupConfig::global();
+    if config.queries == 0 {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(&state.manager.base_path) else {
+        return;
+    };
+    let mut indexes: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+    indexes.sort();
+    for index_name in indexes {
+        warm_index(state.clone(), index_name, WarmupTrigger::Startup, config).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::bare_app_state;
+    use flapjack::types::{Document, FieldValue};
+    use tempfile::TempDir;
+
+    fn make_state(tmp: &TempDir) -> Arc<AppState> {
+        let config = flapjack::analytics::AnalyticsConfig {
+            enabled: true,
+            data_dir: tmp.path().join("analytics"),
+            flush_interval_secs: 3600,
+            flush_size: 100_000,
+            retention_days: 90,
+            pii: Default::default(),
+        };
+        flapjack::fixtures::analytics::seed_analytics(&config, "warmup_seeded", 1).unwrap();
+        Arc::new(AppState {
+            analytics_engine: Some(Arc::new(flapjack::analytics::AnalyticsQueryEngine::new(
+                config,
+            ))),
+            ..bare_app_state(tmp)
+        })
+    }
+
+    #[test]
+    fn only_one_warmup_runs_per_index() {
+        let registry = WarmupRegistry::default();
+        assert!(registry.begin("products", WarmupTrigger::Startup));
+        assert!(!registry.begin("products", WarmupTrigger::Settings));
+        assert!(registry.begin("articles", WarmupTrigger::Settings));
+        registry.update("products", |s| s.state = WarmupState::Done);
+        assert!(registry.begin("products", WarmupTrigger::Manual));
+        assert_eq!(
+            registry.get("products").unwrap().trigger,
+            WarmupTrigger::Manual
+        );
+    }
+
+    async fn make_index(state: &AppState, name: &str) {
+        state.manager.create_tenant(name).unwrap();
+        let doc = Document {
+            id: "1".to_string(),
+            fields: [("title".to_string(), FieldValue::Text("shoe".into()))]
+                .into_iter()
+                .collect(),
+        };
+        state
+            .manager
+            .add_documents_sync(name, vec![doc])
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn replays_top_queries_without_counting_usage() {
+        let tmp = TempDir::new().unwrap();
+        let state = make_state(&tmp);
+        make_index(&state, "warmup_seeded").await;
+        make_index(&state, "warmup_idle").await;
+        let config = WarmupConfig {
+            queries: 5,
+            ..Default::default()
+        };
+
+        warm_index(
+            state.clone(),
+            "warmup_seeded".to_string(),
+            WarmupTrigger::Manual,
+            config,
+        )
+        .await;
+        let status = WarmupRegistry::global().get("warmup_seeded").unwrap();
+        assert_eq!(status.state, WarmupState::Done);
+        assert_eq!(status.queries_total, 5);
+        assert_eq!(status.queries_run, 5);
+        assert_eq!(status.queries_failed, 0);
+        assert!(!state.usage_counters.contains_key("warmup_seeded"));
+
+        warm_index(
+            state,
+            "warmup_idle".to_string(),
+            WarmupTrigger::Manual,
+            config,
+        )
+        .await;
+        let status = WarmupRegistry::global().get("warmup_idle").unwrap();
+        assert_eq!(status.state, WarmupState::Skipped);
+        assert!(status.duration_ms.is_some());
+    }
+}
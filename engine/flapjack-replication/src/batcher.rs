@@ -0,0 +1,196 @@
+use super::peer::PeerClient;
+use super::protocol::PROTOCOL_VERSION;
+use super::types::ReplicateOpsRequest;
+use dashmap::DashMap;
+use flapjack::index::oplog::OpLogEntry;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+type PeerCursors = Arc<DashMap<String, DashMap<String, u64>>>;
+
+/// Coalesces small op writes for one peer into fewer, larger requests.
+///
+/// Ops are queued without blocking the writer. A worker (started on first
+/// use) collects them for up to `window`, or until `max_ops` are pending,
+/// merges them per tenant and ships each tenant's ops as one request.
+pub struct OpBatcher {
+    peer: Arc<PeerClient>,
+    peer_cursors: PeerCursors,
+    window: Duration,
+    max_ops: usize,
+    tx: OnceLock<mpsc::UnboundedSender<(String, Vec<OpLogEntry>)>>,
+}
+
+impl OpBatcher {
+    pub fn new(
+        peer: Arc<PeerClient>,
+        peer_cursors: PeerCursors,
+        window: Duration,
+        max_ops: usize,
+    ) -> Self {
+        Self {
+            peer,
+            peer_cursors,
+            window,
+            max_ops: max_ops.max(1),
+            tx: OnceLock::new(),
+        }
+    }
+
+    /// Queue ops for this peer. Must be called from within a Tokio runtime.
+    pub fn enqueue(&self, tenant_id: String, ops: Vec<OpLogEntry>) {
+        let tx = self.tx.get_or_init(|| {
+            let (tx, rx) = mpsc::unbounded_channel();
+            tokio::spawn(run(
+                Arc::clone(&self.peer),
+                Arc::clone(&self.peer_cursors),
+                rx,
+                self.window,
+                self.max_ops,
+            ));
+            tx
+        });
+        if tx.send((tenant_id, ops)).is_err() {
+            tracing::warn!(
+                "[REPL] batch worker for peer {} stopped, ops dropped",
+                self.peer.peer_id()
+            );
+        }
+    }
+}
+
+/// Append `ops` to the pending batch for `tenant_id`, keeping tenants in
+/// first-seen order and ops in arrival order.
+fn coalesce(pending: &mut Vec<(String, Vec<OpLogEntry>)>, tenant_id: String, ops: Vec<OpLogEntry>) {
+    match pending.iter_mut().find(|(t, _)| *t == tenant_id) {
+        Some((_, existing)) => existing.extend(ops),
+        None => pending.push((tenant_id, ops)),
+    }
+}
+
+async fn run(
+    peer: Arc<PeerClient>,
+    peer_cursors: PeerCursors,
+    mut rx: mpsc::UnboundedReceiver<(String, Vec<OpLogEntry>)>,
+    window: Duration,
+    max_ops: usize,
+) {
+    while let Some((tenant_id, ops)) = rx.recv().await {
+        let mut queued = ops.len();
+        let mut pending = Vec::new();
+        coalesce(&mut pending, tenant_id, ops);
+
+        let deadline = tokio::time::Instant::now() + window;
+        while queued < max_ops {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some((tenant_id, ops))) => {
+                    queued += ops.len();
+                    coalesce(&mut pending, tenant_id, ops);
+                }
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        for (tenant_id, ops) in pending {
+            for chunk in ops.chunks(max_ops) {
+                tokio::spawn(ship(
+                    Arc::clone(&peer),
+                    Arc::clone(&peer_cursors),
+                    tenant_id.clone(),
+                    chunk.to_vec(),
+                ));
+            }
+        }
+    }
+}
+
+/// Send one batch, retrying once after 2s, and record the peer's ack.
+async fn ship(
+    peer: Arc<PeerClient>,
+    peer_cursors: PeerCursors,
+    tenant_id: String,
+    ops: Vec<OpLogEntry>,
+) {
+    let req = ReplicateOpsRequest {
+        tenant_id: tenant_id.clone(),
+        ops,
+        protocol_version: PROTOCOL_VERSION,
+    };
+
+    let result = match peer.replicate_ops(req.clone()).await {
+        Ok(resp) => Ok(resp),
+        Err(e) => {
+            tracing::warn!(
+                "[REPL {}] peer {} failed (will retry in 2s): {}",
+                tenant_id,
+                peer.peer_id(),
+                e
+            );
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            peer.replicate_ops(req).await
+        }
+    };
+
+    match result {
+        Ok(resp) => {
+            let tenant_cursors = peer_cursors.entry(tenant_id.clone()).or_default();
+            tenant_cursors.insert(peer.peer_id().to_string(), resp.acked_seq);
+            tracing::info!(
+                "[REPL {}] peer {} acked seq {}",
+                tenant_id,
+                peer.peer_id(),
+                resp.acked_seq
+            );
+        }
+        Err(e) => {
+            tracing::warn!(
+                "[REPL {}] peer {} failed after retry, ops dropped: {}",
+                tenant_id,
+                peer.peer_id(),
+                e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(seq: u64, tenant: &str) -> OpLogEntry {
+        OpLogEntry {
+            seq,
+            timestamp_ms: seq,
+            node_id: "node-a".to_string(),
+            tenant_id: tenant.to_string(),
+            op_type: "upsert".to_string(),
+            payload: serde_json::json!({}),
+        }
+    }
+
+    #[test]
+    fn test_coalesce_merges_per_tenant_in_order() {
+        let mut pending = Vec::new();
+        coalesce(&mut pending, "a".into(), vec![op(1, "a")]);
+        coalesce(&mut pending, "b".into(), vec![op(2, "b")]);
+        coalesce(&mut pending, "a".into(), vec![op(3, "a"), op(4, "a")]);
+
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].0, "a");
+        let seqs: Vec<u64> = pending[0].1.iter().map(|o| o.seq).collect();
+        assert_eq!(seqs, vec![1, 3, 4]);
+        assert_eq!(pending[1].0, "b");
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_to_unreachable_peer_does_not_block() {
+        let peer = Arc::new(PeerClient::new(
+            "node-b".to_string(),
+            "http://127.0.0.1:1".to_string(),
+        ));
+        let batcher = OpBatcher::new(peer, Arc::new(DashMap::new()), Duration::from_millis(1), 10);
+        batcher.enqueue("t".into(), vec![op(1, "t")]);
+        batcher.enqueue("t".into(), vec![op(2, "t")]);
+    }
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NodeConfig {
@@ -92,6 +93,65 @@ impl NodeConfig {
     }
 }
 
+/// Connection and batching settings for the peer HTTP client.
+///
+/// - `FLAPJACK_REPLICATION_HTTP2` (default `false`): talk HTTP/2 to `http://`
+///   peers (prior knowledge, one multiplexed connection). Only used with
+///   peers that negotiated protocol 3 or later; older nodes get HTTP/1.
+/// - `FLAPJACK_REPLICATION_MAX_INFLIGHT` (default 4): concurrent op batches
+///   in flight per peer.
+/// - `FLAPJACK_REPLICATION_BATCH_MS` (default 5): how long ops for a peer are
+///   collected before shipping; `0` ships whatever is already queued.
+/// - `FLAPJACK_REPLICATION_MAX_BATCH_OPS` (default 500): ops per request.
+/// - `FLAPJACK_REPLICATION_COMPRESSION` (default `false`): gzip op batches and
+///   accept gzip responses. Batches to peers below protocol 3, which can't
+///   decompress request bodies, are sent uncompressed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicationClientConfig {
+    pub http2: bool,
+    pub max_inflight_per_peer: usize,
+    pub batch_window: Duration,
+    pub max_batch_ops: usize,
+    pub compression: bool,
+}
+
+impl Default for ReplicationClientConfig {
+    fn default() -> Self {
+        Self {
+            http2: false,
+            max_inflight_per_peer: 4,
+            batch_window: Duration::from_millis(5),
+            max_batch_ops: 500,
+            compression: false,
+        }
+    }
+}
+
+impl ReplicationClientConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let flag = |var: &str, default: bool| {
+            std::env::var(var)
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(default)
+        };
+        let number = |var: &str| std::env::var(var).ok().and_then(|v| v.parse::<u64>().ok());
+        Self {
+            http2: flag("FLAPJACK_REPLICATION_HTTP2", defaults.http2),
+            max_inflight_per_peer: number("FLAPJACK_REPLICATION_MAX_INFLIGHT")
+                .map(|n| (n as usize).max(1))
+                .unwrap_or(defaults.max_inflight_per_peer),
+            batch_window: number("FLAPJACK_REPLICATION_BATCH_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.batch_window),
+            max_batch_ops: number("FLAPJACK_REPLICATION_MAX_BATCH_OPS")
+                .map(|n| (n as usize).max(1))
+                .unwrap_or(defaults.max_batch_ops),
+            compression: flag("FLAPJACK_REPLICATION_COMPRESSION", defaults.compression),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -248,4 +308,24 @@ mod tests {
         assert_eq!(config.peers.len(), 1);
         assert_eq!(config.peers[0].node_id, "peer-json");
     }
+
+    #[test]
+    fn test_client_config_from_env() {
+        let _guard = ENV_MUTEX.lock().unwrap();
+        std::env::set_var("FLAPJACK_REPLICATION_HTTP2", "true");
+        std::env::set_var("FLAPJACK_REPLICATION_MAX_INFLIGHT", "0");
+        std::env::set_var("FLAPJACK_REPLICATION_BATCH_MS", "20");
+
+        let config = ReplicationClientConfig::from_env();
+
+        std::env::remove_var("FLAPJACK_REPLICATION_HTTP2");
+        std::env::remove_var("FLAPJACK_REPLICATION_MAX_INFLIGHT");
+        std::env::remove_var("FLAPJACK_REPLICATION_BATCH_MS");
+
+        assert!(config.http2);
+        assert_eq!(config.max_inflight_per_peer, 1);
+        assert_eq!(config.batch_window, Duration::from_millis(20));
+        assert_eq!(config.max_batch_ops, 500);
+        assert!(!config.compression);
+    }
 }
@@ -1,9 +1,12 @@
+pub mod batcher;
 pub mod circuit_breaker;
 pub mod config;
 pub mod manager;
 pub mod peer;
+pub mod protocol;
 pub mod task;
 pub mod types;
+pub mod user_mapping;
 
 use once_cell::sync::OnceCell;
 use std::sync::Arc;
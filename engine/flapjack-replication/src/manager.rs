@@ -1,9 +1,13 @@
+use super::batcher::OpBatcher;
 use super::circuit_breaker::CircuitState;
-use super::config::NodeConfig;
+use super::config::{NodeConfig, ReplicationClientConfig};
 use super::peer::PeerClient;
-use super::types::{GetOpsQuery, PeerHealthStatus, ReplicateOpsRequest};
+use super::protocol::PROTOCOL_VERSION;
+use super::types::{GetOpsQuery, PeerHealthStatus};
+use super::user_mapping::{self, MappingSnapshot};
 use dashmap::DashMap;
 use flapjack::index::oplog::OpLogEntry;
+use flapjack::query_suggestions::QsConfig;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::task::JoinHandle;
@@ -16,6 +20,8 @@ pub struct ReplicationManager {
     /// Outer map: tenant_id -> inner map
     /// Inner map: peer_id -> last_acked_seq
     peer_cursors: Arc<DashMap<String, DashMap<String, u64>>>,
+    /// One op batcher per peer, in the same order as `peers`
+    batchers: Vec<OpBatcher>,
     /// Handle to the background health probe task (if running)
     #[allow(dead_code)]
     health_probe_handle: Option<JoinHandle<()>>,
@@ -23,21 +29,42 @@ pub struct ReplicationManager {
 
 impl ReplicationManager {
     pub fn new(node_config: NodeConfig) -> Arc<Self> {
+        Self::with_client_config(node_config, ReplicationClientConfig::from_env())
+    }
+
+    pub fn with_client_config(
+        node_config: NodeConfig,
+        client_config: ReplicationClientConfig,
+    ) -> Arc<Self> {
         let peers: Vec<Arc<PeerClient>> = node_config
             .peers
             .iter()
             .map(|peer_config| {
-                Arc::new(PeerClient::new(
+                Arc::new(PeerClient::with_config(
                     peer_config.node_id.clone(),
                     peer_config.addr.clone(),
+                    &client_config,
                 ))
             })
             .collect();
+        let peer_cursors = Arc::new(DashMap::new());
+        let batchers = peers
+            .iter()
+            .map(|peer| {
+                OpBatcher::new(
+                    Arc::clone(peer),
+                    Arc::clone(&peer_cursors),
+                    client_config.batch_window,
+                    client_config.max_batch_ops,
+                )
+            })
+            .collect();
 
         Arc::new(Self {
             node_config,
             peers,
-            peer_cursors: Arc::new(DashMap::new()),
+            peer_cursors,
+            batchers,
             health_probe_handle: None,
         })
     }
@@ -50,6 +77,135 @@ impl ReplicationManager {
         self.peers.len()
     }
 
+    /// Cluster names for multi-cluster user mapping: this node followed by
+    /// its peers.
+    pub fn cluster_names(&self) -> Vec<String> {
+        std::iter::once(self.node_id().to_string())
+            .chain(self.peers.iter().map(|p| p.peer_id().to_string()))
+            .collect()
+    }
+
+    pub fn peer(&self, node_id: &str) -> Option<Arc<PeerClient>> {
+        self.peers.iter().find(|p| p.peer_id() == node_id).cloned()
+    }
+
+    /// Push the user mapping to every available peer (fire-and-forget).
+    pub fn broadcast_user_mapping(&self, snapshot: MappingSnapshot) {
+        let snapshot = Arc::new(snapshot);
+        for peer in self.peers.iter().filter(|p| p.is_available()) {
+            let peer = Arc::clone(peer);
+            let snapshot = Arc::clone(&snapshot);
+            tokio::spawn(async move {
+                if let Err(e) = peer.push_user_mapping(&snapshot).await {
+                    tracing::warn!("[MCM] {}", e);
+                }
+            });
+        }
+    }
+
+    /// Node that runs cluster-wide jobs such as Query Suggestions builds:
+    /// the lowest node ID among this node and its available peers. Every
+    /// node computes the same answer while they agree on peer health, and
+    /// the job moves to another node when the current one goes down.
+    pub fn primary_node_id(&self) -> String {
+        self.peers
+            .iter()
+            .filter(|p| p.is_available())
+            .map(|p| p.peer_id())
+            .chain(std::iter::once(self.node_id()))
+            .min()
+            .unwrap_or_else(|| self.node_id())
+            .to_string()
+    }
+
+    pub fn is_primary(&self) -> bool {
+        self.primary_node_id() == self.node_id()
+    }
+
+    /// Push a Query Suggestions configuration to every available peer
+    /// (fire-and-forget).
+    pub fn broadcast_qs_config(&self, config: QsConfig) {
+        let config = Arc::new(config);
+        for peer in self.peers.iter().filter(|p| p.is_available()) {
+            let peer = Arc::clone(peer);
+            let config = Arc::clone(&config);
+            tokio::spawn(async move {
+                if let Err(e) = peer.push_qs_config(&config).await {
+                    tracing::warn!("[QS] {}", e);
+                }
+            });
+        }
+    }
+
+    /// Delete a Query Suggestions configuration on every available peer
+    /// (fire-and-forget).
+    pub fn broadcast_qs_config_deletion(&self, index_name: &str) {
+        for peer in self.peers.iter().filter(|p| p.is_available()) {
+            let peer = Arc::clone(peer);
+            let index_name = index_name.to_string();
+            tokio::spawn(async move {
+                if let Err(e) = peer.delete_qs_config(&index_name).await {
+                    tracing::warn!("[QS] {}", e);
+                }
+            });
+        }
+    }
+
+    /// Push a schedule to every available peer (fire-and-forget), so any
+    /// node can take over running it.
+    pub fn broadcast_schedule(&self, schedule: serde_json::Value) {
+        let schedule = Arc::new(schedule);
+        for peer in self.peers.iter().filter(|p| p.is_available()) {
+            let peer = Arc::clone(peer);
+            let schedule = Arc::clone(&schedule);
+            tokio::spawn(async move {
+                if let Err(e) = peer.push_schedule(&schedule).await {
+                    tracing::warn!("[SCHEDULER] {}", e);
+                }
+            });
+        }
+    }
+
+    /// Delete a schedule on every available peer (fire-and-forget).
+    pub fn broadcast_schedule_deletion(&self, id: &str) {
+        for peer in self.peers.iter().filter(|p| p.is_available()) {
+            let peer = Arc::clone(peer);
+            let id = id.to_string();
+            tokio::spawn(async move {
+                if let Err(e) = peer.delete_schedule(&id).await {
+                    tracing::warn!("[SCHEDULER] {}", e);
+                }
+            });
+        }
+    }
+
+    /// Ship a built suggestions index to every available peer. Returns the
+    /// peers that failed to take it.
+    pub async fn ship_suggestions(&self, index_name: &str, snapshot: Vec<u8>) -> Vec<String> {
+        let mut failed = Vec::new();
+        for peer in self.peers.iter().filter(|p| p.is_available()) {
+            if let Err(e) = peer.import_suggestions(index_name, snapshot.clone()).await {
+                tracing::warn!("[QS] {}", e);
+                failed.push(peer.peer_id().to_string());
+            }
+        }
+        failed
+    }
+
+    /// Ship a tenant's rebuilt vector index to every available peer. Returns
+    /// the peers that failed to take it; they pull the files when they next
+    /// catch up past the rebuild.
+    pub async fn ship_vectors(&self, tenant_id: &str, snapshot: Vec<u8>) -> Vec<String> {
+        let mut failed = Vec::new();
+        for peer in self.peers.iter().filter(|p| p.is_available()) {
+            if let Err(e) = peer.import_vectors(tenant_id, snapshot.clone()).await {
+                tracing::warn!("[VECTORS {}] {}", tenant_id, e);
+                failed.push(peer.peer_id().to_string());
+            }
+        }
+        failed
+    }
+
     /// Check if a specific peer is available (circuit breaker not tripped).
     pub fn is_peer_available(&self, node_id: &str) -> bool {
         self.peers
@@ -69,15 +225,18 @@ impl ReplicationManager {
     }
 
     /// Replicate operations to all available peers (fire-and-forget).
-    /// Skips peers with tripped circuit breakers.
+    /// Skips peers with tripped circuit breakers. Ops are batched per peer
+    /// and shipped in the background.
     pub async fn replicate_ops(&self, tenant_id: &str, ops: Vec<OpLogEntry>) {
         if ops.is_empty() {
             return;
         }
+        // Per-user tenants stay on the node the user is pinned to.
+        if user_mapping::is_user_tenant(tenant_id) {
+            return;
+        }
 
-        let tenant_id = tenant_id.to_string();
-
-        for peer in &self.peers {
+        for (peer, batcher) in self.peers.iter().zip(&self.batchers) {
             if !peer.is_available() {
                 tracing::debug!(
                     "[REPL {}] skipping peer {} (circuit breaker open)",
@@ -86,56 +245,7 @@ impl ReplicationManager {
                 );
                 continue;
             }
-
-            let peer = Arc::clone(peer);
-            let tenant_id = tenant_id.clone();
-            let ops = ops.clone();
-            let peer_cursors = Arc::clone(&self.peer_cursors);
-
-            // Fire-and-forget: spawn task and don't await
-            tokio::spawn(async move {
-                let req = ReplicateOpsRequest {
-                    tenant_id: tenant_id.clone(),
-                    ops: ops.clone(),
-                };
-
-                // Attempt replication, retry once after 2s on failure
-                let result = peer.replicate_ops(req.clone()).await;
-                let result = match result {
-                    Ok(resp) => Ok(resp),
-                    Err(e) => {
-                        tracing::warn!(
-                            "[REPL {}] peer {} failed (will retry in 2s): {}",
-                            tenant_id,
-                            peer.peer_id(),
-                            e
-                        );
-                        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-                        peer.replicate_ops(req).await
-                    }
-                };
-
-                match result {
-                    Ok(resp) => {
-                        let tenant_cursors = peer_cursors.entry(tenant_id.clone()).or_default();
-                        tenant_cursors.insert(peer.peer_id().to_string(), resp.acked_seq);
-                        tracing::info!(
-                            "[REPL {}] peer {} acked seq {}",
-                            tenant_id,
-                            peer.peer_id(),
-                            resp.acked_seq
-                        );
-                    }
-                    Err(e) => {
-                        tracing::warn!(
-                            "[REPL {}] peer {} failed after retry, ops dropped: {}",
-                            tenant_id,
-                            peer.peer_id(),
-                            e
-                        );
-                    }
-                }
-            });
+            batcher.enqueue(tenant_id.to_string(), ops.clone());
         }
     }
 
@@ -149,10 +259,14 @@ impl ReplicationManager {
         if self.peers.is_empty() {
             return Err("No peers available for catch-up".to_string());
         }
+        if user_mapping::is_user_tenant(tenant_id) {
+            return Ok(Vec::new());
+        }
 
         let query = GetOpsQuery {
             tenant_id: tenant_id.to_string(),
             since_seq: local_seq,
+            protocol_version: PROTOCOL_VERSION,
         };
 
         let mut last_error = String::from("All peers have tripped circuit breakers");
@@ -316,6 +430,35 @@ mod tests {
         assert_eq!(manager.available_peers().len(), 2);
     }
 
+    #[test]
+    fn test_primary_is_lowest_available_node_id() {
+        let config = NodeConfig {
+            node_id: "node-b".to_string(),
+            bind_addr: "0.0.0.0:7700".to_string(),
+            peers: vec![
+                PeerConfig {
+                    node_id: "node-a".to_string(),
+                    addr: "http://node-a:7700".to_string(),
+                },
+                PeerConfig {
+                    node_id: "node-c".to_string(),
+                    addr: "http://node-c:7700".to_string(),
+                },
+            ],
+        };
+
+        let manager = ReplicationManager::new(config);
+        assert_eq!(manager.primary_node_id(), "node-a");
+        assert!(!manager.is_primary());
+
+        let peer = manager.peer("node-a").unwrap();
+        for _ in 0..3 {
+            peer.circuit_breaker().record_failure();
+        }
+        assert_eq!(manager.primary_node_id(), "node-b");
+        assert!(manager.is_primary());
+    }
+
     #[test]
     fn test_peer_statuses_initially_never_contacted() {
         let config = NodeConfig {
@@ -404,6 +547,23 @@ mod tests {
         // No panic = success
     }
 
+    #[tokio::test]
+    async fn test_user_tenants_are_not_replicated() {
+        let config = NodeConfig {
+            node_id: "node-a".to_string(),
+            bind_addr: "0.0.0.0:7700".to_string(),
+            peers: vec![PeerConfig {
+                node_id: "node-b".to_string(),
+                addr: "http://127.0.0.1:1".to_string(),
+            }],
+        };
+
+        let manager = ReplicationManager::new(config);
+        assert_eq!(manager.cluster_names(), vec!["node-a", "node-b"]);
+        let ops = manager.catch_up_from_peer("products@u1", 0).await.unwrap();
+        assert!(ops.is_empty());
+    }
+
     #[tokio::test]
     async fn test_catch_up_from_peer_no_peers_returns_error() {
         let config = NodeConfig {
@@ -1,38 +1,63 @@
 use super::circuit_breaker::CircuitBreaker;
+use super::config::ReplicationClientConfig;
+use super::protocol::{self, ProtocolInfo, COMPRESSED_TRANSPORT_VERSION, PROTOCOL_HEADER};
 use super::types::{GetOpsQuery, GetOpsResponse, ReplicateOpsRequest, ReplicateOpsResponse};
-use std::sync::atomic::{AtomicU64, Ordering};
+use super::user_mapping::MappingSnapshot;
+use flapjack::query_suggestions::QsConfig;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::Semaphore;
 
 /// Default: trip after 3 consecutive failures, probe again after 30 seconds
 const DEFAULT_FAILURE_THRESHOLD: u32 = 3;
 const DEFAULT_RECOVERY_TIMEOUT_SECS: u64 = 30;
+/// Tenant transfers during user migration can be much larger than op batches.
+const TENANT_TRANSFER_TIMEOUT_SECS: u64 = 300;
+/// Op batches smaller than this are sent uncompressed.
+const COMPRESS_MIN_BYTES: usize = 1024;
 
 /// HTTP client wrapper for communicating with a single peer node
 pub struct PeerClient {
     peer_id: String,
     base_url: String,
+    /// HTTP/1 client; every peer version speaks it.
     http_client: reqwest::Client,
+    /// HTTP/2 client for op batches, when enabled and the peer negotiated
+    /// a version that serves HTTP/2.
+    http2_client: Option<reqwest::Client>,
     last_success: Arc<AtomicU64>, // Unix timestamp in seconds
     circuit_breaker: CircuitBreaker,
+    /// Bounds concurrent op batches in flight to this peer.
+    inflight: Semaphore,
+    compression: bool,
+    /// Negotiated protocol version; 0 until the first handshake.
+    protocol: AtomicU32,
 }
 
 impl PeerClient {
     pub fn new(peer_id: String, base_url: String) -> Self {
-        let http_client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(5))
-            .build()
-            .unwrap_or_else(|_| reqwest::Client::new());
+        Self::with_config(peer_id, base_url, &ReplicationClientConfig::default())
+    }
 
+    pub fn with_config(
+        peer_id: String,
+        base_url: String,
+        config: &ReplicationClientConfig,
+    ) -> Self {
         Self {
             peer_id,
             base_url,
-            http_client,
+            http_client: build_http_client(config, false),
+            http2_client: config.http2.then(|| build_http_client(config, true)),
             last_success: Arc::new(AtomicU64::new(0)),
             circuit_breaker: CircuitBreaker::new(
                 DEFAULT_FAILURE_THRESHOLD,
                 DEFAULT_RECOVERY_TIMEOUT_SECS,
             ),
+            inflight: Semaphore::new(config.max_inflight_per_peer.max(1)),
+            compression: config.compression,
+            protocol: AtomicU32::new(0),
         }
     }
 
@@ -40,6 +65,10 @@ impl PeerClient {
         &self.peer_id
     }
 
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
     pub fn last_success_timestamp(&self) -> u64 {
         self.last_success.load(Ordering::Relaxed)
     }
@@ -54,26 +83,108 @@ impl PeerClient {
         &self.circuit_breaker
     }
 
+    /// Protocol version to use with this peer, negotiated on first use via
+    /// `GET /internal/protocol`. A peer without that endpoint predates
+    /// versioning and speaks version 1.
+    pub async fn protocol_version(&self) -> Result<u32, String> {
+        let cached = self.protocol.load(Ordering::Relaxed);
+        if cached != 0 {
+            return Ok(cached);
+        }
+
+        let url = format!("{}/internal/protocol", self.base_url);
+        let response = self.http_client.get(&url).send().await.map_err(|e| {
+            self.circuit_breaker.record_failure();
+            format!("Protocol handshake with {} failed: {}", self.peer_id, e)
+        })?;
+        let info = if response.status() == reqwest::StatusCode::NOT_FOUND {
+            ProtocolInfo::legacy()
+        } else if response.status().is_success() {
+            response
+                .json::<ProtocolInfo>()
+                .await
+                .map_err(|e| format!("Invalid protocol handshake from {}: {}", self.peer_id, e))?
+        } else {
+            self.circuit_breaker.record_failure();
+            return Err(format!(
+                "Protocol handshake with {} returned {}",
+                self.peer_id,
+                response.status()
+            ));
+        };
+
+        let version = protocol::negotiate(info)
+            .map_err(|e| format!("Peer {} is incompatible: {}", self.peer_id, e))?;
+        tracing::info!(
+            "[REPL] peer {} speaks protocol {}-{}, using {}",
+            self.peer_id,
+            info.min_version,
+            info.version,
+            version
+        );
+        self.protocol.store(version, Ordering::Relaxed);
+        Ok(version)
+    }
+
+    /// Forget the negotiated version so the next request handshakes again,
+    /// e.g. after the peer was upgraded or rolled back.
+    fn reset_protocol(&self) {
+        self.protocol.store(0, Ordering::Relaxed);
+    }
+
+    /// Client to ship op batches with at `version`: HTTP/2 only once the
+    /// peer has said it serves it.
+    fn ops_client(&self, version: u32) -> &reqwest::Client {
+        match &self.http2_client {
+            Some(client) if version >= COMPRESSED_TRANSPORT_VERSION => client,
+            _ => &self.http_client,
+        }
+    }
+
     /// Replicate operations to this peer
     pub async fn replicate_ops(
         &self,
-        req: ReplicateOpsRequest,
+        mut req: ReplicateOpsRequest,
     ) -> Result<ReplicateOpsResponse, String> {
+        let version = self.protocol_version().await?;
+        req.protocol_version = version;
         let url = format!("{}/internal/replicate", self.base_url);
+        let body = serde_json::to_vec(&req)
+            .map_err(|e| format!("Failed to encode ops for {}: {}", self.peer_id, e))?;
 
-        let response = self
-            .http_client
-            .post(&url)
-            .json(&req)
-            .send()
+        let _permit = self
+            .inflight
+            .acquire()
             .await
-            .map_err(|e| {
-                self.circuit_breaker.record_failure();
-                format!("Failed to send request to {}: {}", self.peer_id, e)
-            })?;
+            .map_err(|e| format!("Replication to {} stopped: {}", self.peer_id, e))?;
+        let mut request = self
+            .ops_client(version)
+            .post(&url)
+            .header("content-type", "application/json")
+            .header(PROTOCOL_HEADER, version.to_string());
+        if self.compression
+            && version >= COMPRESSED_TRANSPORT_VERSION
+            && body.len() >= COMPRESS_MIN_BYTES
+        {
+            request = request.header("content-encoding", "gzip").body(gzip(&body));
+        } else {
+            request = request.body(body);
+        }
+        let response = request.send().await.map_err(|e| {
+            self.circuit_breaker.record_failure();
+            // A peer rolled back to an HTTP/1-only version drops HTTP/2
+            // connections; handshake again rather than keep failing.
+            if self.http2_client.is_some() && version >= COMPRESSED_TRANSPORT_VERSION {
+                self.reset_protocol();
+            }
+            format!("Failed to send request to {}: {}", self.peer_id, e)
+        })?;
 
         if !response.status().is_success() {
             self.circuit_breaker.record_failure();
+            if is_protocol_rejection(response.status()) {
+                self.reset_protocol();
+            }
             return Err(format!(
                 "Peer {} returned error: {}",
                 self.peer_id,
@@ -99,18 +210,28 @@ impl PeerClient {
 
     /// Fetch operations from this peer for catch-up
     pub async fn get_ops(&self, query: GetOpsQuery) -> Result<GetOpsResponse, String> {
+        let version = self.protocol_version().await?;
         let url = format!(
-            "{}/internal/ops?tenant_id={}&since_seq={}",
-            self.base_url, query.tenant_id, query.since_seq
+            "{}/internal/ops?tenant_id={}&since_seq={}&protocol_version={}",
+            self.base_url, query.tenant_id, query.since_seq, version
         );
 
-        let response = self.http_client.get(&url).send().await.map_err(|e| {
-            self.circuit_breaker.record_failure();
-            format!("Failed to fetch ops from {}: {}", self.peer_id, e)
-        })?;
+        let response = self
+            .http_client
+            .get(&url)
+            .header(PROTOCOL_HEADER, version.to_string())
+            .send()
+            .await
+            .map_err(|e| {
+                self.circuit_breaker.record_failure();
+                format!("Failed to fetch ops from {}: {}", self.peer_id, e)
+            })?;
 
         if !response.status().is_success() {
             self.circuit_breaker.record_failure();
+            if is_protocol_rejection(response.status()) {
+                self.reset_protocol();
+            }
             return Err(format!(
                 "Peer {} returned error: {}",
                 self.peer_id,
@@ -134,6 +255,185 @@ impl PeerClient {
         Ok(resp)
     }
 
+    /// Push this node's copy of the user mapping; the peer merges it.
+    pub async fn push_user_mapping(&self, snapshot: &MappingSnapshot) -> Result<(), String> {
+        let url = format!("{}/internal/cluster/mapping", self.base_url);
+
+        let response = self
+            .http_client
+            .post(&url)
+            .json(snapshot)
+            .send()
+            .await
+            .map_err(|e| {
+                self.circuit_breaker.record_failure();
+                format!("Failed to push user mapping to {}: {}", self.peer_id, e)
+            })?;
+
+        if !response.status().is_success() {
+            self.circuit_breaker.record_failure();
+            return Err(format!(
+                "Peer {} returned error: {}",
+                self.peer_id,
+                response.status()
+            ));
+        }
+        self.circuit_breaker.record_success();
+        Ok(())
+    }
+
+    /// Upload a tenant snapshot (tar.gz) to this peer, replacing its copy.
+    pub async fn import_tenant(&self, tenant_id: &str, snapshot: Vec<u8>) -> Result<(), String> {
+        let url = format!("{}/internal/cluster/import/{}", self.base_url, tenant_id);
+
+        let response = self
+            .http_client
+            .post(&url)
+            .timeout(Duration::from_secs(TENANT_TRANSFER_TIMEOUT_SECS))
+            .header("content-type", "application/gzip")
+            .body(snapshot)
+            .send()
+            .await
+            .map_err(|e| {
+                self.circuit_breaker.record_failure();
+                format!("Failed to send {} to {}: {}", tenant_id, self.peer_id, e)
+            })?;
+
+        if !response.status().is_success() {
+            self.circuit_breaker.record_failure();
+            return Err(format!(
+                "Peer {} rejected import of {}: {}",
+                self.peer_id,
+                tenant_id,
+                response.status()
+            ));
+        }
+        self.circuit_breaker.record_success();
+        Ok(())
+    }
+
+    /// Store a Query Suggestions configuration on this peer.
+    pub async fn push_qs_config(&self, config: &QsConfig) -> Result<(), String> {
+        let url = format!("{}/internal/qs/configs", self.base_url);
+        self.send_checked(self.http_client.post(&url).json(config), "push QS config")
+            .await
+    }
+
+    /// Delete a Query Suggestions configuration on this peer.
+    pub async fn delete_qs_config(&self, index_name: &str) -> Result<(), String> {
+        let url = format!("{}/internal/qs/configs/{}", self.base_url, index_name);
+        self.send_checked(self.http_client.delete(&url), "delete QS config")
+            .await
+    }
+
+    /// Create or replace a schedule on this peer. Schedules belong to the
+    /// HTTP layer, so they travel as plain JSON.
+    pub async fn push_schedule(&self, schedule: &serde_json::Value) -> Result<(), String> {
+        let url = format!("{}/internal/schedules", self.base_url);
+        self.send_checked(self.http_client.post(&url).json(schedule), "push schedule")
+            .await
+    }
+
+    /// Delete a schedule on this peer.
+    pub async fn delete_schedule(&self, id: &str) -> Result<(), String> {
+        let url = format!("{}/internal/schedules/{}", self.base_url, id);
+        self.send_checked(self.http_client.delete(&url), "delete schedule")
+            .await
+    }
+
+    /// Ask this peer to build a suggestions index itself.
+    pub async fn trigger_qs_build(&self, index_name: &str) -> Result<(), String> {
+        let url = format!("{}/internal/qs/build/{}", self.base_url, index_name);
+        self.send_checked(self.http_client.post(&url), "trigger QS build")
+            .await
+    }
+
+    /// Replace this peer's copy of a built suggestions index (tar.gz).
+    pub async fn import_suggestions(
+        &self,
+        index_name: &str,
+        snapshot: Vec<u8>,
+    ) -> Result<(), String> {
+        let url = format!("{}/internal/qs/indexes/{}", self.base_url, index_name);
+        let request = self
+            .http_client
+            .post(&url)
+            .timeout(Duration::from_secs(TENANT_TRANSFER_TIMEOUT_SECS))
+            .header("content-type", "application/gzip")
+            .body(snapshot);
+        self.send_checked(request, "ship suggestions index").await
+    }
+
+    /// Replace this peer's vector index files for a tenant (tar.gz of the
+    /// tenant's `vectors/` directory).
+    pub async fn import_vectors(&self, tenant_id: &str, snapshot: Vec<u8>) -> Result<(), String> {
+        let url = format!("{}/internal/vectors/{}", self.base_url, tenant_id);
+        let request = self
+            .http_client
+            .post(&url)
+            .timeout(Duration::from_secs(TENANT_TRANSFER_TIMEOUT_SECS))
+            .header("content-type", "application/gzip")
+            .body(snapshot);
+        self.send_checked(request, "ship vector index").await
+    }
+
+    /// Download this peer's vector index files for a tenant (tar.gz).
+    pub async fn fetch_vectors(&self, tenant_id: &str) -> Result<Vec<u8>, String> {
+        let url = format!("{}/internal/vectors/{}", self.base_url, tenant_id);
+        let response = self
+            .http_client
+            .get(&url)
+            .timeout(Duration::from_secs(TENANT_TRANSFER_TIMEOUT_SECS))
+            .send()
+            .await
+            .map_err(|e| {
+                self.circuit_breaker.record_failure();
+                format!(
+                    "Failed to fetch vectors of {} from {}: {}",
+                    tenant_id, self.peer_id, e
+                )
+            })?;
+        if !response.status().is_success() {
+            self.circuit_breaker.record_failure();
+            return Err(format!(
+                "Peer {} returned {} for vectors of {}",
+                self.peer_id,
+                response.status(),
+                tenant_id
+            ));
+        }
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read vectors from {}: {}", self.peer_id, e))?;
+        self.circuit_breaker.record_success();
+        Ok(bytes.to_vec())
+    }
+
+    /// Send `request`, treating transport errors and non-2xx replies as
+    /// failures for the circuit breaker.
+    async fn send_checked(
+        &self,
+        request: reqwest::RequestBuilder,
+        what: &str,
+    ) -> Result<(), String> {
+        let response = request.send().await.map_err(|e| {
+            self.circuit_breaker.record_failure();
+            format!("Failed to {} on {}: {}", what, self.peer_id, e)
+        })?;
+        if !response.status().is_success() {
+            self.circuit_breaker.record_failure();
+            return Err(format!(
+                "Peer {} rejected {}: {}",
+                self.peer_id,
+                what,
+                response.status()
+            ));
+        }
+        self.circuit_breaker.record_success();
+        Ok(())
+    }
+
     /// Ping this peer's status endpoint (for active health probing).
     /// Returns Ok(()) on success, Err on failure. Updates circuit breaker.
     pub async fn health_check(&self) -> Result<(), String> {
@@ -163,6 +463,49 @@ impl PeerClient {
     }
 }
 
+/// Whether a peer refused a request for how it was sent (version header,
+/// encoding or payload shape) rather than for what it failed to apply. Only
+/// these warrant a new handshake; outages and 5xx keep the negotiated
+/// version.
+fn is_protocol_rejection(status: reqwest::StatusCode) -> bool {
+    matches!(
+        status,
+        reqwest::StatusCode::BAD_REQUEST
+            | reqwest::StatusCode::UNSUPPORTED_MEDIA_TYPE
+            | reqwest::StatusCode::UNPROCESSABLE_ENTITY
+    )
+}
+
+/// Pooled client shared by requests to one peer. With HTTP/2 every request
+/// is multiplexed over a single kept-alive connection.
+fn build_http_client(config: &ReplicationClientConfig, http2: bool) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .connect_timeout(Duration::from_secs(2))
+        .pool_idle_timeout(Duration::from_secs(90))
+        .pool_max_idle_per_host(config.max_inflight_per_peer.max(1))
+        .tcp_keepalive(Duration::from_secs(30))
+        .tcp_nodelay(true)
+        .gzip(config.compression);
+    if http2 {
+        builder = builder
+            .http2_prior_knowledge()
+            .http2_adaptive_window(true)
+            .http2_keep_alive_interval(Duration::from_secs(15))
+            .http2_keep_alive_timeout(Duration::from_secs(5))
+            .http2_keep_alive_while_idle(true);
+    }
+    builder.build().unwrap_or_else(|_| reqwest::Client::new())
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    // Writing to a Vec cannot fail.
+    let _ = encoder.write_all(data);
+    encoder.finish().unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -182,4 +525,125 @@ mod tests {
         assert!(peer.is_available());
         assert_eq!(peer.circuit_breaker().state(), CircuitState::Closed);
     }
+
+    #[test]
+    fn test_gzip_round_trip() {
+        use std::io::Read;
+        let data = br#"{"tenant_id":"t","ops":[]}"#.repeat(100);
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(&gzip(&data)[..])
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    /// A batch big enough to be compressed when compression is allowed.
+    fn large_batch() -> ReplicateOpsRequest {
+        let ops = (1..=50)
+            .map(|seq| flapjack::index::oplog::OpLogEntry {
+                seq,
+                timestamp_ms: 1_700_000_000_000,
+                node_id: "node-a".to_string(),
+                tenant_id: "products".to_string(),
+                op_type: "upsert".to_string(),
+                payload: serde_json::json!({"objectID": seq.to_string(), "title": "x"}),
+            })
+            .collect();
+        ReplicateOpsRequest {
+            tenant_id: "products".to_string(),
+            ops,
+            protocol_version: protocol::PROTOCOL_VERSION,
+        }
+    }
+
+    fn enabled_transport() -> ReplicationClientConfig {
+        ReplicationClientConfig {
+            http2: true,
+            compression: true,
+            ..ReplicationClientConfig::default()
+        }
+    }
+
+    async fn handshakes(server: &wiremock::MockServer) -> usize {
+        server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|r| r.url.path() == "/internal/protocol")
+            .count()
+    }
+
+    #[tokio::test]
+    async fn legacy_peer_gets_plain_http1_requests() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // A node built before versioning: no /internal/protocol (wiremock
+        // answers 404) and no request decompression.
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/internal/replicate"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"tenant_id": "products", "acked_seq": 50})),
+            )
+            .mount(&server)
+            .await;
+
+        let peer =
+            PeerClient::with_config("legacy".to_string(), server.uri(), &enabled_transport());
+        let resp = peer.replicate_ops(large_batch()).await.unwrap();
+        assert_eq!(resp.acked_seq, 50);
+        assert_eq!(peer.protocol_version().await, Ok(1));
+        assert!(std::ptr::eq(peer.ops_client(1), &peer.http_client));
+
+        let requests = server.received_requests().await.unwrap();
+        let replicate = requests
+            .iter()
+            .find(|r| r.url.path() == "/internal/replicate")
+            .unwrap();
+        assert!(replicate.headers.get("content-encoding").is_none());
+        let sent: ReplicateOpsRequest = serde_json::from_slice(&replicate.body).unwrap();
+        assert_eq!(sent.ops.len(), 50);
+        assert_eq!(sent.protocol_version, 1);
+    }
+
+    #[tokio::test]
+    async fn only_protocol_rejections_renegotiate() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/internal/replicate"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/internal/replicate"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&server)
+            .await;
+
+        let peer =
+            PeerClient::with_config("legacy".to_string(), server.uri(), &enabled_transport());
+        assert!(peer.replicate_ops(large_batch()).await.is_err());
+        assert!(peer.replicate_ops(large_batch()).await.is_err());
+        assert_eq!(
+            handshakes(&server).await,
+            1,
+            "5xx kept the negotiated version"
+        );
+
+        assert!(peer.replicate_ops(large_batch()).await.is_err());
+        assert_eq!(peer.protocol.load(Ordering::Relaxed), 0);
+        assert_eq!(peer.protocol_version().await, Ok(1));
+        assert_eq!(
+            handshakes(&server).await,
+            2,
+            "400 triggered a new handshake"
+        );
+    }
 }
@@ -0,0 +1,183 @@
+//! Versioning for the internal replication protocol.
+//!
+//! Every `/internal/replicate` and `/internal/ops` request carries the
+//! sender's protocol version in the `x-flapjack-protocol` header. Before the
+//! first request to a peer, the sender asks `GET /internal/protocol` which
+//! versions the peer speaks and uses the highest version both sides support.
+//! Nodes built before versioning existed have neither the header nor the
+//! endpoint; they are treated as version 1.
+//!
+//! Compatibility rule: a node must accept every version from
+//! [`MIN_PROTOCOL_VERSION`] to [`PROTOCOL_VERSION`], and the window is at
+//! least two versions wide, so a cluster can be upgraded one node at a time.
+//! Requests outside the window are rejected instead of being half-applied.
+//!
+//! Versions:
+//! - 1: unversioned payloads.
+//! - 2: payloads carry `protocol_version`; the handshake endpoint exists.
+//! - 3: the node serves HTTP/2 with prior knowledge and accepts gzip request
+//!   bodies. Senders only use either with peers that negotiated 3 or later.
+
+use serde::{Deserialize, Serialize};
+
+pub const PROTOCOL_HEADER: &str = "x-flapjack-protocol";
+pub const PROTOCOL_VERSION: u32 = 3;
+/// First version whose nodes accept HTTP/2 and gzip-compressed requests.
+pub const COMPRESSED_TRANSPORT_VERSION: u32 = 3;
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Body of `GET /internal/protocol`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolInfo {
+    pub version: u32,
+    pub min_version: u32,
+}
+
+impl ProtocolInfo {
+    pub fn local() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            min_version: MIN_PROTOCOL_VERSION,
+        }
+    }
+
+    /// What a pre-versioning peer implicitly speaks.
+    pub fn legacy() -> Self {
+        Self {
+            version: 1,
+            min_version: 1,
+        }
+    }
+}
+
+/// Pick the version to speak with a peer: the highest version both sides
+/// support, or an error if the ranges don't overlap.
+pub fn negotiate(peer: ProtocolInfo) -> Result<u32, String> {
+    let version = PROTOCOL_VERSION.min(peer.version);
+    if version < MIN_PROTOCOL_VERSION || version < peer.min_version {
+        return Err(format!(
+            "no common protocol version (local {}-{}, peer {}-{})",
+            MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, peer.min_version, peer.version
+        ));
+    }
+    Ok(version)
+}
+
+/// Validate the version a request was sent with. A missing header means a
+/// pre-versioning sender (version 1).
+pub fn check_request_version(header: Option<&str>) -> Result<u32, String> {
+    let version = match header {
+        None => 1,
+        Some(v) => v
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| format!("invalid {} header: {:?}", PROTOCOL_HEADER, v))?,
+    };
+    if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version) {
+        return Err(format!(
+            "unsupported protocol version {} (this node supports {}-{})",
+            version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        ));
+    }
+    Ok(version)
+}
+
+pub(crate) fn legacy_version() -> u32 {
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{GetOpsQuery, GetOpsResponse, ReplicateOpsRequest, ReplicateOpsResponse};
+
+    #[test]
+    fn window_spans_previous_version() {
+        assert!(PROTOCOL_VERSION - MIN_PROTOCOL_VERSION >= 1);
+    }
+
+    #[test]
+    fn negotiates_down_to_older_peer() {
+        assert_eq!(negotiate(ProtocolInfo::legacy()), Ok(1));
+        assert_eq!(negotiate(ProtocolInfo::local()), Ok(PROTOCOL_VERSION));
+    }
+
+    #[test]
+    fn newer_peer_that_still_speaks_our_version_is_compatible() {
+        let peer = ProtocolInfo {
+            version: PROTOCOL_VERSION + 1,
+            min_version: PROTOCOL_VERSION,
+        };
+        assert_eq!(negotiate(peer), Ok(PROTOCOL_VERSION));
+    }
+
+    #[test]
+    fn peer_that_dropped_our_version_is_incompatible() {
+        let peer = ProtocolInfo {
+            version: PROTOCOL_VERSION + 2,
+            min_version: PROTOCOL_VERSION + 1,
+        };
+        assert!(negotiate(peer).is_err());
+    }
+
+    #[test]
+    fn request_versions_are_checked() {
+        assert_eq!(check_request_version(None), Ok(1));
+        assert_eq!(
+            check_request_version(Some(&PROTOCOL_VERSION.to_string())),
+            Ok(PROTOCOL_VERSION)
+        );
+        assert!(check_request_version(Some(&(PROTOCOL_VERSION + 1).to_string())).is_err());
+        assert!(check_request_version(Some("0")).is_err());
+        assert!(check_request_version(Some("two")).is_err());
+    }
+
+    // Frozen v1 payloads, as sent by nodes built before versioning. These
+    // must keep parsing for as long as MIN_PROTOCOL_VERSION is 1.
+
+    const V1_REPLICATE: &str = r#"{"tenant_id":"products","ops":[{"seq":7,"timestamp_ms":1700000000000,"node_id":"node-a","tenant_id":"products","op_type":"upsert","payload":{"objectID":"1","body":{"objectID":"1","title":"x"}}}]}"#;
+    const V1_REPLICATE_RESPONSE: &str = r#"{"tenant_id":"products","acked_seq":7}"#;
+    const V1_OPS_RESPONSE: &str = r#"{"tenant_id":"products","ops":[],"current_seq":7}"#;
+
+    #[test]
+    fn v1_payloads_still_parse() {
+        let req: ReplicateOpsRequest = serde_json::from_str(V1_REPLICATE).unwrap();
+        assert_eq!(req.protocol_version, 1);
+        assert_eq!(req.ops[0].seq, 7);
+
+        let resp: ReplicateOpsResponse = serde_json::from_str(V1_REPLICATE_RESPONSE).unwrap();
+        assert_eq!(resp.acked_seq, 7);
+        assert_eq!(resp.protocol_version, 1);
+
+        let ops: GetOpsResponse = serde_json::from_str(V1_OPS_RESPONSE).unwrap();
+        assert_eq!(ops.current_seq, 7);
+        assert_eq!(ops.protocol_version, 1);
+
+        let query: GetOpsQuery =
+            serde_json::from_value(serde_json::json!({"tenant_id": "products", "since_seq": 3}))
+                .unwrap();
+        assert_eq!(query.protocol_version, 1);
+    }
+
+    #[test]
+    fn current_payloads_keep_v1_fields() {
+        // A v1 receiver ignores unknown fields, so current payloads must
+        // still contain every v1 field with its v1 meaning.
+        let req = ReplicateOpsRequest {
+            tenant_id: "products".to_string(),
+            ops: Vec::new(),
+            protocol_version: PROTOCOL_VERSION,
+        };
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["tenant_id"], "products");
+        assert!(json["ops"].is_array());
+
+        let resp = ReplicateOpsResponse {
+            tenant_id: "products".to_string(),
+            acked_seq: 7,
+            protocol_version: PROTOCOL_VERSION,
+        };
+        let json = serde_json::to_value(&resp).unwrap();
+        assert_eq!(json["acked_seq"], 7);
+    }
+}
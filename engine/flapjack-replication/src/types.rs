@@ -1,3 +1,4 @@
+use crate::protocol::legacy_version;
 use flapjack::index::oplog::OpLogEntry;
 use serde::{Deserialize, Serialize};
 
@@ -6,6 +7,9 @@ use serde::{Deserialize, Serialize};
 pub struct ReplicateOpsRequest {
     pub tenant_id: String,
     pub ops: Vec<OpLogEntry>,
+    /// Protocol version the payload was written for; absent in v1 payloads.
+    #[serde(default = "legacy_version")]
+    pub protocol_version: u32,
 }
 
 /// Response from replicating operations
@@ -13,6 +17,9 @@ pub struct ReplicateOpsRequest {
 pub struct ReplicateOpsResponse {
     pub tenant_id: String,
     pub acked_seq: u64, // Highest sequence number successfully applied
+    /// Protocol version the payload was written for; absent in v1 payloads.
+    #[serde(default = "legacy_version")]
+    pub protocol_version: u32,
 }
 
 /// Query parameters for fetching operations
@@ -20,6 +27,9 @@ pub struct ReplicateOpsResponse {
 pub struct GetOpsQuery {
     pub tenant_id: String,
     pub since_seq: u64, // Fetch ops with seq > since_seq
+    /// Protocol version the payload was written for; absent in v1 payloads.
+    #[serde(default = "legacy_version")]
+    pub protocol_version: u32,
 }
 
 /// Response containing operations for catch-up
@@ -28,6 +38,9 @@ pub struct GetOpsResponse {
     pub tenant_id: String,
     pub ops: Vec<OpLogEntry>,
     pub current_seq: u64, // Latest sequence number on this node
+    /// Protocol version the payload was written for; absent in v1 payloads.
+    #[serde(default = "legacy_version")]
+    pub protocol_version: u32,
 }
 
 /// Basic replication status for monitoring
@@ -0,0 +1,517 @@
+//! Multi-cluster user mapping (MCM).
+//!
+//! Every node of a replication group is a "cluster" named after its
+//! `node_id`. A userID is pinned to one cluster; requests carrying
+//! `X-Algolia-User-ID` are served by that node, and the user's records live in
+//! per-user tenants (`{index}@{userID}`) that are never replicated to peers.
+//!
+//! The mapping itself is small and shared: every node keeps a copy in
+//! `{data_dir}/cluster_mapping.json` and pushes it to its peers after each
+//! change. Entries are merged per user with last-writer-wins on `updatedAt`;
+//! removals are kept as tombstones so they win over stale copies.
+//!
+//! Reassigning a user records the previous owner in `migratingFrom`. Until the
+//! old owner has shipped the user's tenants to the new cluster, requests keep
+//! going to the old owner.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use dashmap::DashMap;
+
+pub const MAPPING_FILE: &str = "cluster_mapping.json";
+
+/// Separates the index name from the userID in per-user tenant names.
+pub const USER_TENANT_SEPARATOR: char = '@';
+
+pub const MAX_USER_ID_LEN: usize = 64;
+
+/// Where a userID is pinned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingEntry {
+    pub cluster_name: String,
+    /// Previous owner while the user's data is being moved to `cluster_name`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub migrating_from: Option<String>,
+    #[serde(default)]
+    pub deleted: bool,
+    /// Milliseconds since epoch.
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl MappingEntry {
+    /// The node currently holding the user's data.
+    pub fn serving_cluster(&self) -> &str {
+        self.migrating_from.as_deref().unwrap_or(&self.cluster_name)
+    }
+}
+
+/// Record count and disk usage of a user's tenants, reported by its owner.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserStats {
+    pub nb_records: u64,
+    pub data_size: u64,
+    pub updated_at: i64,
+}
+
+/// A userID as returned by the MCM API.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserId {
+    #[serde(rename = "userID")]
+    pub user_id: String,
+    pub cluster_name: String,
+    pub nb_records: u64,
+    pub data_size: u64,
+}
+
+/// The replicated mapping document.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingSnapshot {
+    #[serde(default)]
+    pub users: BTreeMap<String, MappingEntry>,
+    #[serde(default)]
+    pub stats: BTreeMap<String, UserStats>,
+}
+
+impl MappingSnapshot {
+    /// Merge `other` into `self`, keeping the newer version of every entry.
+    /// Returns true if anything changed.
+    pub fn merge(&mut self, other: &MappingSnapshot) -> bool {
+        let mut changed = false;
+        for (user, entry) in &other.users {
+            if self
+                .users
+                .get(user)
+                .is_none_or(|e| e.updated_at < entry.updated_at)
+            {
+                self.users.insert(user.clone(), entry.clone());
+                changed = true;
+            }
+        }
+        for (user, stats) in &other.stats {
+            if self
+                .stats
+                .get(user)
+                .is_none_or(|s| s.updated_at < stats.updated_at)
+            {
+                self.stats.insert(user.clone(), stats.clone());
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn user_id(&self, user: &str, entry: &MappingEntry) -> UserId {
+        let stats = self.stats.get(user).cloned().unwrap_or_default();
+        UserId {
+            user_id: user.to_string(),
+            cluster_name: entry.cluster_name.clone(),
+            nb_records: stats.nb_records,
+            data_size: stats.data_size,
+        }
+    }
+
+    /// Live (non-deleted) userIDs in userID order.
+    pub fn user_ids(&self) -> impl Iterator<Item = UserId> + '_ {
+        self.users
+            .iter()
+            .filter(|(_, e)| !e.deleted)
+            .map(|(user, entry)| self.user_id(user, entry))
+    }
+}
+
+/// Validate a userID: 1-64 characters of `[A-Za-z0-9._-]`.
+pub fn validate_user_id(user_id: &str) -> Result<(), String> {
+    if user_id.is_empty() || user_id.len() > MAX_USER_ID_LEN {
+        return Err(format!(
+            "userID must be between 1 and {} characters",
+            MAX_USER_ID_LEN
+        ));
+    }
+    if !user_id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+    {
+        return Err(format!(
+            "userID '{}' may only contain letters, digits, '.', '_' and '-'",
+            user_id
+        ));
+    }
+    Ok(())
+}
+
+/// Tenant holding `user_id`'s records for `index_name`.
+pub fn user_tenant(index_name: &str, user_id: &str) -> String {
+    format!("{}{}{}", index_name, USER_TENANT_SEPARATOR, user_id)
+}
+
+/// Split a per-user tenant name into `(index_name, user_id)`.
+pub fn parse_user_tenant(tenant_id: &str) -> Option<(&str, &str)> {
+    tenant_id
+        .rsplit_once(USER_TENANT_SEPARATOR)
+        .filter(|(index, user)| !index.is_empty() && !user.is_empty())
+}
+
+pub fn is_user_tenant(tenant_id: &str) -> bool {
+    parse_user_tenant(tenant_id).is_some()
+}
+
+/// The mapping for one data directory, cached in memory and persisted on
+/// every change.
+pub struct UserMappingStore {
+    path: PathBuf,
+    snapshot: RwLock<MappingSnapshot>,
+}
+
+static STORES: OnceLock<DashMap<PathBuf, Arc<UserMappingStore>>> = OnceLock::new();
+
+impl UserMappingStore {
+    pub fn open(data_dir: &Path) -> std::io::Result<Self> {
+        let path = data_dir.join(MAPPING_FILE);
+        let snapshot = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?
+        } else {
+            MappingSnapshot::default()
+        };
+        Ok(Self {
+            path,
+            snapshot: RwLock::new(snapshot),
+        })
+    }
+
+    /// The store for a data directory, loaded once per process.
+    pub fn for_data_dir(data_dir: &Path) -> std::io::Result<Arc<Self>> {
+        let stores = STORES.get_or_init(DashMap::new);
+        if let Some(store) = stores.get(data_dir) {
+            return Ok(Arc::clone(&store));
+        }
+        let store = Arc::new(Self::open(data_dir)?);
+        Ok(Arc::clone(
+            &stores.entry(data_dir.to_path_buf()).or_insert(store),
+        ))
+    }
+
+    pub fn snapshot(&self) -> MappingSnapshot {
+        self.snapshot.read().unwrap().clone()
+    }
+
+    /// The live mapping entry for `user_id`.
+    pub fn get(&self, user_id: &str) -> Option<MappingEntry> {
+        self.snapshot
+            .read()
+            .unwrap()
+            .users
+            .get(user_id)
+            .filter(|e| !e.deleted)
+            .cloned()
+    }
+
+    pub fn user_id(&self, user_id: &str) -> Option<UserId> {
+        let snapshot = self.snapshot.read().unwrap();
+        snapshot
+            .users
+            .get(user_id)
+            .filter(|e| !e.deleted)
+            .map(|e| snapshot.user_id(user_id, e))
+    }
+
+    fn update<F>(&self, f: F) -> std::io::Result<bool>
+    where
+        F: FnOnce(&mut MappingSnapshot) -> bool,
+    {
+        let mut snapshot = self.snapshot.write().unwrap();
+        let mut updated = snapshot.clone();
+        if !f(&mut updated) {
+            return Ok(false);
+        }
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&updated).map_err(std::io::Error::other)?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(tmp, &self.path)?;
+        *snapshot = updated;
+        Ok(true)
+    }
+
+    /// Pin `user_ids` to `cluster`. Users already served by another cluster
+    /// start migrating; reassigning a migrating user back to its old owner
+    /// cancels the migration.
+    pub fn assign(&self, user_ids: &[String], cluster: &str, now_ms: i64) -> std::io::Result<()> {
+        self.update(|snapshot| {
+            let mut changed = false;
+            for user in user_ids {
+                let entry = match snapshot.users.get(user).filter(|e| !e.deleted) {
+                    Some(existing) if existing.cluster_name == cluster => continue,
+                    Some(existing) => {
+                        let serving = existing.serving_cluster().to_string();
+                        MappingEntry {
+                            cluster_name: cluster.to_string(),
+                            migrating_from: (serving != cluster).then_some(serving),
+                            deleted: false,
+                            created_at: existing.created_at,
+                            updated_at: now_ms,
+                        }
+                    }
+                    None => MappingEntry {
+                        cluster_name: cluster.to_string(),
+                        migrating_from: None,
+                        deleted: false,
+                        created_at: now_ms,
+                        updated_at: now_ms,
+                    },
+                };
+                snapshot.users.insert(user.clone(), entry);
+                changed = true;
+            }
+            changed
+        })?;
+        Ok(())
+    }
+
+    /// Tombstone `user_id`. Returns the removed entry, or `None` if unknown.
+    pub fn remove(&self, user_id: &str, now_ms: i64) -> std::io::Result<Option<MappingEntry>> {
+        let mut removed = None;
+        self.update(|snapshot| {
+            let Some(entry) = snapshot.users.get_mut(user_id).filter(|e| !e.deleted) else {
+                return false;
+            };
+            removed = Some(entry.clone());
+            entry.deleted = true;
+            entry.updated_at = now_ms;
+            true
+        })?;
+        Ok(removed)
+    }
+
+    /// Mark `user_id`'s migration away from `from` as done.
+    pub fn complete_migration(
+        &self,
+        user_id: &str,
+        from: &str,
+        now_ms: i64,
+    ) -> std::io::Result<bool> {
+        self.update(|snapshot| {
+            let Some(entry) = snapshot
+                .users
+                .get_mut(user_id)
+                .filter(|e| e.migrating_from.as_deref() == Some(from))
+            else {
+                return false;
+            };
+            entry.migrating_from = None;
+            entry.updated_at = now_ms;
+            true
+        })
+    }
+
+    /// Record stats for users whose data lives on this node. Only changed
+    /// values are written.
+    pub fn update_stats(&self, stats: &[(String, u64, u64)], now_ms: i64) -> std::io::Result<bool> {
+        self.update(|snapshot| {
+            let mut changed = false;
+            for (user, nb_records, data_size) in stats {
+                let current = snapshot.stats.get(user);
+                if current.is_some_and(|s| s.nb_records == *nb_records && s.data_size == *data_size)
+                {
+                    continue;
+                }
+                snapshot.stats.insert(
+                    user.clone(),
+                    UserStats {
+                        nb_records: *nb_records,
+                        data_size: *data_size,
+                        updated_at: now_ms,
+                    },
+                );
+                changed = true;
+            }
+            changed
+        })
+    }
+
+    /// Merge a peer's copy of the mapping. Returns true if anything changed.
+    pub fn merge(&self, other: &MappingSnapshot) -> std::io::Result<bool> {
+        self.update(|snapshot| snapshot.merge(other))
+    }
+
+    /// Live userIDs matching `query` (substring, case-insensitive), optionally
+    /// restricted to one cluster.
+    pub fn search(&self, query: &str, cluster: Option<&str>) -> Vec<UserId> {
+        let query = query.to_lowercase();
+        self.snapshot
+            .read()
+            .unwrap()
+            .user_ids()
+            .filter(|u| cluster.is_none_or(|c| u.cluster_name == c))
+            .filter(|u| u.user_id.to_lowercase().contains(&query))
+            .collect()
+    }
+
+    /// Up to `limit` users with the most records on each cluster.
+    pub fn top_users(&self, limit: usize) -> BTreeMap<String, Vec<UserId>> {
+        let mut by_cluster: BTreeMap<String, Vec<UserId>> = BTreeMap::new();
+        for user in self.snapshot.read().unwrap().user_ids() {
+            by_cluster
+                .entry(user.cluster_name.clone())
+                .or_default()
+                .push(user);
+        }
+        for users in by_cluster.values_mut() {
+            users.sort_by(|a, b| {
+                b.nb_records
+                    .cmp(&a.nb_records)
+                    .then_with(|| a.user_id.cmp(&b.user_id))
+            });
+            users.truncate(limit);
+        }
+        by_cluster
+    }
+
+    /// Users still being migrated, grouped by destination cluster.
+    pub fn pending(&self) -> BTreeMap<String, Vec<String>> {
+        let mut pending: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (user, entry) in &self.snapshot.read().unwrap().users {
+            if !entry.deleted && entry.migrating_from.is_some() {
+                pending
+                    .entry(entry.cluster_name.clone())
+                    .or_default()
+                    .push(user.clone());
+            }
+        }
+        pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn users(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn user_tenant_round_trip() {
+        let tenant = user_tenant("products", "user-42");
+        assert_eq!(tenant, "products@user-42");
+        assert_eq!(parse_user_tenant(&tenant), Some(("products", "user-42")));
+        assert!(!is_user_tenant("products"));
+        assert!(!is_user_tenant("@user"));
+    }
+
+    #[test]
+    fn user_id_validation() {
+        assert!(validate_user_id("user-42.a_b").is_ok());
+        assert!(validate_user_id("").is_err());
+        assert!(validate_user_id("a@b").is_err());
+        assert!(validate_user_id("a/b").is_err());
+        assert!(validate_user_id(&"a".repeat(MAX_USER_ID_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn reassignment_starts_and_cancels_migration() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = UserMappingStore::open(tmp.path()).unwrap();
+        store.assign(&users(&["u1"]), "node-a", 1).unwrap();
+        assert_eq!(store.get("u1").unwrap().serving_cluster(), "node-a");
+        assert!(store.pending().is_empty());
+
+        store.assign(&users(&["u1"]), "node-b", 2).unwrap();
+        let entry = store.get("u1").unwrap();
+        assert_eq!(entry.cluster_name, "node-b");
+        assert_eq!(entry.serving_cluster(), "node-a");
+        assert_eq!(entry.created_at, 1);
+        assert_eq!(store.pending()["node-b"], users(&["u1"]));
+
+        // Moving back before the data was shipped is a no-op migration.
+        store.assign(&users(&["u1"]), "node-a", 3).unwrap();
+        assert_eq!(store.get("u1").unwrap().migrating_from, None);
+
+        store.assign(&users(&["u1"]), "node-b", 4).unwrap();
+        assert!(!store.complete_migration("u1", "node-c", 5).unwrap());
+        assert!(store.complete_migration("u1", "node-a", 5).unwrap());
+        assert_eq!(store.get("u1").unwrap().serving_cluster(), "node-b");
+    }
+
+    #[test]
+    fn mapping_is_persisted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = UserMappingStore::open(tmp.path()).unwrap();
+        store.assign(&users(&["u1", "u2"]), "node-a", 1).unwrap();
+        store
+            .update_stats(&[("u1".to_string(), 10, 2048)], 2)
+            .unwrap();
+        store.remove("u2", 3).unwrap();
+
+        let reopened = UserMappingStore::open(tmp.path()).unwrap();
+        assert_eq!(
+            reopened.user_id("u1"),
+            Some(UserId {
+                user_id: "u1".to_string(),
+                cluster_name: "node-a".to_string(),
+                nb_records: 10,
+                data_size: 2048,
+            })
+        );
+        assert!(reopened.get("u2").is_none());
+    }
+
+    #[test]
+    fn merge_keeps_newest_entries_and_tombstones() {
+        let tmp = tempfile::tempdir().unwrap();
+        let local = UserMappingStore::open(tmp.path()).unwrap();
+        local.assign(&users(&["u1", "u2"]), "node-a", 1).unwrap();
+
+        let mut remote = local.snapshot();
+        remote.users.get_mut("u1").unwrap().deleted = true;
+        remote.users.get_mut("u1").unwrap().updated_at = 5;
+        // Older change to u2 must lose.
+        remote.users.get_mut("u2").unwrap().cluster_name = "node-b".to_string();
+        remote.users.get_mut("u2").unwrap().updated_at = 0;
+
+        assert!(local.merge(&remote).unwrap());
+        assert!(local.get("u1").is_none());
+        assert_eq!(local.get("u2").unwrap().cluster_name, "node-a");
+        assert!(!local.merge(&remote).unwrap());
+    }
+
+    #[test]
+    fn search_and_top_users() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = UserMappingStore::open(tmp.path()).unwrap();
+        store
+            .assign(&users(&["alice", "alan", "bob"]), "node-a", 1)
+            .unwrap();
+        store.assign(&users(&["carol"]), "node-b", 1).unwrap();
+        store
+            .update_stats(&[("alan".to_string(), 5, 0), ("bob".to_string(), 50, 0)], 2)
+            .unwrap();
+
+        let hits = store.search("AL", None);
+        assert_eq!(
+            hits.iter().map(|u| u.user_id.as_str()).collect::<Vec<_>>(),
+            vec!["alan", "alice"]
+        );
+        assert!(store.search("al", Some("node-b")).is_empty());
+
+        let top = store.top_users(2);
+        assert_eq!(
+            top["node-a"]
+                .iter()
+                .map(|u| u.user_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["bob", "alan"]
+        );
+        assert_eq!(top["node-b"].len(), 1);
+    }
+}
@@ -37,6 +37,105 @@ enum Command {
     Uninstall,
     /// Generate a new admin API key (replaces the current one in keys.json)
     ResetAdminKey,
+    /// Crawl a website into an index of a running server
+    Crawl {
+        /// Crawler configuration (YAML or JSON)
+        #[arg(long)]
+        config: std::path::PathBuf,
+        /// Server to write the records to
+        #[arg(long, default_value = "http://127.0.0.1:7700")]
+        server: String,
+        /// API key with addObject on the target index
+        #[arg(long, env = "FLAPJACK_ADMIN_KEY")]
+        api_key: Option<String>,
+    },
+    /// Write an index of the data directory to a portable .fjpack archive
+    Export {
+        /// Index to export
+        #[arg(long)]
+        index: String,
+        /// Archive to write
+        #[arg(long)]
+        out: std::path::PathBuf,
+        /// Also export the index's stored vectors
+        #[arg(long)]
+        include_vectors: bool,
+    },
+    /// Load a .fjpack archive into the data directory, replacing the index.
+    /// Stop the server first, or use `POST /1/indexes:import` instead.
+    Import {
+        /// Archive to read
+        #[arg(long)]
+        file: std::path::PathBuf,
+        /// Index to import into (default: the index the archive was exported from)
+        #[arg(long)]
+        index: Option<String>,
+    },
+    /// Load-test a running server and report latency percentiles and errors
+    Bench {
+        /// Server to send requests to
+        #[arg(long, default_value = "http://127.0.0.1:7700")]
+        target: String,
+        /// Index to search and write to
+        #[arg(long)]
+        index: String,
+        /// search, write or mixed
+        #[arg(long, default_value = "search")]
+        scenario: flapjack_http::bench::Scenario,
+        /// Requests per second
+        #[arg(long, default_value_t = 50.0)]
+        qps: f64,
+        /// Seconds to run
+        #[arg(long, default_value_t = 30)]
+        duration: u64,
+        /// Most requests in flight; further due requests are dropped and counted
+        #[arg(long, default_value_t = 64)]
+        concurrency: usize,
+        /// Share of mixed-scenario requests that are writes
+        #[arg(long, default_value_t = 0.1)]
+        write_ratio: f64,
+        /// Records per write request
+        #[arg(long, default_value_t = 10)]
+        batch_size: usize,
+        /// Corpus for synthetic queries and written records
+        #[arg(long)]
+        preset: Option<flapjack::fixtures::Preset>,
+        /// Seed for the request mix and written records
+        #[arg(long, default_value_t = 42)]
+        seed: u64,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+        /// API key with search (and addObject for writes) on the index
+        #[arg(long, env = "FLAPJACK_ADMIN_KEY")]
+        api_key: Option<String>,
+    },
+    /// Fill an index of the data directory with reproducible demo data:
+    /// documents, analytics history and A/B test exposures. Stop the server
+    /// first.
+    Seed {
+        /// Index to seed
+        #[arg(long)]
+        index: String,
+        /// ecommerce, docs or media (default: guessed from the index name)
+        #[arg(long)]
+        preset: Option<flapjack::fixtures::Preset>,
+        /// Documents to generate (0 keeps the index's documents)
+        #[arg(long, default_value_t = 1000)]
+        documents: usize,
+        /// Days of search, click and conversion events to generate
+        #[arg(long, default_value_t = 0)]
+        analytics_days: u32,
+        /// Split the generated searches across the arms of this experiment
+        #[arg(long)]
+        experiment: Option<String>,
+        /// Click-through rate of the experiment's variant relative to control
+        #[arg(long, default_value_t = 1.1)]
+        variant_ctr_lift: f64,
+        /// Same seed, same data
+        #[arg(long, default_value_t = flapjack::fixtures::DEFAULT_SEED)]
+        seed: u32,
+    },
 }
 
 fn run_uninstall() -> Result<(), Box<dyn std::error::Error>> {
@@ -123,8 +222,19 @@ fn run_uninstall() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// The runtime is sized for the CPUs the process may use (its cgroup quota
+/// in a container), not the host's cores.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let limits = flapjack::ResourceLimits::global();
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(limits.worker_threads())
+        .max_blocking_threads(limits.blocking_threads())
+        .enable_all()
+        .build()?
+        .block_on(run())
+}
+
+async fn run() -> Result<(), Box<dyn std::error::Error>> {
     let cmd = Cli::command();
     let matches = cmd.get_matches();
     let cli = Cli::from_arg_matches(&matches)?;
@@ -136,6 +246,88 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .map_err(|msg| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg))?;
             run_reset_admin_key(&data_dir)
         }
+        Some(Command::Crawl {
+            config,
+            server,
+            api_key,
+        }) => run_crawl(&config, &server, api_key).await,
+        Some(Command::Export {
+            ref index,
+            ref out,
+            include_vectors,
+        }) => {
+            let data_dir = resolve_data_dir(&cli, &matches)
+                .map_err(|msg| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg))?;
+            run_export(&data_dir, index, out, include_vectors)
+        }
+        Some(Command::Import {
+            ref file,
+            ref index,
+        }) => {
+            let data_dir = resolve_data_dir(&cli, &matches)
+                .map_err(|msg| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg))?;
+            run_import(&data_dir, file, index.as_deref()).await
+        }
+        Some(Command::Bench {
+            target,
+            index,
+            scenario,
+            qps,
+            duration,
+            concurrency,
+            write_ratio,
+            batch_size,
+            preset,
+            seed,
+            json,
+            api_key,
+        }) => {
+            let opts = flapjack_http::bench::BenchOptions {
+                target,
+                api_key,
+                index,
+                scenario,
+                qps,
+                duration: std::time::Duration::from_secs(duration),
+                concurrency,
+                write_ratio,
+                batch_size,
+                preset,
+                seed,
+            };
+            run_bench(&opts, json).await
+        }
+        Some(Command::Seed {
+            ref index,
+            preset,
+            documents,
+            analytics_days,
+            ref experiment,
+            variant_ctr_lift,
+            seed,
+        }) => {
+            let data_dir = resolve_data_dir(&cli, &matches)
+                .map_err(|msg| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg))?;
+            let preset = preset
+                .or_else(|| flapjack::fixtures::Preset::for_index(index))
+                .unwrap_or(flapjack::fixtures::Preset::Ecommerce);
+            let fixture = flapjack::fixtures::analytics::AnalyticsFixture {
+                days: analytics_days,
+                seed,
+                preset: Some(preset),
+                documents: (documents > 0).then_some(documents),
+                experiment: None,
+            };
+            run_seed(
+                &data_dir,
+                index,
+                preset,
+                fixture,
+                experiment.as_deref(),
+                variant_ctr_lift,
+            )
+            .await
+        }
         None => {
             let runtime = resolve_runtime_config(&cli, &matches)
                 .map_err(|msg| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg))?;
@@ -162,6 +354,221 @@ fn run_reset_admin_key(data_dir: &str) -> Result<(), Box<dyn std::error::Error>>
     }
 }
 
+async fn run_crawl(
+    config: &std::path::Path,
+    server: &str,
+    api_key: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match flapjack_http::crawler::crawl_to_server(config, server, api_key).await {
+        Ok(counts) => {
+            println!(
+                "Indexed {} pages ({} fetched, {} skipped, {} errors)",
+                counts.pages_indexed, counts.pages_fetched, counts.pages_skipped, counts.errors
+            );
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("ERROR: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_export(
+    data_dir: &str,
+    index: &str,
+    out: &std::path::Path,
+    include_vectors: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let manager = flapjack::IndexManager::new(data_dir);
+    let result = std::fs::File::create(out)
+        .map_err(flapjack::FlapjackError::from)
+        .and_then(|file| {
+            flapjack::index::archive::export_index(
+                &manager,
+                index,
+                std::io::BufWriter::new(file),
+                include_vectors,
+            )
+        });
+    match result {
+        Ok(manifest) => {
+            println!(
+                "Exported {} documents ({} vectors) of '{}' to {}",
+                manifest.documents,
+                manifest.vectors,
+                index,
+                out.display()
+            );
+            Ok(())
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(out);
+            eprintln!("ERROR: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn run_import(
+    data_dir: &str,
+    file: &std::path::Path,
+    index: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let manager = flapjack::IndexManager::new(data_dir);
+    let reader = std::fs::File::open(file)
+        .map_err(flapjack::FlapjackError::from)
+        .and_then(|f| flapjack::index::archive::ArchiveReader::open(std::io::BufReader::new(f)));
+    let result = match reader {
+        Ok(reader) => {
+            let index = index.unwrap_or(&reader.manifest.index_name).to_string();
+            reader.import_into(&manager, &index).await
+        }
+        Err(e) => Err(e),
+    };
+    manager.graceful_shutdown().await;
+    match result {
+        Ok(summary) => {
+            println!(
+                "Imported {} documents ({} vectors), {} synonyms and {} rules into '{}'",
+                summary.documents,
+                summary.vectors,
+                summary.synonyms,
+                summary.rules,
+                summary.index_name
+            );
+            if summary.skipped > 0 {
+                eprintln!(
+                    "WARNING: skipped {} synonyms or rules this version cannot read",
+                    summary.skipped
+                );
+            }
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("ERROR: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn run_bench(
+    opts: &flapjack_http::bench::BenchOptions,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match flapjack_http::bench::run(opts).await {
+        Ok(report) if json => {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            Ok(())
+        }
+        Ok(report) => {
+            print!("{}", report.summary());
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("ERROR: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn run_seed(
+    data_dir: &str,
+    index: &str,
+    preset: flapjack::fixtures::Preset,
+    mut fixture: flapjack::fixtures::analytics::AnalyticsFixture,
+    experiment: Option<&str>,
+    variant_ctr_lift: f64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use flapjack::fixtures::{analytics, corpus};
+
+    if let Some(id) = experiment {
+        let store =
+            flapjack::experiments::store::ExperimentStore::new(std::path::Path::new(data_dir))
+                .map_err(|e| e.to_string())?;
+        let experiment = store
+            .get(id)
+            .map_err(|e| format!("experiment '{}': {}", id, e))?;
+        if experiment.index_name != index {
+            eprintln!(
+                "ERROR: experiment '{}' runs on '{}', not '{}'",
+                id, experiment.index_name, index
+            );
+            std::process::exit(1);
+        }
+        if experiment.interleaving == Some(true) {
+            eprintln!("ERROR: interleaving experiments cannot be seeded");
+            std::process::exit(1);
+        }
+        fixture.experiment = Some(analytics::ExperimentFixture {
+            experiment,
+            variant_ctr_lift,
+        });
+    }
+
+    let manager = flapjack::IndexManager::new(data_dir);
+    let documents = fixture.documents.unwrap_or(0);
+    let result: flapjack::Result<()> = async {
+        if documents == 0 {
+            return Ok(());
+        }
+        manager.create_tenant(index)?;
+        corpus::template(preset).apply(&manager, index)?;
+        let mut batch = Vec::with_capacity(1000);
+        for record in corpus::records(preset, documents, fixture.seed) {
+            batch.push(flapjack::Document::from_json(&record)?);
+            if batch.len() == 1000 {
+                manager
+                    .add_documents_sync(index, std::mem::take(&mut batch))
+                    .await?;
+            }
+        }
+        if !batch.is_empty() {
+            manager.add_documents_sync(index, batch).await?;
+        }
+        Ok(())
+    }
+    .await;
+    manager.graceful_shutdown().await;
+    if let Err(e) = result {
+        eprintln!("ERROR: {}", e);
+        std::process::exit(1);
+    }
+    if documents > 0 {
+        println!(
+            "Indexed {} {} documents into '{}'",
+            documents, preset, index
+        );
+    }
+
+    if fixture.days > 0 {
+        std::env::set_var("FLAPJACK_DATA_DIR", data_dir);
+        let config = flapjack::analytics::config::AnalyticsConfig::from_env();
+        match analytics::generate_analytics(&config, index, &fixture) {
+            Ok(seeded) => {
+                println!(
+                    "Generated {} days of analytics: {} searches, {} clicks, {} conversions",
+                    seeded.days,
+                    seeded.total_searches,
+                    seeded.total_clicks,
+                    seeded.total_conversions
+                );
+                if fixture.experiment.is_some() {
+                    println!(
+                        "Experiment exposures: {} control, {} variant",
+                        seeded.control_searches, seeded.variant_searches
+                    );
+                }
+            }
+            Err(e) => {
+                eprintln!("ERROR: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    Ok(())
+}
+
 struct RuntimeConfig {
     data_dir: String,
     bind_addr: String,
@@ -4,9 +4,19 @@ use tokio::sync::Notify;
 
 use super::aggregation::QueryAggregator;
 use super::config::AnalyticsConfig;
+use super::dead_letter::{DeadLetterQueue, RejectReason, RejectedEvent};
 use super::schema::{InsightEvent, SearchEvent};
 use super::writer;
 
+/// How long issued queryIDs are remembered for click correlation.
+const QUERY_ID_TTL_MS: i64 = 3_600_000;
+/// Oldest event timestamp accepted by the Insights API.
+const MAX_EVENT_AGE_MS: i64 = 4 * 24 * 60 * 60 * 1000;
+/// Tolerated client clock skew for event timestamps in the future.
+const MAX_EVENT_FUTURE_SKEW_MS: i64 = 15 * 60 * 1000;
+/// Maximum objectIDs per Insights event.
+const MAX_OBJECT_IDS: usize = 20;
+
 /// Central analytics event collector.
 ///
 /// Buffers events in memory and flushes to Parquet files either on a timer
@@ -19,7 +29,12 @@ pub struct AnalyticsCollector {
     aggregator: QueryAggregator,
     /// queryID -> (query, index_name, timestamp_ms) for correlating clicks with searches
     query_id_cache: DashMap<String, QueryIdEntry>,
+    /// Recently rejected Insights events, for `GET /1/events/rejected`.
+    dead_letters: DeadLetterQueue,
+    started_at_ms: i64,
     shutdown: Notify,
+    /// Last failed Parquet write, cleared by the next successful one.
+    flush_error: Mutex<Option<String>>,
 }
 
 #[derive(Clone)]
@@ -37,7 +52,10 @@ impl AnalyticsCollector {
             insight_buffer: Mutex::new(Vec::with_capacity(256)),
             aggregator: QueryAggregator::new(30),
             query_id_cache: DashMap::new(),
+            dead_letters: DeadLetterQueue::default(),
+            started_at_ms: chrono::Utc::now().timestamp_millis(),
             shutdown: Notify::new(),
+            flush_error: Mutex::new(None),
         })
     }
 
@@ -46,10 +64,14 @@ impl AnalyticsCollector {
     }
 
     /// Record a search event. Called from the search path after results are computed.
-    pub fn record_search(&self, event: SearchEvent) {
+    ///
+    /// PII scrubbing (see [`AnalyticsConfig::pii`]) is applied first, so
+    /// redacted values never reach the queryID cache or Parquet files.
+    pub fn record_search(&self, mut event: SearchEvent) {
         if !self.config.enabled {
             return;
         }
+        self.config.pii.scrub_search(&mut event);
 
         // Store queryID mapping for click correlation
         if let Some(ref qid) = event.query_id {
@@ -103,6 +125,78 @@ impl AnalyticsCollector {
         }
     }
 
+    /// Check an incoming Insights event before it is recorded.
+    ///
+    /// On top of [`InsightEvent::validate`], rejects events timestamped more
+    /// than 15 minutes in the future and events whose queryID was not issued
+    /// by a search in the last hour. The queryID check is skipped while
+    /// analytics is disabled and during the first hour after startup, when
+    /// the queryID cache cannot be complete yet.
+    pub fn check_insight(
+        &self,
+        event: &InsightEvent,
+        now_ms: i64,
+    ) -> Result<(), (RejectReason, String)> {
+        let nb_object_ids = event.effective_object_ids().len();
+        if nb_object_ids > MAX_OBJECT_IDS {
+            return Err((
+                RejectReason::TooManyObjectIds,
+                format!(
+                    "objectIDs has {} items, maximum is {}",
+                    nb_object_ids, MAX_OBJECT_IDS
+                ),
+            ));
+        }
+        if let Some(ts) = event.timestamp {
+            if ts < now_ms - MAX_EVENT_AGE_MS {
+                return Err((
+                    RejectReason::TimestampSkew,
+                    "timestamp must be within the last 4 days".to_string(),
+                ));
+            }
+            if ts > now_ms + MAX_EVENT_FUTURE_SKEW_MS {
+                return Err((
+                    RejectReason::TimestampSkew,
+                    "timestamp is more than 15 minutes in the future".to_string(),
+                ));
+            }
+        }
+        event.validate().map_err(|e| (RejectReason::Invalid, e))?;
+        if let Some(ref qid) = event.query_id {
+            let cache_complete = now_ms - self.started_at_ms >= QUERY_ID_TTL_MS;
+            if self.config.enabled && cache_complete && !self.query_id_cache.contains_key(qid) {
+                return Err((
+                    RejectReason::UnknownQueryId,
+                    format!(
+                        "queryID {} was not returned by a search in the last hour",
+                        qid
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Send a rejected Insights event to the dead-letter queue.
+    pub fn reject_insight(&self, event: InsightEvent, reason: RejectReason, message: String) {
+        tracing::debug!(
+            "[analytics] Rejected {} event for {}: {}",
+            event.event_type,
+            event.index,
+            message
+        );
+        self.dead_letters.push(RejectedEvent {
+            received_at: chrono::Utc::now().timestamp_millis(),
+            reason,
+            message,
+            event,
+        });
+    }
+
+    pub fn dead_letters(&self) -> &DeadLetterQueue {
+        &self.dead_letters
+    }
+
     /// Look up a queryID to correlate with the original search.
     pub fn lookup_query_id(&self, query_id: &str) -> Option<QueryIdEntry> {
         self.query_id_cache.get(query_id).map(|e| e.clone())
@@ -137,7 +231,10 @@ impl AnalyticsCollector {
                     index_name,
                     e
                 );
+                *self.flush_error.lock().unwrap() =
+                    Some(format!("search events for {}: {}", index_name, e));
             } else {
+                *self.flush_error.lock().unwrap() = None;
                 tracing::debug!(
                     "[analytics] Flushed {} search events for {}",
                     index_events.len(),
@@ -172,7 +269,10 @@ impl AnalyticsCollector {
                     index_name,
                     e
                 );
+                *self.flush_error.lock().unwrap() =
+                    Some(format!("insight events for {}: {}", index_name, e));
             } else {
+                *self.flush_error.lock().unwrap() = None;
                 tracing::debug!(
                     "[analytics] Flushed {} insight events for {}",
                     index_events.len(),
@@ -182,6 +282,11 @@ impl AnalyticsCollector {
         }
     }
 
+    /// The last event write that failed, unless a later write succeeded.
+    pub fn flush_error(&self) -> Option<String> {
+        self.flush_error.lock().unwrap().clone()
+    }
+
     /// Flush all buffers (called at shutdown or periodically).
     pub fn flush_all(&self) {
         self.flush_searches();
@@ -217,7 +322,7 @@ impl AnalyticsCollector {
 
     /// Evict queryID entries older than 1 hour.
     fn evict_old_query_ids(&self) {
-        let cutoff = chrono::Utc::now().timestamp_millis() - 3_600_000;
+        let cutoff = chrono::Utc::now().timestamp_millis() - QUERY_ID_TTL_MS;
         self.query_id_cache.retain(|_, v| v.timestamp_ms > cutoff);
     }
 }
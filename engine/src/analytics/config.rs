@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use super::pii::PiiScrubConfig;
+
 /// Configuration for the analytics subsystem, loaded from environment variables.
 #[derive(Debug, Clone)]
 pub struct AnalyticsConfig {
@@ -13,6 +15,8 @@ pub struct AnalyticsConfig {
     pub flush_size: usize,
     /// Delete Parquet files older than this many days.
     pub retention_days: u32,
+    /// PII redaction applied to search events before they are stored.
+    pub pii: PiiScrubConfig,
 }
 
 impl AnalyticsConfig {
@@ -40,6 +44,7 @@ impl AnalyticsConfig {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(90),
+            pii: PiiScrubConfig::from_env(),
         }
     }
 
@@ -51,6 +56,7 @@ impl AnalyticsConfig {
             flush_interval_secs: 3600,
             flush_size: 100_000,
             retention_days: 90,
+            pii: PiiScrubConfig::default(),
         }
     }
 
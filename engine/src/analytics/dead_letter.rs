@@ -0,0 +1,194 @@
+//! Dead-letter queue for rejected Insights API events.
+//!
+//! Events that fail validation are not silently dropped: the most recent
+//! rejects are kept in a bounded in-memory buffer (queryable through
+//! `GET /1/events/rejected`) and counted per reason for `/metrics`, so broken
+//! client integrations show up instead of quietly skewing click-through rates.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use super::schema::InsightEvent;
+
+/// Number of rejected events retained for inspection.
+pub const DEAD_LETTER_CAPACITY: usize = 10_000;
+
+/// Why an insight event was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RejectReason {
+    /// Failed the Insights API schema checks (event type, name, token, ...).
+    Invalid,
+    /// More objectIDs than the Insights API allows.
+    TooManyObjectIds,
+    /// Timestamp too far in the past or in the future.
+    TimestampSkew,
+    /// queryID was not issued by a recent search on this server.
+    #[serde(rename = "unknownQueryID")]
+    UnknownQueryId,
+}
+
+impl RejectReason {
+    pub const ALL: [RejectReason; 4] = [
+        RejectReason::Invalid,
+        RejectReason::TooManyObjectIds,
+        RejectReason::TimestampSkew,
+        RejectReason::UnknownQueryId,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectReason::Invalid => "invalid",
+            RejectReason::TooManyObjectIds => "tooManyObjectIds",
+            RejectReason::TimestampSkew => "timestampSkew",
+            RejectReason::UnknownQueryId => "unknownQueryID",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.as_str() == s)
+    }
+
+    fn slot(&self) -> usize {
+        match self {
+            RejectReason::Invalid => 0,
+            RejectReason::TooManyObjectIds => 1,
+            RejectReason::TimestampSkew => 2,
+            RejectReason::UnknownQueryId => 3,
+        }
+    }
+}
+
+/// An event that was refused by the Insights API.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectedEvent {
+    pub received_at: i64,
+    pub reason: RejectReason,
+    pub message: String,
+    pub event: InsightEvent,
+}
+
+/// Bounded buffer of rejected events plus lifetime per-reason counters.
+pub struct DeadLetterQueue {
+    capacity: usize,
+    entries: Mutex<VecDeque<RejectedEvent>>,
+    rejected: [AtomicU64; 4],
+}
+
+impl DeadLetterQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: Mutex::new(VecDeque::new()),
+            rejected: Default::default(),
+        }
+    }
+
+    /// Record a rejected event, evicting the oldest entry when full.
+    pub fn push(&self, entry: RejectedEvent) {
+        self.rejected[entry.reason.slot()].fetch_add(1, Ordering::Relaxed);
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Most recent rejects first, optionally filtered by index and reason.
+    pub fn recent(
+        &self,
+        index: Option<&str>,
+        reason: Option<RejectReason>,
+        limit: usize,
+    ) -> Vec<RejectedEvent> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .iter()
+            .rev()
+            .filter(|e| index.is_none_or(|i| e.event.index == i))
+            .filter(|e| reason.is_none_or(|r| e.reason == r))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Total events rejected for `reason` since startup (not bounded by capacity).
+    pub fn rejected_total(&self, reason: RejectReason) -> u64 {
+        self.rejected[reason.slot()].load(Ordering::Relaxed)
+    }
+}
+
+impl Default for DeadLetterQueue {
+    fn default() -> Self {
+        Self::new(DEAD_LETTER_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(index: &str, reason: RejectReason, received_at: i64) -> RejectedEvent {
+        let event: InsightEvent = serde_json::from_value(serde_json::json!({
+            "eventType": "click",
+            "eventName": "Clicked",
+            "index": index,
+            "userToken": "u1",
+            "objectIDs": ["a"]
+        }))
+        .unwrap();
+        RejectedEvent {
+            received_at,
+            reason,
+            message: "bad".to_string(),
+            event,
+        }
+    }
+
+    #[test]
+    fn keeps_most_recent_entries_and_counts_everything() {
+        let dlq = DeadLetterQueue::new(2);
+        dlq.push(rejected("a", RejectReason::Invalid, 1));
+        dlq.push(rejected("a", RejectReason::UnknownQueryId, 2));
+        dlq.push(rejected("b", RejectReason::UnknownQueryId, 3));
+
+        let recent = dlq.recent(None, None, 10);
+        assert_eq!(
+            recent.iter().map(|e| e.received_at).collect::<Vec<_>>(),
+            vec![3, 2]
+        );
+        assert_eq!(dlq.rejected_total(RejectReason::Invalid), 1);
+        assert_eq!(dlq.rejected_total(RejectReason::UnknownQueryId), 2);
+        assert_eq!(dlq.rejected_total(RejectReason::TimestampSkew), 0);
+    }
+
+    #[test]
+    fn recent_filters_by_index_and_reason() {
+        let dlq = DeadLetterQueue::default();
+        dlq.push(rejected("a", RejectReason::Invalid, 1));
+        dlq.push(rejected("b", RejectReason::TimestampSkew, 2));
+        dlq.push(rejected("a", RejectReason::TimestampSkew, 3));
+
+        assert_eq!(dlq.recent(Some("a"), None, 10).len(), 2);
+        let skew_a = dlq.recent(Some("a"), Some(RejectReason::TimestampSkew), 10);
+        assert_eq!(skew_a.len(), 1);
+        assert_eq!(skew_a[0].received_at, 3);
+        assert_eq!(dlq.recent(None, None, 1).len(), 1);
+    }
+
+    #[test]
+    fn reason_round_trips_through_its_name() {
+        for reason in RejectReason::ALL {
+            assert_eq!(RejectReason::parse(reason.as_str()), Some(reason));
+            assert_eq!(
+                serde_json::to_value(reason).unwrap(),
+                serde_json::json!(reason.as_str())
+            );
+        }
+        assert_eq!(RejectReason::parse("nope"), None);
+    }
+}
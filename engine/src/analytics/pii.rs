@@ -0,0 +1,184 @@
+//! PII scrubbing for search events.
+//!
+//! Applied by the collector before events are buffered, so redacted values
+//! never reach the queryID cache or the Parquet writer. Scrubbing is opt-in:
+//!
+//! - `FLAPJACK_ANALYTICS_REDACT_PII=true` replaces email addresses and phone
+//!   numbers in the query and filters with `[email]` / `[phone]`.
+//! - `FLAPJACK_ANALYTICS_TRUNCATE_IPS=true` zeroes the last octet of IPv4
+//!   addresses and keeps only the /48 prefix of IPv6 addresses.
+
+use std::borrow::Cow;
+use std::net::IpAddr;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use super::schema::SearchEvent;
+
+pub const EMAIL_PLACEHOLDER: &str = "[email]";
+pub const PHONE_PLACEHOLDER: &str = "[phone]";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PiiScrubConfig {
+    pub redact_emails: bool,
+    pub redact_phone_numbers: bool,
+    pub truncate_ips: bool,
+}
+
+fn env_flag(name: &str) -> bool {
+    std::env::var(name)
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+}
+
+fn email_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}").unwrap()
+    })
+}
+
+/// Digit runs with optional separators; candidates are confirmed by digit count.
+fn phone_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\+?\(?\d[\d\s().-]{6,}\d").unwrap())
+}
+
+/// True for 9-15 digit sequences written like a phone number: with a leading
+/// `+` or with separators. Bare digit runs (SKUs, order numbers) are kept.
+fn looks_like_phone(candidate: &str) -> bool {
+    let digits = candidate.chars().filter(char::is_ascii_digit).count();
+    let formatted = candidate.starts_with('+')
+        || candidate
+            .chars()
+            .any(|c| matches!(c, ' ' | '-' | '.' | '(' | ')'));
+    (9..=15).contains(&digits) && formatted
+}
+
+impl PiiScrubConfig {
+    pub fn from_env() -> Self {
+        let redact = env_flag("FLAPJACK_ANALYTICS_REDACT_PII");
+        Self {
+            redact_emails: redact,
+            redact_phone_numbers: redact,
+            truncate_ips: env_flag("FLAPJACK_ANALYTICS_TRUNCATE_IPS"),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.redact_emails || self.redact_phone_numbers || self.truncate_ips
+    }
+
+    /// Redact emails and phone numbers in free text.
+    pub fn scrub_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut out = Cow::Borrowed(text);
+        if self.redact_emails && email_regex().is_match(&out) {
+            out = Cow::Owned(
+                email_regex()
+                    .replace_all(&out, EMAIL_PLACEHOLDER)
+                    .into_owned(),
+            );
+        }
+        if self.redact_phone_numbers {
+            let replaced = phone_regex()
+                .replace_all(&out, |caps: &regex::Captures| {
+                    if looks_like_phone(&caps[0]) {
+                        PHONE_PLACEHOLDER.to_string()
+                    } else {
+                        caps[0].to_string()
+                    }
+                })
+                .into_owned();
+            if replaced != out {
+                out = Cow::Owned(replaced);
+            }
+        }
+        out
+    }
+
+    /// Scrub a search event in place.
+    pub fn scrub_search(&self, event: &mut SearchEvent) {
+        if !self.is_enabled() {
+            return;
+        }
+        if let Cow::Owned(q) = self.scrub_text(&event.query) {
+            event.query = q;
+        }
+        if let Some(filters) = event.filters.as_mut() {
+            if let Cow::Owned(f) = self.scrub_text(filters) {
+                *filters = f;
+            }
+        }
+        if self.truncate_ips {
+            event.user_ip = event.user_ip.as_deref().and_then(truncate_ip);
+        }
+    }
+}
+
+/// Anonymize an IP address: IPv4 keeps /24, IPv6 keeps /48.
+/// Unparseable values are dropped.
+pub fn truncate_ip(ip: &str) -> Option<String> {
+    match ip.trim().parse::<IpAddr>().ok()? {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            Some(format!("{}.{}.{}.0", a, b, c))
+        }
+        IpAddr::V6(v6) => {
+            let s = v6.segments();
+            Some(format!("{:x}:{:x}:{:x}::", s[0], s[1], s[2]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all() -> PiiScrubConfig {
+        PiiScrubConfig {
+            redact_emails: true,
+            redact_phone_numbers: true,
+            truncate_ips: true,
+        }
+    }
+
+    #[test]
+    fn redacts_emails() {
+        assert_eq!(
+            all().scrub_text("orders for jane.doe+shop@example.co.uk please"),
+            "orders for [email] please"
+        );
+    }
+
+    #[test]
+    fn redacts_formatted_phone_numbers_only() {
+        let cfg = all();
+        assert_eq!(cfg.scrub_text("call +1 (555) 123-4567"), "call [phone]");
+        assert_eq!(cfg.scrub_text("+447700900123 "), "[phone] ");
+        assert_eq!(cfg.scrub_text("07700 900 123 store"), "[phone] store");
+        // Bare digit runs and short numbers are not phone numbers.
+        assert_eq!(cfg.scrub_text("sku 12345678901"), "sku 12345678901");
+        assert_eq!(cfg.scrub_text("iphone 15 128 gb"), "iphone 15 128 gb");
+    }
+
+    #[test]
+    fn disabled_config_leaves_text_untouched() {
+        let cfg = PiiScrubConfig::default();
+        assert!(!cfg.is_enabled());
+        assert!(matches!(
+            cfg.scrub_text("jane@example.com"),
+            Cow::Borrowed("jane@example.com")
+        ));
+    }
+
+    #[test]
+    fn truncates_ips() {
+        assert_eq!(truncate_ip("203.0.113.77").as_deref(), Some("203.0.113.0"));
+        assert_eq!(
+            truncate_ip("2001:db8:85a3::8a2e:370:7334").as_deref(),
+            Some("2001:db8:85a3::")
+        );
+        assert_eq!(truncate_ip("not-an-ip"), None);
+    }
+}
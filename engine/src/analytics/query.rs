@@ -4,6 +4,7 @@ use std::path::Path;
 use std::sync::Arc;
 
 use super::config::AnalyticsConfig;
+use crate::index::reranking::ObjectEngagement;
 
 /// DataFusion-based analytics query engine.
 ///
@@ -152,6 +153,66 @@ impl AnalyticsQueryEngine {
         }
     }
 
+    /// Per-query search counts by `query_language`, for the searches whose
+    /// language is known.
+    pub async fn search_languages(
+        &self,
+        index_name: &str,
+        start_date: &str,
+        end_date: &str,
+        tags: Option<&str>,
+    ) -> Result<std::collections::HashMap<String, std::collections::HashMap<String, u64>>, String>
+    {
+        use std::collections::HashMap;
+        let dir = self.config.searches_dir(index_name);
+        if !dir.exists() {
+            return Ok(HashMap::new());
+        }
+        let ctx = self.create_session_with_searches(index_name).await?;
+        let start_ms = date_to_start_ms(start_date)?;
+        let end_ms = date_to_end_ms(end_date)?;
+
+        let mut where_clause = format!(
+            "timestamp_ms >= {} AND timestamp_ms <= {} AND query_language IS NOT NULL",
+            start_ms, end_ms
+        );
+        if let Some(t) = tags {
+            let safe = t.replace('\'', "''");
+            where_clause.push_str(&format!(" AND analytics_tags LIKE '%{}%'", safe));
+        }
+        let sql = format!(
+            "SELECT query, query_language, COUNT(*) as count FROM searches \
+             WHERE {} GROUP BY query, query_language",
+            where_clause
+        );
+        // Files written before query languages were recorded lack the column.
+        let rows = match ctx.sql(&sql).await {
+            Ok(df) => {
+                let batches = df
+                    .collect()
+                    .await
+                    .map_err(|e| format!("Exec error: {}", e))?;
+                batches_to_json(&batches)?
+            }
+            Err(_) => Vec::new(),
+        };
+
+        let mut languages: HashMap<String, HashMap<String, u64>> = HashMap::new();
+        for row in &rows {
+            let (Some(query), Some(language)) =
+                (row["query"].as_str(), row["query_language"].as_str())
+            else {
+                continue;
+            };
+            *languages
+                .entry(query.to_string())
+                .or_default()
+                .entry(language.to_string())
+                .or_default() += row["count"].as_u64().unwrap_or(0);
+        }
+        Ok(languages)
+    }
+
     /// Total search count with daily breakdown.
     pub async fn search_count(
         &self,
@@ -949,6 +1010,60 @@ impl AnalyticsQueryEngine {
         }
     }
 
+    /// Per-objectID click and conversion counts, used to train the
+    /// re-ranking model. Events carrying several objectIDs credit each one.
+    pub async fn object_engagement(
+        &self,
+        index_name: &str,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<std::collections::HashMap<String, ObjectEngagement>, String> {
+        let events_ctx = self.create_session_with_events(index_name).await?;
+        let start_ms = date_to_start_ms(start_date)?;
+        let end_ms = date_to_end_ms(end_date)?;
+
+        let sql = format!(
+            "SELECT event_type, object_ids, COUNT(*) as count \
+             FROM events \
+             WHERE timestamp_ms >= {} AND timestamp_ms <= {} \
+             AND event_type IN ('click', 'conversion') \
+             GROUP BY event_type, object_ids",
+            start_ms, end_ms
+        );
+
+        let rows = match events_ctx.sql(&sql).await {
+            Ok(df) => {
+                let batches = df
+                    .collect()
+                    .await
+                    .map_err(|e| format!("Exec error: {}", e))?;
+                batches_to_json(&batches)?
+            }
+            Err(_) => Vec::new(),
+        };
+
+        let mut engagement: std::collections::HashMap<String, ObjectEngagement> =
+            std::collections::HashMap::new();
+        for row in rows {
+            let count = row.get("count").and_then(|v| v.as_u64()).unwrap_or(0);
+            let is_click = row.get("event_type").and_then(|v| v.as_str()) == Some("click");
+            let ids: Vec<String> = row
+                .get("object_ids")
+                .and_then(|v| v.as_str())
+                .and_then(|s| serde_json::from_str(s).ok())
+                .unwrap_or_default();
+            for id in ids {
+                let entry = engagement.entry(id).or_default();
+                if is_click {
+                    entry.clicks += count;
+                } else {
+                    entry.conversions += count;
+                }
+            }
+        }
+        Ok(engagement)
+    }
+
     /// Analytics status (last updated timestamp).
     pub async fn status(&self, index_name: &str) -> Result<serde_json::Value, String> {
         let dir = self.config.searches_dir(index_name);
@@ -1093,6 +1208,299 @@ impl AnalyticsQueryEngine {
         }))
     }
 
+    /// How often query rule `rule_id` fired, with the click-through and
+    /// conversion rates of the tracked searches it fired on.
+    pub async fn rule_stats(
+        &self,
+        index_name: &str,
+        rule_id: &str,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<serde_json::Value, String> {
+        let start_ms = date_to_start_ms(start_date)?;
+        let end_ms = date_to_end_ms(end_date)?;
+
+        // `applied_rules` is a JSON array string, so match the quoted ID.
+        let needle = serde_json::to_string(rule_id)
+            .map_err(|e| e.to_string())?
+            .replace('\'', "''");
+        let search_ctx = self.create_session_with_searches(index_name).await?;
+        let fired_sql = format!(
+            "SELECT CAST(timestamp_ms / 86400000 * 86400000 AS BIGINT) as day_ms, query_id \
+             FROM searches \
+             WHERE timestamp_ms >= {} AND timestamp_ms <= {} \
+             AND strpos(applied_rules, '{}') > 0",
+            start_ms, end_ms, needle
+        );
+        // Files written before rules were tracked have no `applied_rules`.
+        let fired = match search_ctx.sql(&fired_sql).await {
+            Ok(df) => {
+                let batches = df
+                    .collect()
+                    .await
+                    .map_err(|e| format!("Exec error: {}", e))?;
+                batches_to_json(&batches)?
+            }
+            Err(_) => Vec::new(),
+        };
+
+        // queryID -> (clicked, converted)
+        let events_ctx = self.create_session_with_events(index_name).await?;
+        let events_sql = format!(
+            "SELECT query_id, event_type FROM events \
+             WHERE timestamp_ms >= {} AND query_id IS NOT NULL \
+             AND event_type IN ('click', 'conversion')",
+            start_ms
+        );
+        let mut engaged: std::collections::HashMap<String, (bool, bool)> =
+            std::collections::HashMap::new();
+        if let Ok(df) = events_ctx.sql(&events_sql).await {
+            let batches = df
+                .collect()
+                .await
+                .map_err(|e| format!("Exec error: {}", e))?;
+            for row in batches_to_json(&batches)? {
+                let (Some(qid), Some(kind)) = (
+                    row.get("query_id").and_then(|v| v.as_str()),
+                    row.get("event_type").and_then(|v| v.as_str()),
+                ) else {
+                    continue;
+                };
+                let entry = engaged.entry(qid.to_string()).or_default();
+                match kind {
+                    "click" => entry.0 = true,
+                    _ => entry.1 = true,
+                }
+            }
+        }
+
+        // day_ms -> [fires, tracked, clicked, converted]
+        let mut daily: std::collections::BTreeMap<i64, [i64; 4]> =
+            std::collections::BTreeMap::new();
+        for row in &fired {
+            let Some(ms) = row.get("day_ms").and_then(|v| v.as_i64()) else {
+                continue;
+            };
+            let day = daily.entry(ms).or_default();
+            day[0] += 1;
+            if let Some(qid) = row.get("query_id").and_then(|v| v.as_str()) {
+                day[1] += 1;
+                let (clicked, converted) = engaged.get(qid).copied().unwrap_or_default();
+                day[2] += clicked as i64;
+                day[3] += converted as i64;
+            }
+        }
+
+        let rate = |n: i64, d: i64| {
+            if d > 0 {
+                (n as f64 / d as f64 * 1000.0).round() / 1000.0
+            } else {
+                0.0
+            }
+        };
+        let mut totals = [0i64; 4];
+        let dates: Vec<serde_json::Value> = daily
+            .iter()
+            .map(|(ms, day)| {
+                for (total, n) in totals.iter_mut().zip(day) {
+                    *total += n;
+                }
+                serde_json::json!({
+                    "date": ms_to_date_string(*ms),
+                    "count": day[0],
+                    "trackedSearchCount": day[1],
+                    "clickCount": day[2],
+                    "conversionCount": day[3]
+                })
+            })
+            .collect();
+
+        Ok(serde_json::json!({
+            "ruleID": rule_id,
+            "count": totals[0],
+            "trackedSearchCount": totals[1],
+            "clickCount": totals[2],
+            "conversionCount": totals[3],
+            "clickThroughRate": rate(totals[2], totals[1]),
+            "conversionRate": rate(totals[3], totals[1]),
+            "dates": dates
+        }))
+    }
+
+    /// Impressions of a campaign (searches one of its rules fired in) and
+    /// how often its pinned objects were clicked or converted from them.
+    /// Campaign rules are logged as `<campaignID>/<ruleID>`.
+    pub async fn campaign_stats(
+        &self,
+        index_name: &str,
+        campaign_id: &str,
+        pinned_object_ids: &[String],
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<serde_json::Value, String> {
+        let start_ms = date_to_start_ms(start_date)?;
+        let end_ms = date_to_end_ms(end_date)?;
+
+        // Match the opening quote and the separator of any of its rule IDs.
+        let needle = format!("\"{}/", campaign_id).replace('\'', "''");
+        let search_ctx = self.create_session_with_searches(index_name).await?;
+        let fired_sql = format!(
+            "SELECT CAST(timestamp_ms / 86400000 * 86400000 AS BIGINT) as day_ms, query_id \
+             FROM searches \
+             WHERE timestamp_ms >= {} AND timestamp_ms <= {} \
+             AND strpos(applied_rules, '{}') > 0",
+            start_ms, end_ms, needle
+        );
+        let fired = match search_ctx.sql(&fired_sql).await {
+            Ok(df) => {
+                let batches = df
+                    .collect()
+                    .await
+                    .map_err(|e| format!("Exec error: {}", e))?;
+                batches_to_json(&batches)?
+            }
+            Err(_) => Vec::new(),
+        };
+
+        // queryID -> (pinned object clicked, pinned object converted)
+        let pinned: std::collections::HashSet<&str> =
+            pinned_object_ids.iter().map(|s| s.as_str()).collect();
+        let events_ctx = self.create_session_with_events(index_name).await?;
+        let events_sql = format!(
+            "SELECT query_id, event_type, object_ids FROM events \
+             WHERE timestamp_ms >= {} AND query_id IS NOT NULL \
+             AND event_type IN ('click', 'conversion')",
+            start_ms
+        );
+        let mut engaged: std::collections::HashMap<String, (bool, bool)> =
+            std::collections::HashMap::new();
+        if let Ok(df) = events_ctx.sql(&events_sql).await {
+            let batches = df
+                .collect()
+                .await
+                .map_err(|e| format!("Exec error: {}", e))?;
+            for row in batches_to_json(&batches)? {
+                let (Some(qid), Some(kind), Some(object_ids)) = (
+                    row.get("query_id").and_then(|v| v.as_str()),
+                    row.get("event_type").and_then(|v| v.as_str()),
+                    row.get("object_ids").and_then(|v| v.as_str()),
+                ) else {
+                    continue;
+                };
+                let object_ids: Vec<String> = serde_json::from_str(object_ids).unwrap_or_default();
+                if !object_ids.iter().any(|id| pinned.contains(id.as_str())) {
+                    continue;
+                }
+                let entry = engaged.entry(qid.to_string()).or_default();
+                match kind {
+                    "click" => entry.0 = true,
+                    _ => entry.1 = true,
+                }
+            }
+        }
+
+        // day_ms -> [impressions, tracked, clicked, converted]
+        let mut daily: std::collections::BTreeMap<i64, [i64; 4]> =
+            std::collections::BTreeMap::new();
+        for row in &fired {
+            let Some(ms) = row.get("day_ms").and_then(|v| v.as_i64()) else {
+                continue;
+            };
+            let day = daily.entry(ms).or_default();
+            day[0] += 1;
+            if let Some(qid) = row.get("query_id").and_then(|v| v.as_str()) {
+                day[1] += 1;
+                let (clicked, converted) = engaged.get(qid).copied().unwrap_or_default();
+                day[2] += clicked as i64;
+                day[3] += converted as i64;
+            }
+        }
+
+        let rate = |n: i64, d: i64| {
+            if d > 0 {
+                (n as f64 / d as f64 * 1000.0).round() / 1000.0
+            } else {
+                0.0
+            }
+        };
+        let mut totals = [0i64; 4];
+        let dates: Vec<serde_json::Value> = daily
+            .iter()
+            .map(|(ms, day)| {
+                for (total, n) in totals.iter_mut().zip(day) {
+                    *total += n;
+                }
+                serde_json::json!({
+                    "date": ms_to_date_string(*ms),
+                    "impressions": day[0],
+                    "trackedSearchCount": day[1],
+                    "clickCount": day[2],
+                    "conversionCount": day[3]
+                })
+            })
+            .collect();
+
+        Ok(serde_json::json!({
+            "campaignID": campaign_id,
+            "impressions": totals[0],
+            "trackedSearchCount": totals[1],
+            "clickCount": totals[2],
+            "conversionCount": totals[3],
+            "clickThroughRate": rate(totals[2], totals[1]),
+            "conversionRate": rate(totals[3], totals[1]),
+            "dates": dates
+        }))
+    }
+
+    /// Per-synonym `(triggered, matched)` search counts: how many searches
+    /// ran a synonym's expansion, and how many of those it added hits to.
+    pub async fn synonym_usage(
+        &self,
+        index_name: &str,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<std::collections::HashMap<String, (i64, i64)>, String> {
+        let start_ms = date_to_start_ms(start_date)?;
+        let end_ms = date_to_end_ms(end_date)?;
+
+        let ctx = self.create_session_with_searches(index_name).await?;
+        let sql = format!(
+            "SELECT synonyms_triggered, synonyms_matched FROM searches \
+             WHERE timestamp_ms >= {} AND timestamp_ms <= {} \
+             AND synonyms_triggered IS NOT NULL",
+            start_ms, end_ms
+        );
+        // Files written before synonyms were tracked have neither column.
+        let rows = match ctx.sql(&sql).await {
+            Ok(df) => {
+                let batches = df
+                    .collect()
+                    .await
+                    .map_err(|e| format!("Exec error: {}", e))?;
+                batches_to_json(&batches)?
+            }
+            Err(_) => Vec::new(),
+        };
+
+        let ids = |row: &serde_json::Value, column: &str| -> Vec<String> {
+            row.get(column)
+                .and_then(|v| v.as_str())
+                .and_then(|s| serde_json::from_str(s).ok())
+                .unwrap_or_default()
+        };
+        let mut usage: std::collections::HashMap<String, (i64, i64)> =
+            std::collections::HashMap::new();
+        for row in &rows {
+            for id in ids(row, "synonyms_triggered") {
+                usage.entry(id).or_default().0 += 1;
+            }
+            for id in ids(row, "synonyms_matched") {
+                usage.entry(id).or_default().1 += 1;
+            }
+        }
+        Ok(usage)
+    }
+
     /// Searches with no clicks (cross-references events table).
     pub async fn no_click_searches(
         &self,
@@ -23,10 +23,19 @@ pub struct SearchEvent {
     pub experiment_id: Option<String>,
     pub variant_id: Option<String>,
     pub assignment_method: Option<String>,
+    /// IDs of the query rules that fired, in the order they were applied.
+    pub applied_rules: Vec<String>,
+    /// Synonyms whose expanded query was run.
+    pub synonyms_triggered: Vec<String>,
+    /// Synonyms whose expanded query added matches.
+    pub synonyms_matched: Vec<String>,
+    /// Language of the query: the first of the request's `queryLanguages`,
+    /// else detected from the query text when that is reliable.
+    pub query_language: Option<String>,
 }
 
 /// Sent by client via Insights API (click, conversion, view events).
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InsightEvent {
     pub event_type: String,
@@ -39,9 +48,9 @@ pub struct InsightEvent {
     pub authenticated_user_token: Option<String>,
     #[serde(default)]
     pub query_id: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub object_ids: Vec<String>,
-    #[serde(default, rename = "objectIDs")]
+    #[serde(default, rename = "objectIDs", skip_serializing_if = "Vec::is_empty")]
     pub object_ids_alt: Vec<String>,
     #[serde(default)]
     pub positions: Option<Vec<u32>>,
@@ -138,6 +147,10 @@ pub fn search_event_schema() -> Arc<Schema> {
         Field::new("experiment_id", DataType::Utf8, true),
         Field::new("variant_id", DataType::Utf8, true),
         Field::new("assignment_method", DataType::Utf8, true),
+        Field::new("applied_rules", DataType::Utf8, true), // JSON array string
+        Field::new("synonyms_triggered", DataType::Utf8, true), // JSON array string
+        Field::new("synonyms_matched", DataType::Utf8, true), // JSON array string
+        Field::new("query_language", DataType::Utf8, true),
     ]))
 }
 
@@ -368,9 +381,17 @@ mod tests {
     // ── Arrow schemas ───────────────────────────────────────────────────
 
     #[test]
-    fn search_event_schema_has_19_fields() {
+    fn search_event_schema_has_23_fields() {
         let schema = search_event_schema();
-        assert_eq!(schema.fields().len(), 19);
+        assert_eq!(schema.fields().len(), 23);
+    }
+
+    #[test]
+    fn search_event_schema_has_applied_rules_field() {
+        let schema = search_event_schema();
+        let field = schema.field_with_name("applied_rules").unwrap();
+        assert!(field.is_nullable());
+        assert_eq!(*field.data_type(), DataType::Utf8);
     }
 
     #[test]
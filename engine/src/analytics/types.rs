@@ -8,6 +8,9 @@ pub struct ClusterMetadata {
     pub nodes_total: usize,
     pub nodes_responding: usize,
     pub partial: bool,
+    /// Peers that timed out, failed or were skipped, when `partial` is set.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_nodes: Vec<String>,
     pub node_details: Vec<NodeDetail>,
 }
 
@@ -289,6 +292,7 @@ mod tests {
             nodes_total: 3,
             nodes_responding: 2,
             partial: true,
+            failed_nodes: vec!["node3".to_string()],
             node_details: vec![NodeDetail {
                 node_id: "node1".to_string(),
                 status: NodeStatus::Ok,
@@ -298,5 +302,6 @@ mod tests {
         let json = serde_json::to_string(&meta).unwrap();
         assert!(json.contains("\"nodes_total\":3"));
         assert!(json.contains("\"partial\":true"));
+        assert!(json.contains("\"failed_nodes\":[\"node3\"]"));
     }
 }
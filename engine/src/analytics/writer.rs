@@ -98,6 +98,10 @@ pub(crate) fn search_events_to_batch(
     let mut experiment_id = StringBuilder::with_capacity(len, len * 36);
     let mut variant_id = StringBuilder::with_capacity(len, len * 10);
     let mut assignment_method = StringBuilder::with_capacity(len, len * 12);
+    let mut applied_rules = StringBuilder::with_capacity(len, len * 16);
+    let mut synonyms_triggered = StringBuilder::with_capacity(len, len * 16);
+    let mut synonyms_matched = StringBuilder::with_capacity(len, len * 16);
+    let mut query_language = StringBuilder::with_capacity(len, len * 2);
 
     for e in events {
         timestamp_ms.append_value(e.timestamp_ms);
@@ -152,6 +156,21 @@ pub(crate) fn search_events_to_batch(
             Some(v) => assignment_method.append_value(v),
             None => assignment_method.append_null(),
         }
+        match &e.query_language {
+            Some(v) => query_language.append_value(v),
+            None => query_language.append_null(),
+        }
+        for (builder, ids) in [
+            (&mut applied_rules, &e.applied_rules),
+            (&mut synonyms_triggered, &e.synonyms_triggered),
+            (&mut synonyms_matched, &e.synonyms_matched),
+        ] {
+            if ids.is_empty() {
+                builder.append_null();
+            } else {
+                builder.append_value(serde_json::to_string(ids).unwrap_or_default());
+            }
+        }
     }
 
     let columns: Vec<ArrayRef> = vec![
@@ -174,6 +193,10 @@ pub(crate) fn search_events_to_batch(
         Arc::new(experiment_id.finish()),
         Arc::new(variant_id.finish()),
         Arc::new(assignment_method.finish()),
+        Arc::new(applied_rules.finish()),
+        Arc::new(synonyms_triggered.finish()),
+        Arc::new(synonyms_matched.finish()),
+        Arc::new(query_language.finish()),
     ];
 
     RecordBatch::try_new(schema.clone(), columns).map_err(|e| format!("RecordBatch error: {}", e))
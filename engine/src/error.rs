@@ -46,6 +46,9 @@ pub enum FlapjackError {
     #[error("Batch size {size} exceeds max {max} documents")]
     BatchTooLarge { size: usize, max: usize },
 
+    #[error("Request body exceeds the {max} byte limit for {route_class} requests")]
+    PayloadTooLarge { max: usize, route_class: String },
+
     #[error("Task not found: {0}")]
     TaskNotFound(String),
 
@@ -85,6 +88,19 @@ pub enum FlapjackError {
 
     #[error("Index paused for migration: {0}")]
     IndexPaused(String),
+
+    #[error("Index {index} is in {mode} maintenance mode: {detail}")]
+    IndexMaintenance {
+        index: String,
+        mode: String,
+        detail: String,
+    },
+
+    #[error("Insufficient storage: {free_bytes} bytes free, writes resume above {min_bytes}")]
+    InsufficientStorage { free_bytes: u64, min_bytes: u64 },
+
+    #[error("Namespace {namespace} limit exceeded: {detail}")]
+    NamespaceLimitExceeded { namespace: String, detail: String },
 }
 
 pub type Result<T> = std::result::Result<T, FlapjackError>;
@@ -140,6 +156,7 @@ impl FlapjackError {
             FlapjackError::BufferSizeExceeded { .. } => StatusCode::BAD_REQUEST,
             FlapjackError::DocumentTooLarge { .. } => StatusCode::BAD_REQUEST,
             FlapjackError::BatchTooLarge { .. } => StatusCode::BAD_REQUEST,
+            FlapjackError::PayloadTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
             FlapjackError::TaskNotFound(_) => StatusCode::NOT_FOUND,
             FlapjackError::QueueFull => StatusCode::TOO_MANY_REQUESTS,
             FlapjackError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
@@ -153,8 +170,319 @@ impl FlapjackError {
             FlapjackError::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
             FlapjackError::MemoryPressure { .. } => StatusCode::SERVICE_UNAVAILABLE,
             FlapjackError::IndexPaused(_) => StatusCode::SERVICE_UNAVAILABLE,
+            FlapjackError::IndexMaintenance { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            FlapjackError::InsufficientStorage { .. } => StatusCode::INSUFFICIENT_STORAGE,
+            FlapjackError::NamespaceLimitExceeded { .. } => StatusCode::FORBIDDEN,
+        }
+    }
+
+    /// Machine-readable code for this error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            FlapjackError::TenantNotFound(_) => ErrorCode::IndexNotFound,
+            FlapjackError::IndexAlreadyExists(_) => ErrorCode::IndexAlreadyExists,
+            FlapjackError::InvalidQuery(_) => ErrorCode::InvalidQuery,
+            FlapjackError::QueryTooComplex(_) => ErrorCode::QueryTooComplex,
+            FlapjackError::InvalidSchema(_) => ErrorCode::InvalidSchema,
+            FlapjackError::InvalidDocument(_) => ErrorCode::InvalidDocument,
+            FlapjackError::MissingField(_) => ErrorCode::MissingField,
+            FlapjackError::TypeMismatch { .. } => ErrorCode::TypeMismatch,
+            FlapjackError::FieldNotFound(_) => ErrorCode::FieldNotFound,
+            FlapjackError::TooManyConcurrentWrites { .. } => ErrorCode::TooManyConcurrentWrites,
+            FlapjackError::BufferSizeExceeded { .. } => ErrorCode::BufferSizeExceeded,
+            FlapjackError::DocumentTooLarge { .. } => ErrorCode::DocumentTooLarge,
+            FlapjackError::BatchTooLarge { .. } => ErrorCode::BatchTooLarge,
+            FlapjackError::PayloadTooLarge { .. } => ErrorCode::PayloadTooLarge,
+            FlapjackError::TaskNotFound(_) => ErrorCode::TaskNotFound,
+            FlapjackError::QueueFull => ErrorCode::QueueFull,
+            FlapjackError::Io(_) => ErrorCode::IoError,
+            FlapjackError::Tantivy(_) => ErrorCode::InternalError,
+            FlapjackError::QueryParse(_) => ErrorCode::QueryParseError,
+            FlapjackError::Json(_) => ErrorCode::JsonError,
+            FlapjackError::S3(_) => ErrorCode::S3Error,
+            FlapjackError::Ssl(_) => ErrorCode::SslError,
+            FlapjackError::Acme(_) => ErrorCode::AcmeError,
+            FlapjackError::Config(_) => ErrorCode::ConfigError,
+            FlapjackError::MemoryPressure { .. } => ErrorCode::MemoryPressure,
+            FlapjackError::IndexPaused(_) => ErrorCode::IndexPaused,
+            FlapjackError::IndexMaintenance { .. } => ErrorCode::IndexMaintenance,
+            FlapjackError::InsufficientStorage { .. } => ErrorCode::InsufficientStorage,
+            FlapjackError::NamespaceLimitExceeded { .. } => ErrorCode::NamespaceLimitExceeded,
+        }
+    }
+}
+
+/// Machine-readable error codes, sent as `code` in every error response and
+/// listed by `GET /1/errors`. Codes are stable: clients may branch on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    IndexNotFound,
+    IndexAlreadyExists,
+    InvalidQuery,
+    QueryTooComplex,
+    InvalidSchema,
+    InvalidDocument,
+    MissingField,
+    TypeMismatch,
+    FieldNotFound,
+    TooManyConcurrentWrites,
+    BufferSizeExceeded,
+    DocumentTooLarge,
+    BatchTooLarge,
+    PayloadTooLarge,
+    TaskNotFound,
+    QueueFull,
+    IoError,
+    InternalError,
+    QueryParseError,
+    JsonError,
+    S3Error,
+    SslError,
+    AcmeError,
+    ConfigError,
+    MemoryPressure,
+    IndexPaused,
+    IndexMaintenance,
+    InsufficientStorage,
+    NamespaceLimitExceeded,
+    InvalidCredentials,
+    AclDenied,
+    KeyNotFound,
+    BadRequest,
+    Forbidden,
+    NotFound,
+    MethodNotAllowed,
+    Conflict,
+    UnsupportedMediaType,
+    UnprocessableEntity,
+    RateLimited,
+    BadGateway,
+    ServiceUnavailable,
+}
+
+impl ErrorCode {
+    pub const ALL: &'static [ErrorCode] = &[
+        ErrorCode::IndexNotFound,
+        ErrorCode::IndexAlreadyExists,
+        ErrorCode::InvalidQuery,
+        ErrorCode::QueryTooComplex,
+        ErrorCode::InvalidSchema,
+        ErrorCode::InvalidDocument,
+        ErrorCode::MissingField,
+        ErrorCode::TypeMismatch,
+        ErrorCode::FieldNotFound,
+        ErrorCode::TooManyConcurrentWrites,
+        ErrorCode::BufferSizeExceeded,
+        ErrorCode::DocumentTooLarge,
+        ErrorCode::BatchTooLarge,
+        ErrorCode::PayloadTooLarge,
+        ErrorCode::TaskNotFound,
+        ErrorCode::QueueFull,
+        ErrorCode::IoError,
+        ErrorCode::InternalError,
+        ErrorCode::QueryParseError,
+        ErrorCode::JsonError,
+        ErrorCode::S3Error,
+        ErrorCode::SslError,
+        ErrorCode::AcmeError,
+        ErrorCode::ConfigError,
+        ErrorCode::MemoryPressure,
+        ErrorCode::IndexPaused,
+        ErrorCode::IndexMaintenance,
+        ErrorCode::InsufficientStorage,
+        ErrorCode::NamespaceLimitExceeded,
+        ErrorCode::InvalidCredentials,
+        ErrorCode::AclDenied,
+        ErrorCode::KeyNotFound,
+        ErrorCode::BadRequest,
+        ErrorCode::Forbidden,
+        ErrorCode::NotFound,
+        ErrorCode::MethodNotAllowed,
+        ErrorCode::Conflict,
+        ErrorCode::UnsupportedMediaType,
+        ErrorCode::UnprocessableEntity,
+        ErrorCode::RateLimited,
+        ErrorCode::BadGateway,
+        ErrorCode::ServiceUnavailable,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::IndexNotFound => "index_not_found",
+            ErrorCode::IndexAlreadyExists => "index_already_exists",
+            ErrorCode::InvalidQuery => "invalid_query",
+            ErrorCode::QueryTooComplex => "query_too_complex",
+            ErrorCode::InvalidSchema => "invalid_schema",
+            ErrorCode::InvalidDocument => "invalid_document",
+            ErrorCode::MissingField => "missing_field",
+            ErrorCode::TypeMismatch => "type_mismatch",
+            ErrorCode::FieldNotFound => "field_not_found",
+            ErrorCode::TooManyConcurrentWrites => "too_many_concurrent_writes",
+            ErrorCode::BufferSizeExceeded => "buffer_size_exceeded",
+            ErrorCode::DocumentTooLarge => "document_too_large",
+            ErrorCode::BatchTooLarge => "batch_too_large",
+            ErrorCode::PayloadTooLarge => "payload_too_large",
+            ErrorCode::TaskNotFound => "task_not_found",
+            ErrorCode::QueueFull => "queue_full",
+            ErrorCode::IoError => "io_error",
+            ErrorCode::InternalError => "internal_error",
+            ErrorCode::QueryParseError => "query_parse_error",
+            ErrorCode::JsonError => "json_error",
+            ErrorCode::S3Error => "s3_error",
+            ErrorCode::SslError => "ssl_error",
+            ErrorCode::AcmeError => "acme_error",
+            ErrorCode::ConfigError => "config_error",
+            ErrorCode::MemoryPressure => "memory_pressure",
+            ErrorCode::IndexPaused => "index_paused",
+            ErrorCode::IndexMaintenance => "index_maintenance",
+            ErrorCode::InsufficientStorage => "insufficient_storage",
+            ErrorCode::NamespaceLimitExceeded => "namespace_limit_exceeded",
+            ErrorCode::InvalidCredentials => "invalid_credentials",
+            ErrorCode::AclDenied => "acl_denied",
+            ErrorCode::KeyNotFound => "key_not_found",
+            ErrorCode::BadRequest => "bad_request",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::MethodNotAllowed => "method_not_allowed",
+            ErrorCode::Conflict => "conflict",
+            ErrorCode::UnsupportedMediaType => "unsupported_media_type",
+            ErrorCode::UnprocessableEntity => "unprocessable_entity",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::BadGateway => "bad_gateway",
+            ErrorCode::ServiceUnavailable => "service_unavailable",
+        }
+    }
+
+    /// The HTTP status this code is returned with.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ErrorCode::IndexNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::IndexAlreadyExists => StatusCode::CONFLICT,
+            ErrorCode::InvalidQuery => StatusCode::BAD_REQUEST,
+            ErrorCode::QueryTooComplex => StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidSchema => StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidDocument => StatusCode::BAD_REQUEST,
+            ErrorCode::MissingField => StatusCode::BAD_REQUEST,
+            ErrorCode::TypeMismatch => StatusCode::BAD_REQUEST,
+            ErrorCode::FieldNotFound => StatusCode::BAD_REQUEST,
+            ErrorCode::TooManyConcurrentWrites => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::BufferSizeExceeded => StatusCode::BAD_REQUEST,
+            ErrorCode::DocumentTooLarge => StatusCode::BAD_REQUEST,
+            ErrorCode::BatchTooLarge => StatusCode::BAD_REQUEST,
+            ErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::TaskNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::QueueFull => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::IoError => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::QueryParseError => StatusCode::BAD_REQUEST,
+            ErrorCode::JsonError => StatusCode::BAD_REQUEST,
+            ErrorCode::S3Error => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::SslError => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::AcmeError => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::ConfigError => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::MemoryPressure => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::IndexPaused => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::IndexMaintenance => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::InsufficientStorage => StatusCode::INSUFFICIENT_STORAGE,
+            ErrorCode::NamespaceLimitExceeded => StatusCode::FORBIDDEN,
+            ErrorCode::InvalidCredentials => StatusCode::FORBIDDEN,
+            ErrorCode::AclDenied => StatusCode::FORBIDDEN,
+            ErrorCode::KeyNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::BadRequest => StatusCode::BAD_REQUEST,
+            ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            ErrorCode::Conflict => StatusCode::CONFLICT,
+            ErrorCode::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorCode::UnprocessableEntity => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::BadGateway => StatusCode::BAD_GATEWAY,
+            ErrorCode::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ErrorCode::IndexNotFound => "The index does not exist.",
+            ErrorCode::IndexAlreadyExists => "An index with this name already exists.",
+            ErrorCode::InvalidQuery => "A search parameter or filter is invalid.",
+            ErrorCode::QueryTooComplex => {
+                "The query or filter expression exceeds complexity limits."
+            }
+            ErrorCode::InvalidSchema => "The index schema is invalid.",
+            ErrorCode::InvalidDocument => "A record is malformed.",
+            ErrorCode::MissingField => "A required field is missing.",
+            ErrorCode::TypeMismatch => "A field value has the wrong type.",
+            ErrorCode::FieldNotFound => "A referenced field is not in the schema.",
+            ErrorCode::TooManyConcurrentWrites => "Too many writes are in progress; retry shortly.",
+            ErrorCode::BufferSizeExceeded => "The requested writer buffer is larger than allowed.",
+            ErrorCode::DocumentTooLarge => "A record exceeds the maximum record size.",
+            ErrorCode::BatchTooLarge => "A batch contains too many operations.",
+            ErrorCode::PayloadTooLarge => "The request body exceeds the size limit for its route.",
+            ErrorCode::TaskNotFound => "The task does not exist or was evicted.",
+            ErrorCode::QueueFull => "The write queue is full; retry shortly.",
+            ErrorCode::IoError => "A disk read or write failed.",
+            ErrorCode::InternalError => "An unexpected server error occurred.",
+            ErrorCode::QueryParseError => "The query could not be parsed.",
+            ErrorCode::JsonError => "The request body is not valid JSON for this endpoint.",
+            ErrorCode::S3Error => "A snapshot storage (S3) operation failed.",
+            ErrorCode::SslError => "A TLS certificate operation failed.",
+            ErrorCode::AcmeError => "Certificate issuance through ACME failed.",
+            ErrorCode::ConfigError => "The server configuration is invalid.",
+            ErrorCode::MemoryPressure => {
+                "The server is shedding load under memory pressure; retry shortly."
+            }
+            ErrorCode::IndexPaused => "The index is paused for migration; retry shortly.",
+            ErrorCode::IndexMaintenance => {
+                "An operator put the index in a maintenance mode that rejects this request."
+            }
+            ErrorCode::InsufficientStorage => {
+                "The node is read-only because disk space is low; searches still work."
+            }
+            ErrorCode::NamespaceLimitExceeded => {
+                "The write would take the namespace past its index or document limit."
+            }
+            ErrorCode::InvalidCredentials => "The application ID or API key is missing or invalid.",
+            ErrorCode::AclDenied => "The API key lacks the ACL required by this operation.",
+            ErrorCode::KeyNotFound => "The API key does not exist.",
+            ErrorCode::BadRequest => "The request is invalid.",
+            ErrorCode::Forbidden => "The operation is not allowed.",
+            ErrorCode::NotFound => "The requested resource does not exist.",
+            ErrorCode::MethodNotAllowed => "The HTTP method is not supported on this route.",
+            ErrorCode::Conflict => "The request conflicts with the current state of the resource.",
+            ErrorCode::UnsupportedMediaType => "The request body has an unsupported content type.",
+            ErrorCode::UnprocessableEntity => "The request body does not match the expected shape.",
+            ErrorCode::RateLimited => "Too many requests; retry shortly.",
+            ErrorCode::BadGateway => "An upstream node could not be reached.",
+            ErrorCode::ServiceUnavailable => "The service is temporarily unavailable.",
         }
     }
+
+    /// Generic code for an error response whose handler did not set one.
+    pub fn for_status(status: StatusCode) -> ErrorCode {
+        match status {
+            StatusCode::BAD_REQUEST => ErrorCode::BadRequest,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+            StatusCode::NOT_FOUND => ErrorCode::NotFound,
+            StatusCode::METHOD_NOT_ALLOWED => ErrorCode::MethodNotAllowed,
+            StatusCode::CONFLICT => ErrorCode::Conflict,
+            StatusCode::PAYLOAD_TOO_LARGE => ErrorCode::PayloadTooLarge,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => ErrorCode::UnsupportedMediaType,
+            StatusCode::UNPROCESSABLE_ENTITY => ErrorCode::UnprocessableEntity,
+            StatusCode::TOO_MANY_REQUESTS => ErrorCode::RateLimited,
+            StatusCode::BAD_GATEWAY => ErrorCode::BadGateway,
+            StatusCode::SERVICE_UNAVAILABLE => ErrorCode::ServiceUnavailable,
+            s if s.is_client_error() => ErrorCode::BadRequest,
+            _ => ErrorCode::InternalError,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 #[cfg(test)]
@@ -221,6 +549,15 @@ mod tests {
         assert_eq!(e.status_code(), StatusCode::BAD_REQUEST);
     }
 
+    #[test]
+    fn payload_too_large_is_413() {
+        let e = FlapjackError::PayloadTooLarge {
+            max: 10_485_760,
+            route_class: "search".into(),
+        };
+        assert_eq!(e.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
     #[test]
     fn queue_full_is_429() {
         assert_eq!(
@@ -275,12 +612,74 @@ mod tests {
         assert_eq!(e.status_code(), StatusCode::SERVICE_UNAVAILABLE);
     }
 
+    #[test]
+    fn insufficient_storage_is_507() {
+        let e = FlapjackError::InsufficientStorage {
+            free_bytes: 1,
+            min_bytes: 2,
+        };
+        assert_eq!(e.status_code(), StatusCode::INSUFFICIENT_STORAGE);
+        assert_eq!(e.code().as_str(), "insufficient_storage");
+    }
+
     #[test]
     fn task_not_found_is_404() {
         let e = FlapjackError::TaskNotFound("abc123".into());
         assert_eq!(e.status_code(), StatusCode::NOT_FOUND);
     }
 
+    // ── ErrorCode ───────────────────────────────────────────────────────
+
+    #[test]
+    fn error_codes_are_unique_and_serialize_as_str() {
+        let mut seen = std::collections::HashSet::new();
+        for code in ErrorCode::ALL {
+            assert!(seen.insert(code.as_str()), "duplicate code {}", code);
+            assert_eq!(
+                serde_json::to_value(code).unwrap(),
+                serde_json::json!(code.as_str())
+            );
+            assert!(!code.description().is_empty());
+        }
+    }
+
+    #[test]
+    fn flapjack_error_codes_match_status() {
+        let e = FlapjackError::TenantNotFound("t".into());
+        assert_eq!(e.code(), ErrorCode::IndexNotFound);
+        assert_eq!(e.code().as_str(), "index_not_found");
+        assert_eq!(e.code().status(), e.status_code());
+        let e = FlapjackError::IndexPaused("t".into());
+        assert_eq!(e.code().status(), e.status_code());
+        let e = FlapjackError::IndexMaintenance {
+            index: "t".into(),
+            mode: "readOnly".into(),
+            detail: "writes are rejected".into(),
+        };
+        assert_eq!(e.code().status(), e.status_code());
+        let e = FlapjackError::NamespaceLimitExceeded {
+            namespace: "acme".into(),
+            detail: "maxIndexes is 2".into(),
+        };
+        assert_eq!(e.code().status(), e.status_code());
+    }
+
+    #[test]
+    fn generic_code_for_status() {
+        assert_eq!(
+            ErrorCode::for_status(StatusCode::NOT_FOUND),
+            ErrorCode::NotFound
+        );
+        assert_eq!(
+            ErrorCode::for_status(StatusCode::IM_A_TEAPOT),
+            ErrorCode::BadRequest
+        );
+        assert_eq!(
+            ErrorCode::for_status(StatusCode::GATEWAY_TIMEOUT),
+            ErrorCode::InternalError
+        );
+    }
+
     // ── Display / Error trait ───────────────────────────────────────────
 
     #[test]
@@ -393,6 +792,18 @@ mod tests {
             );
         }
 
+        #[tokio::test]
+        async fn into_response_body_has_code_and_status() {
+            let response = FlapjackError::TenantNotFound("products".into()).into_response();
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(body["code"], "index_not_found");
+            assert_eq!(body["status"], 404);
+            assert!(body["message"].as_str().unwrap().contains("products"));
+        }
+
         #[test]
         fn into_response_status_matches_status_code_for_all_variants() {
             // Exhaustive check: every variant's HTTP response status equals status_code()
@@ -417,6 +828,10 @@ mod tests {
                 },
                 FlapjackError::DocumentTooLarge { size: 100, max: 50 },
                 FlapjackError::BatchTooLarge { size: 100, max: 50 },
+                FlapjackError::PayloadTooLarge {
+                    max: 50,
+                    route_class: "search".into(),
+                },
                 FlapjackError::TaskNotFound("id".into()),
                 FlapjackError::QueueFull,
                 FlapjackError::Io("err".into()),
@@ -433,8 +848,13 @@ mod tests {
                     level: "warn".into(),
                 },
                 FlapjackError::IndexPaused("idx".into()),
+                FlapjackError::InsufficientStorage {
+                    free_bytes: 10,
+                    min_bytes: 20,
+                },
             ];
             for e in errors {
+                assert_eq!(e.code().status(), e.status_code(), "{:?}", e);
                 let expected = e.status_code();
                 let actual = status_from_response(e.clone());
                 assert_eq!(
@@ -458,6 +878,8 @@ use serde::Serialize;
 pub struct ErrorResponse {
     pub error: String,
     pub message: String,
+    pub code: ErrorCode,
+    pub status: u16,
     pub request_id: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub suggestion: Option<String>,
@@ -468,34 +890,22 @@ pub struct ErrorResponse {
 #[cfg(feature = "axum-support")]
 impl IntoResponse for FlapjackError {
     fn into_response(self) -> Response {
-        let (status, error_code, message, suggestion) = match &self {
+        let (message, suggestion) = match &self {
             FlapjackError::TenantNotFound(tenant) => (
-                StatusCode::NOT_FOUND,
-                "index_not_found",
                 format!("Index '{}' does not exist", tenant),
                 Some("Create the index first with POST /indexes".to_string()),
             ),
             FlapjackError::IndexAlreadyExists(tenant) => (
-                StatusCode::CONFLICT,
-                "index_already_exists",
                 format!("Index '{}' already exists", tenant),
                 None,
             ),
-            FlapjackError::InvalidQuery(msg) => {
-                (StatusCode::BAD_REQUEST, "invalid_query", msg.clone(), None)
-            }
+            FlapjackError::InvalidQuery(msg) => (msg.clone(), None),
             FlapjackError::QueryTooComplex(msg) => (
-                StatusCode::BAD_REQUEST,
-                "query_too_complex",
                 msg.clone(),
                 Some("Simplify your query or reduce filter complexity".to_string()),
             ),
-            FlapjackError::InvalidSchema(msg) => {
-                (StatusCode::BAD_REQUEST, "invalid_schema", msg.clone(), None)
-            }
+            FlapjackError::InvalidSchema(msg) => (msg.clone(), None),
             FlapjackError::MissingField(field) => (
-                StatusCode::BAD_REQUEST,
-                "missing_field",
                 format!("Required field '{}' is missing", field),
                 None,
             ),
@@ -504,20 +914,14 @@ impl IntoResponse for FlapjackError {
                 expected,
                 actual,
             } => (
-                StatusCode::BAD_REQUEST,
-                "type_mismatch",
                 format!("Field '{}' expected {}, got {}", field, expected, actual),
                 None,
             ),
             FlapjackError::FieldNotFound(field) => (
-                StatusCode::BAD_REQUEST,
-                "field_not_found",
                 format!("Field '{}' not found in schema", field),
                 None,
             ),
             FlapjackError::TooManyConcurrentWrites { current, max } => (
-                StatusCode::SERVICE_UNAVAILABLE,
-                "too_many_concurrent_writes",
                 format!(
                     "Too many concurrent writes: {} active, max {}",
                     current, max
@@ -525,98 +929,60 @@ impl IntoResponse for FlapjackError {
                 Some("Retry after a short delay".to_string()),
             ),
             FlapjackError::BufferSizeExceeded { requested, max } => (
-                StatusCode::BAD_REQUEST,
-                "buffer_size_exceeded",
                 format!("Buffer size {} exceeds max {} bytes", requested, max),
                 None,
             ),
             FlapjackError::DocumentTooLarge { size, max } => (
-                StatusCode::BAD_REQUEST,
-                "document_too_large",
                 format!("Document size {} exceeds max {} bytes", size, max),
                 Some("Split document into smaller chunks".to_string()),
             ),
             FlapjackError::BatchTooLarge { size, max } => (
-                StatusCode::BAD_REQUEST,
-                "batch_too_large",
                 format!("Batch size {} exceeds max {} documents", size, max),
                 Some("Split batch into smaller chunks".to_string()),
             ),
+            FlapjackError::PayloadTooLarge { max, route_class } => (
+                format!(
+                    "Request body exceeds the {} byte limit for {} requests",
+                    max, route_class
+                ),
+                Some(
+                    "Split the request, or raise the limit (see maxBodySize in GET /1/configuration)"
+                        .to_string(),
+                ),
+            ),
             FlapjackError::TaskNotFound(task_id) => (
-                StatusCode::NOT_FOUND,
-                "task_not_found",
                 format!("Task '{}' not found", task_id),
                 Some("Task may have been evicted (max 1000 tasks per tenant)".to_string()),
             ),
             FlapjackError::QueueFull => (
-                StatusCode::TOO_MANY_REQUESTS,
-                "queue_full",
                 "Write queue full (1000 operations pending)".to_string(),
                 Some("Retry after a short delay".to_string()),
             ),
-            FlapjackError::Io(e) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "io_error",
-                format!("IO error: {}", e),
-                None,
-            ),
-            FlapjackError::Tantivy(e) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "internal_error",
-                format!("Internal error: {}", e),
-                None,
-            ),
-            FlapjackError::QueryParse(e) => (
-                StatusCode::BAD_REQUEST,
-                "query_parse_error",
-                format!("Query parse error: {}", e),
-                None,
-            ),
-            FlapjackError::Json(e) => (
-                StatusCode::BAD_REQUEST,
-                "json_error",
-                format!("JSON error: {}", e),
-                None,
-            ),
+            FlapjackError::Io(e) => (format!("IO error: {}", e), None),
+            FlapjackError::Tantivy(e) => (format!("Internal error: {}", e), None),
+            FlapjackError::QueryParse(e) => (format!("Query parse error: {}", e), None),
+            FlapjackError::Json(e) => (format!("JSON error: {}", e), None),
             FlapjackError::InvalidDocument(msg) => (
-                StatusCode::BAD_REQUEST,
-                "invalid_document",
                 msg.clone(),
                 Some("Check document structure and field types".to_string()),
             ),
             FlapjackError::S3(e) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "s3_error",
                 format!("S3 error: {}", e),
                 Some(
                     "Check FLAPJACK_S3_BUCKET, FLAPJACK_S3_REGION, and AWS credentials".to_string(),
                 ),
             ),
-            FlapjackError::Ssl(e) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "ssl_error",
-                format!("SSL error: {}", e),
-                None,
-            ),
+            FlapjackError::Ssl(e) => (format!("SSL error: {}", e), None),
             FlapjackError::Acme(e) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "acme_error",
                 format!("ACME error: {}", e),
                 Some("Check FLAPJACK_SSL_EMAIL and ensure port 80 is accessible".to_string()),
             ),
-            FlapjackError::Config(e) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "config_error",
-                format!("Configuration error: {}", e),
-                None,
-            ),
+            FlapjackError::Config(e) => (format!("Configuration error: {}", e), None),
             FlapjackError::MemoryPressure {
                 allocated_mb,
                 limit_mb,
                 ref level,
             } => (
-                StatusCode::SERVICE_UNAVAILABLE,
-                "memory_pressure",
                 format!(
                     "Memory pressure: {} MB allocated of {} MB limit ({})",
                     allocated_mb, limit_mb, level
@@ -624,19 +990,31 @@ impl IntoResponse for FlapjackError {
                 Some("Retry after a short delay".to_string()),
             ),
             FlapjackError::IndexPaused(ref index) => (
-                StatusCode::SERVICE_UNAVAILABLE,
-                "index_paused",
                 format!("Index is paused for migration: {}", index),
                 Some("Retry after a short delay".to_string()),
             ),
+            FlapjackError::InsufficientStorage {
+                free_bytes,
+                min_bytes,
+            } => (
+                format!(
+                    "Node is read-only: {} bytes of disk free, writes resume above {} bytes",
+                    free_bytes, min_bytes
+                ),
+                Some("Free disk space or grow the volume; searches are still served".to_string()),
+            ),
         };
 
+        let status = self.status_code();
+        let code = self.code();
         let error_response = ErrorResponse {
-            error: error_code.to_string(),
+            error: code.as_str().to_string(),
             message,
+            code,
+            status: status.as_u16(),
             request_id: format!("req_fj_{}", uuid::Uuid::new_v4()),
             suggestion,
-            docs: Some(format!("https://flapjack.dev/docs/errors/{}", error_code)),
+            docs: Some(format!("https://flapjack.dev/docs/errors/{}", code)),
         };
 
         let mut response = (status, Json(error_response)).into_response();
@@ -645,6 +1023,11 @@ impl IntoResponse for FlapjackError {
                 .headers_mut()
                 .insert("Retry-After", "5".parse().unwrap());
         }
+        if matches!(&self, FlapjackError::InsufficientStorage { .. }) {
+            response
+                .headers_mut()
+                .insert("Retry-After", "60".parse().unwrap());
+        }
         if matches!(&self, FlapjackError::IndexPaused(_)) {
             response
                 .headers_mut()
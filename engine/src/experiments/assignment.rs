@@ -1,10 +1,14 @@
-use super::config::Experiment;
+use super::config::{AssignmentUnit, Experiment, LAYER_BUCKETS};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AssignmentMethod {
     UserToken,
     SessionId,
+    /// The experiment's `assignmentHeader` (`assignmentUnit: "header"`).
+    Header,
     QueryId,
+    /// Arm chosen by the caller (`assignmentMode: "external"`).
+    External,
 }
 
 #[derive(Debug, Clone)]
@@ -13,21 +17,28 @@ pub struct Assignment {
     pub method: AssignmentMethod,
 }
 
+/// Hash the experiment's assignment unit into an arm. `header_value` is the
+/// value of the experiment's `assignmentHeader`, if the request sent it.
 pub fn assign_variant(
     experiment: &Experiment,
     user_token: Option<&str>,
     session_id: Option<&str>,
+    header_value: Option<&str>,
     query_id: &str,
 ) -> Assignment {
-    let (key_suffix, method) = if let Some(ut) = user_token {
-        (ut, AssignmentMethod::UserToken)
-    } else if let Some(sid) = session_id {
-        (sid, AssignmentMethod::SessionId)
-    } else {
-        (query_id, AssignmentMethod::QueryId)
+    let unit = match experiment.assignment_unit.clone().unwrap_or_default() {
+        AssignmentUnit::UserToken => user_token
+            .map(|ut| (ut, AssignmentMethod::UserToken))
+            .or_else(|| session_id.map(|sid| (sid, AssignmentMethod::SessionId))),
+        AssignmentUnit::Session => session_id.map(|sid| (sid, AssignmentMethod::SessionId)),
+        AssignmentUnit::Header => header_value.map(|v| (v, AssignmentMethod::Header)),
     };
+    let (key_suffix, method) = unit.unwrap_or((query_id, AssignmentMethod::QueryId));
 
-    let key = format!("{}:{}", experiment.id, key_suffix);
+    let key = match experiment.assignment_salt.as_deref() {
+        Some(salt) => format!("{}:{}:{}", experiment.id, salt, key_suffix),
+        None => format!("{}:{}", experiment.id, key_suffix),
+    };
     let (h1, _) = murmurhash3_128(key.as_bytes(), 0);
     let bucket = (h1 % 10_000) as f64 / 10_000.0;
 
@@ -39,6 +50,61 @@ pub fn assign_variant(
     Assignment { arm, method }
 }
 
+/// Assignment for `assignmentMode: "external"`, where the caller has already
+/// picked the arm. Returns `None` unless `requested_arm` is "control" or
+/// "variant". Without a user token or session ID the search is recorded as a
+/// query-ID assignment, which keeps it out of per-user arm statistics.
+pub fn external_assignment(
+    requested_arm: &str,
+    user_token: Option<&str>,
+    session_id: Option<&str>,
+) -> Option<Assignment> {
+    let arm = match requested_arm.trim().to_ascii_lowercase().as_str() {
+        "control" => "control",
+        "variant" => "variant",
+        _ => return None,
+    };
+    let method = if user_token.or(session_id).is_some() {
+        AssignmentMethod::External
+    } else {
+        AssignmentMethod::QueryId
+    };
+    Some(Assignment { arm, method })
+}
+
+/// Bucket of `unit` (user token, session or query ID) within `namespace`.
+/// Namespaces are hashed independently of any experiment ID, so a user's
+/// layer or holdout bucket does not correlate with their arm.
+pub fn namespace_bucket(namespace: &str, unit: &str) -> u32 {
+    let key = format!("{}:{}", namespace, unit);
+    let (h1, _) = murmurhash3_128(key.as_bytes(), 0);
+    (h1 % LAYER_BUCKETS as u64) as u32
+}
+
+/// Whether `unit` falls in the index-wide holdout and should see no experiment.
+pub fn in_holdout(index_name: &str, holdout_percent: f64, unit: &str) -> bool {
+    holdout_percent > 0.0
+        && (namespace_bucket(&format!("holdout:{}", index_name), unit) as f64)
+            < holdout_percent / 100.0 * LAYER_BUCKETS as f64
+}
+
+/// Pick which of an index's running experiments `unit` takes part in.
+///
+/// An experiment without a layer owns all traffic. Experiments in a layer
+/// own disjoint bucket ranges of it, so `unit` lands in at most one of them
+/// (or none, if it hashes into an unreserved part of the layer).
+pub fn select_experiment<'a>(running: &'a [Experiment], unit: &str) -> Option<&'a Experiment> {
+    running
+        .iter()
+        .find(|e| match (e.layer.as_deref(), e.layer_buckets) {
+            (Some(layer), Some((start, end))) => {
+                let bucket = namespace_bucket(&format!("layer:{}", layer), unit);
+                start <= bucket && bucket < end
+            }
+            _ => true,
+        })
+}
+
 /// MurmurHash3_x64_128. Returns (h1, h2) — use h1 (lower 64 bits) for bucketing.
 pub(crate) fn murmurhash3_128(data: &[u8], seed: u64) -> (u64, u64) {
     const C1: u64 = 0x87c37b91114253d5;
@@ -358,11 +424,13 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
                 query_overrides: Some(QueryOverrides::default()),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: 0,
@@ -372,23 +440,100 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         }
     }
 
     #[test]
     fn assignment_is_deterministic_for_same_inputs() {
         let exp = exp_with_split(0.5);
-        let result1 = assign_variant(&exp, Some("user-abc"), None, "qid-xyz");
-        let result2 = assign_variant(&exp, Some("user-abc"), None, "qid-xyz");
+        let result1 = assign_variant(&exp, Some("user-abc"), None, None, "qid-xyz");
+        let result2 = assign_variant(&exp, Some("user-abc"), None, None, "qid-xyz");
         assert_eq!(result1.arm, result2.arm);
         assert_eq!(result1.method, result2.method);
     }
 
+    #[test]
+    fn external_assignment_uses_requested_arm() {
+        let a = external_assignment("Variant", Some("user-abc"), None).unwrap();
+        assert_eq!(a.arm, "variant");
+        assert_eq!(a.method, AssignmentMethod::External);
+
+        let a = external_assignment("control", None, Some("sess-1")).unwrap();
+        assert_eq!(a.arm, "control");
+        assert_eq!(a.method, AssignmentMethod::External);
+
+        // No stable ID: recorded like a query-ID fallback.
+        let a = external_assignment("variant", None, None).unwrap();
+        assert_eq!(a.method, AssignmentMethod::QueryId);
+
+        assert!(external_assignment("treatment", Some("user-abc"), None).is_none());
+    }
+
+    #[test]
+    fn layered_experiments_are_mutually_exclusive() {
+        let mut a = exp_with_split(0.5);
+        a.id = "a".to_string();
+        a.layer = Some("ranking".to_string());
+        a.layer_buckets = Some((0, 5_000));
+        let mut b = a.clone();
+        b.id = "b".to_string();
+        b.layer_buckets = Some((5_000, 7_500));
+        let running = vec![a, b];
+
+        let mut counts = std::collections::HashMap::new();
+        for i in 0..10_000 {
+            let unit = format!("user-{i}");
+            let picked = select_experiment(&running, &unit).map(|e| e.id.as_str());
+            *counts.entry(picked).or_insert(0) += 1;
+            // Deterministic per unit.
+            assert_eq!(
+                picked,
+                select_experiment(&running, &unit).map(|e| e.id.as_str())
+            );
+        }
+        let share = |k: Option<&str>| counts[&k] as f64 / 10_000.0;
+        assert!((share(Some("a")) - 0.5).abs() < 0.03);
+        assert!((share(Some("b")) - 0.25).abs() < 0.03);
+        assert!((share(None) - 0.25).abs() < 0.03);
+    }
+
+    #[test]
+    fn unlayered_experiment_takes_all_traffic() {
+        let running = vec![exp_with_split(0.5)];
+        assert!(select_experiment(&running, "anyone").is_some());
+        assert!(select_experiment(&[], "anyone").is_none());
+    }
+
+    #[test]
+    fn holdout_reserves_requested_share() {
+        assert!(!in_holdout("products", 0.0, "user-1"));
+        let held = (0..10_000)
+            .filter(|i| in_holdout("products", 10.0, &format!("user-{i}")))
+            .count();
+        assert!((held as f64 / 10_000.0 - 0.10).abs() < 0.02, "held {held}");
+    }
+
     #[test]
     fn different_users_can_get_different_arms() {
         let exp = exp_with_split(0.5);
         let arms: Vec<&str> = (0..100)
-            .map(|i| assign_variant(&exp, Some(&format!("user-{}", i)), None, "qid").arm)
+            .map(|i| assign_variant(&exp, Some(&format!("user-{}", i)), None, None, "qid").arm)
             .collect();
         let variant_count = arms.iter().filter(|&&a| a == "variant").count();
         assert!(variant_count > 0);
@@ -400,7 +545,9 @@ mod tests {
         let exp = exp_with_split(0.5);
         let n = 100_000u64;
         let variant_count = (0..n)
-            .filter(|i| assign_variant(&exp, Some(&format!("u{}", i)), None, "q").arm == "variant")
+            .filter(|i| {
+                assign_variant(&exp, Some(&format!("u{}", i)), None, None, "q").arm == "variant"
+            })
             .count() as u64;
         let ratio = variant_count as f64 / n as f64;
         assert!((ratio - 0.5).abs() < 0.003, "ratio was {}", ratio);
@@ -411,7 +558,9 @@ mod tests {
         let exp = exp_with_split(0.2);
         let n = 100_000u64;
         let variant_count = (0..n)
-            .filter(|i| assign_variant(&exp, Some(&format!("u{}", i)), None, "q").arm == "variant")
+            .filter(|i| {
+                assign_variant(&exp, Some(&format!("u{}", i)), None, None, "q").arm == "variant"
+            })
             .count() as u64;
         let ratio = variant_count as f64 / n as f64;
         assert!((ratio - 0.2).abs() < 0.003, "ratio was {}", ratio);
@@ -465,31 +614,81 @@ mod tests {
     #[test]
     fn user_token_takes_priority_over_query_id() {
         let exp = exp_with_split(0.5);
-        let result = assign_variant(&exp, Some("user-stable"), None, "qid-unstable");
+        let result = assign_variant(&exp, Some("user-stable"), None, None, "qid-unstable");
         assert_eq!(result.method, AssignmentMethod::UserToken);
     }
 
     #[test]
     fn user_token_takes_priority_over_session_id() {
         let exp = exp_with_split(0.5);
-        let result = assign_variant(&exp, Some("user-stable"), Some("session-123"), "qid-xyz");
+        let result = assign_variant(
+            &exp,
+            Some("user-stable"),
+            Some("session-123"),
+            None,
+            "qid-xyz",
+        );
         assert_eq!(result.method, AssignmentMethod::UserToken);
         // Verify the arm is determined by user_token, not session_id
-        let result_no_session = assign_variant(&exp, Some("user-stable"), None, "qid-xyz");
+        let result_no_session = assign_variant(&exp, Some("user-stable"), None, None, "qid-xyz");
         assert_eq!(result.arm, result_no_session.arm);
     }
 
     #[test]
     fn session_id_takes_priority_over_query_id() {
         let exp = exp_with_split(0.5);
-        let result = assign_variant(&exp, None, Some("session-123"), "qid-xyz");
+        let result = assign_variant(&exp, None, Some("session-123"), None, "qid-xyz");
         assert_eq!(result.method, AssignmentMethod::SessionId);
     }
 
+    #[test]
+    fn session_unit_ignores_user_token() {
+        let mut exp = exp_with_split(0.5);
+        exp.assignment_unit = Some(AssignmentUnit::Session);
+        let result = assign_variant(&exp, Some("user-1"), Some("sess-1"), None, "qid");
+        assert_eq!(result.method, AssignmentMethod::SessionId);
+        let other_user = assign_variant(&exp, Some("user-2"), Some("sess-1"), None, "qid");
+        assert_eq!(result.arm, other_user.arm);
+
+        let no_session = assign_variant(&exp, Some("user-1"), None, None, "qid");
+        assert_eq!(no_session.method, AssignmentMethod::QueryId);
+    }
+
+    #[test]
+    fn header_unit_assigns_by_header_value() {
+        let mut exp = exp_with_split(0.5);
+        exp.assignment_unit = Some(AssignmentUnit::Header);
+        exp.assignment_header = Some("X-Device-ID".to_string());
+        let result = assign_variant(&exp, Some("user-1"), None, Some("device-9"), "qid");
+        assert_eq!(result.method, AssignmentMethod::Header);
+        let same_device = assign_variant(&exp, None, Some("s"), Some("device-9"), "qid-2");
+        assert_eq!(result.arm, same_device.arm);
+
+        let missing = assign_variant(&exp, Some("user-1"), None, None, "qid");
+        assert_eq!(missing.method, AssignmentMethod::QueryId);
+    }
+
+    #[test]
+    fn salt_reshuffles_assignments_deterministically() {
+        let unsalted = exp_with_split(0.5);
+        let mut salted = exp_with_split(0.5);
+        salted.assignment_salt = Some("2024-q3".to_string());
+        let arms = |exp: &Experiment| -> Vec<&'static str> {
+            (0..1000)
+                .map(|i| assign_variant(exp, Some(&format!("user-{i}")), None, None, "q").arm)
+                .collect()
+        };
+        assert_ne!(arms(&unsalted), arms(&salted));
+        assert_eq!(arms(&salted), arms(&salted));
+        let variant_share =
+            arms(&salted).iter().filter(|&&a| a == "variant").count() as f64 / 1000.0;
+        assert!((variant_share - 0.5).abs() < 0.05, "share {variant_share}");
+    }
+
     #[test]
     fn query_id_fallback_produces_query_method() {
         let exp = exp_with_split(0.5);
-        let result = assign_variant(&exp, None, None, "qid-xyz");
+        let result = assign_variant(&exp, None, None, None, "qid-xyz");
         assert_eq!(result.method, AssignmentMethod::QueryId);
     }
 
@@ -501,8 +700,8 @@ mod tests {
         exp2.id = "experiment-bbb".to_string();
         let differs = (0..1000).any(|i| {
             let u = format!("user-{}", i);
-            assign_variant(&exp1, Some(&u), None, "q").arm
-                != assign_variant(&exp2, Some(&u), None, "q").arm
+            assign_variant(&exp1, Some(&u), None, None, "q").arm
+                != assign_variant(&exp2, Some(&u), None, None, "q").arm
         });
         assert!(differs, "assignments should vary between experiments");
     }
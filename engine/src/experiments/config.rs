@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Number of hash buckets a layer (and the holdout) is divided into.
+pub const LAYER_BUCKETS: u32 = 10_000;
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Experiment {
@@ -20,6 +23,114 @@ pub struct Experiment {
     pub conclusion: Option<ExperimentConclusion>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub interleaving: Option<bool>,
+    /// A/A test: the variant is identical to control, so every significant
+    /// difference is a false positive of the assignment or metrics plumbing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aa_test: Option<bool>,
+    /// Who decides the arm for each search. Defaults to the engine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignment_mode: Option<AssignmentMode>,
+    /// What engine assignment splits on. Defaults to the userToken.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignment_unit: Option<AssignmentUnit>,
+    /// Request header holding the unit when `assignmentUnit` is `header`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignment_header: Option<String>,
+    /// Mixed into the assignment hash; a new salt reshuffles every unit
+    /// between the arms.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignment_salt: Option<String>,
+    /// Experiments on the same index that share a layer run concurrently but
+    /// are mutually exclusive: each user lands in at most one of them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer: Option<String>,
+    /// Share of the layer's users, in (0, 1], this experiment receives.
+    /// Defaults to the whole layer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer_traffic: Option<f64>,
+    /// `[start, end)` of the layer buckets (out of [`LAYER_BUCKETS`]) reserved
+    /// for this experiment when it is started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer_buckets: Option<(u32, u32)>,
+    /// Bayesian decision rule: an arm is recommended once its expected loss
+    /// (in units of the primary metric) drops below this value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bayesian_loss_threshold: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_stop: Option<AutoStopPolicy>,
+    /// When the auto-stop checker first saw guard-rail alerts in the current
+    /// unbroken run of alerting checks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guard_rail_alert_since: Option<i64>,
+    /// Why the experiment was stopped automatically, if it was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+    /// Unix ms at which a draft experiment is started automatically.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduled_start_at: Option<i64>,
+    /// Unix ms at which a running experiment is stopped automatically.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduled_end_at: Option<i64>,
+    /// Planned traffic splits of a staged rollout, e.g. `[0.01, 0.05, 0.5]`.
+    /// The experiment starts at the first and each ramp moves to the next.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ramp_stages: Option<Vec<f64>>,
+    /// Every traffic split the experiment has run at, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub traffic_history: Vec<TrafficStage>,
+}
+
+/// A traffic split a running experiment served from `startedAt` until the
+/// next stage began (or the experiment ended).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrafficStage {
+    pub traffic_split: f64,
+    pub started_at: i64,
+}
+
+/// Conditions under which a running experiment is stopped without waiting
+/// for someone to read `/results`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoStopPolicy {
+    /// Stop when any guard-rail alert has persisted for this many hours.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guard_rail_hours: Option<f64>,
+    /// Stop as soon as a sample ratio mismatch is detected.
+    #[serde(default)]
+    pub stop_on_srm: bool,
+    /// Notified with a JSON POST when the experiment is auto-stopped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+}
+
+/// How searches are split between the control and variant arms.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AssignmentMode {
+    /// The engine hashes the user token (or session / query ID) into an arm.
+    #[default]
+    Engine,
+    /// The caller picks the arm and sends it in the `X-Flapjack-AB-Variant`
+    /// header; the engine only applies that arm and logs the exposure.
+    External,
+}
+
+/// The identifier engine assignment hashes into an arm. Searches without it
+/// fall back to their query ID, which keeps them out of arm statistics.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum AssignmentUnit {
+    /// The userToken, else the session ID.
+    #[default]
+    UserToken,
+    /// The session ID from the `X-Flapjack-Session-ID` header, for logged-out
+    /// traffic without a stable userToken.
+    Session,
+    /// The value of the `assignmentHeader` request header, e.g. a device ID
+    /// or a geo bucket set by a CDN.
+    Header,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -37,6 +148,10 @@ pub struct ExperimentArm {
     pub name: String,
     pub query_overrides: Option<QueryOverrides>,
     pub index_name: Option<String>,
+    /// Mode B without a hand-built index: settings merged onto a clone of the
+    /// base index. `indexName` is filled in once the clone is provisioned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings_delta: Option<serde_json::Value>,
 }
 
 /// Query-time parameters overridable per variant arm (Mode A).
@@ -53,6 +168,8 @@ pub struct QueryOverrides {
     pub custom_ranking: Option<Vec<String>>,
     pub attribute_weights: Option<HashMap<String, f32>>,
     pub remove_words_if_no_results: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable_re_ranking: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -93,6 +210,14 @@ pub enum ExperimentError {
     Json(#[from] serde_json::Error),
 }
 
+/// Whether `name` is a valid HTTP header field name (an RFC 7230 token).
+fn is_header_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
 impl Experiment {
     pub fn validate(&self) -> Result<(), ExperimentError> {
         if self.traffic_split <= 0.0 || self.traffic_split >= 1.0 {
@@ -100,20 +225,158 @@ impl Experiment {
                 "trafficSplit must be in (0.0, 1.0) exclusive".to_string(),
             ));
         }
+        if let Some(stages) = &self.ramp_stages {
+            if stages.is_empty() {
+                return Err(ExperimentError::InvalidConfig(
+                    "rampStages must not be empty".to_string(),
+                ));
+            }
+            if stages.iter().any(|s| s.is_nan() || *s <= 0.0 || *s >= 1.0) {
+                return Err(ExperimentError::InvalidConfig(
+                    "rampStages must be in (0.0, 1.0) exclusive".to_string(),
+                ));
+            }
+            if stages.windows(2).any(|w| w[1] <= w[0]) {
+                return Err(ExperimentError::InvalidConfig(
+                    "rampStages must be increasing".to_string(),
+                ));
+            }
+        }
         let has_query_overrides = self.variant.query_overrides.is_some();
-        let has_index_name = self.variant.index_name.is_some();
-        if has_query_overrides == has_index_name {
+        let has_index_name =
+            self.variant.index_name.is_some() || self.variant.settings_delta.is_some();
+        if self.is_aa_test() {
+            if has_query_overrides || has_index_name {
+                return Err(ExperimentError::InvalidConfig(
+                    "an A/A test's variant must not have queryOverrides, indexName or settingsDelta"
+                        .to_string(),
+                ));
+            }
+        } else if has_query_overrides == has_index_name {
             return Err(ExperimentError::InvalidConfig(
-                "variant must define exactly one mode: queryOverrides (Mode A) or indexName (Mode B)"
+                "variant must define exactly one mode: queryOverrides (Mode A) or indexName/settingsDelta (Mode B)"
                     .to_string(),
             ));
         }
-        if self.control.query_overrides.is_some() || self.control.index_name.is_some() {
+        if self
+            .variant
+            .query_overrides
+            .as_ref()
+            .and_then(|o| o.attribute_weights.as_ref())
+            .is_some_and(|w| w.values().any(|w| !w.is_finite() || *w <= 0.0))
+        {
+            return Err(ExperimentError::InvalidConfig(
+                "queryOverrides.attributeWeights must be positive numbers".to_string(),
+            ));
+        }
+        if self
+            .variant
+            .settings_delta
+            .as_ref()
+            .is_some_and(|d| !d.is_object())
+        {
+            return Err(ExperimentError::InvalidConfig(
+                "settingsDelta must be a JSON object".to_string(),
+            ));
+        }
+        if self.control.query_overrides.is_some()
+            || self.control.index_name.is_some()
+            || self.control.settings_delta.is_some()
+        {
             return Err(ExperimentError::InvalidConfig(
-                "control arm must not have queryOverrides or indexName — it is the baseline"
+                "control arm must not have queryOverrides, indexName or settingsDelta — it is the baseline"
                     .to_string(),
             ));
         }
+        if let (Some(start), Some(end)) = (self.scheduled_start_at, self.scheduled_end_at) {
+            if end <= start {
+                return Err(ExperimentError::InvalidConfig(
+                    "scheduledEndAt must be after scheduledStartAt".to_string(),
+                ));
+            }
+        }
+        if let Some(policy) = &self.auto_stop {
+            if policy
+                .guard_rail_hours
+                .is_some_and(|h| h.is_nan() || h < 0.0)
+            {
+                return Err(ExperimentError::InvalidConfig(
+                    "autoStop.guardRailHours must be >= 0".to_string(),
+                ));
+            }
+            if policy
+                .webhook_url
+                .as_deref()
+                .is_some_and(|u| !u.starts_with("http://") && !u.starts_with("https://"))
+            {
+                return Err(ExperimentError::InvalidConfig(
+                    "autoStop.webhookUrl must be an http(s) URL".to_string(),
+                ));
+            }
+        }
+        if self.layer.as_deref().is_some_and(|l| l.trim().is_empty()) {
+            return Err(ExperimentError::InvalidConfig(
+                "layer must not be empty".to_string(),
+            ));
+        }
+        if let Some(share) = self.layer_traffic {
+            if self.layer.is_none() {
+                return Err(ExperimentError::InvalidConfig(
+                    "layerTraffic requires a layer".to_string(),
+                ));
+            }
+            if share.is_nan() || share <= 0.0 || share > 1.0 {
+                return Err(ExperimentError::InvalidConfig(
+                    "layerTraffic must be in (0.0, 1.0]".to_string(),
+                ));
+            }
+        }
+        if self
+            .bayesian_loss_threshold
+            .is_some_and(|t| t.is_nan() || t <= 0.0)
+        {
+            return Err(ExperimentError::InvalidConfig(
+                "bayesianLossThreshold must be > 0".to_string(),
+            ));
+        }
+        let by_header = self.assignment_unit == Some(AssignmentUnit::Header);
+        match self.assignment_header.as_deref() {
+            Some(name) if !by_header => {
+                return Err(ExperimentError::InvalidConfig(format!(
+                    "assignmentHeader '{name}' requires assignmentUnit 'header'"
+                )));
+            }
+            Some(name) if !is_header_name(name) => {
+                return Err(ExperimentError::InvalidConfig(format!(
+                    "assignmentHeader '{name}' is not a valid header name"
+                )));
+            }
+            None if by_header => {
+                return Err(ExperimentError::InvalidConfig(
+                    "assignmentUnit 'header' requires assignmentHeader".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        if self.assignment_unit.is_some() && self.is_externally_assigned() {
+            return Err(ExperimentError::InvalidConfig(
+                "assignmentUnit cannot be combined with assignmentMode 'external'".to_string(),
+            ));
+        }
+        if self
+            .assignment_salt
+            .as_deref()
+            .is_some_and(|s| s.trim().is_empty())
+        {
+            return Err(ExperimentError::InvalidConfig(
+                "assignmentSalt must not be empty".to_string(),
+            ));
+        }
+        if self.interleaving == Some(true) && self.is_externally_assigned() {
+            return Err(ExperimentError::InvalidConfig(
+                "interleaving cannot be combined with assignmentMode 'external'".to_string(),
+            ));
+        }
         if self.interleaving == Some(true) && !has_index_name {
             return Err(ExperimentError::InvalidConfig(
                 "interleaving requires Mode B (variant indexName) — two separate ranked lists needed"
@@ -122,6 +385,45 @@ impl Experiment {
         }
         Ok(())
     }
+
+    pub fn is_aa_test(&self) -> bool {
+        self.aa_test == Some(true)
+    }
+
+    pub fn is_externally_assigned(&self) -> bool {
+        self.assignment_mode == Some(AssignmentMode::External)
+    }
+
+    /// The first planned ramp stage above the current split.
+    pub fn next_ramp_stage(&self) -> Option<f64> {
+        self.ramp_stages
+            .as_ref()?
+            .iter()
+            .copied()
+            .find(|&s| s > self.traffic_split)
+    }
+
+    /// `[start, end)` in Unix ms of each traffic stage; the last is open
+    /// until the experiment ended, if it has.
+    pub fn stage_windows(&self) -> Vec<(i64, Option<i64>)> {
+        self.traffic_history
+            .iter()
+            .enumerate()
+            .map(|(i, stage)| {
+                let end = self
+                    .traffic_history
+                    .get(i + 1)
+                    .map(|next| next.started_at)
+                    .or(self.ended_at);
+                (stage.started_at, end)
+            })
+            .collect()
+    }
+
+    /// Number of layer buckets this experiment needs when it starts.
+    pub fn layer_bucket_count(&self) -> u32 {
+        (self.layer_traffic.unwrap_or(1.0) * LAYER_BUCKETS as f64).round() as u32
+    }
 }
 
 #[cfg(test)]
@@ -139,6 +441,7 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
@@ -147,6 +450,7 @@ mod tests {
                     ..Default::default()
                 }),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: 1700000000000,
@@ -156,6 +460,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         }
     }
 
@@ -193,6 +513,99 @@ mod tests {
         assert!(e.validate().is_err());
     }
 
+    #[test]
+    fn validate_attribute_weight_overrides_are_positive() {
+        let mut e = valid_experiment();
+        let weights = |w: f32| {
+            Some(QueryOverrides {
+                attribute_weights: Some(HashMap::from([("title".to_string(), w)])),
+                ..Default::default()
+            })
+        };
+        e.variant.query_overrides = weights(5.0);
+        assert!(e.validate().is_ok());
+        e.variant.query_overrides = weights(0.0);
+        assert!(e.validate().is_err());
+        e.variant.query_overrides = weights(f32::NAN);
+        assert!(e.validate().is_err());
+    }
+
+    #[test]
+    fn validate_aa_test_variant_applies_nothing() {
+        let mut e = valid_experiment();
+        e.aa_test = Some(true);
+        assert!(e.validate().is_err(), "A/A variant with queryOverrides");
+        e.variant.query_overrides = None;
+        assert!(e.validate().is_ok());
+        e.variant.index_name = Some("products_v2".to_string());
+        assert!(e.validate().is_err(), "A/A variant with indexName");
+        e.variant.index_name = None;
+        e.interleaving = Some(true);
+        assert!(e.validate().is_err(), "A/A test with interleaving");
+    }
+
+    #[test]
+    fn validate_assignment_unit() {
+        let mut e = valid_experiment();
+        e.assignment_unit = Some(AssignmentUnit::Session);
+        assert!(e.validate().is_ok());
+
+        e.assignment_unit = Some(AssignmentUnit::Header);
+        assert!(e.validate().is_err(), "header unit without a header");
+        e.assignment_header = Some("X-Device-ID".to_string());
+        assert!(e.validate().is_ok());
+        e.assignment_header = Some("X Device".to_string());
+        assert!(e.validate().is_err());
+
+        e.assignment_header = Some("CF-IPCountry".to_string());
+        e.assignment_unit = Some(AssignmentUnit::UserToken);
+        assert!(e.validate().is_err(), "header without the header unit");
+
+        e.assignment_header = None;
+        e.assignment_mode = Some(AssignmentMode::External);
+        assert!(e.validate().is_err());
+
+        e.assignment_unit = None;
+        e.assignment_mode = None;
+        e.assignment_salt = Some(" ".to_string());
+        assert!(e.validate().is_err());
+    }
+
+    #[test]
+    fn validate_ramp_stages() {
+        let mut e = valid_experiment();
+        e.ramp_stages = Some(vec![0.01, 0.05, 0.5]);
+        assert!(e.validate().is_ok());
+        for bad in [vec![], vec![0.05, 0.01], vec![0.01, 1.0], vec![0.1, 0.1]] {
+            e.ramp_stages = Some(bad.clone());
+            assert!(e.validate().is_err(), "rampStages {bad:?}");
+        }
+    }
+
+    #[test]
+    fn ramp_stages_and_windows_follow_history() {
+        let mut e = valid_experiment();
+        e.ramp_stages = Some(vec![0.01, 0.05, 0.5]);
+        e.traffic_split = 0.01;
+        assert_eq!(e.next_ramp_stage(), Some(0.05));
+        e.traffic_split = 0.5;
+        assert_eq!(e.next_ramp_stage(), None);
+
+        e.traffic_history = vec![
+            TrafficStage {
+                traffic_split: 0.01,
+                started_at: 100,
+            },
+            TrafficStage {
+                traffic_split: 0.5,
+                started_at: 200,
+            },
+        ];
+        assert_eq!(e.stage_windows(), vec![(100, Some(200)), (200, None)]);
+        e.ended_at = Some(300);
+        assert_eq!(e.stage_windows(), vec![(100, Some(200)), (200, Some(300))]);
+    }
+
     #[test]
     fn validate_mode_b_variant_index_passes() {
         let mut e = valid_experiment();
@@ -201,6 +614,33 @@ mod tests {
         assert!(e.validate().is_ok());
     }
 
+    #[test]
+    fn validate_mode_b_settings_delta_passes() {
+        let mut e = valid_experiment();
+        e.variant.query_overrides = None;
+        e.variant.settings_delta = Some(serde_json::json!({"customRanking": ["desc(sales)"]}));
+        assert!(e.validate().is_ok());
+
+        // Once provisioned, indexName and settingsDelta coexist.
+        e.variant.index_name = Some("products__exp_550e8400".to_string());
+        assert!(e.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_settings_delta_must_be_object() {
+        let mut e = valid_experiment();
+        e.variant.query_overrides = None;
+        e.variant.settings_delta = Some(serde_json::json!(["customRanking"]));
+        assert!(e.validate().is_err());
+    }
+
+    #[test]
+    fn validate_control_with_settings_delta_fails() {
+        let mut e = valid_experiment();
+        e.control.settings_delta = Some(serde_json::json!({}));
+        assert!(e.validate().is_err());
+    }
+
     #[test]
     fn validate_variant_with_both_mode_a_and_mode_b_fails() {
         let mut e = valid_experiment();
@@ -282,6 +722,60 @@ mod tests {
         assert!(e.validate().is_err());
     }
 
+    #[test]
+    fn validate_scheduled_end_after_start() {
+        let mut e = valid_experiment();
+        e.scheduled_start_at = Some(1_700_000_000_000);
+        e.scheduled_end_at = Some(1_700_000_000_000);
+        assert!(e.validate().is_err());
+        e.scheduled_end_at = Some(1_700_086_400_000);
+        assert!(e.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_bayesian_loss_threshold() {
+        let mut e = valid_experiment();
+        e.bayesian_loss_threshold = Some(0.001);
+        assert!(e.validate().is_ok());
+        e.bayesian_loss_threshold = Some(0.0);
+        assert!(e.validate().is_err());
+    }
+
+    #[test]
+    fn validate_layer_traffic() {
+        let mut e = valid_experiment();
+        e.layer_traffic = Some(0.5);
+        assert!(e.validate().is_err(), "layerTraffic without a layer");
+        e.layer = Some("ranking".to_string());
+        assert!(e.validate().is_ok());
+        assert_eq!(e.layer_bucket_count(), 5_000);
+        for bad in [0.0, 1.5, f64::NAN] {
+            e.layer_traffic = Some(bad);
+            assert!(e.validate().is_err(), "layerTraffic {bad}");
+        }
+        e.layer_traffic = None;
+        e.layer = Some("  ".to_string());
+        assert!(e.validate().is_err());
+    }
+
+    #[test]
+    fn validate_auto_stop_policy() {
+        let mut e = valid_experiment();
+        e.auto_stop = Some(AutoStopPolicy {
+            guard_rail_hours: Some(24.0),
+            stop_on_srm: true,
+            webhook_url: Some("https://hooks.example.com/exp".to_string()),
+        });
+        assert!(e.validate().is_ok());
+
+        e.auto_stop.as_mut().unwrap().guard_rail_hours = Some(-1.0);
+        assert!(e.validate().is_err());
+
+        e.auto_stop.as_mut().unwrap().guard_rail_hours = None;
+        e.auto_stop.as_mut().unwrap().webhook_url = Some("ftp://nope".to_string());
+        assert!(e.validate().is_err());
+    }
+
     #[test]
     fn validate_interleaving_false_allows_mode_a() {
         // Non-interleaving experiments still work with Mode A
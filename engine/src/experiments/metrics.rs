@@ -4,9 +4,9 @@
 //! joins on `query_id`, aggregates per-user, and returns arm-level metrics suitable
 //! for the delta method z-test and Welch's t-test.
 //!
-//! **Key rule:** Only searches with `assignment_method IN ('user_token', 'session_id')`
-//! are included in arm statistics. Queries assigned by `query_id` fallback are counted
-//! separately in `no_stable_id_queries`.
+//! **Key rule:** Only searches with `assignment_method IN ('user_token', 'session_id',
+//! 'header', 'external')` are included in arm statistics. Queries assigned by `query_id`
+//! fallback are counted separately in `no_stable_id_queries`.
 
 use std::collections::HashMap;
 use std::path::Path;
@@ -65,7 +65,7 @@ pub struct ArmMetrics {
 }
 
 impl ArmMetrics {
-    fn empty(arm_name: &str) -> Self {
+    pub fn empty(arm_name: &str) -> Self {
         Self {
             arm_name: arm_name.to_string(),
             searches: 0,
@@ -101,11 +101,21 @@ pub struct ExperimentMetrics {
     pub winsorization_cap_applied: Option<f64>,
 }
 
+/// Arm metrics for the searches made on one UTC day.
+#[derive(Debug)]
+pub struct DailyMetrics {
+    /// `YYYY-MM-DD` (UTC).
+    pub date: String,
+    pub control: ArmMetrics,
+    pub variant: ArmMetrics,
+}
+
 // ── Raw event row types (from parquet queries) ──────────────────────
 
 /// A single search event row relevant to experiment metrics.
 #[derive(Debug, Clone)]
 struct SearchRow {
+    timestamp_ms: i64,
     user_token: String,
     variant_id: String,
     query_id: Option<String>,
@@ -233,7 +243,10 @@ fn aggregate_experiment_metrics(
     let mut no_stable_id_queries: u64 = 0;
 
     for s in searches {
-        if s.assignment_method == "user_token" || s.assignment_method == "session_id" {
+        if matches!(
+            s.assignment_method.as_str(),
+            "user_token" | "session_id" | "header" | "external"
+        ) {
             stable_searches.push(s);
         } else {
             no_stable_id_queries += 1;
@@ -473,19 +486,55 @@ struct PreSearchRow {
     has_results: bool,
 }
 
-/// Compute per-user metric values from pre-experiment search/event data.
+/// Per-user pre-experiment covariates for CUPED variance reduction.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PreExperimentCovariates {
+    /// Covariate names, aligned with every vector in `values`.
+    pub names: Vec<String>,
+    /// user_token → covariate values.
+    pub values: HashMap<String, Vec<f64>>,
+}
+
+impl PreExperimentCovariates {
+    /// A covariate set with a single named column.
+    pub fn single(name: &str, values: HashMap<String, f64>) -> Self {
+        Self {
+            names: vec![name.to_string()],
+            values: values.into_iter().map(|(k, v)| (k, vec![v])).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Keep only the columns at `columns`, in that order.
+    pub fn select(&self, columns: &[usize]) -> Self {
+        Self {
+            names: columns.iter().map(|&c| self.names[c].clone()).collect(),
+            values: self
+                .values
+                .iter()
+                .map(|(user, xs)| (user.clone(), columns.iter().map(|&c| xs[c]).collect()))
+                .collect(),
+        }
+    }
+}
+
+/// Compute per-user covariates from pre-experiment search/event data.
 ///
-/// Returns a map of user_token → metric value for use as CUPED covariates.
-/// Uses the same metric calculation as the experiment aggregation.
+/// The first covariate is the experiment's primary metric (same calculation
+/// as the experiment aggregation), followed by the user's pre-period
+/// `searches`, `clicks` and `revenue`.
 fn compute_pre_experiment_covariates(
     searches: &[PreSearchRow],
     events: &[EventRow],
     metric: &super::config::PrimaryMetric,
-) -> HashMap<String, f64> {
+) -> PreExperimentCovariates {
     use super::config::PrimaryMetric;
 
     if searches.is_empty() {
-        return HashMap::new();
+        return PreExperimentCovariates::default();
     }
 
     // Build query_id -> event lookup
@@ -528,8 +577,8 @@ fn compute_pre_experiment_covariates(
         }
     }
 
-    // Convert to metric values
-    per_user
+    // Convert to covariate vectors
+    let values = per_user
         .into_iter()
         .filter(|(_, agg)| agg.searches > 0)
         .map(|(user, agg)| {
@@ -547,7 +596,80 @@ fn compute_pre_experiment_covariates(
                     safe_div(agg.abandoned_searches as f64, with_results as f64)
                 }
             };
-            (user.to_string(), value)
+            (
+                user.to_string(),
+                vec![value, agg.searches as f64, agg.clicks as f64, agg.revenue],
+            )
+        })
+        .collect();
+
+    let metric_name = serde_json::to_value(metric)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_else(|| "primaryMetric".to_string());
+    PreExperimentCovariates {
+        names: vec![
+            metric_name,
+            "searches".to_string(),
+            "clicks".to_string(),
+            "revenue".to_string(),
+        ],
+        values,
+    }
+}
+
+/// Split search rows by UTC day and aggregate each day independently.
+///
+/// Clicks and conversions are joined on `query_id`, so they count toward the
+/// day of the search they belong to. Days are returned in ascending order.
+fn aggregate_daily_metrics(
+    searches: &[SearchRow],
+    events: &[EventRow],
+    winsorization_cap: Option<f64>,
+) -> Vec<DailyMetrics> {
+    const DAY_MS: i64 = 86_400_000;
+    let mut by_day: std::collections::BTreeMap<i64, Vec<SearchRow>> =
+        std::collections::BTreeMap::new();
+    for s in searches {
+        by_day
+            .entry(s.timestamp_ms.div_euclid(DAY_MS))
+            .or_default()
+            .push(s.clone());
+    }
+
+    by_day
+        .into_iter()
+        .map(|(day, rows)| {
+            let m = aggregate_experiment_metrics(&rows, events, winsorization_cap);
+            let date = chrono::DateTime::from_timestamp_millis(day * DAY_MS)
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            DailyMetrics {
+                date,
+                control: m.control,
+                variant: m.variant,
+            }
+        })
+        .collect()
+}
+
+/// Aggregate the searches in each `[start, end)` window (Unix ms; `None`
+/// leaves the end open) independently, one result per window.
+fn aggregate_window_metrics(
+    searches: &[SearchRow],
+    events: &[EventRow],
+    winsorization_cap: Option<f64>,
+    windows: &[(i64, Option<i64>)],
+) -> Vec<ExperimentMetrics> {
+    windows
+        .iter()
+        .map(|&(start, end)| {
+            let rows: Vec<SearchRow> = searches
+                .iter()
+                .filter(|s| s.timestamp_ms >= start && end.is_none_or(|e| s.timestamp_ms < e))
+                .cloned()
+                .collect();
+            aggregate_experiment_metrics(&rows, events, winsorization_cap)
         })
         .collect()
 }
@@ -600,6 +722,14 @@ mod arrow_helpers {
             .value(idx)
     }
 
+    /// Extract an i64 value from an Int64 column.
+    pub fn get_i64(col: &Arc<dyn Array>, idx: usize) -> i64 {
+        col.as_any()
+            .downcast_ref::<arrow::array::Int64Array>()
+            .unwrap()
+            .value(idx)
+    }
+
     /// Extract a bool value from a Boolean column.
     pub fn get_bool(col: &Arc<dyn Array>, idx: usize) -> bool {
         col.as_any()
@@ -652,6 +782,61 @@ pub async fn get_experiment_metrics(
     analytics_data_dir: &Path,
     winsorization_cap: Option<f64>,
 ) -> Result<ExperimentMetrics, String> {
+    let (all_searches, all_events) =
+        read_experiment_rows(experiment_id, index_names, analytics_data_dir).await?;
+
+    Ok(aggregate_experiment_metrics(
+        &all_searches,
+        &all_events,
+        winsorization_cap,
+    ))
+}
+
+/// Read per-day experiment metrics from analytics parquet files.
+#[cfg(feature = "analytics")]
+pub async fn get_experiment_daily_metrics(
+    experiment_id: &str,
+    index_names: &[&str],
+    analytics_data_dir: &Path,
+    winsorization_cap: Option<f64>,
+) -> Result<Vec<DailyMetrics>, String> {
+    let (all_searches, all_events) =
+        read_experiment_rows(experiment_id, index_names, analytics_data_dir).await?;
+
+    Ok(aggregate_daily_metrics(
+        &all_searches,
+        &all_events,
+        winsorization_cap,
+    ))
+}
+
+/// Read experiment metrics for each traffic stage window from analytics
+/// parquet files.
+#[cfg(feature = "analytics")]
+pub async fn get_experiment_window_metrics(
+    experiment_id: &str,
+    index_names: &[&str],
+    analytics_data_dir: &Path,
+    winsorization_cap: Option<f64>,
+    windows: &[(i64, Option<i64>)],
+) -> Result<Vec<ExperimentMetrics>, String> {
+    let (all_searches, all_events) =
+        read_experiment_rows(experiment_id, index_names, analytics_data_dir).await?;
+
+    Ok(aggregate_window_metrics(
+        &all_searches,
+        &all_events,
+        winsorization_cap,
+        windows,
+    ))
+}
+
+#[cfg(feature = "analytics")]
+async fn read_experiment_rows(
+    experiment_id: &str,
+    index_names: &[&str],
+    analytics_data_dir: &Path,
+) -> Result<(Vec<SearchRow>, Vec<EventRow>), String> {
     use datafusion::prelude::*;
 
     let ctx = SessionContext::new();
@@ -677,11 +862,7 @@ pub async fn get_experiment_metrics(
         }
     }
 
-    Ok(aggregate_experiment_metrics(
-        &all_searches,
-        &all_events,
-        winsorization_cap,
-    ))
+    Ok((all_searches, all_events))
 }
 
 /// Read interleaving preference metrics from analytics parquet files.
@@ -770,8 +951,8 @@ async fn read_search_rows(
     // Escape single quotes in experiment_id for safety
     let safe_id = experiment_id.replace('\'', "''");
     let sql = format!(
-        "SELECT user_token, variant_id, query_id, nb_hits, has_results, assignment_method \
-         FROM {} WHERE experiment_id = '{}'",
+        "SELECT timestamp_ms, user_token, variant_id, query_id, nb_hits, has_results, \
+         assignment_method FROM {} WHERE experiment_id = '{}'",
         table_name, safe_id
     );
 
@@ -786,6 +967,7 @@ async fn read_search_rows(
 
     let mut rows = Vec::new();
     for batch in &batches {
+        let timestamp_col = batch.column_by_name("timestamp_ms").unwrap().clone();
         let user_token_col = batch.column_by_name("user_token").unwrap().clone();
         let variant_id_col = batch.column_by_name("variant_id").unwrap().clone();
         let query_id_col = batch.column_by_name("query_id").unwrap().clone();
@@ -807,6 +989,7 @@ async fn read_search_rows(
                 None => continue,
             };
             rows.push(SearchRow {
+                timestamp_ms: arrow_helpers::get_i64(&timestamp_col, i),
                 user_token,
                 variant_id,
                 query_id: arrow_helpers::get_string(&query_id_col, i),
@@ -913,7 +1096,7 @@ async fn read_event_rows(
 /// Read pre-experiment covariate data for CUPED variance reduction.
 ///
 /// Queries analytics parquet files for the time window `[started_at - lookback_days, started_at)`
-/// and returns per-user metric values for the specified primary metric.
+/// and returns per-user covariates (see [`PreExperimentCovariates`]).
 ///
 /// Only the control index is queried (pre-experiment traffic on the same index).
 #[cfg(feature = "analytics")]
@@ -923,7 +1106,7 @@ pub async fn get_pre_experiment_covariates(
     metric: &super::config::PrimaryMetric,
     started_at_ms: i64,
     lookback_days: u32,
-) -> Result<HashMap<String, f64>, String> {
+) -> Result<PreExperimentCovariates, String> {
     use datafusion::prelude::*;
 
     let lookback_ms = (lookback_days as i64) * 24 * 60 * 60 * 1000;
@@ -1041,6 +1224,7 @@ mod tests {
         method: &str,
     ) -> SearchRow {
         SearchRow {
+            timestamp_ms: 0,
             user_token: user.to_string(),
             variant_id: variant.to_string(),
             query_id: qid.map(|s| s.to_string()),
@@ -1435,6 +1619,93 @@ mod tests {
         assert_eq!(m.control.clicks, 1);
     }
 
+    #[test]
+    fn external_assignment_included_in_arm_stats() {
+        let searches = vec![
+            search("u1", "control", Some("q1"), 5, "external"),
+            search("u2", "variant", Some("q2"), 5, "external"),
+        ];
+        let events = vec![click("q2")];
+
+        let m = aggregate_experiment_metrics(&searches, &events, None);
+
+        assert_eq!(m.no_stable_id_queries, 0);
+        assert_eq!(m.control.searches, 1);
+        assert_eq!(m.variant.clicks, 1);
+    }
+
+    // ── Daily time series ───────────────────────────────────────────
+
+    #[test]
+    fn daily_metrics_split_searches_by_utc_day() {
+        const DAY_MS: i64 = 86_400_000;
+        // 2024-01-01T00:00:00Z
+        let day0 = 1_704_067_200_000;
+        let at = |ms: i64, mut row: SearchRow| {
+            row.timestamp_ms = ms;
+            row
+        };
+        let searches = vec![
+            at(
+                day0 + DAY_MS + 5,
+                search("u1", "control", Some("q3"), 5, "user_token"),
+            ),
+            at(
+                day0 + 10,
+                search("u1", "control", Some("q1"), 5, "user_token"),
+            ),
+            at(
+                day0 + DAY_MS - 1,
+                search("u2", "variant", Some("q2"), 5, "user_token"),
+            ),
+            at(
+                day0 + DAY_MS + 9,
+                search("u3", "variant", None, 5, "query_id"),
+            ),
+        ];
+        let events = vec![click("q2"), click("q3")];
+
+        let days = aggregate_daily_metrics(&searches, &events, None);
+
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].date, "2024-01-01");
+        assert_eq!(days[0].control.searches, 1);
+        assert_eq!(days[0].control.clicks, 0);
+        assert_eq!(days[0].variant.searches, 1);
+        assert_eq!(days[0].variant.clicks, 1);
+        assert_eq!(days[1].date, "2024-01-02");
+        assert_eq!(days[1].control.clicks, 1);
+        // query_id-assigned searches stay out of arm stats, per day too.
+        assert_eq!(days[1].variant.searches, 0);
+    }
+
+    #[test]
+    fn window_metrics_split_searches_by_stage() {
+        let at = |ms: i64, mut row: SearchRow| {
+            row.timestamp_ms = ms;
+            row
+        };
+        let searches = vec![
+            at(100, search("u1", "control", Some("q1"), 5, "user_token")),
+            at(199, search("u2", "variant", Some("q2"), 5, "user_token")),
+            at(200, search("u1", "control", Some("q3"), 5, "user_token")),
+            at(950, search("u3", "variant", Some("q4"), 5, "user_token")),
+            at(50, search("u4", "variant", Some("q5"), 5, "user_token")),
+        ];
+        let events = vec![click("q2"), click("q4")];
+
+        let stages =
+            aggregate_window_metrics(&searches, &events, None, &[(100, Some(200)), (200, None)]);
+
+        assert_eq!(stages.len(), 2);
+        assert_eq!(stages[0].control.searches, 1);
+        assert_eq!(stages[0].variant.searches, 1);
+        assert_eq!(stages[0].variant.clicks, 1);
+        assert_eq!(stages[1].control.searches, 1);
+        assert_eq!(stages[1].variant.searches, 1);
+        assert_eq!(stages[1].variant.clicks, 1);
+    }
+
     // ── Winsorization ───────────────────────────────────────────────
 
     #[test]
@@ -1665,17 +1936,45 @@ mod tests {
 
         let covariates = compute_pre_experiment_covariates(&searches, &events, &PrimaryMetric::Ctr);
 
-        assert_eq!(covariates.len(), 2);
+        assert_eq!(covariates.values.len(), 2);
+        assert_eq!(covariates.names[0], "ctr");
         assert!(
-            (covariates["u1"] - 0.5).abs() < 0.001,
+            (covariates.values["u1"][0] - 0.5).abs() < 0.001,
             "u1 CTR should be 0.5, got {}",
-            covariates["u1"]
+            covariates.values["u1"][0]
         );
         assert!(
-            (covariates["u2"] - 0.0).abs() < 0.001,
+            (covariates.values["u2"][0] - 0.0).abs() < 0.001,
             "u2 CTR should be 0.0, got {}",
-            covariates["u2"]
+            covariates.values["u2"][0]
+        );
+    }
+
+    #[test]
+    fn pre_experiment_covariates_include_searches_clicks_and_revenue() {
+        use crate::experiments::config::PrimaryMetric;
+
+        let searches = vec![
+            pre_search("u1", Some("q1"), 5),
+            pre_search("u1", Some("q2"), 5),
+            pre_search("u1", Some("q3"), 0),
+        ];
+        let events = vec![click("q1"), conversion("q2", 12.5)];
+
+        let covariates =
+            compute_pre_experiment_covariates(&searches, &events, &PrimaryMetric::ConversionRate);
+
+        assert_eq!(
+            covariates.names,
+            vec!["conversionRate", "searches", "clicks", "revenue"]
         );
+        let u1 = &covariates.values["u1"];
+        assert!((u1[0] - 1.0 / 3.0).abs() < 1e-9, "{u1:?}");
+        assert_eq!(&u1[1..], &[3.0, 1.0, 12.5]);
+
+        let revenue_only = covariates.select(&[3]);
+        assert_eq!(revenue_only.names, vec!["revenue"]);
+        assert_eq!(revenue_only.values["u1"], vec![12.5]);
     }
 
     #[test]
@@ -1729,6 +2028,10 @@ mod tests {
                 experiment_id: Some(experiment_id.to_string()),
                 variant_id: Some(variant_id.to_string()),
                 assignment_method: Some(assignment_method.to_string()),
+                applied_rules: Vec::new(),
+                synonyms_triggered: Vec::new(),
+                synonyms_matched: Vec::new(),
+                query_language: None,
             }
         }
 
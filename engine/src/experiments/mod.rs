@@ -2,5 +2,7 @@ pub mod assignment;
 pub mod config;
 pub mod interleaving;
 pub mod metrics;
+pub mod provisioning;
+pub mod shadow;
 pub mod stats;
 pub mod store;
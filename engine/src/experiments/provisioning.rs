@@ -0,0 +1,478 @@
+//! Automatic Mode B variant indexes.
+//!
+//! An experiment whose variant arm carries a `settingsDelta` (instead of an
+//! existing `indexName`) gets a private variant index: the base index is
+//! cloned, the delta is merged into its settings, and document writes to the
+//! base index are replayed from its oplog while the experiment runs. The
+//! variant index is deleted when the experiment is concluded or deleted.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::config::{Experiment, ExperimentStatus};
+use super::store::{ExperimentFilter, ExperimentStore};
+use crate::error::{FlapjackError, Result};
+use crate::index::manager::IndexManager;
+use crate::index::settings::IndexSettings;
+use crate::types::Document;
+
+/// Per-variant sync cursor, stored inside the variant index directory.
+pub const SYNC_STATE_FILE: &str = "experiment_sync.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncState {
+    /// Last base-index oplog sequence replayed into the variant.
+    source_seq: u64,
+}
+
+fn sync_state_path(manager: &IndexManager, variant: &str) -> PathBuf {
+    manager.base_path.join(variant).join(SYNC_STATE_FILE)
+}
+
+fn load_sync_state(manager: &IndexManager, variant: &str) -> Result<SyncState> {
+    let path = sync_state_path(manager, variant);
+    if !path.exists() {
+        return Ok(SyncState::default());
+    }
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+fn save_sync_state(manager: &IndexManager, variant: &str, state: &SyncState) -> Result<()> {
+    std::fs::write(
+        sync_state_path(manager, variant),
+        serde_json::to_string(state)?,
+    )?;
+    Ok(())
+}
+
+/// Name of the auto-provisioned variant index for an experiment.
+///
+/// The whole experiment ID is kept (minus separators) so that two experiments
+/// on the same base index never share a variant index.
+pub fn variant_index_name(base_index: &str, experiment_id: &str) -> String {
+    let id: String = experiment_id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    format!("{}__exp_{}", base_index, id)
+}
+
+/// Overlay the top-level keys of `delta` onto `base`.
+pub fn merge_settings_delta(
+    base: &IndexSettings,
+    delta: &serde_json::Value,
+) -> Result<IndexSettings> {
+    let delta = delta.as_object().ok_or_else(|| {
+        FlapjackError::InvalidQuery("settingsDelta must be a JSON object".to_string())
+    })?;
+    let mut merged = serde_json::to_value(base)?;
+    if let Some(obj) = merged.as_object_mut() {
+        for (key, value) in delta {
+            obj.insert(key.clone(), value.clone());
+        }
+    }
+    serde_json::from_value(merged)
+        .map_err(|e| FlapjackError::InvalidQuery(format!("invalid settingsDelta: {}", e)))
+}
+
+/// Rewrite the variant's settings as the base index settings plus `delta`.
+pub fn apply_settings_delta(
+    manager: &IndexManager,
+    base_index: &str,
+    variant_index: &str,
+    delta: &serde_json::Value,
+) -> Result<()> {
+    let base = IndexSettings::load(manager.base_path.join(base_index).join("settings.json"))
+        .unwrap_or_default();
+    let merged = merge_settings_delta(&base, delta)?;
+    merged.save(manager.base_path.join(variant_index).join("settings.json"))?;
+    manager.invalidate_settings_cache(variant_index);
+    manager.invalidate_facet_cache(variant_index);
+    Ok(())
+}
+
+/// Clone `base_index` into `variant_index` and apply `delta` to its settings.
+///
+/// The base oplog position is recorded before the copy so that writes racing
+/// with the clone are replayed by the next [`sync_variant_index`] (upserts are
+/// idempotent, so replaying an already-copied write is harmless).
+pub async fn provision_variant_index(
+    manager: &IndexManager,
+    base_index: &str,
+    variant_index: &str,
+    delta: &serde_json::Value,
+) -> Result<()> {
+    if !manager.base_path.join(base_index).exists() {
+        return Err(FlapjackError::TenantNotFound(base_index.to_string()));
+    }
+    if manager.base_path.join(variant_index).exists() {
+        return Err(FlapjackError::IndexAlreadyExists(variant_index.to_string()));
+    }
+    // Validate the delta before copying any data.
+    merge_settings_delta(&IndexSettings::default(), delta)?;
+
+    let source_seq = manager
+        .get_or_create_oplog(base_index)
+        .map(|ol| ol.current_seq())
+        .unwrap_or(0);
+    manager.copy_index(base_index, variant_index, None).await?;
+    manager.create_tenant(variant_index)?;
+    apply_settings_delta(manager, base_index, variant_index, delta)?;
+    save_sync_state(manager, variant_index, &SyncState { source_seq })?;
+    tracing::info!(
+        "[EXPERIMENTS] provisioned variant index {} from {} at seq {}",
+        variant_index,
+        base_index,
+        source_seq
+    );
+    Ok(())
+}
+
+/// Re-copy the documents of `base_index` into `variant_index`, keeping the
+/// variant's own settings.
+async fn reclone_variant_index(
+    manager: &IndexManager,
+    base_index: &str,
+    variant_index: &str,
+) -> Result<()> {
+    let settings_path = manager.base_path.join(variant_index).join("settings.json");
+    let settings = IndexSettings::load(&settings_path).ok();
+    let source_seq = manager
+        .get_or_create_oplog(base_index)
+        .map(|ol| ol.current_seq())
+        .unwrap_or(0);
+    manager.copy_index(base_index, variant_index, None).await?;
+    manager.create_tenant(variant_index)?;
+    if let Some(settings) = settings {
+        settings.save(&settings_path)?;
+        manager.invalidate_settings_cache(variant_index);
+        manager.invalidate_facet_cache(variant_index);
+    }
+    save_sync_state(manager, variant_index, &SyncState { source_seq })?;
+    tracing::info!(
+        "[EXPERIMENTS] re-cloned variant index {} from {} at seq {}",
+        variant_index,
+        base_index,
+        source_seq
+    );
+    Ok(())
+}
+
+/// Replay document upserts and deletes made to `base_index` since the last
+/// sync into `variant_index`. Settings, synonym and rule changes are not
+/// replayed: the variant keeps its own configuration. Returns the number of
+/// operations applied.
+///
+/// If the base oplog was truncated past the sync cursor, the missing writes
+/// cannot be replayed and the variant is re-cloned from the base instead
+/// (reported as zero operations).
+pub async fn sync_variant_index(
+    manager: &IndexManager,
+    base_index: &str,
+    variant_index: &str,
+) -> Result<usize> {
+    let Some(oplog) = manager.get_or_create_oplog(base_index) else {
+        return Ok(0);
+    };
+    let mut state = load_sync_state(manager, variant_index)?;
+    let entries = oplog.read_since(state.source_seq)?;
+    let Some(first_seq) = entries.first().map(|e| e.seq) else {
+        return Ok(0);
+    };
+    if first_seq > state.source_seq + 1 {
+        tracing::warn!(
+            "[EXPERIMENTS] oplog of {} has a gap after seq {} (next is {}); re-cloning {}",
+            base_index,
+            state.source_seq,
+            first_seq,
+            variant_index
+        );
+        reclone_variant_index(manager, base_index, variant_index).await?;
+        return Ok(0);
+    }
+    let last_seq = entries.last().map_or(first_seq, |e| e.seq);
+
+    let mut applied = 0;
+    let mut upserts: Vec<Document> = Vec::new();
+    let mut deletes: Vec<String> = Vec::new();
+    for entry in &entries {
+        match entry.op_type.as_str() {
+            "upsert" => {
+                if !deletes.is_empty() {
+                    manager
+                        .delete_documents_sync(variant_index, std::mem::take(&mut deletes))
+                        .await?;
+                }
+                if let Some(doc) = entry
+                    .payload
+                    .get("body")
+                    .and_then(|body| Document::from_json(body).ok())
+                {
+                    upserts.push(doc);
+                    applied += 1;
+                }
+            }
+            "delete" => {
+                if !upserts.is_empty() {
+                    manager
+                        .add_documents_sync(variant_index, std::mem::take(&mut upserts))
+                        .await?;
+                }
+                if let Some(id) = entry.payload.get("objectID").and_then(|v| v.as_str()) {
+                    deletes.push(id.to_string());
+                    applied += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    if !upserts.is_empty() {
+        manager.add_documents_sync(variant_index, upserts).await?;
+    }
+    if !deletes.is_empty() {
+        manager
+            .delete_documents_sync(variant_index, deletes)
+            .await?;
+    }
+
+    state.source_seq = last_seq;
+    save_sync_state(manager, variant_index, &state)?;
+    Ok(applied)
+}
+
+/// Delete an auto-provisioned variant index. Missing indexes are ignored.
+pub async fn teardown_variant_index(manager: &IndexManager, variant_index: &str) -> Result<()> {
+    if !manager.base_path.join(variant_index).exists() {
+        return Ok(());
+    }
+    manager.delete_tenant(&variant_index.to_string()).await?;
+    tracing::info!("[EXPERIMENTS] removed variant index {}", variant_index);
+    Ok(())
+}
+
+/// The auto-provisioned variant index of `experiment`, if it has one.
+pub fn provisioned_variant(experiment: &Experiment) -> Option<&str> {
+    experiment
+        .variant
+        .settings_delta
+        .as_ref()
+        .and(experiment.variant.index_name.as_deref())
+}
+
+/// Periodically replay base-index writes into the variant index of every
+/// running experiment with an auto-provisioned variant.
+pub fn spawn_variant_sync(
+    manager: Arc<IndexManager>,
+    store: Arc<ExperimentStore>,
+    interval_secs: u64,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(tokio::time::Duration::from_secs(interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            let running = store.list(Some(ExperimentFilter {
+                index_name: None,
+                status: Some(ExperimentStatus::Running),
+            }));
+            for experiment in running {
+                let Some(variant) = provisioned_variant(&experiment) else {
+                    continue;
+                };
+                if let Err(e) = sync_variant_index(&manager, &experiment.index_name, variant).await
+                {
+                    tracing::warn!(
+                        "[EXPERIMENTS] sync of variant index {} failed: {}",
+                        variant,
+                        e
+                    );
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn doc(id: &str, name: &str) -> Document {
+        Document::from_json(&json!({"_id": id, "name": name})).unwrap()
+    }
+
+    #[test]
+    fn variant_index_name_is_stable() {
+        assert_eq!(
+            variant_index_name("products", "550e8400-e29b-41d4-a716-446655440000"),
+            "products__exp_550e8400e29b41d4a716446655440000"
+        );
+        // IDs sharing a prefix still get distinct variant indexes.
+        assert_ne!(
+            variant_index_name("products", "550e8400-e29b-41d4-a716-446655440000"),
+            variant_index_name("products", "550e8400-0000-4000-8000-000000000000")
+        );
+    }
+
+    #[test]
+    fn merge_settings_delta_overrides_top_level_keys() {
+        let base = IndexSettings {
+            attributes_for_faceting: vec!["brand".to_string()],
+            ..Default::default()
+        };
+        let merged =
+            merge_settings_delta(&base, &json!({"customRanking": ["desc(sales)"]})).unwrap();
+        assert_eq!(merged.attributes_for_faceting, vec!["brand".to_string()]);
+        assert_eq!(merged.custom_ranking, Some(vec!["desc(sales)".to_string()]));
+
+        assert!(merge_settings_delta(&base, &json!(["not", "an", "object"])).is_err());
+    }
+
+    #[tokio::test]
+    async fn provision_sync_and_teardown() {
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        manager.create_tenant("products").unwrap();
+        manager
+            .add_documents_sync("products", vec![doc("1", "shoe"), doc("2", "boot")])
+            .await
+            .unwrap();
+
+        let delta = json!({"customRanking": ["desc(sales)"]});
+        provision_variant_index(&manager, "products", "products__exp_1", &delta)
+            .await
+            .unwrap();
+        assert_eq!(
+            manager
+                .get_settings("products__exp_1")
+                .unwrap()
+                .custom_ranking,
+            Some(vec!["desc(sales)".to_string()])
+        );
+        assert!(manager
+            .get_settings("products")
+            .unwrap()
+            .custom_ranking
+            .is_none());
+
+        manager
+            .add_documents_sync("products", vec![doc("3", "sandal")])
+            .await
+            .unwrap();
+        manager
+            .delete_documents_sync("products", vec!["1".to_string()])
+            .await
+            .unwrap();
+        let applied = sync_variant_index(&manager, "products", "products__exp_1")
+            .await
+            .unwrap();
+        assert_eq!(applied, 2);
+        assert!(manager
+            .get_document("products__exp_1", "3")
+            .unwrap()
+            .is_some());
+        assert!(manager
+            .get_document("products__exp_1", "1")
+            .unwrap()
+            .is_none());
+        // Nothing new to replay.
+        assert_eq!(
+            sync_variant_index(&manager, "products", "products__exp_1")
+                .await
+                .unwrap(),
+            0
+        );
+
+        teardown_variant_index(&manager, "products__exp_1")
+            .await
+            .unwrap();
+        assert!(!tmp.path().join("products__exp_1").exists());
+    }
+
+    #[tokio::test]
+    async fn sync_reclones_after_an_oplog_gap() {
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        manager.create_tenant("products").unwrap();
+        manager
+            .add_documents_sync("products", vec![doc("1", "shoe")])
+            .await
+            .unwrap();
+        let delta = json!({"customRanking": ["desc(sales)"]});
+        provision_variant_index(&manager, "products", "products__exp_1", &delta)
+            .await
+            .unwrap();
+
+        manager
+            .add_documents_sync("products", vec![doc("2", "boot")])
+            .await
+            .unwrap();
+        manager
+            .add_documents_sync("products", vec![doc("3", "sandal")])
+            .await
+            .unwrap();
+
+        // Drop the first unreplayed entry, as if the oplog had been truncated
+        // before the variant caught up.
+        let oplog = manager.get_or_create_oplog("products").unwrap();
+        let cursor = load_sync_state(&manager, "products__exp_1")
+            .unwrap()
+            .source_seq;
+        let missing = oplog.read_since(cursor).unwrap()[0].seq;
+        for entry in std::fs::read_dir(tmp.path().join("products").join("oplog")).unwrap() {
+            let path = entry.unwrap().path();
+            let kept: String = std::fs::read_to_string(&path)
+                .unwrap()
+                .lines()
+                .filter(|line| {
+                    serde_json::from_str::<serde_json::Value>(line)
+                        .ok()
+                        .and_then(|v| v["seq"].as_u64())
+                        != Some(missing)
+                })
+                .map(|line| format!("{}\n", line))
+                .collect();
+            std::fs::write(&path, kept).unwrap();
+        }
+
+        let applied = sync_variant_index(&manager, "products", "products__exp_1")
+            .await
+            .unwrap();
+        assert_eq!(applied, 0);
+        for id in ["1", "2", "3"] {
+            assert!(manager
+                .get_document("products__exp_1", id)
+                .unwrap()
+                .is_some());
+        }
+        assert_eq!(
+            manager
+                .get_settings("products__exp_1")
+                .unwrap()
+                .custom_ranking,
+            Some(vec!["desc(sales)".to_string()])
+        );
+        assert_eq!(
+            load_sync_state(&manager, "products__exp_1")
+                .unwrap()
+                .source_seq,
+            oplog.current_seq()
+        );
+    }
+
+    #[tokio::test]
+    async fn provision_refuses_existing_variant_index() {
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        manager.create_tenant("products").unwrap();
+        manager.create_tenant("taken").unwrap();
+        let err = provision_variant_index(&manager, "products", "taken", &json!({}))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FlapjackError::IndexAlreadyExists(_)));
+    }
+}
@@ -0,0 +1,207 @@
+//! Shadow evaluation of a candidate configuration.
+//!
+//! A share of an index's live queries is silently re-run against a candidate
+//! — another index, query overrides, or both — after the real response has
+//! been sent. Only the comparison is kept: how much the first page of results
+//! overlaps, whether the top hit is the same, zero-result counts and latency.
+//! Shadow queries are never recorded in analytics or experiments, so a risky
+//! relevance change can be checked before it gets an A/B test.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::config::{ExperimentError, QueryOverrides};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShadowConfig {
+    /// Percentage of the index's queries (0–100] re-run against the candidate.
+    pub percentage: f64,
+    /// Index the shadow queries run against; defaults to the index itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub candidate_index: Option<String>,
+    /// Query-time settings applied to the shadow queries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_overrides: Option<QueryOverrides>,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+impl ShadowConfig {
+    pub fn validate(&self, index_name: &str) -> Result<(), ExperimentError> {
+        if self.percentage.is_nan() || self.percentage <= 0.0 || self.percentage > 100.0 {
+            return Err(ExperimentError::InvalidConfig(
+                "shadow percentage must be in (0, 100]".to_string(),
+            ));
+        }
+        let other_index = self
+            .candidate_index
+            .as_deref()
+            .is_some_and(|c| c != index_name);
+        if !other_index && self.query_overrides.is_none() {
+            return Err(ExperimentError::InvalidConfig(
+                "shadow needs a candidateIndex other than the index itself or queryOverrides"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// One live query and its shadow run.
+#[derive(Debug, Clone, Default)]
+pub struct ShadowSample {
+    /// objectIDs of the first page, in order.
+    pub primary_ids: Vec<String>,
+    pub candidate_ids: Vec<String>,
+    pub primary_hits: u64,
+    pub candidate_hits: u64,
+    pub primary_ms: f64,
+    pub candidate_ms: f64,
+}
+
+/// Share of objectIDs the two pages have in common (intersection over
+/// union); two empty pages overlap fully.
+pub fn overlap(a: &[String], b: &[String]) -> f64 {
+    let a: HashSet<&String> = a.iter().collect();
+    let b: HashSet<&String> = b.iter().collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Running totals for one index's shadow evaluation.
+#[derive(Debug, Clone, Default)]
+pub struct ShadowStats {
+    queries: u64,
+    errors: u64,
+    overlap_sum: f64,
+    top_hit_matches: u64,
+    primary_zero_results: u64,
+    candidate_zero_results: u64,
+    primary_ms_sum: f64,
+    candidate_ms_sum: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShadowReport {
+    pub queries: u64,
+    /// Shadow queries that failed (e.g. the candidate index is missing).
+    pub errors: u64,
+    pub mean_overlap: f64,
+    /// Share of queries whose first hit is the same on both sides.
+    pub top_hit_agreement: f64,
+    pub primary_zero_results: u64,
+    pub candidate_zero_results: u64,
+    pub mean_primary_ms: f64,
+    pub mean_candidate_ms: f64,
+}
+
+impl ShadowStats {
+    pub fn record(&mut self, sample: &ShadowSample) {
+        self.queries += 1;
+        self.overlap_sum += overlap(&sample.primary_ids, &sample.candidate_ids);
+        if sample.primary_ids.first() == sample.candidate_ids.first() {
+            self.top_hit_matches += 1;
+        }
+        if sample.primary_hits == 0 {
+            self.primary_zero_results += 1;
+        }
+        if sample.candidate_hits == 0 {
+            self.candidate_zero_results += 1;
+        }
+        self.primary_ms_sum += sample.primary_ms;
+        self.candidate_ms_sum += sample.candidate_ms;
+    }
+
+    pub fn record_error(&mut self) {
+        self.errors += 1;
+    }
+
+    pub fn report(&self) -> ShadowReport {
+        let mean = |sum: f64| {
+            if self.queries == 0 {
+                0.0
+            } else {
+                sum / self.queries as f64
+            }
+        };
+        ShadowReport {
+            queries: self.queries,
+            errors: self.errors,
+            mean_overlap: mean(self.overlap_sum),
+            top_hit_agreement: mean(self.top_hit_matches as f64),
+            primary_zero_results: self.primary_zero_results,
+            candidate_zero_results: self.candidate_zero_results,
+            mean_primary_ms: mean(self.primary_ms_sum),
+            mean_candidate_ms: mean(self.candidate_ms_sum),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn overlap_is_intersection_over_union() {
+        assert_eq!(overlap(&ids(&["a", "b"]), &ids(&["b", "a"])), 1.0);
+        assert_eq!(overlap(&ids(&["a", "b"]), &ids(&["b", "c"])), 1.0 / 3.0);
+        assert_eq!(overlap(&ids(&["a"]), &ids(&[])), 0.0);
+        assert_eq!(overlap(&[], &[]), 1.0);
+    }
+
+    #[test]
+    fn report_averages_samples() {
+        let mut stats = ShadowStats::default();
+        stats.record(&ShadowSample {
+            primary_ids: ids(&["a", "b"]),
+            candidate_ids: ids(&["a", "b"]),
+            primary_hits: 2,
+            candidate_hits: 2,
+            primary_ms: 2.0,
+            candidate_ms: 4.0,
+        });
+        stats.record(&ShadowSample {
+            primary_ids: ids(&["a"]),
+            candidate_ids: ids(&[]),
+            primary_hits: 1,
+            candidate_hits: 0,
+            primary_ms: 4.0,
+            candidate_ms: 8.0,
+        });
+        stats.record_error();
+        let report = stats.report();
+        assert_eq!(report.queries, 2);
+        assert_eq!(report.errors, 1);
+        assert_eq!(report.mean_overlap, 0.5);
+        assert_eq!(report.top_hit_agreement, 0.5);
+        assert_eq!(report.candidate_zero_results, 1);
+        assert_eq!(report.mean_primary_ms, 3.0);
+        assert_eq!(report.mean_candidate_ms, 6.0);
+    }
+
+    #[test]
+    fn candidate_must_differ_from_the_index() {
+        let config = ShadowConfig {
+            percentage: 10.0,
+            candidate_index: Some("products".to_string()),
+            query_overrides: None,
+            created_at: 0,
+        };
+        assert!(config.validate("products").is_err());
+        assert!(config.validate("products_v1").is_ok());
+        let config = ShadowConfig {
+            percentage: 0.0,
+            ..config
+        };
+        assert!(config.validate("products_v1").is_err());
+    }
+}
@@ -282,6 +282,29 @@ pub fn check_sample_ratio_mismatch(
     chi2 > 6.635
 }
 
+// ── Binomial Tail ───────────────────────────────────────────────────
+
+/// P(X >= successes) for X ~ Binomial(trials, p). Used to judge whether an
+/// A/A test produced more false positives than its significance level allows.
+pub fn binomial_upper_tail(successes: u64, trials: u64, p: f64) -> f64 {
+    if successes == 0 {
+        return 1.0;
+    }
+    if successes > trials || p <= 0.0 {
+        return 0.0;
+    }
+    if p >= 1.0 {
+        return 1.0;
+    }
+    let mut pmf = (1.0 - p).powf(trials as f64);
+    let mut below = 0.0;
+    for i in 0..successes {
+        below += pmf;
+        pmf *= (trials - i) as f64 / (i + 1) as f64 * p / (1.0 - p);
+    }
+    (1.0 - below).clamp(0.0, 1.0)
+}
+
 // ── Winsorization ───────────────────────────────────────────────────
 
 /// Caps values above the threshold. Accepts a pre-computed cap (not percentile).
@@ -375,6 +398,64 @@ pub fn beta_binomial_prob_b_greater_a(
     total
 }
 
+/// Expected loss of shipping the arm with `chosen_successes / chosen_trials`
+/// when the other arm might be better: E[max(θ_other − θ_chosen, 0)] under the
+/// same Beta(1,1)-prior posteriors as [`beta_binomial_prob_b_greater_a`].
+///
+/// Uses E[θ·1{θ > φ}] = E[θ]·P(θ⁺ > φ), where θ⁺ has one more success than θ,
+/// so both terms reduce to the closed-form P(B > A).
+pub fn beta_binomial_expected_loss(
+    chosen_successes: u64,
+    chosen_trials: u64,
+    other_successes: u64,
+    other_trials: u64,
+) -> f64 {
+    if chosen_successes > chosen_trials || other_successes > other_trials {
+        return 0.0;
+    }
+    let mean_chosen = (chosen_successes as f64 + 1.0) / (chosen_trials as f64 + 2.0);
+    let mean_other = (other_successes as f64 + 1.0) / (other_trials as f64 + 2.0);
+    let p_other_plus_wins = beta_binomial_prob_b_greater_a(
+        chosen_successes,
+        chosen_trials,
+        other_successes + 1,
+        other_trials + 1,
+    );
+    let p_other_wins_vs_chosen_plus = beta_binomial_prob_b_greater_a(
+        chosen_successes + 1,
+        chosen_trials + 1,
+        other_successes,
+        other_trials,
+    );
+    (mean_other * p_other_plus_wins - mean_chosen * p_other_wins_vs_chosen_plus).max(0.0)
+}
+
+/// Equal-tailed credible interval of the Beta(successes+1, failures+1)
+/// posterior, e.g. `level = 0.95` for a 95% interval.
+pub fn beta_credible_interval(successes: u64, trials: u64, level: f64) -> (f64, f64) {
+    let alpha = successes.min(trials) as f64 + 1.0;
+    let beta = trials.saturating_sub(successes) as f64 + 1.0;
+    let tail = (1.0 - level.clamp(0.0, 1.0)) / 2.0;
+    (
+        beta_quantile(tail, alpha, beta),
+        beta_quantile(1.0 - tail, alpha, beta),
+    )
+}
+
+/// Inverse of the regularized incomplete beta function, by bisection.
+fn beta_quantile(p: f64, a: f64, b: f64) -> f64 {
+    let (mut lo, mut hi) = (0.0_f64, 1.0_f64);
+    for _ in 0..100 {
+        let mid = 0.5 * (lo + hi);
+        if regularized_incomplete_beta(a, b, mid) < p {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    0.5 * (lo + hi)
+}
+
 /// Log of the Beta function: ln(B(a,b)) = ln(Gamma(a)) + ln(Gamma(b)) - ln(Gamma(a+b))
 fn ln_beta(a: f64, b: f64) -> f64 {
     ln_gamma(a) + ln_gamma(b) - ln_gamma(a + b)
@@ -587,7 +668,9 @@ fn regularized_incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
 }
 
 fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
-    const MAX_ITERS: usize = 200;
+    // Convergence takes O(sqrt(max(a, b))) iterations; posteriors over large
+    // arms (credible intervals) need far more than t-test degrees of freedom.
+    const MAX_ITERS: usize = 10_000;
     const EPS: f64 = 3.0e-7;
     const FPMIN: f64 = 1.0e-30;
 
@@ -661,21 +744,44 @@ pub fn cuped_adjust(
     user_ids: &[String],
     covariates: &HashMap<String, f64>,
 ) -> Vec<(f64, f64)> {
-    if covariates.is_empty() || experiment_values.len() != user_ids.len() {
+    let covariates: HashMap<String, Vec<f64>> = covariates
+        .iter()
+        .map(|(uid, &x)| (uid.clone(), vec![x]))
+        .collect();
+    cuped_adjust_multi(experiment_values, user_ids, &covariates)
+}
+
+/// Multi-covariate CUPED (regression adjustment, a.k.a. CUPED++).
+///
+/// Each user maps to a vector of pre-experiment covariates. The adjustment is
+///   Y_adj = Y - sum_j theta_j * (X_ij - mean(X_j))
+/// where theta solves Cov(X, X) * theta = Cov(X, Y), i.e. the OLS coefficients
+/// of Y on X. With a single covariate this reduces to [`cuped_adjust`].
+///
+/// Users without a full covariate vector pass through unchanged. Returns the
+/// original values if fewer than 100 users match or Cov(X, X) is singular;
+/// use [`cuped_select_covariates`] to drop constant or collinear columns first.
+pub fn cuped_adjust_multi(
+    experiment_values: &[(f64, f64)],
+    user_ids: &[String],
+    covariates: &HashMap<String, Vec<f64>>,
+) -> Vec<(f64, f64)> {
+    let k = covariates.values().next().map_or(0, Vec::len);
+    if k == 0 || experiment_values.len() != user_ids.len() {
         return experiment_values.to_vec();
     }
 
-    // Collect matched (index, rate, covariate) triples
-    let matched: Vec<(usize, f64, f64)> = user_ids
+    // Collect matched (index, rate, covariates) triples
+    let matched: Vec<(usize, f64, &[f64])> = user_ids
         .iter()
         .enumerate()
         .filter_map(|(idx, uid)| {
-            let (_, searches) = experiment_values[idx];
+            let (clicks, searches) = experiment_values[idx];
             if searches <= 0.0 {
                 return None;
             }
-            let rate = experiment_values[idx].0 / searches;
-            covariates.get(uid).map(|&cov| (idx, rate, cov))
+            let xs = covariates.get(uid).filter(|xs| xs.len() == k)?;
+            Some((idx, clicks / searches, xs.as_slice()))
         })
         .collect();
 
@@ -683,43 +789,120 @@ pub fn cuped_adjust(
         return experiment_values.to_vec();
     }
 
-    // Compute mean(X) and mean(Y) over matched users
+    let rows: Vec<&[f64]> = matched.iter().map(|(_, _, xs)| *xs).collect();
+    let (mean_x, cov_xx) = covariance_matrix(&rows, k);
     let n = matched.len() as f64;
-    let mean_x = matched.iter().map(|(_, _, x)| x).sum::<f64>() / n;
     let mean_y = matched.iter().map(|(_, y, _)| y).sum::<f64>() / n;
+    let cov_xy: Vec<f64> = (0..k)
+        .map(|j| {
+            matched
+                .iter()
+                .map(|(_, y, xs)| (y - mean_y) * (xs[j] - mean_x[j]))
+                .sum::<f64>()
+                / (n - 1.0)
+        })
+        .collect();
 
-    // Compute Var(X) and Cov(Y, X)
-    let var_x = matched
-        .iter()
-        .map(|(_, _, x)| (x - mean_x).powi(2))
-        .sum::<f64>()
-        / (n - 1.0);
-
-    if var_x < 1e-15 {
+    let Some(theta) = solve_linear_system(cov_xx, cov_xy) else {
         return experiment_values.to_vec();
+    };
+
+    // Apply adjustment: Y_adj = Y - theta . (X_i - mean_X)
+    let mut result = experiment_values.to_vec();
+    for &(idx, rate, xs) in &matched {
+        let searches = result[idx].1;
+        let correction: f64 = (0..k).map(|j| theta[j] * (xs[j] - mean_x[j])).sum();
+        result[idx] = ((rate - correction) * searches, searches);
     }
 
-    let cov_yx = matched
-        .iter()
-        .map(|(_, y, x)| (y - mean_y) * (x - mean_x))
-        .sum::<f64>()
-        / (n - 1.0);
+    result
+}
 
-    let theta = cov_yx / var_x;
+/// Pick the covariate columns usable for [`cuped_adjust_multi`].
+///
+/// Columns are considered in order and kept only if they add information:
+/// constant columns and columns that are linear combinations of already-kept
+/// ones are dropped so that Cov(X, X) stays invertible. Returns the indices
+/// of the kept columns.
+pub fn cuped_select_covariates(covariates: &HashMap<String, Vec<f64>>) -> Vec<usize> {
+    let k = covariates.values().next().map_or(0, Vec::len);
+    let rows: Vec<&[f64]> = covariates
+        .values()
+        .filter(|xs| xs.len() == k)
+        .map(Vec::as_slice)
+        .collect();
+    if rows.len() < 2 {
+        return Vec::new();
+    }
+    let (_, cov) = covariance_matrix(&rows, k);
 
-    // Apply adjustment: Y_adj = Y - theta * (X_i - mean_X)
-    let mut result = experiment_values.to_vec();
-    for &(idx, _rate, cov) in &matched {
-        let (clicks, searches) = result[idx];
-        if searches <= 0.0 {
-            continue;
+    let mut kept: Vec<usize> = Vec::new();
+    for j in 0..k {
+        let mut candidate = kept.clone();
+        candidate.push(j);
+        let sub: Vec<Vec<f64>> = candidate
+            .iter()
+            .map(|&r| candidate.iter().map(|&c| cov[r][c]).collect())
+            .collect();
+        if solve_linear_system(sub, vec![0.0; candidate.len()]).is_some() {
+            kept = candidate;
         }
-        let rate = clicks / searches;
-        let adjusted_rate = rate - theta * (cov - mean_x);
-        result[idx] = (adjusted_rate * searches, searches);
     }
+    kept
+}
 
-    result
+/// Column means and sample covariance matrix of `rows` (each of length `k`).
+fn covariance_matrix(rows: &[&[f64]], k: usize) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let n = rows.len() as f64;
+    let mean: Vec<f64> = (0..k)
+        .map(|j| rows.iter().map(|xs| xs[j]).sum::<f64>() / n)
+        .collect();
+    let mut cov = vec![vec![0.0; k]; k];
+    for xs in rows {
+        for r in 0..k {
+            for c in r..k {
+                cov[r][c] += (xs[r] - mean[r]) * (xs[c] - mean[c]);
+            }
+        }
+    }
+    for r in 0..k {
+        for c in r..k {
+            cov[r][c] /= n - 1.0;
+            cov[c][r] = cov[r][c];
+        }
+    }
+    (mean, cov)
+}
+
+/// Solve `a * x = b` by Gaussian elimination with partial pivoting.
+/// Returns `None` when `a` is (numerically) singular.
+fn solve_linear_system(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let k = b.len();
+    let scale = (0..k).map(|i| a[i][i].abs()).fold(0.0, f64::max);
+    let tolerance = (1e-12 * scale).max(1e-15);
+
+    for col in 0..k {
+        let pivot = (col..k).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < tolerance {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in col + 1..k {
+            let factor = a[row][col] / a[col][col];
+            for c in col..k {
+                a[row][c] -= factor * a[col][c];
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; k];
+    for row in (0..k).rev() {
+        let tail: f64 = (row + 1..k).map(|c| a[row][c] * x[c]).sum();
+        x[row] = (b[row] - tail) / a[row][row];
+    }
+    Some(x)
 }
 
 // ── Interleaving Preference Scoring ─────────────────────────────────
@@ -960,6 +1143,17 @@ mod tests {
 
     // ── SRM Detection ───────────────────────────────────────────────
 
+    #[test]
+    fn binomial_upper_tail_matches_exact_values() {
+        assert_eq!(binomial_upper_tail(0, 10, 0.05), 1.0);
+        assert_eq!(binomial_upper_tail(11, 10, 0.05), 0.0);
+        // P(X >= 1) = 1 - 0.95^10
+        assert!((binomial_upper_tail(1, 10, 0.05) - (1.0 - 0.95f64.powi(10))).abs() < 1e-12);
+        // P(X >= 2) for Binomial(4, 0.5) = 11/16
+        assert!((binomial_upper_tail(2, 4, 0.5) - 11.0 / 16.0).abs() < 1e-12);
+        assert!(binomial_upper_tail(10, 30, 0.05) < 0.001);
+    }
+
     #[test]
     fn srm_not_detected_for_perfect_50_50() {
         assert!(!check_sample_ratio_mismatch(5000, 5000, 0.5));
@@ -1095,6 +1289,45 @@ mod tests {
         assert!(z > 0.0, "z(0.8) should be positive, got {}", z);
     }
 
+    #[test]
+    fn expected_loss_of_identical_uniform_arms_is_one_sixth() {
+        // A, B ~ Uniform(0,1): E[max(A - B, 0)] = 1/6.
+        let loss = beta_binomial_expected_loss(0, 0, 0, 0);
+        assert!((loss - 1.0 / 6.0).abs() < 1e-9, "loss = {loss}");
+    }
+
+    #[test]
+    fn expected_loss_is_small_for_clear_winner() {
+        // Variant 20% vs control 10% on 1000 trials each.
+        let loss_ship_variant = beta_binomial_expected_loss(200, 1000, 100, 1000);
+        let loss_keep_control = beta_binomial_expected_loss(100, 1000, 200, 1000);
+        assert!(loss_ship_variant < 1e-6, "{loss_ship_variant}");
+        assert!(
+            (loss_keep_control - 0.1).abs() < 0.005,
+            "{loss_keep_control}"
+        );
+    }
+
+    #[test]
+    fn credible_interval_matches_known_quantiles() {
+        // Beta(1,1): quantiles are the probabilities themselves.
+        let (lo, hi) = beta_credible_interval(0, 0, 0.95);
+        assert!((lo - 0.025).abs() < 1e-6 && (hi - 0.975).abs() < 1e-6);
+        // Beta(2,1): CDF is x^2, so quantile q is sqrt(q).
+        let (lo, hi) = beta_credible_interval(1, 1, 0.95);
+        assert!((lo - 0.025_f64.sqrt()).abs() < 1e-6, "{lo}");
+        assert!((hi - 0.975_f64.sqrt()).abs() < 1e-6, "{hi}");
+    }
+
+    #[test]
+    fn credible_interval_narrows_with_more_data() {
+        let (lo_small, hi_small) = beta_credible_interval(10, 100, 0.95);
+        let (lo_big, hi_big) = beta_credible_interval(1_000, 10_000, 0.95);
+        assert!(lo_small < 0.1 && hi_small > 0.1);
+        assert!(lo_big < 0.1 && hi_big > 0.1);
+        assert!(hi_big - lo_big < hi_small - lo_small);
+    }
+
     #[test]
     fn beta_binomial_invalid_clicks_do_not_panic() {
         let prob = beta_binomial_prob_b_greater_a(11, 10, 5, 10);
@@ -1348,6 +1581,80 @@ mod tests {
         assert!(any_changed, "matched users should have adjusted values");
     }
 
+    fn rate_variance(values: &[(f64, f64)]) -> f64 {
+        let rates: Vec<f64> = values.iter().map(|(c, s)| c / s).collect();
+        let mean = rates.iter().sum::<f64>() / rates.len() as f64;
+        rates.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (rates.len() - 1) as f64
+    }
+
+    #[test]
+    fn cuped_multi_uses_every_covariate() {
+        // Outcome depends on two independent pre-experiment covariates.
+        let user_ids: Vec<String> = (0..200).map(|i| format!("user_{i}")).collect();
+        let xs: Vec<(f64, f64)> = (0..200)
+            .map(|i| ((i % 10) as f64 / 10.0, ((i * 7) % 13) as f64 / 13.0))
+            .collect();
+        let experiment_values: Vec<(f64, f64)> = xs
+            .iter()
+            .map(|(x1, x2)| ((0.1 + 0.3 * x1 + 0.2 * x2) * 10.0, 10.0))
+            .collect();
+        let both: HashMap<String, Vec<f64>> = user_ids
+            .iter()
+            .zip(&xs)
+            .map(|(uid, (x1, x2))| (uid.clone(), vec![*x1, *x2]))
+            .collect();
+        let first_only: HashMap<String, f64> = user_ids
+            .iter()
+            .zip(&xs)
+            .map(|(uid, (x1, _))| (uid.clone(), *x1))
+            .collect();
+
+        let single = cuped_adjust(&experiment_values, &user_ids, &first_only);
+        let multi = cuped_adjust_multi(&experiment_values, &user_ids, &both);
+
+        assert!(rate_variance(&single) > 1e-4, "{}", rate_variance(&single));
+        assert!(rate_variance(&multi) < 1e-12, "{}", rate_variance(&multi));
+    }
+
+    #[test]
+    fn cuped_multi_singular_covariates_return_original() {
+        let user_ids: Vec<String> = (0..100).map(|i| format!("user_{i}")).collect();
+        let experiment_values: Vec<(f64, f64)> =
+            (0..100).map(|i| ((i as f64) * 0.1, 10.0)).collect();
+        // Second column is a multiple of the first → Cov(X, X) is singular.
+        let covariates: HashMap<String, Vec<f64>> = (0..100)
+            .map(|i| (format!("user_{i}"), vec![i as f64, 2.0 * i as f64]))
+            .collect();
+
+        let adjusted = cuped_adjust_multi(&experiment_values, &user_ids, &covariates);
+        assert_eq!(adjusted, experiment_values);
+    }
+
+    #[test]
+    fn cuped_select_covariates_drops_constant_and_collinear_columns() {
+        let covariates: HashMap<String, Vec<f64>> = (0..50)
+            .map(|i| {
+                let x1 = i as f64;
+                let x2 = ((i * 7) % 11) as f64;
+                (format!("user_{i}"), vec![x1, 3.0, 2.0 * x1 + 1.0, x2])
+            })
+            .collect();
+        assert_eq!(cuped_select_covariates(&covariates), vec![0, 3]);
+        assert!(cuped_select_covariates(&HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn solve_linear_system_solves_and_detects_singularity() {
+        let x = solve_linear_system(vec![vec![2.0, 1.0], vec![1.0, 3.0]], vec![3.0, 5.0]).unwrap();
+        assert!(
+            (x[0] - 0.8).abs() < 1e-12 && (x[1] - 1.4).abs() < 1e-12,
+            "{x:?}"
+        );
+        assert!(
+            solve_linear_system(vec![vec![1.0, 2.0], vec![2.0, 4.0]], vec![1.0, 2.0]).is_none()
+        );
+    }
+
     // ── Interleaving preference scoring tests ───────────────────────────
 
     #[test]
@@ -1,8 +1,18 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use dashmap::DashMap;
 
-use super::config::{Experiment, ExperimentConclusion, ExperimentError, ExperimentStatus};
+use super::config::{
+    Experiment, ExperimentConclusion, ExperimentError, ExperimentStatus, TrafficStage,
+    LAYER_BUCKETS,
+};
+use super::shadow::{ShadowConfig, ShadowReport, ShadowSample, ShadowStats};
+
+/// Per-index holdout percentages, stored next to the experiment files.
+const HOLDOUTS_FILE: &str = "_holdouts.json";
+/// Per-index shadow evaluations, stored the same way.
+const SHADOWS_FILE: &str = "_shadows.json";
 
 fn now_ms() -> i64 {
     chrono::Utc::now().timestamp_millis()
@@ -15,6 +25,10 @@ pub struct ExperimentFilter {
 
 pub struct ExperimentStore {
     experiments: DashMap<String, Experiment>,
+    holdouts: DashMap<String, f64>,
+    shadows: DashMap<String, ShadowConfig>,
+    /// Comparison totals since the shadow was last set; kept in memory only.
+    shadow_stats: DashMap<String, ShadowStats>,
     dir: PathBuf,
 }
 
@@ -24,6 +38,9 @@ impl ExperimentStore {
         std::fs::create_dir_all(&dir)?;
         let store = Self {
             experiments: DashMap::new(),
+            holdouts: DashMap::new(),
+            shadows: DashMap::new(),
+            shadow_stats: DashMap::new(),
             dir,
         };
         store.load_all()?;
@@ -31,9 +48,24 @@ impl ExperimentStore {
     }
 
     fn load_all(&self) -> Result<(), ExperimentError> {
+        let holdouts_path = self.dir.join(HOLDOUTS_FILE);
+        if holdouts_path.exists() {
+            let data = std::fs::read_to_string(&holdouts_path)?;
+            let holdouts: BTreeMap<String, f64> = serde_json::from_str(&data)?;
+            self.holdouts.extend(holdouts);
+        }
+        let shadows_path = self.dir.join(SHADOWS_FILE);
+        if shadows_path.exists() {
+            let data = std::fs::read_to_string(&shadows_path)?;
+            let shadows: BTreeMap<String, ShadowConfig> = serde_json::from_str(&data)?;
+            self.shadows.extend(shadows);
+        }
         for entry in std::fs::read_dir(&self.dir)? {
             let entry = entry?;
             let path = entry.path();
+            if path == holdouts_path || path == shadows_path {
+                continue;
+            }
             if path.extension().and_then(|e| e.to_str()) == Some("json")
                 && !path
                     .file_name()
@@ -113,6 +145,42 @@ impl ExperimentStore {
         Ok(experiment)
     }
 
+    /// Replace a draft experiment's assignment salt, reshuffling which arm
+    /// each unit lands in. Running experiments keep theirs: changing it would
+    /// move users between arms mid-test.
+    pub fn rotate_salt(&self, id: &str, salt: String) -> Result<Experiment, ExperimentError> {
+        let mut experiment = self.get(id)?;
+        if experiment.status != ExperimentStatus::Draft {
+            return Err(ExperimentError::InvalidStatus(format!(
+                "{:?}",
+                experiment.status
+            )));
+        }
+        experiment.assignment_salt = Some(salt);
+        self.atomic_write(&experiment)?;
+        self.experiments.insert(id.to_string(), experiment.clone());
+        Ok(experiment)
+    }
+
+    /// Lowercased request headers that running experiments assign by.
+    pub fn assignment_headers(&self) -> Vec<String> {
+        let mut headers: Vec<String> = self
+            .experiments
+            .iter()
+            .filter(|entry| entry.value().status == ExperimentStatus::Running)
+            .filter_map(|entry| {
+                entry
+                    .value()
+                    .assignment_header
+                    .as_deref()
+                    .map(str::to_ascii_lowercase)
+            })
+            .collect();
+        headers.sort();
+        headers.dedup();
+        headers
+    }
+
     pub fn start(&self, id: &str) -> Result<Experiment, ExperimentError> {
         let mut experiment = self.get(id)?;
         if experiment.status != ExperimentStatus::Draft {
@@ -121,21 +189,71 @@ impl ExperimentStore {
                 experiment.status
             )));
         }
-        // Prevent multiple active experiments on the same index
-        if self.get_active_for_index(&experiment.index_name).is_some() {
+        experiment.layer_buckets = self.reserve_layer_buckets(&experiment)?;
+        let now = now_ms();
+        if let Some(first) = experiment.ramp_stages.as_ref().and_then(|s| s.first()) {
+            experiment.traffic_split = *first;
+        }
+        experiment.status = ExperimentStatus::Running;
+        experiment.started_at = Some(now);
+        experiment.traffic_history = vec![TrafficStage {
+            traffic_split: experiment.traffic_split,
+            started_at: now,
+        }];
+        self.atomic_write(&experiment)?;
+        self.experiments.insert(id.to_string(), experiment.clone());
+        Ok(experiment)
+    }
+
+    /// Move a running experiment to a new traffic split, starting a new stage
+    /// in its traffic history. Raising the split only moves control users
+    /// into the variant; lowering it moves some variant users back.
+    pub fn ramp(&self, id: &str, traffic_split: f64) -> Result<Experiment, ExperimentError> {
+        let mut experiment = self.get(id)?;
+        if experiment.status != ExperimentStatus::Running {
+            return Err(ExperimentError::InvalidStatus(format!(
+                "{:?}",
+                experiment.status
+            )));
+        }
+        if traffic_split.is_nan() || traffic_split <= 0.0 || traffic_split >= 1.0 {
+            return Err(ExperimentError::InvalidConfig(
+                "trafficSplit must be in (0.0, 1.0) exclusive".to_string(),
+            ));
+        }
+        if traffic_split == experiment.traffic_split {
             return Err(ExperimentError::InvalidConfig(format!(
-                "index '{}' already has a running experiment",
-                experiment.index_name
+                "experiment is already at trafficSplit {}",
+                traffic_split
             )));
         }
-        experiment.status = ExperimentStatus::Running;
-        experiment.started_at = Some(now_ms());
+        if experiment.traffic_history.is_empty() {
+            // Started before traffic history was recorded.
+            experiment.traffic_history.push(TrafficStage {
+                traffic_split: experiment.traffic_split,
+                started_at: experiment.started_at.unwrap_or_else(now_ms),
+            });
+        }
+        experiment.traffic_split = traffic_split;
+        experiment.traffic_history.push(TrafficStage {
+            traffic_split,
+            started_at: now_ms(),
+        });
         self.atomic_write(&experiment)?;
         self.experiments.insert(id.to_string(), experiment.clone());
         Ok(experiment)
     }
 
     pub fn stop(&self, id: &str) -> Result<Experiment, ExperimentError> {
+        self.stop_inner(id, None)
+    }
+
+    /// Stop a running experiment and record why (used by the auto-stop checker).
+    pub fn stop_with_reason(&self, id: &str, reason: &str) -> Result<Experiment, ExperimentError> {
+        self.stop_inner(id, Some(reason.to_string()))
+    }
+
+    fn stop_inner(&self, id: &str, reason: Option<String>) -> Result<Experiment, ExperimentError> {
         let mut experiment = self.get(id)?;
         if experiment.status != ExperimentStatus::Running {
             return Err(ExperimentError::InvalidStatus(format!(
@@ -145,6 +263,24 @@ impl ExperimentStore {
         }
         experiment.status = ExperimentStatus::Stopped;
         experiment.ended_at = Some(now_ms());
+        experiment.stop_reason = reason;
+        self.atomic_write(&experiment)?;
+        self.experiments.insert(id.to_string(), experiment.clone());
+        Ok(experiment)
+    }
+
+    /// Persist the start of the current run of guard-rail alerts (`None`
+    /// clears it once alerts stop firing).
+    pub fn set_guard_rail_alert_since(
+        &self,
+        id: &str,
+        since: Option<i64>,
+    ) -> Result<Experiment, ExperimentError> {
+        let mut experiment = self.get(id)?;
+        if experiment.guard_rail_alert_since == since {
+            return Ok(experiment);
+        }
+        experiment.guard_rail_alert_since = since;
         self.atomic_write(&experiment)?;
         self.experiments.insert(id.to_string(), experiment.clone());
         Ok(experiment)
@@ -190,6 +326,214 @@ impl ExperimentStore {
         Ok(())
     }
 
+    /// Start drafts whose `scheduledStartAt` and stop running experiments
+    /// whose `scheduledEndAt` has passed. A draft that cannot start yet (its
+    /// index is busy or its layer is full) stays queued and is retried on the
+    /// next call.
+    /// Returns the experiments that changed status.
+    pub fn run_scheduled_transitions(&self, now_ms: i64) -> Vec<Experiment> {
+        let due: Vec<Experiment> = self
+            .experiments
+            .iter()
+            .filter(|entry| {
+                let e = entry.value();
+                match e.status {
+                    ExperimentStatus::Draft => e.scheduled_start_at.is_some_and(|t| t <= now_ms),
+                    ExperimentStatus::Running => e.scheduled_end_at.is_some_and(|t| t <= now_ms),
+                    _ => false,
+                }
+            })
+            .map(|entry| entry.value().clone())
+            .collect();
+
+        let mut changed = Vec::new();
+        for experiment in due {
+            let result = match experiment.status {
+                // Stay queued (without warning every tick) until the index is free.
+                ExperimentStatus::Draft if self.reserve_layer_buckets(&experiment).is_err() => {
+                    continue
+                }
+                ExperimentStatus::Draft => self.start(&experiment.id),
+                _ => self.stop_with_reason(&experiment.id, "scheduled end reached"),
+            };
+            match result {
+                Ok(updated) => changed.push(updated),
+                Err(e) => tracing::warn!(
+                    "[EXPERIMENTS] scheduled transition for {} failed: {}",
+                    experiment.id,
+                    e
+                ),
+            }
+        }
+        changed
+    }
+
+    /// Running experiments on `index_name`, ordered by ID for stable selection.
+    pub fn running_for_index(&self, index_name: &str) -> Vec<Experiment> {
+        let mut running: Vec<Experiment> = self
+            .experiments
+            .iter()
+            .filter(|entry| {
+                entry.value().status == ExperimentStatus::Running
+                    && entry.value().index_name == index_name
+            })
+            .map(|entry| entry.value().clone())
+            .collect();
+        running.sort_by(|a, b| a.id.cmp(&b.id));
+        running
+    }
+
+    /// Decide whether `experiment` can start next to the index's running
+    /// experiments and, for a layered experiment, reserve the first free
+    /// range of layer buckets large enough for its `layerTraffic`.
+    ///
+    /// Only experiments sharing one layer can run concurrently on an index;
+    /// an experiment without a layer needs the index to itself.
+    fn reserve_layer_buckets(
+        &self,
+        experiment: &Experiment,
+    ) -> Result<Option<(u32, u32)>, ExperimentError> {
+        let running = self.running_for_index(&experiment.index_name);
+        let Some(layer) = experiment.layer.as_deref() else {
+            if running.is_empty() {
+                return Ok(None);
+            }
+            return Err(ExperimentError::InvalidConfig(format!(
+                "index '{}' already has a running experiment",
+                experiment.index_name
+            )));
+        };
+        if let Some(other) = running.iter().find(|e| e.layer.as_deref() != Some(layer)) {
+            return Err(ExperimentError::InvalidConfig(format!(
+                "index '{}' already has a running experiment outside layer '{}' ({})",
+                experiment.index_name, layer, other.id
+            )));
+        }
+
+        let size = experiment.layer_bucket_count();
+        let mut taken: Vec<(u32, u32)> = running.iter().filter_map(|e| e.layer_buckets).collect();
+        taken.sort_unstable();
+        let mut cursor = 0;
+        for (start, end) in taken {
+            if start.saturating_sub(cursor) >= size {
+                break;
+            }
+            cursor = cursor.max(end);
+        }
+        if cursor + size > LAYER_BUCKETS {
+            return Err(ExperimentError::InvalidConfig(format!(
+                "layer '{}' on index '{}' has no room for {}% more traffic",
+                layer,
+                experiment.index_name,
+                size as f64 / LAYER_BUCKETS as f64 * 100.0
+            )));
+        }
+        Ok(Some((cursor, cursor + size)))
+    }
+
+    /// Percentage of an index's users held out of all its experiments.
+    pub fn holdout_percent(&self, index_name: &str) -> f64 {
+        self.holdouts.get(index_name).map(|h| *h).unwrap_or(0.0)
+    }
+
+    pub fn list_holdouts(&self) -> BTreeMap<String, f64> {
+        self.holdouts
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect()
+    }
+
+    /// Set the holdout for `index_name`; `0` removes it.
+    pub fn set_holdout_percent(
+        &self,
+        index_name: &str,
+        percent: f64,
+    ) -> Result<(), ExperimentError> {
+        if percent.is_nan() || !(0.0..100.0).contains(&percent) {
+            return Err(ExperimentError::InvalidConfig(
+                "holdout percentage must be in [0, 100)".to_string(),
+            ));
+        }
+        if percent == 0.0 {
+            self.holdouts.remove(index_name);
+        } else {
+            self.holdouts.insert(index_name.to_string(), percent);
+        }
+        let tmp_path = self.dir.join(format!("{}.tmp", HOLDOUTS_FILE));
+        std::fs::write(
+            &tmp_path,
+            serde_json::to_string_pretty(&self.list_holdouts())?,
+        )?;
+        std::fs::rename(&tmp_path, self.dir.join(HOLDOUTS_FILE))?;
+        Ok(())
+    }
+
+    pub fn shadow_for(&self, index_name: &str) -> Option<ShadowConfig> {
+        self.shadows.get(index_name).map(|s| s.clone())
+    }
+
+    pub fn list_shadows(&self) -> BTreeMap<String, ShadowConfig> {
+        self.shadows
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect()
+    }
+
+    /// Start (or replace) the shadow evaluation of `index_name`, resetting
+    /// its comparison totals.
+    pub fn set_shadow(
+        &self,
+        index_name: &str,
+        mut config: ShadowConfig,
+    ) -> Result<ShadowConfig, ExperimentError> {
+        config.validate(index_name)?;
+        config.created_at = now_ms();
+        self.shadows.insert(index_name.to_string(), config.clone());
+        self.shadow_stats.remove(index_name);
+        self.write_shadows()?;
+        Ok(config)
+    }
+
+    /// Stop the shadow evaluation of `index_name`; false if there was none.
+    pub fn remove_shadow(&self, index_name: &str) -> Result<bool, ExperimentError> {
+        if self.shadows.remove(index_name).is_none() {
+            return Ok(false);
+        }
+        self.shadow_stats.remove(index_name);
+        self.write_shadows()?;
+        Ok(true)
+    }
+
+    fn write_shadows(&self) -> Result<(), ExperimentError> {
+        let tmp_path = self.dir.join(format!("{}.tmp", SHADOWS_FILE));
+        std::fs::write(
+            &tmp_path,
+            serde_json::to_string_pretty(&self.list_shadows())?,
+        )?;
+        std::fs::rename(&tmp_path, self.dir.join(SHADOWS_FILE))?;
+        Ok(())
+    }
+
+    /// Record a shadow run of one of `index_name`'s queries; `None` means
+    /// the shadow query failed.
+    pub fn record_shadow(&self, index_name: &str, sample: Option<&ShadowSample>) {
+        if !self.shadows.contains_key(index_name) {
+            return;
+        }
+        let mut stats = self.shadow_stats.entry(index_name.to_string()).or_default();
+        match sample {
+            Some(sample) => stats.record(sample),
+            None => stats.record_error(),
+        }
+    }
+
+    pub fn shadow_report(&self, index_name: &str) -> ShadowReport {
+        self.shadow_stats
+            .get(index_name)
+            .map(|s| s.report())
+            .unwrap_or_default()
+    }
+
     pub fn get_active_for_index(&self, index_name: &str) -> Option<Experiment> {
         self.experiments
             .iter()
@@ -218,6 +562,7 @@ mod tests {
                 name: "control".to_string(),
                 query_overrides: None,
                 index_name: None,
+                settings_delta: None,
             },
             variant: ExperimentArm {
                 name: "variant".to_string(),
@@ -226,6 +571,7 @@ mod tests {
                     ..Default::default()
                 }),
                 index_name: None,
+                settings_delta: None,
             },
             primary_metric: PrimaryMetric::Ctr,
             created_at: 1700000000000,
@@ -235,6 +581,22 @@ mod tests {
             winsorization_cap: None,
             conclusion: None,
             interleaving: None,
+            auto_stop: None,
+            guard_rail_alert_since: None,
+            stop_reason: None,
+            scheduled_start_at: None,
+            scheduled_end_at: None,
+            assignment_mode: None,
+            layer: None,
+            layer_traffic: None,
+            layer_buckets: None,
+            bayesian_loss_threshold: None,
+            aa_test: None,
+            assignment_unit: None,
+            assignment_header: None,
+            assignment_salt: None,
+            ramp_stages: None,
+            traffic_history: Vec::new(),
         }
     }
 
@@ -342,6 +704,42 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn start_and_ramp_record_traffic_history() {
+        let tmp = TempDir::new().unwrap();
+        let store = ExperimentStore::new(tmp.path()).unwrap();
+        let mut e = make_experiment("e1", "products");
+        e.ramp_stages = Some(vec![0.01, 0.05, 0.5]);
+        store.create(e).unwrap();
+        assert!(matches!(
+            store.ramp("e1", 0.05),
+            Err(ExperimentError::InvalidStatus(_))
+        ));
+
+        let started = store.start("e1").unwrap();
+        assert_eq!(started.traffic_split, 0.01);
+        assert_eq!(started.traffic_history.len(), 1);
+        assert_eq!(
+            started.traffic_history[0].started_at,
+            started.started_at.unwrap()
+        );
+
+        assert!(store.ramp("e1", 0.01).is_err());
+        assert!(store.ramp("e1", 1.0).is_err());
+        let ramped = store.ramp("e1", 0.05).unwrap();
+        assert_eq!(ramped.traffic_split, 0.05);
+        let splits: Vec<f64> = ramped
+            .traffic_history
+            .iter()
+            .map(|s| s.traffic_split)
+            .collect();
+        assert_eq!(splits, vec![0.01, 0.05]);
+        assert_eq!(
+            store.get("e1").unwrap().traffic_history,
+            ramped.traffic_history
+        );
+    }
+
     #[test]
     fn stop_transitions_running_to_stopped() {
         let tmp = TempDir::new().unwrap();
@@ -353,6 +751,171 @@ mod tests {
         assert!(stopped.ended_at.is_some());
     }
 
+    #[test]
+    fn scheduled_transitions_start_and_stop_on_time() {
+        let tmp = TempDir::new().unwrap();
+        let store = ExperimentStore::new(tmp.path()).unwrap();
+        let mut e = make_experiment("e1", "products");
+        e.scheduled_start_at = Some(1_000);
+        e.scheduled_end_at = Some(2_000);
+        store.create(e).unwrap();
+
+        assert!(store.run_scheduled_transitions(999).is_empty());
+        let started = store.run_scheduled_transitions(1_000);
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].status, ExperimentStatus::Running);
+
+        assert!(store.run_scheduled_transitions(1_999).is_empty());
+        let stopped = store.run_scheduled_transitions(2_000);
+        assert_eq!(stopped.len(), 1);
+        assert_eq!(stopped[0].status, ExperimentStatus::Stopped);
+        assert_eq!(
+            stopped[0].stop_reason.as_deref(),
+            Some("scheduled end reached")
+        );
+        assert!(store.run_scheduled_transitions(3_000).is_empty());
+    }
+
+    #[test]
+    fn scheduled_start_waits_for_index_to_be_free() {
+        let tmp = TempDir::new().unwrap();
+        let store = ExperimentStore::new(tmp.path()).unwrap();
+        store
+            .create(make_experiment("running", "products"))
+            .unwrap();
+        store.start("running").unwrap();
+        let mut e = make_experiment("queued", "products");
+        e.scheduled_start_at = Some(1_000);
+        store.create(e).unwrap();
+
+        assert!(store.run_scheduled_transitions(5_000).is_empty());
+        assert_eq!(store.get("queued").unwrap().status, ExperimentStatus::Draft);
+
+        store.stop("running").unwrap();
+        assert_eq!(store.run_scheduled_transitions(6_000).len(), 1);
+    }
+
+    #[test]
+    fn layered_experiments_share_an_index() {
+        let tmp = TempDir::new().unwrap();
+        let store = ExperimentStore::new(tmp.path()).unwrap();
+        let layered = |id: &str, share: f64| {
+            let mut e = make_experiment(id, "products");
+            e.layer = Some("ranking".to_string());
+            e.layer_traffic = Some(share);
+            e
+        };
+        store.create(layered("a", 0.5)).unwrap();
+        store.create(layered("b", 0.3)).unwrap();
+        store.create(layered("c", 0.3)).unwrap();
+        store.create(make_experiment("plain", "products")).unwrap();
+
+        assert_eq!(store.start("a").unwrap().layer_buckets, Some((0, 5_000)));
+        assert_eq!(
+            store.start("b").unwrap().layer_buckets,
+            Some((5_000, 8_000))
+        );
+        // Only 20% of the layer is left.
+        assert!(store.start("c").is_err());
+        // An unlayered experiment still needs the index to itself.
+        assert!(store.start("plain").is_err());
+
+        // Stopping frees the range for the next experiment.
+        store.stop("a").unwrap();
+        assert_eq!(store.start("c").unwrap().layer_buckets, Some((0, 3_000)));
+        assert_eq!(store.running_for_index("products").len(), 2);
+    }
+
+    #[test]
+    fn experiments_in_different_layers_cannot_overlap() {
+        let tmp = TempDir::new().unwrap();
+        let store = ExperimentStore::new(tmp.path()).unwrap();
+        let mut ranking = make_experiment("ranking", "products");
+        ranking.layer = Some("ranking".to_string());
+        ranking.layer_traffic = Some(0.5);
+        let mut ui = make_experiment("ui", "products");
+        ui.layer = Some("ui".to_string());
+        ui.layer_traffic = Some(0.5);
+        store.create(ranking).unwrap();
+        store.create(ui).unwrap();
+
+        store.start("ranking").unwrap();
+        assert!(matches!(
+            store.start("ui"),
+            Err(ExperimentError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn holdouts_persist_across_reload() {
+        let tmp = TempDir::new().unwrap();
+        {
+            let store = ExperimentStore::new(tmp.path()).unwrap();
+            store.create(make_experiment("e1", "products")).unwrap();
+            assert_eq!(store.holdout_percent("products"), 0.0);
+            store.set_holdout_percent("products", 5.0).unwrap();
+            store.set_holdout_percent("articles", 10.0).unwrap();
+            store.set_holdout_percent("articles", 0.0).unwrap();
+            assert!(store.set_holdout_percent("products", 100.0).is_err());
+        }
+        let store = ExperimentStore::new(tmp.path()).unwrap();
+        assert_eq!(store.holdout_percent("products"), 5.0);
+        assert_eq!(store.list_holdouts().len(), 1);
+        assert_eq!(store.list(None).len(), 1);
+    }
+
+    #[test]
+    fn shadows_persist_but_stats_reset() {
+        use crate::experiments::shadow::ShadowConfig;
+
+        let tmp = TempDir::new().unwrap();
+        let config = ShadowConfig {
+            percentage: 10.0,
+            candidate_index: Some("products_v2".to_string()),
+            query_overrides: None,
+            created_at: 0,
+        };
+        {
+            let store = ExperimentStore::new(tmp.path()).unwrap();
+            store.create(make_experiment("e1", "products")).unwrap();
+            store.set_shadow("products", config.clone()).unwrap();
+            store.record_shadow("products", None);
+            store.record_shadow("articles", None);
+            assert_eq!(store.shadow_report("products").errors, 1);
+            assert_eq!(store.shadow_report("articles").errors, 0);
+            store.set_shadow("products", config).unwrap();
+            assert_eq!(store.shadow_report("products").errors, 0);
+        }
+        let store = ExperimentStore::new(tmp.path()).unwrap();
+        let shadow = store.shadow_for("products").unwrap();
+        assert_eq!(shadow.candidate_index.as_deref(), Some("products_v2"));
+        assert!(shadow.created_at > 0);
+        assert_eq!(store.list(None).len(), 1);
+        assert!(store.remove_shadow("products").unwrap());
+        assert!(!store.remove_shadow("products").unwrap());
+        assert!(store.list_shadows().is_empty());
+    }
+
+    #[test]
+    fn stop_with_reason_persists_reason_and_alert_window() {
+        let tmp = TempDir::new().unwrap();
+        let store = ExperimentStore::new(tmp.path()).unwrap();
+        store.create(make_experiment("e1", "products")).unwrap();
+        store.start("e1").unwrap();
+        store
+            .set_guard_rail_alert_since("e1", Some(1_700_000_000_000))
+            .unwrap();
+        store
+            .stop_with_reason("e1", "sample ratio mismatch")
+            .unwrap();
+
+        let reloaded = ExperimentStore::new(tmp.path()).unwrap();
+        let e = reloaded.get("e1").unwrap();
+        assert_eq!(e.status, ExperimentStatus::Stopped);
+        assert_eq!(e.stop_reason.as_deref(), Some("sample ratio mismatch"));
+        assert_eq!(e.guard_rail_alert_since, Some(1_700_000_000_000));
+    }
+
     #[test]
     fn conclude_running_experiment_sets_status_and_conclusion() {
         let tmp = TempDir::new().unwrap();
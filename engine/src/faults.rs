@@ -0,0 +1,209 @@
+//! Fault injection for replication tests (`fault-injection` feature).
+//!
+//! Each [`IndexManager`](crate::IndexManager) owns a [`FaultInjector`], so
+//! the nodes of an in-process test cluster fail independently. Faults are
+//! counted rather than random — "drop the next 2 deliveries", "fail the
+//! next oplog write" — so a test replays the same scenario every run.
+//! Servers built with the feature expose it at `/internal/faults`.
+//!
+//! Never enable the feature in production builds.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use crate::error::{FlapjackError, Result};
+
+/// How long a reordered delivery waits for a later one before it is
+/// applied anyway (e.g. when the sender allows one batch in flight).
+const HOLD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Faults to inject. Counters go down as faults are injected.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FaultConfig {
+    /// Only inject into this index (all indexes when unset)
+    pub tenant: Option<String>,
+    /// Wait this long before applying each incoming `/internal/replicate`
+    /// delivery
+    pub replicate_delay_ms: u64,
+    /// Reject the next N deliveries with 503 without applying them
+    pub replicate_drop: u32,
+    /// Hold the next N deliveries until a later delivery has been applied
+    pub replicate_reorder: u32,
+    /// Fail the next N oplog appends with an I/O error
+    pub disk_write_errors: u32,
+}
+
+/// Faults injected since the last [`FaultInjector::clear`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaultCounts {
+    pub replicate_delayed: u64,
+    pub replicate_dropped: u64,
+    pub replicate_reordered: u64,
+    pub disk_write_errors: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaultStatus {
+    /// What is still armed
+    pub config: FaultConfig,
+    pub injected: FaultCounts,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Apply,
+    Drop,
+}
+
+#[derive(Default)]
+pub struct FaultInjector {
+    status: Mutex<FaultStatus>,
+    /// Wakes held deliveries once a later one has been applied.
+    released: Notify,
+}
+
+impl FaultInjector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Arm `config`, replacing whatever was armed before.
+    pub fn set(&self, config: FaultConfig) {
+        self.status.lock().unwrap().config = config;
+    }
+
+    /// Disarm everything, reset the counts and release held deliveries.
+    pub fn clear(&self) {
+        *self.status.lock().unwrap() = FaultStatus::default();
+        self.released.notify_waiters();
+    }
+
+    pub fn status(&self) -> FaultStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Called before each oplog append: `Err` while disk write errors are
+    /// armed for `tenant_id`.
+    pub fn check_disk_write(&self, tenant_id: &str) -> Result<()> {
+        let mut status = self.status.lock().unwrap();
+        if !applies(&status.config, tenant_id) || status.config.disk_write_errors == 0 {
+            return Ok(());
+        }
+        status.config.disk_write_errors -= 1;
+        status.injected.disk_write_errors += 1;
+        Err(FlapjackError::Io(format!(
+            "injected disk write error ({} oplog)",
+            tenant_id
+        )))
+    }
+
+    /// Called when a replicate delivery for `tenant_id` arrives. Sleeps for
+    /// the armed delay and, for a reordered delivery, until a later one has
+    /// been applied. Call [`after_replicate`](Self::after_replicate) once a
+    /// delivery answered with [`Delivery::Apply`] is applied.
+    pub async fn before_replicate(&self, tenant_id: &str) -> Delivery {
+        let (delay, hold) = {
+            let mut status = self.status.lock().unwrap();
+            if !applies(&status.config, tenant_id) {
+                return Delivery::Apply;
+            }
+            if status.config.replicate_drop > 0 {
+                status.config.replicate_drop -= 1;
+                status.injected.replicate_dropped += 1;
+                return Delivery::Drop;
+            }
+            let hold = status.config.replicate_reorder > 0;
+            if hold {
+                status.config.replicate_reorder -= 1;
+                status.injected.replicate_reordered += 1;
+            }
+            if status.config.replicate_delay_ms > 0 {
+                status.injected.replicate_delayed += 1;
+            }
+            (
+                Duration::from_millis(status.config.replicate_delay_ms),
+                hold,
+            )
+        };
+        if hold {
+            // Register before sleeping so a release during the delay counts.
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            tokio::time::sleep(delay).await;
+            let _ = tokio::time::timeout(HOLD_TIMEOUT, released).await;
+        } else if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        Delivery::Apply
+    }
+
+    /// Release deliveries held by `replicateReorder`.
+    pub fn after_replicate(&self) {
+        self.released.notify_waiters();
+    }
+}
+
+fn applies(config: &FaultConfig, tenant_id: &str) -> bool {
+    match &config.tenant {
+        Some(tenant) => tenant == tenant_id,
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn disk_write_errors_are_counted_down() {
+        let faults = FaultInjector::new();
+        faults.set(FaultConfig {
+            tenant: Some("products".to_string()),
+            disk_write_errors: 2,
+            ..Default::default()
+        });
+        assert!(faults.check_disk_write("other").is_ok());
+        assert!(faults.check_disk_write("products").is_err());
+        assert!(faults.check_disk_write("products").is_err());
+        assert!(faults.check_disk_write("products").is_ok());
+        let status = faults.status();
+        assert_eq!(status.config.disk_write_errors, 0);
+        assert_eq!(status.injected.disk_write_errors, 2);
+    }
+
+    #[tokio::test]
+    async fn held_delivery_is_applied_after_the_next_one() {
+        let faults = Arc::new(FaultInjector::new());
+        faults.set(FaultConfig {
+            replicate_drop: 1,
+            replicate_reorder: 1,
+            ..Default::default()
+        });
+        assert_eq!(faults.before_replicate("t").await, Delivery::Drop);
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let held = {
+            let (faults, order) = (Arc::clone(&faults), Arc::clone(&order));
+            tokio::spawn(async move {
+                assert_eq!(faults.before_replicate("t").await, Delivery::Apply);
+                order.lock().unwrap().push("first");
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(faults.before_replicate("t").await, Delivery::Apply);
+        order.lock().unwrap().push("second");
+        faults.after_replicate();
+        held.await.unwrap();
+
+        assert_eq!(*order.lock().unwrap(), vec!["second", "first"]);
+        assert_eq!(faults.status().injected.replicate_reordered, 1);
+        assert_eq!(faults.status().injected.replicate_dropped, 1);
+    }
+}
@@ -2,9 +2,15 @@
 //!
 //! Writes Parquet files directly to the analytics directory,
 //! producing 30 days of realistic search + click + conversion events.
+//! With an [`ExperimentFixture`], searches are split across the arms of an
+//! A/B test the way the search handler splits them, so the experiment's
+//! results page has data to show.
 
-use super::config::AnalyticsConfig;
-use super::schema::{InsightEvent, SearchEvent};
+use super::{corpus, seed_for, Preset, Rng, DEFAULT_SEED};
+use crate::analytics::config::AnalyticsConfig;
+use crate::analytics::schema::{InsightEvent, SearchEvent};
+use crate::experiments::assignment::{assign_variant, AssignmentMethod};
+use crate::experiments::config::Experiment;
 
 /// Default search queries for when we don't know the index content.
 const DEFAULT_QUERIES: &[(&str, u32, bool)] = &[
@@ -110,6 +116,34 @@ const PRODUCT_QUERIES: &[(&str, u32, bool)] = &[
     ("wholesale bulk", 0, false),
 ];
 
+/// Documentation-themed queries for help center and docs databases.
+const DOCS_QUERIES: &[(&str, u32, bool)] = &[
+    ("", 400, true),
+    ("api keys", 24, true),
+    ("synonyms", 18, true),
+    ("filters", 30, true),
+    ("facets", 26, true),
+    ("query rules", 15, true),
+    ("typo tolerance", 12, true),
+    ("ranking", 28, true),
+    ("replicas", 10, true),
+    ("snapshots", 9, true),
+    ("webhooks", 11, true),
+    ("rate limits", 8, true),
+    ("pagination", 14, true),
+    ("highlighting", 13, true),
+    ("geo search", 7, true),
+    ("a/b testing", 9, true),
+    ("batch indexing", 16, true),
+    ("troubleshooting", 40, true),
+    ("getting started", 35, true),
+    ("how to configure", 60, true),
+    ("pricing", 0, false),
+    ("contact support", 0, false),
+    ("login", 0, false),
+    ("delete account", 0, false),
+];
+
 /// Realistic country distribution with IP ranges and optional region (state).
 /// Format: (country, ip_prefix, weight, region)
 const GEO_DISTRIBUTION: &[(&str, &str, f64, Option<&str>)] = &[
@@ -168,55 +202,6 @@ const DEVICE_TAGS: &[(&str, f64)] = &[
     ("platform:tablet", 0.10),
 ];
 
-/// Simple deterministic pseudo-random number generator (xorshift32).
-/// Avoids pulling in the `rand` crate.
-struct Rng {
-    state: u32,
-}
-
-impl Rng {
-    fn new(seed: u32) -> Self {
-        Self {
-            state: if seed == 0 { 1 } else { seed },
-        }
-    }
-
-    fn next_u32(&mut self) -> u32 {
-        let mut x = self.state;
-        x ^= x << 13;
-        x ^= x >> 17;
-        x ^= x << 5;
-        self.state = x;
-        x
-    }
-
-    /// Returns a value in [0.0, 1.0).
-    fn next_f64(&mut self) -> f64 {
-        (self.next_u32() as f64) / (u32::MAX as f64)
-    }
-
-    /// Returns a value in [lo, hi].
-    fn range(&mut self, lo: u32, hi: u32) -> u32 {
-        if lo >= hi {
-            return lo;
-        }
-        lo + (self.next_u32() % (hi - lo + 1))
-    }
-
-    /// Pick an index based on weighted distribution.
-    fn weighted_pick(&mut self, weights: &[f64]) -> usize {
-        let r = self.next_f64();
-        let mut cumulative = 0.0;
-        for (i, &w) in weights.iter().enumerate() {
-            cumulative += w;
-            if r < cumulative {
-                return i;
-            }
-        }
-        weights.len() - 1
-    }
-}
-
 fn generate_query_id(rng: &mut Rng) -> String {
     let mut hex = String::with_capacity(32);
     for _ in 0..8 {
@@ -227,21 +212,19 @@ fn generate_query_id(rng: &mut Rng) -> String {
     hex
 }
 
+/// Pick the query set for a preset.
+fn queries_for(preset: Option<Preset>) -> &'static [(&'static str, u32, bool)] {
+    match preset {
+        Some(Preset::Media) => MOVIE_QUERIES,
+        Some(Preset::Ecommerce) => PRODUCT_QUERIES,
+        Some(Preset::Docs) => DOCS_QUERIES,
+        None => DEFAULT_QUERIES,
+    }
+}
+
 /// Pick the query set based on the index name.
 fn queries_for_index(index_name: &str) -> &'static [(&'static str, u32, bool)] {
-    let lower = index_name.to_lowercase();
-    if lower.contains("movie") || lower.contains("film") || lower.contains("tmdb") {
-        MOVIE_QUERIES
-    } else if lower.contains("product")
-        || lower.contains("bestbuy")
-        || lower.contains("shop")
-        || lower.contains("ecommerce")
-        || lower.contains("commerce")
-    {
-        PRODUCT_QUERIES
-    } else {
-        DEFAULT_QUERIES
-    }
+    queries_for(Preset::for_index(index_name))
 }
 
 /// Generate user tokens.
@@ -258,12 +241,66 @@ fn generate_object_ids(rng: &mut Rng, count: usize) -> Vec<String> {
         .collect()
 }
 
+/// Pick click targets among the first `documents` records of a fixture
+/// corpus.
+fn corpus_object_ids(rng: &mut Rng, preset: Preset, documents: usize, count: usize) -> Vec<String> {
+    (0..count.min(documents))
+        .map(|_| corpus::object_id(preset, rng.range(0, documents as u32 - 1) as usize))
+        .collect()
+}
+
+fn assignment_method_str(method: &AssignmentMethod) -> &'static str {
+    match method {
+        AssignmentMethod::UserToken => "user_token",
+        AssignmentMethod::SessionId => "session_id",
+        AssignmentMethod::Header => "header",
+        AssignmentMethod::QueryId => "query_id",
+        AssignmentMethod::External => "external",
+    }
+}
+
+/// A/B test to split generated searches across.
+pub struct ExperimentFixture {
+    pub experiment: Experiment,
+    /// Multiplier on the variant arm's click-through rate (1.1 = 10% more
+    /// clicks than control).
+    pub variant_ctr_lift: f64,
+}
+
+/// What [`generate_analytics`] writes.
+pub struct AnalyticsFixture {
+    pub days: u32,
+    pub seed: u32,
+    /// Query vocabulary; guessed from the index name when unset.
+    pub preset: Option<Preset>,
+    /// Size of the preset's corpus (see [`corpus::records`]). When set,
+    /// clicks and conversions target the corpus' objectIDs instead of
+    /// made-up ones.
+    pub documents: Option<usize>,
+    pub experiment: Option<ExperimentFixture>,
+}
+
+impl Default for AnalyticsFixture {
+    fn default() -> Self {
+        Self {
+            days: 30,
+            seed: DEFAULT_SEED,
+            preset: None,
+            documents: None,
+            experiment: None,
+        }
+    }
+}
+
 /// Result of seeding analytics data.
 pub struct SeedResult {
     pub days: u32,
     pub total_searches: usize,
     pub total_clicks: usize,
     pub total_conversions: usize,
+    /// Searches assigned to each arm of the fixture's experiment, if any.
+    pub control_searches: usize,
+    pub variant_searches: usize,
 }
 
 /// Seed analytics data for the given index.
@@ -275,15 +312,36 @@ pub fn seed_analytics(
     index_name: &str,
     days: u32,
 ) -> Result<SeedResult, String> {
-    let queries = queries_for_index(index_name);
-    let mut rng = Rng::new(
-        index_name
-            .bytes()
-            .fold(42u32, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u32)),
-    );
+    generate_analytics(
+        config,
+        index_name,
+        &AnalyticsFixture {
+            days,
+            ..Default::default()
+        },
+    )
+}
+
+/// Generate the analytics history described by `fixture` for the given
+/// index. The same fixture always yields the same events, shifted to end
+/// yesterday.
+pub fn generate_analytics(
+    config: &AnalyticsConfig,
+    index_name: &str,
+    fixture: &AnalyticsFixture,
+) -> Result<SeedResult, String> {
+    let days = fixture.days;
+    let preset = fixture.preset.or_else(|| Preset::for_index(index_name));
+    let queries = queries_for(preset);
+    let mut rng = Rng::new(seed_for(fixture.seed, index_name));
 
     let users = generate_users(&mut rng, 350);
-    let object_ids = generate_object_ids(&mut rng, 200);
+    let object_ids = match (preset, fixture.documents) {
+        (Some(preset), Some(documents)) if documents > 0 => {
+            corpus_object_ids(&mut rng, preset, documents, 200)
+        }
+        _ => generate_object_ids(&mut rng, 200),
+    };
 
     // Build query weight distribution (power-law: top queries get more traffic)
     let query_weights: Vec<f64> = queries
@@ -301,6 +359,8 @@ pub fn seed_analytics(
     let mut total_searches = 0usize;
     let mut total_clicks = 0usize;
     let mut total_conversions = 0usize;
+    let mut control_searches = 0usize;
+    let mut variant_searches = 0usize;
 
     for day_offset in (1..=days).rev() {
         let date = now - chrono::Duration::days(day_offset as i64);
@@ -358,6 +418,36 @@ pub fn seed_analytics(
                 None
             };
 
+            // Split by user, as the search handler does for engine-assigned
+            // experiments.
+            let mut ctr = 0.35;
+            let mut arm = None;
+            if let Some(fx) = &fixture.experiment {
+                let user = users[user_idx].as_str();
+                let assignment = assign_variant(
+                    &fx.experiment,
+                    Some(user),
+                    Some(user),
+                    Some(user),
+                    &query_id,
+                );
+                if assignment.arm == "variant" {
+                    ctr = (ctr * fx.variant_ctr_lift).min(1.0);
+                    variant_searches += 1;
+                } else {
+                    control_searches += 1;
+                }
+                arm = Some((
+                    fx.experiment.id.clone(),
+                    assignment.arm.to_string(),
+                    assignment_method_str(&assignment.method).to_string(),
+                ));
+            }
+            let (experiment_id, variant_id, assignment_method) = match arm {
+                Some((e, v, m)) => (Some(e), Some(v), Some(m)),
+                None => (None, None, None),
+            };
+
             day_searches.push(SearchEvent {
                 timestamp_ms: ts,
                 query: query_text.to_string(),
@@ -375,13 +465,17 @@ pub fn seed_analytics(
                 has_results,
                 country: Some(country_code.to_string()),
                 region: region.map(|r| r.to_string()),
-                experiment_id: None,
-                variant_id: None,
-                assignment_method: None,
+                experiment_id,
+                variant_id,
+                assignment_method,
+                applied_rules: Vec::new(),
+                synonyms_triggered: Vec::new(),
+                synonyms_matched: Vec::new(),
+                query_language: None,
             });
 
             // Generate click events (~35% CTR for searches with results)
-            if has_results && rng.next_f64() < 0.35 {
+            if has_results && rng.next_f64() < ctr {
                 // Click position: heavily weighted toward position 1
                 let position = generate_click_position(&mut rng);
                 let obj_idx = rng.range(0, object_ids.len() as u32 - 1) as usize;
@@ -453,6 +547,8 @@ pub fn seed_analytics(
         total_searches,
         total_clicks,
         total_conversions,
+        control_searches,
+        variant_searches,
     })
 }
 
@@ -489,10 +585,10 @@ fn write_search_events_to_partition(
     events: &[SearchEvent],
     partition_dir: &std::path::Path,
 ) -> Result<(), String> {
-    let schema = super::schema::search_event_schema();
-    let batch = super::writer::search_events_to_batch(events, &schema)?;
+    let schema = crate::analytics::schema::search_event_schema();
+    let batch = crate::analytics::writer::search_events_to_batch(events, &schema)?;
     let path = partition_dir.join("seed_searches.parquet");
-    super::writer::write_parquet_file(&path, batch)
+    crate::analytics::writer::write_parquet_file(&path, batch)
 }
 
 #[cfg(test)]
@@ -500,81 +596,6 @@ fn write_search_events_to_partition(
 mod tests {
     use super::*;
 
-    // --- Rng tests ---
-
-    #[test]
-    fn rng_zero_seed_becomes_one() {
-        let rng = Rng::new(0);
-        assert_eq!(rng.state, 1);
-    }
-
-    #[test]
-    fn rng_nonzero_seed_kept() {
-        let rng = Rng::new(42);
-        assert_eq!(rng.state, 42);
-    }
-
-    #[test]
-    fn rng_deterministic() {
-        let mut a = Rng::new(42);
-        let mut b = Rng::new(42);
-        for _ in 0..100 {
-            assert_eq!(a.next_u32(), b.next_u32());
-        }
-    }
-
-    #[test]
-    fn rng_next_f64_in_range() {
-        let mut rng = Rng::new(123);
-        for _ in 0..1000 {
-            let v = rng.next_f64();
-            assert!((0.0..1.0).contains(&v), "next_f64 out of range: {}", v);
-        }
-    }
-
-    #[test]
-    fn rng_range_within_bounds() {
-        let mut rng = Rng::new(99);
-        for _ in 0..500 {
-            let v = rng.range(5, 10);
-            assert!((5..=10).contains(&v), "range out of bounds: {}", v);
-        }
-    }
-
-    #[test]
-    fn rng_range_lo_equals_hi() {
-        let mut rng = Rng::new(1);
-        assert_eq!(rng.range(7, 7), 7);
-    }
-
-    #[test]
-    fn rng_range_lo_greater_than_hi() {
-        let mut rng = Rng::new(1);
-        assert_eq!(rng.range(10, 5), 10);
-    }
-
-    #[test]
-    fn rng_weighted_pick_single_weight() {
-        let mut rng = Rng::new(42);
-        // Only one weight — always picks index 0
-        for _ in 0..10 {
-            assert_eq!(rng.weighted_pick(&[1.0]), 0);
-        }
-    }
-
-    #[test]
-    fn rng_weighted_pick_extreme_weights() {
-        let mut rng = Rng::new(42);
-        // First weight is 0, second is 1 — index 0 can never be picked
-        // because r is in [0,1) and the condition `r < 0.0` is always false.
-        let mut counts = [0u32; 2];
-        for _ in 0..100 {
-            counts[rng.weighted_pick(&[0.0, 1.0])] += 1;
-        }
-        assert_eq!(counts[0], 0, "weight-0 index should never be picked");
-        assert_eq!(counts[1], 100, "weight-1 index should always be picked");
-    }
-
     // --- generate_query_id ---
 
     #[test]
@@ -616,6 +637,72 @@ mod tests {
         assert_eq!(s.len(), PRODUCT_QUERIES.len());
     }
 
+    #[test]
+    fn queries_for_docs_preset() {
+        assert_eq!(queries_for(Some(Preset::Docs)).len(), DOCS_QUERIES.len());
+        assert_eq!(queries_for_index("help_center")[1].0, "api keys");
+    }
+
+    // --- generate_analytics ---
+
+    #[test]
+    fn experiment_fixture_splits_searches_across_arms() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = AnalyticsConfig {
+            enabled: true,
+            data_dir: dir.path().to_path_buf(),
+            flush_interval_secs: 3600,
+            flush_size: 10_000,
+            retention_days: 90,
+            pii: Default::default(),
+        };
+        let experiment: Experiment = serde_json::from_value(serde_json::json!({
+            "id": "exp-fixture",
+            "name": "fixture",
+            "indexName": "products",
+            "status": "running",
+            "trafficSplit": 0.5,
+            "control": {"name": "control"},
+            "variant": {"name": "variant"},
+            "primaryMetric": "ctr",
+            "createdAt": 0,
+            "minimumDays": 1,
+        }))
+        .unwrap();
+        let fixture = AnalyticsFixture {
+            days: 2,
+            preset: Some(Preset::Ecommerce),
+            documents: Some(100),
+            experiment: Some(ExperimentFixture {
+                experiment,
+                variant_ctr_lift: 1.2,
+            }),
+            ..Default::default()
+        };
+
+        let a = generate_analytics(&config, "products", &fixture).unwrap();
+        assert_eq!(a.control_searches + a.variant_searches, a.total_searches);
+        assert!(a.control_searches > 0 && a.variant_searches > 0);
+        assert!(config.searches_dir("products").exists());
+
+        let b = generate_analytics(&config, "products", &fixture).unwrap();
+        assert_eq!(
+            (a.total_searches, a.total_clicks, a.variant_searches),
+            (b.total_searches, b.total_clicks, b.variant_searches)
+        );
+    }
+
+    #[test]
+    fn corpus_object_ids_stay_within_corpus() {
+        let mut rng = Rng::new(42);
+        let ids = corpus_object_ids(&mut rng, Preset::Media, 10, 200);
+        assert_eq!(ids.len(), 10);
+        for id in &ids {
+            let n: usize = id.trim_start_matches("movie-").parse().unwrap();
+            assert!((1..=10).contains(&n), "outside corpus: {}", id);
+        }
+    }
+
     // --- generate_users / generate_object_ids ---
 
     #[test]
@@ -679,7 +766,12 @@ mod tests {
 
     #[test]
     fn all_query_sets_have_no_result_entries() {
-        for queries in [DEFAULT_QUERIES, MOVIE_QUERIES, PRODUCT_QUERIES] {
+        for queries in [
+            DEFAULT_QUERIES,
+            MOVIE_QUERIES,
+            PRODUCT_QUERIES,
+            DOCS_QUERIES,
+        ] {
             assert!(
                 queries.iter().any(|(_, _, has_results)| !has_results),
                 "query set should have some no-result entries"
@@ -693,8 +785,8 @@ fn write_insight_events_to_partition(
     events: &[InsightEvent],
     partition_dir: &std::path::Path,
 ) -> Result<(), String> {
-    let schema = super::schema::insight_event_schema();
-    let batch = super::writer::insight_events_to_batch(events, &schema)?;
+    let schema = crate::analytics::schema::insight_event_schema();
+    let batch = crate::analytics::writer::insight_events_to_batch(events, &schema)?;
     let path = partition_dir.join("seed_events.parquet");
-    super::writer::write_parquet_file(&path, batch)
+    crate::analytics::writer::write_parquet_file(&path, batch)
 }
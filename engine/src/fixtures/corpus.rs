@@ -0,0 +1,323 @@
+//! Document corpora for the fixture presets.
+//!
+//! Records are plain JSON objects with an `objectID` of the form
+//! `<prefix>-<n>` (`prod-000001`, `doc-000001`, `movie-000001`). IDs depend
+//! only on the preset and position, so generated analytics can click the
+//! same objects whatever the seed.
+
+use serde_json::{json, Value};
+
+use super::{seed_for, Preset, Rng};
+use crate::index::templates::IndexTemplate;
+
+const BRANDS: &[&str] = &[
+    "Apple", "Samsung", "Sony", "Dell", "Google", "LG", "Bose", "Logitech", "HP", "Lenovo",
+];
+
+/// (category, product nouns, min price, max price) — prices in whole dollars.
+const PRODUCT_CATEGORIES: &[(&str, &[&str], u32, u32)] = &[
+    (
+        "Laptops",
+        &["laptop", "ultrabook", "gaming laptop"],
+        450,
+        2800,
+    ),
+    ("Phones", &["phone", "smartphone"], 180, 1400),
+    (
+        "Audio",
+        &[
+            "headphones",
+            "earbuds",
+            "bluetooth speaker",
+            "wireless speaker",
+        ],
+        25,
+        450,
+    ),
+    ("Tablets", &["tablet"], 120, 1100),
+    (
+        "Electronics",
+        &[
+            "monitor",
+            "keyboard",
+            "mouse",
+            "webcam",
+            "router",
+            "tv",
+            "camera",
+            "charger",
+            "printer",
+            "usb hub",
+            "ssd",
+            "microphone",
+            "hdmi cable",
+        ],
+        9,
+        900,
+    ),
+];
+
+const PRODUCT_MODIFIERS: &[&str] = &[
+    "Pro", "Ultra", "Wireless", "Gaming", "Compact", "Max", "Air", "Plus", "Mini", "Studio",
+];
+
+const DOC_SECTIONS: &[&str] = &[
+    "Getting started",
+    "Indexing",
+    "Search",
+    "Relevance",
+    "Analytics",
+    "Security",
+    "API reference",
+];
+
+const DOC_TOPICS: &[&str] = &[
+    "api keys",
+    "synonyms",
+    "query rules",
+    "facets",
+    "filters",
+    "typo tolerance",
+    "replicas",
+    "snapshots",
+    "geo search",
+    "pagination",
+    "highlighting",
+    "ranking",
+    "click analytics",
+    "a/b testing",
+    "webhooks",
+    "rate limits",
+    "batch indexing",
+    "personalization",
+];
+
+const DOC_TITLES: &[&str] = &[
+    "How to configure {}",
+    "{} reference",
+    "Troubleshooting {}",
+    "Introduction to {}",
+    "Best practices for {}",
+];
+
+const GENRES: &[&str] = &[
+    "Action",
+    "Comedy",
+    "Drama",
+    "Sci-Fi",
+    "Romance",
+    "Thriller",
+    "Horror",
+    "Animation",
+    "Documentary",
+    "Adventure",
+    "Crime",
+    "Musical",
+    "Western",
+];
+
+const DIRECTORS: &[&str] = &[
+    "Christopher Nolan",
+    "Steven Spielberg",
+    "Quentin Tarantino",
+    "Greta Gerwig",
+    "Denis Villeneuve",
+    "Kathryn Bigelow",
+    "Bong Joon-ho",
+    "Sofia Coppola",
+    "Ridley Scott",
+    "Ava DuVernay",
+];
+
+const ACTORS: &[&str] = &[
+    "Tom Hanks",
+    "Meryl Streep",
+    "Denzel Washington",
+    "Viola Davis",
+    "Leonardo DiCaprio",
+    "Cate Blanchett",
+    "Keanu Reeves",
+    "Zendaya",
+    "Samuel L. Jackson",
+    "Scarlett Johansson",
+    "Morgan Freeman",
+    "Emma Stone",
+];
+
+const TITLE_ADJECTIVES: &[&str] = &[
+    "Last", "Silent", "Broken", "Midnight", "Hidden", "Golden", "Lost", "Final", "Crimson",
+    "Distant",
+];
+
+const TITLE_NOUNS: &[&str] = &[
+    "Horizon", "Kingdom", "Signal", "Voyage", "Empire", "River", "Protocol", "Garden", "Storm",
+    "Frontier",
+];
+
+/// Franchises the media analytics queries search for.
+const FRANCHISES: &[&str] = &["Batman", "Star Wars", "James Bond", "Marvel", "Pixar"];
+
+/// The `objectID` of the `n`th record (0-based) of a preset.
+pub fn object_id(preset: Preset, n: usize) -> String {
+    let prefix = match preset {
+        Preset::Ecommerce => "prod",
+        Preset::Docs => "doc",
+        Preset::Media => "movie",
+    };
+    format!("{}-{:06}", prefix, n + 1)
+}
+
+/// `count` records of `preset`, generated lazily so large corpora can be
+/// streamed into an index in batches.
+pub fn records(preset: Preset, count: usize, seed: u32) -> impl Iterator<Item = Value> {
+    let mut rng = Rng::new(seed_for(seed, preset.as_str()));
+    (0..count).map(move |n| {
+        let mut record = match preset {
+            Preset::Ecommerce => product(&mut rng),
+            Preset::Docs => doc_page(&mut rng, n),
+            Preset::Media => movie(&mut rng),
+        };
+        record["objectID"] = json!(object_id(preset, n));
+        record
+    })
+}
+
+/// Searchable attributes, facets and custom ranking suited to `preset`.
+pub fn template(preset: Preset) -> IndexTemplate {
+    let settings = match preset {
+        Preset::Ecommerce => json!({
+            "searchableAttributes": ["name", "brand", "category"],
+            "attributesForFaceting": ["brand", "category", "price_range"],
+            "customRanking": ["desc(rating)", "desc(reviews)"],
+        }),
+        Preset::Docs => json!({
+            "searchableAttributes": ["title", "section", "content"],
+            "attributesForFaceting": ["section"],
+        }),
+        Preset::Media => json!({
+            "searchableAttributes": ["title", "cast", "director", "genres"],
+            "attributesForFaceting": ["genres", "year", "director"],
+            "customRanking": ["desc(rating)"],
+        }),
+    };
+    serde_json::from_value(json!({
+        "name": format!("fixtures-{}", preset),
+        "description": format!("Settings for the {} fixture corpus", preset),
+        "settings": settings,
+    }))
+    .expect("fixture template is valid")
+}
+
+fn product(rng: &mut Rng) -> Value {
+    let brand = rng.pick(BRANDS);
+    let (category, nouns, min_price, max_price) = rng.pick(PRODUCT_CATEGORIES);
+    let noun = rng.pick(nouns);
+    let modifier = rng.pick(PRODUCT_MODIFIERS);
+    let price = rng.range(*min_price, *max_price) as f64 + 0.99;
+    let price_range = match price as u32 {
+        0..=49 => "0-50",
+        50..=199 => "50-200",
+        200..=499 => "200-500",
+        _ => "500+",
+    };
+    json!({
+        "name": format!("{} {} {}", brand, modifier, noun),
+        "brand": brand,
+        "category": category,
+        "price": price,
+        "price_range": price_range,
+        "rating": rng.range(25, 50) as f64 / 10.0,
+        "reviews": rng.range(0, 5000),
+        "stock": rng.range(0, 250),
+    })
+}
+
+fn doc_page(rng: &mut Rng, n: usize) -> Value {
+    let section = rng.pick(DOC_SECTIONS);
+    let topic = rng.pick(DOC_TOPICS);
+    let title = rng.pick(DOC_TITLES).replace("{}", topic);
+    let related = rng.pick(DOC_TOPICS);
+    let slug = |s: &str| s.to_lowercase().replace([' ', '/'], "-");
+    json!({
+        "title": title,
+        "section": section,
+        "url": format!("/docs/{}/{}-{}", slug(section), slug(topic), n + 1),
+        "content": format!(
+            "This page covers {} in the {} section. It explains the defaults, the \
+             settings that change them and how {} interacts with {}.",
+            topic,
+            section.to_lowercase(),
+            topic,
+            related
+        ),
+    })
+}
+
+fn movie(rng: &mut Rng) -> Value {
+    let title = if rng.next_f64() < 0.15 {
+        format!(
+            "{}: The {} {}",
+            rng.pick(FRANCHISES),
+            rng.pick(TITLE_ADJECTIVES),
+            rng.pick(TITLE_NOUNS)
+        )
+    } else {
+        format!(
+            "The {} {}",
+            rng.pick(TITLE_ADJECTIVES),
+            rng.pick(TITLE_NOUNS)
+        )
+    };
+    let mut genres: Vec<&str> = Vec::new();
+    for _ in 0..rng.range(1, 3) {
+        let genre = *rng.pick(GENRES);
+        if !genres.contains(&genre) {
+            genres.push(genre);
+        }
+    }
+    let mut cast: Vec<&str> = Vec::new();
+    for _ in 0..rng.range(2, 4) {
+        let actor = *rng.pick(ACTORS);
+        if !cast.contains(&actor) {
+            cast.push(actor);
+        }
+    }
+    json!({
+        "title": title,
+        "genres": genres,
+        "year": rng.range(1960, 2024),
+        "director": rng.pick(DIRECTORS),
+        "cast": cast,
+        "rating": rng.range(30, 95) as f64 / 10.0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_corpus() {
+        for preset in Preset::ALL {
+            let a: Vec<Value> = records(preset, 50, 7).collect();
+            let b: Vec<Value> = records(preset, 50, 7).collect();
+            let c: Vec<Value> = records(preset, 50, 8).collect();
+            assert_eq!(a, b, "{} corpus differs between runs", preset);
+            assert_ne!(a, c, "{} corpus ignores the seed", preset);
+            assert_eq!(a[0]["objectID"], json!(object_id(preset, 0)));
+            assert_eq!(a[49]["objectID"], json!(object_id(preset, 49)));
+        }
+    }
+
+    #[test]
+    fn records_are_valid_documents() {
+        for preset in Preset::ALL {
+            for record in records(preset, 20, 42) {
+                let doc = crate::types::Document::from_json(&record).unwrap();
+                assert!(doc.id.contains('-'));
+            }
+            let template = template(preset);
+            assert!(template.settings.searchable_attributes.is_some());
+        }
+    }
+}
@@ -0,0 +1,236 @@
+//! Deterministic fixtures for demos, tests and load tests.
+//!
+//! Everything here is generated from a seed: the same seed and options give
+//! the same documents, analytics history and experiment exposures on every
+//! machine, so a demo or a benchmark can be rebuilt exactly.
+//!
+//! - [`corpus`] — document corpora for the [`Preset`]s, with index settings
+//!   to match
+//! - `analytics` — search, click and conversion history, optionally split
+//!   across the arms of an A/B test (requires the `analytics` feature)
+//!
+//! `flapjack seed` exposes both on the command line.
+
+#[cfg(feature = "analytics")]
+pub mod analytics;
+pub mod corpus;
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Seed used when none is given.
+pub const DEFAULT_SEED: u32 = 42;
+
+/// Kind of data to generate. Analytics queries are drawn from the preset's
+/// vocabulary, so they find the preset's documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// Products with brand, category, price and rating.
+    Ecommerce,
+    /// Documentation pages with section, URL and body text.
+    Docs,
+    /// Movies with genres, year, director and cast.
+    Media,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Ecommerce, Preset::Docs, Preset::Media];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Preset::Ecommerce => "ecommerce",
+            Preset::Docs => "docs",
+            Preset::Media => "media",
+        }
+    }
+
+    /// The preset an index name suggests (`movies` → media, `shop` →
+    /// e-commerce), if any.
+    pub fn for_index(index_name: &str) -> Option<Preset> {
+        let lower = index_name.to_lowercase();
+        if lower.contains("movie") || lower.contains("film") || lower.contains("tmdb") {
+            Some(Preset::Media)
+        } else if lower.contains("product")
+            || lower.contains("bestbuy")
+            || lower.contains("shop")
+            || lower.contains("ecommerce")
+            || lower.contains("commerce")
+        {
+            Some(Preset::Ecommerce)
+        } else if lower.contains("doc") || lower.contains("help") {
+            Some(Preset::Docs)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Preset::ALL
+            .into_iter()
+            .find(|p| p.as_str() == s)
+            .ok_or_else(|| format!("unknown preset '{}' (expected ecommerce, docs or media)", s))
+    }
+}
+
+/// Seed for the data of one index: `seed` mixed with the index name, so two
+/// indexes seeded alike still differ.
+pub(crate) fn seed_for(seed: u32, index_name: &str) -> u32 {
+    index_name
+        .bytes()
+        .fold(seed, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u32))
+}
+
+/// Simple deterministic pseudo-random number generator (xorshift32).
+/// Avoids pulling in the `rand` crate, whose sequences may change between
+/// versions.
+pub(crate) struct Rng {
+    state: u32,
+}
+
+impl Rng {
+    pub(crate) fn new(seed: u32) -> Self {
+        Self {
+            state: if seed == 0 { 1 } else { seed },
+        }
+    }
+
+    pub(crate) fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// Returns a value in [0.0, 1.0).
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u32() as f64) / (u32::MAX as f64)
+    }
+
+    /// Returns a value in [lo, hi].
+    pub(crate) fn range(&mut self, lo: u32, hi: u32) -> u32 {
+        if lo >= hi {
+            return lo;
+        }
+        lo + (self.next_u32() % (hi - lo + 1))
+    }
+
+    /// Pick an index based on weighted distribution.
+    pub(crate) fn weighted_pick(&mut self, weights: &[f64]) -> usize {
+        let r = self.next_f64();
+        let mut cumulative = 0.0;
+        for (i, &w) in weights.iter().enumerate() {
+            cumulative += w;
+            if r < cumulative {
+                return i;
+            }
+        }
+        weights.len() - 1
+    }
+
+    /// A uniformly chosen element of a non-empty slice.
+    pub(crate) fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.range(0, items.len() as u32 - 1) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rng_zero_seed_becomes_one() {
+        let rng = Rng::new(0);
+        assert_eq!(rng.state, 1);
+    }
+
+    #[test]
+    fn rng_nonzero_seed_kept() {
+        let rng = Rng::new(42);
+        assert_eq!(rng.state, 42);
+    }
+
+    #[test]
+    fn rng_deterministic() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u32(), b.next_u32());
+        }
+    }
+
+    #[test]
+    fn rng_next_f64_in_range() {
+        let mut rng = Rng::new(123);
+        for _ in 0..1000 {
+            let v = rng.next_f64();
+            assert!((0.0..1.0).contains(&v), "next_f64 out of range: {}", v);
+        }
+    }
+
+    #[test]
+    fn rng_range_within_bounds() {
+        let mut rng = Rng::new(99);
+        for _ in 0..500 {
+            let v = rng.range(5, 10);
+            assert!((5..=10).contains(&v), "range out of bounds: {}", v);
+        }
+    }
+
+    #[test]
+    fn rng_range_lo_equals_hi() {
+        let mut rng = Rng::new(1);
+        assert_eq!(rng.range(7, 7), 7);
+    }
+
+    #[test]
+    fn rng_range_lo_greater_than_hi() {
+        let mut rng = Rng::new(1);
+        assert_eq!(rng.range(10, 5), 10);
+    }
+
+    #[test]
+    fn rng_weighted_pick_single_weight() {
+        let mut rng = Rng::new(42);
+        // Only one weight — always picks index 0
+        for _ in 0..10 {
+            assert_eq!(rng.weighted_pick(&[1.0]), 0);
+        }
+    }
+
+    #[test]
+    fn rng_weighted_pick_extreme_weights() {
+        let mut rng = Rng::new(42);
+        // First weight is 0, second is 1 — index 0 can never be picked
+        // because r is in [0,1) and the condition `r < 0.0` is always false.
+        let mut counts = [0u32; 2];
+        for _ in 0..100 {
+            counts[rng.weighted_pick(&[0.0, 1.0])] += 1;
+        }
+        assert_eq!(counts[0], 0, "weight-0 index should never be picked");
+        assert_eq!(counts[1], 100, "weight-1 index should always be picked");
+    }
+
+    #[test]
+    fn presets_parse_and_follow_index_names() {
+        for preset in Preset::ALL {
+            assert_eq!(preset.as_str().parse::<Preset>(), Ok(preset));
+        }
+        assert!("books".parse::<Preset>().is_err());
+        assert_eq!(Preset::for_index("MyShop"), Some(Preset::Ecommerce));
+        assert_eq!(Preset::for_index("tmdb_movies"), Some(Preset::Media));
+        assert_eq!(Preset::for_index("help-center"), Some(Preset::Docs));
+        assert_eq!(Preset::for_index("random_index"), None);
+    }
+}
@@ -0,0 +1,539 @@
+//! Portable index archives (`.fjpack`), for support bundles and for moving
+//! an index between environments.
+//!
+//! A snapshot (see `snapshot.rs`) tars up the engine's own files and only
+//! restores into the same engine version. An archive holds the index's
+//! content as plain JSON instead, so any later version can import it:
+//!
+//! - `manifest.json` — format, format version, engine version, index name
+//!   and counts ([`ArchiveManifest`])
+//! - `documents.ndjson` — one record per line, with its `objectID`
+//! - `settings.json`, `synonyms.json`, `rules.json`
+//! - `vectors.ndjson` — optional, `{"objectID": …, "vector": […]}` per line
+//!
+//! The whole is a gzip-compressed tar. Importers ignore files and fields
+//! they don't know and skip synonyms and rules they cannot parse, so
+//! additions don't need a new format version; [`FORMAT_VERSION`] only
+//! changes when an older importer would misread an archive, and archives
+//! newer than it are refused.
+
+use crate::error::{FlapjackError, Result};
+use crate::index::manager::IndexManager;
+use crate::index::rules::Rule;
+use crate::index::settings::IndexSettings;
+use crate::index::synonyms::Synonym;
+use crate::index::templates::IndexTemplate;
+use crate::types::{field_value_to_json_value, Document};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+use tantivy::TantivyDocument;
+
+pub const ARCHIVE_FORMAT: &str = "fjpack";
+pub const FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const DOCUMENTS_FILE: &str = "documents.ndjson";
+const VECTORS_FILE: &str = "vectors.ndjson";
+const CONFIG_FILES: [&str; 3] = ["settings.json", "synonyms.json", "rules.json"];
+
+/// Documents added per write during an import.
+const IMPORT_BATCH: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveManifest {
+    pub format: String,
+    pub format_version: u32,
+    /// Version of the engine that wrote the archive, for support.
+    pub engine_version: String,
+    pub index_name: String,
+    pub created_at: String,
+    pub documents: usize,
+    #[serde(default)]
+    pub vectors: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub index_name: String,
+    pub documents: usize,
+    pub vectors: usize,
+    pub synonyms: usize,
+    pub rules: usize,
+    /// Synonyms and rules this version could not parse.
+    pub skipped: usize,
+}
+
+/// One JSON line per record, spooled to a temporary file because tar needs
+/// each entry's size before its contents.
+struct Spool {
+    file: BufWriter<File>,
+    count: usize,
+}
+
+impl Spool {
+    fn new() -> Result<Self> {
+        Ok(Spool {
+            file: BufWriter::new(tempfile::tempfile()?),
+            count: 0,
+        })
+    }
+
+    fn push(&mut self, record: &serde_json::Value) -> Result<()> {
+        serde_json::to_writer(&mut self.file, record)?;
+        self.file.write_all(b"\n")?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// The spooled file, rewound, and its length.
+    fn finish(self) -> Result<(File, u64)> {
+        let mut file = self
+            .file
+            .into_inner()
+            .map_err(|e| FlapjackError::Io(e.to_string()))?;
+        let len = file.stream_position()?;
+        file.rewind()?;
+        Ok((file, len))
+    }
+}
+
+/// A document as an archive record: `objectID` first, then its attributes
+/// in name order, so exports of the same data are identical.
+fn record_json(doc: &Document) -> serde_json::Value {
+    let mut names: Vec<&String> = doc.fields.keys().collect();
+    names.sort();
+    let mut record = serde_json::Map::new();
+    record.insert("objectID".to_string(), doc.id.clone().into());
+    for name in names {
+        record.insert(name.clone(), field_value_to_json_value(&doc.fields[name]));
+    }
+    serde_json::Value::Object(record)
+}
+
+fn append<W: Write, R: Read>(
+    builder: &mut tar::Builder<W>,
+    name: &str,
+    len: u64,
+    data: R,
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(len);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    builder.append_data(&mut header, name, data)?;
+    Ok(())
+}
+
+/// Write `index_name` to `out` as an archive. Vectors are included when
+/// `include_vectors` is set and the index has any.
+///
+/// Documents are read from the index's segments, so records hidden from
+/// search (scheduled, expired but not yet purged) are exported too.
+pub fn export_index<W: Write>(
+    manager: &IndexManager,
+    index_name: &str,
+    out: W,
+    include_vectors: bool,
+) -> Result<ArchiveManifest> {
+    let dir = manager.base_path.join(index_name);
+    if !dir.exists() {
+        return Err(FlapjackError::TenantNotFound(index_name.to_string()));
+    }
+    let index = manager.get_or_load(index_name)?;
+
+    #[cfg(feature = "vector-search")]
+    let vector_index = if include_vectors {
+        manager.get_vector_index(index_name)
+    } else {
+        None
+    };
+    #[cfg(not(feature = "vector-search"))]
+    let _ = include_vectors;
+
+    let mut documents = Spool::new()?;
+    #[allow(unused_mut)]
+    let mut vectors = Spool::new()?;
+    let searcher = index.reader().searcher();
+    let schema = index.inner().schema();
+    let converter = index.converter();
+    for reader in searcher.segment_readers() {
+        let store = reader.get_store_reader(1)?;
+        for doc in store.iter::<TantivyDocument>(reader.alive_bitset()) {
+            let doc = converter.from_tantivy(doc?, &schema, String::new())?;
+            #[cfg(feature = "vector-search")]
+            if let Some(vector) = vector_index
+                .as_ref()
+                .and_then(|vi| vi.read().ok()?.get(&doc.id))
+            {
+                vectors.push(&serde_json::json!({"objectID": doc.id, "vector": vector}))?;
+            }
+            documents.push(&record_json(&doc))?;
+        }
+    }
+
+    let manifest = ArchiveManifest {
+        format: ARCHIVE_FORMAT.to_string(),
+        format_version: FORMAT_VERSION,
+        engine_version: env!("CARGO_PKG_VERSION").to_string(),
+        index_name: index_name.to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        documents: documents.count,
+        vectors: vectors.count,
+    };
+
+    let mut builder = tar::Builder::new(GzEncoder::new(out, Compression::default()));
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+    append(
+        &mut builder,
+        MANIFEST_FILE,
+        manifest_json.len() as u64,
+        &manifest_json[..],
+    )?;
+    for name in CONFIG_FILES {
+        let path = dir.join(name);
+        if path.exists() {
+            let file = File::open(&path)?;
+            append(&mut builder, name, file.metadata()?.len(), file)?;
+        }
+    }
+    let (file, len) = documents.finish()?;
+    append(&mut builder, DOCUMENTS_FILE, len, file)?;
+    if vectors.count > 0 {
+        let (file, len) = vectors.finish()?;
+        append(&mut builder, VECTORS_FILE, len, file)?;
+    }
+    builder.into_inner()?.finish()?;
+
+    Ok(manifest)
+}
+
+/// An archive unpacked into a temporary directory, ready to import.
+pub struct ArchiveReader {
+    dir: tempfile::TempDir,
+    pub manifest: ArchiveManifest,
+}
+
+impl ArchiveReader {
+    /// Unpack and check an archive. Blocking.
+    pub fn open<R: Read>(archive: R) -> Result<Self> {
+        let dir = tempfile::tempdir()?;
+        tar::Archive::new(GzDecoder::new(archive))
+            .unpack(dir.path())
+            .map_err(|e| FlapjackError::InvalidDocument(format!("unreadable archive: {}", e)))?;
+
+        let not_an_archive =
+            || FlapjackError::InvalidDocument(format!("not a .{} archive", ARCHIVE_FORMAT));
+        let manifest_path = dir.path().join(MANIFEST_FILE);
+        if !manifest_path.exists() {
+            return Err(not_an_archive());
+        }
+        let manifest: ArchiveManifest = serde_json::from_slice(&std::fs::read(&manifest_path)?)
+            .map_err(|_| not_an_archive())?;
+        if manifest.format != ARCHIVE_FORMAT {
+            return Err(not_an_archive());
+        }
+        if manifest.format_version > FORMAT_VERSION {
+            return Err(FlapjackError::InvalidDocument(format!(
+                "archive format version {} is newer than this engine reads ({})",
+                manifest.format_version, FORMAT_VERSION
+            )));
+        }
+        Ok(ArchiveReader { dir, manifest })
+    }
+
+    fn path(&self, name: &str) -> Option<std::path::PathBuf> {
+        let path = self.dir.path().join(name);
+        path.exists().then_some(path)
+    }
+
+    /// Replace `index_name` (or create it) with the archive's contents.
+    pub async fn import_into(
+        &self,
+        manager: &IndexManager,
+        index_name: &str,
+    ) -> Result<ImportSummary> {
+        if index_name.is_empty() || index_name.starts_with('.') || index_name.contains(['/', '\\'])
+        {
+            return Err(FlapjackError::InvalidQuery(format!(
+                "invalid index name '{}'",
+                index_name
+            )));
+        }
+        let settings: IndexSettings = match self.path("settings.json") {
+            Some(path) => serde_json::from_slice(&std::fs::read(path)?).map_err(|e| {
+                FlapjackError::InvalidDocument(format!("archive settings.json: {}", e))
+            })?,
+            None => IndexSettings::default(),
+        };
+        let (synonyms, skipped_synonyms) = read_entries::<Synonym>(self.path("synonyms.json"))?;
+        let (rules, skipped_rules) = read_entries::<Rule>(self.path("rules.json"))?;
+        let mut summary = ImportSummary {
+            index_name: index_name.to_string(),
+            documents: 0,
+            vectors: 0,
+            synonyms: synonyms.len(),
+            rules: rules.len(),
+            skipped: skipped_synonyms + skipped_rules,
+        };
+
+        #[cfg(feature = "vector-search")]
+        let embedders: Vec<String> = settings
+            .embedders
+            .as_ref()
+            .map(|e| e.keys().cloned().collect())
+            .unwrap_or_default();
+        #[cfg(feature = "vector-search")]
+        let vectors = match self.path(VECTORS_FILE) {
+            Some(_) if embedders.is_empty() => {
+                tracing::warn!(
+                    "[ARCHIVE {}] no embedders configured, skipping the archive's vectors",
+                    index_name
+                );
+                std::collections::HashMap::new()
+            }
+            Some(path) => read_vectors(&path)?,
+            None => std::collections::HashMap::new(),
+        };
+
+        if manager.base_path.join(index_name).exists() {
+            manager.delete_tenant(&index_name.to_string()).await?;
+        }
+        manager.create_tenant(index_name)?;
+        IndexTemplate {
+            name: self.manifest.index_name.clone(),
+            description: None,
+            settings,
+            synonyms,
+            rules,
+        }
+        .apply(manager, index_name)?;
+
+        let Some(path) = self.path(DOCUMENTS_FILE) else {
+            return Ok(summary);
+        };
+        let mut batch = Vec::with_capacity(IMPORT_BATCH);
+        for (n, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let bad_record = |e: String| {
+                FlapjackError::InvalidDocument(format!("{} line {}: {}", DOCUMENTS_FILE, n + 1, e))
+            };
+            #[allow(unused_mut)]
+            let mut record: serde_json::Value =
+                serde_json::from_str(&line).map_err(|e| bad_record(e.to_string()))?;
+            #[cfg(feature = "vector-search")]
+            if let Some(vector) = record
+                .get("objectID")
+                .and_then(|id| id.as_str())
+                .and_then(|id| vectors.get(id))
+            {
+                let by_embedder: serde_json::Map<String, serde_json::Value> = embedders
+                    .iter()
+                    .map(|name| (name.clone(), serde_json::json!(vector)))
+                    .collect();
+                record["_vectors"] = serde_json::Value::Object(by_embedder);
+                summary.vectors += 1;
+            }
+            batch.push(Document::from_json(&record).map_err(|e| bad_record(e.to_string()))?);
+            if batch.len() == IMPORT_BATCH {
+                summary.documents += batch.len();
+                manager
+                    .add_documents_sync(index_name, std::mem::take(&mut batch))
+                    .await?;
+            }
+        }
+        if !batch.is_empty() {
+            summary.documents += batch.len();
+            manager.add_documents_sync(index_name, batch).await?;
+        }
+        Ok(summary)
+    }
+}
+
+/// The entries of a JSON array file that parse as `T`, and how many didn't.
+fn read_entries<T: serde::de::DeserializeOwned>(
+    path: Option<impl AsRef<Path>>,
+) -> Result<(Vec<T>, usize)> {
+    let Some(path) = path else {
+        return Ok((Vec::new(), 0));
+    };
+    let raw: Vec<serde_json::Value> = serde_json::from_slice(&std::fs::read(path.as_ref())?)?;
+    let total = raw.len();
+    let parsed: Vec<T> = raw
+        .into_iter()
+        .filter_map(|value| serde_json::from_value(value).ok())
+        .collect();
+    let skipped = total - parsed.len();
+    Ok((parsed, skipped))
+}
+
+#[cfg(feature = "vector-search")]
+fn read_vectors(path: &Path) -> Result<std::collections::HashMap<String, Vec<f32>>> {
+    #[derive(Deserialize)]
+    struct VectorRecord {
+        #[serde(rename = "objectID")]
+        object_id: String,
+        vector: Vec<f32>,
+    }
+    let mut vectors = std::collections::HashMap::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: VectorRecord = serde_json::from_str(&line)?;
+        vectors.insert(record.object_id, record.vector);
+    }
+    Ok(vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::FieldValue;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn doc(id: &str, title: &str, price: i64) -> Document {
+        let mut fields = HashMap::new();
+        fields.insert("title".to_string(), FieldValue::Text(title.to_string()));
+        fields.insert("price".to_string(), FieldValue::Integer(price));
+        Document {
+            id: id.to_string(),
+            fields,
+        }
+    }
+
+    fn archive_of(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        {
+            let mut builder = tar::Builder::new(GzEncoder::new(&mut out, Compression::fast()));
+            for (name, contents) in files {
+                append(
+                    &mut builder,
+                    name,
+                    contents.len() as u64,
+                    contents.as_bytes(),
+                )
+                .unwrap();
+            }
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+        out
+    }
+
+    #[tokio::test]
+    async fn export_then_import_round_trips() {
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        manager.create_tenant("products").unwrap();
+        manager
+            .add_documents_sync(
+                "products",
+                vec![doc("1", "Red shoe", 50), doc("2", "Blue hat", 20)],
+            )
+            .await
+            .unwrap();
+        let template: IndexTemplate = serde_json::from_value(serde_json::json!({
+            "name": "t",
+            "settings": {"searchableAttributes": ["title"]},
+            "synonyms": [{"objectID": "hat", "type": "synonym", "synonyms": ["hat", "cap"]}],
+            "rules": [{
+                "objectID": "promo",
+                "conditions": [{"pattern": "sale", "anchoring": "contains"}],
+                "consequence": {"params": {"query": "discount"}}
+            }]
+        }))
+        .unwrap();
+        template.apply(&manager, "products").unwrap();
+
+        let mut bytes = Vec::new();
+        let manifest = export_index(&manager, "products", &mut bytes, true).unwrap();
+        assert_eq!(manifest.documents, 2);
+
+        let reader = ArchiveReader::open(&bytes[..]).unwrap();
+        assert_eq!(reader.manifest.index_name, "products");
+        let summary = reader.import_into(&manager, "staging").await.unwrap();
+        assert_eq!(
+            (
+                summary.documents,
+                summary.synonyms,
+                summary.rules,
+                summary.skipped
+            ),
+            (2, 1, 1, 0)
+        );
+
+        let imported = manager.get_document("staging", "1").unwrap().unwrap();
+        assert_eq!(imported.fields["price"], FieldValue::Integer(50));
+        let settings = manager.get_settings("staging").unwrap();
+        assert_eq!(
+            settings.searchable_attributes,
+            Some(vec!["title".to_string()])
+        );
+        assert!(manager
+            .get_synonyms("staging")
+            .unwrap()
+            .get("hat")
+            .is_some());
+        assert!(manager.get_rules("staging").unwrap().get("promo").is_some());
+    }
+
+    #[tokio::test]
+    async fn import_ignores_what_it_does_not_know() {
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        let bytes = archive_of(&[
+            (
+                "manifest.json",
+                r#"{"format":"fjpack","formatVersion":1,"engineVersion":"9.0.0","indexName":"x","createdAt":"","documents":1,"checksum":"abc"}"#,
+            ),
+            (
+                "documents.ndjson",
+                "{\"objectID\":\"a\",\"title\":\"Hi\"}\n\n",
+            ),
+            (
+                "synonyms.json",
+                r#"[{"objectID":"s","type":"synonym","synonyms":["a","b"]},{"objectID":"n","type":"fancy"}]"#,
+            ),
+            ("settings.json", r#"{"hitsPerPage":5,"futureSetting":true}"#),
+            ("analytics.json", "{}"),
+        ]);
+
+        let summary = ArchiveReader::open(&bytes[..])
+            .unwrap()
+            .import_into(&manager, "x")
+            .await
+            .unwrap();
+        assert_eq!(
+            (summary.documents, summary.synonyms, summary.skipped),
+            (1, 1, 1)
+        );
+        assert!(manager.get_document("x", "a").unwrap().is_some());
+    }
+
+    #[test]
+    fn newer_or_foreign_archives_are_refused() {
+        let newer = archive_of(&[(
+            "manifest.json",
+            r#"{"format":"fjpack","formatVersion":2,"engineVersion":"","indexName":"x","createdAt":"","documents":0}"#,
+        )]);
+        let err = ArchiveReader::open(&newer[..]).err().unwrap();
+        assert!(err.to_string().contains("newer"), "{}", err);
+
+        let foreign = archive_of(&[("data.json", "{}")]);
+        assert!(matches!(
+            ArchiveReader::open(&foreign[..]),
+            Err(FlapjackError::InvalidDocument(_))
+        ));
+    }
+}
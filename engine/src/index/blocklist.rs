@@ -0,0 +1,213 @@
+//! Blocked results: an index-level "never return for this query" list.
+//!
+//! Each entry maps a query pattern to objectIDs and/or a filter that must
+//! not come back for matching queries. Entries live in their own file and
+//! are managed through a dedicated endpoint rather than as rules, so keys
+//! that may edit rules cannot lift them. They are matched against the query
+//! as typed, before rules run, and rules cannot pin a blocked object back.
+
+use crate::error::Result;
+use crate::index::rules::{Anchoring, RuleEffects};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+pub const BLOCKLIST_FILE: &str = "blocklist.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockedResults {
+    #[serde(rename = "objectID")]
+    pub object_id: String,
+
+    /// Matched case-insensitively; an empty `contains` pattern blocks for
+    /// every query.
+    pub pattern: String,
+
+    pub anchoring: Anchoring,
+
+    #[serde(rename = "objectIDs", default, skip_serializing_if = "Vec::is_empty")]
+    pub object_ids: Vec<String>,
+
+    /// Filter expression; records matching it are blocked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filters: Option<String>,
+
+    /// Why the results are blocked, e.g. a takedown reference.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl BlockedResults {
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.object_id.is_empty() {
+            return Err("objectID must not be empty".to_string());
+        }
+        if self.object_ids.iter().any(|id| id.is_empty()) {
+            return Err("objectIDs must not contain empty IDs".to_string());
+        }
+        let has_filters = self
+            .filters
+            .as_deref()
+            .is_some_and(|f| !f.trim().is_empty());
+        if self.object_ids.is_empty() && !has_filters {
+            return Err("objectIDs or filters is required".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn matches(&self, query_text: &str) -> bool {
+        self.anchoring.matches(query_text, &self.pattern)
+    }
+}
+
+/// What the entries matching one query block.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Blocked {
+    pub object_ids: HashSet<String>,
+    pub filters: Vec<String>,
+}
+
+impl Blocked {
+    pub fn is_empty(&self) -> bool {
+        self.object_ids.is_empty() && self.filters.is_empty()
+    }
+
+    /// Hide the blocked objectIDs and drop them from the pins. Filtered
+    /// entries are applied to the search filter by the caller, so pins may
+    /// then only come from the results that passed it.
+    pub fn restrict(&self, effects: &mut RuleEffects) {
+        effects.pins.retain(|(id, _)| !self.object_ids.contains(id));
+        effects.hidden.extend(self.object_ids.iter().cloned());
+        if !self.filters.is_empty() {
+            effects.pins_from_results_only = true;
+        }
+    }
+}
+
+pub struct Blocklist {
+    entries: IndexMap<String, BlockedResults>,
+}
+
+impl Default for Blocklist {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Blocklist {
+    pub fn new() -> Self {
+        Blocklist {
+            entries: IndexMap::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let entries: Vec<BlockedResults> = serde_json::from_str(&content)?;
+
+        let mut blocklist = Blocklist::new();
+        for entry in entries {
+            blocklist.insert(entry);
+        }
+        Ok(blocklist)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let entries: Vec<&BlockedResults> = self.entries.values().collect();
+        let content = serde_json::to_string_pretty(&entries)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn get(&self, object_id: &str) -> Option<&BlockedResults> {
+        self.entries.get(object_id)
+    }
+
+    pub fn insert(&mut self, entry: BlockedResults) {
+        self.entries.insert(entry.object_id.clone(), entry);
+    }
+
+    pub fn remove(&mut self, object_id: &str) -> Option<BlockedResults> {
+        self.entries.shift_remove(object_id)
+    }
+
+    pub fn all(&self) -> Vec<BlockedResults> {
+        self.entries.values().cloned().collect()
+    }
+
+    /// Everything the entries matching `query_text` block.
+    pub fn blocked(&self, query_text: &str) -> Blocked {
+        let mut blocked = Blocked::default();
+        for entry in self.entries.values().filter(|e| e.matches(query_text)) {
+            blocked.object_ids.extend(entry.object_ids.iter().cloned());
+            if let Some(filters) = entry.filters.as_ref().filter(|f| !f.trim().is_empty()) {
+                blocked.filters.push(filters.clone());
+            }
+        }
+        blocked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(value: serde_json::Value) -> BlockedResults {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn validate_requires_something_to_block() {
+        let e = entry(json!({"objectID": "b1", "pattern": "acme", "anchoring": "is"}));
+        assert!(e.validate().is_err());
+        let e = entry(json!({
+            "objectID": "b1", "pattern": "acme", "anchoring": "is", "filters": " "
+        }));
+        assert!(e.validate().is_err());
+        let e = entry(json!({
+            "objectID": "b1", "pattern": "acme", "anchoring": "is", "objectIDs": ["p1"]
+        }));
+        assert!(e.validate().is_ok());
+    }
+
+    #[test]
+    fn blocked_collects_matching_entries() {
+        let mut blocklist = Blocklist::new();
+        blocklist.insert(entry(json!({
+            "objectID": "b1", "pattern": "Acme", "anchoring": "contains",
+            "objectIDs": ["p1", "p2"]
+        })));
+        blocklist.insert(entry(json!({
+            "objectID": "b2", "pattern": "acme anvil", "anchoring": "is",
+            "filters": "brand:Acme"
+        })));
+
+        let blocked = blocklist.blocked("acme anvil");
+        assert_eq!(blocked.object_ids.len(), 2);
+        assert_eq!(blocked.filters, vec!["brand:Acme".to_string()]);
+
+        let blocked = blocklist.blocked("ACME rockets");
+        assert!(blocked.object_ids.contains("p1"));
+        assert!(blocked.filters.is_empty());
+
+        assert!(blocklist.blocked("anvil").is_empty());
+    }
+
+    #[test]
+    fn restrict_hides_and_unpins_blocked_ids() {
+        let blocked = Blocked {
+            object_ids: ["p1".to_string()].into_iter().collect(),
+            filters: vec!["brand:Acme".to_string()],
+        };
+        let mut effects = RuleEffects {
+            pins: vec![("p1".to_string(), 0), ("p2".to_string(), 1)],
+            ..Default::default()
+        };
+        blocked.restrict(&mut effects);
+        assert_eq!(effects.pins, vec![("p2".to_string(), 1)]);
+        assert_eq!(effects.hidden, vec!["p1".to_string()]);
+        assert!(effects.pins_from_results_only);
+    }
+}
@@ -0,0 +1,295 @@
+//! Merchandising campaigns: rules grouped under one owner and schedule.
+//!
+//! A seasonal promotion is usually many pins across many queries. A campaign
+//! holds them as rules with one shared validity, so the promotion is
+//! scheduled, switched off and reported on as a single object. Its rules are
+//! searched after the index's own rules, as `<campaignID>/<ruleID>`, which is
+//! also how analytics attributes applied rules back to the campaign.
+
+use crate::error::Result;
+use crate::index::rules::{Promote, Rule, TimeRange};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+pub const CAMPAIGNS_FILE: &str = "campaigns.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Campaign {
+    #[serde(rename = "objectID")]
+    pub object_id: String,
+
+    pub name: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    /// When the campaign runs, in Unix seconds; always when empty. A rule
+    /// with its own validity only fires where both overlap.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub validity: Vec<TimeRange>,
+
+    /// Searches the campaign is booked to be shown in, reported against in
+    /// its stats. Nothing stops the campaign once the quota is reached.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impression_quota: Option<u64>,
+
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CampaignStatus {
+    Disabled,
+    Scheduled,
+    Active,
+    Ended,
+}
+
+impl Campaign {
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.object_id.is_empty() || self.object_id.contains('/') {
+            return Err("objectID must be non-empty and must not contain '/'".to_string());
+        }
+        if self.name.trim().is_empty() {
+            return Err("name must not be empty".to_string());
+        }
+        if self.validity.iter().any(|r| r.from > r.until) {
+            return Err("validity ranges must not end before they start".to_string());
+        }
+        if self.impression_quota == Some(0) {
+            return Err("impressionQuota must be positive".to_string());
+        }
+        let mut seen = HashSet::new();
+        for rule in &self.rules {
+            if rule.object_id.is_empty() {
+                return Err("every rule needs an objectID".to_string());
+            }
+            if !seen.insert(rule.object_id.as_str()) {
+                return Err(format!("duplicate rule objectID {}", rule.object_id));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    pub fn status(&self, now: i64) -> CampaignStatus {
+        if !self.is_enabled() {
+            CampaignStatus::Disabled
+        } else if self.validity.is_empty()
+            || self
+                .validity
+                .iter()
+                .any(|r| now >= r.from && now <= r.until)
+        {
+            CampaignStatus::Active
+        } else if self.validity.iter().any(|r| r.from > now) {
+            CampaignStatus::Scheduled
+        } else {
+            CampaignStatus::Ended
+        }
+    }
+
+    /// The ID a campaign rule is searched and reported under.
+    pub fn rule_id(&self, rule_id: &str) -> String {
+        format!("{}/{}", self.object_id, rule_id)
+    }
+
+    /// The campaign's rules as the index searches them: renamed, and valid
+    /// only within the campaign's dates. None while the campaign is disabled.
+    pub fn effective_rules(&self) -> Vec<Rule> {
+        if !self.is_enabled() {
+            return Vec::new();
+        }
+        self.rules
+            .iter()
+            .map(|rule| {
+                let mut rule = rule.clone();
+                rule.object_id = self.rule_id(&rule.object_id);
+                if !self.validity.is_empty() {
+                    rule.validity = Some(match &rule.validity {
+                        Some(own) => overlap(&self.validity, own),
+                        None => self.validity.clone(),
+                    });
+                }
+                rule
+            })
+            .collect()
+    }
+
+    /// Every objectID the campaign's rules pin.
+    pub fn pinned_object_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = Vec::new();
+        for promote in self
+            .rules
+            .iter()
+            .filter_map(|r| r.consequence.promote.as_ref())
+            .flatten()
+        {
+            let pinned = match promote {
+                Promote::Single { object_id, .. } => std::slice::from_ref(object_id),
+                Promote::Multiple { object_ids, .. } => object_ids.as_slice(),
+            };
+            for id in pinned {
+                if !ids.contains(id) {
+                    ids.push(id.clone());
+                }
+            }
+        }
+        ids
+    }
+}
+
+fn overlap(a: &[TimeRange], b: &[TimeRange]) -> Vec<TimeRange> {
+    let mut ranges = Vec::new();
+    for x in a {
+        for y in b {
+            let (from, until) = (x.from.max(y.from), x.until.min(y.until));
+            if from <= until {
+                ranges.push(TimeRange { from, until });
+            }
+        }
+    }
+    ranges
+}
+
+pub struct CampaignStore {
+    campaigns: IndexMap<String, Campaign>,
+}
+
+impl Default for CampaignStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CampaignStore {
+    pub fn new() -> Self {
+        CampaignStore {
+            campaigns: IndexMap::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let campaigns: Vec<Campaign> = serde_json::from_str(&content)?;
+
+        let mut store = CampaignStore::new();
+        for campaign in campaigns {
+            store.insert(campaign);
+        }
+        Ok(store)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let campaigns: Vec<&Campaign> = self.campaigns.values().collect();
+        let content = serde_json::to_string_pretty(&campaigns)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn get(&self, object_id: &str) -> Option<&Campaign> {
+        self.campaigns.get(object_id)
+    }
+
+    pub fn insert(&mut self, campaign: Campaign) {
+        self.campaigns.insert(campaign.object_id.clone(), campaign);
+    }
+
+    pub fn remove(&mut self, object_id: &str) -> Option<Campaign> {
+        self.campaigns.shift_remove(object_id)
+    }
+
+    pub fn all(&self) -> Vec<Campaign> {
+        self.campaigns.values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn campaign(value: serde_json::Value) -> Campaign {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn summer_sale() -> Campaign {
+        campaign(json!({
+            "objectID": "summer",
+            "name": "Summer sale",
+            "owner": "merch@example.com",
+            "validity": [{"from": 1000, "until": 2000}],
+            "rules": [
+                {
+                    "objectID": "sandals",
+                    "conditions": [{"pattern": "sandals", "anchoring": "contains"}],
+                    "consequence": {"promote": [{"objectID": "s1", "position": 0}]}
+                },
+                {
+                    "objectID": "hats",
+                    "conditions": [{"pattern": "hat", "anchoring": "contains"}],
+                    "consequence": {"promote": [{"objectIDs": ["h1", "s1"], "position": 1}]},
+                    "validity": [{"from": 1500, "until": 3000}]
+                }
+            ]
+        }))
+    }
+
+    #[test]
+    fn effective_rules_share_the_campaign_dates() {
+        let rules = summer_sale().effective_rules();
+        assert_eq!(rules[0].object_id, "summer/sandals");
+        assert!(rules[0].is_valid_at(1000) && !rules[0].is_valid_at(2001));
+        assert_eq!(rules[1].object_id, "summer/hats");
+        assert!(!rules[1].is_valid_at(1400));
+        assert!(rules[1].is_valid_at(1800));
+        assert!(!rules[1].is_valid_at(2500));
+
+        let mut disabled = summer_sale();
+        disabled.enabled = Some(false);
+        assert!(disabled.effective_rules().is_empty());
+    }
+
+    #[test]
+    fn status_follows_validity() {
+        let c = summer_sale();
+        assert_eq!(c.status(500), CampaignStatus::Scheduled);
+        assert_eq!(c.status(1500), CampaignStatus::Active);
+        assert_eq!(c.status(2500), CampaignStatus::Ended);
+        let mut c = c;
+        c.enabled = Some(false);
+        assert_eq!(c.status(1500), CampaignStatus::Disabled);
+    }
+
+    #[test]
+    fn pinned_object_ids_are_deduplicated() {
+        assert_eq!(summer_sale().pinned_object_ids(), vec!["s1", "h1"]);
+    }
+
+    #[test]
+    fn validate_rejects_bad_campaigns() {
+        assert!(summer_sale().validate().is_ok());
+        let mut c = summer_sale();
+        c.object_id = "a/b".to_string();
+        assert!(c.validate().is_err());
+        let mut c = summer_sale();
+        c.validity = vec![TimeRange { from: 10, until: 5 }];
+        assert!(c.validate().is_err());
+        let mut c = summer_sale();
+        c.rules[1].object_id = "sandals".to_string();
+        assert!(c.validate().is_err());
+    }
+}
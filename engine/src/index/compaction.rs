@@ -0,0 +1,269 @@
+//! Per-index policy for background compaction (the `compaction` setting).
+//!
+//! The scheduler merges an index's segments once it is fragmented enough —
+//! too many deleted documents or too many segments — and only inside an
+//! optional off-peak window. Throttled compactions merge a few segments at a
+//! time with a pause in between, so searches keep their disk bandwidth.
+
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_min_deleted_ratio() -> f64 {
+    0.2
+}
+
+fn default_max_segments() -> usize {
+    20
+}
+
+fn default_min_interval_secs() -> u64 {
+    3600
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionPolicy {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Off-peak window, `HH:MM` in UTC. `end` before `start` spans midnight.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<CompactionWindow>,
+
+    /// Compact once this share of documents is deleted.
+    #[serde(default = "default_min_deleted_ratio")]
+    pub min_deleted_ratio: f64,
+
+    /// Compact once the index has more segments than this (`0` ignores the
+    /// segment count).
+    #[serde(default = "default_max_segments")]
+    pub max_segments: usize,
+
+    /// Leave indexes smaller than this on disk alone.
+    #[serde(default)]
+    pub min_size_bytes: u64,
+
+    /// Minimum time between two compactions of the index.
+    #[serde(default = "default_min_interval_secs")]
+    pub min_interval_secs: u64,
+
+    /// Segments merged per step; `0` merges everything at once.
+    #[serde(default)]
+    pub merge_batch_segments: usize,
+
+    /// Pause between merge steps.
+    #[serde(default)]
+    pub throttle_ms: u64,
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> Self {
+        CompactionPolicy {
+            enabled: true,
+            window: None,
+            min_deleted_ratio: default_min_deleted_ratio(),
+            max_segments: default_max_segments(),
+            min_size_bytes: 0,
+            min_interval_secs: default_min_interval_secs(),
+            merge_batch_segments: 0,
+            throttle_ms: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompactionWindow {
+    pub start: String,
+    pub end: String,
+}
+
+/// How a compaction merges segments.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactOptions {
+    /// Segments merged per step; `0` merges everything at once.
+    pub merge_batch: usize,
+    pub throttle: Duration,
+}
+
+/// Fragmentation of an index, as the scheduler sees it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Fragmentation {
+    pub documents: u64,
+    pub deleted_documents: u64,
+    pub segments: usize,
+    pub size_bytes: u64,
+}
+
+impl Fragmentation {
+    pub fn deleted_ratio(&self) -> f64 {
+        let total = self.documents + self.deleted_documents;
+        if total == 0 {
+            0.0
+        } else {
+            self.deleted_documents as f64 / total as f64
+        }
+    }
+}
+
+impl CompactOptions {
+    /// Segments per merge for an index with `segments` segments.
+    pub fn batch_size(&self, segments: usize) -> usize {
+        if self.merge_batch == 0 {
+            segments.max(2)
+        } else {
+            self.merge_batch.max(2)
+        }
+    }
+}
+
+/// Merges needed to bring `segments` down to one, `batch` at a time
+/// (single leftover segments count as a step).
+pub fn merge_steps(mut segments: usize, batch: usize) -> usize {
+    let mut steps = 0;
+    while segments > 1 {
+        segments = segments.div_ceil(batch);
+        steps += segments;
+    }
+    steps
+}
+
+fn parse_time(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s, "%H:%M").ok()
+}
+
+impl CompactionPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(window) = &self.window {
+            for t in [&window.start, &window.end] {
+                if parse_time(t).is_none() {
+                    return Err(format!(
+                        "compaction.window: invalid time '{}' (expected HH:MM)",
+                        t
+                    ));
+                }
+            }
+        }
+        if !(0.0..=1.0).contains(&self.min_deleted_ratio) {
+            return Err("compaction.minDeletedRatio must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn in_window(&self, now: NaiveTime) -> bool {
+        let Some(window) = &self.window else {
+            return true;
+        };
+        let (Some(start), Some(end)) = (parse_time(&window.start), parse_time(&window.end)) else {
+            return false;
+        };
+        if start <= end {
+            start <= now && now < end
+        } else {
+            now >= start || now < end
+        }
+    }
+
+    /// Why the index should be compacted now, or `None` to leave it.
+    /// `since_last` is the time since its last compaction, if any.
+    pub fn compaction_reason(
+        &self,
+        f: &Fragmentation,
+        since_last: Option<Duration>,
+    ) -> Option<String> {
+        if !self.enabled || f.segments == 0 || f.size_bytes < self.min_size_bytes {
+            return None;
+        }
+        if since_last.is_some_and(|d| d < Duration::from_secs(self.min_interval_secs)) {
+            return None;
+        }
+        if f.deleted_documents > 0 && f.deleted_ratio() >= self.min_deleted_ratio {
+            return Some(format!(
+                "{:.0}% of documents deleted",
+                f.deleted_ratio() * 100.0
+            ));
+        }
+        if self.max_segments > 0 && f.segments > self.max_segments {
+            return Some(format!("{} segments", f.segments));
+        }
+        None
+    }
+
+    pub fn compact_options(&self) -> CompactOptions {
+        CompactOptions {
+            merge_batch: self.merge_batch_segments,
+            throttle: Duration::from_millis(self.throttle_ms),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveTime {
+        parse_time(s).unwrap()
+    }
+
+    #[test]
+    fn window_may_span_midnight() {
+        let policy = CompactionPolicy {
+            window: Some(CompactionWindow {
+                start: "22:00".into(),
+                end: "04:00".into(),
+            }),
+            ..Default::default()
+        };
+        assert!(policy.in_window(at("23:30")));
+        assert!(policy.in_window(at("01:00")));
+        assert!(!policy.in_window(at("04:00")));
+        assert!(!policy.in_window(at("12:00")));
+        assert!(CompactionPolicy::default().in_window(at("12:00")));
+    }
+
+    #[test]
+    fn compacts_on_deletes_or_segment_count() {
+        let policy = CompactionPolicy::default();
+        let mut f = Fragmentation {
+            documents: 90,
+            deleted_documents: 10,
+            segments: 5,
+            size_bytes: 1000,
+        };
+        assert_eq!(policy.compaction_reason(&f, None), None);
+        f.deleted_documents = 30;
+        assert!(policy.compaction_reason(&f, None).is_some());
+        assert_eq!(
+            policy.compaction_reason(&f, Some(Duration::from_secs(60))),
+            None,
+            "compacted a minute ago"
+        );
+        f.deleted_documents = 0;
+        f.segments = 21;
+        assert_eq!(
+            policy.compaction_reason(&f, None).as_deref(),
+            Some("21 segments")
+        );
+    }
+
+    #[test]
+    fn merge_steps_counts_every_pass() {
+        assert_eq!(merge_steps(1, 4), 0);
+        assert_eq!(merge_steps(10, 10), 1);
+        // 3 -> [2, 1] -> [2] -> 1
+        assert_eq!(merge_steps(3, 2), 3);
+        assert_eq!(merge_steps(16, 4), 5);
+    }
+
+    #[test]
+    fn rejects_bad_window() {
+        let policy: CompactionPolicy =
+            serde_json::from_value(serde_json::json!({"window": {"start": "2am", "end": "05:00"}}))
+                .unwrap();
+        assert!(policy.validate().is_err());
+    }
+}
@@ -1,10 +1,12 @@
 use crate::error::{FlapjackError, Result};
 use crate::index::facet_translation::{extract_facet_paths, is_hierarchical_facet};
+use crate::index::language;
 use crate::index::schema::Schema;
 use crate::index::settings::IndexSettings;
 use crate::types::{Document, DocumentId, FieldValue};
 use serde_json::{Map, Value};
 use std::collections::BTreeMap;
+use std::sync::Arc;
 use tantivy::schema::{Field, OwnedValue};
 use tantivy::TantivyDocument;
 
@@ -118,6 +120,17 @@ impl DocumentConverter {
 
         let mut json_fields = fields_to_json(&doc.fields);
 
+        if let Some(s) = settings.filter(|s| s.auto_detect_language) {
+            let searchable = s.searchable_paths();
+            let detected = language::detect_language(&doc.fields, searchable.as_deref());
+            if let (Value::Object(map), Some(lang)) = (&mut json_fields, detected) {
+                map.insert(
+                    language::DETECTED_LANGUAGE_ATTR.to_string(),
+                    Value::String(lang),
+                );
+            }
+        }
+
         if let Value::Object(ref mut map) = json_fields {
             if let Some(geoloc) = map.remove("_geoloc") {
                 if let Some((lat, lng)) = extract_geoloc(&geoloc) {
@@ -143,8 +156,12 @@ impl DocumentConverter {
         tantivy_doc.add_object(self.json_filter_field, json_to_btree(&filter_json)?);
         tantivy_doc.add_object(self.json_exact_field, json_to_btree(&search_json)?);
 
-        let facet_fields: std::collections::HashSet<String> =
-            settings.map(|s| s.facet_set()).unwrap_or_default();
+        // filterOnly() attributes are filtered through `_json_filter`; skipping
+        // them here keeps their values out of the facet-count field.
+        let facet_fields: std::collections::HashSet<String> = settings
+            .map(|s| s.countable_facet_set())
+            .unwrap_or_default();
+        let normalized = settings.map(|s| s.normalized_facets()).unwrap_or_default();
 
         for (field_name, value) in json_fields.as_object().unwrap() {
             let dominated = facet_fields.contains(field_name)
@@ -155,29 +172,20 @@ impl DocumentConverter {
                 continue;
             }
 
+            let normalization = normalized.get(field_name.as_str());
+            let facet_path = |s: &str| {
+                let bucket = normalization.map(|n| n.bucket(s));
+                let s = bucket.as_deref().unwrap_or(s);
+                let truncated = if s.len() > 1000 { &s[..1000] } else { s };
+                format!("/{}/{}", field_name, truncated)
+            };
             let paths = if is_hierarchical_facet(value) {
                 extract_facet_paths(field_name, value)?
             } else if let Value::String(s) = value {
-                let truncated = if s.len() > 1000 {
-                    &s[..1000]
-                } else {
-                    s.as_str()
-                };
-                vec![format!("/{}/{}", field_name, truncated)]
+                vec![facet_path(s)]
             } else if let Value::Array(arr) = value {
                 arr.iter()
-                    .filter_map(|item| {
-                        if let Value::String(s) = item {
-                            let truncated = if s.len() > 1000 {
-                                &s[..1000]
-                            } else {
-                                s.as_str()
-                            };
-                            Some(format!("/{}/{}", field_name, truncated))
-                        } else {
-                            None
-                        }
-                    })
+                    .filter_map(|item| item.as_str().map(facet_path))
                     .collect()
             } else {
                 vec![]
@@ -219,6 +227,80 @@ impl DocumentConverter {
     }
 }
 
+/// Every live document of an index, read segment by segment from one
+/// searcher snapshot. Unlike paging through an empty search this includes
+/// documents hidden from results (scheduled, unpublished, expired but not
+/// yet purged), and it reads each document once.
+pub struct StoredDocuments {
+    searcher: tantivy::Searcher,
+    schema: tantivy::schema::Schema,
+    converter: Arc<DocumentConverter>,
+    segment: usize,
+    doc: tantivy::DocId,
+    store: Option<tantivy::store::StoreReader>,
+}
+
+impl StoredDocuments {
+    pub fn new(
+        searcher: tantivy::Searcher,
+        schema: tantivy::schema::Schema,
+        converter: Arc<DocumentConverter>,
+    ) -> Self {
+        Self {
+            searcher,
+            schema,
+            converter,
+            segment: 0,
+            doc: 0,
+            store: None,
+        }
+    }
+
+    fn next_segment(&mut self) {
+        self.segment += 1;
+        self.doc = 0;
+        self.store = None;
+    }
+}
+
+impl Iterator for StoredDocuments {
+    type Item = Result<Document>;
+
+    fn next(&mut self) -> Option<Result<Document>> {
+        loop {
+            let reader = self.searcher.segment_readers().get(self.segment)?;
+            if self.doc >= reader.max_doc() {
+                self.next_segment();
+                continue;
+            }
+            let doc = self.doc;
+            self.doc += 1;
+            if reader
+                .alive_bitset()
+                .is_some_and(|alive| !alive.is_alive(doc))
+            {
+                continue;
+            }
+            if self.store.is_none() {
+                match reader.get_store_reader(1) {
+                    Ok(store) => self.store = Some(store),
+                    Err(e) => {
+                        self.next_segment();
+                        return Some(Err(e.into()));
+                    }
+                }
+            }
+            return Some(
+                self.store
+                    .as_ref()?
+                    .get::<TantivyDocument>(doc)
+                    .map_err(FlapjackError::from)
+                    .and_then(|d| self.converter.from_tantivy(d, &self.schema, String::new())),
+            );
+        }
+    }
+}
+
 fn owned_value_to_fields(
     value: &OwnedValue,
 ) -> Result<std::collections::HashMap<String, FieldValue>> {
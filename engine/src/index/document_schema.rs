@@ -0,0 +1,380 @@
+//! Write-time record validation for the `documentSchema` setting.
+//!
+//! `documentSchema` is a JSON Schema that every written record (after
+//! `ingestTransforms`, without `objectID`) must satisfy; records that don't
+//! are rejected with the reasons instead of being indexed. The supported
+//! keywords are `type`, `enum`, `const`, `required`, `properties`,
+//! `additionalProperties`, `items`, `minItems`/`maxItems`,
+//! `minLength`/`maxLength`, `pattern`, `minimum`/`maximum` and
+//! `exclusiveMinimum`/`exclusiveMaximum`. Annotations (`title`,
+//! `description`, `$schema`, ...) are ignored, and schemas using keywords
+//! that change validation but aren't supported (`$ref`, `anyOf`, ...) are
+//! refused when the setting is saved rather than silently not enforced.
+
+use regex::Regex;
+use serde_json::{Map, Value};
+
+/// Keywords that carry no constraint.
+const ANNOTATIONS: &[&str] = &[
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+    "deprecated",
+    "readOnly",
+    "writeOnly",
+    "format",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct DocumentSchema {
+    types: Vec<String>,
+    enum_values: Option<Vec<Value>>,
+    required: Vec<String>,
+    properties: Vec<(String, DocumentSchema)>,
+    /// Schema for properties not listed in `properties`.
+    additional_properties: Option<Box<DocumentSchema>>,
+    /// `additionalProperties: false`.
+    no_additional_properties: bool,
+    items: Option<Box<DocumentSchema>>,
+    min_items: Option<usize>,
+    max_items: Option<usize>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    pattern: Option<Regex>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    exclusive_minimum: Option<f64>,
+    exclusive_maximum: Option<f64>,
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn pointer(path: &str) -> &str {
+    if path.is_empty() {
+        "/"
+    } else {
+        path
+    }
+}
+
+impl DocumentSchema {
+    pub fn compile(schema: &Value) -> Result<Self, String> {
+        Self::compile_at(schema, "documentSchema")
+    }
+
+    fn compile_at(schema: &Value, at: &str) -> Result<Self, String> {
+        let map = match schema {
+            Value::Bool(true) => return Ok(DocumentSchema::default()),
+            Value::Object(map) => map,
+            _ => return Err(format!("{}: a schema must be an object", at)),
+        };
+        let mut compiled = DocumentSchema::default();
+        let count = |key: &str, v: &Value| {
+            v.as_u64()
+                .map(|n| n as usize)
+                .ok_or_else(|| format!("{}.{} must be a non-negative integer", at, key))
+        };
+        let number = |key: &str, v: &Value| {
+            v.as_f64()
+                .ok_or_else(|| format!("{}.{} must be a number", at, key))
+        };
+        for (key, value) in map {
+            let key = key.as_str();
+            match key {
+                "type" => {
+                    compiled.types = match value {
+                        Value::String(t) => vec![t.clone()],
+                        Value::Array(ts) => ts
+                            .iter()
+                            .map(|t| t.as_str().map(str::to_string))
+                            .collect::<Option<_>>()
+                            .ok_or_else(|| format!("{}.type must list type names", at))?,
+                        _ => return Err(format!("{}.type must be a string or array", at)),
+                    };
+                    let known = [
+                        "null", "boolean", "integer", "number", "string", "array", "object",
+                    ];
+                    if let Some(t) = compiled.types.iter().find(|t| !known.contains(&t.as_str())) {
+                        return Err(format!("{}.type: unknown type '{}'", at, t));
+                    }
+                }
+                "enum" => {
+                    compiled.enum_values = Some(
+                        value
+                            .as_array()
+                            .cloned()
+                            .ok_or_else(|| format!("{}.enum must be an array", at))?,
+                    );
+                }
+                "const" => compiled.enum_values = Some(vec![value.clone()]),
+                "required" => {
+                    compiled.required = value
+                        .as_array()
+                        .and_then(|names| {
+                            names
+                                .iter()
+                                .map(|n| n.as_str().map(str::to_string))
+                                .collect()
+                        })
+                        .ok_or_else(|| format!("{}.required must list property names", at))?;
+                }
+                "properties" => {
+                    let props = value
+                        .as_object()
+                        .ok_or_else(|| format!("{}.properties must be an object", at))?;
+                    for (name, prop) in props {
+                        let child = Self::compile_at(prop, &format!("{}.properties.{}", at, name))?;
+                        compiled.properties.push((name.clone(), child));
+                    }
+                }
+                "additionalProperties" => match value {
+                    Value::Bool(allowed) => compiled.no_additional_properties = !allowed,
+                    schema => {
+                        compiled.additional_properties = Some(Box::new(Self::compile_at(
+                            schema,
+                            &format!("{}.additionalProperties", at),
+                        )?))
+                    }
+                },
+                "items" => {
+                    compiled.items =
+                        Some(Box::new(Self::compile_at(value, &format!("{}.items", at))?))
+                }
+                "minItems" => compiled.min_items = Some(count(key, value)?),
+                "maxItems" => compiled.max_items = Some(count(key, value)?),
+                "minLength" => compiled.min_length = Some(count(key, value)?),
+                "maxLength" => compiled.max_length = Some(count(key, value)?),
+                "pattern" => {
+                    let pattern = value
+                        .as_str()
+                        .ok_or_else(|| format!("{}.pattern must be a string", at))?;
+                    compiled.pattern = Some(
+                        Regex::new(pattern)
+                            .map_err(|e| format!("{}.pattern is invalid: {}", at, e))?,
+                    );
+                }
+                "minimum" => compiled.minimum = Some(number(key, value)?),
+                "maximum" => compiled.maximum = Some(number(key, value)?),
+                "exclusiveMinimum" => compiled.exclusive_minimum = Some(number(key, value)?),
+                "exclusiveMaximum" => compiled.exclusive_maximum = Some(number(key, value)?),
+                k if ANNOTATIONS.contains(&k) => {}
+                k => return Err(format!("{}: unsupported keyword '{}'", at, k)),
+            }
+        }
+        Ok(compiled)
+    }
+
+    /// Every way `record` breaks the schema, as `"/path: reason"`.
+    pub fn validate(&self, record: &Value) -> Vec<String> {
+        let mut errors = Vec::new();
+        self.validate_at(record, "", &mut errors);
+        errors
+    }
+
+    /// Validate a record given as its attributes.
+    pub fn validate_record(&self, record: &Map<String, Value>) -> Vec<String> {
+        self.validate(&Value::Object(record.clone()))
+    }
+
+    fn validate_at(&self, value: &Value, path: &str, errors: &mut Vec<String>) {
+        let actual = type_name(value);
+        if !self.types.is_empty()
+            && !self
+                .types
+                .iter()
+                .any(|t| t == actual || (t == "number" && actual == "integer"))
+        {
+            errors.push(format!(
+                "{}: expected {}, got {}",
+                pointer(path),
+                self.types.join(" or "),
+                actual
+            ));
+            return;
+        }
+        if let Some(allowed) = &self.enum_values {
+            if !allowed.contains(value) {
+                errors.push(format!(
+                    "{}: {} is not an allowed value",
+                    pointer(path),
+                    value
+                ));
+            }
+        }
+        match value {
+            Value::Object(map) => self.validate_object(map, path, errors),
+            Value::Array(items) => {
+                if let Some(min) = self.min_items.filter(|&min| items.len() < min) {
+                    errors.push(format!(
+                        "{}: expected at least {} items",
+                        pointer(path),
+                        min
+                    ));
+                }
+                if let Some(max) = self.max_items.filter(|&max| items.len() > max) {
+                    errors.push(format!("{}: expected at most {} items", pointer(path), max));
+                }
+                if let Some(item_schema) = &self.items {
+                    for (i, item) in items.iter().enumerate() {
+                        item_schema.validate_at(item, &format!("{}/{}", path, i), errors);
+                    }
+                }
+            }
+            Value::String(s) => {
+                let len = s.chars().count();
+                if let Some(min) = self.min_length.filter(|&min| len < min) {
+                    errors.push(format!(
+                        "{}: shorter than {} characters",
+                        pointer(path),
+                        min
+                    ));
+                }
+                if let Some(max) = self.max_length.filter(|&max| len > max) {
+                    errors.push(format!("{}: longer than {} characters", pointer(path), max));
+                }
+                if let Some(pattern) = self.pattern.as_ref().filter(|p| !p.is_match(s)) {
+                    errors.push(format!(
+                        "{}: does not match /{}/",
+                        pointer(path),
+                        pattern.as_str()
+                    ));
+                }
+            }
+            Value::Number(n) => {
+                let n = n.as_f64().unwrap_or_default();
+                let at = pointer(path);
+                if let Some(min) = self.minimum.filter(|&min| n < min) {
+                    errors.push(format!("{}: {} is less than {}", at, n, min));
+                }
+                if let Some(max) = self.maximum.filter(|&max| n > max) {
+                    errors.push(format!("{}: {} is greater than {}", at, n, max));
+                }
+                if let Some(min) = self.exclusive_minimum.filter(|&min| n <= min) {
+                    errors.push(format!("{}: {} is not greater than {}", at, n, min));
+                }
+                if let Some(max) = self.exclusive_maximum.filter(|&max| n >= max) {
+                    errors.push(format!("{}: {} is not less than {}", at, n, max));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn validate_object(&self, map: &Map<String, Value>, path: &str, errors: &mut Vec<String>) {
+        for name in &self.required {
+            if !map.contains_key(name) {
+                errors.push(format!("{}/{}: required", path, name));
+            }
+        }
+        for (name, value) in map {
+            let child_path = format!("{}/{}", path, name);
+            match self.properties.iter().find(|(p, _)| p == name) {
+                Some((_, schema)) => schema.validate_at(value, &child_path, errors),
+                None if self.no_additional_properties => {
+                    errors.push(format!("{}: not allowed", child_path))
+                }
+                None => {
+                    if let Some(schema) = &self.additional_properties {
+                        schema.validate_at(value, &child_path, errors);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn product_schema() -> DocumentSchema {
+        DocumentSchema::compile(&json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "Product",
+            "type": "object",
+            "required": ["name", "price"],
+            "properties": {
+                "name": {"type": "string", "minLength": 1},
+                "price": {"type": "number", "minimum": 0},
+                "stock": {"type": "integer"},
+                "sku": {"type": "string", "pattern": "^[A-Z]{3}-\\d+$"},
+                "color": {"enum": ["red", "blue"]},
+                "tags": {"type": "array", "items": {"type": "string"}, "maxItems": 2}
+            },
+            "additionalProperties": false
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn valid_record_passes() {
+        let errors = product_schema().validate(&json!({
+            "name": "Boot",
+            "price": 120,
+            "stock": 3,
+            "sku": "BTS-12",
+            "color": "red",
+            "tags": ["leather"]
+        }));
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn reports_every_violation_with_its_path() {
+        let mut errors = product_schema().validate(&json!({
+            "name": "",
+            "price": "12.99",
+            "stock": 2.5,
+            "sku": "bts12",
+            "color": "green",
+            "tags": ["a", 3, "c"],
+            "internal": true
+        }));
+        errors.sort();
+        assert_eq!(
+            errors,
+            vec![
+                "/color: \"green\" is not an allowed value",
+                "/internal: not allowed",
+                "/name: shorter than 1 characters",
+                "/price: expected number, got string",
+                "/sku: does not match /^[A-Z]{3}-\\d+$/",
+                "/stock: expected integer, got number",
+                "/tags/1: expected string, got integer",
+                "/tags: expected at most 2 items",
+            ]
+        );
+        assert_eq!(
+            product_schema().validate(&json!({"name": "Mug"})),
+            vec!["/price: required"]
+        );
+        assert_eq!(
+            product_schema().validate(&json!({"name": "Mug", "price": -1})),
+            vec!["/price: -1 is less than 0"]
+        );
+    }
+
+    #[test]
+    fn refuses_unsupported_or_malformed_schemas() {
+        assert!(DocumentSchema::compile(&json!({"anyOf": [{"type": "string"}]})).is_err());
+        assert!(DocumentSchema::compile(&json!({"properties": {"a": {"$ref": "#/x"}}})).is_err());
+        assert!(DocumentSchema::compile(&json!({"type": "decimal"})).is_err());
+        assert!(DocumentSchema::compile(&json!({"pattern": "("})).is_err());
+        assert!(DocumentSchema::compile(&json!("object")).is_err());
+    }
+}
@@ -0,0 +1,574 @@
+//! Duplicate detection for the `duplicateDetection` setting.
+//!
+//! Every written record is fingerprinted from its text attributes:
+//!
+//! - `_contentHash`: a hash of the normalized text (case, punctuation and
+//!   whitespace ignored), equal for exact duplicates.
+//! - `_simhash`: a 64-bit SimHash of the text's character trigrams.
+//!   Near-duplicates differ in only a few bits.
+//! - `_simhashBands`: the SimHash split into `maxDistance + 1` bands. Two
+//!   fingerprints within `maxDistance` bits of each other share at least one
+//!   band, so candidates are found with an ordinary filter.
+//!
+//! ```json
+//! "duplicateDetection": {
+//!   "attributes": ["title", "brand", "description"],
+//!   "maxDistance": 3,
+//!   "policy": "overwrite"
+//! }
+//! ```
+//!
+//! The write path looks up an incoming record's fingerprint among existing
+//! records and applies the policy: `reject` refuses the record, `overwrite`
+//! deletes the record it duplicates, and `tag` (the default) keeps both and
+//! sets `_duplicateOf` on the new one.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::types::{Document, FieldValue, Filter};
+
+pub const CONTENT_HASH_ATTRIBUTE: &str = "_contentHash";
+pub const SIMHASH_ATTRIBUTE: &str = "_simhash";
+pub const SIMHASH_BANDS_ATTRIBUTE: &str = "_simhashBands";
+pub const DUPLICATE_OF_ATTRIBUTE: &str = "_duplicateOf";
+
+/// Largest `maxDistance`; beyond it bands get too narrow to be selective.
+pub const MAX_DISTANCE_LIMIT: u32 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DuplicatePolicy {
+    /// Refuse the incoming record.
+    Reject,
+    /// Index the incoming record and delete the one it duplicates.
+    Overwrite,
+    /// Index both; the incoming record gets `_duplicateOf`.
+    #[default]
+    Tag,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateDetection {
+    /// Attributes (dotted paths allowed) to compare, strings and numbers.
+    /// Empty compares every top-level string attribute.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<String>,
+    /// SimHash bits two records may differ by and still be duplicates.
+    /// 0 only catches exact duplicates.
+    #[serde(default)]
+    pub max_distance: u32,
+    #[serde(default)]
+    pub policy: DuplicatePolicy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint {
+    pub content_hash: i64,
+    pub simhash: u64,
+}
+
+/// Records found to duplicate each other.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateCluster {
+    #[serde(rename = "objectIDs")]
+    pub object_ids: Vec<String>,
+    /// Whether every record has the same normalized text.
+    pub exact: bool,
+}
+
+/// Duplicate clusters across an index (`GET /1/indexes/:indexName/duplicates`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateReport {
+    /// Records carrying a fingerprint.
+    pub nb_records: usize,
+    pub nb_clusters: usize,
+    pub clusters: Vec<DuplicateCluster>,
+}
+
+impl DuplicateDetection {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_distance > MAX_DISTANCE_LIMIT {
+            return Err(format!(
+                "duplicateDetection.maxDistance must be at most {}",
+                MAX_DISTANCE_LIMIT
+            ));
+        }
+        if self.attributes.iter().any(|a| a.is_empty()) {
+            return Err("duplicateDetection.attributes must not contain empty names".to_string());
+        }
+        Ok(())
+    }
+
+    /// Fingerprint of a record, or `None` if it has no text to compare.
+    pub fn fingerprint(&self, record: &Map<String, Value>) -> Option<Fingerprint> {
+        let texts = self.texts(record);
+        let words: Vec<Vec<String>> = texts.iter().map(|t| normalized_words(t)).collect();
+        if words.iter().all(|w| w.is_empty()) {
+            return None;
+        }
+        let mut hasher = Sha256::new();
+        for attribute_words in &words {
+            hasher.update(attribute_words.join(" ").as_bytes());
+            hasher.update([0x1f]);
+        }
+        let digest = hasher.finalize();
+        let mut head = [0u8; 8];
+        head.copy_from_slice(&digest[..8]);
+        // 52 bits stay exact as a JSON number in any client.
+        let content_hash = (u64::from_be_bytes(head) >> 12) as i64;
+        Some(Fingerprint {
+            content_hash,
+            simhash: simhash(&words.concat()),
+        })
+    }
+
+    /// Replace the document's fingerprint attributes with fresh ones.
+    pub fn annotate(&self, doc: &mut Document) {
+        for attribute in [
+            CONTENT_HASH_ATTRIBUTE,
+            SIMHASH_ATTRIBUTE,
+            SIMHASH_BANDS_ATTRIBUTE,
+        ] {
+            doc.fields.remove(attribute);
+        }
+        let record: Map<String, Value> = doc
+            .fields
+            .iter()
+            .map(|(k, v)| (k.clone(), crate::types::field_value_to_json_value(v)))
+            .collect();
+        let Some(fingerprint) = self.fingerprint(&record) else {
+            return;
+        };
+        doc.fields.insert(
+            CONTENT_HASH_ATTRIBUTE.to_string(),
+            FieldValue::Integer(fingerprint.content_hash),
+        );
+        doc.fields.insert(
+            SIMHASH_ATTRIBUTE.to_string(),
+            FieldValue::Integer(fingerprint.simhash as i64),
+        );
+        if self.max_distance > 0 {
+            doc.fields.insert(
+                SIMHASH_BANDS_ATTRIBUTE.to_string(),
+                FieldValue::Array(
+                    self.bands(&fingerprint)
+                        .into_iter()
+                        .map(FieldValue::Integer)
+                        .collect(),
+                ),
+            );
+        }
+    }
+
+    /// Filter matching every record that may duplicate `fingerprint`.
+    pub fn candidate_filter(&self, fingerprint: &Fingerprint) -> Filter {
+        let mut filters = vec![Filter::Equals {
+            field: CONTENT_HASH_ATTRIBUTE.to_string(),
+            value: FieldValue::Integer(fingerprint.content_hash),
+        }];
+        filters.extend(
+            self.bands(fingerprint)
+                .into_iter()
+                .map(|band| Filter::Equals {
+                    field: SIMHASH_BANDS_ATTRIBUTE.to_string(),
+                    value: FieldValue::Integer(band),
+                }),
+        );
+        Filter::Or(filters)
+    }
+
+    pub fn is_duplicate(&self, a: &Fingerprint, b: &Fingerprint) -> bool {
+        a.content_hash == b.content_hash
+            || (self.max_distance > 0 && (a.simhash ^ b.simhash).count_ones() <= self.max_distance)
+    }
+
+    /// Group fingerprinted records into clusters of two or more duplicates,
+    /// largest first.
+    pub fn clusters(&self, records: &[(String, Fingerprint)]) -> Vec<DuplicateCluster> {
+        let mut parent: Vec<usize> = (0..records.len()).collect();
+        let mut buckets: HashMap<i64, Vec<usize>> = HashMap::new();
+        for (i, (_, fingerprint)) in records.iter().enumerate() {
+            buckets.entry(fingerprint.content_hash).or_default().push(i);
+        }
+        let mut band_buckets: HashMap<i64, Vec<usize>> = HashMap::new();
+        if self.max_distance > 0 {
+            for (i, (_, fingerprint)) in records.iter().enumerate() {
+                for band in self.bands(fingerprint) {
+                    band_buckets.entry(band).or_default().push(i);
+                }
+            }
+        }
+        for members in buckets.values() {
+            for &other in &members[1..] {
+                union(&mut parent, members[0], other);
+            }
+        }
+        for members in band_buckets.values() {
+            for (n, &a) in members.iter().enumerate() {
+                for &b in &members[n + 1..] {
+                    if self.is_duplicate(&records[a].1, &records[b].1) {
+                        union(&mut parent, a, b);
+                    }
+                }
+            }
+        }
+
+        let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+        for i in 0..records.len() {
+            let root = find(&mut parent, i);
+            groups.entry(root).or_default().push(i);
+        }
+        let mut clusters: Vec<DuplicateCluster> = groups
+            .into_values()
+            .filter(|members| members.len() > 1)
+            .map(|members| {
+                let hash = records[members[0]].1.content_hash;
+                let mut object_ids: Vec<String> =
+                    members.iter().map(|&i| records[i].0.clone()).collect();
+                object_ids.sort();
+                DuplicateCluster {
+                    exact: members.iter().all(|&i| records[i].1.content_hash == hash),
+                    object_ids,
+                }
+            })
+            .collect();
+        clusters.sort_by(|a, b| {
+            b.object_ids
+                .len()
+                .cmp(&a.object_ids.len())
+                .then_with(|| a.object_ids.cmp(&b.object_ids))
+        });
+        clusters
+    }
+
+    /// Band `i` of `maxDistance + 1` equal slices of the SimHash, tagged
+    /// with its position so equal bits in different bands don't collide.
+    fn bands(&self, fingerprint: &Fingerprint) -> Vec<i64> {
+        if self.max_distance == 0 {
+            return Vec::new();
+        }
+        let count = self.max_distance as u64 + 1;
+        (0..count)
+            .map(|i| {
+                let start = i * 64 / count;
+                let width = (i + 1) * 64 / count - start;
+                let bits = (fingerprint.simhash >> start) & ((1u64 << width) - 1);
+                ((i << 32) | bits) as i64
+            })
+            .collect()
+    }
+
+    fn texts(&self, record: &Map<String, Value>) -> Vec<String> {
+        let mut texts = Vec::new();
+        if self.attributes.is_empty() {
+            let mut keys: Vec<&String> = record
+                .keys()
+                .filter(|k| !k.starts_with('_') && k.as_str() != "objectID")
+                .collect();
+            keys.sort();
+            for key in keys {
+                collect_texts(&record[key.as_str()], false, &mut texts);
+            }
+        } else {
+            for attribute in &self.attributes {
+                if let Some(value) = lookup(record, attribute) {
+                    collect_texts(value, true, &mut texts);
+                }
+            }
+        }
+        texts
+    }
+}
+
+impl Fingerprint {
+    /// The fingerprint stored on an indexed document, if any.
+    pub fn from_document(doc: &Document) -> Option<Self> {
+        match (
+            doc.fields.get(CONTENT_HASH_ATTRIBUTE),
+            doc.fields.get(SIMHASH_ATTRIBUTE),
+        ) {
+            (Some(FieldValue::Integer(hash)), Some(FieldValue::Integer(simhash))) => {
+                Some(Fingerprint {
+                    content_hash: *hash,
+                    simhash: *simhash as u64,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn lookup<'a>(record: &'a Map<String, Value>, path: &str) -> Option<&'a Value> {
+    let mut parts = path.split('.');
+    let mut value = record.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_object()?.get(part)?;
+    }
+    Some(value)
+}
+
+fn collect_texts(value: &Value, numbers: bool, out: &mut Vec<String>) {
+    match value {
+        Value::String(s) => out.push(s.clone()),
+        Value::Number(n) if numbers => out.push(n.to_string()),
+        Value::Array(items) => items.iter().for_each(|v| collect_texts(v, numbers, out)),
+        _ => {}
+    }
+}
+
+fn normalized_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Characters per SimHash feature.
+const SHINGLE: usize = 3;
+
+/// SimHash over overlapping character shingles of the normalized text.
+fn simhash(words: &[String]) -> u64 {
+    let text: Vec<char> = words.join(" ").chars().collect();
+    let features: Vec<String> = if text.len() <= SHINGLE {
+        vec![text.iter().collect()]
+    } else {
+        text.windows(SHINGLE).map(|w| w.iter().collect()).collect()
+    };
+    let mut weights = [0i32; 64];
+    for feature in &features {
+        let hash = feature_hash(feature);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, &w)| w > 0)
+        .fold(0u64, |acc, (bit, _)| acc | 1 << bit)
+}
+
+/// FNV-1a with a final mix so every output bit depends on the whole input.
+/// Stable across builds, unlike `DefaultHasher`.
+fn feature_hash(feature: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in feature.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^ (hash >> 33)
+}
+
+fn find(parent: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    let mut node = i;
+    while parent[node] != root {
+        let next = parent[node];
+        parent[node] = root;
+        node = next;
+    }
+    root
+}
+
+fn union(parent: &mut [usize], a: usize, b: usize) {
+    let (ra, rb) = (find(parent, a), find(parent, b));
+    if ra != rb {
+        parent[ra.max(rb)] = ra.min(rb);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    fn detection(max_distance: u32) -> DuplicateDetection {
+        DuplicateDetection {
+            attributes: vec!["title".to_string(), "description".to_string()],
+            max_distance,
+            policy: DuplicatePolicy::Tag,
+        }
+    }
+
+    const DESCRIPTION: &str = "Lightweight trail running shoe with a breathable mesh upper, \
+        a rock plate for rough terrain and a grippy outsole that holds on wet stone";
+
+    #[test]
+    fn exact_duplicates_ignore_case_punctuation_and_other_attributes() {
+        let config = detection(0);
+        let a = config
+            .fingerprint(&record(json!({
+                "objectID": "1", "title": "Trail Runner 2", "description": DESCRIPTION,
+                "price": 120
+            })))
+            .unwrap();
+        let b = config
+            .fingerprint(&record(json!({
+                "objectID": "feed-b-77", "title": "trail  runner-2",
+                "description": DESCRIPTION.to_uppercase(), "price": 99
+            })))
+            .unwrap();
+        assert_eq!(a, b);
+
+        let c = config
+            .fingerprint(&record(
+                json!({"title": "Trail Runner 3", "description": DESCRIPTION}),
+            ))
+            .unwrap();
+        assert_ne!(a.content_hash, c.content_hash);
+        assert!(!config.is_duplicate(&a, &c));
+        assert!(config.fingerprint(&record(json!({"price": 5}))).is_none());
+    }
+
+    #[test]
+    fn near_duplicates_share_a_band_and_fall_within_distance() {
+        let config = detection(MAX_DISTANCE_LIMIT);
+        let a = config
+            .fingerprint(&record(
+                json!({"title": "Trail Runner 2", "description": DESCRIPTION}),
+            ))
+            .unwrap();
+        let edited = DESCRIPTION.replace("rough", "rocky");
+        let b = config
+            .fingerprint(&record(
+                json!({"title": "Trail Runner 2", "description": edited}),
+            ))
+            .unwrap();
+        assert_ne!(a.content_hash, b.content_hash);
+        let distance = (a.simhash ^ b.simhash).count_ones();
+        assert!(distance <= MAX_DISTANCE_LIMIT, "distance {}", distance);
+        assert!(config.is_duplicate(&a, &b));
+        let (bands_a, bands_b) = (config.bands(&a), config.bands(&b));
+        assert_eq!(bands_a.len(), 8);
+        assert!(bands_a.iter().any(|band| bands_b.contains(band)));
+
+        let unrelated = config
+            .fingerprint(&record(json!({
+                "title": "Espresso machine",
+                "description": "Fifteen bar pump, steam wand and a removable water tank"
+            })))
+            .unwrap();
+        assert!(!config.is_duplicate(&a, &unrelated));
+    }
+
+    #[test]
+    fn default_attributes_are_top_level_strings() {
+        let config = DuplicateDetection::default();
+        let a = config
+            .fingerprint(&record(json!({
+                "objectID": "1", "name": "Desk lamp", "brand": "Lumo", "stock": 4,
+                "_duplicateOf": "x"
+            })))
+            .unwrap();
+        let b = config
+            .fingerprint(&record(
+                json!({"brand": "lumo", "name": "Desk Lamp", "stock": 9}),
+            ))
+            .unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn annotate_replaces_fingerprint_attributes() {
+        let config = detection(3);
+        let mut doc = Document {
+            id: "1".to_string(),
+            fields: HashMap::from([
+                (
+                    "title".to_string(),
+                    FieldValue::Text("Desk lamp".to_string()),
+                ),
+                (SIMHASH_ATTRIBUTE.to_string(), FieldValue::Integer(1)),
+            ]),
+        };
+        config.annotate(&mut doc);
+        let fingerprint = Fingerprint::from_document(&doc).unwrap();
+        assert_eq!(
+            Some(fingerprint),
+            config.fingerprint(&record(json!({"title": "Desk lamp"})))
+        );
+        match doc.fields.get(SIMHASH_BANDS_ATTRIBUTE) {
+            Some(FieldValue::Array(bands)) => assert_eq!(bands.len(), 4),
+            other => panic!("unexpected bands {:?}", other),
+        }
+
+        doc.fields.remove("title");
+        config.annotate(&mut doc);
+        assert!(Fingerprint::from_document(&doc).is_none());
+        assert!(!doc.fields.contains_key(SIMHASH_BANDS_ATTRIBUTE));
+    }
+
+    #[test]
+    fn clusters_group_exact_and_near_duplicates() {
+        let config = detection(MAX_DISTANCE_LIMIT);
+        let fp = |title: &str, description: &str| {
+            config
+                .fingerprint(&record(json!({"title": title, "description": description})))
+                .unwrap()
+        };
+        let edited = DESCRIPTION.replace("rough", "rocky");
+        let records = vec![
+            ("b".to_string(), fp("Trail Runner 2", DESCRIPTION)),
+            ("lamp".to_string(), fp("Desk lamp", "Warm light, dimmable")),
+            ("a".to_string(), fp("trail runner 2", DESCRIPTION)),
+            ("c".to_string(), fp("Trail Runner 2", &edited)),
+            ("x".to_string(), fp("Kettle", "Boils water")),
+            ("y".to_string(), fp("KETTLE", "boils water!")),
+        ];
+        assert_eq!(
+            config.clusters(&records),
+            vec![
+                DuplicateCluster {
+                    object_ids: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+                    exact: false,
+                },
+                DuplicateCluster {
+                    object_ids: vec!["x".to_string(), "y".to_string()],
+                    exact: true,
+                },
+            ]
+        );
+        assert_eq!(detection(0).clusters(&records).len(), 2);
+        assert_eq!(detection(0).clusters(&records)[0].object_ids.len(), 2);
+    }
+
+    #[test]
+    fn validate_limits_distance() {
+        assert!(detection(MAX_DISTANCE_LIMIT).validate().is_ok());
+        assert!(detection(MAX_DISTANCE_LIMIT + 1).validate().is_err());
+        let mut config = detection(0);
+        config.attributes.push(String::new());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn policy_defaults_to_tag() {
+        let config: DuplicateDetection = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(config.policy, DuplicatePolicy::Tag);
+        let config: DuplicateDetection =
+            serde_json::from_value(json!({"maxDistance": 2, "policy": "reject"})).unwrap();
+        assert_eq!(config.policy, DuplicatePolicy::Reject);
+        assert_eq!(config.max_distance, 2);
+    }
+}
@@ -0,0 +1,165 @@
+//! Expiring documents.
+//!
+//! A document expires at its `_expiresAt` attribute: Unix seconds, or an
+//! RFC 3339 string that is converted to seconds on write. Indexes with a
+//! `defaultTtl` setting stamp `_expiresAt` on written documents that don't
+//! set one. Expired documents drop out of search results straight away and
+//! are deleted by a periodic sweep.
+//!
+//! The query-time exclusion lives in `visibility`, with publish windows.
+
+use dashmap::DashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::types::{Document, FieldValue, Filter};
+
+pub const EXPIRES_AT_ATTRIBUTE: &str = "_expiresAt";
+
+const MARKER_FILE: &str = "expiring";
+
+/// Marker file path -> whether it exists.
+fn markers() -> &'static DashMap<PathBuf, bool> {
+    static MARKERS: OnceLock<DashMap<PathBuf, bool>> = OnceLock::new();
+    MARKERS.get_or_init(DashMap::new)
+}
+
+/// Whether the marker file `name` exists in `index_dir`. Marker files
+/// record that an index has held documents with a given attribute, so
+/// searches on other indexes skip the checks for it.
+pub(crate) fn has_marker(index_dir: &Path, name: &str) -> bool {
+    let path = index_dir.join(name);
+    if let Some(known) = markers().get(&path) {
+        return *known;
+    }
+    let exists = path.exists();
+    markers().insert(path, exists);
+    exists
+}
+
+pub(crate) fn set_marker(index_dir: &Path, name: &str) {
+    if has_marker(index_dir, name) {
+        return;
+    }
+    let path = index_dir.join(name);
+    if let Err(e) = std::fs::write(&path, b"") {
+        tracing::warn!("failed to write marker {}: {}", path.display(), e);
+    }
+    markers().insert(path, true);
+}
+
+/// Whether the index in `index_dir` has ever held an expiring document.
+pub fn has_expiring(index_dir: &Path) -> bool {
+    has_marker(index_dir, MARKER_FILE)
+}
+
+/// Record that the index in `index_dir` holds expiring documents.
+pub fn mark_expiring(index_dir: &Path) {
+    set_marker(index_dir, MARKER_FILE)
+}
+
+/// Normalize a written document's `_expiresAt` to Unix seconds, stamping
+/// `now + default_ttl` on documents without one.
+pub fn stamp_expiry(doc: &mut Document, default_ttl: Option<u64>, now: i64) -> Result<(), String> {
+    let expires_at = match doc.fields.get(EXPIRES_AT_ATTRIBUTE) {
+        Some(value) => parse_timestamp(value).ok_or_else(|| {
+            format!(
+                "{} must be Unix seconds or an RFC 3339 date, got {:?}",
+                EXPIRES_AT_ATTRIBUTE, value
+            )
+        })?,
+        None => match default_ttl {
+            Some(ttl) => now.saturating_add(ttl as i64),
+            None => return Ok(()),
+        },
+    };
+    doc.fields.insert(
+        EXPIRES_AT_ATTRIBUTE.to_string(),
+        FieldValue::Integer(expires_at),
+    );
+    Ok(())
+}
+
+/// Unix seconds from an integer, float, numeric string or RFC 3339 date.
+pub(crate) fn parse_timestamp(value: &FieldValue) -> Option<i64> {
+    match value {
+        FieldValue::Integer(seconds) => Some(*seconds),
+        FieldValue::Float(seconds) if seconds.is_finite() => Some(*seconds as i64),
+        FieldValue::Text(text) | FieldValue::Facet(text) => {
+            text.trim().parse().ok().or_else(|| {
+                chrono::DateTime::parse_from_rfc3339(text.trim())
+                    .ok()
+                    .map(|date| date.timestamp())
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Unix seconds at which the document expires, if it does.
+pub fn expires_at(doc: &Document) -> Option<i64> {
+    match doc.fields.get(EXPIRES_AT_ATTRIBUTE)? {
+        FieldValue::Integer(seconds) => Some(*seconds),
+        _ => None,
+    }
+}
+
+/// Matches documents that have expired by `now`.
+pub fn expired_filter(now: i64) -> Filter {
+    Filter::LessThanOrEqual {
+        field: EXPIRES_AT_ATTRIBUTE.to_string(),
+        value: FieldValue::Integer(now),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn doc(expires_at: Option<FieldValue>) -> Document {
+        let mut fields = HashMap::new();
+        if let Some(value) = expires_at {
+            fields.insert(EXPIRES_AT_ATTRIBUTE.to_string(), value);
+        }
+        Document {
+            id: "1".to_string(),
+            fields,
+        }
+    }
+
+    #[test]
+    fn stamp_normalizes_to_seconds_and_applies_default_ttl() {
+        let now = 1_700_000_000;
+        let mut d = doc(Some(FieldValue::Text("2024-01-01T00:00:00Z".to_string())));
+        stamp_expiry(&mut d, Some(60), now).unwrap();
+        assert_eq!(expires_at(&d), Some(1_704_067_200));
+
+        let mut d = doc(Some(FieldValue::Float(1_700_000_100.7)));
+        stamp_expiry(&mut d, None, now).unwrap();
+        assert_eq!(expires_at(&d), Some(1_700_000_100));
+
+        let mut d = doc(None);
+        stamp_expiry(&mut d, Some(3600), now).unwrap();
+        assert_eq!(expires_at(&d), Some(now + 3600));
+
+        let mut d = doc(None);
+        stamp_expiry(&mut d, None, now).unwrap();
+        assert_eq!(expires_at(&d), None);
+
+        let mut d = doc(Some(FieldValue::Text("next tuesday".to_string())));
+        assert!(stamp_expiry(&mut d, Some(60), now).is_err());
+    }
+
+    #[test]
+    fn marker_survives_cache_misses() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().join("products");
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(!has_expiring(&dir));
+        mark_expiring(&dir);
+        assert!(has_expiring(&dir));
+        markers().remove(&dir.join(MARKER_FILE));
+        assert!(has_expiring(&dir));
+    }
+}
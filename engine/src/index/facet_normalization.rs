@@ -0,0 +1,85 @@
+//! Facet value canonicalization for `facetValueNormalization`.
+//!
+//! Values of a configured attribute are bucketed under a canonical key
+//! (trimmed and/or case-folded) before they reach the `_facets` field, and
+//! `displayNames` maps keys to the string shown in facet counts. With
+//! `{"caseFold": true, "displayNames": {"usa": "United States"}}`, "usa",
+//! "USA" and "United States" all count as one `United States` bucket.
+//! Stored documents keep their original values.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+fn is_false(v: &bool) -> bool {
+    !*v
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FacetValueNormalization {
+    /// Bucket values case-insensitively.
+    #[serde(skip_serializing_if = "is_false")]
+    pub case_fold: bool,
+
+    /// Strip leading and trailing whitespace and collapse inner runs.
+    #[serde(skip_serializing_if = "is_false")]
+    pub trim: bool,
+
+    /// Value -> preferred display string. Keys are canonicalized like
+    /// document values, and a display string buckets with its own keys.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub display_names: HashMap<String, String>,
+}
+
+impl FacetValueNormalization {
+    pub fn canonical_key(&self, value: &str) -> String {
+        let value = if self.trim {
+            value.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else {
+            value.to_string()
+        };
+        if self.case_fold {
+            value.to_lowercase()
+        } else {
+            value
+        }
+    }
+
+    /// The facet value `value` is indexed and counted as.
+    pub fn bucket(&self, value: &str) -> String {
+        let key = self.canonical_key(value);
+        self.display_names
+            .iter()
+            .find(|(from, to)| self.canonical_key(from) == key || self.canonical_key(to) == key)
+            .map(|(_, to)| to.clone())
+            .unwrap_or(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn countries() -> FacetValueNormalization {
+        FacetValueNormalization {
+            case_fold: true,
+            trim: true,
+            display_names: HashMap::from([("usa".to_string(), "United States".to_string())]),
+        }
+    }
+
+    #[test]
+    fn variants_share_the_display_bucket() {
+        let n = countries();
+        for value in ["usa", "USA", " Usa ", "United States", "united  states"] {
+            assert_eq!(n.bucket(value), "United States", "{value:?}");
+        }
+        assert_eq!(n.bucket(" France"), "france");
+    }
+
+    #[test]
+    fn default_leaves_values_alone() {
+        let n = FacetValueNormalization::default();
+        assert_eq!(n.bucket(" USA "), " USA ");
+    }
+}
@@ -0,0 +1,415 @@
+//! Server-side record transforms for the `ingestTransforms` setting.
+//!
+//! Each written record passes through the index's transforms, in order,
+//! before it is indexed, embedded or logged for replication, so feeds can be
+//! loaded as-is and reshaped on the way in:
+//!
+//! ```json
+//! "ingestTransforms": [
+//!   {"type": "rename", "from": "product_name", "to": "name"},
+//!   {"type": "drop", "attributes": ["internal_notes"]},
+//!   {"type": "extract", "path": "$.specs.dimensions[0].width", "to": "width"},
+//!   {"type": "bucket", "attribute": "price", "to": "price_range",
+//!    "ranges": [{"below": 50, "value": "under 50"}, {"below": 200, "value": "50-200"}],
+//!    "otherwise": "200+"},
+//!   {"type": "geo", "lat": "latitude", "lng": "longitude"}
+//! ]
+//! ```
+//!
+//! Transforms never reject a record: a step whose source attribute is
+//! missing or has the wrong type does nothing. `objectID` cannot be
+//! changed.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::types::{field_value_to_json_value, json_value_to_field_value, Document};
+
+fn is_false(v: &bool) -> bool {
+    !*v
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum IngestTransform {
+    /// Move attribute `from` to `to`, replacing any value already there.
+    Rename { from: String, to: String },
+    /// Remove attributes.
+    Drop { attributes: Vec<String> },
+    /// Copy the value at a JSONPath (`$.a.b[0]`, `$.items[*].sku`,
+    /// `$['odd key']`) into `to`. `[*]` collects every match into an array.
+    Extract {
+        path: String,
+        to: String,
+        /// Remove the top-level attribute the path starts from.
+        #[serde(default, skip_serializing_if = "is_false")]
+        remove_source: bool,
+    },
+    /// Set `to` to the `value` of the first range whose `below` bound is
+    /// greater than the numeric `attribute`, or to `otherwise`.
+    Bucket {
+        attribute: String,
+        to: String,
+        ranges: Vec<BucketRange>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        otherwise: Option<String>,
+    },
+    /// Build `_geoloc` from latitude and longitude attributes (numbers or
+    /// numeric strings).
+    Geo {
+        lat: String,
+        lng: String,
+        #[serde(default, skip_serializing_if = "is_false")]
+        remove_source: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BucketRange {
+    pub below: f64,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PathStep {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+/// Parse the supported JSONPath subset: `$`, then `.key`, `['key']`,
+/// `[n]` and `[*]` steps.
+fn parse_path(path: &str) -> Result<Vec<PathStep>, String> {
+    let invalid = || format!("invalid JSONPath '{}'", path);
+    let mut rest = path.strip_prefix('$').ok_or_else(invalid)?;
+    let mut steps = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                return Err(invalid());
+            }
+            steps.push(PathStep::Key(after[..end].to_string()));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(invalid)?;
+            let inner = after[..end].trim();
+            let step = if inner == "*" {
+                PathStep::Wildcard
+            } else if let Some(key) = inner
+                .strip_prefix('\'')
+                .and_then(|k| k.strip_suffix('\''))
+                .or_else(|| inner.strip_prefix('"').and_then(|k| k.strip_suffix('"')))
+            {
+                PathStep::Key(key.to_string())
+            } else {
+                PathStep::Index(inner.parse().map_err(|_| invalid())?)
+            };
+            steps.push(step);
+            rest = &after[end + 1..];
+        } else {
+            return Err(invalid());
+        }
+    }
+    match steps.first() {
+        Some(PathStep::Key(_)) => Ok(steps),
+        _ => Err(invalid()),
+    }
+}
+
+fn select(value: &Value, steps: &[PathStep]) -> Option<Value> {
+    let Some((step, rest)) = steps.split_first() else {
+        return Some(value.clone());
+    };
+    match step {
+        PathStep::Key(key) => select(value.as_object()?.get(key)?, rest),
+        PathStep::Index(i) => select(value.as_array()?.get(*i)?, rest),
+        PathStep::Wildcard => {
+            let items: Vec<Value> = match value {
+                Value::Array(items) => items.iter().filter_map(|v| select(v, rest)).collect(),
+                Value::Object(map) => map.values().filter_map(|v| select(v, rest)).collect(),
+                _ => return None,
+            };
+            Some(Value::Array(items))
+        }
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn is_object_id(attribute: &str) -> bool {
+    attribute == "objectID" || attribute == "_id"
+}
+
+impl IngestTransform {
+    pub fn validate(&self) -> Result<(), String> {
+        let targets: Vec<&str> = match self {
+            IngestTransform::Rename { from, to } => vec![from, to],
+            IngestTransform::Drop { attributes } => attributes.iter().map(String::as_str).collect(),
+            IngestTransform::Extract { path, to, .. } => {
+                parse_path(path)?;
+                vec![to]
+            }
+            IngestTransform::Bucket {
+                attribute,
+                to,
+                ranges,
+                ..
+            } => {
+                if ranges.windows(2).any(|w| w[0].below >= w[1].below) {
+                    return Err(format!(
+                        "bucket ranges for '{}' must have increasing 'below' bounds",
+                        attribute
+                    ));
+                }
+                vec![attribute, to]
+            }
+            IngestTransform::Geo { lat, lng, .. } => vec![lat, lng],
+        };
+        if targets.iter().any(|a| a.is_empty()) {
+            return Err("ingest transform attribute names cannot be empty".to_string());
+        }
+        if targets.iter().any(|a| is_object_id(a)) {
+            return Err("ingest transforms cannot change objectID".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, record: &mut Map<String, Value>) {
+        match self {
+            IngestTransform::Rename { from, to } => {
+                if let Some(value) = record.remove(from) {
+                    record.insert(to.clone(), value);
+                }
+            }
+            IngestTransform::Drop { attributes } => {
+                for attribute in attributes {
+                    record.remove(attribute);
+                }
+            }
+            IngestTransform::Extract {
+                path,
+                to,
+                remove_source,
+            } => {
+                let Ok(steps) = parse_path(path) else {
+                    return;
+                };
+                // parse_path guarantees the path starts with a key
+                let Some((PathStep::Key(source), rest)) = steps.split_first() else {
+                    return;
+                };
+                let selected = record.get(source).and_then(|v| select(v, rest));
+                if *remove_source {
+                    record.remove(source);
+                }
+                match selected {
+                    Some(Value::Null) | None => {}
+                    Some(value) => {
+                        record.insert(to.clone(), value);
+                    }
+                }
+            }
+            IngestTransform::Bucket {
+                attribute,
+                to,
+                ranges,
+                otherwise,
+            } => {
+                let Some(n) = record.get(attribute).and_then(as_number) else {
+                    return;
+                };
+                let bucket = ranges
+                    .iter()
+                    .find(|range| n < range.below)
+                    .map(|range| &range.value)
+                    .or(otherwise.as_ref());
+                if let Some(bucket) = bucket {
+                    record.insert(to.clone(), Value::String(bucket.clone()));
+                }
+            }
+            IngestTransform::Geo {
+                lat,
+                lng,
+                remove_source,
+            } => {
+                let point = record
+                    .get(lat)
+                    .and_then(as_number)
+                    .zip(record.get(lng).and_then(as_number));
+                let Some((lat_value, lng_value)) = point else {
+                    return;
+                };
+                if !(-90.0..=90.0).contains(&lat_value) || !(-180.0..=180.0).contains(&lng_value) {
+                    return;
+                }
+                if *remove_source {
+                    record.remove(lat);
+                    record.remove(lng);
+                }
+                record.insert(
+                    "_geoloc".to_string(),
+                    serde_json::json!({"lat": lat_value, "lng": lng_value}),
+                );
+            }
+        }
+    }
+}
+
+/// Run `transforms` over a record's attributes, in order.
+pub fn transform_document(transforms: &[IngestTransform], doc: &mut Document) {
+    if transforms.is_empty() {
+        return;
+    }
+    let mut record: Map<String, Value> = doc
+        .fields
+        .iter()
+        .map(|(k, v)| (k.clone(), field_value_to_json_value(v)))
+        .collect();
+    for transform in transforms {
+        transform.apply(&mut record);
+    }
+    doc.fields = record
+        .into_iter()
+        .filter(|(k, _)| !is_object_id(k))
+        .filter_map(|(k, v)| json_value_to_field_value(&v).map(|v| (k, v)))
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn run(transforms: serde_json::Value, record: serde_json::Value) -> serde_json::Value {
+        let transforms: Vec<IngestTransform> = serde_json::from_value(transforms).unwrap();
+        for t in &transforms {
+            t.validate().unwrap();
+        }
+        let mut record = record.as_object().unwrap().clone();
+        for t in &transforms {
+            t.apply(&mut record);
+        }
+        Value::Object(record)
+    }
+
+    #[test]
+    fn rename_drop_and_geo() {
+        let out = run(
+            json!([
+                {"type": "rename", "from": "product_name", "to": "name"},
+                {"type": "drop", "attributes": ["internal_notes", "missing"]},
+                {"type": "geo", "lat": "latitude", "lng": "longitude", "removeSource": true}
+            ]),
+            json!({
+                "product_name": "Boot",
+                "internal_notes": "margin 40%",
+                "latitude": "48.85",
+                "longitude": 2.35
+            }),
+        );
+        assert_eq!(
+            out,
+            json!({"name": "Boot", "_geoloc": {"lat": 48.85, "lng": 2.35}})
+        );
+    }
+
+    #[test]
+    fn extract_json_paths() {
+        let record = json!({
+            "specs": {"dimensions": [{"width": 30}, {"width": 40}], "odd key": "x"},
+            "items": [{"sku": "a"}, {"sku": "b"}, {"id": 3}]
+        });
+        let out = run(
+            json!([
+                {"type": "extract", "path": "$.specs.dimensions[1].width", "to": "width"},
+                {"type": "extract", "path": "$.items[*].sku", "to": "skus", "removeSource": true},
+                {"type": "extract", "path": "$.specs['odd key']", "to": "odd"},
+                {"type": "extract", "path": "$.specs.nothing", "to": "nothing"}
+            ]),
+            record,
+        );
+        assert_eq!(out["width"], 40);
+        assert_eq!(out["skus"], json!(["a", "b"]));
+        assert_eq!(out["odd"], "x");
+        assert!(out.get("items").is_none());
+        assert!(out.get("nothing").is_none());
+
+        for bad in ["specs.width", "$", "$.a[", "$.a[x]", "$..a"] {
+            assert!(parse_path(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn bucket_numeric_attribute() {
+        let transforms = json!([{
+            "type": "bucket", "attribute": "price", "to": "price_range",
+            "ranges": [{"below": 50, "value": "under 50"}, {"below": 200, "value": "50-200"}],
+            "otherwise": "200+"
+        }]);
+        let range = |price: serde_json::Value| {
+            run(transforms.clone(), json!({ "price": price }))["price_range"].clone()
+        };
+        assert_eq!(range(json!(12.5)), "under 50");
+        assert_eq!(range(json!(50)), "50-200");
+        assert_eq!(range(json!("999")), "200+");
+        assert_eq!(range(json!("n/a")), Value::Null);
+    }
+
+    #[test]
+    fn rejects_invalid_definitions() {
+        let parse = |v: serde_json::Value| serde_json::from_value::<IngestTransform>(v).unwrap();
+        assert!(
+            parse(json!({"type": "rename", "from": "sku", "to": "objectID"}))
+                .validate()
+                .is_err()
+        );
+        assert!(
+            parse(json!({"type": "extract", "path": "specs.width", "to": "w"}))
+                .validate()
+                .is_err()
+        );
+        assert!(parse(json!({
+            "type": "bucket", "attribute": "price", "to": "range",
+            "ranges": [{"below": 100, "value": "a"}, {"below": 50, "value": "b"}]
+        }))
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn transforms_document_fields() {
+        let mut doc = Document::from_json(&json!({
+            "objectID": "1",
+            "lat": 10.0,
+            "lng": 20.0,
+            "title": "Tent"
+        }))
+        .unwrap();
+        transform_document(
+            &[
+                IngestTransform::Geo {
+                    lat: "lat".into(),
+                    lng: "lng".into(),
+                    remove_source: true,
+                },
+                IngestTransform::Rename {
+                    from: "title".into(),
+                    to: "name".into(),
+                },
+            ],
+            &mut doc,
+        );
+        assert_eq!(doc.id, "1");
+        let json = doc.to_json();
+        assert_eq!(json["name"], "Tent");
+        assert_eq!(json["_geoloc"], json!({"lat": 10.0, "lng": 20.0}));
+        assert!(json.get("lat").is_none());
+    }
+}
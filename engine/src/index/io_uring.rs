@@ -0,0 +1,259 @@
+//! io_uring read path for stored documents and fast fields (opt-in).
+//!
+//! Built with the `io-uring` feature on Linux and enabled with
+//! `FLAPJACK_IO_URING=true`, indexes open through [`UringDirectory`]: the
+//! doc store (`.store`) and fast fields (`.fast`) are read with io_uring
+//! into owned buffers instead of through the mmap, so a cold query issues
+//! one read per block it needs rather than taking a page fault per 4 KB
+//! page. Everything else — postings, term dictionaries, metadata and all
+//! writes — stays on tantivy's `MmapDirectory`.
+//!
+//! Each thread gets its own small ring. Where io_uring is unavailable (old
+//! kernel, seccomp profile) reads fall back to `pread`.
+//! `cargo bench --bench io_uring --features io-uring` compares both paths.
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io;
+use std::ops::Range;
+use std::os::unix::fs::FileExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use io_uring::{opcode, types, IoUring};
+use tantivy::directory::error::{
+    DeleteError, LockError, OpenDirectoryError, OpenReadError, OpenWriteError,
+};
+use tantivy::directory::{
+    Directory, DirectoryLock, FileHandle, Lock, MmapDirectory, OwnedBytes, WatchCallback,
+    WatchHandle, WritePtr,
+};
+use tantivy::HasLen;
+
+const RING_ENTRIES: u32 = 8;
+
+/// Extensions of the segment files read through io_uring.
+const URING_EXTENSIONS: [&str; 2] = ["store", "fast"];
+
+enum Ring {
+    Uninit,
+    Ready(IoUring),
+    Unavailable,
+}
+
+thread_local! {
+    static RING: RefCell<Ring> = const { RefCell::new(Ring::Uninit) };
+}
+
+/// Whether `FLAPJACK_IO_URING` asks for the io_uring read path. Read on
+/// every index open, so it can differ between indexes opened by one process.
+pub fn enabled() -> bool {
+    std::env::var("FLAPJACK_IO_URING")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+}
+
+/// `MmapDirectory` with doc-store and fast-field reads going through
+/// io_uring.
+#[derive(Clone, Debug)]
+pub struct UringDirectory {
+    mmap: MmapDirectory,
+    root: PathBuf,
+}
+
+impl UringDirectory {
+    pub fn open(path: &Path) -> Result<Self, OpenDirectoryError> {
+        Ok(UringDirectory {
+            mmap: MmapDirectory::open(path)?,
+            root: path.to_path_buf(),
+        })
+    }
+
+    fn reads_with_uring(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| URING_EXTENSIONS.contains(&ext))
+    }
+}
+
+impl Directory for UringDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        if !Self::reads_with_uring(path) {
+            return self.mmap.get_file_handle(path);
+        }
+        let open_error = |e: io::Error| {
+            if e.kind() == io::ErrorKind::NotFound {
+                OpenReadError::FileDoesNotExist(path.to_path_buf())
+            } else {
+                OpenReadError::wrap_io_error(e, path.to_path_buf())
+            }
+        };
+        let file = File::open(self.root.join(path)).map_err(open_error)?;
+        let len = file.metadata().map_err(open_error)?.len() as usize;
+        Ok(Arc::new(UringFileHandle { file, len }))
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        self.mmap.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.mmap.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        self.mmap.open_write(path)
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.mmap.atomic_read(path)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.mmap.atomic_write(path, data)
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        self.mmap.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> tantivy::Result<WatchHandle> {
+        self.mmap.watch(watch_callback)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.mmap.sync_directory()
+    }
+}
+
+/// An open segment file. Deleting the file (a merge) leaves the handle
+/// readable, as with an mmap.
+#[derive(Debug)]
+struct UringFileHandle {
+    file: File,
+    len: usize,
+}
+
+impl HasLen for UringFileHandle {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl FileHandle for UringFileHandle {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        let mut buf = vec![0u8; range.len()];
+        read_exact_at(&self.file, &mut buf, range.start as u64)?;
+        Ok(OwnedBytes::new(buf))
+    }
+}
+
+/// Fill `buf` from `file` at `offset` with this thread's ring, or `pread`
+/// when there is none.
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    RING.with(|ring| {
+        let mut ring = ring.borrow_mut();
+        if matches!(*ring, Ring::Uninit) {
+            *ring = match IoUring::new(RING_ENTRIES) {
+                Ok(r) => Ring::Ready(r),
+                Err(e) => {
+                    tracing::warn!("io_uring unavailable, falling back to pread: {}", e);
+                    Ring::Unavailable
+                }
+            };
+        }
+        let Ring::Ready(ring) = &mut *ring else {
+            return file.read_exact_at(buf, offset);
+        };
+
+        while !buf.is_empty() {
+            let len = buf.len().min(u32::MAX as usize) as u32;
+            let entry = opcode::Read::new(types::Fd(file.as_raw_fd()), buf.as_mut_ptr(), len)
+                .offset(offset)
+                .build();
+            // SAFETY: `file` and `buf` outlive the read, which has completed
+            // by the time the completion is taken below.
+            unsafe {
+                ring.submission()
+                    .push(&entry)
+                    .map_err(|_| io::Error::other("io_uring submission queue is full"))?;
+            }
+            loop {
+                match ring.submit_and_wait(1) {
+                    Ok(_) => break,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                }
+            }
+            let result = ring
+                .completion()
+                .next()
+                .ok_or_else(|| io::Error::other("io_uring completion missing"))?
+                .result();
+            if result < 0 {
+                let err = io::Error::from_raw_os_error(-result);
+                if err.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(err);
+            }
+            if result == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let read = result as usize;
+            buf = &mut std::mem::take(&mut buf)[read..];
+            offset += read as u64;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tantivy::schema::{Schema, STORED, TEXT};
+    use tantivy::{doc, Index, TantivyDocument};
+
+    #[test]
+    fn reads_ranges_of_a_file() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(tmp.path().join("0.store"), &data).unwrap();
+
+        let dir = UringDirectory::open(tmp.path()).unwrap();
+        let handle = dir.get_file_handle(Path::new("0.store")).unwrap();
+        assert_eq!(handle.len(), data.len());
+        let bytes = handle.read_bytes(4_000..70_000).unwrap();
+        assert_eq!(bytes.as_slice(), &data[4_000..70_000]);
+        assert!(handle.read_bytes(99_990..100_010).is_err());
+        assert!(matches!(
+            dir.get_file_handle(Path::new("missing.store")),
+            Err(OpenReadError::FileDoesNotExist(_))
+        ));
+    }
+
+    #[test]
+    fn index_round_trips_through_the_directory() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut builder = Schema::builder();
+        let title = builder.add_text_field("title", TEXT | STORED);
+        let index = Index::create(
+            UringDirectory::open(tmp.path()).unwrap(),
+            builder.build(),
+            tantivy::IndexSettings::default(),
+        )
+        .unwrap();
+        let mut writer = index.writer(15_000_000).unwrap();
+        writer.add_document(doc!(title => "hello uring")).unwrap();
+        writer.commit().unwrap();
+
+        let reopened = Index::open(UringDirectory::open(tmp.path()).unwrap()).unwrap();
+        let searcher = reopened.reader().unwrap().searcher();
+        let stored: TantivyDocument = searcher.doc(tantivy::DocAddress::new(0, 0)).unwrap();
+        assert_eq!(
+            stored.get_first(title).and_then(|v| v.as_str()),
+            Some("hello uring")
+        );
+    }
+}
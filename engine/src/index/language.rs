@@ -0,0 +1,175 @@
+//! Per-document language detection for `autoDetectLanguage`.
+//!
+//! When the setting is on, every document written to the index gets a
+//! `_detectedLanguage` attribute holding the language of its text, as an
+//! ISO 639-1 code where one exists (`en`, `fr`, ...). The attribute is
+//! stored with the document, filterable and counted as a facet.
+
+use crate::types::FieldValue;
+use std::collections::HashMap;
+
+pub const DETECTED_LANGUAGE_ATTR: &str = "_detectedLanguage";
+
+/// Detection is only as good as the first few sentences anyway.
+const MAX_SAMPLE_BYTES: usize = 2048;
+
+/// Detect the language of a document's text. Only `attributes` are read
+/// when given (e.g. the searchable attributes); otherwise every string.
+/// Returns `None` when there's too little text for a reliable guess.
+pub fn detect_language(
+    fields: &HashMap<String, FieldValue>,
+    attributes: Option<&[String]>,
+) -> Option<String> {
+    let mut sample = String::new();
+    match attributes {
+        Some(attrs) => {
+            for attr in attrs {
+                if let Some(value) = lookup(fields, attr) {
+                    collect_text(value, &mut sample);
+                }
+            }
+        }
+        None => {
+            let mut names: Vec<&String> = fields.keys().collect();
+            names.sort();
+            for name in names {
+                if name != DETECTED_LANGUAGE_ATTR {
+                    collect_text(&fields[name], &mut sample);
+                }
+            }
+        }
+    }
+
+    let info = whatlang::detect(&sample)?;
+    if !info.is_reliable() {
+        return None;
+    }
+    Some(iso_639_1(info.lang()).to_string())
+}
+
+/// Detect the language of a search query. Most queries are a word or two,
+/// too short for detection, so this is `None` far more often than not.
+pub fn detect_query_language(query: &str) -> Option<String> {
+    let info = whatlang::detect(query)?;
+    if !info.is_reliable() {
+        return None;
+    }
+    Some(iso_639_1(info.lang()).to_string())
+}
+
+fn lookup<'a>(fields: &'a HashMap<String, FieldValue>, path: &str) -> Option<&'a FieldValue> {
+    let mut parts = path.split('.');
+    let mut value = fields.get(parts.next()?)?;
+    for part in parts {
+        match value {
+            FieldValue::Object(map) => value = map.get(part)?,
+            _ => return None,
+        }
+    }
+    Some(value)
+}
+
+fn collect_text(value: &FieldValue, sample: &mut String) {
+    if sample.len() >= MAX_SAMPLE_BYTES {
+        return;
+    }
+    match value {
+        FieldValue::Text(s) => {
+            sample.push_str(s);
+            sample.push('\n');
+        }
+        FieldValue::Array(items) => items.iter().for_each(|v| collect_text(v, sample)),
+        FieldValue::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            for key in keys {
+                collect_text(&map[key], sample);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Two-letter code for the languages `queryLanguages` uses; the ISO 639-3
+/// code for the rest.
+fn iso_639_1(lang: whatlang::Lang) -> &'static str {
+    use whatlang::Lang;
+    match lang {
+        Lang::Eng => "en",
+        Lang::Fra => "fr",
+        Lang::Deu => "de",
+        Lang::Spa => "es",
+        Lang::Ita => "it",
+        Lang::Por => "pt",
+        Lang::Nld => "nl",
+        Lang::Rus => "ru",
+        Lang::Ukr => "uk",
+        Lang::Pol => "pl",
+        Lang::Ces => "cs",
+        Lang::Swe => "sv",
+        Lang::Dan => "da",
+        Lang::Nob => "no",
+        Lang::Fin => "fi",
+        Lang::Tur => "tr",
+        Lang::Hun => "hu",
+        Lang::Ell => "el",
+        Lang::Bul => "bg",
+        Lang::Ron => "ro",
+        Lang::Ara => "ar",
+        Lang::Heb => "he",
+        Lang::Hin => "hi",
+        Lang::Cmn => "zh",
+        Lang::Jpn => "ja",
+        Lang::Kor => "ko",
+        Lang::Vie => "vi",
+        Lang::Tha => "th",
+        Lang::Ind => "id",
+        other => other.code(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> FieldValue {
+        FieldValue::Text(s.to_string())
+    }
+
+    #[test]
+    fn detects_from_all_text_fields() {
+        let fields = HashMap::from([
+            (
+                "title".to_string(),
+                text("Le chat est assis sur le tapis et regarde par la fenêtre"),
+            ),
+            ("price".to_string(), FieldValue::Integer(12)),
+        ]);
+        assert_eq!(detect_language(&fields, None).as_deref(), Some("fr"));
+    }
+
+    #[test]
+    fn only_reads_the_given_attributes() {
+        let fields = HashMap::from([
+            (
+                "body".to_string(),
+                text("The quick brown fox jumps over the lazy dog near the river bank"),
+            ),
+            ("sku".to_string(), text("ZX-10")),
+        ]);
+        assert_eq!(
+            detect_language(&fields, Some(&["body".to_string()])).as_deref(),
+            Some("en")
+        );
+        assert_eq!(detect_language(&fields, Some(&["sku".to_string()])), None);
+    }
+
+    #[test]
+    fn detects_long_queries_only() {
+        assert_eq!(
+            detect_query_language("wo kann ich günstige winterschuhe für kinder kaufen").as_deref(),
+            Some("de")
+        );
+        assert_eq!(detect_query_language("tv"), None);
+    }
+}
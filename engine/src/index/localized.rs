@@ -0,0 +1,162 @@
+//! Per-language variants of an attribute within one record.
+//!
+//! An attribute listed in `localizedAttributes` holds an object keyed by
+//! language code: `{"title": {"en": "Boots", "de": "Stiefel"}}`. A search
+//! only matches the variants in its query languages (the request's
+//! `queryLanguages`, else the index's), and its hits show each localized
+//! attribute as the variant for the first of those languages the record
+//! has, highlighted like a plain attribute. A key such as `pt-BR` counts as
+//! `pt`. Without query languages every variant is searched and hits keep the
+//! whole object.
+
+use std::borrow::Cow;
+
+use crate::types::{Document, FieldValue};
+
+/// Whether the variant key `key` is in `language`.
+fn key_matches(key: &str, language: &str) -> bool {
+    let primary = key.split(['-', '_']).next().unwrap_or(key);
+    primary.eq_ignore_ascii_case(language)
+}
+
+/// The language key of `path` if it is a variant of a localized attribute.
+fn variant_key<'a>(path: &'a str, localized: &[String]) -> Option<&'a str> {
+    localized.iter().find_map(|attribute| {
+        let rest = path.strip_prefix(attribute.as_str())?.strip_prefix('.')?;
+        rest.split('.').next()
+    })
+}
+
+/// Drop the searchable paths of variants outside `languages`, with their
+/// weights. Everything is kept if that would leave nothing to search.
+pub fn restrict_paths(
+    paths: Vec<String>,
+    weights: Vec<f32>,
+    localized: &[String],
+    languages: &[String],
+) -> (Vec<String>, Vec<f32>) {
+    if localized.is_empty() || languages.is_empty() {
+        return (paths, weights);
+    }
+    let (kept_paths, kept_weights): (Vec<String>, Vec<f32>) = paths
+        .iter()
+        .zip(&weights)
+        .filter(|(path, _)| match variant_key(path, localized) {
+            Some(key) => languages.iter().any(|l| key_matches(key, l)),
+            None => true,
+        })
+        .map(|(path, weight)| (path.clone(), *weight))
+        .unzip();
+    if kept_paths.is_empty() {
+        (paths, weights)
+    } else {
+        (kept_paths, kept_weights)
+    }
+}
+
+/// `doc` with each localized attribute replaced by its variant in the first
+/// of `languages` it has.
+pub fn localize<'a>(
+    doc: &'a Document,
+    localized: &[String],
+    languages: &[String],
+) -> Cow<'a, Document> {
+    let mut doc = Cow::Borrowed(doc);
+    for attribute in localized {
+        let Some(FieldValue::Object(variants)) = doc.fields.get(attribute) else {
+            continue;
+        };
+        let chosen = languages.iter().find_map(|language| {
+            let mut keys: Vec<&String> = variants.keys().collect();
+            keys.sort();
+            keys.into_iter()
+                .find(|key| key_matches(key, language))
+                .map(|key| variants[key].clone())
+        });
+        if let Some(value) = chosen {
+            doc.to_mut().fields.insert(attribute.clone(), value);
+        }
+    }
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn text(s: &str) -> FieldValue {
+        FieldValue::Text(s.to_string())
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn record() -> Document {
+        let title: HashMap<String, FieldValue> = [
+            ("en".to_string(), text("Hiking boots")),
+            ("de".to_string(), text("Wanderstiefel")),
+            ("pt-BR".to_string(), text("Botas de caminhada")),
+        ]
+        .into_iter()
+        .collect();
+        Document {
+            id: "1".to_string(),
+            fields: [
+                ("title".to_string(), FieldValue::Object(title)),
+                ("brand".to_string(), text("Acme")),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    #[test]
+    fn restrict_paths_keeps_query_language_variants() {
+        let paths = strings(&["title.en", "title.de", "title.pt-BR", "brand", "titles"]);
+        let weights = vec![1.0, 1.0, 1.0, 0.5, 0.25];
+        let localized = strings(&["title"]);
+
+        let (kept, kept_weights) = restrict_paths(
+            paths.clone(),
+            weights.clone(),
+            &localized,
+            &strings(&["pt", "en"]),
+        );
+        assert_eq!(
+            kept,
+            strings(&["title.en", "title.pt-BR", "brand", "titles"])
+        );
+        assert_eq!(kept_weights, vec![1.0, 1.0, 0.5, 0.25]);
+
+        let (kept, _) = restrict_paths(paths.clone(), weights.clone(), &localized, &[]);
+        assert_eq!(kept, paths);
+        let only_variants = strings(&["title.en", "title.de"]);
+        let (kept, _) = restrict_paths(
+            only_variants.clone(),
+            vec![1.0, 1.0],
+            &localized,
+            &strings(&["fr"]),
+        );
+        assert_eq!(kept, only_variants);
+    }
+
+    #[test]
+    fn localize_picks_first_available_language() {
+        let doc = record();
+        let localized = strings(&["title"]);
+
+        let de = localize(&doc, &localized, &strings(&["fr", "de", "en"]));
+        assert_eq!(de.fields.get("title"), Some(&text("Wanderstiefel")));
+        assert_eq!(de.fields.get("brand"), Some(&text("Acme")));
+        let pt = localize(&doc, &localized, &strings(&["pt"]));
+        assert_eq!(pt.fields.get("title"), Some(&text("Botas de caminhada")));
+
+        assert!(matches!(
+            localize(&doc, &localized, &strings(&["fr"])),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(localize(&doc, &localized, &[]), Cow::Borrowed(_)));
+    }
+}
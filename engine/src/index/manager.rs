@@ -1,18 +1,28 @@
 use crate::error::{FlapjackError, Result};
+use crate::index::blocklist::{Blocklist, BLOCKLIST_FILE};
+use crate::index::campaigns::{CampaignStore, CAMPAIGNS_FILE};
+use crate::index::compaction::{CompactOptions, Fragmentation};
+use crate::index::namespace::{self, NamespaceStore, NamespaceUsage};
 use crate::index::oplog::OpLog;
 use crate::index::relevance::RelevanceConfig;
+use crate::index::reranking::{ReRankingModel, RERANKING_MODEL_FILE};
 use crate::index::rules::RuleStore;
 use crate::index::settings::IndexSettings;
 use crate::index::synonyms::SynonymStore;
 use crate::index::task_queue::TaskQueue;
+use crate::index::task_store::{TaskMap, TaskRetention, TaskStore};
+#[cfg(feature = "s3-snapshots")]
+use crate::index::tiered::{self, ColdDirectory, ColdManifest, ColdStorage, SegmentCache};
 use crate::index::utils::copy_dir_recursive;
+use crate::index::wal::{self, WriteAheadLog, WAL_FILE};
 use crate::index::write_queue::{
     create_write_queue, VectorWriteContext, WriteAction, WriteOp, WriteQueue,
 };
 use crate::index::Index;
-use crate::query::{QueryExecutor, QueryParser};
+use crate::query::{AdvancedSyntaxQuery, QueryExecutor, QueryParser};
 use crate::types::{
-    Document, FacetRequest, Filter, SearchResult, Sort, TaskInfo, TaskStatus, TenantId,
+    Document, FacetRequest, Filter, ScoredDocument, SearchResult, Sort, TaskInfo, TaskProgress,
+    TaskStatus, TenantId,
 };
 use dashmap::DashMap;
 use std::collections::{HashMap, HashSet};
@@ -51,11 +61,15 @@ pub struct IndexManager {
     pub(crate) write_queues: DashMap<TenantId, WriteQueue>,
     pub(crate) write_task_handles: DashMap<TenantId, JoinHandle<Result<()>>>,
     pub(crate) oplogs: DashMap<TenantId, Arc<OpLog>>,
-    tasks: Arc<DashMap<String, TaskInfo>>,
+    wals: DashMap<TenantId, Arc<WriteAheadLog>>,
+    tasks: Arc<TaskMap>,
     task_queue: TaskQueue,
     settings_cache: DashMap<TenantId, Arc<IndexSettings>>,
     rules_cache: DashMap<TenantId, Arc<RuleStore>>,
+    blocklist_cache: DashMap<TenantId, Arc<Blocklist>>,
     synonyms_cache: DashMap<TenantId, Arc<SynonymStore>>,
+    reranking_cache: DashMap<TenantId, Arc<ReRankingModel>>,
+    namespaces: NamespaceStore,
     pub facet_cache: Arc<
         DashMap<
             String,
@@ -78,17 +92,72 @@ pub struct IndexManager {
     #[cfg(feature = "vector-search")]
     vector_indices:
         Arc<DashMap<TenantId, Arc<std::sync::RwLock<crate::vector::index::VectorIndex>>>>,
+    /// When each tenant was last searched since startup; see
+    /// [`IndexManager::idle_tenants`].
+    last_searched: DashMap<TenantId, std::time::Instant>,
+    started_at: std::time::Instant,
+    #[cfg(feature = "s3-snapshots")]
+    cold_storage: std::sync::RwLock<Option<Arc<dyn ColdStorage>>>,
+    #[cfg(feature = "s3-snapshots")]
+    segment_cache: std::sync::OnceLock<Arc<SegmentCache>>,
+    /// Serialises demotion and promotion of each tenant.
+    #[cfg(feature = "s3-snapshots")]
+    tier_locks: DashMap<TenantId, Arc<std::sync::Mutex<()>>>,
+    #[cfg(feature = "fault-injection")]
+    faults: Arc<crate::faults::FaultInjector>,
 }
 
 const DEFAULT_FACET_CACHE_CAP: usize = 500;
 
+fn open_namespace_store(base_path: &Path) -> NamespaceStore {
+    match NamespaceStore::open(base_path) {
+        Ok(store) => store,
+        Err(e) => {
+            tracing::warn!(
+                "Failed to load namespaces from {}: {}; namespace limits are not enforced",
+                base_path.display(),
+                e
+            );
+            NamespaceStore::default()
+        }
+    }
+}
+
+fn open_task_map(base_path: &Path) -> TaskMap {
+    match TaskStore::open(base_path, TaskRetention::from_env()) {
+        Ok(store) => TaskMap::persistent(store),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to load task history from {}: {}; task status will not survive restarts",
+                base_path.display(),
+                e
+            );
+            TaskMap::default()
+        }
+    }
+}
+
+/// Whether the index ranking lists `exact` ahead of `typo`. Without
+/// settings the default ranking applies, which puts `typo` first.
+fn exact_before_typo(settings: Option<&IndexSettings>) -> bool {
+    let Some(ranking) = settings.and_then(|s| s.ranking.as_ref()) else {
+        return false;
+    };
+    let position = |criterion: &str| ranking.iter().position(|c| c == criterion);
+    match (position("exact"), position("typo")) {
+        (Some(exact), Some(typo)) => exact < typo,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
 impl IndexManager {
     /// Create a new IndexManager with the given base directory.
     ///
     /// Each tenant's index will be stored in `{base_path}/{tenant_id}/`.
     pub fn new<P: AsRef<Path>>(base_path: P) -> Arc<Self> {
         Arc::new_cyclic(|weak| {
-            let tasks = Arc::new(DashMap::new());
+            let tasks = Arc::new(open_task_map(base_path.as_ref()));
             IndexManager {
                 base_path: base_path.as_ref().to_path_buf(),
                 loaded: DashMap::new(),
@@ -96,20 +165,40 @@ impl IndexManager {
                 write_queues: DashMap::new(),
                 write_task_handles: DashMap::new(),
                 oplogs: DashMap::new(),
+                wals: DashMap::new(),
                 tasks: tasks.clone(),
                 task_queue: TaskQueue::new(weak.clone(), tasks),
                 settings_cache: DashMap::new(),
                 rules_cache: DashMap::new(),
+                blocklist_cache: DashMap::new(),
                 synonyms_cache: DashMap::new(),
+                reranking_cache: DashMap::new(),
+                namespaces: open_namespace_store(base_path.as_ref()),
                 facet_cache: Arc::new(DashMap::new()),
                 facet_cache_cap: std::sync::atomic::AtomicUsize::new(DEFAULT_FACET_CACHE_CAP),
                 lww_map: Arc::new(DashMap::new()),
                 #[cfg(feature = "vector-search")]
                 vector_indices: Arc::new(DashMap::new()),
+                last_searched: DashMap::new(),
+                started_at: std::time::Instant::now(),
+                #[cfg(feature = "s3-snapshots")]
+                cold_storage: std::sync::RwLock::new(None),
+                #[cfg(feature = "s3-snapshots")]
+                segment_cache: std::sync::OnceLock::new(),
+                #[cfg(feature = "s3-snapshots")]
+                tier_locks: DashMap::new(),
+                #[cfg(feature = "fault-injection")]
+                faults: Arc::new(crate::faults::FaultInjector::new()),
             }
         })
     }
 
+    /// This node's fault injector (test builds only).
+    #[cfg(feature = "fault-injection")]
+    pub fn faults(&self) -> &Arc<crate::faults::FaultInjector> {
+        &self.faults
+    }
+
     /// Get the oplog for a tenant (for external access)
     pub fn get_oplog(&self, tenant_id: &str) -> Option<Arc<OpLog>> {
         self.oplogs.get(tenant_id).map(|r| Arc::clone(&r))
@@ -158,15 +247,50 @@ impl IndexManager {
         None
     }
 
+    /// The rules searches apply: the index's own, then those of its
+    /// enabled campaigns.
     pub fn get_rules(&self, tenant_id: &str) -> Option<Arc<RuleStore>> {
         if let Some(cached) = self.rules_cache.get(tenant_id) {
             return Some(Arc::clone(&cached));
         }
         let path = self.base_path.join(tenant_id).join("rules.json");
+        let campaigns_path = self.base_path.join(tenant_id).join(CAMPAIGNS_FILE);
+        if !path.exists() && !campaigns_path.exists() {
+            return None;
+        }
+        let mut store = if path.exists() {
+            RuleStore::load(&path).ok()?
+        } else {
+            RuleStore::new()
+        };
+        if campaigns_path.exists() {
+            match CampaignStore::load(&campaigns_path) {
+                Ok(campaigns) => {
+                    for campaign in campaigns.all() {
+                        for rule in campaign.effective_rules() {
+                            store.insert(rule);
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("[RULES {}] unreadable campaigns: {}", tenant_id, e),
+            }
+        }
+        let arc = Arc::new(store);
+        self.rules_cache
+            .insert(tenant_id.to_string(), Arc::clone(&arc));
+        Some(arc)
+    }
+
+    /// Blocked results of a tenant, if any were saved.
+    pub fn get_blocklist(&self, tenant_id: &str) -> Option<Arc<Blocklist>> {
+        if let Some(cached) = self.blocklist_cache.get(tenant_id) {
+            return Some(Arc::clone(&cached));
+        }
+        let path = self.base_path.join(tenant_id).join(BLOCKLIST_FILE);
         if path.exists() {
-            if let Ok(s) = RuleStore::load(&path) {
-                let arc = Arc::new(s);
-                self.rules_cache
+            if let Ok(b) = Blocklist::load(&path) {
+                let arc = Arc::new(b);
+                self.blocklist_cache
                     .insert(tenant_id.to_string(), Arc::clone(&arc));
                 return Some(arc);
             }
@@ -190,6 +314,35 @@ impl IndexManager {
         None
     }
 
+    /// Trained re-ranking model for a tenant, if one has been written.
+    pub fn get_reranking_model(&self, tenant_id: &str) -> Option<Arc<ReRankingModel>> {
+        if let Some(cached) = self.reranking_cache.get(tenant_id) {
+            return Some(Arc::clone(&cached));
+        }
+        let path = self.base_path.join(tenant_id).join(RERANKING_MODEL_FILE);
+        if path.exists() {
+            if let Ok(m) = ReRankingModel::load(&path) {
+                let arc = Arc::new(m);
+                self.reranking_cache
+                    .insert(tenant_id.to_string(), Arc::clone(&arc));
+                return Some(arc);
+            }
+        }
+        None
+    }
+
+    /// Persist a freshly trained re-ranking model and swap it into the cache.
+    pub fn save_reranking_model(&self, tenant_id: &str, model: ReRankingModel) -> Result<()> {
+        let dir = self.base_path.join(tenant_id);
+        if !dir.exists() {
+            return Err(FlapjackError::TenantNotFound(tenant_id.to_string()));
+        }
+        model.save(dir.join(RERANKING_MODEL_FILE))?;
+        self.reranking_cache
+            .insert(tenant_id.to_string(), Arc::new(model));
+        Ok(())
+    }
+
     pub fn invalidate_settings_cache(&self, tenant_id: &str) {
         self.settings_cache.remove(tenant_id);
     }
@@ -198,17 +351,59 @@ impl IndexManager {
         self.rules_cache.remove(tenant_id);
     }
 
+    pub fn invalidate_blocklist_cache(&self, tenant_id: &str) {
+        self.blocklist_cache.remove(tenant_id);
+    }
+
     pub fn invalidate_synonyms_cache(&self, tenant_id: &str) {
         self.synonyms_cache.remove(tenant_id);
     }
 
+    pub fn invalidate_reranking_cache(&self, tenant_id: &str) {
+        self.reranking_cache.remove(tenant_id);
+    }
+
+    /// Look up a task by id or numeric id. Tasks evicted from memory, or
+    /// from before a restart, are served from the persisted history.
     pub fn get_task(&self, task_id: &str) -> Result<TaskInfo> {
         self.tasks
-            .get(task_id)
-            .map(|task| task.clone())
+            .get_or_load(task_id)
             .ok_or_else(|| FlapjackError::TaskNotFound(task_id.to_string()))
     }
 
+    /// Tasks created at or after `since`, oldest first, optionally limited
+    /// to one index. Includes persisted history as well as live tasks.
+    pub fn list_tasks(
+        &self,
+        since: std::time::SystemTime,
+        index_name: Option<&str>,
+        limit: usize,
+    ) -> Vec<TaskInfo> {
+        let mut by_id: HashMap<String, TaskInfo> = self
+            .tasks
+            .store()
+            .map(|store| store.all())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|t| (t.id.clone(), t))
+            .collect();
+        for entry in self.tasks.iter() {
+            if *entry.key() == entry.value().id {
+                by_id.insert(entry.key().clone(), entry.value().clone());
+            }
+        }
+
+        let prefix = index_name.map(|name| format!("task_{}_", name));
+        let mut tasks: Vec<TaskInfo> = by_id
+            .into_values()
+            .filter(|t| t.created_at >= since)
+            .filter(|t| prefix.as_ref().map_or(true, |p| t.id.starts_with(p)))
+            .collect();
+        tasks.sort_by_key(|t| (t.created_at, t.numeric_id));
+        tasks.truncate(limit);
+        tasks
+    }
+
     /// Count tasks in Enqueued or Processing state for a given tenant.
     pub fn pending_task_count(&self, tenant_id: &str) -> usize {
         let prefix = format!("task_{}_", tenant_id);
@@ -258,7 +453,8 @@ impl IndexManager {
 
         let path = self.base_path.join(tenant_id);
         if path.exists() {
-            let index = Arc::new(Index::open(&path)?);
+            let index = Arc::new(self.open_tenant_index(tenant_id, &path)?);
+            load_text_normalization(&index, &path);
             let _ = index.searchable_paths();
             self.loaded.insert(tenant_id.to_string(), index);
             #[cfg(feature = "vector-search")]
@@ -266,6 +462,7 @@ impl IndexManager {
             return Ok(());
         }
 
+        self.check_namespace_limits(tenant_id, 0)?;
         std::fs::create_dir_all(&path)?;
         let schema = crate::index::schema::Schema::builder().build();
         let index = Arc::new(Index::create(&path, schema)?);
@@ -290,11 +487,11 @@ impl IndexManager {
             return Err(FlapjackError::TenantNotFound(tenant_id.to_string()));
         }
 
-        let index = match Index::open(&path) {
+        let index = match self.open_tenant_index(tenant_id, &path) {
             Ok(idx) => Arc::new(idx),
             Err(e) => {
                 let oplog_dir = path.join("oplog");
-                if oplog_dir.exists() {
+                if oplog_dir.exists() && !self.is_cold(tenant_id) {
                     tracing::warn!("[RECOVERY {}] Index::open failed ({}), but oplog exists — creating fresh index for replay", tenant_id, e);
                     let cs_path = path.join("committed_seq");
                     if cs_path.exists() {
@@ -311,6 +508,7 @@ impl IndexManager {
                 }
             }
         };
+        load_text_normalization(&index, &path);
         self.recover_from_oplog(tenant_id, &index, &path)?;
         #[cfg(feature = "vector-search")]
         self.load_vector_index(tenant_id, &path);
@@ -320,6 +518,85 @@ impl IndexManager {
         Ok(index)
     }
 
+    /// Open the tantivy index in `path`, through the segment cache when the
+    /// tenant is cold.
+    fn open_tenant_index(&self, tenant_id: &str, path: &Path) -> Result<Index> {
+        #[cfg(feature = "s3-snapshots")]
+        if let Some(manifest) = ColdManifest::load(path)? {
+            let directory = ColdDirectory::open(
+                path,
+                tenant_id,
+                manifest,
+                self.cold_storage()?,
+                self.segment_cache()?,
+            )?;
+            return Index::open_in_directory(directory);
+        }
+        #[cfg(not(feature = "s3-snapshots"))]
+        let _ = tenant_id;
+        Index::open(path)
+    }
+
+    /// Whether the tenant's segments are in cold storage.
+    pub fn is_cold(&self, tenant_id: &str) -> bool {
+        #[cfg(feature = "s3-snapshots")]
+        {
+            self.base_path
+                .join(tenant_id)
+                .join(tiered::COLD_MANIFEST)
+                .exists()
+        }
+        #[cfg(not(feature = "s3-snapshots"))]
+        {
+            let _ = tenant_id;
+            false
+        }
+    }
+
+    /// [`get_or_load`](Self::get_or_load) for writing: a cold index is
+    /// promoted back to local disk first. Async callers should
+    /// [`promote_for_write`](Self::promote_for_write) beforehand; a promotion
+    /// left to this point runs in `block_in_place` on a multi-threaded
+    /// runtime, so the worker's other tasks move elsewhere while it downloads.
+    fn load_for_write(&self, tenant_id: &str) -> Result<Arc<Index>> {
+        #[cfg(feature = "s3-snapshots")]
+        if self.is_cold(tenant_id) {
+            match tokio::runtime::Handle::try_current() {
+                Ok(handle)
+                    if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread =>
+                {
+                    tokio::task::block_in_place(|| self.promote_tenant(tenant_id))?
+                }
+                _ => self.promote_tenant(tenant_id)?,
+            }
+        }
+        self.get_or_load(tenant_id)
+    }
+
+    /// Tenants on disk, not yet cold, that have not been searched for
+    /// `idle` — counting from startup for those not searched since.
+    pub fn idle_tenants(&self, idle: std::time::Duration) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(&self.base_path) else {
+            return Vec::new();
+        };
+        let mut tenants: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().join("meta.json").exists())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|tenant| !tenant.starts_with('.') && !self.is_cold(tenant))
+            .filter(|tenant| {
+                let last = self
+                    .last_searched
+                    .get(tenant)
+                    .map(|t| *t)
+                    .unwrap_or(self.started_at);
+                last.elapsed() >= idle
+            })
+            .collect();
+        tenants.sort();
+        tenants
+    }
+
     fn recover_from_oplog(
         &self,
         tenant_id: &str,
@@ -343,6 +620,8 @@ impl IndexManager {
 
         let node_id = std::env::var("FLAPJACK_NODE_ID").unwrap_or_else(|_| "unknown".to_string());
         let oplog = OpLog::open(&oplog_dir, tenant_id, &node_id)?;
+        #[cfg(feature = "fault-injection")]
+        let oplog = oplog.with_faults(Arc::clone(&self.faults));
 
         // P3: Rebuild lww_map from ALL retained oplog entries (read from seq=0).
         // This runs on every startup — crash or normal — so that stale replicated ops
@@ -427,6 +706,11 @@ impl IndexManager {
             );
             None
         };
+        index.set_text_normalization(
+            settings
+                .as_ref()
+                .and_then(crate::tokenizer::TextNormalization::from_settings),
+        );
 
         // Phase 3: Replay document ops
         let mut writer = index.writer()?;
@@ -705,9 +989,42 @@ impl IndexManager {
             None,
             None,
             None,
+            None,
+            None,
         )
     }
 
+    /// Run the stop-word and plural stage of a search on `query_text` with
+    /// the index's settings (or the given overrides) and report what it did.
+    pub fn analyze_query(
+        &self,
+        tenant_id: &str,
+        query_text: &str,
+        remove_stop_words_override: Option<&crate::query::stopwords::RemoveStopWordsValue>,
+        ignore_plurals_override: Option<&crate::query::plurals::IgnorePluralsValue>,
+        query_languages_override: Option<&Vec<String>>,
+        query_type_override: Option<&str>,
+    ) -> Result<crate::query::analysis::QueryAnalysis> {
+        self.get_or_load(tenant_id)?;
+        let settings = self.get_settings(tenant_id);
+        let qt = query_type_override.unwrap_or_else(|| {
+            settings
+                .as_ref()
+                .map(|s| s.query_type.as_str())
+                .unwrap_or("prefixLast")
+        });
+        Ok(crate::query::analysis::analyze_query(
+            query_text,
+            remove_stop_words_override.or(settings.as_ref().map(|s| &s.remove_stop_words)),
+            ignore_plurals_override.or(settings.as_ref().map(|s| &s.ignore_plurals)),
+            query_languages_override
+                .map(|v| v.as_slice())
+                .or(settings.as_ref().map(|s| s.query_languages.as_slice()))
+                .unwrap_or(&[]),
+            qt,
+        ))
+    }
+
     pub fn search_full_with_stop_words(
         &self,
         tenant_id: &str,
@@ -731,9 +1048,37 @@ impl IndexManager {
         enable_rules: Option<bool>,
         rule_contexts: Option<&[String]>,
         restrict_searchable_attrs: Option<&[String]>,
+        exhaustive_nb_hits_override: Option<bool>,
+        attribute_weights_override: Option<&HashMap<String, f32>>,
     ) -> Result<SearchResult> {
         let t0 = std::time::Instant::now();
+        // Filter-only queries stop counting at the budget and report an
+        // estimated total, unless the caller asks for an exact count.
+        let count_budget = if exhaustive_nb_hits_override == Some(true) {
+            None
+        } else {
+            crate::query::executor::count::count_budget()
+        };
+        let mut exhaustive_nb_hits = true;
         let index = self.get_or_load(tenant_id)?;
+        match self.last_searched.get_mut(tenant_id) {
+            Some(mut last) => *last = t0,
+            None => {
+                self.last_searched.insert(tenant_id.to_string(), t0);
+            }
+        }
+        let live_filter = crate::index::visibility::live_filter(
+            &self.base_path.join(tenant_id),
+            filter,
+            chrono::Utc::now().timestamp(),
+        );
+        let filter = live_filter.as_ref().or(filter);
+        // Blocked results are decided on the query as typed, before stop
+        // words or rules rewrite it.
+        let blocked = self
+            .get_blocklist(tenant_id)
+            .map(|b| b.blocked(query_text))
+            .filter(|b| !b.is_empty());
         let t1 = t0.elapsed();
         let reader = index.reader();
         let t2 = t0.elapsed();
@@ -743,11 +1088,18 @@ impl IndexManager {
         if let Some(ref s) = settings {
             tracing::debug!("[SEARCH] Loaded settings query_type={}", s.query_type);
         }
+        let mut attribute_weights = settings
+            .as_ref()
+            .map(|s| s.attribute_weights.clone())
+            .unwrap_or_default();
+        if let Some(overrides) = attribute_weights_override {
+            attribute_weights.extend(overrides.iter().map(|(k, v)| (k.clone(), *v)));
+        }
         let relevance_config = RelevanceConfig {
             searchable_attributes: settings
                 .as_ref()
                 .and_then(|s| s.searchable_attributes.clone()),
-            attribute_weights: HashMap::new(),
+            attribute_weights,
         };
 
         let qt = query_type_override.unwrap_or_else(|| {
@@ -756,46 +1108,35 @@ impl IndexManager {
                 .map(|s| s.query_type.as_str())
                 .unwrap_or("prefixLast")
         });
-        let effective_stop_words =
-            remove_stop_words_override.or(settings.as_ref().map(|s| &s.remove_stop_words));
-        let query_text_stopped = match effective_stop_words {
-            Some(sw) => crate::query::stopwords::remove_stop_words(query_text, sw, qt),
-            None => query_text.to_string(),
+        // With advancedSyntax, quoted phrases and -exclusions are matched
+        // exactly: stop words, synonyms and split/concat alternatives only
+        // rewrite the plain words around them.
+        let adv_syntax = advanced_syntax_override.unwrap_or(false);
+        let advanced = |text: &str| {
+            Some(AdvancedSyntaxQuery::parse(text)).filter(|q| adv_syntax && q.has_operators())
         };
-        let plural_map: Option<std::collections::HashMap<String, Vec<String>>> = {
-            let effective_ignore_plurals =
-                ignore_plurals_override.or(settings.as_ref().map(|s| &s.ignore_plurals));
-            let effective_query_languages = query_languages_override
-                .map(|v| v.as_slice())
-                .or(settings.as_ref().map(|s| s.query_languages.as_slice()))
-                .unwrap_or(&[]);
-            match effective_ignore_plurals {
-                Some(ip) if *ip != crate::query::plurals::IgnorePluralsValue::Disabled => {
-                    let langs = crate::query::plurals::resolve_plural_languages(
-                        ip,
-                        effective_query_languages,
-                    );
-                    if crate::query::plurals::should_expand_english(&langs) {
-                        let words: Vec<&str> = query_text_stopped.split_whitespace().collect();
-                        let mut map = std::collections::HashMap::new();
-                        for w in words {
-                            let lower = w.to_lowercase();
-                            let forms = crate::query::plurals::expand_plurals(&lower);
-                            if forms.len() > 1 {
-                                map.insert(lower, forms);
-                            }
-                        }
-                        if map.is_empty() {
-                            None
-                        } else {
-                            Some(map)
-                        }
-                    } else {
-                        None
-                    }
-                }
-                _ => None,
-            }
+        let query_advanced = advanced(query_text);
+        let query_languages = query_languages_override
+            .map(|v| v.as_slice())
+            .or(settings.as_ref().map(|s| s.query_languages.as_slice()))
+            .unwrap_or(&[]);
+        let analysis = crate::query::analysis::analyze_query(
+            query_advanced
+                .as_ref()
+                .map_or(query_text, |q| q.remaining.as_str()),
+            remove_stop_words_override.or(settings.as_ref().map(|s| &s.remove_stop_words)),
+            ignore_plurals_override.or(settings.as_ref().map(|s| &s.ignore_plurals)),
+            query_languages,
+            qt,
+        );
+        let plural_map: Option<HashMap<String, Vec<String>>> = if analysis.plurals.is_empty() {
+            None
+        } else {
+            Some(analysis.plurals.into_iter().collect())
+        };
+        let query_text_stopped = match &query_advanced {
+            Some(q) => q.with_remaining(&analysis.query_after_stop_words),
+            None => analysis.query_after_stop_words,
         };
         let query_text = &query_text_stopped;
 
@@ -814,15 +1155,37 @@ impl IndexManager {
         } else {
             (query_text.to_string(), None)
         };
-        let synonyms_enabled = enable_synonyms.unwrap_or(true);
-        let expanded_queries = if synonyms_enabled {
-            if let Some(store) = self.get_synonyms(tenant_id) {
-                store.expand_query(&query_text_rewritten)
-            } else {
-                vec![query_text_rewritten.clone()]
+        let rule_effects = match &blocked {
+            Some(blocked) => {
+                let mut effects = rule_effects.unwrap_or_default();
+                blocked.restrict(&mut effects);
+                Some(effects)
             }
-        } else {
-            vec![query_text_rewritten.clone()]
+            None => rule_effects,
+        };
+        let synonyms_enabled = enable_synonyms.unwrap_or(true);
+        let (expanded_queries, synonym_sources): (Vec<String>, Vec<Option<String>>) =
+            match self.get_synonyms(tenant_id).filter(|_| synonyms_enabled) {
+                Some(store) => match advanced(&query_text_rewritten) {
+                    Some(q) => store
+                        .expand_query_with_sources(&q.remaining)
+                        .into_iter()
+                        .map(|(expanded, source)| (q.with_remaining(&expanded), source))
+                        .unzip(),
+                    None => store
+                        .expand_query_with_sources(&query_text_rewritten)
+                        .into_iter()
+                        .unzip(),
+                },
+                None => (vec![query_text_rewritten.clone()], vec![None]),
+            };
+        // Query words must go through the same normalization as indexed text.
+        let expanded_queries: Vec<String> = match index.text_normalization() {
+            Some(normalization) => expanded_queries
+                .iter()
+                .map(|q| normalization.normalize_query(q))
+                .collect(),
+            None => expanded_queries,
         };
         let schema = index.inner().schema();
 
@@ -838,15 +1201,20 @@ impl IndexManager {
 
         let (searchable_paths, field_weights): (Vec<String>, Vec<f32>) =
             match &relevance_config.searchable_attributes {
-                Some(attrs) => {
-                    let _attr_set: std::collections::HashSet<&str> =
-                        attrs.iter().map(|s| s.as_str()).collect();
+                Some(_) => {
                     let mut weighted: Vec<(String, f32)> = Vec::new();
                     let mut unweighted: Vec<String> = Vec::new();
 
+                    // Earlier `searchableAttributes` entries weigh 100x more;
+                    // comma-grouped attributes share a level. `attributeWeights`
+                    // replaces the weight of a level's attributes.
+                    let levels = relevance_config.searchable_levels();
                     for path in &all_searchable_paths {
-                        if let Some(pos) = attrs.iter().position(|a| a == path) {
-                            weighted.push((path.clone(), 100_f32.powi(-(pos as i32))));
+                        if let Some(level) = levels.iter().position(|a| a.covers(path)) {
+                            let weight = relevance_config
+                                .weight_override(path)
+                                .unwrap_or_else(|| 100_f32.powi(-(level as i32)));
+                            weighted.push((path.clone(), weight));
                         } else {
                             unweighted.push(path.clone());
                         }
@@ -865,7 +1233,10 @@ impl IndexManager {
                     (paths, weights)
                 }
                 None => {
-                    let weights = vec![1.0; all_searchable_paths.len()];
+                    let weights = all_searchable_paths
+                        .iter()
+                        .map(|path| relevance_config.weight_override(path).unwrap_or(1.0))
+                        .collect();
                     (all_searchable_paths.clone(), weights)
                 }
             };
@@ -888,6 +1259,15 @@ impl IndexManager {
         } else {
             (searchable_paths, field_weights)
         };
+        let (searchable_paths, field_weights) = crate::index::localized::restrict_paths(
+            searchable_paths,
+            field_weights,
+            settings
+                .as_ref()
+                .map(|s| s.localized_attributes.as_slice())
+                .unwrap_or(&[]),
+            query_languages,
+        );
 
         let json_exact_field = schema
             .get_field("_json_exact")
@@ -907,12 +1287,12 @@ impl IndexManager {
         };
         let effective_sort: Option<&Sort> = sort.or(default_sort_owned.as_ref());
 
-        let typo_enabled = typo_tolerance_override.unwrap_or(true);
+        let typo_enabled = typo_tolerance_override
+            .unwrap_or_else(|| settings.as_ref().is_none_or(|s| s.typo_tolerance_enabled()));
         let min_word_1_typo = settings
             .as_ref()
             .map(|s| s.min_word_size_for_1_typo as usize)
             .unwrap_or(4);
-        let adv_syntax = advanced_syntax_override.unwrap_or(false);
 
         let parser = QueryParser::new_with_weights(
             &schema,
@@ -1006,6 +1386,11 @@ impl IndexManager {
                             );
                         }
                         (count, facets_map)
+                    } else if let Some(budget) = count_budget {
+                        let scan =
+                            executor.budgeted_scan(&searcher, final_query.as_ref(), budget, 0)?;
+                        exhaustive_nb_hits = scan.exhaustive;
+                        (scan.total, HashMap::new())
                     } else {
                         let count =
                             searcher.search(final_query.as_ref(), &tantivy::collector::Count)?;
@@ -1019,12 +1404,22 @@ impl IndexManager {
                 facets: facets_map,
                 user_data: Vec::new(),
                 applied_rules: Vec::new(),
+                rendering_content: None,
+                exhaustive_nb_hits,
+                synonyms_triggered: Vec::new(),
+                synonyms_matched: Vec::new(),
             });
         }
 
         let mut all_results = Vec::new();
         let mut seen_ids = HashSet::new();
+        // Queries from this index on are split/concat alternatives, and the
+        // documents only they match rank like one-typo matches.
+        let mut first_alternative_idx = usize::MAX;
+        let mut alternative_ids: HashSet<String> = HashSet::new();
         let mut query_totals: Vec<usize> = Vec::new();
+        let mut synonyms_triggered: Vec<String> = Vec::new();
+        let mut synonyms_matched: Vec<String> = Vec::new();
 
         let effective_limit = limit + offset;
         let mut split_alternatives_generated = false;
@@ -1042,7 +1437,8 @@ impl IndexManager {
             let executor = QueryExecutor::new(index.converter(), schema.clone())
                 .with_settings(settings.clone())
                 .with_query(expanded_query.clone())
-                .with_max_values_per_facet(max_values_per_facet);
+                .with_max_values_per_facet(max_values_per_facet)
+                .with_count_budget(count_budget);
 
             let expanded_parsed =
                 executor.expand_short_query_with_searcher(parsed_query, &searcher)?;
@@ -1117,12 +1513,25 @@ impl IndexManager {
 
             // Track total from this query for final total calculation
             query_totals.push(result.total);
+            exhaustive_nb_hits &= result.exhaustive_nb_hits;
 
+            let matched_before = all_results.len();
             for doc in result.documents {
                 if seen_ids.insert(doc.document.id.clone()) {
+                    if query_idx >= first_alternative_idx {
+                        alternative_ids.insert(doc.document.id.clone());
+                    }
                     all_results.push(doc);
                 }
             }
+            if let Some(Some(object_id)) = synonym_sources.get(query_idx) {
+                if !synonyms_triggered.contains(object_id) {
+                    synonyms_triggered.push(object_id.clone());
+                }
+                if all_results.len() > matched_before && !synonyms_matched.contains(object_id) {
+                    synonyms_matched.push(object_id.clone());
+                }
+            }
 
             query_idx += 1;
 
@@ -1135,18 +1544,26 @@ impl IndexManager {
             // (original + synonyms) didn't produce enough results.
             if query_idx == expanded_queries.len()
                 && !split_alternatives_generated
+                && typo_enabled
                 && !query_text.trim().is_empty()
                 && all_results.len() < effective_limit
             {
                 split_alternatives_generated = true;
+                first_alternative_idx = expanded_queries.len();
                 let base_queries = expanded_queries.clone();
                 for eq in &base_queries {
+                    let eq_advanced = advanced(eq);
                     let alts = crate::query::splitting::generate_alternatives(
-                        eq,
+                        eq_advanced.as_ref().map_or(eq, |q| &q.remaining),
                         &searcher,
                         json_exact_field,
                         &searchable_paths,
+                        min_word_1_typo,
                     );
+                    let alts = alts.into_iter().map(|alt| match &eq_advanced {
+                        Some(q) => q.with_remaining(&alt),
+                        None => alt,
+                    });
                     for alt in alts {
                         if !expanded_queries.contains(&alt) {
                             expanded_queries.push(alt);
@@ -1159,11 +1576,53 @@ impl IndexManager {
             }
         }
 
-        all_results.sort_by(|a, b| {
-            b.score
-                .partial_cmp(&a.score)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
+        let exact_criterion = self.exact_criterion(
+            tenant_id,
+            settings.as_deref(),
+            &query_text_rewritten,
+            &searchable_paths,
+            plural_map.as_ref(),
+            synonyms_enabled,
+        );
+        // Split/concat-only matches count as typos and sort after direct
+        // matches; `typo` and `exact` apply in the order the index ranking
+        // lists them, and the relevance score breaks the remaining ties.
+        let typo_rank = |doc: &ScoredDocument| {
+            effective_sort.is_none() && alternative_ids.contains(&doc.document.id)
+        };
+        match exact_criterion {
+            Some(criterion) if effective_sort.is_none() => {
+                let exact_first = exact_before_typo(settings.as_deref());
+                let mut keyed: Vec<(bool, usize, ScoredDocument)> = all_results
+                    .into_iter()
+                    .map(|doc| (typo_rank(&doc), criterion.count(&doc.document), doc))
+                    .collect();
+                keyed.sort_by(|a, b| {
+                    let typo = a.0.cmp(&b.0);
+                    let exact = b.1.cmp(&a.1);
+                    let ranked = if exact_first {
+                        exact.then(typo)
+                    } else {
+                        typo.then(exact)
+                    };
+                    ranked.then_with(|| {
+                        b.2.score
+                            .partial_cmp(&a.2.score)
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    })
+                });
+                all_results = keyed.into_iter().map(|(_, _, doc)| doc).collect();
+            }
+            _ => {
+                all_results.sort_by(|a, b| {
+                    typo_rank(a).cmp(&typo_rank(b)).then_with(|| {
+                        b.score
+                            .partial_cmp(&a.score)
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    })
+                });
+            }
+        }
 
         let result_count = all_results.len();
 
@@ -1187,29 +1646,31 @@ impl IndexManager {
         let end = (start + limit).min(result_count);
         let page_results = all_results[start..end].to_vec();
 
-        let (final_docs, user_data, applied_rules) = if let Some(ref effects) = rule_effects {
-            // Apply rules (pins/hides) after synonym expansion so they
-            // operate on the full merged result set.
-            let executor = QueryExecutor::new(index.converter(), schema.clone())
-                .with_settings(settings.clone())
-                .with_query(query_text.to_string())
-                .with_max_values_per_facet(max_values_per_facet);
-            let docs = executor.apply_rules_to_results(&searcher, page_results, effects)?;
-            // Adjust total for hidden docs that matched the query
-            let hidden_count = effects
-                .hidden
-                .iter()
-                .filter(|id| all_results.iter().any(|d| &d.document.id == *id))
-                .count();
-            total = total.saturating_sub(hidden_count);
-            (
-                docs,
-                effects.user_data.clone(),
-                effects.applied_rules.clone(),
-            )
-        } else {
-            (page_results, Vec::new(), Vec::new())
-        };
+        let (final_docs, user_data, applied_rules, rendering_content) =
+            if let Some(ref effects) = rule_effects {
+                // Apply rules (pins/hides) after synonym expansion so they
+                // operate on the full merged result set.
+                let executor = QueryExecutor::new(index.converter(), schema.clone())
+                    .with_settings(settings.clone())
+                    .with_query(query_text.to_string())
+                    .with_max_values_per_facet(max_values_per_facet);
+                let docs = executor.apply_rules_to_results(&searcher, page_results, effects)?;
+                // Adjust total for hidden docs that matched the query
+                let hidden_count = effects
+                    .hidden
+                    .iter()
+                    .filter(|id| all_results.iter().any(|d| &d.document.id == *id))
+                    .count();
+                total = total.saturating_sub(hidden_count);
+                (
+                    docs,
+                    effects.user_data.clone(),
+                    effects.applied_rules.clone(),
+                    effects.rendering_content.clone(),
+                )
+            } else {
+                (page_results, Vec::new(), Vec::new(), None)
+            };
 
         // removeWordsIfNoResults: retry with fewer words if we got 0 results
         let remove_strategy = remove_words_override
@@ -1257,6 +1718,8 @@ impl IndexManager {
                         enable_rules,
                         rule_contexts,
                         restrict_searchable_attrs,
+                        exhaustive_nb_hits_override,
+                        attribute_weights_override,
                     ) {
                         if retry.total > 0 {
                             return Ok(retry);
@@ -1277,9 +1740,109 @@ impl IndexManager {
             facets: facets_map,
             user_data,
             applied_rules,
+            rendering_content,
+            exhaustive_nb_hits,
+            synonyms_triggered,
+            synonyms_matched,
         })
     }
 
+    /// Build the `exact` ranking criterion for a query, or `None` when the
+    /// index ranking omits `exact` or the criterion would not discriminate.
+    /// Plurals and synonyms are accepted as exact per `alternativesAsExact`.
+    fn exact_criterion(
+        &self,
+        tenant_id: &str,
+        settings: Option<&IndexSettings>,
+        query_text: &str,
+        searchable_paths: &[String],
+        plural_map: Option<&HashMap<String, Vec<String>>>,
+        synonyms_enabled: bool,
+    ) -> Option<crate::query::exact::ExactCriterion> {
+        use crate::query::exact::{ExactCriterion, SingleWordQueryMode};
+
+        let defaults;
+        let settings = match settings {
+            Some(s) => s,
+            None => {
+                defaults = IndexSettings::default();
+                &defaults
+            }
+        };
+        let ranks_exact = settings
+            .ranking
+            .as_ref()
+            .is_none_or(|r| r.iter().any(|c| c == "exact"));
+        if !ranks_exact {
+            return None;
+        }
+
+        let mut criterion = ExactCriterion::new(
+            query_text,
+            SingleWordQueryMode::parse(&settings.exact_on_single_word_query),
+            searchable_paths.to_vec(),
+        );
+        if !criterion.is_active() {
+            return None;
+        }
+
+        let allows = |alt: &str| settings.alternatives_as_exact.iter().any(|a| a == alt);
+        let synonyms =
+            if synonyms_enabled && (allows("singleWordSynonym") || allows("multiWordsSynonym")) {
+                self.get_synonyms(tenant_id)
+            } else {
+                None
+            };
+        let words = criterion.words().to_vec();
+        for word in &words {
+            if allows("ignorePlurals") {
+                if let Some(forms) = plural_map.and_then(|m| m.get(word)) {
+                    criterion = criterion.with_alternatives(word, forms);
+                }
+            }
+            if let Some(store) = &synonyms {
+                let accepted: Vec<String> = store
+                    .alternatives_for(word)
+                    .into_iter()
+                    .filter(|alt| {
+                        if alt.contains(char::is_whitespace) {
+                            allows("multiWordsSynonym")
+                        } else {
+                            allows("singleWordSynonym")
+                        }
+                    })
+                    .collect();
+                criterion = criterion.with_alternatives(word, accepted);
+            }
+        }
+        Some(criterion)
+    }
+
+    /// "Did you mean" spelling suggestions for `query_text`, built from the
+    /// tenant's term dictionary (edit distance, then term frequency).
+    pub fn suggest_spellings(
+        &self,
+        tenant_id: &str,
+        query_text: &str,
+        max_suggestions: usize,
+    ) -> Result<Vec<String>> {
+        let index = self.get_or_load(tenant_id)?;
+        let searcher = index.reader().searcher();
+        let json_exact_field = index
+            .inner()
+            .schema()
+            .get_field("_json_exact")
+            .map_err(|_| FlapjackError::FieldNotFound("_json_exact".to_string()))?;
+        let searchable_paths = index.searchable_paths();
+        Ok(crate::query::spelling::suggest(
+            query_text,
+            &searcher,
+            json_exact_field,
+            &searchable_paths,
+            max_suggestions,
+        ))
+    }
+
     /// Get or create a write queue for the given tenant.
     ///
     /// DRY helper — all write paths (add, delete, compact) go through this.
@@ -1303,6 +1866,7 @@ impl IndexManager {
                     Arc::clone(&self.facet_cache),
                     Arc::clone(&self.lww_map),
                     vector_ctx,
+                    self.get_or_create_wal(tenant_id),
                 );
                 self.write_task_handles
                     .insert(tenant_id.to_string(), handle);
@@ -1341,7 +1905,11 @@ impl IndexManager {
         upsert: bool,
         no_lww_update: bool,
     ) -> Result<TaskInfo> {
-        let index = self.get_or_load(tenant_id)?;
+        let index = self.load_for_write(tenant_id)?;
+        // Replicated writes were already admitted on the node that took them.
+        if !no_lww_update {
+            self.check_namespace_limits(tenant_id, docs.len())?;
+        }
 
         let numeric_id = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -1367,25 +1935,21 @@ impl IndexManager {
         } else {
             docs.into_iter().map(WriteAction::Add).collect()
         };
-        if tx
-            .try_send(WriteOp {
+        self.enqueue_write(
+            tenant_id,
+            &tx,
+            WriteOp {
                 task_id: task_id.clone(),
                 actions,
-            })
-            .is_err()
-        {
-            self.tasks.alter(&task_id, |_, mut t| {
-                t.status = TaskStatus::Failed("Queue full".to_string());
-                t
-            });
-            return Err(FlapjackError::QueueFull);
-        }
+            },
+            numeric_id,
+        )?;
 
         Ok(task)
     }
 
     pub fn delete_documents(&self, tenant_id: &str, object_ids: Vec<String>) -> Result<TaskInfo> {
-        let index = self.get_or_load(tenant_id)?;
+        let index = self.load_for_write(tenant_id)?;
 
         let numeric_id = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -1401,19 +1965,15 @@ impl IndexManager {
         let tx = self.get_or_create_write_queue(tenant_id, &index);
 
         let actions = object_ids.into_iter().map(WriteAction::Delete).collect();
-        if tx
-            .try_send(WriteOp {
+        self.enqueue_write(
+            tenant_id,
+            &tx,
+            WriteOp {
                 task_id: task_id.clone(),
                 actions,
-            })
-            .is_err()
-        {
-            self.tasks.alter(&task_id, |_, mut t| {
-                t.status = TaskStatus::Failed("Queue full".to_string());
-                t
-            });
-            return Err(FlapjackError::QueueFull);
-        }
+            },
+            numeric_id,
+        )?;
 
         Ok(task)
     }
@@ -1425,7 +1985,7 @@ impl IndexManager {
         tenant_id: &str,
         object_ids: Vec<String>,
     ) -> Result<TaskInfo> {
-        let index = self.get_or_load(tenant_id)?;
+        let index = self.load_for_write(tenant_id)?;
 
         let numeric_id = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -1444,21 +2004,81 @@ impl IndexManager {
             .into_iter()
             .map(WriteAction::DeleteNoLwwUpdate)
             .collect();
-        if tx
-            .try_send(WriteOp {
+        self.enqueue_write(
+            tenant_id,
+            &tx,
+            WriteOp {
                 task_id: task_id.clone(),
                 actions,
-            })
-            .is_err()
-        {
+            },
+            numeric_id,
+        )?;
+
+        Ok(task)
+    }
+
+    /// Log a document write to the tenant's WAL and hand it to the write
+    /// queue. If the queue is full the write is rejected and its WAL record
+    /// retired, so it is not replayed later.
+    fn enqueue_write(
+        &self,
+        tenant_id: &str,
+        tx: &WriteQueue,
+        op: WriteOp,
+        numeric_id: i64,
+    ) -> Result<()> {
+        let task_id = op.task_id.clone();
+        let wal = self.get_or_create_wal(tenant_id);
+        if let Some(ref wal) = wal {
+            if let Err(e) = wal.append(&op, numeric_id) {
+                self.tasks.alter(&task_id, |_, mut t| {
+                    t.status = TaskStatus::Failed(format!("WAL append failed: {}", e));
+                    t
+                });
+                return Err(e);
+            }
+        }
+        if tx.try_send(op).is_err() {
+            if let Some(ref wal) = wal {
+                wal.mark_done(&task_id);
+            }
             self.tasks.alter(&task_id, |_, mut t| {
                 t.status = TaskStatus::Failed("Queue full".to_string());
                 t
             });
             return Err(FlapjackError::QueueFull);
         }
+        Ok(())
+    }
 
-        Ok(task)
+    /// Re-queue writes that were acknowledged before a crash but never
+    /// committed, keeping their original task IDs. Returns how many writes
+    /// were replayed.
+    pub async fn replay_wal(&self, tenant_id: &str) -> Result<usize> {
+        if !self.base_path.join(tenant_id).join(WAL_FILE).exists() {
+            return Ok(0);
+        }
+        let Some(wal) = self.get_or_create_wal(tenant_id) else {
+            return Ok(0);
+        };
+        let pending = wal.pending()?;
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        let index = self.load_for_write(tenant_id)?;
+        let tx = self.get_or_create_write_queue(tenant_id, &index);
+        let count = pending.len();
+        for write in pending {
+            let task = TaskInfo::new(write.task_id.clone(), write.numeric_id, write.actions.len());
+            self.tasks.insert(task.id.clone(), task.clone());
+            self.tasks.insert(write.numeric_id.to_string(), task);
+            tx.send(write.into_write_op())
+                .await
+                .map_err(|_| FlapjackError::QueueFull)?;
+        }
+        tracing::info!("[WAL {}] replayed {} uncommitted writes", tenant_id, count);
+        Ok(count)
     }
 
     /// Compact an index by merging all segments and garbage-collecting stale files.
@@ -1466,7 +2086,12 @@ impl IndexManager {
     /// This reclaims disk space from deleted documents. The operation is
     /// enqueued on the write queue so it serialises with other writes.
     pub fn compact_index(&self, tenant_id: &str) -> Result<TaskInfo> {
-        let index = self.get_or_load(tenant_id)?;
+        self.compact_index_with(tenant_id, CompactOptions::default())
+    }
+
+    /// [`compact_index`](Self::compact_index) with batched, throttled merges.
+    pub fn compact_index_with(&self, tenant_id: &str, options: CompactOptions) -> Result<TaskInfo> {
+        let index = self.load_for_write(tenant_id)?;
 
         let numeric_id = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -1482,7 +2107,7 @@ impl IndexManager {
         if tx
             .try_send(WriteOp {
                 task_id: task_id.clone(),
-                actions: vec![WriteAction::Compact],
+                actions: vec![WriteAction::Compact(options)],
             })
             .is_err()
         {
@@ -1496,6 +2121,65 @@ impl IndexManager {
         Ok(task)
     }
 
+    /// Deleted documents, segments and size of a loaded tenant's index, for
+    /// the compaction scheduler. `None` if the tenant is not loaded.
+    pub fn fragmentation(&self, tenant_id: &str) -> Option<Fragmentation> {
+        let index = self.loaded.get(tenant_id)?;
+        let searcher = index.reader().searcher();
+        let readers = searcher.segment_readers();
+        Some(Fragmentation {
+            documents: searcher.num_docs(),
+            deleted_documents: readers.iter().map(|r| r.num_deleted_docs() as u64).sum(),
+            segments: readers.len(),
+            size_bytes: self.tenant_storage_bytes(tenant_id),
+        })
+    }
+
+    /// When a loaded tenant's index was last compacted.
+    pub fn last_compaction(&self, tenant_id: &str) -> Option<std::time::SystemTime> {
+        self.loaded.get(tenant_id)?.last_compaction()
+    }
+
+    /// Document counts, disk usage by component and document shape of a
+    /// tenant's index.
+    pub fn index_stats(&self, tenant_id: &str) -> Result<crate::index::stats::IndexStats> {
+        let index = self.get_or_load(tenant_id)?;
+        crate::index::stats::index_stats(&index, &self.base_path.join(tenant_id))
+    }
+
+    /// Clusters of duplicate records, from the fingerprints stored at
+    /// indexing time under the index's `duplicateDetection` setting. Records
+    /// hidden from search (scheduled, unpublished) are included.
+    pub fn duplicate_report(
+        &self,
+        tenant_id: &str,
+    ) -> Result<crate::index::duplicates::DuplicateReport> {
+        use crate::index::duplicates::{DuplicateReport, Fingerprint};
+
+        let detection = self
+            .get_settings(tenant_id)
+            .and_then(|s| s.duplicate_detection.clone())
+            .ok_or_else(|| {
+                FlapjackError::InvalidQuery(format!(
+                    "duplicateDetection is not enabled on index '{}'",
+                    tenant_id
+                ))
+            })?;
+        let mut records = Vec::new();
+        for doc in self.get_or_load(tenant_id)?.stored_documents() {
+            let doc = doc?;
+            if let Some(fingerprint) = Fingerprint::from_document(&doc) {
+                records.push((doc.id, fingerprint));
+            }
+        }
+        let clusters = detection.clusters(&records);
+        Ok(DuplicateReport {
+            nb_records: records.len(),
+            nb_clusters: clusters.len(),
+            clusters,
+        })
+    }
+
     /// Compact an index and wait for the operation to complete.
     pub async fn compact_index_sync(&self, tenant_id: &str) -> Result<()> {
         let task = self.compact_index(tenant_id)?;
@@ -1521,10 +2205,13 @@ impl IndexManager {
         self.write_queues.remove(tenant_id);
         self.writers.remove(tenant_id);
         self.oplogs.remove(tenant_id);
+        self.wals.remove(tenant_id);
         self.loaded.remove(tenant_id);
         self.settings_cache.remove(tenant_id);
         self.rules_cache.remove(tenant_id);
+        self.blocklist_cache.remove(tenant_id);
         self.synonyms_cache.remove(tenant_id);
+        self.reranking_cache.remove(tenant_id);
         Ok(())
     }
 
@@ -1538,12 +2225,27 @@ impl IndexManager {
 
         self.writers.remove(tenant_id);
         self.oplogs.remove(tenant_id);
+        self.wals.remove(tenant_id);
         self.loaded.remove(tenant_id);
         self.settings_cache.remove(tenant_id);
         self.rules_cache.remove(tenant_id);
+        self.blocklist_cache.remove(tenant_id);
         self.synonyms_cache.remove(tenant_id);
+        self.reranking_cache.remove(tenant_id);
 
         let path = self.base_path.join(tenant_id);
+        #[cfg(feature = "s3-snapshots")]
+        if let (Ok(Some(manifest)), Ok(storage)) = (ColdManifest::load(&path), self.cold_storage())
+        {
+            let tenant = tenant_id.to_string();
+            let _ = tokio::task::spawn_blocking(move || {
+                tiered::delete_objects(&tenant, &manifest, storage.as_ref())
+            })
+            .await;
+            if let Ok(cache) = self.segment_cache() {
+                cache.remove_prefix(&tiered::object_key(tenant_id, ""));
+            }
+        }
         if path.exists() {
             // Retry remove_dir_all to handle Tantivy merge threads that may still
             // be writing segment files after the IndexWriter is dropped. The drop
@@ -1834,6 +2536,241 @@ impl IndexManager {
         self.make_noop_task(destination)
     }
 
+    /// Copy settings, synonyms and rules into `destination`, then a
+    /// deterministic `percent` sample of the source documents, including
+    /// those hidden from search (scheduled, unpublished).
+    ///
+    /// A document is kept when a stable hash of its objectID falls below the
+    /// threshold, so repeated clones of the same data select the same subset.
+    /// Returns the task and the number of documents copied.
+    pub async fn clone_index_sampled(
+        &self,
+        source: &str,
+        destination: &str,
+        percent: f64,
+    ) -> Result<(TaskInfo, usize)> {
+        if !self.base_path.join(source).exists() {
+            return Err(FlapjackError::TenantNotFound(source.to_string()));
+        }
+        let scopes = ["settings", "synonyms", "rules"].map(String::from);
+        self.copy_index(source, destination, Some(&scopes)).await?;
+
+        const BATCH: usize = 1000;
+        let threshold = (percent.clamp(0.0, 100.0) * 100.0).round() as u64;
+        let mut copied = 0;
+        let mut batch = Vec::with_capacity(BATCH);
+        for doc in self.get_or_load(source)?.stored_documents() {
+            let doc = doc?;
+            if sample_bucket(&doc.id) < threshold {
+                batch.push(doc);
+            }
+            if batch.len() == BATCH {
+                copied += batch.len();
+                self.add_documents_sync(destination, std::mem::take(&mut batch))
+                    .await?;
+            }
+        }
+        if !batch.is_empty() {
+            copied += batch.len();
+            self.add_documents_sync(destination, batch).await?;
+        }
+
+        Ok((self.make_noop_task(destination)?, copied))
+    }
+
+    /// Rewrite every document of an index so it is re-tokenized with the
+    /// current settings, e.g. after a text-normalization change. Returns the
+    /// number of documents rewritten.
+    pub async fn reindex_documents(&self, tenant_id: &str) -> Result<usize> {
+        self.reindex_documents_with_progress(tenant_id, |_, _| {})
+            .await
+    }
+
+    /// [`reindex_documents`](Self::reindex_documents), calling `on_progress`
+    /// with the documents rewritten so far and the total after each batch.
+    pub async fn reindex_documents_with_progress(
+        &self,
+        tenant_id: &str,
+        on_progress: impl Fn(usize, usize),
+    ) -> Result<usize> {
+        const PAGE: usize = 1000;
+        // Collect IDs from the segment stores up front, so documents hidden
+        // from search are rewritten too, then re-read each batch so writes
+        // made meanwhile are not reverted.
+        let ids = self
+            .get_or_load(tenant_id)?
+            .stored_documents()
+            .map(|doc| doc.map(|doc| doc.id))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut rewritten = 0;
+        for chunk in ids.chunks(PAGE) {
+            let mut docs = Vec::with_capacity(chunk.len());
+            for id in chunk {
+                if let Some(doc) = self.get_document(tenant_id, id)? {
+                    docs.push(doc);
+                }
+            }
+            rewritten += docs.len();
+            self.add_documents_sync(tenant_id, docs).await?;
+            on_progress(rewritten, ids.len());
+        }
+        Ok(rewritten)
+    }
+
+    /// Re-index a tenant in the background, e.g. after a settings change
+    /// that is applied at indexing time. The returned task reports the
+    /// documents rewritten so far as its progress.
+    pub fn spawn_reindex(self: &Arc<Self>, tenant_id: &str) -> TaskInfo {
+        let task = self.start_task(format!("task_{}_{}", tenant_id, uuid::Uuid::new_v4()));
+        let manager = Arc::clone(self);
+        let (tenant, task_id) = (tenant_id.to_string(), task.id.clone());
+        tokio::spawn(async move {
+            let result = manager
+                .reindex_documents_with_progress(&tenant, |completed, total| {
+                    manager.update_task(&task_id, |t| {
+                        t.progress = Some(TaskProgress { completed, total });
+                    });
+                })
+                .await;
+            let status = match result {
+                Ok(count) => {
+                    tracing::info!("[REINDEX {}] rewrote {} documents", tenant, count);
+                    TaskStatus::Succeeded
+                }
+                Err(e) => {
+                    tracing::error!("[REINDEX {}] failed: {}", tenant, e);
+                    TaskStatus::Failed(e.to_string())
+                }
+            };
+            manager.update_task(&task_id, |t| t.status = status.clone());
+        });
+        task
+    }
+
+    /// Delete the documents whose `_expiresAt` has passed. Returns how many
+    /// were deleted.
+    pub async fn purge_expired(&self, tenant_id: &str) -> Result<usize> {
+        const PAGE: usize = 1000;
+        let filter = crate::index::expiry::expired_filter(chrono::Utc::now().timestamp());
+        let mut purged = 0;
+        loop {
+            let page = self.search(tenant_id, "", Some(&filter), None, PAGE)?;
+            let ids: Vec<String> = page
+                .documents
+                .into_iter()
+                .map(|scored| scored.document.id)
+                .collect();
+            if ids.is_empty() {
+                return Ok(purged);
+            }
+            purged += ids.len();
+            let last_page = ids.len() < PAGE;
+            self.delete_documents_sync(tenant_id, ids).await?;
+            if last_page {
+                return Ok(purged);
+            }
+        }
+    }
+
+    /// The registered namespaces and their limits.
+    pub fn namespaces(&self) -> &NamespaceStore {
+        &self.namespaces
+    }
+
+    /// Tenant ids of the indexes in namespace `name`, sorted.
+    pub fn namespace_tenants(&self, name: &str) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(&self.base_path) else {
+            return Vec::new();
+        };
+        let mut tenants: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|dir| namespace::parse_namespaced_tenant(dir).is_some_and(|(ns, _)| ns == name))
+            .collect();
+        tenants.sort();
+        tenants
+    }
+
+    pub fn namespace_usage(&self, name: &str) -> NamespaceUsage {
+        let tenants = self.namespace_tenants(name);
+        NamespaceUsage {
+            indexes: tenants.len(),
+            documents: self.namespace_documents(&tenants),
+            disk_bytes: tenants
+                .iter()
+                .map(|t| {
+                    crate::index::storage_size::dir_size_bytes(&self.base_path.join(t)).unwrap_or(0)
+                })
+                .sum(),
+        }
+    }
+
+    fn namespace_documents(&self, tenants: &[String]) -> u64 {
+        tenants
+            .iter()
+            .filter_map(|t| self.get_or_load(t).ok())
+            .map(|index| index.reader().searcher().num_docs())
+            .sum()
+    }
+
+    /// Reject creating `tenant_id` or writing `new_documents` to it when that
+    /// would take its namespace past a registered limit. Upserts are counted
+    /// as new documents.
+    fn check_namespace_limits(&self, tenant_id: &str, new_documents: usize) -> Result<()> {
+        let Some((name, _)) = namespace::parse_namespaced_tenant(tenant_id) else {
+            return Ok(());
+        };
+        let Some(ns) = self.namespaces.get(name) else {
+            return Ok(());
+        };
+        let exceeded = |detail: String| FlapjackError::NamespaceLimitExceeded {
+            namespace: name.to_string(),
+            detail,
+        };
+        let tenants = self.namespace_tenants(name);
+        if let Some(max) = ns.limits.max_indexes {
+            if !tenants.iter().any(|t| t == tenant_id) && tenants.len() >= max {
+                return Err(exceeded(format!("maxIndexes is {}", max)));
+            }
+        }
+        if let Some(max) = ns.limits.max_documents {
+            if new_documents > 0 && self.namespace_documents(&tenants) + new_documents as u64 > max
+            {
+                return Err(exceeded(format!("maxDocuments is {}", max)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Register a task that runs outside the write queues. The caller
+    /// reports on it through [`update_task`](Self::update_task).
+    pub fn start_task(&self, task_id: String) -> TaskInfo {
+        let numeric_id = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        let mut task = TaskInfo::new(task_id.clone(), numeric_id, 0);
+        task.status = TaskStatus::Processing;
+        self.tasks.insert(task_id, task.clone());
+        self.tasks.insert(numeric_id.to_string(), task.clone());
+        task
+    }
+
+    /// Update a task under both its id and its numeric id.
+    pub fn update_task(&self, task_id: &str, f: impl Fn(&mut TaskInfo)) {
+        let Some(numeric_id) = self.tasks.get(task_id).map(|t| t.numeric_id.to_string()) else {
+            return;
+        };
+        for id in [task_id, numeric_id.as_str()] {
+            self.tasks.alter(id, |_, mut t| {
+                f(&mut t);
+                t
+            });
+        }
+    }
+
     pub fn make_noop_task(&self, index_name: &str) -> Result<TaskInfo> {
         let numeric_id = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -1847,6 +2784,26 @@ impl IndexManager {
         Ok(task)
     }
 
+    /// The tenant's write-ahead log, or `None` when `FLAPJACK_WAL=false` or
+    /// the log cannot be opened.
+    fn get_or_create_wal(&self, tenant_id: &str) -> Option<Arc<WriteAheadLog>> {
+        if !wal::wal_enabled() {
+            return None;
+        }
+        let entry = self
+            .wals
+            .entry(tenant_id.to_string())
+            .or_try_insert_with(|| {
+                WriteAheadLog::open(&self.base_path.join(tenant_id))
+                    .map(Arc::new)
+                    .map_err(|e| {
+                        tracing::error!("[WAL {}] open failed: {}", tenant_id, e);
+                        e
+                    })
+            });
+        entry.ok().map(|e| Arc::clone(&e))
+    }
+
     pub fn get_or_create_oplog(&self, tenant_id: &str) -> Option<Arc<OpLog>> {
         let entry = self
             .oplogs
@@ -1855,12 +2812,13 @@ impl IndexManager {
                 let oplog_dir = self.base_path.join(tenant_id).join("oplog");
                 let node_id =
                     std::env::var("FLAPJACK_NODE_ID").unwrap_or_else(|_| "unknown".to_string());
-                OpLog::open(&oplog_dir, tenant_id, &node_id)
-                    .map(Arc::new)
-                    .map_err(|e| {
-                        tracing::error!("[OPLOG {}] open failed: {}", tenant_id, e);
-                        e
-                    })
+                let oplog = OpLog::open(&oplog_dir, tenant_id, &node_id);
+                #[cfg(feature = "fault-injection")]
+                let oplog = oplog.map(|o| o.with_faults(Arc::clone(&self.faults)));
+                oplog.map(Arc::new).map_err(|e| {
+                    tracing::error!("[OPLOG {}] open failed: {}", tenant_id, e);
+                    e
+                })
             });
         match entry {
             Ok(e) => Some(Arc::clone(&e)),
@@ -1906,6 +2864,82 @@ impl IndexManager {
         Ok(Some(document))
     }
 
+    /// The documents of `object_ids` that exist, keyed by objectID, read in
+    /// one pass over the index rather than one lookup per ID.
+    pub fn get_documents(
+        &self,
+        tenant_id: &str,
+        object_ids: &[String],
+    ) -> Result<HashMap<String, Document>> {
+        if object_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let index = self.get_or_load(tenant_id)?;
+        let searcher = index.reader().searcher();
+        let schema = index.inner().schema();
+        let id_field = schema
+            .get_field("_id")
+            .map_err(|_| FlapjackError::FieldNotFound("_id".to_string()))?;
+
+        let ids_query = tantivy::query::TermSetQuery::new(
+            object_ids
+                .iter()
+                .map(|id| tantivy::Term::from_field_text(id_field, id)),
+        );
+        let mut documents = HashMap::new();
+        for address in searcher.search(&ids_query, &tantivy::collector::DocSetCollector)? {
+            let retrieved_doc = searcher.doc(address)?;
+            let document = index
+                .converter()
+                .from_tantivy(retrieved_doc, &schema, String::new())?;
+            documents.insert(document.id.clone(), document);
+        }
+        Ok(documents)
+    }
+
+    /// The subset of `object_ids` whose documents match `filter`, for hits
+    /// found outside the keyword search (e.g. by vector search).
+    pub fn filter_object_ids(
+        &self,
+        tenant_id: &str,
+        object_ids: &[String],
+        filter: &Filter,
+    ) -> Result<HashSet<String>> {
+        if object_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let index = self.get_or_load(tenant_id)?;
+        let settings = self.get_settings(tenant_id);
+        let searcher = index.reader().searcher();
+        let schema = index.inner().schema();
+        let id_field = schema
+            .get_field("_id")
+            .map_err(|_| FlapjackError::FieldNotFound("_id".to_string()))?;
+
+        let filter_query = crate::query::filter::FilterCompiler::new(schema.clone())
+            .compile(filter, settings.as_deref())?;
+        let ids_query = tantivy::query::TermSetQuery::new(
+            object_ids
+                .iter()
+                .map(|id| tantivy::Term::from_field_text(id_field, id)),
+        );
+        let query = tantivy::query::BooleanQuery::new(vec![
+            (tantivy::query::Occur::Must, Box::new(ids_query)),
+            (tantivy::query::Occur::Must, filter_query),
+        ]);
+
+        let mut matched = HashSet::new();
+        for address in searcher.search(&query, &tantivy::collector::DocSetCollector)? {
+            let doc: tantivy::TantivyDocument = searcher.doc(address)?;
+            if let Some(value) = doc.get_first(id_field) {
+                if let tantivy::schema::OwnedValue::Str(id) = value.into() {
+                    matched.insert(id);
+                }
+            }
+        }
+        Ok(matched)
+    }
+
     /// Gracefully shut down all write queues, flushing pending writes.
     ///
     /// Drops all write queue senders (triggering final batch flush in each
@@ -1946,6 +2980,26 @@ impl IndexManager {
     }
 }
 
+/// Register the analyzers for the index's saved normalization settings, so
+/// queries against an index nobody has written to since startup match.
+fn load_text_normalization(index: &Index, path: &Path) {
+    if let Ok(settings) = IndexSettings::load(path.join("settings.json")) {
+        index.set_text_normalization(crate::tokenizer::TextNormalization::from_settings(
+            &settings,
+        ));
+    }
+}
+
+/// Stable bucket in `0..10_000` for an objectID (FNV-1a), used for sampling.
+fn sample_bucket(object_id: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in object_id.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash % 10_000
+}
+
 #[cfg(test)]
 #[allow(clippy::items_after_test_module)]
 mod tests {
@@ -1987,6 +3041,223 @@ mod tests {
         assert_eq!(count, Some(3), "should have 3 docs after adding 3");
     }
 
+    #[tokio::test]
+    async fn sampled_clone_keeps_documents_hidden_from_search() {
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        manager.create_tenant("src").unwrap();
+        let doc = |id: &str, publish_at: Option<i64>| {
+            let mut fields = HashMap::from([(
+                "name".to_string(),
+                crate::types::FieldValue::Text(id.to_string()),
+            )]);
+            if let Some(at) = publish_at {
+                fields.insert(
+                    "_publishAt".to_string(),
+                    crate::types::FieldValue::Integer(at),
+                );
+            }
+            Document {
+                id: id.to_string(),
+                fields,
+            }
+        };
+        let later = chrono::Utc::now().timestamp() + 86_400;
+        manager
+            .add_documents_sync(
+                "src",
+                vec![doc("live", None), doc("scheduled", Some(later))],
+            )
+            .await
+            .unwrap();
+
+        let (_, copied) = manager
+            .clone_index_sampled("src", "dst", 100.0)
+            .await
+            .unwrap();
+        assert_eq!(copied, 2);
+        assert!(manager.get_document("dst", "scheduled").unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn get_documents_returns_existing_ids_only() {
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        manager.create_tenant("t1").unwrap();
+        let docs = ["d1", "d2"]
+            .into_iter()
+            .map(|id| Document {
+                id: id.to_string(),
+                fields: HashMap::from([(
+                    "name".to_string(),
+                    crate::types::FieldValue::Text(id.to_uppercase()),
+                )]),
+            })
+            .collect();
+        manager.add_documents_sync("t1", docs).await.unwrap();
+
+        let ids = ["d2".to_string(), "missing".to_string(), "d1".to_string()];
+        let found = manager.get_documents("t1", &ids).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(
+            found["d2"].fields.get("name"),
+            Some(&crate::types::FieldValue::Text("D2".to_string()))
+        );
+        assert!(!found.contains_key("missing"));
+    }
+
+    #[test]
+    fn started_tasks_update_under_both_ids() {
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        let task = manager.start_task("bulk_settings_1".to_string());
+        assert_eq!(task.status, TaskStatus::Processing);
+
+        manager.update_task(&task.id, |t| {
+            t.status = TaskStatus::Succeeded;
+            t.progress = Some(crate::types::TaskProgress {
+                completed: 1,
+                total: 1,
+            });
+        });
+        for id in [task.id.clone(), task.numeric_id.to_string()] {
+            let updated = manager.get_task(&id).unwrap();
+            assert_eq!(updated.status, TaskStatus::Succeeded);
+            assert_eq!(updated.progress.map(|p| p.completed), Some(1));
+        }
+    }
+
+    #[tokio::test]
+    async fn namespace_limits_cap_indexes_and_documents() {
+        use crate::index::namespace::{Namespace, NamespaceLimits};
+
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        manager
+            .namespaces()
+            .put(Namespace {
+                name: "acme".to_string(),
+                limits: NamespaceLimits {
+                    max_indexes: Some(1),
+                    max_documents: Some(1),
+                },
+                created_at: 0,
+            })
+            .unwrap();
+        manager.create_tenant("acme~products").unwrap();
+        let err = manager.create_tenant("acme~articles").unwrap_err();
+        assert!(matches!(err, FlapjackError::NamespaceLimitExceeded { .. }));
+        manager.create_tenant("other~articles").unwrap();
+
+        let doc = |id: &str| Document {
+            id: id.to_string(),
+            fields: HashMap::from([(
+                "name".to_string(),
+                crate::types::FieldValue::Text(id.to_string()),
+            )]),
+        };
+        manager
+            .add_documents_sync("acme~products", vec![doc("d1")])
+            .await
+            .unwrap();
+        let err = manager
+            .add_documents("acme~products", vec![doc("d2")])
+            .unwrap_err();
+        assert!(matches!(err, FlapjackError::NamespaceLimitExceeded { .. }));
+
+        let usage = manager.namespace_usage("acme");
+        assert_eq!(usage.indexes, 1);
+        assert_eq!(usage.documents, 1);
+        assert_eq!(manager.namespace_tenants("other"), vec!["other~articles"]);
+    }
+
+    #[cfg(feature = "s3-snapshots")]
+    #[tokio::test]
+    async fn cold_tenants_search_and_promote_on_write() {
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        let storage = Arc::new(crate::index::tiered::MemoryColdStorage::default());
+        manager.set_cold_storage(storage.clone());
+        manager.create_tenant("archive").unwrap();
+        let doc = |id: &str, name: &str| Document {
+            id: id.to_string(),
+            fields: HashMap::from([(
+                "name".to_string(),
+                crate::types::FieldValue::Text(name.to_string()),
+            )]),
+        };
+        manager
+            .add_documents_sync("archive", vec![doc("d1", "Alice"), doc("d2", "Bob")])
+            .await
+            .unwrap();
+
+        let demoting = Arc::clone(&manager);
+        let manifest = tokio::task::spawn_blocking(move || demoting.demote_tenant("archive"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(manifest.documents, 2);
+        assert!(manager.is_cold("archive"));
+        assert!(!storage.objects.lock().unwrap().is_empty());
+        assert!(manager.idle_tenants(std::time::Duration::ZERO).is_empty());
+
+        let result = manager.search("archive", "alice", None, None, 10).unwrap();
+        assert_eq!(result.total, 1);
+        assert!(manager.segment_cache().unwrap().stats().files > 0);
+
+        manager
+            .add_documents_sync("archive", vec![doc("d3", "Alice Again")])
+            .await
+            .unwrap();
+        assert!(!manager.is_cold("archive"));
+        assert!(storage.objects.lock().unwrap().is_empty());
+        let result = manager.search("archive", "alice", None, None, 10).unwrap();
+        assert_eq!(result.total, 2);
+    }
+
+    #[cfg(feature = "s3-snapshots")]
+    #[tokio::test]
+    async fn concurrent_promotions_download_once() {
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        let storage = Arc::new(crate::index::tiered::MemoryColdStorage::default());
+        manager.set_cold_storage(storage.clone());
+        manager.create_tenant("archive").unwrap();
+        let doc = Document {
+            id: "d1".to_string(),
+            fields: HashMap::from([(
+                "name".to_string(),
+                crate::types::FieldValue::Text("Alice".to_string()),
+            )]),
+        };
+        manager
+            .add_documents_sync("archive", vec![doc])
+            .await
+            .unwrap();
+        let demoting = Arc::clone(&manager);
+        let manifest = tokio::task::spawn_blocking(move || demoting.demote_tenant("archive"))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let promotions: Vec<_> = (0..4)
+            .map(|_| {
+                let manager = Arc::clone(&manager);
+                tokio::spawn(async move { manager.promote_for_write("archive").await })
+            })
+            .collect();
+        for promotion in promotions {
+            promotion.await.unwrap().unwrap();
+        }
+        assert!(!manager.is_cold("archive"));
+        assert_eq!(
+            storage.gets.load(std::sync::atomic::Ordering::SeqCst),
+            manifest.files.len()
+        );
+        let result = manager.search("archive", "alice", None, None, 10).unwrap();
+        assert_eq!(result.total, 1);
+    }
+
     #[tokio::test]
     async fn tenant_doc_count_returns_none_for_unloaded() {
         let tmp = TempDir::new().unwrap();
@@ -1994,6 +3265,61 @@ mod tests {
         assert_eq!(manager.tenant_doc_count("nonexistent"), None);
     }
 
+    #[tokio::test]
+    async fn replay_wal_applies_uncommitted_writes() {
+        let tmp = TempDir::new().unwrap();
+        {
+            let manager = IndexManager::new(tmp.path());
+            manager.create_tenant("t1").unwrap();
+        }
+        // A write that was acknowledged but never reached the write queue.
+        let wal = WriteAheadLog::open(&tmp.path().join("t1")).unwrap();
+        let op = WriteOp {
+            task_id: "task_t1_lost".to_string(),
+            actions: vec![WriteAction::Add(Document {
+                id: "d1".to_string(),
+                fields: HashMap::from([(
+                    "name".to_string(),
+                    crate::types::FieldValue::Text("Alice".to_string()),
+                )]),
+            })],
+        };
+        wal.append(&op, 42).unwrap();
+        drop(wal);
+
+        let manager = IndexManager::new(tmp.path());
+        assert_eq!(manager.replay_wal("t1").await.unwrap(), 1);
+        loop {
+            match manager.get_task("task_t1_lost").unwrap().status {
+                TaskStatus::Succeeded => break,
+                TaskStatus::Failed(e) => panic!("replayed write failed: {}", e),
+                _ => tokio::time::sleep(tokio::time::Duration::from_millis(10)).await,
+            }
+        }
+        assert!(manager.get_document("t1", "d1").unwrap().is_some());
+        assert_eq!(manager.get_task("42").unwrap().id, "task_t1_lost");
+        assert_eq!(manager.replay_wal("t1").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn committed_writes_are_not_replayed() {
+        let tmp = TempDir::new().unwrap();
+        {
+            let manager = IndexManager::new(tmp.path());
+            manager.create_tenant("t1").unwrap();
+            let docs = vec![Document {
+                id: "d1".to_string(),
+                fields: HashMap::from([(
+                    "name".to_string(),
+                    crate::types::FieldValue::Text("Alice".to_string()),
+                )]),
+            }];
+            manager.add_documents_sync("t1", docs).await.unwrap();
+        }
+        let manager = IndexManager::new(tmp.path());
+        assert_eq!(manager.replay_wal("t1").await.unwrap(), 0);
+    }
+
     #[tokio::test]
     async fn loaded_tenant_ids_returns_correct_ids() {
         let tmp = TempDir::new().unwrap();
@@ -2219,6 +3545,85 @@ mod tests {
         assert_eq!(results[0].doc_id, "doc1");
     }
 
+    #[cfg(feature = "vector-search")]
+    #[tokio::test]
+    async fn test_rebuild_vector_index_saves_and_logs() {
+        use usearch::ffi::MetricKind;
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        manager.create_tenant("rebuild_t").unwrap();
+
+        let mut vi = crate::vector::index::VectorIndex::new(3, MetricKind::Cos).unwrap();
+        vi.add("doc1", &[1.0, 0.0, 0.0]).unwrap();
+        vi.add("doc2", &[0.0, 1.0, 0.0]).unwrap();
+        vi.remove("doc2").unwrap();
+        manager.set_vector_index("rebuild_t", vi);
+
+        assert_eq!(manager.rebuild_vector_index("rebuild_t").unwrap(), Some(1));
+        let vectors_dir = tmp.path().join("rebuild_t").join("vectors");
+        assert!(vectors_dir.join("id_map.json").exists());
+        let oplog = manager.get_oplog("rebuild_t").unwrap();
+        let ops = oplog.read_since(0).unwrap();
+        assert_eq!(ops.last().unwrap().op_type, "vectors_rebuilt");
+        assert_eq!(ops.last().unwrap().payload["count"], 1);
+
+        assert_eq!(manager.reload_vector_index("rebuild_t").unwrap(), 1);
+        manager.create_tenant("plain_t").unwrap();
+        assert_eq!(manager.rebuild_vector_index("plain_t").unwrap(), None);
+    }
+
+    #[cfg(feature = "vector-search")]
+    #[tokio::test]
+    async fn test_legacy_vector_format_is_upgraded_in_background() {
+        use usearch::ffi::MetricKind;
+        let tmp = TempDir::new().unwrap();
+        let tenant_id = "legacy_vec_t";
+        let tenant_path = tmp.path().join(tenant_id);
+        std::fs::create_dir_all(&tenant_path).unwrap();
+        {
+            let schema = crate::index::schema::Schema::builder().build();
+            let _ = crate::index::Index::create(&tenant_path, schema).unwrap();
+        }
+        let settings = crate::index::settings::IndexSettings {
+            embedders: Some(std::collections::HashMap::from([(
+                "default".to_string(),
+                serde_json::json!({ "source": "userProvided", "dimensions": 3 }),
+            )])),
+            ..Default::default()
+        };
+        settings.save(tenant_path.join("settings.json")).unwrap();
+
+        // Save, then strip it back to format 1: no version, no raw vectors.
+        let vectors_dir = tenant_path.join("vectors");
+        let mut vi = crate::vector::index::VectorIndex::new(3, MetricKind::Cos).unwrap();
+        vi.add("doc1", &[1.0, 0.0, 0.0]).unwrap();
+        vi.save(&vectors_dir).unwrap();
+        let meta_path = vectors_dir.join("id_map.json");
+        let mut meta: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&meta_path).unwrap()).unwrap();
+        meta.as_object_mut().unwrap().remove("format_version");
+        std::fs::write(&meta_path, meta.to_string()).unwrap();
+        std::fs::remove_file(vectors_dir.join("vectors.bin")).unwrap();
+
+        let manager = IndexManager::new(tmp.path());
+        manager.get_or_load(tenant_id).unwrap();
+        let vi = manager.get_vector_index(tenant_id).unwrap();
+        assert_eq!(vi.read().unwrap().len(), 1);
+
+        for _ in 0..100 {
+            if !vi.read().unwrap().needs_upgrade() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(!vi.read().unwrap().needs_upgrade());
+        assert!(vectors_dir.join("vectors.bin").exists());
+        let reloaded =
+            crate::vector::index::VectorIndex::load(&vectors_dir, MetricKind::Cos).unwrap();
+        assert!(!reloaded.needs_upgrade());
+        assert_eq!(reloaded.get("doc1"), Some(vec![1.0, 0.0, 0.0]));
+    }
+
     #[cfg(feature = "vector-search")]
     #[tokio::test]
     async fn test_load_no_vectors_dir_ok() {
@@ -3086,6 +4491,88 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_index_stats_reports_documents_and_shape() {
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        manager.create_tenant("stats_t").unwrap();
+        let doc = |id: &str, attrs: usize| Document {
+            id: id.to_string(),
+            fields: (0..attrs)
+                .map(|i| {
+                    (
+                        format!("attr{}", i),
+                        crate::types::FieldValue::Text("value".to_string()),
+                    )
+                })
+                .collect(),
+        };
+        let docs = vec![doc("a", 1), doc("b", 3), doc("c", 12)];
+        manager.add_documents_sync("stats_t", docs).await.unwrap();
+        manager
+            .delete_documents_sync("stats_t", vec!["a".to_string()])
+            .await
+            .unwrap();
+        manager.compact_index_sync("stats_t").await.unwrap();
+
+        let stats = manager.index_stats("stats_t").unwrap();
+        assert_eq!(stats.documents, 2);
+        assert_eq!(stats.sampled_documents, 2);
+        assert!(stats.disk_bytes.docstore > 0);
+        assert!(stats.disk_bytes.postings > 0);
+        assert!(stats.last_build_at.is_some());
+        assert!(stats.last_compaction_at.is_some());
+        let bucket = |min: usize| {
+            stats
+                .attributes_per_document
+                .iter()
+                .find(|b| b.min == min)
+                .unwrap()
+                .documents
+        };
+        assert_eq!(bucket(3), 1, "3 attributes fall in 3..=5");
+        assert_eq!(bucket(11), 1, "12 attributes fall in 11..=20");
+        assert_eq!(stats.attributes[0].attribute, "attr0");
+        assert_eq!(stats.attributes[0].documents, 2);
+    }
+
+    #[tokio::test]
+    async fn test_batched_compaction_reports_progress() {
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        manager.create_tenant("compact_t").unwrap();
+        for id in ["a", "b", "c"] {
+            let doc = Document {
+                id: id.to_string(),
+                fields: HashMap::new(),
+            };
+            manager
+                .add_documents_sync("compact_t", vec![doc])
+                .await
+                .unwrap();
+        }
+        assert!(manager.fragmentation("compact_t").unwrap().segments > 1);
+
+        let options = CompactOptions {
+            merge_batch: 2,
+            throttle: std::time::Duration::from_millis(1),
+        };
+        let task = manager.compact_index_with("compact_t", options).unwrap();
+        let task = loop {
+            let task = manager.get_task(&task.id).unwrap();
+            if !matches!(task.status, TaskStatus::Enqueued | TaskStatus::Processing) {
+                break task;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+        assert_eq!(task.status, TaskStatus::Succeeded);
+        let progress = task.progress.unwrap();
+        assert!(progress.total > 0);
+        assert_eq!(progress.completed, progress.total);
+        assert_eq!(manager.fragmentation("compact_t").unwrap().segments, 1);
+        assert!(manager.last_compaction("compact_t").is_some());
+    }
+
     #[cfg(feature = "vector-search")]
     #[tokio::test]
     async fn test_vectors_lost_when_embedder_model_changes() {
@@ -3170,6 +4657,142 @@ mod tests {
     }
 }
 
+// ── Tiered storage (behind s3-snapshots feature) ──
+
+#[cfg(feature = "s3-snapshots")]
+impl IndexManager {
+    /// Where demoted indexes keep their segments. Without it cold indexes
+    /// cannot be opened or demoted.
+    pub fn set_cold_storage(&self, storage: Arc<dyn ColdStorage>) {
+        *self.cold_storage.write().unwrap_or_else(|e| e.into_inner()) = Some(storage);
+    }
+
+    fn cold_storage(&self) -> Result<Arc<dyn ColdStorage>> {
+        self.cold_storage
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .ok_or_else(|| {
+                FlapjackError::Config(
+                    "cold storage needs FLAPJACK_S3_BUCKET to be configured".to_string(),
+                )
+            })
+    }
+
+    pub fn segment_cache(&self) -> Result<Arc<SegmentCache>> {
+        if let Some(cache) = self.segment_cache.get() {
+            return Ok(Arc::clone(cache));
+        }
+        let cache = Arc::new(SegmentCache::from_env(&self.base_path)?);
+        Ok(Arc::clone(self.segment_cache.get_or_init(|| cache)))
+    }
+
+    /// The tenant's manifest if it is cold.
+    pub fn cold_manifest(&self, tenant_id: &str) -> Result<Option<ColdManifest>> {
+        ColdManifest::load(&self.base_path.join(tenant_id))
+    }
+
+    /// Promote a cold tenant on a blocking thread, for async write paths to
+    /// call before taking a write.
+    pub async fn promote_for_write(self: &Arc<Self>, tenant_id: &str) -> Result<()> {
+        if !self.is_cold(tenant_id) {
+            return Ok(());
+        }
+        let manager = Arc::clone(self);
+        let tenant = tenant_id.to_string();
+        tokio::task::spawn_blocking(move || manager.promote_tenant(&tenant))
+            .await
+            .map_err(|e| FlapjackError::Io(e.to_string()))?
+    }
+
+    fn tier_lock(&self, tenant_id: &str) -> Arc<std::sync::Mutex<()>> {
+        Arc::clone(
+            &self
+                .tier_locks
+                .entry(tenant_id.to_string())
+                .or_insert_with(|| Arc::new(std::sync::Mutex::new(()))),
+        )
+    }
+
+    /// Move the tenant's segments to cold storage. Blocks while they upload;
+    /// fails if the tenant has writes pending or its writer does not shut
+    /// down within 30s.
+    pub fn demote_tenant(&self, tenant_id: &str) -> Result<ColdManifest> {
+        let storage = self.cold_storage()?;
+        let lock = self.tier_lock(tenant_id);
+        let _tiering = lock.lock().unwrap_or_else(|e| e.into_inner());
+        let path = self.base_path.join(tenant_id);
+        if let Some(manifest) = ColdManifest::load(&path)? {
+            return Ok(manifest);
+        }
+        let pending = self.pending_task_count(tenant_id);
+        if pending > 0 {
+            return Err(FlapjackError::InvalidQuery(format!(
+                "Index {} has {} pending tasks; demote it once they finish",
+                tenant_id, pending
+            )));
+        }
+        let documents = self.get_or_load(tenant_id)?.reader().searcher().num_docs();
+
+        // Closing the write queue lets its task commit and drop the writer,
+        // which stops merges from rewriting segments during the upload.
+        self.unload(&tenant_id.to_string())?;
+        if let Some((_, handle)) = self.write_task_handles.remove(tenant_id) {
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+            while !handle.is_finished() && std::time::Instant::now() < deadline {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            if !handle.is_finished() {
+                self.write_task_handles
+                    .insert(tenant_id.to_string(), handle);
+                return Err(FlapjackError::InvalidQuery(format!(
+                    "Index {} is still committing writes; demote it once they finish",
+                    tenant_id
+                )));
+            }
+        }
+
+        let manifest = tiered::demote(&path, tenant_id, documents, storage.as_ref())?;
+        self.loaded.remove(tenant_id);
+        tracing::info!(
+            "Demoted {} to cold storage ({} files, {} bytes)",
+            tenant_id,
+            manifest.files.len(),
+            manifest.bytes()
+        );
+        Ok(manifest)
+    }
+
+    /// Bring a cold tenant's segments back to local disk. Blocks while they
+    /// download; concurrent calls wait for the first and then find the
+    /// tenant hot.
+    pub fn promote_tenant(&self, tenant_id: &str) -> Result<()> {
+        let lock = self.tier_lock(tenant_id);
+        let _tiering = lock.lock().unwrap_or_else(|e| e.into_inner());
+        let path = self.base_path.join(tenant_id);
+        let Some(manifest) = ColdManifest::load(&path)? else {
+            return Ok(());
+        };
+        let storage = self.cold_storage()?;
+        self.unload(&tenant_id.to_string())?;
+        tiered::promote(
+            &path,
+            tenant_id,
+            &manifest,
+            storage.as_ref(),
+            &self.segment_cache()?,
+        )?;
+        self.loaded.remove(tenant_id);
+        tracing::info!(
+            "Promoted {} from cold storage ({} files, {} bytes)",
+            tenant_id,
+            manifest.files.len(),
+            manifest.bytes()
+        );
+        Ok(())
+    }
+}
+
 // ── Vector index storage (behind vector-search feature) ──
 
 #[cfg(feature = "vector-search")]
@@ -3266,12 +4889,16 @@ impl IndexManager {
         match crate::vector::index::VectorIndex::load(&vectors_dir, usearch::ffi::MetricKind::Cos) {
             Ok(vi) => {
                 let count = vi.len();
+                let needs_upgrade = vi.needs_upgrade();
                 self.set_vector_index(tenant_id, vi);
                 tracing::info!(
                     "[LOAD {}] loaded vector index from disk ({} vectors)",
                     tenant_id,
                     count
                 );
+                if needs_upgrade {
+                    self.upgrade_vector_index_in_background(tenant_id, vectors_dir);
+                }
             }
             Err(e) => {
                 tracing::warn!("[LOAD {}] failed to load vector index: {}", tenant_id, e);
@@ -3280,6 +4907,124 @@ impl IndexManager {
     }
 }
 
+#[cfg(feature = "vector-search")]
+impl IndexManager {
+    /// Rebuild the tenant's HNSW graph from its current vectors and save it.
+    ///
+    /// The rebuild is recorded in the oplog as `vectors_rebuilt`. Replicas
+    /// install the saved files instead of rebuilding or replaying every
+    /// insert, so they end up with the same graph. Returns the number of
+    /// vectors, or `None` if the tenant has no vector index.
+    pub fn rebuild_vector_index(&self, tenant_id: &str) -> Result<Option<usize>> {
+        self.get_or_load(tenant_id)?;
+        let Some(vi) = self.get_vector_index(tenant_id) else {
+            return Ok(None);
+        };
+        let count =
+            rebuild_vector_index_online(&vi, &self.base_path.join(tenant_id).join("vectors"))?;
+
+        self.append_oplog(
+            tenant_id,
+            "vectors_rebuilt",
+            serde_json::json!({ "count": count }),
+        );
+        tracing::info!(
+            "[VECTORS {}] rebuilt HNSW graph ({} vectors)",
+            tenant_id,
+            count
+        );
+        Ok(Some(count))
+    }
+
+    /// Rewrite a vector index loaded from an older on-disk format, on a
+    /// background thread. Queries keep using the loaded index until the
+    /// rebuilt one is swapped in.
+    fn upgrade_vector_index_in_background(&self, tenant_id: &str, vectors_dir: PathBuf) {
+        let Some(vi) = self.get_vector_index(tenant_id) else {
+            return;
+        };
+        let tenant_id = tenant_id.to_string();
+        std::thread::spawn(
+            move || match rebuild_vector_index_online(&vi, &vectors_dir) {
+                Ok(count) => tracing::info!(
+                    "[VECTORS {}] upgraded vector index to format {} ({} vectors)",
+                    tenant_id,
+                    crate::vector::index::FORMAT_VERSION,
+                    count
+                ),
+                Err(e) => tracing::warn!(
+                    "[VECTORS {}] vector index upgrade failed, keeping the loaded index: {}",
+                    tenant_id,
+                    e
+                ),
+            },
+        );
+    }
+
+    /// Replace the in-memory vector index with the files in
+    /// `{tenant}/vectors`, e.g. after a peer shipped its rebuilt graph.
+    /// Writes in flight keep their handle and land in the new index.
+    pub fn reload_vector_index(&self, tenant_id: &str) -> Result<usize> {
+        let vectors_dir = self.base_path.join(tenant_id).join("vectors");
+        let loaded =
+            crate::vector::index::VectorIndex::load(&vectors_dir, usearch::ffi::MetricKind::Cos)
+                .map_err(|e| FlapjackError::Io(format!("failed to load vector index: {}", e)))?;
+        let count = loaded.len();
+        let needs_upgrade = loaded.needs_upgrade();
+        match self.get_vector_index(tenant_id) {
+            Some(vi) => {
+                *vi.write()
+                    .map_err(|_| FlapjackError::Io("vector index lock poisoned".to_string()))? =
+                    loaded;
+            }
+            None => self.set_vector_index(tenant_id, loaded),
+        }
+        if needs_upgrade {
+            self.upgrade_vector_index_in_background(tenant_id, vectors_dir);
+        }
+        Ok(count)
+    }
+}
+
+/// Rebuild `vi`'s HNSW graph and save it to `vectors_dir`.
+///
+/// The new graph is built without holding the lock, so searches and writes
+/// carry on against the current one; writes made meanwhile are replayed
+/// onto the new graph before it's swapped in. Returns the number of vectors.
+#[cfg(feature = "vector-search")]
+fn rebuild_vector_index_online(
+    vi: &std::sync::RwLock<crate::vector::index::VectorIndex>,
+    vectors_dir: &Path,
+) -> Result<usize> {
+    let poisoned = || FlapjackError::Io("vector index lock poisoned".to_string());
+    let source = vi
+        .write()
+        .map_err(|_| poisoned())?
+        .start_rebuild()
+        .ok_or_else(|| FlapjackError::Io("vector rebuild already in progress".to_string()))?;
+    let rebuilt = crate::vector::index::VectorIndex::from_vectors(
+        source.dimensions,
+        usearch::ffi::MetricKind::Cos,
+        source.vectors,
+    );
+
+    let mut guard = vi.write().map_err(|_| poisoned())?;
+    let rebuilt = match rebuilt {
+        Ok(rebuilt) => rebuilt,
+        Err(e) => {
+            guard.cancel_rebuild();
+            return Err(FlapjackError::Io(format!("vector rebuild failed: {}", e)));
+        }
+    };
+    guard
+        .finish_rebuild(rebuilt)
+        .map_err(|e| FlapjackError::Io(format!("vector rebuild failed: {}", e)))?;
+    guard
+        .save(vectors_dir)
+        .map_err(|e| FlapjackError::Io(format!("failed to save vector index: {}", e)))?;
+    Ok(guard.len())
+}
+
 impl Drop for IndexManager {
     /// Abort all background write tasks when the manager is dropped.
     ///
@@ -1,4 +1,5 @@
 use crate::error::{FlapjackError, Result};
+use crate::index::resources::ResourceLimits;
 use std::env;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
@@ -21,16 +22,19 @@ impl Default for MemoryBudgetConfig {
 }
 
 impl MemoryBudgetConfig {
+    /// Without `FLAPJACK_MAX_CONCURRENT_WRITERS`, as many writers as the
+    /// memory limit has room for (see [`ResourceLimits`]).
     pub fn from_env() -> Self {
+        let max_buffer_mb = env::var("FLAPJACK_MAX_BUFFER_MB")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(31);
         MemoryBudgetConfig {
-            max_buffer_mb: env::var("FLAPJACK_MAX_BUFFER_MB")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(31),
+            max_buffer_mb,
             max_concurrent_writers: env::var("FLAPJACK_MAX_CONCURRENT_WRITERS")
                 .ok()
                 .and_then(|s| s.parse().ok())
-                .unwrap_or(40),
+                .unwrap_or_else(|| ResourceLimits::global().max_concurrent_writers(max_buffer_mb)),
             max_doc_mb: env::var("FLAPJACK_MAX_DOC_MB")
                 .ok()
                 .and_then(|s| s.parse().ok())
@@ -109,6 +113,10 @@ impl MemoryBudget {
         self.max_concurrent_writers
     }
 
+    pub fn max_document_size_bytes(&self) -> usize {
+        self.max_document_size_bytes
+    }
+
     pub fn reset_for_test(&self) {
         self.active_writers.store(0, Ordering::SeqCst);
     }
@@ -223,17 +223,9 @@ impl MemoryObserver {
             }
         }
 
-        // Priority 2: cgroup v2 memory limit (Linux only)
-        #[cfg(target_os = "linux")]
-        {
-            if let Ok(contents) = std::fs::read_to_string("/sys/fs/cgroup/memory.max") {
-                let trimmed = contents.trim();
-                if trimmed != "max" {
-                    if let Ok(bytes) = trimmed.parse::<usize>() {
-                        return (bytes, "cgroup".to_string());
-                    }
-                }
-            }
+        // Priority 2: cgroup v2 or v1 memory limit (Linux; absent elsewhere)
+        if let Some(bytes) = super::resources::process_cgroup_memory_limit() {
+            return (bytes, "cgroup".to_string());
         }
 
         // Priority 3: total system memory via sysinfo
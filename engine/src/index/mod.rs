@@ -1,10 +1,26 @@
+pub mod archive;
+pub mod blocklist;
+pub mod campaigns;
+pub mod compaction;
 pub mod document;
+pub mod document_schema;
+pub mod duplicates;
+pub mod expiry;
+pub mod facet_normalization;
 pub mod facet_translation;
+pub mod ingest_transform;
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+pub mod io_uring;
+pub mod language;
+pub mod localized;
 pub mod manager;
 pub mod memory;
 pub mod memory_observer;
+pub mod namespace;
 pub mod oplog;
 pub mod relevance;
+pub mod reranking;
+pub mod resources;
 pub mod rules;
 #[cfg(feature = "s3-snapshots")]
 pub mod s3;
@@ -12,14 +28,22 @@ pub mod schema;
 pub mod settings;
 #[cfg(feature = "s3-snapshots")]
 pub mod snapshot;
+pub mod stats;
 pub mod storage_size;
 pub mod synonyms;
 pub mod task_queue;
+pub mod task_store;
+pub mod templates;
+#[cfg(feature = "s3-snapshots")]
+pub mod tiered;
 mod utils;
+pub mod visibility;
+pub mod wal;
 pub mod write_queue;
 pub mod writer;
 
 use crate::error::Result;
+use crate::tokenizer::TextNormalization;
 use crate::types::Document;
 use document::DocumentConverter;
 use memory::{MemoryBudget, MemoryBudgetConfig};
@@ -71,6 +95,75 @@ pub struct Index {
     converter: Arc<DocumentConverter>,
     budget: Arc<MemoryBudget>,
     searchable_paths_cache: std::sync::RwLock<Option<Vec<String>>>,
+    text_normalization: std::sync::RwLock<Option<Arc<TextNormalization>>>,
+    last_compaction: std::sync::Mutex<Option<std::time::SystemTime>>,
+}
+
+/// Register the `edge_ngram_lower` and `simple` analyzers, with the index's
+/// text normalization (if any) applied around lowercasing.
+fn register_tokenizers(inner: &TantivyIndex, normalization: Option<Arc<TextNormalization>>) {
+    use crate::tokenizer::{CamelCaseSplitFilter, CjkAwareTokenizer, NormalizeTokenFilter};
+    use tantivy::tokenizer::{EdgeNgramFilter, LowerCaser, TextAnalyzer};
+
+    let (edge_ngram_tokenizer, simple_tokenizer) = match normalization {
+        Some(n) => {
+            let analyzer = |edge_ngram: bool| {
+                let mut builder = TextAnalyzer::builder(CjkAwareTokenizer).dynamic();
+                if n.splits_camel_case() {
+                    builder = builder.filter_dynamic(CamelCaseSplitFilter);
+                }
+                builder = builder
+                    .filter_dynamic(LowerCaser)
+                    .filter_dynamic(NormalizeTokenFilter::new(Arc::clone(&n)));
+                if edge_ngram {
+                    builder = builder.filter_dynamic(EdgeNgramFilter::new(2, 20).unwrap());
+                }
+                builder.build()
+            };
+            (analyzer(true), analyzer(false))
+        }
+        None => (
+            TextAnalyzer::builder(CjkAwareTokenizer)
+                .filter(LowerCaser)
+                .filter(EdgeNgramFilter::new(2, 20).unwrap())
+                .build(),
+            TextAnalyzer::builder(CjkAwareTokenizer)
+                .filter(LowerCaser)
+                .build(),
+        ),
+    };
+    inner
+        .tokenizers()
+        .register("edge_ngram_lower", edge_ngram_tokenizer);
+    inner.tokenizers().register("simple", simple_tokenizer);
+}
+
+/// Create the tantivy index in `path`, on the io_uring read path when the
+/// `io-uring` feature is built and `FLAPJACK_IO_URING` is set.
+fn create_tantivy_index(path: &Path, schema: tantivy::schema::Schema) -> Result<TantivyIndex> {
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    if io_uring::enabled() {
+        let dir = io_uring::UringDirectory::open(path).map_err(tantivy::TantivyError::from)?;
+        if TantivyIndex::exists(&dir).map_err(tantivy::TantivyError::from)? {
+            return Err(tantivy::TantivyError::IndexAlreadyExists.into());
+        }
+        return Ok(TantivyIndex::create(
+            dir,
+            schema,
+            tantivy::IndexSettings::default(),
+        )?);
+    }
+    Ok(TantivyIndex::create_in_dir(path, schema)?)
+}
+
+/// Open the tantivy index in `path`; see [`create_tantivy_index`].
+fn open_tantivy_index(path: &Path) -> Result<TantivyIndex> {
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    if io_uring::enabled() {
+        let dir = io_uring::UringDirectory::open(path).map_err(tantivy::TantivyError::from)?;
+        return Ok(TantivyIndex::open(dir)?);
+    }
+    Ok(TantivyIndex::open_in_dir(path)?)
 }
 
 impl Index {
@@ -99,24 +192,9 @@ impl Index {
         budget: Arc<MemoryBudget>,
     ) -> Result<Self> {
         let tantivy_schema = schema.to_tantivy();
-        let inner = TantivyIndex::create_in_dir(path, tantivy_schema.clone())?;
-
-        let edge_ngram_tokenizer =
-            tantivy::tokenizer::TextAnalyzer::builder(crate::tokenizer::CjkAwareTokenizer)
-                .filter(tantivy::tokenizer::LowerCaser)
-                .filter(tantivy::tokenizer::EdgeNgramFilter::new(2, 20).unwrap())
-                .build();
-
-        inner
-            .tokenizers()
-            .register("edge_ngram_lower", edge_ngram_tokenizer);
+        let inner = create_tantivy_index(path.as_ref(), tantivy_schema.clone())?;
 
-        let simple_tokenizer =
-            tantivy::tokenizer::TextAnalyzer::builder(crate::tokenizer::CjkAwareTokenizer)
-                .filter(tantivy::tokenizer::LowerCaser)
-                .build();
-
-        inner.tokenizers().register("simple", simple_tokenizer);
+        register_tokenizers(&inner, None);
 
         let reader = inner
             .reader_builder()
@@ -131,6 +209,8 @@ impl Index {
             converter,
             budget,
             searchable_paths_cache: std::sync::RwLock::new(None),
+            text_normalization: std::sync::RwLock::new(None),
+            last_compaction: std::sync::Mutex::new(None),
         })
     }
 
@@ -141,24 +221,17 @@ impl Index {
 
     /// Open an existing index with an explicit memory budget.
     pub fn open_with_budget<P: AsRef<Path>>(path: P, budget: Arc<MemoryBudget>) -> Result<Self> {
-        let inner = TantivyIndex::open_in_dir(path)?;
-
-        let edge_ngram_tokenizer =
-            tantivy::tokenizer::TextAnalyzer::builder(crate::tokenizer::CjkAwareTokenizer)
-                .filter(tantivy::tokenizer::LowerCaser)
-                .filter(tantivy::tokenizer::EdgeNgramFilter::new(2, 20).unwrap())
-                .build();
-
-        inner
-            .tokenizers()
-            .register("edge_ngram_lower", edge_ngram_tokenizer);
+        Self::from_tantivy(open_tantivy_index(path.as_ref())?, budget)
+    }
 
-        let simple_tokenizer =
-            tantivy::tokenizer::TextAnalyzer::builder(crate::tokenizer::CjkAwareTokenizer)
-                .filter(tantivy::tokenizer::LowerCaser)
-                .build();
+    /// Open an existing index stored in `directory` rather than a local
+    /// path, such as a cold index's `ColdDirectory`.
+    pub fn open_in_directory<D: tantivy::Directory>(directory: D) -> Result<Self> {
+        Self::from_tantivy(TantivyIndex::open(directory)?, get_global_budget())
+    }
 
-        inner.tokenizers().register("simple", simple_tokenizer);
+    fn from_tantivy(inner: TantivyIndex, budget: Arc<MemoryBudget>) -> Result<Self> {
+        register_tokenizers(&inner, None);
 
         let reader = inner
             .reader_builder()
@@ -175,6 +248,8 @@ impl Index {
             converter,
             budget,
             searchable_paths_cache: std::sync::RwLock::new(None),
+            text_normalization: std::sync::RwLock::new(None),
+            last_compaction: std::sync::Mutex::new(None),
         })
     }
 
@@ -214,6 +289,15 @@ impl Index {
         Arc::clone(&self.converter)
     }
 
+    /// Every live document, including those hidden from search results.
+    pub fn stored_documents(&self) -> document::StoredDocuments {
+        document::StoredDocuments::new(
+            self.reader.searcher(),
+            self.inner.schema(),
+            self.converter(),
+        )
+    }
+
     /// Add a single [`Document`] using an explicit writer.
     ///
     /// You must call `writer.commit()` afterwards to persist, then
@@ -332,4 +416,31 @@ impl Index {
         let mut cache = self.searchable_paths_cache.write().unwrap();
         *cache = None;
     }
+
+    /// When the index was last compacted since it was loaded.
+    pub fn last_compaction(&self) -> Option<std::time::SystemTime> {
+        *self.last_compaction.lock().unwrap()
+    }
+
+    pub(crate) fn record_compaction(&self) {
+        *self.last_compaction.lock().unwrap() = Some(std::time::SystemTime::now());
+    }
+
+    /// Switch the text normalization applied to indexed tokens. Only
+    /// documents written afterwards are affected; existing ones keep the
+    /// tokens they were indexed with until they are rewritten.
+    pub fn set_text_normalization(&self, normalization: Option<TextNormalization>) {
+        let normalization = normalization.map(Arc::new);
+        let mut current = self.text_normalization.write().unwrap();
+        if current.as_deref() == normalization.as_deref() {
+            return;
+        }
+        register_tokenizers(&self.inner, normalization.clone());
+        *current = normalization;
+    }
+
+    /// The normalization indexed tokens go through; queries must match it.
+    pub fn text_normalization(&self) -> Option<Arc<TextNormalization>> {
+        self.text_normalization.read().unwrap().clone()
+    }
 }
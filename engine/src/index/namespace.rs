@@ -0,0 +1,203 @@
+//! Tenant namespaces: several customers on one node, isolated by index-name
+//! prefix.
+//!
+//! Index `products` of namespace `acme` is the tenant `acme~products`, stored
+//! in `{base_path}/acme~products/`. A namespace needs no registration to hold
+//! indexes; registering it in `{base_path}/namespaces.json` attaches the
+//! limits [`IndexManager`](crate::IndexManager) enforces when indexes are
+//! created and documents written.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+
+/// Separates the namespace from the index name in a tenant id.
+pub const NAMESPACE_SEPARATOR: char = '~';
+
+const NAMESPACES_FILE: &str = "namespaces.json";
+const MAX_NAME_LEN: usize = 64;
+
+pub fn validate_namespace(name: &str) -> std::result::Result<(), String> {
+    if name.is_empty()
+        || name.len() > MAX_NAME_LEN
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "namespace must be 1-{} characters of [a-z0-9_-]",
+            MAX_NAME_LEN
+        ));
+    }
+    Ok(())
+}
+
+/// Tenant id of `index_name` in `namespace`.
+pub fn namespaced_tenant(namespace: &str, index_name: &str) -> String {
+    format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, index_name)
+}
+
+/// Split a tenant id into namespace and index name; `None` for tenants
+/// outside any namespace.
+pub fn parse_namespaced_tenant(tenant_id: &str) -> Option<(&str, &str)> {
+    let (namespace, index_name) = tenant_id.split_once(NAMESPACE_SEPARATOR)?;
+    (!namespace.is_empty() && !index_name.is_empty()).then_some((namespace, index_name))
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_indexes: Option<usize>,
+    /// Documents across all of the namespace's indexes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_documents: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Namespace {
+    /// Set from the URL on `PUT /1/namespaces/:name`.
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub limits: NamespaceLimits,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+/// What a namespace holds on this node.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceUsage {
+    pub indexes: usize,
+    pub documents: u64,
+    pub disk_bytes: u64,
+}
+
+/// The registered namespaces of one data directory.
+#[derive(Default)]
+pub struct NamespaceStore {
+    path: PathBuf,
+    namespaces: RwLock<BTreeMap<String, Namespace>>,
+}
+
+impl NamespaceStore {
+    pub fn open(base_path: &Path) -> Result<Self> {
+        let path = base_path.join(NAMESPACES_FILE);
+        let namespaces: Vec<Namespace> = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            path,
+            namespaces: RwLock::new(
+                namespaces
+                    .into_iter()
+                    .map(|n| (n.name.clone(), n))
+                    .collect(),
+            ),
+        })
+    }
+
+    fn save(&self, namespaces: &BTreeMap<String, Namespace>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let list: Vec<&Namespace> = namespaces.values().collect();
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&list)?)?;
+        std::fs::rename(tmp, &self.path)?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<Namespace> {
+        let namespaces = self.namespaces.read().unwrap_or_else(|e| e.into_inner());
+        namespaces.values().cloned().collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Namespace> {
+        let namespaces = self.namespaces.read().unwrap_or_else(|e| e.into_inner());
+        namespaces.get(name).cloned()
+    }
+
+    /// Register a namespace or replace its limits, keeping its creation time.
+    pub fn put(&self, mut namespace: Namespace) -> Result<Namespace> {
+        let mut namespaces = self.namespaces.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(existing) = namespaces.get(&namespace.name) {
+            namespace.created_at = existing.created_at;
+        }
+        let mut updated = namespaces.clone();
+        updated.insert(namespace.name.clone(), namespace.clone());
+        self.save(&updated)?;
+        *namespaces = updated;
+        Ok(namespace)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<bool> {
+        let mut namespaces = self.namespaces.write().unwrap_or_else(|e| e.into_inner());
+        if !namespaces.contains_key(name) {
+            return Ok(false);
+        }
+        let mut updated = namespaces.clone();
+        updated.remove(name);
+        self.save(&updated)?;
+        *namespaces = updated;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tenant_ids_round_trip() {
+        let tenant = namespaced_tenant("acme", "products");
+        assert_eq!(tenant, "acme~products");
+        assert_eq!(parse_namespaced_tenant(&tenant), Some(("acme", "products")));
+        assert_eq!(parse_namespaced_tenant("products"), None);
+        assert_eq!(parse_namespaced_tenant("~products"), None);
+        assert_eq!(parse_namespaced_tenant("products@alice"), None);
+
+        assert!(validate_namespace("acme-eu_1").is_ok());
+        assert!(validate_namespace("").is_err());
+        assert!(validate_namespace("Acme").is_err());
+        assert!(validate_namespace("acme~eu").is_err());
+    }
+
+    #[test]
+    fn store_persists_and_keeps_creation_time() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let store = NamespaceStore::open(tmp.path()).unwrap();
+        store
+            .put(Namespace {
+                name: "acme".to_string(),
+                limits: NamespaceLimits {
+                    max_indexes: Some(2),
+                    max_documents: None,
+                },
+                created_at: 10,
+            })
+            .unwrap();
+        let updated = store
+            .put(Namespace {
+                name: "acme".to_string(),
+                limits: NamespaceLimits::default(),
+                created_at: 20,
+            })
+            .unwrap();
+        assert_eq!(updated.created_at, 10);
+
+        let reopened = NamespaceStore::open(tmp.path()).unwrap();
+        assert_eq!(reopened.get("acme"), Some(updated));
+        assert!(reopened.delete("acme").unwrap());
+        assert!(!reopened.delete("acme").unwrap());
+        assert!(NamespaceStore::open(tmp.path()).unwrap().list().is_empty());
+    }
+}
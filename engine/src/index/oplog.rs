@@ -30,6 +30,8 @@ pub struct OpLog {
     node_id: String,
     current_seq: AtomicU64,
     segment: Mutex<ActiveSegment>,
+    #[cfg(feature = "fault-injection")]
+    faults: Option<std::sync::Arc<crate::faults::FaultInjector>>,
 }
 
 impl OpLog {
@@ -57,9 +59,18 @@ impl OpLog {
                 size: seg_size,
                 id: next_seg_id,
             }),
+            #[cfg(feature = "fault-injection")]
+            faults: None,
         })
     }
 
+    /// Fail appends while `faults` has disk write errors armed.
+    #[cfg(feature = "fault-injection")]
+    pub fn with_faults(mut self, faults: std::sync::Arc<crate::faults::FaultInjector>) -> Self {
+        self.faults = Some(faults);
+        self
+    }
+
     fn scan_existing(dir: &Path) -> crate::error::Result<(u64, u32)> {
         let mut max_seq: u64 = 0;
         let mut max_seg_id: u32 = 0;
@@ -111,6 +122,10 @@ impl OpLog {
     }
 
     pub fn append(&self, op_type: &str, payload: serde_json::Value) -> crate::error::Result<u64> {
+        #[cfg(feature = "fault-injection")]
+        if let Some(faults) = &self.faults {
+            faults.check_disk_write(&self.tenant_id)?;
+        }
         let seq = self.current_seq.fetch_add(1, Ordering::SeqCst) + 1;
         let entry = OpLogEntry {
             seq,
@@ -141,6 +156,10 @@ impl OpLog {
     }
 
     pub fn append_batch(&self, ops: &[(String, serde_json::Value)]) -> crate::error::Result<u64> {
+        #[cfg(feature = "fault-injection")]
+        if let Some(faults) = &self.faults {
+            faults.check_disk_write(&self.tenant_id)?;
+        }
         let mut last_seq = self.current_seq.load(Ordering::SeqCst);
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -301,6 +320,29 @@ mod tests {
         assert_eq!(since1[0].seq, 2);
     }
 
+    #[cfg(feature = "fault-injection")]
+    #[test]
+    fn injected_disk_errors_fail_appends_without_using_a_seq() {
+        let tmp = TempDir::new().unwrap();
+        let faults = std::sync::Arc::new(crate::faults::FaultInjector::new());
+        let oplog = OpLog::open(tmp.path(), "t1", "node1")
+            .unwrap()
+            .with_faults(faults.clone());
+        faults.set(crate::faults::FaultConfig {
+            disk_write_errors: 1,
+            ..Default::default()
+        });
+
+        assert!(oplog
+            .append("upsert", serde_json::json!({"objectID": "1"}))
+            .is_err());
+        let seq = oplog
+            .append("upsert", serde_json::json!({"objectID": "1"}))
+            .unwrap();
+        assert_eq!(seq, 1);
+        assert_eq!(oplog.read_since(0).unwrap().len(), 1);
+    }
+
     #[test]
     fn test_batch_append() {
         let tmp = TempDir::new().unwrap();
@@ -12,6 +12,61 @@ pub struct RelevanceConfig {
     pub attribute_weights: HashMap<String, f32>,
 }
 
+/// One `searchableAttributes` entry. Comma-separated attributes
+/// (`"title,alternate_title"`) share a priority level; `unordered(attr)`
+/// drops the bonus for matching early within the attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchableAttribute {
+    pub paths: Vec<String>,
+    pub ordered: bool,
+}
+
+impl SearchableAttribute {
+    pub fn parse(spec: &str) -> Self {
+        let spec = spec.trim();
+        let (inner, ordered) = match spec
+            .strip_prefix("unordered(")
+            .and_then(|s| s.strip_suffix(')'))
+        {
+            Some(inner) => (inner, false),
+            None => (
+                spec.strip_prefix("ordered(")
+                    .and_then(|s| s.strip_suffix(')'))
+                    .unwrap_or(spec),
+                true,
+            ),
+        };
+        SearchableAttribute {
+            paths: inner
+                .split(',')
+                .map(|p| p.trim().to_string())
+                .filter(|p| !p.is_empty())
+                .collect(),
+            ordered,
+        }
+    }
+
+    /// True for `path` itself and anything nested below it (`brand` covers
+    /// `brand.name`).
+    pub fn covers(&self, path: &str) -> bool {
+        self.paths.iter().any(|p| {
+            path == p
+                || path
+                    .strip_prefix(p.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+    }
+}
+
+/// Parse `searchableAttributes` into priority levels, earliest first.
+pub fn parse_searchable_attributes(specs: &[String]) -> Vec<SearchableAttribute> {
+    specs
+        .iter()
+        .map(|s| SearchableAttribute::parse(s))
+        .filter(|a| !a.paths.is_empty())
+        .collect()
+}
+
 impl RelevanceConfig {
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
         let content = std::fs::read_to_string(path)?;
@@ -22,9 +77,9 @@ impl RelevanceConfig {
     pub fn derive_weights(&self) -> HashMap<String, f32> {
         let mut weights = HashMap::new();
 
-        if let Some(attrs) = &self.searchable_attributes {
-            for (idx, field) in attrs.iter().enumerate() {
-                let default_weight = 100_f32.powi(-(idx as i32));
+        for (idx, attr) in self.searchable_levels().iter().enumerate() {
+            let default_weight = 100_f32.powi(-(idx as i32));
+            for field in &attr.paths {
                 let weight = self
                     .attribute_weights
                     .get(field)
@@ -36,6 +91,32 @@ impl RelevanceConfig {
 
         weights
     }
+
+    /// `searchableAttributes` parsed into priority levels (empty when unset).
+    pub fn searchable_levels(&self) -> Vec<SearchableAttribute> {
+        self.searchable_attributes
+            .as_deref()
+            .map(parse_searchable_attributes)
+            .unwrap_or_default()
+    }
+
+    /// Priority level of an indexed path (0 = most important), or `None`
+    /// when the path is not listed in `searchableAttributes`.
+    pub fn priority_of(&self, path: &str) -> Option<usize> {
+        self.searchable_levels().iter().position(|a| a.covers(path))
+    }
+
+    /// The `attributeWeights` entry for an indexed path: its own, or that of
+    /// the closest attribute it is nested under (`brand` for `brand.name`).
+    pub fn weight_override(&self, path: &str) -> Option<f32> {
+        let mut attr = path;
+        loop {
+            if let Some(weight) = self.attribute_weights.get(attr) {
+                return Some(*weight);
+            }
+            attr = attr.rsplit_once('.')?.0;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -113,6 +194,73 @@ mod tests {
         assert!((w["body"] - 2.0).abs() < 1e-6);
     }
 
+    #[test]
+    fn parse_unordered_and_same_level_attributes() {
+        assert_eq!(
+            SearchableAttribute::parse("unordered(description)"),
+            SearchableAttribute {
+                paths: vec!["description".to_string()],
+                ordered: false,
+            }
+        );
+        assert_eq!(
+            SearchableAttribute::parse("title, alternate_title"),
+            SearchableAttribute {
+                paths: vec!["title".to_string(), "alternate_title".to_string()],
+                ordered: true,
+            }
+        );
+        assert_eq!(
+            SearchableAttribute::parse("ordered(brand)").paths,
+            vec!["brand"]
+        );
+    }
+
+    #[test]
+    fn priority_of_uses_levels_and_nested_paths() {
+        let cfg = RelevanceConfig {
+            searchable_attributes: Some(vec![
+                "title,subtitle".to_string(),
+                "unordered(brand)".to_string(),
+            ]),
+            attribute_weights: HashMap::new(),
+        };
+        assert_eq!(cfg.priority_of("title"), Some(0));
+        assert_eq!(cfg.priority_of("subtitle"), Some(0));
+        assert_eq!(cfg.priority_of("brand.name"), Some(1));
+        assert_eq!(cfg.priority_of("brandname"), None);
+    }
+
+    #[test]
+    fn weight_override_applies_to_nested_paths() {
+        let cfg = RelevanceConfig {
+            searchable_attributes: None,
+            attribute_weights: HashMap::from([
+                ("brand".to_string(), 3.0),
+                ("brand.name".to_string(), 5.0),
+            ]),
+        };
+        assert_eq!(cfg.weight_override("brand.name"), Some(5.0));
+        assert_eq!(cfg.weight_override("brand.name.short"), Some(5.0));
+        assert_eq!(cfg.weight_override("brand.country"), Some(3.0));
+        assert_eq!(cfg.weight_override("brandname"), None);
+    }
+
+    #[test]
+    fn derive_weights_shares_weight_within_level() {
+        let cfg = RelevanceConfig {
+            searchable_attributes: Some(vec![
+                "title,subtitle".to_string(),
+                "unordered(body)".to_string(),
+            ]),
+            attribute_weights: HashMap::new(),
+        };
+        let w = cfg.derive_weights();
+        assert!((w["title"] - 1.0).abs() < 1e-6);
+        assert!((w["subtitle"] - 1.0).abs() < 1e-6);
+        assert!((w["body"] - 0.01).abs() < 1e-6);
+    }
+
     #[test]
     fn config_deserializes_from_json() {
         let json = r#"{"searchableAttributes":["title","body"],"attributeWeights":{"title":10.0}}"#;
@@ -0,0 +1,331 @@
+//! Insights-driven dynamic re-ranking ("learning to rank lite").
+//!
+//! A [`ReRankingModel`] holds per-object engagement priors (clicks and
+//! conversions) aggregated from the analytics event store. It is trained
+//! out-of-band (nightly by default) and persisted as `reranking.json` in the
+//! tenant directory. At query time the top-K keyword candidates are re-scored
+//! with a logistic blend of their text rank, CTR prior, conversion prior and
+//! (optionally) freshness, then re-sorted.
+
+use crate::error::Result;
+use crate::types::{FieldValue, ScoredDocument};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+pub const RERANKING_MODEL_FILE: &str = "reranking.json";
+
+fn default_top_k() -> usize {
+    50
+}
+
+fn default_lookback_days() -> u32 {
+    30
+}
+
+fn default_half_life_days() -> f64 {
+    30.0
+}
+
+/// Per-index re-ranking configuration (`reRanking` in index settings).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReRankingSettings {
+    /// Number of top keyword candidates that are re-scored.
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
+    /// Days of click/conversion history used when training the model.
+    #[serde(default = "default_lookback_days")]
+    pub lookback_days: u32,
+    /// Numeric attribute holding a unix timestamp (seconds) used for freshness.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub freshness_attribute: Option<String>,
+    #[serde(default = "default_half_life_days")]
+    pub freshness_half_life_days: f64,
+    #[serde(default)]
+    pub weights: BlendWeights,
+}
+
+impl Default for ReRankingSettings {
+    fn default() -> Self {
+        ReRankingSettings {
+            top_k: default_top_k(),
+            lookback_days: default_lookback_days(),
+            freshness_attribute: None,
+            freshness_half_life_days: default_half_life_days(),
+            weights: BlendWeights::default(),
+        }
+    }
+}
+
+/// Coefficients of the logistic blend. `text` scores the original keyword
+/// rank, so a large `text` weight keeps re-ranking conservative.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BlendWeights {
+    pub text: f64,
+    pub ctr: f64,
+    pub conversion: f64,
+    pub freshness: f64,
+}
+
+impl Default for BlendWeights {
+    fn default() -> Self {
+        BlendWeights {
+            text: 4.0,
+            ctr: 1.5,
+            conversion: 1.0,
+            freshness: 1.0,
+        }
+    }
+}
+
+/// Aggregated engagement for one objectID over the training window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ObjectEngagement {
+    pub clicks: u64,
+    pub conversions: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReRankingModel {
+    pub trained_at: i64,
+    pub lookback_days: u32,
+    pub max_clicks: u64,
+    pub max_conversions: u64,
+    pub priors: HashMap<String, ObjectEngagement>,
+}
+
+impl ReRankingModel {
+    pub fn train(
+        engagement: HashMap<String, ObjectEngagement>,
+        lookback_days: u32,
+        trained_at: i64,
+    ) -> Self {
+        let max_clicks = engagement.values().map(|e| e.clicks).max().unwrap_or(0);
+        let max_conversions = engagement
+            .values()
+            .map(|e| e.conversions)
+            .max()
+            .unwrap_or(0);
+        ReRankingModel {
+            trained_at,
+            lookback_days,
+            max_clicks,
+            max_conversions,
+            priors: engagement,
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let content = serde_json::to_string(self)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.priors.is_empty()
+    }
+
+    /// Blend score in (0, 1) for the candidate at `rank` out of `k`.
+    pub fn score(
+        &self,
+        rank: usize,
+        k: usize,
+        doc: &ScoredDocument,
+        config: &ReRankingSettings,
+        now_secs: i64,
+    ) -> f64 {
+        let w = &config.weights;
+        let text = if k > 0 {
+            1.0 - rank as f64 / k as f64
+        } else {
+            0.0
+        };
+        let prior = self
+            .priors
+            .get(&doc.document.id)
+            .copied()
+            .unwrap_or_default();
+        let ctr = log_normalized(prior.clicks, self.max_clicks);
+        let conversion = log_normalized(prior.conversions, self.max_conversions);
+        let freshness = config
+            .freshness_attribute
+            .as_deref()
+            .and_then(|attr| doc.document.fields.get(attr))
+            .and_then(timestamp_secs)
+            .map(|ts| freshness_decay(now_secs - ts, config.freshness_half_life_days))
+            .unwrap_or(0.0);
+
+        let z = w.text * text + w.ctr * ctr + w.conversion * conversion + w.freshness * freshness;
+        1.0 / (1.0 + (-z).exp())
+    }
+
+    /// Re-sort the first `top_k` documents by blend score. Documents past
+    /// `top_k` keep their keyword order. Ties keep the original rank.
+    pub fn rerank(&self, docs: &mut [ScoredDocument], config: &ReRankingSettings, now_secs: i64) {
+        let k = config.top_k.min(docs.len());
+        if k < 2 {
+            return;
+        }
+        let mut scored: Vec<(f64, usize)> = docs[..k]
+            .iter()
+            .enumerate()
+            .map(|(rank, doc)| (self.score(rank, k, doc, config, now_secs), rank))
+            .collect();
+        scored.sort_by(|a, b| {
+            b.0.partial_cmp(&a.0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.1.cmp(&b.1))
+        });
+        let reordered: Vec<ScoredDocument> = scored.iter().map(|(_, i)| docs[*i].clone()).collect();
+        docs[..k].clone_from_slice(&reordered);
+    }
+}
+
+fn log_normalized(value: u64, max: u64) -> f64 {
+    if max == 0 {
+        return 0.0;
+    }
+    (1.0 + value as f64).ln() / (1.0 + max as f64).ln()
+}
+
+fn timestamp_secs(value: &FieldValue) -> Option<i64> {
+    match value {
+        FieldValue::Integer(i) => Some(*i),
+        FieldValue::Float(f) => Some(*f as i64),
+        FieldValue::Date(d) => Some(*d),
+        _ => None,
+    }
+}
+
+fn freshness_decay(age_secs: i64, half_life_days: f64) -> f64 {
+    if half_life_days <= 0.0 {
+        return 0.0;
+    }
+    let age_days = age_secs.max(0) as f64 / 86_400.0;
+    0.5_f64.powf(age_days / half_life_days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Document;
+
+    fn doc(id: &str) -> ScoredDocument {
+        ScoredDocument {
+            document: Document {
+                id: id.to_string(),
+                fields: HashMap::new(),
+            },
+            score: 1.0,
+        }
+    }
+
+    fn ids(docs: &[ScoredDocument]) -> Vec<&str> {
+        docs.iter().map(|d| d.document.id.as_str()).collect()
+    }
+
+    #[test]
+    fn empty_model_keeps_keyword_order() {
+        let model = ReRankingModel::default();
+        let mut docs = vec![doc("a"), doc("b"), doc("c")];
+        model.rerank(&mut docs, &ReRankingSettings::default(), 0);
+        assert_eq!(ids(&docs), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn heavily_clicked_doc_moves_up() {
+        let mut engagement = HashMap::new();
+        engagement.insert(
+            "c".to_string(),
+            ObjectEngagement {
+                clicks: 500,
+                conversions: 40,
+            },
+        );
+        let model = ReRankingModel::train(engagement, 30, 0);
+        let mut docs = vec![doc("a"), doc("b"), doc("c")];
+        model.rerank(&mut docs, &ReRankingSettings::default(), 0);
+        assert_eq!(ids(&docs)[0], "c");
+    }
+
+    #[test]
+    fn rerank_only_touches_top_k() {
+        let mut engagement = HashMap::new();
+        engagement.insert(
+            "d".to_string(),
+            ObjectEngagement {
+                clicks: 1000,
+                conversions: 0,
+            },
+        );
+        let model = ReRankingModel::train(engagement, 30, 0);
+        let config = ReRankingSettings {
+            top_k: 2,
+            ..Default::default()
+        };
+        let mut docs = vec![doc("a"), doc("b"), doc("c"), doc("d")];
+        model.rerank(&mut docs, &config, 0);
+        assert_eq!(ids(&docs), vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn freshness_prefers_recent_documents() {
+        let model = ReRankingModel::default();
+        let config = ReRankingSettings {
+            freshness_attribute: Some("publishedAt".to_string()),
+            freshness_half_life_days: 1.0,
+            weights: BlendWeights {
+                text: 0.5,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let now = 10 * 86_400;
+        let mut old = doc("old");
+        old.document
+            .fields
+            .insert("publishedAt".to_string(), FieldValue::Integer(0));
+        let mut fresh = doc("fresh");
+        fresh
+            .document
+            .fields
+            .insert("publishedAt".to_string(), FieldValue::Integer(now));
+        let mut docs = vec![old, fresh];
+        model.rerank(&mut docs, &config, now);
+        assert_eq!(ids(&docs), vec!["fresh", "old"]);
+    }
+
+    #[test]
+    fn model_roundtrips_through_disk() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(RERANKING_MODEL_FILE);
+        let mut engagement = HashMap::new();
+        engagement.insert(
+            "x".to_string(),
+            ObjectEngagement {
+                clicks: 3,
+                conversions: 1,
+            },
+        );
+        let model = ReRankingModel::train(engagement, 7, 1_700_000_000_000);
+        model.save(&path).unwrap();
+        let loaded = ReRankingModel::load(&path).unwrap();
+        assert_eq!(loaded.max_clicks, 3);
+        assert_eq!(loaded.lookback_days, 7);
+        assert_eq!(loaded.priors["x"].conversions, 1);
+    }
+
+    #[test]
+    fn settings_defaults_from_empty_json() {
+        let s: ReRankingSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(s, ReRankingSettings::default());
+    }
+}
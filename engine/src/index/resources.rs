@@ -0,0 +1,204 @@
+//! CPU and memory available to the process, for sizing thread pools, caches
+//! and indexing concurrency.
+//!
+//! In a container the host's core count is the wrong number: a pod limited
+//! to 2 CPUs on a 16-core node would otherwise run 16 runtime workers. The
+//! CPU limit is read from the cgroup quota (v2 `cpu.max`, v1
+//! `cpu.cfs_quota_us`), falling back to the cores the process may run on;
+//! the memory limit comes from [`MemoryObserver`].
+//!
+//! Overrides, all optional:
+//! - `FLAPJACK_CPUS` — CPUs to size for
+//! - `FLAPJACK_WORKER_THREADS` — async runtime workers (default: CPUs)
+//! - `FLAPJACK_BLOCKING_THREADS` — threads for searches and other blocking
+//!   work (default: 4 per CPU, at least 8)
+//! - `FLAPJACK_MEMORY_LIMIT_MB` — memory to size for (see [`MemoryObserver`])
+
+use std::env;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::memory_observer::MemoryObserver;
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// cgroup v1 reports "no limit" as a huge page-aligned number.
+const CGROUP_V1_UNLIMITED: u64 = 1 << 60;
+
+/// Indexing buffers may use this fraction of the memory limit.
+const WRITER_MEMORY_SHARE: usize = 4;
+const DEFAULT_MAX_WRITERS: usize = 40;
+const DEFAULT_FACET_CACHE_CAP: usize = 500;
+const MIN_FACET_CACHE_CAP: usize = 50;
+
+static GLOBAL_LIMITS: OnceLock<ResourceLimits> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceLimits {
+    pub cpus: usize,
+    /// `env`, `cgroup` or `host`.
+    pub cpu_source: &'static str,
+    /// 0 when unknown.
+    pub memory_bytes: usize,
+    pub memory_source: String,
+}
+
+impl ResourceLimits {
+    /// Detected once per process.
+    pub fn global() -> &'static ResourceLimits {
+        GLOBAL_LIMITS.get_or_init(ResourceLimits::detect)
+    }
+
+    pub fn detect() -> Self {
+        let host = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        let (cpus, cpu_source) = match env_usize("FLAPJACK_CPUS") {
+            Some(cpus) => (cpus, "env"),
+            None => match cgroup_cpus(Path::new(CGROUP_ROOT)) {
+                Some(cpus) => (cpus.min(host), "cgroup"),
+                None => (host, "host"),
+            },
+        };
+        let (memory_bytes, memory_source) = MemoryObserver::global().system_memory_limit();
+        ResourceLimits {
+            cpus: cpus.max(1),
+            cpu_source,
+            memory_bytes,
+            memory_source: memory_source.to_string(),
+        }
+    }
+
+    pub fn worker_threads(&self) -> usize {
+        env_usize("FLAPJACK_WORKER_THREADS").unwrap_or(self.cpus)
+    }
+
+    /// Searches run on the blocking pool, so this caps concurrent searches.
+    pub fn blocking_threads(&self) -> usize {
+        env_usize("FLAPJACK_BLOCKING_THREADS").unwrap_or((self.cpus * 4).max(8))
+    }
+
+    /// Concurrent index writers whose `buffer_mb` buffers fit in a quarter
+    /// of the memory limit, up to the usual 40.
+    pub fn max_concurrent_writers(&self, buffer_mb: usize) -> usize {
+        if self.memory_bytes == 0 || buffer_mb == 0 {
+            return DEFAULT_MAX_WRITERS;
+        }
+        let share = self.memory_bytes / WRITER_MEMORY_SHARE;
+        (share / (buffer_mb * 1024 * 1024)).clamp(1, DEFAULT_MAX_WRITERS)
+    }
+
+    /// Facet cache entries: the usual 500 from 2 GB up, fewer below.
+    pub fn facet_cache_cap(&self) -> usize {
+        if self.memory_bytes == 0 {
+            return DEFAULT_FACET_CACHE_CAP;
+        }
+        (self.memory_bytes / (4 * 1024 * 1024)).clamp(MIN_FACET_CACHE_CAP, DEFAULT_FACET_CACHE_CAP)
+    }
+}
+
+fn env_usize(name: &str) -> Option<usize> {
+    env::var(name)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|n| *n > 0)
+}
+
+/// CPUs allowed by the cgroup quota under `root`, rounded up.
+fn cgroup_cpus(root: &Path) -> Option<usize> {
+    if let Ok(contents) = std::fs::read_to_string(root.join("cpu.max")) {
+        return parse_cpu_max(&contents);
+    }
+    let quota = std::fs::read_to_string(root.join("cpu/cpu.cfs_quota_us")).ok()?;
+    let period = std::fs::read_to_string(root.join("cpu/cpu.cfs_period_us")).ok()?;
+    cpus_from_quota(quota.trim().parse().ok()?, period.trim().parse().ok()?)
+}
+
+/// cgroup v2 `cpu.max`: `"<quota> <period>"`, or `"max <period>"` when
+/// unlimited.
+fn parse_cpu_max(contents: &str) -> Option<usize> {
+    let mut parts = contents.split_whitespace();
+    let quota = parts.next()?.parse().ok()?;
+    let period = parts.next()?.parse().ok()?;
+    cpus_from_quota(quota, period)
+}
+
+/// A negative quota (cgroup v1's "unlimited") has no limit.
+fn cpus_from_quota(quota: i64, period: i64) -> Option<usize> {
+    if quota <= 0 || period <= 0 {
+        return None;
+    }
+    Some(((quota + period - 1) / period) as usize)
+}
+
+/// Memory limit of the cgroup under `root`, in bytes.
+fn cgroup_memory_limit(root: &Path) -> Option<usize> {
+    if let Ok(contents) = std::fs::read_to_string(root.join("memory.max")) {
+        return contents.trim().parse().ok();
+    }
+    let contents = std::fs::read_to_string(root.join("memory/memory.limit_in_bytes")).ok()?;
+    let bytes: u64 = contents.trim().parse().ok()?;
+    (bytes < CGROUP_V1_UNLIMITED).then_some(bytes as usize)
+}
+
+/// [`cgroup_memory_limit`] of this process's cgroup.
+pub(crate) fn process_cgroup_memory_limit() -> Option<usize> {
+    cgroup_memory_limit(Path::new(CGROUP_ROOT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(cpus: usize, memory_mb: usize) -> ResourceLimits {
+        ResourceLimits {
+            cpus,
+            cpu_source: "cgroup",
+            memory_bytes: memory_mb * 1024 * 1024,
+            memory_source: "cgroup".to_string(),
+        }
+    }
+
+    #[test]
+    fn cgroup_v2_and_v1_quotas() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let v1 = tmp.path().join("v1");
+        std::fs::create_dir_all(v1.join("cpu")).unwrap();
+        std::fs::create_dir_all(v1.join("memory")).unwrap();
+        std::fs::write(v1.join("cpu/cpu.cfs_quota_us"), "150000\n").unwrap();
+        std::fs::write(v1.join("cpu/cpu.cfs_period_us"), "100000\n").unwrap();
+        std::fs::write(
+            v1.join("memory/memory.limit_in_bytes"),
+            "9223372036854771712\n",
+        )
+        .unwrap();
+        assert_eq!(cgroup_cpus(&v1), Some(2));
+        assert_eq!(cgroup_memory_limit(&v1), None);
+
+        let v2 = tmp.path().join("v2");
+        std::fs::create_dir_all(&v2).unwrap();
+        std::fs::write(v2.join("cpu.max"), "max 100000\n").unwrap();
+        std::fs::write(v2.join("memory.max"), "536870912\n").unwrap();
+        assert_eq!(cgroup_cpus(&v2), None);
+        assert_eq!(cgroup_memory_limit(&v2), Some(512 * 1024 * 1024));
+        std::fs::write(v2.join("cpu.max"), "200000 100000\n").unwrap();
+        assert_eq!(cgroup_cpus(&v2), Some(2));
+
+        assert_eq!(cgroup_cpus(&tmp.path().join("none")), None);
+    }
+
+    #[test]
+    fn sizes_follow_the_limits() {
+        let small = limits(2, 512);
+        assert_eq!(small.max_concurrent_writers(31), 4);
+        assert_eq!(small.facet_cache_cap(), 128);
+
+        let large = limits(16, 64 * 1024);
+        assert_eq!(large.max_concurrent_writers(31), DEFAULT_MAX_WRITERS);
+        assert_eq!(large.facet_cache_cap(), DEFAULT_FACET_CACHE_CAP);
+
+        let unknown = limits(4, 0);
+        assert_eq!(unknown.max_concurrent_writers(31), DEFAULT_MAX_WRITERS);
+        assert_eq!(unknown.facet_cache_cap(), DEFAULT_FACET_CACHE_CAP);
+    }
+}
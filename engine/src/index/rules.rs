@@ -1,6 +1,7 @@
 use crate::error::Result;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -76,6 +77,116 @@ pub struct Consequence {
 pub struct ConsequenceParams {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub query: Option<String>,
+
+    #[serde(rename = "renderingContent", skip_serializing_if = "Option::is_none")]
+    pub rendering_content: Option<RenderingContent>,
+}
+
+/// Merchandising content returned verbatim in the search response's
+/// `renderingContent` block (Algolia-compatible subset). Set per index in
+/// settings and per query by rules.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RenderingContent {
+    #[serde(
+        rename = "facetOrdering",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub facet_ordering: Option<FacetOrdering>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect: Option<Redirect>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub widgets: Option<Widgets>,
+}
+
+/// The order InstantSearch's dynamic widgets lay out facets and their
+/// values in. Only echoed to the client; facet counts are unaffected.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FacetOrdering {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub facets: Option<FacetsOrder>,
+
+    /// Per facet attribute; `*` applies to facets not listed.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub values: BTreeMap<String, ValuesOrder>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FacetsOrder {
+    /// Facet attributes to show first, in order; `*` for all the others.
+    #[serde(default)]
+    pub order: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ValuesOrder {
+    /// Facet values to pin first, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub order: Vec<String>,
+
+    #[serde(
+        rename = "sortRemainingBy",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub sort_remaining_by: Option<SortRemainingBy>,
+
+    /// Facet values never to show.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hide: Vec<String>,
+}
+
+/// How the values not pinned by `order` are sorted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortRemainingBy {
+    Count,
+    Alpha,
+    Hidden,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Redirect {
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Widgets {
+    /// Banner objects (`image`, `link`, ...) are passed through untouched.
+    #[serde(default)]
+    pub banners: Vec<serde_json::Value>,
+}
+
+impl RenderingContent {
+    pub fn is_empty(&self) -> bool {
+        self.facet_ordering.is_none()
+            && self.redirect.is_none()
+            && self
+                .widgets
+                .as_ref()
+                .map(|w| w.banners.is_empty())
+                .unwrap_or(true)
+    }
+
+    /// Fold another rule's content, or the index's, into this one. The first
+    /// facet ordering and redirect win (rules apply in store order, before
+    /// the index settings); banners accumulate.
+    pub fn merge(&mut self, other: &RenderingContent) {
+        if self.facet_ordering.is_none() {
+            self.facet_ordering = other.facet_ordering.clone();
+        }
+        if self.redirect.is_none() {
+            self.redirect = other.redirect.clone();
+        }
+        if let Some(other_widgets) = &other.widgets {
+            self.widgets
+                .get_or_insert_with(Widgets::default)
+                .banners
+                .extend(other_widgets.banners.iter().cloned());
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,19 +248,22 @@ impl Rule {
                 }
             }
 
-            if self.matches_pattern(query_text, &condition.pattern, &condition.anchoring) {
+            if condition.anchoring.matches(query_text, &condition.pattern) {
                 return true;
             }
         }
 
         false
     }
+}
 
-    fn matches_pattern(&self, query_text: &str, pattern: &str, anchoring: &Anchoring) -> bool {
+impl Anchoring {
+    /// Case-insensitive match of `query_text` against a condition pattern.
+    pub fn matches(&self, query_text: &str, pattern: &str) -> bool {
         let query_lower = query_text.to_lowercase();
         let pattern_lower = pattern.to_lowercase();
 
-        match anchoring {
+        match self {
             Anchoring::Is => query_lower == pattern_lower,
             Anchoring::StartsWith => query_lower.starts_with(&pattern_lower),
             Anchoring::EndsWith => query_lower.ends_with(&pattern_lower),
@@ -161,10 +275,14 @@ impl Rule {
 #[derive(Debug, Default, Clone)]
 pub struct RuleEffects {
     pub pins: Vec<(String, usize)>,
+    /// Only pin documents the search itself matched, instead of fetching
+    /// missing ones by objectID regardless of filters.
+    pub pins_from_results_only: bool,
     pub hidden: Vec<String>,
     pub user_data: Vec<serde_json::Value>,
     pub applied_rules: Vec<String>,
     pub query_rewrite: Option<String>,
+    pub rendering_content: Option<RenderingContent>,
 }
 
 pub struct RuleStore {
@@ -309,6 +427,18 @@ impl RuleStore {
             if let Some(user_data) = &rule.consequence.user_data {
                 effects.user_data.push(user_data.clone());
             }
+
+            if let Some(content) = rule
+                .consequence
+                .params
+                .as_ref()
+                .and_then(|p| p.rendering_content.as_ref())
+            {
+                effects
+                    .rendering_content
+                    .get_or_insert_with(RenderingContent::default)
+                    .merge(content);
+            }
         }
 
         effects.pins.sort_by_key(|(_, pos)| *pos);
@@ -528,6 +658,69 @@ mod tests {
         assert_eq!(effects.user_data, vec![json!({"banner": "sale"})]);
     }
 
+    #[test]
+    fn apply_rules_rendering_content_first_redirect_wins_and_banners_merge() {
+        let mut store = RuleStore::new();
+        let mut r1 = rule_with_pattern("r1", "returns", Anchoring::Contains);
+        r1.consequence.params = Some(
+            serde_json::from_value(json!({
+                "renderingContent": {
+                    "redirect": {"url": "https://example.com/returns-policy"},
+                    "widgets": {"banners": [{"image": {"urls": [{"url": "a.png"}]}}]}
+                }
+            }))
+            .unwrap(),
+        );
+        let mut r2 = rule_with_pattern("r2", "returns", Anchoring::Is);
+        r2.consequence.params = Some(
+            serde_json::from_value(json!({
+                "renderingContent": {
+                    "redirect": {"url": "https://example.com/other"},
+                    "widgets": {"banners": [{"link": {"url": "/sale"}}]}
+                }
+            }))
+            .unwrap(),
+        );
+        store.insert(r1);
+        store.insert(r2);
+
+        let effects = store.apply_rules("returns", None);
+        let content = effects.rendering_content.expect("rendering content");
+        assert_eq!(
+            content.redirect.unwrap().url,
+            "https://example.com/returns-policy"
+        );
+        assert_eq!(content.widgets.unwrap().banners.len(), 2);
+    }
+
+    #[test]
+    fn rule_rendering_content_overrides_index_facet_ordering() {
+        let index: RenderingContent = serde_json::from_value(json!({
+            "facetOrdering": {"facets": {"order": ["brand", "color"]}},
+            "redirect": {"url": "https://example.com/index"}
+        }))
+        .unwrap();
+        let mut rule: RenderingContent = serde_json::from_value(json!({
+            "facetOrdering": {"facets": {"order": ["size"]}}
+        }))
+        .unwrap();
+        rule.merge(&index);
+        assert_eq!(rule.facet_ordering.unwrap().facets.unwrap().order, ["size"]);
+        assert_eq!(rule.redirect.unwrap().url, "https://example.com/index");
+        assert!(serde_json::from_value::<RenderingContent>(json!({
+            "facetOrdering": {"values": {"brand": {"sortRemainingBy": "random"}}}
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn apply_rules_without_rendering_content_leaves_none() {
+        let mut store = RuleStore::new();
+        store.insert(rule_with_pattern("r1", "promo", Anchoring::Contains));
+        let effects = store.apply_rules("promo", None);
+        assert!(effects.rendering_content.is_none());
+    }
+
     #[test]
     fn apply_rules_no_match_returns_empty() {
         let mut store = RuleStore::new();
@@ -569,6 +762,7 @@ mod tests {
         let mut rule = rule_with_pattern("r1", "tv", Anchoring::Is);
         rule.consequence.params = Some(ConsequenceParams {
             query: Some("television".to_string()),
+            rendering_content: None,
         });
         store.insert(rule);
 
@@ -584,6 +778,7 @@ mod tests {
         let mut rule = rule_with_pattern("r1", "tv", Anchoring::Is);
         rule.consequence.params = Some(ConsequenceParams {
             query: Some("television".to_string()),
+            rendering_content: None,
         });
         store.insert(rule);
 
@@ -42,20 +42,28 @@ impl S3Config {
 }
 
 pub async fn upload_snapshot(config: &S3Config, index_name: &str, data: &[u8]) -> Result<String> {
-    let bucket = config.bucket_internal()?;
     let timestamp = chrono::Utc::now().format("%Y%m%dT%H%M%SZ");
     let key = format!("snapshots/{}/{}.tar.gz", index_name, timestamp);
-
-    bucket
-        .put_object(&key, data)
-        .await
-        .map_err(|e| crate::error::FlapjackError::S3(format!("S3 upload: {}", e)))?;
+    put_object(config, &key, data).await?;
 
     tracing::info!("Uploaded snapshot s3://{}/{}", config.bucket_name, key);
     Ok(key)
 }
 
 pub async fn download_snapshot(config: &S3Config, key: &str) -> Result<Vec<u8>> {
+    get_object(config, key).await
+}
+
+pub async fn put_object(config: &S3Config, key: &str, data: &[u8]) -> Result<()> {
+    let bucket = config.bucket_internal()?;
+    bucket
+        .put_object(key, data)
+        .await
+        .map_err(|e| crate::error::FlapjackError::S3(format!("S3 upload: {}", e)))?;
+    Ok(())
+}
+
+pub async fn get_object(config: &S3Config, key: &str) -> Result<Vec<u8>> {
     let bucket = config.bucket_internal()?;
     let response = bucket
         .get_object(key)
@@ -99,6 +107,10 @@ pub async fn list_snapshots(config: &S3Config, index_name: &str) -> Result<Vec<S
 }
 
 pub async fn delete_snapshot(config: &S3Config, key: &str) -> Result<()> {
+    delete_object(config, key).await
+}
+
+pub async fn delete_object(config: &S3Config, key: &str) -> Result<()> {
     let bucket = config.bucket_internal()?;
     bucket
         .delete_object(key)
@@ -1,3 +1,11 @@
+use crate::index::compaction::CompactionPolicy;
+use crate::index::document_schema::DocumentSchema;
+use crate::index::duplicates::DuplicateDetection;
+use crate::index::facet_normalization::FacetValueNormalization;
+use crate::index::ingest_transform::IngestTransform;
+use crate::index::relevance::{parse_searchable_attributes, SearchableAttribute};
+use crate::index::reranking::ReRankingSettings;
+use crate::index::rules::RenderingContent;
 use crate::query::plurals::IgnorePluralsValue;
 use crate::query::stopwords::RemoveStopWordsValue;
 use serde::{Deserialize, Serialize, Serializer};
@@ -39,6 +47,10 @@ fn vec_is_empty(v: &[String]) -> bool {
     v.is_empty()
 }
 
+fn is_false(v: &bool) -> bool {
+    !*v
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
@@ -53,6 +65,16 @@ pub struct SemanticSearchSettings {
     pub event_sources: Option<Vec<String>>,
 }
 
+/// Where a `sortBy` value sends a search: to another index holding the same
+/// records ranked differently, or to this index with a `sort` applied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SortReplica {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<Vec<String>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct IndexSettings {
@@ -99,6 +121,16 @@ pub struct IndexSettings {
     #[serde(rename = "minWordSizefor2Typos")]
     pub min_word_size_for_2_typos: u32,
 
+    /// `true`, `false`, `"min"` or `"strict"`; only `false` changes
+    /// matching. Turns off fuzzy matching and split/concatenated-word
+    /// alternatives unless a query overrides it.
+    #[serde(
+        rename = "typoTolerance",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub typo_tolerance: Option<serde_json::Value>,
+
     #[serde(rename = "maxValuesPerFacet")]
     pub max_values_per_facet: u32,
 
@@ -177,6 +209,135 @@ pub struct IndexSettings {
 
     #[serde(rename = "semanticSearch", skip_serializing_if = "Option::is_none")]
     pub semantic_search: Option<SemanticSearchSettings>,
+
+    #[serde(rename = "enableReRanking", default, skip_serializing_if = "is_false")]
+    pub enable_re_ranking: bool,
+
+    #[serde(rename = "reRanking", skip_serializing_if = "Option::is_none")]
+    pub re_ranking: Option<ReRankingSettings>,
+
+    #[serde(
+        rename = "autoCorrectIfNoResults",
+        default,
+        skip_serializing_if = "is_false"
+    )]
+    pub auto_correct_if_no_results: bool,
+
+    /// Detect each document's language at ingest and store it as
+    /// `_detectedLanguage`.
+    #[serde(
+        rename = "autoDetectLanguage",
+        default,
+        skip_serializing_if = "is_false"
+    )]
+    pub auto_detect_language: bool,
+
+    /// NFKC-normalize text (full-width forms, ligatures, ...).
+    #[serde(rename = "normalizeUnicode", default, skip_serializing_if = "is_false")]
+    pub normalize_unicode: bool,
+
+    #[serde(rename = "removeDiacritics", default, skip_serializing_if = "is_false")]
+    pub remove_diacritics: bool,
+
+    /// Letters `removeDiacritics` leaves alone, on top of those that are
+    /// distinct letters in `queryLanguages`.
+    #[serde(
+        rename = "keepDiacriticsOnCharacters",
+        default,
+        skip_serializing_if = "String::is_empty"
+    )]
+    pub keep_diacritics_on_characters: String,
+
+    /// Scripts to transliterate to Latin: `cyrillic`, `greek`.
+    #[serde(
+        rename = "transliterate",
+        default,
+        skip_serializing_if = "vec_is_empty"
+    )]
+    pub transliterate: Vec<String>,
+
+    /// Index camelCase words with their parts and split them in queries.
+    #[serde(rename = "splitCamelCase", default, skip_serializing_if = "is_false")]
+    pub split_camel_case: bool,
+
+    /// Facet attribute -> how its values are canonicalized and displayed.
+    #[serde(
+        rename = "facetValueNormalization",
+        default,
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub facet_value_normalization: HashMap<String, FacetValueNormalization>,
+
+    /// Background compaction policy; `None` leaves compaction to the API.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compaction: Option<CompactionPolicy>,
+
+    /// Reshaping applied to every written record before it is indexed.
+    #[serde(
+        rename = "ingestTransforms",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub ingest_transforms: Vec<IngestTransform>,
+
+    /// JSON Schema written records must satisfy (see `document_schema`).
+    #[serde(
+        rename = "documentSchema",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub document_schema: Option<serde_json::Value>,
+
+    /// Duplicate fingerprinting and the policy for duplicates at ingest.
+    #[serde(
+        rename = "duplicateDetection",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub duplicate_detection: Option<DuplicateDetection>,
+
+    /// Seconds until written documents without `_expiresAt` expire.
+    #[serde(
+        rename = "defaultTtl",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub default_ttl: Option<u64>,
+
+    /// Attributes holding one variant per language (see `localized`).
+    #[serde(
+        rename = "localizedAttributes",
+        default,
+        skip_serializing_if = "vec_is_empty"
+    )]
+    pub localized_attributes: Vec<String>,
+
+    /// Searchable attribute -> ranking weight, replacing the weight its
+    /// position in `searchableAttributes` gives it (100x per level, 1.0 for
+    /// the first). Applies to the attribute and anything nested below it.
+    #[serde(
+        rename = "attributeWeights",
+        default,
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub attribute_weights: HashMap<String, f32>,
+
+    /// `sortBy` value -> the replica it selects.
+    #[serde(
+        rename = "sortReplicas",
+        default,
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub sort_replicas: HashMap<String, SortReplica>,
+
+    /// Facet ordering and redirect echoed in every search response; rules'
+    /// `renderingContent` takes precedence.
+    #[serde(
+        rename = "renderingContent",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rendering_content: Option<RenderingContent>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -211,6 +372,7 @@ impl Default for IndexSettings {
             hits_per_page: 20,
             min_word_size_for_1_typo: 4,
             min_word_size_for_2_typos: 8,
+            typo_tolerance: None,
             max_values_per_facet: 100,
             pagination_limited_to: 1000,
             exact_on_single_word_query: "attribute".to_string(),
@@ -234,6 +396,25 @@ impl Default for IndexSettings {
             embedders: None,
             mode: None,
             semantic_search: None,
+            enable_re_ranking: false,
+            re_ranking: None,
+            auto_correct_if_no_results: false,
+            auto_detect_language: false,
+            normalize_unicode: false,
+            remove_diacritics: false,
+            keep_diacritics_on_characters: String::new(),
+            transliterate: Vec::new(),
+            split_camel_case: false,
+            facet_value_normalization: HashMap::new(),
+            compaction: None,
+            ingest_transforms: Vec::new(),
+            document_schema: None,
+            duplicate_detection: None,
+            default_ttl: None,
+            localized_attributes: Vec::new(),
+            attribute_weights: HashMap::new(),
+            sort_replicas: HashMap::new(),
+            rendering_content: None,
         }
     }
 }
@@ -276,6 +457,124 @@ impl IndexSettings {
             .collect()
     }
 
+    /// Facet attributes that get value counts (everything but `filterOnly`).
+    pub fn countable_facet_set(&self) -> HashSet<String> {
+        let mut set: HashSet<String> = self
+            .attributes_for_faceting
+            .iter()
+            .filter(|s| !s.starts_with("filterOnly("))
+            .map(|s| parse_facet_modifier(s))
+            .collect();
+        if self.auto_detect_language {
+            set.insert(crate::index::language::DETECTED_LANGUAGE_ATTR.to_string());
+        }
+        set
+    }
+
+    /// Counted facet attributes with a `facetValueNormalization` entry.
+    pub fn normalized_facets(&self) -> HashMap<&str, &FacetValueNormalization> {
+        let countable = self.countable_facet_set();
+        self.facet_value_normalization
+            .iter()
+            .filter(|(attr, _)| countable.contains(attr.as_str()))
+            .map(|(attr, n)| (attr.as_str(), n))
+            .collect()
+    }
+
+    /// `searchableAttributes` flattened to plain paths in priority order,
+    /// with `unordered()` modifiers and comma grouping removed.
+    pub fn searchable_paths(&self) -> Option<Vec<String>> {
+        self.searchable_attributes.as_deref().map(|attrs| {
+            parse_searchable_attributes(attrs)
+                .into_iter()
+                .flat_map(|a| a.paths)
+                .collect()
+        })
+    }
+
+    /// Attributes declared with the `unordered()` modifier.
+    pub fn unordered_searchable_attributes(&self) -> Vec<SearchableAttribute> {
+        self.searchable_attributes
+            .as_deref()
+            .map(parse_searchable_attributes)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|a| !a.ordered)
+            .collect()
+    }
+
+    /// The settings changed since `previous` that are applied when a
+    /// document is written, so existing documents must be re-indexed to
+    /// pick them up. Empty when the change only affects queries.
+    ///
+    /// `searchableAttributes` only counts while `autoDetectLanguage` is on:
+    /// every text attribute is indexed, and the list is otherwise applied
+    /// at query time.
+    pub fn reindex_reasons(&self, previous: &IndexSettings) -> Vec<&'static str> {
+        use crate::tokenizer::TextNormalization;
+
+        let mut reasons = Vec::new();
+        if TextNormalization::from_settings(self) != TextNormalization::from_settings(previous) {
+            let changed = [
+                (
+                    "normalizeUnicode",
+                    self.normalize_unicode != previous.normalize_unicode,
+                ),
+                (
+                    "removeDiacritics",
+                    self.remove_diacritics != previous.remove_diacritics,
+                ),
+                (
+                    "keepDiacriticsOnCharacters",
+                    self.keep_diacritics_on_characters != previous.keep_diacritics_on_characters,
+                ),
+                (
+                    "queryLanguages",
+                    self.query_languages != previous.query_languages,
+                ),
+                (
+                    "transliterate",
+                    self.transliterate != previous.transliterate,
+                ),
+                (
+                    "splitCamelCase",
+                    self.split_camel_case != previous.split_camel_case,
+                ),
+            ];
+            reasons.extend(
+                changed
+                    .into_iter()
+                    .filter(|(_, c)| *c)
+                    .map(|(name, _)| name),
+            );
+        }
+        if self.auto_detect_language != previous.auto_detect_language {
+            reasons.push("autoDetectLanguage");
+        } else if self.auto_detect_language
+            && self.searchable_paths() != previous.searchable_paths()
+        {
+            reasons.push("searchableAttributes");
+        }
+        if self.countable_facet_set() != previous.countable_facet_set() {
+            reasons.push("attributesForFaceting");
+        }
+        if self.normalized_facets() != previous.normalized_facets() {
+            reasons.push("facetValueNormalization");
+        }
+        if self.ingest_transforms != previous.ingest_transforms {
+            reasons.push("ingestTransforms");
+        }
+        let fingerprint_inputs = |s: &IndexSettings| {
+            s.duplicate_detection
+                .as_ref()
+                .map(|d| (d.attributes.clone(), d.max_distance))
+        };
+        if fingerprint_inputs(self) != fingerprint_inputs(previous) {
+            reasons.push("duplicateDetection");
+        }
+        reasons
+    }
+
     pub fn default_with_facets(facets: Vec<String>) -> Self {
         Self {
             attributes_for_faceting: facets,
@@ -284,10 +583,8 @@ impl IndexSettings {
     }
 
     pub fn should_retrieve(&self, field: &str) -> bool {
-        if let Some(unretrievable) = &self.unretrievable_attributes {
-            if unretrievable.contains(&field.to_string()) {
-                return false;
-            }
+        if self.is_unretrievable(field) {
+            return false;
         }
 
         if let Some(retrievable) = &self.attributes_to_retrieve {
@@ -300,10 +597,147 @@ impl IndexSettings {
         true
     }
 
+    /// True if `path` is listed in `unretrievableAttributes` or nested under
+    /// an attribute that is (`cost.amount` under `cost`).
+    pub fn is_unretrievable(&self, path: &str) -> bool {
+        self.unretrievable_attributes.iter().flatten().any(|attr| {
+            path == attr
+                || path
+                    .strip_prefix(attr.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+    }
+
+    /// Remove every unretrievable attribute from a hit, or from its
+    /// `_highlightResult`/`_snippetResult` object. Dotted attributes are
+    /// removed from nested objects, including objects inside arrays.
+    pub fn strip_unretrievable(&self, object: &mut serde_json::Map<String, serde_json::Value>) {
+        for attr in self.unretrievable_attributes.iter().flatten() {
+            remove_json_path(object, attr);
+        }
+    }
+
+    pub fn typo_tolerance_enabled(&self) -> bool {
+        match &self.typo_tolerance {
+            Some(serde_json::Value::Bool(false)) => false,
+            Some(serde_json::Value::String(s)) => s != "false",
+            _ => true,
+        }
+    }
+
     pub fn is_neural_search_active(&self) -> bool {
         matches!(self.mode, Some(IndexMode::NeuralSearch))
     }
 
+    /// Effective re-ranking configuration (defaults when `reRanking` is unset).
+    pub fn re_ranking_config(&self) -> ReRankingSettings {
+        self.re_ranking.clone().unwrap_or_default()
+    }
+
+    /// Reject `transliterate` values other than the supported scripts.
+    pub fn validate_transliterate(&self) -> Result<(), String> {
+        use crate::tokenizer::normalize::TRANSLITERATION_SCRIPTS;
+        match self
+            .transliterate
+            .iter()
+            .find(|s| !TRANSLITERATION_SCRIPTS.contains(&s.as_str()))
+        {
+            Some(script) => Err(format!(
+                "transliterate: unsupported script '{}' (expected one of {})",
+                script,
+                TRANSLITERATION_SCRIPTS.join(", ")
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub fn validate_compaction(&self) -> Result<(), String> {
+        match &self.compaction {
+            Some(policy) => policy.validate(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn validate_ingest_transforms(&self) -> Result<(), String> {
+        self.ingest_transforms
+            .iter()
+            .try_for_each(IngestTransform::validate)
+    }
+
+    pub fn validate_duplicate_detection(&self) -> Result<(), String> {
+        match &self.duplicate_detection {
+            Some(config) => config.validate(),
+            None => Ok(()),
+        }
+    }
+
+    /// Reject attribute weights that are not positive finite numbers.
+    pub fn validate_attribute_weights(&self) -> Result<(), String> {
+        match self
+            .attribute_weights
+            .iter()
+            .find(|(_, w)| !w.is_finite() || **w <= 0.0)
+        {
+            Some((attr, weight)) => Err(format!(
+                "attributeWeights: weight of '{}' must be a positive number, got {}",
+                attr, weight
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub fn validate_default_ttl(&self) -> Result<(), String> {
+        match self.default_ttl {
+            Some(0) => Err("defaultTtl must be at least 1 second".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn validate_localized_attributes(&self) -> Result<(), String> {
+        match self
+            .localized_attributes
+            .iter()
+            .find(|a| a.is_empty() || a.as_str() == "objectID")
+        {
+            Some(attribute) => Err(format!(
+                "localizedAttributes: '{}' can't hold language variants",
+                attribute
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub fn validate_sort_replicas(&self) -> Result<(), String> {
+        let mut names: Vec<&String> = self.sort_replicas.keys().collect();
+        names.sort();
+        for name in names {
+            let replica = &self.sort_replicas[name];
+            match (&replica.index, &replica.sort) {
+                (Some(index), None) if !index.is_empty() => {}
+                (None, Some(specs))
+                    if !specs.is_empty()
+                        && specs
+                            .iter()
+                            .all(|s| s.ends_with(":asc") || s.ends_with(":desc")) => {}
+                _ => {
+                    return Err(format!(
+                        "sortReplicas.{}: needs either an index or a sort like [\"price:asc\"]",
+                        name
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The compiled `documentSchema`, if one is set.
+    pub fn compiled_document_schema(&self) -> Result<Option<DocumentSchema>, String> {
+        self.document_schema
+            .as_ref()
+            .map(DocumentSchema::compile)
+            .transpose()
+    }
+
     /// Validate embedder configurations. Returns Ok(()) if no embedders or if
     /// the vector-search feature is not enabled. With the feature, each config
     /// is parsed into EmbedderConfig and validated.
@@ -382,6 +816,24 @@ pub fn detect_embedder_changes(
     changes
 }
 
+fn remove_json_path(object: &mut serde_json::Map<String, serde_json::Value>, path: &str) {
+    object.remove(path);
+    let Some((head, rest)) = path.split_once('.') else {
+        return;
+    };
+    match object.get_mut(head) {
+        Some(serde_json::Value::Object(child)) => remove_json_path(child, rest),
+        Some(serde_json::Value::Array(items)) => {
+            for item in items {
+                if let serde_json::Value::Object(child) = item {
+                    remove_json_path(child, rest);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
 fn parse_facet_modifier(attr: &str) -> String {
     if let Some(stripped) = attr.strip_prefix("filterOnly(") {
         stripped.trim_end_matches(')').to_string()
@@ -405,6 +857,19 @@ mod tests {
         assert_eq!(parse_facet_modifier("searchable(brand)"), "brand");
     }
 
+    #[test]
+    fn unordered_attributes_cover_nested_paths() {
+        let settings = IndexSettings {
+            searchable_attributes: Some(vec!["unordered(brand)".to_string(), "title".to_string()]),
+            ..Default::default()
+        };
+        let unordered = settings.unordered_searchable_attributes();
+        assert!(unordered.iter().any(|a| a.covers("brand")));
+        assert!(unordered.iter().any(|a| a.covers("brand.name")));
+        assert!(!unordered.iter().any(|a| a.covers("title")));
+        assert!(!unordered.iter().any(|a| a.covers("brandName")));
+    }
+
     #[test]
     fn test_facet_set() {
         let settings = IndexSettings {
@@ -422,6 +887,59 @@ mod tests {
         assert!(facets.contains("brand"));
     }
 
+    #[test]
+    fn test_countable_facet_set_excludes_filter_only() {
+        let settings = IndexSettings {
+            attributes_for_faceting: vec![
+                "category".to_string(),
+                "filterOnly(price)".to_string(),
+                "searchable(brand)".to_string(),
+            ],
+            ..Default::default()
+        };
+
+        let countable = settings.countable_facet_set();
+        assert!(countable.contains("category"));
+        assert!(countable.contains("brand"));
+        assert!(!countable.contains("price"));
+
+        let searchable = settings.searchable_facet_set();
+        assert_eq!(searchable.len(), 1);
+        assert!(searchable.contains("brand"));
+    }
+
+    #[test]
+    fn test_unretrievable_attributes_cover_nested_paths() {
+        let settings = IndexSettings {
+            unretrievable_attributes: Some(vec!["cost".to_string(), "pricing.margin".to_string()]),
+            ..Default::default()
+        };
+        assert!(settings.is_unretrievable("cost"));
+        assert!(settings.is_unretrievable("cost.amount"));
+        assert!(!settings.is_unretrievable("costume"));
+        assert!(settings.is_unretrievable("pricing.margin"));
+        assert!(!settings.is_unretrievable("pricing"));
+        assert!(!settings.should_retrieve("cost.amount"));
+
+        let mut hit = serde_json::json!({
+            "objectID": "1",
+            "title": "Shoe",
+            "cost": {"amount": 12},
+            "pricing": {"price": 40, "margin": 28},
+            "variants": [{"cost": 3}]
+        });
+        settings.strip_unretrievable(hit.as_object_mut().unwrap());
+        assert_eq!(
+            hit,
+            serde_json::json!({
+                "objectID": "1",
+                "title": "Shoe",
+                "pricing": {"price": 40},
+                "variants": [{"cost": 3}]
+            })
+        );
+    }
+
     #[test]
     fn test_distinct_value() {
         let bool_false = DistinctValue::Bool(false);
@@ -864,4 +1382,48 @@ mod tests {
             "neuralSearch should be neural"
         );
     }
+
+    #[test]
+    fn reindex_reasons_name_index_time_settings() {
+        let previous = IndexSettings {
+            attributes_for_faceting: vec!["brand".to_string()],
+            searchable_attributes: Some(vec!["name".to_string()]),
+            ..Default::default()
+        };
+
+        let query_time = IndexSettings {
+            searchable_attributes: Some(vec!["name".to_string(), "brand".to_string()]),
+            ignore_plurals: IgnorePluralsValue::All,
+            attributes_for_faceting: vec!["searchable(brand)".to_string()],
+            ..previous.clone()
+        };
+        assert!(query_time.reindex_reasons(&previous).is_empty());
+
+        let index_time = IndexSettings {
+            attributes_for_faceting: vec!["brand".to_string(), "category".to_string()],
+            remove_diacritics: true,
+            ..previous.clone()
+        };
+        assert_eq!(
+            index_time.reindex_reasons(&previous),
+            vec!["removeDiacritics", "attributesForFaceting"]
+        );
+
+        let detecting = IndexSettings {
+            auto_detect_language: true,
+            ..previous.clone()
+        };
+        assert_eq!(
+            detecting.reindex_reasons(&previous),
+            vec!["autoDetectLanguage", "attributesForFaceting"]
+        );
+        let narrowed = IndexSettings {
+            searchable_attributes: Some(vec!["description".to_string()]),
+            ..detecting.clone()
+        };
+        assert_eq!(
+            narrowed.reindex_reasons(&detecting),
+            vec!["searchableAttributes"]
+        );
+    }
 }
@@ -0,0 +1,236 @@
+//! Index statistics for `GET /1/indexes/:index/stats`: document counts,
+//! on-disk bytes per component and the shape of the documents, for capacity
+//! planning.
+//!
+//! Document shape (sizes, attribute counts) comes from the first
+//! [`SAMPLE_DOCUMENTS`] live documents rather than a full scan, so the
+//! endpoint stays cheap on large indexes.
+
+use crate::error::Result;
+use crate::index::storage_size::dir_size_bytes;
+use crate::index::Index;
+use crate::types::field_value_to_json_value;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::SystemTime;
+use tantivy::TantivyDocument;
+
+pub const SAMPLE_DOCUMENTS: usize = 10_000;
+
+/// Upper bounds of the attributes-per-document histogram buckets; the last
+/// bucket is open-ended.
+const ATTRIBUTE_COUNT_BOUNDS: [usize; 8] = [1, 2, 5, 10, 20, 50, 100, 200];
+
+/// Attributes reported individually, largest total size first.
+const MAX_REPORTED_ATTRIBUTES: usize = 100;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexStats {
+    pub documents: u64,
+    /// Deleted documents still taking space until their segments merge.
+    pub deleted_documents: u64,
+    pub segments: usize,
+    pub disk_bytes: DiskUsage,
+    pub sampled_documents: usize,
+    /// Mean JSON size of the sampled documents.
+    pub average_document_bytes: u64,
+    pub attributes_per_document: Vec<HistogramBucket>,
+    pub attributes: Vec<AttributeStats>,
+    /// Last commit of the keyword index.
+    pub last_build_at: Option<String>,
+    /// Last `compact` since the index was loaded.
+    pub last_compaction_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsage {
+    pub total: u64,
+    pub docstore: u64,
+    /// Term dictionaries, postings and positions.
+    pub postings: u64,
+    pub fast_fields: u64,
+    pub field_norms: u64,
+    pub deletes: u64,
+    pub vectors: u64,
+    /// Settings, rules, synonyms, logs and index metadata.
+    pub other: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistogramBucket {
+    pub min: usize,
+    /// `None` for the open-ended last bucket.
+    pub max: Option<usize>,
+    pub documents: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributeStats {
+    pub attribute: String,
+    /// Sampled documents with the attribute.
+    pub documents: usize,
+    pub average_bytes: u64,
+}
+
+pub fn index_stats(index: &Index, path: &Path) -> Result<IndexStats> {
+    let searcher = index.reader().searcher();
+    let segment_readers = searcher.segment_readers();
+    let mut stats = IndexStats {
+        documents: searcher.num_docs(),
+        deleted_documents: segment_readers
+            .iter()
+            .map(|r| r.num_deleted_docs() as u64)
+            .sum(),
+        segments: segment_readers.len(),
+        disk_bytes: disk_usage(path)?,
+        last_build_at: std::fs::metadata(path.join("meta.json"))
+            .and_then(|m| m.modified())
+            .ok()
+            .map(to_rfc3339),
+        last_compaction_at: index.last_compaction().map(to_rfc3339),
+        ..Default::default()
+    };
+
+    let schema = index.inner().schema();
+    let converter = index.converter();
+    let mut counts = vec![0usize; ATTRIBUTE_COUNT_BOUNDS.len() + 1];
+    let mut total_bytes = 0u64;
+    let mut attributes: HashMap<String, (usize, u64)> = HashMap::new();
+    'segments: for reader in segment_readers {
+        let store = reader.get_store_reader(1)?;
+        for doc in store.iter::<TantivyDocument>(reader.alive_bitset()) {
+            if stats.sampled_documents >= SAMPLE_DOCUMENTS {
+                break 'segments;
+            }
+            let doc = converter.from_tantivy(doc?, &schema, String::new())?;
+            stats.sampled_documents += 1;
+
+            let bucket = ATTRIBUTE_COUNT_BOUNDS
+                .iter()
+                .position(|&max| doc.fields.len() <= max)
+                .unwrap_or(ATTRIBUTE_COUNT_BOUNDS.len());
+            counts[bucket] += 1;
+
+            let json = doc.to_json();
+            total_bytes += serde_json::to_vec(&json).map_or(0, |b| b.len() as u64);
+            for (name, value) in &doc.fields {
+                let bytes = serde_json::to_vec(&field_value_to_json_value(value))
+                    .map_or(0, |b| b.len() as u64);
+                let entry = attributes.entry(name.clone()).or_default();
+                entry.0 += 1;
+                entry.1 += bytes;
+            }
+        }
+    }
+
+    if stats.sampled_documents > 0 {
+        stats.average_document_bytes = total_bytes / stats.sampled_documents as u64;
+    }
+    stats.attributes_per_document = counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, documents)| HistogramBucket {
+            min: if i == 0 {
+                0
+            } else {
+                ATTRIBUTE_COUNT_BOUNDS[i - 1] + 1
+            },
+            max: ATTRIBUTE_COUNT_BOUNDS.get(i).copied(),
+            documents,
+        })
+        .collect();
+
+    let mut attributes: Vec<(String, usize, u64)> = attributes
+        .into_iter()
+        .map(|(name, (documents, bytes))| (name, documents, bytes))
+        .collect();
+    attributes.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+    stats.attributes = attributes
+        .into_iter()
+        .take(MAX_REPORTED_ATTRIBUTES)
+        .map(|(attribute, documents, bytes)| AttributeStats {
+            attribute,
+            documents,
+            average_bytes: bytes / documents as u64,
+        })
+        .collect();
+    Ok(stats)
+}
+
+/// Bytes under an index directory, by the component the files belong to.
+fn disk_usage(path: &Path) -> Result<DiskUsage> {
+    let mut usage = DiskUsage::default();
+    if !path.is_dir() {
+        return Ok(usage);
+    }
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            continue;
+        }
+        let entry_path = entry.path();
+        if file_type.is_dir() {
+            let bytes = dir_size_bytes(&entry_path)?;
+            if entry.file_name() == "vectors" {
+                usage.vectors += bytes;
+            } else {
+                usage.other += bytes;
+            }
+            continue;
+        }
+        let bytes = entry.metadata()?.len();
+        let extension = entry_path.extension().and_then(|e| e.to_str());
+        let component = match extension {
+            Some("store") => &mut usage.docstore,
+            Some("idx") | Some("pos") | Some("term") => &mut usage.postings,
+            Some("fast") => &mut usage.fast_fields,
+            Some("fieldnorm") => &mut usage.field_norms,
+            Some("del") => &mut usage.deletes,
+            _ => &mut usage.other,
+        };
+        *component += bytes;
+    }
+    usage.total = usage.docstore
+        + usage.postings
+        + usage.fast_fields
+        + usage.field_norms
+        + usage.deletes
+        + usage.vectors
+        + usage.other;
+    Ok(usage)
+}
+
+fn to_rfc3339(time: SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disk_usage_splits_by_component() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("abc.store"), [0u8; 100]).unwrap();
+        std::fs::write(tmp.path().join("abc.idx"), [0u8; 10]).unwrap();
+        std::fs::write(tmp.path().join("abc.term"), [0u8; 5]).unwrap();
+        std::fs::write(tmp.path().join("abc.fast"), [0u8; 20]).unwrap();
+        std::fs::write(tmp.path().join("settings.json"), [0u8; 7]).unwrap();
+        std::fs::create_dir(tmp.path().join("vectors")).unwrap();
+        std::fs::write(tmp.path().join("vectors/index.usearch"), [0u8; 40]).unwrap();
+
+        let usage = disk_usage(tmp.path()).unwrap();
+        assert_eq!(usage.docstore, 100);
+        assert_eq!(usage.postings, 15);
+        assert_eq!(usage.fast_fields, 20);
+        assert_eq!(usage.vectors, 40);
+        assert_eq!(usage.other, 7);
+        assert_eq!(usage.total, 182);
+    }
+}
@@ -185,22 +185,63 @@ impl SynonymStore {
         (page_items, total)
     }
 
+    /// Synonyms of a single query word: the other members of any regular
+    /// synonym group containing it, plus the targets of one-way synonyms whose
+    /// input is exactly that word. Multi-word synonyms are returned as-is.
+    pub fn alternatives_for(&self, word: &str) -> Vec<String> {
+        let mut alternatives: Vec<String> = Vec::new();
+        for syn in self.synonyms.values() {
+            let candidates: &[String] = match syn {
+                Synonym::Regular { synonyms, .. }
+                    if synonyms.iter().any(|s| s.eq_ignore_ascii_case(word)) =>
+                {
+                    synonyms
+                }
+                Synonym::OneWay {
+                    input, synonyms, ..
+                } if input.eq_ignore_ascii_case(word) => synonyms,
+                _ => continue,
+            };
+            for alt in candidates {
+                let lower = alt.to_lowercase();
+                if !lower.eq_ignore_ascii_case(word) && !alternatives.contains(&lower) {
+                    alternatives.push(lower);
+                }
+            }
+        }
+        alternatives
+    }
+
     pub fn expand_query(&self, query: &str) -> Vec<String> {
+        self.expand_query_with_sources(query)
+            .into_iter()
+            .map(|(q, _)| q)
+            .collect()
+    }
+
+    /// Like [`expand_query`](Self::expand_query), paired with the objectID of
+    /// the synonym that produced each variant (`None` for the query itself).
+    pub fn expand_query_with_sources(&self, query: &str) -> Vec<(String, Option<String>)> {
         let tokens: Vec<&str> = query.split_whitespace().collect();
-        let mut expanded = vec![query.to_string()];
+        let mut expanded: Vec<(String, Option<String>)> = vec![(query.to_string(), None)];
+        let mut push = |new_query: String, object_id: &str| {
+            if !expanded.iter().any(|(q, _)| *q == new_query) {
+                expanded.push((new_query, Some(object_id.to_string())));
+            }
+        };
 
         for syn in self.synonyms.values() {
             match syn {
-                Synonym::Regular { synonyms, .. } => {
+                Synonym::Regular {
+                    object_id,
+                    synonyms,
+                } => {
                     for token in &tokens {
                         for s in synonyms {
                             if s.eq_ignore_ascii_case(token) {
                                 for alt in synonyms {
                                     if !alt.eq_ignore_ascii_case(token) {
-                                        let new_query = query.replace(token, alt);
-                                        if !expanded.contains(&new_query) {
-                                            expanded.push(new_query);
-                                        }
+                                        push(query.replace(token, alt), object_id);
                                     }
                                 }
                             }
@@ -208,16 +249,16 @@ impl SynonymStore {
                     }
                 }
                 Synonym::OneWay {
-                    input, synonyms, ..
+                    object_id,
+                    input,
+                    synonyms,
                 } => {
                     if query.to_lowercase().contains(&input.to_lowercase()) {
                         for s in synonyms {
                             let new_query = query
                                 .to_lowercase()
                                 .replace(&input.to_lowercase(), &s.to_lowercase());
-                            if !expanded.contains(&new_query) {
-                                expanded.push(new_query);
-                            }
+                            push(new_query, object_id);
                         }
                     }
                 }
@@ -227,6 +268,11 @@ impl SynonymStore {
 
         expanded
     }
+
+    /// All synonyms, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &Synonym> {
+        self.synonyms.values()
+    }
 }
 
 #[cfg(test)]
@@ -435,6 +481,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn expand_with_sources_names_the_synonym() {
+        let mut store = SynonymStore::new();
+        store.insert(regular("laptops", &["laptop", "notebook"]));
+        store.insert(oneway("phones", "phone", &["mobile"]));
+
+        let expanded = store.expand_query_with_sources("laptop phone");
+        assert_eq!(expanded[0], ("laptop phone".to_string(), None));
+        assert!(expanded.contains(&("notebook phone".to_string(), Some("laptops".to_string()))));
+        assert!(expanded.contains(&("laptop mobile".to_string(), Some("phones".to_string()))));
+        assert_eq!(expanded.len(), 3);
+    }
+
+    // -- alternatives_for --
+
+    #[test]
+    fn alternatives_for_regular_and_oneway() {
+        let mut store = SynonymStore::new();
+        store.insert(regular("1", &["tv", "television", "flat screen"]));
+        store.insert(oneway("2", "tv", &["telly"]));
+
+        let mut alts = store.alternatives_for("TV");
+        alts.sort();
+        assert_eq!(alts, vec!["flat screen", "telly", "television"]);
+        assert!(store.alternatives_for("telly").is_empty());
+    }
+
     // -- serde round-trip --
 
     #[test]
@@ -1,6 +1,6 @@
 use crate::error::Result;
+use crate::index::task_store::TaskMap;
 use crate::types::{TaskInfo, TaskStatus, TenantId};
-use dashmap::DashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Weak};
 use tokio::sync::mpsc;
@@ -18,7 +18,7 @@ pub enum TaskCommand {
 }
 
 impl TaskQueue {
-    pub fn new(manager: Weak<crate::IndexManager>, tasks: Arc<DashMap<String, TaskInfo>>) -> Self {
+    pub fn new(manager: Weak<crate::IndexManager>, tasks: Arc<TaskMap>) -> Self {
         let (tx, rx) = mpsc::channel(100);
 
         tokio::spawn(process_tasks(rx, tasks.clone(), manager));
@@ -45,7 +45,7 @@ impl TaskQueue {
 
 async fn process_tasks(
     mut rx: mpsc::Receiver<TaskCommand>,
-    tasks: Arc<DashMap<String, TaskInfo>>,
+    tasks: Arc<TaskMap>,
     manager_weak: Weak<crate::IndexManager>,
 ) {
     while let Some(cmd) = rx.recv().await {
@@ -75,7 +75,7 @@ async fn process_export(
     _tenant_id: TenantId,
     _dest_path: PathBuf,
     _manager: Arc<crate::IndexManager>,
-    _tasks: Arc<DashMap<String, TaskInfo>>,
+    _tasks: Arc<TaskMap>,
 ) {
     _tasks.alter(&_task_id, |_, mut t| {
         t.status = TaskStatus::Processing;
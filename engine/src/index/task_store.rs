@@ -0,0 +1,402 @@
+//! Task status that survives restarts.
+//!
+//! [`TaskMap`] is the live task table shared by the manager and write queues.
+//! Every change to a task is also appended to `{base_path}/tasks.jsonl` as
+//! one JSON line; the latest line for a task wins. On startup the log is
+//! replayed into a [`TaskStore`], pruned, and rewritten compactly, so clients
+//! polling a task across a restart still find it.
+//!
+//! History is bounded by `FLAPJACK_TASK_HISTORY_MAX` tasks (default 10000)
+//! and `FLAPJACK_TASK_HISTORY_HOURS` (default 168). Tasks still enqueued or
+//! processing when the previous process stopped are reported as failed,
+//! since their outcome is unknown, unless their write is still pending in a
+//! tenant's write-ahead log: those are replayed on boot and keep their status.
+
+use crate::index::wal;
+use crate::types::{TaskInfo, TaskStatus};
+use dashmap::DashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+pub const TASK_LOG_FILE: &str = "tasks.jsonl";
+pub const INTERRUPTED_MESSAGE: &str = "Task interrupted by a server restart";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskRetention {
+    pub max_tasks: usize,
+    pub max_age: Duration,
+}
+
+impl Default for TaskRetention {
+    fn default() -> Self {
+        Self {
+            max_tasks: 10_000,
+            max_age: Duration::from_secs(7 * 24 * 3600),
+        }
+    }
+}
+
+impl TaskRetention {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let number = |var: &str| std::env::var(var).ok().and_then(|v| v.parse::<u64>().ok());
+        Self {
+            max_tasks: number("FLAPJACK_TASK_HISTORY_MAX")
+                .map(|n| n as usize)
+                .unwrap_or(defaults.max_tasks),
+            max_age: number("FLAPJACK_TASK_HISTORY_HOURS")
+                .map(|h| Duration::from_secs(h * 3600))
+                .unwrap_or(defaults.max_age),
+        }
+    }
+}
+
+struct StoreState {
+    tasks: HashMap<String, TaskInfo>,
+    /// numeric_id -> task id
+    numeric: HashMap<i64, String>,
+    writer: Option<BufWriter<File>>,
+    /// Lines in the log file, live or superseded.
+    log_lines: usize,
+}
+
+/// On-disk task history: an append-only JSON-lines log plus an in-memory
+/// index of the latest record per task.
+pub struct TaskStore {
+    path: PathBuf,
+    retention: TaskRetention,
+    state: Mutex<StoreState>,
+}
+
+impl TaskStore {
+    /// Load the history under `base_path`, marking unfinished tasks that
+    /// won't be replayed from a WAL as interrupted, and compacting the log.
+    pub fn open(base_path: &Path, retention: TaskRetention) -> std::io::Result<Self> {
+        let path = base_path.join(TASK_LOG_FILE);
+        let mut tasks: HashMap<String, TaskInfo> = HashMap::new();
+        if let Ok(file) = File::open(&path) {
+            for line in BufReader::new(file).lines() {
+                let line = line?;
+                // A torn final line from a crash is skipped.
+                if let Ok(task) = serde_json::from_str::<TaskInfo>(&line) {
+                    tasks.insert(task.id.clone(), task);
+                }
+            }
+        }
+        let replayed = if wal::wal_enabled() {
+            wal::pending_task_ids(base_path)
+        } else {
+            HashSet::new()
+        };
+        for task in tasks.values_mut() {
+            if matches!(task.status, TaskStatus::Enqueued | TaskStatus::Processing)
+                && !replayed.contains(&task.id)
+            {
+                task.status = TaskStatus::Failed(INTERRUPTED_MESSAGE.to_string());
+            }
+        }
+
+        let store = Self {
+            path,
+            retention,
+            state: Mutex::new(StoreState {
+                numeric: HashMap::new(),
+                tasks,
+                writer: None,
+                log_lines: 0,
+            }),
+        };
+        {
+            let mut state = store.state.lock().unwrap();
+            if !state.tasks.is_empty() {
+                store.compact(&mut state)?;
+            }
+        }
+        Ok(store)
+    }
+
+    fn prune(&self, state: &mut StoreState) {
+        let cutoff = SystemTime::now()
+            .checked_sub(self.retention.max_age)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        state.tasks.retain(|_, t| t.created_at >= cutoff);
+        if state.tasks.len() > self.retention.max_tasks {
+            let mut by_age: Vec<(SystemTime, String)> = state
+                .tasks
+                .values()
+                .map(|t| (t.created_at, t.id.clone()))
+                .collect();
+            by_age.sort();
+            let excess = by_age.len() - self.retention.max_tasks;
+            for (_, id) in by_age.into_iter().take(excess) {
+                state.tasks.remove(&id);
+            }
+        }
+        state.numeric = state
+            .tasks
+            .values()
+            .map(|t| (t.numeric_id, t.id.clone()))
+            .collect();
+    }
+
+    /// Rewrite the log with one line per retained task.
+    fn compact(&self, state: &mut StoreState) -> std::io::Result<()> {
+        self.prune(state);
+        let tmp = self.path.with_extension("jsonl.tmp");
+        {
+            let mut out = BufWriter::new(File::create(&tmp)?);
+            let mut tasks: Vec<&TaskInfo> = state.tasks.values().collect();
+            tasks.sort_by_key(|t| t.created_at);
+            for task in tasks {
+                serde_json::to_writer(&mut out, task)?;
+                out.write_all(b"\n")?;
+            }
+            out.flush()?;
+        }
+        std::fs::rename(&tmp, &self.path)?;
+        state.log_lines = state.tasks.len();
+        state.writer = None;
+        Ok(())
+    }
+
+    /// Persist the current state of `task`. Unchanged tasks are not rewritten.
+    pub fn record(&self, task: &TaskInfo) -> std::io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(existing) = state.tasks.get(&task.id) {
+            if existing.status == task.status
+                && existing.indexed_documents == task.indexed_documents
+                && existing.rejected_count == task.rejected_count
+            {
+                return Ok(());
+            }
+        }
+        state.numeric.insert(task.numeric_id, task.id.clone());
+        state.tasks.insert(task.id.clone(), task.clone());
+
+        if state.log_lines > 2 * self.retention.max_tasks.max(state.tasks.len()) {
+            return self.compact(&mut state);
+        }
+        if state.writer.is_none() {
+            if let Some(parent) = self.path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            state.writer = Some(BufWriter::new(file));
+        }
+        let writer = state.writer.as_mut().expect("writer opened above");
+        serde_json::to_writer(&mut *writer, task)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        state.log_lines += 1;
+        Ok(())
+    }
+
+    /// Look up a task by id or numeric id.
+    pub fn get(&self, task_id: &str) -> Option<TaskInfo> {
+        let state = self.state.lock().unwrap();
+        if let Some(task) = state.tasks.get(task_id) {
+            return Some(task.clone());
+        }
+        let numeric: i64 = task_id.parse().ok()?;
+        let id = state.numeric.get(&numeric)?;
+        state.tasks.get(id).cloned()
+    }
+
+    /// All retained tasks, oldest first.
+    pub fn all(&self) -> Vec<TaskInfo> {
+        let state = self.state.lock().unwrap();
+        let mut tasks: Vec<TaskInfo> = state.tasks.values().cloned().collect();
+        tasks.sort_by_key(|t| t.created_at);
+        tasks
+    }
+}
+
+/// Live task table. Reads go straight to the underlying `DashMap`; inserts
+/// and updates of a task under its primary id are also written to the
+/// [`TaskStore`] when one is attached.
+#[derive(Default)]
+pub struct TaskMap {
+    tasks: DashMap<String, TaskInfo>,
+    store: Option<TaskStore>,
+}
+
+impl Deref for TaskMap {
+    type Target = DashMap<String, TaskInfo>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tasks
+    }
+}
+
+impl TaskMap {
+    pub fn persistent(store: TaskStore) -> Self {
+        Self {
+            tasks: DashMap::new(),
+            store: Some(store),
+        }
+    }
+
+    pub fn store(&self) -> Option<&TaskStore> {
+        self.store.as_ref()
+    }
+
+    fn persist(&self, key: &str, task: &TaskInfo) {
+        // Numeric-id aliases hold copies of the same task.
+        if key != task.id {
+            return;
+        }
+        if let Some(store) = &self.store {
+            if let Err(e) = store.record(task) {
+                tracing::warn!("Failed to persist task {}: {}", task.id, e);
+            }
+        }
+    }
+
+    pub fn insert(&self, key: String, task: TaskInfo) -> Option<TaskInfo> {
+        self.persist(&key, &task);
+        self.tasks.insert(key, task)
+    }
+
+    pub fn alter(&self, key: &str, f: impl FnOnce(&String, TaskInfo) -> TaskInfo) {
+        self.tasks.alter(key, f);
+        if self.store.is_some() {
+            if let Some(task) = self.tasks.get(key) {
+                self.persist(key, &task);
+            }
+        }
+    }
+
+    /// A live task, or its persisted state if it is no longer in memory.
+    pub fn get_or_load(&self, task_id: &str) -> Option<TaskInfo> {
+        self.tasks
+            .get(task_id)
+            .map(|t| t.clone())
+            .or_else(|| self.store.as_ref().and_then(|s| s.get(task_id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, numeric_id: i64) -> TaskInfo {
+        TaskInfo::new(id.to_string(), numeric_id, 1)
+    }
+
+    #[test]
+    fn tasks_survive_reopen() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let map =
+            TaskMap::persistent(TaskStore::open(tmp.path(), TaskRetention::default()).unwrap());
+        map.insert("task_a_1".into(), task("task_a_1", 11));
+        map.insert("11".into(), task("task_a_1", 11));
+        map.alter("task_a_1", |_, mut t| {
+            t.status = TaskStatus::Succeeded;
+            t.indexed_documents = 1;
+            t
+        });
+        drop(map);
+
+        let store = TaskStore::open(tmp.path(), TaskRetention::default()).unwrap();
+        let loaded = store.get("task_a_1").unwrap();
+        assert_eq!(loaded.status, TaskStatus::Succeeded);
+        assert_eq!(loaded.indexed_documents, 1);
+        assert_eq!(store.get("11").unwrap().id, "task_a_1");
+        assert_eq!(store.all().len(), 1);
+    }
+
+    #[test]
+    fn unfinished_tasks_are_marked_interrupted() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let store = TaskStore::open(tmp.path(), TaskRetention::default()).unwrap();
+        store.record(&task("task_a_1", 1)).unwrap();
+        drop(store);
+
+        let store = TaskStore::open(tmp.path(), TaskRetention::default()).unwrap();
+        assert_eq!(
+            store.get("task_a_1").unwrap().status,
+            TaskStatus::Failed(INTERRUPTED_MESSAGE.to_string())
+        );
+    }
+
+    #[test]
+    fn tasks_pending_in_the_wal_are_not_interrupted() {
+        use crate::index::wal::WriteAheadLog;
+        use crate::index::write_queue::{WriteAction, WriteOp};
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let store = TaskStore::open(tmp.path(), TaskRetention::default()).unwrap();
+        store.record(&task("task_a_1", 1)).unwrap();
+        store.record(&task("task_a_2", 2)).unwrap();
+        drop(store);
+        let wal = WriteAheadLog::open(&tmp.path().join("a")).unwrap();
+        let op = WriteOp {
+            task_id: "task_a_2".to_string(),
+            actions: vec![WriteAction::Delete("1".to_string())],
+        };
+        wal.append(&op, 2).unwrap();
+        drop(wal);
+
+        let store = TaskStore::open(tmp.path(), TaskRetention::default()).unwrap();
+        assert_eq!(
+            store.get("task_a_1").unwrap().status,
+            TaskStatus::Failed(INTERRUPTED_MESSAGE.to_string())
+        );
+        assert_eq!(store.get("task_a_2").unwrap().status, TaskStatus::Enqueued);
+    }
+
+    #[test]
+    fn retention_keeps_newest_tasks_and_compacts() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let retention = TaskRetention {
+            max_tasks: 2,
+            ..Default::default()
+        };
+        let store = TaskStore::open(tmp.path(), retention).unwrap();
+        for i in 0..5 {
+            let mut t = task(&format!("task_a_{}", i), i);
+            t.created_at = SystemTime::UNIX_EPOCH + Duration::from_secs(2_000_000_000 + i as u64);
+            store.record(&t).unwrap();
+        }
+        drop(store);
+
+        let store = TaskStore::open(tmp.path(), retention).unwrap();
+        let ids: Vec<String> = store.all().into_iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec!["task_a_3", "task_a_4"]);
+        let lines = std::fs::read_to_string(tmp.path().join(TASK_LOG_FILE))
+            .unwrap()
+            .lines()
+            .count();
+        assert_eq!(lines, 2);
+    }
+
+    #[test]
+    fn expired_tasks_are_dropped() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let store = TaskStore::open(tmp.path(), TaskRetention::default()).unwrap();
+        let mut old = task("task_a_old", 1);
+        old.created_at = SystemTime::UNIX_EPOCH;
+        store.record(&old).unwrap();
+        drop(store);
+
+        let store = TaskStore::open(tmp.path(), TaskRetention::default()).unwrap();
+        assert!(store.get("task_a_old").is_none());
+    }
+
+    #[test]
+    fn in_memory_map_does_not_touch_disk() {
+        let map = TaskMap::default();
+        map.insert("task_a_1".into(), task("task_a_1", 1));
+        assert!(map.store().is_none());
+        assert_eq!(map.get_or_load("task_a_1").unwrap().numeric_id, 1);
+        assert!(map.get_or_load("missing").is_none());
+    }
+}
@@ -0,0 +1,218 @@
+//! Named index templates for provisioning new indexes.
+//!
+//! A template bundles a settings object with a skeleton of synonyms and rules.
+//! Templates live in a single `templates.json` file under the data directory
+//! (not a subdirectory, which would be picked up as an index) and are applied
+//! once when an index is created with `template: "<name>"`.
+
+use crate::error::{FlapjackError, Result};
+use crate::index::manager::IndexManager;
+use crate::index::rules::{Rule, RuleStore};
+use crate::index::settings::IndexSettings;
+use crate::index::synonyms::{Synonym, SynonymStore};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub const TEMPLATES_FILE: &str = "templates.json";
+
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexTemplate {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub settings: IndexSettings,
+    #[serde(default)]
+    pub synonyms: Vec<Synonym>,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+impl IndexTemplate {
+    /// Write the template's settings, synonyms and rules into `tenant_id`.
+    ///
+    /// Existing synonyms and rules are replaced. Each write is recorded in the
+    /// oplog so replicas converge on the same configuration.
+    pub fn apply(&self, manager: &IndexManager, tenant_id: &str) -> Result<()> {
+        let dir = manager.base_path.join(tenant_id);
+        if !dir.exists() {
+            return Err(FlapjackError::TenantNotFound(tenant_id.to_string()));
+        }
+
+        self.settings.save(dir.join("settings.json"))?;
+        manager.invalidate_settings_cache(tenant_id);
+        manager.invalidate_facet_cache(tenant_id);
+        manager.append_oplog(
+            tenant_id,
+            "settings",
+            serde_json::to_value(&self.settings).unwrap_or_default(),
+        );
+
+        if !self.synonyms.is_empty() {
+            let mut store = SynonymStore::new();
+            for synonym in &self.synonyms {
+                store.insert(synonym.clone());
+            }
+            store.save(dir.join("synonyms.json"))?;
+            manager.invalidate_synonyms_cache(tenant_id);
+            manager.append_oplog(
+                tenant_id,
+                "save_synonyms",
+                serde_json::json!({"synonyms": self.synonyms, "replace": true}),
+            );
+        }
+
+        if !self.rules.is_empty() {
+            let mut store = RuleStore::new();
+            for rule in &self.rules {
+                store.insert(rule.clone());
+            }
+            store.save(&dir.join("rules.json"))?;
+            manager.invalidate_rules_cache(tenant_id);
+            manager.append_oplog(
+                tenant_id,
+                "save_rules",
+                serde_json::json!({"rules": self.rules, "clearExisting": true}),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// File-backed store of named templates (`{data_dir}/templates.json`).
+pub struct TemplateStore {
+    path: PathBuf,
+}
+
+impl TemplateStore {
+    pub fn new(base_dir: &Path) -> Self {
+        Self {
+            path: base_dir.join(TEMPLATES_FILE),
+        }
+    }
+
+    pub fn list(&self) -> Result<Vec<IndexTemplate>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let json = std::fs::read_to_string(&self.path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<IndexTemplate>> {
+        Ok(self.list()?.into_iter().find(|t| t.name == name))
+    }
+
+    fn save_all(&self, templates: &[IndexTemplate]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(templates)?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(tmp, &self.path)?;
+        Ok(())
+    }
+
+    /// Insert or replace the template with the same name.
+    pub fn put(&self, template: IndexTemplate) -> Result<()> {
+        let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut all = self.list()?;
+        match all.iter_mut().find(|t| t.name == template.name) {
+            Some(existing) => *existing = template,
+            None => all.push(template),
+        }
+        self.save_all(&all)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<bool> {
+        let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut all = self.list()?;
+        let before = all.len();
+        all.retain(|t| t.name != name);
+        if all.len() == before {
+            return Ok(false);
+        }
+        self.save_all(&all)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn template(name: &str) -> IndexTemplate {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "settings": {"searchableAttributes": ["title", "brand"]},
+            "synonyms": [
+                {"objectID": "tv", "type": "synonym", "synonyms": ["tv", "television"]}
+            ],
+            "rules": [{
+                "objectID": "promo",
+                "conditions": [{"pattern": "sale", "anchoring": "contains"}],
+                "consequence": {"params": {"query": "discount"}}
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn store_put_get_delete() {
+        let tmp = TempDir::new().unwrap();
+        let store = TemplateStore::new(tmp.path());
+        assert!(store.list().unwrap().is_empty());
+
+        store.put(template("ecommerce-default")).unwrap();
+        store.put(template("blog")).unwrap();
+        let mut replaced = template("blog");
+        replaced.description = Some("v2".to_string());
+        store.put(replaced).unwrap();
+
+        assert_eq!(store.list().unwrap().len(), 2);
+        let blog = store.get("blog").unwrap().unwrap();
+        assert_eq!(blog.description.as_deref(), Some("v2"));
+
+        assert!(store.delete("blog").unwrap());
+        assert!(!store.delete("blog").unwrap());
+        assert!(store.get("blog").unwrap().is_none());
+        assert!(store.get("ecommerce-default").unwrap().is_some());
+    }
+
+    #[test]
+    fn apply_writes_settings_synonyms_and_rules() {
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        manager.create_tenant("shop").unwrap();
+
+        template("ecommerce-default")
+            .apply(&manager, "shop")
+            .unwrap();
+
+        let dir = tmp.path().join("shop");
+        let settings = IndexSettings::load(dir.join("settings.json")).unwrap();
+        assert_eq!(
+            settings.searchable_attributes,
+            Some(vec!["title".to_string(), "brand".to_string()])
+        );
+        let synonyms = SynonymStore::load(dir.join("synonyms.json")).unwrap();
+        assert!(synonyms.get("tv").is_some());
+        let rules = RuleStore::load(&dir.join("rules.json")).unwrap();
+        assert!(rules.get("promo").is_some());
+    }
+
+    #[test]
+    fn apply_to_missing_index_fails() {
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        let err = template("t").apply(&manager, "missing").unwrap_err();
+        assert!(matches!(err, FlapjackError::TenantNotFound(_)));
+    }
+}
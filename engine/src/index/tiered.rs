@@ -0,0 +1,703 @@
+//! Tiered storage: rarely-searched indexes demoted to object storage.
+//!
+//! Demoting an index uploads its segment files to `cold/{tenant}/` in the
+//! snapshot bucket, records them in `{tenant}/cold.json` and deletes the
+//! local copies. Everything else — `meta.json`, settings, rules, the oplog —
+//! stays on local disk, so a cold index still lists and serves its settings
+//! without touching the bucket.
+//!
+//! Cold indexes open through [`ColdDirectory`], which downloads a segment
+//! file the first time tantivy reads from it. Downloads land in the
+//! [`SegmentCache`] shared by all cold indexes of the node, which is bounded
+//! by `FLAPJACK_SEGMENT_CACHE_MB` and evicts the least recently used files.
+//! Writing to a cold index promotes it back to local disk first.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use tantivy::directory::error::{DeleteError, LockError, OpenReadError, OpenWriteError};
+use tantivy::directory::{
+    Directory, DirectoryLock, FileHandle, Lock, MmapDirectory, OwnedBytes, WatchCallback,
+    WatchHandle, WritePtr,
+};
+use tantivy::HasLen;
+
+use crate::error::{FlapjackError, Result};
+use crate::index::s3::{self, S3Config};
+
+/// Present in a tenant directory while the index is cold.
+pub const COLD_MANIFEST: &str = "cold.json";
+
+const SEGMENT_CACHE_DIR: &str = ".segment_cache";
+const DEFAULT_SEGMENT_CACHE_MB: u64 = 10 * 1024;
+
+/// Blocking object storage for cold segment files.
+pub trait ColdStorage: Send + Sync {
+    fn get(&self, key: &str) -> Result<Vec<u8>>;
+    fn put(&self, key: &str, data: &[u8]) -> Result<()>;
+    fn delete(&self, key: &str) -> Result<()>;
+}
+
+/// [`ColdStorage`] in the S3 bucket snapshots go to.
+pub struct S3ColdStorage {
+    config: S3Config,
+}
+
+impl S3ColdStorage {
+    pub fn new(config: S3Config) -> Self {
+        Self { config }
+    }
+}
+
+impl ColdStorage for S3ColdStorage {
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let (config, key) = (self.config.clone(), key.to_string());
+        run(async move { s3::get_object(&config, &key).await })
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        let (config, key, data) = (self.config.clone(), key.to_string(), data.to_vec());
+        run(async move { s3::put_object(&config, &key, &data).await })
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        let (config, key) = (self.config.clone(), key.to_string());
+        run(async move { s3::delete_object(&config, &key).await })
+    }
+}
+
+/// Run `fut` on a runtime of its own and wait for the result. Segment reads
+/// happen inside tantivy's synchronous `Directory` calls, which may be on a
+/// thread that is already running a runtime, where `block_on` would panic.
+fn run<T: Send + 'static>(
+    fut: impl std::future::Future<Output = Result<T>> + Send + 'static,
+) -> Result<T> {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    let runtime = RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("flapjack-cold-storage")
+            .enable_all()
+            .build()
+            .expect("failed to build the cold storage runtime")
+    });
+    let (tx, rx) = std::sync::mpsc::channel();
+    runtime.spawn(async move {
+        let _ = tx.send(fut.await);
+    });
+    rx.recv()
+        .map_err(|_| FlapjackError::S3("cold storage request was dropped".to_string()))?
+}
+
+/// Object key of a cold segment file.
+pub fn object_key(tenant_id: &str, file_name: &str) -> String {
+    format!("cold/{}/{}", tenant_id, file_name)
+}
+
+/// Tantivy names segment files `{segment uuid}.{component}`, with delete
+/// files also carrying an opstamp: `{segment uuid}.{opstamp}.del`.
+fn is_segment_file(name: &str) -> bool {
+    match name.split_once('.') {
+        Some((stem, _)) => stem.len() == 32 && stem.chars().all(|c| c.is_ascii_hexdigit()),
+        None => false,
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColdManifest {
+    /// Segment file name → size in bytes.
+    pub files: BTreeMap<String, u64>,
+    /// Documents at demotion, so listing the index needn't open it.
+    pub documents: u64,
+    pub demoted_at: i64,
+}
+
+impl ColdManifest {
+    /// The manifest of the index in `tenant_dir`; `None` when it is not cold.
+    pub fn load(tenant_dir: &Path) -> Result<Option<Self>> {
+        let path = tenant_dir.join(COLD_MANIFEST);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&std::fs::read_to_string(path)?)?))
+    }
+
+    fn save(&self, tenant_dir: &Path) -> Result<()> {
+        let tmp = tenant_dir.join(format!("{}.tmp", COLD_MANIFEST));
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(tmp, tenant_dir.join(COLD_MANIFEST))?;
+        Ok(())
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.files.values().sum()
+    }
+}
+
+/// Upload the segment files of the index in `tenant_dir` and replace them
+/// with a manifest. Nothing may be writing to the index.
+pub fn demote(
+    tenant_dir: &Path,
+    tenant_id: &str,
+    documents: u64,
+    storage: &dyn ColdStorage,
+) -> Result<ColdManifest> {
+    let mut files = BTreeMap::new();
+    for entry in std::fs::read_dir(tenant_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !entry.file_type()?.is_file() || !is_segment_file(&name) {
+            continue;
+        }
+        let data = std::fs::read(entry.path())?;
+        storage.put(&object_key(tenant_id, &name), &data)?;
+        files.insert(name, data.len() as u64);
+    }
+    let manifest = ColdManifest {
+        files,
+        documents,
+        demoted_at: chrono::Utc::now().timestamp_millis(),
+    };
+    manifest.save(tenant_dir)?;
+    for name in manifest.files.keys() {
+        std::fs::remove_file(tenant_dir.join(name))?;
+    }
+    Ok(manifest)
+}
+
+/// Bring the segment files of a cold index back to `tenant_dir`, then drop
+/// the manifest and the uploaded copies.
+pub fn promote(
+    tenant_dir: &Path,
+    tenant_id: &str,
+    manifest: &ColdManifest,
+    storage: &dyn ColdStorage,
+    cache: &SegmentCache,
+) -> Result<()> {
+    for name in manifest.files.keys() {
+        let key = object_key(tenant_id, name);
+        let data = match cache.cached_path(&key).and_then(|p| std::fs::read(p).ok()) {
+            Some(data) => data,
+            None => storage.get(&key)?,
+        };
+        let tmp = tenant_dir.join(format!("{}.promoting", name));
+        std::fs::write(&tmp, &data)?;
+        std::fs::rename(tmp, tenant_dir.join(name))?;
+    }
+    std::fs::remove_file(tenant_dir.join(COLD_MANIFEST))?;
+    cache.remove_prefix(&object_key(tenant_id, ""));
+    delete_objects(tenant_id, manifest, storage);
+    Ok(())
+}
+
+/// Best-effort removal of a cold index's uploaded segment files.
+pub fn delete_objects(tenant_id: &str, manifest: &ColdManifest, storage: &dyn ColdStorage) {
+    for name in manifest.files.keys() {
+        let key = object_key(tenant_id, name);
+        if let Err(e) = storage.delete(&key) {
+            tracing::warn!("Failed to delete cold segment {}: {}", key, e);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentCacheStats {
+    pub files: usize,
+    pub bytes: u64,
+    pub capacity_bytes: u64,
+}
+
+struct CacheEntry {
+    bytes: u64,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    bytes: u64,
+    clock: u64,
+}
+
+/// Local copies of cold segment files, stored under their object keys and
+/// evicted least recently used first once over capacity.
+pub struct SegmentCache {
+    dir: PathBuf,
+    capacity_bytes: u64,
+    state: Mutex<CacheState>,
+}
+
+impl SegmentCache {
+    /// `{base_path}/.segment_cache`, sized by `FLAPJACK_SEGMENT_CACHE_MB`.
+    pub fn from_env(base_path: &Path) -> Result<Self> {
+        let mb = std::env::var("FLAPJACK_SEGMENT_CACHE_MB")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_SEGMENT_CACHE_MB);
+        Self::open(base_path.join(SEGMENT_CACHE_DIR), mb * 1024 * 1024)
+    }
+
+    /// Open the cache in `dir`, keeping the files a previous run left there.
+    pub fn open(dir: PathBuf, capacity_bytes: u64) -> Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        let mut found = Vec::new();
+        collect_cached_files(&dir, &dir, &mut found)?;
+        // Oldest first, so that the clock orders them by last modification.
+        found.sort_by_key(|(_, _, modified)| *modified);
+        let mut state = CacheState::default();
+        for (key, bytes, _) in found {
+            state.clock += 1;
+            state.bytes += bytes;
+            state.entries.insert(
+                key,
+                CacheEntry {
+                    bytes,
+                    last_used: state.clock,
+                },
+            );
+        }
+        let cache = Self {
+            dir,
+            capacity_bytes,
+            state: Mutex::new(state),
+        };
+        cache.evict(
+            &mut cache.state.lock().unwrap_or_else(|e| e.into_inner()),
+            None,
+        );
+        Ok(cache)
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(key)
+    }
+
+    /// The local copy of `key`, if cached. Counts as a use.
+    pub fn cached_path(&self, key: &str) -> Option<PathBuf> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.clock += 1;
+        let clock = state.clock;
+        state.entries.get_mut(key)?.last_used = clock;
+        Some(self.path(key))
+    }
+
+    /// The local copy of `key`, downloaded from `storage` on a miss.
+    pub fn fetch(&self, key: &str, storage: &dyn ColdStorage) -> Result<PathBuf> {
+        if let Some(path) = self.cached_path(key) {
+            return Ok(path);
+        }
+        let data = storage.get(key)?;
+        let path = self.path(key);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let part = self.dir.join(format!("{}.part", uuid::Uuid::new_v4()));
+        std::fs::write(&part, &data)?;
+        std::fs::rename(&part, &path)?;
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.clock += 1;
+        let entry = CacheEntry {
+            bytes: data.len() as u64,
+            last_used: state.clock,
+        };
+        state.bytes += entry.bytes;
+        if let Some(replaced) = state.entries.insert(key.to_string(), entry) {
+            state.bytes -= replaced.bytes;
+        }
+        self.evict(&mut state, Some(key));
+        Ok(path)
+    }
+
+    /// Drop least recently used files, other than `keep`, until under
+    /// capacity.
+    fn evict(&self, state: &mut CacheState, keep: Option<&str>) {
+        while state.bytes > self.capacity_bytes {
+            let victim = state
+                .entries
+                .iter()
+                .filter(|(key, _)| Some(key.as_str()) != keep)
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            let Some(victim) = victim else { break };
+            if let Some(entry) = state.entries.remove(&victim) {
+                state.bytes -= entry.bytes;
+            }
+            let _ = std::fs::remove_file(self.path(&victim));
+        }
+    }
+
+    /// Drop every file whose key starts with `prefix`.
+    pub fn remove_prefix(&self, prefix: &str) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let keys: Vec<String> = state
+            .entries
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+        for key in keys {
+            if let Some(entry) = state.entries.remove(&key) {
+                state.bytes -= entry.bytes;
+            }
+            let _ = std::fs::remove_file(self.path(&key));
+        }
+    }
+
+    pub fn stats(&self) -> SegmentCacheStats {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        SegmentCacheStats {
+            files: state.entries.len(),
+            bytes: state.bytes,
+            capacity_bytes: self.capacity_bytes,
+        }
+    }
+}
+
+/// Cached files under `dir` as (key, size, modified); half-written downloads
+/// are deleted.
+fn collect_cached_files(
+    root: &Path,
+    dir: &Path,
+    found: &mut Vec<(String, u64, std::time::SystemTime)>,
+) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            collect_cached_files(root, &path, found)?;
+        } else if path.extension().is_some_and(|ext| ext == "part") {
+            let _ = std::fs::remove_file(&path);
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let key = relative.to_string_lossy().replace('\\', "/");
+            let modified = metadata.modified().unwrap_or(std::time::UNIX_EPOCH);
+            found.push((key, metadata.len(), modified));
+        }
+    }
+    Ok(())
+}
+
+/// Read-only view of a cold index: its segment files come from the
+/// [`SegmentCache`], everything else from the local tenant directory.
+#[derive(Clone)]
+pub struct ColdDirectory {
+    local: MmapDirectory,
+    tenant_id: String,
+    manifest: Arc<ColdManifest>,
+    storage: Arc<dyn ColdStorage>,
+    cache: Arc<SegmentCache>,
+}
+
+impl fmt::Debug for ColdDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ColdDirectory")
+            .field("tenant_id", &self.tenant_id)
+            .field("files", &self.manifest.files.len())
+            .finish()
+    }
+}
+
+impl ColdDirectory {
+    pub fn open(
+        tenant_dir: &Path,
+        tenant_id: &str,
+        manifest: ColdManifest,
+        storage: Arc<dyn ColdStorage>,
+        cache: Arc<SegmentCache>,
+    ) -> Result<Self> {
+        Ok(Self {
+            local: MmapDirectory::open(tenant_dir).map_err(tantivy::TantivyError::from)?,
+            tenant_id: tenant_id.to_string(),
+            manifest: Arc::new(manifest),
+            storage,
+            cache,
+        })
+    }
+
+    fn cold_file_len(&self, path: &Path) -> Option<u64> {
+        self.manifest.files.get(path.to_str()?).copied()
+    }
+}
+
+impl Directory for ColdDirectory {
+    fn get_file_handle(
+        &self,
+        path: &Path,
+    ) -> std::result::Result<Arc<dyn FileHandle>, OpenReadError> {
+        let Some(len) = self.cold_file_len(path) else {
+            return self.local.get_file_handle(path);
+        };
+        Ok(Arc::new(ColdFile {
+            key: object_key(&self.tenant_id, &path.to_string_lossy()),
+            len: len as usize,
+            storage: Arc::clone(&self.storage),
+            cache: Arc::clone(&self.cache),
+            file: OnceLock::new(),
+        }))
+    }
+
+    fn delete(&self, path: &Path) -> std::result::Result<(), DeleteError> {
+        self.local.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> std::result::Result<bool, OpenReadError> {
+        if self.cold_file_len(path).is_some() {
+            return Ok(true);
+        }
+        self.local.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> std::result::Result<WritePtr, OpenWriteError> {
+        self.local.open_write(path)
+    }
+
+    fn atomic_read(&self, path: &Path) -> std::result::Result<Vec<u8>, OpenReadError> {
+        self.local.atomic_read(path)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.local.atomic_write(path, data)
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> std::result::Result<DirectoryLock, LockError> {
+        self.local.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> tantivy::Result<WatchHandle> {
+        self.local.watch(watch_callback)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.local.sync_directory()
+    }
+}
+
+/// A cold segment file, downloaded on its first read. The open descriptor
+/// keeps it readable after the cache evicts it.
+struct ColdFile {
+    key: String,
+    len: usize,
+    storage: Arc<dyn ColdStorage>,
+    cache: Arc<SegmentCache>,
+    file: OnceLock<File>,
+}
+
+impl fmt::Debug for ColdFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ColdFile")
+            .field("key", &self.key)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl ColdFile {
+    fn file(&self) -> io::Result<&File> {
+        if let Some(file) = self.file.get() {
+            return Ok(file);
+        }
+        let file = self
+            .open_cached()
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        Ok(self.file.get_or_init(|| file))
+    }
+
+    fn open_cached(&self) -> Result<File> {
+        // A concurrent fetch can evict the file between download and open.
+        for _ in 0..2 {
+            let path = self.cache.fetch(&self.key, self.storage.as_ref())?;
+            match File::open(&path) {
+                Ok(file) => return Ok(file),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(FlapjackError::Io(format!(
+            "{} was evicted from the segment cache before it could be opened",
+            self.key
+        )))
+    }
+}
+
+impl HasLen for ColdFile {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl FileHandle for ColdFile {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        let mut buf = vec![0u8; range.len()];
+        read_exact_at(self.file()?, &mut buf, range.start as u64)?;
+        Ok(OwnedBytes::new(buf))
+    }
+}
+
+/// Fill `buf` from `offset` without moving a cursor other readers of the
+/// shared handle depend on.
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+
+/// Fill `buf` from `offset`. `seek_read` moves the handle's cursor, but every
+/// read here names its offset, so concurrent readers are unaffected.
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// [`ColdStorage`] in memory, counting downloads.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct MemoryColdStorage {
+    pub objects: Mutex<HashMap<String, Vec<u8>>>,
+    pub gets: std::sync::atomic::AtomicUsize,
+}
+
+#[cfg(test)]
+impl ColdStorage for MemoryColdStorage {
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        self.gets.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        self.objects
+            .lock()
+            .unwrap()
+            .get(key)
+            .cloned()
+            .ok_or_else(|| FlapjackError::S3(format!("no such key: {}", key)))
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        self.objects
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), data.to_vec());
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        self.objects.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::Ordering;
+    use tantivy::schema::{Schema, STORED, TEXT};
+    use tantivy::{doc, Index, TantivyDocument};
+
+    #[test]
+    fn cache_evicts_least_recently_used() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let storage = MemoryColdStorage::default();
+        for key in ["cold/t/a", "cold/t/b", "cold/t/c"] {
+            storage.put(key, &[0u8; 400]).unwrap();
+        }
+        let cache = SegmentCache::open(tmp.path().join("cache"), 1000).unwrap();
+        cache.fetch("cold/t/a", &storage).unwrap();
+        cache.fetch("cold/t/b", &storage).unwrap();
+        cache.fetch("cold/t/a", &storage).unwrap();
+        assert_eq!(storage.gets.load(Ordering::SeqCst), 2);
+
+        cache.fetch("cold/t/c", &storage).unwrap();
+        assert!(cache.cached_path("cold/t/b").is_none());
+        assert!(!tmp.path().join("cache/cold/t/b").exists());
+        assert_eq!(cache.stats().files, 2);
+        assert_eq!(cache.stats().bytes, 800);
+
+        let reopened = SegmentCache::open(tmp.path().join("cache"), 1000).unwrap();
+        assert!(reopened.cached_path("cold/t/a").is_some());
+        assert!(reopened.cached_path("cold/t/c").is_some());
+    }
+
+    #[test]
+    fn demoted_index_reads_lazily_and_promotes_back() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let tenant_dir = tmp.path().join("t");
+        std::fs::create_dir_all(&tenant_dir).unwrap();
+        let mut builder = Schema::builder();
+        let title = builder.add_text_field("title", TEXT | STORED);
+        let index = Index::create_in_dir(&tenant_dir, builder.build()).unwrap();
+        let mut writer = index.writer(15_000_000).unwrap();
+        writer.add_document(doc!(title => "archived")).unwrap();
+        writer.commit().unwrap();
+        drop(writer);
+        drop(index);
+
+        let storage = Arc::new(MemoryColdStorage::default());
+        let manifest = demote(&tenant_dir, "t", 1, storage.as_ref()).unwrap();
+        assert!(!manifest.files.is_empty());
+        assert!(manifest.files.keys().all(|f| !tenant_dir.join(f).exists()));
+        assert!(tenant_dir.join("meta.json").exists());
+        assert_eq!(
+            ColdManifest::load(&tenant_dir).unwrap(),
+            Some(manifest.clone())
+        );
+
+        let cache = Arc::new(SegmentCache::open(tmp.path().join("cache"), 1 << 30).unwrap());
+        let dir = ColdDirectory::open(
+            &tenant_dir,
+            "t",
+            manifest.clone(),
+            storage.clone(),
+            Arc::clone(&cache),
+        )
+        .unwrap();
+        let cold = Index::open(dir).unwrap();
+        let searcher = cold.reader().unwrap().searcher();
+        let stored: TantivyDocument = searcher.doc(tantivy::DocAddress::new(0, 0)).unwrap();
+        assert_eq!(
+            stored.get_first(title).and_then(|v| v.as_str()),
+            Some("archived")
+        );
+        assert!(storage.gets.load(Ordering::SeqCst) > 0);
+        assert!(storage.gets.load(Ordering::SeqCst) <= manifest.files.len());
+
+        promote(&tenant_dir, "t", &manifest, storage.as_ref(), &cache).unwrap();
+        assert!(manifest.files.keys().all(|f| tenant_dir.join(f).exists()));
+        assert_eq!(ColdManifest::load(&tenant_dir).unwrap(), None);
+        assert!(storage.objects.lock().unwrap().is_empty());
+        assert_eq!(cache.stats().files, 0);
+        assert_eq!(
+            Index::open_in_dir(&tenant_dir)
+                .unwrap()
+                .load_metas()
+                .unwrap()
+                .segments
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn segment_file_names() {
+        assert!(is_segment_file("0123456789abcdef0123456789abcdef.store"));
+        assert!(is_segment_file("0123456789abcdef0123456789abcdef.12.del"));
+        assert!(!is_segment_file("meta.json"));
+        assert!(!is_segment_file(".managed.json"));
+        assert!(!is_segment_file("settings.json"));
+    }
+}
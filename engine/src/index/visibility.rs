@@ -0,0 +1,191 @@
+//! Query-time visibility: publish windows and expiry.
+//!
+//! A document with `_publishAt` is indexed straight away but only becomes
+//! searchable at that time; one with `_unpublishAt` stops being searchable
+//! then, without being deleted. Both take Unix seconds or an RFC 3339 date
+//! and are stored as seconds. Documents past their `_expiresAt` (see
+//! `expiry`) are hidden the same way until the sweep deletes them.
+//!
+//! Every search is narrowed by a filter built from the current time. Indexes
+//! that never held a document with these attributes skip it, and a search
+//! whose own filter mentions one of them opts out of that attribute's
+//! condition: `_publishAt > 1700000000` finds documents still scheduled.
+
+use std::path::Path;
+
+use crate::index::expiry::{self, parse_timestamp, EXPIRES_AT_ATTRIBUTE};
+use crate::types::{Document, FieldValue, Filter};
+
+pub const PUBLISH_AT_ATTRIBUTE: &str = "_publishAt";
+pub const UNPUBLISH_AT_ATTRIBUTE: &str = "_unpublishAt";
+
+const MARKER_FILE: &str = "scheduled";
+
+/// Record that the index in `index_dir` holds documents with a window.
+pub fn mark_scheduled(index_dir: &Path) {
+    expiry::set_marker(index_dir, MARKER_FILE)
+}
+
+/// Normalize a written document's `_publishAt` and `_unpublishAt` to Unix
+/// seconds.
+pub fn stamp_window(doc: &mut Document) -> Result<(), String> {
+    let mut bounds = [None, None];
+    for (bound, attribute) in bounds
+        .iter_mut()
+        .zip([PUBLISH_AT_ATTRIBUTE, UNPUBLISH_AT_ATTRIBUTE])
+    {
+        let Some(value) = doc.fields.get(attribute) else {
+            continue;
+        };
+        let seconds = parse_timestamp(value).ok_or_else(|| {
+            format!(
+                "{} must be Unix seconds or an RFC 3339 date, got {:?}",
+                attribute, value
+            )
+        })?;
+        doc.fields
+            .insert(attribute.to_string(), FieldValue::Integer(seconds));
+        *bound = Some(seconds);
+    }
+    if let [Some(publish), Some(unpublish)] = bounds {
+        if unpublish <= publish {
+            return Err(format!(
+                "{} must be after {}",
+                UNPUBLISH_AT_ATTRIBUTE, PUBLISH_AT_ATTRIBUTE
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Whether a written document carries a publish window.
+pub fn has_window(doc_json: &serde_json::Value) -> bool {
+    doc_json.get(PUBLISH_AT_ATTRIBUTE).is_some() || doc_json.get(UNPUBLISH_AT_ATTRIBUTE).is_some()
+}
+
+/// `filter` narrowed to documents visible at `now` in the index in
+/// `index_dir`, or `None` if nothing needs hiding.
+pub fn live_filter(index_dir: &Path, filter: Option<&Filter>, now: i64) -> Option<Filter> {
+    let mentions = |attribute: &str| filter.is_some_and(|f| mentions_attribute(f, attribute));
+    let mut conditions = Vec::new();
+    if expiry::has_expiring(index_dir) && !mentions(EXPIRES_AT_ATTRIBUTE) {
+        conditions.push(Filter::Not(Box::new(expiry::expired_filter(now))));
+    }
+    if expiry::has_marker(index_dir, MARKER_FILE) {
+        if !mentions(PUBLISH_AT_ATTRIBUTE) {
+            conditions.push(Filter::Not(Box::new(Filter::GreaterThan {
+                field: PUBLISH_AT_ATTRIBUTE.to_string(),
+                value: FieldValue::Integer(now),
+            })));
+        }
+        if !mentions(UNPUBLISH_AT_ATTRIBUTE) {
+            conditions.push(Filter::Not(Box::new(Filter::LessThanOrEqual {
+                field: UNPUBLISH_AT_ATTRIBUTE.to_string(),
+                value: FieldValue::Integer(now),
+            })));
+        }
+    }
+    if conditions.is_empty() {
+        return None;
+    }
+    conditions.extend(filter.cloned());
+    Some(if conditions.len() == 1 {
+        conditions.remove(0)
+    } else {
+        Filter::And(conditions)
+    })
+}
+
+fn mentions_attribute(filter: &Filter, attribute: &str) -> bool {
+    match filter {
+        Filter::Equals { field, .. }
+        | Filter::NotEquals { field, .. }
+        | Filter::GreaterThan { field, .. }
+        | Filter::GreaterThanOrEqual { field, .. }
+        | Filter::LessThan { field, .. }
+        | Filter::LessThanOrEqual { field, .. }
+        | Filter::Range { field, .. } => field == attribute,
+        Filter::Not(inner) => mentions_attribute(inner, attribute),
+        Filter::And(filters) | Filter::Or(filters) => {
+            filters.iter().any(|f| mentions_attribute(f, attribute))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn doc(fields: &[(&str, FieldValue)]) -> Document {
+        Document {
+            id: "1".to_string(),
+            fields: fields
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn stamp_window_normalizes_and_checks_order() {
+        let mut d = doc(&[
+            (
+                PUBLISH_AT_ATTRIBUTE,
+                FieldValue::Text("2024-01-01T00:00:00Z".to_string()),
+            ),
+            (UNPUBLISH_AT_ATTRIBUTE, FieldValue::Integer(1_704_153_600)),
+        ]);
+        stamp_window(&mut d).unwrap();
+        assert_eq!(
+            d.fields.get(PUBLISH_AT_ATTRIBUTE),
+            Some(&FieldValue::Integer(1_704_067_200))
+        );
+
+        let mut d = doc(&[
+            (PUBLISH_AT_ATTRIBUTE, FieldValue::Integer(200)),
+            (UNPUBLISH_AT_ATTRIBUTE, FieldValue::Integer(100)),
+        ]);
+        assert!(stamp_window(&mut d).is_err());
+        let mut d = doc(&[(PUBLISH_AT_ATTRIBUTE, FieldValue::Text("soon".to_string()))]);
+        assert!(stamp_window(&mut d).is_err());
+        let mut d = doc(&[("title", FieldValue::Text("x".to_string()))]);
+        stamp_window(&mut d).unwrap();
+    }
+
+    #[test]
+    fn live_filter_adds_conditions_for_marked_indexes() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().join("products");
+        std::fs::create_dir_all(&dir).unwrap();
+        let brand = Filter::Equals {
+            field: "brand".to_string(),
+            value: FieldValue::Text("acme".to_string()),
+        };
+        assert!(live_filter(&dir, Some(&brand), 100).is_none());
+
+        expiry::mark_expiring(&dir);
+        assert!(matches!(live_filter(&dir, None, 100), Some(Filter::Not(_))));
+        match live_filter(&dir, Some(&brand), 100) {
+            Some(Filter::And(parts)) => assert_eq!(parts.len(), 2),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        mark_scheduled(&dir);
+        match live_filter(&dir, Some(&brand), 100) {
+            Some(Filter::And(parts)) => assert_eq!(parts.len(), 4),
+            other => panic!("unexpected {:?}", other),
+        }
+        let scheduled = Filter::And(vec![
+            brand,
+            Filter::GreaterThan {
+                field: PUBLISH_AT_ATTRIBUTE.to_string(),
+                value: FieldValue::Integer(100),
+            },
+        ]);
+        match live_filter(&dir, Some(&scheduled), 100) {
+            Some(Filter::And(parts)) => assert_eq!(parts.len(), 3),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
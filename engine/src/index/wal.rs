@@ -0,0 +1,420 @@
+//! Write-ahead log for acknowledged document writes.
+//!
+//! A write is acknowledged (a task ID is returned) as soon as it is queued,
+//! but it only reaches Tantivy when the write queue commits its batch. Each
+//! queued write is appended to `{tenant}/wal.jsonl` before it is handed to
+//! the queue, and a `done` record is appended once it is committed. On boot,
+//! writes without a `done` record are re-queued with their original task IDs.
+//!
+//! Done records are only appended while other writes are outstanding; once
+//! none are, the log is truncated. Under steady load that never happens, so
+//! at a commit boundary where the log has doubled since its last checkpoint
+//! it is rewritten with just its outstanding writes.
+//!
+//! `FLAPJACK_WAL=false` disables the log. `FLAPJACK_WAL_FSYNC=false` skips
+//! the fsync after each append, trading crash safety for write latency.
+//! Appends made while an fsync is running share the next one.
+
+use crate::index::write_queue::{WriteAction, WriteOp};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+pub const WAL_FILE: &str = "wal.jsonl";
+
+/// Smallest log size that triggers a checkpoint.
+const CHECKPOINT_BYTES: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum WalRecord {
+    Write {
+        task_id: String,
+        numeric_id: i64,
+        actions: Vec<WriteAction>,
+    },
+    Done {
+        task_id: String,
+    },
+}
+
+/// A write that was acknowledged but never committed.
+#[derive(Debug)]
+pub struct PendingWrite {
+    pub task_id: String,
+    pub numeric_id: i64,
+    pub actions: Vec<WriteAction>,
+}
+
+impl PendingWrite {
+    /// The op to re-queue. `Add` is replayed as `Upsert` because the crash
+    /// may have happened after the Tantivy commit but before the `done`
+    /// record was written; upserting the same document twice is harmless.
+    pub fn into_write_op(self) -> WriteOp {
+        let actions = self
+            .actions
+            .into_iter()
+            .map(|action| match action {
+                WriteAction::Add(doc) => WriteAction::Upsert(doc),
+                other => other,
+            })
+            .collect();
+        WriteOp {
+            task_id: self.task_id,
+            actions,
+        }
+    }
+}
+
+pub fn wal_enabled() -> bool {
+    env_flag("FLAPJACK_WAL", true)
+}
+
+fn env_flag(var: &str, default: bool) -> bool {
+    match std::env::var(var) {
+        Ok(v) => !matches!(
+            v.to_ascii_lowercase().as_str(),
+            "0" | "false" | "off" | "no"
+        ),
+        Err(_) => default,
+    }
+}
+
+struct WalState {
+    file: File,
+    /// Task IDs written but not yet done.
+    outstanding: HashSet<String>,
+    /// Bytes in the log file.
+    len: u64,
+    /// Log size right after the last checkpoint.
+    checkpoint_len: u64,
+}
+
+struct SyncState {
+    /// A handle on the log file, for fsyncs that run outside the state lock.
+    file: File,
+    /// Write records appended up to here are on disk.
+    synced: u64,
+}
+
+pub struct WriteAheadLog {
+    path: PathBuf,
+    fsync: bool,
+    checkpoint_bytes: u64,
+    state: Mutex<WalState>,
+    /// Write records appended so far.
+    appended: AtomicU64,
+    sync: Mutex<SyncState>,
+}
+
+impl WriteAheadLog {
+    pub fn open(tenant_dir: &Path) -> crate::error::Result<Self> {
+        Self::open_with(tenant_dir, CHECKPOINT_BYTES)
+    }
+
+    fn open_with(tenant_dir: &Path, checkpoint_bytes: u64) -> crate::error::Result<Self> {
+        std::fs::create_dir_all(tenant_dir)?;
+        let path = tenant_dir.join(WAL_FILE);
+        let outstanding = read_pending(&path)?
+            .into_iter()
+            .map(|w| w.task_id)
+            .collect();
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        if ends_mid_line(&path)? {
+            file.write_all(b"\n")?;
+        }
+        let len = file.metadata()?.len();
+        let sync_file = file.try_clone()?;
+        Ok(Self {
+            path,
+            fsync: env_flag("FLAPJACK_WAL_FSYNC", true),
+            checkpoint_bytes,
+            state: Mutex::new(WalState {
+                file,
+                outstanding,
+                len,
+                checkpoint_len: 0,
+            }),
+            appended: AtomicU64::new(0),
+            sync: Mutex::new(SyncState {
+                file: sync_file,
+                synced: 0,
+            }),
+        })
+    }
+
+    fn write_record(&self, state: &mut WalState, record: &WalRecord) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        state.file.write_all(&line)?;
+        state.len += line.len() as u64;
+        Ok(())
+    }
+
+    /// Record a write before it is queued. Returns once the record is on
+    /// disk (unless fsync is off).
+    pub fn append(&self, op: &WriteOp, numeric_id: i64) -> crate::error::Result<()> {
+        let record = WalRecord::Write {
+            task_id: op.task_id.clone(),
+            numeric_id,
+            actions: op.actions.clone(),
+        };
+        let seq = {
+            let mut state = self.state.lock().unwrap();
+            self.write_record(&mut state, &record)?;
+            state.outstanding.insert(op.task_id.clone());
+            self.appended.fetch_add(1, Ordering::AcqRel) + 1
+        };
+        if self.fsync {
+            self.sync_through(seq)?;
+        }
+        Ok(())
+    }
+
+    /// Group commit: make write record `seq` durable. One fsync covers every
+    /// record appended before it started, so writers that arrive during an
+    /// fsync wait for it and share the next one rather than queueing one each.
+    fn sync_through(&self, seq: u64) -> std::io::Result<()> {
+        let mut sync = self.sync.lock().unwrap();
+        if sync.synced >= seq {
+            return Ok(());
+        }
+        let target = self.appended.load(Ordering::Acquire);
+        sync.file.sync_data()?;
+        sync.synced = target;
+        Ok(())
+    }
+
+    /// Record that a write was committed (or will never be applied). Once
+    /// nothing is outstanding the log is truncated; otherwise it is
+    /// checkpointed when it has doubled since the last checkpoint.
+    ///
+    /// Done records are not fsynced: losing one only replays an upsert that
+    /// was already applied.
+    pub fn mark_done(&self, task_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        if !state.outstanding.remove(task_id) {
+            return;
+        }
+        let result = if state.outstanding.is_empty() {
+            state.file.set_len(0).map_err(Into::into).map(|()| {
+                state.len = 0;
+                state.checkpoint_len = 0;
+            })
+        } else {
+            let record = WalRecord::Done {
+                task_id: task_id.to_string(),
+            };
+            match self.write_record(&mut state, &record) {
+                Ok(()) if state.len >= self.checkpoint_bytes.max(2 * state.checkpoint_len) => {
+                    self.checkpoint(&mut state)
+                }
+                Ok(()) => Ok(()),
+                Err(e) => Err(e.into()),
+            }
+        };
+        if let Err(e) = result {
+            tracing::warn!("[WAL] failed to mark {} done: {}", task_id, e);
+        }
+    }
+
+    /// Rewrite the log with only its outstanding writes, swapping the new
+    /// file in once it is on disk.
+    fn checkpoint(&self, state: &mut WalState) -> crate::error::Result<()> {
+        let pending = read_pending(&self.path)?;
+        let tmp = self.path.with_extension("jsonl.tmp");
+        let mut file = File::create(&tmp)?;
+        let mut len = 0;
+        for write in pending {
+            let mut line = serde_json::to_vec(&WalRecord::Write {
+                task_id: write.task_id,
+                numeric_id: write.numeric_id,
+                actions: write.actions,
+            })?;
+            line.push(b'\n');
+            file.write_all(&line)?;
+            len += line.len() as u64;
+        }
+        file.sync_data()?;
+        std::fs::rename(&tmp, &self.path)?;
+
+        state.file = OpenOptions::new().append(true).open(&self.path)?;
+        state.len = len;
+        state.checkpoint_len = len;
+        let mut sync = self.sync.lock().unwrap();
+        sync.file = state.file.try_clone()?;
+        sync.synced = self.appended.load(Ordering::Acquire);
+        Ok(())
+    }
+
+    /// Writes that were logged but never marked done, in log order.
+    pub fn pending(&self) -> crate::error::Result<Vec<PendingWrite>> {
+        let _state = self.state.lock().unwrap();
+        read_pending(&self.path)
+    }
+}
+
+/// Task IDs of writes still pending in any tenant's log under `base_path`.
+/// They are re-queued on boot, so their tasks are not lost.
+pub fn pending_task_ids(base_path: &Path) -> HashSet<String> {
+    let Ok(entries) = std::fs::read_dir(base_path) else {
+        return HashSet::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path().join(WAL_FILE))
+        .filter(|path| path.is_file())
+        .filter_map(|path| read_pending(&path).ok())
+        .flatten()
+        .map(|write| write.task_id)
+        .collect()
+}
+
+fn ends_mid_line(path: &Path) -> std::io::Result<bool> {
+    use std::io::{Read, Seek, SeekFrom};
+    let mut file = File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Ok(false);
+    }
+    file.seek(SeekFrom::End(-1))?;
+    let mut last = [0u8; 1];
+    file.read_exact(&mut last)?;
+    Ok(last[0] != b'\n')
+}
+
+fn read_pending(path: &Path) -> crate::error::Result<Vec<PendingWrite>> {
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut writes: Vec<PendingWrite> = Vec::new();
+    let mut done: HashSet<String> = HashSet::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        // A torn final line means the write was never acknowledged.
+        let Ok(record) = serde_json::from_str::<WalRecord>(&line) else {
+            continue;
+        };
+        match record {
+            WalRecord::Write {
+                task_id,
+                numeric_id,
+                actions,
+            } => writes.push(PendingWrite {
+                task_id,
+                numeric_id,
+                actions,
+            }),
+            WalRecord::Done { task_id } => {
+                done.insert(task_id);
+            }
+        }
+    }
+    writes.retain(|w| !done.contains(&w.task_id));
+    Ok(writes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Document, FieldValue};
+    use std::collections::HashMap;
+
+    fn upsert(id: &str) -> WriteOp {
+        WriteOp {
+            task_id: format!("task_t_{}", id),
+            actions: vec![WriteAction::Add(Document {
+                id: id.to_string(),
+                fields: HashMap::from([(
+                    "title".to_string(),
+                    FieldValue::Text(format!("doc {}", id)),
+                )]),
+            })],
+        }
+    }
+
+    #[test]
+    fn pending_writes_survive_reopen() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let wal = WriteAheadLog::open(tmp.path()).unwrap();
+        wal.append(&upsert("1"), 1).unwrap();
+        wal.append(&upsert("2"), 2).unwrap();
+        wal.mark_done("task_t_1");
+        drop(wal);
+
+        let wal = WriteAheadLog::open(tmp.path()).unwrap();
+        let pending = wal.pending().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].task_id, "task_t_2");
+        assert_eq!(pending[0].numeric_id, 2);
+    }
+
+    #[test]
+    fn log_is_truncated_when_nothing_is_outstanding() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let wal = WriteAheadLog::open(tmp.path()).unwrap();
+        wal.append(&upsert("1"), 1).unwrap();
+        wal.mark_done("task_t_1");
+        let len = std::fs::metadata(tmp.path().join(WAL_FILE)).unwrap().len();
+        assert_eq!(len, 0);
+
+        wal.append(&upsert("2"), 2).unwrap();
+        assert_eq!(wal.pending().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn log_is_checkpointed_while_writes_stay_outstanding() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let wal = WriteAheadLog::open_with(tmp.path(), 4096).unwrap();
+        wal.append(&upsert("slow"), 1).unwrap();
+        for i in 0..500 {
+            let op = upsert(&i.to_string());
+            wal.append(&op, i + 2).unwrap();
+            wal.mark_done(&op.task_id);
+        }
+        let len = std::fs::metadata(tmp.path().join(WAL_FILE)).unwrap().len();
+        assert!(len < 3 * 4096, "log grew to {} bytes", len);
+        drop(wal);
+
+        let wal = WriteAheadLog::open(tmp.path()).unwrap();
+        let pending = wal.pending().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].task_id, "task_t_slow");
+        wal.mark_done("task_t_slow");
+        assert!(wal.pending().unwrap().is_empty());
+    }
+
+    #[test]
+    fn torn_last_line_is_ignored() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let wal = WriteAheadLog::open(tmp.path()).unwrap();
+        wal.append(&upsert("1"), 1).unwrap();
+        drop(wal);
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(tmp.path().join(WAL_FILE))
+            .unwrap();
+        file.write_all(b"{\"kind\":\"write\",\"task_id\":\"tas")
+            .unwrap();
+
+        let wal = WriteAheadLog::open(tmp.path()).unwrap();
+        wal.append(&upsert("2"), 2).unwrap();
+        assert_eq!(wal.pending().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn add_is_replayed_as_upsert() {
+        let op = PendingWrite {
+            task_id: "task_t_1".to_string(),
+            numeric_id: 1,
+            actions: upsert("1").actions,
+        }
+        .into_write_op();
+        assert!(matches!(op.actions[0], WriteAction::Upsert(_)));
+    }
+}
@@ -1,6 +1,13 @@
 //! Async write queue with hybrid batching for Flapjack.
 
-use crate::types::{DocFailure, Document, TaskInfo, TaskStatus};
+use crate::index::compaction::{merge_steps, CompactOptions};
+use crate::index::expiry::{mark_expiring, stamp_expiry, EXPIRES_AT_ATTRIBUTE};
+use crate::index::ingest_transform::transform_document;
+use crate::index::task_store::TaskMap;
+use crate::index::visibility::{has_window, mark_scheduled, stamp_window};
+use crate::index::wal::WriteAheadLog;
+use crate::types::{DocFailure, Document, TaskInfo, TaskProgress, TaskStatus};
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
@@ -30,6 +37,7 @@ impl VectorWriteContext {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum WriteAction {
     Add(Document),
     Upsert(Document),
@@ -39,7 +47,7 @@ pub enum WriteAction {
     Delete(String),
     /// Like Delete but skips lww_map update — same rationale as UpsertNoLwwUpdate.
     DeleteNoLwwUpdate(String),
-    Compact,
+    Compact(CompactOptions),
 }
 
 pub struct WriteOp {
@@ -55,7 +63,7 @@ pub(crate) fn create_write_queue(
     writers: Arc<
         dashmap::DashMap<String, Arc<tokio::sync::Mutex<crate::index::ManagedIndexWriter>>>,
     >,
-    tasks: Arc<dashmap::DashMap<String, TaskInfo>>,
+    tasks: Arc<TaskMap>,
     base_path: std::path::PathBuf,
     oplog: Option<Arc<crate::index::oplog::OpLog>>,
     facet_cache: Arc<
@@ -70,6 +78,7 @@ pub(crate) fn create_write_queue(
     >,
     lww_map: Arc<dashmap::DashMap<String, dashmap::DashMap<String, (u64, String)>>>,
     vector_ctx: VectorWriteContext,
+    wal: Option<Arc<WriteAheadLog>>,
 ) -> (
     WriteQueue,
     tokio::task::JoinHandle<crate::error::Result<()>>,
@@ -96,6 +105,7 @@ pub(crate) fn create_write_queue(
             facet_cache,
             lww_map,
             vector_ctx,
+            wal,
         )
         .await
     });
@@ -109,7 +119,7 @@ async fn process_writes(
     _writers: Arc<
         dashmap::DashMap<String, Arc<tokio::sync::Mutex<crate::index::ManagedIndexWriter>>>,
     >,
-    tasks: Arc<dashmap::DashMap<String, TaskInfo>>,
+    tasks: Arc<TaskMap>,
     mut rx: mpsc::Receiver<WriteOp>,
     base_path: std::path::PathBuf,
     oplog: Option<Arc<crate::index::oplog::OpLog>>,
@@ -125,6 +135,7 @@ async fn process_writes(
     >,
     lww_map: Arc<dashmap::DashMap<String, dashmap::DashMap<String, (u64, String)>>>,
     vector_ctx: VectorWriteContext,
+    wal: Option<Arc<WriteAheadLog>>,
 ) -> crate::error::Result<()> {
     let mut writer = match index.writer() {
         Ok(w) => {
@@ -167,16 +178,19 @@ async fn process_writes(
         match timeout_at(deadline.into(), rx.recv()).await {
             Ok(Some(op)) => {
                 let action_count = op.actions.len();
-                let is_compact = matches!(op.actions.first(), Some(WriteAction::Compact));
+                let compact = match op.actions.first() {
+                    Some(WriteAction::Compact(options)) => Some(*options),
+                    _ => None,
+                };
                 tracing::debug!(
                     "[WQ {}] received op task={} actions={}{}",
                     tenant_id,
                     op.task_id,
                     action_count,
-                    if is_compact { " (compact)" } else { "" }
+                    if compact.is_some() { " (compact)" } else { "" }
                 );
 
-                if is_compact {
+                if let Some(options) = compact {
                     // Flush any pending writes first
                     if !pending.is_empty() {
                         commit_batch(
@@ -190,10 +204,19 @@ async fn process_writes(
                             &facet_cache,
                             &lww_map,
                             &vector_ctx,
+                            &wal,
                         )
                         .await?;
                     }
-                    compact_segments(&index, &tasks, &op.task_id, &mut writer, &tenant_id)?;
+                    compact_segments(
+                        &index,
+                        &tasks,
+                        &op.task_id,
+                        &mut writer,
+                        &tenant_id,
+                        options,
+                    )
+                    .await?;
                     deadline = Instant::now() + Duration::from_millis(100);
                     continue;
                 }
@@ -216,6 +239,7 @@ async fn process_writes(
                         &facet_cache,
                         &lww_map,
                         &vector_ctx,
+                        &wal,
                     )
                     .await?;
                     deadline = Instant::now() + Duration::from_millis(100);
@@ -239,6 +263,7 @@ async fn process_writes(
                         &facet_cache,
                         &lww_map,
                         &vector_ctx,
+                        &wal,
                     )
                     .await?;
                 }
@@ -262,6 +287,7 @@ async fn process_writes(
                         &facet_cache,
                         &lww_map,
                         &vector_ctx,
+                        &wal,
                     )
                     .await?;
                 }
@@ -346,7 +372,7 @@ fn process_doc_vectors(
 #[allow(unused_mut, unused_variables)]
 async fn commit_batch(
     index: &Arc<crate::index::Index>,
-    tasks: &Arc<dashmap::DashMap<String, TaskInfo>>,
+    tasks: &Arc<TaskMap>,
     ops: &mut Vec<WriteOp>,
     writer: &mut crate::index::ManagedIndexWriter,
     tenant_id: &str,
@@ -364,6 +390,7 @@ async fn commit_batch(
     >,
     lww_map: &Arc<dashmap::DashMap<String, dashmap::DashMap<String, (u64, String)>>>,
     vector_ctx: &VectorWriteContext,
+    wal: &Option<Arc<WriteAheadLog>>,
 ) -> crate::error::Result<()> {
     tracing::warn!("[WQ {}] commit_batch: {} operations", tenant_id, ops.len());
 
@@ -376,6 +403,23 @@ async fn commit_batch(
     } else {
         None
     };
+    index.set_text_normalization(
+        settings
+            .as_ref()
+            .and_then(crate::tokenizer::TextNormalization::from_settings),
+    );
+
+    // Replicated writes arrive already transformed, fingerprinted and
+    // with their timestamps normalized by their origin.
+    let ingest_transforms = settings
+        .as_ref()
+        .map(|s| s.ingest_transforms.as_slice())
+        .unwrap_or_default();
+    let duplicate_detection = settings
+        .as_ref()
+        .and_then(|s| s.duplicate_detection.as_ref());
+    let default_ttl = settings.as_ref().and_then(|s| s.default_ttl);
+    let now = chrono::Utc::now().timestamp();
 
     // Pre-parse embedder configs from settings (used for _vectors validation and embedding).
     #[cfg(feature = "vector-search")]
@@ -441,6 +485,20 @@ async fn commit_batch(
                     deleted_ids.push(object_id);
                 }
                 WriteAction::Add(mut doc) => {
+                    transform_document(ingest_transforms, &mut doc);
+                    if let Some(config) = duplicate_detection {
+                        config.annotate(&mut doc);
+                    }
+                    if let Err(message) = stamp_expiry(&mut doc, default_ttl, now)
+                        .and_then(|()| stamp_window(&mut doc))
+                    {
+                        rejected.push(DocFailure {
+                            doc_id: doc.id,
+                            error: "validation_error".to_string(),
+                            message,
+                        });
+                        continue;
+                    }
                     let doc_json = doc.to_json();
                     #[cfg(feature = "vector-search")]
                     let vectors = match process_doc_vectors(&mut doc, &doc_json, &embedder_configs)
@@ -479,6 +537,20 @@ async fn commit_batch(
                     }
                 }
                 WriteAction::Upsert(mut doc) => {
+                    transform_document(ingest_transforms, &mut doc);
+                    if let Some(config) = duplicate_detection {
+                        config.annotate(&mut doc);
+                    }
+                    if let Err(message) = stamp_expiry(&mut doc, default_ttl, now)
+                        .and_then(|()| stamp_window(&mut doc))
+                    {
+                        rejected.push(DocFailure {
+                            doc_id: doc.id,
+                            error: "validation_error".to_string(),
+                            message,
+                        });
+                        continue;
+                    }
                     let doc_json = doc.to_json();
                     #[cfg(feature = "vector-search")]
                     let vectors = match process_doc_vectors(&mut doc, &doc_json, &embedder_configs)
@@ -560,12 +632,26 @@ async fn commit_batch(
                         }
                     }
                 }
-                WriteAction::Compact => {
+                WriteAction::Compact(_) => {
                     // Handled in the process_writes loop, should not reach here
                 }
             }
         }
 
+        let index_dir = base_path.join(tenant_id);
+        if valid_docs
+            .iter()
+            .any(|(_, doc_json, _)| doc_json.get(EXPIRES_AT_ATTRIBUTE).is_some())
+        {
+            mark_expiring(&index_dir);
+        }
+        if valid_docs
+            .iter()
+            .any(|(_, doc_json, _)| has_window(doc_json))
+        {
+            mark_scheduled(&index_dir);
+        }
+
         // Phase 2+3: Embed documents and update VectorIndex.
         #[cfg(feature = "vector-search")]
         if !embedder_configs.is_empty() {
@@ -577,6 +663,12 @@ async fn commit_batch(
                 std::collections::HashMap<String, Vec<f32>>,
             > = std::collections::HashMap::new();
 
+            // Replicated upserts carry the vectors the origin computed in
+            // `_vectors`. Re-embedding them here could give a different
+            // vector (model drift, failed call) and diverge from the origin.
+            let primary_ids: std::collections::HashSet<&str> =
+                primary_upsert_ids.iter().map(String::as_str).collect();
+
             for (embedder_name, config) in &embedder_configs {
                 // Separate docs with user-provided vectors from those needing embedding.
                 let mut vectors_to_store: Vec<(String, Vec<f32>)> = Vec::new();
@@ -592,8 +684,11 @@ async fn commit_batch(
                         }
                     }
                     // No user-provided vector for this embedder.
-                    if config.source == EmbedderSource::UserProvided {
-                        // UserProvided can't generate embeddings — skip.
+                    if config.source == EmbedderSource::UserProvided
+                        || !primary_ids.contains(doc_id.as_str())
+                    {
+                        // UserProvided can't generate embeddings, and replicated docs
+                        // without a vector had none on the origin either — skip.
                         continue;
                     }
                     // Render through document template for embedding.
@@ -619,32 +714,9 @@ async fn commit_batch(
                     let texts: Vec<&str> =
                         docs_needing_embed.iter().map(|(_, t)| t.as_str()).collect();
 
-                    // Sub-batch into groups of 50 if >100 texts.
-                    let embeddings = if texts.len() > 100 {
-                        let mut all_vecs = Vec::new();
-                        let mut failed = false;
-                        for chunk in texts.chunks(50) {
-                            match embedder.embed_documents(chunk).await {
-                                Ok(batch) => all_vecs.extend(batch),
-                                Err(e) => {
-                                    tracing::warn!(
-                                        "[WQ {}] embedding sub-batch failed for '{}': {}",
-                                        tenant_id,
-                                        embedder_name,
-                                        e
-                                    );
-                                    failed = true;
-                                    break;
-                                }
-                            }
-                        }
-                        if failed {
-                            continue; // Skip vector update for this embedder
-                        }
-                        Ok(all_vecs)
-                    } else {
-                        embedder.embed_documents(&texts).await
-                    };
+                    let embeddings = crate::vector::scheduler::EmbeddingScheduler::global()
+                        .embed_documents(tenant_id, embedder_name, &embedder, config, &texts)
+                        .await;
 
                     match embeddings {
                         Ok(vecs) => {
@@ -890,40 +962,72 @@ async fn commit_batch(
             task.rejected_count = total_rejected;
             task
         });
+
+        if let Some(wal) = wal {
+            wal.mark_done(&op.task_id);
+        }
     }
 
     Ok(())
 }
 
 /// Force-merge all segments into one and garbage-collect stale files.
-fn compact_segments(
+///
+/// With `options.merge_batch` set, segments are merged a batch at a time,
+/// pausing `options.throttle` between merges; each merge is a progress step
+/// of the task.
+async fn compact_segments(
     index: &Arc<crate::index::Index>,
-    tasks: &Arc<dashmap::DashMap<String, TaskInfo>>,
+    tasks: &Arc<TaskMap>,
     task_id: &str,
     writer: &mut crate::index::ManagedIndexWriter,
     tenant_id: &str,
+    options: CompactOptions,
 ) -> crate::error::Result<()> {
-    tasks.alter(task_id, |_, mut t| {
-        t.status = TaskStatus::Processing;
-        t
-    });
-
-    let segment_ids = index.inner().searchable_segment_ids()?;
+    let mut segment_ids = index.inner().searchable_segment_ids()?;
+    let batch = options.batch_size(segment_ids.len());
+    let total = merge_steps(segment_ids.len(), batch);
+    let numeric_id = if let Some(task_ref) = tasks.get(task_id) {
+        task_ref.numeric_id.to_string()
+    } else {
+        task_id.to_string()
+    };
+    let set_progress = |completed: usize| {
+        for id in [task_id, numeric_id.as_str()] {
+            tasks.alter(id, |_, mut t| {
+                t.status = TaskStatus::Processing;
+                t.progress = Some(TaskProgress { completed, total });
+                t
+            });
+        }
+    };
+    set_progress(0);
     tracing::info!(
-        "[WQ {}] compacting {} segments",
+        "[WQ {}] compacting {} segments ({} merges)",
         tenant_id,
-        segment_ids.len()
+        segment_ids.len(),
+        total
     );
 
-    let result: crate::error::Result<()> = (|| {
-        if segment_ids.len() > 1 {
-            let merge_future = writer.merge(&segment_ids);
-            // Block on the merge (runs in Tantivy's merge thread pool).
-            // wait() returns Option<SegmentMeta>; None means all docs were deleted.
-            if let Err(e) = merge_future.wait() {
-                tracing::error!("[WQ {}] merge failed: {}", tenant_id, e);
-                return Err(crate::error::FlapjackError::Tantivy(e.to_string()));
+    let result: crate::error::Result<()> = async {
+        let mut completed = 0;
+        while segment_ids.len() > 1 {
+            for group in segment_ids.chunks(batch) {
+                if group.len() > 1 {
+                    // Block on the merge (runs in Tantivy's merge thread pool).
+                    // wait() returns Option<SegmentMeta>; None means all docs were deleted.
+                    if let Err(e) = writer.merge(group).wait() {
+                        tracing::error!("[WQ {}] merge failed: {}", tenant_id, e);
+                        return Err(crate::error::FlapjackError::Tantivy(e.to_string()));
+                    }
+                }
+                completed += 1;
+                set_progress(completed.min(total));
+                if !options.throttle.is_zero() && completed < total {
+                    tokio::time::sleep(options.throttle).await;
+                }
             }
+            segment_ids = index.inner().searchable_segment_ids()?;
         }
 
         // Clean up orphaned segment files left by completed merges
@@ -939,14 +1043,10 @@ fn compact_segments(
 
         index.reader().reload()?;
         index.invalidate_searchable_paths_cache();
+        index.record_compaction();
         Ok(())
-    })();
-
-    let numeric_id = if let Some(task_ref) = tasks.get(task_id) {
-        task_ref.numeric_id.to_string()
-    } else {
-        task_id.to_string()
-    };
+    }
+    .await;
 
     let status = match &result {
         Ok(()) => TaskStatus::Succeeded,
@@ -1007,7 +1107,7 @@ mod tests {
     ) -> (
         WriteQueue,
         tokio::task::JoinHandle<crate::error::Result<()>>,
-        Arc<dashmap::DashMap<String, TaskInfo>>,
+        Arc<TaskMap>,
     ) {
         let tenant_path = tmp.path().join(tenant_id);
         std::fs::create_dir_all(&tenant_path).unwrap();
@@ -1015,7 +1115,7 @@ mod tests {
         let index = Arc::new(crate::index::Index::create(&tenant_path, schema).unwrap());
 
         let writers = Arc::new(dashmap::DashMap::new());
-        let tasks: Arc<dashmap::DashMap<String, TaskInfo>> = Arc::new(dashmap::DashMap::new());
+        let tasks: Arc<TaskMap> = Arc::new(TaskMap::default());
         let facet_cache = Arc::new(dashmap::DashMap::new());
         let lww_map = Arc::new(dashmap::DashMap::new());
 
@@ -1034,6 +1134,7 @@ mod tests {
             facet_cache,
             lww_map,
             vector_ctx,
+            None,
         );
 
         (tx, handle, tasks)
@@ -1217,7 +1318,7 @@ mod tests {
         let index = Arc::new(crate::index::Index::create(&tenant_path, schema).unwrap());
 
         let writers = Arc::new(dashmap::DashMap::new());
-        let tasks: Arc<dashmap::DashMap<String, TaskInfo>> = Arc::new(dashmap::DashMap::new());
+        let tasks: Arc<TaskMap> = Arc::new(TaskMap::default());
         let facet_cache = Arc::new(dashmap::DashMap::new());
         let lww_map = Arc::new(dashmap::DashMap::new());
         let vector_indices: Arc<
@@ -1236,6 +1337,7 @@ mod tests {
             facet_cache,
             lww_map,
             vector_ctx,
+            None,
         );
 
         let task_id = "vec_task_1".to_string();
@@ -1290,7 +1392,7 @@ mod tests {
         ) -> (
             WriteQueue,
             tokio::task::JoinHandle<crate::error::Result<()>>,
-            Arc<dashmap::DashMap<String, TaskInfo>>,
+            Arc<TaskMap>,
             VectorIndicesMap,
         ) {
             let tenant_path = tmp.path().join(tenant_id);
@@ -1308,7 +1410,7 @@ mod tests {
             let index = Arc::new(crate::index::Index::create(&tenant_path, schema).unwrap());
 
             let writers = Arc::new(dashmap::DashMap::new());
-            let tasks: Arc<dashmap::DashMap<String, TaskInfo>> = Arc::new(dashmap::DashMap::new());
+            let tasks: Arc<TaskMap> = Arc::new(TaskMap::default());
             let facet_cache = Arc::new(dashmap::DashMap::new());
             let lww_map = Arc::new(dashmap::DashMap::new());
             let vector_indices: VectorIndicesMap = Arc::new(dashmap::DashMap::new());
@@ -1325,6 +1427,7 @@ mod tests {
                 facet_cache,
                 lww_map,
                 vector_ctx,
+                None,
             );
 
             (tx, handle, tasks, vector_indices)
@@ -2036,7 +2139,7 @@ mod tests {
             let index = Arc::new(crate::index::Index::create(&tenant_path, schema).unwrap());
 
             let writers = Arc::new(dashmap::DashMap::new());
-            let tasks: Arc<dashmap::DashMap<String, TaskInfo>> = Arc::new(dashmap::DashMap::new());
+            let tasks: Arc<TaskMap> = Arc::new(TaskMap::default());
             let facet_cache = Arc::new(dashmap::DashMap::new());
             let lww_map = Arc::new(dashmap::DashMap::new());
             let vector_indices: VectorIndicesMap = Arc::new(dashmap::DashMap::new());
@@ -2052,6 +2155,7 @@ mod tests {
                 facet_cache,
                 lww_map,
                 vector_ctx,
+                None,
             );
 
             let task_id = "strip_task".to_string();
@@ -2385,7 +2489,7 @@ mod tests {
         ) -> (
             WriteQueue,
             tokio::task::JoinHandle<crate::error::Result<()>>,
-            Arc<dashmap::DashMap<String, TaskInfo>>,
+            Arc<TaskMap>,
             VectorIndicesMap,
             Arc<crate::index::oplog::OpLog>,
         ) {
@@ -2403,7 +2507,7 @@ mod tests {
             let index = Arc::new(crate::index::Index::create(&tenant_path, schema).unwrap());
 
             let writers = Arc::new(dashmap::DashMap::new());
-            let tasks: Arc<dashmap::DashMap<String, TaskInfo>> = Arc::new(dashmap::DashMap::new());
+            let tasks: Arc<TaskMap> = Arc::new(TaskMap::default());
             let facet_cache = Arc::new(dashmap::DashMap::new());
             let lww_map = Arc::new(dashmap::DashMap::new());
             let vector_indices: VectorIndicesMap = Arc::new(dashmap::DashMap::new());
@@ -2424,6 +2528,7 @@ mod tests {
                 facet_cache,
                 lww_map,
                 vector_ctx,
+                None,
             );
 
             (tx, handle, tasks, vector_indices, oplog)
@@ -329,6 +329,7 @@ fn test_config(dir: &std::path::Path) -> AnalyticsConfig {
         flush_interval_secs: 3600,
         flush_size: 10_000, // won't auto-flush in tests
         retention_days: 90,
+        pii: Default::default(),
     }
 }
 
@@ -360,6 +361,10 @@ fn search_event(
         experiment_id: None,
         variant_id: None,
         assignment_method: None,
+        applied_rules: Vec::new(),
+        synonyms_triggered: Vec::new(),
+        synonyms_matched: Vec::new(),
+        query_language: None,
     }
 }
 
@@ -645,6 +650,7 @@ async fn analytics_disabled_suppresses_recording() {
         flush_interval_secs: 3600,
         flush_size: 1,
         retention_days: 90,
+        pii: Default::default(),
     };
     let collector = AnalyticsCollector::new(config.clone());
     let engine = AnalyticsQueryEngine::new(config);
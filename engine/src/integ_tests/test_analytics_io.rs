@@ -7,6 +7,7 @@
 
 use crate::analytics::collector::AnalyticsCollector;
 use crate::analytics::config::AnalyticsConfig;
+use crate::analytics::dead_letter::RejectReason;
 use crate::analytics::query::AnalyticsQueryEngine;
 use crate::analytics::schema::{InsightEvent, SearchEvent};
 use crate::analytics::writer;
@@ -22,6 +23,7 @@ fn collector_config(dir: &std::path::Path, flush_size: usize) -> AnalyticsConfig
         flush_interval_secs: 3600,
         flush_size,
         retention_days: 7,
+        pii: Default::default(),
     }
 }
 
@@ -32,6 +34,7 @@ fn writer_config(dir: &std::path::Path) -> AnalyticsConfig {
         flush_interval_secs: 1,
         flush_size: 100,
         retention_days: 7,
+        pii: Default::default(),
     }
 }
 
@@ -42,6 +45,7 @@ fn cleanup_config(dir: &std::path::Path) -> AnalyticsConfig {
         flush_interval_secs: 3600,
         flush_size: 10_000,
         retention_days: 90,
+        pii: Default::default(),
     }
 }
 
@@ -68,6 +72,10 @@ fn make_search(query: &str, index: &str, query_id: Option<&str>) -> SearchEvent
         experiment_id: None,
         variant_id: None,
         assignment_method: None,
+        applied_rules: Vec::new(),
+        synonyms_triggered: Vec::new(),
+        synonyms_matched: Vec::new(),
+        query_language: None,
     }
 }
 
@@ -92,6 +100,10 @@ fn make_search_ev(query: &str, index: &str, nb_hits: u32) -> SearchEvent {
         experiment_id: None,
         variant_id: None,
         assignment_method: None,
+        applied_rules: Vec::new(),
+        synonyms_triggered: Vec::new(),
+        synonyms_matched: Vec::new(),
+        query_language: None,
     }
 }
 
@@ -198,6 +210,7 @@ fn disabled_collector_does_not_write() {
         flush_interval_secs: 3600,
         flush_size: 1,
         retention_days: 7,
+        pii: Default::default(),
     };
     let collector = AnalyticsCollector::new(config);
     collector.record_search(make_search("laptop", "products", None));
@@ -228,6 +241,110 @@ fn query_id_cache_returns_none_for_unknown() {
     assert!(collector.lookup_query_id("nonexistent").is_none());
 }
 
+#[test]
+fn record_search_scrubs_pii_before_caching() {
+    let tmp = TempDir::new().unwrap();
+    let mut config = collector_config(tmp.path(), 1000);
+    config.pii = crate::analytics::pii::PiiScrubConfig {
+        redact_emails: true,
+        redact_phone_numbers: true,
+        truncate_ips: true,
+    };
+    let collector = AnalyticsCollector::new(config);
+    let qid = "c".repeat(32);
+    let event = make_search("jane@example.com order", "products", Some(&qid));
+    collector.record_search(event);
+
+    let entry = collector.lookup_query_id(&qid).unwrap();
+    assert_eq!(entry.query, "[email] order");
+}
+
+const HOUR_MS: i64 = 3_600_000;
+
+#[test]
+fn check_insight_rejects_skewed_timestamps_and_oversized_object_ids() {
+    let tmp = TempDir::new().unwrap();
+    let collector = AnalyticsCollector::new(collector_config(tmp.path(), 1000));
+    let now = chrono::Utc::now().timestamp_millis();
+
+    assert!(collector
+        .check_insight(&make_insight("click", "products"), now)
+        .is_ok());
+
+    let mut future = make_insight("click", "products");
+    future.timestamp = Some(now + HOUR_MS);
+    assert_eq!(
+        collector.check_insight(&future, now).unwrap_err().0,
+        RejectReason::TimestampSkew
+    );
+
+    let mut stale = make_insight("click", "products");
+    stale.timestamp = Some(now - 5 * 24 * HOUR_MS);
+    assert_eq!(
+        collector.check_insight(&stale, now).unwrap_err().0,
+        RejectReason::TimestampSkew
+    );
+
+    let mut oversized = make_insight("view", "products");
+    oversized.object_ids = (0..21).map(|i| format!("obj{i}")).collect();
+    assert_eq!(
+        collector.check_insight(&oversized, now).unwrap_err().0,
+        RejectReason::TooManyObjectIds
+    );
+
+    let mut invalid = make_insight("hover", "products");
+    invalid.timestamp = None;
+    assert_eq!(
+        collector.check_insight(&invalid, now).unwrap_err().0,
+        RejectReason::Invalid
+    );
+}
+
+#[test]
+fn check_insight_rejects_unknown_query_ids_once_cache_is_warm() {
+    let tmp = TempDir::new().unwrap();
+    let collector = AnalyticsCollector::new(collector_config(tmp.path(), 1000));
+    let known = "a".repeat(32);
+    collector.record_search(make_search("laptop", "products", Some(&known)));
+
+    let mut event = make_insight("conversion", "products");
+    event.timestamp = None;
+    event.query_id = Some("b".repeat(32));
+
+    // Just after startup the cache may be missing pre-restart searches.
+    let now = chrono::Utc::now().timestamp_millis();
+    assert!(collector.check_insight(&event, now).is_ok());
+
+    let later = now + 2 * HOUR_MS;
+    let (reason, message) = collector.check_insight(&event, later).unwrap_err();
+    assert_eq!(reason, RejectReason::UnknownQueryId);
+    assert!(message.contains(&"b".repeat(32)), "{message}");
+
+    event.query_id = Some(known);
+    assert!(collector.check_insight(&event, later).is_ok());
+}
+
+#[test]
+fn rejected_insights_land_in_dead_letter_queue() {
+    let tmp = TempDir::new().unwrap();
+    let collector = AnalyticsCollector::new(collector_config(tmp.path(), 1000));
+    collector.reject_insight(
+        make_insight("click", "products"),
+        RejectReason::TimestampSkew,
+        "too old".to_string(),
+    );
+
+    let rejected = collector.dead_letters().recent(Some("products"), None, 10);
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].message, "too old");
+    assert_eq!(
+        collector
+            .dead_letters()
+            .rejected_total(RejectReason::TimestampSkew),
+        1
+    );
+}
+
 #[test]
 fn insight_events_flush_to_parquet() {
     let tmp = TempDir::new().unwrap();
@@ -471,6 +588,137 @@ async fn filters_query_returns_filter_data() {
     assert_eq!(filters[0]["count"], 2);
 }
 
+#[tokio::test]
+async fn rule_stats_counts_fires_and_engagement() {
+    let tmp = TempDir::new().unwrap();
+    let config = writer_config(tmp.path());
+    let mut e1 = make_search("shoes", "products", Some(&"1".repeat(32)));
+    e1.applied_rules = vec!["pin-shoes".to_string(), "banner".to_string()];
+    let mut e2 = make_search("boots", "products", Some(&"2".repeat(32)));
+    e2.applied_rules = vec!["pin-shoes".to_string()];
+    let mut e3 = make_search("sandals", "products", None);
+    e3.applied_rules = vec!["pin-shoes".to_string()];
+    let mut e4 = make_search("hats", "products", Some(&"3".repeat(32)));
+    e4.applied_rules = vec!["pin-shoes-2".to_string()];
+    let searches_dir = config.searches_dir("products");
+    writer::flush_search_events(&[e1, e2, e3, e4], &searches_dir).unwrap();
+
+    let mut click = make_insight("click", "products");
+    click.query_id = Some("1".repeat(32));
+    let mut conversion = make_insight("conversion", "products");
+    conversion.query_id = Some("1".repeat(32));
+    let events_dir = config.events_dir("products");
+    writer::flush_insight_events(&[click, conversion], &events_dir).unwrap();
+
+    let engine = AnalyticsQueryEngine::new(config);
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let result = engine
+        .rule_stats("products", "pin-shoes", &today, &today)
+        .await
+        .unwrap();
+    assert_eq!(result["count"], 3);
+    assert_eq!(result["trackedSearchCount"], 2);
+    assert_eq!(result["clickCount"], 1);
+    assert_eq!(result["conversionCount"], 1);
+    assert_eq!(result["clickThroughRate"], 0.5);
+    assert_eq!(result["dates"].as_array().unwrap().len(), 1);
+
+    let unused = engine
+        .rule_stats("products", "never-fires", &today, &today)
+        .await
+        .unwrap();
+    assert_eq!(unused["count"], 0);
+}
+
+#[tokio::test]
+async fn campaign_stats_count_impressions_and_pinned_clicks() {
+    let tmp = TempDir::new().unwrap();
+    let config = writer_config(tmp.path());
+    let mut e1 = make_search("sandals", "products", Some(&"1".repeat(32)));
+    e1.applied_rules = vec!["summer/sandals".to_string()];
+    let mut e2 = make_search("hats", "products", Some(&"2".repeat(32)));
+    e2.applied_rules = vec!["banner".to_string(), "summer/hats".to_string()];
+    let mut e3 = make_search("scarves", "products", Some(&"3".repeat(32)));
+    e3.applied_rules = vec!["summertime/scarves".to_string()];
+    let searches_dir = config.searches_dir("products");
+    writer::flush_search_events(&[e1, e2, e3], &searches_dir).unwrap();
+
+    // A click on the pinned sandal counts; a click on another hit does not.
+    let mut pinned_click = make_insight("click", "products");
+    pinned_click.query_id = Some("1".repeat(32));
+    pinned_click.object_ids = vec!["s1".to_string()];
+    let mut other_click = make_insight("click", "products");
+    other_click.query_id = Some("2".repeat(32));
+    let events_dir = config.events_dir("products");
+    writer::flush_insight_events(&[pinned_click, other_click], &events_dir).unwrap();
+
+    let engine = AnalyticsQueryEngine::new(config);
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let result = engine
+        .campaign_stats(
+            "products",
+            "summer",
+            &["s1".to_string(), "h1".to_string()],
+            &today,
+            &today,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result["impressions"], 2);
+    assert_eq!(result["trackedSearchCount"], 2);
+    assert_eq!(result["clickCount"], 1);
+    assert_eq!(result["clickThroughRate"], 0.5);
+}
+
+#[tokio::test]
+async fn synonym_usage_counts_triggered_and_matched() {
+    let tmp = TempDir::new().unwrap();
+    let config = writer_config(tmp.path());
+    let mut e1 = make_search("laptop", "products", None);
+    e1.synonyms_triggered = vec!["laptops".to_string(), "computers".to_string()];
+    e1.synonyms_matched = vec!["laptops".to_string()];
+    let mut e2 = make_search("notebook", "products", None);
+    e2.synonyms_triggered = vec!["laptops".to_string()];
+    let e3 = make_search("phone", "products", None);
+    let searches_dir = config.searches_dir("products");
+    writer::flush_search_events(&[e1, e2, e3], &searches_dir).unwrap();
+
+    let engine = AnalyticsQueryEngine::new(config);
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let usage = engine
+        .synonym_usage("products", &today, &today)
+        .await
+        .unwrap();
+    assert_eq!(usage.get("laptops"), Some(&(2, 1)));
+    assert_eq!(usage.get("computers"), Some(&(1, 0)));
+    assert_eq!(usage.len(), 2);
+}
+
+#[tokio::test]
+async fn search_languages_counts_known_languages_per_query() {
+    let tmp = TempDir::new().unwrap();
+    let config = writer_config(tmp.path());
+    let mut e1 = make_search("schuhe", "products", None);
+    e1.query_language = Some("de".to_string());
+    let mut e2 = make_search("schuhe", "products", None);
+    e2.query_language = Some("de".to_string());
+    let mut e3 = make_search("schuhe", "products", None);
+    e3.query_language = Some("nl".to_string());
+    let e4 = make_search("tv", "products", None);
+    let searches_dir = config.searches_dir("products");
+    writer::flush_search_events(&[e1, e2, e3, e4], &searches_dir).unwrap();
+
+    let engine = AnalyticsQueryEngine::new(config);
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let languages = engine
+        .search_languages("products", &today, &today, None)
+        .await
+        .unwrap();
+    assert_eq!(languages["schuhe"]["de"], 2);
+    assert_eq!(languages["schuhe"]["nl"], 1);
+    assert!(!languages.contains_key("tv"));
+}
+
 #[tokio::test]
 async fn filter_values_extracts_attribute_values() {
     let tmp = TempDir::new().unwrap();
@@ -527,6 +775,10 @@ fn writer_roundtrip_with_experiment_fields() {
         experiment_id: Some("exp-1".to_string()),
         variant_id: Some("variant".to_string()),
         assignment_method: Some("user_token".to_string()),
+        applied_rules: Vec::new(),
+        synonyms_triggered: Vec::new(),
+        synonyms_matched: Vec::new(),
+        query_language: None,
     };
     writer::flush_search_events(&[event], &dir).unwrap();
 
@@ -607,6 +859,10 @@ fn writer_roundtrip_with_null_experiment_fields() {
         experiment_id: None,
         variant_id: None,
         assignment_method: None,
+        applied_rules: Vec::new(),
+        synonyms_triggered: Vec::new(),
+        synonyms_matched: Vec::new(),
+        query_language: None,
     };
     writer::flush_search_events(&[event], &dir).unwrap();
 
@@ -681,6 +937,10 @@ fn cleanup_search_event(query: &str, index: &str) -> SearchEvent {
         experiment_id: None,
         variant_id: None,
         assignment_method: None,
+        applied_rules: Vec::new(),
+        synonyms_triggered: Vec::new(),
+        synonyms_matched: Vec::new(),
+        query_language: None,
     }
 }
 
@@ -848,6 +1108,7 @@ async fn cleanup_analytics_dir_missing() {
         flush_interval_secs: 3600,
         flush_size: 10_000,
         retention_days: 90,
+        pii: Default::default(),
     };
     let engine = AnalyticsQueryEngine::new(config);
     let removed = run_cleanup(&engine, index_dir.path());
@@ -1674,3 +1674,234 @@ mod response_fields {
         assert!(result.facets.is_empty());
     }
 }
+
+#[tokio::test]
+async fn test_filter_only_attribute_filters_but_has_no_counts() {
+    let docs = vec![
+        doc(
+            "1",
+            vec![("brand", text("Apple")), ("color", text("Black"))],
+        ),
+        doc(
+            "2",
+            vec![("brand", text("Samsung")), ("color", text("White"))],
+        ),
+    ];
+    let (_tmp, mgr) = setup_with_settings(vec!["brand", "filterOnly(color)"], docs).await;
+
+    let result = mgr
+        .search_with_facets(
+            "test",
+            "",
+            None,
+            None,
+            10,
+            0,
+            Some(&[facet_req("brand"), facet_req("color")]),
+        )
+        .unwrap();
+    assert_eq!(result.facets.get("brand").unwrap().len(), 2);
+    assert!(
+        result.facets.get("color").is_none_or(|c| c.is_empty()),
+        "filterOnly(color) must not produce facet counts"
+    );
+
+    let filter = crate::types::Filter::Equals {
+        field: "color".to_string(),
+        value: text("Black"),
+    };
+    let filtered = mgr.search("test", "", Some(&filter), None, 10).unwrap();
+    assert_eq!(filtered.total, 1);
+    assert_eq!(filtered.documents[0].document.id, "1");
+}
+
+#[tokio::test]
+async fn test_detected_language_is_stored_and_faceted() {
+    let temp_dir = TempDir::new().unwrap();
+    let manager = IndexManager::new(temp_dir.path());
+    manager.create_tenant("test").unwrap();
+    let settings = IndexSettings {
+        auto_detect_language: true,
+        ..Default::default()
+    };
+    settings
+        .save(temp_dir.path().join("test/settings.json"))
+        .unwrap();
+    let docs = vec![
+        doc(
+            "1",
+            vec![(
+                "body",
+                text("The quick brown fox jumps over the lazy dog near the river bank"),
+            )],
+        ),
+        doc(
+            "2",
+            vec![(
+                "body",
+                text("Le chat est assis sur le tapis et regarde par la fenêtre"),
+            )],
+        ),
+        doc("3", vec![("body", text("ZX-10"))]),
+    ];
+    manager.add_documents_sync("test", docs).await.unwrap();
+
+    let result = manager
+        .search_with_facets(
+            "test",
+            "",
+            None,
+            None,
+            10,
+            0,
+            Some(&[facet_req("_detectedLanguage")]),
+        )
+        .unwrap();
+    let counts: HashMap<String, u64> = result.facets["_detectedLanguage"]
+        .iter()
+        .map(|f| (f.path.clone(), f.count))
+        .collect();
+    assert_eq!(counts.get("en"), Some(&1));
+    assert_eq!(counts.get("fr"), Some(&1));
+
+    let filter = crate::types::Filter::Equals {
+        field: "_detectedLanguage".to_string(),
+        value: text("fr"),
+    };
+    let filtered = manager.search("test", "", Some(&filter), None, 10).unwrap();
+    assert_eq!(filtered.total, 1);
+    assert_eq!(filtered.documents[0].document.id, "2");
+}
+
+#[tokio::test]
+async fn test_normalized_facet_values_share_a_bucket() {
+    use crate::index::facet_normalization::FacetValueNormalization;
+
+    let temp_dir = TempDir::new().unwrap();
+    let manager = IndexManager::new(temp_dir.path());
+    manager.create_tenant("test").unwrap();
+    let settings = IndexSettings {
+        attributes_for_faceting: vec!["country".into()],
+        facet_value_normalization: HashMap::from([(
+            "country".to_string(),
+            FacetValueNormalization {
+                case_fold: true,
+                trim: true,
+                display_names: HashMap::from([("usa".into(), "United States".into())]),
+            },
+        )]),
+        ..Default::default()
+    };
+    settings
+        .save(temp_dir.path().join("test/settings.json"))
+        .unwrap();
+    let docs = vec![
+        doc("1", vec![("country", text("usa"))]),
+        doc("2", vec![("country", text("USA "))]),
+        doc("3", vec![("country", text("United States"))]),
+        doc("4", vec![("country", text("France"))]),
+    ];
+    manager.add_documents_sync("test", docs).await.unwrap();
+
+    let result = manager
+        .search_with_facets("test", "", None, None, 10, 0, Some(&[facet_req("country")]))
+        .unwrap();
+    let counts = result.facets.get("country").unwrap();
+    assert_eq!(counts.len(), 2);
+    assert_eq!(
+        counts
+            .iter()
+            .find(|f| f.path == "United States")
+            .map(|f| f.count),
+        Some(3)
+    );
+    assert!(counts.iter().any(|f| f.path == "france"));
+
+    // Filtering on the displayed value (or any variant) matches every variant.
+    for value in ["United States", "usa"] {
+        let filter = crate::types::Filter::Equals {
+            field: "country".to_string(),
+            value: text(value),
+        };
+        let filtered = manager.search("test", "", Some(&filter), None, 10).unwrap();
+        assert_eq!(filtered.total, 3, "{value}");
+    }
+    let not_us = crate::types::Filter::NotEquals {
+        field: "country".to_string(),
+        value: text("USA"),
+    };
+    let filtered = manager.search("test", "", Some(&not_us), None, 10).unwrap();
+    assert_eq!(filtered.total, 1);
+
+    // Stored documents keep what was written.
+    let stored = manager.get_document("test", "2").unwrap().unwrap();
+    assert_eq!(stored.fields.get("country"), Some(&text("USA ")));
+}
+
+#[tokio::test]
+async fn test_ingest_transforms_reshape_written_documents() {
+    let temp_dir = TempDir::new().unwrap();
+    let manager = IndexManager::new(temp_dir.path());
+    manager.create_tenant("test").unwrap();
+    let settings: IndexSettings = serde_json::from_value(serde_json::json!({
+        "attributesForFaceting": ["price_range"],
+        "ingestTransforms": [
+            {"type": "rename", "from": "product_name", "to": "name"},
+            {"type": "drop", "attributes": ["cost"]},
+            {"type": "bucket", "attribute": "price", "to": "price_range",
+             "ranges": [{"below": 50, "value": "under 50"}], "otherwise": "50+"},
+            {"type": "geo", "lat": "lat", "lng": "lon", "removeSource": true}
+        ]
+    }))
+    .unwrap();
+    settings
+        .save(temp_dir.path().join("test/settings.json"))
+        .unwrap();
+    let docs = vec![
+        doc(
+            "1",
+            vec![
+                ("product_name", text("Tent")),
+                ("price", int(120)),
+                ("cost", int(70)),
+                ("lat", float(45.5)),
+                ("lon", float(-73.6)),
+            ],
+        ),
+        doc("2", vec![("product_name", text("Mug")), ("price", int(12))]),
+        doc(
+            "3",
+            vec![("product_name", text("Lamp")), ("price", int(30))],
+        ),
+    ];
+    manager.add_documents_sync("test", docs).await.unwrap();
+
+    let stored = manager.get_document("test", "1").unwrap().unwrap();
+    assert_eq!(stored.fields.get("name"), Some(&text("Tent")));
+    assert_eq!(stored.fields.get("price_range"), Some(&text("50+")));
+    for removed in ["product_name", "cost", "lat", "lon"] {
+        assert!(!stored.fields.contains_key(removed), "{removed}");
+    }
+    let geoloc = serde_json::to_value(stored.fields.get("_geoloc").unwrap()).unwrap();
+    assert_eq!(geoloc, serde_json::json!({"lat": 45.5, "lng": -73.6}));
+
+    let result = manager
+        .search_with_facets(
+            "test",
+            "",
+            None,
+            None,
+            10,
+            0,
+            Some(&[facet_req("price_range")]),
+        )
+        .unwrap();
+    let counts = result.facets.get("price_range").unwrap();
+    assert_eq!(
+        counts
+            .iter()
+            .find(|f| f.path == "under 50")
+            .map(|f| f.count),
+        Some(2)
+    );
+}
@@ -1,8 +1,10 @@
 //! Query integration tests moved inline from engine/tests/test_query.rs.
 //!
 //! Covers: plurals, stopwords, synonym store persistence, highlighter regression,
-//! and JSON prefix search. The 2 tests that depend on flapjack_http::dto::SearchRequest
-//! remain in engine/tests/test_query.rs.
+//! JSON prefix search, did-you-mean spelling suggestions, searchableAttributes
+//! ordering (including `unordered()`), text normalization, camelCase splitting,
+//! advancedSyntax phrases/exclusions and localizedAttributes. The 2 tests that
+//! depend on flapjack_http::dto::SearchRequest remain in engine/tests/test_query.rs.
 
 use crate::index::settings::IndexSettings;
 use crate::index::synonyms::{Synonym, SynonymStore};
@@ -296,6 +298,8 @@ mod plurals {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .unwrap();
         let ids: Vec<&str> = result
@@ -339,6 +343,39 @@ mod plurals {
         assert!(!ids.contains(&"2"));
     }
 
+    #[tokio::test]
+    async fn localized_attributes_search_query_language_variants() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = IndexManager::new(temp_dir.path());
+        manager.create_tenant("test").unwrap();
+
+        let settings = IndexSettings {
+            query_languages: vec!["de".to_string()],
+            localized_attributes: vec!["title".to_string()],
+            ..Default::default()
+        };
+        settings
+            .save(temp_dir.path().join("test/settings.json"))
+            .unwrap();
+        manager.invalidate_settings_cache("test");
+
+        let title: HashMap<String, FieldValue> = [
+            ("en".to_string(), text("hiking boots")),
+            ("de".to_string(), text("wanderstiefel")),
+        ]
+        .into_iter()
+        .collect();
+        let docs = vec![doc("1", vec![("title", FieldValue::Object(title))])];
+        manager.add_documents_sync("test", docs).await.unwrap();
+
+        let found = manager
+            .search("test", "wanderstiefel", None, None, 10)
+            .unwrap();
+        assert_eq!(found.documents.len(), 1);
+        let found = manager.search("test", "boots", None, None, 10).unwrap();
+        assert!(found.documents.is_empty());
+    }
+
     #[tokio::test]
     async fn serde_roundtrip_settings() {
         let settings = IndexSettings {
@@ -577,6 +614,8 @@ mod stopwords {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .unwrap();
         let without_override = manager
@@ -602,6 +641,8 @@ mod stopwords {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .unwrap();
         assert_eq!(
@@ -660,6 +701,8 @@ mod stopwords {
                 None,
                 None,
                 None,
+                None,
+                None,
             )
             .unwrap();
         assert!(
@@ -805,3 +848,339 @@ mod json_prefix_search {
         );
     }
 }
+
+// ============================================================
+// Did-you-mean spelling suggestions
+// ============================================================
+
+mod spelling {
+    use super::*;
+
+    #[tokio::test]
+    async fn suggests_indexed_word_for_misspelling() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = IndexManager::new(temp_dir.path());
+        manager.create_tenant("products").unwrap();
+        manager
+            .add_documents_sync(
+                "products",
+                vec![
+                    doc("1", vec![("title", text("Gaming Laptop"))]),
+                    doc("2", vec![("title", text("Laptop Stand"))]),
+                ],
+            )
+            .await
+            .unwrap();
+
+        let suggestions = manager
+            .suggest_spellings("products", "gaming lpatop", 3)
+            .unwrap();
+        assert_eq!(
+            suggestions.first().map(String::as_str),
+            Some("gaming laptop")
+        );
+
+        let none = manager.suggest_spellings("products", "laptop", 3).unwrap();
+        assert!(none.is_empty(), "known words need no correction");
+    }
+}
+
+// ============================================================
+// searchableAttributes ordering and unordered()
+// ============================================================
+
+mod searchable_attributes {
+    use super::*;
+
+    async fn ranked_ids(searchable: &[&str]) -> Vec<String> {
+        ranked_ids_with_weights(searchable, &[]).await
+    }
+
+    async fn ranked_ids_with_weights(searchable: &[&str], weights: &[(&str, f32)]) -> Vec<String> {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = IndexManager::new(temp_dir.path());
+        manager.create_tenant("products").unwrap();
+        let settings = IndexSettings {
+            searchable_attributes: Some(searchable.iter().map(|s| s.to_string()).collect()),
+            attribute_weights: weights.iter().map(|(a, w)| (a.to_string(), *w)).collect(),
+            ..Default::default()
+        };
+        settings
+            .save(temp_dir.path().join("products/settings.json"))
+            .unwrap();
+        manager.invalidate_settings_cache("products");
+        manager
+            .add_documents_sync(
+                "products",
+                vec![
+                    doc(
+                        "in_title",
+                        vec![("title", text("red shoe")), ("description", text("casual"))],
+                    ),
+                    doc(
+                        "in_description",
+                        vec![
+                            ("title", text("sneaker")),
+                            ("description", text("red apple")),
+                        ],
+                    ),
+                ],
+            )
+            .await
+            .unwrap();
+
+        manager
+            .search("products", "red", None, None, 10)
+            .unwrap()
+            .documents
+            .into_iter()
+            .map(|d| d.document.id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn earlier_attribute_ranks_first() {
+        assert_eq!(
+            ranked_ids(&["title", "description"]).await,
+            vec!["in_title", "in_description"]
+        );
+        assert_eq!(
+            ranked_ids(&["description", "title"]).await,
+            vec!["in_description", "in_title"]
+        );
+    }
+
+    #[tokio::test]
+    async fn attribute_weights_outrank_position() {
+        assert_eq!(
+            ranked_ids_with_weights(&["title", "description"], &[("description", 2.0)]).await,
+            vec!["in_description", "in_title"]
+        );
+        assert_eq!(
+            ranked_ids_with_weights(&["title", "description"], &[("title", 0.5)]).await,
+            vec!["in_title", "in_description"]
+        );
+    }
+
+    #[tokio::test]
+    async fn unordered_modifier_keeps_attribute_priority() {
+        assert_eq!(
+            ranked_ids(&["unordered(description)", "title"]).await,
+            vec!["in_description", "in_title"]
+        );
+    }
+}
+
+// ============================================================
+// Text normalization
+// ============================================================
+
+mod text_normalization {
+    use super::*;
+
+    fn ids(manager: &IndexManager, query: &str) -> Vec<String> {
+        manager
+            .search("test", query, None, None, 10)
+            .unwrap()
+            .documents
+            .into_iter()
+            .map(|d| d.document.id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn diacritics_fold_on_both_sides() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = IndexManager::new(temp_dir.path());
+        manager.create_tenant("test").unwrap();
+        let settings = IndexSettings {
+            remove_diacritics: true,
+            transliterate: vec!["cyrillic".to_string()],
+            ..Default::default()
+        };
+        settings
+            .save(temp_dir.path().join("test/settings.json"))
+            .unwrap();
+
+        let docs = vec![
+            doc("1", vec![("name", text("Crème brûlée"))]),
+            doc("2", vec![("name", text("Москва"))]),
+        ];
+        manager.add_documents_sync("test", docs).await.unwrap();
+
+        assert_eq!(ids(&manager, "creme brulee"), vec!["1"]);
+        assert_eq!(ids(&manager, "brûlée"), vec!["1"]);
+        assert_eq!(ids(&manager, "moskva"), vec!["2"]);
+    }
+
+    #[tokio::test]
+    async fn reindex_applies_new_normalization() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = IndexManager::new(temp_dir.path());
+        manager.create_tenant("test").unwrap();
+        let docs = vec![doc("1", vec![("name", text("Москва"))])];
+        manager.add_documents_sync("test", docs).await.unwrap();
+        assert!(ids(&manager, "moskva").is_empty());
+
+        let settings = IndexSettings {
+            transliterate: vec!["cyrillic".to_string()],
+            ..Default::default()
+        };
+        settings
+            .save(temp_dir.path().join("test/settings.json"))
+            .unwrap();
+        manager.invalidate_settings_cache("test");
+        assert_eq!(manager.reindex_documents("test").await.unwrap(), 1);
+        assert_eq!(ids(&manager, "moskva"), vec!["1"]);
+    }
+
+    #[tokio::test]
+    async fn reindex_includes_documents_hidden_from_search() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = IndexManager::new(temp_dir.path());
+        manager.create_tenant("test").unwrap();
+        let later = chrono::Utc::now().timestamp() + 86_400;
+        let docs = vec![
+            doc("1", vec![("name", text("Москва"))]),
+            doc(
+                "2",
+                vec![
+                    ("name", text("Москва")),
+                    ("_publishAt", FieldValue::Integer(later)),
+                ],
+            ),
+        ];
+        manager.add_documents_sync("test", docs).await.unwrap();
+        assert_eq!(manager.reindex_documents("test").await.unwrap(), 2);
+    }
+}
+
+mod advanced_syntax {
+    use super::*;
+
+    async fn fixture() -> (TempDir, Arc<IndexManager>) {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = IndexManager::new(temp_dir.path());
+        manager.create_tenant("test").unwrap();
+        let mut store = SynonymStore::new();
+        store.insert(Synonym::Regular {
+            object_id: "laptop-notebook".to_string(),
+            synonyms: vec!["laptop".to_string(), "notebook".to_string()],
+        });
+        store
+            .save(temp_dir.path().join("test/synonyms.json"))
+            .unwrap();
+
+        let docs = vec![
+            doc("1", vec![("name", text("gaming laptop review"))]),
+            doc("2", vec![("name", text("laptop for gaming"))]),
+            doc("3", vec![("name", text("refurbished gaming laptop"))]),
+            doc("4", vec![("name", text("open box laptop deal"))]),
+            doc("5", vec![("name", text("gaming notebook"))]),
+        ];
+        manager.add_documents_sync("test", docs).await.unwrap();
+        (temp_dir, manager)
+    }
+
+    fn ids(manager: &IndexManager, query: &str) -> Vec<String> {
+        let mut ids: Vec<String> = manager
+            .search_full_with_stop_words(
+                "test",
+                query,
+                None,
+                None,
+                10,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(true),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+            .documents
+            .into_iter()
+            .map(|d| d.document.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn phrase_requires_adjacent_exact_words() {
+        let (_tmp, manager) = fixture().await;
+        assert_eq!(ids(&manager, "\"gaming laptop\""), vec!["1", "3"]);
+        assert_eq!(ids(&manager, "review \"gaming laptop\""), vec!["1"]);
+        assert!(ids(&manager, "\"gamng laptop\"").is_empty());
+        assert!(ids(&manager, "\"gaming lap\"").is_empty());
+    }
+
+    #[tokio::test]
+    async fn synonyms_do_not_rewrite_phrases() {
+        let (_tmp, manager) = fixture().await;
+        assert_eq!(ids(&manager, "gaming laptop"), vec!["1", "2", "3", "5"]);
+        assert_eq!(ids(&manager, "\"gaming notebook\""), vec!["5"]);
+    }
+
+    #[tokio::test]
+    async fn exclusions_drop_words_and_phrases() {
+        let (_tmp, manager) = fixture().await;
+        // "laptop" still expands to "notebook"; the exclusions stay put.
+        assert_eq!(
+            ids(&manager, "laptop -refurbished"),
+            vec!["1", "2", "4", "5"]
+        );
+        assert_eq!(
+            ids(&manager, "laptop -\"open box\""),
+            vec!["1", "2", "3", "5"]
+        );
+        assert_eq!(ids(&manager, "-gaming"), vec!["4"]);
+    }
+}
+
+mod camel_case {
+    use super::*;
+
+    #[tokio::test]
+    async fn camel_and_snake_case_queries_match_spaced_words() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = IndexManager::new(temp_dir.path());
+        manager.create_tenant("test").unwrap();
+        let settings = IndexSettings {
+            split_camel_case: true,
+            ..Default::default()
+        };
+        settings
+            .save(temp_dir.path().join("test/settings.json"))
+            .unwrap();
+
+        let docs = vec![
+            doc("1", vec![("name", text("MacBook Pro 16"))]),
+            doc("2", vec![("name", text("ThinkPad X1"))]),
+        ];
+        manager.add_documents_sync("test", docs).await.unwrap();
+
+        for query in ["MacBookPro", "mac_book_pro", "macbook pro", "mac book"] {
+            let ids: Vec<String> = manager
+                .search("test", query, None, None, 10)
+                .unwrap()
+                .documents
+                .into_iter()
+                .map(|d| d.document.id)
+                .collect();
+            assert_eq!(ids, vec!["1"], "query {:?}", query);
+        }
+    }
+}
@@ -816,3 +816,132 @@ async fn test_custom_ranking_missing_values() {
     assert_eq!(results.documents[1].document.id, "1");
     assert_eq!(results.documents[2].document.id, "2");
 }
+
+// ============================================================
+// exact criterion — exactOnSingleWordQuery / alternativesAsExact
+// ============================================================
+
+async fn exact_fixture(settings: IndexSettings) -> (TempDir, Arc<IndexManager>) {
+    let temp = TempDir::new().unwrap();
+    let manager = IndexManager::new(temp.path());
+    manager.create_tenant("test").unwrap();
+    settings
+        .save(temp.path().join("test/settings.json"))
+        .unwrap();
+
+    let docs: Vec<Document> = vec![
+        json!({"_id": "juice", "name": "Apple Juice", "popularity": 1000}),
+        json!({"_id": "sauce", "name": "Applesauce", "popularity": 900}),
+        json!({"_id": "brand", "name": "Apple", "popularity": 1}),
+    ]
+    .into_iter()
+    .map(|v| Document::from_json(&v).unwrap())
+    .collect();
+    manager.add_documents_sync("test", docs).await.unwrap();
+    (temp, manager)
+}
+
+fn ids(result: &crate::types::SearchResult) -> Vec<&str> {
+    result
+        .documents
+        .iter()
+        .map(|d| d.document.id.as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_exact_single_word_attribute_mode_ranks_whole_value_first() {
+    let (_tmp, manager) = exact_fixture(IndexSettings {
+        custom_ranking: Some(vec!["desc(popularity)".to_string()]),
+        ..Default::default()
+    })
+    .await;
+
+    let result = manager.search("test", "apple", None, None, 10).unwrap();
+    assert_eq!(result.documents.len(), 3);
+    assert_eq!(ids(&result)[0], "brand");
+}
+
+#[tokio::test]
+async fn test_exact_single_word_word_mode_ranks_whole_words_above_prefixes() {
+    let (_tmp, manager) = exact_fixture(IndexSettings {
+        custom_ranking: Some(vec!["desc(popularity)".to_string()]),
+        exact_on_single_word_query: "word".to_string(),
+        ..Default::default()
+    })
+    .await;
+
+    let result = manager.search("test", "apple", None, None, 10).unwrap();
+    let ids = ids(&result);
+    assert_eq!(ids.len(), 3);
+    assert_eq!(ids[2], "sauce", "prefix-only match ranks last");
+}
+
+#[tokio::test]
+async fn test_exact_plural_counts_when_alternatives_as_exact() {
+    let (_tmp, manager) = exact_fixture(IndexSettings {
+        ignore_plurals: crate::query::plurals::IgnorePluralsValue::All,
+        ..Default::default()
+    })
+    .await;
+
+    let result = manager.search("test", "apples", None, None, 10).unwrap();
+    assert!(!result.documents.is_empty());
+    assert_eq!(ids(&result)[0], "brand");
+}
+
+async fn typo_fixture(settings: IndexSettings) -> (TempDir, Arc<IndexManager>) {
+    let temp = TempDir::new().unwrap();
+    let manager = IndexManager::new(temp.path());
+    manager.create_tenant("test").unwrap();
+    settings
+        .save(temp.path().join("test/settings.json"))
+        .unwrap();
+
+    // For "toothbrush holder": "typo" matches directly but with a typo and
+    // a prefix (no exact word), "split" only through the split alternative
+    // "tooth brush holder" (one exact word).
+    let docs: Vec<Document> = vec![
+        json!({"_id": "typo", "name": "Toothbrsh holders"}),
+        json!({"_id": "split", "name": "Tooth brush holder"}),
+    ]
+    .into_iter()
+    .map(|v| Document::from_json(&v).unwrap())
+    .collect();
+    manager.add_documents_sync("test", docs).await.unwrap();
+    (temp, manager)
+}
+
+#[tokio::test]
+async fn test_exact_after_typo_keeps_split_matches_last() {
+    let (_tmp, manager) = typo_fixture(IndexSettings::default()).await;
+
+    let result = manager
+        .search("test", "toothbrush holder", None, None, 10)
+        .unwrap();
+    assert_eq!(ids(&result), vec!["typo", "split"]);
+}
+
+#[tokio::test]
+async fn test_exact_before_typo_ranks_exact_words_first() {
+    let ranking = [
+        "exact",
+        "typo",
+        "geo",
+        "words",
+        "filters",
+        "proximity",
+        "attribute",
+        "custom",
+    ];
+    let (_tmp, manager) = typo_fixture(IndexSettings {
+        ranking: Some(ranking.iter().map(|c| c.to_string()).collect()),
+        ..Default::default()
+    })
+    .await;
+
+    let result = manager
+        .search("test", "toothbrush holder", None, None, 10)
+        .unwrap();
+    assert_eq!(ids(&result), vec!["split", "typo"]);
+}
@@ -275,6 +275,44 @@ async fn test_user_data_in_response() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_rendering_content_redirect_in_response() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let manager = IndexManager::new(temp_dir.path());
+    manager.create_tenant("test")?;
+
+    let mut fields = HashMap::new();
+    fields.insert(
+        "name".to_string(),
+        crate::types::FieldValue::Text("returns desk".to_string()),
+    );
+    let doc = Document {
+        id: "1".to_string(),
+        fields,
+    };
+    manager.add_documents_sync("test", vec![doc]).await?;
+
+    let rule = json!({
+        "objectID": "returns-redirect",
+        "conditions": [{"pattern": "returns", "anchoring": "is"}],
+        "consequence": {"params": {"renderingContent": {
+            "redirect": {"url": "https://example.com/returns"},
+            "widgets": {"banners": [{"image": {"urls": [{"url": "https://example.com/b.png"}]}}]}
+        }}}
+    });
+    std::fs::write(
+        temp_dir.path().join("test").join("rules.json"),
+        serde_json::to_string(&vec![rule])?,
+    )?;
+
+    let result = manager.search("test", "returns", None, None, 10)?;
+    let content = result.rendering_content.expect("renderingContent");
+    assert_eq!(content.redirect.unwrap().url, "https://example.com/returns");
+    assert_eq!(content.widgets.unwrap().banners.len(), 1);
+    assert_eq!(result.applied_rules, vec!["returns-redirect".to_string()]);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_query_rewrite() -> Result<()> {
     let temp_dir = TempDir::new()?;
@@ -562,6 +562,78 @@ mod splitting_integration {
         }
     }
 
+    mod typo_class {
+        use super::*;
+        use crate::index::settings::IndexSettings;
+
+        #[tokio::test]
+        async fn split_matches_rank_after_direct_matches() {
+            let f = get_fixture().await;
+            assert_eq!(search_ids(&f.mgr, "hotdog"), vec!["2", "1"]);
+            assert_eq!(search_ids(&f.mgr, "backpack"), vec!["7", "8"]);
+        }
+
+        #[tokio::test]
+        async fn typo_tolerance_off_disables_alternatives() {
+            let f = get_fixture().await;
+            let ids: Vec<String> = f
+                .mgr
+                .search_full_with_stop_words(
+                    "test",
+                    "hotdog",
+                    None,
+                    None,
+                    20,
+                    0,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(false),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap()
+                .documents
+                .into_iter()
+                .map(|d| d.document.id)
+                .collect();
+            assert_eq!(ids, vec!["2"]);
+        }
+
+        #[tokio::test]
+        async fn min_word_size_limits_alternatives() {
+            let temp_dir = TempDir::new().unwrap();
+            let manager = IndexManager::new(temp_dir.path());
+            manager.create_tenant("test").unwrap();
+            let settings = IndexSettings {
+                min_word_size_for_1_typo: 8,
+                ..Default::default()
+            };
+            settings
+                .save(temp_dir.path().join("test/settings.json"))
+                .unwrap();
+            let docs = vec![
+                doc("1", vec![("name", text("hot dog stand"))]),
+                doc("2", vec![("name", text("rock star poster"))]),
+            ];
+            manager.add_documents_sync("test", docs).await.unwrap();
+
+            assert!(search_ids(&manager, "hotdog").is_empty());
+            assert_eq!(search_ids(&manager, "rockstar"), vec!["2"]);
+        }
+    }
+
     mod edge_cases {
         use super::*;
 
@@ -66,6 +66,9 @@
 
 pub mod error;
 pub mod experiments;
+#[cfg(feature = "fault-injection")]
+pub mod faults;
+pub mod fixtures;
 pub mod index;
 pub mod query;
 pub mod tokenizer;
@@ -80,7 +83,7 @@ pub mod analytics;
 #[cfg(feature = "analytics")]
 pub mod query_suggestions;
 
-pub use error::{FlapjackError, Result};
+pub use error::{ErrorCode, FlapjackError, Result};
 pub use index::{manager::IndexManager, Index, ManagedIndexWriter};
 pub use query::{QueryExecutor, QueryParser};
 pub use types::*;
@@ -88,6 +91,7 @@ pub use types::*;
 pub use index::get_global_budget;
 pub use index::memory::{MemoryBudget, MemoryBudgetConfig};
 pub use index::memory_observer::{MemoryObserver, MemoryStats, PressureLevel};
+pub use index::resources::ResourceLimits;
 pub use types::{FacetCount, FacetRequest};
 
 // Re-export from flapjack-ssl
@@ -0,0 +1,112 @@
+//! The query-text stage that runs before parsing: stop-word removal, then
+//! plural expansion of the words that are left. Search and the
+//! `/query/analyze` preview both go through [`analyze_query`], so the preview
+//! shows exactly what a search would do.
+
+use super::plurals::{self, IgnorePluralsValue};
+use super::stopwords::{self, RemoveStopWordsValue};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryAnalysis {
+    pub query: String,
+    /// Words dropped as stop words, in query order.
+    pub removed_stop_words: Vec<String>,
+    /// Query text after stop-word removal.
+    pub query_after_stop_words: String,
+    /// Lowercased word -> every form it matches, for words with more than
+    /// one form.
+    pub plurals: BTreeMap<String, Vec<String>>,
+    /// Lowercased words of the final query, with their plural forms.
+    pub terms: Vec<Vec<String>>,
+}
+
+pub fn analyze_query(
+    query: &str,
+    remove_stop_words: Option<&RemoveStopWordsValue>,
+    ignore_plurals: Option<&IgnorePluralsValue>,
+    query_languages: &[String],
+    query_type: &str,
+) -> QueryAnalysis {
+    let stopped = match remove_stop_words {
+        Some(sw) => stopwords::remove_stop_words(query, sw, query_type),
+        None => query.to_string(),
+    };
+
+    // Stop-word removal only drops words, so what's left is a subsequence.
+    let mut kept = stopped.split_whitespace().peekable();
+    let mut removed_stop_words = Vec::new();
+    for word in query.split_whitespace() {
+        if kept.peek() == Some(&word) {
+            kept.next();
+        } else {
+            removed_stop_words.push(word.to_string());
+        }
+    }
+
+    let expand = match ignore_plurals {
+        Some(ip) if *ip != IgnorePluralsValue::Disabled => {
+            plurals::should_expand_english(&plurals::resolve_plural_languages(ip, query_languages))
+        }
+        _ => false,
+    };
+    let mut plural_map = BTreeMap::new();
+    let mut terms = Vec::new();
+    for word in stopped.split_whitespace() {
+        let lower = word.to_lowercase();
+        let forms = if expand {
+            plurals::expand_plurals(&lower)
+        } else {
+            vec![lower.clone()]
+        };
+        if forms.len() > 1 {
+            plural_map.insert(lower, forms.clone());
+        }
+        terms.push(forms);
+    }
+
+    QueryAnalysis {
+        query: query.to_string(),
+        removed_stop_words,
+        query_after_stop_words: stopped,
+        plurals: plural_map,
+        terms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_removed_words_and_plural_forms() {
+        let a = analyze_query(
+            "the cars of paris",
+            Some(&RemoveStopWordsValue::All),
+            Some(&IgnorePluralsValue::All),
+            &[],
+            "prefixNone",
+        );
+        assert_eq!(a.removed_stop_words, vec!["the", "of"]);
+        assert_eq!(a.query_after_stop_words, "cars paris");
+        assert_eq!(a.plurals["cars"], vec!["cars", "car"]);
+        assert_eq!(a.terms[0], vec!["cars", "car"]);
+    }
+
+    #[test]
+    fn prefix_word_is_kept_and_disabled_settings_are_noops() {
+        let a = analyze_query(
+            "shoes for the",
+            Some(&RemoveStopWordsValue::All),
+            Some(&IgnorePluralsValue::Disabled),
+            &[],
+            "prefixLast",
+        );
+        assert_eq!(a.removed_stop_words, vec!["for"]);
+        assert_eq!(a.query_after_stop_words, "shoes the");
+        assert!(a.plurals.is_empty());
+        assert_eq!(a.terms, vec![vec!["shoes"], vec!["the"]]);
+    }
+}
@@ -0,0 +1,291 @@
+use crate::types::{Document, FieldValue};
+use std::collections::HashMap;
+
+/// How the `exact` criterion treats single-word queries
+/// (`exactOnSingleWordQuery`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SingleWordQueryMode {
+    /// Exact only when an entire attribute value equals the query word.
+    Attribute,
+    /// Exact whenever the query word appears as a whole word.
+    Word,
+    /// Single-word queries never count as exact.
+    None,
+}
+
+impl SingleWordQueryMode {
+    /// Unknown values fall back to Algolia's default, `attribute`.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "word" => SingleWordQueryMode::Word,
+            "none" => SingleWordQueryMode::None,
+            _ => SingleWordQueryMode::Attribute,
+        }
+    }
+}
+
+/// The `exact` ranking criterion: the number of query words (stop words
+/// already removed) that a hit matches as whole words rather than through a
+/// prefix or a typo. Alternatives registered via [`with_alternatives`]
+/// (plurals, synonyms allowed by `alternativesAsExact`) count as exact too.
+///
+/// [`with_alternatives`]: ExactCriterion::with_alternatives
+#[derive(Debug, Clone)]
+pub struct ExactCriterion {
+    words: Vec<String>,
+    alternatives: HashMap<String, Vec<Vec<String>>>,
+    single_word_mode: SingleWordQueryMode,
+    paths: Vec<String>,
+}
+
+impl ExactCriterion {
+    pub fn new(
+        query_text: &str,
+        single_word_mode: SingleWordQueryMode,
+        paths: Vec<String>,
+    ) -> Self {
+        ExactCriterion {
+            words: tokenize(query_text),
+            alternatives: HashMap::new(),
+            single_word_mode,
+            paths,
+        }
+    }
+
+    /// Accept `alternatives` of `word` as exact matches. Multi-word
+    /// alternatives must appear as consecutive words.
+    pub fn with_alternatives<I, S>(mut self, word: &str, alternatives: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let word = word.to_lowercase();
+        let entry = self.alternatives.entry(word.clone()).or_default();
+        for alt in alternatives {
+            let tokens = tokenize(alt.as_ref());
+            if !tokens.is_empty() && tokens != [word.clone()] && !entry.contains(&tokens) {
+                entry.push(tokens);
+            }
+        }
+        self
+    }
+
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// False when the criterion cannot distinguish any two hits.
+    pub fn is_active(&self) -> bool {
+        match self.words.len() {
+            0 => false,
+            1 => self.single_word_mode != SingleWordQueryMode::None,
+            _ => true,
+        }
+    }
+
+    /// Number of query words `doc` matches exactly (higher ranks first).
+    pub fn count(&self, doc: &Document) -> usize {
+        if !self.is_active() {
+            return 0;
+        }
+        let attributes = self.attribute_tokens(doc);
+
+        if self.words.len() == 1 {
+            let word = &self.words[0];
+            let matched = match self.single_word_mode {
+                SingleWordQueryMode::Attribute => attributes
+                    .iter()
+                    .any(|tokens| self.forms(word).any(|form| tokens.as_slice() == form)),
+                SingleWordQueryMode::Word => attributes
+                    .iter()
+                    .any(|tokens| self.forms(word).any(|form| contains_sequence(tokens, form))),
+                SingleWordQueryMode::None => false,
+            };
+            return usize::from(matched);
+        }
+
+        self.words
+            .iter()
+            .filter(|word| {
+                attributes
+                    .iter()
+                    .any(|tokens| self.forms(word).any(|form| contains_sequence(tokens, form)))
+            })
+            .count()
+    }
+
+    /// The word itself followed by its accepted alternatives.
+    fn forms<'a>(&'a self, word: &'a String) -> impl Iterator<Item = &'a [String]> + 'a {
+        std::iter::once(std::slice::from_ref(word)).chain(
+            self.alternatives
+                .get(word)
+                .into_iter()
+                .flatten()
+                .map(|alt| alt.as_slice()),
+        )
+    }
+
+    /// Tokenized text of every searchable attribute value (array elements are
+    /// separate values). Falls back to all fields when no paths are known.
+    fn attribute_tokens(&self, doc: &Document) -> Vec<Vec<String>> {
+        let mut texts: Vec<&str> = Vec::new();
+        if self.paths.is_empty() {
+            for value in doc.fields.values() {
+                collect_texts(value, &mut texts);
+            }
+        } else {
+            for path in &self.paths {
+                let mut segments = path.split('.');
+                let Some(root) = segments.next().and_then(|first| doc.fields.get(first)) else {
+                    continue;
+                };
+                let mut values = vec![root];
+                for segment in segments {
+                    values = values
+                        .into_iter()
+                        .flat_map(|v| child_values(v, segment))
+                        .collect();
+                }
+                for value in values {
+                    collect_texts(value, &mut texts);
+                }
+            }
+        }
+        texts.into_iter().map(tokenize).collect()
+    }
+}
+
+fn child_values<'a>(value: &'a FieldValue, key: &str) -> Vec<&'a FieldValue> {
+    match value {
+        FieldValue::Object(map) => map.get(key).into_iter().collect(),
+        FieldValue::Array(items) => items.iter().flat_map(|i| child_values(i, key)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn collect_texts<'a>(value: &'a FieldValue, out: &mut Vec<&'a str>) {
+    match value {
+        FieldValue::Text(s) | FieldValue::Facet(s) => out.push(s),
+        FieldValue::Array(items) => items.iter().for_each(|i| collect_texts(i, out)),
+        FieldValue::Object(map) => map.values().for_each(|v| collect_texts(v, out)),
+        _ => {}
+    }
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+        .collect()
+}
+
+fn contains_sequence(haystack: &[String], needle: &[String]) -> bool {
+    !needle.is_empty() && haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(fields: &[(&str, &str)]) -> Document {
+        Document {
+            id: "1".to_string(),
+            fields: fields
+                .iter()
+                .map(|(k, v)| (k.to_string(), FieldValue::Text(v.to_string())))
+                .collect(),
+        }
+    }
+
+    fn paths() -> Vec<String> {
+        vec!["brand".to_string(), "name".to_string()]
+    }
+
+    #[test]
+    fn parse_single_word_mode() {
+        assert_eq!(
+            SingleWordQueryMode::parse("word"),
+            SingleWordQueryMode::Word
+        );
+        assert_eq!(
+            SingleWordQueryMode::parse("none"),
+            SingleWordQueryMode::None
+        );
+        assert_eq!(
+            SingleWordQueryMode::parse("attribute"),
+            SingleWordQueryMode::Attribute
+        );
+        assert_eq!(
+            SingleWordQueryMode::parse("bogus"),
+            SingleWordQueryMode::Attribute
+        );
+    }
+
+    #[test]
+    fn single_word_attribute_mode_requires_whole_value() {
+        let c = ExactCriterion::new("Apple", SingleWordQueryMode::Attribute, paths());
+        assert_eq!(c.count(&doc(&[("brand", "apple"), ("name", "iPhone")])), 1);
+        assert_eq!(c.count(&doc(&[("brand", "Apple Inc"), ("name", "x")])), 0);
+        assert_eq!(c.count(&doc(&[("brand", "Applesauce Co")])), 0);
+    }
+
+    #[test]
+    fn single_word_word_mode_accepts_whole_word() {
+        let c = ExactCriterion::new("apple", SingleWordQueryMode::Word, paths());
+        assert_eq!(c.count(&doc(&[("brand", "Apple Inc")])), 1);
+        assert_eq!(c.count(&doc(&[("brand", "Applesauce Co")])), 0);
+    }
+
+    #[test]
+    fn single_word_none_mode_is_inactive() {
+        let c = ExactCriterion::new("apple", SingleWordQueryMode::None, paths());
+        assert!(!c.is_active());
+        assert_eq!(c.count(&doc(&[("brand", "apple")])), 0);
+    }
+
+    #[test]
+    fn multi_word_counts_whole_word_matches() {
+        let c = ExactCriterion::new("red apple", SingleWordQueryMode::Attribute, paths());
+        assert_eq!(c.count(&doc(&[("name", "Red Apple Juice")])), 2);
+        assert_eq!(c.count(&doc(&[("name", "Red Applesauce")])), 1);
+    }
+
+    #[test]
+    fn only_searchable_paths_are_considered() {
+        let c = ExactCriterion::new("apple", SingleWordQueryMode::Word, paths());
+        assert_eq!(c.count(&doc(&[("description", "apple")])), 0);
+    }
+
+    #[test]
+    fn nested_paths_and_arrays() {
+        let mut d = doc(&[]);
+        let mut inner = HashMap::new();
+        inner.insert(
+            "name".to_string(),
+            FieldValue::Array(vec![
+                FieldValue::Text("Nike Air".to_string()),
+                FieldValue::Text("Nike".to_string()),
+            ]),
+        );
+        d.fields
+            .insert("brand".to_string(), FieldValue::Object(inner));
+        let c = ExactCriterion::new(
+            "nike",
+            SingleWordQueryMode::Attribute,
+            vec!["brand.name".to_string()],
+        );
+        assert_eq!(c.count(&d), 1);
+    }
+
+    #[test]
+    fn alternatives_count_as_exact() {
+        let c = ExactCriterion::new("shoe", SingleWordQueryMode::Attribute, paths())
+            .with_alternatives("shoe", ["shoes"]);
+        assert_eq!(c.count(&doc(&[("name", "Shoes")])), 1);
+
+        let c = ExactCriterion::new("tv stand", SingleWordQueryMode::Attribute, paths())
+            .with_alternatives("tv", ["television", "flat screen"]);
+        assert_eq!(c.count(&doc(&[("name", "Flat Screen Stand")])), 2);
+        assert_eq!(c.count(&doc(&[("name", "Flat Stand")])), 1);
+    }
+}
@@ -0,0 +1,161 @@
+//! Budgeted match counting for filter-only queries.
+//!
+//! Counting every match of a broad filter means walking its whole posting
+//! list, even when only the first page is shown. With a count budget the walk
+//! stops once the budget is reached and the rest is extrapolated from the
+//! match density seen so far; the result is reported with
+//! `exhaustiveNbHits: false`.
+
+use super::QueryExecutor;
+use crate::error::Result;
+use std::sync::OnceLock;
+use tantivy::query::{EnableScoring, Query as TantivyQuery};
+use tantivy::{DocAddress, DocSet, Searcher, TERMINATED};
+
+pub const DEFAULT_COUNT_BUDGET: usize = 100_000;
+
+/// Matches counted exactly before `nbHits` becomes an estimate
+/// (`FLAPJACK_COUNT_BUDGET`, `0` to always count exhaustively).
+pub fn count_budget() -> Option<usize> {
+    static BUDGET: OnceLock<Option<usize>> = OnceLock::new();
+    *BUDGET.get_or_init(|| {
+        let budget = std::env::var("FLAPJACK_COUNT_BUDGET")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_COUNT_BUDGET);
+        (budget > 0).then_some(budget)
+    })
+}
+
+pub(crate) struct BudgetedScan {
+    pub total: usize,
+    pub exhaustive: bool,
+    /// The first `keep` matches in doc-address order.
+    pub first_docs: Vec<DocAddress>,
+}
+
+impl QueryExecutor {
+    /// Walk the matches of `query` in doc order, keeping the first `keep`,
+    /// until `budget` matches have been counted.
+    pub(crate) fn budgeted_scan(
+        &self,
+        searcher: &Searcher,
+        query: &dyn TantivyQuery,
+        budget: usize,
+        keep: usize,
+    ) -> Result<BudgetedScan> {
+        let weight = query.weight(EnableScoring::disabled_from_searcher(searcher))?;
+        let segments = searcher.segment_readers();
+        let total_docs: u64 = segments.iter().map(|s| s.max_doc() as u64).sum();
+        let mut matched = 0usize;
+        let mut scanned: u64 = 0;
+        let mut first_docs = Vec::with_capacity(keep.min(budget));
+
+        for (ord, reader) in segments.iter().enumerate() {
+            let mut scorer = weight.scorer(reader, 1.0)?;
+            let alive = reader.alive_bitset();
+            let mut doc = scorer.doc();
+            while doc != TERMINATED {
+                if alive.is_none_or(|bits| bits.is_alive(doc)) {
+                    matched += 1;
+                    if first_docs.len() < keep {
+                        first_docs.push(DocAddress::new(ord as u32, doc));
+                    }
+                    if matched >= budget && first_docs.len() >= keep {
+                        scanned += doc as u64 + 1;
+                        let estimate = (matched as u64).saturating_mul(total_docs) / scanned;
+                        return Ok(BudgetedScan {
+                            total: (estimate as usize).max(matched),
+                            exhaustive: false,
+                            first_docs,
+                        });
+                    }
+                }
+                doc = scorer.advance();
+            }
+            scanned += reader.max_doc() as u64;
+        }
+
+        Ok(BudgetedScan {
+            total: matched,
+            exhaustive: true,
+            first_docs,
+        })
+    }
+
+    /// Budgeted scan for a relevance-sorted query whose order does not depend
+    /// on scores (no text, no custom ranking, no distinct). Returns `None`
+    /// when no budget applies or the matches fit within it, in which case the
+    /// caller runs the normal exhaustive search.
+    pub(crate) fn try_budgeted_scan(
+        &self,
+        searcher: &Searcher,
+        query: &dyn TantivyQuery,
+        has_text_query: bool,
+        distinct_count: Option<u32>,
+        keep: usize,
+    ) -> Result<Option<BudgetedScan>> {
+        let Some(budget) = self.count_budget else {
+            return Ok(None);
+        };
+        let has_custom_ranking = self
+            .settings
+            .as_ref()
+            .and_then(|s| s.custom_ranking.as_ref())
+            .is_some_and(|cr| !cr.is_empty());
+        if has_text_query || has_custom_ranking || distinct_count.unwrap_or(0) > 0 {
+            return Ok(None);
+        }
+        let scan = self.budgeted_scan(searcher, query, budget, keep)?;
+        Ok((!scan.exhaustive).then_some(scan))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::index::manager::IndexManager;
+    use crate::types::{Document, FieldValue};
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn doc(id: usize) -> Document {
+        Document {
+            id: id.to_string(),
+            fields: HashMap::from([
+                (
+                    "title".to_string(),
+                    FieldValue::Text(format!("item {}", id)),
+                ),
+                (
+                    "kind".to_string(),
+                    FieldValue::Text(if id % 2 == 0 { "even" } else { "odd" }.to_string()),
+                ),
+            ]),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_budgeted_scan_estimates_past_budget() {
+        let tmp = TempDir::new().unwrap();
+        let manager = IndexManager::new(tmp.path());
+        manager.create_tenant("t").unwrap();
+        manager
+            .add_documents_sync("t", (0..200).map(doc).collect())
+            .await
+            .unwrap();
+
+        let index = manager.get_or_load("t").unwrap();
+        let searcher = index.reader().searcher();
+        let executor = super::QueryExecutor::new(index.converter(), index.inner().schema());
+        let query = tantivy::query::AllQuery;
+
+        let scan = executor.budgeted_scan(&searcher, &query, 50, 10).unwrap();
+        assert!(!scan.exhaustive);
+        assert_eq!(scan.first_docs.len(), 10);
+        assert!(scan.total >= 50);
+
+        let scan = executor.budgeted_scan(&searcher, &query, 1000, 10).unwrap();
+        assert!(scan.exhaustive);
+        assert_eq!(scan.total, 200);
+    }
+}
@@ -69,6 +69,32 @@ impl QueryExecutor {
         let tf2 = tf0.elapsed();
         let (documents, total) = match sort {
             None | Some(Sort::ByRelevance) => {
+                if let Some(scan) = self.try_budgeted_scan(
+                    searcher,
+                    final_query.as_ref(),
+                    has_text_query,
+                    distinct_count,
+                    limit + offset,
+                )? {
+                    let page = scan
+                        .first_docs
+                        .into_iter()
+                        .skip(offset)
+                        .take(limit)
+                        .map(|address| (1.0, address))
+                        .collect();
+                    return Ok(SearchResult {
+                        documents: self.reconstruct_documents(searcher, page)?,
+                        total: scan.total,
+                        facets: HashMap::new(),
+                        user_data: Vec::new(),
+                        applied_rules: Vec::new(),
+                        rendering_content: None,
+                        exhaustive_nb_hits: false,
+                        synonyms_triggered: Vec::new(),
+                        synonyms_matched: Vec::new(),
+                    });
+                }
                 let (docs, count) =
                     self.execute_relevance_sort(searcher, final_query, limit, offset)?;
                 tracing::debug!(
@@ -113,6 +139,10 @@ impl QueryExecutor {
             facets: HashMap::new(),
             user_data: Vec::new(),
             applied_rules: Vec::new(),
+            rendering_content: None,
+            exhaustive_nb_hits: true,
+            synonyms_triggered: Vec::new(),
+            synonyms_matched: Vec::new(),
         })
     }
 
@@ -157,6 +187,10 @@ impl QueryExecutor {
                 facets: self.extract_facet_counts(facets, facet_requests),
                 user_data: Vec::new(),
                 applied_rules: Vec::new(),
+                rendering_content: None,
+                exhaustive_nb_hits: true,
+                synonyms_triggered: Vec::new(),
+                synonyms_matched: Vec::new(),
             });
         }
 
@@ -235,6 +269,10 @@ impl QueryExecutor {
             facets: self.extract_facet_counts(facet_counts, facet_requests),
             user_data: Vec::new(),
             applied_rules: Vec::new(),
+            rendering_content: None,
+            exhaustive_nb_hits: true,
+            synonyms_triggered: Vec::new(),
+            synonyms_matched: Vec::new(),
         })
     }
 
@@ -1,5 +1,6 @@
 use crate::error::Result;
 use crate::index::document::DocumentConverter;
+use crate::index::relevance::SearchableAttribute;
 use crate::index::settings::IndexSettings;
 use crate::query::filter::FilterCompiler;
 use crate::query::parser::ShortQueryPlaceholder;
@@ -13,6 +14,7 @@ use tantivy::Searcher;
 /// full IndexSettings struct on every search (it can be 1+ KB).
 type SettingsRef = Option<Arc<IndexSettings>>;
 
+pub mod count;
 mod facets;
 mod relevance;
 mod rules;
@@ -25,8 +27,14 @@ pub struct QueryExecutor {
     pub(crate) settings: SettingsRef,
     pub(crate) json_search_field: tantivy::schema::Field,
     pub(crate) searchable_paths: Vec<String>,
+    /// Searchable attributes declared `unordered()`: match position is
+    /// ignored in every path they cover.
+    pub(crate) unordered_attributes: Vec<SearchableAttribute>,
     pub(crate) query_text: String,
     pub(crate) max_values_per_facet: Option<usize>,
+    /// Matches counted exactly before totals become estimates; `None`
+    /// counts exhaustively.
+    pub(crate) count_budget: Option<usize>,
 }
 
 impl QueryExecutor {
@@ -41,11 +49,18 @@ impl QueryExecutor {
             settings: None,
             json_search_field,
             searchable_paths: vec![],
+            unordered_attributes: Vec::new(),
             query_text: String::new(),
             max_values_per_facet: None,
+            count_budget: None,
         }
     }
 
+    pub fn with_count_budget(mut self, budget: Option<usize>) -> Self {
+        self.count_budget = budget;
+        self
+    }
+
     pub fn with_max_values_per_facet(mut self, max: Option<usize>) -> Self {
         self.max_values_per_facet = max;
         self
@@ -53,8 +68,9 @@ impl QueryExecutor {
 
     pub fn with_settings(mut self, settings: SettingsRef) -> Self {
         if let Some(ref s) = settings {
-            if let Some(ref attrs) = s.searchable_attributes {
-                self.searchable_paths = attrs.clone();
+            if let Some(paths) = s.searchable_paths() {
+                self.searchable_paths = paths;
+                self.unordered_attributes = s.unordered_searchable_attributes();
             }
         }
         self.settings = settings;
@@ -265,6 +281,10 @@ impl QueryExecutor {
             facets: std::collections::HashMap::new(),
             user_data: Vec::new(),
             applied_rules: Vec::new(),
+            rendering_content: None,
+            exhaustive_nb_hits: true,
+            synonyms_triggered: Vec::new(),
+            synonyms_matched: Vec::new(),
         }
     }
 }
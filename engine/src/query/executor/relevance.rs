@@ -202,10 +202,8 @@ impl QueryExecutor {
                         while doc_id != TERMINATED {
                             let key = (segment_ord as u32, doc_id);
                             if let Some(min_pos) = doc_positions.get_mut(&key) {
-                                let mut positions: Vec<u32> =
-                                    Vec::with_capacity(postings.term_freq() as usize);
-                                postings.positions(&mut positions);
-                                if let Some(&first_pos) = positions.first() {
+                                let first_pos = self.first_match_position(path, &mut postings);
+                                if let Some(first_pos) = first_pos {
                                     *min_pos = (*min_pos).min(first_pos);
                                 }
                             }
@@ -261,10 +259,7 @@ impl QueryExecutor {
                     // walking through every doc in the posting list.
                     let doc_id = postings.seek(addr.doc_id);
                     if doc_id == addr.doc_id {
-                        let mut positions: Vec<u32> =
-                            Vec::with_capacity(postings.term_freq() as usize);
-                        postings.positions(&mut positions);
-                        if let Some(&first_pos) = positions.first() {
+                        if let Some(first_pos) = self.first_match_position(path, &mut postings) {
                             min_pos = min_pos.min(first_pos);
                         }
                     }
@@ -274,4 +269,17 @@ impl QueryExecutor {
 
         Ok(min_pos)
     }
+
+    /// Position of the first match within `path` for the current posting.
+    /// `unordered()` attributes report 0 so early and late matches tie.
+    fn first_match_position<P: Postings>(&self, path: &str, postings: &mut P) -> Option<u32> {
+        let mut positions: Vec<u32> = Vec::with_capacity(postings.term_freq() as usize);
+        postings.positions(&mut positions);
+        let first = positions.first().copied()?;
+        if self.unordered_attributes.iter().any(|a| a.covers(path)) {
+            Some(0)
+        } else {
+            Some(first)
+        }
+    }
 }
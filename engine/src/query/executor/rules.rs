@@ -35,7 +35,7 @@ impl QueryExecutor {
 
             if let Some(pos) = documents.iter().position(|d| &d.document.id == pin_id) {
                 pinned_docs.push((documents.remove(pos), *target_pos));
-            } else {
+            } else if !effects.pins_from_results_only {
                 let term = tantivy::Term::from_field_text(id_field, pin_id);
                 let term_query =
                     tantivy::query::TermQuery::new(term, tantivy::schema::IndexRecordOption::Basic);
@@ -1,12 +1,15 @@
 use crate::error::Result;
+use crate::index::facet_normalization::FacetValueNormalization;
 use crate::index::settings::IndexSettings;
-use crate::types::Filter;
-use std::collections::HashSet;
-use tantivy::query::{AllQuery, BooleanQuery, Occur, Query};
-use tantivy::schema::Schema;
+use crate::types::{FieldValue, Filter};
+use std::collections::{HashMap, HashSet};
+use tantivy::query::{AllQuery, BooleanQuery, Occur, Query, TermQuery};
+use tantivy::schema::{Facet, IndexRecordOption, Schema, Term};
+
+/// Normalized facet attributes, see [`IndexSettings::normalized_facets`].
+type NormalizedFacets<'a> = HashMap<&'a str, &'a FacetValueNormalization>;
 
 pub struct FilterCompiler {
-    #[allow(dead_code)]
     schema: Schema,
     query_parser: tantivy::query::QueryParser,
 }
@@ -29,8 +32,8 @@ impl FilterCompiler {
         }
     }
 
-    const MAX_FILTER_DEPTH: usize = 10;
-    const MAX_BOOLEAN_CLAUSES: usize = 1000;
+    pub const MAX_FILTER_DEPTH: usize = 10;
+    pub const MAX_BOOLEAN_CLAUSES: usize = 1000;
 
     pub fn compile(
         &self,
@@ -52,8 +55,12 @@ impl FilterCompiler {
             return Ok(Box::new(tantivy::query::EmptyQuery));
         }
 
-        if self.has_not(filter) {
-            self.compile_with_hybrid(filter, 0)
+        // Values of normalized facets are only indexed canonicalized, in
+        // `_facets`; `_json_filter` holds them as written.
+        let normalized = settings.map(|s| s.normalized_facets()).unwrap_or_default();
+
+        if self.has_not(filter) || self.uses_normalized_facet(filter, &normalized) {
+            self.compile_with_hybrid(filter, 0, &normalized)
         } else {
             let query_string = self.to_query_string(filter)?;
             self.query_parser
@@ -87,6 +94,37 @@ impl FilterCompiler {
         }
     }
 
+    fn uses_normalized_facet(&self, filter: &Filter, normalized: &NormalizedFacets) -> bool {
+        match filter {
+            Filter::Equals { field, value } | Filter::NotEquals { field, value } => {
+                matches!(value, FieldValue::Text(_)) && normalized.contains_key(field.as_str())
+            }
+            Filter::And(filters) | Filter::Or(filters) => filters
+                .iter()
+                .any(|f| self.uses_normalized_facet(f, normalized)),
+            Filter::Not(inner) => self.uses_normalized_facet(inner, normalized),
+            _ => false,
+        }
+    }
+
+    /// Match `field:value` on the canonical bucket in `_facets`.
+    fn normalized_facet_query(
+        &self,
+        field: &str,
+        value: &str,
+        normalization: &FacetValueNormalization,
+    ) -> Result<Box<dyn Query>> {
+        let facets_field = self
+            .schema
+            .get_field("_facets")
+            .map_err(|_| crate::error::FlapjackError::FieldNotFound("_facets".to_string()))?;
+        let facet = Facet::from(format!("/{}/{}", field, normalization.bucket(value)).as_str());
+        Ok(Box::new(TermQuery::new(
+            Term::from_facet(facets_field, &facet),
+            IndexRecordOption::Basic,
+        )))
+    }
+
     fn to_query_string(&self, filter: &Filter) -> Result<String> {
         match filter {
             Filter::Equals { field, value } => match value {
@@ -223,7 +261,12 @@ impl FilterCompiler {
         count_recursive(filter)
     }
 
-    fn compile_with_hybrid(&self, filter: &Filter, depth: usize) -> Result<Box<dyn Query>> {
+    fn compile_with_hybrid(
+        &self,
+        filter: &Filter,
+        depth: usize,
+        normalized: &NormalizedFacets,
+    ) -> Result<Box<dyn Query>> {
         if depth > Self::MAX_FILTER_DEPTH {
             return Err(crate::error::FlapjackError::InvalidQuery(format!(
                 "Filter nesting exceeds {} levels",
@@ -232,8 +275,25 @@ impl FilterCompiler {
         }
 
         match filter {
+            Filter::Equals {
+                field,
+                value: FieldValue::Text(s),
+            } if normalized.contains_key(field.as_str()) => {
+                self.normalized_facet_query(field, s, normalized[field.as_str()])
+            }
+            Filter::NotEquals {
+                field,
+                value: FieldValue::Text(s),
+            } if normalized.contains_key(field.as_str()) => {
+                let equals_query =
+                    self.normalized_facet_query(field, s, normalized[field.as_str()])?;
+                Ok(Box::new(BooleanQuery::new(vec![
+                    (Occur::Must, Box::new(AllQuery) as Box<dyn Query>),
+                    (Occur::MustNot, equals_query),
+                ])))
+            }
             Filter::Not(inner) => {
-                let inner_query = self.compile_with_hybrid(inner, depth + 1)?;
+                let inner_query = self.compile_with_hybrid(inner, depth + 1, normalized)?;
                 Ok(Box::new(BooleanQuery::new(vec![
                     (Occur::Must, Box::new(AllQuery) as Box<dyn Query>),
                     (Occur::MustNot, inner_query),
@@ -253,14 +313,20 @@ impl FilterCompiler {
             Filter::And(filters) => {
                 let mut subqueries = Vec::new();
                 for f in filters {
-                    subqueries.push((Occur::Must, self.compile_with_hybrid(f, depth + 1)?));
+                    subqueries.push((
+                        Occur::Must,
+                        self.compile_with_hybrid(f, depth + 1, normalized)?,
+                    ));
                 }
                 Ok(Box::new(BooleanQuery::new(subqueries)))
             }
             Filter::Or(filters) => {
                 let mut subqueries = Vec::new();
                 for f in filters {
-                    subqueries.push((Occur::Should, self.compile_with_hybrid(f, depth + 1)?));
+                    subqueries.push((
+                        Occur::Should,
+                        self.compile_with_hybrid(f, depth + 1, normalized)?,
+                    ));
                 }
                 Ok(Box::new(BooleanQuery::new(subqueries)))
             }
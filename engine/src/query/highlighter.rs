@@ -1,6 +1,8 @@
+use crate::tokenizer::TextNormalization;
 use crate::types::{Document, FieldValue};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -31,6 +33,7 @@ pub enum HighlightValue {
 pub struct Highlighter {
     pre_tag: String,
     post_tag: String,
+    normalization: Option<Arc<TextNormalization>>,
 }
 
 impl Default for Highlighter {
@@ -38,13 +41,44 @@ impl Default for Highlighter {
         Self {
             pre_tag: "<em>".to_string(),
             post_tag: "</em>".to_string(),
+            normalization: None,
         }
     }
 }
 
 impl Highlighter {
     pub fn new(pre_tag: String, post_tag: String) -> Self {
-        Self { pre_tag, post_tag }
+        Self {
+            pre_tag,
+            post_tag,
+            normalization: None,
+        }
+    }
+
+    /// Match query words against text normalized the way the index
+    /// normalizes it, so e.g. "creme" highlights "Crème".
+    pub fn with_normalization(mut self, normalization: Option<Arc<TextNormalization>>) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Lowercased and normalized `text`, plus the byte offsets back into
+    /// `text` when normalization is on.
+    fn fold_text(&self, text: &str) -> (String, Option<Vec<usize>>) {
+        match &self.normalization {
+            Some(n) => {
+                let (folded, offsets) = n.normalize_with_offsets(text);
+                (folded, Some(offsets))
+            }
+            None => (text.to_lowercase(), None),
+        }
+    }
+
+    fn fold_word(&self, word: &str) -> String {
+        match &self.normalization {
+            Some(n) => n.normalize(word),
+            None => word.to_lowercase(),
+        }
     }
 
     pub fn highlight_document(
@@ -106,12 +140,15 @@ impl Highlighter {
     }
 
     pub fn highlight_text(&self, text: &str, query_words: &[String]) -> HighlightResult {
-        let text_lower = text.to_lowercase();
+        let (text_lower, offsets) = self.fold_text(text);
         let mut matched_words = Vec::new();
+        // Steps 1-3 match in `text_lower`; step 4 matches words of `text`.
         let mut match_positions = Vec::new();
+        let mut fuzzy_positions = Vec::new();
 
         // Pre-compute lowercased query words to avoid repeated allocation
-        let query_words_lower: Vec<String> = query_words.iter().map(|w| w.to_lowercase()).collect();
+        let query_words_lower: Vec<String> =
+            query_words.iter().map(|w| self.fold_word(w)).collect();
 
         // 1. Exact substring matching for each query word
         for (qi, word_lower) in query_words_lower.iter().enumerate() {
@@ -192,7 +229,7 @@ impl Highlighter {
             };
 
             for (word_start, text_word) in &text_words {
-                let text_word_lower = text_word.to_lowercase();
+                let text_word_lower = self.fold_word(text_word);
                 for (qi, query_lower) in query_words_lower.iter().enumerate() {
                     let ql_chars = query_lower.chars().count();
                     let twl_chars = text_word_lower.chars().count();
@@ -202,7 +239,7 @@ impl Highlighter {
                         if distance <= max_distance && distance > 0 {
                             matched_words.push(query_words[qi].clone());
                             let highlight_len = ql_chars.min(text_word.len());
-                            match_positions.push((*word_start, word_start + highlight_len));
+                            fuzzy_positions.push((*word_start, word_start + highlight_len));
                         } else if twl_chars > ql_chars {
                             let prefix: String = text_word_lower.chars().take(ql_chars).collect();
                             let prefix_distance = strsim::damerau_levenshtein(query_lower, &prefix);
@@ -213,7 +250,7 @@ impl Highlighter {
                                     .nth(ql_chars)
                                     .map(|(i, _)| word_start + i)
                                     .unwrap_or(word_start + text_word.len());
-                                match_positions.push((*word_start, highlight_end));
+                                fuzzy_positions.push((*word_start, highlight_end));
                             }
                         }
                         if ql_chars >= 4 {
@@ -231,7 +268,7 @@ impl Highlighter {
                                         .nth(suffix_len)
                                         .map(|(i, _)| word_start + i)
                                         .unwrap_or(word_start + text_word.len());
-                                    match_positions.push((*word_start, highlight_end));
+                                    fuzzy_positions.push((*word_start, highlight_end));
                                 }
                             }
                         }
@@ -244,6 +281,15 @@ impl Highlighter {
             return self.no_match(text.to_string());
         }
 
+        let mut match_positions: Vec<(usize, usize)> = match &offsets {
+            Some(offsets) => match_positions
+                .into_iter()
+                .map(|(start, end)| (offsets[start], offsets[end]))
+                .collect(),
+            None => match_positions,
+        };
+        match_positions.extend(fuzzy_positions);
+
         // Merge overlapping/adjacent positions into single spans
         match_positions.sort_by_key(|(start, _)| *start);
         match_positions.dedup();
@@ -408,13 +454,15 @@ impl Highlighter {
         }
 
         // Find the word index where the first match occurs
-        let text_lower = text.to_lowercase();
-        let query_words_lower: Vec<String> = query_words.iter().map(|w| w.to_lowercase()).collect();
+        let (text_lower, offsets) = self.fold_text(text);
+        let query_words_lower: Vec<String> =
+            query_words.iter().map(|w| self.fold_word(w)).collect();
 
         let first_match_byte = query_words_lower
             .iter()
             .filter_map(|qw| text_lower.find(qw.as_str()))
             .min()
+            .map(|pos| offsets.as_ref().map_or(pos, |o| o[pos]))
             .unwrap_or(0);
 
         // Find which word index corresponds to this byte offset
@@ -764,6 +812,16 @@ mod tests {
         assert!(matches!(r.match_level, MatchLevel::None));
     }
 
+    #[test]
+    fn hl_normalized_match_maps_back_to_original_text() {
+        let mut settings = crate::index::settings::IndexSettings::default();
+        settings.remove_diacritics = true;
+        let hl = h().with_normalization(TextNormalization::from_settings(&settings).map(Arc::new));
+        let r = hl.highlight_text("Crème brûlée", &["creme".to_string(), "brulee".to_string()]);
+        assert!(matches!(r.match_level, MatchLevel::Full));
+        assert_eq!(r.value, "<em>Crème brûlée</em>");
+    }
+
     // --- highlight_document ---
 
     #[test]
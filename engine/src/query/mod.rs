@@ -1,3 +1,5 @@
+pub mod analysis;
+pub mod exact;
 pub mod executor;
 pub mod filter;
 pub mod fuzzy;
@@ -5,9 +7,10 @@ pub mod geo;
 pub mod highlighter;
 pub mod parser;
 pub mod plurals;
+pub mod spelling;
 pub mod splitting;
 pub mod stopwords;
 
 pub use executor::QueryExecutor;
 pub use filter::FilterCompiler;
-pub use parser::QueryParser;
+pub use parser::{AdvancedSyntaxQuery, QueryParser};
@@ -103,6 +103,71 @@ impl Scorer for EmptyScorer {
     }
 }
 
+/// A query split into its `advancedSyntax` operators — `"exact phrases"`
+/// and `-excluded` words or `-"phrases"` — and the plain words around them.
+///
+/// Stop words, synonyms and split/concat alternatives only apply to the
+/// plain words: search rewrites [`remaining`](Self::remaining) and puts the
+/// operators back with [`with_remaining`](Self::with_remaining).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdvancedSyntaxQuery {
+    pub phrases: Vec<String>,
+    pub exclusions: Vec<String>,
+    pub remaining: String,
+    /// The query ends with a phrase or exclusion, so its last plain word
+    /// isn't the word being typed.
+    pub ends_with_operator: bool,
+}
+
+impl AdvancedSyntaxQuery {
+    pub fn parse(text: &str) -> Self {
+        let (phrases, exclusions, remaining) = QueryParser::preprocess_advanced_syntax(text);
+        let inside_quotes = text.matches('"').count() % 2 == 1;
+        let ends_with_operator = inside_quotes
+            || text
+                .split_whitespace()
+                .last()
+                .is_some_and(|w| w.ends_with('"') || (w.len() > 1 && w.starts_with('-')));
+        AdvancedSyntaxQuery {
+            phrases,
+            exclusions,
+            remaining,
+            ends_with_operator,
+        }
+    }
+
+    pub fn has_operators(&self) -> bool {
+        !self.phrases.is_empty() || !self.exclusions.is_empty()
+    }
+
+    /// Query text with the plain words replaced by `remaining`. Operators go
+    /// after the words if they came last in the original query, before them
+    /// otherwise, so prefix matching of the last word is unchanged.
+    pub fn with_remaining(&self, remaining: &str) -> String {
+        let operators: Vec<String> = self
+            .phrases
+            .iter()
+            .map(|p| format!("\"{}\"", p))
+            .chain(self.exclusions.iter().map(|e| {
+                if e.contains(char::is_whitespace) {
+                    format!("-\"{}\"", e)
+                } else {
+                    format!("-{}", e)
+                }
+            }))
+            .collect();
+        let operators = operators.join(" ");
+        let remaining = remaining.trim();
+        if remaining.is_empty() {
+            operators
+        } else if self.ends_with_operator {
+            format!("{} {}", remaining, operators)
+        } else {
+            format!("{} {}", operators, remaining)
+        }
+    }
+}
+
 pub struct QueryParser {
     fields: Vec<tantivy::schema::Field>,
     json_exact_field: Option<tantivy::schema::Field>,
@@ -199,14 +264,9 @@ impl QueryParser {
     pub fn parse(&self, query: &Query) -> Result<Box<dyn TantivyQuery>> {
         // Advanced syntax: extract "phrases" and -exclusions before normal parsing
         if self.advanced_syntax {
-            let (phrases, exclusions, remaining) = Self::preprocess_advanced_syntax(&query.text);
-            if !phrases.is_empty() || !exclusions.is_empty() {
-                return self.parse_with_advanced_syntax(
-                    &remaining,
-                    &phrases,
-                    &exclusions,
-                    query.text.ends_with(' '),
-                );
+            let advanced = AdvancedSyntaxQuery::parse(&query.text);
+            if advanced.has_operators() {
+                return self.parse_with_advanced_syntax(&advanced, query.text.ends_with(' '));
             }
         }
 
@@ -512,6 +572,16 @@ impl QueryParser {
                 }
             } else if c == '-' && (remaining.is_empty() || remaining.ends_with(' ')) {
                 chars.next();
+                if chars.peek() == Some(&'"') {
+                    // -"phrase": exclude the whole phrase
+                    chars.next();
+                    let phrase: String = chars.by_ref().take_while(|&nc| nc != '"').collect();
+                    let trimmed = phrase.trim().to_string();
+                    if !trimmed.is_empty() {
+                        exclusions.push(trimmed);
+                    }
+                    continue;
+                }
                 let mut word = String::new();
                 while let Some(&nc) = chars.peek() {
                     if nc.is_whitespace() {
@@ -532,23 +602,25 @@ impl QueryParser {
     }
 
     /// Build a query combining phrases (Must), exclusions (MustNot), and remaining text.
+    ///
+    /// Phrase words must appear consecutively in one attribute, exactly: no
+    /// prefix, typo or plural matching. Excluded words and phrases are exact
+    /// too. The remaining words are parsed as a normal query; the last one
+    /// only gets prefix matching if nothing follows it.
     fn parse_with_advanced_syntax(
         &self,
-        remaining_text: &str,
-        phrases: &[String],
-        exclusions: &[String],
-        _has_trailing_space: bool,
+        advanced: &AdvancedSyntaxQuery,
+        has_trailing_space: bool,
     ) -> Result<Box<dyn TantivyQuery>> {
-        let json_search_field = self.fields[0];
-        let exact_field = self.json_exact_field.unwrap_or(json_search_field);
-
         let mut clauses: Vec<(tantivy::query::Occur, Box<dyn TantivyQuery>)> = Vec::new();
 
         // Parse remaining text as a normal query
-        if !remaining_text.trim().is_empty() {
-            let sub_query = Query {
-                text: remaining_text.to_string(),
-            };
+        if !advanced.remaining.trim().is_empty() {
+            let mut text = advanced.remaining.clone();
+            if has_trailing_space || advanced.ends_with_operator {
+                text.push(' ');
+            }
+            let sub_query = Query { text };
             // Temporarily disable advanced_syntax to avoid recursion
             let normal_parser = QueryParser {
                 advanced_syntax: false,
@@ -559,71 +631,68 @@ impl QueryParser {
             }
         }
 
-        // Phrase queries: all words in the phrase must match (exact, on same field paths)
-        for phrase in phrases {
-            let words: Vec<String> = phrase
-                .to_lowercase()
-                .split_whitespace()
-                .map(|s| s.to_string())
-                .collect();
-            if words.is_empty() {
-                continue;
-            }
-            let mut phrase_clauses: Vec<(tantivy::query::Occur, Box<dyn TantivyQuery>)> =
-                Vec::new();
-            for word in &words {
-                let mut field_queries: Vec<(tantivy::query::Occur, Box<dyn TantivyQuery>)> =
-                    Vec::new();
-                for (path_idx, path) in self.searchable_paths.iter().enumerate() {
-                    let term_text = format!("{}\0s{}", path, word);
-                    let term = tantivy::Term::from_field_text(exact_field, &term_text);
-                    let tq: Box<dyn TantivyQuery> = Box::new(tantivy::query::TermQuery::new(
-                        term,
-                        tantivy::schema::IndexRecordOption::WithFreqs,
-                    ));
-                    let weight = self.weights.get(path_idx).copied().unwrap_or(1.0);
-                    field_queries.push((
-                        tantivy::query::Occur::Should,
-                        Box::new(tantivy::query::BoostQuery::new(tq, weight)),
-                    ));
-                }
-                phrase_clauses.push((
-                    tantivy::query::Occur::Must,
-                    Box::new(tantivy::query::BooleanQuery::new(field_queries)),
-                ));
+        for phrase in &advanced.phrases {
+            if let Some(q) = self.exact_phrase_query(phrase, true) {
+                clauses.push((tantivy::query::Occur::Must, q));
             }
-            clauses.push((
-                tantivy::query::Occur::Must,
-                Box::new(tantivy::query::BooleanQuery::new(phrase_clauses)),
-            ));
         }
 
-        // Exclusion queries: MustNot for each excluded term
-        for exclusion in exclusions {
-            let word = exclusion.to_lowercase();
-            let mut field_queries: Vec<(tantivy::query::Occur, Box<dyn TantivyQuery>)> = Vec::new();
-            for path in &self.searchable_paths {
-                let term_text = format!("{}\0s{}", path, word);
-                let term = tantivy::Term::from_field_text(exact_field, &term_text);
-                let tq: Box<dyn TantivyQuery> = Box::new(tantivy::query::TermQuery::new(
-                    term,
-                    tantivy::schema::IndexRecordOption::WithFreqs,
-                ));
-                field_queries.push((tantivy::query::Occur::Should, tq));
+        // Exclusion queries: MustNot for each excluded word or phrase
+        for exclusion in &advanced.exclusions {
+            if let Some(q) = self.exact_phrase_query(exclusion, false) {
+                clauses.push((tantivy::query::Occur::MustNot, q));
             }
-            clauses.push((
-                tantivy::query::Occur::MustNot,
-                Box::new(tantivy::query::BooleanQuery::new(field_queries)),
-            ));
         }
 
-        if clauses.is_empty() {
-            return Ok(Box::new(tantivy::query::AllQuery));
+        // A query of only exclusions returns everything else.
+        if !clauses
+            .iter()
+            .any(|(occur, _)| *occur == tantivy::query::Occur::Must)
+        {
+            clauses.push((
+                tantivy::query::Occur::Must,
+                Box::new(tantivy::query::AllQuery),
+            ));
         }
 
         Ok(Box::new(tantivy::query::BooleanQuery::new(clauses)))
     }
 
+    /// Exact match of `phrase` in any searchable attribute: a term query for
+    /// one word, a phrase query (consecutive positions) for several.
+    fn exact_phrase_query(&self, phrase: &str, weighted: bool) -> Option<Box<dyn TantivyQuery>> {
+        let exact_field = self.json_exact_field.unwrap_or(self.fields[0]);
+        let words = split_cjk_aware(&phrase.to_lowercase());
+        if words.is_empty() {
+            return None;
+        }
+        let mut field_queries: Vec<(tantivy::query::Occur, Box<dyn TantivyQuery>)> = Vec::new();
+        for (path_idx, path) in self.searchable_paths.iter().enumerate() {
+            let terms: Vec<tantivy::Term> = words
+                .iter()
+                .map(|word| {
+                    tantivy::Term::from_field_text(exact_field, &format!("{}\0s{}", path, word))
+                })
+                .collect();
+            let q: Box<dyn TantivyQuery> = if terms.len() == 1 {
+                Box::new(tantivy::query::TermQuery::new(
+                    terms.into_iter().next().unwrap(),
+                    tantivy::schema::IndexRecordOption::WithFreqs,
+                ))
+            } else {
+                Box::new(tantivy::query::PhraseQuery::new(terms))
+            };
+            let q: Box<dyn TantivyQuery> = if weighted {
+                let weight = self.weights.get(path_idx).copied().unwrap_or(1.0);
+                Box::new(tantivy::query::BoostQuery::new(q, weight))
+            } else {
+                q
+            };
+            field_queries.push((tantivy::query::Occur::Should, q));
+        }
+        Some(Box::new(tantivy::query::BooleanQuery::new(field_queries)))
+    }
+
     /// Clone parser fields without the Clone trait (for recursion avoidance)
     fn clone_parser(&self) -> QueryParser {
         QueryParser {
@@ -830,6 +899,31 @@ mod tests {
         assert!(remaining.contains("well"));
     }
 
+    #[test]
+    fn advanced_excluded_phrase() {
+        let (phrases, exclusions, remaining) =
+            QueryParser::preprocess_advanced_syntax(r#"laptop -"open box" deal"#);
+        assert!(phrases.is_empty());
+        assert_eq!(exclusions, vec!["open box"]);
+        assert_eq!(remaining, "laptop deal");
+    }
+
+    #[test]
+    fn advanced_query_round_trips_operators() {
+        let q = AdvancedSyntaxQuery::parse(r#""gaming laptop" the lap"#);
+        assert!(!q.ends_with_operator);
+        assert_eq!(q.with_remaining("lap"), r#""gaming laptop" lap"#);
+
+        let q = AdvancedSyntaxQuery::parse(r#"the lap -"open box" -used"#);
+        assert!(q.ends_with_operator);
+        let text = q.with_remaining("lap");
+        assert_eq!(text, r#"lap -"open box" -used"#);
+        assert_eq!(AdvancedSyntaxQuery::parse(&text).exclusions, q.exclusions);
+
+        assert!(AdvancedSyntaxQuery::parse(r#"lap "open phr"#).ends_with_operator);
+        assert!(!AdvancedSyntaxQuery::parse("well-known lap").has_operators());
+    }
+
     // --- extract_terms ---
 
     #[test]
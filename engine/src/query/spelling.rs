@@ -0,0 +1,351 @@
+use levenshtein_automata::{Distance, LevenshteinAutomatonBuilder, DFA, SINK_STATE};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use tantivy::schema::Field;
+use tantivy::Searcher;
+use tantivy_fst::Automaton;
+
+/// Minimum token length (in chars) before spelling corrections are attempted.
+const MIN_WORD_LEN: usize = 4;
+
+/// Maximum edit distance allowed for a token of `len` chars.
+fn max_distance(len: usize) -> usize {
+    if len < MIN_WORD_LEN {
+        0
+    } else if len < 8 {
+        1
+    } else {
+        2
+    }
+}
+
+/// Optimal string alignment distance (Levenshtein plus adjacent transpositions),
+/// bailing out early once the distance is known to exceed `max`.
+fn edit_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let mut prev_prev: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur: Vec<usize> = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        cur[0] = i;
+        let mut row_min = cur[0];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut d = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d = d.min(prev_prev[j - 2] + 1);
+            }
+            cur[j] = d;
+            row_min = row_min.min(d);
+        }
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut prev_prev, &mut prev);
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    let d = prev[b.len()];
+    (d <= max).then_some(d)
+}
+
+/// Where corrections are looked up: the index's term dictionaries, or a
+/// plain word list in tests.
+trait Vocabulary {
+    fn contains(&self, word: &str) -> bool;
+
+    /// Words within `max` edits of `token`, with their document frequency.
+    fn neighbours(&self, token: &str, max: usize) -> HashMap<String, u64>;
+}
+
+/// The whole words (`_json_exact`, not the edge-ngrams in `_json_search`)
+/// indexed under the searchable paths, summed across segments.
+struct IndexVocabulary<'a> {
+    searcher: &'a Searcher,
+    json_exact_field: Field,
+    searchable_paths: &'a [String],
+}
+
+impl IndexVocabulary<'_> {
+    fn prefixes(&self) -> impl Iterator<Item = String> + '_ {
+        self.searchable_paths
+            .iter()
+            .map(|path| format!("{}\0s", path))
+    }
+}
+
+impl Vocabulary for IndexVocabulary<'_> {
+    fn contains(&self, word: &str) -> bool {
+        self.searcher.segment_readers().iter().any(|segment| {
+            let Ok(inv_index) = segment.inverted_index(self.json_exact_field) else {
+                return false;
+            };
+            self.prefixes().any(|prefix| {
+                let key = format!("{}{}", prefix, word);
+                matches!(inv_index.terms().get(key.as_bytes()), Ok(Some(_)))
+            })
+        })
+    }
+
+    fn neighbours(&self, token: &str, max: usize) -> HashMap<String, u64> {
+        let dfa = automaton_builder(max).build_dfa(token);
+        let mut words: HashMap<String, u64> = HashMap::new();
+        for segment in self.searcher.segment_readers() {
+            let Ok(inv_index) = segment.inverted_index(self.json_exact_field) else {
+                continue;
+            };
+            for prefix in self.prefixes() {
+                let automaton = PrefixedDfa {
+                    prefix: prefix.as_bytes(),
+                    dfa: &dfa,
+                };
+                let Ok(mut stream) = inv_index.terms().search(automaton).into_stream() else {
+                    continue;
+                };
+                while stream.advance() {
+                    if let Ok(word) = std::str::from_utf8(&stream.key()[prefix.len()..]) {
+                        *words.entry(word.to_string()).or_insert(0) +=
+                            stream.value().doc_freq as u64;
+                    }
+                }
+            }
+        }
+        words
+    }
+}
+
+/// Levenshtein automaton builders (with transpositions) for distances 1 and
+/// 2; building one is expensive, so they are shared.
+fn automaton_builder(max: usize) -> &'static LevenshteinAutomatonBuilder {
+    static BUILDERS: Lazy<[LevenshteinAutomatonBuilder; 2]> = Lazy::new(|| {
+        [
+            LevenshteinAutomatonBuilder::new(1, true),
+            LevenshteinAutomatonBuilder::new(2, true),
+        ]
+    });
+    &BUILDERS[max.clamp(1, 2) - 1]
+}
+
+/// Matches terms made of `prefix` (a path in the `_json_exact` field)
+/// followed by a word the DFA accepts, so the term dictionary's FST only
+/// visits words within reach instead of the whole vocabulary.
+struct PrefixedDfa<'a> {
+    prefix: &'a [u8],
+    dfa: &'a DFA,
+}
+
+#[derive(Clone, Copy)]
+enum PrefixedState {
+    /// Bytes of the prefix matched so far.
+    Prefix(usize),
+    Word(u32),
+    Dead,
+}
+
+impl Automaton for PrefixedDfa<'_> {
+    type State = PrefixedState;
+
+    fn start(&self) -> PrefixedState {
+        if self.prefix.is_empty() {
+            PrefixedState::Word(self.dfa.initial_state())
+        } else {
+            PrefixedState::Prefix(0)
+        }
+    }
+
+    fn is_match(&self, state: &PrefixedState) -> bool {
+        match state {
+            PrefixedState::Word(s) => matches!(self.dfa.distance(*s), Distance::Exact(_)),
+            _ => false,
+        }
+    }
+
+    fn can_match(&self, state: &PrefixedState) -> bool {
+        match state {
+            PrefixedState::Prefix(_) => true,
+            PrefixedState::Word(s) => *s != SINK_STATE,
+            PrefixedState::Dead => false,
+        }
+    }
+
+    fn accept(&self, state: &PrefixedState, byte: u8) -> PrefixedState {
+        match *state {
+            PrefixedState::Prefix(i) if self.prefix[i] == byte => {
+                if i + 1 == self.prefix.len() {
+                    PrefixedState::Word(self.dfa.initial_state())
+                } else {
+                    PrefixedState::Prefix(i + 1)
+                }
+            }
+            PrefixedState::Word(s) => PrefixedState::Word(self.dfa.transition(s, byte)),
+            _ => PrefixedState::Dead,
+        }
+    }
+}
+
+/// Ranked corrections for a single token: closest edit distance first, then
+/// most frequent in the index.
+fn corrections_for(token: &str, vocabulary: &impl Vocabulary, limit: usize) -> Vec<String> {
+    let chars: Vec<char> = token.chars().collect();
+    let max = max_distance(chars.len());
+    if max == 0 {
+        return Vec::new();
+    }
+    let mut candidates: Vec<(usize, u64, String)> = vocabulary
+        .neighbours(token, max)
+        .into_iter()
+        .filter(|(word, _)| word != token)
+        .filter_map(|(word, freq)| {
+            let word_chars: Vec<char> = word.chars().collect();
+            edit_distance(&chars, &word_chars, max).map(|d| (d, freq, word))
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)).then(a.2.cmp(&b.2)));
+    candidates
+        .into_iter()
+        .take(limit)
+        .map(|(_, _, w)| w)
+        .collect()
+}
+
+/// Suggest alternative spellings for `query_text` from the index's own term
+/// dictionary. Tokens found in the index are kept as-is; unknown tokens are
+/// replaced by their closest, most frequent neighbour. The n-th suggestion uses
+/// each misspelled token's n-th best correction (falling back to its best).
+///
+/// Returns an empty list when every token is already known or no correction
+/// is close enough.
+pub fn suggest(
+    query_text: &str,
+    searcher: &Searcher,
+    json_exact_field: Field,
+    searchable_paths: &[String],
+    max_suggestions: usize,
+) -> Vec<String> {
+    let tokens: Vec<String> = query_text
+        .split_whitespace()
+        .map(|t| t.to_lowercase())
+        .collect();
+    if tokens.is_empty() || max_suggestions == 0 {
+        return Vec::new();
+    }
+
+    let vocabulary = IndexVocabulary {
+        searcher,
+        json_exact_field,
+        searchable_paths,
+    };
+    suggest_from_vocabulary(&tokens, &vocabulary, max_suggestions)
+}
+
+fn suggest_from_vocabulary(
+    tokens: &[String],
+    vocabulary: &impl Vocabulary,
+    max_suggestions: usize,
+) -> Vec<String> {
+    let per_token: Vec<Vec<String>> = tokens
+        .iter()
+        .map(|t| {
+            if vocabulary.contains(t) {
+                Vec::new()
+            } else {
+                corrections_for(t, vocabulary, max_suggestions)
+            }
+        })
+        .collect();
+
+    let depth = per_token.iter().map(|c| c.len()).max().unwrap_or(0);
+    let mut suggestions: Vec<String> = Vec::new();
+    for n in 0..depth {
+        let candidate = tokens
+            .iter()
+            .zip(&per_token)
+            .map(|(token, corrections)| {
+                corrections
+                    .get(n)
+                    .or_else(|| corrections.first())
+                    .map(String::as_str)
+                    .unwrap_or(token.as_str())
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !suggestions.contains(&candidate) {
+            suggestions.push(candidate);
+        }
+    }
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    impl Vocabulary for HashMap<String, u64> {
+        fn contains(&self, word: &str) -> bool {
+            self.contains_key(word)
+        }
+
+        fn neighbours(&self, _token: &str, _max: usize) -> HashMap<String, u64> {
+            self.clone()
+        }
+    }
+
+    fn dict(words: &[(&str, u64)]) -> HashMap<String, u64> {
+        words.iter().map(|(w, f)| (w.to_string(), *f)).collect()
+    }
+
+    fn tokens(q: &str) -> Vec<String> {
+        q.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn edit_distance_substitution_and_transposition() {
+        assert_eq!(
+            edit_distance(&chars("laptop"), &chars("laptap"), 2),
+            Some(1)
+        );
+        assert_eq!(
+            edit_distance(&chars("laptop"), &chars("lpatop"), 2),
+            Some(1)
+        );
+        assert_eq!(edit_distance(&chars("laptop"), &chars("desktop"), 2), None);
+    }
+
+    #[test]
+    fn short_tokens_are_not_corrected() {
+        let d = dict(&[("cat", 10)]);
+        assert!(suggest_from_vocabulary(&tokens("cot"), &d, 3).is_empty());
+    }
+
+    #[test]
+    fn known_tokens_produce_no_suggestion() {
+        let d = dict(&[("laptop", 10)]);
+        assert!(suggest_from_vocabulary(&tokens("laptop"), &d, 3).is_empty());
+    }
+
+    #[test]
+    fn prefers_closer_then_more_frequent() {
+        let d = dict(&[("laptop", 5), ("laptops", 50), ("lapdog", 100)]);
+        let s = suggest_from_vocabulary(&tokens("laptpo"), &d, 3);
+        assert_eq!(s[0], "laptop");
+    }
+
+    #[test]
+    fn keeps_known_words_and_corrects_unknown_ones() {
+        let d = dict(&[("gaming", 10), ("laptop", 10)]);
+        let s = suggest_from_vocabulary(&tokens("gaming laptpo"), &d, 3);
+        assert_eq!(s, vec!["gaming laptop".to_string()]);
+    }
+
+    #[test]
+    fn multiple_suggestions_ordered_by_rank() {
+        let d = dict(&[("shirt", 100), ("short", 10)]);
+        let s = suggest_from_vocabulary(&tokens("shart"), &d, 3);
+        assert_eq!(s, vec!["shirt".to_string(), "short".to_string()]);
+    }
+}
@@ -26,13 +26,15 @@ fn term_exists(
 }
 
 /// Generate split alternatives for a query.
-/// For each token >= 4 chars, tries all 2-part splits where both halves >= 2 chars
-/// and both exist in the index. Takes the first valid split per token.
+/// For each token >= `min_word_size` (and >= 4) chars, tries all 2-part splits
+/// where both halves >= 2 chars and both exist in the index. Takes the first
+/// valid split per token.
 fn generate_split_alternatives(
     tokens: &[&str],
     searcher: &Searcher,
     json_exact_field: Field,
     searchable_paths: &[String],
+    min_word_size: usize,
 ) -> Vec<String> {
     let mut alternatives = Vec::new();
 
@@ -40,7 +42,7 @@ fn generate_split_alternatives(
         let lower = token.to_lowercase();
         let chars: Vec<char> = lower.chars().collect();
         let char_count = chars.len();
-        if char_count < 4 {
+        if char_count < min_word_size.max(4) {
             continue;
         }
 
@@ -76,7 +78,8 @@ fn generate_split_alternatives(
 /// Generate concatenation alternatives for a query.
 /// Bi-gram: concatenate adjacent pairs (first 5 tokens only).
 /// All-word: concatenate all tokens if >= 3.
-fn generate_concat_alternatives(tokens: &[&str]) -> Vec<String> {
+/// Concatenations shorter than `min_word_size` chars are skipped.
+fn generate_concat_alternatives(tokens: &[&str], min_word_size: usize) -> Vec<String> {
     let mut alternatives = Vec::new();
     if tokens.len() < 2 {
         return alternatives;
@@ -90,6 +93,9 @@ fn generate_concat_alternatives(tokens: &[&str]) -> Vec<String> {
             tokens[i].to_lowercase(),
             tokens[i + 1].to_lowercase()
         );
+        if concat.chars().count() < min_word_size {
+            continue;
+        }
         let mut alt_tokens: Vec<String> = Vec::with_capacity(tokens.len());
         for (j, t) in tokens.iter().enumerate() {
             if j == i {
@@ -106,7 +112,9 @@ fn generate_concat_alternatives(tokens: &[&str]) -> Vec<String> {
     // All-word concatenation if >= 3 tokens
     if tokens.len() >= 3 {
         let all_concat: String = tokens.iter().map(|t| t.to_lowercase()).collect();
-        alternatives.push(all_concat);
+        if all_concat.chars().count() >= min_word_size {
+            alternatives.push(all_concat);
+        }
     }
 
     alternatives
@@ -114,11 +122,16 @@ fn generate_concat_alternatives(tokens: &[&str]) -> Vec<String> {
 
 /// Generate all split and concat alternatives for a query string.
 /// Returns alternative query strings (not including the original).
+///
+/// Splitting and concatenating count as one typo, so only words of at least
+/// `min_word_size` chars (`minWordSizefor1Typo`) are split and only
+/// concatenations that long are tried.
 pub fn generate_alternatives(
     query_text: &str,
     searcher: &Searcher,
     json_exact_field: Field,
     searchable_paths: &[String],
+    min_word_size: usize,
 ) -> Vec<String> {
     let tokens: Vec<&str> = query_text.split_whitespace().collect();
     if tokens.is_empty() {
@@ -131,8 +144,9 @@ pub fn generate_alternatives(
         searcher,
         json_exact_field,
         searchable_paths,
+        min_word_size,
     ));
-    alternatives.extend(generate_concat_alternatives(&tokens));
+    alternatives.extend(generate_concat_alternatives(&tokens, min_word_size));
     alternatives
 }
 
@@ -142,13 +156,13 @@ mod tests {
 
     #[test]
     fn concat_two_words() {
-        let alts = generate_concat_alternatives(&["blue", "tooth"]);
+        let alts = generate_concat_alternatives(&["blue", "tooth"], 0);
         assert_eq!(alts, vec!["bluetooth"]);
     }
 
     #[test]
     fn concat_three_words() {
-        let alts = generate_concat_alternatives(&["ice", "cream", "cone"]);
+        let alts = generate_concat_alternatives(&["ice", "cream", "cone"], 0);
         assert!(alts.contains(&"icecream cone".to_string()));
         assert!(alts.contains(&"ice creamcone".to_string()));
         assert!(alts.contains(&"icecreamcone".to_string()));
@@ -156,18 +170,24 @@ mod tests {
 
     #[test]
     fn concat_respects_5_token_limit() {
-        let alts = generate_concat_alternatives(&["a", "b", "c", "d", "e", "f", "g"]);
+        let alts = generate_concat_alternatives(&["a", "b", "c", "d", "e", "f", "g"], 0);
         // 4 bi-grams (pairs within first 5 tokens) + 1 all-word
         assert_eq!(alts.len(), 5);
     }
 
+    #[test]
+    fn concat_skips_words_below_min_size() {
+        let alts = generate_concat_alternatives(&["ab", "cd", "efgh"], 5);
+        assert_eq!(alts, vec!["ab cdefgh", "abcdefgh"]);
+    }
+
     #[test]
     fn concat_single_token() {
-        assert!(generate_concat_alternatives(&["hello"]).is_empty());
+        assert!(generate_concat_alternatives(&["hello"], 0).is_empty());
     }
 
     #[test]
     fn concat_empty() {
-        assert!(generate_concat_alternatives(&[]).is_empty());
+        assert!(generate_concat_alternatives(&[], 0).is_empty());
     }
 }
@@ -1,9 +1,14 @@
-use super::config::{BuildStatus, LogEntry, QsConfig, QsConfigStore};
+use super::config::{BuildStatus, BuildTimings, LogEntry, QsConfig, QsConfigStore, QsSourceIndex};
 use crate::analytics::AnalyticsQueryEngine;
+use crate::index::settings::IndexSettings;
 use crate::types::{Document, FieldValue};
 use crate::IndexManager;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
+
+/// Faceted attribute holding a suggestion's languages (`languageFacet`).
+pub const LANGUAGE_ATTR: &str = "language";
 
 fn log_info(msg: &str) -> LogEntry {
     LogEntry {
@@ -11,6 +16,7 @@ fn log_info(msg: &str) -> LogEntry {
         level: "INFO".to_string(),
         message: msg.to_string(),
         context_level: 1,
+        ..Default::default()
     }
 }
 
@@ -20,6 +26,7 @@ fn log_skip(msg: &str) -> LogEntry {
         level: "SKIP".to_string(),
         message: msg.to_string(),
         context_level: 2,
+        ..Default::default()
     }
 }
 
@@ -29,13 +36,65 @@ fn log_error(msg: &str) -> LogEntry {
         level: "ERROR".to_string(),
         message: msg.to_string(),
         context_level: 0,
+        ..Default::default()
+    }
+}
+
+/// The languages to tag a suggestion with, most searched first: those of
+/// `searches` (search count per language) that are in `allowed`, or all of
+/// them when `allowed` is empty.
+fn suggestion_languages(searches: &HashMap<String, u64>, allowed: &[String]) -> Vec<String> {
+    let mut languages: Vec<(&String, u64)> = searches
+        .iter()
+        .filter(|(lang, _)| allowed.is_empty() || allowed.contains(lang))
+        .map(|(lang, count)| (lang, *count))
+        .collect();
+    languages.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    languages
+        .into_iter()
+        .map(|(lang, _)| lang.clone())
+        .collect()
+}
+
+/// Build phase that reads top searches and their languages from analytics.
+pub const PHASE_ANALYTICS_SCAN: &str = "analyticsScan";
+/// Build phase that filters searches and merges them across sources.
+pub const PHASE_DEDUPE: &str = "dedupe";
+/// Build phase that writes the staging index and swaps it in.
+pub const PHASE_INDEX_WRITE: &str = "indexWrite";
+
+fn log_phase(phase: &str, duration_ms: u64) -> LogEntry {
+    LogEntry {
+        phase: Some(phase.to_string()),
+        duration_ms: Some(duration_ms),
+        ..log_info(&format!("Phase {} took {} ms", phase, duration_ms))
     }
 }
 
+fn elapsed_ms(since: Instant) -> u64 {
+    since.elapsed().as_millis() as u64
+}
+
+/// Record the phase a running build is in, for the status endpoint.
+fn enter_phase(store: &QsConfigStore, index_name: &str, phase: &str) {
+    let mut status = store.load_status(index_name);
+    status.phase = Some(phase.to_string());
+    store.save_status(&status).ok();
+}
+
+/// Write out pending log entries so clients following the log see them
+/// while the build is still running.
+fn flush_log(store: &QsConfigStore, index_name: &str, entries: &mut Vec<LogEntry>) {
+    store.append_log(index_name, entries).ok();
+    entries.clear();
+}
+
 /// Build a suggestions index from analytics data.
 ///
 /// Uses an atomic swap: builds into `{indexName}__building`, then renames
 /// to `{indexName}` so the live index is never empty during a rebuild.
+/// Log entries are written at the end of each phase, and the status records
+/// the current phase and, once done, how long each phase took.
 ///
 /// Returns the number of suggestions written.
 pub async fn build_suggestions_index(
@@ -44,6 +103,7 @@ pub async fn build_suggestions_index(
     manager: &Arc<IndexManager>,
     analytics_engine: &Arc<AnalyticsQueryEngine>,
 ) -> Result<usize, String> {
+    let build_started = Instant::now();
     let staging_name = format!("{}__building", config.index_name);
 
     let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
@@ -62,6 +122,7 @@ pub async fn build_suggestions_index(
             .collect::<Vec<_>>()
             .join(", ")
     )));
+    flush_log(store, &config.index_name, &mut log_entries);
 
     // Set up staging index: delete if it exists, then recreate fresh
     if manager.get_or_load(&staging_name).is_ok() {
@@ -82,10 +143,22 @@ pub async fn build_suggestions_index(
     manager
         .create_tenant(&staging_name)
         .map_err(|e| e.to_string())?;
+    if config.language_facet {
+        let settings_path = manager.base_path.join(&staging_name).join("settings.json");
+        let mut settings = IndexSettings::load(&settings_path).unwrap_or_default();
+        settings
+            .attributes_for_faceting
+            .push(LANGUAGE_ATTR.to_string());
+        settings.save(&settings_path).map_err(|e| e.to_string())?;
+        manager.invalidate_settings_cache(&staging_name);
+    }
 
-    // Aggregate suggestions across all source indices.
-    // Key: query string → max popularity across sources
-    let mut query_map: HashMap<String, u64> = HashMap::new();
+    // Phase 1: read top searches (and their languages) of every source.
+    enter_phase(store, &config.index_name, PHASE_ANALYTICS_SCAN);
+    let phase_started = Instant::now();
+    let mut source_searches: Vec<(&QsSourceIndex, Vec<serde_json::Value>)> = vec![];
+    // Key: query string → language → search count across sources
+    let mut query_languages: HashMap<String, HashMap<String, u64>> = HashMap::new();
 
     for source in &config.source_indices {
         let tags_opt: Option<String> = if source.analytics_tags.is_empty() {
@@ -123,7 +196,57 @@ pub async fn build_suggestions_index(
             source.index_name
         )));
 
-        for item in &searches {
+        if config.language_facet {
+            match analytics_engine
+                .search_languages(
+                    &source.index_name,
+                    &thirty_days_ago,
+                    &today,
+                    tags_opt.as_deref(),
+                )
+                .await
+            {
+                Ok(languages) => {
+                    for (query, counts) in languages {
+                        let merged = query_languages.entry(query).or_default();
+                        for (lang, count) in counts {
+                            *merged.entry(lang).or_default() += count;
+                        }
+                    }
+                }
+                Err(e) => log_entries.push(log_error(&format!(
+                    "Query language lookup failed for '{}': {}",
+                    source.index_name, e
+                ))),
+            }
+        }
+
+        if !source.generate.is_empty() {
+            log_entries.push(log_info(
+                "generate field present — facet-value suggestions deferred to v2",
+            ));
+        }
+        if !source.facets.is_empty() {
+            log_entries.push(log_info(
+                "facets field present — facet enrichment deferred to v2",
+            ));
+        }
+
+        source_searches.push((source, searches));
+    }
+
+    let analytics_scan_ms = elapsed_ms(phase_started);
+    log_entries.push(log_phase(PHASE_ANALYTICS_SCAN, analytics_scan_ms));
+    flush_log(store, &config.index_name, &mut log_entries);
+
+    // Phase 2: filter searches and merge them across sources.
+    enter_phase(store, &config.index_name, PHASE_DEDUPE);
+    let phase_started = Instant::now();
+    // Key: query string → max popularity across sources
+    let mut query_map: HashMap<String, u64> = HashMap::new();
+
+    for (source, searches) in &source_searches {
+        for item in searches {
             // top_searches returns {search: "...", count: N, nbHits: M}
             // count = how many times searched (→ popularity)
             // nbHits = average result count per search (Algolia minHits filters on this)
@@ -167,17 +290,6 @@ pub async fn build_suggestions_index(
             let entry = query_map.entry(query).or_insert(0);
             *entry = (*entry).max(count);
         }
-
-        if !source.generate.is_empty() {
-            log_entries.push(log_info(
-                "generate field present — facet-value suggestions deferred to v2",
-            ));
-        }
-        if !source.facets.is_empty() {
-            log_entries.push(log_info(
-                "facets field present — facet enrichment deferred to v2",
-            ));
-        }
     }
 
     // Build Document list
@@ -191,6 +303,15 @@ pub async fn build_suggestions_index(
             "popularity".to_string(),
             FieldValue::Integer(*popularity as i64),
         );
+        if let Some(searches) = query_languages.get(query) {
+            let languages = suggestion_languages(searches, &config.languages);
+            if !languages.is_empty() {
+                fields.insert(
+                    LANGUAGE_ATTR.to_string(),
+                    FieldValue::Array(languages.into_iter().map(FieldValue::Text).collect()),
+                );
+            }
+        }
         // Stub exact_nb_hits per source index (v2: run a search per suggestion)
         for source in &config.source_indices {
             fields.insert(
@@ -207,11 +328,19 @@ pub async fn build_suggestions_index(
         });
     }
 
+    let dedupe_ms = elapsed_ms(phase_started);
+    log_entries.push(log_phase(PHASE_DEDUPE, dedupe_ms));
+    flush_log(store, &config.index_name, &mut log_entries);
+
+    // Phase 3: write the staging index and swap it in.
+    enter_phase(store, &config.index_name, PHASE_INDEX_WRITE);
+    let phase_started = Instant::now();
     let doc_count = docs.len();
     log_entries.push(log_info(&format!(
         "Writing {} suggestions to staging index '{}'",
         doc_count, staging_name
     )));
+    flush_log(store, &config.index_name, &mut log_entries);
 
     if !docs.is_empty() {
         manager
@@ -226,13 +355,16 @@ pub async fn build_suggestions_index(
         .await
         .map_err(|e| e.to_string())?;
 
+    let index_write_ms = elapsed_ms(phase_started);
+    log_entries.push(log_phase(PHASE_INDEX_WRITE, index_write_ms));
+
     let now = chrono::Utc::now().to_rfc3339();
     log_entries.push(log_info(&format!(
         "Build complete: {} suggestions written to '{}'",
         doc_count, config.index_name
     )));
 
-    store.append_log(&config.index_name, &log_entries).ok();
+    flush_log(store, &config.index_name, &mut log_entries);
     store.truncate_log(&config.index_name, 1000).ok();
 
     let status = BuildStatus {
@@ -240,8 +372,35 @@ pub async fn build_suggestions_index(
         is_running: false,
         last_built_at: Some(now.clone()),
         last_successful_built_at: Some(now),
+        phase: None,
+        last_build_timings: Some(BuildTimings {
+            analytics_scan_ms,
+            dedupe_ms,
+            index_write_ms,
+            total_ms: elapsed_ms(build_started),
+        }),
     };
     store.save_status(&status).ok();
 
     Ok(doc_count)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggestion_languages_are_ranked_and_restricted() {
+        let searches = HashMap::from([
+            ("en".to_string(), 3),
+            ("de".to_string(), 12),
+            ("fr".to_string(), 3),
+        ]);
+        assert_eq!(suggestion_languages(&searches, &[]), vec!["de", "en", "fr"]);
+        assert_eq!(
+            suggestion_languages(&searches, &["fr".to_string(), "de".to_string()]),
+            vec!["de", "fr"]
+        );
+        assert!(suggestion_languages(&searches, &["it".to_string()]).is_empty());
+    }
+}
@@ -42,6 +42,12 @@ pub struct QsConfig {
     pub source_indices: Vec<QsSourceIndex>,
     #[serde(default)]
     pub languages: Vec<String>,
+    /// Tag each suggestion with the languages its searches were made in
+    /// (the faceted `language` attribute), so one suggestions index can serve
+    /// every storefront language with `filters: "language:de"`. When
+    /// `languages` is set, only those languages are tagged.
+    #[serde(default)]
+    pub language_facet: bool,
     #[serde(default)]
     pub exclude: Vec<String>,
     #[serde(default)]
@@ -58,15 +64,39 @@ pub struct BuildStatus {
     pub is_running: bool,
     pub last_built_at: Option<String>,
     pub last_successful_built_at: Option<String>,
+    /// Phase of the running build: `analyticsScan`, `dedupe` or `indexWrite`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_build_timings: Option<BuildTimings>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How long each phase of a successful build took, in milliseconds.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildTimings {
+    pub analytics_scan_ms: u64,
+    pub dedupe_ms: u64,
+    pub index_write_ms: u64,
+    pub total_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct LogEntry {
+    /// Position in the log, assigned on append. Entries written before
+    /// sequence numbers existed read as 0.
+    #[serde(default)]
+    pub seq: u64,
     pub timestamp: String,
     pub level: String,
     pub message: String,
     pub context_level: u8,
+    /// Set on the entry that closes a build phase, with its duration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
 }
 
 /// Manages Query Suggestions config/status/log files on disk.
@@ -180,18 +210,25 @@ impl QsConfigStore {
         std::fs::write(path, json)
     }
 
+    /// Append entries, numbering them after the last entry in the log.
     pub fn append_log(&self, index_name: &str, entries: &[LogEntry]) -> std::io::Result<()> {
         if entries.is_empty() {
             return Ok(());
         }
         self.ensure_dir()?;
+        let mut seq = self.read_logs(index_name).last().map_or(0, |e| e.seq);
         let path = self.log_path(index_name);
         let mut file = std::fs::OpenOptions::new()
             .create(true)
             .append(true)
             .open(&path)?;
         for entry in entries {
-            let line = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+            seq += 1;
+            let entry = LogEntry {
+                seq,
+                ..entry.clone()
+            };
+            let line = serde_json::to_string(&entry).map_err(std::io::Error::other)?;
             writeln!(file, "{}", line)?;
         }
         Ok(())
@@ -228,6 +265,14 @@ impl QsConfigStore {
             })
             .unwrap_or_default()
     }
+
+    /// Entries appended after the one numbered `since`.
+    pub fn read_logs_since(&self, index_name: &str, since: u64) -> Vec<LogEntry> {
+        self.read_logs(index_name)
+            .into_iter()
+            .filter(|e| e.seq > since)
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -248,6 +293,7 @@ mod tests {
                 replicas: false,
             }],
             languages: vec![],
+            language_facet: false,
             exclude: vec![],
             allow_special_characters: false,
             enable_personalization: false,
@@ -310,6 +356,7 @@ mod tests {
             is_running: false,
             last_built_at: Some("2026-02-19T12:00:00Z".to_string()),
             last_successful_built_at: Some("2026-02-19T12:00:00Z".to_string()),
+            ..Default::default()
         };
         store.save_status(&status).unwrap();
         let loaded = store.load_status("test");
@@ -326,12 +373,14 @@ mod tests {
                 level: "INFO".to_string(),
                 message: "Build started".to_string(),
                 context_level: 1,
+                ..Default::default()
             },
             LogEntry {
                 timestamp: "2026-02-19T12:00:01Z".to_string(),
                 level: "INFO".to_string(),
                 message: "Build complete: 42 suggestions".to_string(),
                 context_level: 1,
+                ..Default::default()
             },
         ];
         store.append_log("test", &entries).unwrap();
@@ -350,6 +399,7 @@ mod tests {
                 level: "INFO".to_string(),
                 message: format!("entry {}", i),
                 context_level: 1,
+                ..Default::default()
             })
             .collect();
         store.append_log("test", &entries).unwrap();
@@ -359,4 +409,28 @@ mod tests {
         assert_eq!(logs[0].message, "entry 5");
         assert_eq!(logs[4].message, "entry 9");
     }
+
+    #[test]
+    fn log_entries_are_numbered_across_appends_and_truncation() {
+        let tmp = TempDir::new().unwrap();
+        let store = QsConfigStore::new(tmp.path());
+        let entry = |message: &str| LogEntry {
+            timestamp: "2026-02-19T00:00:00Z".to_string(),
+            level: "INFO".to_string(),
+            message: message.to_string(),
+            context_level: 1,
+            ..Default::default()
+        };
+        store
+            .append_log("test", &[entry("a"), entry("b"), entry("c")])
+            .unwrap();
+        store.truncate_log("test", 2).unwrap();
+        store.append_log("test", &[entry("d")]).unwrap();
+
+        let seqs: Vec<u64> = store.read_logs("test").iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, vec![2, 3, 4]);
+        let newer = store.read_logs_since("test", 3);
+        assert_eq!(newer.len(), 1);
+        assert_eq!(newer[0].message, "d");
+    }
 }
@@ -2,4 +2,4 @@ pub mod builder;
 pub mod config;
 
 pub use builder::build_suggestions_index;
-pub use config::{BuildStatus, LogEntry, QsConfig, QsConfigStore, QsSourceIndex};
+pub use config::{BuildStatus, BuildTimings, LogEntry, QsConfig, QsConfigStore, QsSourceIndex};
@@ -0,0 +1,183 @@
+//! camelCase word splitting for `splitCamelCase`.
+//!
+//! "MacBookPro" is indexed as itself plus `Mac`, `Book` and `Pro`, and a
+//! query for "MacBookPro" searches for the three parts, so it matches
+//! "MacBook Pro", "macbook pro" and "mac_book_pro" alike. snake_case needs
+//! no help: `_` already separates words on both sides.
+
+use tantivy::tokenizer::{Token, TokenFilter, TokenStream, Tokenizer};
+
+/// Byte ranges of the camelCase parts of `word`; a single range when it has
+/// no internal case change. An acronym stays together: "HTMLParser" is
+/// `HTML` + `Parser`.
+pub fn camel_case_parts(word: &str) -> Vec<(usize, usize)> {
+    let chars: Vec<(usize, char)> = word.char_indices().collect();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for i in 1..chars.len() {
+        let (offset, c) = chars[i];
+        let prev = chars[i - 1].1;
+        let next_is_lower = chars.get(i + 1).is_some_and(|(_, n)| n.is_lowercase());
+        if c.is_uppercase() && (prev.is_lowercase() || (prev.is_uppercase() && next_is_lower)) {
+            parts.push((start, offset));
+            start = offset;
+        }
+    }
+    if !word.is_empty() {
+        parts.push((start, word.len()));
+    }
+    parts
+}
+
+/// Put a space between the camelCase parts of every word of `text`.
+pub fn split_camel_case(text: &str) -> String {
+    fn push_word(out: &mut String, word: &str) {
+        for (i, (start, end)) in camel_case_parts(word).into_iter().enumerate() {
+            if i > 0 {
+                out.push(' ');
+            }
+            out.push_str(&word[start..end]);
+        }
+    }
+
+    let mut out = String::with_capacity(text.len() + 8);
+    let mut word_start = None;
+    for (offset, c) in text.char_indices() {
+        if c.is_alphanumeric() {
+            word_start.get_or_insert(offset);
+        } else {
+            if let Some(start) = word_start.take() {
+                push_word(&mut out, &text[start..offset]);
+            }
+            out.push(c);
+        }
+    }
+    if let Some(start) = word_start {
+        push_word(&mut out, &text[start..]);
+    }
+    out
+}
+
+/// Token filter emitting each camelCase token followed by its parts, at the
+/// same position. Must run before lowercasing.
+#[derive(Clone, Default)]
+pub struct CamelCaseSplitFilter;
+
+impl TokenFilter for CamelCaseSplitFilter {
+    type Tokenizer<T: Tokenizer> = CamelCaseSplitFilterWrapper<T>;
+
+    fn transform<T: Tokenizer>(self, tokenizer: T) -> Self::Tokenizer<T> {
+        CamelCaseSplitFilterWrapper { inner: tokenizer }
+    }
+}
+
+#[derive(Clone)]
+pub struct CamelCaseSplitFilterWrapper<T> {
+    inner: T,
+}
+
+impl<T: Tokenizer> Tokenizer for CamelCaseSplitFilterWrapper<T> {
+    type TokenStream<'a> = CamelCaseSplitTokenStream<T::TokenStream<'a>>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        CamelCaseSplitTokenStream {
+            inner: self.inner.token_stream(text),
+            pending: Vec::new(),
+            token: Token::default(),
+        }
+    }
+}
+
+pub struct CamelCaseSplitTokenStream<T> {
+    inner: T,
+    /// Parts of the last token still to emit, in reverse order.
+    pending: Vec<Token>,
+    token: Token,
+}
+
+impl<T: TokenStream> TokenStream for CamelCaseSplitTokenStream<T> {
+    fn advance(&mut self) -> bool {
+        if let Some(part) = self.pending.pop() {
+            self.token = part;
+            return true;
+        }
+        if !self.inner.advance() {
+            return false;
+        }
+        self.token = self.inner.token().clone();
+        let parts = camel_case_parts(&self.token.text);
+        if parts.len() > 1 {
+            self.pending = parts
+                .into_iter()
+                .rev()
+                .map(|(start, end)| Token {
+                    offset_from: self.token.offset_from + start,
+                    offset_to: self.token.offset_from + end,
+                    text: self.token.text[start..end].to_string(),
+                    ..self.token.clone()
+                })
+                .collect();
+        }
+        true
+    }
+
+    fn token(&self) -> &Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tantivy::tokenizer::SimpleTokenizer;
+
+    fn parts(word: &str) -> Vec<&str> {
+        camel_case_parts(word)
+            .into_iter()
+            .map(|(s, e)| &word[s..e])
+            .collect()
+    }
+
+    #[test]
+    fn splits_on_case_changes() {
+        assert_eq!(parts("MacBookPro"), vec!["Mac", "Book", "Pro"]);
+        assert_eq!(parts("iPhone"), vec!["i", "Phone"]);
+        assert_eq!(parts("HTMLParser"), vec!["HTML", "Parser"]);
+        assert_eq!(parts("macbook"), vec!["macbook"]);
+        assert_eq!(parts("USB"), vec!["USB"]);
+        assert!(parts("").is_empty());
+    }
+
+    #[test]
+    fn splits_words_in_text() {
+        assert_eq!(
+            split_camel_case("new MacBookPro, 16\""),
+            "new Mac Book Pro, 16\""
+        );
+        assert_eq!(split_camel_case("mac_book_pro"), "mac_book_pro");
+    }
+
+    #[test]
+    fn filter_emits_token_then_parts() {
+        let mut tokenizer = CamelCaseSplitFilter.transform(SimpleTokenizer::default());
+        let mut stream = tokenizer.token_stream("MacBook Pro");
+        let mut tokens = Vec::new();
+        while stream.advance() {
+            let t = stream.token();
+            tokens.push((t.text.clone(), t.position));
+        }
+        assert_eq!(
+            tokens,
+            vec![
+                ("MacBook".to_string(), 0),
+                ("Mac".to_string(), 0),
+                ("Book".to_string(), 0),
+                ("Pro".to_string(), 1),
+            ]
+        );
+    }
+}
@@ -1,4 +1,8 @@
+pub mod camel_case;
 pub mod cjk_tokenizer;
 pub mod edge_ngram_filter;
+pub mod normalize;
+pub use camel_case::CamelCaseSplitFilter;
 pub use cjk_tokenizer::CjkAwareTokenizer;
 pub use edge_ngram_filter::EdgeNgramTokenFilter;
+pub use normalize::{NormalizeTokenFilter, TextNormalization};
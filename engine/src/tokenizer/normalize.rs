@@ -0,0 +1,408 @@
+//! Text normalization applied identically to indexed tokens, query text,
+//! highlighting and facet-value search.
+//!
+//! Configured per index by `normalizeUnicode` (NFKC), `removeDiacritics`
+//! (with `keepDiacriticsOnCharacters` and language defaults for letters that
+//! are distinct in that language, e.g. å/ä/ö for Swedish) and
+//! `transliterate` (`cyrillic`, `greek`). `splitCamelCase` rides along
+//! because it changes the index analyzers the same way. Normalization works one character
+//! at a time so that positions in the normalized text map back to the
+//! original, which highlighting needs.
+
+use super::camel_case::split_camel_case;
+use crate::index::settings::IndexSettings;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tantivy::tokenizer::{Token, TokenFilter, TokenStream, Tokenizer};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextNormalization {
+    nfkc: bool,
+    remove_diacritics: bool,
+    /// Lowercase letters left alone by diacritic removal.
+    keep: HashSet<char>,
+    cyrillic: bool,
+    greek: bool,
+    split_camel_case: bool,
+}
+
+/// Letters that are separate letters of the alphabet, not accented
+/// variants, in these languages.
+fn language_keep_chars(lang: &str) -> &'static str {
+    match lang {
+        "sv" | "fi" => "åäö",
+        "da" | "no" | "nb" | "nn" => "æøå",
+        "is" => "áéíóúýþæöð",
+        "tr" => "çğıöşü",
+        "et" => "äöõü",
+        _ => "",
+    }
+}
+
+/// Supported `transliterate` values.
+pub const TRANSLITERATION_SCRIPTS: &[&str] = &["cyrillic", "greek"];
+
+impl TextNormalization {
+    /// The normalization `settings` ask for, or `None` when they leave text
+    /// as the default lowercasing tokenizer sees it.
+    pub fn from_settings(settings: &IndexSettings) -> Option<Self> {
+        let transliterate = |script: &str| settings.transliterate.iter().any(|s| s == script);
+        let normalization = TextNormalization {
+            nfkc: settings.normalize_unicode,
+            remove_diacritics: settings.remove_diacritics,
+            keep: settings
+                .query_languages
+                .iter()
+                .flat_map(|lang| language_keep_chars(lang).chars())
+                .chain(settings.keep_diacritics_on_characters.chars())
+                .flat_map(char::to_lowercase)
+                .collect(),
+            cyrillic: transliterate("cyrillic"),
+            greek: transliterate("greek"),
+            split_camel_case: settings.split_camel_case,
+        };
+        if normalization.nfkc
+            || normalization.remove_diacritics
+            || normalization.cyrillic
+            || normalization.greek
+            || normalization.split_camel_case
+        {
+            Some(normalization)
+        } else {
+            None
+        }
+    }
+
+    /// Lowercase and normalize `text`.
+    pub fn normalize(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        for c in text.chars() {
+            self.push_normalized(c, &mut out);
+        }
+        out
+    }
+
+    /// Normalize query text, splitting camelCase words first when
+    /// `splitCamelCase` is on, to match how they were indexed.
+    pub fn normalize_query(&self, query: &str) -> String {
+        if self.split_camel_case {
+            self.normalize(&split_camel_case(query))
+        } else {
+            self.normalize(query)
+        }
+    }
+
+    /// Whether camelCase tokens are indexed with their parts.
+    pub fn splits_camel_case(&self) -> bool {
+        self.split_camel_case
+    }
+
+    /// Like [`normalize`](Self::normalize), plus for each byte of the result
+    /// the offset in `text` of the character it came from, followed by
+    /// `text.len()`.
+    pub fn normalize_with_offsets(&self, text: &str) -> (String, Vec<usize>) {
+        let mut out = String::with_capacity(text.len());
+        let mut offsets = Vec::with_capacity(text.len() + 1);
+        for (offset, c) in text.char_indices() {
+            self.push_normalized(c, &mut out);
+            offsets.resize(out.len(), offset);
+        }
+        offsets.push(text.len());
+        (out, offsets)
+    }
+
+    fn push_normalized(&self, c: char, out: &mut String) {
+        if self.nfkc && !c.is_ascii() {
+            for n in std::iter::once(c).nfkc() {
+                for l in n.to_lowercase() {
+                    self.push_folded(l, out);
+                }
+            }
+        } else {
+            for l in c.to_lowercase() {
+                self.push_folded(l, out);
+            }
+        }
+    }
+
+    fn push_folded(&self, c: char, out: &mut String) {
+        if c.is_ascii() {
+            out.push(c);
+            return;
+        }
+        if let Some(latin) = self.transliteration(c) {
+            out.push_str(latin);
+            return;
+        }
+        if !self.remove_diacritics || self.keep.contains(&c) {
+            out.push(c);
+            return;
+        }
+        if let Some(folded) = fold_letter(c) {
+            out.push_str(folded);
+            return;
+        }
+        for d in std::iter::once(c).nfd() {
+            if !is_combining_mark(d) {
+                out.push(d);
+            }
+        }
+    }
+
+    fn transliteration(&self, c: char) -> Option<&'static str> {
+        if self.cyrillic {
+            if let Some(latin) = cyrillic_to_latin(c) {
+                return Some(latin);
+            }
+        }
+        if self.greek {
+            // Accented Greek vowels transliterate like their base letter.
+            let base = std::iter::once(c).nfd().next().unwrap_or(c);
+            if let Some(latin) = greek_to_latin(base) {
+                return Some(latin);
+            }
+        }
+        None
+    }
+}
+
+/// Letters with no canonical decomposition that still read as a base
+/// Latin letter once the diacritic is dropped.
+fn fold_letter(c: char) -> Option<&'static str> {
+    Some(match c {
+        'ø' => "o",
+        'æ' => "ae",
+        'œ' => "oe",
+        'ß' => "ss",
+        'đ' | 'ð' => "d",
+        'ł' => "l",
+        'þ' => "th",
+        'ı' => "i",
+        'ħ' => "h",
+        _ => return None,
+    })
+}
+
+fn cyrillic_to_latin(c: char) -> Option<&'static str> {
+    Some(match c {
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' | 'ґ' | 'ѓ' => "g",
+        'д' => "d",
+        'е' | 'ё' | 'э' => "e",
+        'ж' => "zh",
+        'з' => "z",
+        'и' | 'і' => "i",
+        'й' | 'ы' => "y",
+        'к' | 'ќ' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' | 'ў' => "u",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ю' => "yu",
+        'я' => "ya",
+        'ї' => "yi",
+        'є' => "ye",
+        'ђ' => "dj",
+        'ј' => "j",
+        'љ' => "lj",
+        'њ' => "nj",
+        'ћ' => "c",
+        'џ' | 'ѕ' => "dz",
+        _ => return None,
+    })
+}
+
+fn greek_to_latin(c: char) -> Option<&'static str> {
+    Some(match c {
+        'α' => "a",
+        'β' => "v",
+        'γ' => "g",
+        'δ' => "d",
+        'ε' => "e",
+        'ζ' => "z",
+        'η' | 'ι' => "i",
+        'θ' => "th",
+        'κ' => "k",
+        'λ' => "l",
+        'μ' => "m",
+        'ν' => "n",
+        'ξ' => "x",
+        'ο' | 'ω' => "o",
+        'π' => "p",
+        'ρ' => "r",
+        'σ' | 'ς' => "s",
+        'τ' => "t",
+        'υ' => "y",
+        'φ' => "f",
+        'χ' => "ch",
+        'ψ' => "ps",
+        _ => return None,
+    })
+}
+
+/// Token filter applying a [`TextNormalization`] after lowercasing.
+#[derive(Clone)]
+pub struct NormalizeTokenFilter {
+    normalization: Arc<TextNormalization>,
+}
+
+impl NormalizeTokenFilter {
+    pub fn new(normalization: Arc<TextNormalization>) -> Self {
+        NormalizeTokenFilter { normalization }
+    }
+}
+
+impl TokenFilter for NormalizeTokenFilter {
+    type Tokenizer<T: Tokenizer> = NormalizeFilterWrapper<T>;
+
+    fn transform<T: Tokenizer>(self, tokenizer: T) -> Self::Tokenizer<T> {
+        NormalizeFilterWrapper {
+            inner: tokenizer,
+            normalization: self.normalization,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct NormalizeFilterWrapper<T> {
+    inner: T,
+    normalization: Arc<TextNormalization>,
+}
+
+impl<T: Tokenizer> Tokenizer for NormalizeFilterWrapper<T> {
+    type TokenStream<'a> = NormalizeTokenStream<T::TokenStream<'a>>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        NormalizeTokenStream {
+            inner: self.inner.token_stream(text),
+            normalization: Arc::clone(&self.normalization),
+        }
+    }
+}
+
+pub struct NormalizeTokenStream<T> {
+    inner: T,
+    normalization: Arc<TextNormalization>,
+}
+
+impl<T: TokenStream> TokenStream for NormalizeTokenStream<T> {
+    fn advance(&mut self) -> bool {
+        if !self.inner.advance() {
+            return false;
+        }
+        let token = self.inner.token_mut();
+        if !token.text.is_ascii() {
+            token.text = self.normalization.normalize(&token.text);
+        }
+        true
+    }
+
+    fn token(&self) -> &Token {
+        self.inner.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.inner.token_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalization(f: impl FnOnce(&mut IndexSettings)) -> TextNormalization {
+        let mut settings = IndexSettings::default();
+        f(&mut settings);
+        TextNormalization::from_settings(&settings).unwrap()
+    }
+
+    #[test]
+    fn defaults_leave_text_alone() {
+        assert!(TextNormalization::from_settings(&IndexSettings::default()).is_none());
+    }
+
+    #[test]
+    fn nfkc_folds_compatibility_forms() {
+        let n = normalization(|s| s.normalize_unicode = true);
+        assert_eq!(n.normalize("Ｆｕｌｌ ﬁle №5"), "full file no5");
+        assert_eq!(n.normalize("café"), "café");
+    }
+
+    #[test]
+    fn diacritics_are_removed_except_kept_letters() {
+        let n = normalization(|s| s.remove_diacritics = true);
+        assert_eq!(
+            n.normalize("Crème Brûlée Smørrebrød"),
+            "creme brulee smorrebrod"
+        );
+
+        let swedish = normalization(|s| {
+            s.remove_diacritics = true;
+            s.query_languages = vec!["sv".to_string()];
+        });
+        assert_eq!(swedish.normalize("Ålänning café"), "ålänning cafe");
+
+        let explicit = normalization(|s| {
+            s.remove_diacritics = true;
+            s.keep_diacritics_on_characters = "É".to_string();
+        });
+        assert_eq!(explicit.normalize("été à"), "été a");
+    }
+
+    #[test]
+    fn transliterates_cyrillic_and_greek() {
+        let n = normalization(|s| {
+            s.transliterate = vec!["cyrillic".to_string(), "greek".to_string()];
+        });
+        assert_eq!(n.normalize("Москва"), "moskva");
+        assert_eq!(n.normalize("Αθήνα"), "athina");
+    }
+
+    #[test]
+    fn camel_case_splitting_applies_to_queries_only() {
+        let n = normalization(|s| s.split_camel_case = true);
+        assert_eq!(n.normalize_query("MacBookPro"), "mac book pro");
+        assert_eq!(n.normalize("MacBookPro"), "macbookpro");
+    }
+
+    #[test]
+    fn offsets_point_back_into_the_original() {
+        let n = normalization(|s| s.remove_diacritics = true);
+        let text = "Ölbaum Straße";
+        let (normalized, offsets) = n.normalize_with_offsets(text);
+        assert_eq!(normalized, "olbaum strasse");
+        assert_eq!(offsets.len(), normalized.len() + 1);
+        let start = normalized.find("strasse").unwrap();
+        let end = start + "strasse".len();
+        assert_eq!(&text[offsets[start]..offsets[end]], "Straße");
+    }
+
+    #[test]
+    fn filter_normalizes_tokens() {
+        let n = Arc::new(normalization(|s| s.remove_diacritics = true));
+        let mut tokenizer =
+            NormalizeTokenFilter::new(n).transform(tantivy::tokenizer::SimpleTokenizer::default());
+        let mut stream = tokenizer.token_stream("Crème fraîche");
+        let mut tokens = Vec::new();
+        while stream.advance() {
+            tokens.push(stream.token().text.clone());
+        }
+        assert_eq!(tokens, vec!["creme", "fraiche"]);
+    }
+}
@@ -236,6 +236,14 @@ pub struct SearchResult {
     pub user_data: Vec<serde_json::Value>,
     /// IDs of query rules that fired.
     pub applied_rules: Vec<String>,
+    /// Banners/redirect contributed by query rules.
+    pub rendering_content: Option<crate::index::rules::RenderingContent>,
+    /// False when `total` was estimated after the count budget ran out.
+    pub exhaustive_nb_hits: bool,
+    /// Synonyms whose expanded query was run.
+    pub synonyms_triggered: Vec<String>,
+    /// Synonyms whose expanded query matched documents the others didn't.
+    pub synonyms_matched: Vec<String>,
 }
 
 /// A single facet value and its document count.
@@ -261,6 +269,25 @@ pub struct TaskInfo {
     pub rejected_documents: Vec<DocFailure>,
     pub rejected_count: usize,
     pub created_at: std::time::SystemTime,
+    /// Steps done so far, for tasks that report progress (compaction).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<TaskProgress>,
+    /// Outcome per index, for tasks that span several (bulk settings).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub index_results: Vec<TaskIndexResult>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TaskProgress {
+    pub completed: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TaskIndexResult {
+    pub index_name: String,
+    /// `None` when the index was updated.
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -290,6 +317,8 @@ impl TaskInfo {
             rejected_documents: Vec::new(),
             rejected_count: 0,
             created_at: std::time::SystemTime::now(),
+            progress: None,
+            index_results: Vec::new(),
         }
     }
 }
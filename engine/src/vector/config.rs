@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use super::VectorError;
 
 /// Source type for an embedder configuration.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum EmbedderSource {
     OpenAi,
@@ -30,6 +30,12 @@ pub struct EmbedderConfig {
     pub headers: Option<HashMap<String, String>>,
     pub document_template: Option<String>,
     pub document_template_max_bytes: Option<usize>,
+    /// Texts sent per embedding request.
+    pub max_batch_size: Option<usize>,
+    /// Requests per minute the provider allows; unlimited when unset.
+    pub requests_per_minute: Option<u32>,
+    /// Provider price per million tokens, for usage cost estimates.
+    pub cost_per_million_tokens: Option<f64>,
 }
 
 impl EmbedderConfig {
@@ -43,6 +49,16 @@ impl EmbedderConfig {
 
     /// Validate that required fields are present for the given source type.
     pub fn validate(&self) -> Result<(), VectorError> {
+        if self.max_batch_size == Some(0) {
+            return Err(VectorError::EmbeddingError(
+                "`maxBatchSize` must be at least 1".into(),
+            ));
+        }
+        if self.requests_per_minute == Some(0) {
+            return Err(VectorError::EmbeddingError(
+                "`requestsPerMinute` must be at least 1".into(),
+            ));
+        }
         match self.source {
             EmbedderSource::OpenAi => {
                 if self.api_key.is_none() {
@@ -857,6 +873,9 @@ mod tests {
             headers: None,
             document_template: None,
             document_template_max_bytes: None,
+            max_batch_size: None,
+            requests_per_minute: None,
+            cost_per_million_tokens: None,
         };
         assert!(config.validate().is_ok());
     }
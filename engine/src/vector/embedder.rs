@@ -223,6 +223,11 @@ impl RestEmbedder {
                 .text()
                 .await
                 .unwrap_or_else(|_| "failed to read response body".into());
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return Err(VectorError::RateLimited(format!(
+                    "embedder returned {status}: {body_text}"
+                )));
+            }
             return Err(VectorError::EmbeddingError(format!(
                 "embedder returned {status}: {body_text}"
             )));
@@ -446,6 +451,11 @@ impl OpenAiEmbedder {
                 .text()
                 .await
                 .unwrap_or_else(|_| "failed to read response body".into());
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return Err(VectorError::RateLimited(format!(
+                    "OpenAI API error ({status}): {body_text}"
+                )));
+            }
             // Try to parse OpenAI error format
             if let Ok(error_json) = serde_json::from_str::<serde_json::Value>(&body_text) {
                 if let Some(msg) = error_json
@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::Path;
 
 use serde::{Deserialize, Serialize};
@@ -59,13 +60,268 @@ impl IdMap {
     pub fn is_empty(&self) -> bool {
         self.doc_to_key.is_empty()
     }
+
+    pub fn doc_ids(&self) -> impl Iterator<Item = &str> {
+        self.doc_to_key.keys().map(|s| s.as_str())
+    }
+}
+
+/// On-disk format written by [`VectorIndex::save`].
+///
+/// - 1: `index.usearch` + `id_map.json` (no `format_version` field).
+/// - 2: adds `vectors.bin`, the raw vectors in a layout of our own, so the
+///   graph can be rebuilt after a usearch upgrade without re-embedding.
+pub const FORMAT_VERSION: u32 = 2;
+
+const RAW_VECTORS_FILE: &str = "vectors.bin";
+
+/// HNSW vector index wrapping usearch with string doc ID mapping.
+pub struct VectorIndex {
+    inner: Index,
+    id_map: IdMap,
+    dimensions: usize,
+    /// The files this index was loaded from are an older format, or their
+    /// graph couldn't be read; see [`VectorIndex::needs_upgrade`].
+    upgrade_pending: bool,
+    /// Doc IDs written since [`VectorIndex::start_rebuild`], replayed onto
+    /// the new graph by [`VectorIndex::finish_rebuild`].
+    rebuild_changes: Option<HashSet<String>>,
+}
+
+/// The vectors of an index at the start of an online rebuild.
+pub struct RebuildSource {
+    pub dimensions: usize,
+    pub vectors: Vec<(String, Vec<f32>)>,
+}
+
+impl VectorIndex {
+    pub fn new(dimensions: usize, metric: MetricKind) -> Result<Self, VectorError> {
+        let options = IndexOptions {
+            dimensions,
+            metric,
+            quantization: ScalarKind::F32,
+            connectivity: 0,
+            expansion_add: 0,
+            expansion_search: 0,
+            multi: false,
+        };
+        let inner = Index::new(&options).map_err(|e| VectorError::HnswError(e.to_string()))?;
+        Ok(Self {
+            inner,
+            id_map: IdMap::new(),
+            dimensions,
+            upgrade_pending: false,
+            rebuild_changes: None,
+        })
+    }
+
+    /// An index holding `vectors`, inserted in the given order.
+    pub fn from_vectors(
+        dimensions: usize,
+        metric: MetricKind,
+        vectors: Vec<(String, Vec<f32>)>,
+    ) -> Result<Self, VectorError> {
+        let mut index = Self::new(dimensions, metric)?;
+        index
+            .inner
+            .reserve(vectors.len())
+            .map_err(|e| VectorError::HnswError(e.to_string()))?;
+        for (doc_id, vector) in vectors {
+            index.add(&doc_id, &vector)?;
+        }
+        Ok(index)
+    }
+
+    pub fn add(&mut self, doc_id: &str, vector: &[f32]) -> Result<(), VectorError> {
+        if vector.len() != self.dimensions {
+            return Err(VectorError::DimensionMismatch {
+                expected: self.dimensions,
+                got: vector.len(),
+            });
+        }
+        if let Some(changes) = &mut self.rebuild_changes {
+            changes.insert(doc_id.to_owned());
+        }
+
+        if let Some(key) = self.id_map.get_key(doc_id) {
+            // Replace: remove old vector, re-add with same key
+            let _ = self
+                .inner
+                .remove(key)
+                .map_err(|e| VectorError::HnswError(e.to_string()))?;
+            self.inner
+                .add(key, vector)
+                .map_err(|e| VectorError::HnswError(e.to_string()))?;
+        } else {
+            let key = self.id_map.insert(doc_id);
+            self.inner
+                .reserve(self.id_map.len())
+                .map_err(|e| VectorError::HnswError(e.to_string()))?;
+            self.inner
+                .add(key, vector)
+                .map_err(|e| VectorError::HnswError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, doc_id: &str) -> Result<(), VectorError> {
+        let key = self
+            .id_map
+            .get_key(doc_id)
+            .ok_or_else(|| VectorError::DocumentNotFound {
+                doc_id: doc_id.to_owned(),
+            })?;
+        let _ = self
+            .inner
+            .remove(key)
+            .map_err(|e| VectorError::HnswError(e.to_string()))?;
+        self.id_map.remove_by_doc(doc_id);
+        if let Some(changes) = &mut self.rebuild_changes {
+            changes.insert(doc_id.to_owned());
+        }
+        Ok(())
+    }
+
+    pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<VectorSearchResult>, VectorError> {
+        if query.len() != self.dimensions {
+            return Err(VectorError::DimensionMismatch {
+                expected: self.dimensions,
+                got: query.len(),
+            });
+        }
+        if self.id_map.is_empty() {
+            return Ok(Vec::new());
+        }
+        let matches = self
+            .inner
+            .search(query, k)
+            .map_err(|e| VectorError::HnswError(e.to_string()))?;
+        let mut results = Vec::with_capacity(matches.keys.len());
+        for (key, distance) in matches.keys.iter().zip(matches.distances.iter()) {
+            if let Some(doc_id) = self.id_map.get_doc(*key) {
+                results.push(VectorSearchResult {
+                    doc_id: doc_id.to_owned(),
+                    distance: *distance,
+                });
+            }
+        }
+        Ok(results)
+    }
+
+    /// The stored vector for `doc_id`.
+    pub fn get(&self, doc_id: &str) -> Option<Vec<f32>> {
+        let key = self.id_map.get_key(doc_id)?;
+        let mut vector = vec![0f32; self.dimensions];
+        match self.inner.get(key, &mut vector) {
+            Ok(found) if found > 0 => Some(vector),
+            _ => None,
+        }
+    }
+
+    /// A fresh graph with the same vectors, inserted in doc ID order.
+    ///
+    /// Removals leave dead nodes in the HNSW graph, so a long-lived index
+    /// slowly loses recall; rebuilding drops them.
+    pub fn rebuilt(&self, metric: MetricKind) -> Result<Self, VectorError> {
+        Self::from_vectors(self.dimensions, metric, self.live_vectors())
+    }
+
+    /// Every stored vector, in doc ID order.
+    fn live_vectors(&self) -> Vec<(String, Vec<f32>)> {
+        let mut doc_ids: Vec<&str> = self.id_map.doc_ids().collect();
+        doc_ids.sort_unstable();
+        doc_ids
+            .into_iter()
+            .filter_map(|doc_id| Some((doc_id.to_owned(), self.get(doc_id)?)))
+            .collect()
+    }
+
+    /// Copy the vectors out for a rebuild that runs without holding this
+    /// index, and start recording the docs written in the meantime. `None`
+    /// if a rebuild is already running.
+    pub fn start_rebuild(&mut self) -> Option<RebuildSource> {
+        if self.rebuild_changes.is_some() {
+            return None;
+        }
+        self.rebuild_changes = Some(HashSet::new());
+        Some(RebuildSource {
+            dimensions: self.dimensions,
+            vectors: self.live_vectors(),
+        })
+    }
+
+    /// Replay the writes made since [`VectorIndex::start_rebuild`] onto
+    /// `rebuilt`, then replace this index with it. Fails if this index was
+    /// replaced in the meantime, since `rebuilt` no longer matches it.
+    pub fn finish_rebuild(&mut self, mut rebuilt: VectorIndex) -> Result<(), VectorError> {
+        let Some(changes) = self.rebuild_changes.take() else {
+            return Err(VectorError::HnswError(
+                "vector index was replaced during rebuild".to_string(),
+            ));
+        };
+        for doc_id in changes {
+            match self.get(&doc_id) {
+                Some(vector) => rebuilt.add(&doc_id, &vector)?,
+                None => match rebuilt.remove(&doc_id) {
+                    Ok(()) | Err(VectorError::DocumentNotFound { .. }) => {}
+                    Err(e) => return Err(e),
+                },
+            }
+        }
+        *self = rebuilt;
+        Ok(())
+    }
+
+    /// Stop recording writes for a rebuild that failed.
+    pub fn cancel_rebuild(&mut self) {
+        self.rebuild_changes = None;
+    }
+
+    /// Whether the files this index was loaded from should be rewritten in
+    /// the current format, by rebuilding the graph and saving it.
+    pub fn needs_upgrade(&self) -> bool {
+        self.upgrade_pending
+    }
+
+    pub fn len(&self) -> usize {
+        self.doc_to_key.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.doc_to_key.is_empty()
+    }
+
+    pub fn doc_ids(&self) -> impl Iterator<Item = &str> {
+        self.doc_to_key.keys().map(|s| s.as_str())
+    }
 }
 
+/// On-disk format written by [`VectorIndex::save`].
+///
+/// - 1: `index.usearch` + `id_map.json` (no `format_version` field).
+/// - 2: adds `vectors.bin`, the raw vectors in a layout of our own, so the
+///   graph can be rebuilt after a usearch upgrade without re-embedding.
+pub const FORMAT_VERSION: u32 = 2;
+
+const RAW_VECTORS_FILE: &str = "vectors.bin";
+
 /// HNSW vector index wrapping usearch with string doc ID mapping.
 pub struct VectorIndex {
     inner: Index,
     id_map: IdMap,
     dimensions: usize,
+    /// The files this index was loaded from are an older format, or their
+    /// graph couldn't be read; see [`VectorIndex::needs_upgrade`].
+    upgrade_pending: bool,
+    /// Doc IDs written since [`VectorIndex::start_rebuild`], replayed onto
+    /// the new graph by [`VectorIndex::finish_rebuild`].
+    rebuild_changes: Option<HashSet<String>>,
+}
+
+/// The vectors of an index at the start of an online rebuild.
+pub struct RebuildSource {
+    pub dimensions: usize,
+    pub vectors: Vec<(String, Vec<f32>)>,
 }
 
 impl VectorIndex {
@@ -84,9 +340,28 @@ impl VectorIndex {
             inner,
             id_map: IdMap::new(),
             dimensions,
+            upgrade_pending: false,
+            rebuild_changes: None,
         })
     }
 
+    /// An index holding `vectors`, inserted in the given order.
+    pub fn from_vectors(
+        dimensions: usize,
+        metric: MetricKind,
+        vectors: Vec<(String, Vec<f32>)>,
+    ) -> Result<Self, VectorError> {
+        let mut index = Self::new(dimensions, metric)?;
+        index
+            .inner
+            .reserve(vectors.len())
+            .map_err(|e| VectorError::HnswError(e.to_string()))?;
+        for (doc_id, vector) in vectors {
+            index.add(&doc_id, &vector)?;
+        }
+        Ok(index)
+    }
+
     pub fn add(&mut self, doc_id: &str, vector: &[f32]) -> Result<(), VectorError> {
         if vector.len() != self.dimensions {
             return Err(VectorError::DimensionMismatch {
@@ -94,6 +369,9 @@ impl VectorIndex {
                 got: vector.len(),
             });
         }
+        if let Some(changes) = &mut self.rebuild_changes {
+            changes.insert(doc_id.to_owned());
+        }
 
         if let Some(key) = self.id_map.get_key(doc_id) {
             // Replace: remove old vector, re-add with same key
@@ -128,6 +406,9 @@ impl VectorIndex {
             .remove(key)
             .map_err(|e| VectorError::HnswError(e.to_string()))?;
         self.id_map.remove_by_doc(doc_id);
+        if let Some(changes) = &mut self.rebuild_changes {
+            changes.insert(doc_id.to_owned());
+        }
         Ok(())
     }
 
@@ -157,6 +438,36 @@ impl VectorIndex {
         Ok(results)
     }
 
+    /// The stored vector for `doc_id`.
+    pub fn get(&self, doc_id: &str) -> Option<Vec<f32>> {
+        let key = self.id_map.get_key(doc_id)?;
+        let mut vector = vec![0f32; self.dimensions];
+        match self.inner.get(key, &mut vector) {
+            Ok(found) if found > 0 => Some(vector),
+            _ => None,
+        }
+    }
+
+    /// A fresh graph with the same vectors, inserted in doc ID order.
+    ///
+    /// Removals leave dead nodes in the HNSW graph, so a long-lived index
+    /// slowly loses recall; rebuilding drops them.
+    pub fn rebuilt(&self, metric: MetricKind) -> Result<Self, VectorError> {
+        let mut doc_ids: Vec<&str> = self.id_map.doc_ids().collect();
+        doc_ids.sort_unstable();
+        let mut rebuilt = Self::new(self.dimensions, metric)?;
+        rebuilt
+            .inner
+            .reserve(doc_ids.len())
+            .map_err(|e| VectorError::HnswError(e.to_string()))?;
+        for doc_id in doc_ids {
+            if let Some(vector) = self.get(doc_id) {
+                rebuilt.add(doc_id, &vector)?;
+            }
+        }
+        Ok(rebuilt)
+    }
+
     pub fn len(&self) -> usize {
         self.id_map.len()
     }
@@ -184,7 +495,10 @@ impl VectorIndex {
             .save(index_path_str)
             .map_err(|e| VectorError::HnswError(e.to_string()))?;
 
+        self.save_raw_vectors(&dir.join(RAW_VECTORS_FILE))?;
+
         let meta = PersistenceMeta {
+            format_version: FORMAT_VERSION,
             id_map: &self.id_map,
             dimensions: self.dimensions,
         };
@@ -195,11 +509,22 @@ impl VectorIndex {
         Ok(())
     }
 
+    /// Load an index saved in any format up to [`FORMAT_VERSION`].
+    ///
+    /// Older formats load as-is and report [`VectorIndex::needs_upgrade`].
+    /// If the graph can't be read (e.g. its layout changed in a usearch
+    /// upgrade), it is rebuilt from `vectors.bin` when that exists.
     pub fn load(dir: &Path, metric: MetricKind) -> Result<Self, VectorError> {
         let meta_path = dir.join("id_map.json");
         let meta_json = std::fs::read_to_string(&meta_path)?;
         let meta: OwnedPersistenceMeta = serde_json::from_str(&meta_json)
             .map_err(|e| VectorError::SerializationError(e.to_string()))?;
+        if meta.format_version > FORMAT_VERSION {
+            return Err(VectorError::UnsupportedFormat {
+                found: meta.format_version,
+                supported: FORMAT_VERSION,
+            });
+        }
 
         let options = IndexOptions {
             dimensions: meta.dimensions,
@@ -222,22 +547,79 @@ impl VectorIndex {
 
         // Only load HNSW data if the index file has content (empty index saves a 0-byte file or may not exist)
         if index_path.exists() && std::fs::metadata(&index_path)?.len() > 0 {
-            inner
-                .load(index_path_str)
-                .map_err(|e| VectorError::HnswError(e.to_string()))?;
+            if let Err(e) = inner.load(index_path_str) {
+                let raw_path = dir.join(RAW_VECTORS_FILE);
+                if !raw_path.exists() {
+                    return Err(VectorError::HnswError(e.to_string()));
+                }
+                tracing::warn!(
+                    "unreadable HNSW graph in {} ({}), rebuilding from raw vectors",
+                    dir.display(),
+                    e
+                );
+                let vectors = Self::load_raw_vectors(&raw_path, meta.dimensions)?;
+                let mut index = Self::from_vectors(meta.dimensions, metric, vectors)?;
+                index.upgrade_pending = true;
+                return Ok(index);
+            }
         }
 
         Ok(Self {
             inner,
             id_map: meta.id_map,
             dimensions: meta.dimensions,
+            upgrade_pending: meta.format_version < FORMAT_VERSION,
+            rebuild_changes: None,
         })
     }
+
+    /// `vectors.bin`: per vector, in doc ID order, a little-endian u32 doc
+    /// ID length, the doc ID bytes, then `dimensions` little-endian f32s.
+    fn save_raw_vectors(&self, path: &Path) -> Result<(), VectorError> {
+        let mut out = BufWriter::new(std::fs::File::create(path)?);
+        for (doc_id, vector) in self.live_vectors() {
+            out.write_all(&(doc_id.len() as u32).to_le_bytes())?;
+            out.write_all(doc_id.as_bytes())?;
+            for x in vector {
+                out.write_all(&x.to_le_bytes())?;
+            }
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    fn load_raw_vectors(
+        path: &Path,
+        dimensions: usize,
+    ) -> Result<Vec<(String, Vec<f32>)>, VectorError> {
+        let mut bytes = Vec::new();
+        BufReader::new(std::fs::File::open(path)?).read_to_end(&mut bytes)?;
+        let truncated = || VectorError::SerializationError(format!("truncated {}", path.display()));
+        let mut vectors = Vec::new();
+        let mut rest = bytes.as_slice();
+        while !rest.is_empty() {
+            let (len, tail) = rest.split_first_chunk::<4>().ok_or_else(truncated)?;
+            let len = u32::from_le_bytes(*len) as usize;
+            if tail.len() < len + dimensions * 4 {
+                return Err(truncated());
+            }
+            let doc_id = String::from_utf8(tail[..len].to_vec())
+                .map_err(|e| VectorError::SerializationError(e.to_string()))?;
+            let vector = tail[len..len + dimensions * 4]
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+            vectors.push((doc_id, vector));
+            rest = &tail[len + dimensions * 4..];
+        }
+        Ok(vectors)
+    }
 }
 
 /// Serialization helper for save — borrows IdMap.
 #[derive(Serialize)]
 struct PersistenceMeta<'a> {
+    format_version: u32,
     id_map: &'a IdMap,
     dimensions: usize,
 }
@@ -245,10 +627,17 @@ struct PersistenceMeta<'a> {
 /// Deserialization helper for load — owns IdMap.
 #[derive(Deserialize)]
 struct OwnedPersistenceMeta {
+    /// Missing in format 1.
+    #[serde(default = "legacy_format_version")]
+    format_version: u32,
     id_map: IdMap,
     dimensions: usize,
 }
 
+fn legacy_format_version() -> u32 {
+    1
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -411,6 +800,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_rebuilt_keeps_live_vectors_only() {
+        let mut idx = VectorIndex::new(3, cos_metric()).unwrap();
+        idx.add("doc1", &[1.0, 0.0, 0.0]).unwrap();
+        idx.add("doc2", &[0.0, 1.0, 0.0]).unwrap();
+        idx.add("doc3", &[0.0, 0.0, 1.0]).unwrap();
+        idx.remove("doc2").unwrap();
+
+        let rebuilt = idx.rebuilt(cos_metric()).unwrap();
+        assert_eq!(rebuilt.len(), 2);
+        assert_eq!(rebuilt.get("doc3"), Some(vec![0.0, 0.0, 1.0]));
+        assert_eq!(rebuilt.get("doc2"), None);
+        let results = rebuilt.search(&[1.0, 0.0, 0.0], 1).unwrap();
+        assert_eq!(results[0].doc_id, "doc1");
+    }
+
     // ── Persistence tests (2.16) ──
 
     #[test]
@@ -450,4 +855,93 @@ mod tests {
         assert!(loaded.is_empty());
         assert_eq!(loaded.dimensions(), 4);
     }
+
+    #[test]
+    fn test_load_legacy_format_needs_upgrade() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().join("legacy_idx");
+
+        let mut idx = VectorIndex::new(3, cos_metric()).unwrap();
+        idx.add("doc1", &[1.0, 0.0, 0.0]).unwrap();
+        idx.save(&dir).unwrap();
+        assert!(!VectorIndex::load(&dir, cos_metric())
+            .unwrap()
+            .needs_upgrade());
+
+        // Format 1 had no version field and no raw vectors.
+        let meta_path = dir.join("id_map.json");
+        let mut meta: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&meta_path).unwrap()).unwrap();
+        meta.as_object_mut().unwrap().remove("format_version");
+        std::fs::write(&meta_path, meta.to_string()).unwrap();
+        std::fs::remove_file(dir.join(RAW_VECTORS_FILE)).unwrap();
+
+        let loaded = VectorIndex::load(&dir, cos_metric()).unwrap();
+        assert!(loaded.needs_upgrade());
+        assert_eq!(
+            loaded.search(&[1.0, 0.0, 0.0], 1).unwrap()[0].doc_id,
+            "doc1"
+        );
+    }
+
+    #[test]
+    fn test_load_rejects_newer_format() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().join("future_idx");
+        VectorIndex::new(3, cos_metric())
+            .unwrap()
+            .save(&dir)
+            .unwrap();
+
+        let meta_path = dir.join("id_map.json");
+        let mut meta: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&meta_path).unwrap()).unwrap();
+        meta["format_version"] = serde_json::json!(FORMAT_VERSION + 1);
+        std::fs::write(&meta_path, meta.to_string()).unwrap();
+
+        assert!(matches!(
+            VectorIndex::load(&dir, cos_metric()),
+            Err(VectorError::UnsupportedFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unreadable_graph_is_rebuilt_from_raw_vectors() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().join("raw_idx");
+
+        let mut idx = VectorIndex::new(3, cos_metric()).unwrap();
+        idx.add("doc1", &[1.0, 0.0, 0.0]).unwrap();
+        idx.add("doc2", &[0.0, 1.0, 0.0]).unwrap();
+        idx.save(&dir).unwrap();
+        std::fs::write(dir.join("index.usearch"), b"not a usearch graph").unwrap();
+
+        let loaded = VectorIndex::load(&dir, cos_metric()).unwrap();
+        assert!(loaded.needs_upgrade());
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.get("doc2"), Some(vec![0.0, 1.0, 0.0]));
+    }
+
+    #[test]
+    fn test_online_rebuild_keeps_concurrent_writes() {
+        let mut idx = VectorIndex::new(3, cos_metric()).unwrap();
+        idx.add("doc1", &[1.0, 0.0, 0.0]).unwrap();
+        idx.add("doc2", &[0.0, 1.0, 0.0]).unwrap();
+
+        let source = idx.start_rebuild().unwrap();
+        assert!(idx.start_rebuild().is_none());
+        let rebuilt =
+            VectorIndex::from_vectors(source.dimensions, cos_metric(), source.vectors).unwrap();
+
+        // Written while the new graph was building.
+        idx.add("doc3", &[0.0, 1.0, 0.0]).unwrap();
+        idx.add("doc1", &[0.0, 0.0, 1.0]).unwrap();
+        idx.remove("doc2").unwrap();
+
+        idx.finish_rebuild(rebuilt).unwrap();
+        assert_eq!(idx.len(), 2);
+        assert_eq!(idx.get("doc1"), Some(vec![0.0, 0.0, 1.0]));
+        assert_eq!(idx.get("doc2"), None);
+        assert_eq!(idx.get("doc3"), Some(vec![0.0, 1.0, 0.0]));
+    }
 }
@@ -1,6 +1,7 @@
 pub mod config;
 pub mod embedder;
 pub mod index;
+pub mod scheduler;
 pub mod vectors_field;
 
 use serde::{Deserialize, Serialize};
@@ -29,6 +30,12 @@ pub enum VectorError {
 
     #[error("embedding error: {0}")]
     EmbeddingError(String),
+
+    #[error("embedder rate limited: {0}")]
+    RateLimited(String),
+
+    #[error("unsupported vector index format {found} (this build reads up to {supported})")]
+    UnsupportedFormat { found: u32, supported: u32 },
 }
 
 /// A single result from a vector similarity search.
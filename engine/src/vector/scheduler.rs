@@ -0,0 +1,407 @@
+//! Shared scheduler for calls to embedding providers.
+//!
+//! Every document and query embedding goes through [`EmbeddingScheduler`]:
+//!
+//! - Documents are sent in batches of the embedder's `maxBatchSize`.
+//! - Queries arriving for the same index and embedder within
+//!   [`QUERY_BATCH_WINDOW`] are sent together as one request.
+//! - Requests to a provider are spaced to its `requestsPerMinute`, and a
+//!   rate-limited (429) request is retried with exponential backoff, pushing
+//!   back every other request to that provider too.
+//! - Texts, requests and tokens are counted per index and embedder. Tokens
+//!   are estimated at 4 bytes each, since not every provider reports them;
+//!   the cost estimate uses the embedder's `costPerMillionTokens`.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+
+use super::config::EmbedderConfig;
+use super::embedder::Embedder;
+use super::VectorError;
+
+/// Texts per request when the embedder sets no `maxBatchSize`.
+pub const DEFAULT_BATCH_SIZE: usize = 100;
+/// How long a query waits for others to share its request.
+pub const QUERY_BATCH_WINDOW: Duration = Duration::from_millis(2);
+const MAX_RETRIES: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Embedding usage of one embedder of one index since startup.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddingUsage {
+    /// Requests sent to the provider, including retries.
+    pub requests: u64,
+    /// Texts embedded successfully.
+    pub texts: u64,
+    pub estimated_tokens: u64,
+    /// In the currency of `costPerMillionTokens`; 0 when it isn't set.
+    pub estimated_cost: f64,
+    /// Requests the provider rejected with a rate limit.
+    pub rate_limited: u64,
+    /// Batches that failed for good.
+    pub failures: u64,
+}
+
+/// Spaces requests to one provider.
+#[derive(Default)]
+struct ProviderLimiter {
+    next_slot: Mutex<Option<Instant>>,
+}
+
+impl ProviderLimiter {
+    /// Wait for this provider's next request slot.
+    async fn acquire(&self, requests_per_minute: Option<u32>) {
+        let interval = requests_per_minute
+            .map(|rpm| Duration::from_secs(60) / rpm.max(1))
+            .unwrap_or_default();
+        let slot = {
+            let mut next = self.next_slot.lock().unwrap();
+            let now = Instant::now();
+            let slot = next.map_or(now, |n| n.max(now));
+            *next = Some(slot + interval);
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+
+    /// Hold every request to this provider for `delay`.
+    fn back_off(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut next = self.next_slot.lock().unwrap();
+        *next = Some(next.map_or(until, |n| n.max(until)));
+    }
+}
+
+type QueryReply = oneshot::Sender<Result<Vec<f32>, String>>;
+
+struct PendingQueries {
+    embedder: Arc<Embedder>,
+    config: EmbedderConfig,
+    queries: Vec<(String, QueryReply)>,
+}
+
+pub struct EmbeddingScheduler {
+    limiters: Mutex<HashMap<u64, Arc<ProviderLimiter>>>,
+    pending: Mutex<HashMap<(String, String), PendingQueries>>,
+    usage: Mutex<BTreeMap<(String, String), EmbeddingUsage>>,
+}
+
+impl EmbeddingScheduler {
+    pub fn new() -> Self {
+        Self {
+            limiters: Mutex::new(HashMap::new()),
+            pending: Mutex::new(HashMap::new()),
+            usage: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn global() -> &'static EmbeddingScheduler {
+        static SCHEDULER: OnceLock<EmbeddingScheduler> = OnceLock::new();
+        SCHEDULER.get_or_init(EmbeddingScheduler::new)
+    }
+
+    /// Embed `texts` for `index` in batches of the embedder's
+    /// `maxBatchSize`. Fails on the first batch that can't be embedded.
+    pub async fn embed_documents(
+        &self,
+        index: &str,
+        embedder_name: &str,
+        embedder: &Embedder,
+        config: &EmbedderConfig,
+        texts: &[&str],
+    ) -> Result<Vec<Vec<f32>>, VectorError> {
+        let batch_size = config.max_batch_size.unwrap_or(DEFAULT_BATCH_SIZE).max(1);
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(batch_size) {
+            vectors.extend(
+                self.send_batch(index, embedder_name, embedder, config, batch)
+                    .await?,
+            );
+        }
+        Ok(vectors)
+    }
+
+    /// Embed one query, sharing a request with the other queries for the
+    /// same index and embedder that arrive within [`QUERY_BATCH_WINDOW`].
+    pub async fn embed_query(
+        &'static self,
+        index: &str,
+        embedder_name: &str,
+        embedder: Arc<Embedder>,
+        config: &EmbedderConfig,
+        query: &str,
+    ) -> Result<Vec<f32>, VectorError> {
+        let key = (index.to_string(), embedder_name.to_string());
+        let (tx, rx) = oneshot::channel();
+        let first = {
+            let mut pending = self.pending.lock().unwrap();
+            let entry = pending
+                .entry(key.clone())
+                .or_insert_with(|| PendingQueries {
+                    embedder,
+                    config: config.clone(),
+                    queries: Vec::new(),
+                });
+            entry.queries.push((query.to_string(), tx));
+            entry.queries.len() == 1
+        };
+        if first {
+            tokio::spawn(async move {
+                tokio::time::sleep(QUERY_BATCH_WINDOW).await;
+                let Some(batch) = self.pending.lock().unwrap().remove(&key) else {
+                    return;
+                };
+                let texts: Vec<&str> = batch.queries.iter().map(|(q, _)| q.as_str()).collect();
+                let result = self
+                    .embed_documents(&key.0, &key.1, &batch.embedder, &batch.config, &texts)
+                    .await;
+                match result {
+                    Ok(vectors) => {
+                        for ((_, reply), vector) in batch.queries.into_iter().zip(vectors) {
+                            let _ = reply.send(Ok(vector));
+                        }
+                    }
+                    Err(e) => {
+                        let message = e.to_string();
+                        for (_, reply) in batch.queries {
+                            let _ = reply.send(Err(message.clone()));
+                        }
+                    }
+                }
+            });
+        }
+        rx.await
+            .map_err(|_| VectorError::EmbeddingError("query embedding was dropped".into()))?
+            .map_err(VectorError::EmbeddingError)
+    }
+
+    /// One request, retried with backoff while the provider rate-limits it.
+    async fn send_batch(
+        &self,
+        index: &str,
+        embedder_name: &str,
+        embedder: &Embedder,
+        config: &EmbedderConfig,
+        texts: &[&str],
+    ) -> Result<Vec<Vec<f32>>, VectorError> {
+        let limiter = self.limiter(config);
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            limiter.acquire(config.requests_per_minute).await;
+            let result = embedder.embed_documents(texts).await;
+            let retry = matches!(result, Err(VectorError::RateLimited(_))) && attempt < MAX_RETRIES;
+            self.record(index, embedder_name, config, texts, &result, retry);
+            if !retry {
+                return result;
+            }
+            tracing::warn!(
+                "[EMBED {}] '{}' rate limited, retrying in {:?}",
+                index,
+                embedder_name,
+                backoff
+            );
+            limiter.back_off(backoff);
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+            attempt += 1;
+        }
+    }
+
+    /// Requests share a limiter when they go to the same provider endpoint
+    /// with the same credentials.
+    fn limiter(&self, config: &EmbedderConfig) -> Arc<ProviderLimiter> {
+        let mut hasher = DefaultHasher::new();
+        config.source.hash(&mut hasher);
+        config.url.hash(&mut hasher);
+        config.api_key.hash(&mut hasher);
+        let key = hasher.finish();
+        Arc::clone(self.limiters.lock().unwrap().entry(key).or_default())
+    }
+
+    fn record(
+        &self,
+        index: &str,
+        embedder_name: &str,
+        config: &EmbedderConfig,
+        texts: &[&str],
+        result: &Result<Vec<Vec<f32>>, VectorError>,
+        will_retry: bool,
+    ) {
+        let mut usage = self.usage.lock().unwrap();
+        let entry = usage
+            .entry((index.to_string(), embedder_name.to_string()))
+            .or_default();
+        entry.requests += 1;
+        match result {
+            Ok(_) => {
+                let tokens = texts.iter().map(|t| estimate_tokens(t)).sum::<u64>();
+                entry.texts += texts.len() as u64;
+                entry.estimated_tokens += tokens;
+                if let Some(price) = config.cost_per_million_tokens {
+                    entry.estimated_cost += tokens as f64 * price / 1_000_000.0;
+                }
+            }
+            Err(e) => {
+                if matches!(e, VectorError::RateLimited(_)) {
+                    entry.rate_limited += 1;
+                }
+                if !will_retry {
+                    entry.failures += 1;
+                }
+            }
+        }
+    }
+
+    /// Usage of each embedder of `index`.
+    pub fn usage(&self, index: &str) -> BTreeMap<String, EmbeddingUsage> {
+        self.usage
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((i, _), _)| i == index)
+            .map(|((_, name), u)| (name.clone(), u.clone()))
+            .collect()
+    }
+
+    /// Usage of every index and embedder, keyed by `(index, embedder)`.
+    pub fn all_usage(&self) -> BTreeMap<(String, String), EmbeddingUsage> {
+        self.usage.lock().unwrap().clone()
+    }
+}
+
+impl Default for EmbeddingScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn estimate_tokens(text: &str) -> u64 {
+    (text.len() as u64).div_ceil(4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::config::EmbedderSource;
+    use crate::vector::embedder::create_embedder;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn openai_config(server: &MockServer) -> EmbedderConfig {
+        EmbedderConfig {
+            source: EmbedderSource::OpenAi,
+            api_key: Some("sk-test".into()),
+            url: Some(server.uri()),
+            max_batch_size: Some(2),
+            cost_per_million_tokens: Some(1000.0),
+            ..Default::default()
+        }
+    }
+
+    fn embeddings(n: usize) -> serde_json::Value {
+        let data: Vec<_> = (0..n)
+            .map(|i| serde_json::json!({ "index": i, "embedding": [i as f64, 1.0] }))
+            .collect();
+        serde_json::json!({ "data": data })
+    }
+
+    #[tokio::test]
+    async fn documents_are_batched_and_counted() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(embeddings(2)))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let config = openai_config(&server);
+        let embedder = create_embedder(&config).unwrap();
+        let scheduler = EmbeddingScheduler::new();
+
+        let vectors = scheduler
+            .embed_documents(
+                "idx",
+                "default",
+                &embedder,
+                &config,
+                &["abcd", "efgh", "ij", "kl"],
+            )
+            .await
+            .unwrap();
+        assert_eq!(vectors.len(), 4);
+
+        let usage = &scheduler.usage("idx")["default"];
+        assert_eq!(usage.requests, 2);
+        assert_eq!(usage.texts, 4);
+        assert_eq!(usage.estimated_tokens, 4);
+        assert!((usage.estimated_cost - 0.004).abs() < 1e-9);
+        assert!(scheduler.usage("other").is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limited_requests_are_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).set_body_string("slow down"))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(embeddings(1)))
+            .mount(&server)
+            .await;
+        let config = openai_config(&server);
+        let embedder = create_embedder(&config).unwrap();
+        let scheduler = EmbeddingScheduler::new();
+
+        let vectors = scheduler
+            .embed_documents("idx", "default", &embedder, &config, &["hello"])
+            .await
+            .unwrap();
+        assert_eq!(vectors.len(), 1);
+        let usage = &scheduler.usage("idx")["default"];
+        assert_eq!(usage.requests, 3);
+        assert_eq!(usage.rate_limited, 2);
+        assert_eq!(usage.failures, 0);
+    }
+
+    #[tokio::test]
+    async fn requests_are_spaced_to_the_rate_limit() {
+        let limiter = ProviderLimiter::default();
+        let start = Instant::now();
+        limiter.acquire(Some(1200)).await;
+        limiter.acquire(Some(1200)).await;
+        limiter.acquire(Some(1200)).await;
+        // 1200/min is one request per 50ms.
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn concurrent_queries_share_a_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(embeddings(2)))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let config = openai_config(&server);
+        let embedder = Arc::new(create_embedder(&config).unwrap());
+        let scheduler: &'static EmbeddingScheduler = Box::leak(Box::default());
+
+        let (a, b) = tokio::join!(
+            scheduler.embed_query("idx", "default", Arc::clone(&embedder), &config, "red"),
+            scheduler.embed_query("idx", "default", Arc::clone(&embedder), &config, "blue"),
+        );
+        assert_eq!(a.unwrap(), vec![0.0, 1.0]);
+        assert_eq!(b.unwrap(), vec![1.0, 1.0]);
+        assert_eq!(scheduler.usage("idx")["default"].requests, 1);
+    }
+}
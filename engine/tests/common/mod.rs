@@ -55,10 +55,15 @@ pub async fn spawn_server_with_key(admin_key: Option<&str>) -> (String, TempDir)
                     .put(flapjack_http::handlers::update_key)
                     .delete(flapjack_http::handlers::delete_key),
             )
+            .route("/1/keys/audit", get(flapjack_http::handlers::key_audit_log))
             .route(
                 "/1/keys/:key/restore",
                 post(flapjack_http::handlers::restore_key),
             )
+            .route(
+                "/1/keys/:key/rotate",
+                post(flapjack_http::handlers::rotate_key),
+            )
             .with_state(ks.clone())
     } else {
         Router::new()
@@ -71,6 +76,10 @@ pub async fn spawn_server_with_key(admin_key: Option<&str>) -> (String, TempDir)
     let protected = Router::new()
         .route("/1/indexes", post(flapjack_http::handlers::create_index))
         .route("/1/indexes", get(flapjack_http::handlers::list_indices))
+        .route(
+            "/1/indexes:import",
+            post(flapjack_http::handlers::archive::import_archive),
+        )
         .route(
             "/1/indexes/:indexName/batch",
             post(flapjack_http::handlers::add_documents),
@@ -164,7 +173,15 @@ pub async fn spawn_server_with_key(admin_key: Option<&str>) -> (String, TempDir)
             "/1/indexes/:indexName/operation",
             post(flapjack_http::handlers::operation_index),
         )
+        .route(
+            "/1/indexes/:indexName/clone",
+            post(flapjack_http::handlers::clone_index),
+        )
         .route("/1/tasks/:task_id", get(flapjack_http::handlers::get_task))
+        .route(
+            "/1/configuration",
+            get(flapjack_http::handlers::configuration::get_configuration),
+        )
         .route(
             "/1/indexes/:indexName/task/:task_id",
             get(flapjack_http::handlers::get_task_for_index),
@@ -235,7 +252,10 @@ pub async fn spawn_server_with_key(admin_key: Option<&str>) -> (String, TempDir)
         .merge(health_route)
         .merge(key_routes)
         .merge(protected)
-        .layer(auth_middleware);
+        .layer(auth_middleware)
+        .layer(middleware::from_fn(
+            flapjack_http::body_limits::enforce_body_limits,
+        ));
 
     let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
     let addr = listener.local_addr().unwrap().to_string();
@@ -275,10 +295,11 @@ pub async fn spawn_server_with_qs_analytics(source_index_name: &str) -> (String,
         flush_interval_secs: 3600,
         flush_size: 100_000,
         retention_days: 90,
+        pii: Default::default(),
     };
 
     // Seed 30 days of analytics directly to disk (no HTTP roundtrip needed)
-    flapjack::analytics::seed::seed_analytics(&analytics_config, source_index_name, 30)
+    flapjack::fixtures::analytics::seed_analytics(&analytics_config, source_index_name, 30)
         .expect("Failed to seed analytics data");
 
     let analytics_engine = Arc::new(flapjack::analytics::AnalyticsQueryEngine::new(
@@ -230,6 +230,87 @@ async fn test_search_key_acl_enforced() {
     assert_eq!(resp.status(), 403, "search key should block key management");
 }
 
+#[tokio::test]
+async fn test_auth_failures_are_counted_by_key() {
+    use flapjack_http::key_monitor::{key_label, AuthFailure, KeyMonitor};
+
+    let (addr, _temp, search_key) = setup().await;
+    let client = reqwest::Client::new();
+    let monitor = KeyMonitor::global();
+    let count_for = |label: &str| {
+        monitor
+            .failures_by_key()
+            .into_iter()
+            .find(|(k, _)| k == label)
+            .map_or(0, |(_, n)| n)
+    };
+    let denied_before = monitor.failures_by_reason(AuthFailure::AclDenied);
+    let key_before = count_for(&key_label(&search_key));
+
+    let resp = authed(
+        &client,
+        "GET",
+        &format!("http://{}/1/keys", addr),
+        &search_key,
+    )
+    .header("x-forwarded-for", "203.0.113.9")
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    assert!(monitor.failures_by_reason(AuthFailure::AclDenied) > denied_before);
+    assert!(count_for(&key_label(&search_key)) > key_before);
+    assert!(monitor
+        .failures_by_ip()
+        .iter()
+        .any(|(ip, n)| ip == "203.0.113.9" && *n >= 1));
+}
+
+#[tokio::test]
+async fn test_key_allowed_origins() {
+    let (addr, _temp, _) = setup().await;
+    let client = reqwest::Client::new();
+    create_index(&client, &addr, "products", ADMIN_KEY).await;
+
+    let resp = authed(
+        &client,
+        "POST",
+        &format!("http://{}/1/keys", addr),
+        ADMIN_KEY,
+    )
+    .json(&json!({"acl": ["search"], "allowedOrigins": ["https://*.example.com"]}))
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), 201);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let key = body["key"].as_str().unwrap().to_string();
+
+    let search = |origin: Option<&'static str>| {
+        let mut req = authed(
+            &client,
+            "POST",
+            &format!("http://{}/1/indexes/products/query", addr),
+            &key,
+        )
+        .json(&json!({"query": ""}));
+        if let Some(origin) = origin {
+            req = req.header("origin", origin);
+        }
+        req.send()
+    };
+    let resp = search(Some("https://shop.example.com")).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = search(Some("https://evil.com")).await.unwrap();
+    assert_eq!(resp.status(), 403);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "forbidden");
+    // Server-side callers send no Origin header.
+    let resp = search(None).await.unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
 #[tokio::test]
 async fn test_create_and_use_scoped_key() {
     let (addr, _temp, _) = setup().await;
@@ -480,6 +561,99 @@ async fn test_key_ttl_expiry() {
     assert_eq!(resp.status(), 403, "expired key should be rejected");
 }
 
+#[tokio::test]
+async fn test_key_rotation_grace_window() {
+    let (addr, _temp, old_key) = setup().await;
+    let client = reqwest::Client::new();
+
+    create_index(&client, &addr, "test", ADMIN_KEY).await;
+
+    let resp = authed(
+        &client,
+        "POST",
+        &format!("http://{}/1/keys/{}/rotate", addr, old_key),
+        ADMIN_KEY,
+    )
+    .json(&json!({"gracePeriod": 1}))
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), 201);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let new_key = body["key"].as_str().unwrap().to_string();
+    assert_ne!(new_key, old_key);
+    assert!(body["previousKeyExpiresAt"].is_string());
+
+    let search = |key: String| {
+        authed(
+            &client,
+            "POST",
+            &format!("http://{}/1/indexes/test/query", addr),
+            &key,
+        )
+        .json(&json!({"query": "test"}))
+        .send()
+    };
+    assert_eq!(search(old_key.clone()).await.unwrap().status(), 200);
+    assert_eq!(search(new_key.clone()).await.unwrap().status(), 200);
+
+    let list: serde_json::Value = authed(
+        &client,
+        "GET",
+        &format!("http://{}/1/keys", addr),
+        ADMIN_KEY,
+    )
+    .send()
+    .await
+    .unwrap()
+    .json()
+    .await
+    .unwrap();
+    let pending = list["pendingExpiry"].as_array().unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0]["description"], "Test Search Key");
+
+    let audit: serde_json::Value = authed(
+        &client,
+        "GET",
+        &format!("http://{}/1/keys/audit", addr),
+        ADMIN_KEY,
+    )
+    .send()
+    .await
+    .unwrap()
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(audit["events"][0]["event"], "rotate");
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    assert_eq!(
+        search(old_key).await.unwrap().status(),
+        403,
+        "old key should stop working after the grace window"
+    );
+    assert_eq!(search(new_key).await.unwrap().status(), 200);
+}
+
+#[tokio::test]
+async fn test_cannot_rotate_admin_key() {
+    let (addr, _temp, _) = setup().await;
+    let client = reqwest::Client::new();
+
+    let resp = authed(
+        &client,
+        "POST",
+        &format!("http://{}/1/keys/{}/rotate", addr, ADMIN_KEY),
+        ADMIN_KEY,
+    )
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), 403);
+}
+
 #[tokio::test]
 async fn test_key_crud_lifecycle() {
     let (addr, _temp, _) = setup().await;
@@ -149,6 +149,24 @@ async fn test_response_fields_star_keeps_all() {
     assert!(fields.contains(&"*".to_string()));
 }
 
+#[tokio::test]
+async fn test_wants_response_field() {
+    use flapjack_http::dto::SearchRequest;
+
+    let req = SearchRequest::default();
+    assert!(req.wants_response_field("processingTimingsMS"));
+
+    let req = SearchRequest {
+        response_fields: Some(vec!["hits".to_string(), "nbHits".to_string()]),
+        ..Default::default()
+    };
+    assert!(req.wants_response_field("hits"));
+    assert!(!req.wants_response_field("facets"));
+    assert!(!req.wants_response_field("exhaustiveNbHits"));
+    // Warnings survive field selection.
+    assert!(req.wants_response_field("message"));
+}
+
 #[tokio::test]
 async fn test_params_string_response_fields() {
     use flapjack_http::dto::SearchRequest;
@@ -379,4 +379,37 @@ mod correctness {
         assert!(ids.contains(&"3"));
         assert!(ids.contains(&"4"));
     }
+
+    #[tokio::test]
+    async fn test_nested_array_and_date_math_filters() {
+        let tmp = TempDir::new().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let docs = vec![
+            serde_json::json!({
+                "objectID": "fresh",
+                "variants": [{"price": 30}, {"price": 80}],
+                "published_at": now - 2 * 86_400,
+            }),
+            serde_json::json!({
+                "objectID": "stale",
+                "variants": [{"price": 90}],
+                "published_at": now - 30 * 86_400,
+            }),
+        ]
+        .iter()
+        .map(|v| Document::from_json(v).unwrap())
+        .collect();
+        let manager = setup(&tmp, "test", vec![], docs).await;
+
+        for expr in [
+            "variants.price < 50",
+            "published_at > now-7d",
+            "published_at:now-7d TO now",
+        ] {
+            let filter = parse_filter(expr).unwrap();
+            let result = manager.search("test", "", Some(&filter), None, 10).unwrap();
+            assert_eq!(result.total, 1, "{} should match one doc", expr);
+            assert_eq!(result.documents[0].document.id, "fresh", "{}", expr);
+        }
+    }
 }
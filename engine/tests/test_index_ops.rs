@@ -316,6 +316,40 @@ mod operation_index {
         assert_eq!(results.documents.len(), 0);
     }
 
+    #[tokio::test]
+    async fn clone_sampled_copies_config_and_stable_subset() {
+        let tmp = TempDir::new().unwrap();
+        let mgr = IndexManager::new(tmp.path());
+        mgr.create_tenant("src").unwrap();
+        let ids: Vec<String> = (0..200).map(|i| i.to_string()).collect();
+        let ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+        mgr.add_documents_sync("src", make_docs(&ids))
+            .await
+            .unwrap();
+
+        let (_, all) = mgr.clone_index_sampled("src", "full", 100.0).await.unwrap();
+        assert_eq!(all, 200);
+
+        let (_, none) = mgr.clone_index_sampled("src", "empty", 0.0).await.unwrap();
+        assert_eq!(none, 0);
+        assert!(tmp.path().join("empty").join("settings.json").exists());
+        let results = mgr.search("empty", "Item", None, None, 10).unwrap();
+        assert_eq!(results.documents.len(), 0);
+
+        let (_, first) = mgr.clone_index_sampled("src", "a", 25.0).await.unwrap();
+        let (_, second) = mgr.clone_index_sampled("src", "b", 25.0).await.unwrap();
+        assert_eq!(first, second, "sampling must be deterministic");
+        assert!(first > 20 && first < 80, "sampled {}", first);
+    }
+
+    #[tokio::test]
+    async fn clone_sampled_missing_source_errors() {
+        let tmp = TempDir::new().unwrap();
+        let mgr = IndexManager::new(tmp.path());
+        assert!(mgr.clone_index_sampled("ghost", "dst", 50.0).await.is_err());
+        assert!(!tmp.path().join("dst").exists());
+    }
+
     #[tokio::test]
     async fn move_then_search_destination() {
         let tmp = TempDir::new().unwrap();
@@ -393,6 +427,56 @@ mod snapshot {
     }
 }
 
+// ============================================================
+// PORTABLE ARCHIVES
+// ============================================================
+
+mod archive {
+    use super::*;
+    use flapjack::index::archive::export_index;
+
+    #[tokio::test]
+    async fn import_endpoint_loads_an_exported_archive() {
+        let src_dir = TempDir::new().unwrap();
+        let src_mgr = IndexManager::new(src_dir.path());
+        src_mgr.create_tenant("products").unwrap();
+        src_mgr
+            .add_documents_sync("products", make_docs(&["1", "2", "3"]))
+            .await
+            .unwrap();
+        let mut archive = Vec::new();
+        export_index(&src_mgr, "products", &mut archive, false).unwrap();
+
+        let (addr, _temp) = common::spawn_server().await;
+        let client = reqwest::Client::new();
+        let resp = client
+            .post(format!(
+                "http://{}/1/indexes:import?indexName=restored",
+                addr
+            ))
+            .header("x-algolia-api-key", "test")
+            .header("x-algolia-application-id", "test")
+            .body(archive)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["indexName"], "restored");
+        assert_eq!(body["documents"], 3);
+
+        let resp = client
+            .post(format!("http://{}/1/indexes:import", addr))
+            .header("x-algolia-api-key", "test")
+            .header("x-algolia-application-id", "test")
+            .body("not an archive")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 400);
+    }
+}
+
 // ============================================================
 // OPLOG INTEGRATION
 // ============================================================
@@ -460,6 +460,84 @@ async fn logs_returned_after_build() {
     assert!(first["contextLevel"].is_number());
 }
 
+#[tokio::test]
+async fn logs_record_phase_timings_and_resume_from_seq() {
+    let (addr, _tmp) = spawn_server_with_qs_analytics("products").await;
+    let base = format!("http://{}", addr);
+
+    post_config(&base, basic_config("phases_test", "products")).await;
+    wait_for_build(&base, "phases_test").await;
+
+    let status = get_status(&base, "phases_test").await;
+    let timings = &status["lastBuildTimings"];
+    for field in ["analyticsScanMs", "dedupeMs", "indexWriteMs", "totalMs"] {
+        assert!(timings[field].is_u64(), "missing {} in {}", field, timings);
+    }
+    assert!(
+        status.get("phase").is_none(),
+        "no phase once the build is over"
+    );
+
+    let logs: Value = auth(client().get(format!("{}/1/logs/phases_test", base)))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let entries = logs.as_array().unwrap();
+    let phases: Vec<&str> = entries
+        .iter()
+        .filter(|e| e["durationMs"].is_u64())
+        .filter_map(|e| e["phase"].as_str())
+        .collect();
+    assert_eq!(phases, vec!["analyticsScan", "dedupe", "indexWrite"]);
+
+    // Resuming after the second-to-last entry returns only the last one,
+    // straight away since no build is running.
+    let since = entries[entries.len() - 2]["seq"].as_u64().unwrap();
+    let newer: Value = auth(client().get(format!(
+        "{}/1/logs/phases_test?since={}&wait=30",
+        base, since
+    )))
+    .send()
+    .await
+    .unwrap()
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(newer.as_array().unwrap().len(), 1);
+    assert_eq!(newer[0]["seq"], entries[entries.len() - 1]["seq"]);
+}
+
+#[tokio::test]
+async fn logs_stream_as_server_sent_events_until_build_is_done() {
+    let (addr, _tmp) = spawn_server_with_qs_analytics("products").await;
+    let base = format!("http://{}", addr);
+
+    post_config(&base, basic_config("stream_test", "products")).await;
+
+    let resp = auth(client().get(format!("{}/1/logs/stream_test", base)))
+        .header("accept", "text/event-stream")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/event-stream"));
+
+    let body = tokio::time::timeout(tokio::time::Duration::from_secs(10), resp.text())
+        .await
+        .expect("stream should end when the build is done")
+        .unwrap();
+    assert!(body.contains("event: log"), "{}", body);
+    assert!(body.contains("\"phase\":\"indexWrite\""), "{}", body);
+    let done = body.find("event: done").expect("stream ends with done");
+    assert!(body[done..].contains("\"lastBuildTimings\""), "{}", body);
+}
+
 // ── trigger build endpoint ────────────────────────────────────────────────────
 
 #[tokio::test]
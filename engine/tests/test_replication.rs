@@ -1265,10 +1265,11 @@ async fn test_run_rollup_broadcast_sends_to_peer() {
         flush_interval_secs: 3600,
         flush_size: 100_000,
         retention_days: 90,
+        pii: Default::default(),
     };
 
     // Seed analytics data so discover_indexes() finds "products"
-    flapjack::analytics::seed::seed_analytics(&analytics_config, "products", 1)
+    flapjack::fixtures::analytics::seed_analytics(&analytics_config, "products", 1)
         .expect("seed_analytics must succeed");
 
     let engine = Arc::new(flapjack::analytics::AnalyticsQueryEngine::new(
@@ -1344,8 +1345,9 @@ async fn test_rollup_broadcaster_integration_periodic() {
         flush_interval_secs: 3600,
         flush_size: 100_000,
         retention_days: 90,
+        pii: Default::default(),
     };
-    flapjack::analytics::seed::seed_analytics(&analytics_config, "widgets", 1)
+    flapjack::fixtures::analytics::seed_analytics(&analytics_config, "widgets", 1)
         .expect("seed_analytics must succeed");
 
     let engine = Arc::new(flapjack::analytics::AnalyticsQueryEngine::new(
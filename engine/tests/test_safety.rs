@@ -514,6 +514,36 @@ mod memory_safety {
         std::env::remove_var("FLAPJACK_MAX_BATCH_SIZE");
     }
 
+    #[tokio::test]
+    async fn test_search_body_over_limit_returns_structured_413() {
+        let (addr, _tmp) = common::spawn_server().await;
+        let client = reqwest::Client::new();
+
+        let config: serde_json::Value = client
+            .get(format!("http://{}/1/configuration", addr))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let search_limit = config["maxBodySize"]["search"].as_u64().unwrap() as usize;
+        assert!(config["maxBodySize"]["ingest"].as_u64().unwrap() as usize >= search_limit);
+
+        let oversized = format!(r#"{{"query":"{}"}}"#, "x".repeat(search_limit + 1));
+        let resp = client
+            .post(format!("http://{}/1/indexes/test_idx/query", addr))
+            .header("content-type", "application/json")
+            .body(oversized)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 413);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["error"], "payload_too_large");
+        assert!(body["message"].as_str().unwrap().contains("search"));
+    }
+
     #[tokio::test]
     async fn test_health_returns_json_with_memory_fields() {
         let (addr, _tmp) = common::spawn_server().await;
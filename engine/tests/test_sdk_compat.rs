@@ -762,6 +762,51 @@ async fn test_multi_index_get_objects() {
     assert_eq!(results[1]["objectID"], "a1");
 }
 
+#[tokio::test]
+async fn test_get_objects_projection_and_missing_ids() {
+    let (addr, _dir) = spawn_server().await;
+    let client = algolia_client();
+    let base = format!("http://{}", addr);
+
+    seed_index(
+        &base,
+        "products",
+        vec![json!({"objectID": "p1", "name": "Laptop", "price": 999})],
+    )
+    .await;
+
+    let res = h(client.post(format!("{}/1/indexes/*/objects", base)))
+        .json(&json!({
+            "requests": [
+                {"indexName": "products", "objectID": "p1", "attributesToRetrieve": ["name"]},
+                {"indexName": "products", "objectID": "nope"},
+                {"indexName": "no_such_index", "objectID": "p1"},
+                {"indexName": "products", "objectID": "p1"}
+            ]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200, "missing objects must not fail the call");
+    let body: serde_json::Value = res.json().await.unwrap();
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results[0], json!({"objectID": "p1", "name": "Laptop"}));
+    assert!(results[1].is_null());
+    assert!(results[2].is_null());
+    assert_eq!(results[3]["price"], 999);
+    assert!(body["message"].as_str().unwrap().contains("nope"));
+
+    let requests: Vec<_> = (0..1001)
+        .map(|i| json!({"indexName": "products", "objectID": format!("p{}", i)}))
+        .collect();
+    let res = h(client.post(format!("{}/1/indexes/*/objects", base)))
+        .json(&json!({ "requests": requests }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400, "more than 1000 objects is rejected");
+}
+
 // ──────────────────────────────────────────────────────────────────
 // Synonyms CRUD
 // ──────────────────────────────────────────────────────────────────
@@ -1,7 +1,8 @@
 mod common;
 
 use flapjack_http::auth::{
-    generate_secured_api_key, validate_secured_key, KeyStore, SecuredKeyRestrictions,
+    generate_secured_api_key, sign_claims_token, validate_claims_token, validate_secured_key,
+    KeyStore, SecuredKeyRestrictions,
 };
 use tempfile::TempDir;
 
@@ -264,6 +265,7 @@ fn test_filters_merge_logic() {
         user_token: None,
         hits_per_page: None,
         restrict_sources: None,
+        ..Default::default()
     };
 
     let mut req: SearchRequest = serde_json::from_str(r#"{"query":"phone"}"#).unwrap();
@@ -300,6 +302,7 @@ fn test_hits_per_page_cap() {
         user_token: None,
         hits_per_page: Some(5),
         restrict_sources: None,
+        ..Default::default()
     };
 
     use flapjack_http::dto::SearchRequest;
@@ -360,6 +363,29 @@ fn test_multiple_restrictions_combined() {
     assert_eq!(r.hits_per_page, Some(10));
     assert_eq!(r.user_token, Some("user42".to_string()));
 }
+#[test]
+fn test_templated_filters_resolved_from_claims_token() {
+    let (_dir, store) = setup_key_store();
+    let search_key = get_search_key(&store);
+
+    // One secured key shared by every tenant; the tenant comes from claims.
+    let params = "filters=tenant_id%3A%7Bclaim.tenant%7D&validUntil=9999999999";
+    let secured = generate_secured_api_key(&search_key, params);
+    let (parent, mut r) = validate_secured_key(&secured, &store).unwrap();
+    assert_eq!(r.filters, Some("tenant_id:{claim.tenant}".to_string()));
+    assert!(r.resolved_filters().is_err(), "no claims → fail closed");
+
+    let token = sign_claims_token(&serde_json::json!({"tenant": "t-981"}), &search_key);
+    r.claims = validate_claims_token(&token, parent.hmac_key.as_deref().unwrap()).unwrap();
+    assert_eq!(
+        r.resolved_filters().unwrap(),
+        Some("tenant_id:\"t-981\"".to_string())
+    );
+
+    let forged = sign_claims_token(&serde_json::json!({"tenant": "t-1"}), "not_the_parent");
+    assert!(validate_claims_token(&forged, &search_key).is_none());
+}
+
 #[test]
 fn test_parent_index_restriction_enforced() {
     let (_dir, store) = setup_key_store();
@@ -378,6 +404,12 @@ fn test_parent_index_restriction_enforced() {
         query_parameters: String::new(),
         referers: vec![],
         validity: 0,
+        metadata: Default::default(),
+        expires_at: None,
+        allowed_attributes: Vec::new(),
+        denied_attributes: Vec::new(),
+        allowed_origins: Vec::new(),
+        namespace: None,
     });
 
     let params = "restrictIndices=%5B%22users%22%5D&validUntil=9999999999";
@@ -836,6 +868,12 @@ mod e2e {
             query_parameters: String::new(),
             referers: vec![],
             validity: 0,
+            metadata: Default::default(),
+            expires_at: None,
+            allowed_attributes: Vec::new(),
+            denied_attributes: Vec::new(),
+            allowed_origins: Vec::new(),
+            namespace: None,
         });
 
         let secured = generate_secured_api_key(&scoped_plaintext, "validUntil=9999999999");
@@ -889,4 +927,62 @@ mod e2e {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_e2e_secured_key_filter_applies_to_browse() -> Result<()> {
+        let admin_key = "admin_key_1234567890abcdef";
+        let (addr, _tmp) = common::spawn_server_with_key(Some(admin_key)).await;
+        setup_index(&addr, admin_key).await;
+
+        let settings = serde_json::json!({"attributesForFaceting": ["brand"]});
+        let settings_resp =
+            http_post(&addr, "/1/indexes/products/settings", &settings, admin_key).await;
+        let client = reqwest::Client::new();
+        common::wait_for_response_task_authed(&client, &addr, settings_resp, Some(admin_key)).await;
+
+        let key_resp = http_post(
+            &addr,
+            "/1/keys",
+            &serde_json::json!({"acl": ["search", "browse"], "description": "export"}),
+            admin_key,
+        )
+        .await;
+        let browse_key = key_resp.json::<serde_json::Value>().await?["key"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let secured =
+            generate_secured_api_key(&browse_key, "filters=brand%3ASamsung&validUntil=9999999999");
+
+        let resp = http_post(
+            &addr,
+            "/1/indexes/products/browse",
+            &serde_json::json!({"attributesToRetrieve": ["brand"]}),
+            &secured,
+        )
+        .await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = resp.json().await?;
+        assert_eq!(
+            body["nbHits"], 2,
+            "secured key should only export Samsung docs"
+        );
+        for hit in body["hits"].as_array().unwrap() {
+            assert_eq!(hit["brand"], "Samsung");
+            assert!(hit.get("name").is_none(), "name was not retrieved");
+        }
+
+        // User facetFilters narrow the export further but cannot widen it.
+        let resp = http_post(
+            &addr,
+            "/1/indexes/products/browse",
+            &serde_json::json!({"params": "facetFilters=%5B%22brand%3AApple%22%5D"}),
+            &secured,
+        )
+        .await;
+        let body: serde_json::Value = resp.json().await?;
+        assert_eq!(body["nbHits"], 0);
+
+        Ok(())
+    }
 }
@@ -433,7 +433,6 @@ mod cors {
     use std::sync::Arc;
     use tempfile::TempDir;
     use tokio::net::TcpListener;
-    use tower_http::cors::CorsLayer;
 
     async fn spawn_server_with_cors() -> (String, TempDir) {
         let temp_dir = TempDir::new().unwrap();
@@ -464,7 +463,7 @@ mod cors {
             .layer(middleware::from_fn(
                 flapjack_http::middleware::normalize_content_type,
             ))
-            .layer(CorsLayer::very_permissive().max_age(std::time::Duration::from_secs(86400)))
+            .layer(flapjack_http::cors::CorsConfig::default().layer())
             .layer(middleware::from_fn(
                 flapjack_http::middleware::allow_private_network,
             ));